@@ -0,0 +1,460 @@
+//! Administration Commands for the Binance Spot Controller.
+//!
+//! Usage:
+//!   ctl-admin halt [reason]    Kill switch: halt trading on every component
+//!   ctl-admin resume [reason]  Resume trading after a halt
+//!   ctl-admin reset-breaker [reason]
+//!                              Reset a tripped circuit breaker, resuming trading
+//!                              and the feeds
+//!   ctl-admin status           Show the trading state of the status table
+//!   ctl-admin alerts           Follow the alerts raised by the components
+//!   ctl-admin streams          Show the busiest and the silent market data streams
+//!   ctl-admin rings [pattern]  List the rings matching the pattern (e.g. `TOP_*_PS`),
+//!                              with their element type
+//!   ctl-admin pause-feed <target> [reason]
+//!                              Stop publishing the market data of a set
+//!                              (`{kind}/{set}`), feed kind or symbol
+//!   ctl-admin resume-feed <target> [reason]
+//!                              Resume publishing after a pause
+//!   ctl-admin load-strategy <name> [reason]
+//!                              Load a strategy plugin of the catalog of its host
+//!   ctl-admin unload-strategy <name> [reason]
+//!                              Unload a strategy plugin, its orders left live
+//!   ctl-admin swap-strategy <name> [reason]
+//!                              Hot-swap the running strategy for another plugin
+//!                              of the catalog, e.g. a new build, keeping its orders
+//!   ctl-admin audit [since_ms] Replay the control-plane actions of the audit
+//!                              journal, and the control state they left
+//!   ctl-admin header [path]    Write the C header of the ring messages and shared
+//!                              regions, for the C and C++ components
+//!
+//! The halt is recorded in the status table before the HALT command is broadcast
+//! through the control ring, so components started afterwards still see it.
+//! A halt of a tripped circuit breaker can't be resumed, only reset once its
+//! cause is addressed.
+//! Alerts are only printed from the time `alerts` attaches to the alerts ring.
+//! The feed commands wait for the acknowledgement of ctl-md-handler, and the
+//! strategy commands for the one of the strategy host, published to the alerts
+//! ring. Every command changing the control state is recorded in
+//! the audit journal (`CTL_AUDIT_JOURNAL`) before it is applied, along with the
+//! actions of the components. The header asserts the size and the field
+//! offsets of every type at compile time, and defines the layout hash of every
+//! ring message; regenerate it after changing a shared type. The exit code tells the class of a failure (see
+//! `FatalKind`), e.g. 12 while ctl-resource-manager isn't running.
+
+use std::env;
+use std::fs;
+use std::process::ExitCode;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use ctl_core::{
+    AlertKind, AlertMessage, AuditAction, AuditJournal, AuditRecord, Capability, ControlCommand, ControlMessage,
+    ControlState, EalConfig, Fatal, FatalError, FatalKind, LcoresConfig, Preflight, StatusRegion, ALERTS_RING_NAME,
+    CONTROL_RING_NAME, DEFAULT_AUDIT_JOURNAL_PATH, STATUS_REGION_NAME,
+};
+use ctl_feed::{
+    CandleMessage, DiscoveredRing, MetricsRegion, RawMessage, RingPattern, SignalMessage, StreamReport,
+    TradeStatsMessage, METRICS_REGION_NAME, SILENT_STREAM_AFTER_MS,
+};
+use ctl_md_handler::HwResourcesConfig;
+use ctl_oms::OrderEntryMessage;
+use ctl_shm::{CHeader, ShmError, ShmMessage, ShmRegion};
+use ctl_time::now_ms;
+use dpdk::{ConsumeStartState, DpdkEnv, DpdkEnvBuilder, DpdkProcessType};
+use tracing::{info, warn};
+
+// Logging configuration, its levels reloaded on change
+const LOG_CONFIG_PATH: &str = "configs/logging.yaml";
+
+// The market data configuration, holding the EAL options of the secondary processes
+const MD_CONFIG_PATH: &str = "configs/market-data/hw-resources.yaml";
+
+// The lcore of ctl-admin, unless configured, apart from the other components
+const LCORES_CONFIG_PATH: &str = "configs/lcores.yaml";
+const LCORES_COMPONENT: &str = "ctl-admin";
+const DEFAULT_LCORE: u32 = 15;
+
+// The audit journal of the control-plane actions, unless given by its environment variable
+const AUDIT_JOURNAL_ENV: &str = "CTL_AUDIT_JOURNAL";
+const AUDIT_COMPONENT: &str = "ctl-admin";
+
+// Sleep between the polls of an empty alerts ring
+const ALERTS_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+// Number of the busiest streams shown
+const BUSIEST_STREAMS: usize = 20;
+
+// Time the feed and strategy commands wait for their acknowledgement
+const ACK_TIMEOUT: Duration = Duration::from_secs(2);
+
+const USAGE: &str = "Usage: ctl-admin <halt [reason] | resume [reason] | reset-breaker [reason] | status | \
+                     alerts | streams | rings [pattern] | pause-feed <target> [reason] | \
+                     resume-feed <target> [reason] | load-strategy <name> [reason] | \
+                     unload-strategy <name> [reason] | swap-strategy <name> [reason] | audit [since_ms] | \
+                     header [path]>";
+
+/// Initializes the DPDK secondary process.
+fn attach(eal: &EalConfig) -> Result<DpdkEnv, FatalError> {
+    let lcore = LcoresConfig::from_file(LCORES_CONFIG_PATH)?.lcore(LCORES_COMPONENT, DEFAULT_LCORE) as usize;
+
+    // Verify the host before the EAL initialization, which reports its failures cryptically
+    Preflight::new()
+        .require_hugepages_mounted()
+        .require_lcores(&[lcore])
+        .require_capabilities(&[Capability::IpcLock])
+        .require_primary()
+        .with_file_prefix(eal.file_prefix())
+        .run()?;
+
+    let dpdk_env = DpdkEnvBuilder::default()
+        .process_type(DpdkProcessType::Secondary)
+        .lcore_ids(vec![lcore])
+        .main_lcore_id(lcore)
+        .eal_args(eal.secondary_args())
+        .build()
+        .fatal(FatalKind::DpdkInit)?;
+    Ok(dpdk_env)
+}
+
+/// Checks that a ring holds messages of the layout this binary was built with.
+fn check_layout<T: ShmMessage>(ring_name: &str) -> Result<(), FatalError> {
+    ShmRegion::<MetricsRegion>::open(METRICS_REGION_NAME)?
+        .check_layout::<T>(ring_name)
+        .fatal(FatalKind::SharedState)
+}
+
+/// Broadcasts a command through the control ring.
+fn broadcast(dpdk_env: &DpdkEnv, command: ControlCommand, reason: &str) -> Result<(), FatalError> {
+    let ring = dpdk_env.pubsub_lookup::<ControlMessage>(CONTROL_RING_NAME).fatal(FatalKind::SharedState)?;
+    check_layout::<ControlMessage>(CONTROL_RING_NAME)?;
+    ring.publish(&ControlMessage::new(command, now_ms(), reason)).fatal(FatalKind::Internal)?;
+    info!("Broadcast {:?} through {}", command, CONTROL_RING_NAME);
+    Ok(())
+}
+
+/// Broadcasts a command of a target (a feed or a strategy) through the control
+/// ring, printing its acknowledgements.
+///
+/// # Errors
+/// Returns an error if no acknowledgement is received within `ACK_TIMEOUT`.
+fn broadcast_target(
+    dpdk_env: &DpdkEnv,
+    command: ControlCommand,
+    target: &str,
+    reason: &str,
+) -> Result<(), FatalError> {
+    // Attach to the alerts before broadcasting, not to miss the acknowledgements
+    let alerts = dpdk_env.pubsub_lookup::<AlertMessage>(ALERTS_RING_NAME).fatal(FatalKind::SharedState)?;
+    check_layout::<AlertMessage>(ALERTS_RING_NAME)?;
+    let mut acks = alerts.attach_consumer().fatal(FatalKind::SharedState)?;
+
+    let ring = dpdk_env.pubsub_lookup::<ControlMessage>(CONTROL_RING_NAME).fatal(FatalKind::SharedState)?;
+    check_layout::<ControlMessage>(CONTROL_RING_NAME)?;
+    ring.publish(&ControlMessage::new(command, now_ms(), reason).with_target(target)).fatal(FatalKind::Internal)?;
+    info!("Broadcast {:?} of '{}' through {}", command, target, CONTROL_RING_NAME);
+
+    let deadline = Instant::now() + ACK_TIMEOUT;
+    let mut acked = false;
+    while Instant::now() < deadline {
+        match acks.consume_start() {
+            ConsumeStartState::Success(mut guard) => {
+                if guard.try_commit().is_err() {
+                    continue;
+                }
+                let alert = guard.as_ref().get();
+                if alert.kind() == AlertKind::ControlAck {
+                    print_alert(alert);
+                    acked = true;
+                }
+            }
+            ConsumeStartState::SpedPast(_guard) => {
+                warn!("Alerts overwritten before they were read");
+            }
+            ConsumeStartState::InFlight(_) => {}
+            ConsumeStartState::Empty => {
+                if acked {
+                    return Ok(());
+                }
+                thread::sleep(ALERTS_POLL_INTERVAL);
+            }
+        }
+    }
+    if acked {
+        return Ok(());
+    }
+    Err(FatalError::new(
+        FatalKind::Internal,
+        format!("No acknowledgement of {:?} within {:?}", command, ACK_TIMEOUT),
+    ))
+}
+
+/// Prints the alerts raised by the components until interrupted.
+fn follow_alerts(eal: &EalConfig) -> Result<(), FatalError> {
+    let dpdk_env = attach(eal)?;
+    let ring = dpdk_env.pubsub_lookup::<AlertMessage>(ALERTS_RING_NAME).fatal(FatalKind::SharedState)?;
+    check_layout::<AlertMessage>(ALERTS_RING_NAME)?;
+    let mut consumer = ring.attach_consumer().fatal(FatalKind::SharedState)?;
+    info!("Following alerts of {}", ALERTS_RING_NAME);
+
+    loop {
+        match consumer.consume_start() {
+            ConsumeStartState::Success(mut guard) => {
+                if guard.try_commit().is_err() {
+                    continue;
+                }
+                print_alert(guard.as_ref().get());
+            }
+            ConsumeStartState::SpedPast(_guard) => {
+                warn!("Alerts overwritten before they were printed");
+            }
+            ConsumeStartState::InFlight(_) => {}
+            ConsumeStartState::Empty => thread::sleep(ALERTS_POLL_INTERVAL),
+        }
+    }
+}
+
+/// Prints an alert.
+fn print_alert(alert: &AlertMessage) {
+    println!(
+        "{} {:?} {:?} [{}] {}",
+        alert.raised_at_ms,
+        alert.severity(),
+        alert.kind(),
+        alert.source(),
+        alert.detail()
+    );
+}
+
+/// Prints the busiest streams and the silent ones, as sampled by ctl-md-handler.
+fn print_streams(metrics: &MetricsRegion) {
+    let now_ms = now_ms();
+    let report = StreamReport::from_streams(metrics.registered_streams(), BUSIEST_STREAMS, now_ms, SILENT_STREAM_AFTER_MS);
+    println!("Busiest streams (messages/s):");
+    for rate in &report.busiest {
+        println!("  {:<48} {:>8}", rate.stream, rate.rate);
+    }
+    println!("Silent streams (no message for {} ms):", SILENT_STREAM_AFTER_MS);
+    for rate in &report.silent {
+        println!("  {:<48} last message {} ms ago", rate.stream, now_ms.saturating_sub(rate.last_message_ms));
+    }
+}
+
+/// Returns the element type of a ring, from the layout hash of its messages.
+fn element_name(ring: &DiscoveredRing) -> &'static str {
+    if ring.holds::<RawMessage>() {
+        "RawMessage"
+    } else if ring.holds::<TradeStatsMessage>() {
+        "TradeStatsMessage"
+    } else if ring.holds::<CandleMessage>() {
+        "CandleMessage"
+    } else if ring.holds::<SignalMessage>() {
+        "SignalMessage"
+    } else if ring.holds::<AlertMessage>() {
+        "AlertMessage"
+    } else if ring.holds::<ControlMessage>() {
+        "ControlMessage"
+    } else if ring.holds::<OrderEntryMessage>() {
+        "OrderEntryMessage"
+    } else {
+        "unknown layout, stale binary?"
+    }
+}
+
+/// Prints the rings matching a pattern, registered by ctl-resource-manager,
+/// with the lag of their consumers.
+fn print_rings(metrics: &MetricsRegion, pattern: &RingPattern) {
+    for ring in metrics.discover(pattern) {
+        match ring.slot_size {
+            0 => println!("  {:<32} {:>8} {}", ring.name, ring.ring_size, element_name(&ring)),
+            slot_size => println!(
+                "  {:<32} {:>8} {} ({} byte slots)",
+                ring.name,
+                ring.ring_size,
+                element_name(&ring),
+                slot_size
+            ),
+        }
+        let Some(entry) = metrics.find_ring(&ring.name).map(|index| &metrics.rings[index]) else {
+            continue;
+        };
+        for (index, cursor) in entry.attached() {
+            let name = cursor.name();
+            let name = if name.is_empty() { "(anonymous)" } else { name.as_str() };
+            println!("    consumer {} {:<24} lag {}", index, name, entry.lag(index).unwrap_or_default());
+        }
+    }
+}
+
+/// Prints the records of the audit journal from `since_ms`, and the control
+/// state replayed from the whole journal.
+fn print_audit(records: &[AuditRecord], since_ms: u64) {
+    for record in records.iter().filter(|record| record.at_ms >= since_ms) {
+        let origin = &record.origin;
+        println!(
+            "{} {}[{}] ({}) {:?} {}",
+            record.at_ms, origin.component, origin.pid, origin.user, record.action, record.reason
+        );
+    }
+    let state = ControlState::replay(records);
+    match (state.halted_at_ms, state.tripped_by) {
+        (Some(at_ms), Some(signal)) => println!("Trading halted since {} by a breaker trip on {:?}", at_ms, signal),
+        (Some(at_ms), None) => println!("Trading halted since {}", at_ms),
+        (None, _) => println!("Trading active"),
+    }
+    let paused: Vec<&str> =
+        state.paused.iter().map(|target| if target.is_empty() { "(every feed)" } else { target }).collect();
+    println!("Paused feeds: {}", if paused.is_empty() { "none".to_string() } else { paused.join(", ") });
+}
+
+/// Prints the trading state.
+fn print_status(status: &StatusRegion) {
+    match status.run_epoch() {
+        0 => println!("Resource manager starting, run not set up yet"),
+        epoch => println!("Run epoch {} started at {} (epoch ms)", epoch, status.started_at_ms()),
+    }
+    if status.is_halted() {
+        println!("Trading HALTED since {} (epoch ms)", status.halted_at_ms());
+    } else {
+        println!("Trading active");
+    }
+    println!("Halts since startup: {}", status.halt_count());
+    if status.is_tripped() {
+        println!(
+            "Circuit breaker TRIPPED by {:?} since {} (epoch ms), reset with `ctl-admin reset-breaker`",
+            status.tripped_by(),
+            status.tripped_at_ms()
+        );
+    }
+    if status.is_degraded() {
+        println!("Order entry DEGRADED to the REST fallback since {} (epoch ms)", status.degraded_at_ms());
+    }
+}
+
+/// Renders the C header of the ring messages and shared regions.
+fn c_header() -> Result<String, ShmError> {
+    let mut header = CHeader::new("CTL_SHARED_H");
+    header.section("Shared memory rings (ctl-shm)");
+    ctl_shm::declare_c_ring(&mut header)?;
+    ctl_core::declare_c_types(&mut header)?;
+    ctl_feed::declare_c_types(&mut header)?;
+    ctl_oms::declare_c_types(&mut header)?;
+    Ok(header.render())
+}
+
+fn main() -> ExitCode {
+    ctl_core::exit_code(run())
+}
+
+fn run() -> Result<(), FatalError> {
+    let _log = ctl_log::init_from_file(LOG_CONFIG_PATH).fatal(FatalKind::Config)?;
+
+    let args: Vec<String> = env::args().skip(1).collect();
+    let Some(command) = args.first() else {
+        return Err(FatalError::new(FatalKind::Usage, USAGE));
+    };
+    let reason = args[1..].join(" ");
+    let audit_path = env::var(AUDIT_JOURNAL_ENV).unwrap_or_else(|_| DEFAULT_AUDIT_JOURNAL_PATH.to_string());
+    if command == "audit" {
+        let since_ms = match args.get(1) {
+            Some(since) => since.parse().map_err(|_| FatalError::new(FatalKind::Usage, USAGE))?,
+            None => 0,
+        };
+        print_audit(&AuditJournal::read(&audit_path)?, since_ms);
+        return Ok(());
+    }
+    if command == "header" {
+        let header = c_header().fatal(FatalKind::Internal)?;
+        match args.get(1) {
+            Some(path) => {
+                fs::write(path, header).fatal(FatalKind::Io)?;
+                info!("C header written to {}", path);
+            }
+            None => print!("{}", header),
+        }
+        return Ok(());
+    }
+    // Recorded before acting, so that no action goes unrecorded
+    let audit = || AuditJournal::open(&audit_path, AUDIT_COMPONENT);
+
+    let eal = HwResourcesConfig::from_file(MD_CONFIG_PATH).fatal(FatalKind::Config)?.eal;
+    eal.apply_region_prefix();
+
+    let status = ShmRegion::<StatusRegion>::open(STATUS_REGION_NAME)?;
+    match command.as_str() {
+        "halt" => {
+            audit()?.record(AuditAction::Halt, &reason)?;
+            if !status.halt(now_ms()) {
+                info!("Trading already halted, broadcasting HALT again");
+            }
+            broadcast(&attach(&eal)?, ControlCommand::Halt, &reason)?;
+        }
+        "resume" => {
+            if status.is_tripped() {
+                print_status(&status);
+                let detail = "Circuit breaker tripped, reset it with `ctl-admin reset-breaker`";
+                return Err(FatalError::new(FatalKind::Internal, detail));
+            }
+            audit()?.record(AuditAction::Resume, &reason)?;
+            if !status.resume() {
+                info!("Trading was not halted");
+            }
+            broadcast(&attach(&eal)?, ControlCommand::Resume, &reason)?;
+        }
+        "reset-breaker" => {
+            if !status.reset_breaker() {
+                info!("Circuit breaker was not tripped");
+                print_status(&status);
+                return Ok(());
+            }
+            audit()?.record(AuditAction::ResetBreaker, &reason)?;
+            status.resume();
+            let dpdk_env = attach(&eal)?;
+            broadcast(&dpdk_env, ControlCommand::Resume, &reason)?;
+            broadcast_target(&dpdk_env, ControlCommand::ResumeFeed, "", &reason)?;
+        }
+        "status" => {}
+        "alerts" => return follow_alerts(&eal),
+        "streams" => {
+            print_streams(&ShmRegion::<MetricsRegion>::open(METRICS_REGION_NAME)?);
+            return Ok(());
+        }
+        "rings" => {
+            let pattern = RingPattern::new(args.get(1).map_or("*", String::as_str));
+            print_rings(&ShmRegion::<MetricsRegion>::open(METRICS_REGION_NAME)?, &pattern);
+            return Ok(());
+        }
+        "pause-feed" | "resume-feed" => {
+            let Some(target) = args.get(1) else {
+                return Err(FatalError::new(FatalKind::Usage, USAGE));
+            };
+            let reason = args[2..].join(" ");
+            let (command, action) = match command.as_str() {
+                "pause-feed" => (ControlCommand::PauseFeed, AuditAction::PauseFeed { target: target.clone() }),
+                _ => (ControlCommand::ResumeFeed, AuditAction::ResumeFeed { target: target.clone() }),
+            };
+            let dpdk_env = attach(&eal)?;
+            audit()?.record(action, &reason)?;
+            return broadcast_target(&dpdk_env, command, target, &reason);
+        }
+        "load-strategy" | "unload-strategy" | "swap-strategy" => {
+            let Some(name) = args.get(1) else {
+                return Err(FatalError::new(FatalKind::Usage, USAGE));
+            };
+            let reason = args[2..].join(" ");
+            let (command, action) = match command.as_str() {
+                "load-strategy" => (ControlCommand::LoadStrategy, AuditAction::LoadStrategy { name: name.clone() }),
+                "unload-strategy" => {
+                    (ControlCommand::UnloadStrategy, AuditAction::UnloadStrategy { name: name.clone() })
+                }
+                _ => (ControlCommand::SwapStrategy, AuditAction::SwapStrategy { name: name.clone() }),
+            };
+            let dpdk_env = attach(&eal)?;
+            audit()?.record(action, &reason)?;
+            return broadcast_target(&dpdk_env, command, name, &reason);
+        }
+        _ => return Err(FatalError::new(FatalKind::Usage, USAGE)),
+    }
+    print_status(&status);
+    Ok(())
+}
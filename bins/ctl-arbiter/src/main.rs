@@ -0,0 +1,535 @@
+//! Arbiter of the A/B lines of the market data feeds.
+//!
+//! Under the `arbitration` item of the market data configuration, the feeds are
+//! published by two md-handlers, started with `--line a` and `--line b`, to the
+//! line rings `{KIND}_{symbol_id}_LA` and `_LB` created by ctl-resource-manager
+//! next to each pub-sub ring. This binary connects as a DPDK secondary process,
+//! consumes both line rings of each pub-sub ring and forwards the first copy of
+//! each event to the pub-sub ring, sequenced anew, so that the strategies are
+//! covered against the loss or the lag of a line without knowing of it.
+//!
+//! A line receiving no message while the other one does is raised on the alerts
+//! ring as silent, and again once it carries messages.
+//!
+//! The configuration paths are given on the command line, or by their
+//! environment variables (see `--help`). With `--check`, the configurations
+//! are validated and the arbitration printed instead.
+//!
+//! The run epoch of ctl-resource-manager is checked while the rings are idle.
+//! Once it restarted, its rings gone stale, the arbiter executes itself anew,
+//! waiting for the next run before attaching again.
+//!
+//! The exit code tells the class of a failure (see `FatalKind`).
+
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::sync::atomic::Ordering;
+use std::time::{Duration, Instant};
+
+use clap::Parser;
+use ctl_core::{
+    reattach, reattaching, wait_for_run, AlertKind, AlertMessage, AlertSeverity, Capability, EpochWatch, Fatal,
+    FatalError, FatalKind, LcoresConfig, Line, Poller, PollingConfig, PollingPolicy, Preflight, RingName,
+    SchedulingConfig, StatusRegion, ALERTS_RING_NAME, REATTACH_POLL_INTERVAL, STATUS_REGION_NAME,
+};
+#[cfg(feature = "message-checksums")]
+use ctl_feed::Checksummed;
+use ctl_feed::{
+    Arbiter, Arbitration, ConsumerCursor, MetricsRegion, RawMessage, RingConsume, RingDirectory, RingError,
+    RingMetrics, RingPattern, RingPublisher, METRICS_REGION_NAME,
+};
+use ctl_md_handler::{ArbitrationConfig, HwResourcesConfig};
+use ctl_shm::ShmRegion;
+use dpdk::{ConsumeStartState, DpdkEnvBuilder, DpdkProcessType, DpdkPubSubRing};
+use tracing::{info, warn};
+
+// Logging configuration, its levels reloaded on change
+const LOG_CONFIG_PATH: &str = "configs/logging.yaml";
+
+// The market data configuration, holding the arbitration and the EAL options of the secondary processes
+const MD_CONFIG_PATH: &str = "configs/market-data/hw-resources.yaml";
+
+// The line A rings, each paired with the line B and pub-sub rings of its name
+const LINE_A_PATTERN: &str = "*_LA";
+
+// The name of the consumer cursors of the arbiter in the line rings
+const CONSUMER_NAME: &str = "arbiter";
+
+// The lcore of the arbiter, unless configured, past the other busy-polling consumers
+const LCORES_CONFIG_PATH: &str = "configs/lcores.yaml";
+const LCORES_COMPONENT: &str = "arbiter";
+const DEFAULT_LCORE: u32 = 17;
+
+// Polling policy between empty polls, unless configured for this component
+const POLLING_CONFIG_PATH: &str = "configs/polling.yaml";
+const POLLING_COMPONENT: &str = "arbiter";
+const DEFAULT_POLLING: PollingPolicy = PollingPolicy::BusyPoll;
+
+// Scheduling of the threads of this component, unless configured left untouched
+const SCHEDULING_CONFIG_PATH: &str = "configs/scheduling.yaml";
+const SCHEDULING_COMPONENT: &str = "arbiter";
+
+// Source of the alerts published to the alerts ring
+const ALERT_SOURCE: &str = "arbiter";
+
+// Interval between the checks of the run epoch of ctl-resource-manager
+const EPOCH_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+// Interval between the checks of the silence of the lines
+const SILENCE_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Arbiter of the A/B lines, forwarding the first copy of each event.
+#[derive(Debug, Parser)]
+#[command(version)]
+struct Args {
+    /// Logging configuration.
+    #[arg(long, env = "CTL_LOG_CONFIG", default_value = LOG_CONFIG_PATH)]
+    log_config: PathBuf,
+    /// Market data configuration.
+    #[arg(long, env = "CTL_MD_CONFIG", default_value = MD_CONFIG_PATH)]
+    md_config: PathBuf,
+    /// Polling configuration.
+    #[arg(long, env = "CTL_POLLING_CONFIG", default_value = POLLING_CONFIG_PATH)]
+    polling_config: PathBuf,
+    /// Lcores configuration.
+    #[arg(long, env = "CTL_LCORES_CONFIG", default_value = LCORES_CONFIG_PATH)]
+    lcores_config: PathBuf,
+    /// Scheduling configuration.
+    #[arg(long, env = "CTL_SCHEDULING_CONFIG", default_value = SCHEDULING_CONFIG_PATH)]
+    scheduling_config: PathBuf,
+    /// Validate the configurations and print the arbitration, without
+    /// initializing DPDK.
+    #[arg(long)]
+    check: bool,
+}
+
+/// The alerts ring on the DPDK runtime.
+struct DpdkAlerts(DpdkPubSubRing<AlertMessage>);
+
+impl RingPublisher<AlertMessage> for DpdkAlerts {
+    fn publish(&self, message: &AlertMessage) -> Result<(), RingError> {
+        self.0
+            .publish(message)
+            .map(|_| ())
+            .map_err(|e| RingError::Publish(e.to_string()))
+    }
+}
+
+/// The rings of an arbitrated pub-sub ring, named by the convention
+/// `{KIND}_{symbol_id}_{PS,LA,LB}`.
+struct ArbitratedRings {
+    /// The pub-sub ring name.
+    name: String,
+    /// The pub-sub ring, published to.
+    out: DpdkPubSubRing<RawMessage>,
+    /// The line ring names, by line.
+    line_names: [String; 2],
+    /// The line rings, consumed, by line.
+    lines: [DpdkPubSubRing<RawMessage>; 2],
+    /// The slot size of the rings.
+    slot_size: usize,
+}
+
+/// The arbitration of the lines of a pub-sub ring, whatever its backend.
+struct Arbitrated<'a, P, A> {
+    /// The pub-sub ring, published to.
+    out: P,
+    /// Metrics of the pub-sub ring, sequencing the forwarded messages.
+    out_metrics: &'a RingMetrics,
+    /// Metrics of the line rings, by line.
+    line_metrics: [&'a RingMetrics; 2],
+    /// The consumer cursors in the line ring metrics, by line.
+    cursors: [&'a ConsumerCursor; 2],
+    /// The arbiter of the events of the lines.
+    arbiter: Arbiter,
+    /// The alerts ring, told when a line is overtaken or silent.
+    alerts: A,
+    /// Time without a message on a line after which it is silent, in milliseconds.
+    silent_after_ms: u64,
+    /// Number of messages dropped for failing their checksum.
+    #[cfg(feature = "message-checksums")]
+    corrupt_count: u64,
+}
+
+impl<'a, P: RingPublisher<RawMessage>, A: RingPublisher<AlertMessage>> Arbitrated<'a, P, A> {
+    #[allow(clippy::too_many_arguments)]
+    fn new(
+        out: P,
+        out_metrics: &'a RingMetrics,
+        line_metrics: [&'a RingMetrics; 2],
+        cursors: [&'a ConsumerCursor; 2],
+        slot_size: usize,
+        alerts: A,
+        arbitration: ArbitrationConfig,
+        now_ms: u64,
+    ) -> Self {
+        Self {
+            out,
+            out_metrics,
+            line_metrics,
+            cursors,
+            arbiter: Arbiter::new(slot_size, now_ms).with_dedup_window(arbitration.dedup_window),
+            alerts,
+            silent_after_ms: arbitration.silent_after_ms,
+            #[cfg(feature = "message-checksums")]
+            corrupt_count: 0,
+        }
+    }
+
+    /// Handles the result of a consume of a line at `now_ms`, returning true if it did work.
+    ///
+    /// LATENCY: FAST_PATH
+    ///
+    /// # Errors
+    /// Returns an error if a message can't be forwarded to the pub-sub ring.
+    fn on_consume(&mut self, line: Line, consumed: RingConsume<RawMessage>, now_ms: u64) -> Result<bool, RingError> {
+        let (line_metrics, cursor) = (self.line_metrics[line.index()], self.cursors[line.index()]);
+        match consumed {
+            RingConsume::Message(msg) => {
+                cursor.advance();
+                #[cfg(feature = "message-checksums")]
+                if !msg.verify() {
+                    // Torn or corrupted in its slot, dropped, the other line carrying its copy
+                    self.corrupt_count += 1;
+                    let detail =
+                        format!("{} message seq {} failed its checksum, dropped", line_metrics.name(), msg.header.seq);
+                    warn!("{}", detail);
+                    self.alert(AlertKind::CorruptMessage, AlertSeverity::Warning, &detail);
+                    return Ok(true);
+                }
+                match self.arbiter.push(line, &msg, now_ms) {
+                    Arbitration::Forward(messages) => {
+                        for message in messages {
+                            // Sequenced in the pub-sub ring, keeping the publish time of its line
+                            let mut message = *message;
+                            message.header.stamp(self.out_metrics.record_publish(), message.header.ts_ms);
+                            #[cfg(feature = "message-checksums")]
+                            message.seal();
+                            self.out.publish(&message)?;
+                        }
+                    }
+                    Arbitration::Duplicate | Arbitration::Partial => {}
+                    Arbitration::Dropped => {
+                        warn!("{} fragment of seq {} lost, payload dropped", line_metrics.name(), msg.header.seq);
+                    }
+                }
+                Ok(true)
+            }
+            RingConsume::InFlight => Ok(false),
+            RingConsume::SpedPast => {
+                // Overtaken by the handler of the line, its missed events are carried by the other line
+                self.arbiter.reset(line);
+                cursor.sped_past(line_metrics.head.load(Ordering::Acquire));
+                let detail = format!("{} consumer overtaken by producer, some messages missed", line_metrics.name());
+                warn!("{}, skipped", detail);
+                self.alert(AlertKind::RingOverflow, AlertSeverity::Warning, &detail);
+                Ok(true)
+            }
+            RingConsume::Empty => Ok(false),
+        }
+    }
+
+    /// Raises the lines turning silent at `now_ms`, or carrying messages again.
+    ///
+    /// LATENCY: SLOW_PATH
+    fn check_silence(&mut self, now_ms: u64) {
+        for change in self.arbiter.check_silence(now_ms, self.silent_after_ms) {
+            let name = self.line_metrics[change.line.index()].name();
+            if change.silent {
+                let detail =
+                    format!("{} silent for {}ms while line {} carries it", name, change.quiet_ms, change.line.other());
+                warn!("{}", detail);
+                self.alert(AlertKind::LineSilent, AlertSeverity::Warning, &detail);
+            } else {
+                let detail = format!("{} carries messages again", name);
+                info!("{}", detail);
+                self.alert(AlertKind::LineSilent, AlertSeverity::Info, &detail);
+            }
+        }
+    }
+
+    /// Publishes an alert to the alerts ring.
+    fn alert(&self, kind: AlertKind, severity: AlertSeverity, detail: &str) {
+        let alert = AlertMessage::new(kind, severity, ctl_time::now_ms(), ALERT_SOURCE, detail);
+        if let Err(e) = self.alerts.publish(&alert) {
+            warn!("Failed to publish alert to {}: {}", ALERTS_RING_NAME, e);
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    ctl_core::exit_code(run())
+}
+
+fn run() -> Result<(), FatalError> {
+    let args = Args::parse();
+    let _log = ctl_log::init_from_file(&args.log_config).fatal(FatalKind::Config)?;
+
+    info!("=== Binance Spot Line Arbiter ===");
+
+    let md_config = HwResourcesConfig::from_file(&args.md_config).fatal(FatalKind::Config)?;
+    md_config.eal.apply_region_prefix();
+    let arbitration = md_config
+        .arbitration
+        .ok_or_else(|| format!("No arbitration in {}", args.md_config.display()))
+        .fatal(FatalKind::Config)?;
+    info!("Arbitration: {:?}", arbitration);
+    let polling = PollingConfig::from_file(&args.polling_config)?.policy(POLLING_COMPONENT, DEFAULT_POLLING);
+    info!("Polling: {:?}", polling);
+    let scheduling = SchedulingConfig::from_file(&args.scheduling_config)?.policy(SCHEDULING_COMPONENT);
+    info!("Scheduling: {:?}", scheduling);
+    let lcore = LcoresConfig::from_file(&args.lcores_config)?.lcore(LCORES_COMPONENT, DEFAULT_LCORE) as usize;
+
+    if args.check {
+        println!(
+            "lines of {} on lcore {}, polling {:?}, silent after {}ms, dedup window {}",
+            LINE_A_PATTERN, lcore, polling, arbitration.silent_after_ms, arbitration.dedup_window
+        );
+        println!("Configuration OK");
+        return Ok(());
+    }
+
+    // Executed anew after a restart of ctl-resource-manager, wait for its next run to attach to
+    if let Some(stale) = reattaching() {
+        info!("Reattaching after the run epoch {}, waiting for the resource manager...", stale);
+        let epoch = wait_for_run(STATUS_REGION_NAME, stale, REATTACH_POLL_INTERVAL);
+        info!("Resource manager started the run epoch {}", epoch);
+    }
+
+    // Verify the host before the EAL initialization, which reports its failures cryptically
+    Preflight::new()
+        .require_hugepages_mounted()
+        .require_isolated_lcores(&[lcore])
+        .require_capabilities(&[Capability::IpcLock])
+        .require_capabilities(&scheduling.capabilities())
+        .require_primary()
+        .with_file_prefix(md_config.eal.file_prefix())
+        .run()?;
+
+    // Before the EAL, deriving the affinity of its control threads from the process
+    scheduling.apply()?;
+
+    let dpdk_env = DpdkEnvBuilder::default()
+        .process_type(DpdkProcessType::Secondary)
+        .lcore_ids(vec![lcore])
+        .main_lcore_id(lcore)
+        .eal_args(md_config.eal.secondary_args())
+        .build()
+        .fatal(FatalKind::DpdkInit)?;
+
+    info!("DPDK environment initialized");
+
+    // Calibrate the timestamps of the hot path before the first message
+    info!("Timestamps from the {:?} clock ({:?} Hz)", ctl_time::calibrate(), ctl_time::tsc_hz());
+
+    // Record the run of ctl-resource-manager attached to, its rings being stale once it restarts
+    let epoch = EpochWatch::attach(STATUS_REGION_NAME, &ShmRegion::<StatusRegion>::open(STATUS_REGION_NAME)?)?;
+    info!("Attached to run epoch {}", epoch.attached());
+
+    // Discover the line rings among those registered by resource-manager, looked up
+    // with the layout of their messages checked
+    let metrics = ShmRegion::<MetricsRegion>::open(METRICS_REGION_NAME)?;
+    let directory = RingDirectory::new(&dpdk_env, &metrics);
+    let mut rings = Vec::new();
+    for discovered in directory.discover_of::<RawMessage>(&RingPattern::new(LINE_A_PATTERN)) {
+        let line_a = discovered.name.parse::<RingName>().fatal(FatalKind::SharedState)?;
+        let name = line_a.to_pubsub().to_string();
+        let line_names = Line::ALL.map(|line| line_a.on_line(line).to_string());
+        let [a, b] = &line_names;
+        let lines = [
+            directory.lookup::<RawMessage>(a).fatal(FatalKind::SharedState)?,
+            directory.lookup::<RawMessage>(b).fatal(FatalKind::SharedState)?,
+        ];
+        let out = directory.lookup::<RawMessage>(&name).fatal(FatalKind::SharedState)?;
+        let slot_size = metrics.slot_size(&name).fatal(FatalKind::SharedState)?;
+        info!("Arbitrating {} and {} to {}", a, b, name);
+        rings.push(ArbitratedRings { name, out, line_names, lines, slot_size });
+    }
+    if rings.is_empty() {
+        return Err(FatalError::new(FatalKind::SharedState, format!("No line rings match '{}'", LINE_A_PATTERN)));
+    }
+
+    let alerts = DpdkAlerts(dpdk_env.pubsub_lookup::<AlertMessage>(ALERTS_RING_NAME).fatal(FatalKind::SharedState)?);
+    metrics.check_layout::<AlertMessage>(ALERTS_RING_NAME).fatal(FatalKind::SharedState)?;
+
+    // Track the consumer positions in the line ring metrics so their lag can be
+    // observed, refusing a second arbiter
+    let find_metrics = |ring_name: &str| {
+        metrics
+            .find_ring(ring_name)
+            .map(|index| &metrics.rings[index])
+            .ok_or_else(|| format!("Ring '{}' not found in metrics region", ring_name))
+            .fatal(FatalKind::SharedState)
+    };
+    let now_ms = ctl_time::now_ms();
+    let mut arbitrated = Vec::new();
+    for ring in &rings {
+        let [a, b] = &ring.line_names;
+        let line_metrics = [find_metrics(a)?, find_metrics(b)?];
+        let mut cursors = Vec::new();
+        for ring_metrics in line_metrics {
+            let index = ring_metrics
+                .attach_named_consumer(CONSUMER_NAME, std::process::id() as u64)
+                .fatal(FatalKind::SharedState)?;
+            cursors.push(&ring_metrics.consumers[index]);
+        }
+        let consumers = [
+            ring.lines[0].attach_consumer().fatal(FatalKind::SharedState)?,
+            ring.lines[1].attach_consumer().fatal(FatalKind::SharedState)?,
+        ];
+        let handling = Arbitrated::new(
+            &ring.out,
+            find_metrics(&ring.name)?,
+            line_metrics,
+            [cursors[0], cursors[1]],
+            ring.slot_size,
+            &alerts,
+            arbitration,
+            now_ms,
+        );
+        arbitrated.push((handling, consumers));
+    }
+
+    info!("=== Arbiter Running ({} rings) ===", arbitrated.len());
+
+    let mut poller = Poller::new(polling);
+    let mut last_epoch_check = Instant::now();
+    let mut last_silence_check = Instant::now();
+    loop {
+        let mut did_work = false;
+        let now_ms = ctl_time::now_ms();
+        for (handling, consumers) in arbitrated.iter_mut() {
+            for line in Line::ALL {
+                let consumed = match consumers[line.index()].consume_start() {
+                    ConsumeStartState::Success(mut guard) => match guard.try_commit() {
+                        // Mark the message as consumed before reading it
+                        Ok(_) => RingConsume::Message(*guard.as_ref().get()),
+                        // Commit failed, retry on the next pass
+                        Err(_) => continue,
+                    },
+                    ConsumeStartState::InFlight(_guard) => RingConsume::InFlight,
+                    ConsumeStartState::SpedPast(_guard) => RingConsume::SpedPast,
+                    ConsumeStartState::Empty => RingConsume::Empty,
+                };
+                did_work |= handling.on_consume(line, consumed, now_ms).fatal(FatalKind::Internal)?;
+            }
+        }
+
+        if last_silence_check.elapsed() >= SILENCE_CHECK_INTERVAL {
+            for (handling, _) in arbitrated.iter_mut() {
+                handling.check_silence(now_ms);
+            }
+            last_silence_check = Instant::now();
+        }
+
+        // Reattach on a restart of ctl-resource-manager, rather than reading its stale rings
+        if !did_work && last_epoch_check.elapsed() >= EPOCH_CHECK_INTERVAL {
+            if let Err(e) = epoch.check() {
+                warn!("{}, reattaching", e);
+                return Err(reattach(&epoch));
+            }
+            last_epoch_check = Instant::now();
+        }
+
+        // Wait before the next poll according to the configured policy
+        poller.wait(did_work);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ctl_feed::{EventType, MediumTag, MemoryRing, MessageHeader, RingConsumer, RingLike};
+
+    fn trade(id: u64, seq: u64) -> RawMessage {
+        let payload = format!(r#"{{"e":"trade","s":"BTCUSDT","t":{},"p":"1.0"}}"#, id);
+        let mut message = RawMessage { header: MessageHeader::new(EventType::Trade, 0), ..RawMessage::default() };
+        message.header.medium = MediumTag::Json as u8;
+        message.header.stamp(seq, 1_000);
+        message.data[..payload.len()].copy_from_slice(payload.as_bytes());
+        message
+    }
+
+    #[test]
+    fn test_forward_first_copy() {
+        let out = MemoryRing::<RawMessage>::new(8);
+        let alerts = MemoryRing::<AlertMessage>::new(4);
+        let (out_metrics, a_metrics, b_metrics) =
+            (RingMetrics::default(), RingMetrics::default(), RingMetrics::default());
+        let cursors = [&a_metrics.consumers[0], &b_metrics.consumers[0]];
+        let lines = [&a_metrics, &b_metrics];
+        let mut arbitrated =
+            Arbitrated::new(&out, &out_metrics, lines, cursors, 64, &alerts, ArbitrationConfig::default(), 0);
+        let mut consumer = out.consumer();
+
+        // Line B is ahead on the second trade, line A on the first and third
+        let pushes = [(Line::A, trade(1, 1)), (Line::B, trade(1, 1)), (Line::B, trade(2, 2)), (Line::A, trade(2, 2))];
+        for (line, message) in pushes.into_iter().chain([(Line::A, trade(3, 3))]) {
+            assert!(arbitrated.on_consume(line, RingConsume::Message(message), 1_000).unwrap());
+        }
+        let forwarded: Vec<u64> = std::iter::from_fn(|| match consumer.consume() {
+            RingConsume::Message(message) => Some(message.header.seq),
+            _ => None,
+        })
+        .collect();
+        // Sequenced anew in the pub-sub ring
+        assert_eq!(forwarded, vec![1, 2, 3]);
+        assert_eq!(out_metrics.head.load(Ordering::Relaxed), 3);
+        assert_eq!(arbitrated.arbiter.stats(Line::B).forwarded, 1);
+        assert_eq!(arbitrated.arbiter.stats(Line::A).duplicates, 1);
+        assert_eq!(a_metrics.consumers[0].position.load(Ordering::Relaxed), 3);
+    }
+
+    #[test]
+    fn test_sped_past_line() {
+        let out = MemoryRing::<RawMessage>::new(8);
+        let alerts = MemoryRing::<AlertMessage>::new(4);
+        let mut alert_consumer = alerts.consumer();
+        let (out_metrics, a_metrics, b_metrics) =
+            (RingMetrics::default(), RingMetrics::default(), RingMetrics::default());
+        let cursors = [&a_metrics.consumers[0], &b_metrics.consumers[0]];
+        let lines = [&a_metrics, &b_metrics];
+        let mut arbitrated =
+            Arbitrated::new(&out, &out_metrics, lines, cursors, 64, &alerts, ArbitrationConfig::default(), 0);
+
+        for _ in 0..10 {
+            a_metrics.record_publish();
+        }
+        assert!(arbitrated.on_consume(Line::A, RingConsume::SpedPast, 1_000).unwrap());
+        assert_eq!(a_metrics.consumers[0].overruns.load(Ordering::Relaxed), 1);
+        let RingConsume::Message(alert) = alert_consumer.consume() else {
+            panic!("expected an overflow alert");
+        };
+        assert_eq!(alert.kind(), AlertKind::RingOverflow);
+    }
+
+    #[test]
+    fn test_silence_alerts() {
+        let out = MemoryRing::<RawMessage>::new(8);
+        let alerts = MemoryRing::<AlertMessage>::new(4);
+        let mut alert_consumer = alerts.consumer();
+        let (out_metrics, a_metrics, b_metrics) =
+            (RingMetrics::default(), RingMetrics::default(), RingMetrics::default());
+        let cursors = [&a_metrics.consumers[0], &b_metrics.consumers[0]];
+        let lines = [&a_metrics, &b_metrics];
+        let mut arbitrated =
+            Arbitrated::new(&out, &out_metrics, lines, cursors, 64, &alerts, ArbitrationConfig::default(), 0);
+
+        // Line B carries the trades alone
+        arbitrated.on_consume(Line::B, RingConsume::Message(trade(1, 1)), 6_000).unwrap();
+        arbitrated.check_silence(6_000);
+        let RingConsume::Message(alert) = alert_consumer.consume() else {
+            panic!("expected a silent line alert");
+        };
+        assert_eq!((alert.kind(), alert.severity), (AlertKind::LineSilent, AlertSeverity::Warning as u8));
+        arbitrated.check_silence(6_500);
+        assert!(matches!(alert_consumer.consume(), RingConsume::Empty));
+
+        arbitrated.on_consume(Line::A, RingConsume::Message(trade(1, 1)), 7_000).unwrap();
+        arbitrated.check_silence(7_000);
+        let RingConsume::Message(alert) = alert_consumer.consume() else {
+            panic!("expected a recovered line alert");
+        };
+        assert_eq!(alert.severity, AlertSeverity::Info as u8);
+    }
+}
@@ -0,0 +1,352 @@
+//! Configuration module for the backtester.
+//!
+//! This module provides the YAML parser and validation for the backtest
+//! configuration defined in `configs/backtester/backtest.yaml`.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use ctl_core::CONTROL_TARGET_SIZE;
+use serde::Deserialize;
+
+use crate::{BacktestConfigError, QuoterConfig, TriangleConfig, WasmLimits};
+
+/// The session, models and strategy of a backtest.
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+pub struct BacktestConfig {
+    /// The recorded session replayed.
+    pub session_path: String,
+    /// The paper trading configuration of the simulated exchange.
+    pub paper_config_path: String,
+    /// The file the fills are written to as `executionReport` payloads, if any.
+    #[serde(default)]
+    pub fills_path: Option<String>,
+    /// The component ID of the strategy in its client order IDs.
+    pub component_id: u16,
+    /// The configuration of the touch quoter, unless another strategy is tested.
+    #[serde(default)]
+    pub quoter: Option<QuoterConfig>,
+    /// The configuration of the triangular arbitrage, in place of the touch quoter.
+    #[serde(default)]
+    pub triangle: Option<TriangleConfig>,
+    /// The strategy plugin tested, in place of the touch quoter.
+    #[serde(default)]
+    pub plugin: Option<PluginConfig>,
+    /// The sandboxed WASM strategy tested, in place of the touch quoter.
+    #[serde(default)]
+    pub wasm: Option<WasmConfig>,
+    /// The catalog of the strategy plugins, by name, in place of the touch
+    /// quoter. The session follows the control ring, loading, unloading and
+    /// swapping them on the commands of ctl-admin.
+    #[serde(default)]
+    pub plugins: HashMap<String, PluginConfig>,
+    /// The plugin of the catalog running from the start of the session, none
+    /// until loaded otherwise.
+    #[serde(default)]
+    pub initial_plugin: Option<String>,
+}
+
+/// Default fuel of each callback of a WASM strategy.
+const DEFAULT_WASM_FUEL: u64 = 10_000_000;
+
+/// Default largest linear memory of a WASM strategy, in bytes.
+const DEFAULT_WASM_MAX_MEMORY_BYTES: usize = 64 << 20;
+
+fn default_wasm_fuel() -> u64 {
+    DEFAULT_WASM_FUEL
+}
+
+fn default_wasm_max_memory_bytes() -> usize {
+    DEFAULT_WASM_MAX_MEMORY_BYTES
+}
+
+/// Returns the configuration of a strategy as JSON.
+fn config_json(config: &serde_yaml::Value) -> Result<String, BacktestConfigError> {
+    serde_json::to_string(config).map_err(|e| {
+        BacktestConfigError::ValidationError(format!("Strategy configuration isn't JSON compatible: {}", e))
+    })
+}
+
+/// A strategy plugin and its configuration.
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+pub struct PluginConfig {
+    /// The path of the shared object of the plugin.
+    pub path: String,
+    /// The configuration of the strategy, passed to the plugin as JSON.
+    #[serde(default)]
+    pub config: serde_yaml::Value,
+}
+
+impl PluginConfig {
+    /// Returns the configuration of the strategy as JSON.
+    ///
+    /// # Errors
+    /// Returns an error if the configuration has no JSON equivalent, e.g. a non-string key.
+    pub fn config_json(&self) -> Result<String, BacktestConfigError> {
+        config_json(&self.config)
+    }
+}
+
+/// A sandboxed WASM strategy, its configuration and limits.
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+pub struct WasmConfig {
+    /// The path of the WASM module.
+    pub path: String,
+    /// The configuration of the strategy, passed to the module as JSON.
+    #[serde(default)]
+    pub config: serde_yaml::Value,
+    /// The fuel of each callback, roughly one per instruction.
+    #[serde(default = "default_wasm_fuel")]
+    pub fuel: u64,
+    /// The largest linear memory of the module, in bytes.
+    #[serde(default = "default_wasm_max_memory_bytes")]
+    pub max_memory_bytes: usize,
+}
+
+impl WasmConfig {
+    /// Returns the configuration of the strategy as JSON.
+    ///
+    /// # Errors
+    /// Returns an error if the configuration has no JSON equivalent, e.g. a non-string key.
+    pub fn config_json(&self) -> Result<String, BacktestConfigError> {
+        config_json(&self.config)
+    }
+
+    /// Returns the limits of the module.
+    pub fn limits(&self) -> WasmLimits {
+        WasmLimits { fuel: self.fuel, max_memory_bytes: self.max_memory_bytes }
+    }
+}
+
+impl BacktestConfig {
+    /// Parses the backtest configuration from a YAML file.
+    ///
+    /// # Errors
+    /// Returns an error if the file cannot be read, parsed, or fails validation.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, BacktestConfigError> {
+        let content = fs::read_to_string(path)?;
+        Self::from_str(&content)
+    }
+
+    /// Parses the backtest configuration from a YAML string.
+    ///
+    /// # Errors
+    /// Returns an error if the YAML cannot be parsed or fails validation.
+    pub fn from_str(content: &str) -> Result<Self, BacktestConfigError> {
+        let config: Self = serde_yaml::from_str(content)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Validates the backtest configuration.
+    fn validate(&self) -> Result<(), BacktestConfigError> {
+        let catalog = !self.plugins.is_empty() || self.initial_plugin.is_some();
+        let quoter = match (&self.quoter, &self.triangle, &self.plugin, &self.wasm, catalog) {
+            (Some(quoter), None, None, None, false) => quoter,
+            (None, Some(triangle), None, None, false) => return Self::validate_triangle(triangle),
+            (None, None, Some(plugin), None, false) => {
+                if plugin.path.is_empty() {
+                    return Err(BacktestConfigError::ValidationError("Plugin path cannot be empty".to_string()));
+                }
+                plugin.config_json()?;
+                return Ok(());
+            }
+            (None, None, None, Some(wasm), false) => {
+                if wasm.path.is_empty() {
+                    return Err(BacktestConfigError::ValidationError("WASM path cannot be empty".to_string()));
+                }
+                if wasm.fuel == 0 {
+                    return Err(BacktestConfigError::ValidationError("WASM fuel must be greater than 0".to_string()));
+                }
+                wasm.config_json()?;
+                return Ok(());
+            }
+            (None, None, None, None, true) => return self.validate_plugins(),
+            _ => {
+                return Err(BacktestConfigError::ValidationError(
+                    "Exactly one of 'quoter', 'triangle', 'plugin', 'plugins' and 'wasm' must be configured"
+                        .to_string(),
+                ));
+            }
+        };
+        if quoter.symbols.is_empty() {
+            return Err(BacktestConfigError::ValidationError(
+                "At least one symbol must be quoted".to_string(),
+            ));
+        }
+        if quoter.qty <= 0.0 {
+            return Err(BacktestConfigError::ValidationError(format!(
+                "Quote quantity must be greater than 0, got {}",
+                quoter.qty
+            )));
+        }
+        if quoter.max_position < quoter.qty {
+            return Err(BacktestConfigError::ValidationError(format!(
+                "Maximum position {} is below the quote quantity {}",
+                quoter.max_position, quoter.qty
+            )));
+        }
+        Ok(())
+    }
+
+    /// Validates the catalog of the strategy plugins.
+    fn validate_plugins(&self) -> Result<(), BacktestConfigError> {
+        for (name, plugin) in &self.plugins {
+            // The name is the target of the control commands
+            if name.is_empty() || name.len() > CONTROL_TARGET_SIZE {
+                return Err(BacktestConfigError::ValidationError(format!(
+                    "Plugin name '{}' must be 1 to {} bytes",
+                    name, CONTROL_TARGET_SIZE
+                )));
+            }
+            if plugin.path.is_empty() {
+                return Err(BacktestConfigError::ValidationError(format!("Plugin '{}' path cannot be empty", name)));
+            }
+            plugin.config_json()?;
+        }
+        match &self.initial_plugin {
+            Some(initial) if !self.plugins.contains_key(initial) => Err(BacktestConfigError::ValidationError(
+                format!("Initial plugin '{}' isn't in the catalog", initial),
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// Validates the configuration of the triangular arbitrage.
+    fn validate_triangle(triangle: &TriangleConfig) -> Result<(), BacktestConfigError> {
+        let [bridge, cross, direct] = triangle.symbols();
+        let distinct = bridge != cross && cross != direct && bridge != direct;
+        if bridge.is_empty() || cross.is_empty() || direct.is_empty() || !distinct {
+            return Err(BacktestConfigError::ValidationError("The triangle needs three distinct symbols".to_string()));
+        }
+        let valid = triangle.qty.is_finite()
+            && triangle.qty > 0.0
+            && triangle.min_edge_bps.is_finite()
+            && triangle.fee_bps.is_finite()
+            && triangle.fee_bps >= 0.0;
+        if !valid {
+            return Err(BacktestConfigError::ValidationError(format!("Invalid triangle {:?}", triangle)));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = "
+session_path: data/sessions/session.tsv
+paper_config_path: configs/oms/paper.yaml
+component_id: 900
+quoter:
+  symbols: [BTCUSDT]
+  qty: 0.001
+  max_position: 0.01
+";
+
+    #[test]
+    fn test_parse_config() {
+        let config = BacktestConfig::from_str(CONFIG).unwrap();
+        assert_eq!(config.session_path, "data/sessions/session.tsv");
+        assert_eq!(config.fills_path, None);
+        assert_eq!(config.component_id, 900);
+        assert_eq!(config.quoter.unwrap().symbols, vec!["BTCUSDT"]);
+    }
+
+    #[test]
+    fn test_parse_plugin() {
+        let quoter_start = CONFIG.find("quoter:").unwrap();
+        let plugin = "
+plugin:
+  path: target/release/libmaker.so
+  config:
+    symbols: [BTCUSDT]
+    spread_bps: 5
+";
+        let config = BacktestConfig::from_str(&format!("{}{}", &CONFIG[..quoter_start], plugin)).unwrap();
+        assert_eq!(config.quoter, None);
+        let parsed = config.plugin.unwrap();
+        assert_eq!(parsed.path, "target/release/libmaker.so");
+        assert_eq!(parsed.config_json().unwrap(), r#"{"symbols":["BTCUSDT"],"spread_bps":5}"#);
+
+        // Either the quoter or a plugin
+        assert!(BacktestConfig::from_str(&format!("{}{}", CONFIG, plugin)).is_err());
+        assert!(BacktestConfig::from_str(&CONFIG[..quoter_start]).is_err());
+    }
+
+    #[test]
+    fn test_parse_plugins() {
+        let quoter_start = CONFIG.find("quoter:").unwrap();
+        let plugins = "
+plugins:
+  maker:
+    path: target/release/libmaker.so
+  maker-v2:
+    path: target/release/v2/libmaker.so
+    config:
+      spread_bps: 5
+initial_plugin: maker
+";
+        let config = BacktestConfig::from_str(&format!("{}{}", &CONFIG[..quoter_start], plugins)).unwrap();
+        assert_eq!(config.plugins.len(), 2);
+        assert_eq!(config.plugins["maker-v2"].config_json().unwrap(), r#"{"spread_bps":5}"#);
+        assert_eq!(config.initial_plugin.as_deref(), Some("maker"));
+
+        // Either the quoter or a catalog, the initial plugin being of the catalog
+        assert!(BacktestConfig::from_str(&format!("{}{}", CONFIG, plugins)).is_err());
+        let content = format!("{}{}", &CONFIG[..quoter_start], plugins.replace("plugin: maker", "plugin: taker"));
+        assert!(BacktestConfig::from_str(&content).is_err());
+        assert!(BacktestConfig::from_str(&format!("{}initial_plugin: maker\n", CONFIG)).is_err());
+    }
+
+    #[test]
+    fn test_parse_wasm() {
+        let quoter_start = CONFIG.find("quoter:").unwrap();
+        let wasm = "
+wasm:
+  path: strategies/maker.wasm
+  fuel: 1000000
+";
+        let config = BacktestConfig::from_str(&format!("{}{}", &CONFIG[..quoter_start], wasm)).unwrap();
+        let parsed = config.wasm.unwrap();
+        assert_eq!(parsed.limits(), WasmLimits { fuel: 1_000_000, max_memory_bytes: DEFAULT_WASM_MAX_MEMORY_BYTES });
+        assert_eq!(parsed.config_json().unwrap(), "null");
+
+        let content = format!("{}{}", &CONFIG[..quoter_start], wasm.replace("1000000", "0"));
+        assert!(BacktestConfig::from_str(&content).is_err());
+    }
+
+    #[test]
+    fn test_parse_triangle() {
+        let quoter_start = CONFIG.find("quoter:").unwrap();
+        let triangle = "
+triangle:
+  bridge_symbol: BTCUSDT
+  cross_symbol: ETHBTC
+  direct_symbol: ETHUSDT
+  qty: 0.01
+  min_edge_bps: 2
+";
+        let config = BacktestConfig::from_str(&format!("{}{}", &CONFIG[..quoter_start], triangle)).unwrap();
+        let parsed = config.triangle.unwrap();
+        assert_eq!(parsed.symbols(), ["BTCUSDT", "ETHBTC", "ETHUSDT"]);
+        assert_eq!((parsed.fee_bps, parsed.leg_timeout_ms, parsed.cooldown_ms), (10.0, 1_000, 1_000));
+
+        assert!(BacktestConfig::from_str(&format!("{}{}", CONFIG, triangle)).is_err());
+        let content = format!("{}{}", &CONFIG[..quoter_start], triangle.replace("ETHBTC", "ETHUSDT"));
+        assert!(BacktestConfig::from_str(&content).is_err());
+        let content = format!("{}{}", &CONFIG[..quoter_start], triangle.replace("qty: 0.01", "qty: 0"));
+        assert!(BacktestConfig::from_str(&content).is_err());
+    }
+
+    #[test]
+    fn test_invalid_quoter() {
+        assert!(BacktestConfig::from_str(&CONFIG.replace("qty: 0.001", "qty: 0")).is_err());
+        assert!(BacktestConfig::from_str(&CONFIG.replace("[BTCUSDT]", "[]")).is_err());
+
+        let result = BacktestConfig::from_str(&CONFIG.replace("max_position: 0.01", "max_position: 0.0001"));
+        assert!(result.unwrap_err().to_string().contains("below"));
+    }
+}
@@ -0,0 +1,315 @@
+//! The backtest loop, wiring the replayed session, the paper exchange and a strategy.
+//!
+//! The session drives a replayed clock: every event advances the paper exchange
+//! to its receive time, so a run over the same session and configuration is
+//! deterministic. Execution reports are applied to the order table and, as the
+//! `executionReport` payloads of the live stream, to the positions.
+
+use std::fmt;
+
+use ctl_core::ClientOrderIdGenerator;
+use ctl_oms::{
+    ExecutionType, OmsError, Order, OrderStatus, OrderTable, PaperConfig, PaperExchange, PaperReport, Quote,
+};
+use ctl_position::{ExecutionReport, Position};
+use hashbrown::HashMap;
+
+use crate::{Context, MarketEvent, OrderAction, SessionEvent, Strategy};
+
+/// The outcome of a backtest over a symbol.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SymbolReport {
+    /// The symbol.
+    pub symbol: String,
+    /// The final position.
+    pub position: Position,
+    /// The last mid price, zero without a best bid/ask.
+    pub mark_price: f64,
+}
+
+impl SymbolReport {
+    /// Returns the unrealized PnL of the final position at the mark price.
+    pub fn unrealized_pnl(&self) -> f64 {
+        self.position.unrealized_pnl(self.mark_price)
+    }
+
+    /// Returns the realized and unrealized PnL, net of the quote commissions.
+    pub fn pnl(&self) -> f64 {
+        self.position.realized_pnl + self.unrealized_pnl()
+    }
+}
+
+/// The outcome of a backtest.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BacktestReport {
+    /// The number of market data events replayed.
+    pub events: u64,
+    /// The number of orders submitted.
+    pub orders: u64,
+    /// The number of fills.
+    pub fills: u64,
+    /// The number of fills as maker.
+    pub maker_fills: u64,
+    /// The number of orders canceled.
+    pub cancels: u64,
+    /// The number of orders rejected.
+    pub rejects: u64,
+    /// The notional volume of the fills.
+    pub notional: f64,
+    /// The commissions of the fills, in the fee asset.
+    pub commission: f64,
+    /// The number of orders still open at the end of the session.
+    pub open_orders: usize,
+    /// The outcome of each symbol traded, by symbol.
+    pub symbols: Vec<SymbolReport>,
+}
+
+impl BacktestReport {
+    /// Returns the PnL over all the symbols.
+    pub fn pnl(&self) -> f64 {
+        self.symbols.iter().map(SymbolReport::pnl).sum()
+    }
+}
+
+impl fmt::Display for BacktestReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Events:      {}", self.events)?;
+        writeln!(
+            f,
+            "Orders:      {} ({} canceled, {} rejected, {} open)",
+            self.orders, self.cancels, self.rejects, self.open_orders
+        )?;
+        writeln!(f, "Fills:       {} ({} maker)", self.fills, self.maker_fills)?;
+        writeln!(f, "Notional:    {:.8}", self.notional)?;
+        writeln!(f, "Commission:  {:.8}", self.commission)?;
+        for symbol in &self.symbols {
+            writeln!(
+                f,
+                "[{}] position: {:.8} @ {:.8}, mark: {:.8}, realized: {:.8}, unrealized: {:.8}, fills: {}",
+                symbol.symbol,
+                symbol.position.base,
+                symbol.position.avg_entry_price,
+                symbol.mark_price,
+                symbol.position.realized_pnl,
+                symbol.unrealized_pnl(),
+                symbol.position.fill_count,
+            )?;
+        }
+        write!(f, "PnL:         {:.8}", self.pnl())
+    }
+}
+
+/// A backtest of a strategy against the paper exchange.
+pub struct Backtest<S> {
+    /// The strategy under test.
+    strategy: S,
+    /// The simulated exchange.
+    exchange: PaperExchange,
+    /// The orders of the strategy.
+    orders: OrderTable,
+    /// The generator of the client order IDs.
+    ids: ClientOrderIdGenerator,
+    /// The positions, by symbol.
+    positions: HashMap<String, Position>,
+    /// The last mid prices, by symbol.
+    marks: HashMap<String, f64>,
+    /// The fill reports, in execution order.
+    fills: Vec<PaperReport>,
+    /// The counters of the report.
+    report: BacktestReport,
+    /// The replayed time, in milliseconds.
+    now_ms: u64,
+}
+
+impl<S: Strategy> Backtest<S> {
+    /// Creates a backtest of `strategy`, identified by `component_id` in its client order IDs.
+    ///
+    /// The IDs have a zero epoch, so a run always issues the same IDs.
+    pub fn new(strategy: S, config: PaperConfig, component_id: u16) -> Self {
+        Self {
+            strategy,
+            exchange: PaperExchange::new(config),
+            orders: OrderTable::default(),
+            ids: ClientOrderIdGenerator::new(component_id, 0),
+            positions: HashMap::new(),
+            marks: HashMap::new(),
+            fills: Vec::new(),
+            report: BacktestReport::default(),
+            now_ms: 0,
+        }
+    }
+
+    /// Returns the strategy under test, e.g. to apply the control commands of a `PluginHost`.
+    pub fn strategy_mut(&mut self) -> &mut S {
+        &mut self.strategy
+    }
+
+    /// Returns the fill reports, in execution order.
+    pub fn fills(&self) -> &[PaperReport] {
+        &self.fills
+    }
+
+    /// Replays a market data event to the paper exchange, then the strategy.
+    ///
+    /// # Errors
+    /// Returns an error if a report is an invalid transition of its order,
+    /// which means the paper exchange and the order table disagree.
+    pub fn on_event(&mut self, event: &SessionEvent) -> Result<(), OmsError> {
+        // Receive times may step back across connections, the clock doesn't
+        self.now_ms = self.now_ms.max(event.recv_time_ms);
+        self.report.events += 1;
+
+        let reports = match event.event {
+            MarketEvent::Top(top) => {
+                self.marks.insert(event.symbol.clone(), (top.bid_price + top.ask_price) / 2.0);
+                let quote = Quote {
+                    bid_price: top.bid_price,
+                    bid_qty: top.bid_qty,
+                    ask_price: top.ask_price,
+                    ask_qty: top.ask_qty,
+                };
+                self.exchange.on_quote(&event.symbol, quote, self.now_ms)
+            }
+            MarketEvent::Trade { price, qty } => self.exchange.on_trade(&event.symbol, price, qty, self.now_ms),
+        };
+        for report in &reports {
+            self.on_report(report)?;
+        }
+
+        let mut actions = Vec::new();
+        let mut ctx = Context::new(&mut self.ids, &mut actions, self.now_ms);
+        match event.event {
+            MarketEvent::Top(top) => self.strategy.on_top(&event.symbol, &top, &mut ctx),
+            MarketEvent::Trade { price, qty } => self.strategy.on_trade(&event.symbol, price, qty, &mut ctx),
+        }
+        self.apply(actions)
+    }
+
+    /// Ends the backtest, returning its outcome.
+    pub fn finish(self) -> BacktestReport {
+        let mut report = self.report;
+        report.open_orders = self.orders.open_orders().count();
+
+        let mut symbols: Vec<_> = self
+            .positions
+            .into_iter()
+            .map(|(symbol, position)| {
+                let mark_price = self.marks.get(&symbol).copied().unwrap_or_default();
+                SymbolReport { symbol, position, mark_price }
+            })
+            .collect();
+        symbols.sort_by(|a, b| a.symbol.cmp(&b.symbol));
+        report.symbols = symbols;
+        report
+    }
+
+    /// Applies an execution report to the orders, the positions and the strategy.
+    fn on_report(&mut self, report: &PaperReport) -> Result<(), OmsError> {
+        self.orders.update(&report.update)?;
+
+        match report.execution_type {
+            ExecutionType::Trade => {
+                self.report.fills += 1;
+                self.report.maker_fills += u64::from(report.is_maker);
+                self.report.notional += report.last_price * report.last_qty;
+                self.report.commission += report.commission;
+
+                let json = report.to_json();
+                if let Some(fill) = ExecutionReport::from_json(json.as_bytes()).and_then(|r| r.fill()) {
+                    self.positions.entry(report.symbol.clone()).or_default().apply(&fill);
+                }
+                self.fills.push(report.clone());
+            }
+            ExecutionType::Canceled => self.report.cancels += 1,
+            ExecutionType::Rejected => self.report.rejects += 1,
+            ExecutionType::New => {}
+        }
+
+        let mut actions = Vec::new();
+        let mut ctx = Context::new(&mut self.ids, &mut actions, self.now_ms);
+        self.strategy.on_report(report, &mut ctx);
+        self.apply(actions)
+    }
+
+    /// Sends the order actions of the strategy to the paper exchange.
+    fn apply(&mut self, actions: Vec<OrderAction>) -> Result<(), OmsError> {
+        for action in actions {
+            match action {
+                OrderAction::Submit { client_order_id, symbol, side, price, qty } => {
+                    let order = Order {
+                        client_order_id,
+                        order_id: None,
+                        symbol,
+                        side,
+                        price,
+                        orig_qty: qty,
+                        executed_qty: 0.0,
+                        status: OrderStatus::PendingNew,
+                        list_client_order_id: None,
+                    };
+                    self.orders.insert(order.clone())?;
+                    self.exchange.submit(order, self.now_ms);
+                    self.report.orders += 1;
+                }
+                OrderAction::Cancel { client_order_id } => self.exchange.cancel(&client_order_id, self.now_ms),
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{QuoterConfig, Replayer, TouchQuoter};
+
+    const SESSION: &str = concat!(
+        "1000\ttop\t{\"u\":1,\"s\":\"BTCUSDT\",\"b\":\"99.0\",\"B\":\"1.0\",\"a\":\"101.0\",\"A\":\"1.0\"}\n",
+        "1010\ttrade\t{\"e\":\"trade\",\"s\":\"BTCUSDT\",\"t\":1,\"p\":\"98.5\",\"q\":\"2.0\",\"T\":1010}\n",
+        "1020\ttrade\t{\"e\":\"trade\",\"s\":\"BTCUSDT\",\"t\":2,\"p\":\"101.5\",\"q\":\"2.0\",\"T\":1020}\n",
+        "1030\ttop\t{\"u\":2,\"s\":\"BTCUSDT\",\"b\":\"99.0\",\"B\":\"1.0\",\"a\":\"101.0\",\"A\":\"1.0\"}\n",
+    );
+
+    fn run() -> (BacktestReport, Vec<PaperReport>) {
+        let config = PaperConfig::from_str("latency_ms: 5\nmaker_fee_bps: 10.0\nfee_asset: USDT\n").unwrap();
+        let strategy = TouchQuoter::new(QuoterConfig {
+            symbols: vec!["BTCUSDT".to_string()],
+            qty: 1.0,
+            max_position: 1.0,
+        });
+        let mut backtest = Backtest::new(strategy, config, 7);
+        for event in Replayer::new(SESSION.as_bytes()) {
+            backtest.on_event(&event.unwrap()).unwrap();
+        }
+        let fills = backtest.fills().to_vec();
+        (backtest.finish(), fills)
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let (report, fills) = run();
+        assert_eq!(report.events, 4);
+        assert_eq!(report.orders, 4);
+
+        // The bid fills on the trade through it, the ask on the one through it
+        assert_eq!(report.fills, 2);
+        assert_eq!(report.maker_fills, 2);
+        assert_eq!((fills[0].side, fills[0].last_price), (ctl_oms::Side::Buy, 99.0));
+        assert_eq!((fills[1].side, fills[1].last_price), (ctl_oms::Side::Sell, 101.0));
+        assert!((report.commission - 0.2).abs() < 1e-9);
+
+        // Re-quoted at the touch after the round trip
+        assert_eq!(report.open_orders, 2);
+        let symbol = &report.symbols[0];
+        assert_eq!(symbol.symbol, "BTCUSDT");
+        assert_eq!(symbol.position.base, 0.0);
+        assert_eq!(symbol.mark_price, 100.0);
+        assert!((report.pnl() - 1.8).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_deterministic() {
+        assert_eq!(run(), run());
+    }
+}
@@ -0,0 +1,72 @@
+use thiserror::Error;
+
+/// Errors that can occur when parsing or validating the backtest configuration.
+#[derive(Debug, Error)]
+pub enum BacktestConfigError {
+    /// Error reading the configuration file.
+    #[error("Failed to read configuration file: {0}")]
+    FileReadError(#[from] std::io::Error),
+    /// Error parsing the YAML configuration.
+    #[error("Failed to parse YAML configuration: {0}")]
+    YamlParseError(#[from] serde_yaml::Error),
+    /// Validation error with a descriptive message.
+    #[error("Configuration validation error: {0}")]
+    ValidationError(String),
+}
+
+/// Errors that can occur when loading a strategy plugin.
+#[derive(Debug, Error)]
+pub enum PluginError {
+    /// Error loading the shared object or its vtable.
+    #[error("Failed to load strategy plugin: {0}")]
+    Load(#[from] libloading::Error),
+    /// The plugin returned no vtable.
+    #[error("Strategy plugin returned no vtable")]
+    MissingVtable,
+    /// The plugin was built for another version of the vtable.
+    #[error("Strategy plugin built for ABI version {found}, expected {expected}")]
+    AbiVersion { expected: u32, found: u32 },
+    /// The plugin refused its configuration.
+    #[error("Strategy plugin refused its configuration")]
+    Refused,
+    /// No plugin of this name in the catalog of the host.
+    #[error("No strategy plugin named '{0}'")]
+    UnknownPlugin(String),
+    /// The plugin isn't the one running.
+    #[error("Strategy plugin '{0}' isn't running")]
+    NotLoaded(String),
+    /// No plugin is running.
+    #[error("No strategy plugin running")]
+    NoneRunning,
+    /// A plugin is already running, to be swapped or unloaded first.
+    #[error("Strategy plugin '{0}' is already running, swap or unload it first")]
+    AlreadyLoaded(String),
+}
+
+/// Errors that can occur when loading a WASM strategy.
+#[derive(Debug, Error)]
+pub enum WasmError {
+    /// Error reading the module.
+    #[error("Failed to read WASM module: {0}")]
+    IoError(#[from] std::io::Error),
+    /// Error compiling, instantiating or calling the module, e.g. a missing export.
+    #[error("WASM module error: {0}")]
+    Wasm(#[from] wasmi::Error),
+    /// The module exports no memory.
+    #[error("WASM module exports no {0}")]
+    MissingExport(&'static str),
+    /// The module refused its configuration, with its status.
+    #[error("WASM module refused its configuration with status {0}")]
+    Refused(i32),
+}
+
+/// Errors that can occur when replaying a recorded session.
+#[derive(Debug, Error)]
+pub enum ReplayError {
+    /// A session record isn't `{recv_time_ms}\t{kind}\t{payload}`.
+    #[error("Invalid session record at line {line}: {reason}")]
+    InvalidRecord { line: usize, reason: String },
+    /// Error reading the session file.
+    #[error("Session I/O error: {0}")]
+    IoError(#[from] std::io::Error),
+}
@@ -0,0 +1,32 @@
+//! Deterministic backtests for Binance Spot.
+//!
+//! Replays a recorded market data session through the paper exchange of
+//! ctl-oms and a strategy in a single process, in place of the rings, and
+//! reports the fills and PnL of the strategy at the end of the session.
+//!
+//! The strategy is one of the examples, the touch quoter or the triangular
+//! arbitrage, or one loaded from a shared object implementing the C-compatible
+//! vtable of the `plugin` module, or an experimental one run as a sandboxed
+//! WASM module by the `wasm` module. The plugins of a catalog are hosted by a
+//! `PluginHost`, loaded, unloaded and hot-swapped on the commands of ctl-admin.
+
+mod config;
+mod engine;
+mod errors;
+mod plugin;
+mod replay;
+mod strategy;
+mod triangle;
+mod wasm;
+
+pub use config::{BacktestConfig, PluginConfig, WasmConfig};
+pub use engine::{Backtest, BacktestReport, SymbolReport};
+pub use errors::{BacktestConfigError, PluginError, ReplayError, WasmError};
+pub use plugin::{
+    PluginHost, PluginStrategy, StrategyHost, StrategyReport, StrategyStr, StrategyTop, StrategyVtable, SIDE_BUY, SIDE_SELL,
+    STRATEGY_ABI_VERSION, STRATEGY_VTABLE_SYMBOL,
+};
+pub use replay::{MarketEvent, Replayer, SessionEvent};
+pub use strategy::{Context, OrderAction, QuoterConfig, Strategy, TouchQuoter};
+pub use triangle::{cycle_edges_bps, Cycle, TriangleConfig, TriangularArbitrage};
+pub use wasm::{WasmLimits, WasmStrategy, SCRATCH_ID_OFFSET, SCRATCH_SIZE};
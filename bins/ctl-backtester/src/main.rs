@@ -0,0 +1,211 @@
+//! Backtest Harness for Binance Spot.
+//!
+//! This binary replays a recorded session through the paper exchange and the
+//! configured strategy, without DPDK or shared memory, and prints the PnL and
+//! fill report at the end of the session. The strategy is the touch quoter,
+//! the triangular arbitrage, a plugin loaded from a shared object, or a
+//! sandboxed WASM module.
+//!
+//! Given a catalog of plugins instead, the session attaches to the rings of
+//! ctl-resource-manager as a DPDK secondary process, applying the
+//! LoadStrategy, UnloadStrategy and SwapStrategy commands of ctl-admin between
+//! the events and acknowledging them through the alerts ring.
+
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::process::ExitCode;
+
+use ctl_backtester::{
+    Backtest, BacktestConfig, PluginHost, PluginStrategy, Replayer, Strategy, TouchQuoter, TriangularArbitrage,
+    WasmStrategy,
+};
+use ctl_core::{
+    AlertKind, AlertMessage, AlertSeverity, Capability, ControlMessage, Fatal, FatalError, FatalKind, LcoresConfig,
+    Preflight, ALERTS_RING_NAME, CONTROL_RING_NAME,
+};
+use ctl_feed::{MetricsRegion, METRICS_REGION_NAME};
+use ctl_md_handler::HwResourcesConfig;
+use ctl_oms::PaperConfig;
+use ctl_shm::ShmRegion;
+use ctl_time::now_ms;
+use dpdk::{ConsumeStartState, DpdkEnvBuilder, DpdkProcessType, DpdkPubSubRing};
+use tracing::{info, warn};
+
+// Logging configuration, its levels reloaded on change
+const LOG_CONFIG_PATH: &str = "configs/logging.yaml";
+
+// Configuration file path, overridden by the first argument
+const CONFIG_PATH: &str = "configs/backtester/backtest.yaml";
+
+// The market data configuration, holding the EAL options of the secondary processes
+const MD_CONFIG_PATH: &str = "configs/market-data/hw-resources.yaml";
+
+// The lcore following the control ring, unless configured, shared with the mostly idle tools
+const LCORES_CONFIG_PATH: &str = "configs/lcores.yaml";
+const LCORES_COMPONENT: &str = "backtester";
+const DEFAULT_LCORE: u32 = 15;
+
+// The source of the acknowledgements of the strategy commands
+const ALERT_SOURCE: &str = "backtester";
+
+fn main() -> ExitCode {
+    ctl_core::exit_code(run())
+}
+
+fn run() -> Result<(), FatalError> {
+    let _log = ctl_log::init_from_file(LOG_CONFIG_PATH).fatal(FatalKind::Config)?;
+
+    info!("=== Binance Spot Backtester ===");
+
+    let config_path = std::env::args().nth(1).unwrap_or_else(|| CONFIG_PATH.to_string());
+    let config = BacktestConfig::from_file(&config_path).fatal(FatalKind::Config)?;
+    let paper_config = PaperConfig::from_file(&config.paper_config_path).fatal(FatalKind::Config)?;
+
+    info!("Session: {}", config.session_path);
+    info!(
+        "Latency: {}ms, slippage: {}bps, fees: {}/{}bps (maker/taker)",
+        paper_config.latency_ms, paper_config.slippage_bps, paper_config.maker_fee_bps, paper_config.taker_fee_bps
+    );
+
+    if !config.plugins.is_empty() || config.initial_plugin.is_some() {
+        return replay_catalog(&config, paper_config);
+    }
+    let strategy: Box<dyn Strategy> = match (&config.quoter, &config.triangle, &config.plugin, &config.wasm) {
+        (Some(quoter), _, _, _) => {
+            info!("Quoting symbols: {:?}", quoter.symbols);
+            Box::new(TouchQuoter::new(quoter.clone()))
+        }
+        (None, Some(triangle), _, _) => {
+            info!("Arbitraging triangle: {:?}, min edge {} bps", triangle.symbols(), triangle.min_edge_bps);
+            Box::new(TriangularArbitrage::new(triangle.clone()))
+        }
+        (None, None, Some(plugin), _) => {
+            let strategy = PluginStrategy::load(&plugin.path, &plugin.config_json().fatal(FatalKind::Config)?)
+                .fatal(FatalKind::Config)?;
+            info!("Loaded strategy plugin: {}", plugin.path);
+            Box::new(strategy)
+        }
+        (None, None, None, Some(wasm)) => {
+            let strategy = WasmStrategy::load(&wasm.path, &wasm.config_json().fatal(FatalKind::Config)?, wasm.limits())
+                .fatal(FatalKind::Config)?;
+            info!("Loaded WASM strategy: {} ({:?})", wasm.path, wasm.limits());
+            Box::new(strategy)
+        }
+        (None, None, None, None) => unreachable!("validated"),
+    };
+    replay(Backtest::new(strategy, paper_config, config.component_id), &config, |_| {})
+}
+
+/// Replays the session through `backtest`, calling `between` with its strategy
+/// after each event, then writes the fills and prints the report.
+fn replay<S: Strategy>(
+    mut backtest: Backtest<S>,
+    config: &BacktestConfig,
+    mut between: impl FnMut(&mut S),
+) -> Result<(), FatalError> {
+    let mut replayer = Replayer::open(&config.session_path).fatal(FatalKind::Io)?;
+    for event in replayer.by_ref() {
+        backtest.on_event(&event.fatal(FatalKind::Io)?).fatal(FatalKind::Internal)?;
+        between(backtest.strategy_mut());
+    }
+    info!("Skipped {} duplicate events", replayer.duplicates());
+
+    if let Some(fills_path) = &config.fills_path {
+        let mut writer = BufWriter::new(File::create(fills_path)?);
+        for fill in backtest.fills() {
+            writeln!(writer, "{}", fill.to_json())?;
+        }
+        writer.flush()?;
+        info!("Wrote {} fills to {}", backtest.fills().len(), fills_path);
+    }
+
+    println!("=== Backtest Report ===\n");
+    println!("{}", backtest.finish());
+    Ok(())
+}
+
+/// Replays the session through a host of the plugin catalog, following the
+/// strategy commands of the control ring.
+fn replay_catalog(config: &BacktestConfig, paper_config: PaperConfig) -> Result<(), FatalError> {
+    let mut host = PluginHost::new();
+    for (name, plugin) in &config.plugins {
+        host = host.with_plugin(name, &plugin.path, &plugin.config_json().fatal(FatalKind::Config)?);
+        info!("Strategy plugin {}: {}", name, plugin.path);
+    }
+    if let Some(initial) = &config.initial_plugin {
+        host.load(initial).fatal(FatalKind::Config)?;
+        info!("Loaded strategy plugin: {}", initial);
+    }
+
+    let md_config = HwResourcesConfig::from_file(MD_CONFIG_PATH).fatal(FatalKind::Config)?;
+    md_config.eal.apply_region_prefix();
+    let lcore = LcoresConfig::from_file(LCORES_CONFIG_PATH)?.lcore(LCORES_COMPONENT, DEFAULT_LCORE) as usize;
+
+    // Verify the host before the EAL initialization, which reports its failures cryptically
+    Preflight::new()
+        .require_hugepages_mounted()
+        .require_capabilities(&[Capability::IpcLock])
+        .require_primary()
+        .with_file_prefix(md_config.eal.file_prefix())
+        .run()?;
+
+    let dpdk_env = DpdkEnvBuilder::default()
+        .process_type(DpdkProcessType::Secondary)
+        .lcore_ids(vec![lcore])
+        .main_lcore_id(lcore)
+        .eal_args(md_config.eal.secondary_args())
+        .build()
+        .fatal(FatalKind::DpdkInit)?;
+
+    let metrics = ShmRegion::<MetricsRegion>::open(METRICS_REGION_NAME)?;
+    let alerts = dpdk_env.pubsub_lookup::<AlertMessage>(ALERTS_RING_NAME).fatal(FatalKind::SharedState)?;
+    metrics.check_layout::<AlertMessage>(ALERTS_RING_NAME).fatal(FatalKind::SharedState)?;
+    let control = dpdk_env.pubsub_lookup::<ControlMessage>(CONTROL_RING_NAME).fatal(FatalKind::SharedState)?;
+    metrics.check_layout::<ControlMessage>(CONTROL_RING_NAME).fatal(FatalKind::SharedState)?;
+    let mut commands = control.attach_consumer().fatal(FatalKind::SharedState)?;
+    info!("Following the strategy commands of: {}", CONTROL_RING_NAME);
+
+    let backtest = Backtest::new(host, paper_config, config.component_id);
+    replay(backtest, config, |host| {
+        loop {
+            match commands.consume_start() {
+                ConsumeStartState::Success(mut guard) => {
+                    if guard.try_commit().is_err() {
+                        continue;
+                    }
+                    apply_strategy_command(host, guard.as_ref().get(), &alerts);
+                }
+                ConsumeStartState::SpedPast(_guard) => {
+                    warn!("Control consumer overtaken by producer, some commands missed");
+                }
+                ConsumeStartState::InFlight(_) | ConsumeStartState::Empty => break,
+            }
+        }
+    })
+}
+
+/// Applies a strategy command to the plugins of `host`, acknowledging it
+/// through the alerts ring.
+fn apply_strategy_command(host: &mut PluginHost, message: &ControlMessage, alerts: &DpdkPubSubRing<AlertMessage>) {
+    // Trading and market data commands, applied by the OMS and ctl-md-handler
+    let Some(result) = host.on_control(message) else {
+        return;
+    };
+    let (command, name) = (message.command(), message.target());
+    let (severity, detail) = match result {
+        Ok(()) => {
+            let detail = format!("{:?} '{}': running {}", command, name, host.running().unwrap_or("none"));
+            info!("{} ({})", detail, message.reason());
+            (AlertSeverity::Info, detail)
+        }
+        Err(e) => {
+            let detail = format!("{:?} '{}' failed: {}", command, name, e);
+            warn!("{}", detail);
+            (AlertSeverity::Warning, detail)
+        }
+    };
+    let alert = AlertMessage::new(AlertKind::ControlAck, severity, now_ms(), ALERT_SOURCE, &detail);
+    if let Err(e) = alerts.publish(&alert) {
+        warn!("Failed to publish the acknowledgement to {}: {:?}", ALERTS_RING_NAME, e);
+    }
+}
@@ -0,0 +1,607 @@
+//! Strategies loaded from shared objects.
+//!
+//! A strategy plugin is a shared object (a `cdylib`) exporting the function
+//! `ctl_strategy_vtable`, returning a pointer to its `StrategyVtable`. Every
+//! type crossing the boundary is `repr(C)`, so a plugin may be built by another
+//! compiler version than the host: the host creates an instance from the
+//! configuration of the strategy as JSON, then calls it back on each event with
+//! a `StrategyHost`, through which the instance submits and cancels its orders.
+//! A plugin built for another `STRATEGY_ABI_VERSION` is refused.
+//!
+//! The strings passed either way are borrowed for the duration of the call. A
+//! plugin must not unwind out of its callbacks.
+//!
+//! A `PluginHost` runs one plugin of a catalog at a time, applying to it the
+//! LoadStrategy, UnloadStrategy and SwapStrategy commands of ctl-admin, so a
+//! strategy is updated without bouncing the market data of its process. The
+//! dynamic loader returns the plugin already loaded from a path, so a new build
+//! joins the catalog under a path of its own.
+
+use std::ffi::c_void;
+use std::path::{Path, PathBuf};
+use std::{ptr, slice, str};
+
+use ctl_core::{ControlCommand, ControlMessage};
+use ctl_feed::TopSnapshot;
+use ctl_oms::{ExecutionType, PaperReport, Side};
+use hashbrown::HashMap;
+use libloading::Library;
+
+use crate::{Context, PluginError, Strategy};
+
+/// The version of the vtable layout, bumped on any change of the types below.
+pub const STRATEGY_ABI_VERSION: u32 = 1;
+
+/// The symbol of the function returning the vtable of a plugin.
+pub const STRATEGY_VTABLE_SYMBOL: &str = "ctl_strategy_vtable";
+
+/// The side of a buy order.
+pub const SIDE_BUY: u8 = 0;
+
+/// The side of a sell order.
+pub const SIDE_SELL: u8 = 1;
+
+/// A UTF-8 string borrowed for the duration of a call.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct StrategyStr {
+    /// The first byte of the string.
+    pub ptr: *const u8,
+    /// The length of the string, in bytes.
+    pub len: usize,
+}
+
+impl StrategyStr {
+    /// Borrows `s` across the boundary.
+    pub fn new(s: &str) -> Self {
+        Self { ptr: s.as_ptr(), len: s.len() }
+    }
+
+    /// Returns the string, `None` if it isn't UTF-8.
+    ///
+    /// # Safety
+    /// `ptr` must point to `len` bytes alive for `'a`.
+    pub unsafe fn as_str<'a>(self) -> Option<&'a str> {
+        if self.len == 0 {
+            return Some("");
+        }
+        str::from_utf8(unsafe { slice::from_raw_parts(self.ptr, self.len) }).ok()
+    }
+}
+
+/// A best bid/ask update of a symbol, as its `TopSnapshot`.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StrategyTop {
+    pub update_id: u64,
+    pub bid_price: f64,
+    pub bid_qty: f64,
+    pub ask_price: f64,
+    pub ask_qty: f64,
+}
+
+/// An execution report of an order of the strategy.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct StrategyReport {
+    /// The client order ID of the order.
+    pub client_order_id: StrategyStr,
+    /// The symbol of the order.
+    pub symbol: StrategyStr,
+    /// `SIDE_BUY` or `SIDE_SELL`.
+    pub side: u8,
+    /// Whether the report is a fill.
+    pub is_fill: bool,
+    /// Whether the order is done, filled, cancelled, rejected or expired.
+    pub is_terminal: bool,
+    /// The price of the fill, 0 if not a fill.
+    pub last_price: f64,
+    /// The quantity of the fill, 0 if not a fill.
+    pub last_qty: f64,
+    /// The quantity of the order filled so far.
+    pub executed_qty: f64,
+}
+
+/// The callbacks of the host to an instance, valid for the duration of a call.
+#[repr(C)]
+pub struct StrategyHost {
+    /// The context of the host, passed back to its callbacks.
+    pub context: *mut c_void,
+    /// The time of the event, in milliseconds.
+    pub now_ms: u64,
+    /// Submits a limit order, writing its client order ID to `id` (up to
+    /// `id_capacity` bytes) and returning the full length of the ID.
+    pub submit: unsafe extern "C" fn(
+        context: *mut c_void,
+        symbol: StrategyStr,
+        side: u8,
+        price: f64,
+        qty: f64,
+        id: *mut u8,
+        id_capacity: usize,
+    ) -> usize,
+    /// Cancels an order.
+    pub cancel: unsafe extern "C" fn(context: *mut c_void, client_order_id: StrategyStr),
+}
+
+/// The entry points of a strategy plugin.
+#[repr(C)]
+pub struct StrategyVtable {
+    /// The `STRATEGY_ABI_VERSION` the plugin was built for.
+    pub abi_version: u32,
+    /// Creates an instance from its configuration as JSON, null if the configuration is invalid.
+    pub create: unsafe extern "C" fn(config: StrategyStr) -> *mut c_void,
+    /// Destroys an instance.
+    pub destroy: unsafe extern "C" fn(instance: *mut c_void),
+    /// Called on a best bid/ask update of a symbol.
+    pub on_top: unsafe extern "C" fn(
+        instance: *mut c_void,
+        symbol: StrategyStr,
+        top: *const StrategyTop,
+        host: *mut StrategyHost,
+    ),
+    /// Called on a trade of a symbol.
+    pub on_trade: unsafe extern "C" fn(
+        instance: *mut c_void,
+        symbol: StrategyStr,
+        price: f64,
+        qty: f64,
+        host: *mut StrategyHost,
+    ),
+    /// Called on an execution report of one of the orders of the instance.
+    pub on_report:
+        unsafe extern "C" fn(instance: *mut c_void, report: *const StrategyReport, host: *mut StrategyHost),
+}
+
+/// The signature of the exported `ctl_strategy_vtable`.
+type VtableFn = unsafe extern "C" fn() -> *const StrategyVtable;
+
+/// Submits an order through the `Context` behind `context`.
+unsafe extern "C" fn host_submit(
+    context: *mut c_void,
+    symbol: StrategyStr,
+    side: u8,
+    price: f64,
+    qty: f64,
+    id: *mut u8,
+    id_capacity: usize,
+) -> usize {
+    let ctx = unsafe { &mut *context.cast::<Context<'_>>() };
+    let Some(symbol) = (unsafe { symbol.as_str() }) else {
+        return 0;
+    };
+    let side = if side == SIDE_SELL { Side::Sell } else { Side::Buy };
+    let client_order_id = ctx.submit(symbol, side, price, qty);
+    let len = client_order_id.len().min(id_capacity);
+    if !id.is_null() {
+        unsafe { ptr::copy_nonoverlapping(client_order_id.as_ptr(), id, len) };
+    }
+    client_order_id.len()
+}
+
+/// Cancels an order through the `Context` behind `context`.
+unsafe extern "C" fn host_cancel(context: *mut c_void, client_order_id: StrategyStr) {
+    let ctx = unsafe { &mut *context.cast::<Context<'_>>() };
+    if let Some(client_order_id) = unsafe { client_order_id.as_str() } {
+        ctx.cancel(client_order_id);
+    }
+}
+
+/// A strategy instance of a plugin.
+///
+/// Dropping it destroys the instance, then unloads the plugin.
+pub struct PluginStrategy {
+    /// The vtable of the plugin, alive as long as `library`.
+    vtable: *const StrategyVtable,
+    /// The instance, owned.
+    instance: *mut c_void,
+    /// The plugin, `None` for a vtable linked in the host.
+    library: Option<Library>,
+}
+
+impl PluginStrategy {
+    /// Loads the plugin at `path` and creates an instance from `config`, as JSON.
+    ///
+    /// # Errors
+    /// Returns an error if the plugin cannot be loaded, doesn't export its
+    /// vtable, was built for another ABI version, or refuses the configuration.
+    ///
+    /// LATENCY: SLOW_PATH
+    pub fn load<P: AsRef<Path>>(path: P, config: &str) -> Result<Self, PluginError> {
+        // SAFETY: loading runs the initializers of the plugin, trusted as the strategy itself
+        let library = unsafe { Library::new(path.as_ref()) }?;
+        let vtable = unsafe { library.get::<VtableFn>(STRATEGY_VTABLE_SYMBOL.as_bytes())?() };
+        let mut strategy = unsafe { Self::from_vtable(vtable, config) }?;
+        strategy.library = Some(library);
+        Ok(strategy)
+    }
+
+    /// Creates an instance of the strategy of `vtable` from `config`, as JSON.
+    ///
+    /// # Errors
+    /// Returns an error if the vtable is null or of another ABI version, or the
+    /// configuration is refused.
+    ///
+    /// # Safety
+    /// `vtable` must be null or point to a vtable outliving the strategy.
+    pub unsafe fn from_vtable(vtable: *const StrategyVtable, config: &str) -> Result<Self, PluginError> {
+        let Some(table) = (unsafe { vtable.as_ref() }) else {
+            return Err(PluginError::MissingVtable);
+        };
+        if table.abi_version != STRATEGY_ABI_VERSION {
+            return Err(PluginError::AbiVersion { expected: STRATEGY_ABI_VERSION, found: table.abi_version });
+        }
+        let instance = unsafe { (table.create)(StrategyStr::new(config)) };
+        if instance.is_null() {
+            return Err(PluginError::Refused);
+        }
+        Ok(Self { vtable, instance, library: None })
+    }
+
+    /// Hot-swaps the instance for one of the plugin at `path`, created from
+    /// `config`. The current instance is kept if the new one cannot be created.
+    ///
+    /// The orders of the current instance stay live, their reports going to
+    /// the new one.
+    ///
+    /// # Errors
+    /// Returns an error if the new instance cannot be created, as from `load`.
+    ///
+    /// LATENCY: SLOW_PATH
+    pub fn swap<P: AsRef<Path>>(&mut self, path: P, config: &str) -> Result<(), PluginError> {
+        *self = Self::load(path, config)?;
+        Ok(())
+    }
+
+    /// Returns the vtable of the plugin.
+    fn vtable(&self) -> &StrategyVtable {
+        // SAFETY: checked non-null on creation, alive as long as the library
+        unsafe { &*self.vtable }
+    }
+
+    /// Calls `f` with the instance and a host submitting through `ctx`.
+    fn call(&mut self, ctx: &mut Context<'_>, f: impl FnOnce(&StrategyVtable, *mut c_void, *mut StrategyHost)) {
+        let mut host = StrategyHost {
+            now_ms: ctx.now_ms(),
+            context: (ctx as *mut Context<'_>).cast(),
+            submit: host_submit,
+            cancel: host_cancel,
+        };
+        f(self.vtable(), self.instance, &mut host);
+    }
+}
+
+impl Drop for PluginStrategy {
+    fn drop(&mut self) {
+        // The instance is destroyed before its plugin is unloaded
+        unsafe { (self.vtable().destroy)(self.instance) };
+    }
+}
+
+impl Strategy for PluginStrategy {
+    fn on_top(&mut self, symbol: &str, top: &TopSnapshot, ctx: &mut Context<'_>) {
+        let top = StrategyTop {
+            update_id: top.update_id,
+            bid_price: top.bid_price,
+            bid_qty: top.bid_qty,
+            ask_price: top.ask_price,
+            ask_qty: top.ask_qty,
+        };
+        self.call(ctx, |vtable, instance, host| unsafe {
+            (vtable.on_top)(instance, StrategyStr::new(symbol), &top, host)
+        });
+    }
+
+    fn on_trade(&mut self, symbol: &str, price: f64, qty: f64, ctx: &mut Context<'_>) {
+        self.call(ctx, |vtable, instance, host| unsafe {
+            (vtable.on_trade)(instance, StrategyStr::new(symbol), price, qty, host)
+        });
+    }
+
+    fn on_report(&mut self, report: &PaperReport, ctx: &mut Context<'_>) {
+        let report = StrategyReport {
+            client_order_id: StrategyStr::new(&report.update.client_order_id),
+            symbol: StrategyStr::new(&report.symbol),
+            side: match report.side {
+                Side::Buy => SIDE_BUY,
+                Side::Sell => SIDE_SELL,
+            },
+            is_fill: report.execution_type == ExecutionType::Trade,
+            is_terminal: report.update.status.is_terminal(),
+            last_price: report.last_price,
+            last_qty: report.last_qty,
+            executed_qty: report.update.executed_qty,
+        };
+        self.call(ctx, |vtable, instance, host| unsafe { (vtable.on_report)(instance, &report, host) });
+    }
+}
+
+/// Where a plugin of a catalog is created from.
+enum PluginSource {
+    /// A shared object, loaded from its path.
+    Library(PathBuf),
+    /// A vtable linked in the host.
+    Linked(*const StrategyVtable),
+}
+
+/// Runs one strategy plugin of a catalog at a time, loaded, unloaded and
+/// hot-swapped by name.
+///
+/// The events are dropped while no plugin runs, and the reports of the orders
+/// an unloaded plugin left live with them.
+#[derive(Default)]
+pub struct PluginHost {
+    /// The plugins, by name, with their configuration as JSON.
+    catalog: HashMap<String, (PluginSource, String)>,
+    /// The running plugin, with its name.
+    running: Option<(String, PluginStrategy)>,
+}
+
+impl PluginHost {
+    /// Creates a host of an empty catalog.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the plugin at `path` to the catalog as `name`, created from `config`, as JSON.
+    pub fn with_plugin<P: Into<PathBuf>>(mut self, name: &str, path: P, config: &str) -> Self {
+        self.catalog.insert(name.to_string(), (PluginSource::Library(path.into()), config.to_string()));
+        self
+    }
+
+    /// Adds the strategy of `vtable` to the catalog as `name`, created from `config`, as JSON.
+    ///
+    /// # Safety
+    /// `vtable` must be null or point to a vtable outliving the host.
+    pub unsafe fn with_linked_plugin(mut self, name: &str, vtable: *const StrategyVtable, config: &str) -> Self {
+        self.catalog.insert(name.to_string(), (PluginSource::Linked(vtable), config.to_string()));
+        self
+    }
+
+    /// Returns the name of the running plugin, if any.
+    pub fn running(&self) -> Option<&str> {
+        self.running.as_ref().map(|(name, _)| name.as_str())
+    }
+
+    /// Creates an instance of the plugin `name`.
+    fn create(&self, name: &str) -> Result<PluginStrategy, PluginError> {
+        let Some((source, config)) = self.catalog.get(name) else {
+            return Err(PluginError::UnknownPlugin(name.to_string()));
+        };
+        match source {
+            PluginSource::Library(path) => PluginStrategy::load(path, config),
+            // SAFETY: the vtable outlives the host, as required by `with_linked_plugin`
+            PluginSource::Linked(vtable) => unsafe { PluginStrategy::from_vtable(*vtable, config) },
+        }
+    }
+
+    /// Loads the plugin `name`.
+    ///
+    /// # Errors
+    /// Returns an error if a plugin is already running, or the plugin cannot be
+    /// created, as from `PluginStrategy::load`.
+    ///
+    /// LATENCY: SLOW_PATH
+    pub fn load(&mut self, name: &str) -> Result<(), PluginError> {
+        if let Some(running) = self.running() {
+            return Err(PluginError::AlreadyLoaded(running.to_string()));
+        }
+        self.running = Some((name.to_string(), self.create(name)?));
+        Ok(())
+    }
+
+    /// Unloads the plugin `name`, leaving its orders live.
+    ///
+    /// # Errors
+    /// Returns an error if the plugin isn't the one running.
+    pub fn unload(&mut self, name: &str) -> Result<(), PluginError> {
+        if self.running() != Some(name) {
+            return Err(PluginError::NotLoaded(name.to_string()));
+        }
+        self.running = None;
+        Ok(())
+    }
+
+    /// Hot-swaps the running plugin for the plugin `name`. The running plugin
+    /// is kept if the new one cannot be created.
+    ///
+    /// The orders of the running plugin stay live, their reports going to the
+    /// new one.
+    ///
+    /// # Errors
+    /// Returns an error if no plugin is running, or the plugin cannot be
+    /// created, as from `PluginStrategy::load`.
+    ///
+    /// LATENCY: SLOW_PATH
+    pub fn swap(&mut self, name: &str) -> Result<(), PluginError> {
+        if self.running.is_none() {
+            return Err(PluginError::NoneRunning);
+        }
+        let strategy = self.create(name)?;
+        // The instance swapped out is destroyed once replaced
+        self.running = Some((name.to_string(), strategy));
+        Ok(())
+    }
+
+    /// Applies a strategy command of the control ring to the plugin of its
+    /// target, returning `None` for the other commands.
+    pub fn on_control(&mut self, message: &ControlMessage) -> Option<Result<(), PluginError>> {
+        let name = message.target();
+        match message.command() {
+            ControlCommand::LoadStrategy => Some(self.load(name)),
+            ControlCommand::UnloadStrategy => Some(self.unload(name)),
+            ControlCommand::SwapStrategy => Some(self.swap(name)),
+            // Trading and market data commands, applied by the OMS and ctl-md-handler
+            _ => None,
+        }
+    }
+}
+
+impl Strategy for PluginHost {
+    fn on_top(&mut self, symbol: &str, top: &TopSnapshot, ctx: &mut Context<'_>) {
+        if let Some((_, strategy)) = &mut self.running {
+            strategy.on_top(symbol, top, ctx);
+        }
+    }
+
+    fn on_trade(&mut self, symbol: &str, price: f64, qty: f64, ctx: &mut Context<'_>) {
+        if let Some((_, strategy)) = &mut self.running {
+            strategy.on_trade(symbol, price, qty, ctx);
+        }
+    }
+
+    fn on_report(&mut self, report: &PaperReport, ctx: &mut Context<'_>) {
+        if let Some((_, strategy)) = &mut self.running {
+            strategy.on_report(report, ctx);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::cell::Cell;
+
+    use ctl_core::ClientOrderIdGenerator;
+
+    use crate::OrderAction;
+
+    thread_local! {
+        /// The instances of the test plugin alive, by test thread.
+        static ALIVE: Cell<usize> = const { Cell::new(0) };
+    }
+
+    /// A plugin bidding the quantity of its configuration at the touch, and
+    /// cancelling the bid on the next trade.
+    struct Bidder {
+        qty: f64,
+        bid: Option<String>,
+    }
+
+    unsafe extern "C" fn create(config: StrategyStr) -> *mut c_void {
+        let Some(qty) = unsafe { config.as_str() }.and_then(|config| config.parse().ok()) else {
+            return ptr::null_mut();
+        };
+        ALIVE.set(ALIVE.get() + 1);
+        Box::into_raw(Box::new(Bidder { qty, bid: None })).cast()
+    }
+
+    unsafe extern "C" fn destroy(instance: *mut c_void) {
+        drop(unsafe { Box::from_raw(instance.cast::<Bidder>()) });
+        ALIVE.set(ALIVE.get() - 1);
+    }
+
+    unsafe extern "C" fn on_top(
+        instance: *mut c_void,
+        symbol: StrategyStr,
+        top: *const StrategyTop,
+        host: *mut StrategyHost,
+    ) {
+        let (bidder, top, host) = unsafe { (&mut *instance.cast::<Bidder>(), &*top, &*host) };
+        let mut id = [0u8; 64];
+        let (price, qty) = (top.bid_price, bidder.qty);
+        let len = unsafe { (host.submit)(host.context, symbol, SIDE_BUY, price, qty, id.as_mut_ptr(), id.len()) };
+        bidder.bid = Some(String::from_utf8_lossy(&id[..len]).into_owned());
+    }
+
+    unsafe extern "C" fn on_trade(instance: *mut c_void, _: StrategyStr, _: f64, _: f64, host: *mut StrategyHost) {
+        let (bidder, host) = unsafe { (&mut *instance.cast::<Bidder>(), &*host) };
+        if let Some(bid) = bidder.bid.take() {
+            unsafe { (host.cancel)(host.context, StrategyStr::new(&bid)) };
+        }
+    }
+
+    unsafe extern "C" fn on_report(_: *mut c_void, _: *const StrategyReport, _: *mut StrategyHost) {}
+
+    static VTABLE: StrategyVtable =
+        StrategyVtable { abi_version: STRATEGY_ABI_VERSION, create, destroy, on_top, on_trade, on_report };
+
+    #[test]
+    fn test_plugin_callbacks() {
+        let mut strategy = unsafe { PluginStrategy::from_vtable(&VTABLE, "0.5") }.unwrap();
+        let mut ids = ClientOrderIdGenerator::new(1, 0);
+        let mut actions = Vec::new();
+        let top = TopSnapshot { update_id: 1, bid_price: 99.0, bid_qty: 1.0, ask_price: 100.0, ask_qty: 1.0 };
+
+        strategy.on_top("BTCUSDT", &top, &mut Context::new(&mut ids, &mut actions, 0));
+        strategy.on_trade("BTCUSDT", 99.5, 1.0, &mut Context::new(&mut ids, &mut actions, 1));
+        assert_eq!(actions, vec![
+            OrderAction::Submit {
+                client_order_id: "ctl-1-0-0".to_string(),
+                symbol: "BTCUSDT".to_string(),
+                side: Side::Buy,
+                price: 99.0,
+                qty: 0.5,
+            },
+            OrderAction::Cancel { client_order_id: "ctl-1-0-0".to_string() },
+        ]);
+
+        assert_eq!(ALIVE.get(), 1);
+        drop(strategy);
+        assert_eq!(ALIVE.get(), 0);
+    }
+
+    #[test]
+    fn test_plugin_host_commands() {
+        let mut host = unsafe {
+            PluginHost::new()
+                .with_linked_plugin("small", &VTABLE, "0.5")
+                .with_linked_plugin("large", &VTABLE, "2")
+                .with_linked_plugin("broken", &VTABLE, "not a quantity")
+        };
+        let command = |command, name| ControlMessage::new(command, 0, "test").with_target(name);
+        let mut ids = ClientOrderIdGenerator::new(1, 0);
+        let top = TopSnapshot { update_id: 1, bid_price: 99.0, bid_qty: 1.0, ask_price: 100.0, ask_qty: 1.0 };
+        let mut quote = |host: &mut PluginHost| {
+            let mut actions = Vec::new();
+            host.on_top("BTCUSDT", &top, &mut Context::new(&mut ids, &mut actions, 0));
+            actions.iter().find_map(|action| match action {
+                OrderAction::Submit { qty, .. } => Some(*qty),
+                _ => None,
+            })
+        };
+
+        assert!(host.on_control(&ControlMessage::new(ControlCommand::Halt, 0, "test")).is_none());
+        let result = host.on_control(&command(ControlCommand::SwapStrategy, "large"));
+        assert!(matches!(result, Some(Err(PluginError::NoneRunning))));
+        let result = host.on_control(&command(ControlCommand::LoadStrategy, "unknown"));
+        assert!(matches!(result, Some(Err(PluginError::UnknownPlugin(_)))));
+        assert_eq!(quote(&mut host), None);
+
+        host.on_control(&command(ControlCommand::LoadStrategy, "small")).unwrap().unwrap();
+        assert_eq!(host.running(), Some("small"));
+        assert_eq!(quote(&mut host), Some(0.5));
+        let result = host.on_control(&command(ControlCommand::LoadStrategy, "large"));
+        assert!(matches!(result, Some(Err(PluginError::AlreadyLoaded(_)))));
+
+        // A refused swap keeps the running instance
+        let result = host.on_control(&command(ControlCommand::SwapStrategy, "broken"));
+        assert!(matches!(result, Some(Err(PluginError::Refused))));
+        assert_eq!((host.running(), ALIVE.get()), (Some("small"), 1));
+
+        host.on_control(&command(ControlCommand::SwapStrategy, "large")).unwrap().unwrap();
+        assert_eq!((host.running(), ALIVE.get()), (Some("large"), 1));
+        assert_eq!(quote(&mut host), Some(2.0));
+
+        let result = host.on_control(&command(ControlCommand::UnloadStrategy, "small"));
+        assert!(matches!(result, Some(Err(PluginError::NotLoaded(_)))));
+        host.on_control(&command(ControlCommand::UnloadStrategy, "large")).unwrap().unwrap();
+        assert_eq!((host.running(), ALIVE.get()), (None, 0));
+        assert_eq!(quote(&mut host), None);
+    }
+
+    #[test]
+    fn test_refused_plugins() {
+        let refused = unsafe { PluginStrategy::from_vtable(&VTABLE, "not a quantity") };
+        assert!(matches!(refused, Err(PluginError::Refused)));
+        let missing = unsafe { PluginStrategy::from_vtable(ptr::null(), "0.5") };
+        assert!(matches!(missing, Err(PluginError::MissingVtable)));
+
+        let newer = StrategyVtable { abi_version: STRATEGY_ABI_VERSION + 1, ..VTABLE };
+        let result = unsafe { PluginStrategy::from_vtable(&newer, "0.5") };
+        assert!(matches!(result, Err(PluginError::AbiVersion { expected: STRATEGY_ABI_VERSION, .. })));
+
+        assert!(matches!(PluginStrategy::load("/nonexistent/libstrategy.so", "0.5"), Err(PluginError::Load(_))));
+    }
+}
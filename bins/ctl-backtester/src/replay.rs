@@ -0,0 +1,209 @@
+//! Replay of a recorded market data session.
+//!
+//! A session file holds one record per received payload, in receive order:
+//!
+//! ```text
+//! {recv_time_ms}\t{kind}\t{payload}
+//! ```
+//!
+//! where `kind` is the feed kind of the payload (`top` or `trade`) and `payload`
+//! the payload as published to the rings. Records of other kinds and payloads
+//! that aren't market data (e.g. subscription responses) are skipped, as are
+//! the copies of the events recorded twice (e.g. replayed on a resubscribe),
+//! told apart by their trade and update IDs.
+
+use std::fs::File;
+use std::io::{BufRead, BufReader, Lines};
+use std::path::Path;
+
+use ctl_feed::{payload_event_id, Deduper, EventType, TopSnapshot};
+use serde::Deserialize;
+
+use crate::ReplayError;
+
+/// A trade stream payload.
+/// https://github.com/binance/binance-spot-api-docs/blob/master/web-socket-streams.md#trade-streams
+#[derive(Deserialize)]
+struct TradePayload<'a> {
+    #[serde(rename = "s")]
+    symbol: &'a str,
+    #[serde(rename = "p")]
+    price: &'a str,
+    #[serde(rename = "q")]
+    qty: &'a str,
+}
+
+/// A market data update of a symbol.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MarketEvent {
+    /// The best bid/ask.
+    Top(TopSnapshot),
+    /// A trade.
+    Trade { price: f64, qty: f64 },
+}
+
+impl MarketEvent {
+    /// Returns the event type of the update on the rings.
+    pub fn event_type(&self) -> EventType {
+        match self {
+            MarketEvent::Top(_) => EventType::BookTicker,
+            MarketEvent::Trade { .. } => EventType::Trade,
+        }
+    }
+}
+
+/// A replayed market data update.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SessionEvent {
+    /// The time the payload was received, in milliseconds.
+    pub recv_time_ms: u64,
+    /// The symbol.
+    pub symbol: String,
+    /// The update.
+    pub event: MarketEvent,
+    /// The ID of the event, its trade ID or order book update ID, if any.
+    pub event_id: Option<u64>,
+}
+
+impl SessionEvent {
+    /// Parses a session record, returning `None` if it isn't market data.
+    ///
+    /// # Errors
+    /// Returns an error if the record is malformed.
+    pub fn from_record(record: &str, line: usize) -> Result<Option<Self>, ReplayError> {
+        let invalid = |reason: &str| ReplayError::InvalidRecord { line, reason: reason.to_string() };
+
+        let mut fields = record.splitn(3, '\t');
+        let (Some(recv_time_ms), Some(kind), Some(payload)) = (fields.next(), fields.next(), fields.next()) else {
+            return Err(invalid("expected 3 tab-separated fields"));
+        };
+        let recv_time_ms = recv_time_ms.parse().map_err(|_| invalid("invalid receive time"))?;
+
+        let event = match kind {
+            "top" => TopSnapshot::from_book_ticker(payload.as_bytes())
+                .map(|(symbol, top)| (symbol.to_string(), MarketEvent::Top(top))),
+            "trade" => serde_json::from_str::<TradePayload<'_>>(payload).ok().and_then(|trade| {
+                let event = MarketEvent::Trade { price: trade.price.parse().ok()?, qty: trade.qty.parse().ok()? };
+                Some((trade.symbol.to_string(), event))
+            }),
+            _ => None,
+        };
+        Ok(event.map(|(symbol, event)| {
+            let event_id = payload_event_id(event.event_type(), payload.as_bytes());
+            Self { recv_time_ms, symbol, event, event_id }
+        }))
+    }
+}
+
+/// Reads the market data updates of a session file, in receive order.
+pub struct Replayer<R> {
+    /// The lines of the session.
+    lines: Lines<R>,
+    /// The number of the last line read.
+    line: usize,
+    /// The IDs of the events replayed, by symbol and event type.
+    deduper: Deduper<(String, u8)>,
+}
+
+impl Replayer<BufReader<File>> {
+    /// Opens a session file.
+    ///
+    /// # Errors
+    /// Returns an error if the file cannot be opened.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, ReplayError> {
+        Ok(Self::new(BufReader::new(File::open(path)?)))
+    }
+}
+
+impl<R: BufRead> Replayer<R> {
+    /// Creates a replayer reading the session from `reader`.
+    pub fn new(reader: R) -> Self {
+        Self { lines: reader.lines(), line: 0, deduper: Deduper::default() }
+    }
+
+    /// Returns the number of copies of events skipped.
+    pub fn duplicates(&self) -> u64 {
+        self.deduper.duplicates() + self.deduper.stale()
+    }
+}
+
+impl<R: BufRead> Iterator for Replayer<R> {
+    type Item = Result<SessionEvent, ReplayError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let record = match self.lines.next()? {
+                Ok(record) => record,
+                Err(e) => return Some(Err(e.into())),
+            };
+            self.line += 1;
+            if record.trim().is_empty() {
+                continue;
+            }
+            match SessionEvent::from_record(&record, self.line) {
+                Ok(Some(event)) => {
+                    let key = (event.symbol.clone(), event.event.event_type() as u8);
+                    if event.event_id.is_some_and(|id| !self.deduper.admit(key, id)) {
+                        continue;
+                    }
+                    return Some(Ok(event));
+                }
+                Ok(None) => continue,
+                Err(e) => return Some(Err(e)),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SESSION: &str = concat!(
+        "1000\ttop\t{\"u\":400900217,\"s\":\"BNBUSDT\",\"b\":\"25.35190000\",\"B\":\"31.21000000\",\"a\":\"25.36520000\",\"A\":\"40.66000000\"}\n",
+        "1001\ttop\t{\"result\":null,\"id\":1}\n",
+        "\n",
+        "1002\ttrade\t{\"e\":\"trade\",\"E\":1002,\"s\":\"BNBUSDT\",\"t\":12345,\"p\":\"25.36\",\"q\":\"2\",\"T\":1002,\"m\":true,\"M\":true}\n",
+        "1003\tdepth\t{}\n",
+    );
+
+    #[test]
+    fn test_replay_session() {
+        let events: Vec<_> = Replayer::new(SESSION.as_bytes()).collect::<Result<_, _>>().unwrap();
+        assert_eq!(events.len(), 2);
+
+        assert_eq!(events[0].recv_time_ms, 1000);
+        assert_eq!(events[0].symbol, "BNBUSDT");
+        let MarketEvent::Top(top) = events[0].event else {
+            panic!("expected a top");
+        };
+        assert_eq!((top.bid_price, top.ask_price), (25.3519, 25.3652));
+
+        assert_eq!(events[1].recv_time_ms, 1002);
+        assert_eq!(events[1].event, MarketEvent::Trade { price: 25.36, qty: 2.0 });
+        assert_eq!((events[0].event_id, events[1].event_id), (Some(400900217), Some(12345)));
+    }
+
+    #[test]
+    fn test_duplicates_skipped() {
+        let session = concat!(
+            "1000\ttrade\t{\"e\":\"trade\",\"s\":\"BNBUSDT\",\"t\":1,\"p\":\"25.36\",\"q\":\"2\"}\n",
+            "1001\ttrade\t{\"e\":\"trade\",\"s\":\"BNBUSDT\",\"t\":2,\"p\":\"25.37\",\"q\":\"1\"}\n",
+            "1002\ttrade\t{\"e\":\"trade\",\"s\":\"BNBUSDT\",\"t\":1,\"p\":\"25.36\",\"q\":\"2\"}\n",
+            "1003\ttrade\t{\"e\":\"trade\",\"s\":\"ETHUSDT\",\"t\":1,\"p\":\"1800.0\",\"q\":\"1\"}\n",
+        );
+        let mut replayer = Replayer::new(session.as_bytes());
+        let events: Vec<_> = replayer.by_ref().collect::<Result<_, _>>().unwrap();
+        let times: Vec<u64> = events.iter().map(|event| event.recv_time_ms).collect();
+        assert_eq!(times, vec![1000, 1001, 1003]);
+        assert_eq!(replayer.duplicates(), 1);
+    }
+
+    #[test]
+    fn test_invalid_record() {
+        let mut replayer = Replayer::new("1000\ttop\t{}\nbad record\n".as_bytes());
+        let err = replayer.next().unwrap().unwrap_err();
+        assert!(matches!(err, ReplayError::InvalidRecord { line: 2, .. }));
+        assert!(SessionEvent::from_record("soon\ttop\t{}", 1).is_err());
+    }
+}
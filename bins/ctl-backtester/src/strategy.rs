@@ -0,0 +1,258 @@
+//! The strategy interface of the backtest, and an example strategy.
+
+use ctl_core::ClientOrderIdGenerator;
+use ctl_feed::TopSnapshot;
+use ctl_oms::{ExecutionType, PaperReport, Side};
+use hashbrown::HashMap;
+use serde::Deserialize;
+
+/// An order action requested by a strategy.
+#[derive(Debug, Clone, PartialEq)]
+pub enum OrderAction {
+    /// Submits a limit order.
+    Submit { client_order_id: String, symbol: String, side: Side, price: f64, qty: f64 },
+    /// Cancels an order.
+    Cancel { client_order_id: String },
+}
+
+/// The context of a strategy callback, collecting the order actions.
+pub struct Context<'a> {
+    /// The generator of the client order IDs.
+    ids: &'a mut ClientOrderIdGenerator,
+    /// The actions requested.
+    actions: &'a mut Vec<OrderAction>,
+    /// The replayed time, in milliseconds.
+    now_ms: u64,
+}
+
+impl<'a> Context<'a> {
+    /// Creates a context appending the actions to `actions`.
+    pub fn new(ids: &'a mut ClientOrderIdGenerator, actions: &'a mut Vec<OrderAction>, now_ms: u64) -> Self {
+        Self { ids, actions, now_ms }
+    }
+
+    /// Returns the replayed time, in milliseconds.
+    pub fn now_ms(&self) -> u64 {
+        self.now_ms
+    }
+
+    /// Submits a limit order, returning its client order ID.
+    pub fn submit(&mut self, symbol: &str, side: Side, price: f64, qty: f64) -> String {
+        let client_order_id = self.ids.next_id().to_string();
+        self.actions.push(OrderAction::Submit {
+            client_order_id: client_order_id.clone(),
+            symbol: symbol.to_string(),
+            side,
+            price,
+            qty,
+        });
+        client_order_id
+    }
+
+    /// Cancels an order.
+    pub fn cancel(&mut self, client_order_id: &str) {
+        self.actions.push(OrderAction::Cancel { client_order_id: client_order_id.to_string() });
+    }
+}
+
+/// A strategy driven by the replayed market data and its execution reports.
+pub trait Strategy {
+    /// Called on a best bid/ask update of a symbol.
+    fn on_top(&mut self, symbol: &str, top: &TopSnapshot, ctx: &mut Context<'_>);
+
+    /// Called on a trade of a symbol.
+    fn on_trade(&mut self, _symbol: &str, _price: f64, _qty: f64, _ctx: &mut Context<'_>) {}
+
+    /// Called on an execution report of one of the strategy's orders.
+    fn on_report(&mut self, _report: &PaperReport, _ctx: &mut Context<'_>) {}
+}
+
+impl<S: Strategy + ?Sized> Strategy for Box<S> {
+    fn on_top(&mut self, symbol: &str, top: &TopSnapshot, ctx: &mut Context<'_>) {
+        (**self).on_top(symbol, top, ctx);
+    }
+
+    fn on_trade(&mut self, symbol: &str, price: f64, qty: f64, ctx: &mut Context<'_>) {
+        (**self).on_trade(symbol, price, qty, ctx);
+    }
+
+    fn on_report(&mut self, report: &PaperReport, ctx: &mut Context<'_>) {
+        (**self).on_report(report, ctx);
+    }
+}
+
+/// The configuration of the [`TouchQuoter`].
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct QuoterConfig {
+    /// The symbols quoted.
+    pub symbols: Vec<String>,
+    /// The quantity of each quote.
+    pub qty: f64,
+    /// The largest absolute base position, the side extending it stops quoting beyond.
+    pub max_position: f64,
+}
+
+/// A resting quote.
+#[derive(Debug, Clone, PartialEq)]
+struct Quote {
+    client_order_id: String,
+    price: f64,
+}
+
+/// The quotes and position of a symbol.
+#[derive(Debug, Clone, Default)]
+struct SymbolQuotes {
+    bid: Option<Quote>,
+    ask: Option<Quote>,
+    position: f64,
+}
+
+/// An example strategy quoting both sides at the touch.
+///
+/// Joins the best bid and ask with a fixed quantity, cancelling and replacing a
+/// quote when the touch moves away from it, and stops quoting the side that
+/// would take the position beyond the configured limit.
+#[derive(Debug, Clone)]
+pub struct TouchQuoter {
+    config: QuoterConfig,
+    symbols: HashMap<String, SymbolQuotes>,
+}
+
+impl TouchQuoter {
+    /// Creates the strategy.
+    pub fn new(config: QuoterConfig) -> Self {
+        let symbols = config.symbols.iter().map(|s| (s.clone(), SymbolQuotes::default())).collect();
+        Self { config, symbols }
+    }
+
+    /// Moves a quote to `price`, or pulls it if `price` is `None`.
+    fn requote(
+        quote: &mut Option<Quote>,
+        price: Option<f64>,
+        symbol: &str,
+        side: Side,
+        qty: f64,
+        ctx: &mut Context<'_>,
+    ) {
+        if let Some(current) = quote.as_ref() {
+            if Some(current.price) == price {
+                return;
+            }
+            ctx.cancel(&current.client_order_id);
+            *quote = None;
+        }
+        if let Some(price) = price {
+            let client_order_id = ctx.submit(symbol, side, price, qty);
+            *quote = Some(Quote { client_order_id, price });
+        }
+    }
+}
+
+impl Strategy for TouchQuoter {
+    fn on_top(&mut self, symbol: &str, top: &TopSnapshot, ctx: &mut Context<'_>) {
+        let Some(quotes) = self.symbols.get_mut(symbol) else {
+            return;
+        };
+        let qty = self.config.qty;
+        let bid = (quotes.position + qty <= self.config.max_position).then_some(top.bid_price);
+        let ask = (quotes.position - qty >= -self.config.max_position).then_some(top.ask_price);
+        Self::requote(&mut quotes.bid, bid, symbol, Side::Buy, qty, ctx);
+        Self::requote(&mut quotes.ask, ask, symbol, Side::Sell, qty, ctx);
+    }
+
+    fn on_report(&mut self, report: &PaperReport, _ctx: &mut Context<'_>) {
+        let Some(quotes) = self.symbols.get_mut(&report.symbol) else {
+            return;
+        };
+        if report.execution_type == ExecutionType::Trade {
+            quotes.position += match report.side {
+                Side::Buy => report.last_qty,
+                Side::Sell => -report.last_qty,
+            };
+        }
+        if report.update.status.is_terminal() {
+            for quote in [&mut quotes.bid, &mut quotes.ask] {
+                if quote.as_ref().is_some_and(|q| q.client_order_id == report.update.client_order_id) {
+                    *quote = None;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use ctl_oms::{OrderStatus, OrderUpdate};
+
+    fn top(bid_price: f64, ask_price: f64) -> TopSnapshot {
+        TopSnapshot { update_id: 1, bid_price, bid_qty: 1.0, ask_price, ask_qty: 1.0 }
+    }
+
+    fn fill(client_order_id: &str, side: Side, qty: f64) -> PaperReport {
+        PaperReport {
+            update: OrderUpdate {
+                client_order_id: client_order_id.to_string(),
+                order_id: Some(1),
+                status: OrderStatus::Filled,
+                executed_qty: qty,
+            },
+            symbol: "BTCUSDT".to_string(),
+            side,
+            execution_type: ExecutionType::Trade,
+            last_price: 100.0,
+            last_qty: qty,
+            commission: 0.0,
+            commission_asset: "USDT".to_string(),
+            is_maker: true,
+            trade_id: 1,
+            transact_time_ms: 0,
+        }
+    }
+
+    #[test]
+    fn test_quotes_touch() {
+        let mut quoter = TouchQuoter::new(QuoterConfig {
+            symbols: vec!["BTCUSDT".to_string()],
+            qty: 1.0,
+            max_position: 1.0,
+        });
+        let mut ids = ClientOrderIdGenerator::new(1, 0);
+        let mut actions = Vec::new();
+
+        quoter.on_top("BTCUSDT", &top(99.0, 100.0), &mut Context::new(&mut ids, &mut actions, 0));
+        quoter.on_top("ETHUSDT", &top(9.0, 10.0), &mut Context::new(&mut ids, &mut actions, 0));
+        assert_eq!(actions.len(), 2);
+        assert!(matches!(&actions[0], OrderAction::Submit { side: Side::Buy, price, .. } if *price == 99.0));
+        assert!(matches!(&actions[1], OrderAction::Submit { side: Side::Sell, price, .. } if *price == 100.0));
+
+        // An unchanged touch keeps the quotes, a moved bid is replaced
+        actions.clear();
+        quoter.on_top("BTCUSDT", &top(99.0, 100.0), &mut Context::new(&mut ids, &mut actions, 1));
+        assert!(actions.is_empty());
+        quoter.on_top("BTCUSDT", &top(99.5, 100.0), &mut Context::new(&mut ids, &mut actions, 2));
+        assert_eq!(actions.len(), 2);
+        assert!(matches!(&actions[0], OrderAction::Cancel { client_order_id } if client_order_id == "ctl-1-0-0"));
+    }
+
+    #[test]
+    fn test_position_limit() {
+        let mut quoter = TouchQuoter::new(QuoterConfig {
+            symbols: vec!["BTCUSDT".to_string()],
+            qty: 1.0,
+            max_position: 1.0,
+        });
+        let mut ids = ClientOrderIdGenerator::new(1, 0);
+        let mut actions = Vec::new();
+        quoter.on_top("BTCUSDT", &top(99.0, 100.0), &mut Context::new(&mut ids, &mut actions, 0));
+
+        // Long at the limit, only the ask is quoted again
+        quoter.on_report(&fill("ctl-1-0-0", Side::Buy, 1.0), &mut Context::new(&mut ids, &mut actions, 1));
+        actions.clear();
+        quoter.on_top("BTCUSDT", &top(98.0, 99.0), &mut Context::new(&mut ids, &mut actions, 2));
+        assert_eq!(actions.len(), 2);
+        assert!(matches!(&actions[0], OrderAction::Cancel { .. }));
+        assert!(matches!(&actions[1], OrderAction::Submit { side: Side::Sell, .. }));
+    }
+}
@@ -0,0 +1,323 @@
+//! An example strategy trading the cross-rate dislocations of a triangle of symbols.
+
+use ctl_feed::TopSnapshot;
+use ctl_oms::{PaperReport, Side};
+use serde::Deserialize;
+
+use crate::{Context, Strategy};
+
+/// Default fee of each leg, in basis points, the taker fee of the base tier.
+const DEFAULT_FEE_BPS: f64 = 10.0;
+
+/// Default time the legs of a cycle are left working before the unfilled ones are cancelled.
+const DEFAULT_LEG_TIMEOUT_MS: u64 = 1_000;
+
+/// Default minimum interval between the starts of two cycles.
+const DEFAULT_COOLDOWN_MS: u64 = 1_000;
+
+fn default_fee_bps() -> f64 {
+    DEFAULT_FEE_BPS
+}
+
+fn default_leg_timeout_ms() -> u64 {
+    DEFAULT_LEG_TIMEOUT_MS
+}
+
+fn default_cooldown_ms() -> u64 {
+    DEFAULT_COOLDOWN_MS
+}
+
+/// The configuration of the [`TriangularArbitrage`].
+///
+/// The triangle trades an asset against a quote asset both directly and
+/// through a bridge asset, e.g. ETH against USDT directly on ETHUSDT and
+/// through BTC on ETHBTC and BTCUSDT.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct TriangleConfig {
+    /// The bridge asset priced in the quote asset (e.g. `BTCUSDT`).
+    pub bridge_symbol: String,
+    /// The asset priced in the bridge asset (e.g. `ETHBTC`).
+    pub cross_symbol: String,
+    /// The asset priced in the quote asset (e.g. `ETHUSDT`).
+    pub direct_symbol: String,
+    /// The largest quantity of the asset traded by a cycle.
+    pub qty: f64,
+    /// The smallest edge of a cycle net of the fees, in basis points.
+    pub min_edge_bps: f64,
+    /// The fee of each leg, in basis points.
+    #[serde(default = "default_fee_bps")]
+    pub fee_bps: f64,
+    /// The time the legs of a cycle are left working before the unfilled ones
+    /// are cancelled, in milliseconds.
+    #[serde(default = "default_leg_timeout_ms")]
+    pub leg_timeout_ms: u64,
+    /// The minimum interval between the starts of two cycles, in milliseconds.
+    #[serde(default = "default_cooldown_ms")]
+    pub cooldown_ms: u64,
+}
+
+impl TriangleConfig {
+    /// Returns the three symbols of the triangle.
+    pub fn symbols(&self) -> [&str; 3] {
+        [&self.bridge_symbol, &self.cross_symbol, &self.direct_symbol]
+    }
+}
+
+/// The direction a cycle goes around the triangle, from the quote asset back to it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cycle {
+    /// Buys the bridge asset, buys the asset with it, sells the asset directly.
+    ViaBridge,
+    /// Buys the asset directly, sells it for the bridge asset, sells the bridge asset.
+    ViaDirect,
+}
+
+/// The legs of the cycle in flight.
+#[derive(Debug, Clone, Default)]
+struct Legs {
+    /// The client order IDs of the legs not yet terminal.
+    open: Vec<String>,
+    /// The time the cycle started, in milliseconds.
+    started_ms: u64,
+    /// Whether the unfilled legs were cancelled.
+    cancelled: bool,
+}
+
+/// An example strategy arbitraging the cross rate of a triangle of symbols.
+///
+/// Watches the Tops of the three symbols and, when going around the triangle
+/// in either direction at the touch returns more than the configured edge net
+/// of the fees, sends the three legs at once as marketable limit orders,
+/// sized to the quantity shown at each touch. The legs left unfilled past the
+/// leg timeout are cancelled, and no new cycle starts until all are terminal.
+#[derive(Debug, Clone)]
+pub struct TriangularArbitrage {
+    config: TriangleConfig,
+    /// The last Top of the bridge, cross and direct symbols.
+    tops: [Option<TopSnapshot>; 3],
+    /// The legs of the cycle in flight, if any.
+    legs: Option<Legs>,
+    /// The time the last cycle started, in milliseconds.
+    last_cycle_ms: Option<u64>,
+}
+
+impl TriangularArbitrage {
+    /// Creates the strategy.
+    pub fn new(config: TriangleConfig) -> Self {
+        Self { config, tops: [None; 3], legs: None, last_cycle_ms: None }
+    }
+
+    /// Returns the best cycle around the triangle at the touch and its edge net
+    /// of the fees, in basis points, `None` until the three Tops are known.
+    pub fn best_cycle(&self) -> Option<(Cycle, f64)> {
+        let [Some(bridge), Some(cross), Some(direct)] = self.tops else {
+            return None;
+        };
+        let fees_bps = 3.0 * self.config.fee_bps;
+        let (via_bridge, via_direct) = cycle_edges_bps(&bridge, &cross, &direct)?;
+        if via_bridge >= via_direct {
+            Some((Cycle::ViaBridge, via_bridge - fees_bps))
+        } else {
+            Some((Cycle::ViaDirect, via_direct - fees_bps))
+        }
+    }
+
+    /// Cancels the unfilled legs once the leg timeout elapsed.
+    fn expire_legs(&mut self, ctx: &mut Context<'_>) {
+        let Some(legs) = self.legs.as_mut() else {
+            return;
+        };
+        if legs.cancelled || ctx.now_ms() < legs.started_ms + self.config.leg_timeout_ms {
+            return;
+        }
+        for client_order_id in &legs.open {
+            ctx.cancel(client_order_id);
+        }
+        legs.cancelled = true;
+    }
+
+    /// Sends the three legs of a cycle.
+    fn start_cycle(&mut self, cycle: Cycle, ctx: &mut Context<'_>) {
+        let [Some(bridge), Some(cross), Some(direct)] = self.tops else {
+            return;
+        };
+        let config = &self.config;
+        // Each leg trades the same quantity of the asset, the bridge leg its value in the bridge asset
+        let orders = match cycle {
+            Cycle::ViaBridge => {
+                let qty = config.qty.min(cross.ask_qty).min(direct.bid_qty).min(bridge.ask_qty / cross.ask_price);
+                [
+                    (&config.bridge_symbol, Side::Buy, bridge.ask_price, qty * cross.ask_price),
+                    (&config.cross_symbol, Side::Buy, cross.ask_price, qty),
+                    (&config.direct_symbol, Side::Sell, direct.bid_price, qty),
+                ]
+            }
+            Cycle::ViaDirect => {
+                let qty = config.qty.min(direct.ask_qty).min(cross.bid_qty).min(bridge.bid_qty / cross.bid_price);
+                [
+                    (&config.direct_symbol, Side::Buy, direct.ask_price, qty),
+                    (&config.cross_symbol, Side::Sell, cross.bid_price, qty),
+                    (&config.bridge_symbol, Side::Sell, bridge.bid_price, qty * cross.bid_price),
+                ]
+            }
+        };
+        if !orders.iter().all(|&(_, _, _, qty)| qty > 0.0) {
+            return;
+        }
+        let open = orders.into_iter().map(|(symbol, side, price, qty)| ctx.submit(symbol, side, price, qty)).collect();
+        self.legs = Some(Legs { open, started_ms: ctx.now_ms(), cancelled: false });
+        self.last_cycle_ms = Some(ctx.now_ms());
+    }
+}
+
+/// Returns the edges of going around the triangle via the bridge and via the
+/// direct symbol at the touch, before fees, in basis points, `None` if a Top
+/// has no price.
+pub fn cycle_edges_bps(bridge: &TopSnapshot, cross: &TopSnapshot, direct: &TopSnapshot) -> Option<(f64, f64)> {
+    let prices = [bridge, cross, direct].map(|top| [top.bid_price, top.ask_price]);
+    if !prices.as_flattened().iter().all(|&price| price > 0.0) {
+        return None;
+    }
+    let via_bridge = direct.bid_price / (bridge.ask_price * cross.ask_price) - 1.0;
+    let via_direct = cross.bid_price * bridge.bid_price / direct.ask_price - 1.0;
+    Some((via_bridge * 10_000.0, via_direct * 10_000.0))
+}
+
+impl Strategy for TriangularArbitrage {
+    fn on_top(&mut self, symbol: &str, top: &TopSnapshot, ctx: &mut Context<'_>) {
+        let Some(index) = self.config.symbols().iter().position(|s| *s == symbol) else {
+            return;
+        };
+        self.tops[index] = Some(*top);
+
+        if self.legs.is_some() {
+            self.expire_legs(ctx);
+            return;
+        }
+        if self.last_cycle_ms.is_some_and(|at| ctx.now_ms() < at + self.config.cooldown_ms) {
+            return;
+        }
+        if let Some((cycle, edge_bps)) = self.best_cycle() {
+            if edge_bps >= self.config.min_edge_bps {
+                self.start_cycle(cycle, ctx);
+            }
+        }
+    }
+
+    fn on_report(&mut self, report: &PaperReport, _ctx: &mut Context<'_>) {
+        let Some(legs) = self.legs.as_mut() else {
+            return;
+        };
+        if report.update.status.is_terminal() {
+            legs.open.retain(|client_order_id| *client_order_id != report.update.client_order_id);
+        }
+        if legs.open.is_empty() {
+            self.legs = None;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use ctl_core::ClientOrderIdGenerator;
+    use ctl_oms::{ExecutionType, OrderStatus, OrderUpdate};
+
+    use crate::OrderAction;
+
+    fn config() -> TriangleConfig {
+        TriangleConfig {
+            bridge_symbol: "BTCUSDT".to_string(),
+            cross_symbol: "ETHBTC".to_string(),
+            direct_symbol: "ETHUSDT".to_string(),
+            qty: 1.0,
+            min_edge_bps: 5.0,
+            fee_bps: 1.0,
+            leg_timeout_ms: 100,
+            cooldown_ms: 1_000,
+        }
+    }
+
+    fn top(bid_price: f64, ask_price: f64) -> TopSnapshot {
+        TopSnapshot { update_id: 1, bid_price, bid_qty: 10.0, ask_price, ask_qty: 10.0 }
+    }
+
+    fn filled(client_order_id: &str) -> PaperReport {
+        PaperReport {
+            update: OrderUpdate {
+                client_order_id: client_order_id.to_string(),
+                order_id: Some(1),
+                status: OrderStatus::Filled,
+                executed_qty: 1.0,
+            },
+            symbol: "ETHUSDT".to_string(),
+            side: Side::Buy,
+            execution_type: ExecutionType::Trade,
+            last_price: 3_000.0,
+            last_qty: 1.0,
+            commission: 0.0,
+            commission_asset: "USDT".to_string(),
+            is_maker: false,
+            trade_id: 1,
+            transact_time_ms: 0,
+        }
+    }
+
+    /// Feeds the Tops of the triangle, ETHUSDT last, returning the actions of its Top.
+    fn feed(
+        strategy: &mut TriangularArbitrage,
+        ids: &mut ClientOrderIdGenerator,
+        direct: TopSnapshot,
+        now_ms: u64,
+    ) -> Vec<OrderAction> {
+        let mut actions = Vec::new();
+        strategy.on_top("BTCUSDT", &top(49_999.0, 50_000.0), &mut Context::new(ids, &mut actions, now_ms));
+        strategy.on_top("ETHBTC", &top(0.05999, 0.06), &mut Context::new(ids, &mut actions, now_ms));
+        strategy.on_top("ETHUSDT", &direct, &mut Context::new(ids, &mut actions, now_ms));
+        actions
+    }
+
+    #[test]
+    fn test_cycle_edges() {
+        // ETH costs 3000 USDT through BTC, sold directly at 3006: 20 bps before fees
+        let (via_bridge, via_direct) =
+            cycle_edges_bps(&top(49_999.0, 50_000.0), &top(0.05999, 0.06), &top(3_006.0, 3_007.0)).unwrap();
+        assert!((via_bridge - 20.0).abs() < 1e-6);
+        assert!(via_direct < 0.0);
+        assert_eq!(cycle_edges_bps(&top(0.0, 50_000.0), &top(0.05999, 0.06), &top(3_006.0, 3_007.0)), None);
+    }
+
+    #[test]
+    fn test_sends_legs_on_dislocation() {
+        let mut strategy = TriangularArbitrage::new(config());
+        let mut ids = ClientOrderIdGenerator::new(1, 0);
+
+        // In line, no cycle
+        assert!(feed(&mut strategy, &mut ids, top(2_999.0, 3_001.0), 0).is_empty());
+
+        let actions = feed(&mut strategy, &mut ids, top(3_006.0, 3_007.0), 0);
+        assert_eq!(actions.len(), 3);
+        let OrderAction::Submit { symbol, side: Side::Buy, qty, .. } = &actions[0] else {
+            panic!("expected the bridge leg");
+        };
+        assert_eq!((symbol.as_str(), *qty), ("BTCUSDT", 0.06));
+        assert!(matches!(&actions[1], OrderAction::Submit { side: Side::Buy, qty, .. } if *qty == 1.0));
+        assert!(matches!(&actions[2], OrderAction::Submit { side: Side::Sell, price, .. } if *price == 3_006.0));
+
+        // No new cycle while the legs are working, the unfilled ones cancelled past the timeout
+        assert!(feed(&mut strategy, &mut ids, top(3_006.0, 3_007.0), 50).is_empty());
+        let mut actions = Vec::new();
+        strategy.on_report(&filled("ctl-1-0-0"), &mut Context::new(&mut ids, &mut actions, 60));
+        let actions = feed(&mut strategy, &mut ids, top(3_006.0, 3_007.0), 100);
+        assert_eq!(actions.len(), 2);
+        assert!(matches!(&actions[0], OrderAction::Cancel { client_order_id } if client_order_id == "ctl-1-0-1"));
+
+        // All legs terminal, the next cycle waits for the cooldown
+        let mut actions = Vec::new();
+        strategy.on_report(&filled("ctl-1-0-1"), &mut Context::new(&mut ids, &mut actions, 110));
+        strategy.on_report(&filled("ctl-1-0-2"), &mut Context::new(&mut ids, &mut actions, 110));
+        assert!(feed(&mut strategy, &mut ids, top(3_006.0, 3_007.0), 500).is_empty());
+        assert_eq!(feed(&mut strategy, &mut ids, top(3_006.0, 3_007.0), 1_000).len(), 3);
+    }
+}
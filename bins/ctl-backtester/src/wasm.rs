@@ -0,0 +1,405 @@
+//! Strategies run as sandboxed WASM modules.
+//!
+//! Unlike a plugin, a WASM strategy runs in an interpreter with no access to
+//! the memory of its host: it reads the market data and submits its orders
+//! through the functions imported from the host, and each of its callbacks is
+//! metered, so a strategy looping forever or growing its memory beyond its
+//! limit traps instead of stalling or crashing the host. A trapped strategy is
+//! disabled, its later callbacks skipped.
+//!
+//! The module exports its `memory` and:
+//!
+//! - `alloc(len: i32) -> i32`, the address of `len` bytes the host writes the
+//!   configuration to, as JSON
+//! - `init(config: i32, config_len: i32) -> i32`, 0 if the configuration is accepted
+//! - `scratch() -> i32`, the address of `SCRATCH_SIZE` bytes the host writes the
+//!   strings of a callback to, the symbol at 0 and the client order ID at
+//!   `SCRATCH_ID_OFFSET`
+//! - `on_top(symbol_len: i32, update_id: i64, bid_price: f64, bid_qty: f64, ask_price: f64, ask_qty: f64)`
+//! - `on_trade(symbol_len: i32, price: f64, qty: f64)`
+//! - `on_report(id_len: i32, symbol_len: i32, side: i32, is_fill: i32, is_terminal: i32,
+//!   last_price: f64, last_qty: f64, executed_qty: f64)`
+//!
+//! The host functions are imported from the `ctl` module:
+//!
+//! - `now_ms() -> i64`, the time of the event
+//! - `top(symbol: i32, symbol_len: i32, out: i32) -> i32`, writing the last best
+//!   bid/ask of a symbol to `out` (the update ID as an i64, then the bid price
+//!   and quantity and the ask price and quantity as f64s), 0 if none yet
+//! - `submit(symbol: i32, symbol_len: i32, side: i32, price: f64, qty: f64, id: i32, id_capacity: i32) -> i32`,
+//!   submitting a limit order (side 0 to buy, 1 to sell), writing its client
+//!   order ID to `id` and returning the full length of the ID
+//! - `cancel(id: i32, id_len: i32)`, cancelling an order
+
+use std::ffi::c_void;
+use std::fs;
+use std::path::Path;
+use std::ptr;
+
+use ctl_feed::TopSnapshot;
+use ctl_oms::{ExecutionType, PaperReport, Side};
+use hashbrown::HashMap;
+use tracing::warn;
+use wasmi::{
+    Caller, Config, Engine, Error, Extern, Linker, Memory, Module, Store, StoreLimits, StoreLimitsBuilder, TypedFunc,
+};
+
+use crate::{Context, Strategy, WasmError};
+
+/// The size of the scratch buffer of a module.
+pub const SCRATCH_SIZE: usize = 128;
+
+/// The offset of the client order ID in the scratch buffer.
+pub const SCRATCH_ID_OFFSET: usize = 64;
+
+/// The size of the best bid/ask written by `top`.
+const TOP_SIZE: usize = 40;
+
+/// The resources a WASM strategy may use.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WasmLimits {
+    /// The fuel of each callback, roughly one per instruction.
+    pub fuel: u64,
+    /// The largest linear memory of the module, in bytes.
+    pub max_memory_bytes: usize,
+}
+
+/// The state of the host, reached by the host functions.
+struct HostState {
+    /// The memory limit of the module.
+    limits: StoreLimits,
+    /// The `Context` of the callback running, null between the callbacks.
+    context: *mut c_void,
+    /// The time of the event, in milliseconds.
+    now_ms: u64,
+    /// The last best bid/ask of each symbol.
+    tops: HashMap<String, TopSnapshot>,
+}
+
+impl HostState {
+    /// Returns the `Context` of the callback running.
+    fn context(&mut self) -> Result<&mut Context<'_>, Error> {
+        if self.context.is_null() {
+            return Err(Error::new("host function called outside a callback"));
+        }
+        // SAFETY: set to a live `Context` for the duration of a callback only
+        Ok(unsafe { &mut *self.context.cast::<Context<'_>>() })
+    }
+}
+
+/// Returns the memory exported by the module of `caller`.
+fn memory(caller: &Caller<'_, HostState>) -> Result<Memory, Error> {
+    caller
+        .get_export("memory")
+        .and_then(Extern::into_memory)
+        .ok_or_else(|| Error::new("module exports no memory"))
+}
+
+/// Reads a UTF-8 string of the module's memory.
+fn read_str(caller: &Caller<'_, HostState>, ptr: i32, len: i32) -> Result<String, Error> {
+    let mut bytes = vec![0; len as u32 as usize];
+    memory(caller)?.read(caller, ptr as u32 as usize, &mut bytes).map_err(|e| Error::new(e.to_string()))?;
+    String::from_utf8(bytes).map_err(|_| Error::new("string isn't UTF-8"))
+}
+
+/// Writes bytes to the module's memory.
+fn write_bytes(caller: &mut Caller<'_, HostState>, ptr: i32, bytes: &[u8]) -> Result<(), Error> {
+    memory(caller)?.write(caller, ptr as u32 as usize, bytes).map_err(|e| Error::new(e.to_string()))
+}
+
+/// Imports the host functions into `linker`.
+fn link(linker: &mut Linker<HostState>) -> Result<(), Error> {
+    linker.func_wrap("ctl", "now_ms", |caller: Caller<'_, HostState>| caller.data().now_ms as i64)?;
+    linker.func_wrap(
+        "ctl",
+        "top",
+        |mut caller: Caller<'_, HostState>, symbol: i32, symbol_len: i32, out: i32| -> Result<i32, Error> {
+            let symbol = read_str(&caller, symbol, symbol_len)?;
+            let Some(top) = caller.data().tops.get(&symbol).copied() else {
+                return Ok(0);
+            };
+            let mut bytes = [0u8; TOP_SIZE];
+            bytes[..8].copy_from_slice(&top.update_id.to_le_bytes());
+            for (index, value) in [top.bid_price, top.bid_qty, top.ask_price, top.ask_qty].into_iter().enumerate() {
+                bytes[8 * (index + 1)..8 * (index + 2)].copy_from_slice(&value.to_le_bytes());
+            }
+            write_bytes(&mut caller, out, &bytes)?;
+            Ok(1)
+        },
+    )?;
+    linker.func_wrap(
+        "ctl",
+        "submit",
+        |mut caller: Caller<'_, HostState>,
+         symbol: i32,
+         symbol_len: i32,
+         side: i32,
+         price: f64,
+         qty: f64,
+         id: i32,
+         id_capacity: i32|
+         -> Result<i32, Error> {
+            let symbol = read_str(&caller, symbol, symbol_len)?;
+            let side = if side == 1 { Side::Sell } else { Side::Buy };
+            let client_order_id = caller.data_mut().context()?.submit(&symbol, side, price, qty);
+            let len = client_order_id.len().min(id_capacity.max(0) as usize);
+            write_bytes(&mut caller, id, &client_order_id.as_bytes()[..len])?;
+            Ok(client_order_id.len() as i32)
+        },
+    )?;
+    linker.func_wrap("ctl", "cancel", |mut caller: Caller<'_, HostState>, id: i32, id_len: i32| -> Result<(), Error> {
+        let client_order_id = read_str(&caller, id, id_len)?;
+        caller.data_mut().context()?.cancel(&client_order_id);
+        Ok(())
+    })?;
+    Ok(())
+}
+
+/// A strategy run by a sandboxed WASM module.
+pub struct WasmStrategy {
+    /// The instance of the module and the state of the host.
+    store: Store<HostState>,
+    /// The memory of the module.
+    memory: Memory,
+    /// The address of the scratch buffer of the module.
+    scratch: usize,
+    /// The fuel of each callback.
+    fuel: u64,
+    /// The callbacks of the module.
+    on_top: TypedFunc<(i32, i64, f64, f64, f64, f64), ()>,
+    on_trade: TypedFunc<(i32, f64, f64), ()>,
+    on_report: TypedFunc<(i32, i32, i32, i32, i32, f64, f64, f64), ()>,
+    /// The trap that disabled the strategy, if any.
+    trap: Option<String>,
+}
+
+impl WasmStrategy {
+    /// Loads the module at `path` and initializes it with `config`, as JSON.
+    ///
+    /// # Errors
+    /// Returns an error if the module cannot be read or instantiated within
+    /// `limits`, lacks an export, or refuses the configuration.
+    ///
+    /// LATENCY: SLOW_PATH
+    pub fn load<P: AsRef<Path>>(path: P, config: &str, limits: WasmLimits) -> Result<Self, WasmError> {
+        Self::from_bytes(&fs::read(path)?, config, limits)
+    }
+
+    /// Instantiates a module from its binary form and initializes it with
+    /// `config`, as JSON.
+    ///
+    /// # Errors
+    /// As from `load`.
+    pub fn from_bytes(wasm: &[u8], config: &str, limits: WasmLimits) -> Result<Self, WasmError> {
+        let mut engine_config = Config::default();
+        engine_config.consume_fuel(true);
+        let engine = Engine::new(&engine_config);
+        let module = Module::new(&engine, wasm)?;
+
+        let state = HostState {
+            limits: StoreLimitsBuilder::new().memory_size(limits.max_memory_bytes).instances(1).build(),
+            context: ptr::null_mut(),
+            now_ms: 0,
+            tops: HashMap::new(),
+        };
+        let mut store = Store::new(&engine, state);
+        store.limiter(|state| &mut state.limits);
+        store.set_fuel(limits.fuel)?;
+
+        let mut linker = Linker::new(&engine);
+        link(&mut linker)?;
+        let instance = linker.instantiate(&mut store, &module)?.start(&mut store)?;
+        let memory = instance.get_memory(&store, "memory").ok_or(WasmError::MissingExport("memory"))?;
+
+        // The configuration, in memory allocated by the module
+        let alloc = instance.get_typed_func::<i32, i32>(&store, "alloc")?;
+        let ptr = alloc.call(&mut store, config.len() as i32)?;
+        memory.write(&mut store, ptr as u32 as usize, config.as_bytes()).map_err(|e| Error::new(e.to_string()))?;
+        let init = instance.get_typed_func::<(i32, i32), i32>(&store, "init")?;
+        let status = init.call(&mut store, (ptr, config.len() as i32))?;
+        if status != 0 {
+            return Err(WasmError::Refused(status));
+        }
+        let scratch = instance.get_typed_func::<(), i32>(&store, "scratch")?.call(&mut store, ())?;
+
+        Ok(Self {
+            memory,
+            scratch: scratch as u32 as usize,
+            fuel: limits.fuel,
+            on_top: instance.get_typed_func(&store, "on_top")?,
+            on_trade: instance.get_typed_func(&store, "on_trade")?,
+            on_report: instance.get_typed_func(&store, "on_report")?,
+            store,
+            trap: None,
+        })
+    }
+
+    /// Returns the trap that disabled the strategy, if any.
+    pub fn trap(&self) -> Option<&str> {
+        self.trap.as_deref()
+    }
+
+    /// Writes a string to the scratch buffer at `offset`, returning its length.
+    fn write_scratch(&mut self, offset: usize, s: &str) -> Result<i32, Error> {
+        if s.len() > SCRATCH_ID_OFFSET {
+            return Err(Error::new(format!("'{}' doesn't fit the scratch buffer", s)));
+        }
+        self.memory.write(&mut self.store, self.scratch + offset, s.as_bytes()).map_err(|e| Error::new(e.to_string()))?;
+        Ok(s.len() as i32)
+    }
+
+    /// Runs a callback of the module with the host submitting through `ctx`,
+    /// disabling the strategy if it traps.
+    fn call(&mut self, ctx: &mut Context<'_>, f: impl FnOnce(&mut Self) -> Result<(), Error>) {
+        if self.trap.is_some() {
+            return;
+        }
+        let state = self.store.data_mut();
+        state.context = (ctx as *mut Context<'_>).cast();
+        state.now_ms = ctx.now_ms();
+        let result = self.store.set_fuel(self.fuel).and_then(|()| f(self));
+        self.store.data_mut().context = ptr::null_mut();
+        if let Err(e) = result {
+            warn!("WASM strategy trapped, disabled: {}", e);
+            self.trap = Some(e.to_string());
+        }
+    }
+}
+
+impl Strategy for WasmStrategy {
+    fn on_top(&mut self, symbol: &str, top: &TopSnapshot, ctx: &mut Context<'_>) {
+        self.store.data_mut().tops.insert(symbol.to_string(), *top);
+        self.call(ctx, |wasm| {
+            let symbol_len = wasm.write_scratch(0, symbol)?;
+            let args = (symbol_len, top.update_id as i64, top.bid_price, top.bid_qty, top.ask_price, top.ask_qty);
+            wasm.on_top.call(&mut wasm.store, args)
+        });
+    }
+
+    fn on_trade(&mut self, symbol: &str, price: f64, qty: f64, ctx: &mut Context<'_>) {
+        self.call(ctx, |wasm| {
+            let symbol_len = wasm.write_scratch(0, symbol)?;
+            wasm.on_trade.call(&mut wasm.store, (symbol_len, price, qty))
+        });
+    }
+
+    fn on_report(&mut self, report: &PaperReport, ctx: &mut Context<'_>) {
+        self.call(ctx, |wasm| {
+            let id_len = wasm.write_scratch(SCRATCH_ID_OFFSET, &report.update.client_order_id)?;
+            let symbol_len = wasm.write_scratch(0, &report.symbol)?;
+            let args = (
+                id_len,
+                symbol_len,
+                matches!(report.side, Side::Sell) as i32,
+                (report.execution_type == ExecutionType::Trade) as i32,
+                report.update.status.is_terminal() as i32,
+                report.last_price,
+                report.last_qty,
+                report.update.executed_qty,
+            );
+            wasm.on_report.call(&mut wasm.store, args)
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use ctl_core::ClientOrderIdGenerator;
+    use ctl_oms::{OrderStatus, OrderUpdate};
+
+    use crate::OrderAction;
+
+    /// Bids the quantity of its configuration at the touch, cancels the bid on
+    /// a trade of a symbol with a known touch, and spins forever on a report.
+    const BIDDER: &str = r#"
+(module
+  (import "ctl" "submit" (func $submit (param i32 i32 i32 f64 f64 i32 i32) (result i32)))
+  (import "ctl" "cancel" (func $cancel (param i32 i32)))
+  (import "ctl" "top" (func $top (param i32 i32 i32) (result i32)))
+  (memory (export "memory") 1)
+  (global $id_len (mut i32) (i32.const 0))
+  (func (export "alloc") (param i32) (result i32) (i32.const 1024))
+  (func (export "scratch") (result i32) (i32.const 0))
+  (func (export "init") (param $config i32) (param $len i32) (result i32)
+    (i32.ne (i32.load8_u (local.get $config)) (i32.const 123)))
+  (func (export "on_top") (param $symbol_len i32) (param i64 f64 f64 f64 f64)
+    (global.set $id_len
+      (call $submit (i32.const 0) (local.get $symbol_len) (i32.const 0) (local.get 2) (f64.const 0.5)
+        (i32.const 512) (i32.const 64))))
+  (func (export "on_trade") (param $symbol_len i32) (param f64 f64)
+    (if (call $top (i32.const 0) (local.get $symbol_len) (i32.const 256))
+      (then (call $cancel (i32.const 512) (global.get $id_len)))))
+  (func (export "on_report") (param i32 i32 i32 i32 i32 f64 f64 f64)
+    (loop $spin (br $spin))))
+"#;
+
+    const LIMITS: WasmLimits = WasmLimits { fuel: 100_000, max_memory_bytes: 1 << 20 };
+
+    fn report(client_order_id: &str) -> PaperReport {
+        PaperReport {
+            update: OrderUpdate {
+                client_order_id: client_order_id.to_string(),
+                order_id: Some(1),
+                status: OrderStatus::New,
+                executed_qty: 0.0,
+            },
+            symbol: "BTCUSDT".to_string(),
+            side: Side::Buy,
+            execution_type: ExecutionType::New,
+            last_price: 0.0,
+            last_qty: 0.0,
+            commission: 0.0,
+            commission_asset: "USDT".to_string(),
+            is_maker: true,
+            trade_id: 0,
+            transact_time_ms: 0,
+        }
+    }
+
+    #[test]
+    fn test_sandboxed_callbacks() {
+        let wasm = wat::parse_str(BIDDER).unwrap();
+        let mut strategy = WasmStrategy::from_bytes(&wasm, "{}", LIMITS).unwrap();
+        let mut ids = ClientOrderIdGenerator::new(1, 0);
+        let mut actions = Vec::new();
+        let top = TopSnapshot { update_id: 1, bid_price: 99.0, bid_qty: 1.0, ask_price: 100.0, ask_qty: 1.0 };
+
+        // No touch of ETHUSDT yet, its trade is ignored
+        strategy.on_top("BTCUSDT", &top, &mut Context::new(&mut ids, &mut actions, 0));
+        strategy.on_trade("ETHUSDT", 10.0, 1.0, &mut Context::new(&mut ids, &mut actions, 1));
+        strategy.on_trade("BTCUSDT", 99.5, 1.0, &mut Context::new(&mut ids, &mut actions, 2));
+        assert_eq!(actions, vec![
+            OrderAction::Submit {
+                client_order_id: "ctl-1-0-0".to_string(),
+                symbol: "BTCUSDT".to_string(),
+                side: Side::Buy,
+                price: 99.0,
+                qty: 0.5,
+            },
+            OrderAction::Cancel { client_order_id: "ctl-1-0-0".to_string() },
+        ]);
+
+        // Spinning runs out of fuel, disabling the strategy rather than stalling the host
+        strategy.on_report(&report("ctl-1-0-0"), &mut Context::new(&mut ids, &mut actions, 3));
+        assert!(strategy.trap().is_some());
+        actions.clear();
+        strategy.on_top("BTCUSDT", &top, &mut Context::new(&mut ids, &mut actions, 4));
+        assert!(actions.is_empty());
+    }
+
+    #[test]
+    fn test_refused_modules() {
+        let wasm = wat::parse_str(BIDDER).unwrap();
+        assert!(matches!(WasmStrategy::from_bytes(&wasm, "[]", LIMITS), Err(WasmError::Refused(1))));
+
+        // A memory beyond the limit isn't instantiated
+        let large = wat::parse_str(&BIDDER.replace("(memory (export \"memory\") 1)", "(memory (export \"memory\") 64)"))
+            .unwrap();
+        assert!(matches!(WasmStrategy::from_bytes(&large, "{}", LIMITS), Err(WasmError::Wasm(_))));
+
+        let missing = wat::parse_str("(module (memory (export \"memory\") 1))").unwrap();
+        assert!(matches!(WasmStrategy::from_bytes(&missing, "{}", LIMITS), Err(WasmError::Wasm(_))));
+    }
+}
@@ -0,0 +1,229 @@
+//! The state shown by the console: the orders replayed from the OMS journal,
+//! the executions seen in it, the positions of their symbols and the alerts.
+
+use std::collections::VecDeque;
+
+use ctl_core::AlertMessage;
+use ctl_feed::LastTopRegion;
+use ctl_oms::{JournalRecord, OmsError, Order, OrderStatus, OrderTable, Side};
+use ctl_position::{Position, PositionRegion};
+
+/// Number of executions kept, the oldest dropped first.
+const MAX_EXECUTIONS: usize = 200;
+
+/// Number of alerts kept, the oldest dropped first.
+const MAX_ALERTS: usize = 100;
+
+/// A fill of an order, seen as the growth of its executed quantity in the journal.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Execution {
+    /// The client order ID of the order.
+    pub client_order_id: String,
+    /// The symbol.
+    pub symbol: String,
+    /// The side.
+    pub side: Side,
+    /// The quantity filled.
+    pub qty: f64,
+    /// The limit price of the order, the journal not recording the fill price.
+    pub price: f64,
+    /// The status of the order after the fill.
+    pub status: OrderStatus,
+    /// The time the fill was read from the journal, in milliseconds since the epoch.
+    pub seen_at_ms: u64,
+}
+
+/// The position of a symbol traded, marked to the mid price of its Top.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PositionRow {
+    /// The symbol.
+    pub symbol: String,
+    /// The position published by the position tracker.
+    pub position: Position,
+    /// The mid price of the last Top, `None` without a two-sided Top.
+    pub mark: Option<f64>,
+}
+
+impl PositionRow {
+    /// Returns the unrealized PnL at the mark price.
+    pub fn unrealized_pnl(&self) -> Option<f64> {
+        self.mark.map(|mark| self.position.unrealized_pnl(mark))
+    }
+}
+
+/// Reads the positions of the symbols traded (with their symbol IDs), marked
+/// to the last-value Top region. The symbols without fills are skipped.
+pub fn read_positions(
+    symbols: &[(String, u32)],
+    positions: &PositionRegion,
+    last_top: &LastTopRegion,
+) -> Vec<PositionRow> {
+    symbols
+        .iter()
+        .filter_map(|(symbol, symbol_id)| {
+            let position = positions.read(*symbol_id)?;
+            let mark = last_top
+                .read(*symbol_id)
+                .filter(|top| top.bid_price > 0.0 && top.ask_price > 0.0)
+                .map(|top| (top.bid_price + top.ask_price) / 2.0);
+            Some(PositionRow { symbol: symbol.clone(), position, mark })
+        })
+        .collect()
+}
+
+/// The orders, executions and alerts shown by the console.
+#[derive(Debug, Default)]
+pub struct Blotter {
+    orders: OrderTable,
+    /// The executions, the newest first.
+    executions: VecDeque<Execution>,
+    /// The symbols of the orders, sorted.
+    symbols: Vec<String>,
+    /// The alerts, the newest first.
+    alerts: VecDeque<AlertMessage>,
+}
+
+impl Blotter {
+    /// Creates an empty blotter.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Forgets the orders and executions, to replay a journal read over from its start.
+    pub fn clear_orders(&mut self) {
+        self.orders = OrderTable::default();
+        self.executions.clear();
+        self.symbols.clear();
+    }
+
+    /// Applies a record of the journal, read at `now_ms`.
+    ///
+    /// # Errors
+    /// Returns an error if the record doesn't apply to the orders replayed so far.
+    pub fn apply(&mut self, record: JournalRecord, now_ms: u64) -> Result<(), OmsError> {
+        match record {
+            JournalRecord::Submitted(order) => {
+                self.add_symbol(&order.symbol);
+                self.orders.insert(order)
+            }
+            JournalRecord::ListSubmitted { list, legs } => {
+                for leg in &legs {
+                    self.add_symbol(&leg.symbol);
+                }
+                self.orders.insert_list(list, legs)
+            }
+            JournalRecord::Updated(update) => {
+                let executed_qty = self.orders.get(&update.client_order_id).map_or(0.0, |order| order.executed_qty);
+                if !self.orders.update(&update)? || update.executed_qty <= executed_qty {
+                    return Ok(());
+                }
+                let Some(order) = self.orders.get(&update.client_order_id) else {
+                    return Ok(());
+                };
+                self.executions.push_front(Execution {
+                    client_order_id: order.client_order_id.clone(),
+                    symbol: order.symbol.clone(),
+                    side: order.side,
+                    qty: update.executed_qty - executed_qty,
+                    price: order.price,
+                    status: order.status,
+                    seen_at_ms: now_ms,
+                });
+                self.executions.truncate(MAX_EXECUTIONS);
+                Ok(())
+            }
+            JournalRecord::ListUpdated(update) => self.orders.update_list(&update).map(|_| ()),
+        }
+    }
+
+    /// Records an alert of the alerts ring.
+    pub fn record_alert(&mut self, alert: AlertMessage) {
+        self.alerts.push_front(alert);
+        self.alerts.truncate(MAX_ALERTS);
+    }
+
+    /// Returns the open orders, by client order ID.
+    pub fn open_orders(&self) -> Vec<&Order> {
+        let mut orders: Vec<_> = self.orders.open_orders().collect();
+        orders.sort_by(|a, b| a.client_order_id.cmp(&b.client_order_id));
+        orders
+    }
+
+    /// Returns the executions, the newest first.
+    pub fn executions(&self) -> impl Iterator<Item = &Execution> {
+        self.executions.iter()
+    }
+
+    /// Returns the alerts, the newest first.
+    pub fn alerts(&self) -> impl Iterator<Item = &AlertMessage> {
+        self.alerts.iter()
+    }
+
+    /// Returns the symbols of the orders, sorted.
+    pub fn symbols(&self) -> &[String] {
+        &self.symbols
+    }
+
+    /// Adds the symbol of an order, if not seen yet.
+    fn add_symbol(&mut self, symbol: &str) {
+        if let Err(index) = self.symbols.binary_search_by(|seen| seen.as_str().cmp(symbol)) {
+            self.symbols.insert(index, symbol.to_string());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ctl_oms::OrderUpdate;
+
+    use super::*;
+
+    fn order(client_order_id: &str, symbol: &str) -> Order {
+        Order {
+            client_order_id: client_order_id.to_string(),
+            order_id: None,
+            symbol: symbol.to_string(),
+            side: Side::Buy,
+            price: 100.0,
+            orig_qty: 2.0,
+            executed_qty: 0.0,
+            status: OrderStatus::PendingNew,
+            list_client_order_id: None,
+        }
+    }
+
+    fn update(client_order_id: &str, status: OrderStatus, executed_qty: f64) -> JournalRecord {
+        JournalRecord::Updated(OrderUpdate {
+            client_order_id: client_order_id.to_string(),
+            order_id: Some(1),
+            status,
+            executed_qty,
+        })
+    }
+
+    #[test]
+    fn test_blotter_apply() {
+        let mut blotter = Blotter::new();
+        blotter.apply(JournalRecord::Submitted(order("b", "ETHUSDT")), 0).unwrap();
+        blotter.apply(JournalRecord::Submitted(order("a", "BTCUSDT")), 0).unwrap();
+        blotter.apply(JournalRecord::Submitted(order("c", "BTCUSDT")), 0).unwrap();
+        assert_eq!(blotter.symbols(), ["BTCUSDT".to_string(), "ETHUSDT".to_string()]);
+
+        blotter.apply(update("a", OrderStatus::New, 0.0), 1).unwrap();
+        blotter.apply(update("a", OrderStatus::PartiallyFilled, 0.5), 2).unwrap();
+        blotter.apply(update("a", OrderStatus::Filled, 2.0), 3).unwrap();
+        blotter.apply(update("b", OrderStatus::Canceled, 0.0), 4).unwrap();
+        let fills: Vec<_> = blotter.executions().map(|fill| (fill.qty, fill.status, fill.seen_at_ms)).collect();
+        assert_eq!(fills, vec![(1.5, OrderStatus::Filled, 3), (0.5, OrderStatus::PartiallyFilled, 2)]);
+        let open: Vec<_> = blotter.open_orders().iter().map(|order| order.client_order_id.as_str()).collect();
+        assert_eq!(open, vec!["c"]);
+
+        // A duplicated update is no new execution
+        blotter.apply(update("a", OrderStatus::Filled, 2.0), 5).unwrap();
+        assert_eq!(blotter.executions().count(), 2);
+        assert!(blotter.apply(update("z", OrderStatus::New, 0.0), 6).is_err());
+
+        blotter.clear_orders();
+        assert!(blotter.open_orders().is_empty() && blotter.executions().next().is_none());
+    }
+}
@@ -0,0 +1,163 @@
+//! The order entry command line of the console.
+
+use ctl_oms::{NewOrder, Order, OrderType, Side, TimeInForce};
+
+use crate::CommandError;
+
+/// Parses an order entered on the command line as the new order
+/// `client_order_id`: `<buy|sell> <symbol> <qty> <price> [gtc|ioc|fok|maker]`,
+/// or `<buy|sell> <symbol> <qty> market`.
+///
+/// A limit order is good-til-canceled unless another time in force follows its
+/// price; `maker` enters a limit maker order, rejected if it would take.
+pub fn parse_order(line: &str, client_order_id: &str) -> Result<NewOrder, CommandError> {
+    let mut fields = line.split_whitespace();
+    let side = match fields.next().map(str::to_ascii_lowercase).as_deref() {
+        Some("buy") => Side::Buy,
+        Some("sell") => Side::Sell,
+        Some(other) => return Err(CommandError::UnknownSide(other.to_string())),
+        None => return Err(CommandError::Missing("side")),
+    };
+    let symbol = fields.next().ok_or(CommandError::Missing("symbol"))?.to_ascii_uppercase();
+    let qty = positive("quantity", fields.next())?;
+
+    let price = fields.next().ok_or(CommandError::Missing("price"))?;
+    let (order_type, price, time_in_force) = if price.eq_ignore_ascii_case("market") {
+        (OrderType::Market, 0.0, None)
+    } else {
+        let price = positive("price", Some(price))?;
+        match fields.next().map(str::to_ascii_lowercase).as_deref() {
+            None | Some("gtc") => (OrderType::Limit, price, Some(TimeInForce::Gtc)),
+            Some("ioc") => (OrderType::Limit, price, Some(TimeInForce::Ioc)),
+            Some("fok") => (OrderType::Limit, price, Some(TimeInForce::Fok)),
+            Some("maker") => (OrderType::LimitMaker, price, None),
+            Some(other) => return Err(CommandError::UnknownOption(other.to_string())),
+        }
+    };
+    if let Some(extra) = fields.next() {
+        return Err(CommandError::Unexpected(extra.to_string()));
+    }
+
+    Ok(NewOrder {
+        client_order_id: client_order_id.to_string(),
+        symbol,
+        side,
+        order_type,
+        price,
+        qty,
+        stop_price: None,
+        time_in_force,
+        iceberg_qty: None,
+        self_trade_prevention_mode: None,
+    })
+}
+
+/// Parses a field that must be a positive number.
+fn positive(field: &'static str, value: Option<&str>) -> Result<f64, CommandError> {
+    let value = value.ok_or(CommandError::Missing(field))?;
+    match value.parse::<f64>() {
+        Ok(number) if number.is_finite() && number > 0.0 => Ok(number),
+        _ => Err(CommandError::InvalidNumber { field, value: value.to_string() }),
+    }
+}
+
+/// Returns the name of a side on the exchange.
+pub fn side_name(side: Side) -> &'static str {
+    match side {
+        Side::Buy => "BUY",
+        Side::Sell => "SELL",
+    }
+}
+
+/// Describes an order for the confirmation prompt, e.g. `BUY 0.01 BTCUSDT @ 65000 IOC`.
+pub fn describe_order(order: &NewOrder) -> String {
+    let mut description = format!("{} {} {}", side_name(order.side), order.qty, order.symbol);
+    match (order.order_type, order.time_in_force) {
+        (OrderType::Market, _) => description.push_str(" at market"),
+        (order_type, time_in_force) => {
+            description.push_str(&format!(" @ {}", order.price));
+            match time_in_force {
+                Some(time_in_force) => description.push_str(&format!(" {:?}", time_in_force).to_ascii_uppercase()),
+                None => description.push_str(&format!(" {}", order_type.as_str())),
+            }
+        }
+    }
+    description
+}
+
+/// An order entry awaiting the confirmation of the operator.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Pending {
+    /// A new order.
+    New(NewOrder),
+    /// A cancel of an open order.
+    Cancel {
+        /// The client order ID of the order.
+        client_order_id: String,
+        /// The description of the order.
+        description: String,
+    },
+}
+
+impl Pending {
+    /// Returns the cancel of an open order.
+    pub fn cancel(order: &Order) -> Self {
+        Self::Cancel {
+            client_order_id: order.client_order_id.clone(),
+            description: format!("{} {} {} @ {}", side_name(order.side), order.orig_qty, order.symbol, order.price),
+        }
+    }
+
+    /// Describes the entry for the confirmation prompt.
+    pub fn describe(&self) -> String {
+        match self {
+            Self::New(order) => describe_order(order),
+            Self::Cancel { client_order_id, description } => format!("CANCEL {} ({})", client_order_id, description),
+        }
+    }
+}
+
+/// What the command line of the console is doing.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum Mode {
+    /// Browsing the orders.
+    #[default]
+    Browse,
+    /// Typing an order, with the text typed so far.
+    Entry(String),
+    /// Awaiting the confirmation of an entry.
+    Confirm(Pending),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_order() {
+        let order = parse_order("buy btcusdt 0.01 65000", "ctl-9-a-0").unwrap();
+        assert_eq!((order.side, order.symbol.as_str(), order.qty, order.price), (Side::Buy, "BTCUSDT", 0.01, 65000.0));
+        assert_eq!((order.order_type, order.time_in_force), (OrderType::Limit, Some(TimeInForce::Gtc)));
+        assert_eq!(describe_order(&order), "BUY 0.01 BTCUSDT @ 65000 GTC");
+
+        let order = parse_order("SELL ETHUSDT 0.5 2400.5 maker", "ctl-9-a-1").unwrap();
+        assert_eq!((order.order_type, order.time_in_force), (OrderType::LimitMaker, None));
+        assert_eq!(describe_order(&order), "SELL 0.5 ETHUSDT @ 2400.5 LIMIT_MAKER");
+
+        let order = parse_order("sell BNBUSDT 2 market", "ctl-9-a-2").unwrap();
+        assert_eq!((order.order_type, order.price, order.time_in_force), (OrderType::Market, 0.0, None));
+        assert_eq!(describe_order(&order), "SELL 2 BNBUSDT at market");
+    }
+
+    #[test]
+    fn test_parse_invalid_order() {
+        let parse = |line| parse_order(line, "ctl-9-a-0").unwrap_err();
+        assert_eq!(parse(""), CommandError::Missing("side"));
+        assert_eq!(parse("hold BTCUSDT 1 2"), CommandError::UnknownSide("hold".to_string()));
+        assert_eq!(parse("buy BTCUSDT 1"), CommandError::Missing("price"));
+        let invalid = CommandError::InvalidNumber { field: "quantity", value: "-1".to_string() };
+        assert_eq!(parse("buy BTCUSDT -1 2"), invalid);
+        assert_eq!(parse("buy BTCUSDT 1 2 gtd"), CommandError::UnknownOption("gtd".to_string()));
+        assert_eq!(parse("buy BTCUSDT 1 market ioc"), CommandError::Unexpected("ioc".to_string()));
+    }
+}
@@ -0,0 +1,24 @@
+use thiserror::Error;
+
+/// Errors of an order entered on the command line of the console.
+#[derive(Debug, Error, PartialEq)]
+pub enum CommandError {
+    /// The order doesn't start with its side.
+    #[error("Expected buy or sell, got '{0}'")]
+    UnknownSide(String),
+    /// A field of the order is missing.
+    #[error("Missing {0}")]
+    Missing(&'static str),
+    /// A quantity or a price isn't a positive number.
+    #[error("Invalid {field} '{value}'")]
+    InvalidNumber { field: &'static str, value: String },
+    /// The option following the price isn't known.
+    #[error("Unknown option '{0}', expected gtc, ioc, fok or maker")]
+    UnknownOption(String),
+    /// The order has more fields than expected.
+    #[error("Unexpected '{0}'")]
+    Unexpected(String),
+    /// The symbol isn't in the symbol info configuration.
+    #[error("Unknown symbol '{0}'")]
+    UnknownSymbol(String),
+}
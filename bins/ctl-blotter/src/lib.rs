@@ -0,0 +1,19 @@
+//! Operator console of the orders of Binance Spot.
+//!
+//! Shows the open orders and the executions of an OMS, followed in its
+//! journal, the positions of their symbols read from the position region and
+//! marked to the last-value Top region, the trading state of the status table
+//! and the alerts of the components, so the operators can step in during an
+//! incident without external tooling. The orders and cancels entered manually
+//! are published to the order entry ring, the OMS checking them like the orders
+//! of its strategy.
+
+mod blotter;
+mod command;
+mod errors;
+mod ui;
+
+pub use blotter::{read_positions, Blotter, Execution, PositionRow};
+pub use command::{describe_order, parse_order, side_name, Mode, Pending};
+pub use errors::CommandError;
+pub use ui::{draw, Console};
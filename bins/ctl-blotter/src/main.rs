@@ -0,0 +1,307 @@
+//! Operator Console for the Binance Spot Controller.
+//!
+//! Connects as a DPDK secondary process and shows the open orders and the
+//! executions of an OMS, followed in its journal, the positions of their
+//! symbols marked to the last-value Top region, the trading state and the
+//! alerts, redrawn a few times a second. The orders and cancels entered are
+//! published to the order entry ring, addressed to the OMS of `--component-id`,
+//! which checks them like the orders of its strategy (symbol rules, order rate
+//! budget, halt and circuit breaker): an entry rejected shows in the alerts,
+//! an entry accepted in the orders once journaled.
+//!
+//! Keys: `q` quits, the arrows select an open order, `n` enters an order
+//! (e.g. `buy BTCUSDT 0.01 65000 ioc`, `sell ETHUSDT 0.5 market`), `c` cancels
+//! the selected order; each entry is confirmed with `y`.
+//!
+//! The logs are only written until the terminal is taken over. The exit code
+//! tells the class of a failure (see `FatalKind`).
+
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use clap::Parser;
+use ctl_blotter::{draw, parse_order, read_positions, Blotter, CommandError, Console, Mode, Pending};
+use ctl_core::{
+    AlertMessage, AuditOrigin, Capability, ClientOrderIdGenerator, Fatal, FatalError, FatalKind, LcoresConfig,
+    Preflight, StatusRegion, ALERTS_RING_NAME, STATUS_REGION_NAME,
+};
+use ctl_feed::{LastTopRegion, MetricsRegion, LAST_TOP_REGION_NAME, METRICS_REGION_NAME};
+use ctl_md_handler::{HwResourcesConfig, SymbolInfoConfig};
+use ctl_oms::{JournalRecord, JournalTail, OrderEntryMessage, ORDER_ENTRY_RING_NAME};
+use ctl_position::{PositionRegion, POSITION_REGION_NAME};
+use ctl_shm::ShmRegion;
+use ctl_time::now_ms;
+use dpdk::{ConsumeStartState, DpdkEnvBuilder, DpdkProcessType, DpdkPubSubRing};
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::widgets::TableState;
+use tracing::info;
+
+// Logging configuration, its levels reloaded on change
+const LOG_CONFIG_PATH: &str = "configs/logging.yaml";
+
+// The market data configuration, holding the EAL options of the secondary processes
+const MD_CONFIG_PATH: &str = "configs/market-data/hw-resources.yaml";
+
+// The symbol IDs of the positions and the Top region
+const SYMBOL_INFO_PATH: &str = "configs/market-data/symbolinfo.yaml";
+
+// The lcore of the console, unless configured, shared with the mostly idle tools
+const LCORES_CONFIG_PATH: &str = "configs/lcores.yaml";
+const LCORES_COMPONENT: &str = "blotter";
+const DEFAULT_LCORE: u32 = 15;
+
+// Interval between the redraws, reading the journal and the regions
+const REFRESH_INTERVAL: Duration = Duration::from_millis(250);
+
+// Pause between the polls of the alerts ring once it is empty
+const IDLE_PAUSE: Duration = Duration::from_millis(5);
+
+/// Operator console of the orders, positions and executions of an OMS.
+#[derive(Debug, Parser)]
+#[command(version)]
+struct Args {
+    /// Journal of the OMS.
+    #[arg(long, env = "CTL_OMS_JOURNAL")]
+    journal: PathBuf,
+    /// Component ID of the OMS the orders are entered to.
+    #[arg(long, env = "CTL_OMS_COMPONENT_ID")]
+    component_id: u16,
+    /// Logging configuration.
+    #[arg(long, env = "CTL_LOG_CONFIG", default_value = LOG_CONFIG_PATH)]
+    log_config: PathBuf,
+    /// Market data configuration.
+    #[arg(long, env = "CTL_MD_CONFIG", default_value = MD_CONFIG_PATH)]
+    md_config: PathBuf,
+    /// Symbol info configuration.
+    #[arg(long, env = "CTL_SYMBOL_INFO", default_value = SYMBOL_INFO_PATH)]
+    symbol_info: PathBuf,
+    /// Lcores configuration.
+    #[arg(long, env = "CTL_LCORES_CONFIG", default_value = LCORES_CONFIG_PATH)]
+    lcores_config: PathBuf,
+}
+
+/// Restores the terminal when dropped, including on a fatal error.
+struct TerminalGuard;
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        ratatui::restore();
+    }
+}
+
+/// Publishes the confirmed entries to the order entry ring.
+struct OrderEntry {
+    ring: DpdkPubSubRing<OrderEntryMessage>,
+    ids: ClientOrderIdGenerator,
+    operator: String,
+}
+
+impl OrderEntry {
+    /// Publishes a confirmed entry, returning the message shown.
+    fn publish(&self, pending: &Pending) -> Result<String, FatalError> {
+        let component_id = self.ids.component_id();
+        let message = match pending {
+            Pending::New(order) => OrderEntryMessage::new_order(component_id, order, now_ms(), &self.operator),
+            Pending::Cancel { client_order_id, .. } => {
+                OrderEntryMessage::cancel(component_id, client_order_id, now_ms(), &self.operator)
+            }
+        };
+        let message = match message {
+            Ok(message) => message,
+            Err(e) => return Ok(format!("Not entered: {}", e)),
+        };
+        self.ring.publish(&message).fatal(FatalKind::Internal)?;
+        match pending {
+            Pending::New(order) => {
+                Ok(format!("Entered {} as {}, pending the OMS checks", pending.describe(), order.client_order_id))
+            }
+            Pending::Cancel { .. } => Ok(format!("Entered {}, pending the OMS", pending.describe())),
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    ctl_core::exit_code(run())
+}
+
+fn run() -> Result<(), FatalError> {
+    let args = Args::parse();
+    let _log = ctl_log::init_from_file(&args.log_config).fatal(FatalKind::Config)?;
+
+    info!("=== Binance Spot Operator Console ===");
+
+    let md_config = HwResourcesConfig::from_file(&args.md_config).fatal(FatalKind::Config)?;
+    let symbol_info = SymbolInfoConfig::from_file(&args.symbol_info).fatal(FatalKind::Config)?;
+    md_config.eal.apply_region_prefix();
+    let lcore = LcoresConfig::from_file(&args.lcores_config)?.lcore(LCORES_COMPONENT, DEFAULT_LCORE) as usize;
+
+    // Verify the host before the EAL initialization, which reports its failures cryptically
+    Preflight::new()
+        .require_hugepages_mounted()
+        .require_capabilities(&[Capability::IpcLock])
+        .require_primary()
+        .with_file_prefix(md_config.eal.file_prefix())
+        .run()?;
+
+    let dpdk_env = DpdkEnvBuilder::default()
+        .process_type(DpdkProcessType::Secondary)
+        .lcore_ids(vec![lcore])
+        .main_lcore_id(lcore)
+        .eal_args(md_config.eal.secondary_args())
+        .build()
+        .fatal(FatalKind::DpdkInit)?;
+
+    let metrics = ShmRegion::<MetricsRegion>::open(METRICS_REGION_NAME)?;
+    let status = ShmRegion::<StatusRegion>::open(STATUS_REGION_NAME)?;
+    let positions = ShmRegion::<PositionRegion>::open(POSITION_REGION_NAME)?;
+    let last_top = ShmRegion::<LastTopRegion>::open(LAST_TOP_REGION_NAME)?;
+
+    // Attach to the alerts before entering orders, not to miss their rejections
+    let alerts = dpdk_env.pubsub_lookup::<AlertMessage>(ALERTS_RING_NAME).fatal(FatalKind::SharedState)?;
+    metrics.check_layout::<AlertMessage>(ALERTS_RING_NAME).fatal(FatalKind::SharedState)?;
+    let mut alerts = alerts.attach_consumer().fatal(FatalKind::SharedState)?;
+    let ring = dpdk_env.pubsub_lookup::<OrderEntryMessage>(ORDER_ENTRY_RING_NAME).fatal(FatalKind::SharedState)?;
+    metrics.check_layout::<OrderEntryMessage>(ORDER_ENTRY_RING_NAME).fatal(FatalKind::SharedState)?;
+
+    // The operator is the user running the console, the one behind `sudo` if any
+    let operator = AuditOrigin::current(LCORES_COMPONENT).user;
+    let mut entry = OrderEntry { ring, ids: ClientOrderIdGenerator::start(args.component_id), operator };
+    let mut journal = JournalTail::open(&args.journal, true).fatal(FatalKind::Config)?;
+    info!(
+        "Following journal {} of the OMS of component {} as {}",
+        args.journal.display(),
+        args.component_id,
+        entry.operator
+    );
+
+    let mut terminal = ratatui::try_init().fatal(FatalKind::Io)?;
+    let _terminal_guard = TerminalGuard;
+    let mut table_state = TableState::default().with_selected(Some(0));
+    let mut blotter = Blotter::new();
+    let mut console = Console {
+        mode: Mode::Browse,
+        message: String::new(),
+        trading: status.snapshot(),
+        component_id: args.component_id,
+        operator: entry.operator.clone(),
+    };
+
+    let mut resets = journal.resets();
+    let mut last_refresh: Option<Instant> = None;
+    loop {
+        let mut did_work = false;
+        match alerts.consume_start() {
+            ConsumeStartState::Success(mut guard) => {
+                if guard.try_commit().is_ok() {
+                    blotter.record_alert(*guard.as_ref().get());
+                }
+                did_work = true;
+            }
+            ConsumeStartState::SpedPast(_guard) => {
+                console.message = "Alerts overwritten before they were read".to_string();
+                did_work = true;
+            }
+            ConsumeStartState::InFlight(_) | ConsumeStartState::Empty => {}
+        }
+
+        let now = Instant::now();
+        if last_refresh.is_none_or(|last| now.duration_since(last) >= REFRESH_INTERVAL) {
+            last_refresh = Some(now);
+            let now_ms = now_ms();
+            match journal.poll() {
+                Ok(records) => {
+                    if journal.resets() != resets {
+                        resets = journal.resets();
+                        blotter.clear_orders();
+                    }
+                    for record in records {
+                        let applied = serde_json::from_slice::<JournalRecord>(&record)
+                            .map_err(|e| e.to_string())
+                            .and_then(|record| blotter.apply(record, now_ms).map_err(|e| e.to_string()));
+                        if let Err(e) = applied {
+                            console.message = format!("Journal record not applied: {}", e);
+                        }
+                    }
+                }
+                Err(e) => console.message = format!("Failed to read journal {}: {}", args.journal.display(), e),
+            }
+            let symbols: Vec<(String, u32)> = blotter
+                .symbols()
+                .iter()
+                .filter_map(|symbol| Some((symbol.clone(), symbol_info.symbol_id(symbol)?)))
+                .collect();
+            let position_rows = read_positions(&symbols, &positions, &last_top);
+            console.trading = status.snapshot();
+            terminal
+                .draw(|frame| draw(frame, &blotter, &position_rows, &console, &mut table_state, now_ms))
+                .fatal(FatalKind::Io)?;
+
+            while event::poll(Duration::ZERO).fatal(FatalKind::Io)? {
+                let Event::Key(key) = event::read().fatal(FatalKind::Io)? else {
+                    continue;
+                };
+                if key.kind != KeyEventKind::Press {
+                    continue;
+                }
+                if !on_key(key.code, &mut console, &blotter, &mut table_state, &mut entry, &symbol_info)? {
+                    return Ok(());
+                }
+            }
+        }
+        if !did_work {
+            thread::sleep(IDLE_PAUSE);
+        }
+    }
+}
+
+/// Handles a key of the operator, returning false to quit.
+fn on_key(
+    key: KeyCode,
+    console: &mut Console,
+    blotter: &Blotter,
+    table_state: &mut TableState,
+    entry: &mut OrderEntry,
+    symbol_info: &SymbolInfoConfig,
+) -> Result<bool, FatalError> {
+    match (&mut console.mode, key) {
+        (Mode::Browse, KeyCode::Char('q') | KeyCode::Esc) => return Ok(false),
+        (Mode::Browse, KeyCode::Down) => table_state.select_next(),
+        (Mode::Browse, KeyCode::Up) => table_state.select_previous(),
+        (Mode::Browse, KeyCode::Char('n')) => console.mode = Mode::Entry(String::new()),
+        (Mode::Browse, KeyCode::Char('c')) => {
+            let open_orders = blotter.open_orders();
+            match table_state.selected().and_then(|index| open_orders.get(index)) {
+                Some(order) => console.mode = Mode::Confirm(Pending::cancel(order)),
+                None => console.message = "No open order selected".to_string(),
+            }
+        }
+        (Mode::Entry(input), KeyCode::Char(c)) => input.push(c),
+        (Mode::Entry(input), KeyCode::Backspace) => {
+            input.pop();
+        }
+        (Mode::Entry(input), KeyCode::Enter) => {
+            let parsed = parse_order(input, &entry.ids.next_id().to_string()).and_then(|order| {
+                match symbol_info.symbol_id(&order.symbol) {
+                    Some(_) => Ok(order),
+                    None => Err(CommandError::UnknownSymbol(order.symbol)),
+                }
+            });
+            match parsed {
+                Ok(order) => console.mode = Mode::Confirm(Pending::New(order)),
+                // Keep the order typed, to be fixed
+                Err(e) => console.message = e.to_string(),
+            }
+        }
+        (Mode::Entry(_), KeyCode::Esc) | (Mode::Confirm(_), KeyCode::Char('n') | KeyCode::Esc) => {
+            console.mode = Mode::Browse;
+        }
+        (Mode::Confirm(pending), KeyCode::Char('y')) => {
+            console.message = entry.publish(pending)?;
+            console.mode = Mode::Browse;
+        }
+        _ => {}
+    }
+    Ok(true)
+}
@@ -0,0 +1,243 @@
+//! The rendering of the console in the terminal.
+
+use ctl_core::{AlertSeverity, TradingStatus};
+use ctl_oms::Side;
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Cell, Paragraph, Row, Table, TableState};
+use ratatui::Frame;
+
+use crate::{side_name, Blotter, Mode, PositionRow};
+
+/// The column headers of the open orders, with their widths.
+const ORDER_COLUMNS: [(&str, u16); 7] = [
+    ("Client order ID", 36),
+    ("Symbol", 12),
+    ("Side", 5),
+    ("Price", 14),
+    ("Qty", 12),
+    ("Filled", 12),
+    ("Status", 16),
+];
+
+/// The column headers of the positions, with their widths.
+const POSITION_COLUMNS: [(&str, u16); 7] = [
+    ("Symbol", 12),
+    ("Position", 14),
+    ("Avg entry", 14),
+    ("Mark", 14),
+    ("Realized", 14),
+    ("Unrealized", 14),
+    ("Fills", 8),
+];
+
+/// The column headers of the executions, with their widths.
+const EXECUTION_COLUMNS: [(&str, u16); 6] =
+    [("Age", 7), ("Symbol", 12), ("Side", 5), ("Qty", 12), ("Price", 14), ("Status", 16)];
+
+/// The syntax of an order entered on the command line.
+const ENTRY_USAGE: &str = "<buy|sell> <symbol> <qty> <price> [gtc|ioc|fok|maker] | market";
+
+/// Number of position rows the panel is sized for, the others cut off.
+const MAX_POSITION_ROWS: usize = 6;
+
+/// The placeholder of a value not known yet.
+const MISSING: &str = "-";
+
+/// The state of the console shown around the tables.
+#[derive(Debug, Clone)]
+pub struct Console {
+    /// What the command line is doing.
+    pub mode: Mode,
+    /// The outcome of the last entry, or the last error.
+    pub message: String,
+    /// The trading state of the status table.
+    pub trading: TradingStatus,
+    /// The component ID of the OMS the orders are entered to.
+    pub component_id: u16,
+    /// The operator entering the orders.
+    pub operator: String,
+}
+
+/// Draws the console at `now_ms`: the trading state, the open orders with
+/// the selected one highlighted, the positions, the executions, the alerts
+/// and the command line.
+pub fn draw(
+    frame: &mut Frame<'_>,
+    blotter: &Blotter,
+    positions: &[PositionRow],
+    console: &Console,
+    table_state: &mut TableState,
+    now_ms: u64,
+) {
+    let position_rows = positions.len().clamp(1, MAX_POSITION_ROWS) as u16;
+    let [header_area, orders_area, positions_area, bottom_area, prompt_area, message_area] = Layout::vertical([
+        Constraint::Length(1),
+        Constraint::Min(6),
+        Constraint::Length(position_rows + 3),
+        Constraint::Length(10),
+        Constraint::Length(1),
+        Constraint::Length(1),
+    ])
+    .areas(frame.area());
+    let [executions_area, alerts_area] =
+        Layout::horizontal([Constraint::Percentage(50), Constraint::Percentage(50)]).areas(bottom_area);
+
+    frame.render_widget(Paragraph::new(header(console)), header_area);
+
+    let open_orders = blotter.open_orders();
+    let rows = open_orders.iter().map(|order| {
+        Row::new([
+            Cell::from(order.client_order_id.clone()),
+            Cell::from(order.symbol.clone()),
+            Cell::from(side_name(order.side)).style(side_style(order.side)),
+            Cell::from(order.price.to_string()),
+            Cell::from(order.orig_qty.to_string()),
+            Cell::from(order.executed_qty.to_string()),
+            Cell::from(format!("{:?}", order.status)),
+        ])
+    });
+    let table = Table::new(rows, widths(&ORDER_COLUMNS))
+        .header(header_row(&ORDER_COLUMNS))
+        .block(Block::bordered().title(format!(" Open orders: {} ", open_orders.len())))
+        .row_highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+    frame.render_stateful_widget(table, orders_area, table_state);
+
+    let rows = positions.iter().map(|row| {
+        let position = &row.position;
+        Row::new([
+            Cell::from(row.symbol.clone()),
+            Cell::from(position.base.to_string()),
+            Cell::from(position.avg_entry_price.to_string()),
+            Cell::from(row.mark.map_or(MISSING.to_string(), |mark| mark.to_string())),
+            Cell::from(format!("{:.4}", position.realized_pnl)).style(pnl_style(position.realized_pnl)),
+            Cell::from(row.unrealized_pnl().map_or(MISSING.to_string(), |pnl| format!("{:.4}", pnl)))
+                .style(row.unrealized_pnl().map_or(Style::default(), pnl_style)),
+            Cell::from(position.fill_count.to_string()),
+        ])
+    });
+    let table = Table::new(rows, widths(&POSITION_COLUMNS))
+        .header(header_row(&POSITION_COLUMNS))
+        .block(Block::bordered().title(" Positions "));
+    frame.render_widget(table, positions_area);
+
+    let rows = blotter.executions().map(|fill| {
+        Row::new([
+            Cell::from(format_age(now_ms.saturating_sub(fill.seen_at_ms))),
+            Cell::from(fill.symbol.clone()),
+            Cell::from(side_name(fill.side)).style(side_style(fill.side)),
+            Cell::from(fill.qty.to_string()),
+            Cell::from(fill.price.to_string()),
+            Cell::from(format!("{:?}", fill.status)),
+        ])
+    });
+    let table = Table::new(rows, widths(&EXECUTION_COLUMNS))
+        .header(header_row(&EXECUTION_COLUMNS))
+        .block(Block::bordered().title(" Executions "));
+    frame.render_widget(table, executions_area);
+
+    let lines: Vec<Line<'_>> = blotter
+        .alerts()
+        .map(|alert| {
+            let style = match alert.severity() {
+                AlertSeverity::Info => Style::default(),
+                AlertSeverity::Warning => Style::default().fg(Color::Yellow),
+                AlertSeverity::Critical => Style::default().fg(Color::Red),
+            };
+            Line::styled(
+                format!(
+                    "{:>6} {:?} [{}] {}",
+                    format_age(now_ms.saturating_sub(alert.raised_at_ms)),
+                    alert.kind(),
+                    alert.source(),
+                    alert.detail()
+                ),
+                style,
+            )
+        })
+        .collect();
+    frame.render_widget(Paragraph::new(lines).block(Block::bordered().title(" Alerts ")), alerts_area);
+
+    let prompt = match &console.mode {
+        Mode::Browse => " q quit, \u{2191}/\u{2193} select, n new order, c cancel the selected order".to_string(),
+        Mode::Entry(input) => format!(" order> {}_   ({}, Esc back)", input, ENTRY_USAGE),
+        Mode::Confirm(pending) => format!(" {} ? y/n", pending.describe()),
+    };
+    let prompt = Line::styled(prompt, Style::default().add_modifier(Modifier::BOLD));
+    frame.render_widget(Paragraph::new(prompt), prompt_area);
+    frame.render_widget(Paragraph::new(Line::from(format!(" {}", console.message))), message_area);
+}
+
+/// Returns the line of the trading state.
+fn header(console: &Console) -> Line<'static> {
+    let trading = &console.trading;
+    let mut spans = vec![Span::raw(format!(
+        " ctl-blotter | OMS {} | operator {} | ",
+        console.component_id, console.operator
+    ))];
+    let alarm = Style::default().fg(Color::Red).add_modifier(Modifier::BOLD);
+    if trading.halted {
+        spans.push(Span::styled("TRADING HALTED", alarm));
+    } else {
+        spans.push(Span::styled("trading active", Style::default().fg(Color::Green)));
+    }
+    if trading.tripped {
+        let by = trading.tripped_by.map_or(String::new(), |signal| format!(" by {:?}", signal));
+        spans.push(Span::styled(format!(" | BREAKER TRIPPED{}", by), alarm));
+    }
+    if trading.degraded {
+        spans.push(Span::styled(" | DEGRADED to REST", Style::default().fg(Color::Yellow)));
+    }
+    Line::from(spans)
+}
+
+/// Returns the header row of a table.
+fn header_row(columns: &[(&'static str, u16)]) -> Row<'static> {
+    Row::new(columns.iter().map(|(name, _)| Cell::from(*name))).style(Style::default().add_modifier(Modifier::BOLD))
+}
+
+/// Returns the widths of the columns of a table.
+fn widths(columns: &[(&str, u16)]) -> Vec<Constraint> {
+    columns.iter().map(|(_, width)| Constraint::Length(*width)).collect()
+}
+
+/// Returns the style of a side.
+fn side_style(side: Side) -> Style {
+    match side {
+        Side::Buy => Style::default().fg(Color::Green),
+        Side::Sell => Style::default().fg(Color::Red),
+    }
+}
+
+/// Returns the style of a PnL, by its sign.
+fn pnl_style(pnl: f64) -> Style {
+    if pnl < 0.0 {
+        Style::default().fg(Color::Red)
+    } else {
+        Style::default().fg(Color::Green)
+    }
+}
+
+/// Formats an age, in its largest unit.
+fn format_age(age_ms: u64) -> String {
+    match age_ms {
+        0..1_000 => format!("{}ms", age_ms),
+        1_000..60_000 => format!("{:.1}s", age_ms as f64 / 1_000.0),
+        60_000..3_600_000 => format!("{}m", age_ms / 60_000),
+        _ => format!("{}h", age_ms / 3_600_000),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_age() {
+        assert_eq!(format_age(350), "350ms");
+        assert_eq!(format_age(4_300), "4.3s");
+        assert_eq!(format_age(180_000), "3m");
+        assert_eq!(format_age(7_200_000), "2h");
+    }
+}
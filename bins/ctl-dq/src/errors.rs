@@ -0,0 +1,9 @@
+use thiserror::Error;
+
+/// Errors that can occur when checking the data quality.
+#[derive(Debug, Error)]
+pub enum DqError {
+    /// Error reading a session file.
+    #[error("Session I/O error: {0}")]
+    IoError(#[from] std::io::Error),
+}
@@ -0,0 +1,16 @@
+//! Data-quality checks of the Binance Spot market data.
+//!
+//! Scans a recorded session, or samples the live rings over a window, and
+//! measures the quality of each stream (sequence gaps, out-of-order times,
+//! latency percentiles and coverage), reported as JSON or CSV for the daily
+//! checks.
+
+mod errors;
+mod quality;
+mod rings;
+mod session;
+
+pub use errors::DqError;
+pub use quality::{LatencyPercentiles, Observation, QualityReport, QualityTracker, RingQuality, StreamQuality};
+pub use rings::RingCheck;
+pub use session::{payload_fields, scan_session};
@@ -0,0 +1,215 @@
+//! Data-quality report of the market data, for the daily checks.
+//!
+//! With `--session`, scans a session file written by the recorder (or by
+//! ctl-historical) without DPDK. Otherwise connects as a DPDK secondary
+//! process, attaches a consumer to each ring matching `--rings` and samples
+//! them for `--window-s` seconds. Either way, reports per stream and symbol the
+//! ID gaps, the out-of-order receive and event times, the latency percentiles
+//! and the coverage of the minutes, as JSON or CSV (`--format`), to stdout or
+//! `--output`.
+//!
+//! The exit code tells the class of a failure (see `FatalKind`).
+
+use std::fs::{self, File};
+use std::io::BufReader;
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::sync::atomic::Ordering;
+use std::time::{Duration, Instant};
+
+use clap::Parser;
+use ctl_core::{Capability, Fatal, FatalError, FatalKind, LcoresConfig, Preflight};
+use ctl_dq::{scan_session, QualityReport, QualityTracker, RingCheck};
+use ctl_feed::{MetricsRegion, RawMessage, RingDirectory, RingPattern, METRICS_REGION_NAME};
+use ctl_md_handler::HwResourcesConfig;
+use ctl_shm::ShmRegion;
+use dpdk::{ConsumeStartState, DpdkEnvBuilder, DpdkProcessType};
+use tracing::{info, warn};
+
+// Logging configuration, its levels reloaded on change
+const LOG_CONFIG_PATH: &str = "configs/logging.yaml";
+
+// The market data configuration, holding the EAL options of the secondary processes
+const MD_CONFIG_PATH: &str = "configs/market-data/hw-resources.yaml";
+
+// The rings sampled, unless given
+const RING_PATTERN: &str = "*_PS";
+
+// The sampling window of the rings, unless given
+const DEFAULT_WINDOW_S: u64 = 60;
+
+// The name of the consumer cursors of the checks in the rings
+const CONSUMER_NAME: &str = "dq";
+
+// The lcore of the checks, unless configured, shared with the mostly idle tools
+const LCORES_CONFIG_PATH: &str = "configs/lcores.yaml";
+const LCORES_COMPONENT: &str = "dq";
+const DEFAULT_LCORE: u32 = 15;
+
+// Pause between the polls of the rings once they are all empty
+const IDLE_PAUSE: Duration = Duration::from_micros(100);
+
+/// The format of the report.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ReportFormat {
+    /// The whole report, as pretty JSON.
+    Json,
+    /// The streams, one row each.
+    Csv,
+}
+
+/// Parses a report format of the command line.
+fn parse_format(name: &str) -> Result<ReportFormat, String> {
+    match name {
+        "json" => Ok(ReportFormat::Json),
+        "csv" => Ok(ReportFormat::Csv),
+        _ => Err(format!("unknown report format '{}', expected json or csv", name)),
+    }
+}
+
+/// Data-quality report of a session file or of the live rings.
+#[derive(Debug, Parser)]
+#[command(version)]
+struct Args {
+    /// Logging configuration.
+    #[arg(long, env = "CTL_LOG_CONFIG", default_value = LOG_CONFIG_PATH)]
+    log_config: PathBuf,
+    /// Market data configuration.
+    #[arg(long, env = "CTL_MD_CONFIG", default_value = MD_CONFIG_PATH)]
+    md_config: PathBuf,
+    /// Lcores configuration.
+    #[arg(long, env = "CTL_LCORES_CONFIG", default_value = LCORES_CONFIG_PATH)]
+    lcores_config: PathBuf,
+    /// The session file scanned, instead of sampling the live rings.
+    #[arg(long)]
+    session: Option<PathBuf>,
+    /// The rings sampled (e.g. `TRADE_*_PS`).
+    #[arg(long, default_value = RING_PATTERN, conflicts_with = "session")]
+    rings: String,
+    /// The sampling window of the rings, in seconds.
+    #[arg(long, default_value_t = DEFAULT_WINDOW_S, conflicts_with = "session")]
+    window_s: u64,
+    /// The format of the report, `json` or `csv`.
+    #[arg(long, default_value = "json", value_parser = parse_format)]
+    format: ReportFormat,
+    /// The file the report is written to, instead of stdout.
+    #[arg(long)]
+    output: Option<PathBuf>,
+}
+
+fn main() -> ExitCode {
+    ctl_core::exit_code(run())
+}
+
+fn run() -> Result<(), FatalError> {
+    let args = Args::parse();
+    let _log = ctl_log::init_from_file(&args.log_config).fatal(FatalKind::Config)?;
+
+    let report = match &args.session {
+        Some(path) => {
+            info!("Scanning session {}", path.display());
+            let mut tracker = QualityTracker::new();
+            let records = scan_session(BufReader::new(File::open(path)?), &mut tracker).fatal(FatalKind::Io)?;
+            info!("Scanned {} records", records);
+            tracker.report()
+        }
+        None => sample_rings(&args)?,
+    };
+
+    let output = match args.format {
+        ReportFormat::Json => serde_json::to_string_pretty(&report).fatal(FatalKind::Internal)? + "\n",
+        ReportFormat::Csv => report.to_csv(),
+    };
+    match &args.output {
+        Some(path) => fs::write(path, output)?,
+        None => print!("{}", output),
+    }
+    Ok(())
+}
+
+/// Samples the rings matching the pattern over the window.
+fn sample_rings(args: &Args) -> Result<QualityReport, FatalError> {
+    let md_config = HwResourcesConfig::from_file(&args.md_config).fatal(FatalKind::Config)?;
+    md_config.eal.apply_region_prefix();
+    let lcore = LcoresConfig::from_file(&args.lcores_config)?.lcore(LCORES_COMPONENT, DEFAULT_LCORE) as usize;
+
+    // Verify the host before the EAL initialization, which reports its failures cryptically
+    Preflight::new()
+        .require_hugepages_mounted()
+        .require_capabilities(&[Capability::IpcLock])
+        .require_primary()
+        .with_file_prefix(md_config.eal.file_prefix())
+        .run()?;
+
+    let dpdk_env = DpdkEnvBuilder::default()
+        .process_type(DpdkProcessType::Secondary)
+        .lcore_ids(vec![lcore])
+        .main_lcore_id(lcore)
+        .eal_args(md_config.eal.secondary_args())
+        .build()
+        .fatal(FatalKind::DpdkInit)?;
+
+    let metrics = ShmRegion::<MetricsRegion>::open(METRICS_REGION_NAME)?;
+    let directory = RingDirectory::new(&dpdk_env, &metrics);
+    let find_metrics = |ring_name: &str| {
+        metrics
+            .find_ring(ring_name)
+            .map(|index| &metrics.rings[index])
+            .ok_or_else(|| format!("Ring '{}' not found in metrics region", ring_name))
+            .fatal(FatalKind::SharedState)
+    };
+
+    // Attach to the rings, tracking the positions in their metrics like any consumer
+    let mut sampled = Vec::new();
+    for discovered in directory.discover_of::<RawMessage>(&RingPattern::new(&args.rings)) {
+        let ring = directory.lookup::<RawMessage>(&discovered.name).fatal(FatalKind::SharedState)?;
+        let slot_size = metrics.slot_size(&discovered.name).fatal(FatalKind::SharedState)?;
+        let ring_metrics = find_metrics(&discovered.name)?;
+        let index = ring_metrics
+            .attach_named_consumer(CONSUMER_NAME, std::process::id() as u64)
+            .fatal(FatalKind::SharedState)?;
+        let consumer = ring.attach_consumer().fatal(FatalKind::SharedState)?;
+        sampled.push((RingCheck::new(&discovered.name, slot_size), ring_metrics, index, consumer));
+    }
+    if sampled.is_empty() {
+        return Err(FatalError::new(FatalKind::SharedState, format!("No rings match '{}'", args.rings)));
+    }
+
+    info!("Sampling {} rings for {}s", sampled.len(), args.window_s);
+
+    let mut tracker = QualityTracker::new();
+    let window = Duration::from_secs(args.window_s);
+    let started = Instant::now();
+    while started.elapsed() < window {
+        let mut did_work = false;
+        for (check, ring_metrics, index, consumer) in sampled.iter_mut() {
+            let cursor = &ring_metrics.consumers[*index];
+            match consumer.consume_start() {
+                ConsumeStartState::Success(mut guard) => {
+                    // Mark the message as consumed before reading it, retrying a failed commit
+                    if guard.try_commit().is_ok() {
+                        cursor.advance();
+                        check.on_message(guard.as_ref().get(), &mut tracker);
+                        did_work = true;
+                    }
+                }
+                ConsumeStartState::SpedPast(_guard) => {
+                    warn!("{} consumer overtaken by producer, some messages missed", check.name());
+                    cursor.sped_past(ring_metrics.head.load(Ordering::Acquire));
+                    check.on_sped_past();
+                    did_work = true;
+                }
+                ConsumeStartState::InFlight(_guard) | ConsumeStartState::Empty => {}
+            }
+        }
+        if !did_work {
+            std::thread::sleep(IDLE_PAUSE);
+        }
+    }
+
+    for (check, ring_metrics, index, _) in &sampled {
+        ring_metrics.detach_consumer(*index);
+        tracker.record_ring(check.quality());
+    }
+    Ok(tracker.report())
+}
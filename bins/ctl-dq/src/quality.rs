@@ -0,0 +1,348 @@
+//! Data-quality measures of the market data streams.
+//!
+//! A `QualityTracker` observes the messages of the streams, one per source (a
+//! session record kind, or a ring) and symbol, and measures per stream:
+//! - the gaps of its event IDs, for the events numbered without a hole (the
+//!   trade and aggregate trade IDs), and the IDs going backwards;
+//! - the receive times and event times going backwards;
+//! - the percentiles of its latency, from the event time at the exchange to the
+//!   receive time, skewed by the offset of the local clock;
+//! - its coverage, the minutes of the window it carried a message in, and its
+//!   longest silence.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use hashbrown::HashMap;
+use serde::Serialize;
+
+/// Milliseconds per minute, the unit of the coverage.
+const MINUTE_MS: u64 = 60_000;
+
+/// A message of a stream.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Observation<'a> {
+    /// The source of the stream, e.g. `trade` or a ring name.
+    pub source: &'a str,
+    /// The symbol of the stream.
+    pub symbol: &'a str,
+    /// The time the message was received, in milliseconds since the epoch.
+    pub recv_time_ms: u64,
+    /// The time of the event at the exchange, if carried.
+    pub event_time_ms: Option<u64>,
+    /// The ID of the event, if numbered without a hole.
+    pub event_id: Option<u64>,
+}
+
+/// The latency percentiles of a stream, in milliseconds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct LatencyPercentiles {
+    /// The median.
+    pub p50: i64,
+    /// The 90th percentile.
+    pub p90: i64,
+    /// The 99th percentile.
+    pub p99: i64,
+    /// The 99.9th percentile.
+    pub p999: i64,
+    /// The highest latency.
+    pub max: i64,
+}
+
+/// The data-quality measures of a stream.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct StreamQuality {
+    /// The source of the stream.
+    pub source: String,
+    /// The symbol of the stream.
+    pub symbol: String,
+    /// The number of messages.
+    pub messages: u64,
+    /// The receive time of the first message.
+    pub first_ms: u64,
+    /// The receive time of the last message.
+    pub last_ms: u64,
+    /// The number of jumps of the event IDs.
+    pub id_gaps: u64,
+    /// The number of events missing in the jumps.
+    pub missing_ids: u64,
+    /// The number of event IDs at or below the previous one.
+    pub backward_ids: u64,
+    /// The number of receive times before the previous one.
+    pub out_of_order_recv: u64,
+    /// The number of event times before the previous one.
+    pub out_of_order_event: u64,
+    /// The latency percentiles, if the messages carry their event time.
+    pub latency_ms: Option<LatencyPercentiles>,
+    /// The number of minutes of the window with a message.
+    pub covered_minutes: u64,
+    /// The fraction of the minutes of the window with a message.
+    pub coverage: f64,
+    /// The longest time between two messages, in milliseconds.
+    pub longest_silence_ms: u64,
+}
+
+/// The data-quality measures of a ring, over all its symbols.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RingQuality {
+    /// The ring name.
+    pub name: String,
+    /// The number of messages consumed.
+    pub messages: u64,
+    /// The number of jumps of the sequence numbers.
+    pub seq_gaps: u64,
+    /// The number of messages missing in the jumps.
+    pub missing_seqs: u64,
+    /// The number of times the consumer was sped past by the producer.
+    pub sped_past: u64,
+}
+
+/// The data-quality report of a window.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct QualityReport {
+    /// The receive time of the first message of the window.
+    pub start_ms: u64,
+    /// The receive time of the last message of the window.
+    pub end_ms: u64,
+    /// The number of records or messages without market data, e.g. subscription responses.
+    pub skipped: u64,
+    /// The number of malformed records.
+    pub malformed: u64,
+    /// The streams, by source and symbol.
+    pub streams: Vec<StreamQuality>,
+    /// The rings consumed, empty for a session.
+    pub rings: Vec<RingQuality>,
+}
+
+impl QualityReport {
+    /// Returns the report of the streams as CSV, one row per stream.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from(
+            "source,symbol,messages,first_ms,last_ms,id_gaps,missing_ids,backward_ids,out_of_order_recv,\
+             out_of_order_event,latency_p50_ms,latency_p90_ms,latency_p99_ms,latency_p999_ms,latency_max_ms,\
+             covered_minutes,coverage,longest_silence_ms\n",
+        );
+        for stream in &self.streams {
+            let latency = match stream.latency_ms {
+                Some(l) => format!("{},{},{},{},{}", l.p50, l.p90, l.p99, l.p999, l.max),
+                None => ",,,,".to_string(),
+            };
+            csv.push_str(&format!(
+                "{},{},{},{},{},{},{},{},{},{},{},{},{:.4},{}\n",
+                stream.source,
+                stream.symbol,
+                stream.messages,
+                stream.first_ms,
+                stream.last_ms,
+                stream.id_gaps,
+                stream.missing_ids,
+                stream.backward_ids,
+                stream.out_of_order_recv,
+                stream.out_of_order_event,
+                latency,
+                stream.covered_minutes,
+                stream.coverage,
+                stream.longest_silence_ms
+            ));
+        }
+        csv
+    }
+}
+
+/// The running measures of a stream.
+#[derive(Debug, Default)]
+struct StreamState {
+    messages: u64,
+    first_ms: u64,
+    last_ms: u64,
+    last_id: Option<u64>,
+    last_event_ms: Option<u64>,
+    id_gaps: u64,
+    missing_ids: u64,
+    backward_ids: u64,
+    out_of_order_recv: u64,
+    out_of_order_event: u64,
+    /// The number of messages by latency, in milliseconds.
+    latencies: BTreeMap<i64, u64>,
+    /// The minutes since the epoch with a message.
+    minutes: BTreeSet<u64>,
+    longest_silence_ms: u64,
+}
+
+impl StreamState {
+    fn observe(&mut self, observation: &Observation<'_>) {
+        let recv_ms = observation.recv_time_ms;
+        if self.messages == 0 {
+            self.first_ms = recv_ms;
+        } else if recv_ms < self.last_ms {
+            self.out_of_order_recv += 1;
+        } else {
+            self.longest_silence_ms = self.longest_silence_ms.max(recv_ms - self.last_ms);
+        }
+        self.messages += 1;
+        self.last_ms = self.last_ms.max(recv_ms);
+        self.minutes.insert(recv_ms / MINUTE_MS);
+
+        if let Some(id) = observation.event_id {
+            match self.last_id {
+                Some(last) if id <= last => self.backward_ids += 1,
+                Some(last) if id > last + 1 => {
+                    self.id_gaps += 1;
+                    self.missing_ids += id - last - 1;
+                }
+                _ => {}
+            }
+            self.last_id = Some(self.last_id.map_or(id, |last| last.max(id)));
+        }
+        if let Some(event_ms) = observation.event_time_ms {
+            if self.last_event_ms.is_some_and(|last| event_ms < last) {
+                self.out_of_order_event += 1;
+            }
+            self.last_event_ms = Some(self.last_event_ms.map_or(event_ms, |last| last.max(event_ms)));
+            *self.latencies.entry(recv_ms as i64 - event_ms as i64).or_default() += 1;
+        }
+    }
+
+    /// Returns the latency below which a fraction `q` of the messages fall.
+    fn percentile(&self, q: f64) -> i64 {
+        let total: u64 = self.latencies.values().sum();
+        let rank = ((total as f64 * q).ceil() as u64).max(1);
+        let mut seen = 0;
+        for (&latency, &count) in &self.latencies {
+            seen += count;
+            if seen >= rank {
+                return latency;
+            }
+        }
+        0
+    }
+
+    fn report(&self, source: &str, symbol: &str, window_minutes: u64) -> StreamQuality {
+        let latency_ms = (!self.latencies.is_empty()).then(|| LatencyPercentiles {
+            p50: self.percentile(0.5),
+            p90: self.percentile(0.9),
+            p99: self.percentile(0.99),
+            p999: self.percentile(0.999),
+            max: self.latencies.keys().next_back().copied().unwrap_or_default(),
+        });
+        StreamQuality {
+            source: source.to_string(),
+            symbol: symbol.to_string(),
+            messages: self.messages,
+            first_ms: self.first_ms,
+            last_ms: self.last_ms,
+            id_gaps: self.id_gaps,
+            missing_ids: self.missing_ids,
+            backward_ids: self.backward_ids,
+            out_of_order_recv: self.out_of_order_recv,
+            out_of_order_event: self.out_of_order_event,
+            latency_ms,
+            covered_minutes: self.minutes.len() as u64,
+            coverage: self.minutes.len() as f64 / window_minutes.max(1) as f64,
+            longest_silence_ms: self.longest_silence_ms,
+        }
+    }
+}
+
+/// Measures the data quality of the streams observed.
+#[derive(Debug, Default)]
+pub struct QualityTracker {
+    /// The streams, by source and symbol.
+    streams: HashMap<(String, String), StreamState>,
+    /// The rings consumed, by name.
+    rings: Vec<RingQuality>,
+    /// The number of records or messages without market data.
+    skipped: u64,
+    /// The number of malformed records.
+    malformed: u64,
+}
+
+impl QualityTracker {
+    /// Creates a tracker having observed no message.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Observes a message of a stream.
+    pub fn observe(&mut self, observation: Observation<'_>) {
+        let key = (observation.source.to_string(), observation.symbol.to_string());
+        self.streams.entry(key).or_default().observe(&observation);
+    }
+
+    /// Counts a record or message without market data.
+    pub fn skip(&mut self) {
+        self.skipped += 1;
+    }
+
+    /// Counts a malformed record.
+    pub fn malformed(&mut self) {
+        self.malformed += 1;
+    }
+
+    /// Records the measures of a ring consumed.
+    pub fn record_ring(&mut self, ring: RingQuality) {
+        self.rings.push(ring);
+    }
+
+    /// Returns the report of the streams observed, sorted by source and symbol,
+    /// their coverage over the window from the first message to the last one of
+    /// all the streams.
+    pub fn report(&self) -> QualityReport {
+        let start_ms = self.streams.values().map(|stream| stream.first_ms).min().unwrap_or_default();
+        let end_ms = self.streams.values().map(|stream| stream.last_ms).max().unwrap_or_default();
+        let window_minutes = end_ms / MINUTE_MS - start_ms / MINUTE_MS + 1;
+        let mut streams: Vec<StreamQuality> = self
+            .streams
+            .iter()
+            .map(|((source, symbol), stream)| stream.report(source, symbol, window_minutes))
+            .collect();
+        streams.sort_by(|a, b| (&a.source, &a.symbol).cmp(&(&b.source, &b.symbol)));
+        let mut rings = self.rings.clone();
+        rings.sort_by(|a, b| a.name.cmp(&b.name));
+        QualityReport { start_ms, end_ms, skipped: self.skipped, malformed: self.malformed, streams, rings }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trade(recv_time_ms: u64, event_time_ms: u64, id: u64) -> Observation<'static> {
+        Observation {
+            source: "trade",
+            symbol: "BTCUSDT",
+            recv_time_ms,
+            event_time_ms: Some(event_time_ms),
+            event_id: Some(id),
+        }
+    }
+
+    #[test]
+    fn test_stream_quality() {
+        let mut tracker = QualityTracker::new();
+        tracker.observe(trade(1_000, 990, 1));
+        tracker.observe(trade(1_010, 1_000, 2));
+        // Three trades missed, then a late copy
+        tracker.observe(trade(1_020, 1_005, 6));
+        tracker.observe(trade(1_015, 1_004, 2));
+        // Silent for four minutes
+        tracker.observe(trade(241_020, 241_000, 7));
+        tracker.observe(Observation { symbol: "ETHUSDT", event_id: None, ..trade(1_000, 1_000, 0) });
+
+        let report = tracker.report();
+        assert_eq!((report.start_ms, report.end_ms), (1_000, 241_020));
+        assert_eq!(report.streams.len(), 2);
+        let btc = &report.streams[0];
+        assert_eq!(btc.symbol, "BTCUSDT");
+        assert_eq!(btc.messages, 5);
+        assert_eq!((btc.id_gaps, btc.missing_ids, btc.backward_ids), (1, 3, 1));
+        assert_eq!((btc.out_of_order_recv, btc.out_of_order_event), (1, 1));
+        assert_eq!(btc.latency_ms, Some(LatencyPercentiles { p50: 11, p90: 20, p99: 20, p999: 20, max: 20 }));
+        assert_eq!((btc.covered_minutes, btc.longest_silence_ms), (2, 240_000));
+        assert_eq!(btc.coverage, 0.4);
+        assert_eq!(report.streams[1].latency_ms.unwrap().max, 0);
+
+        let csv = report.to_csv();
+        assert_eq!(csv.lines().count(), 3);
+        assert!(csv.lines().nth(1).unwrap().starts_with("trade,BTCUSDT,5,1000,241020,1,3,1,1,1,11,20,20,20,20,2,0.4000,"));
+    }
+}
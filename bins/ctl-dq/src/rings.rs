@@ -0,0 +1,126 @@
+//! Data quality of the live rings.
+//!
+//! The messages of a raw ring are sequenced by their producer from 1 without a
+//! hole, so a jump of the sequence numbers consumed means messages missed,
+//! usually overwritten once the consumer was sped past. The payloads, once
+//! reassembled, are observed as the messages of the stream of the ring and
+//! their symbol ID, received at the time stamped in their header.
+
+use ctl_feed::{RawMessage, Reassembler, Reassembly};
+
+use crate::{payload_fields, Observation, QualityTracker, RingQuality};
+
+/// The checks of a ring consumed over a sampling window.
+#[derive(Debug)]
+pub struct RingCheck {
+    /// The ring name.
+    name: String,
+    /// The number of messages consumed.
+    messages: u64,
+    /// The sequence number of the last message consumed.
+    last_seq: Option<u64>,
+    /// The number of jumps of the sequence numbers.
+    seq_gaps: u64,
+    /// The number of messages missing in the jumps.
+    missing_seqs: u64,
+    /// The number of times the consumer was sped past.
+    sped_past: u64,
+    /// The reassembler of the payloads fragmented over several slots.
+    reassembler: Reassembler,
+}
+
+impl RingCheck {
+    /// Creates the checks of a raw ring of `slot_size` bytes per slot.
+    pub fn new(name: &str, slot_size: usize) -> Self {
+        Self {
+            name: name.to_string(),
+            messages: 0,
+            last_seq: None,
+            seq_gaps: 0,
+            missing_seqs: 0,
+            sped_past: 0,
+            reassembler: Reassembler::new(slot_size),
+        }
+    }
+
+    /// Returns the ring name.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Checks a message consumed, observing its payload once reassembled.
+    pub fn on_message(&mut self, message: &RawMessage, tracker: &mut QualityTracker) {
+        self.messages += 1;
+        let seq = message.header.seq;
+        if let Some(last) = self.last_seq.filter(|&last| seq > last + 1) {
+            self.seq_gaps += 1;
+            self.missing_seqs += seq - last - 1;
+        }
+        self.last_seq = Some(self.last_seq.map_or(seq, |last| last.max(seq)));
+
+        let payload = match self.reassembler.push(message) {
+            Reassembly::Complete(payload) => payload,
+            Reassembly::Partial | Reassembly::Dropped => return,
+        };
+        let symbol = message.header.symbol_id().map(|id| id.to_string()).unwrap_or_default();
+        match payload_fields(payload) {
+            Some((_, event_time_ms, event_id)) => tracker.observe(Observation {
+                source: &self.name,
+                symbol: &symbol,
+                recv_time_ms: message.header.ts_ms,
+                event_time_ms,
+                event_id,
+            }),
+            None => tracker.skip(),
+        }
+    }
+
+    /// Records the consumer sped past by the producer.
+    pub fn on_sped_past(&mut self) {
+        self.sped_past += 1;
+        self.reassembler.reset();
+    }
+
+    /// Returns the measures of the ring.
+    pub fn quality(&self) -> RingQuality {
+        RingQuality {
+            name: self.name.clone(),
+            messages: self.messages,
+            seq_gaps: self.seq_gaps,
+            missing_seqs: self.missing_seqs,
+            sped_past: self.sped_past,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use ctl_feed::{EventType, MessageHeader};
+
+    fn trade(seq: u64, id: u64) -> RawMessage {
+        let mut message = RawMessage::default();
+        message.header = MessageHeader::new(EventType::Trade, 3);
+        message.header.stamp(seq, 1_010);
+        let payload = format!(r#"{{"e":"trade","E":1000,"s":"BTCUSDT","t":{},"p":"1.0","q":"1.0"}}"#, id);
+        message.data[..payload.len()].copy_from_slice(payload.as_bytes());
+        message
+    }
+
+    #[test]
+    fn test_ring_check() {
+        let mut tracker = QualityTracker::new();
+        let mut check = RingCheck::new("TRADE_3_PS", 256);
+        check.on_message(&trade(1, 100), &mut tracker);
+        check.on_sped_past();
+        check.on_message(&trade(5, 104), &mut tracker);
+
+        let quality = check.quality();
+        assert_eq!((quality.messages, quality.seq_gaps, quality.missing_seqs, quality.sped_past), (2, 1, 3, 1));
+        let report = tracker.report();
+        assert_eq!((report.streams[0].source.as_str(), report.streams[0].symbol.as_str()), ("TRADE_3_PS", "3"));
+        assert_eq!(report.streams[0].missing_ids, 3);
+        assert_eq!(report.streams[0].latency_ms.unwrap().p50, 10);
+    }
+}
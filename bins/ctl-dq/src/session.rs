@@ -0,0 +1,104 @@
+//! Data quality of a recorded session.
+//!
+//! A session file holds one `{recv_time_ms}\t{kind}\t{payload}` record per
+//! received payload, in receive order (see the replayer of ctl-backtester). Each
+//! record of market data is observed as a message of the stream of its kind
+//! and symbol, the records without a symbol (e.g. subscription responses)
+//! skipped and the malformed ones counted.
+
+use std::io::BufRead;
+
+use ctl_feed::{payload_event_id, EventType};
+use serde::Deserialize;
+
+use crate::{DqError, Observation, QualityTracker};
+
+/// The fields of a stream payload read by the checks.
+#[derive(Deserialize)]
+struct PayloadFields<'a> {
+    /// The event name, e.g. `trade` or `aggTrade`, absent from the book tickers.
+    #[serde(rename = "e", borrow)]
+    event: Option<&'a str>,
+    /// The event time at the exchange, absent from the book tickers.
+    #[serde(rename = "E")]
+    event_time_ms: Option<u64>,
+    /// The symbol.
+    #[serde(rename = "s", borrow)]
+    symbol: Option<&'a str>,
+}
+
+/// Returns the symbol and event time of a JSON stream payload, and the ID of
+/// its event if numbered without a hole (a trade or an aggregate trade).
+pub fn payload_fields(payload: &[u8]) -> Option<(&str, Option<u64>, Option<u64>)> {
+    let fields: PayloadFields<'_> = serde_json::from_slice(payload).ok()?;
+    let event_type = match fields.event {
+        Some("trade") => EventType::Trade,
+        Some("aggTrade") => EventType::AggTrade,
+        _ => EventType::Unknown,
+    };
+    Some((fields.symbol?, fields.event_time_ms, payload_event_id(event_type, payload)))
+}
+
+/// Observes the records of a session, returning the number of records read.
+///
+/// # Errors
+/// Returns an error if the session cannot be read.
+pub fn scan_session<R: BufRead>(reader: R, tracker: &mut QualityTracker) -> Result<u64, DqError> {
+    let mut records = 0;
+    for record in reader.lines() {
+        let record = record?;
+        if record.trim().is_empty() {
+            continue;
+        }
+        records += 1;
+        let mut fields = record.splitn(3, '\t');
+        let (Some(recv_time_ms), Some(kind), Some(payload)) = (fields.next(), fields.next(), fields.next()) else {
+            tracker.malformed();
+            continue;
+        };
+        let Ok(recv_time_ms) = recv_time_ms.parse() else {
+            tracker.malformed();
+            continue;
+        };
+        match payload_fields(payload.as_bytes()) {
+            Some((symbol, event_time_ms, event_id)) => tracker.observe(Observation {
+                source: kind,
+                symbol,
+                recv_time_ms,
+                event_time_ms,
+                event_id,
+            }),
+            None => tracker.skip(),
+        }
+    }
+    Ok(records)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scan_session() {
+        let session = concat!(
+            "1000\ttop\t{\"u\":400900217,\"s\":\"BNBUSDT\",\"b\":\"25.35\",\"B\":\"31.21\",\"a\":\"25.36\",\"A\":\"40.66\"}\n",
+            "1001\ttop\t{\"result\":null,\"id\":1}\n",
+            "1002\ttrade\t{\"e\":\"trade\",\"E\":1001,\"s\":\"BNBUSDT\",\"t\":12345,\"p\":\"25.36\",\"q\":\"2\",\"T\":1001}\n",
+            "1003\ttrade\t{\"e\":\"trade\",\"E\":1002,\"s\":\"BNBUSDT\",\"t\":12348,\"p\":\"25.36\",\"q\":\"1\",\"T\":1002}\n",
+            "\n",
+            "soon\ttrade\t{}\n",
+        );
+        let mut tracker = QualityTracker::new();
+        assert_eq!(scan_session(session.as_bytes(), &mut tracker).unwrap(), 5);
+
+        let report = tracker.report();
+        assert_eq!((report.skipped, report.malformed), (1, 1));
+        let sources: Vec<&str> = report.streams.iter().map(|stream| stream.source.as_str()).collect();
+        assert_eq!(sources, vec!["top", "trade"]);
+        // The book tickers carry neither an event time nor contiguous IDs
+        assert_eq!(report.streams[0].latency_ms, None);
+        let trades = &report.streams[1];
+        assert_eq!((trades.messages, trades.id_gaps, trades.missing_ids), (2, 1, 2));
+        assert_eq!(trades.latency_ms.unwrap().max, 1);
+    }
+}
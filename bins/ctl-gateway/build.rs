@@ -0,0 +1,10 @@
+//! Generates the gRPC service of the gateway from its protobuf definition,
+//! with a vendored `protoc` so the build needs none installed.
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    println!("cargo:rerun-if-changed=proto/gateway.proto");
+    let mut config = prost_build::Config::new();
+    config.protoc_executable(protoc_bin_vendored::protoc_bin_path()?);
+    tonic_build::configure().compile_protos_with_config(config, &["proto/gateway.proto"], &["proto"])?;
+    Ok(())
+}
@@ -0,0 +1,116 @@
+//! Configuration module for the market data gateway.
+//!
+//! This module provides the YAML parser and validation for the gateway
+//! configuration defined in `configs/gateway/gateway.yaml`.
+
+use std::fs;
+use std::net::SocketAddr;
+use std::path::Path;
+use std::time::Duration;
+
+use serde::Deserialize;
+
+use crate::GatewayConfigError;
+
+/// Default number of updates buffered per subscription.
+const DEFAULT_BUFFER: usize = 4096;
+
+/// Default interval between the polls of the book snapshots, in milliseconds.
+const DEFAULT_BOOK_INTERVAL_MS: u64 = 100;
+
+fn default_buffer() -> usize {
+    DEFAULT_BUFFER
+}
+
+fn default_book_interval_ms() -> u64 {
+    DEFAULT_BOOK_INTERVAL_MS
+}
+
+/// The configuration of the gRPC market data gateway.
+#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+pub struct GatewayConfig {
+    /// The address the gRPC server listens on (e.g. `0.0.0.0:50051`).
+    pub listen: String,
+    /// The updates buffered per subscription, a subscriber lagging further
+    /// missing the updates published meanwhile.
+    #[serde(default = "default_buffer")]
+    pub buffer: usize,
+    /// The interval between the polls of the book snapshots, in milliseconds.
+    #[serde(default = "default_book_interval_ms")]
+    pub book_interval_ms: u64,
+}
+
+impl GatewayConfig {
+    /// Parses the gateway configuration from a YAML file.
+    ///
+    /// # Errors
+    /// Returns an error if the file cannot be read, parsed, or fails validation.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, GatewayConfigError> {
+        let content = fs::read_to_string(path)?;
+        Self::from_str(&content)
+    }
+
+    /// Parses the gateway configuration from a YAML string.
+    ///
+    /// # Errors
+    /// Returns an error if the YAML cannot be parsed or fails validation.
+    pub fn from_str(content: &str) -> Result<Self, GatewayConfigError> {
+        let config: Self = serde_yaml::from_str(content)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Validates the gateway configuration.
+    fn validate(&self) -> Result<(), GatewayConfigError> {
+        self.listen_addr()?;
+        if self.buffer == 0 {
+            return Err(GatewayConfigError::ValidationError("Buffer must be greater than 0".to_string()));
+        }
+        if self.book_interval_ms == 0 {
+            return Err(GatewayConfigError::ValidationError(
+                "Book interval must be greater than 0".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Returns the address the gRPC server listens on.
+    ///
+    /// # Errors
+    /// Returns an error if the address is not an IP address and port.
+    pub fn listen_addr(&self) -> Result<SocketAddr, GatewayConfigError> {
+        self.listen
+            .parse()
+            .map_err(|e| {
+                GatewayConfigError::ValidationError(format!("Invalid listen address '{}': {}", self.listen, e))
+            })
+    }
+
+    /// Returns the interval between the polls of the book snapshots.
+    pub fn book_interval(&self) -> Duration {
+        Duration::from_millis(self.book_interval_ms)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_config() {
+        let config = GatewayConfig::from_str("listen: 127.0.0.1:50051").unwrap();
+        assert_eq!(config.listen_addr().unwrap(), "127.0.0.1:50051".parse().unwrap());
+        assert_eq!(config.buffer, DEFAULT_BUFFER);
+        assert_eq!(config.book_interval(), Duration::from_millis(DEFAULT_BOOK_INTERVAL_MS));
+
+        let config = GatewayConfig::from_str("listen: 0.0.0.0:9000\nbuffer: 16\nbook_interval_ms: 250").unwrap();
+        assert_eq!((config.buffer, config.book_interval_ms), (16, 250));
+    }
+
+    #[test]
+    fn test_invalid_config() {
+        assert!(GatewayConfig::from_str("listen: localhost").is_err());
+        assert!(GatewayConfig::from_str("listen: 0.0.0.0:9000\nbuffer: 0").is_err());
+        assert!(GatewayConfig::from_str("listen: 0.0.0.0:9000\nbook_interval_ms: 0").is_err());
+    }
+}
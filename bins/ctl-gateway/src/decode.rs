@@ -0,0 +1,92 @@
+//! Decoding of the ring messages into the updates streamed.
+//!
+//! The gateway is off the hot path, so the payloads, once reassembled, are
+//! parsed in full rather than scanned for their fields.
+
+use ctl_feed::{slot_payload, TopSnapshot};
+use serde::Deserialize;
+
+use crate::proto::{Top, Trade};
+
+/// A trade stream payload.
+/// https://github.com/binance/binance-spot-api-docs/blob/master/web-socket-streams.md#trade-streams
+#[derive(Deserialize)]
+struct TradePayload<'a> {
+    #[serde(rename = "s")]
+    symbol: &'a str,
+    #[serde(rename = "t")]
+    trade_id: u64,
+    #[serde(rename = "T")]
+    trade_time_ms: u64,
+    #[serde(rename = "p")]
+    price: &'a str,
+    #[serde(rename = "q")]
+    qty: &'a str,
+    #[serde(rename = "m")]
+    buyer_maker: bool,
+}
+
+/// Decodes a bookTicker payload received at `recv_time_ms`.
+/// Returns `None` if the payload is not a book ticker.
+pub fn decode_top(payload: &[u8], recv_time_ms: u64) -> Option<Top> {
+    let (symbol, top) = TopSnapshot::from_book_ticker(slot_payload(payload))?;
+    Some(top_message(symbol, &top, recv_time_ms))
+}
+
+/// Returns the update of the best bid/ask `top` of a symbol, e.g. read from
+/// the last-value Top region at `recv_time_ms`.
+pub fn top_message(symbol: &str, top: &TopSnapshot, recv_time_ms: u64) -> Top {
+    Top {
+        symbol: symbol.to_string(),
+        update_id: top.update_id,
+        bid_price: top.bid_price,
+        bid_qty: top.bid_qty,
+        ask_price: top.ask_price,
+        ask_qty: top.ask_qty,
+        recv_time_ms,
+    }
+}
+
+/// Decodes a trade payload received at `recv_time_ms`.
+/// Returns `None` if the payload is not a trade.
+pub fn decode_trade(payload: &[u8], recv_time_ms: u64) -> Option<Trade> {
+    let trade: TradePayload<'_> = serde_json::from_slice(slot_payload(payload)).ok()?;
+    Some(Trade {
+        symbol: trade.symbol.to_string(),
+        trade_id: trade.trade_id,
+        trade_time_ms: trade.trade_time_ms,
+        price: trade.price.parse().ok()?,
+        qty: trade.qty.parse().ok()?,
+        buyer_maker: trade.buyer_maker,
+        recv_time_ms,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_top() {
+        let mut data = [0u8; 256];
+        let json = br#"{"u":400900217,"s":"BNBUSDT","b":"25.35190000","B":"31.21000000","a":"25.36520000","#;
+        let json = [&json[..], br#""A":"40.66000000"}"#].concat();
+        data[..json.len()].copy_from_slice(&json);
+
+        let top = decode_top(&data, 1_000).unwrap();
+        assert_eq!(top.symbol, "BNBUSDT");
+        assert_eq!((top.update_id, top.bid_price, top.ask_qty), (400900217, 25.3519, 40.66));
+        assert_eq!(top.recv_time_ms, 1_000);
+        assert!(decode_top(br#"{"e":"trade"}"#, 0).is_none());
+    }
+
+    #[test]
+    fn test_decode_trade() {
+        let json = r#"{"e":"trade","E":123456789,"s":"BNBBTC","t":12345,"p":"0.001","q":"100","T":123456785,"m":true}"#;
+        let trade = decode_trade(json.as_bytes(), 123456790).unwrap();
+        assert_eq!(trade.symbol, "BNBBTC");
+        assert_eq!((trade.trade_id, trade.trade_time_ms, trade.price, trade.qty), (12345, 123456785, 0.001, 100.0));
+        assert!(trade.buyer_maker);
+        assert!(decode_trade(br#"{"e":"trade","s":"BNBBTC","p":"x"}"#, 0).is_none());
+    }
+}
@@ -0,0 +1,141 @@
+//! Fan-out of the decoded updates to the subscriptions of their symbol.
+//!
+//! The ring consumer publishes from its own thread and must never wait on a
+//! subscriber, so each subscription has a bounded channel: a subscriber whose
+//! channel is full misses the update, counted as dropped, and a subscription
+//! whose stream was closed is removed on the next update of its symbols.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+
+use hashbrown::{HashMap, HashSet};
+use tokio::sync::mpsc::{self, error::TrySendError};
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::Status;
+
+/// The stream of a subscription, as served by the gRPC service.
+pub type UpdateStream<T> = ReceiverStream<Result<T, Status>>;
+
+/// The subscriptions to the updates of a kind, by symbol.
+#[derive(Debug)]
+pub struct Fanout<T> {
+    /// The symbols served.
+    symbols: HashSet<String>,
+    /// The senders of the subscriptions of each symbol.
+    subscriptions: Mutex<HashMap<String, Vec<mpsc::Sender<Result<T, Status>>>>>,
+    /// The updates buffered per subscription.
+    buffer: usize,
+    /// Number of updates dropped for full subscriptions.
+    dropped: AtomicU64,
+}
+
+impl<T: Clone> Fanout<T> {
+    /// Creates the fan-out of the updates of `symbols`, buffering `buffer` updates per subscription.
+    pub fn new<S: Into<String>>(symbols: impl IntoIterator<Item = S>, buffer: usize) -> Arc<Self> {
+        Arc::new(Self {
+            symbols: symbols.into_iter().map(Into::into).collect(),
+            subscriptions: Mutex::new(HashMap::new()),
+            buffer,
+            dropped: AtomicU64::new(0),
+        })
+    }
+
+    /// Returns true if the updates of the symbol are served.
+    pub fn serves(&self, symbol: &str) -> bool {
+        self.symbols.contains(symbol)
+    }
+
+    /// Subscribes to the updates of the symbols.
+    ///
+    /// # Errors
+    /// Returns `INVALID_ARGUMENT` without symbols, `NOT_FOUND` if a symbol is not served.
+    pub fn subscribe(&self, symbols: &[String]) -> Result<UpdateStream<T>, Status> {
+        if symbols.is_empty() {
+            return Err(Status::invalid_argument("no symbols requested"));
+        }
+        if let Some(symbol) = symbols.iter().find(|symbol| !self.serves(symbol)) {
+            return Err(Status::not_found(format!("symbol {} is not served", symbol)));
+        }
+
+        let (sender, receiver) = mpsc::channel(self.buffer);
+        let mut subscriptions = self.subscriptions.lock().unwrap_or_else(PoisonError::into_inner);
+        for symbol in symbols.iter().collect::<HashSet<_>>() {
+            subscriptions.entry(symbol.clone()).or_default().push(sender.clone());
+        }
+        Ok(ReceiverStream::new(receiver))
+    }
+
+    /// Publishes an update of a symbol to its subscriptions.
+    pub fn publish(&self, symbol: &str, update: &T) {
+        let mut subscriptions = self.subscriptions.lock().unwrap_or_else(PoisonError::into_inner);
+        let Some(senders) = subscriptions.get_mut(symbol) else {
+            return;
+        };
+        senders.retain(|sender| match sender.try_send(Ok(update.clone())) {
+            Ok(()) => true,
+            Err(TrySendError::Full(_)) => {
+                self.dropped.fetch_add(1, Ordering::Relaxed);
+                true
+            }
+            Err(TrySendError::Closed(_)) => false,
+        });
+        if senders.is_empty() {
+            subscriptions.remove(symbol);
+        }
+    }
+
+    /// Returns the number of open subscriptions of a symbol.
+    pub fn subscribers(&self, symbol: &str) -> usize {
+        let subscriptions = self.subscriptions.lock().unwrap_or_else(PoisonError::into_inner);
+        subscriptions.get(symbol).map_or(0, Vec::len)
+    }
+
+    /// Returns the number of updates dropped for full subscriptions.
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use tokio_stream::StreamExt;
+    use tonic::Code;
+
+    #[tokio::test]
+    async fn test_fanout() {
+        let fanout = Fanout::new(["BTCUSDT", "ETHUSDT"], 2);
+        let mut both = fanout.subscribe(&["BTCUSDT".to_string(), "ETHUSDT".to_string()]).unwrap();
+        let mut btc = fanout.subscribe(&["BTCUSDT".to_string()]).unwrap();
+        assert_eq!(fanout.subscribers("BTCUSDT"), 2);
+
+        fanout.publish("BTCUSDT", &1);
+        fanout.publish("ETHUSDT", &2);
+        fanout.publish("BNBUSDT", &3);
+        assert_eq!(both.next().await.unwrap().unwrap(), 1);
+        assert_eq!(both.next().await.unwrap().unwrap(), 2);
+        assert_eq!(btc.next().await.unwrap().unwrap(), 1);
+
+        // A full subscription misses the update, without holding back the others
+        fanout.publish("BTCUSDT", &4);
+        fanout.publish("BTCUSDT", &5);
+        assert_eq!(both.next().await.unwrap().unwrap(), 4);
+        fanout.publish("BTCUSDT", &6);
+        assert_eq!(fanout.dropped(), 1);
+        assert_eq!(btc.next().await.unwrap().unwrap(), 4);
+        assert_eq!(btc.next().await.unwrap().unwrap(), 5);
+
+        // A closed subscription is removed on the next update
+        drop(btc);
+        fanout.publish("BTCUSDT", &7);
+        assert_eq!(fanout.subscribers("BTCUSDT"), 1);
+    }
+
+    #[test]
+    fn test_invalid_subscriptions() {
+        let fanout = Fanout::<u64>::new(["BTCUSDT"], 2);
+        assert_eq!(fanout.subscribe(&[]).unwrap_err().code(), Code::InvalidArgument);
+        assert_eq!(fanout.subscribe(&["ETHUSDT".to_string()]).unwrap_err().code(), Code::NotFound);
+    }
+}
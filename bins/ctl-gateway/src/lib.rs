@@ -0,0 +1,25 @@
+//! gRPC market data gateway for Binance Spot.
+//!
+//! Consumes the Top and trade rings like any other consumer and streams their
+//! updates, per symbol, to off-host consumers (dashboards, research services)
+//! that can't attach to the rings as DPDK secondaries. The book subscriptions
+//! stream the snapshots of the book snapshot regions whenever they change.
+//!
+//! The service is defined in `proto/gateway.proto`.
+
+mod config;
+mod decode;
+mod errors;
+mod fanout;
+mod service;
+
+/// The messages and the service generated from `proto/gateway.proto`.
+pub mod proto {
+    tonic::include_proto!("ctl.gateway.v1");
+}
+
+pub use config::GatewayConfig;
+pub use decode::{decode_top, decode_trade, top_message};
+pub use errors::GatewayConfigError;
+pub use fanout::{Fanout, UpdateStream};
+pub use service::{book_message, BookSource, GatewayService};
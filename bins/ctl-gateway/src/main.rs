@@ -0,0 +1,286 @@
+//! gRPC Market Data Gateway for the Binance Spot Controller.
+//!
+//! Connects as a DPDK secondary process, attaches a consumer to the Top and
+//! trade rings of the market data configuration, and serves their updates to
+//! off-host consumers over gRPC (`proto/gateway.proto`), per symbol. The book
+//! subscriptions stream the snapshots of the book snapshot regions found at
+//! startup.
+//!
+//! The rings are consumed on the main thread, pinned to the lcore of the
+//! gateway, under the polling policy of `configs/polling.yaml`, and the gRPC
+//! server runs on its own runtime. A subscriber lagging its buffer misses the
+//! updates published meanwhile; the gateway never holds back the rings for it.
+//! Once overtaken by a producer, the gateway recovers per
+//! `configs/overtaken.yaml`, streaming the last-value Tops of the symbols of a
+//! Top ring again under `skip-and-request-snapshot`.
+//!
+//! The exit code tells the class of a failure (see `FatalKind`).
+
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use clap::Parser;
+use ctl_book::{book_region_name, BookSnapshot, BookSnapshotRegion};
+use ctl_core::{
+    Capability, Fatal, FatalError, FatalKind, LcoresConfig, OvertakenConfig, OvertakenPolicy, Poller, PollingConfig,
+    PollingPolicy, Preflight, RingKind, RingName,
+};
+use ctl_feed::{
+    dpdk_consume, LastTopRegion, MetricsRegion, RawMessage, RingRead, RingReader, LAST_TOP_REGION_NAME,
+    METRICS_REGION_NAME,
+};
+use ctl_gateway::proto::market_data_server::MarketDataServer;
+use ctl_gateway::proto::{Top, Trade};
+use ctl_gateway::{decode_top, decode_trade, top_message, BookSource, Fanout, GatewayConfig, GatewayService};
+use ctl_md_handler::{HwResourcesConfig, SymbolInfoConfig};
+use ctl_shm::ShmRegion;
+use ctl_time::now_ms;
+use dpdk::{DpdkEnvBuilder, DpdkProcessType};
+use hashbrown::HashMap;
+use tracing::{error, info, warn};
+
+// Logging configuration, its levels reloaded on change
+const LOG_CONFIG_PATH: &str = "configs/logging.yaml";
+
+// The market data configuration, holding the feeds and the EAL options of the secondary processes
+const MD_CONFIG_PATH: &str = "configs/market-data/hw-resources.yaml";
+
+// The symbol IDs of the rings and book regions
+const SYMBOL_INFO_PATH: &str = "configs/market-data/symbolinfo.yaml";
+
+// The gateway configuration
+const GATEWAY_CONFIG_PATH: &str = "configs/gateway/gateway.yaml";
+
+// The name of the consumer cursors of the gateway in the rings
+const CONSUMER_NAME: &str = "gateway";
+
+// The lcore of the gateway, unless configured, shared with the mostly idle tools
+const LCORES_CONFIG_PATH: &str = "configs/lcores.yaml";
+const LCORES_COMPONENT: &str = "gateway";
+const DEFAULT_LCORE: u32 = 15;
+
+// Polling policy between the passes over the empty rings, unless configured for this component
+const POLLING_CONFIG_PATH: &str = "configs/polling.yaml";
+const POLLING_COMPONENT: &str = "gateway";
+const DEFAULT_POLLING: PollingPolicy = PollingPolicy::Sleep { sleep_us: 100 };
+
+// Recovery once overtaken by a producer, unless configured for this component
+const OVERTAKEN_CONFIG_PATH: &str = "configs/overtaken.yaml";
+const OVERTAKEN_COMPONENT: &str = "gateway";
+const DEFAULT_OVERTAKEN: OvertakenPolicy = OvertakenPolicy::SkipToHead;
+
+// Interval between the logs of the updates dropped for lagging subscribers
+const DROPPED_LOG_INTERVAL: Duration = Duration::from_secs(60);
+
+/// gRPC market data gateway.
+#[derive(Debug, Parser)]
+#[command(version)]
+struct Args {
+    /// Logging configuration.
+    #[arg(long, env = "CTL_LOG_CONFIG", default_value = LOG_CONFIG_PATH)]
+    log_config: PathBuf,
+    /// Market data configuration.
+    #[arg(long, env = "CTL_MD_CONFIG", default_value = MD_CONFIG_PATH)]
+    md_config: PathBuf,
+    /// Symbol info configuration.
+    #[arg(long, env = "CTL_SYMBOL_INFO", default_value = SYMBOL_INFO_PATH)]
+    symbol_info: PathBuf,
+    /// Gateway configuration.
+    #[arg(long, env = "CTL_GATEWAY_CONFIG", default_value = GATEWAY_CONFIG_PATH)]
+    gateway_config: PathBuf,
+    /// Lcores configuration.
+    #[arg(long, env = "CTL_LCORES_CONFIG", default_value = LCORES_CONFIG_PATH)]
+    lcores_config: PathBuf,
+    /// Polling configuration.
+    #[arg(long, env = "CTL_POLLING_CONFIG", default_value = POLLING_CONFIG_PATH)]
+    polling_config: PathBuf,
+    /// Overtaken consumer recovery configuration.
+    #[arg(long, env = "CTL_OVERTAKEN_CONFIG", default_value = OVERTAKEN_CONFIG_PATH)]
+    overtaken_config: PathBuf,
+}
+
+/// The book snapshot regions found at startup, by symbol.
+struct ShmBooks(HashMap<String, ShmRegion<BookSnapshotRegion>>);
+
+impl BookSource for ShmBooks {
+    fn serves(&self, symbol: &str) -> bool {
+        self.0.contains_key(symbol)
+    }
+
+    fn read(&self, symbol: &str) -> Option<BookSnapshot> {
+        self.0.get(symbol)?.read()
+    }
+}
+
+fn main() -> ExitCode {
+    ctl_core::exit_code(run())
+}
+
+fn run() -> Result<(), FatalError> {
+    let args = Args::parse();
+    let _log = ctl_log::init_from_file(&args.log_config).fatal(FatalKind::Config)?;
+
+    info!("=== Binance Spot Market Data Gateway ===");
+
+    let md_config = HwResourcesConfig::from_file(&args.md_config).fatal(FatalKind::Config)?;
+    let symbol_info = SymbolInfoConfig::from_file(&args.symbol_info).fatal(FatalKind::Config)?;
+    let config = GatewayConfig::from_file(&args.gateway_config).fatal(FatalKind::Config)?;
+    let addr = config.listen_addr().fatal(FatalKind::Config)?;
+    md_config.eal.apply_region_prefix();
+    let lcore = LcoresConfig::from_file(&args.lcores_config)?.lcore(LCORES_COMPONENT, DEFAULT_LCORE) as usize;
+    let polling = PollingConfig::from_file(&args.polling_config)?.policy(POLLING_COMPONENT, DEFAULT_POLLING);
+    info!("Polling: {:?}", polling);
+    let overtaken = OvertakenConfig::from_file(&args.overtaken_config)?.policy(OVERTAKEN_COMPONENT, DEFAULT_OVERTAKEN);
+    info!("Overtaken: {:?}", overtaken);
+
+    // Verify the host before the EAL initialization, which reports its failures cryptically
+    Preflight::new()
+        .require_hugepages_mounted()
+        .require_capabilities(&[Capability::IpcLock])
+        .require_primary()
+        .with_file_prefix(md_config.eal.file_prefix())
+        .run()?;
+
+    let dpdk_env = DpdkEnvBuilder::default()
+        .process_type(DpdkProcessType::Secondary)
+        .lcore_ids(vec![lcore])
+        .main_lcore_id(lcore)
+        .eal_args(md_config.eal.secondary_args())
+        .build()
+        .fatal(FatalKind::DpdkInit)?;
+
+    let metrics = ShmRegion::<MetricsRegion>::open(METRICS_REGION_NAME)?;
+    // The Tops streamed again once overtaken are read from the last-value Top region
+    let last_top = match overtaken {
+        OvertakenPolicy::SkipAndRequestSnapshot => Some(ShmRegion::<LastTopRegion>::open(LAST_TOP_REGION_NAME)?),
+        _ => None,
+    };
+
+    // Attach to the Top and trade rings, tracking the positions in their metrics like any consumer
+    let mut rings = Vec::new();
+    let mut symbols: HashMap<RingKind, Vec<String>> = HashMap::new();
+    for (kind, feed_kind) in [(RingKind::Top, "top"), (RingKind::Trade, "trade")] {
+        let Some(feed) = md_config.find_feed(feed_kind) else {
+            warn!("No {} feed configured, its subscriptions not served", kind);
+            continue;
+        };
+        for feed_set in feed.feed_sets() {
+            let symbol_id = |symbol: &str| {
+                symbol_info
+                    .symbol_id(symbol)
+                    .ok_or_else(|| format!("Symbol '{}' not found in symbolinfo.yaml", symbol))
+                    .fatal(FatalKind::Config)
+            };
+            for symbol in feed_set.ring_symbols() {
+                let name = RingName::pubsub(kind, symbol_id(symbol)?).to_string();
+                let reader = RingReader::attach(&metrics, &name, CONSUMER_NAME, feed_set.slot_size)
+                    .fatal(FatalKind::SharedState)?
+                    .with_overtaken(overtaken);
+                let ring = dpdk_env.pubsub_lookup::<RawMessage>(&name).fatal(FatalKind::SharedState)?;
+                let consumer = ring.attach_consumer().fatal(FatalKind::SharedState)?;
+                // The symbols whose Tops are streamed again once overtaken
+                let published = if feed_set.aggregate { &feed_set.symbols[..] } else { std::slice::from_ref(symbol) };
+                let snapshot_symbols = published
+                    .iter()
+                    .map(|symbol| Ok((symbol.clone(), symbol_id(symbol)?)))
+                    .collect::<Result<Vec<_>, FatalError>>()?;
+                info!("[{}] {} ({} symbols)", symbol, name, feed_set.symbols.len());
+                rings.push((kind, reader, consumer, snapshot_symbols));
+            }
+            symbols.entry(kind).or_default().extend(feed_set.symbols.iter().cloned());
+        }
+    }
+    if rings.is_empty() {
+        return Err(FatalError::new(FatalKind::Config, "No top or trade rings to serve"));
+    }
+
+    // The books of the symbols whose regions exist, written by a book builder
+    let mut books = HashMap::new();
+    for symbol in symbol_info.symbols() {
+        if let Ok(region) = ShmRegion::<BookSnapshotRegion>::open(&book_region_name(symbol.id)) {
+            books.insert(symbol.name.clone(), region);
+        }
+    }
+    info!("Serving the books of {} symbols", books.len());
+
+    let tops = Fanout::<Top>::new(symbols.remove(&RingKind::Top).unwrap_or_default(), config.buffer);
+    let trades = Fanout::<Trade>::new(symbols.remove(&RingKind::Trade).unwrap_or_default(), config.buffer);
+    let service = GatewayService::new(
+        Arc::clone(&tops),
+        Arc::clone(&trades),
+        Arc::new(ShmBooks(books)),
+        config.book_interval(),
+        config.buffer,
+    );
+
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .thread_name("gateway-grpc")
+        .enable_all()
+        .build()
+        .fatal(FatalKind::Internal)?;
+    let server = runtime.spawn(
+        tonic::transport::Server::builder().add_service(MarketDataServer::new(service)).serve(addr),
+    );
+    info!("Serving gRPC on {}", addr);
+
+    let mut last_log = Instant::now();
+    let mut poller = Poller::new(polling);
+    loop {
+        let mut did_work = false;
+        for (kind, reader, consumer, snapshot_symbols) in rings.iter_mut() {
+            let read = reader.read(dpdk_consume!(consumer)).fatal(FatalKind::Overtaken)?;
+            did_work |= read.did_work();
+            match read {
+                RingRead::Payload(message, payload) => match kind {
+                    RingKind::Top => {
+                        if let Some(top) = decode_top(payload, message.header.ts_ms) {
+                            tops.publish(&top.symbol, &top);
+                        }
+                    }
+                    _ => {
+                        if let Some(trade) = decode_trade(payload, message.header.ts_ms) {
+                            trades.publish(&trade.symbol, &trade);
+                        }
+                    }
+                },
+                RingRead::Overtaken { snapshot } => {
+                    warn!("{} consumer overtaken by producer, some messages missed", reader.name());
+                    // The trades missed are gone, the Tops are conflated in the last-value region
+                    let Some(last_top) = last_top.as_ref().filter(|_| snapshot && *kind == RingKind::Top) else {
+                        continue;
+                    };
+                    for (symbol, symbol_id) in snapshot_symbols.iter() {
+                        if let Some(top) = last_top.read(*symbol_id) {
+                            tops.publish(symbol, &top_message(symbol, &top, now_ms()));
+                        }
+                    }
+                }
+                RingRead::Consumed | RingRead::Idle => {}
+            }
+        }
+
+        if !did_work {
+            if server.is_finished() {
+                let detail = match runtime.block_on(server) {
+                    Ok(Ok(())) => "gRPC server stopped".to_string(),
+                    Ok(Err(e)) => format!("gRPC server failed: {}", e),
+                    Err(e) => format!("gRPC server panicked: {}", e),
+                };
+                error!("{}", detail);
+                for (_, reader, _, _) in &rings {
+                    reader.detach();
+                }
+                return Err(FatalError::new(FatalKind::Network, detail));
+            }
+            if last_log.elapsed() >= DROPPED_LOG_INTERVAL {
+                last_log = Instant::now();
+                info!("Updates dropped for lagging subscribers: top {}, trade {}", tops.dropped(), trades.dropped());
+            }
+        }
+
+        // Wait before the next pass according to the configured policy
+        poller.wait(did_work);
+    }
+}
@@ -0,0 +1,193 @@
+//! The gRPC market data service.
+//!
+//! The Top and trade subscriptions stream the updates fanned out by the ring
+//! consumer. The book subscriptions poll the book snapshot regions of their
+//! symbols, streaming a snapshot whenever its version changed since the last
+//! poll, so a slow subscriber only ever skips to the latest book.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use ctl_book::BookSnapshot;
+use hashbrown::HashMap;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::{Request, Response, Status};
+
+use crate::proto::market_data_server::MarketData;
+use crate::proto::{Book, BookRequest, Level, SubscribeRequest, Top, Trade};
+use crate::{Fanout, UpdateStream};
+
+/// The book snapshots of the symbols.
+pub trait BookSource: Send + Sync + 'static {
+    /// Returns true if the book of the symbol is served.
+    fn serves(&self, symbol: &str) -> bool;
+
+    /// Reads the latest snapshot of the book of a symbol, `None` until first written.
+    fn read(&self, symbol: &str) -> Option<BookSnapshot>;
+}
+
+/// Returns the book message of a snapshot, truncated to `depth` levels per side unless zero.
+pub fn book_message(symbol: &str, snapshot: &BookSnapshot, depth: usize) -> Book {
+    let levels = |levels: &[ctl_book::Level]| {
+        let depth = if depth == 0 { levels.len() } else { depth.min(levels.len()) };
+        levels[..depth].iter().map(|level| Level { price: level.price, qty: level.qty }).collect()
+    };
+    Book {
+        symbol: symbol.to_string(),
+        update_id: snapshot.update_id,
+        stale: snapshot.is_stale(),
+        bids: levels(snapshot.bids()),
+        asks: levels(snapshot.asks()),
+    }
+}
+
+/// The gRPC market data service of the gateway.
+pub struct GatewayService {
+    /// The Top updates.
+    tops: Arc<Fanout<Top>>,
+    /// The trades.
+    trades: Arc<Fanout<Trade>>,
+    /// The book snapshots.
+    books: Arc<dyn BookSource>,
+    /// The interval between the polls of the book snapshots.
+    book_interval: Duration,
+    /// The snapshots buffered per book subscription.
+    buffer: usize,
+}
+
+impl GatewayService {
+    /// Creates the service of the updates fanned out and of the book snapshots.
+    pub fn new(
+        tops: Arc<Fanout<Top>>,
+        trades: Arc<Fanout<Trade>>,
+        books: Arc<dyn BookSource>,
+        book_interval: Duration,
+        buffer: usize,
+    ) -> Self {
+        Self { tops, trades, books, book_interval, buffer }
+    }
+}
+
+#[tonic::async_trait]
+impl MarketData for GatewayService {
+    type SubscribeTopStream = UpdateStream<Top>;
+    type SubscribeTradesStream = UpdateStream<Trade>;
+    type SubscribeBookStream = UpdateStream<Book>;
+
+    async fn subscribe_top(
+        &self,
+        request: Request<SubscribeRequest>,
+    ) -> Result<Response<Self::SubscribeTopStream>, Status> {
+        self.tops.subscribe(&request.into_inner().symbols).map(Response::new)
+    }
+
+    async fn subscribe_trades(
+        &self,
+        request: Request<SubscribeRequest>,
+    ) -> Result<Response<Self::SubscribeTradesStream>, Status> {
+        self.trades.subscribe(&request.into_inner().symbols).map(Response::new)
+    }
+
+    async fn subscribe_book(
+        &self,
+        request: Request<BookRequest>,
+    ) -> Result<Response<Self::SubscribeBookStream>, Status> {
+        let BookRequest { symbols, depth } = request.into_inner();
+        if symbols.is_empty() {
+            return Err(Status::invalid_argument("no symbols requested"));
+        }
+        if let Some(symbol) = symbols.iter().find(|symbol| !self.books.serves(symbol)) {
+            return Err(Status::not_found(format!("book of symbol {} is not served", symbol)));
+        }
+
+        let (sender, receiver) = mpsc::channel(self.buffer);
+        let books = Arc::clone(&self.books);
+        let mut interval = tokio::time::interval(self.book_interval);
+        tokio::spawn(async move {
+            let mut versions: HashMap<String, u64> = HashMap::new();
+            loop {
+                interval.tick().await;
+                for symbol in &symbols {
+                    let Some(snapshot) = books.read(symbol) else {
+                        continue;
+                    };
+                    if versions.insert(symbol.clone(), snapshot.version) == Some(snapshot.version) {
+                        continue;
+                    }
+                    let book = book_message(symbol, &snapshot, depth as usize);
+                    // The subscriber went away
+                    if sender.send(Ok(book)).await.is_err() {
+                        return;
+                    }
+                }
+            }
+        });
+        Ok(Response::new(ReceiverStream::new(receiver)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use ctl_book::BookSnapshotRegion;
+    use tokio_stream::StreamExt;
+
+    struct Books(HashMap<String, BookSnapshotRegion>);
+
+    impl BookSource for Books {
+        fn serves(&self, symbol: &str) -> bool {
+            self.0.contains_key(symbol)
+        }
+
+        fn read(&self, symbol: &str) -> Option<BookSnapshot> {
+            self.0.get(symbol)?.read()
+        }
+    }
+
+    fn level(price: f64, qty: f64) -> ctl_book::Level {
+        ctl_book::Level { price, qty }
+    }
+
+    fn service() -> GatewayService {
+        let region = BookSnapshotRegion::default();
+        region.write(7, &[level(100.0, 1.0), level(99.0, 2.0)], &[level(101.0, 3.0)]);
+        let books = Books(HashMap::from([("BTCUSDT".to_string(), region)]));
+        let tops = Fanout::new(["BTCUSDT"], 8);
+        let trades = Fanout::new(["BTCUSDT"], 8);
+        GatewayService::new(tops, trades, Arc::new(books), Duration::from_millis(1), 8)
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_top() {
+        let service = service();
+        let request = Request::new(SubscribeRequest { symbols: vec!["BTCUSDT".to_string()] });
+        let mut stream = service.subscribe_top(request).await.unwrap().into_inner();
+        let top = Top { symbol: "BTCUSDT".to_string(), update_id: 1, ..Top::default() };
+        service.tops.publish("BTCUSDT", &top);
+        assert_eq!(stream.next().await.unwrap().unwrap(), top);
+
+        let request = Request::new(SubscribeRequest { symbols: vec!["ETHUSDT".to_string()] });
+        assert!(service.subscribe_trades(request).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_book() {
+        let service = service();
+        let request = Request::new(BookRequest { symbols: vec!["BTCUSDT".to_string()], depth: 1 });
+        let mut stream = service.subscribe_book(request).await.unwrap().into_inner();
+
+        let book = stream.next().await.unwrap().unwrap();
+        assert_eq!((book.symbol.as_str(), book.update_id, book.stale), ("BTCUSDT", 7, false));
+        assert_eq!(book.bids, vec![Level { price: 100.0, qty: 1.0 }]);
+        assert_eq!(book.asks, vec![Level { price: 101.0, qty: 3.0 }]);
+
+        // Unchanged snapshots aren't streamed again
+        let next = tokio::time::timeout(Duration::from_millis(20), stream.next()).await;
+        assert!(next.is_err());
+
+        let request = Request::new(BookRequest { symbols: vec!["ETHUSDT".to_string()], depth: 0 });
+        assert!(service.subscribe_book(request).await.is_err());
+    }
+}
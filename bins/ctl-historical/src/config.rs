@@ -0,0 +1,184 @@
+//! Configuration module for the historical data downloader.
+//!
+//! This module provides the YAML parser and validation for the download
+//! configuration defined in `configs/historical/historical.yaml`.
+
+use std::fs;
+use std::path::Path;
+
+use ctl_rest::BINANCE_REST_ENDPOINT;
+use serde::Deserialize;
+
+use crate::{Date, HistoricalConfigError, BINANCE_VISION_ENDPOINT};
+
+/// The kline intervals of the exchange.
+pub const KLINE_INTERVALS: [&str; 16] =
+    ["1s", "1m", "3m", "5m", "15m", "30m", "1h", "2h", "4h", "6h", "8h", "12h", "1d", "3d", "1w", "1M"];
+
+fn default_interval() -> String {
+    "1m".to_string()
+}
+
+fn default_vision_endpoint() -> String {
+    BINANCE_VISION_ENDPOINT.to_string()
+}
+
+fn default_rest_endpoint() -> String {
+    BINANCE_REST_ENDPOINT.to_string()
+}
+
+/// Where the historical data is downloaded from.
+#[derive(Debug, Clone, Copy, Default, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum HistoricalSource {
+    /// The archives of Binance Vision.
+    #[default]
+    Vision,
+    /// The klines of the REST API.
+    Rest,
+}
+
+/// The historical data downloaded.
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum HistoricalKind {
+    /// The trades.
+    Trades,
+    /// The aggregate trades.
+    AggTrades,
+    /// The klines, replayed as the synthetic trades of their prices.
+    Klines,
+}
+
+impl HistoricalKind {
+    /// Returns the name of the data in the paths of the archives.
+    pub fn archive_name(&self) -> &'static str {
+        match self {
+            HistoricalKind::Trades => "trades",
+            HistoricalKind::AggTrades => "aggTrades",
+            HistoricalKind::Klines => "klines",
+        }
+    }
+}
+
+/// The period covered by an archive.
+#[derive(Debug, Clone, Copy, Default, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum ArchivePeriod {
+    /// An archive per day, published the next day.
+    #[default]
+    Daily,
+    /// An archive per month, published once the month ended.
+    Monthly,
+}
+
+/// The historical data downloaded and the session it is converted into.
+#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+pub struct HistoricalConfig {
+    /// Where the data is downloaded from.
+    #[serde(default)]
+    pub source: HistoricalSource,
+    /// The data downloaded, only the klines from the REST API.
+    pub kind: HistoricalKind,
+    /// The kline interval.
+    #[serde(default = "default_interval")]
+    pub interval: String,
+    /// The period of the archives downloaded.
+    #[serde(default)]
+    pub period: ArchivePeriod,
+    /// The symbols downloaded.
+    pub symbols: Vec<String>,
+    /// The first day downloaded, UTC.
+    pub start_date: Date,
+    /// The last day downloaded, UTC.
+    pub end_date: Date,
+    /// The session file written.
+    pub output_path: String,
+    /// Base URL of the archives.
+    #[serde(default = "default_vision_endpoint")]
+    pub vision_endpoint: String,
+    /// Base URL of the REST API.
+    #[serde(default = "default_rest_endpoint")]
+    pub rest_endpoint: String,
+}
+
+impl HistoricalConfig {
+    /// Parses the historical data configuration from a YAML file.
+    ///
+    /// # Errors
+    /// Returns an error if the file cannot be read, parsed, or fails validation.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, HistoricalConfigError> {
+        let content = fs::read_to_string(path)?;
+        Self::from_str(&content)
+    }
+
+    /// Parses the historical data configuration from a YAML string.
+    ///
+    /// # Errors
+    /// Returns an error if the YAML cannot be parsed or fails validation.
+    pub fn from_str(content: &str) -> Result<Self, HistoricalConfigError> {
+        let config: Self = serde_yaml::from_str(content)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Validates the historical data configuration.
+    fn validate(&self) -> Result<(), HistoricalConfigError> {
+        let invalid = |reason: String| Err(HistoricalConfigError::ValidationError(reason));
+        if self.symbols.is_empty() {
+            return invalid("At least one symbol must be downloaded".to_string());
+        }
+        if self.symbols.iter().any(String::is_empty) {
+            return invalid("'symbols' contains an empty symbol".to_string());
+        }
+        if self.start_date > self.end_date {
+            return invalid(format!("'start_date' {} is after 'end_date' {}", self.start_date, self.end_date));
+        }
+        if self.source == HistoricalSource::Rest && self.kind != HistoricalKind::Klines {
+            return invalid(format!("Only the klines are downloaded from the REST API, not {:?}", self.kind));
+        }
+        if !KLINE_INTERVALS.contains(&self.interval.as_str()) {
+            return invalid(format!("Unknown kline interval '{}'", self.interval));
+        }
+        if self.output_path.is_empty() {
+            return invalid("'output_path' cannot be empty".to_string());
+        }
+        if self.vision_endpoint.is_empty() || self.rest_endpoint.is_empty() {
+            return invalid("The endpoints cannot be empty".to_string());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = "
+kind: trades
+symbols: [BTCUSDT, ETHUSDT]
+start_date: 2024-01-01
+end_date: 2024-01-03
+output_path: data/sessions/historical.tsv
+";
+
+    #[test]
+    fn test_parse_config() {
+        let config = HistoricalConfig::from_str(CONFIG).unwrap();
+        assert_eq!(config.source, HistoricalSource::Vision);
+        assert_eq!(config.kind, HistoricalKind::Trades);
+        assert_eq!(config.period, ArchivePeriod::Daily);
+        assert_eq!(config.start_date, Date { year: 2024, month: 1, day: 1 });
+        assert_eq!(config.vision_endpoint, BINANCE_VISION_ENDPOINT);
+    }
+
+    #[test]
+    fn test_invalid_config() {
+        let rest_trades = format!("{}source: rest", CONFIG);
+        assert!(HistoricalConfig::from_str(&rest_trades).unwrap_err().to_string().contains("klines"));
+        assert!(HistoricalConfig::from_str(&format!("{}interval: 7m", CONFIG)).is_err());
+        assert!(HistoricalConfig::from_str(&CONFIG.replace("2024-01-03", "2023-12-31")).is_err());
+        assert!(HistoricalConfig::from_str(&CONFIG.replace("2024-01-03", "2024-02-30")).is_err());
+        assert!(HistoricalConfig::from_str(&CONFIG.replace("[BTCUSDT, ETHUSDT]", "[]")).is_err());
+    }
+}
@@ -0,0 +1,230 @@
+//! Conversion of the historical data into session records.
+//!
+//! A session file holds one `{recv_time_ms}\t{kind}\t{payload}` record per
+//! payload, as read by the replayer of ctl-backtester. The trades and the
+//! aggregate trades are written as the payloads of their streams, timed by
+//! their trade times. A kline, without its trades, is written as four synthetic
+//! trades of a quarter of its volume each: its open at its open time, its low
+//! and high (in the order of its direction) in between, and its close at its
+//! close time. The synthetic trades carry no trade ID.
+
+use std::fmt;
+
+use ctl_rest::{AggTrade, HistoricalTrade, Kline};
+
+use crate::{HistoricalError, HistoricalKind};
+
+/// The times from which the archives are in microseconds, past any time in milliseconds.
+const MICROS_THRESHOLD: u64 = 100_000_000_000_000;
+
+/// A record of a session file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SessionRecord {
+    /// The time the payload was received, its trade time, in milliseconds.
+    pub recv_time_ms: u64,
+    /// The trade payload.
+    pub payload: String,
+}
+
+impl fmt::Display for SessionRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}\ttrade\t{}", self.recv_time_ms, self.payload)
+    }
+}
+
+/// Returns a time of an archive in milliseconds, the archives from 2025 being
+/// in microseconds.
+fn time_ms(time: u64) -> u64 {
+    if time >= MICROS_THRESHOLD { time / 1_000 } else { time }
+}
+
+/// Converts the CSV rows of an archive of `kind` of a symbol into session
+/// records, in the order of the rows.
+///
+/// # Errors
+/// Returns an error if a row isn't in the format of the data.
+pub fn records_from_csv(
+    kind: HistoricalKind,
+    symbol: &str,
+    name: &str,
+    csv: &str,
+) -> Result<Vec<SessionRecord>, HistoricalError> {
+    let mut records = Vec::new();
+    for (index, row) in csv.lines().enumerate() {
+        let fields: Vec<&str> = row.trim().split(',').collect();
+        // Some archives start with a header row
+        if fields[0].is_empty() || (index == 0 && fields[0].parse::<u64>().is_err()) {
+            continue;
+        }
+        let invalid = |reason: &str| HistoricalError::InvalidRow {
+            name: name.to_string(),
+            line: index + 1,
+            reason: reason.to_string(),
+        };
+        match kind {
+            HistoricalKind::Trades => {
+                let trade = trade_from_row(&fields).ok_or_else(|| invalid("expected a trade"))?;
+                records.push(SessionRecord { recv_time_ms: trade.time, payload: trade.to_stream_payload(symbol) });
+            }
+            HistoricalKind::AggTrades => {
+                let trade = agg_trade_from_row(&fields).ok_or_else(|| invalid("expected an aggregate trade"))?;
+                records.push(SessionRecord { recv_time_ms: trade.time, payload: trade.to_stream_payload(symbol) });
+            }
+            HistoricalKind::Klines => {
+                let kline = kline_from_row(&fields).ok_or_else(|| invalid("expected a kline"))?;
+                records.extend(kline_records(symbol, &kline));
+            }
+        }
+    }
+    Ok(records)
+}
+
+/// Parses a trade row: `id,price,qty,quote_qty,time,is_buyer_maker,is_best_match`.
+fn trade_from_row(fields: &[&str]) -> Option<HistoricalTrade> {
+    let [id, price, qty, _, time, is_buyer_maker, is_best_match] = fields else {
+        return None;
+    };
+    Some(HistoricalTrade {
+        id: id.parse().ok()?,
+        price: price.to_string(),
+        qty: qty.to_string(),
+        time: time_ms(time.parse().ok()?),
+        is_buyer_maker: parse_bool(is_buyer_maker)?,
+        is_best_match: parse_bool(is_best_match)?,
+    })
+}
+
+/// Parses an aggregate trade row:
+/// `id,price,qty,first_trade_id,last_trade_id,time,is_buyer_maker,is_best_match`.
+fn agg_trade_from_row(fields: &[&str]) -> Option<AggTrade> {
+    let [id, price, qty, first_trade_id, last_trade_id, time, is_buyer_maker, is_best_match] = fields else {
+        return None;
+    };
+    Some(AggTrade {
+        id: id.parse().ok()?,
+        price: price.to_string(),
+        qty: qty.to_string(),
+        first_trade_id: first_trade_id.parse().ok()?,
+        last_trade_id: last_trade_id.parse().ok()?,
+        time: time_ms(time.parse().ok()?),
+        is_buyer_maker: parse_bool(is_buyer_maker)?,
+        is_best_match: parse_bool(is_best_match)?,
+    })
+}
+
+/// Parses a kline row, in the columns of the kline/candlestick data endpoint.
+fn kline_from_row(fields: &[&str]) -> Option<Kline> {
+    let [
+        open_time,
+        open,
+        high,
+        low,
+        close,
+        volume,
+        close_time,
+        quote_volume,
+        trade_count,
+        taker_buy_volume,
+        taker_buy_quote_volume,
+        _,
+    ] = fields
+    else {
+        return None;
+    };
+    Some(Kline {
+        open_time_ms: time_ms(open_time.parse().ok()?),
+        open: open.to_string(),
+        high: high.to_string(),
+        low: low.to_string(),
+        close: close.to_string(),
+        volume: volume.to_string(),
+        close_time_ms: time_ms(close_time.parse().ok()?),
+        quote_volume: quote_volume.to_string(),
+        trade_count: trade_count.parse().ok()?,
+        taker_buy_volume: taker_buy_volume.to_string(),
+        taker_buy_quote_volume: taker_buy_quote_volume.to_string(),
+    })
+}
+
+fn parse_bool(field: &str) -> Option<bool> {
+    match field {
+        "True" | "true" => Some(true),
+        "False" | "false" => Some(false),
+        _ => None,
+    }
+}
+
+/// Returns the synthetic trades of a kline of a symbol, none if it had no trade.
+pub fn kline_records(symbol: &str, kline: &Kline) -> Vec<SessionRecord> {
+    let (Ok(open), Ok(close), Ok(volume)) =
+        (kline.open.parse::<f64>(), kline.close.parse::<f64>(), kline.volume.parse::<f64>())
+    else {
+        return Vec::new();
+    };
+    if kline.trade_count == 0 || volume <= 0.0 {
+        return Vec::new();
+    }
+    let (first, second) = if close >= open { (&kline.low, &kline.high) } else { (&kline.high, &kline.low) };
+    let third_ms = kline.close_time_ms.saturating_sub(kline.open_time_ms) / 3;
+    let qty = volume / 4.0;
+    [
+        (kline.open_time_ms, &kline.open),
+        (kline.open_time_ms + third_ms, first),
+        (kline.open_time_ms + 2 * third_ms, second),
+        (kline.close_time_ms, &kline.close),
+    ]
+    .into_iter()
+    .map(|(time_ms, price)| SessionRecord {
+        recv_time_ms: time_ms,
+        payload: format!(
+            r#"{{"e":"trade","E":{},"s":"{}","p":"{}","q":"{}","T":{},"m":false,"M":true}}"#,
+            time_ms, symbol, price, qty, time_ms
+        ),
+    })
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trades_from_csv() {
+        let csv = "id,price,qty,quote_qty,time,is_buyer_maker,is_best_match\n\
+                   28457,4.00000100,12.00000000,48.000012,1499865549590,True,True\n\
+                   28458,4.00000200,1.00000000,4.000002,1735689600000000,False,True\n";
+        let records = records_from_csv(HistoricalKind::Trades, "BNBBTC", "BNBBTC-trades", csv).unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(
+            records[0].to_string(),
+            "1499865549590\ttrade\t{\"e\":\"trade\",\"E\":1499865549590,\"s\":\"BNBBTC\",\"t\":28457,\
+             \"p\":\"4.00000100\",\"q\":\"12.00000000\",\"T\":1499865549590,\"m\":true,\"M\":true}"
+        );
+        // The archives from 2025 are in microseconds
+        assert_eq!(records[1].recv_time_ms, 1735689600000);
+
+        let err = records_from_csv(HistoricalKind::Trades, "BNBBTC", "BNBBTC-trades", "1,2,3\n").unwrap_err();
+        assert!(matches!(err, HistoricalError::InvalidRow { line: 1, .. }));
+    }
+
+    #[test]
+    fn test_agg_trades_from_csv() {
+        let csv = "26129,0.01633102,4.70443515,27781,27781,1498793709153,True,True\n";
+        let records = records_from_csv(HistoricalKind::AggTrades, "BNBBTC", "BNBBTC-aggTrades", csv).unwrap();
+        assert_eq!(records[0].recv_time_ms, 1498793709153);
+        assert!(records[0].payload.contains("\"a\":26129"));
+    }
+
+    #[test]
+    fn test_kline_trades() {
+        let csv = "1499040000000,10.0,12.0,9.0,11.0,8.0,1499040059999,84.0,3,4.0,42.0,0\n\
+                   1499040060000,11.0,11.0,11.0,11.0,0.0,1499040119999,0.0,0,0.0,0.0,0\n";
+        let records = records_from_csv(HistoricalKind::Klines, "BNBBTC", "BNBBTC-1m", csv).unwrap();
+        let times: Vec<u64> = records.iter().map(|record| record.recv_time_ms).collect();
+        assert_eq!(times, vec![1499040000000, 1499040019999, 1499040039998, 1499040059999]);
+        // Rising, so low before high, each a quarter of the volume
+        let prices: Vec<&str> = records.iter().map(|record| record.payload.split('"').nth(13).unwrap()).collect();
+        assert_eq!(prices, vec!["10.0", "9.0", "12.0", "11.0"]);
+        assert!(records[0].payload.contains("\"q\":\"2\""));
+    }
+}
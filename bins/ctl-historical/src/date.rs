@@ -0,0 +1,127 @@
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Deserializer};
+
+/// Milliseconds per day.
+pub const DAY_MS: u64 = 86_400_000;
+
+/// A UTC calendar date, the unit of the daily archives.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Date {
+    /// The year.
+    pub year: u32,
+    /// The month, from 1.
+    pub month: u32,
+    /// The day of the month, from 1.
+    pub day: u32,
+}
+
+impl Date {
+    /// Returns the date of a day since the epoch.
+    pub fn from_days(days: u64) -> Self {
+        // The civil calendar from the days since 0000-03-01, in eras of 400 years
+        let z = days + 719_468;
+        let era = z / 146_097;
+        let doe = z % 146_097;
+        let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+        let year = (yoe + era * 400) as u32 + (month <= 2) as u32;
+        Self { year, month, day }
+    }
+
+    /// Returns the days since the epoch of the date.
+    pub fn days(&self) -> u64 {
+        let year = (self.year - (self.month <= 2) as u32) as u64;
+        let era = year / 400;
+        let yoe = year % 400;
+        let mp = (self.month as u64 + 9) % 12;
+        let doy = (153 * mp + 2) / 5 + self.day as u64 - 1;
+        let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+        era * 146_097 + doe - 719_468
+    }
+
+    /// Returns the start of the date, in milliseconds since the epoch.
+    pub fn start_ms(&self) -> u64 {
+        self.days() * DAY_MS
+    }
+
+    /// Returns the next day.
+    pub fn next_day(&self) -> Self {
+        Self::from_days(self.days() + 1)
+    }
+
+    /// Returns the first day of the month of the date.
+    pub fn first_of_month(&self) -> Self {
+        Self { day: 1, ..*self }
+    }
+
+    /// Returns the first day of the next month.
+    pub fn next_month(&self) -> Self {
+        match self.month {
+            12 => Self { year: self.year + 1, month: 1, day: 1 },
+            month => Self { year: self.year, month: month + 1, day: 1 },
+        }
+    }
+
+    /// Returns the month of the date as `YYYY-MM`, naming the monthly archives.
+    pub fn month_str(&self) -> String {
+        format!("{:04}-{:02}", self.year, self.month)
+    }
+}
+
+impl fmt::Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
+
+impl FromStr for Date {
+    type Err = String;
+
+    /// Parses a `YYYY-MM-DD` date from 1970.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("invalid date '{}', expected YYYY-MM-DD", s);
+        let mut parts = s.splitn(3, '-').map(|part| part.parse::<u32>().map_err(|_| invalid()));
+        let (Some(year), Some(month), Some(day)) = (parts.next(), parts.next(), parts.next()) else {
+            return Err(invalid());
+        };
+        let date = Self { year: year?, month: month?, day: day? };
+        // Rejects the days past the end of their month, normalized into the next one
+        if date.year < 1970 || !(1..=12).contains(&date.month) || date.day == 0 || Self::from_days(date.days()) != date {
+            return Err(invalid());
+        }
+        Ok(date)
+    }
+}
+
+impl<'de> Deserialize<'de> for Date {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dates() {
+        let date: Date = "2024-02-28".parse().unwrap();
+        assert_eq!(date.days(), 19_781);
+        assert_eq!(Date::from_days(0).to_string(), "1970-01-01");
+        assert_eq!(date.next_day().to_string(), "2024-02-29");
+        assert_eq!(date.next_day().next_day().to_string(), "2024-03-01");
+        assert_eq!(date.start_ms(), 1_709_078_400_000);
+        assert_eq!("2023-12-31".parse::<Date>().unwrap().next_month().to_string(), "2024-01-01");
+        assert_eq!(date.first_of_month().month_str(), "2024-02");
+
+        assert!("2023-02-29".parse::<Date>().is_err());
+        assert!("2024-13-01".parse::<Date>().is_err());
+        assert!("2024-1".parse::<Date>().is_err());
+    }
+}
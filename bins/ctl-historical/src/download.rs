@@ -0,0 +1,190 @@
+//! Download of the historical data.
+//!
+//! The archives of Binance Vision are zipped CSV files, one per symbol and day
+//! (or month), at
+//! `{endpoint}/data/spot/{daily|monthly}/{trades|aggTrades|klines}/{symbol}/[{interval}/]{name}.zip`,
+//! named `{symbol}-{trades|aggTrades|interval}-{YYYY-MM-DD|YYYY-MM}`. An
+//! archive is published the day after its period ends, a missing one answered
+//! 404. The klines of the REST API are fetched by pages of up to 1000.
+
+use std::io::{Cursor, Read};
+
+use ctl_rest::{RestClient, MAX_KLINES_LIMIT};
+use reqwest::StatusCode;
+use reqwest::blocking::Client;
+use tracing::debug;
+
+use crate::{
+    kline_records, records_from_csv, ArchivePeriod, Date, HistoricalConfig, HistoricalError, HistoricalKind,
+    HistoricalSource, SessionRecord, DAY_MS,
+};
+
+/// Base URL of the Binance Vision archives.
+pub const BINANCE_VISION_ENDPOINT: &str = "https://data.binance.vision";
+
+/// Returns the name of the archive of a symbol over the period starting at `date`.
+pub fn archive_name(period: ArchivePeriod, kind: HistoricalKind, symbol: &str, interval: &str, date: Date) -> String {
+    let data = match kind {
+        HistoricalKind::Klines => interval,
+        _ => kind.archive_name(),
+    };
+    match period {
+        ArchivePeriod::Daily => format!("{}-{}-{}", symbol, data, date),
+        ArchivePeriod::Monthly => format!("{}-{}-{}", symbol, data, date.month_str()),
+    }
+}
+
+/// Returns the URL of the archive of a symbol over the period starting at `date`.
+pub fn archive_url(
+    endpoint: &str,
+    period: ArchivePeriod,
+    kind: HistoricalKind,
+    symbol: &str,
+    interval: &str,
+    date: Date,
+) -> String {
+    let period_dir = match period {
+        ArchivePeriod::Daily => "daily",
+        ArchivePeriod::Monthly => "monthly",
+    };
+    let interval_dir = match kind {
+        HistoricalKind::Klines => format!("{}/", interval),
+        _ => String::new(),
+    };
+    format!(
+        "{}/data/spot/{}/{}/{}/{}{}.zip",
+        endpoint.trim_end_matches('/'),
+        period_dir,
+        kind.archive_name(),
+        symbol,
+        interval_dir,
+        archive_name(period, kind, symbol, interval, date)
+    )
+}
+
+/// Returns the first days of the periods covering `start..=end`.
+pub fn period_starts(period: ArchivePeriod, start: Date, end: Date) -> Vec<Date> {
+    let mut starts = Vec::new();
+    let mut date = match period {
+        ArchivePeriod::Daily => start,
+        ArchivePeriod::Monthly => start.first_of_month(),
+    };
+    while date <= end {
+        starts.push(date);
+        date = match period {
+            ArchivePeriod::Daily => date.next_day(),
+            ArchivePeriod::Monthly => date.next_month(),
+        };
+    }
+    starts
+}
+
+/// Downloads the historical data of the configured symbols, period by period.
+pub struct Downloader<'a> {
+    /// The configuration of the download.
+    config: &'a HistoricalConfig,
+    /// The HTTP client of the archives.
+    http: Client,
+    /// The client of the REST API.
+    rest: RestClient,
+}
+
+impl<'a> Downloader<'a> {
+    /// Creates a downloader of the configured data.
+    pub fn new(config: &'a HistoricalConfig) -> Result<Self, HistoricalError> {
+        Ok(Self { config, http: Client::builder().build()?, rest: RestClient::new(&config.rest_endpoint)? })
+    }
+
+    /// Returns the first days of the periods downloaded, the days from the REST API.
+    pub fn periods(&self) -> Vec<Date> {
+        let period = match self.config.source {
+            HistoricalSource::Vision => self.config.period,
+            HistoricalSource::Rest => ArchivePeriod::Daily,
+        };
+        period_starts(period, self.config.start_date, self.config.end_date)
+    }
+
+    /// Returns the records of a symbol over the period starting at `date`,
+    /// within the configured days, `None` if its archive isn't published.
+    ///
+    /// LATENCY: SLOW_PATH
+    pub fn fetch(&self, symbol: &str, date: Date) -> Result<Option<Vec<SessionRecord>>, HistoricalError> {
+        let records = match self.config.source {
+            HistoricalSource::Vision => self.fetch_archive(symbol, date)?,
+            HistoricalSource::Rest => Some(self.fetch_klines(symbol, date)?),
+        };
+        // A monthly archive covers the days around the configured ones
+        let (start_ms, end_ms) = (self.config.start_date.start_ms(), self.config.end_date.next_day().start_ms());
+        Ok(records.map(|records| {
+            records.into_iter().filter(|record| (start_ms..end_ms).contains(&record.recv_time_ms)).collect()
+        }))
+    }
+
+    /// Downloads and converts the archive of a symbol.
+    fn fetch_archive(&self, symbol: &str, date: Date) -> Result<Option<Vec<SessionRecord>>, HistoricalError> {
+        let HistoricalConfig { vision_endpoint, period, kind, interval, .. } = self.config;
+        let url = archive_url(vision_endpoint, *period, *kind, symbol, interval, date);
+        debug!("Downloading {}", url);
+        let response = self.http.get(&url).send()?;
+        match response.status() {
+            StatusCode::OK => {}
+            StatusCode::NOT_FOUND => return Ok(None),
+            status => return Err(HistoricalError::Status { url, status: status.as_u16() }),
+        }
+        let bytes = response.bytes()?;
+
+        // A single CSV file per archive
+        let mut archive = zip::ZipArchive::new(Cursor::new(bytes))?;
+        let mut csv = String::new();
+        archive.by_index(0)?.read_to_string(&mut csv)?;
+        let name = archive_name(*period, *kind, symbol, interval, date);
+        records_from_csv(*kind, symbol, &name, &csv).map(Some)
+    }
+
+    /// Fetches the klines of a symbol over a day from the REST API.
+    fn fetch_klines(&self, symbol: &str, date: Date) -> Result<Vec<SessionRecord>, HistoricalError> {
+        let interval = &self.config.interval;
+        let (mut start_ms, end_ms) = (date.start_ms(), date.start_ms() + DAY_MS - 1);
+        let mut records = Vec::new();
+        while start_ms <= end_ms {
+            let klines = self.rest.klines(symbol, interval, start_ms, end_ms, MAX_KLINES_LIMIT)?;
+            let Some(last) = klines.last() else {
+                break;
+            };
+            start_ms = last.close_time_ms + 1;
+            records.extend(klines.iter().flat_map(|kline| kline_records(symbol, kline)));
+        }
+        Ok(records)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_archive_urls() {
+        let date: Date = "2024-01-05".parse().unwrap();
+        assert_eq!(
+            archive_url(BINANCE_VISION_ENDPOINT, ArchivePeriod::Daily, HistoricalKind::Trades, "BTCUSDT", "1m", date),
+            "https://data.binance.vision/data/spot/daily/trades/BTCUSDT/BTCUSDT-trades-2024-01-05.zip"
+        );
+        assert_eq!(
+            archive_url(BINANCE_VISION_ENDPOINT, ArchivePeriod::Monthly, HistoricalKind::Klines, "BTCUSDT", "1h", date),
+            "https://data.binance.vision/data/spot/monthly/klines/BTCUSDT/1h/BTCUSDT-1h-2024-01.zip"
+        );
+        assert_eq!(
+            archive_name(ArchivePeriod::Daily, HistoricalKind::AggTrades, "ETHUSDT", "1m", date),
+            "ETHUSDT-aggTrades-2024-01-05"
+        );
+    }
+
+    #[test]
+    fn test_period_starts() {
+        let (start, end) = ("2023-12-30".parse().unwrap(), "2024-02-01".parse().unwrap());
+        assert_eq!(period_starts(ArchivePeriod::Daily, start, end).len(), 34);
+        let months: Vec<String> =
+            period_starts(ArchivePeriod::Monthly, start, end).iter().map(Date::to_string).collect();
+        assert_eq!(months, vec!["2023-12-01", "2024-01-01", "2024-02-01"]);
+    }
+}
@@ -0,0 +1,39 @@
+use ctl_rest::RestError;
+use thiserror::Error;
+
+/// Errors that can occur when parsing or validating the historical data configuration.
+#[derive(Debug, Error)]
+pub enum HistoricalConfigError {
+    /// Error reading the configuration file.
+    #[error("Failed to read configuration file: {0}")]
+    FileReadError(#[from] std::io::Error),
+    /// Error parsing the YAML configuration.
+    #[error("Failed to parse YAML configuration: {0}")]
+    YamlParseError(#[from] serde_yaml::Error),
+    /// Validation error with a descriptive message.
+    #[error("Configuration validation error: {0}")]
+    ValidationError(String),
+}
+
+/// Errors that can occur when downloading or converting the historical data.
+#[derive(Debug, Error)]
+pub enum HistoricalError {
+    /// Error downloading an archive.
+    #[error("HTTP error: {0}")]
+    Http(#[from] reqwest::Error),
+    /// An archive was answered with an unexpected status.
+    #[error("{url} answered {status}")]
+    Status { url: String, status: u16 },
+    /// Error unpacking an archive.
+    #[error("Invalid archive: {0}")]
+    Archive(#[from] zip::result::ZipError),
+    /// A row of an archive isn't in the format of its data.
+    #[error("Invalid row {line} of {name}: {reason}")]
+    InvalidRow { name: String, line: usize, reason: String },
+    /// Error fetching the klines from the REST API.
+    #[error("REST error: {0}")]
+    Rest(#[from] RestError),
+    /// Error reading an archive.
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+}
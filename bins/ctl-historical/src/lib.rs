@@ -0,0 +1,18 @@
+//! Historical market data for the Binance Spot backtests.
+//!
+//! Downloads the trades, aggregate trades or klines of the configured symbols
+//! from the archives of Binance Vision (or the klines from the REST API), and
+//! converts them into the session format read by the replayer of
+//! ctl-backtester, so a backtest runs without first capturing live data.
+
+mod config;
+mod convert;
+mod date;
+mod download;
+mod errors;
+
+pub use config::{ArchivePeriod, HistoricalConfig, HistoricalKind, HistoricalSource, KLINE_INTERVALS};
+pub use convert::{kline_records, records_from_csv, SessionRecord};
+pub use date::{Date, DAY_MS};
+pub use download::{archive_name, archive_url, period_starts, Downloader, BINANCE_VISION_ENDPOINT};
+pub use errors::{HistoricalConfigError, HistoricalError};
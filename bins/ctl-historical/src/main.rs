@@ -0,0 +1,81 @@
+//! Historical Data Downloader for Binance Spot.
+//!
+//! This tool downloads the historical trades, aggregate trades or klines of the
+//! configured symbols over the configured days, from the Binance Vision archives
+//! or the REST API, and writes them as a session file for ctl-backtester, the
+//! records of all the symbols merged in the order of their times. It runs
+//! offline from the rest of the controller, without DPDK or shared memory.
+//!
+//! The exit code tells the class of a failure (see `FatalKind`).
+
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use clap::Parser;
+use ctl_core::{Fatal, FatalError, FatalKind};
+use ctl_historical::{Downloader, HistoricalConfig};
+use tracing::{info, warn};
+
+// Logging configuration, its levels reloaded on change
+const LOG_CONFIG_PATH: &str = "configs/logging.yaml";
+
+// Configuration file path
+const CONFIG_PATH: &str = "configs/historical/historical.yaml";
+
+/// Downloader of the historical market data of the backtests.
+#[derive(Debug, Parser)]
+#[command(version)]
+struct Args {
+    /// Logging configuration.
+    #[arg(long, env = "CTL_LOG_CONFIG", default_value = LOG_CONFIG_PATH)]
+    log_config: PathBuf,
+    /// Historical data configuration.
+    #[arg(long, env = "CTL_HISTORICAL_CONFIG", default_value = CONFIG_PATH)]
+    config: PathBuf,
+}
+
+fn main() -> ExitCode {
+    ctl_core::exit_code(run())
+}
+
+fn run() -> Result<(), FatalError> {
+    let args = Args::parse();
+    let _log = ctl_log::init_from_file(&args.log_config).fatal(FatalKind::Config)?;
+
+    info!("=== Binance Spot Historical Data ===");
+
+    let config = HistoricalConfig::from_file(&args.config).fatal(FatalKind::Config)?;
+    info!(
+        "{:?} {:?} of {:?} from {} to {}, from {:?}",
+        config.period, config.kind, config.symbols, config.start_date, config.end_date, config.source
+    );
+
+    let downloader = Downloader::new(&config).fatal(FatalKind::Network)?;
+    if let Some(dir) = Path::new(&config.output_path).parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut writer = BufWriter::new(File::create(&config.output_path)?);
+    let mut total = 0;
+    for date in downloader.periods() {
+        // The records of the symbols merged by time, the ties in the order of the symbols
+        let mut records = Vec::new();
+        for symbol in &config.symbols {
+            match downloader.fetch(symbol, date).fatal(FatalKind::Network)? {
+                Some(symbol_records) => records.extend(symbol_records),
+                None => warn!("[{}] No archive published for {}, skipped", symbol, date),
+            }
+        }
+        records.sort_by_key(|record| record.recv_time_ms);
+        for record in &records {
+            writeln!(writer, "{}", record)?;
+        }
+        info!("{}: {} records", date, records.len());
+        total += records.len();
+    }
+    writer.flush()?;
+
+    info!("Wrote {} records to {}", total, config.output_path);
+    Ok(())
+}
@@ -0,0 +1,160 @@
+//! Configuration module for the Kafka sink.
+//!
+//! This module provides the YAML parser and validation for the sink
+//! configuration defined in `configs/kafka-sink/kafka-sink.yaml`.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use crate::{KafkaSinkConfigError, TopicKind};
+
+/// Default prefix of the topics.
+const DEFAULT_TOPIC_PREFIX: &str = "binance-spot";
+
+/// Default time the producer waits for a batch to fill, in milliseconds.
+const DEFAULT_LINGER_MS: u64 = 50;
+
+/// Default maximum number of messages of a batch.
+const DEFAULT_BATCH_MESSAGES: usize = 10_000;
+
+/// Default compression of the batches.
+const DEFAULT_COMPRESSION: &str = "lz4";
+
+/// The compression codecs of the producer.
+const COMPRESSIONS: [&str; 5] = ["none", "gzip", "snappy", "lz4", "zstd"];
+
+fn default_topic_prefix() -> String {
+    DEFAULT_TOPIC_PREFIX.to_string()
+}
+
+fn default_linger_ms() -> u64 {
+    DEFAULT_LINGER_MS
+}
+
+fn default_batch_messages() -> usize {
+    DEFAULT_BATCH_MESSAGES
+}
+
+fn default_compression() -> String {
+    DEFAULT_COMPRESSION.to_string()
+}
+
+/// The configuration of the Kafka sink.
+#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+pub struct KafkaSinkConfig {
+    /// The bootstrap brokers (e.g. `kafka-1:9092,kafka-2:9092`).
+    pub brokers: String,
+    /// The URL of the schema registry the schemas of the topics are registered with.
+    pub schema_registry: String,
+    /// The prefix of the topics, each named `<prefix>.<kind>` (e.g. `binance-spot.trade`).
+    #[serde(default = "default_topic_prefix")]
+    pub topic_prefix: String,
+    /// The time the producer waits for a batch to fill, in milliseconds.
+    #[serde(default = "default_linger_ms")]
+    pub linger_ms: u64,
+    /// The maximum number of messages of a batch.
+    #[serde(default = "default_batch_messages")]
+    pub batch_messages: usize,
+    /// The compression of the batches (none, gzip, snappy, lz4, zstd).
+    #[serde(default = "default_compression")]
+    pub compression: String,
+    /// The OMS journals whose records are sunk to the execution topic.
+    #[serde(default)]
+    pub journals: Vec<PathBuf>,
+    /// Whether the journals are sunk from their start rather than from their end.
+    #[serde(default)]
+    pub from_start: bool,
+    /// Additional librdkafka properties of the producer (e.g. `security.protocol`).
+    #[serde(default)]
+    pub properties: BTreeMap<String, String>,
+}
+
+impl KafkaSinkConfig {
+    /// Parses the sink configuration from a YAML file.
+    ///
+    /// # Errors
+    /// Returns an error if the file cannot be read, parsed, or fails validation.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, KafkaSinkConfigError> {
+        let content = fs::read_to_string(path)?;
+        Self::from_str(&content)
+    }
+
+    /// Parses the sink configuration from a YAML string.
+    ///
+    /// # Errors
+    /// Returns an error if the YAML cannot be parsed or fails validation.
+    pub fn from_str(content: &str) -> Result<Self, KafkaSinkConfigError> {
+        let config: Self = serde_yaml::from_str(content)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Validates the sink configuration.
+    fn validate(&self) -> Result<(), KafkaSinkConfigError> {
+        if self.brokers.trim().is_empty() {
+            return Err(KafkaSinkConfigError::ValidationError("Brokers must not be empty".to_string()));
+        }
+        if !self.schema_registry.starts_with("http://") && !self.schema_registry.starts_with("https://") {
+            return Err(KafkaSinkConfigError::ValidationError(format!(
+                "Schema registry '{}' is not an HTTP URL",
+                self.schema_registry
+            )));
+        }
+        if self.topic_prefix.is_empty() {
+            return Err(KafkaSinkConfigError::ValidationError("Topic prefix must not be empty".to_string()));
+        }
+        if self.batch_messages == 0 {
+            return Err(KafkaSinkConfigError::ValidationError(
+                "Batch messages must be greater than 0".to_string(),
+            ));
+        }
+        if !COMPRESSIONS.contains(&self.compression.as_str()) {
+            return Err(KafkaSinkConfigError::ValidationError(format!(
+                "Unknown compression '{}', expected one of {:?}",
+                self.compression, COMPRESSIONS
+            )));
+        }
+        Ok(())
+    }
+
+    /// Returns the topic of a kind of messages.
+    pub fn topic(&self, kind: TopicKind) -> String {
+        format!("{}.{}", self.topic_prefix, kind.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = "brokers: localhost:9092\nschema_registry: http://localhost:8081\n";
+
+    #[test]
+    fn test_parse_config() {
+        let config = KafkaSinkConfig::from_str(CONFIG).unwrap();
+        assert_eq!(config.topic(TopicKind::AggTrade), "binance-spot.aggtrade");
+        assert_eq!((config.linger_ms, config.batch_messages), (DEFAULT_LINGER_MS, DEFAULT_BATCH_MESSAGES));
+        assert_eq!(config.compression, DEFAULT_COMPRESSION);
+        assert!(config.journals.is_empty() && config.properties.is_empty());
+
+        let yaml = format!(
+            "{}topic_prefix: md\njournals: [/var/lib/ctl/oms.journal]\nproperties:\n  security.protocol: SSL\n",
+            CONFIG
+        );
+        let config = KafkaSinkConfig::from_str(&yaml).unwrap();
+        assert_eq!(config.topic(TopicKind::Execution), "md.execution");
+        assert_eq!(config.journals, vec![PathBuf::from("/var/lib/ctl/oms.journal")]);
+        assert_eq!(config.properties["security.protocol"], "SSL");
+    }
+
+    #[test]
+    fn test_invalid_config() {
+        assert!(KafkaSinkConfig::from_str("brokers: ''\nschema_registry: http://localhost:8081").is_err());
+        assert!(KafkaSinkConfig::from_str("brokers: localhost:9092\nschema_registry: localhost:8081").is_err());
+        assert!(KafkaSinkConfig::from_str(&format!("{}compression: brotli", CONFIG)).is_err());
+        assert!(KafkaSinkConfig::from_str(&format!("{}batch_messages: 0", CONFIG)).is_err());
+    }
+}
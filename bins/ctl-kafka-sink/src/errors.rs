@@ -0,0 +1,32 @@
+use thiserror::Error;
+
+/// Errors that can occur when parsing or validating the sink configuration.
+#[derive(Debug, Error)]
+pub enum KafkaSinkConfigError {
+    /// Error reading the configuration file.
+    #[error("Failed to read configuration file: {0}")]
+    FileReadError(#[from] std::io::Error),
+    /// Error parsing the YAML configuration.
+    #[error("Failed to parse YAML configuration: {0}")]
+    YamlParseError(#[from] serde_yaml::Error),
+    /// Validation error with a descriptive message.
+    #[error("Configuration validation error: {0}")]
+    ValidationError(String),
+}
+
+/// Errors that can occur when sinking the messages to Kafka.
+#[derive(Debug, Error)]
+pub enum SinkError {
+    /// Error reaching the schema registry.
+    #[error("Schema registry request failed: {0}")]
+    Registry(#[from] reqwest::Error),
+    /// The schema registry refused a schema.
+    #[error("Schema registry refused the schema of {subject}: {status} {body}")]
+    SchemaRefused { subject: String, status: u16, body: String },
+    /// Error of the Kafka producer.
+    #[error("Kafka producer error: {0}")]
+    Kafka(#[from] rdkafka::error::KafkaError),
+    /// Error reading an OMS journal.
+    #[error("Failed to read journal: {0}")]
+    Journal(#[from] std::io::Error),
+}
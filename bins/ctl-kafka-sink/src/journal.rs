@@ -0,0 +1,29 @@
+//! Keys of the OMS journal records sunk to the execution topic.
+//!
+//! The journals are tailed with `ctl_oms::JournalTail`, each complete line
+//! appended being a record sunk as is.
+
+use serde_json::Value;
+
+/// Returns the key of a journal record, the client order ID of its order or
+/// list, so the records of an order keep their order within its partition.
+pub fn record_key(record: &[u8]) -> Option<String> {
+    let record: Value = serde_json::from_slice(record).ok()?;
+    ["/client_order_id", "/list_client_order_id", "/list/list_client_order_id"]
+        .iter()
+        .find_map(|pointer| record.pointer(pointer)?.as_str())
+        .map(str::to_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_key() {
+        assert_eq!(record_key(br#"{"type":"updated","client_order_id":"abc"}"#).unwrap(), "abc");
+        let list = br#"{"type":"list_submitted","list":{"list_client_order_id":"oco-1"},"legs":[]}"#;
+        assert_eq!(record_key(list).unwrap(), "oco-1");
+        assert!(record_key(b"not json").is_none());
+    }
+}
@@ -0,0 +1,23 @@
+//! Kafka sink of the market and execution data of Binance Spot.
+//!
+//! Consumes the Top, trade and aggregated trade rings like any other consumer,
+//! and tails the OMS journals, batching their messages into Kafka topics, one
+//! per kind (`<prefix>.top`, `<prefix>.trade`, `<prefix>.aggtrade`,
+//! `<prefix>.execution`). The values are the JSON payloads and records as is,
+//! framed in the wire format of the schema registry with the ID of the JSON
+//! Schema of their topic, so the standard consumers of the data pipeline can
+//! deserialize them.
+
+mod config;
+mod errors;
+mod journal;
+mod registry;
+mod sink;
+mod topic;
+
+pub use config::KafkaSinkConfig;
+pub use errors::{KafkaSinkConfigError, SinkError};
+pub use journal::record_key;
+pub use registry::{encode, SchemaRegistry};
+pub use sink::{DeliveryCounters, KafkaSink, SinkStats};
+pub use topic::TopicKind;
@@ -0,0 +1,251 @@
+//! Kafka Sink for the Binance Spot Controller.
+//!
+//! Connects as a DPDK secondary process, attaches a consumer to the Top, trade
+//! and aggregated trade rings of the market data configuration, tails the OMS
+//! journals of the sink configuration, and batches their messages into Kafka
+//! topics, one per kind, in the wire format of the schema registry.
+//!
+//! The sink is optional: the plant runs without it, and it never holds back
+//! the rings. A message finding the producer queue full is dropped and
+//! counted, as are the messages the brokers fail to acknowledge.
+//!
+//! The rings are polled under the policy of `configs/polling.yaml`. Once
+//! overtaken by a producer, the sink recovers per `configs/overtaken.yaml`;
+//! the raw payloads aren't conflated, so a snapshot requested by
+//! `skip-and-request-snapshot` is a skip to the head.
+//!
+//! The exit code tells the class of a failure (see `FatalKind`).
+
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::time::{Duration, Instant};
+
+use clap::Parser;
+use ctl_core::{
+    Capability, Fatal, FatalError, FatalKind, LcoresConfig, OvertakenConfig, OvertakenPolicy, Poller, PollingConfig,
+    PollingPolicy, Preflight, RingKind, RingName,
+};
+use ctl_feed::{dpdk_consume, slot_payload, MetricsRegion, RawMessage, RingRead, RingReader, METRICS_REGION_NAME};
+use ctl_kafka_sink::{record_key, KafkaSink, KafkaSinkConfig, SchemaRegistry, TopicKind};
+use ctl_md_handler::{HwResourcesConfig, SymbolInfoConfig};
+use ctl_oms::JournalTail;
+use ctl_shm::ShmRegion;
+use dpdk::{DpdkEnvBuilder, DpdkProcessType};
+use hashbrown::HashMap;
+use serde::Deserialize;
+use tracing::{error, info, warn};
+
+// Logging configuration, its levels reloaded on change
+const LOG_CONFIG_PATH: &str = "configs/logging.yaml";
+
+// The market data configuration, holding the feeds and the EAL options of the secondary processes
+const MD_CONFIG_PATH: &str = "configs/market-data/hw-resources.yaml";
+
+// The symbol IDs of the rings
+const SYMBOL_INFO_PATH: &str = "configs/market-data/symbolinfo.yaml";
+
+// The sink configuration
+const SINK_CONFIG_PATH: &str = "configs/kafka-sink/kafka-sink.yaml";
+
+// The name of the consumer cursors of the sink in the rings
+const CONSUMER_NAME: &str = "kafka-sink";
+
+// The lcore of the sink, unless configured, shared with the mostly idle tools
+const LCORES_CONFIG_PATH: &str = "configs/lcores.yaml";
+const LCORES_COMPONENT: &str = "kafka-sink";
+const DEFAULT_LCORE: u32 = 15;
+
+// Polling policy between the passes over the empty rings, unless configured for this component
+const POLLING_CONFIG_PATH: &str = "configs/polling.yaml";
+const POLLING_COMPONENT: &str = "kafka-sink";
+const DEFAULT_POLLING: PollingPolicy = PollingPolicy::Sleep { sleep_us: 100 };
+
+// Recovery once overtaken by a producer, unless configured for this component
+const OVERTAKEN_CONFIG_PATH: &str = "configs/overtaken.yaml";
+const OVERTAKEN_COMPONENT: &str = "kafka-sink";
+const DEFAULT_OVERTAKEN: OvertakenPolicy = OvertakenPolicy::SkipToHead;
+
+// Interval between the polls of the OMS journals
+const JOURNAL_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+// Interval between the logs of the counters of the sink
+const STATS_LOG_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Kafka sink of the market data rings and the OMS journals.
+#[derive(Debug, Parser)]
+#[command(version)]
+struct Args {
+    /// Logging configuration.
+    #[arg(long, env = "CTL_LOG_CONFIG", default_value = LOG_CONFIG_PATH)]
+    log_config: PathBuf,
+    /// Market data configuration.
+    #[arg(long, env = "CTL_MD_CONFIG", default_value = MD_CONFIG_PATH)]
+    md_config: PathBuf,
+    /// Symbol info configuration.
+    #[arg(long, env = "CTL_SYMBOL_INFO", default_value = SYMBOL_INFO_PATH)]
+    symbol_info: PathBuf,
+    /// Sink configuration.
+    #[arg(long, env = "CTL_KAFKA_SINK_CONFIG", default_value = SINK_CONFIG_PATH)]
+    sink_config: PathBuf,
+    /// Lcores configuration.
+    #[arg(long, env = "CTL_LCORES_CONFIG", default_value = LCORES_CONFIG_PATH)]
+    lcores_config: PathBuf,
+    /// Polling configuration.
+    #[arg(long, env = "CTL_POLLING_CONFIG", default_value = POLLING_CONFIG_PATH)]
+    polling_config: PathBuf,
+    /// Overtaken consumer recovery configuration.
+    #[arg(long, env = "CTL_OVERTAKEN_CONFIG", default_value = OVERTAKEN_CONFIG_PATH)]
+    overtaken_config: PathBuf,
+}
+
+/// The symbol of a feed payload, the key of its message.
+#[derive(Deserialize)]
+struct PayloadSymbol<'a> {
+    #[serde(rename = "s")]
+    symbol: &'a str,
+}
+
+fn main() -> ExitCode {
+    ctl_core::exit_code(run())
+}
+
+fn run() -> Result<(), FatalError> {
+    let args = Args::parse();
+    let _log = ctl_log::init_from_file(&args.log_config).fatal(FatalKind::Config)?;
+
+    info!("=== Binance Spot Kafka Sink ===");
+
+    let md_config = HwResourcesConfig::from_file(&args.md_config).fatal(FatalKind::Config)?;
+    let symbol_info = SymbolInfoConfig::from_file(&args.symbol_info).fatal(FatalKind::Config)?;
+    let config = KafkaSinkConfig::from_file(&args.sink_config).fatal(FatalKind::Config)?;
+    md_config.eal.apply_region_prefix();
+    let lcore = LcoresConfig::from_file(&args.lcores_config)?.lcore(LCORES_COMPONENT, DEFAULT_LCORE) as usize;
+    let polling = PollingConfig::from_file(&args.polling_config)?.policy(POLLING_COMPONENT, DEFAULT_POLLING);
+    info!("Polling: {:?}", polling);
+    let overtaken = OvertakenConfig::from_file(&args.overtaken_config)?.policy(OVERTAKEN_COMPONENT, DEFAULT_OVERTAKEN);
+    info!("Overtaken: {:?}", overtaken);
+
+    // Verify the host before the EAL initialization, which reports its failures cryptically
+    Preflight::new()
+        .require_hugepages_mounted()
+        .require_capabilities(&[Capability::IpcLock])
+        .require_primary()
+        .with_file_prefix(md_config.eal.file_prefix())
+        .run()?;
+
+    let dpdk_env = DpdkEnvBuilder::default()
+        .process_type(DpdkProcessType::Secondary)
+        .lcore_ids(vec![lcore])
+        .main_lcore_id(lcore)
+        .eal_args(md_config.eal.secondary_args())
+        .build()
+        .fatal(FatalKind::DpdkInit)?;
+
+    let metrics = ShmRegion::<MetricsRegion>::open(METRICS_REGION_NAME)?;
+
+    // Attach to the raw rings of the feeds, tracking the positions in their metrics like any consumer
+    let mut rings = Vec::new();
+    let mut kinds = Vec::new();
+    for (kind, feed_kind) in [(RingKind::Top, "top"), (RingKind::Trade, "trade"), (RingKind::AggTrade, "aggtrade")] {
+        let topic_kind = TopicKind::from_ring_kind(kind).expect("raw ring kinds are sunk");
+        let Some(feed) = md_config.find_feed(feed_kind) else {
+            continue;
+        };
+        for feed_set in feed.feed_sets() {
+            for symbol in feed_set.ring_symbols() {
+                let symbol_id = symbol_info
+                    .symbol_id(symbol)
+                    .ok_or_else(|| format!("Symbol '{}' not found in symbolinfo.yaml", symbol))
+                    .fatal(FatalKind::Config)?;
+                let name = RingName::pubsub(kind, symbol_id).to_string();
+                let reader = RingReader::attach(&metrics, &name, CONSUMER_NAME, feed_set.slot_size)
+                    .fatal(FatalKind::SharedState)?
+                    .with_overtaken(overtaken);
+                let ring = dpdk_env.pubsub_lookup::<RawMessage>(&name).fatal(FatalKind::SharedState)?;
+                let consumer = ring.attach_consumer().fatal(FatalKind::SharedState)?;
+                info!("[{}] {} -> {}", symbol, name, config.topic(topic_kind));
+                rings.push((topic_kind, reader, consumer));
+            }
+        }
+        kinds.push(topic_kind);
+    }
+
+    let mut journals = Vec::new();
+    for path in &config.journals {
+        journals.push(JournalTail::open(path, config.from_start).fatal(FatalKind::Config)?);
+        info!("{} -> {}", path.display(), config.topic(TopicKind::Execution));
+    }
+    if !journals.is_empty() {
+        kinds.push(TopicKind::Execution);
+    }
+    if kinds.is_empty() {
+        return Err(FatalError::new(FatalKind::Config, "No rings or journals to sink"));
+    }
+
+    // Register the schemas of the topics before the first message references them
+    let registry = SchemaRegistry::new(&config.schema_registry).fatal(FatalKind::Network)?;
+    let mut schemas = HashMap::new();
+    for kind in kinds {
+        let topic = config.topic(kind);
+        let id = registry.register(&topic, kind.schema()).fatal(FatalKind::Network)?;
+        info!("Schema {} of {}", id, topic);
+        schemas.insert(kind, id);
+    }
+    let mut sink = KafkaSink::new(&config, schemas).fatal(FatalKind::Config)?;
+    info!("Sinking to {}", config.brokers);
+
+    let mut last_journal_poll = Instant::now();
+    let mut last_log = Instant::now();
+    let mut poller = Poller::new(polling);
+    loop {
+        let mut did_work = false;
+        for (kind, reader, consumer) in rings.iter_mut() {
+            let read = reader.read(dpdk_consume!(consumer)).fatal(FatalKind::Overtaken)?;
+            did_work |= read.did_work();
+            match read {
+                RingRead::Payload(message, payload) => {
+                    let payload = slot_payload(payload);
+                    let key = serde_json::from_slice::<PayloadSymbol<'_>>(payload).ok().map(|p| p.symbol);
+                    if let Err(e) = sink.send(*kind, key, payload, Some(message.header.ts_ms as i64)) {
+                        error!("{} message not sunk: {}", reader.name(), e);
+                    }
+                }
+                RingRead::Overtaken { .. } => {
+                    warn!("{} consumer overtaken by producer, some messages missed", reader.name());
+                }
+                RingRead::Consumed | RingRead::Idle => {}
+            }
+        }
+        sink.poll();
+        if !did_work {
+            if last_journal_poll.elapsed() >= JOURNAL_POLL_INTERVAL {
+                last_journal_poll = Instant::now();
+                for journal in &mut journals {
+                    let records = match journal.poll() {
+                        Ok(records) => records,
+                        Err(e) => {
+                            warn!("Failed to read journal {}: {}", journal.path().display(), e);
+                            continue;
+                        }
+                    };
+                    for record in records {
+                        let key = record_key(&record);
+                        if let Err(e) = sink.send(TopicKind::Execution, key.as_deref(), &record, None) {
+                            error!("{} record not sunk: {}", journal.path().display(), e);
+                        }
+                    }
+                }
+            }
+            if last_log.elapsed() >= STATS_LOG_INTERVAL {
+                last_log = Instant::now();
+                let stats = sink.stats();
+                info!(
+                    "Messages delivered: {}, failed: {}, dropped for a full queue: {}",
+                    stats.delivered, stats.failed, stats.dropped
+                );
+            }
+        }
+        // Wait before the next pass according to the configured policy
+        poller.wait(did_work);
+    }
+}
@@ -0,0 +1,84 @@
+//! Registration of the schemas of the topics with a Confluent-compatible schema
+//! registry, and the wire format of the values referencing them.
+//! https://docs.confluent.io/platform/current/schema-registry/fundamentals/serdes-develop/index.html#wire-format
+
+use reqwest::blocking::Client;
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::SinkError;
+
+/// The magic byte leading the values in the wire format.
+const MAGIC_BYTE: u8 = 0;
+
+/// The content type of the requests of the registry.
+const CONTENT_TYPE: &str = "application/vnd.schemaregistry.v1+json";
+
+/// The response of a schema registration.
+#[derive(Deserialize)]
+struct Registered {
+    id: u32,
+}
+
+/// A client of the schema registry.
+#[derive(Debug)]
+pub struct SchemaRegistry {
+    /// The URL of the registry, without its trailing slash.
+    url: String,
+    http: Client,
+}
+
+impl SchemaRegistry {
+    /// Creates the client of the registry at `url`.
+    ///
+    /// # Errors
+    /// Returns an error if the HTTP client cannot be built.
+    pub fn new(url: &str) -> Result<Self, SinkError> {
+        Ok(Self { url: url.trim_end_matches('/').to_string(), http: Client::builder().build()? })
+    }
+
+    /// Registers the JSON Schema of the values of a topic under its subject
+    /// (`<topic>-value`), returning its ID. Registering a schema already
+    /// registered returns its existing ID.
+    ///
+    /// # Errors
+    /// Returns an error if the registry is unreachable or refuses the schema.
+    pub fn register(&self, topic: &str, schema: &str) -> Result<u32, SinkError> {
+        let subject = format!("{}-value", topic);
+        let response = self
+            .http
+            .post(format!("{}/subjects/{}/versions", self.url, subject))
+            .header(reqwest::header::CONTENT_TYPE, CONTENT_TYPE)
+            .body(json!({ "schemaType": "JSON", "schema": schema }).to_string())
+            .send()?;
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().unwrap_or_default();
+            return Err(SinkError::SchemaRefused { subject, status: status.as_u16(), body });
+        }
+        Ok(response.json::<Registered>()?.id)
+    }
+}
+
+/// Writes a value in the wire format of the schema registry into `out`: the
+/// magic byte, the big-endian ID of its schema, then the JSON value itself.
+pub fn encode(schema_id: u32, value: &[u8], out: &mut Vec<u8>) {
+    out.clear();
+    out.reserve(5 + value.len());
+    out.push(MAGIC_BYTE);
+    out.extend_from_slice(&schema_id.to_be_bytes());
+    out.extend_from_slice(value);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode() {
+        let mut out = vec![9, 9];
+        encode(0x0102_0304, br#"{"s":"BTCUSDT"}"#, &mut out);
+        assert_eq!(&out[..5], &[0, 1, 2, 3, 4]);
+        assert_eq!(&out[5..], br#"{"s":"BTCUSDT"}"#);
+    }
+}
@@ -0,0 +1,140 @@
+//! The Kafka producer of the sink.
+//!
+//! The producer batches the messages of each topic partition, a batch being
+//! sent once full or after `linger_ms`. Sending never blocks the caller: a
+//! message finding the queue of the producer full is dropped and counted, so a
+//! broker outage costs the sink its backlog rather than holding back the rings
+//! it consumes.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use hashbrown::HashMap;
+use rdkafka::ClientConfig;
+use rdkafka::client::ClientContext;
+use rdkafka::error::KafkaError;
+use rdkafka::producer::{BaseProducer, BaseRecord, DeliveryResult, Producer, ProducerContext};
+use rdkafka::types::RDKafkaErrorCode;
+
+use crate::{encode, KafkaSinkConfig, SinkError, TopicKind};
+
+/// The counters of the deliveries of the producer.
+#[derive(Debug, Default)]
+pub struct DeliveryCounters {
+    /// Number of messages acknowledged by the brokers.
+    delivered: AtomicU64,
+    /// Number of messages the brokers failed to acknowledge.
+    failed: AtomicU64,
+}
+
+impl ClientContext for DeliveryCounters {}
+
+impl ProducerContext for DeliveryCounters {
+    type DeliveryOpaque = ();
+
+    fn delivery(&self, result: &DeliveryResult<'_>, _: Self::DeliveryOpaque) {
+        let counter = if result.is_ok() { &self.delivered } else { &self.failed };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// The counters of the messages of the sink.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SinkStats {
+    /// Number of messages acknowledged by the brokers.
+    pub delivered: u64,
+    /// Number of messages the brokers failed to acknowledge.
+    pub failed: u64,
+    /// Number of messages dropped for a full producer queue.
+    pub dropped: u64,
+}
+
+/// The sink of the messages to their topics.
+pub struct KafkaSink {
+    producer: BaseProducer<DeliveryCounters>,
+    /// The topic and the schema ID of each kind of messages.
+    topics: HashMap<TopicKind, (String, u32)>,
+    /// The encoded value of the message being sent.
+    value: Vec<u8>,
+    /// Number of messages dropped for a full producer queue.
+    dropped: u64,
+}
+
+impl KafkaSink {
+    /// Creates the producer of the topics of the kinds of `schemas`, by the ID of their schema.
+    ///
+    /// # Errors
+    /// Returns an error if the producer cannot be created from the configuration.
+    pub fn new(config: &KafkaSinkConfig, schemas: HashMap<TopicKind, u32>) -> Result<Self, SinkError> {
+        let mut client = ClientConfig::new();
+        client
+            .set("bootstrap.servers", &config.brokers)
+            .set("linger.ms", config.linger_ms.to_string())
+            .set("batch.num.messages", config.batch_messages.to_string())
+            .set("compression.type", &config.compression);
+        for (key, value) in &config.properties {
+            client.set(key, value);
+        }
+        let producer = client.create_with_context(DeliveryCounters::default())?;
+        let topics = schemas.into_iter().map(|(kind, id)| (kind, (config.topic(kind), id))).collect();
+        Ok(Self { producer, topics, value: Vec::new(), dropped: 0 })
+    }
+
+    /// Queues a message of a kind, keyed for its partition and timestamped in
+    /// milliseconds. Returns `false` if the message is dropped, its kind not
+    /// sunk or the queue of the producer full.
+    ///
+    /// # Errors
+    /// Returns an error if the producer refuses the message for another reason
+    /// (e.g. a message larger than `message.max.bytes`).
+    pub fn send(
+        &mut self,
+        kind: TopicKind,
+        key: Option<&str>,
+        value: &[u8],
+        timestamp_ms: Option<i64>,
+    ) -> Result<bool, SinkError> {
+        let Some((topic, schema_id)) = self.topics.get(&kind) else {
+            return Ok(false);
+        };
+        encode(*schema_id, value, &mut self.value);
+        let mut record = BaseRecord::<str, [u8]>::to(topic).payload(&self.value);
+        if let Some(key) = key {
+            record = record.key(key);
+        }
+        if let Some(timestamp_ms) = timestamp_ms {
+            record = record.timestamp(timestamp_ms);
+        }
+        match self.producer.send(record) {
+            Ok(()) => Ok(true),
+            Err((KafkaError::MessageProduction(RDKafkaErrorCode::QueueFull), _)) => {
+                self.dropped += 1;
+                Ok(false)
+            }
+            Err((e, _)) => Err(e.into()),
+        }
+    }
+
+    /// Serves the delivery reports of the messages sent, without waiting.
+    pub fn poll(&self) {
+        self.producer.poll(Duration::ZERO);
+    }
+
+    /// Waits up to `timeout` for the messages queued to be delivered.
+    ///
+    /// # Errors
+    /// Returns an error if some messages are still queued after the timeout.
+    pub fn flush(&self, timeout: Duration) -> Result<(), SinkError> {
+        Ok(self.producer.flush(timeout)?)
+    }
+
+    /// Returns the counters of the messages of the sink.
+    pub fn stats(&self) -> SinkStats {
+        let counters = self.producer.context();
+        SinkStats {
+            delivered: counters.delivered.load(Ordering::Relaxed),
+            failed: counters.failed.load(Ordering::Relaxed),
+            dropped: self.dropped,
+        }
+    }
+}
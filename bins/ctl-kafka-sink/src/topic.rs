@@ -0,0 +1,141 @@
+//! The topics of the sink, one per kind of message, and the JSON Schemas of
+//! their values registered with the schema registry.
+//!
+//! The market data values are the payloads as received from Binance and the
+//! execution values the records of the OMS journals, so the schemas only pin
+//! the fields the downstream pipeline relies on.
+
+use ctl_core::RingKind;
+
+/// The schema of the book ticker payloads.
+/// https://github.com/binance/binance-spot-api-docs/blob/master/web-socket-streams.md#individual-symbol-book-ticker-streams
+const TOP_SCHEMA: &str = r#"{
+  "$schema": "http://json-schema.org/draft-07/schema#",
+  "title": "BookTicker",
+  "type": "object",
+  "properties": {
+    "u": { "type": "integer" },
+    "s": { "type": "string" },
+    "b": { "type": "string" },
+    "B": { "type": "string" },
+    "a": { "type": "string" },
+    "A": { "type": "string" }
+  },
+  "required": ["u", "s", "b", "B", "a", "A"]
+}"#;
+
+/// The schema of the trade payloads.
+/// https://github.com/binance/binance-spot-api-docs/blob/master/web-socket-streams.md#trade-streams
+const TRADE_SCHEMA: &str = r#"{
+  "$schema": "http://json-schema.org/draft-07/schema#",
+  "title": "Trade",
+  "type": "object",
+  "properties": {
+    "e": { "const": "trade" },
+    "E": { "type": "integer" },
+    "s": { "type": "string" },
+    "t": { "type": "integer" },
+    "p": { "type": "string" },
+    "q": { "type": "string" },
+    "T": { "type": "integer" },
+    "m": { "type": "boolean" }
+  },
+  "required": ["e", "E", "s", "t", "p", "q", "T", "m"]
+}"#;
+
+/// The schema of the aggregated trade payloads.
+/// https://github.com/binance/binance-spot-api-docs/blob/master/web-socket-streams.md#aggregate-trade-streams
+const AGG_TRADE_SCHEMA: &str = r#"{
+  "$schema": "http://json-schema.org/draft-07/schema#",
+  "title": "AggTrade",
+  "type": "object",
+  "properties": {
+    "e": { "const": "aggTrade" },
+    "E": { "type": "integer" },
+    "s": { "type": "string" },
+    "a": { "type": "integer" },
+    "p": { "type": "string" },
+    "q": { "type": "string" },
+    "f": { "type": "integer" },
+    "l": { "type": "integer" },
+    "T": { "type": "integer" },
+    "m": { "type": "boolean" }
+  },
+  "required": ["e", "E", "s", "a", "p", "q", "f", "l", "T", "m"]
+}"#;
+
+/// The schema of the OMS journal records (see `ctl_oms::JournalRecord`).
+const EXECUTION_SCHEMA: &str = r#"{
+  "$schema": "http://json-schema.org/draft-07/schema#",
+  "title": "JournalRecord",
+  "type": "object",
+  "properties": {
+    "type": { "enum": ["submitted", "updated", "list_submitted", "list_updated"] }
+  },
+  "required": ["type"]
+}"#;
+
+/// The kind of the messages of a topic.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TopicKind {
+    /// The book ticker payloads of the Top rings.
+    Top,
+    /// The trade payloads of the trade rings.
+    Trade,
+    /// The aggregated trade payloads of the aggregated trade rings.
+    AggTrade,
+    /// The records of the OMS journals.
+    Execution,
+}
+
+impl TopicKind {
+    /// Every topic kind.
+    pub const ALL: [TopicKind; 4] = [TopicKind::Top, TopicKind::Trade, TopicKind::AggTrade, TopicKind::Execution];
+
+    /// Returns the topic kind of the raw rings of a kind, `None` for the rings not sunk.
+    pub fn from_ring_kind(kind: RingKind) -> Option<Self> {
+        match kind {
+            RingKind::Top => Some(TopicKind::Top),
+            RingKind::Trade => Some(TopicKind::Trade),
+            RingKind::AggTrade => Some(TopicKind::AggTrade),
+            RingKind::Depth | RingKind::Stats | RingKind::Kline | RingKind::Signal => None,
+        }
+    }
+
+    /// Returns the name of the kind, the suffix of its topic (e.g. `trade`).
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TopicKind::Top => "top",
+            TopicKind::Trade => "trade",
+            TopicKind::AggTrade => "aggtrade",
+            TopicKind::Execution => "execution",
+        }
+    }
+
+    /// Returns the JSON Schema of the values of the topics of the kind.
+    pub fn schema(&self) -> &'static str {
+        match self {
+            TopicKind::Top => TOP_SCHEMA,
+            TopicKind::Trade => TRADE_SCHEMA,
+            TopicKind::AggTrade => AGG_TRADE_SCHEMA,
+            TopicKind::Execution => EXECUTION_SCHEMA,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use serde_json::Value;
+
+    #[test]
+    fn test_schemas() {
+        for kind in TopicKind::ALL {
+            let schema: Value = serde_json::from_str(kind.schema()).unwrap();
+            assert_eq!(schema["type"], "object", "{}", kind.as_str());
+        }
+        assert_eq!(TopicKind::from_ring_kind(RingKind::AggTrade), Some(TopicKind::AggTrade));
+        assert_eq!(TopicKind::from_ring_kind(RingKind::Stats), None);
+    }
+}
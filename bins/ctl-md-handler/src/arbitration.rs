@@ -0,0 +1,57 @@
+//! A/B line arbitration of the feeds.
+//!
+//! With an `arbitration` item, the feeds are published by two md-handlers,
+//! started with `--line a` and `--line b` on their own connections (their own
+//! configuration, differing in lcores and endpoints). Each line publishes to
+//! its line rings (`{KIND}_{symbol_id}_LA`, `_LB`), planned by the resource
+//! manager next to the pub-sub ring, and ctl-arbiter forwards the first copy of
+//! each event to the pub-sub ring, the strategies consuming it unchanged. The
+//! events are told apart by their trade and update IDs, remembered within a
+//! window per symbol and event type, so the payloads must be JSON (the FIX
+//! parser translating its events to JSON).
+
+use ctl_feed::DEFAULT_DEDUP_WINDOW;
+use serde::Deserialize;
+
+const DEFAULT_SILENT_AFTER_MS: u64 = 5_000;
+
+fn default_silent_after_ms() -> u64 {
+    DEFAULT_SILENT_AFTER_MS
+}
+
+fn default_dedup_window() -> u64 {
+    DEFAULT_DEDUP_WINDOW
+}
+
+/// Arbitration of the A/B lines of the feeds.
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Eq, Hash)]
+pub struct ArbitrationConfig {
+    /// Time without a message on a line, while the other line carries messages,
+    /// after which the line is reported silent, in milliseconds.
+    #[serde(default = "default_silent_after_ms")]
+    pub silent_after_ms: u64,
+    /// Number of event IDs remembered behind the last one forwarded of each
+    /// symbol and event type, an event lost by a line being forwarded from the
+    /// other line as long as it is within the window.
+    #[serde(default = "default_dedup_window")]
+    pub dedup_window: u64,
+}
+
+impl Default for ArbitrationConfig {
+    fn default() -> Self {
+        Self { silent_after_ms: DEFAULT_SILENT_AFTER_MS, dedup_window: DEFAULT_DEDUP_WINDOW }
+    }
+}
+
+impl ArbitrationConfig {
+    /// Validates the arbitration configuration.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.silent_after_ms == 0 {
+            return Err("arbitration 'silent_after_ms' must be greater than 0".to_string());
+        }
+        if self.dedup_window == 0 {
+            return Err("arbitration 'dedup_window' must be greater than 0".to_string());
+        }
+        Ok(())
+    }
+}
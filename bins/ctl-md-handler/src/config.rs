@@ -4,21 +4,31 @@
 //! configuration defined in `configs/market-data/hw-resources.yaml`.
 
 use atx_handler::{HandlerConfig, HandlerWorkerConfig};
+use ctl_core::EalConfig;
+use ctl_feed::{validate_slot_size, OverflowPolicy, SymbolScale, MAX_EXPONENT, RAW_MESSAGE_SIZE};
+use ctl_websocket::{
+    has_update_speed, AddressPolicy, FailoverPolicy, ProbeConfig, ReconnectPolicy, UpdateSpeed,
+    DEFAULT_MAX_MESSAGE_SIZE,
+};
 use serde::Deserialize;
 use hashbrown::{HashMap, HashSet};
 use std::fs;
 use std::path::Path;
 use std::ops::RangeInclusive;
 
-use crate::{HwResourcesConfigError, SymbolInfoConfigError};
+use crate::{ArbitrationConfig, HwResourcesConfigError, RestartPolicy, StalenessPolicy, SymbolInfoConfigError};
 
 /// A protocol/parser combination for data transmission.
 #[derive(Debug, Clone, Deserialize, PartialEq, Eq, Hash)]
 pub struct Medium {
     /// Protocol type (e.g., "websocket").
     pub protocol: String,
-    /// Parser type (e.g., "json", "sbe", "fix").
+    /// Parser type: "json" or "sbe" over websocket, "fix" over FIX.
     pub parser: String,
+    /// Optional update speed qualifier for the streams (e.g., "100ms", "1000ms"),
+    /// only for the depth feed.
+    #[serde(default)]
+    pub update_speed: Option<UpdateSpeed>,
 }
 
 impl Medium {
@@ -34,6 +44,16 @@ impl Medium {
                 "Medium parser cannot be empty".to_string(),
             ));
         }
+        let supported = matches!(
+            (self.protocol.as_str(), self.parser.as_str()),
+            ("websocket", "json") | ("websocket", "sbe") | ("fix", "fix")
+        );
+        if !supported {
+            return Err(HwResourcesConfigError::ValidationError(format!(
+                "Medium '{}' is not supported, only 'websocket/json', 'websocket/sbe' and 'fix/fix' are",
+                self.name()
+            )));
+        }
         Ok(())
     }
 
@@ -56,6 +76,13 @@ pub struct SymbolSet {
     pub symbols: Vec<String>,
     /// List of protocol/parser mediums for this set.
     pub medium: Vec<Medium>,
+    /// Producer behavior when this set's rings are full.
+    #[serde(default)]
+    pub overflow: OverflowPolicy,
+    /// Whether the symbols of this set share a single ring, demultiplexed by the
+    /// symbol ID of the message headers, instead of one ring per symbol.
+    #[serde(default)]
+    pub aggregate: bool,
 }
 
 impl SymbolSet {
@@ -129,10 +156,99 @@ impl SymbolSet {
             }
         }
 
+        // Validate overflow policy
+        self.overflow.validate().map_err(|e| {
+            HwResourcesConfigError::ValidationError(format!("Set '{}': {}", self.name, e))
+        })?;
+
         Ok(())
     }
 }
 
+/// A view of the symbols of a feed that share the same CPUs, rings, and mediums.
+///
+/// This is either one of the feed's named sets, or the feed's direct configuration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FeedSet<'a> {
+    /// Feed kind (e.g., "top", "trade").
+    pub kind: &'a str,
+    /// Symbol set name, `None` for feeds using direct configuration.
+    pub set: Option<&'a str>,
+    /// Number of CPU cores to use.
+    pub num_cpus: u32,
+    /// Ring buffer size.
+    pub ring_size: u32,
+    /// Payload bytes of the messages of the rings.
+    pub slot_size: usize,
+    /// Maximum bytes of a websocket message reassembled from its frames.
+    pub max_message_size: usize,
+    /// List of symbols.
+    pub symbols: &'a [String],
+    /// List of protocol/parser mediums.
+    pub medium: &'a [Medium],
+    /// Producer behavior when the rings are full.
+    pub overflow: OverflowPolicy,
+    /// Websocket endpoints of the feed, primary first (empty for the default endpoint).
+    pub endpoints: &'a [String],
+    /// FIX market data endpoints of the feed, primary first (empty for the default endpoint).
+    pub fix_endpoints: &'a [String],
+    /// SBE websocket endpoints of the feed, primary first (empty for the default endpoint).
+    pub sbe_endpoints: &'a [String],
+    /// When the feed switches to its next endpoint.
+    pub failover: FailoverPolicy,
+    /// When the feed reconnects to its endpoint after a failure.
+    pub reconnect: ReconnectPolicy,
+    /// How the feed picks the address of the host of its endpoint.
+    pub addresses: AddressPolicy,
+    /// When the streams of the feed are stale.
+    pub staleness: &'a StalenessPolicy,
+    /// Whether the symbols share a single ring, named after the first symbol.
+    pub aggregate: bool,
+}
+
+impl FeedSet<'_> {
+    /// Returns the name of the set, `{kind}/{set}` or `{kind}` for direct configuration.
+    pub fn name(&self) -> String {
+        match self.set {
+            Some(set) => format!("{}/{}", self.kind, set),
+            None => self.kind.to_string(),
+        }
+    }
+
+    /// Returns the name of the FeedGroup running a medium of the set.
+    ///
+    /// This is the set name, suffixed with `@{protocol}/{parser}` when the set
+    /// fans out to multiple mediums.
+    pub fn group_name(&self, medium: &Medium) -> String {
+        if self.medium.len() > 1 {
+            format!("{}@{}", self.name(), medium.name())
+        } else {
+            self.name()
+        }
+    }
+
+    /// Returns the symbols owning a ring, which is named after them: the first
+    /// symbol of an aggregated set, every symbol otherwise.
+    pub fn ring_symbols(&self) -> &[String] {
+        if self.aggregate {
+            &self.symbols[..self.symbols.len().min(1)]
+        } else {
+            self.symbols
+        }
+    }
+
+    /// Returns the symbol owning the ring carrying the messages of a symbol,
+    /// `None` if the symbol is not in the set.
+    pub fn ring_symbol(&self, symbol: &str) -> Option<&str> {
+        let symbol = self.symbols.iter().find(|s| *s == symbol)?;
+        if self.aggregate {
+            self.symbols.first().map(String::as_str)
+        } else {
+            Some(symbol)
+        }
+    }
+}
+
 /// Configuration for a single feed.
 ///
 /// A feed can either have:
@@ -152,9 +268,51 @@ pub struct FeedConfig {
     /// List of protocol/parser mediums (used when not using sets).
     #[serde(default)]
     pub medium: Vec<Medium>,
+    /// Producer behavior when the rings are full (used when not using sets).
+    #[serde(default)]
+    pub overflow: Option<OverflowPolicy>,
     /// Optional named symbol sets with individual configurations.
     #[serde(default)]
     pub sets: Vec<SymbolSet>,
+    /// Websocket endpoints of the feed, primary first, shared by all its sets.
+    /// The default endpoint is used when empty.
+    #[serde(default)]
+    pub endpoints: Vec<String>,
+    /// FIX market data endpoints of the feed, primary first, shared by the sets
+    /// listing the `fix` protocol. The default endpoint is used when empty.
+    #[serde(default)]
+    pub fix_endpoints: Vec<String>,
+    /// SBE websocket endpoints of the feed, primary first, shared by the sets
+    /// listing the `sbe` parser. The default endpoint is used when empty.
+    /// Binance requires the API key in the `X-MBX-APIKEY` header of the SBE
+    /// handshakes, which the feeds don't send, e.g. listing a local proxy
+    /// adding it here.
+    #[serde(default)]
+    pub sbe_endpoints: Vec<String>,
+    /// When the feed switches to its next endpoint.
+    #[serde(default)]
+    pub failover: FailoverPolicy,
+    /// When the feed reconnects to its websocket endpoint after a failure,
+    /// until `failover.max_failures` consecutive failures switch endpoints.
+    #[serde(default)]
+    pub reconnect: ReconnectPolicy,
+    /// How the feed picks the address of the host of its websocket endpoint,
+    /// among all those it resolves to.
+    #[serde(default)]
+    pub addresses: AddressPolicy,
+    /// When the streams of the feed are stale, by symbol.
+    #[serde(default)]
+    pub staleness: StalenessPolicy,
+    /// Payload bytes of the messages of the rings of the feed, shared by all
+    /// its sets, e.g. small for the book tickers, the larger payloads being
+    /// fragmented. `RAW_MESSAGE_SIZE` when unset.
+    #[serde(default)]
+    pub slot_size: Option<usize>,
+    /// Maximum bytes of a websocket message of the feed reassembled from its
+    /// continuation frames, the larger ones being dropped, e.g. large for the
+    /// depth snapshots. `DEFAULT_MAX_MESSAGE_SIZE` when unset.
+    #[serde(default)]
+    pub max_message_size: Option<usize>,
 }
 
 impl FeedConfig {
@@ -172,13 +330,98 @@ impl FeedConfig {
             ));
         }
 
+        // Validate endpoints and failover policy, shared by sets and direct configuration
+        let mut seen_endpoints = HashSet::new();
+        for endpoint in &self.endpoints {
+            if !endpoint.starts_with("ws://") && !endpoint.starts_with("wss://") {
+                return Err(HwResourcesConfigError::ValidationError(format!(
+                    "Endpoint '{}' of feed '{}' must be a ws:// or wss:// URL",
+                    endpoint, self.kind
+                )));
+            }
+            if !seen_endpoints.insert(endpoint.as_str()) {
+                return Err(HwResourcesConfigError::ValidationError(format!(
+                    "Duplicate endpoint '{}' in feed '{}'",
+                    endpoint, self.kind
+                )));
+            }
+        }
+        let mut seen_fix_endpoints = HashSet::new();
+        for endpoint in &self.fix_endpoints {
+            if !endpoint.starts_with("tcp://") && !endpoint.starts_with("tls://") {
+                return Err(HwResourcesConfigError::ValidationError(format!(
+                    "FIX endpoint '{}' of feed '{}' must be a tcp:// or tls:// URL",
+                    endpoint, self.kind
+                )));
+            }
+            if !seen_fix_endpoints.insert(endpoint.as_str()) {
+                return Err(HwResourcesConfigError::ValidationError(format!(
+                    "Duplicate FIX endpoint '{}' in feed '{}'",
+                    endpoint, self.kind
+                )));
+            }
+        }
+        let mut seen_sbe_endpoints = HashSet::new();
+        for endpoint in &self.sbe_endpoints {
+            if !endpoint.starts_with("ws://") && !endpoint.starts_with("wss://") {
+                return Err(HwResourcesConfigError::ValidationError(format!(
+                    "SBE endpoint '{}' of feed '{}' must be a ws:// or wss:// URL",
+                    endpoint, self.kind
+                )));
+            }
+            if !seen_sbe_endpoints.insert(endpoint.as_str()) {
+                return Err(HwResourcesConfigError::ValidationError(format!(
+                    "Duplicate SBE endpoint '{}' in feed '{}'",
+                    endpoint, self.kind
+                )));
+            }
+        }
+        self.failover.validate().map_err(|e| {
+            HwResourcesConfigError::ValidationError(format!("Feed '{}': {}", self.kind, e))
+        })?;
+        self.reconnect.validate().map_err(|e| {
+            HwResourcesConfigError::ValidationError(format!("Feed '{}': {}", self.kind, e))
+        })?;
+        self.addresses.validate().map_err(|e| {
+            HwResourcesConfigError::ValidationError(format!("Feed '{}': {}", self.kind, e))
+        })?;
+        self.staleness.validate().map_err(|e| {
+            HwResourcesConfigError::ValidationError(format!("Feed '{}': {}", self.kind, e))
+        })?;
+        if self.max_message_size == Some(0) {
+            return Err(HwResourcesConfigError::ValidationError(format!(
+                "Feed '{}': 'max_message_size' must be greater than 0",
+                self.kind
+            )));
+        }
+        if let Some(slot_size) = self.slot_size {
+            validate_slot_size(slot_size).map_err(|e| {
+                HwResourcesConfigError::ValidationError(format!("Feed '{}': {}", self.kind, e))
+            })?;
+        }
+        // Only the depth streams have a speed variant, the others are refused once qualified
+        let mut mediums = self.sets.iter().flat_map(|set| &set.medium).chain(&self.medium);
+        if !has_update_speed(&self.kind) && mediums.any(|medium| medium.update_speed.is_some()) {
+            return Err(HwResourcesConfigError::ValidationError(format!(
+                "Feed '{}' has no update speed variant, only the depth feed has one",
+                self.kind
+            )));
+        }
+        let symbols = self.all_symbols();
+        if let Some(symbol) = self.staleness.symbols.keys().find(|symbol| !symbols.contains(&symbol.as_str())) {
+            return Err(HwResourcesConfigError::ValidationError(format!(
+                "Staleness threshold of symbol '{}' not in feed '{}'",
+                symbol, self.kind
+            )));
+        }
+
         // Check if using sets or direct configuration
         let has_sets = !self.sets.is_empty();
-        let has_direct = self.num_cpus.is_some() || self.ring_size.is_some() || !self.symbols.is_empty() || !self.medium.is_empty();
+        let has_direct = self.num_cpus.is_some() || self.ring_size.is_some() || !self.symbols.is_empty() || !self.medium.is_empty() || self.overflow.is_some();
 
         if has_sets && has_direct {
             return Err(HwResourcesConfigError::ValidationError(format!(
-                "Feed '{}' cannot have both 'sets' and direct configuration (num_cpus/ring_size/symbols/medium)",
+                "Feed '{}' cannot have both 'sets' and direct configuration (num_cpus/ring_size/symbols/medium/overflow)",
                 self.kind
             )));
         }
@@ -289,6 +532,13 @@ impl FeedConfig {
                     )));
                 }
             }
+
+            // Validate overflow policy
+            if let Some(overflow) = &self.overflow {
+                overflow.validate().map_err(|e| {
+                    HwResourcesConfigError::ValidationError(format!("Feed '{}': {}", self.kind, e))
+                })?;
+            }
         }
 
         Ok(())
@@ -306,11 +556,83 @@ impl FeedConfig {
         }
     }
 
+    /// Returns the symbol sets of this feed, or its direct configuration as a single set.
+    pub fn feed_sets(&self) -> Vec<FeedSet<'_>> {
+        if !self.sets.is_empty() {
+            self.sets
+                .iter()
+                .map(|set| FeedSet {
+                    kind: &self.kind,
+                    set: Some(&set.name),
+                    num_cpus: set.num_cpus,
+                    ring_size: set.ring_size,
+                    slot_size: self.slot_size(),
+                    max_message_size: self.max_message_size(),
+                    symbols: &set.symbols,
+                    medium: &set.medium,
+                    overflow: set.overflow,
+                    endpoints: &self.endpoints,
+                    fix_endpoints: &self.fix_endpoints,
+                    sbe_endpoints: &self.sbe_endpoints,
+                    failover: self.failover,
+                    reconnect: self.reconnect,
+                    addresses: self.addresses,
+                    staleness: &self.staleness,
+                    aggregate: set.aggregate,
+                })
+                .collect()
+        } else {
+            vec![FeedSet {
+                kind: &self.kind,
+                set: None,
+                num_cpus: self.num_cpus.unwrap_or_default(),
+                ring_size: self.ring_size.unwrap_or_default(),
+                slot_size: self.slot_size(),
+                max_message_size: self.max_message_size(),
+                symbols: &self.symbols,
+                medium: &self.medium,
+                overflow: self.overflow.unwrap_or_default(),
+                endpoints: &self.endpoints,
+                fix_endpoints: &self.fix_endpoints,
+                sbe_endpoints: &self.sbe_endpoints,
+                failover: self.failover,
+                reconnect: self.reconnect,
+                addresses: self.addresses,
+                staleness: &self.staleness,
+                aggregate: false,
+            }]
+        }
+    }
+
+    /// Returns the payload bytes of the messages of the rings of the feed.
+    pub fn slot_size(&self) -> usize {
+        self.slot_size.unwrap_or(RAW_MESSAGE_SIZE)
+    }
+
+    /// Returns the maximum bytes of a websocket message of the feed.
+    pub fn max_message_size(&self) -> usize {
+        self.max_message_size.unwrap_or(DEFAULT_MAX_MESSAGE_SIZE)
+    }
+
     /// Returns whether this feed uses symbol sets.
     pub fn uses_sets(&self) -> bool {
         !self.sets.is_empty()
     }
 
+    /// Returns the overflow policy of the ring for a symbol, if the symbol is in this feed.
+    pub fn overflow_policy(&self, symbol: &str) -> Option<OverflowPolicy> {
+        if !self.sets.is_empty() {
+            self.sets
+                .iter()
+                .find(|set| set.symbols.iter().any(|s| s == symbol))
+                .map(|set| set.overflow)
+        } else if self.symbols.iter().any(|s| s == symbol) {
+            Some(self.overflow.unwrap_or_default())
+        } else {
+            None
+        }
+    }
+
     /// Returns all mediums across all sets or direct medium list.
     pub fn all_mediums(&self) -> Vec<&Medium> {
         if !self.sets.is_empty() {
@@ -381,6 +703,21 @@ enum ConfigItem {
     PubSubs {
         pubsubs: Vec<FeedWrapper>,
     },
+    Eal {
+        eal: EalConfig,
+    },
+    Restart {
+        restart: RestartPolicy,
+    },
+    Arbitration {
+        arbitration: ArbitrationConfig,
+    },
+    Probe {
+        probe: ProbeConfig,
+    },
+    Extends {
+        extends: String,
+    },
 }
 
 /// The root hardware resources configuration.
@@ -394,11 +731,23 @@ pub struct HwResourcesConfig {
     pub worker_cpus: RangeInclusive<u32>,
     /// List of pub/sub configurations.
     pub pubsub_configs: Vec<PubSubConfig>,
+    /// EAL options of the handler, also used by the other secondary processes
+    /// to attach to the same controller instance.
+    pub eal: EalConfig,
+    /// When the workers of an exited FeedGroup are restarted.
+    pub restart: RestartPolicy,
+    /// The arbitration of the A/B lines of the feeds, `None` for a single line.
+    pub arbitration: Option<ArbitrationConfig>,
+    /// The latency probing of the candidate endpoints, `None` if they aren't probed.
+    pub probe: Option<ProbeConfig>,
 }
 
 impl HwResourcesConfig {
     /// Parses the hardware resources configuration from a YAML file.
     ///
+    /// A file with an `extends` item is an overlay, merged onto its base file
+    /// (see the `overlay` module).
+    ///
     /// # Arguments
     /// * `path` - Path to the YAML configuration file.
     ///
@@ -406,10 +755,11 @@ impl HwResourcesConfig {
     /// A validated `HwResourcesConfig` instance.
     ///
     /// # Errors
-    /// Returns an error if the file cannot be read, parsed, or fails validation.
+    /// Returns an error if a file cannot be read or parsed, or the merged
+    /// configuration fails validation.
     pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, HwResourcesConfigError> {
-        let content = fs::read_to_string(path)?;
-        Self::from_str(&content)
+        let items = overlay::load(path.as_ref())?;
+        Self::from_items(serde_yaml::from_value(items)?)
     }
 
     /// Parses the hardware resources configuration from a YAML string.
@@ -423,11 +773,18 @@ impl HwResourcesConfig {
     /// # Errors
     /// Returns an error if the YAML cannot be parsed or fails validation.
     pub fn from_str(content: &str) -> Result<Self, HwResourcesConfigError> {
-        let items: Vec<ConfigItem> = serde_yaml::from_str(content)?;
-        
+        Self::from_items(serde_yaml::from_str(content)?)
+    }
+
+    /// Builds the configuration from its items, validating it.
+    fn from_items(items: Vec<ConfigItem>) -> Result<Self, HwResourcesConfigError> {
         let mut main_cpu: Option<u32> = None;
         let mut worker_cpus: Option<String> = None;
         let mut pubsub_configs: Vec<PubSubConfig> = Vec::new();
+        let mut eal: Option<EalConfig> = None;
+        let mut restart: Option<RestartPolicy> = None;
+        let mut arbitration: Option<ArbitrationConfig> = None;
+        let mut probe: Option<ProbeConfig> = None;
 
         for item in items {
             match item {
@@ -450,6 +807,44 @@ impl HwResourcesConfig {
                 ConfigItem::PubSubs { pubsubs } => {
                     pubsub_configs.push(PubSubConfig { pubsubs });
                 }
+                ConfigItem::Eal { eal: options } => {
+                    if eal.is_some() {
+                        return Err(HwResourcesConfigError::ValidationError(
+                            "Duplicate 'eal' configuration".to_string(),
+                        ));
+                    }
+                    eal = Some(options);
+                }
+                ConfigItem::Restart { restart: policy } => {
+                    if restart.is_some() {
+                        return Err(HwResourcesConfigError::ValidationError(
+                            "Duplicate 'restart' configuration".to_string(),
+                        ));
+                    }
+                    restart = Some(policy);
+                }
+                ConfigItem::Arbitration { arbitration: config } => {
+                    if arbitration.is_some() {
+                        return Err(HwResourcesConfigError::ValidationError(
+                            "Duplicate 'arbitration' configuration".to_string(),
+                        ));
+                    }
+                    arbitration = Some(config);
+                }
+                ConfigItem::Probe { probe: config } => {
+                    if probe.is_some() {
+                        return Err(HwResourcesConfigError::ValidationError(
+                            "Duplicate 'probe' configuration".to_string(),
+                        ));
+                    }
+                    probe = Some(config);
+                }
+                ConfigItem::Extends { .. } => {
+                    return Err(HwResourcesConfigError::ValidationError(format!(
+                        "'{}' is only supported when loading from a file",
+                        overlay::EXTENDS_KEY
+                    )));
+                }
             }
         }
 
@@ -471,6 +866,10 @@ impl HwResourcesConfig {
             main_cpu,
             worker_cpus,
             pubsub_configs,
+            eal: eal.unwrap_or_default(),
+            restart: restart.unwrap_or_default(),
+            arbitration,
+            probe,
         };
         config.validate()?;
         Ok(config)
@@ -522,6 +921,29 @@ impl HwResourcesConfig {
             pubsub.validate()?;
         }
 
+        self.eal.validate().map_err(HwResourcesConfigError::ValidationError)?;
+        self.restart.validate().map_err(HwResourcesConfigError::ValidationError)?;
+
+        // The arbiter tells the copies of an event apart by the IDs of their JSON payloads, translated from FIX and SBE
+        if let Some(arbitration) = &self.arbitration {
+            arbitration.validate().map_err(HwResourcesConfigError::ValidationError)?;
+            for feed in self.all_feeds() {
+                let unkeyed =
+                    feed.all_mediums().into_iter().find(|m| !matches!(m.parser.as_str(), "json" | "fix" | "sbe"));
+                if let Some(medium) = unkeyed {
+                    return Err(HwResourcesConfigError::ValidationError(format!(
+                        "Feed '{}' medium '{}' can't be arbitrated, only the json, fix and sbe parsers are",
+                        feed.kind,
+                        medium.name()
+                    )));
+                }
+            }
+        }
+
+        if let Some(probe) = &self.probe {
+            probe.validate().map_err(HwResourcesConfigError::ValidationError)?;
+        }
+
         Ok(())
     }
 
@@ -543,6 +965,24 @@ impl HwResourcesConfig {
             .flat_map(|f| f.all_symbols())
             .collect()
     }
+
+    /// Sets the websocket endpoint of the feeds without configured endpoints,
+    /// followed by the other candidate endpoints when they are probed.
+    pub fn set_default_endpoint(&mut self, endpoint: &str) {
+        let mut defaults = vec![endpoint.to_string()];
+        if let Some(probe) = &self.probe {
+            defaults.extend(probe.endpoints.iter().filter(|candidate| *candidate != endpoint).cloned());
+        }
+        let feeds = self
+            .pubsub_configs
+            .iter_mut()
+            .flat_map(|ps| ps.pubsubs.iter_mut().map(|fw| &mut fw.feed));
+        for feed in feeds {
+            if feed.endpoints.is_empty() {
+                feed.endpoints = defaults.clone();
+            }
+        }
+    }
 }
 
 impl HandlerConfig for HwResourcesConfig {
@@ -583,12 +1023,20 @@ pub struct SymbolInfo {
     pub name: String,
     /// Unique numeric ID for the symbol.
     pub id: u32,
+    /// The tick and step exponents the prices and quantities are normalized to, if given.
+    pub scale: Option<SymbolScale>,
 }
 
 /// Helper struct for YAML parsing (matches the YAML format).
 #[derive(Debug, Clone, Deserialize)]
 struct SymbolInfoEntry {
     id: u32,
+    /// The number of decimals of the tick size, e.g. 2 for 0.01.
+    #[serde(default)]
+    tick_exponent: Option<u8>,
+    /// The number of decimals of the step size, e.g. 5 for 0.00001.
+    #[serde(default)]
+    step_exponent: Option<u8>,
 }
 
 /// Configuration holding all symbol information.
@@ -638,9 +1086,19 @@ impl SymbolInfoConfig {
 
         for entry in entries {
             for (name, info) in entry {
+                let scale = match (info.tick_exponent, info.step_exponent) {
+                    (Some(tick_exponent), Some(step_exponent))
+                        if tick_exponent <= MAX_EXPONENT && step_exponent <= MAX_EXPONENT =>
+                    {
+                        Some(SymbolScale { tick_exponent, step_exponent })
+                    }
+                    (None, None) => None,
+                    _ => return Err(SymbolInfoConfigError::InvalidScale(name)),
+                };
                 let symbol_info = SymbolInfo {
                     name: name.clone(),
                     id: info.id,
+                    scale,
                 };
 
                 // Check for duplicate IDs
@@ -679,6 +1137,11 @@ impl SymbolInfoConfig {
         self.symbols_by_name.get(name).map(|s| s.id)
     }
 
+    /// Get the tick and step exponents of a symbol by name, if given.
+    pub fn scale(&self, name: &str) -> Option<SymbolScale> {
+        self.symbols_by_name.get(name).and_then(|s| s.scale)
+    }
+
     /// Iterator over all symbols.
     pub fn symbols(&self) -> impl Iterator<Item = &SymbolInfo> {
         self.symbols_by_name.values()
@@ -716,8 +1179,8 @@ mod tests {
             medium:
               - protocol: websocket
                 parser: json
-              - protocol: websocket
-                parser: sbe
+              - protocol: fix
+                parser: fix
           - name: B
             num_cpus: 4
             ring_size: 65536
@@ -764,6 +1227,73 @@ mod tests {
         assert_eq!(trade_feed.medium.len(), 1);
     }
 
+    #[test]
+    fn test_parse_eal_options() {
+        let config = HwResourcesConfig::from_str(VALID_CONFIG).unwrap();
+        assert_eq!(config.eal, EalConfig::default());
+
+        let content = format!("- eal:\n    file_prefix: ctl-b\n    no_telemetry: true\n{}", VALID_CONFIG);
+        let config = HwResourcesConfig::from_str(&content).unwrap();
+        assert_eq!(config.eal.file_prefix(), "ctl-b");
+        assert_eq!(config.eal.secondary_args(), vec!["--file-prefix=ctl-b", "--no-telemetry"]);
+
+        let content = format!("- eal:\n    extra_args: [--proc-type=primary]\n{}", VALID_CONFIG);
+        assert!(HwResourcesConfig::from_str(&content).is_err());
+    }
+
+    #[test]
+    fn test_parse_restart_policy() {
+        let config = HwResourcesConfig::from_str(VALID_CONFIG).unwrap();
+        assert_eq!(config.restart, RestartPolicy::default());
+
+        let content = format!("- restart:\n    max_retries: 3\n    initial_backoff_ms: 200\n{}", VALID_CONFIG);
+        let config = HwResourcesConfig::from_str(&content).unwrap();
+        assert_eq!(config.restart.max_retries, 3);
+        assert_eq!(config.restart.initial_backoff_ms, 200);
+        assert_eq!(config.restart.max_backoff_ms, RestartPolicy::default().max_backoff_ms);
+
+        let content = format!("- restart:\n    initial_backoff_ms: 0\n{}", VALID_CONFIG);
+        assert!(HwResourcesConfig::from_str(&content).is_err());
+    }
+
+    #[test]
+    fn test_parse_arbitration() {
+        let config = HwResourcesConfig::from_str(VALID_CONFIG).unwrap();
+        assert_eq!(config.arbitration, None);
+
+        let feeds = r#"
+- main_cpu: 0
+- worker_cpus: 1-2
+- pubsubs:
+    - feed:
+        kind: trade
+        num_cpus: 2
+        ring_size: 1024
+        symbols:
+          - BTCUSDT
+        medium:
+          - protocol: websocket
+            parser: json
+          - protocol: fix
+            parser: fix
+"#;
+        let content = format!("- arbitration:\n    silent_after_ms: 2000\n{}", feeds);
+        let config = HwResourcesConfig::from_str(&content).unwrap();
+        assert_eq!(config.arbitration, Some(ArbitrationConfig { silent_after_ms: 2000, ..Default::default() }));
+        let content = format!("- arbitration: {{}}\n{}", feeds);
+        assert_eq!(HwResourcesConfig::from_str(&content).unwrap().arbitration, Some(ArbitrationConfig::default()));
+
+        // The SBE parser runs over websocket only
+        let sbe = feeds.replace("protocol: fix\n            parser: fix", "protocol: websocket\n            parser: sbe");
+        assert!(HwResourcesConfig::from_str(&format!("- arbitration: {{}}\n{}", sbe)).is_ok());
+        let content = format!("- arbitration: {{}}\n{}", VALID_CONFIG.replace("parser: fix", "parser: sbe"));
+        assert!(HwResourcesConfig::from_str(&content).unwrap_err().to_string().contains("'fix/sbe' is not supported"));
+        let content = format!("- arbitration:\n    silent_after_ms: 0\n{}", feeds);
+        assert!(HwResourcesConfig::from_str(&content).is_err());
+        let content = format!("- arbitration: {{}}\n- arbitration: {{}}\n{}", feeds);
+        assert!(HwResourcesConfig::from_str(&content).is_err());
+    }
+
     #[test]
     fn test_all_symbols() {
         let config = HwResourcesConfig::from_str(VALID_CONFIG).expect("Failed to parse config");
@@ -777,6 +1307,31 @@ mod tests {
         assert!(symbols.contains("DOTUSDT"));
     }
 
+    #[test]
+    fn test_feed_sets() {
+        let config = HwResourcesConfig::from_str(VALID_CONFIG).expect("Failed to parse config");
+
+        let top_feed = config.find_feed("top").expect("top feed not found");
+        let sets = top_feed.feed_sets();
+        assert_eq!(sets.len(), 2);
+        assert_eq!(sets[0].name(), "top/A");
+        assert_eq!(sets[0].symbols, &["BTCUSDT", "ETHUSDT", "SOLUSDT"]);
+        assert_eq!(sets[1].name(), "top/B");
+        assert_eq!(sets[1].medium.len(), 1);
+
+        // Set A fans out to two mediums, set B runs a single one
+        assert_eq!(sets[0].group_name(&sets[0].medium[0]), "top/A@websocket/json");
+        assert_eq!(sets[0].group_name(&sets[0].medium[1]), "top/A@fix/fix");
+        assert_eq!(sets[1].group_name(&sets[1].medium[0]), "top/B");
+
+        let trade_feed = config.find_feed("trade").expect("trade feed not found");
+        let sets = trade_feed.feed_sets();
+        assert_eq!(sets.len(), 1);
+        assert_eq!(sets[0].name(), "trade");
+        assert_eq!(sets[0].num_cpus, 4);
+        assert_eq!(sets[0].ring_size, 65536);
+    }
+
     #[test]
     fn test_all_mediums() {
         let config = HwResourcesConfig::from_str(VALID_CONFIG).expect("Failed to parse config");
@@ -813,6 +1368,60 @@ mod tests {
         assert!(result.unwrap_err().to_string().contains("power of 2"));
     }
 
+    #[test]
+    fn test_slot_size() {
+        let config_str = |slot_size: &str| format!(r#"
+- main_cpu: 0
+- worker_cpus: 1-4
+- pubsubs:
+    - feed:
+        kind: top
+        num_cpus: 1
+        ring_size: 1024
+        {slot_size}
+        symbols:
+          - BTCUSDT
+        medium:
+          - protocol: websocket
+            parser: json
+"#);
+        let config = HwResourcesConfig::from_str(&config_str("slot_size: 128")).unwrap();
+        assert_eq!(config.find_feed("top").unwrap().feed_sets()[0].slot_size, 128);
+        let config = HwResourcesConfig::from_str(&config_str("")).unwrap();
+        assert_eq!(config.find_feed("top").unwrap().slot_size(), RAW_MESSAGE_SIZE);
+
+        for slot_size in ["slot_size: 32", "slot_size: 100", "slot_size: 4096"] {
+            let result = HwResourcesConfig::from_str(&config_str(slot_size));
+            assert!(result.unwrap_err().to_string().contains("slot size"));
+        }
+    }
+
+    #[test]
+    fn test_max_message_size() {
+        let config_str = |max_message_size: &str| format!(r#"
+- main_cpu: 0
+- worker_cpus: 1-4
+- pubsubs:
+    - feed:
+        kind: top
+        num_cpus: 1
+        ring_size: 1024
+        {max_message_size}
+        symbols:
+          - BTCUSDT
+        medium:
+          - protocol: websocket
+            parser: json
+"#);
+        let config = HwResourcesConfig::from_str(&config_str("max_message_size: 4194304")).unwrap();
+        assert_eq!(config.find_feed("top").unwrap().feed_sets()[0].max_message_size, 4 << 20);
+        let config = HwResourcesConfig::from_str(&config_str("")).unwrap();
+        assert_eq!(config.find_feed("top").unwrap().max_message_size(), DEFAULT_MAX_MESSAGE_SIZE);
+
+        let result = HwResourcesConfig::from_str(&config_str("max_message_size: 0"));
+        assert!(result.unwrap_err().to_string().contains("max_message_size"));
+    }
+
     #[test]
     fn test_empty_kind() {
         let config_str = r#"
@@ -1064,4 +1673,368 @@ mod tests {
         assert!(result.is_err());
         assert!(result.unwrap_err().to_string().contains("Duplicate medium"));
     }
+
+    #[test]
+    fn test_medium_update_speed() {
+        let config_str = r#"
+- main_cpu: 0
+- worker_cpus: 1-4
+- pubsubs:
+    - feed:
+        kind: depth
+        num_cpus: 1
+        ring_size: 1024
+        symbols:
+          - TEST
+        medium:
+          - protocol: websocket
+            parser: json
+            update_speed: 100ms
+"#;
+        let config = HwResourcesConfig::from_str(config_str).expect("Failed to parse config");
+        let feed = config.find_feed("depth").expect("depth feed not found");
+        assert_eq!(feed.medium[0].update_speed, Some(UpdateSpeed::Ms100));
+
+        // A feed without a speed variant refuses one
+        let result = HwResourcesConfig::from_str(&config_str.replace("kind: depth", "kind: top"));
+        assert!(result.unwrap_err().to_string().contains("Feed 'top' has no update speed variant"));
+
+        let config = HwResourcesConfig::from_str(VALID_CONFIG).expect("Failed to parse config");
+        let trade_feed = config.find_feed("trade").expect("trade feed not found");
+        assert_eq!(trade_feed.medium[0].update_speed, None);
+    }
+
+    #[test]
+    fn test_invalid_medium_update_speed() {
+        let config_str = r#"
+- main_cpu: 0
+- worker_cpus: 1-4
+- pubsubs:
+    - feed:
+        kind: depth
+        num_cpus: 1
+        ring_size: 1024
+        symbols:
+          - TEST
+        medium:
+          - protocol: websocket
+            parser: json
+            update_speed: 250ms
+"#;
+        let result = HwResourcesConfig::from_str(config_str);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_overflow_policy() {
+        let config_str = r#"
+- main_cpu: 0
+- worker_cpus: 1-4
+- pubsubs:
+    - feed:
+        kind: top
+        sets:
+          - name: A
+            num_cpus: 1
+            ring_size: 1024
+            symbols:
+              - TEST1
+            medium:
+              - protocol: websocket
+                parser: json
+            overflow:
+              policy: block-with-timeout
+              timeout_us: 500
+          - name: B
+            num_cpus: 1
+            ring_size: 1024
+            symbols:
+              - TEST2
+            medium:
+              - protocol: websocket
+                parser: json
+    - feed:
+        kind: trade
+        num_cpus: 1
+        ring_size: 1024
+        symbols:
+          - TEST1
+        medium:
+          - protocol: websocket
+            parser: json
+        overflow:
+          policy: drop-newest
+"#;
+        let config = HwResourcesConfig::from_str(config_str).expect("Failed to parse config");
+        let top_feed = config.find_feed("top").expect("top feed not found");
+        assert_eq!(
+            top_feed.overflow_policy("TEST1"),
+            Some(OverflowPolicy::BlockWithTimeout { timeout_us: 500 })
+        );
+        assert_eq!(top_feed.overflow_policy("TEST2"), Some(OverflowPolicy::OverwriteOldest));
+        assert_eq!(top_feed.overflow_policy("UNKNOWN"), None);
+
+        let trade_feed = config.find_feed("trade").expect("trade feed not found");
+        assert_eq!(trade_feed.overflow_policy("TEST1"), Some(OverflowPolicy::DropNewest));
+    }
+
+    #[test]
+    fn test_zero_overflow_timeout() {
+        let config_str = r#"
+- main_cpu: 0
+- worker_cpus: 1-4
+- pubsubs:
+    - feed:
+        kind: test
+        num_cpus: 1
+        ring_size: 1024
+        symbols:
+          - TEST
+        medium:
+          - protocol: websocket
+            parser: json
+        overflow:
+          policy: block-with-timeout
+          timeout_us: 0
+"#;
+        let result = HwResourcesConfig::from_str(config_str);
+        assert!(result.is_err());
+        assert!(result.unwrap_err().to_string().contains("timeout_us"));
+    }
+
+    #[test]
+    fn test_feed_endpoints() {
+        let config_str = r#"
+- main_cpu: 0
+- worker_cpus: 1-4
+- pubsubs:
+    - feed:
+        kind: test
+        endpoints:
+          - wss://stream.binance.com:9443/ws
+          - wss://stream.binance.com:443/ws
+        failover:
+          max_failures: 5
+        reconnect:
+          initial_backoff_ms: 1000
+          max_backoff_ms: 60000
+        addresses:
+          max_connect_ms: 500
+        sets:
+          - name: A
+            num_cpus: 1
+            ring_size: 1024
+            symbols:
+              - TEST
+            medium:
+              - protocol: websocket
+                parser: json
+"#;
+        let config = HwResourcesConfig::from_str(config_str).expect("Failed to parse config");
+        let feed = config.find_feed("test").expect("test feed not found");
+        let sets = feed.feed_sets();
+        assert_eq!(sets[0].endpoints.len(), 2);
+        assert_eq!(sets[0].failover.max_failures, 5);
+        assert_eq!(sets[0].failover.stale_after_ms, FailoverPolicy::default().stale_after_ms);
+        assert_eq!(
+            sets[0].reconnect,
+            ReconnectPolicy { initial_backoff_ms: 1_000, max_backoff_ms: 60_000, ..ReconnectPolicy::default() }
+        );
+        assert_eq!(sets[0].addresses, AddressPolicy { max_connect_ms: 500, ..AddressPolicy::default() });
+        assert_eq!(sets[0].staleness, &StalenessPolicy::default());
+
+        let invalid = config_str.replace("wss://stream.binance.com:443/ws", "stream.binance.com:443");
+        let result = HwResourcesConfig::from_str(&invalid);
+        assert!(result.unwrap_err().to_string().contains("ws:// or wss://"));
+
+        let zero_failures = config_str.replace("max_failures: 5", "max_failures: 0");
+        assert!(HwResourcesConfig::from_str(&zero_failures).is_err());
+        let inverted_backoff = config_str.replace("max_backoff_ms: 60000", "max_backoff_ms: 500");
+        assert!(HwResourcesConfig::from_str(&inverted_backoff).unwrap_err().to_string().contains("reconnect policy"));
+
+        // The default endpoint only applies to the feeds without endpoints
+        let mut config = config;
+        config.set_default_endpoint("wss://testnet.binance.vision/ws");
+        assert_eq!(config.find_feed("test").unwrap().endpoints.len(), 2);
+
+        let default_str = config_str
+            .replace("          - wss://stream.binance.com:9443/ws\n", "")
+            .replace("          - wss://stream.binance.com:443/ws\n", "")
+            .replace("        endpoints:\n", "");
+        let mut config = HwResourcesConfig::from_str(&default_str).expect("Failed to parse config");
+        assert!(config.find_feed("test").unwrap().endpoints.is_empty());
+        config.set_default_endpoint("wss://testnet.binance.vision/ws");
+        assert_eq!(config.find_feed("test").unwrap().endpoints, vec!["wss://testnet.binance.vision/ws".to_string()]);
+
+        // Followed by the other candidates when probed
+        let probed_str = format!("- probe:\n    interval_ms: 30000\n{}", default_str);
+        let mut config = HwResourcesConfig::from_str(&probed_str).expect("Failed to parse config");
+        assert_eq!(config.probe, Some(ProbeConfig { interval_ms: 30_000, ..ProbeConfig::default() }));
+        config.set_default_endpoint("wss://stream.binance.com:443/ws");
+        assert_eq!(config.find_feed("test").unwrap().endpoints, vec![
+            "wss://stream.binance.com:443/ws".to_string(),
+            "wss://stream.binance.com:9443/ws".to_string(),
+            "wss://data-stream.binance.vision/ws".to_string(),
+        ]);
+
+        let invalid_str = format!("- probe:\n    timeout_ms: 0\n{}", default_str);
+        assert!(HwResourcesConfig::from_str(&invalid_str).is_err());
+        let duplicate_str = format!("- probe: {{}}\n- probe: {{}}\n{}", default_str);
+        assert!(HwResourcesConfig::from_str(&duplicate_str).is_err());
+    }
+
+    #[test]
+    fn test_feed_staleness() {
+        let config_str = r#"
+- main_cpu: 0
+- worker_cpus: 1-4
+- pubsubs:
+    - feed:
+        kind: trade
+        staleness:
+          after_ms: 5000
+          symbols:
+            ILLIQUIDUSDT: 300000
+          action: failover
+        num_cpus: 1
+        ring_size: 1024
+        symbols:
+          - BTCUSDT
+          - ILLIQUIDUSDT
+        medium:
+          - protocol: websocket
+            parser: json
+"#;
+        let config = HwResourcesConfig::from_str(config_str).expect("Failed to parse config");
+        let sets = config.find_feed("trade").expect("trade feed not found").feed_sets();
+        assert_eq!(sets[0].staleness.after_ms("BTCUSDT"), 5_000);
+        assert_eq!(sets[0].staleness.after_ms("ILLIQUIDUSDT"), 300_000);
+        assert_eq!(sets[0].staleness.action, crate::StaleAction::Failover);
+
+        let unknown = config_str.replace("ILLIQUIDUSDT: 300000", "ETHUSDT: 300000");
+        let result = HwResourcesConfig::from_str(&unknown);
+        assert!(result.unwrap_err().to_string().contains("'ETHUSDT' not in feed"));
+    }
+
+    #[test]
+    fn test_aggregated_set() {
+        let config_str = r#"
+- main_cpu: 0
+- worker_cpus: 1-4
+- pubsubs:
+    - feed:
+        kind: trade
+        sets:
+          - name: liquid
+            num_cpus: 1
+            ring_size: 1024
+            symbols: [BTCUSDT, ETHUSDT]
+            medium:
+              - protocol: websocket
+                parser: json
+          - name: longtail
+            num_cpus: 1
+            ring_size: 4096
+            aggregate: true
+            symbols: [ADAUSDT, XRPUSDT, DOTUSDT]
+            medium:
+              - protocol: websocket
+                parser: json
+"#;
+        let config = HwResourcesConfig::from_str(config_str).expect("Failed to parse config");
+        let sets = config.find_feed("trade").expect("trade feed not found").feed_sets();
+        assert!(!sets[0].aggregate);
+        assert_eq!(sets[0].ring_symbols(), &["BTCUSDT", "ETHUSDT"]);
+        assert_eq!(sets[0].ring_symbol("ETHUSDT"), Some("ETHUSDT"));
+
+        assert!(sets[1].aggregate);
+        assert_eq!(sets[1].ring_symbols(), &["ADAUSDT"]);
+        assert_eq!(sets[1].ring_symbol("DOTUSDT"), Some("ADAUSDT"));
+        assert_eq!(sets[1].ring_symbol("BTCUSDT"), None);
+    }
+
+    #[test]
+    fn test_feed_fix_endpoints() {
+        let config_str = r#"
+- main_cpu: 0
+- worker_cpus: 1-4
+- pubsubs:
+    - feed:
+        kind: top
+        fix_endpoints:
+          - tls://fix-md.binance.com:9000
+        sets:
+          - name: A
+            num_cpus: 2
+            ring_size: 1024
+            symbols:
+              - BTCUSDT
+            medium:
+              - protocol: websocket
+                parser: json
+              - protocol: fix
+                parser: fix
+"#;
+        let config = HwResourcesConfig::from_str(config_str).expect("Failed to parse config");
+        let sets = config.find_feed("top").expect("top feed not found").feed_sets();
+        assert_eq!(sets[0].fix_endpoints, ["tls://fix-md.binance.com:9000".to_string()]);
+        assert!(sets[0].endpoints.is_empty());
+        assert_eq!(sets[0].group_name(&sets[0].medium[1]), "top/A@fix/fix");
+
+        let invalid = config_str.replace("tls://fix-md.binance.com:9000", "wss://fix-md.binance.com:9000");
+        let result = HwResourcesConfig::from_str(&invalid);
+        assert!(result.unwrap_err().to_string().contains("tcp:// or tls://"));
+
+        let duplicate = config_str.replace(
+            "          - tls://fix-md.binance.com:9000\n",
+            "          - tls://fix-md.binance.com:9000\n          - tls://fix-md.binance.com:9000\n",
+        );
+        let result = HwResourcesConfig::from_str(&duplicate);
+        assert!(result.unwrap_err().to_string().contains("Duplicate FIX endpoint"));
+    }
+
+    #[test]
+    fn test_feed_sbe_endpoints() {
+        let config_str = r#"
+- main_cpu: 0
+- worker_cpus: 1-4
+- pubsubs:
+    - feed:
+        kind: trade
+        sbe_endpoints:
+          - ws://127.0.0.1:9443/ws
+        num_cpus: 2
+        ring_size: 1024
+        symbols:
+          - BTCUSDT
+        medium:
+          - protocol: websocket
+            parser: json
+          - protocol: websocket
+            parser: sbe
+"#;
+        let config = HwResourcesConfig::from_str(config_str).expect("Failed to parse config");
+        let sets = config.find_feed("trade").expect("trade feed not found").feed_sets();
+        assert_eq!(sets[0].sbe_endpoints, ["ws://127.0.0.1:9443/ws".to_string()]);
+        assert!(sets[0].endpoints.is_empty());
+        assert_eq!(sets[0].group_name(&sets[0].medium[1]), "trade@websocket/sbe");
+
+        let invalid = config_str.replace("ws://127.0.0.1:9443/ws", "tls://127.0.0.1:9443");
+        let result = HwResourcesConfig::from_str(&invalid);
+        assert!(result.unwrap_err().to_string().contains("ws:// or wss://"));
+    }
+
+    #[test]
+    fn test_symbol_info_scales() {
+        let content = "- BTCUSDT:\n    id: 0\n    tick_exponent: 2\n    step_exponent: 5\n- ETHUSDT:\n    id: 1\n";
+        let symbol_info = SymbolInfoConfig::from_str(content).unwrap();
+        assert_eq!(symbol_info.scale("BTCUSDT"), Some(SymbolScale { tick_exponent: 2, step_exponent: 5 }));
+        assert_eq!(symbol_info.scale("ETHUSDT"), None);
+        assert_eq!(symbol_info.get_by_id(0).unwrap().scale, symbol_info.scale("BTCUSDT"));
+
+        let partial = "- BTCUSDT:\n    id: 0\n    tick_exponent: 2\n";
+        assert!(matches!(SymbolInfoConfig::from_str(partial), Err(SymbolInfoConfigError::InvalidScale(_))));
+        let too_fine = "- BTCUSDT:\n    id: 0\n    tick_exponent: 19\n    step_exponent: 5\n";
+        assert!(matches!(SymbolInfoConfig::from_str(too_fine), Err(SymbolInfoConfigError::InvalidScale(_))));
+    }
 }
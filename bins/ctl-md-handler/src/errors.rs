@@ -29,4 +29,23 @@ pub enum SymbolInfoConfigError {
     /// Duplicate symbol name found.
     #[error("Duplicate symbol name: {0}")]
     DuplicateName(String),
-}
\ No newline at end of file
+    /// A symbol gives only one of its exponents, or exceeds the largest exponent.
+    #[error("Invalid tick/step exponents for symbol {0}")]
+    InvalidScale(String),
+}
+/// Errors that can occur when planning the lcore allocation of the feed groups.
+#[derive(Debug, Error)]
+pub enum LcorePlanError {
+    /// The main CPU is also part of the worker CPUs.
+    #[error("Main CPU {0} overlaps with the worker CPUs")]
+    MainCpuInWorkers(u32),
+    /// A feed or set requests zero CPUs.
+    #[error("{0} must request at least one CPU")]
+    ZeroCpus(String),
+    /// A set requests fewer CPUs than it has mediums to fan out to.
+    #[error("{target} requests {num_cpus} CPUs but fans out to {mediums} mediums")]
+    TooFewCpusForMediums { target: String, num_cpus: u32, mediums: u32 },
+    /// The requested CPUs exceed the worker CPU budget.
+    #[error("Requested {required} worker CPUs but only {available} are configured in 'worker_cpus'")]
+    InsufficientCpus { required: u32, available: u32 },
+}
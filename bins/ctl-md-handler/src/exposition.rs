@@ -0,0 +1,255 @@
+//! Prometheus metrics of the FeedGroups.
+//!
+//! Besides the shared metrics region, read by the other components, the handler
+//! can expose its own counters on `/metrics` for a Prometheus scraper: the
+//! messages and message rate of each symbol, the reconnections and parse
+//! errors of each FeedGroup, and the frames, bytes, request round trip and
+//! time since the last data of its websocket connection, labeled by feed kind,
+//! symbol set and medium. With
+//! the endpoints probed, the latencies of the phases of the last probe of each
+//! endpoint, whether it succeeded and the rank of the endpoint, labeled by
+//! endpoint.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Instant;
+
+use ctl_core::{MetricType, PrometheusText};
+use ctl_feed::{MetricsRegion, ParseErrorCounter};
+use ctl_websocket::{ConnectionStats, ConnectionStatsHandle, EndpointProbe};
+
+/// The counters of a FeedGroup, kept across its restarts.
+#[derive(Debug, Clone)]
+pub struct GroupMetrics {
+    /// The feed kind, e.g. `top`.
+    pub feed: String,
+    /// The symbol set, empty for a feed configured without sets.
+    pub set: String,
+    /// The medium, e.g. `websocket/json`.
+    pub medium: String,
+    /// The streams, by symbol and stream name.
+    pub streams: Vec<(String, String)>,
+    /// The messages failed to parse, counted by the parser.
+    pub parse_errors: ParseErrorCounter,
+    /// The statistics of the websocket connection, `None` for a FIX session.
+    pub connection: Option<ConnectionStatsHandle>,
+    /// The endpoint switches of the feed, each reconnecting it.
+    failovers: Arc<AtomicU64>,
+    /// The restarts of the FeedGroup, each reconnecting its feed.
+    restarts: Arc<AtomicU64>,
+}
+
+impl GroupMetrics {
+    /// Creates the counters of a FeedGroup at zero.
+    pub fn new(feed: &str, set: Option<&str>, medium: &str, streams: Vec<(String, String)>) -> Self {
+        Self {
+            feed: feed.to_string(),
+            set: set.unwrap_or_default().to_string(),
+            medium: medium.to_string(),
+            streams,
+            parse_errors: ParseErrorCounter::new(),
+            connection: None,
+            failovers: Arc::default(),
+            restarts: Arc::default(),
+        }
+    }
+
+    /// Tracks the statistics of the websocket connection of the FeedGroup in `connection`.
+    pub fn with_connection(mut self, connection: ConnectionStatsHandle) -> Self {
+        self.connection = Some(connection);
+        self
+    }
+
+    /// Counts a switch of the feed to another endpoint.
+    pub fn record_failover(&self) {
+        self.failovers.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Counts a restart of the FeedGroup.
+    pub fn record_restart(&self) {
+        self.restarts.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Returns the labels of the FeedGroup.
+    fn labels(&self) -> [(&str, &str); 3] {
+        [("feed", self.feed.as_str()), ("set", self.set.as_str()), ("medium", self.medium.as_str())]
+    }
+}
+
+/// Renders the counters of the FeedGroups, the message counts and rates of
+/// their streams read from the metrics region, and the last probes of the
+/// endpoints, the best-ranked first.
+///
+/// LATENCY: SLOW_PATH
+pub fn render_metrics(groups: &[GroupMetrics], region: &MetricsRegion, probes: &[EndpointProbe]) -> String {
+    let streams: HashMap<String, (u64, u64)> = region
+        .registered_streams()
+        .map(|stats| (stats.name(), (stats.messages.load(Ordering::Relaxed), stats.rate.load(Ordering::Relaxed))))
+        .collect();
+    let mut text = PrometheusText::new();
+
+    // The streams not registered yet, e.g. of a FeedGroup never started, are left out
+    let symbol_samples = |text: &mut PrometheusText, name: &str, value: fn(&(u64, u64)) -> u64| {
+        for group in groups {
+            for (symbol, stream) in &group.streams {
+                let Some(stats) = streams.get(stream) else { continue };
+                let [feed, set, medium] = group.labels();
+                text.sample(name, &[feed, set, medium, ("symbol", symbol)], value(stats));
+            }
+        }
+    };
+    text.family("ctl_md_messages_total", MetricType::Counter, "Messages received per symbol.");
+    symbol_samples(&mut text, "ctl_md_messages_total", |&(messages, _)| messages);
+    text.family("ctl_md_message_rate", MetricType::Gauge, "Messages per second per symbol, over the last sample.");
+    symbol_samples(&mut text, "ctl_md_message_rate", |&(_, rate)| rate);
+
+    text.family("ctl_md_reconnects_total", MetricType::Counter, "Reconnections of the feed of a FeedGroup.");
+    for group in groups {
+        let [feed, set, medium] = group.labels();
+        for (reason, count) in [("failover", &group.failovers), ("restart", &group.restarts)] {
+            text.sample(
+                "ctl_md_reconnects_total",
+                &[feed, set, medium, ("reason", reason)],
+                count.load(Ordering::Relaxed),
+            );
+        }
+    }
+
+    text.family("ctl_md_parse_errors_total", MetricType::Counter, "Messages of a FeedGroup failed to parse.");
+    for group in groups {
+        text.sample("ctl_md_parse_errors_total", &group.labels(), group.parse_errors.count());
+    }
+
+    // The FIX sessions, without connection statistics, are left out
+    let now = Instant::now();
+    let connections: Vec<(&GroupMetrics, ConnectionStats)> = groups
+        .iter()
+        .filter_map(|group| group.connection.as_ref().map(|connection| (group, connection.snapshot(now))))
+        .collect();
+    let families: [(&str, MetricType, &str, fn(&ConnectionStats) -> Option<u64>); 7] = [
+        ("ctl_md_ws_frames_total", MetricType::Counter, "Frames received by the connection.", |stats| {
+            Some(stats.frames)
+        }),
+        ("ctl_md_ws_bytes_total", MetricType::Counter, "Payload bytes received by the connection.", |stats| {
+            Some(stats.bytes)
+        }),
+        ("ctl_md_ws_frame_rate", MetricType::Gauge, "Frames per second, over the last second.", |stats| {
+            Some(stats.frame_rate)
+        }),
+        ("ctl_md_ws_byte_rate", MetricType::Gauge, "Payload bytes per second, over the last second.", |stats| {
+            Some(stats.byte_rate)
+        }),
+        ("ctl_md_ws_rtt_us", MetricType::Gauge, "Round trip of the last request answered.", |stats| stats.rtt_us),
+        ("ctl_md_ws_since_last_data_ms", MetricType::Gauge, "Time since the last frame.", |stats| {
+            stats.since_last_data_ms
+        }),
+        ("ctl_md_ws_bad_responses_total", MetricType::Counter, "Malformed responses dropped.", |stats| {
+            Some(stats.bad_responses)
+        }),
+    ];
+    for (name, metric_type, help, value) in families {
+        text.family(name, metric_type, help);
+        for (group, stats) in &connections {
+            let Some(value) = value(stats) else { continue };
+            text.sample(name, &group.labels(), value);
+        }
+    }
+
+    if probes.is_empty() {
+        return text.finish();
+    }
+    text.family("ctl_md_endpoint_latency_us", MetricType::Gauge, "Latency of a phase of an endpoint, last probed.");
+    for probe in probes {
+        let phases = [("tcp", probe.tcp_connect), ("tls", probe.tls_handshake), ("first_message", probe.first_message)];
+        for (phase, latency) in phases {
+            let Some(latency) = latency else { continue };
+            let labels = [("endpoint", probe.endpoint.as_str()), ("phase", phase)];
+            text.sample("ctl_md_endpoint_latency_us", &labels, latency.as_micros() as u64);
+        }
+    }
+    text.family("ctl_md_endpoint_up", MetricType::Gauge, "Whether the last probe of an endpoint succeeded.");
+    for probe in probes {
+        text.sample("ctl_md_endpoint_up", &[("endpoint", probe.endpoint.as_str())], probe.latency().is_some() as u64);
+    }
+    text.family("ctl_md_endpoint_rank", MetricType::Gauge, "Rank of an endpoint by latency, 1 for the best.");
+    for (rank, probe) in probes.iter().enumerate() {
+        text.sample("ctl_md_endpoint_rank", &[("endpoint", probe.endpoint.as_str())], rank as u64 + 1);
+    }
+    text.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::time::Duration;
+
+    use ctl_feed::DummyParserError;
+    use ctl_shm::ShmRegion;
+
+    #[test]
+    fn test_render_metrics() {
+        let name = format!("ctl_md_exposition_test_{}", std::process::id());
+        let region = ShmRegion::<MetricsRegion>::create(&name).unwrap();
+        let index = region.register_stream("btcusdt@bookTicker", 1_000).unwrap();
+        for _ in 0..3 {
+            region.streams[index].record(2_000);
+        }
+        region.streams[index].sample(1_000);
+
+        let streams = vec![
+            ("BTCUSDT".to_string(), "btcusdt@bookTicker".to_string()),
+            ("ETHUSDT".to_string(), "ethusdt@bookTicker".to_string()),
+        ];
+        let group = GroupMetrics::new("top", Some("A"), "websocket/json", streams);
+        group.record_failover();
+        group.record_restart();
+        group.record_restart();
+        group.parse_errors.record(&DummyParserError::General);
+        let connection = ConnectionStatsHandle::new();
+        connection.record_frame(128, Instant::now());
+        connection.record_bad_response();
+        let group = group.with_connection(connection);
+        let fix = GroupMetrics::new("trade", None, "fix/fix", Vec::new());
+
+        let text = render_metrics(&[group, fix], &region, &[]);
+        let labels = "feed=\"top\",set=\"A\",medium=\"websocket/json\"";
+        assert!(text.contains(&format!("ctl_md_messages_total{{{},symbol=\"BTCUSDT\"}} 3\n", labels)));
+        assert!(text.contains(&format!("ctl_md_message_rate{{{},symbol=\"BTCUSDT\"}} 3\n", labels)));
+        assert!(!text.contains("ETHUSDT"));
+        assert!(text.contains(&format!("ctl_md_reconnects_total{{{},reason=\"failover\"}} 1\n", labels)));
+        assert!(text.contains(&format!("ctl_md_reconnects_total{{{},reason=\"restart\"}} 2\n", labels)));
+        assert!(text.contains(&format!("ctl_md_parse_errors_total{{{}}} 1\n", labels)));
+        assert!(text.contains(&format!("ctl_md_ws_frames_total{{{}}} 1\n", labels)));
+        assert!(text.contains(&format!("ctl_md_ws_bytes_total{{{}}} 128\n", labels)));
+        assert!(text.contains(&format!("ctl_md_ws_since_last_data_ms{{{}}} ", labels)));
+        assert!(text.contains(&format!("ctl_md_ws_bad_responses_total{{{}}} 1\n", labels)));
+        assert!(!text.contains("ctl_md_ws_rtt_us{"));
+        assert!(!text.contains("ctl_md_ws_frames_total{feed=\"trade\""));
+        assert!(text.contains("# TYPE ctl_md_message_rate gauge\n"));
+        assert!(!text.contains("ctl_md_endpoint"));
+    }
+
+    #[test]
+    fn test_render_endpoint_probes() {
+        let name = format!("ctl_md_exposition_probe_test_{}", std::process::id());
+        let region = ShmRegion::<MetricsRegion>::create(&name).unwrap();
+        let mut best = EndpointProbe::new("wss://stream.binance.com:9443/ws");
+        best.tcp_connect = Some(Duration::from_micros(1_500));
+        best.tls_handshake = Some(Duration::from_millis(4));
+        best.first_message = Some(Duration::from_millis(20));
+        let mut failed = EndpointProbe::new("wss://data-stream.binance.vision/ws");
+        failed.error = Some("TCP connect: timed out".to_string());
+
+        let text = render_metrics(&[], &region, &[best, failed]);
+        let best = "endpoint=\"wss://stream.binance.com:9443/ws\"";
+        let failed = "endpoint=\"wss://data-stream.binance.vision/ws\"";
+        assert!(text.contains(&format!("ctl_md_endpoint_latency_us{{{},phase=\"tcp\"}} 1500\n", best)));
+        assert!(text.contains(&format!("ctl_md_endpoint_latency_us{{{},phase=\"first_message\"}} 20000\n", best)));
+        assert!(!text.contains(&format!("ctl_md_endpoint_latency_us{{{}", failed)));
+        assert!(text.contains(&format!("ctl_md_endpoint_up{{{}}} 1\n", best)));
+        assert!(text.contains(&format!("ctl_md_endpoint_up{{{}}} 0\n", failed)));
+        assert!(text.contains(&format!("ctl_md_endpoint_rank{{{}}} 2\n", failed)));
+    }
+}
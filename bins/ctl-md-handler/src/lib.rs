@@ -3,13 +3,24 @@
 //! This crate provides the configuration and handler logic for processing
 //! market data from Binance Spot.
 
+mod arbitration;
 mod config;
 mod errors;
+mod exposition;
+mod overlay;
+mod plan;
+mod restart;
+mod staleness;
 
-pub use errors::{HwResourcesConfigError, SymbolInfoConfigError};
+pub use arbitration::ArbitrationConfig;
+pub use exposition::{render_metrics, GroupMetrics};
+pub use errors::{HwResourcesConfigError, LcorePlanError, SymbolInfoConfigError};
+pub use plan::{LcoreAssignment, LcorePlan};
+pub use restart::{RestartDecision, RestartPolicy, RestartTracker};
+pub use staleness::{StaleAction, StaleChange, StalenessPolicy, StalenessTracker};
 
 pub use config::{
-    FeedConfig, FeedWrapper, HwResourcesConfig, PubSubConfig, SymbolSet,
+    FeedConfig, FeedSet, FeedWrapper, HwResourcesConfig, Medium, PubSubConfig, SymbolSet,
     SymbolInfo, SymbolInfoConfig,
 };
 
@@ -4,155 +4,1016 @@
 //! to DPDK shared memory rings. It runs as a DPDK secondary process, looking up
 //! rings created by ctl-resource-manager.
 //!
+//! With the `shm-rings` feature, it opens the shared memory rings created by a
+//! ctl-resource-manager built with the same feature instead, the workers of its
+//! FeedGroups running on threads of the process, so the handler and the
+//! consumers built with the feature run on a host without hugepages or an EAL.
+//!
 //! # Architecture
 //!
-//! - Creates FeedGroups for each feed kind (Top, Trade, AggTrade)
+//! - Creates a FeedGroup named `{kind}/{set}` for each symbol set of each feed kind
+//!   (Top, Trade, AggTrade, Depth), running on the lcores planned for the set with its
+//!   own connections, and publishing to the set's rings: the ring of each of its
+//!   symbols, or the single ring of an aggregated set
+//! - Sets listing multiple mediums fan out to one FeedGroup per medium, named
+//!   `{kind}/{set}@{protocol}/{parser}`, all publishing to the set's rings with the
+//!   producing medium tagged in the message header
+//! - Top feeds also overwrite a per-symbol last-value slot in shared memory
+//! - The prices and quantities of the symbols with tick/step exponents in the
+//!   symbol info are published in fixed-point alongside their payloads
+//! - Each feed fails over between its configured endpoints, reporting switches
+//!   to the main thread
+//! - Under the `probe` of the configuration, the candidate endpoints are probed
+//!   for their TCP connect, TLS handshake and first message latencies, at
+//!   startup and then every interval by a thread off the hot path. The feeds
+//!   start on the best-ranked of their endpoints and fail over to the
+//!   best-ranked other one, the latencies exposed on `/metrics`
+//! - Each feed periodically reconciles its subscriptions with LIST_SUBSCRIPTIONS,
+//!   resubscribing the lost streams and reporting the drifts to the main thread
+//! - The responses to the subscription requests of the feeds are reported to
+//!   the main thread and logged, the rejected ones as warnings
 //! - Each FeedGroup manages one or more WebSocket connections (Feeds)
-//! - Workers poll feeds, parse messages, and publish to shared rings
+//! - Mediums with the `fix` protocol run FIX market data sessions instead (Top
+//!   and Trade), logged on with the Ed25519 API key of `BINANCE_FIX_API_KEY`
+//!   and `BINANCE_FIX_PRIVATE_KEY`, their events translated into the websocket
+//!   payloads by the FIX parser
+//! - Mediums with the `sbe` parser subscribe to the SBE streams (Top and Trade,
+//!   the Top feeds to `bestBidAsk`) on the SBE endpoints, their binary events
+//!   translated into the websocket payloads by the SBE parser, which publishes
+//!   the leading trades of a trades event itself
+//! - Workers poll feeds, parse messages, and publish to shared rings, the
+//!   messages admitted under the overflow policy of their ring and counted by
+//!   outcome in the metrics region
+//! - Each FeedGroup counts its messages per stream in the metrics region, the
+//!   main thread sampling their rates and checking each stream against the
+//!   staleness threshold of its symbol, alerting on the stale streams and
+//!   optionally failing their feed over or halting trading
+//! - Once a circuit breaker trips in the status table, every FeedGroup is
+//!   paused until resumed by `ctl-admin reset-breaker`
+//! - With the `latency-histograms` feature, each FeedGroup records its parse
+//!   and ring publish times in a latency group of the metrics region, its
+//!   parser publishing the messages the workers otherwise publish
 //! - Main thread coordinates feedgroups, polls feedback, and handles commands
+//! - The PauseFeed/ResumeFeed commands of the control ring pause the publishing
+//!   of a set, feed kind or symbol, keeping its connections, and are
+//!   acknowledged through the alerts ring
+//! - FeedGroups whose workers exit are rebuilt after a backoff, per the restart
+//!   policy, the handler shutting down once the restarts are exhausted
+//! - With an endpoint in `configs/health.yaml`, a thread off the hot path
+//!   serves `/healthz` (the main loop heartbeat) and `/status` (the trading
+//!   state, FeedGroups, rings, consumer lag and streams) over HTTP, and with
+//!   `metrics` enabled `/metrics`, the message counts and rates of each symbol
+//!   and the reconnections and parse errors of each FeedGroup for Prometheus
+//! - Each websocket connection tracks its frame and byte rates, the round trip
+//!   of its requests and the time since its last data, reported per FeedGroup
+//!   on `/status` and `/metrics`
+//! - With `sample_every` in `configs/telemetry.yaml`, a share of the messages is
+//!   traced from their receive, their parse recorded as the `md.parse` span and
+//!   stamped in their header for the consumers to continue the trace, the spans
+//!   exported over OTLP by a thread off the hot path
+//! - The actions the handler takes on its own (halting trading on a stale
+//!   stream, resubscribing drifted streams, reloading the log levels) are
+//!   recorded in the audit journal, along with those of the operators
+//! - The run epoch of ctl-resource-manager is checked every second. Once it
+//!   restarted, rather than publishing to its stale rings, the handler executes
+//!   itself anew, waiting for the next run before looking up the rings and
+//!   subscribing the streams again
+//! - Under the `arbitration` of the configuration, two handlers run the feeds on
+//!   their own connections, started with `--line a` and `--line b`, each
+//!   publishing to the line rings of its line for ctl-arbiter to forward the
+//!   first copy of each event to the pub-sub rings
+//!
+//! The configuration paths and the default endpoint are given on the command
+//! line, or by their environment variables (see `--help`). With `--check`, the
+//! configurations are validated and the planned FeedGroups printed instead.
+//! The exit code tells the class of a failure (see `FatalKind`), e.g. 15 when
+//! the FIX session is refused its credentials.
 
-use std::error::Error;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::Ordering;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::time::{Duration, Instant};
 
 use atx_feed::{
-    Feed, FeedGroup, FeedGroupConfig, FeedGroupWorkerCommandAck, FeedGroupWorkerFeedback,
-    FeedKind, FeedProtocol, Stream, Streams,
+    FeedGroupWorkerCommandAck, FeedGroupWorkerFeedback, FeedKind, FeedParseProtocol, FeedProtocol, Stream, Streams,
+};
+#[cfg(not(feature = "shm-rings"))]
+use atx_feed::{Feed, FeedGroup, FeedGroupConfig, FeedGroupError};
+use clap::Parser;
+use ctl_core::{
+    reattach, reattaching, wait_for_run, AlertKind, AlertMessage, AlertSeverity, AuditAction, AuditJournal,
+    ControlCommand, ControlMessage, EpochWatch, Fatal, FatalError, FatalKind, HealthConfig, HealthRoutes, HealthServer,
+    Heartbeat, Line, Poller, PollingConfig, PollingPolicy, Preflight, RingName, SchedulingConfig, SchedulingPolicy,
+    StatusRegion, TelemetryConfig, Tracer, TradingStatus, ALERTS_RING_NAME, CONTROL_RING_NAME,
+    DEFAULT_AUDIT_JOURNAL_PATH, REATTACH_POLL_INTERVAL, STATUS_REGION_NAME,
+};
+#[cfg(not(feature = "shm-rings"))]
+use ctl_core::Capability;
+use ctl_feed::{
+    AggTrade, Depth, DummyParser, FixParser, FragmentSink, GuardedPublisher, LagAlert, LastTopHandle, LastTopRegion,
+    MediumTag, MetricsRegion, MetricsStatus, OverflowGuard, ParseErrorCounter, PauseHandle, RawMessage, RingConsume,
+    RingMetricsHandle, RingPublisher, SbeParser, StreamReport, StreamStatsHandle, SymbolScale, Top, Trade,
+    LAST_TOP_REGION_NAME, METRICS_REGION_NAME, SBE_BEST_BID_ASK_SUFFIX,
+};
+#[cfg(not(feature = "shm-rings"))]
+use ctl_feed::{dpdk_consume, FeedGroups};
+#[cfg(feature = "shm-rings")]
+use ctl_feed::{RingConsumer, RingLike, ShmFeedGroup as FeedGroup, ShmFeedGroups as FeedGroups, ShmJoinHandle};
+#[cfg(feature = "latency-histograms")]
+use ctl_feed::LatencyRecorder;
+use ctl_fix::{FixConn, FixConnectorError, FixCredentials, FixSession, MarketDataEntries};
+use ctl_md_handler::{
+    render_metrics, FeedSet, GroupMetrics, HwResourcesConfig, LcorePlan, Medium, RestartDecision, RestartTracker,
+    StaleAction, StaleChange, StalenessTracker, SymbolInfoConfig,
+};
+use ctl_shm::ShmRegion;
+#[cfg(feature = "shm-rings")]
+use ctl_shm::{ShmError, ShmMessage, ShmRing};
+use ctl_time::now_ms;
+use ctl_websocket::{
+    stream_name, ConnectionStats, ConnectionStatsHandle, EndpointProber, EndpointRanking, EndpointSwitch,
+    FailoverTrigger, RequestAck, StreamSuffix, SubscriptionDrift, SwitchReason, WSConn, WSResponse,
 };
-use atx_handler::{HandlerBuilder, HandlerRunner};
-use ctl_feed::{AggTrade, DummyParser, RawMessage, Top, Trade};
-use ctl_md_handler::{HwResourcesConfig, SymbolInfoConfig};
-use ctl_websocket::WSConn;
-use dpdk::{DpdkEnv, DpdkEnvBuilder, DpdkLCoreId, DpdkPubSubRing, DpdkProcessType, MultiJoinHandle};
+use dpdk::DpdkLCoreId;
+#[cfg(not(feature = "shm-rings"))]
+use dpdk::{DpdkEnv, DpdkEnvBuilder, DpdkPubSubRing, DpdkProcessType, MultiJoinHandle};
+use serde::Serialize;
+use tracing::{error, info, warn};
+
+// Logging configuration, its levels reloaded on change
+const LOG_CONFIG_PATH: &str = "configs/logging.yaml";
 
 // Configuration file paths
 const MD_CONFIG_PATH: &str = "configs/market-data/hw-resources.yaml";
 const SYMBOL_INFO_PATH: &str = "configs/market-data/symbolinfo.yaml";
+const POLLING_CONFIG_PATH: &str = "configs/polling.yaml";
+
+// Polling policy of the main thread, unless configured for this component
+const POLLING_COMPONENT: &str = "md-handler";
+const DEFAULT_POLLING: PollingPolicy = PollingPolicy::Sleep { sleep_us: 10_000 };
+
+// Scheduling of the threads of this component, unless configured left untouched
+const SCHEDULING_CONFIG_PATH: &str = "configs/scheduling.yaml";
+const SCHEDULING_COMPONENT: &str = "md-handler";
+
+// Health and status endpoints of this component, unless configured served by none
+const HEALTH_CONFIG_PATH: &str = "configs/health.yaml";
+const HEALTH_COMPONENT: &str = "md-handler";
+
+// Tracing of the messages of this component, unless configured traced by none
+const TELEMETRY_CONFIG_PATH: &str = "configs/telemetry.yaml";
+const TELEMETRY_COMPONENT: &str = "md-handler";
 
-// WebSocket endpoint for Binance Spot
+// Origin of the actions recorded in the audit journal
+const AUDIT_COMPONENT: &str = "md-handler";
+
+// Default WebSocket endpoint for Binance Spot, used for feeds without configured endpoints
 const BINANCE_WS_ENDPOINT: &str = "wss://stream.binance.com:9443/ws";
 
+// Default SBE market data endpoint for Binance Spot, used for feeds without configured SBE endpoints
+const BINANCE_SBE_ENDPOINT: &str = "wss://stream-sbe.binance.com:9443/ws";
+
+// Default FIX market data endpoint for Binance Spot, used for feeds without configured FIX endpoints
+const BINANCE_FIX_ENDPOINT: &str = "tls://fix-md.binance.com:9000";
+
+// Prefix of the SenderCompID of the FIX sessions, suffixed with the line and the index of their FeedGroup
+const FIX_SENDER_COMP_ID_PREFIX: &str = "CTLMD";
+
 // Channel capacities for command/feedback queues
+#[cfg(not(feature = "shm-rings"))]
 const COMMAND_CHANNEL_CAPACITY: usize = 1024;
+#[cfg(not(feature = "shm-rings"))]
 const FEEDBACK_CHANNEL_CAPACITY: usize = 1024;
 
-/// Creates a FeedGroup for the Top (book ticker) feed kind.
+// Consumer lag monitoring: alert when a consumer lags by this percentage of the ring size
+const LAG_ALERT_THRESHOLD_PCT: u64 = 75;
+const LAG_CHECK_INTERVAL: Duration = Duration::from_millis(100);
+
+// Stream statistics: sample the message rates, logging the busiest streams
+const STREAM_SAMPLE_INTERVAL: Duration = Duration::from_secs(10);
+const BUSIEST_STREAMS: usize = 5;
+
+// Interval between the checks of the streams against their staleness thresholds
+const STALENESS_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+// Interval between the checks of the run epoch of ctl-resource-manager
+const EPOCH_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+// Interval between the reconciliations of the subscriptions of each connection
+const SUBSCRIPTION_RECONCILE_INTERVAL: Duration = Duration::from_secs(30);
+
+// Source of the alerts published to the alerts ring
+const ALERT_SOURCE: &str = "md-handler";
+
+// The rings looked up on the DPDK runtime, the FeedGroup workers running on its lcores, or with the
+// `shm-rings` feature the shared memory rings of ctl-resource-manager, needing neither hugepages nor
+// an EAL, the workers running on threads of the process
+#[cfg(not(feature = "shm-rings"))]
+type RingEnv = DpdkEnv;
+#[cfg(not(feature = "shm-rings"))]
+type Ring<T> = DpdkPubSubRing<T>;
+#[cfg(not(feature = "shm-rings"))]
+type WorkersHandle = MultiJoinHandle<Result<(), FeedGroupError>>;
+#[cfg(feature = "shm-rings")]
+type RingEnv = ShmRings;
+#[cfg(feature = "shm-rings")]
+type Ring<T> = ShmRing<T>;
+#[cfg(feature = "shm-rings")]
+type WorkersHandle = ShmJoinHandle;
+
+/// The shared memory rings of ctl-resource-manager, opened by name.
+#[cfg(feature = "shm-rings")]
+struct ShmRings;
+
+#[cfg(feature = "shm-rings")]
+impl ShmRings {
+    /// Opens a ring of `T` messages, as the DPDK runtime looks it up.
+    fn pubsub_lookup<T: ShmMessage>(&self, name: &str) -> Result<ShmRing<T>, ShmError> {
+        ShmRing::open(name)
+    }
+}
+
+// Builds the FeedGroup of a feed connection publishing to a ring
+#[cfg(not(feature = "shm-rings"))]
+macro_rules! build_feedgroup {
+    ($name:expr, $ring_env:expr, $worker_lcore_ids:expr, $ring:expr, $parser:expr, $conn:expr) => {
+        FeedGroup::validated_build(FeedGroupConfig {
+            name: $name,
+            dpdk_env: $ring_env,
+            worker_lcore_ids: $worker_lcore_ids,
+            publisher: $ring,
+            parser: $parser,
+            feeds: vec![Feed::new($name, $conn)],
+            command_channel_capacity: COMMAND_CHANNEL_CAPACITY,
+            feedback_channel_capacity: FEEDBACK_CHANNEL_CAPACITY,
+        })
+        .fatal(FatalKind::Config)
+    };
+}
+#[cfg(feature = "shm-rings")]
+macro_rules! build_feedgroup {
+    ($name:expr, $ring_env:expr, $worker_lcore_ids:expr, $ring:expr, $parser:expr, $conn:expr) => {
+        FeedGroup::new($name, $worker_lcore_ids, FragmentSink::new($ring), $parser, vec![$conn])
+            .fatal(FatalKind::Config)
+    };
+}
+
+// Consumes the next message of a ring as a `RingConsume`
+#[cfg(not(feature = "shm-rings"))]
+macro_rules! consume {
+    ($consumer:expr) => {
+        dpdk_consume!($consumer)
+    };
+}
+#[cfg(feature = "shm-rings")]
+macro_rules! consume {
+    ($consumer:expr) => {
+        RingConsumer::consume(&mut $consumer)
+    };
+}
+
+/// Binance Spot market data handler.
+#[derive(Debug, Parser)]
+#[command(version)]
+struct Args {
+    /// Logging configuration.
+    #[arg(long, env = "CTL_LOG_CONFIG", default_value = LOG_CONFIG_PATH)]
+    log_config: PathBuf,
+    /// Market data configuration.
+    #[arg(long, env = "CTL_MD_CONFIG", default_value = MD_CONFIG_PATH)]
+    md_config: PathBuf,
+    /// Symbol info configuration.
+    #[arg(long, env = "CTL_SYMBOL_INFO", default_value = SYMBOL_INFO_PATH)]
+    symbol_info: PathBuf,
+    /// Polling configuration.
+    #[arg(long, env = "CTL_POLLING_CONFIG", default_value = POLLING_CONFIG_PATH)]
+    polling_config: PathBuf,
+    /// Scheduling configuration.
+    #[arg(long, env = "CTL_SCHEDULING_CONFIG", default_value = SCHEDULING_CONFIG_PATH)]
+    scheduling_config: PathBuf,
+    /// Health endpoint configuration.
+    #[arg(long, env = "CTL_HEALTH_CONFIG", default_value = HEALTH_CONFIG_PATH)]
+    health_config: PathBuf,
+    /// Telemetry configuration.
+    #[arg(long, env = "CTL_TELEMETRY_CONFIG", default_value = TELEMETRY_CONFIG_PATH)]
+    telemetry_config: PathBuf,
+    /// Audit journal of the control-plane actions.
+    #[arg(long, env = "CTL_AUDIT_JOURNAL", default_value = DEFAULT_AUDIT_JOURNAL_PATH)]
+    audit_journal: PathBuf,
+    /// WebSocket endpoint of the feeds without configured endpoints.
+    #[arg(long, env = "CTL_WS_ENDPOINT", default_value = BINANCE_WS_ENDPOINT)]
+    ws_endpoint: String,
+    /// The line published by the handler (`a` or `b`), required under the
+    /// arbitration of the market data configuration.
+    #[arg(long, env = "CTL_MD_LINE")]
+    line: Option<Line>,
+    /// Validate the configurations and print the planned FeedGroups, without
+    /// initializing DPDK or connecting the feeds.
+    #[arg(long)]
+    check: bool,
+}
+
+/// The channels the feeds running on the workers report to, and the ranking
+/// of the endpoints they start on and fail over by.
+struct Reporters {
+    /// The endpoint switches.
+    switches: Sender<EndpointSwitch>,
+    /// The subscription drifts.
+    drifts: Sender<SubscriptionDrift>,
+    /// The responses to the subscription requests.
+    acks: Sender<RequestAck>,
+    /// The ranking of the endpoints by latency, `None` if they aren't probed.
+    ranking: Option<EndpointRanking>,
+}
+
+/// Creates the FeedGroup running a websocket medium of a symbol set of a feed kind.
 ///
-/// Looks up rings for each symbol and creates WebSocket feeds to subscribe to bookTicker streams.
-fn create_top_feedgroup<'a>(
-    dpdk_env: &'a DpdkEnv,
-    md_config: &HwResourcesConfig,
+/// Creates the WebSocket feed subscribing to the set's streams and looks up the set's rings,
+/// recording the messages published by `parser` in the ring metrics. The SBE medium
+/// subscribes to the SBE streams on the SBE endpoints, its parser `P` translating
+/// the events for `parser`.
+#[allow(clippy::too_many_arguments)]
+fn create_feedgroup<'a, K, P>(
+    ring_env: &'a RingEnv,
+    feed_set: &FeedSet<'_>,
+    line: Option<Line>,
+    medium: &Medium,
+    tag: MediumTag,
+    parser: DummyParser,
     symbol_info: &SymbolInfoConfig,
+    metrics: &Arc<ShmRegion<MetricsRegion>>,
+    reporters: &Reporters,
+    failover: &FailoverTrigger,
+    connection: Option<&ConnectionStatsHandle>,
     worker_lcore_ids: Vec<DpdkLCoreId>,
-) -> Result<FeedGroup<'a, WSConn<Top>, Top, DummyParser>, Box<dyn Error>> {
-    let feed_config = md_config
-        .find_feed("top")
-        .ok_or("Feed kind 'top' not found in config")?;
-
-    let symbols: Vec<&str> = feed_config.all_symbols();
-    if symbols.is_empty() {
-        return Err("No symbols configured for 'top' feed".into());
+) -> Result<FeedGroup<'a, WSConn<K>, K, P>, FatalError>
+where
+    K: StreamSuffix,
+    P: From<DummyParser> + FeedParseProtocol<WSConn<K>, K, FeedParsedMessage = RawMessage>,
+{
+    let name = feed_set.group_name(medium);
+    if feed_set.symbols.is_empty() {
+        return Err(FatalError::new(FatalKind::Config, format!("No symbols configured for '{}'", name)));
     }
 
-    // Create streams for all symbols
-    let mut streams: Streams<Top> = Streams::new();
-    for symbol in &symbols {
+    // Create streams for all symbols of the set
+    let mut streams: Streams<K> = Streams::new();
+    for symbol in feed_set.symbols {
         streams.insert(Stream::new(symbol.to_lowercase().leak()));
     }
 
-    // Create WebSocket connection and subscribe to streams
-    let mut ws_conn = WSConn::<Top>::new(BINANCE_WS_ENDPOINT)?;
-    FeedProtocol::update(&mut ws_conn, &streams)?;
+    // Create WebSocket connection, failing over between the feed's endpoints, and subscribe to streams
+    let (configured, default_endpoint) = match tag {
+        MediumTag::Sbe => (feed_set.sbe_endpoints, BINANCE_SBE_ENDPOINT),
+        _ => (feed_set.endpoints, BINANCE_WS_ENDPOINT),
+    };
+    let mut endpoints =
+        if configured.is_empty() { vec![default_endpoint.to_string()] } else { configured.to_vec() };
+    // Probed, the feed starts on the best-ranked endpoint and fails over to the best-ranked other one
+    if let Some(ranking) = &reporters.ranking {
+        endpoints = ranking.rank(&endpoints);
+    }
+    let mut ws_conn = WSConn::<K>::with_address_policy(endpoints, feed_set.failover, feed_set.addresses)
+        .fatal(FatalKind::Network)?;
+    if let Some(ranking) = &reporters.ranking {
+        ws_conn.set_endpoint_ranking(ranking.clone());
+    }
+    ws_conn.set_failover_reporter(&name, reporters.switches.clone());
+    ws_conn.set_failover_trigger(failover.clone());
+    if let Some(connection) = connection {
+        ws_conn.set_stats(connection.clone());
+    }
+    ws_conn.set_reconnect_policy(feed_set.reconnect);
+    ws_conn.set_max_message_size(feed_set.max_message_size);
+    ws_conn.set_drift_reporter(&name, reporters.drifts.clone());
+    ws_conn.set_ack_reporter(&name, reporters.acks.clone());
+    ws_conn.set_reconcile_interval(Some(SUBSCRIPTION_RECONCILE_INTERVAL));
+    ws_conn.set_update_speed(medium.update_speed);
+    let suffix = medium_suffix(feed_set.kind, tag).unwrap_or(K::SUFFIX);
+    ws_conn.update_streams(&streams, suffix).fatal(FatalKind::Network)?;
+    if let Some(id) = &ws_conn.last_update().subscribe {
+        info!("[{}] Subscribing {} streams, request {:?}", name, feed_set.symbols.len(), id);
+    }
 
-    // Create feeds (one feed per connection for now)
-    let feeds = vec![Feed::new("TopFeed", ws_conn)];
-
-    // Lookup the ring for the first symbol (for now, using single ring per kind)
-    // Ring naming convention: {KIND}_{symbol_id}_PS
-    let first_symbol = symbols.first().ok_or("No symbols for top feed")?;
-    let symbol_id = symbol_info
-        .symbol_id(first_symbol)
-        .ok_or_else(|| format!("Symbol '{}' not found in symbolinfo.yaml", first_symbol))?;
-    let ring_name = format!("TOP_{}_PS", symbol_id);
-    let ring: DpdkPubSubRing<RawMessage> = dpdk_env.pubsub_lookup::<RawMessage>(&ring_name)?;
-
-    println!(
-        "[TopFeedGroup] Created with {} symbols, {} workers, ring: {}",
-        symbols.len(),
+    let endpoint = match ws_conn.active_address() {
+        Some(addr) => format!("{} ({})", ws_conn.active_endpoint(), addr),
+        None => ws_conn.active_endpoint().to_string(),
+    };
+
+    // Count the messages of each stream of the set
+    let stream_names: Vec<(String, String)> = feed_set
+        .symbols
+        .iter()
+        .map(|symbol| (symbol.clone(), medium_stream(ws_conn.stream_name(&symbol.to_lowercase(), suffix), tag)))
+        .collect();
+    let stream_stats = register_stream_stats(&name, &stream_names, metrics)?;
+
+    let name: &'static str = name.leak();
+    let (parser, ring, ring_names) =
+        route_set_rings(ring_env, feed_set, line, symbol_info, metrics, parser.with_stream_stats(stream_stats))?;
+
+    info!(
+        "[{}] Created with {} symbols, {} workers, medium: {}, endpoint: {}, rings: {}",
+        name,
+        feed_set.symbols.len(),
         worker_lcore_ids.len(),
-        ring_name
+        medium.name(),
+        endpoint,
+        ring_names.join(", ")
     );
 
-    let config = FeedGroupConfig {
-        name: "TopFeedGroup",
-        dpdk_env,
-        worker_lcore_ids,
-        publisher: ring,
-        parser: DummyParser,
-        feeds,
-        command_channel_capacity: COMMAND_CHANNEL_CAPACITY,
-        feedback_channel_capacity: FEEDBACK_CHANNEL_CAPACITY,
-    };
-
-    Ok(FeedGroup::validated_build(config)?)
+    // Create feeds (one feed per connection for now)
+    build_feedgroup!(name, ring_env, worker_lcore_ids, ring, P::from(parser), ws_conn)
 }
 
-/// Creates a FeedGroup for the Trade feed kind.
+/// Creates the FeedGroup running the FIX medium of a symbol set of a feed kind.
 ///
-/// Looks up rings for each symbol and creates WebSocket feeds to subscribe to trade streams.
-fn create_trade_feedgroup<'a>(
-    dpdk_env: &'a DpdkEnv,
-    md_config: &HwResourcesConfig,
+/// Logs on a FIX market data session subscribing to the set's symbols and looks up the
+/// set's rings, the FIX parser translating the events for `parser`.
+#[allow(clippy::too_many_arguments)]
+fn create_fix_feedgroup<'a, K>(
+    ring_env: &'a RingEnv,
+    feed_set: &FeedSet<'_>,
+    line: Option<Line>,
+    medium: &Medium,
+    sender_comp_id: &str,
+    parser: DummyParser,
     symbol_info: &SymbolInfoConfig,
+    metrics: &Arc<ShmRegion<MetricsRegion>>,
+    reporters: &Reporters,
+    failover: &FailoverTrigger,
     worker_lcore_ids: Vec<DpdkLCoreId>,
-) -> Result<FeedGroup<'a, WSConn<Trade>, Trade, DummyParser>, Box<dyn Error>> {
-    let feed_config = md_config
-        .find_feed("trade")
-        .ok_or("Feed kind 'trade' not found in config")?;
-
-    let symbols: Vec<&str> = feed_config.all_symbols();
-    if symbols.is_empty() {
-        return Err("No symbols configured for 'trade' feed".into());
+) -> Result<FeedGroup<'a, FixConn<K>, K, FixParser>, FatalError>
+where
+    K: MarketDataEntries + StreamSuffix,
+    FixParser: FeedParseProtocol<FixConn<K>, K, FeedParsedMessage = RawMessage>,
+{
+    let name = feed_set.group_name(medium);
+    if feed_set.symbols.is_empty() {
+        return Err(FatalError::new(FatalKind::Config, format!("No symbols configured for '{}'", name)));
     }
 
-    // Create streams for all symbols
-    let mut streams: Streams<Trade> = Streams::new();
-    for symbol in &symbols {
-        streams.insert(Stream::new(symbol.to_lowercase().leak()));
+    // Create streams for all symbols of the set, subscribed by their uppercase symbol
+    let mut streams: Streams<K> = Streams::new();
+    for symbol in feed_set.symbols {
+        streams.insert(Stream::new(symbol.to_uppercase().leak()));
     }
 
-    // Create WebSocket connection and subscribe to streams
-    let mut ws_conn = WSConn::<Trade>::new(BINANCE_WS_ENDPOINT)?;
-    FeedProtocol::update(&mut ws_conn, &streams)?;
+    // Log on, failing over between the feed's FIX endpoints, and request the market data
+    let endpoints = if feed_set.fix_endpoints.is_empty() {
+        vec![BINANCE_FIX_ENDPOINT.to_string()]
+    } else {
+        feed_set.fix_endpoints.to_vec()
+    };
+    let session = FixSession::new(sender_comp_id);
+    let credentials = FixCredentials::from_env().map_err(fix_fatal)?;
+    let mut fix_conn =
+        FixConn::<K>::with_endpoints(endpoints, feed_set.failover, session, credentials).map_err(fix_fatal)?;
+    fix_conn.set_failover_reporter(&name, reporters.switches.clone());
+    fix_conn.set_failover_trigger(failover.clone());
+    FeedProtocol::update(&mut fix_conn, &streams).fatal(FatalKind::Network)?;
+
+    let endpoint = fix_conn.active_endpoint().to_string();
 
-    // Create feeds
-    let feeds = vec![Feed::new("TradeFeed", ws_conn)];
+    // Count the messages of each stream of the set
+    let stream_names: Vec<(String, String)> = feed_set
+        .symbols
+        .iter()
+        .map(|symbol| (symbol.clone(), format!("{}@{}@fix", symbol.to_lowercase(), K::SUFFIX)))
+        .collect();
+    let stream_stats = register_stream_stats(&name, &stream_names, metrics)?;
 
-    // Lookup the ring for the first symbol
-    let first_symbol = symbols.first().ok_or("No symbols for trade feed")?;
-    let symbol_id = symbol_info
-        .symbol_id(first_symbol)
-        .ok_or_else(|| format!("Symbol '{}' not found in symbolinfo.yaml", first_symbol))?;
-    let ring_name = format!("TRADE_{}_PS", symbol_id);
-    let ring: DpdkPubSubRing<RawMessage> = dpdk_env.pubsub_lookup::<RawMessage>(&ring_name)?;
+    let name: &'static str = name.leak();
+    let (parser, ring, ring_names) =
+        route_set_rings(ring_env, feed_set, line, symbol_info, metrics, parser.with_stream_stats(stream_stats))?;
 
-    println!(
-        "[TradeFeedGroup] Created with {} symbols, {} workers, ring: {}",
-        symbols.len(),
+    info!(
+        "[{}] Created with {} symbols, {} workers, medium: {}, endpoint: {}, sender: {}, rings: {}",
+        name,
+        feed_set.symbols.len(),
         worker_lcore_ids.len(),
-        ring_name
+        medium.name(),
+        endpoint,
+        sender_comp_id,
+        ring_names.join(", ")
     );
 
-    let config = FeedGroupConfig {
-        name: "TradeFeedGroup",
-        dpdk_env,
-        worker_lcore_ids,
-        publisher: ring,
-        parser: DummyParser,
-        feeds,
-        command_channel_capacity: COMMAND_CHANNEL_CAPACITY,
-        feedback_channel_capacity: FEEDBACK_CHANNEL_CAPACITY,
-    };
+    // Create feeds (one session per feed)
+    build_feedgroup!(name, ring_env, worker_lcore_ids, ring, FixParser::from(parser), fix_conn)
+}
+
+/// Classifies the failure of a FIX session, the refused credentials apart.
+fn fix_fatal(e: FixConnectorError) -> FatalError {
+    let kind = if e.is_auth_failure() { FatalKind::NetworkAuth } else { FatalKind::Network };
+    FatalError::new(kind, e)
+}
+
+/// Registers the statistics of the streams of a FeedGroup, by symbol and stream name.
+fn register_stream_stats(
+    name: &str,
+    stream_names: &[(String, String)],
+    metrics: &Arc<ShmRegion<MetricsRegion>>,
+) -> Result<StreamStatsHandle, FatalError> {
+    StreamStatsHandle::register(metrics.clone(), stream_names, now_ms())
+        .ok_or_else(|| format!("No stream statistics available for '{}'", name))
+        .fatal(FatalKind::SharedState)
+}
+
+/// Looks up the rings of a symbol set, configuring its parser to publish to
+/// them, and returns the ring published to by the workers and the ring names.
+///
+/// The symbols of an aggregated set share a single ring, named after the first
+/// symbol of the set, the consumers demultiplexing them by the symbol IDs of
+/// the message headers. Otherwise each symbol has its own ring, the workers
+/// publishing to the ring of the first symbol and the parser publishing the
+/// messages of the other symbols to their ring, by the symbol IDs of their
+/// headers. The handler of a line publishes to the line rings instead,
+/// arbitrated to the pub-sub rings.
+///
+/// The rings must have been registered with the slot size of the set, the
+/// parser writing the payloads into them, admitting the messages under the
+/// overflow policy of the set and publishing the leading fragments of the
+/// payloads larger than a slot, and the leading events of the payloads
+/// carrying several, itself.
+fn route_set_rings(
+    ring_env: &RingEnv,
+    feed_set: &FeedSet<'_>,
+    line: Option<Line>,
+    symbol_info: &SymbolInfoConfig,
+    metrics: &Arc<ShmRegion<MetricsRegion>>,
+    parser: DummyParser,
+) -> Result<(DummyParser, Ring<RawMessage>, Vec<String>), FatalError> {
+    let mut parser = parser.with_slot_size(feed_set.slot_size);
+    let mut publisher = None;
+    let mut ring_names = Vec::new();
+    for (symbol_id, ring_name) in set_ring_names(feed_set, symbol_info, line)? {
+        let ring_name = ring_name.to_string();
+        metrics.check_layout::<RawMessage>(&ring_name).fatal(FatalKind::SharedState)?;
+        metrics.check_slot_size(&ring_name, feed_set.slot_size).fatal(FatalKind::SharedState)?;
+        let ring_metrics = RingMetricsHandle::lookup(metrics.clone(), &ring_name)
+            .ok_or_else(|| format!("Ring '{}' not found in metrics region", ring_name))
+            .fatal(FatalKind::SharedState)?;
+        let overflow = OverflowGuard::new(feed_set.overflow, ring_metrics.clone());
+        // The payloads larger than a slot are published as fragments, the leading ones by the parser
+        let ring = ring_env.pubsub_lookup::<RawMessage>(&ring_name).fatal(FatalKind::SharedState)?;
+        let sink = FragmentSink::new(GuardedPublisher::new(ring, overflow.clone()));
+        if publisher.is_none() {
+            // The parser admits the messages of the workers' ring before the workers publish them
+            publisher = Some(ring_env.pubsub_lookup::<RawMessage>(&ring_name).fatal(FatalKind::SharedState)?);
+            parser = parser.with_fragments(sink).with_overflow(overflow).with_metrics(ring_metrics);
+            // The parser publishes the leading events of the payloads carrying several, e.g. SBE trades
+            let ring = ring_env.pubsub_lookup::<RawMessage>(&ring_name).fatal(FatalKind::SharedState)?;
+            parser = parser.with_group_ring(FragmentSink::new(ring));
+            // The parser publishes the admitted messages itself to time the publish
+            #[cfg(feature = "latency-histograms")]
+            {
+                let ring = ring_env.pubsub_lookup::<RawMessage>(&ring_name).fatal(FatalKind::SharedState)?;
+                parser = parser.with_publisher(FragmentSink::new(ring));
+            }
+        } else {
+            parser = parser.with_symbol_ring(symbol_id, sink, ring_metrics);
+        }
+        ring_names.push(ring_name);
+    }
+    let publisher = publisher
+        .ok_or_else(|| format!("No symbols configured for '{}'", feed_set.name()))
+        .fatal(FatalKind::Config)?;
+    Ok((parser, publisher, ring_names))
+}
+
+/// Returns the rings of a symbol set by the ID of the symbol they are named
+/// after: the ring of the first symbol of an aggregated set, of every symbol
+/// otherwise, the line rings for the handler of a line.
+fn set_ring_names(
+    feed_set: &FeedSet<'_>,
+    symbol_info: &SymbolInfoConfig,
+    line: Option<Line>,
+) -> Result<Vec<(u32, RingName)>, FatalError> {
+    feed_set
+        .ring_symbols()
+        .iter()
+        .map(|symbol| {
+            let symbol_id = symbol_info
+                .symbol_id(symbol)
+                .ok_or_else(|| format!("Symbol '{}' not found in symbolinfo.yaml", symbol))
+                .fatal(FatalKind::Config)?;
+            let name = RingName::feed(feed_set.kind, symbol_id)?;
+            Ok((symbol_id, line.map_or(name, |line| name.on_line(line))))
+        })
+        .collect()
+}
+
+/// A FeedGroup to create, running a medium of a symbol set.
+struct GroupSpec<'c> {
+    /// The FeedGroup name.
+    name: String,
+    /// The symbol set.
+    feed_set: FeedSet<'c>,
+    /// The medium of the set.
+    medium: &'c Medium,
+    /// The lcores planned for the medium.
+    workers: Vec<DpdkLCoreId>,
+    /// The index of the FeedGroup in the plan, distinguishing its FIX session.
+    index: usize,
+    /// The line published by the FeedGroup, `None` without arbitration.
+    line: Option<Line>,
+}
+
+impl GroupSpec<'_> {
+    /// Returns the endpoints of the medium, primary first (empty for the default endpoint).
+    fn endpoints(&self) -> &[String] {
+        match (self.medium.protocol.as_str(), self.medium.parser.as_str()) {
+            ("fix", _) => self.feed_set.fix_endpoints,
+            (_, "sbe") => self.feed_set.sbe_endpoints,
+            _ => self.feed_set.endpoints,
+        }
+    }
+
+    /// Returns the `SenderCompID` of the FIX session of the FeedGroup, distinct
+    /// between the handlers of the two lines.
+    fn sender_comp_id(&self) -> String {
+        let line = self.line.map(|line| line.as_str().to_uppercase()).unwrap_or_default();
+        format!("{}{}{:02}", FIX_SENDER_COMP_ID_PREFIX, line, self.index)
+    }
+}
+
+/// Plans one FeedGroup per medium of each symbol set of every configured feed,
+/// running on the lcores planned for the medium and publishing to `line`.
+///
+/// # Errors
+/// Returns an error if the line is missing under arbitration, or given without it.
+fn plan_feedgroups<'c>(
+    md_config: &'c HwResourcesConfig,
+    lcore_plan: &LcorePlan,
+    line: Option<Line>,
+) -> Result<Vec<GroupSpec<'c>>, FatalError> {
+    match (&md_config.arbitration, line) {
+        (Some(_), None) => {
+            let detail = "The feeds are arbitrated, the handler must be given its line with --line";
+            return Err(FatalError::new(FatalKind::Config, detail));
+        }
+        (None, Some(line)) => {
+            let detail = format!("Line {} given, but the feeds are not arbitrated", line);
+            return Err(FatalError::new(FatalKind::Config, detail));
+        }
+        _ => {}
+    }
+    let mut specs = Vec::new();
+
+    for feed in md_config.all_feeds() {
+        for feed_set in feed.feed_sets() {
+            let assignment = lcore_plan
+                .assignment(feed_set.kind, feed_set.set)
+                .ok_or_else(|| format!("No lcores planned for '{}'", feed_set.name()))
+                .fatal(FatalKind::Config)?;
+
+            for (index, medium) in feed_set.medium.iter().enumerate() {
+                let name = feed_set.group_name(medium);
+                let workers: Vec<DpdkLCoreId> = assignment
+                    .medium_lcores(index)
+                    .ok_or_else(|| format!("No lcores planned for '{}'", name))
+                    .fatal(FatalKind::Config)?
+                    .iter()
+                    .map(|&cpu| cpu as DpdkLCoreId)
+                    .collect();
+                let index = specs.len();
+                specs.push(GroupSpec { name, feed_set, medium, workers, index, line });
+            }
+        }
+    }
+
+    Ok(specs)
+}
+
+/// Returns the tag of the medium of a spec, failing for the mediums not implemented.
+fn medium_tag(spec: &GroupSpec<'_>) -> Result<MediumTag, FatalError> {
+    // The JSON parser over websocket, and the SBE and FIX parsers for the kinds they provide
+    match (spec.medium.protocol.as_str(), MediumTag::from_parser(&spec.medium.parser)) {
+        ("websocket", Some(tag @ MediumTag::Json)) => Ok(tag),
+        ("websocket", Some(tag @ MediumTag::Sbe)) if matches!(spec.feed_set.kind, "top" | "trade") => Ok(tag),
+        ("fix", Some(tag @ MediumTag::Fix)) if matches!(spec.feed_set.kind, "top" | "trade") => Ok(tag),
+        _ => {
+            let detail = format!("Unsupported medium '{}' for '{}'", spec.medium.name(), spec.name);
+            Err(FatalError::new(FatalKind::Config, detail))
+        }
+    }
+}
+
+/// Returns the stream suffix of a feed kind, `None` for the kinds not implemented.
+fn stream_suffix(kind: &str) -> Option<&'static str> {
+    match kind {
+        "top" => Some(Top::SUFFIX),
+        "trade" => Some(Trade::SUFFIX),
+        "aggtrade" => Some(AggTrade::SUFFIX),
+        "depth" => Some(Depth::SUFFIX),
+        _ => None,
+    }
+}
+
+/// Returns the stream suffix of a feed kind over a medium, the SBE Top feeds
+/// subscribing to the best bid and ask events, `None` for the kinds not implemented.
+fn medium_suffix(kind: &str, tag: MediumTag) -> Option<&'static str> {
+    match (kind, tag) {
+        ("top", MediumTag::Sbe) => Some(SBE_BEST_BID_ASK_SUFFIX),
+        (kind, _) => stream_suffix(kind),
+    }
+}
+
+/// Returns the name a stream is counted by in the metrics region, the SBE
+/// streams told apart from the JSON streams of the same name.
+fn medium_stream(stream: String, tag: MediumTag) -> String {
+    match tag {
+        MediumTag::Sbe => format!("{}@sbe", stream),
+        _ => stream,
+    }
+}
+
+/// Prints the planned FeedGroups with their lcores, endpoints, ring and streams,
+/// failing on the specs the handler couldn't create.
+fn print_plan(specs: &[GroupSpec<'_>], symbol_info: &SymbolInfoConfig) -> Result<(), FatalError> {
+    for spec in specs {
+        let GroupSpec { name, feed_set, medium, workers, .. } = spec;
+        let tag = medium_tag(spec)?;
+        let suffix = medium_suffix(feed_set.kind, tag)
+            .ok_or_else(|| format!("Unsupported feed kind '{}'", feed_set.kind))
+            .fatal(FatalKind::Config)?;
+        if let Some(symbol) = feed_set.symbols.iter().find(|symbol| symbol_info.symbol_id(symbol).is_none()) {
+            let detail = format!("Symbol '{}' not found in symbolinfo.yaml", symbol);
+            return Err(FatalError::new(FatalKind::Config, detail));
+        }
+        let ring_names: Vec<String> =
+            set_ring_names(feed_set, symbol_info, spec.line)?.iter().map(|(_, name)| name.to_string()).collect();
+        if ring_names.is_empty() {
+            return Err(FatalError::new(FatalKind::Config, format!("No symbols configured for '{}'", name)));
+        }
+
+        println!(
+            "{}: lcores {:?}, medium {}, endpoints {:?}, rings {} ({} byte slots)",
+            name,
+            workers,
+            medium.name(),
+            spec.endpoints(),
+            ring_names.join(", "),
+            feed_set.slot_size
+        );
+        if tag == MediumTag::Fix {
+            let md_req_ids: Vec<String> = feed_set
+                .symbols
+                .iter()
+                .map(|symbol| match feed_set.kind {
+                    "top" => FixConn::<Top>::md_req_id(symbol),
+                    _ => FixConn::<Trade>::md_req_id(symbol),
+                })
+                .collect();
+            println!("  sender: {}, requests: {}", spec.sender_comp_id(), md_req_ids.join(", "));
+            continue;
+        }
+        let streams: Vec<String> = feed_set
+            .symbols
+            .iter()
+            .map(|symbol| stream_name(&symbol.to_lowercase(), suffix, medium.update_speed))
+            .collect();
+        println!("  streams: {}", streams.join(", "));
+    }
+    println!("{} FeedGroups planned", specs.len());
+    Ok(())
+}
+
+/// Creates the FeedGroup of a spec, connecting its feed.
+#[allow(clippy::too_many_arguments)]
+fn create_spec_feedgroup<'a>(
+    ring_env: &'a RingEnv,
+    spec: &GroupSpec<'_>,
+    pause: &PauseHandle,
+    failover: &FailoverTrigger,
+    parse_errors: &ParseErrorCounter,
+    connection: Option<&ConnectionStatsHandle>,
+    tracer: Option<&Tracer>,
+    symbol_info: &SymbolInfoConfig,
+    metrics: &Arc<ShmRegion<MetricsRegion>>,
+    last_top: &Arc<ShmRegion<LastTopRegion>>,
+    reporters: &Reporters,
+) -> Result<FeedGroups<'a>, FatalError> {
+    let GroupSpec { name: group_name, feed_set, medium, workers, line, .. } = spec;
+    let workers = workers.clone();
+
+    let tag = medium_tag(spec)?;
+    // Tag the messages with the symbol IDs of their symbols
+    let symbols = feed_set
+        .symbols
+        .iter()
+        .map(|symbol| {
+            symbol_info
+                .symbol_id(symbol)
+                .map(|id| (symbol.clone(), id))
+                .ok_or_else(|| format!("Symbol '{}' not found in symbolinfo.yaml", symbol))
+        })
+        .collect::<Result<Vec<(String, u32)>, String>>()
+        .fatal(FatalKind::Config)?;
+    // Normalize the prices and quantities of the symbols with tick/step exponents
+    let scales: Vec<(u32, SymbolScale)> = symbols
+        .iter()
+        .filter_map(|(symbol, id)| symbol_info.scale(symbol).map(|scale| (*id, scale)))
+        .collect();
+    let mut parser = DummyParser::new(tag)
+        .with_pause(pause.clone())
+        .with_parse_errors(parse_errors.clone())
+        .with_symbol_ids(&symbols)
+        .with_scales(&scales);
+    if let Some(tracer) = tracer {
+        parser = parser.with_tracer(tracer.clone());
+    }
+
+    // Top feeds also overwrite the last-value cache of their symbols
+    if feed_set.kind == "top" {
+        let handle = LastTopHandle::new(last_top.clone(), &symbols)
+            .ok_or_else(|| format!("Symbol IDs of '{}' exceed the last-value cache", group_name))
+            .fatal(FatalKind::Config)?;
+        parser = parser.with_last_top(handle);
+    }
+
+    // Record the latencies of the feedgroup in its latency group, kept across restarts
+    #[cfg(feature = "latency-histograms")]
+    {
+        let recorder = metrics
+            .register_latency_group(group_name)
+            .and_then(|index| LatencyRecorder::new(metrics.clone(), index))
+            .ok_or_else(|| format!("No latency group available for '{}'", group_name))
+            .fatal(FatalKind::SharedState)?;
+        parser = parser.with_latency(recorder);
+    }
+
+    if tag == MediumTag::Fix {
+        let sender = spec.sender_comp_id();
+        return Ok(match feed_set.kind {
+            "top" => create_fix_feedgroup::<Top>(ring_env, feed_set, *line, medium, &sender, parser, symbol_info, metrics, reporters, failover, workers)?.into(),
+            "trade" => create_fix_feedgroup::<Trade>(ring_env, feed_set, *line, medium, &sender, parser, symbol_info, metrics, reporters, failover, workers)?.into(),
+            kind => {
+                return Err(FatalError::new(FatalKind::Config, format!("Unsupported feed kind '{}' over FIX", kind)))
+            }
+        });
+    }
+
+    if tag == MediumTag::Sbe {
+        return Ok(match feed_set.kind {
+            "top" => create_feedgroup::<Top, SbeParser>(ring_env, feed_set, *line, medium, tag, parser, symbol_info, metrics, reporters, failover, connection, workers)?.into(),
+            "trade" => create_feedgroup::<Trade, SbeParser>(ring_env, feed_set, *line, medium, tag, parser, symbol_info, metrics, reporters, failover, connection, workers)?.into(),
+            kind => {
+                return Err(FatalError::new(FatalKind::Config, format!("Unsupported feed kind '{}' over SBE", kind)))
+            }
+        });
+    }
+
+    Ok(match feed_set.kind {
+        "top" => create_feedgroup::<Top, DummyParser>(ring_env, feed_set, *line, medium, tag, parser, symbol_info, metrics, reporters, failover, connection, workers)?.into(),
+        "trade" => create_feedgroup::<Trade, DummyParser>(ring_env, feed_set, *line, medium, tag, parser, symbol_info, metrics, reporters, failover, connection, workers)?.into(),
+        "aggtrade" => create_feedgroup::<AggTrade, DummyParser>(ring_env, feed_set, *line, medium, tag, parser, symbol_info, metrics, reporters, failover, connection, workers)?.into(),
+        "depth" => create_feedgroup::<Depth, DummyParser>(ring_env, feed_set, *line, medium, tag, parser, symbol_info, metrics, reporters, failover, connection, workers)?.into(),
+        kind => return Err(FatalError::new(FatalKind::Config, format!("Unsupported feed kind '{}'", kind))),
+    })
+}
+
+/// A running FeedGroup and its restarts.
+struct RunningGroup<'a, 'c> {
+    /// The spec the FeedGroup was created from, to rebuild it.
+    spec: GroupSpec<'c>,
+    /// The FeedGroup.
+    feedgroup: FeedGroups<'a>,
+    /// The pause state of the FeedGroup, kept across restarts.
+    pause: PauseHandle,
+    /// The trigger failing the feed of the FeedGroup over, kept across restarts.
+    failover: FailoverTrigger,
+    /// The counters of the FeedGroup exposed on `/metrics`, kept across restarts.
+    counters: GroupMetrics,
+    /// The streams of the FeedGroup, by symbol and stream name.
+    streams: Vec<(String, String)>,
+    /// The stale streams of the FeedGroup.
+    staleness: StalenessTracker,
+    /// The handle of the workers, `None` while waiting for a restart.
+    handle: Option<WorkersHandle>,
+    /// The consecutive restarts.
+    restarts: RestartTracker,
+    /// The time of the pending restart.
+    restart_at: Option<Instant>,
+}
+
+/// The state of a FeedGroup reported by the `/status` endpoint.
+#[derive(Debug, Clone, Serialize)]
+struct GroupStatus {
+    /// The FeedGroup name.
+    name: String,
+    /// Whether the workers are running, false while waiting for a restart.
+    running: bool,
+    /// Whether the publishing of the FeedGroup is paused.
+    paused: bool,
+    /// The consecutive restarts.
+    restarts: u32,
+    /// The lcores planned for the workers.
+    lcores: Vec<DpdkLCoreId>,
+    /// The statistics of the websocket connection, `None` for a FIX session.
+    connection: Option<ConnectionStats>,
+}
+
+impl GroupStatus {
+    /// Reads the state of a running FeedGroup.
+    fn of(group: &RunningGroup<'_, '_>) -> Self {
+        Self {
+            name: group.spec.name.clone(),
+            running: group.handle.is_some(),
+            paused: group.pause.is_paused(),
+            restarts: group.restarts.attempts(),
+            lcores: group.spec.workers.clone(),
+            connection: group.counters.connection.as_ref().map(|connection| connection.snapshot(Instant::now())),
+        }
+    }
+}
 
-    Ok(FeedGroup::validated_build(config)?)
+/// The status of the handler reported by the `/status` endpoint.
+#[derive(Debug, Serialize)]
+struct HandlerStatus {
+    /// The trading state of the status table.
+    trading: TradingStatus,
+    /// The FeedGroups, as last published by the main thread.
+    feedgroups: Vec<GroupStatus>,
+    /// The rings and streams of the metrics region.
+    #[serde(flatten)]
+    metrics: MetricsStatus,
+}
+
+/// Starts the workers of a FeedGroup.
+fn run_feedgroup(feedgroup: &mut FeedGroups<'_>) -> Result<WorkersHandle, FatalError> {
+    match feedgroup {
+        FeedGroups::JsonTop(fg) => fg.run(),
+        FeedGroups::JsonTrade(fg) => fg.run(),
+        FeedGroups::JsonAggTrade(fg) => fg.run(),
+        FeedGroups::JsonDepth(fg) => fg.run(),
+        FeedGroups::FixTop(fg) => fg.run(),
+        FeedGroups::FixTrade(fg) => fg.run(),
+        FeedGroups::SbeTop(fg) => fg.run(),
+        FeedGroups::SbeTrade(fg) => fg.run(),
+    }
+    // Spawned on the lcores of the DPDK runtime, or as threads of the process
+    .fatal(if cfg!(feature = "shm-rings") { FatalKind::Host } else { FatalKind::DpdkInit })
+}
+
+/// Polls and handles all pending feedback of a FeedGroup.
+/// Returns true if any feedback was handled.
+fn poll_feedgroup(group_name: &str, feedgroup: &mut FeedGroups<'_>) -> bool {
+    let mut handled = false;
+    match feedgroup {
+        FeedGroups::JsonTop(fg) => {
+            while let Some(feedback) = fg.poll_feedback() {
+                handle_feedback(group_name, feedback);
+                handled = true;
+            }
+        }
+        FeedGroups::JsonTrade(fg) => {
+            while let Some(feedback) = fg.poll_feedback() {
+                handle_feedback(group_name, feedback);
+                handled = true;
+            }
+        }
+        FeedGroups::JsonAggTrade(fg) => {
+            while let Some(feedback) = fg.poll_feedback() {
+                handle_feedback(group_name, feedback);
+                handled = true;
+            }
+        }
+        FeedGroups::JsonDepth(fg) => {
+            while let Some(feedback) = fg.poll_feedback() {
+                handle_feedback(group_name, feedback);
+                handled = true;
+            }
+        }
+        FeedGroups::FixTop(fg) => {
+            while let Some(feedback) = fg.poll_feedback() {
+                handle_feedback(group_name, feedback);
+                handled = true;
+            }
+        }
+        FeedGroups::FixTrade(fg) => {
+            while let Some(feedback) = fg.poll_feedback() {
+                handle_feedback(group_name, feedback);
+                handled = true;
+            }
+        }
+        FeedGroups::SbeTop(fg) => {
+            while let Some(feedback) = fg.poll_feedback() {
+                handle_feedback(group_name, feedback);
+                handled = true;
+            }
+        }
+        FeedGroups::SbeTrade(fg) => {
+            while let Some(feedback) = fg.poll_feedback() {
+                handle_feedback(group_name, feedback);
+                handled = true;
+            }
+        }
+    }
+    handled
 }
 
 /// Handles feedback from a FeedGroup worker.
@@ -167,20 +1028,20 @@ where
         FeedGroupWorkerFeedback::FeedGroupWorkerCommandAck(ack) => match ack {
             FeedGroupWorkerCommandAck::AddFeed(removed) => {
                 if let Some(_feed) = removed {
-                    println!("[{}] AddFeed: replaced existing feed", group_name);
+                    info!("[{}] AddFeed: replaced existing feed", group_name);
                 } else {
-                    println!("[{}] AddFeed: new feed added", group_name);
+                    info!("[{}] AddFeed: new feed added", group_name);
                 }
             }
             FeedGroupWorkerCommandAck::RemoveFeed(removed) => {
                 if removed.is_some() {
-                    println!("[{}] RemoveFeed: feed removed", group_name);
+                    info!("[{}] RemoveFeed: feed removed", group_name);
                 } else {
-                    println!("[{}] RemoveFeed: feed not found", group_name);
+                    info!("[{}] RemoveFeed: feed not found", group_name);
                 }
             }
             FeedGroupWorkerCommandAck::AddStream(is_new) => {
-                println!(
+                info!(
                     "[{}] AddStream: {}",
                     group_name,
                     if is_new { "newly added" } else { "already existed" }
@@ -188,37 +1049,354 @@ where
             }
             FeedGroupWorkerCommandAck::RemoveStream(removed) => {
                 if removed.is_some() {
-                    println!("[{}] RemoveStream: stream removed", group_name);
+                    info!("[{}] RemoveStream: stream removed", group_name);
                 } else {
-                    println!("[{}] RemoveStream: stream not found", group_name);
+                    info!("[{}] RemoveStream: stream not found", group_name);
                 }
             }
         },
     }
 }
 
-fn main() -> Result<(), Box<dyn Error>> {
-    println!("=== Binance Spot Market Data Handler ===");
-    println!("Starting as DPDK secondary process...\n");
+/// Publishes an alert to the alerts ring, only logged if the ring rejects it.
+fn raise_alert(alerts: &impl RingPublisher<AlertMessage>, kind: AlertKind, severity: AlertSeverity, detail: &str) {
+    let alert = AlertMessage::new(kind, severity, now_ms(), ALERT_SOURCE, detail);
+    if let Err(e) = alerts.publish(&alert) {
+        warn!("Failed to publish {:?} alert to {}: {:?}", kind, ALERTS_RING_NAME, e);
+    }
+}
 
-    // Load configurations
-    let md_config = HwResourcesConfig::from_file(MD_CONFIG_PATH)?;
-    let symbol_info = SymbolInfoConfig::from_file(SYMBOL_INFO_PATH)?;
+/// Schedules the restart of an exited FeedGroup after its backoff.
+///
+/// # Errors
+/// Returns an error of the class of the exit, `kind`, to shut the handler down
+/// if the restarts are exhausted.
+fn schedule_restart(
+    group: &mut RunningGroup<'_, '_>,
+    alerts: &impl RingPublisher<AlertMessage>,
+    kind: FatalKind,
+) -> Result<(), FatalError> {
+    match group.restarts.on_exit(Instant::now()) {
+        RestartDecision::Restart { attempt, backoff } => {
+            warn!("[{}] Restarting in {:?} (attempt {})", group.spec.name, backoff, attempt);
+            group.restart_at = Some(Instant::now() + backoff);
+            Ok(())
+        }
+        RestartDecision::Escalate { attempts } => {
+            let detail = format!("[{}] Exited after {} restarts, shutting down", group.spec.name, attempts);
+            error!("{}", detail);
+            raise_alert(alerts, AlertKind::WorkerFailure, AlertSeverity::Critical, &detail);
+            Err(FatalError::new(kind, detail))
+        }
+    }
+}
 
-    println!("Loaded market data config from: {}", MD_CONFIG_PATH);
-    println!("Loaded symbol info from: {}", SYMBOL_INFO_PATH);
-    println!("Main CPU: {}", md_config.main_cpu);
-    println!("Worker CPUs: {:?}", md_config.worker_cpus);
-    println!();
+/// Applies a PauseFeed/ResumeFeed command to the FeedGroups of its target,
+/// acknowledging it through the alerts ring.
+///
+/// The target is a FeedGroup or set name, a feed kind, or a symbol, every
+/// FeedGroup if empty. Pausing a symbol only drops its messages.
+fn apply_feed_command(
+    groups: &[RunningGroup<'_, '_>],
+    message: &ControlMessage,
+    alerts: &impl RingPublisher<AlertMessage>,
+) {
+    let paused = match message.command() {
+        ControlCommand::PauseFeed => true,
+        ControlCommand::ResumeFeed => false,
+        // Trading and strategy commands, applied by the OMS, the strategies and their hosts
+        _ => return,
+    };
+    let target = message.target();
 
-    // Collect all lcore IDs needed
-    let main_lcore_id = md_config.main_cpu as DpdkLCoreId;
-    let worker_cpus: Vec<DpdkLCoreId> = md_config
-        .worker_cpus
-        .clone()
-        .map(|cpu| cpu as DpdkLCoreId)
+    let mut matched = Vec::new();
+    for group in groups {
+        let GroupSpec { name, feed_set, .. } = &group.spec;
+        let changed = if target.is_empty() || target == name.as_str() || target == feed_set.name() || target == feed_set.kind {
+            group.pause.set_paused(paused)
+        } else if let Some(changed) = group.pause.set_symbol_paused(target, paused) {
+            changed
+        } else {
+            continue;
+        };
+        matched.push(format!("{}{}", name, if changed { "" } else { " (unchanged)" }));
+    }
+
+    let action = if paused { "Paused" } else { "Resumed" };
+    let target = if target.is_empty() { "*" } else { target };
+    if matched.is_empty() {
+        let detail = format!("{} '{}': no matching feedgroup", action, target);
+        warn!("{}", detail);
+        raise_alert(alerts, AlertKind::ControlAck, AlertSeverity::Warning, &detail);
+        return;
+    }
+    let detail = format!("{} '{}' on {}", action, target, matched.join(", "));
+    info!("{} ({})", detail, message.reason());
+    raise_alert(alerts, AlertKind::ControlAck, AlertSeverity::Info, &detail);
+}
+
+/// Pauses every FeedGroup once a circuit breaker trips, returning true if it just tripped.
+///
+/// The feeds are resumed by the ResumeFeed command of `ctl-admin reset-breaker`.
+fn check_breaker(
+    groups: &[RunningGroup<'_, '_>],
+    status: &StatusRegion,
+    tripped: &mut bool,
+    alerts: &impl RingPublisher<AlertMessage>,
+) -> bool {
+    let now_tripped = status.is_tripped();
+    if now_tripped == *tripped {
+        return false;
+    }
+    *tripped = now_tripped;
+    if !now_tripped {
+        return false;
+    }
+    for group in groups {
+        group.pause.set_paused(true);
+    }
+    let detail = format!("Circuit breaker tripped by {:?}, paused every feedgroup", status.tripped_by());
+    error!("{}", detail);
+    raise_alert(alerts, AlertKind::BreakerTripped, AlertSeverity::Critical, &detail);
+    true
+}
+
+/// Handles an endpoint switch reported by a feed.
+fn handle_endpoint_switch(switch: EndpointSwitch, alerts: &impl RingPublisher<AlertMessage>) {
+    let reason = match switch.reason {
+        SwitchReason::Failures(n) => format!("{} consecutive failures", n),
+        SwitchReason::Stale(elapsed) => format!("no data for {:?}", elapsed),
+        SwitchReason::Requested => "a stale stream".to_string(),
+    };
+    let detail = format!("[{}] Switched endpoint {} -> {} after {}", switch.feed, switch.from, switch.to, reason);
+    warn!("{}", detail);
+    raise_alert(alerts, AlertKind::EndpointFailover, AlertSeverity::Warning, &detail);
+}
+
+/// Handles a subscription drift reported by a feed, its missing streams already
+/// resubscribed, recording the resubscription in the audit journal.
+fn handle_subscription_drift(
+    drift: SubscriptionDrift,
+    alerts: &impl RingPublisher<AlertMessage>,
+    audit: &AuditJournal,
+) {
+    let detail = format!(
+        "[{}] Subscriptions drifted on {}: {} missing (resubscribed) {:?}, {} unexpected {:?}",
+        drift.feed,
+        drift.endpoint,
+        drift.missing.len(),
+        drift.missing,
+        drift.unexpected.len(),
+        drift.unexpected
+    );
+    warn!("{}", detail);
+    raise_alert(alerts, AlertKind::SubscriptionDrift, AlertSeverity::Warning, &detail);
+    if !drift.missing.is_empty() {
+        let action = AuditAction::Resubscribe { feed: drift.feed, streams: drift.missing };
+        record_audit(audit, action, &format!("subscriptions drifted on {}", drift.endpoint));
+    }
+}
+
+/// Polls and handles all subscription drifts reported by the feeds.
+/// Returns true if any drift was handled.
+fn poll_subscription_drifts(
+    drifts: &Receiver<SubscriptionDrift>,
+    alerts: &impl RingPublisher<AlertMessage>,
+    audit: &AuditJournal,
+) -> bool {
+    let mut handled = false;
+    while let Ok(drift) = drifts.try_recv() {
+        handle_subscription_drift(drift, alerts, audit);
+        handled = true;
+    }
+    handled
+}
+
+/// Polls and logs the responses to the subscription requests of the feeds.
+/// Returns true if any response was handled.
+fn poll_request_acks(acks: &Receiver<RequestAck>) -> bool {
+    let mut handled = false;
+    while let Ok(RequestAck { feed, ack }) = acks.try_recv() {
+        match &ack.response {
+            WSResponse::Result { .. } => info!("[{}] Request {:?} acknowledged: {:?}", feed, ack.id, ack.request),
+            response => warn!("[{}] Request {:?} rejected: {:?}, request: {:?}", feed, ack.id, response, ack.request),
+        }
+        handled = true;
+    }
+    handled
+}
+
+/// Samples the message rates of the streams, logging the busiest. The silent
+/// streams are alerted on by `check_staleness`.
+fn sample_streams(metrics: &MetricsRegion, elapsed: Duration) {
+    let elapsed_ms = elapsed.as_millis() as u64;
+    for stats in metrics.registered_streams() {
+        stats.sample(elapsed_ms);
+    }
+    let busiest = StreamReport::busiest(metrics.registered_streams(), BUSIEST_STREAMS);
+    let busiest: Vec<String> = busiest.iter().map(|rate| format!("{} {}/s", rate.stream, rate.rate)).collect();
+    info!("Busiest streams: {}", busiest.join(", "));
+}
+
+/// Checks the streams of the running FeedGroups against the staleness
+/// thresholds of their symbols, alerting on the streams turning stale and
+/// applying the action of their feed. Returns true if any stream changed.
+///
+/// The FeedGroups restarting are skipped, their streams counting again once restarted.
+fn check_staleness(
+    groups: &mut [RunningGroup<'_, '_>],
+    metrics: &MetricsRegion,
+    status: &StatusRegion,
+    control: &impl RingPublisher<ControlMessage>,
+    alerts: &impl RingPublisher<AlertMessage>,
+    audit: &AuditJournal,
+) -> bool {
+    let now_ms = now_ms();
+    let last_messages: HashMap<String, u64> = metrics
+        .registered_streams()
+        .map(|stats| (stats.name(), stats.last_message_ms.load(Ordering::Relaxed)))
         .collect();
 
+    let mut changed = false;
+    for group in groups.iter_mut() {
+        if group.handle.is_none() {
+            group.staleness.clear();
+            continue;
+        }
+        for (symbol, stream) in &group.streams {
+            let Some(&last_message_ms) = last_messages.get(stream) else {
+                continue;
+            };
+            match group.staleness.check(stream, symbol, last_message_ms, now_ms) {
+                Some(StaleChange::Stale { elapsed_ms, after_ms }) => {
+                    let detail = format!(
+                        "[{}] Stream {} stale for {} ms, expected within {} ms",
+                        group.spec.name, stream, elapsed_ms, after_ms
+                    );
+                    warn!("{}", detail);
+                    raise_alert(alerts, AlertKind::SilentStream, AlertSeverity::Warning, &detail);
+                    apply_stale_action(group, stream, status, control, audit);
+                }
+                Some(StaleChange::Recovered) => {
+                    let detail = format!("[{}] Stream {} receiving again", group.spec.name, stream);
+                    info!("{}", detail);
+                    raise_alert(alerts, AlertKind::SilentStream, AlertSeverity::Info, &detail);
+                }
+                None => continue,
+            }
+            changed = true;
+        }
+    }
+    changed
+}
+
+/// Applies the staleness action of the feed of a FeedGroup to a stale stream.
+fn apply_stale_action(
+    group: &RunningGroup<'_, '_>,
+    stream: &str,
+    status: &StatusRegion,
+    control: &impl RingPublisher<ControlMessage>,
+    audit: &AuditJournal,
+) {
+    match group.staleness.policy().action {
+        StaleAction::Alert => {}
+        StaleAction::Failover => {
+            if group.failover.request() {
+                warn!("[{}] Failing over on stale stream {}", group.spec.name, stream);
+            }
+        }
+        StaleAction::Halt => {
+            // Recorded in the status table first, like the kill switch
+            let reason = format!("stale {}", stream);
+            record_audit(audit, AuditAction::Halt, &reason);
+            status.halt(now_ms());
+            match control.publish(&ControlMessage::new(ControlCommand::Halt, now_ms(), &reason)) {
+                Ok(_) => error!("[{}] Halted trading on stale stream {}", group.spec.name, stream),
+                Err(e) => error!("Failed to publish HALT to {}: {:?}", CONTROL_RING_NAME, e),
+            }
+        }
+    }
+}
+
+/// Records an action of the handler in the audit journal, the action standing
+/// even if the journal fails.
+fn record_audit(audit: &AuditJournal, action: AuditAction, reason: &str) {
+    if let Err(e) = audit.record(action, reason) {
+        error!("Failed to record in the audit journal: {}", e);
+    }
+}
+
+/// Returns the streams of the FeedGroup of a spec, by symbol and stream name,
+/// as counted in the metrics region.
+fn spec_streams(spec: &GroupSpec<'_>) -> Vec<(String, String)> {
+    let tag = MediumTag::from_parser(&spec.medium.parser).unwrap_or(MediumTag::Unknown);
+    let Some(suffix) = medium_suffix(spec.feed_set.kind, tag) else {
+        return Vec::new();
+    };
+    spec.feed_set
+        .symbols
+        .iter()
+        .map(|symbol| {
+            let stream = if spec.medium.protocol == "fix" {
+                format!("{}@{}@fix", symbol.to_lowercase(), suffix)
+            } else {
+                medium_stream(stream_name(&symbol.to_lowercase(), suffix, spec.medium.update_speed), tag)
+            };
+            (symbol.clone(), stream)
+        })
+        .collect()
+}
+
+/// Polls and handles all endpoint switches reported by the feeds, counting
+/// them as reconnections of their FeedGroup. Returns true if any switch was handled.
+fn poll_endpoint_switches(
+    switches: &Receiver<EndpointSwitch>,
+    groups: &[RunningGroup<'_, '_>],
+    alerts: &impl RingPublisher<AlertMessage>,
+) -> bool {
+    let mut handled = false;
+    while let Ok(switch) = switches.try_recv() {
+        if let Some(group) = groups.iter().find(|group| group.spec.name == switch.feed) {
+            group.counters.record_failover();
+        }
+        handle_endpoint_switch(switch, alerts);
+        handled = true;
+    }
+    handled
+}
+
+/// Handles a consumer lag alert raised by the metrics region.
+fn handle_lag_alert(alert: LagAlert, alerts: &impl RingPublisher<AlertMessage>) {
+    let detail = format!(
+        "Ring {} consumer {} ({}) lagging by {}/{} messages, about to be sped past",
+        alert.ring, alert.consumer, alert.consumer_name, alert.lag, alert.ring_size
+    );
+    warn!("{}", detail);
+    raise_alert(alerts, AlertKind::ConsumerLag, AlertSeverity::Warning, &detail);
+}
+
+/// Verifies the host, then initializes DPDK as a secondary process of ctl-resource-manager.
+#[cfg(not(feature = "shm-rings"))]
+fn init_rings(
+    md_config: &HwResourcesConfig,
+    scheduling: &SchedulingPolicy,
+    main_lcore_id: DpdkLCoreId,
+    worker_cpus: &[DpdkLCoreId],
+) -> Result<RingEnv, FatalError> {
+    // Verify the host before the EAL initialization, which reports its failures cryptically
+    Preflight::new()
+        .require_hugepages_mounted()
+        .require_lcores(&[main_lcore_id])
+        .require_isolated_lcores(worker_cpus)
+        .require_capabilities(&[Capability::IpcLock])
+        .require_capabilities(&scheduling.capabilities())
+        .require_primary()
+        .with_file_prefix(md_config.eal.file_prefix())
+        .run()?;
+
+    // Before the EAL, deriving the affinity of its control threads from the process
+    scheduling.apply()?;
+
     // All lcores = main + workers
     let mut all_lcores = vec![main_lcore_id];
     all_lcores.extend(worker_cpus.iter().cloned());
@@ -228,126 +1406,402 @@ fn main() -> Result<(), Box<dyn Error>> {
         .process_type(DpdkProcessType::Secondary)
         .lcore_ids(all_lcores)
         .main_lcore_id(main_lcore_id)
-        .build()?;
+        .eal_args(md_config.eal.secondary_args())
+        .build()
+        .fatal(FatalKind::DpdkInit)?;
 
-    println!("DPDK environment initialized as secondary process");
-    println!();
+    info!("DPDK environment initialized as secondary process");
+    Ok(dpdk_env)
+}
 
-    // Allocate worker CPUs to feed groups
-    // For now, split workers evenly between configured feed kinds
-    let mut available_workers = worker_cpus.clone();
-    let num_feed_kinds = md_config.all_feeds().count();
-    let workers_per_kind = if num_feed_kinds > 0 {
-        available_workers.len() / num_feed_kinds
-    } else {
-        0
-    };
+/// Applies the scheduling of the threads, the shared memory rings needing
+/// neither hugepages, isolated lcores nor an EAL.
+#[cfg(feature = "shm-rings")]
+fn init_rings(
+    _md_config: &HwResourcesConfig,
+    scheduling: &SchedulingPolicy,
+    _main_lcore_id: DpdkLCoreId,
+    _worker_cpus: &[DpdkLCoreId],
+) -> Result<RingEnv, FatalError> {
+    Preflight::new().require_capabilities(&scheduling.capabilities()).run()?;
+    scheduling.apply()?;
 
-    // Track all handles for multi-join
-    let mut handles: Vec<MultiJoinHandle<Result<(), atx_feed::FeedGroupError>>> = Vec::new();
+    info!("Publishing to the rings on shared memory files, without DPDK");
+    Ok(ShmRings)
+}
 
-    // Create Top FeedGroup if configured
-    let mut top_feedgroup = if md_config.find_feed("top").is_some() {
-        let top_workers: Vec<DpdkLCoreId> = available_workers
-            .drain(..workers_per_kind.min(available_workers.len()))
-            .collect();
+fn main() -> ExitCode {
+    ctl_core::exit_code(run())
+}
 
-        if !top_workers.is_empty() {
-            Some(create_top_feedgroup(
-                &dpdk_env,
-                &md_config,
-                &symbol_info,
-                top_workers,
-            )?)
-        } else {
-            println!("[Warning] No workers available for TopFeedGroup");
-            None
-        }
-    } else {
-        None
-    };
+fn run() -> Result<(), FatalError> {
+    let args = Args::parse();
+    let log = ctl_log::init_from_file(&args.log_config).fatal(FatalKind::Config)?;
 
-    // Create Trade FeedGroup if configured
-    let mut trade_feedgroup = if md_config.find_feed("trade").is_some() {
-        let trade_workers: Vec<DpdkLCoreId> = available_workers
-            .drain(..workers_per_kind.min(available_workers.len()))
-            .collect();
+    info!("=== Binance Spot Market Data Handler ===");
+    #[cfg(not(feature = "shm-rings"))]
+    info!("Starting as DPDK secondary process...");
 
-        if !trade_workers.is_empty() {
-            Some(create_trade_feedgroup(
-                &dpdk_env,
-                &md_config,
-                &symbol_info,
-                trade_workers,
-            )?)
-        } else {
-            println!("[Warning] No workers available for TradeFeedGroup");
-            None
+    // Load configurations
+    let mut md_config = HwResourcesConfig::from_file(&args.md_config).fatal(FatalKind::Config)?;
+    md_config.set_default_endpoint(&args.ws_endpoint);
+    let symbol_info = SymbolInfoConfig::from_file(&args.symbol_info).fatal(FatalKind::Config)?;
+    let polling = PollingConfig::from_file(&args.polling_config)?.policy(POLLING_COMPONENT, DEFAULT_POLLING);
+    let scheduling = SchedulingConfig::from_file(&args.scheduling_config)?.policy(SCHEDULING_COMPONENT);
+    let health = HealthConfig::from_file(&args.health_config)?.endpoint(HEALTH_COMPONENT).cloned();
+    let telemetry = TelemetryConfig::from_file(&args.telemetry_config)?.policy(TELEMETRY_COMPONENT);
+
+    info!("Loaded market data config from: {}", args.md_config.display());
+    info!("Loaded symbol info from: {}", args.symbol_info.display());
+    info!("Default endpoint: {}", args.ws_endpoint);
+    info!("Main thread polling: {:?}", polling);
+    info!("Scheduling: {:?}", scheduling);
+    info!("Health endpoint: {:?}", health);
+    info!("Telemetry: {:?}", telemetry);
+    info!("Main CPU: {}", md_config.main_cpu);
+    info!("Worker CPUs: {:?}", md_config.worker_cpus);
+    if let Some(line) = args.line {
+        info!("Publishing line {} of the arbitrated feeds", line);
+    }
+
+    // Attach to the regions of the controller instance
+    md_config.eal.apply_region_prefix();
+
+    // Plan the worker lcores of each feed/set according to their num_cpus
+    let lcore_plan = LcorePlan::from_config(&md_config).fatal(FatalKind::Config)?;
+    for assignment in &lcore_plan.assignments {
+        info!(
+            "Planned {}{}: lcores {:?}",
+            assignment.kind,
+            assignment.set.as_ref().map(|s| format!("/{}", s)).unwrap_or_default(),
+            assignment.lcores
+        );
+    }
+    if !lcore_plan.unused.is_empty() {
+        info!("Unused lcores: {:?}", lcore_plan.unused);
+    }
+
+    if args.check {
+        print_plan(&plan_feedgroups(&md_config, &lcore_plan, args.line)?, &symbol_info)?;
+        println!("Configuration OK");
+        return Ok(());
+    }
+
+    // Before the scheduling, so that the exporter of the spans isn't pinned with the main thread
+    let tracer = telemetry.tracer(TELEMETRY_COMPONENT).fatal(FatalKind::Host)?;
+
+    // Record the actions of the handler along with those of the operators
+    let audit = AuditJournal::open(&args.audit_journal, AUDIT_COMPONENT)?;
+    info!("Recording actions in audit journal: {}", args.audit_journal.display());
+    let reloads = audit.clone();
+    log.on_reload(move |path| {
+        record_audit(&reloads, AuditAction::ConfigReload { path: path.display().to_string() }, "");
+    });
+
+    // Collect all lcore IDs needed
+    let main_lcore_id = md_config.main_cpu as DpdkLCoreId;
+    let worker_cpus: Vec<DpdkLCoreId> = md_config
+        .worker_cpus
+        .clone()
+        .map(|cpu| cpu as DpdkLCoreId)
+        .collect();
+
+    // Executed anew after a restart of ctl-resource-manager, wait for its next run to attach to
+    if let Some(stale) = reattaching() {
+        info!("Reattaching after the run epoch {}, waiting for the resource manager...", stale);
+        let epoch = wait_for_run(STATUS_REGION_NAME, stale, REATTACH_POLL_INTERVAL);
+        info!("Resource manager started the run epoch {}", epoch);
+    }
+
+    let ring_env = init_rings(&md_config, &scheduling, main_lcore_id, &worker_cpus)?;
+
+    // Calibrate the timestamps of the hot path before the first message
+    info!("Timestamps from the {:?} clock ({:?} Hz)", ctl_time::calibrate(), ctl_time::tsc_hz());
+
+    // Attach to the metrics region created by ctl-resource-manager
+    let metrics = Arc::new(ShmRegion::<MetricsRegion>::open(METRICS_REGION_NAME)?);
+    info!("Attached to metrics region: {}", METRICS_REGION_NAME);
+
+    // Attach to the last-value Top region created by ctl-resource-manager
+    let last_top = Arc::new(ShmRegion::<LastTopRegion>::open(LAST_TOP_REGION_NAME)?);
+    info!("Attached to last-value Top region: {}", LAST_TOP_REGION_NAME);
+
+    // Attach to the status region created by ctl-resource-manager
+    let status = Arc::new(ShmRegion::<StatusRegion>::open(STATUS_REGION_NAME)?);
+    info!("Attached to status region: {}", STATUS_REGION_NAME);
+
+    // Record the run of ctl-resource-manager attached to, its regions and rings
+    // being stale once it restarts
+    let epoch = EpochWatch::attach(STATUS_REGION_NAME, &status)?;
+    info!("Attached to run epoch {}", epoch.attached());
+
+    // Look up the alerts ring created by ctl-resource-manager
+    let alerts = ring_env.pubsub_lookup::<AlertMessage>(ALERTS_RING_NAME).fatal(FatalKind::SharedState)?;
+    metrics.check_layout::<AlertMessage>(ALERTS_RING_NAME).fatal(FatalKind::SharedState)?;
+    info!("Publishing alerts to: {}", ALERTS_RING_NAME);
+
+    // Follow the control ring for the feed commands
+    let control = ring_env.pubsub_lookup::<ControlMessage>(CONTROL_RING_NAME).fatal(FatalKind::SharedState)?;
+    metrics.check_layout::<ControlMessage>(CONTROL_RING_NAME).fatal(FatalKind::SharedState)?;
+    #[cfg(not(feature = "shm-rings"))]
+    let mut control_consumer = control.attach_consumer().fatal(FatalKind::SharedState)?;
+    #[cfg(feature = "shm-rings")]
+    let mut control_consumer = RingLike::consumer(&control);
+    info!("Following commands of: {}", CONTROL_RING_NAME);
+
+    // Endpoint switches, subscription drifts and request responses are reported by the feeds running on the workers
+    let (switch_tx, switch_rx) = mpsc::channel::<EndpointSwitch>();
+    let (drift_tx, drift_rx) = mpsc::channel::<SubscriptionDrift>();
+    let (ack_tx, ack_rx) = mpsc::channel::<RequestAck>();
+
+    // Probe the candidate endpoints before creating the feeds, then every interval off the hot path
+    let ranking = match &md_config.probe {
+        Some(probe) => {
+            let prober = EndpointProber::new(probe.clone(), EndpointRanking::new());
+            prober.probe_all();
+            for result in prober.ranking().probes() {
+                match result.latency() {
+                    Some(latency) => info!(
+                        "Endpoint {}: {:?} (TCP {:?}, TLS {:?}, first message {:?})",
+                        result.endpoint,
+                        latency,
+                        result.tcp_connect,
+                        result.tls_handshake,
+                        result.first_message
+                    ),
+                    None => warn!("Endpoint {}: probe failed: {}", result.endpoint, result.error.unwrap_or_default()),
+                }
+            }
+            let ranking = prober.ranking().clone();
+            prober.spawn().fatal(FatalKind::Host)?;
+            info!("Probing {} endpoints every {} ms", probe.endpoints.len(), probe.interval_ms);
+            Some(ranking)
         }
-    } else {
-        None
+        None => None,
     };
+    let reporters = Reporters { switches: switch_tx, drifts: drift_tx, acks: ack_tx, ranking: ranking.clone() };
+
+    // Create one FeedGroup per medium of each symbol set
+    let specs = plan_feedgroups(&md_config, &lcore_plan, args.line)?;
+    let mut groups = Vec::new();
+    for spec in specs {
+        let pause = PauseHandle::new(spec.feed_set.symbols);
+        let failover = FailoverTrigger::new();
+        let streams = spec_streams(&spec);
+        let mut counters =
+            GroupMetrics::new(spec.feed_set.kind, spec.feed_set.set, &spec.medium.name(), streams.clone());
+        // The websocket connections track their frames, kept across restarts
+        if spec.medium.protocol != "fix" {
+            counters = counters.with_connection(ConnectionStatsHandle::new());
+        }
+        let feedgroup = create_spec_feedgroup(
+            &ring_env,
+            &spec,
+            &pause,
+            &failover,
+            &counters.parse_errors,
+            counters.connection.as_ref(),
+            tracer.as_ref(),
+            &symbol_info,
+            &metrics,
+            &last_top,
+            &reporters,
+        )?;
+        let staleness = StalenessTracker::new(spec.feed_set.staleness.clone());
+        groups.push(RunningGroup {
+            spec,
+            feedgroup,
+            pause,
+            failover,
+            counters,
+            streams,
+            staleness,
+            handle: None,
+            restarts: RestartTracker::new(md_config.restart, Instant::now()),
+            restart_at: None,
+        });
+    }
 
     // Run all feedgroups
-    println!("\nStarting FeedGroup workers...\n");
+    info!("Starting FeedGroup workers...");
+    info!("Restart policy: {:?}", md_config.restart);
 
-    if let Some(ref mut fg) = top_feedgroup {
-        let handle = fg.run()?;
-        println!("[TopFeedGroup] Workers started on lcores: {:?}", handle.lcore_ids());
-        handles.push(handle);
+    for group in groups.iter_mut() {
+        let handle = run_feedgroup(&mut group.feedgroup)?;
+        info!("[{}] Workers started on lcores: {:?}", group.spec.name, handle.lcore_ids());
+        group.handle = Some(handle);
     }
 
-    if let Some(ref mut fg) = trade_feedgroup {
-        let handle = fg.run()?;
-        println!("[TradeFeedGroup] Workers started on lcores: {:?}", handle.lcore_ids());
-        handles.push(handle);
+    // Serve the health and status endpoints off the hot path, the FeedGroups
+    // being published by the main thread along with its lag checks
+    let heartbeat = Heartbeat::new();
+    let group_status = Arc::new(Mutex::new(groups.iter().map(GroupStatus::of).collect::<Vec<_>>()));
+    if let Some(endpoint) = &health {
+        let (region, status, group_status) = (metrics.clone(), status.clone(), group_status.clone());
+        let counters: Vec<GroupMetrics> = groups.iter().map(|group| group.counters.clone()).collect();
+        let exposed = metrics.clone();
+        let routes = HealthRoutes::new(move || {
+            let report = HandlerStatus {
+                trading: status.snapshot(),
+                feedgroups: group_status.lock().map(|groups| groups.clone()).unwrap_or_default(),
+                metrics: MetricsStatus::from_region(&region),
+            };
+            serde_json::to_string(&report).unwrap_or_default()
+        })
+        .with_metrics(move || {
+            let probes = ranking.as_ref().map(EndpointRanking::probes).unwrap_or_default();
+            render_metrics(&counters, &exposed, &probes)
+        });
+        let server = HealthServer::spawn(endpoint, heartbeat.clone(), routes).fatal(FatalKind::Host)?;
+        let paths = if endpoint.metrics { "/healthz, /status and /metrics" } else { "/healthz and /status" };
+        info!("Serving {} on {}", paths, server.local_addr());
     }
 
-    println!("\n=== Market Data Handler Running ===");
-    println!("Polling for feedback and monitoring workers...\n");
+    info!("=== Market Data Handler Running ===");
+    info!("Polling for feedback and monitoring workers...");
 
     // Main coordination loop
+    let mut poller = Poller::new(polling);
+    let mut last_lag_check = Instant::now();
+    let mut last_stream_sample = Instant::now();
+    let mut last_staleness_check = Instant::now();
+    let mut last_epoch_check = Instant::now();
+    let mut breaker_tripped = false;
     loop {
+        heartbeat.beat(now_ms());
+
         // Poll feedback from all feedgroups
-        if let Some(ref mut fg) = top_feedgroup {
-            while let Some(feedback) = fg.poll_feedback() {
-                handle_feedback("TopFeedGroup", feedback);
+        let mut did_work = false;
+        for group in groups.iter_mut() {
+            did_work |= poll_feedgroup(&group.spec.name, &mut group.feedgroup);
+        }
+        did_work |= poll_endpoint_switches(&switch_rx, &groups, &alerts);
+        did_work |= poll_subscription_drifts(&drift_rx, &alerts, &audit);
+        did_work |= poll_request_acks(&ack_rx);
+        did_work |= check_breaker(&groups, &status, &mut breaker_tripped, &alerts);
+
+        // Apply the feed commands of the control ring
+        loop {
+            match consume!(control_consumer) {
+                RingConsume::Message(message) => {
+                    apply_feed_command(&groups, &message, &alerts);
+                    did_work = true;
+                }
+                RingConsume::SpedPast => {
+                    warn!("Control consumer overtaken by producer, some commands missed");
+                }
+                RingConsume::InFlight | RingConsume::Empty => break,
             }
         }
 
-        if let Some(ref mut fg) = trade_feedgroup {
-            while let Some(feedback) = fg.poll_feedback() {
-                handle_feedback("TradeFeedGroup", feedback);
+        // Check consumer lag against the producers
+        if last_lag_check.elapsed() >= LAG_CHECK_INTERVAL {
+            for ring in metrics.registered() {
+                for alert in ring.check_lag(LAG_ALERT_THRESHOLD_PCT) {
+                    handle_lag_alert(alert, &alerts);
+                }
+            }
+            if let Ok(mut statuses) = group_status.lock() {
+                *statuses = groups.iter().map(GroupStatus::of).collect();
+            }
+            last_lag_check = Instant::now();
+        }
+
+        // Sample the message rates of the streams
+        let elapsed = last_stream_sample.elapsed();
+        if elapsed >= STREAM_SAMPLE_INTERVAL {
+            sample_streams(&metrics, elapsed);
+            last_stream_sample = Instant::now();
+        }
+
+        // Check the streams against the staleness thresholds of their symbols
+        if last_staleness_check.elapsed() >= STALENESS_CHECK_INTERVAL {
+            did_work |= check_staleness(&mut groups, &metrics, &status, &control, &alerts, &audit);
+            last_staleness_check = Instant::now();
+        }
+
+        // Reattach on a restart of ctl-resource-manager, before using its stale regions and rings
+        if last_epoch_check.elapsed() >= EPOCH_CHECK_INTERVAL {
+            if let Err(e) = epoch.check() {
+                warn!("{}, reattaching", e);
+                return Err(reattach(&epoch));
             }
+            last_epoch_check = Instant::now();
         }
 
-        // Check if any workers have completed/errored using try_join
-        for (i, handle) in handles.iter().enumerate() {
-            if let Some(result) = handle.try_join() {
-                match result {
-                    Ok(results) => {
-                        for (j, worker_result) in results.into_iter().enumerate() {
-                            if let Err(e) = worker_result {
-                                eprintln!(
-                                    "[Error] Handle {} Worker {} error: {:?}",
-                                    i, j, e
-                                );
-                            }
+        // Check if any workers have completed/errored using try_join, scheduling their restart
+        for group in groups.iter_mut() {
+            let Some(result) = group.handle.as_ref().and_then(|handle| handle.try_join()) else {
+                continue;
+            };
+            group.handle = None;
+            did_work = true;
+            match result {
+                Ok(results) => {
+                    for (j, worker_result) in results.into_iter().enumerate() {
+                        if let Err(e) = worker_result {
+                            let detail = format!("[{}] Worker {} error: {:?}", group.spec.name, j, e);
+                            error!("{}", detail);
+                            raise_alert(&alerts, AlertKind::WorkerFailure, AlertSeverity::Critical, &detail);
                         }
-                        println!("[Info] Handle {} workers completed", i);
-                    }
-                    Err(e) => {
-                        eprintln!("[Error] Handle {} join error: {:?}", i, e);
                     }
+                    warn!("[{}] Workers completed unexpectedly", group.spec.name);
+                }
+                Err(e) => {
+                    error!("[{}] Join error: {:?}", group.spec.name, e);
+                }
+            }
+            schedule_restart(group, &alerts, FatalKind::Internal)?;
+        }
+
+        // Rebuild the feedgroups whose restart backoff elapsed, reconnecting their feeds
+        for group in groups.iter_mut() {
+            if group.restart_at.is_none_or(|at| Instant::now() < at) {
+                continue;
+            }
+            group.restart_at = None;
+            did_work = true;
+            let restarted = create_spec_feedgroup(
+                &ring_env,
+                &group.spec,
+                &group.pause,
+                &group.failover,
+                &group.counters.parse_errors,
+                group.counters.connection.as_ref(),
+                tracer.as_ref(),
+                &symbol_info,
+                &metrics,
+                &last_top,
+                &reporters,
+            )
+                .and_then(|feedgroup| {
+                    group.feedgroup = feedgroup;
+                    run_feedgroup(&mut group.feedgroup)
+                });
+            match restarted {
+                Ok(handle) => {
+                    let detail = format!(
+                        "[{}] Restarted (attempt {}) on lcores: {:?}",
+                        group.spec.name,
+                        group.restarts.attempts(),
+                        handle.lcore_ids()
+                    );
+                    info!("{}", detail);
+                    raise_alert(&alerts, AlertKind::WorkerFailure, AlertSeverity::Info, &detail);
+                    group.handle = Some(handle);
+                    group.restarts.on_restart(Instant::now());
+                    group.counters.record_restart();
+                }
+                Err(e) => {
+                    // A refused session or missing ring outlasts the restarts, exiting with its class
+                    error!("[{}] Restart failed: {}", group.spec.name, e);
+                    schedule_restart(group, &alerts, e.kind())?;
                 }
-                // Worker completed - in production would restart or shutdown gracefully
-                println!("[Warning] Workers completed unexpectedly, continuing...");
             }
         }
 
-        // Small sleep to avoid busy-spinning on the main thread
-        // In production, this could be replaced with more sophisticated event handling
-        std::thread::sleep(std::time::Duration::from_millis(10));
+        // Wait before the next poll according to the configured policy
+        poller.wait(did_work);
     }
 
     // Note: This is unreachable in the current implementation
@@ -0,0 +1,322 @@
+//! Overlays of the hardware resources configuration.
+//!
+//! A configuration file may hold an `extends` item naming its base file,
+//! relative to its own directory, e.g. a per-environment overlay extending the
+//! shared `hw-resources.yaml`. The base is loaded first, itself possibly
+//! extending another file, and the items of the overlay are merged onto it:
+//!
+//! - `main_cpu` and `worker_cpus` are replaced
+//! - `eal`, `restart`, `arbitration` and `probe` are merged key by key, the
+//!   overlay's keys replacing the base's
+//! - the feeds of `pubsubs` are merged by `kind` and their `sets` by `name`, the
+//!   other keys of a feed or set merged key by key; new feeds and sets are appended
+//! - lists, such as `symbols`, `medium` or `endpoints`, are replaced as a whole
+//!
+//! Only the merged configuration is validated, so an overlay may be partial.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde_yaml::{Mapping, Value};
+
+use crate::HwResourcesConfigError;
+
+/// The item naming the base of an overlay.
+pub(crate) const EXTENDS_KEY: &str = "extends";
+
+/// Maximum length of a chain of overlays.
+const MAX_EXTENDS_DEPTH: usize = 8;
+
+/// Loads the configuration items of a file, merged onto its bases.
+pub(crate) fn load(path: &Path) -> Result<Value, HwResourcesConfigError> {
+    load_chain(path, &mut Vec::new())
+}
+
+/// Loads a file of a chain of overlays, `chain` holding the files extending it.
+fn load_chain(path: &Path, chain: &mut Vec<PathBuf>) -> Result<Value, HwResourcesConfigError> {
+    let canonical = fs::canonicalize(path)?;
+    if chain.contains(&canonical) {
+        return Err(invalid(format!("Cyclic 'extends' of {}", path.display())));
+    }
+    if chain.len() >= MAX_EXTENDS_DEPTH {
+        return Err(invalid(format!("More than {} nested 'extends' at {}", MAX_EXTENDS_DEPTH, path.display())));
+    }
+    chain.push(canonical);
+
+    let mut overlay: Value = serde_yaml::from_str(&fs::read_to_string(path)?)?;
+    let Some(base) = take_extends(&mut overlay)? else {
+        return Ok(overlay);
+    };
+    let base_path = path.parent().unwrap_or_else(|| Path::new(".")).join(base);
+    let mut config = load_chain(&base_path, chain)?;
+    merge_config(&mut config, overlay)?;
+    Ok(config)
+}
+
+/// Removes the `extends` item of the configuration items, returning its base file.
+fn take_extends(config: &mut Value) -> Result<Option<String>, HwResourcesConfigError> {
+    let items = root_items(config)?;
+    let mut bases = Vec::new();
+    items.retain(|item| match item.get(EXTENDS_KEY) {
+        Some(base) => {
+            bases.push(base.clone());
+            false
+        }
+        None => true,
+    });
+
+    match bases.as_slice() {
+        [] => Ok(None),
+        [Value::String(base)] => Ok(Some(base.clone())),
+        [_] => Err(invalid("'extends' must name a file".to_string())),
+        _ => Err(invalid("Duplicate 'extends' configuration".to_string())),
+    }
+}
+
+/// Merges the configuration items of an overlay onto the items of its base.
+pub(crate) fn merge_config(config: &mut Value, overlay: Value) -> Result<(), HwResourcesConfigError> {
+    let items = root_items(config)?;
+    let Value::Sequence(overlay_items) = overlay else {
+        return Err(invalid("The configuration must be a list of items".to_string()));
+    };
+
+    for item in overlay_items {
+        let Some((key, value)) = single_entry(item) else {
+            return Err(invalid("Each configuration item must be a single-key map".to_string()));
+        };
+        if key.as_str() == Some("pubsubs") {
+            merge_pubsubs(items, value)?;
+            continue;
+        }
+        match items.iter_mut().find_map(|item| item.get_mut(&key)) {
+            Some(existing) => merge_value(existing, value),
+            None => items.push(Value::Mapping(Mapping::from_iter([(key, value)]))),
+        }
+    }
+    Ok(())
+}
+
+/// Merges the feeds of an overlay `pubsubs` item onto the feeds of the base, by kind.
+fn merge_pubsubs(items: &mut Vec<Value>, pubsubs: Value) -> Result<(), HwResourcesConfigError> {
+    let Value::Sequence(feeds) = pubsubs else {
+        return Err(invalid("'pubsubs' must be a list of feeds".to_string()));
+    };
+
+    for feed in feeds {
+        let kind = feed_kind(&feed)
+            .ok_or_else(|| invalid("Each feed of 'pubsubs' must have a 'kind'".to_string()))?
+            .to_string();
+        if let Some(existing) = find_feed(items, &kind) {
+            merge_value(existing, feed);
+            continue;
+        }
+        // A new feed joins the last pub/sub group of the base
+        match items
+            .iter_mut()
+            .rev()
+            .find_map(|item| item.get_mut("pubsubs").and_then(Value::as_sequence_mut))
+        {
+            Some(group) => group.push(feed),
+            None => items.push(Value::Mapping(Mapping::from_iter([(
+                Value::from("pubsubs"),
+                Value::Sequence(vec![feed]),
+            )]))),
+        }
+    }
+    Ok(())
+}
+
+/// Merges an overlay value onto a base value: maps key by key, the sets of a
+/// feed by name, and any other value replaced.
+fn merge_value(base: &mut Value, overlay: Value) {
+    match (base, overlay) {
+        (Value::Mapping(base), Value::Mapping(overlay)) => {
+            for (key, value) in overlay {
+                match base.get_mut(&key) {
+                    Some(existing) if key.as_str() == Some("sets") => merge_sets(existing, value),
+                    Some(existing) => merge_value(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, overlay) => *base = overlay,
+    }
+}
+
+/// Merges the sets of an overlay feed onto the sets of the base feed, by name.
+fn merge_sets(base: &mut Value, overlay: Value) {
+    match (base, overlay) {
+        (Value::Sequence(sets), Value::Sequence(overlay_sets)) => {
+            for set in overlay_sets {
+                let name = set.get("name").and_then(Value::as_str).map(str::to_string);
+                let existing = sets.iter_mut().find(|existing| {
+                    name.is_some() && existing.get("name").and_then(Value::as_str) == name.as_deref()
+                });
+                match existing {
+                    Some(existing) => merge_value(existing, set),
+                    None => sets.push(set),
+                }
+            }
+        }
+        (base, overlay) => *base = overlay,
+    }
+}
+
+/// Finds the feed of a kind among the `pubsubs` items.
+fn find_feed<'a>(items: &'a mut [Value], kind: &str) -> Option<&'a mut Value> {
+    items
+        .iter_mut()
+        .filter_map(|item| item.get_mut("pubsubs").and_then(Value::as_sequence_mut))
+        .flat_map(|feeds| feeds.iter_mut())
+        .find(|feed| feed_kind(feed) == Some(kind))
+}
+
+/// Returns the kind of a feed of a `pubsubs` item.
+fn feed_kind(feed: &Value) -> Option<&str> {
+    feed.get("feed")?.get("kind")?.as_str()
+}
+
+/// Returns the only entry of a configuration item.
+fn single_entry(item: Value) -> Option<(Value, Value)> {
+    let Value::Mapping(map) = item else {
+        return None;
+    };
+    if map.len() != 1 {
+        return None;
+    }
+    map.into_iter().next()
+}
+
+/// Returns the items of the configuration root.
+fn root_items(config: &mut Value) -> Result<&mut Vec<Value>, HwResourcesConfigError> {
+    config
+        .as_sequence_mut()
+        .ok_or_else(|| invalid("The configuration must be a list of items".to_string()))
+}
+
+fn invalid(message: String) -> HwResourcesConfigError {
+    HwResourcesConfigError::ValidationError(message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::HwResourcesConfig;
+    use std::io::Write;
+
+    const BASE: &str = r#"
+- main_cpu: 0
+- worker_cpus: 1-12
+- pubsubs:
+    - feed:
+        kind: top
+        endpoints:
+          - wss://stream.binance.com:9443/ws
+        sets:
+          - name: A
+            num_cpus: 2
+            ring_size: 1024
+            symbols: [BTCUSDT, ETHUSDT]
+            medium:
+              - protocol: websocket
+                parser: json
+          - name: B
+            num_cpus: 2
+            ring_size: 1024
+            symbols: [SOLUSDT]
+            medium:
+              - protocol: websocket
+                parser: json
+- restart:
+    max_retries: 3
+"#;
+
+    const STAGING: &str = r#"
+- worker_cpus: 1-6
+- pubsubs:
+    - feed:
+        kind: top
+        endpoints:
+          - wss://testnet.binance.vision/ws
+        sets:
+          - name: B
+            ring_size: 4096
+          - name: C
+            num_cpus: 1
+            ring_size: 1024
+            symbols: [ADAUSDT]
+            medium:
+              - protocol: websocket
+                parser: json
+    - feed:
+        kind: trade
+        num_cpus: 1
+        ring_size: 1024
+        symbols: [BTCUSDT]
+        medium:
+          - protocol: websocket
+            parser: json
+- restart:
+    initial_backoff_ms: 100
+- eal:
+    file_prefix: staging
+"#;
+
+    fn merged(base: &str, overlay: &str) -> HwResourcesConfig {
+        let mut config: Value = serde_yaml::from_str(base).unwrap();
+        merge_config(&mut config, serde_yaml::from_str(overlay).unwrap()).unwrap();
+        HwResourcesConfig::from_str(&serde_yaml::to_string(&config).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_merge_overlay() {
+        let config = merged(BASE, STAGING);
+        assert_eq!(config.main_cpu, 0);
+        assert_eq!(config.worker_cpus, 1..=6);
+        assert_eq!(config.eal.file_prefix(), "staging");
+        assert_eq!(config.restart.max_retries, 3);
+        assert_eq!(config.restart.initial_backoff_ms, 100);
+
+        let top = config.find_feed("top").unwrap();
+        assert_eq!(top.endpoints, vec!["wss://testnet.binance.vision/ws".to_string()]);
+        let sets: Vec<_> = top.sets.iter().map(|set| (set.name.as_str(), set.ring_size)).collect();
+        assert_eq!(sets, vec![("A", 1024), ("B", 4096), ("C", 1024)]);
+        // The symbols of a set not listed in the overlay are kept
+        assert_eq!(top.sets[1].symbols, vec!["SOLUSDT".to_string()]);
+
+        // A new feed joins the pub/sub group of the base
+        assert_eq!(config.pubsub_configs.len(), 1);
+        assert!(config.find_feed("trade").is_some());
+    }
+
+    #[test]
+    fn test_merge_is_deterministic() {
+        assert_eq!(merged(BASE, STAGING), merged(BASE, STAGING));
+        // Merging an empty overlay keeps the base
+        assert_eq!(merged(BASE, "[]"), HwResourcesConfig::from_str(BASE).unwrap());
+    }
+
+    #[test]
+    fn test_load_extends() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("hw-resources.yaml"), BASE).unwrap();
+        fs::create_dir(dir.path().join("staging")).unwrap();
+        let overlay = dir.path().join("staging").join("hw-resources.yaml");
+        let mut file = fs::File::create(&overlay).unwrap();
+        write!(file, "- extends: ../hw-resources.yaml\n{}", STAGING).unwrap();
+
+        let config = HwResourcesConfig::from_file(&overlay).unwrap();
+        assert_eq!(config, merged(BASE, STAGING));
+    }
+
+    #[test]
+    fn test_cyclic_extends() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("a.yaml"), "- extends: b.yaml\n").unwrap();
+        fs::write(dir.path().join("b.yaml"), "- extends: a.yaml\n").unwrap();
+        let result = HwResourcesConfig::from_file(dir.path().join("a.yaml"));
+        assert!(result.unwrap_err().to_string().contains("Cyclic"));
+    }
+}
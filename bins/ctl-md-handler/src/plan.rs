@@ -0,0 +1,259 @@
+//! Lcore allocation planner for the Market Data Handler.
+//!
+//! Assigns the `worker_cpus` of the configuration to the feeds and symbol sets
+//! according to their `num_cpus`, in configuration order. The lcores of a set
+//! fanning out to multiple mediums are split evenly across its mediums.
+
+use crate::{HwResourcesConfig, LcorePlanError};
+
+/// The lcores assigned to a single feed or symbol set.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LcoreAssignment {
+    /// Feed kind (e.g., "top", "trade").
+    pub kind: String,
+    /// Symbol set name, `None` for feeds using direct configuration.
+    pub set: Option<String>,
+    /// The assigned lcores.
+    pub lcores: Vec<u32>,
+    /// The assigned lcores split per medium, in medium order.
+    pub mediums: Vec<Vec<u32>>,
+}
+
+impl LcoreAssignment {
+    /// Returns the lcores assigned to a medium of the set, by medium index.
+    pub fn medium_lcores(&self, index: usize) -> Option<&[u32]> {
+        self.mediums.get(index).map(|lcores| lcores.as_slice())
+    }
+}
+
+/// Splits lcores evenly across `n` mediums, the first mediums taking the remainder.
+fn split_lcores(lcores: &[u32], n: usize) -> Vec<Vec<u32>> {
+    let base = lcores.len() / n;
+    let extra = lcores.len() % n;
+    let mut rest = lcores;
+    (0..n)
+        .map(|i| {
+            let (head, tail) = rest.split_at(base + usize::from(i < extra));
+            rest = tail;
+            head.to_vec()
+        })
+        .collect()
+}
+
+/// The lcore allocation plan for all feeds.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LcorePlan {
+    /// The assignments in configuration order.
+    pub assignments: Vec<LcoreAssignment>,
+    /// Worker lcores left unassigned.
+    pub unused: Vec<u32>,
+}
+
+impl LcorePlan {
+    /// Plans the lcore allocation from the configuration.
+    ///
+    /// # Errors
+    /// Returns an error if the main CPU is a worker CPU, a feed or set requests
+    /// zero CPUs or fewer CPUs than mediums, or the requested CPUs don't fit
+    /// the `worker_cpus` budget.
+    pub fn from_config(config: &HwResourcesConfig) -> Result<Self, LcorePlanError> {
+        if config.worker_cpus.contains(&config.main_cpu) {
+            return Err(LcorePlanError::MainCpuInWorkers(config.main_cpu));
+        }
+
+        // Collect the requests in configuration order
+        let mut requests: Vec<(String, Option<String>, u32, usize)> = Vec::new();
+        for feed in config.all_feeds() {
+            for feed_set in feed.feed_sets() {
+                requests.push((
+                    feed_set.kind.to_string(),
+                    feed_set.set.map(str::to_string),
+                    feed_set.num_cpus,
+                    feed_set.medium.len().max(1),
+                ));
+            }
+        }
+
+        for (kind, set, num_cpus, mediums) in &requests {
+            let target = || match set {
+                Some(set) => format!("Feed '{}' set '{}'", kind, set),
+                None => format!("Feed '{}'", kind),
+            };
+            if *num_cpus == 0 {
+                return Err(LcorePlanError::ZeroCpus(target()));
+            }
+            if (*num_cpus as usize) < *mediums {
+                return Err(LcorePlanError::TooFewCpusForMediums {
+                    target: target(),
+                    num_cpus: *num_cpus,
+                    mediums: *mediums as u32,
+                });
+            }
+        }
+
+        let mut available: Vec<u32> = config.worker_cpus.clone().collect();
+        let required: u32 = requests.iter().map(|(_, _, n, _)| n).sum();
+        if required as usize > available.len() {
+            return Err(LcorePlanError::InsufficientCpus {
+                required,
+                available: available.len() as u32,
+            });
+        }
+
+        let assignments = requests
+            .into_iter()
+            .map(|(kind, set, num_cpus, mediums)| {
+                let lcores: Vec<u32> = available.drain(..num_cpus as usize).collect();
+                LcoreAssignment {
+                    kind,
+                    set,
+                    mediums: split_lcores(&lcores, mediums),
+                    lcores,
+                }
+            })
+            .collect();
+
+        Ok(Self { assignments, unused: available })
+    }
+
+    /// Returns all lcores assigned to a feed kind, across its sets.
+    pub fn for_kind(&self, kind: &str) -> Vec<u32> {
+        self.assignments
+            .iter()
+            .filter(|a| a.kind == kind)
+            .flat_map(|a| a.lcores.iter().copied())
+            .collect()
+    }
+
+    /// Returns the lcores assigned to a symbol set of a feed kind.
+    pub fn for_set(&self, kind: &str, set: &str) -> Option<&[u32]> {
+        self.lcores(kind, Some(set))
+    }
+
+    /// Returns the lcores assigned to a feed kind and set (`None` for direct configuration).
+    pub fn lcores(&self, kind: &str, set: Option<&str>) -> Option<&[u32]> {
+        self.assignment(kind, set).map(|a| a.lcores.as_slice())
+    }
+
+    /// Returns the assignment of a feed kind and set (`None` for direct configuration).
+    pub fn assignment(&self, kind: &str, set: Option<&str>) -> Option<&LcoreAssignment> {
+        self.assignments
+            .iter()
+            .find(|a| a.kind == kind && a.set.as_deref() == set)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(main_cpu: u32, worker_cpus: &str, top_a: u32, top_b: u32, trade: u32) -> HwResourcesConfig {
+        let content = format!(r#"
+- main_cpu: {main_cpu}
+- worker_cpus: {worker_cpus}
+- pubsubs:
+    - feed:
+        kind: top
+        sets:
+          - name: A
+            num_cpus: {top_a}
+            ring_size: 1024
+            symbols:
+              - BTCUSDT
+            medium:
+              - protocol: websocket
+                parser: json
+          - name: B
+            num_cpus: {top_b}
+            ring_size: 1024
+            symbols:
+              - ETHUSDT
+            medium:
+              - protocol: websocket
+                parser: json
+    - feed:
+        kind: trade
+        num_cpus: {trade}
+        ring_size: 1024
+        symbols:
+          - BTCUSDT
+        medium:
+          - protocol: websocket
+            parser: json
+"#);
+        HwResourcesConfig::from_str(&content).expect("Failed to parse config")
+    }
+
+    #[test]
+    fn test_plan_assigns_in_config_order() {
+        let plan = LcorePlan::from_config(&config(0, "1-12", 4, 2, 3)).unwrap();
+        assert_eq!(plan.for_set("top", "A"), Some(&[1, 2, 3, 4][..]));
+        assert_eq!(plan.for_set("top", "B"), Some(&[5, 6][..]));
+        assert_eq!(plan.for_kind("top"), vec![1, 2, 3, 4, 5, 6]);
+        assert_eq!(plan.for_kind("trade"), vec![7, 8, 9]);
+        assert_eq!(plan.lcores("trade", None), Some(&[7, 8, 9][..]));
+        assert_eq!(plan.unused, vec![10, 11, 12]);
+    }
+
+    #[test]
+    fn test_plan_insufficient_cpus() {
+        let result = LcorePlan::from_config(&config(0, "1-8", 4, 4, 4));
+        assert!(matches!(
+            result,
+            Err(LcorePlanError::InsufficientCpus { required: 12, available: 8 })
+        ));
+    }
+
+    #[test]
+    fn test_plan_main_cpu_in_workers() {
+        let result = LcorePlan::from_config(&config(3, "1-12", 1, 1, 1));
+        assert!(matches!(result, Err(LcorePlanError::MainCpuInWorkers(3))));
+    }
+
+    #[test]
+    fn test_split_lcores() {
+        assert_eq!(split_lcores(&[1, 2, 3, 4, 5], 2), vec![vec![1, 2, 3], vec![4, 5]]);
+        assert_eq!(split_lcores(&[1, 2], 1), vec![vec![1, 2]]);
+    }
+
+    #[test]
+    fn test_plan_splits_mediums() {
+        let content = r#"
+- main_cpu: 0
+- worker_cpus: 1-8
+- pubsubs:
+    - feed:
+        kind: top
+        sets:
+          - name: A
+            num_cpus: 3
+            ring_size: 1024
+            symbols:
+              - BTCUSDT
+            medium:
+              - protocol: websocket
+                parser: json
+              - protocol: fix
+                parser: fix
+"#;
+        let config = HwResourcesConfig::from_str(content).expect("Failed to parse config");
+        let plan = LcorePlan::from_config(&config).unwrap();
+        let assignment = plan.assignment("top", Some("A")).unwrap();
+        assert_eq!(assignment.medium_lcores(0), Some(&[1, 2][..]));
+        assert_eq!(assignment.medium_lcores(1), Some(&[3][..]));
+        assert_eq!(assignment.medium_lcores(2), None);
+
+        let too_few = content.replace("num_cpus: 3", "num_cpus: 1");
+        let config = HwResourcesConfig::from_str(&too_few).expect("Failed to parse config");
+        assert!(matches!(
+            LcorePlan::from_config(&config),
+            Err(LcorePlanError::TooFewCpusForMediums { num_cpus: 1, mediums: 2, .. })
+        ));
+    }
+
+    #[test]
+    fn test_plan_zero_cpus() {
+        let result = LcorePlan::from_config(&config(0, "1-12", 1, 0, 1));
+        assert!(result.unwrap_err().to_string().contains("set 'B'"));
+    }
+}
@@ -0,0 +1,213 @@
+//! Restart policy of the FeedGroup workers.
+//!
+//! When the workers of a FeedGroup exit, the handler rebuilds the FeedGroup,
+//! reconnecting its feeds, after an exponentially growing backoff. A FeedGroup
+//! exiting more than `max_retries` consecutive times escalates to a shutdown
+//! of the handler, leaving the restart to its supervisor. A FeedGroup running
+//! for `reset_after_ms` since its last restart is considered recovered. The
+//! backoffs are jittered, so that the FeedGroups exiting together (e.g. on a
+//! network outage) don't reconnect in lockstep.
+
+use std::time::{Duration, Instant};
+
+use ctl_retry::{Jitter, RetryPolicy};
+use serde::Deserialize;
+
+const DEFAULT_MAX_RETRIES: u32 = 5;
+const DEFAULT_INITIAL_BACKOFF_MS: u64 = 500;
+const DEFAULT_MAX_BACKOFF_MS: u64 = 30_000;
+const DEFAULT_RESET_AFTER_MS: u64 = 60_000;
+
+fn default_max_retries() -> u32 {
+    DEFAULT_MAX_RETRIES
+}
+
+fn default_initial_backoff_ms() -> u64 {
+    DEFAULT_INITIAL_BACKOFF_MS
+}
+
+fn default_max_backoff_ms() -> u64 {
+    DEFAULT_MAX_BACKOFF_MS
+}
+
+fn default_reset_after_ms() -> u64 {
+    DEFAULT_RESET_AFTER_MS
+}
+
+/// When the workers of an exited FeedGroup are restarted.
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Eq, Hash)]
+pub struct RestartPolicy {
+    /// Number of consecutive restarts before escalating to a shutdown (0 never restarts).
+    #[serde(default = "default_max_retries")]
+    pub max_retries: u32,
+    /// Backoff before the first restart, in milliseconds, doubled on each consecutive restart.
+    #[serde(default = "default_initial_backoff_ms")]
+    pub initial_backoff_ms: u64,
+    /// Longest backoff before a restart, in milliseconds.
+    #[serde(default = "default_max_backoff_ms")]
+    pub max_backoff_ms: u64,
+    /// Running time after a restart resetting the consecutive restarts, in milliseconds.
+    #[serde(default = "default_reset_after_ms")]
+    pub reset_after_ms: u64,
+    /// Share of the backoff randomized, in percent (0 disables it).
+    #[serde(default)]
+    pub jitter_pct: u32,
+}
+
+impl Default for RestartPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: DEFAULT_MAX_RETRIES,
+            initial_backoff_ms: DEFAULT_INITIAL_BACKOFF_MS,
+            max_backoff_ms: DEFAULT_MAX_BACKOFF_MS,
+            reset_after_ms: DEFAULT_RESET_AFTER_MS,
+            jitter_pct: 0,
+        }
+    }
+}
+
+impl RestartPolicy {
+    /// Validates the policy configuration.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.initial_backoff_ms == 0 || self.initial_backoff_ms > self.max_backoff_ms {
+            return Err(format!(
+                "restart policy must have 0 < 'initial_backoff_ms' <= 'max_backoff_ms', got {} and {}",
+                self.initial_backoff_ms, self.max_backoff_ms
+            ));
+        }
+        self.retry_policy().validate()
+    }
+
+    /// Returns the backoff policy of the restarts.
+    pub fn retry_policy(&self) -> RetryPolicy {
+        RetryPolicy {
+            max_retries: self.max_retries,
+            initial_backoff_ms: self.initial_backoff_ms,
+            max_backoff_ms: self.max_backoff_ms,
+            multiplier: 2,
+            jitter_pct: self.jitter_pct,
+        }
+    }
+
+    /// Returns the backoff before a restart, by consecutive restart starting at 1, without jitter.
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        self.retry_policy().backoff(attempt)
+    }
+}
+
+/// What to do with an exited FeedGroup.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RestartDecision {
+    /// Restart the FeedGroup after the backoff.
+    Restart {
+        /// The consecutive restart, starting at 1.
+        attempt: u32,
+        /// The backoff before the restart.
+        backoff: Duration,
+    },
+    /// Shut the handler down, the restarts are exhausted.
+    Escalate {
+        /// The consecutive restarts attempted.
+        attempts: u32,
+    },
+}
+
+/// Tracks the consecutive restarts of a FeedGroup.
+#[derive(Debug, Clone)]
+pub struct RestartTracker {
+    /// The policy.
+    policy: RestartPolicy,
+    /// Consecutive restarts since the FeedGroup last recovered.
+    attempts: u32,
+    /// The time the workers were last started.
+    started_at: Instant,
+    /// The source of the jitter of the backoffs.
+    jitter: Jitter,
+}
+
+impl RestartTracker {
+    /// Creates a tracker of a FeedGroup started at `now`.
+    pub fn new(policy: RestartPolicy, now: Instant) -> Self {
+        Self { policy, attempts: 0, started_at: now, jitter: Jitter::from_entropy() }
+    }
+
+    /// Returns the consecutive restarts since the FeedGroup last recovered.
+    pub fn attempts(&self) -> u32 {
+        self.attempts
+    }
+
+    /// Records an exit of the workers, or a failed restart, at `now`.
+    pub fn on_exit(&mut self, now: Instant) -> RestartDecision {
+        if now.duration_since(self.started_at) >= Duration::from_millis(self.policy.reset_after_ms) {
+            self.attempts = 0;
+        }
+        if self.attempts >= self.policy.max_retries {
+            return RestartDecision::Escalate { attempts: self.attempts };
+        }
+        self.attempts += 1;
+        let backoff = self.policy.retry_policy().jittered(self.attempts, &mut self.jitter);
+        RestartDecision::Restart { attempt: self.attempts, backoff }
+    }
+
+    /// Records a restart of the workers at `now`.
+    pub fn on_restart(&mut self, now: Instant) {
+        self.started_at = now;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(max_retries: u32) -> RestartPolicy {
+        RestartPolicy { max_retries, initial_backoff_ms: 100, max_backoff_ms: 250, reset_after_ms: 1_000, jitter_pct: 0 }
+    }
+
+    #[test]
+    fn test_backoff() {
+        let policy = policy(5);
+        assert_eq!(policy.backoff(1), Duration::from_millis(100));
+        assert_eq!(policy.backoff(2), Duration::from_millis(200));
+        assert_eq!(policy.backoff(3), Duration::from_millis(250));
+        assert_eq!(policy.backoff(u32::MAX), Duration::from_millis(250));
+        assert!(policy.validate().is_ok());
+        assert!(RestartPolicy { initial_backoff_ms: 0, ..policy }.validate().is_err());
+        assert!(RestartPolicy { max_backoff_ms: 50, ..policy }.validate().is_err());
+        assert!(RestartPolicy { jitter_pct: 101, ..policy }.validate().is_err());
+    }
+
+    #[test]
+    fn test_escalate_after_max_retries() {
+        let start = Instant::now();
+        let mut tracker = RestartTracker::new(policy(2), start);
+        assert_eq!(
+            tracker.on_exit(start),
+            RestartDecision::Restart { attempt: 1, backoff: Duration::from_millis(100) }
+        );
+        tracker.on_restart(start);
+        assert_eq!(
+            tracker.on_exit(start),
+            RestartDecision::Restart { attempt: 2, backoff: Duration::from_millis(200) }
+        );
+        assert_eq!(tracker.on_exit(start), RestartDecision::Escalate { attempts: 2 });
+
+        let mut tracker = RestartTracker::new(policy(0), start);
+        assert_eq!(tracker.on_exit(start), RestartDecision::Escalate { attempts: 0 });
+    }
+
+    #[test]
+    fn test_recovery_resets_attempts() {
+        let start = Instant::now();
+        let mut tracker = RestartTracker::new(policy(1), start);
+        tracker.on_exit(start);
+        tracker.on_restart(start);
+        assert_eq!(tracker.attempts(), 1);
+
+        // Running past reset_after_ms since the restart counts as recovered
+        let later = start + Duration::from_millis(1_000);
+        assert_eq!(
+            tracker.on_exit(later),
+            RestartDecision::Restart { attempt: 1, backoff: Duration::from_millis(100) }
+        );
+    }
+}
@@ -0,0 +1,158 @@
+//! Staleness of the market data streams.
+//!
+//! A feed losing a single stream keeps its connection alive with the others,
+//! so the endpoint failover never notices it, and the strategies keep quoting
+//! on a book that stopped moving. The handler compares the time since the last
+//! message of each stream against the expected activity of its symbol (an
+//! illiquid symbol trades less often than BTCUSDT), alerts when a stream turns
+//! stale, and optionally fails the feed over to its next endpoint or halts
+//! trading.
+
+use std::collections::BTreeMap;
+
+use hashbrown::HashSet;
+use serde::Deserialize;
+
+/// Default time without a message after which a stream is stale, in milliseconds.
+const DEFAULT_AFTER_MS: u64 = 60_000;
+
+fn default_after_ms() -> u64 {
+    DEFAULT_AFTER_MS
+}
+
+/// What the handler does when a stream turns stale, besides alerting.
+#[derive(Debug, Clone, Copy, Default, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "kebab-case")]
+pub enum StaleAction {
+    /// Only alerts.
+    #[default]
+    Alert,
+    /// Switches the feed of the stream to its next endpoint.
+    Failover,
+    /// Halts trading through the control ring, the strategies no longer
+    /// quoting until an operator resumes.
+    Halt,
+}
+
+/// When the streams of a feed are stale.
+#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+pub struct StalenessPolicy {
+    /// Time without a message after which a stream is stale, in milliseconds.
+    #[serde(default = "default_after_ms")]
+    pub after_ms: u64,
+    /// The expected activity of the symbols overriding `after_ms`, in milliseconds by symbol.
+    #[serde(default)]
+    pub symbols: BTreeMap<String, u64>,
+    /// What to do when a stream turns stale.
+    #[serde(default)]
+    pub action: StaleAction,
+}
+
+impl Default for StalenessPolicy {
+    fn default() -> Self {
+        Self { after_ms: DEFAULT_AFTER_MS, symbols: BTreeMap::new(), action: StaleAction::default() }
+    }
+}
+
+impl StalenessPolicy {
+    /// Validates the policy configuration.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.after_ms == 0 {
+            return Err("staleness 'after_ms' must be greater than 0".to_string());
+        }
+        if let Some((symbol, _)) = self.symbols.iter().find(|(_, after_ms)| **after_ms == 0) {
+            return Err(format!("staleness threshold of symbol '{}' must be greater than 0", symbol));
+        }
+        Ok(())
+    }
+
+    /// Returns the time without a message after which a stream of `symbol` is stale.
+    pub fn after_ms(&self, symbol: &str) -> u64 {
+        self.symbols.get(symbol).copied().unwrap_or(self.after_ms)
+    }
+}
+
+/// A change of the staleness of a stream.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StaleChange {
+    /// The stream turned stale, no message received for `elapsed_ms` against
+    /// an expected `after_ms`.
+    Stale { elapsed_ms: u64, after_ms: u64 },
+    /// The stream received a message again.
+    Recovered,
+}
+
+/// Tracks the stale streams of a FeedGroup.
+#[derive(Debug, Clone)]
+pub struct StalenessTracker {
+    /// The policy.
+    policy: StalenessPolicy,
+    /// The streams currently stale.
+    stale: HashSet<String>,
+}
+
+impl StalenessTracker {
+    /// Creates a tracker with no stream stale.
+    pub fn new(policy: StalenessPolicy) -> Self {
+        Self { policy, stale: HashSet::new() }
+    }
+
+    /// Returns the policy.
+    pub fn policy(&self) -> &StalenessPolicy {
+        &self.policy
+    }
+
+    /// Checks a stream of `symbol` last receiving a message at
+    /// `last_message_ms`, returning its change since the last check.
+    pub fn check(&mut self, stream: &str, symbol: &str, last_message_ms: u64, now_ms: u64) -> Option<StaleChange> {
+        let after_ms = self.policy.after_ms(symbol);
+        let elapsed_ms = now_ms.saturating_sub(last_message_ms);
+        if elapsed_ms >= after_ms {
+            return self
+                .stale
+                .insert(stream.to_string())
+                .then_some(StaleChange::Stale { elapsed_ms, after_ms });
+        }
+        self.stale.remove(stream).then_some(StaleChange::Recovered)
+    }
+
+    /// Forgets the stale streams, e.g. when the FeedGroup restarts.
+    pub fn clear(&mut self) {
+        self.stale.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_symbol_specific_thresholds() {
+        let policy: StalenessPolicy =
+            serde_yaml::from_str("after_ms: 5000\nsymbols:\n  ILLIQUIDUSDT: 300000\naction: failover").unwrap();
+        assert_eq!(policy.action, StaleAction::Failover);
+        assert!(policy.validate().is_ok());
+        let mut tracker = StalenessTracker::new(policy);
+
+        assert_eq!(tracker.check("btcusdt@trade", "BTCUSDT", 0, 4_999), None);
+        assert_eq!(
+            tracker.check("btcusdt@trade", "BTCUSDT", 0, 5_000),
+            Some(StaleChange::Stale { elapsed_ms: 5_000, after_ms: 5_000 })
+        );
+        assert_eq!(tracker.check("btcusdt@trade", "BTCUSDT", 0, 6_000), None);
+        assert_eq!(tracker.check("illiquidusdt@trade", "ILLIQUIDUSDT", 0, 6_000), None);
+
+        assert_eq!(tracker.check("btcusdt@trade", "BTCUSDT", 5_500, 6_000), Some(StaleChange::Recovered));
+        assert_eq!(tracker.check("btcusdt@trade", "BTCUSDT", 5_500, 6_000), None);
+    }
+
+    #[test]
+    fn test_validate_and_defaults() {
+        let policy: StalenessPolicy = serde_yaml::from_str("{}").unwrap();
+        assert_eq!(policy, StalenessPolicy::default());
+
+        assert!(StalenessPolicy { after_ms: 0, ..StalenessPolicy::default() }.validate().is_err());
+        let symbols = BTreeMap::from([("BTCUSDT".to_string(), 0)]);
+        assert!(StalenessPolicy { symbols, ..StalenessPolicy::default() }.validate().is_err());
+    }
+}
@@ -3,84 +3,829 @@
 //! This binary connects as a DPDK secondary process and reads RawMessage
 //! data from the shared rings created by ctl-resource-manager and published
 //! to by ctl-md-handler.
+//!
+//! With the `shm-rings` feature, it opens the shared memory rings created by a
+//! ctl-resource-manager built with the same feature instead, needing neither
+//! hugepages nor an EAL.
+//!
+//! The configuration paths are given on the command line, or by their
+//! environment variables (see `--help`). With `--check`, the configurations
+//! are validated and the ring to consume printed instead.
+//!
+//! The consumed messages are read by the `RingReader` of a `Subscriber`,
+//! independent of the ring backend, so its handling is tested on in-process
+//! rings.
+//!
+//! With `--snapshot`, the subscriber joins mid-stream: once attached, it reads
+//! the conflated state of the symbols of the ring (latest Top, book snapshot)
+//! and the open orders of an OMS journal before streaming the deltas, skipping
+//! those already in the state. Once overtaken by the producer, the subscriber
+//! skips the missed messages, reads the state again or exits, per its policy in
+//! `configs/overtaken.yaml`, exiting with the code of `FatalKind::Overtaken`.
+//!
+//! The run epoch of ctl-resource-manager is checked while the ring is idle. Once
+//! it restarted, its ring gone stale, the subscriber executes itself anew,
+//! waiting for the next run before looking up the ring and attaching its
+//! consumer again.
+//!
+//! The messages traced by the handler continue their trace with the `md.consume`
+//! span of the subscriber, exported per `configs/telemetry.yaml`.
+
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
-use std::error::Error;
+use clap::Parser;
+use ctl_core::{
+    reattach, reattaching, wait_for_run, AlertKind, AlertMessage, AlertSeverity, EpochWatch, Fatal, FatalError,
+    FatalKind, LcoresConfig, OvertakenConfig, OvertakenPolicy, Poller, PollingConfig, PollingPolicy, Preflight,
+    RingName, SchedulingConfig, StatusRegion, TelemetryConfig, Tracer, ALERTS_RING_NAME, REATTACH_POLL_INTERVAL,
+    STATUS_REGION_NAME,
+};
+#[cfg(not(feature = "shm-rings"))]
+use ctl_core::Capability;
+use ctl_book::{book_region_name, BookSnapshotRegion};
+#[cfg(feature = "latency-histograms")]
+use ctl_feed::WakeLatencyRecorder;
+#[cfg(feature = "message-checksums")]
+use ctl_feed::Checksummed;
+use ctl_feed::{
+    EventType, JoinGate, LastTopRegion, MessageFilter, MessageHeader, MetricsRegion, RawMessage, RingConsume,
+    RingError, RingPattern, RingPublisher, RingRead, RingReader, LAST_TOP_REGION_NAME, METRICS_REGION_NAME,
+};
+#[cfg(not(feature = "shm-rings"))]
+use ctl_feed::{dpdk_consume, RingDirectory};
+#[cfg(feature = "shm-rings")]
+use ctl_feed::{RingConsumer, RingLike, ShmRingDirectory};
+use ctl_md_handler::HwResourcesConfig;
+use ctl_oms::Journal;
+use ctl_shm::ShmRegion;
+#[cfg(not(feature = "shm-rings"))]
+use dpdk::{DpdkEnvBuilder, DpdkProcessType};
+use tracing::{error, info, warn};
 
-use ctl_feed::RawMessage;
-use dpdk::{ConsumeStartState, DpdkEnvBuilder, DpdkProcessType};
+// Logging configuration, its levels reloaded on change
+const LOG_CONFIG_PATH: &str = "configs/logging.yaml";
+
+// The market data configuration, holding the EAL options of the secondary processes
+const MD_CONFIG_PATH: &str = "configs/market-data/hw-resources.yaml";
 
 // Ring naming convention: {KIND}_{symbol_id}_PS
 // Using BTCUSDT (symbol_id=0) as default for testing
-const RING_NAME: &str = "TOP_0_PS";
+const RING_PATTERN: &str = "TOP_0_PS";
 
-// Use a separate lcore that doesn't conflict with md-handler workers
-const SUBSCRIBER_LCORE: usize = 13;
+// The name of the consumer cursor of the subscriber, unless given
+const DEFAULT_CONSUMER: &str = "md-subscriber";
 
-fn main() -> Result<(), Box<dyn Error>> {
-    println!("=== Binance Spot Market Data Subscriber ===");
-    println!("Starting as DPDK secondary process...\n");
+// The lcore of the subscriber, unless configured, past the md-handler workers
+const LCORES_CONFIG_PATH: &str = "configs/lcores.yaml";
+const LCORES_COMPONENT: &str = "md-subscriber";
+const DEFAULT_LCORE: u32 = 13;
 
-    let dpdk_env = DpdkEnvBuilder::default()
-        .process_type(DpdkProcessType::Secondary)
-        .lcore_ids(vec![SUBSCRIBER_LCORE])
-        .main_lcore_id(SUBSCRIBER_LCORE)
-        .build()?;
+// Polling policy between empty polls, unless configured for this component
+const POLLING_CONFIG_PATH: &str = "configs/polling.yaml";
+const POLLING_COMPONENT: &str = "md-subscriber";
+const DEFAULT_POLLING: PollingPolicy = PollingPolicy::BusyPoll;
 
-    println!("DPDK environment initialized");
-    println!("Looking up ring: {}", RING_NAME);
+// Recovery once overtaken by the producer, unless configured for this component
+const OVERTAKEN_CONFIG_PATH: &str = "configs/overtaken.yaml";
+const OVERTAKEN_COMPONENT: &str = "md-subscriber";
+const DEFAULT_OVERTAKEN: OvertakenPolicy = OvertakenPolicy::SkipToHead;
 
-    // Look up the ring by name and type - must match what was registered by resource-manager
-    let ring = dpdk_env.pubsub_lookup::<RawMessage>(RING_NAME)?;
+// Scheduling of the threads of this component, unless configured left untouched
+const SCHEDULING_CONFIG_PATH: &str = "configs/scheduling.yaml";
+const SCHEDULING_COMPONENT: &str = "md-subscriber";
 
-    println!("Ring found, attaching consumer...");
-    let mut consumer = ring.attach_consumer()?;
+// Tracing of the messages of this component, unless configured traced by none
+const TELEMETRY_CONFIG_PATH: &str = "configs/telemetry.yaml";
+const TELEMETRY_COMPONENT: &str = "md-subscriber";
 
-    println!("Consumer attached, starting to read messages...\n");
+// Source of the alerts published to the alerts ring
+const ALERT_SOURCE: &str = "md-subscriber";
 
-    let mut msg_count: u64 = 0;
-    let mut empty_polls: u64 = 0;
+// Interval between the checks of the run epoch of ctl-resource-manager
+const EPOCH_CHECK_INTERVAL: Duration = Duration::from_secs(1);
 
-    loop {
-        match consumer.consume_start() {
-            ConsumeStartState::Success(mut guard) => {
-                // Try to commit first (mark message as consumed)
-                match guard.try_commit() {
-                    Ok(_) => {
-                        let msg = guard.as_ref();
-                        let data = &msg.get().data;
-                        
-                        // Find the actual message length (up to first null byte or end)
-                        let len = data.iter().position(|&b| b == 0).unwrap_or(data.len());
-                        let msg_str = String::from_utf8_lossy(&data[..len]);
-                        
-                        msg_count += 1;
-                        println!("[{}] Received: {}", msg_count, msg_str);
-                    }
-                    Err(_) => {
-                        // Commit failed, retry
-                        continue;
-                    }
+/// Dummy market data subscriber, consuming a shared ring.
+#[derive(Debug, Parser)]
+#[command(version)]
+struct Args {
+    /// Logging configuration.
+    #[arg(long, env = "CTL_LOG_CONFIG", default_value = LOG_CONFIG_PATH)]
+    log_config: PathBuf,
+    /// Market data configuration.
+    #[arg(long, env = "CTL_MD_CONFIG", default_value = MD_CONFIG_PATH)]
+    md_config: PathBuf,
+    /// Polling configuration.
+    #[arg(long, env = "CTL_POLLING_CONFIG", default_value = POLLING_CONFIG_PATH)]
+    polling_config: PathBuf,
+    /// Lcores configuration.
+    #[arg(long, env = "CTL_LCORES_CONFIG", default_value = LCORES_CONFIG_PATH)]
+    lcores_config: PathBuf,
+    /// Scheduling configuration.
+    #[arg(long, env = "CTL_SCHEDULING_CONFIG", default_value = SCHEDULING_CONFIG_PATH)]
+    scheduling_config: PathBuf,
+    /// Overtaken consumer recovery configuration.
+    #[arg(long, env = "CTL_OVERTAKEN_CONFIG", default_value = OVERTAKEN_CONFIG_PATH)]
+    overtaken_config: PathBuf,
+    /// Telemetry configuration.
+    #[arg(long, env = "CTL_TELEMETRY_CONFIG", default_value = TELEMETRY_CONFIG_PATH)]
+    telemetry_config: PathBuf,
+    /// The ring to consume, the first market data ring matching the pattern
+    /// (e.g. `TRADE_*_PS`) in name order.
+    #[arg(long, env = "CTL_SUBSCRIBER_RING", default_value = RING_PATTERN)]
+    ring: String,
+    /// The consumer name its cursor is attached under, unique among the
+    /// consumers of the ring (e.g. `recorder`, `monitor`); every consumer
+    /// reads every message of the ring.
+    #[arg(long, env = "CTL_SUBSCRIBER_CONSUMER", default_value = DEFAULT_CONSUMER)]
+    consumer: String,
+    /// Only handle the messages of these symbol IDs, skipping the others.
+    #[arg(long = "symbol-id")]
+    symbol_ids: Vec<u32>,
+    /// Only handle the messages of these event types (e.g. `trade`), skipping the others.
+    #[arg(long = "event-type", value_parser = parse_event_type)]
+    event_types: Vec<EventType>,
+    /// Read the conflated state of the symbols of the ring (latest Top, book
+    /// snapshot) once attached, before streaming the deltas not already in it.
+    #[arg(long)]
+    snapshot: bool,
+    /// The OMS journal whose open orders are read with the snapshot.
+    #[arg(long, requires = "snapshot")]
+    oms_journal: Option<PathBuf>,
+    /// Validate the configurations and print the ring to consume, without
+    /// initializing DPDK.
+    #[arg(long)]
+    check: bool,
+}
+
+/// Parses an event type name of the command line.
+fn parse_event_type(name: &str) -> Result<EventType, String> {
+    EventType::from_name(name).ok_or_else(|| format!("unknown event type '{}'", name))
+}
+
+// Consumes the next message of the ring as a `RingConsume`
+#[cfg(not(feature = "shm-rings"))]
+macro_rules! consume {
+    ($consumer:expr) => {
+        dpdk_consume!($consumer)
+    };
+}
+#[cfg(feature = "shm-rings")]
+macro_rules! consume {
+    ($consumer:expr) => {
+        RingConsumer::consume(&mut $consumer)
+    };
+}
+
+/// The handling of the messages consumed from the ring, whatever its backend.
+struct Subscriber<'a, A> {
+    /// The reader of the consumed ring, reassembling the fragmented payloads
+    /// and recovering per the overtaken policy.
+    reader: RingReader<'a>,
+    /// The alerts ring, told when the consumer is overtaken.
+    alerts: A,
+    /// Number of messages received.
+    msg_count: u64,
+    /// Number of polls of the empty ring.
+    empty_polls: u64,
+    /// The filter of the handled messages.
+    filter: MessageFilter,
+    /// Number of messages skipped by the filter.
+    skipped: u64,
+    /// The gate of the deltas already in the state read on attach.
+    gate: JoinGate,
+    /// Whether the state is to be read again, having been overtaken.
+    snapshot_requested: bool,
+    /// Tracer of the consumes of the traced messages.
+    tracer: Option<Tracer>,
+    /// Number of messages dropped for failing their checksum.
+    #[cfg(feature = "message-checksums")]
+    corrupt_count: u64,
+    /// Recorder of the wake latency of the messages, in the latency group of their feedgroup.
+    #[cfg(feature = "latency-histograms")]
+    wake_latency: Option<WakeLatencyRecorder>,
+}
+
+impl<'a, A: RingPublisher<AlertMessage>> Subscriber<'a, A> {
+    fn new(reader: RingReader<'a>, alerts: A) -> Self {
+        Self {
+            reader,
+            alerts,
+            msg_count: 0,
+            empty_polls: 0,
+            filter: MessageFilter::new(),
+            skipped: 0,
+            gate: JoinGate::new(),
+            snapshot_requested: false,
+            tracer: None,
+            #[cfg(feature = "message-checksums")]
+            corrupt_count: 0,
+            #[cfg(feature = "latency-histograms")]
+            wake_latency: None,
+        }
+    }
+
+    /// Only handles the messages matching the filter, skipping the others unparsed.
+    fn with_filter(mut self, filter: MessageFilter) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    /// Skips the deltas already in the state read on attach.
+    fn with_gate(mut self, gate: JoinGate) -> Self {
+        self.gate = gate;
+        self
+    }
+
+    /// Records the consume of the traced messages as the `md.consume` span,
+    /// continuing their trace.
+    fn with_tracer(mut self, tracer: Tracer) -> Self {
+        self.tracer = Some(tracer);
+        self
+    }
+
+    /// Returns true once if the state is to be read again, having been overtaken.
+    fn take_snapshot_request(&mut self) -> bool {
+        std::mem::take(&mut self.snapshot_requested)
+    }
+
+    /// Records the wake latency of the messages.
+    #[cfg(feature = "latency-histograms")]
+    fn with_wake_latency(mut self, wake_latency: WakeLatencyRecorder) -> Self {
+        self.wake_latency = Some(wake_latency);
+        self
+    }
+
+    /// Handles the result of a consume, returning true if it did work.
+    ///
+    /// LATENCY: FAST_PATH
+    ///
+    /// # Errors
+    /// Returns an error if overtaken by the producer under the fail-fast policy.
+    fn on_consume(&mut self, consumed: RingConsume<RawMessage>) -> Result<bool, RingError> {
+        let mut trace_start_ns = None;
+        match &consumed {
+            RingConsume::Message(msg) if !self.filter.matches(&msg.header) => {
+                self.skipped += 1;
+                self.reader.skip();
+                return Ok(true);
+            }
+            RingConsume::Message(msg) => {
+                trace_start_ns = self.trace_start(&msg.header);
+                #[cfg(feature = "message-checksums")]
+                if !msg.verify() {
+                    // Torn or corrupted in its slot, dropped
+                    self.corrupt_count += 1;
+                    self.reader.skip();
+                    let (name, seq) = (self.reader.name(), msg.header.seq);
+                    let detail = format!("{} message seq {} failed its checksum, dropped", name, seq);
+                    warn!("{}", detail);
+                    self.alert(AlertKind::CorruptMessage, AlertSeverity::Warning, &detail);
+                    return Ok(true);
+                }
+                #[cfg(feature = "latency-histograms")]
+                if let Some(wake_latency) = &mut self.wake_latency {
+                    wake_latency.record(&msg.header, ctl_time::monotonic_ns());
                 }
             }
-            ConsumeStartState::InFlight(_guard) => {
-                // Another consumer is in-flight - retry
-                // This is rare in single-consumer scenarios
+            RingConsume::Empty => {
+                self.empty_polls += 1;
+                // Periodically report we're still alive
+                if self.empty_polls % 1_000_000 == 0 {
+                    info!("Waiting for messages... (total received: {}, skipped: {})", self.msg_count, self.skipped);
+                }
             }
-            ConsumeStartState::SpedPast(_guard) => {
+            RingConsume::InFlight | RingConsume::SpedPast => {}
+        }
+
+        // The fragments of a payload larger than a slot are buffered until its last one
+        let (header, payload) = match self.reader.read(consumed) {
+            Ok(RingRead::Payload(msg, payload)) => (msg.header, payload),
+            Ok(RingRead::Consumed) => return Ok(true),
+            Ok(RingRead::Idle) => return Ok(false),
+            Ok(RingRead::Overtaken { snapshot }) => {
                 // Consumer was overtaken by the producer - some messages were missed
-                // The guard still contains valid data we can read
-                println!("[Warning] Consumer overtaken by producer, some messages missed");
-            }
-            ConsumeStartState::Empty => {
-                empty_polls += 1;
-                // Periodically report we're still alive
-                if empty_polls % 1_000_000 == 0 {
-                    println!("[Status] Waiting for messages... (total received: {})", msg_count);
+                let detail = format!("{} consumer overtaken by producer, some messages missed", self.reader.name());
+                if snapshot {
+                    warn!("{}, reading the state again", detail);
+                    self.snapshot_requested = true;
+                } else {
+                    warn!("{}, skipped", detail);
                 }
+                self.alert(AlertKind::RingOverflow, AlertSeverity::Warning, &detail);
+                return Ok(true);
+            }
+            Err(e) => {
+                let detail = format!("{} consumer overtaken by producer, some messages missed", self.reader.name());
+                error!("{}, exiting", detail);
+                self.alert(AlertKind::RingOverflow, AlertSeverity::Critical, &detail);
+                return Err(e);
+            }
+        };
+        // Already in the state read on attach
+        if !self.gate.admit(&header, payload) {
+            return Ok(true);
+        }
+        let msg_str = String::from_utf8_lossy(payload);
+
+        self.msg_count += 1;
+        ctl_log::hot_debug!(
+            "[{}] Received {:?} seq {} of symbol {:?} ({:?}): {}",
+            self.msg_count,
+            header.event_type(),
+            header.seq,
+            header.symbol_id(),
+            header.medium(),
+            msg_str
+        );
+        if let Some(start_ns) = trace_start_ns {
+            self.record_trace(&header, start_ns);
+        }
+        Ok(true)
+    }
+
+    /// Returns the start time of the consume of a message, if traced.
+    ///
+    /// LATENCY: FAST_PATH
+    fn trace_start(&self, header: &MessageHeader) -> Option<u64> {
+        self.tracer.as_ref().filter(|_| header.trace().is_traced()).map(|_| ctl_time::now_ns())
+    }
+
+    /// Records the `md.consume` span of a traced message, a child of the span
+    /// of its publisher.
+    ///
+    /// LATENCY: FAST_PATH (traced messages only)
+    fn record_trace(&self, header: &MessageHeader, start_ns: u64) {
+        if let Some(tracer) = &self.tracer {
+            let attributes = [("symbol_id", header.symbol_id as u64), ("seq", header.seq)];
+            tracer.record("md.consume", header.trace(), start_ns, &attributes);
+        }
+    }
+
+    /// Publishes an alert to the alerts ring.
+    fn alert(&self, kind: AlertKind, severity: AlertSeverity, detail: &str) {
+        let alert = AlertMessage::new(kind, severity, ctl_time::now_ms(), ALERT_SOURCE, detail);
+        if let Err(e) = self.alerts.publish(&alert) {
+            warn!("Failed to publish alert to {}: {}", ALERTS_RING_NAME, e);
+        }
+    }
+}
+
+/// Reads the conflated state of the symbols and the open orders of the OMS
+/// journal, if any, returning the gate of the deltas already in it.
+///
+/// LATENCY: SLOW_PATH
+fn read_snapshot(symbol_ids: &[u32], oms_journal: Option<&Path>) -> Result<JoinGate, FatalError> {
+    let mut gate = JoinGate::new();
+    let last_top = ShmRegion::<LastTopRegion>::open(LAST_TOP_REGION_NAME)?;
+    for &symbol_id in symbol_ids {
+        match gate.read_top(&last_top, symbol_id) {
+            Some(top) => info!(
+                "Symbol {} top at update {}: bid {}@{}, ask {}@{}",
+                symbol_id, top.update_id, top.bid_qty, top.bid_price, top.ask_qty, top.ask_price
+            ),
+            None => info!("Symbol {} has no top yet", symbol_id),
+        }
+
+        // Only the symbols with a book builder have a book snapshot region
+        let book = ShmRegion::<BookSnapshotRegion>::open(&book_region_name(symbol_id))
+            .ok()
+            .and_then(|region| region.read());
+        match book {
+            Some(book) if book.is_stale() => warn!("Symbol {} book is being resynced, not snapshotted", symbol_id),
+            Some(book) => {
+                gate.record(symbol_id, EventType::Depth, book.update_id);
+                info!(
+                    "Symbol {} book at update {}: {} bids from {:?}, {} asks from {:?}",
+                    symbol_id,
+                    book.update_id,
+                    book.bids().len(),
+                    book.best_bid(),
+                    book.asks().len(),
+                    book.best_ask()
+                );
             }
+            None => info!("Symbol {} has no book snapshot", symbol_id),
         }
     }
 
+    if let Some(path) = oms_journal {
+        let orders = Journal::read(path).fatal(FatalKind::Io)?;
+        let mut count = 0;
+        for order in orders.open_orders() {
+            count += 1;
+            info!(
+                "Open order {} {:?} {} {}@{} ({:?}, {} filled)",
+                order.client_order_id,
+                order.side,
+                order.symbol,
+                order.orig_qty,
+                order.price,
+                order.status,
+                order.executed_qty
+            );
+        }
+        info!("{} open orders in {}", count, path.display());
+    }
+    Ok(gate)
+}
+
+/// Classifies the failure of the consumer, overtaken under the fail-fast policy
+/// apart.
+fn consume_fatal(e: RingError) -> FatalError {
+    let kind = if matches!(e, RingError::Overtaken(_)) { FatalKind::Overtaken } else { FatalKind::Internal };
+    FatalError::new(kind, e)
+}
+
+fn main() -> ExitCode {
+    ctl_core::exit_code(run())
+}
+
+fn run() -> Result<(), FatalError> {
+    let args = Args::parse();
+    let _log = ctl_log::init_from_file(&args.log_config).fatal(FatalKind::Config)?;
+
+    info!("=== Binance Spot Market Data Subscriber ===");
+    #[cfg(not(feature = "shm-rings"))]
+    info!("Starting as DPDK secondary process...");
+
+    let md_config = HwResourcesConfig::from_file(&args.md_config).fatal(FatalKind::Config)?;
+    md_config.eal.apply_region_prefix();
+    let polling = PollingConfig::from_file(&args.polling_config)?.policy(POLLING_COMPONENT, DEFAULT_POLLING);
+    info!("Polling: {:?}", polling);
+    let scheduling = SchedulingConfig::from_file(&args.scheduling_config)?.policy(SCHEDULING_COMPONENT);
+    let overtaken = OvertakenConfig::from_file(&args.overtaken_config)?.policy(OVERTAKEN_COMPONENT, DEFAULT_OVERTAKEN);
+    info!("Overtaken: {:?}", overtaken);
+    info!("Scheduling: {:?}", scheduling);
+    let lcore = LcoresConfig::from_file(&args.lcores_config)?.lcore(LCORES_COMPONENT, DEFAULT_LCORE) as usize;
+    let telemetry = TelemetryConfig::from_file(&args.telemetry_config)?.policy(TELEMETRY_COMPONENT);
+    info!("Telemetry: {:?}", telemetry);
+
+    if args.check {
+        println!("ring {} on lcore {}, polling {:?}, overtaken {:?}", args.ring, lcore, polling, overtaken);
+        println!("Configuration OK");
+        return Ok(());
+    }
+
+    // Executed anew after a restart of ctl-resource-manager, wait for its next run to attach to
+    if let Some(stale) = reattaching() {
+        info!("Reattaching after the run epoch {}, waiting for the resource manager...", stale);
+        let epoch = wait_for_run(STATUS_REGION_NAME, stale, REATTACH_POLL_INTERVAL);
+        info!("Resource manager started the run epoch {}", epoch);
+    }
+
+    // Verify the host before the EAL initialization, which reports its failures cryptically
+    #[cfg(not(feature = "shm-rings"))]
+    Preflight::new()
+        .require_hugepages_mounted()
+        .require_isolated_lcores(&[lcore])
+        .require_capabilities(&[Capability::IpcLock])
+        .require_capabilities(&scheduling.capabilities())
+        .require_primary()
+        .with_file_prefix(md_config.eal.file_prefix())
+        .run()?;
+    // The shared memory rings need neither hugepages, isolated lcores nor a primary process
+    #[cfg(feature = "shm-rings")]
+    Preflight::new().require_capabilities(&scheduling.capabilities()).run()?;
+
+    // Before the scheduling, so that the exporter of the spans isn't pinned with the consumer
+    let tracer = telemetry.tracer(TELEMETRY_COMPONENT).fatal(FatalKind::Host)?;
+
+    // Before the EAL, deriving the affinity of its control threads from the process
+    scheduling.apply()?;
+
+    #[cfg(not(feature = "shm-rings"))]
+    let dpdk_env = DpdkEnvBuilder::default()
+        .process_type(DpdkProcessType::Secondary)
+        .lcore_ids(vec![lcore])
+        .main_lcore_id(lcore)
+        .eal_args(md_config.eal.secondary_args())
+        .build()
+        .fatal(FatalKind::DpdkInit)?;
+
+    #[cfg(not(feature = "shm-rings"))]
+    info!("DPDK environment initialized");
+
+    // Calibrate the timestamps of the hot path before the first message
+    info!("Timestamps from the {:?} clock ({:?} Hz)", ctl_time::calibrate(), ctl_time::tsc_hz());
+
+    // Record the run of ctl-resource-manager attached to, its rings being stale once it restarts
+    let epoch = EpochWatch::attach(STATUS_REGION_NAME, &ShmRegion::<StatusRegion>::open(STATUS_REGION_NAME)?)?;
+    info!("Attached to run epoch {}", epoch.attached());
+
+    // Discover the ring among those registered by resource-manager, looked up with the
+    // layout of its messages checked, so a stale binary fails instead of reading garbage
+    let metrics = Arc::new(ShmRegion::<MetricsRegion>::open(METRICS_REGION_NAME)?);
+    #[cfg(not(feature = "shm-rings"))]
+    let directory = RingDirectory::new(&dpdk_env, &metrics);
+    #[cfg(feature = "shm-rings")]
+    let directory = ShmRingDirectory::new(&metrics);
+    let ring_name = directory
+        .discover_of::<RawMessage>(&RingPattern::new(&args.ring))
+        .into_iter()
+        .next()
+        .map(|ring| ring.name)
+        .ok_or_else(|| format!("No market data ring matches '{}'", args.ring))
+        .fatal(FatalKind::SharedState)?;
+    info!("Looking up ring: {}", ring_name);
+    let ring = directory.lookup::<RawMessage>(&ring_name).fatal(FatalKind::SharedState)?;
+    let slot_size = metrics.slot_size(&ring_name).fatal(FatalKind::SharedState)?;
+    info!("Ring slot size: {} bytes", slot_size);
+
+    let alerts = directory.lookup::<AlertMessage>(ALERTS_RING_NAME).fatal(FatalKind::SharedState)?;
+
+    // Track the consumer position in the metrics region so its lag can be observed,
+    // refusing a second subscriber of the same name
+    let reader = RingReader::attach(&metrics, &ring_name, &args.consumer, slot_size)
+        .fatal(FatalKind::SharedState)?
+        .with_overtaken(overtaken);
+
+    info!("Ring found, attaching consumer {}...", args.consumer);
+    #[cfg(not(feature = "shm-rings"))]
+    let mut consumer = ring.attach_consumer().fatal(FatalKind::SharedState)?;
+    #[cfg(feature = "shm-rings")]
+    let mut consumer = RingLike::consumer(&ring);
+
+    info!("Consumer attached, starting to read messages...");
+
+    // Read the state once attached, so no delta published meanwhile is missed
+    let symbol_ids = match args.symbol_ids.is_empty() {
+        true => ring_name.parse::<RingName>().map(|name| vec![name.symbol_id]).unwrap_or_default(),
+        false => args.symbol_ids.clone(),
+    };
+    let gate = if args.snapshot {
+        read_snapshot(&symbol_ids, args.oms_journal.as_deref())?
+    } else {
+        JoinGate::new()
+    };
+
+    let filter = args.symbol_ids.iter().fold(MessageFilter::new(), |filter, &id| filter.with_symbol(id));
+    let filter = args.event_types.iter().fold(filter, |filter, &event_type| filter.with_event_type(event_type));
+    if !filter.is_empty() {
+        info!("Handling only symbol IDs {:?} of event types {:?}", args.symbol_ids, args.event_types);
+    }
+    let mut subscriber = Subscriber::new(reader, alerts).with_filter(filter).with_gate(gate);
+    if let Some(tracer) = tracer {
+        subscriber = subscriber.with_tracer(tracer);
+    }
+    // Record the wake latency of the messages in the latency group of their feedgroup
+    #[cfg(feature = "latency-histograms")]
+    {
+        subscriber = subscriber.with_wake_latency(WakeLatencyRecorder::new(metrics.clone()));
+    }
+    let mut poller = Poller::new(polling);
+    let mut last_epoch_check = Instant::now();
+
+    loop {
+        // Mark the message as consumed before reading it
+        let did_work = subscriber.on_consume(consume!(consumer)).map_err(consume_fatal)?;
+        // Overtaken, the deltas missed are made up for by the current state
+        if subscriber.take_snapshot_request() {
+            subscriber.gate = read_snapshot(&symbol_ids, args.oms_journal.as_deref())?;
+        }
+
+        // Reattach on a restart of ctl-resource-manager, rather than reading its stale ring
+        if !did_work && last_epoch_check.elapsed() >= EPOCH_CHECK_INTERVAL {
+            if let Err(e) = epoch.check() {
+                warn!("{}, reattaching", e);
+                return Err(reattach(&epoch));
+            }
+            last_epoch_check = Instant::now();
+        }
+
+        // Wait before the next poll according to the configured policy
+        poller.wait(did_work);
+    }
+
     #[allow(unreachable_code)]
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::Ordering;
+    use ctl_feed::{MediumTag, MemoryRing, RingConsumer, RingLike, RingMetrics, RAW_MESSAGE_SIZE};
+
+    fn reader(ring_metrics: &RingMetrics) -> RingReader<'_> {
+        RingReader::new(ring_metrics, 0, RAW_MESSAGE_SIZE)
+    }
+
+    fn message(payload: &str) -> RawMessage {
+        let mut message = RawMessage::default();
+        message.header.medium = MediumTag::Json as u8;
+        message.data[..payload.len()].copy_from_slice(payload.as_bytes());
+        message
+    }
+
+    #[test]
+    fn test_consume() {
+        let ring = MemoryRing::<RawMessage>::new(4);
+        let alerts = MemoryRing::<AlertMessage>::new(4);
+        let mut alert_consumer = alerts.consumer();
+        let ring_metrics = RingMetrics::default();
+        let cursor = &ring_metrics.consumers[0];
+        let mut subscriber = Subscriber::new(reader(&ring_metrics), &alerts);
+
+        let mut consumer = ring.consumer();
+        assert!(!subscriber.on_consume(consumer.consume()).unwrap());
+        assert_eq!(subscriber.empty_polls, 1);
+
+        for payload in [r#"{"s":"BTCUSDT"}"#, r#"{"s":"ETHUSDT"}"#] {
+            ring.publish(&message(payload)).unwrap();
+            ring_metrics.record_publish();
+        }
+        while subscriber.on_consume(consumer.consume()).unwrap() {}
+        assert_eq!(subscriber.msg_count, 2);
+        assert_eq!(cursor.position.load(Ordering::Relaxed), 2);
+        assert!(matches!(alert_consumer.consume(), RingConsume::Empty));
+    }
+
+    #[test]
+    fn test_sped_past_alert() {
+        let ring = MemoryRing::<RawMessage>::new(4);
+        let alerts = MemoryRing::<AlertMessage>::new(4);
+        let mut alert_consumer = alerts.consumer();
+        let ring_metrics = RingMetrics::default();
+        let cursor = &ring_metrics.consumers[0];
+        let mut subscriber = Subscriber::new(reader(&ring_metrics), &alerts);
+
+        let mut consumer = ring.consumer();
+        for _ in 0..10 {
+            ring.publish(&message("{}")).unwrap();
+            ring_metrics.record_publish();
+        }
+        assert!(subscriber.on_consume(consumer.consume()).unwrap());
+        assert_eq!(cursor.overruns.load(Ordering::Relaxed), 1);
+        assert_eq!(cursor.position.load(Ordering::Relaxed), 10);
+        let RingConsume::Message(alert) = alert_consumer.consume() else {
+            panic!("expected an overflow alert");
+        };
+        assert_eq!(alert.kind, AlertKind::RingOverflow as u8);
+
+        // The messages still held are consumed after the resynchronization
+        while subscriber.on_consume(consumer.consume()).unwrap() {}
+        assert_eq!(subscriber.msg_count, 4);
+    }
+
+    #[test]
+    fn test_overtaken_policies() {
+        let ring = MemoryRing::<RawMessage>::new(4);
+        let alerts = MemoryRing::<AlertMessage>::new(4);
+        let mut alert_consumer = alerts.consumer();
+        let ring_metrics = RingMetrics::default();
+        let cursor = &ring_metrics.consumers[0];
+
+        // The state is requested once
+        let mut subscriber =
+            Subscriber::new(reader(&ring_metrics).with_overtaken(OvertakenPolicy::SkipAndRequestSnapshot), &alerts);
+        let mut consumer = ring.consumer();
+        for _ in 0..10 {
+            ring.publish(&message("{}")).unwrap();
+            ring_metrics.record_publish();
+        }
+        assert!(subscriber.on_consume(consumer.consume()).unwrap());
+        assert!(subscriber.take_snapshot_request());
+        assert!(!subscriber.take_snapshot_request());
+        assert!(matches!(alert_consumer.consume(), RingConsume::Message(_)));
+
+        // The subscriber stops with a critical alert
+        let mut subscriber = Subscriber::new(reader(&ring_metrics).with_overtaken(OvertakenPolicy::FailFast), &alerts);
+        let mut consumer = ring.consumer();
+        for _ in 0..10 {
+            ring.publish(&message("{}")).unwrap();
+            ring_metrics.record_publish();
+        }
+        assert!(matches!(subscriber.on_consume(consumer.consume()), Err(RingError::Overtaken(_))));
+        let RingConsume::Message(alert) = alert_consumer.consume() else {
+            panic!("expected an overflow alert");
+        };
+        assert_eq!(alert.severity, AlertSeverity::Critical as u8);
+    }
+
+    #[test]
+    fn test_filter() {
+        let ring = MemoryRing::<RawMessage>::new(8);
+        let alerts = MemoryRing::<AlertMessage>::new(4);
+        let ring_metrics = RingMetrics::default();
+        let cursor = &ring_metrics.consumers[0];
+        let filter = MessageFilter::new().with_symbol(1).with_event_type(EventType::Trade);
+        let mut subscriber = Subscriber::new(reader(&ring_metrics), &alerts).with_filter(filter);
+
+        let mut consumer = ring.consumer();
+        for (event_type, symbol_id) in [(EventType::Trade, 1), (EventType::Trade, 2), (EventType::BookTicker, 1)] {
+            let mut message = message(r#"{"s":"BTCUSDT"}"#);
+            message.header.event_type = event_type as u8;
+            message.header.symbol_id = symbol_id;
+            ring.publish(&message).unwrap();
+            ring_metrics.record_publish();
+        }
+        while subscriber.on_consume(consumer.consume()).unwrap() {}
+        assert_eq!(subscriber.msg_count, 1);
+        assert_eq!(subscriber.skipped, 2);
+        // The skipped messages are consumed all the same
+        assert_eq!(cursor.position.load(Ordering::Relaxed), 3);
+    }
+
+    #[test]
+    fn test_fragmented_payload() {
+        let ring = MemoryRing::<RawMessage>::new(8);
+        let alerts = MemoryRing::<AlertMessage>::new(4);
+        let ring_metrics = RingMetrics::default();
+        let cursor = &ring_metrics.consumers[0];
+        let mut subscriber = Subscriber::new(RingReader::new(&ring_metrics, 0, 64), &alerts);
+
+        // A 152 byte payload in three 64 byte slots
+        let payload = format!(r#"{{"s":"BTCUSDT","b":"{}"}}"#, "1".repeat(130));
+        let mut consumer = ring.consumer();
+        for (index, chunk) in payload.as_bytes().chunks(64).enumerate() {
+            let mut message = message(std::str::from_utf8(chunk).unwrap());
+            message.header.set_fragment(index as u16, index < 2);
+            ring.publish(&message).unwrap();
+            ring_metrics.record_publish();
+        }
+        while subscriber.on_consume(consumer.consume()).unwrap() {}
+        assert_eq!(subscriber.msg_count, 1);
+        assert_eq!(cursor.position.load(Ordering::Relaxed), 3);
+    }
+
+    #[test]
+    fn test_snapshot_gate() {
+        let ring = MemoryRing::<RawMessage>::new(8);
+        let alerts = MemoryRing::<AlertMessage>::new(4);
+        let ring_metrics = RingMetrics::default();
+        let cursor = &ring_metrics.consumers[0];
+        let mut gate = JoinGate::new();
+        gate.record(0, EventType::BookTicker, 11);
+        let mut subscriber = Subscriber::new(reader(&ring_metrics), &alerts).with_gate(gate);
+
+        // The updates published between the attach and the snapshot are skipped
+        let mut consumer = ring.consumer();
+        for update_id in [10, 11, 12, 13] {
+            let mut message = message(&format!(r#"{{"u":{},"s":"BTCUSDT"}}"#, update_id));
+            message.header.event_type = EventType::BookTicker as u8;
+            ring.publish(&message).unwrap();
+            ring_metrics.record_publish();
+        }
+        while subscriber.on_consume(consumer.consume()).unwrap() {}
+        assert_eq!(subscriber.msg_count, 2);
+        assert_eq!(subscriber.gate.skipped(), 2);
+        assert_eq!(cursor.position.load(Ordering::Relaxed), 4);
+    }
+
+    #[test]
+    fn test_traced_consume() {
+        let ring = MemoryRing::<RawMessage>::new(4);
+        let alerts = MemoryRing::<AlertMessage>::new(4);
+        let ring_metrics = RingMetrics::default();
+        let cursor = &ring_metrics.consumers[0];
+        let (sink, spans) = std::sync::mpsc::sync_channel(4);
+        let tracer = Tracer::new(0).with_sink(sink, 0);
+        let mut subscriber = Subscriber::new(reader(&ring_metrics), &alerts).with_tracer(tracer);
+
+        // Only the traced message is recorded, a child of the span of its publisher
+        let parent = ctl_core::TraceContext { trace_id: 7, span_id: 9 };
+        let mut consumer = ring.consumer();
+        for trace in [ctl_core::TraceContext::default(), parent] {
+            let mut message = message(r#"{"s":"BTCUSDT"}"#);
+            message.header.set_trace(trace);
+            ring.publish(&message).unwrap();
+            ring_metrics.record_publish();
+        }
+        while subscriber.on_consume(consumer.consume()).unwrap() {}
+        let span = spans.try_recv().unwrap();
+        assert_eq!((span.name, span.context.trace_id, span.parent_span_id), ("md.consume", 7, 9));
+        assert!(spans.try_recv().is_err());
+    }
+
+    #[cfg(feature = "message-checksums")]
+    #[test]
+    fn test_corrupt_message_alert() {
+        let ring = MemoryRing::<RawMessage>::new(4);
+        let alerts = MemoryRing::<AlertMessage>::new(4);
+        let mut alert_consumer = alerts.consumer();
+        let ring_metrics = RingMetrics::default();
+        let cursor = &ring_metrics.consumers[0];
+        let mut subscriber = Subscriber::new(reader(&ring_metrics), &alerts);
+
+        let mut consumer = ring.consumer();
+        let mut sealed = message(r#"{"s":"BTCUSDT"}"#);
+        sealed.seal();
+        let mut torn = sealed;
+        torn.data[2] = b'x';
+        for message in [sealed, torn] {
+            ring.publish(&message).unwrap();
+            ring_metrics.record_publish();
+        }
+        while subscriber.on_consume(consumer.consume()).unwrap() {}
+        assert_eq!(subscriber.msg_count, 1);
+        assert_eq!(subscriber.corrupt_count, 1);
+        assert_eq!(cursor.position.load(Ordering::Relaxed), 2);
+        let RingConsume::Message(alert) = alert_consumer.consume() else {
+            panic!("expected a corrupt message alert");
+        };
+        assert_eq!(alert.kind(), AlertKind::CorruptMessage);
+    }
+}
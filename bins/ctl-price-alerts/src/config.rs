@@ -0,0 +1,320 @@
+//! Configuration module for the price alerts.
+//!
+//! This module provides the YAML parser and validation for the price alerts
+//! configuration defined in `configs/price-alerts/price-alerts.yaml`.
+
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+use serde::Deserialize;
+
+use crate::PriceAlertsConfigError;
+
+/// Default minimum interval between the alerts of a rule, in milliseconds.
+const DEFAULT_COOLDOWN_MS: u64 = 60_000;
+
+/// Default length of the windows of a volume spike rule, in seconds.
+const DEFAULT_WINDOW_SECS: u64 = 60;
+
+/// Default number of windows the volume of the current window is compared to.
+const DEFAULT_BASELINE_WINDOWS: usize = 30;
+
+/// Default timeout of a notification request, in milliseconds.
+const DEFAULT_TIMEOUT_MS: u64 = 5_000;
+
+/// Default number of alerts queued for the notifier, the newest dropped past it.
+const DEFAULT_MAX_PENDING: usize = 256;
+
+/// Default URL of the Telegram Bot API.
+const DEFAULT_TELEGRAM_API: &str = "https://api.telegram.org";
+
+fn default_cooldown_ms() -> u64 {
+    DEFAULT_COOLDOWN_MS
+}
+
+fn default_window_secs() -> u64 {
+    DEFAULT_WINDOW_SECS
+}
+
+fn default_baseline_windows() -> usize {
+    DEFAULT_BASELINE_WINDOWS
+}
+
+fn default_timeout_ms() -> u64 {
+    DEFAULT_TIMEOUT_MS
+}
+
+fn default_max_pending() -> usize {
+    DEFAULT_MAX_PENDING
+}
+
+fn default_telegram_api() -> String {
+    DEFAULT_TELEGRAM_API.to_string()
+}
+
+/// The side of a level a price crosses to.
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Direction {
+    /// From below the level to at or above it.
+    Above,
+    /// From above the level to at or below it.
+    Below,
+}
+
+impl Direction {
+    /// Returns the name of the direction.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Direction::Above => "above",
+            Direction::Below => "below",
+        }
+    }
+}
+
+/// A rule evaluated against the rings of its symbol.
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum AlertRule {
+    /// The last trade price crosses a level.
+    PriceCross {
+        /// The symbol (e.g. `BTCUSDT`).
+        symbol: String,
+        /// The price level.
+        level: f64,
+        /// The side of the level the price crosses to.
+        direction: Direction,
+    },
+    /// The spread of the Top exceeds a threshold.
+    Spread {
+        /// The symbol (e.g. `BTCUSDT`).
+        symbol: String,
+        /// The widest spread, in basis points of the mid price.
+        max_bps: f64,
+    },
+    /// The volume traded in a window exceeds a multiple of the mean volume of
+    /// the windows before it.
+    VolumeSpike {
+        /// The symbol (e.g. `BTCUSDT`).
+        symbol: String,
+        /// The length of the windows, in seconds.
+        #[serde(default = "default_window_secs")]
+        window_secs: u64,
+        /// The number of windows the mean volume is taken over.
+        #[serde(default = "default_baseline_windows")]
+        baseline_windows: usize,
+        /// The multiple of the mean volume raising the alert.
+        factor: f64,
+        /// The smallest volume of a window raising the alert, quiet symbols
+        /// spiking on a few trades otherwise.
+        #[serde(default)]
+        min_qty: f64,
+    },
+}
+
+impl AlertRule {
+    /// Returns the symbol of the rule.
+    pub fn symbol(&self) -> &str {
+        match self {
+            AlertRule::PriceCross { symbol, .. }
+            | AlertRule::Spread { symbol, .. }
+            | AlertRule::VolumeSpike { symbol, .. } => symbol,
+        }
+    }
+
+    /// Returns whether the rule is evaluated against the Top rings.
+    pub fn needs_top(&self) -> bool {
+        matches!(self, AlertRule::Spread { .. })
+    }
+
+    /// Returns whether the rule is evaluated against the trade rings.
+    pub fn needs_trades(&self) -> bool {
+        !self.needs_top()
+    }
+}
+
+/// The Telegram chat the alerts are sent to.
+#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+pub struct TelegramConfig {
+    /// The token of the bot sending the messages.
+    pub bot_token: String,
+    /// The chat ID (e.g. `-1001234567890`).
+    pub chat_id: String,
+    /// The URL of the Bot API.
+    #[serde(default = "default_telegram_api")]
+    pub api_url: String,
+}
+
+/// The notification channels the alerts are delivered to, besides the alerts ring.
+#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+pub struct NotifyConfig {
+    /// The URL the alerts are posted to as JSON.
+    #[serde(default)]
+    pub webhook_url: Option<String>,
+    /// The Telegram chat the alerts are sent to.
+    #[serde(default)]
+    pub telegram: Option<TelegramConfig>,
+    /// The timeout of a notification request, in milliseconds.
+    #[serde(default = "default_timeout_ms")]
+    pub timeout_ms: u64,
+    /// The number of alerts queued for the notifier, the newest dropped past it.
+    #[serde(default = "default_max_pending")]
+    pub max_pending: usize,
+}
+
+impl NotifyConfig {
+    /// Returns the timeout of a notification request.
+    pub fn timeout(&self) -> Duration {
+        Duration::from_millis(self.timeout_ms)
+    }
+}
+
+/// The configuration of the price alerts.
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+pub struct PriceAlertsConfig {
+    /// The rules.
+    pub rules: Vec<AlertRule>,
+    /// The minimum interval between the alerts of a rule, in milliseconds.
+    #[serde(default = "default_cooldown_ms")]
+    pub cooldown_ms: u64,
+    /// The notification channels, the alerts only published to the alerts ring if unset.
+    #[serde(default)]
+    pub notify: Option<NotifyConfig>,
+}
+
+impl PriceAlertsConfig {
+    /// Parses the price alerts configuration from a YAML file.
+    ///
+    /// # Errors
+    /// Returns an error if the file cannot be read, parsed, or fails validation.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, PriceAlertsConfigError> {
+        let content = fs::read_to_string(path)?;
+        Self::from_str(&content)
+    }
+
+    /// Parses the price alerts configuration from a YAML string.
+    ///
+    /// # Errors
+    /// Returns an error if the YAML cannot be parsed or fails validation.
+    pub fn from_str(content: &str) -> Result<Self, PriceAlertsConfigError> {
+        let config: Self = serde_yaml::from_str(content)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Validates the price alerts configuration.
+    fn validate(&self) -> Result<(), PriceAlertsConfigError> {
+        if self.rules.is_empty() {
+            return Err(PriceAlertsConfigError::ValidationError("At least one rule must be configured".to_string()));
+        }
+        for rule in &self.rules {
+            if rule.symbol().is_empty() {
+                return Err(PriceAlertsConfigError::ValidationError("Rule symbol cannot be empty".to_string()));
+            }
+            let valid = match rule {
+                AlertRule::PriceCross { level, .. } => level.is_finite() && *level > 0.0,
+                AlertRule::Spread { max_bps, .. } => max_bps.is_finite() && *max_bps > 0.0,
+                AlertRule::VolumeSpike { window_secs, baseline_windows, factor, min_qty, .. } => {
+                    *window_secs > 0
+                        && *baseline_windows > 0
+                        && factor.is_finite()
+                        && *factor > 1.0
+                        && min_qty.is_finite()
+                        && *min_qty >= 0.0
+                }
+            };
+            if !valid {
+                return Err(PriceAlertsConfigError::ValidationError(format!("Invalid rule {:?}", rule)));
+            }
+        }
+        let Some(notify) = &self.notify else {
+            return Ok(());
+        };
+        let is_http = |url: &str| url.starts_with("http://") || url.starts_with("https://");
+        if let Some(url) = &notify.webhook_url {
+            if !is_http(url) {
+                return Err(PriceAlertsConfigError::ValidationError(format!(
+                    "Webhook URL '{}' is not an HTTP URL",
+                    url
+                )));
+            }
+        }
+        if let Some(telegram) = &notify.telegram {
+            if telegram.bot_token.is_empty() || telegram.chat_id.is_empty() || !is_http(&telegram.api_url) {
+                return Err(PriceAlertsConfigError::ValidationError(
+                    "Telegram needs a bot token, a chat ID and an HTTP API URL".to_string(),
+                ));
+            }
+        }
+        if notify.webhook_url.is_none() && notify.telegram.is_none() {
+            return Err(PriceAlertsConfigError::ValidationError(
+                "Notify needs a webhook URL or a Telegram chat".to_string(),
+            ));
+        }
+        if notify.timeout_ms == 0 || notify.max_pending == 0 {
+            return Err(PriceAlertsConfigError::ValidationError(
+                "Notify timeout and max pending must be greater than 0".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Returns the minimum interval between the alerts of a rule.
+    pub fn cooldown(&self) -> Duration {
+        Duration::from_millis(self.cooldown_ms)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_config() {
+        let yaml = r#"
+rules:
+  - kind: price_cross
+    symbol: BTCUSDT
+    level: 70000
+    direction: above
+  - kind: spread
+    symbol: ETHUSDT
+    max_bps: 5
+  - kind: volume_spike
+    symbol: BTCUSDT
+    factor: 4
+notify:
+  telegram:
+    bot_token: "123:abc"
+    chat_id: "-100"
+"#;
+        let config = PriceAlertsConfig::from_str(yaml).unwrap();
+        assert_eq!(config.rules.len(), 3);
+        let symbol = "BTCUSDT".to_string();
+        assert_eq!(config.rules[0], AlertRule::PriceCross { symbol, level: 70000.0, direction: Direction::Above });
+        assert!(config.rules[1].needs_top() && config.rules[2].needs_trades());
+        let AlertRule::VolumeSpike { window_secs, baseline_windows, min_qty, .. } = config.rules[2] else {
+            panic!("expected a volume spike rule");
+        };
+        assert_eq!((window_secs, baseline_windows, min_qty), (DEFAULT_WINDOW_SECS, DEFAULT_BASELINE_WINDOWS, 0.0));
+        assert_eq!(config.cooldown(), Duration::from_secs(60));
+
+        let notify = config.notify.unwrap();
+        assert_eq!((notify.webhook_url, notify.max_pending), (None, DEFAULT_MAX_PENDING));
+        assert_eq!(notify.telegram.unwrap().api_url, DEFAULT_TELEGRAM_API);
+    }
+
+    #[test]
+    fn test_invalid_config() {
+        let rule = "rules:\n  - kind: spread\n    symbol: BTCUSDT\n    max_bps: 5\n";
+        assert!(PriceAlertsConfig::from_str(rule).is_ok());
+        assert!(PriceAlertsConfig::from_str("rules: []").is_err());
+        assert!(PriceAlertsConfig::from_str(&rule.replace("max_bps: 5", "max_bps: 0")).is_err());
+        let spike = "rules:\n  - kind: volume_spike\n    symbol: BTCUSDT\n    factor: 0.5\n";
+        assert!(PriceAlertsConfig::from_str(spike).is_err());
+        assert!(PriceAlertsConfig::from_str(&format!("{}notify:\n  timeout_ms: 100\n", rule)).is_err());
+        assert!(PriceAlertsConfig::from_str(&format!("{}notify:\n  webhook_url: localhost\n", rule)).is_err());
+        assert!(PriceAlertsConfig::from_str(&format!("{}notify:\n  webhook_url: http://hooks\n", rule)).is_ok());
+    }
+}
@@ -0,0 +1,26 @@
+use thiserror::Error;
+
+/// Errors that can occur when parsing or validating the price alerts configuration.
+#[derive(Debug, Error)]
+pub enum PriceAlertsConfigError {
+    /// Error reading the configuration file.
+    #[error("Failed to read configuration file: {0}")]
+    FileReadError(#[from] std::io::Error),
+    /// Error parsing the YAML configuration.
+    #[error("Failed to parse YAML configuration: {0}")]
+    YamlParseError(#[from] serde_yaml::Error),
+    /// Validation error with a descriptive message.
+    #[error("Configuration validation error: {0}")]
+    ValidationError(String),
+}
+
+/// Errors that can occur when delivering an alert to a notification channel.
+#[derive(Debug, Error)]
+pub enum NotifyError {
+    /// Error reaching the channel.
+    #[error("Notification request failed: {0}")]
+    Request(#[from] reqwest::Error),
+    /// The channel refused the notification.
+    #[error("Notification refused: {status} {body}")]
+    Rejected { status: u16, body: String },
+}
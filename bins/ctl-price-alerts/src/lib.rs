@@ -0,0 +1,21 @@
+//! Price and spread alerting of the market data of Binance Spot.
+//!
+//! Evaluates the configured rules against the Top and trade rings like any
+//! other consumer: a trade price crossing a level, a Top spread widening past
+//! a threshold, or the volume of a window spiking past a multiple of the mean
+//! volume of the windows before it. The alerts raised are published to the
+//! alerts ring and, optionally, delivered to a webhook and a Telegram chat by
+//! a notifier thread behind a bounded queue, so a slow or unreachable channel
+//! never holds back the rings.
+
+mod config;
+mod errors;
+mod notify;
+mod rules;
+mod trade;
+
+pub use config::{AlertRule, Direction, NotifyConfig, PriceAlertsConfig, TelegramConfig};
+pub use errors::{NotifyError, PriceAlertsConfigError};
+pub use notify::{Notifier, NotifyStats};
+pub use rules::{RuleEngine, Triggered};
+pub use trade::TradePrint;
@@ -0,0 +1,259 @@
+//! Price Alerts for the Binance Spot Controller.
+//!
+//! Connects as a DPDK secondary process, attaches a consumer to the Top rings
+//! and the trade rings (or the aggregated trade rings without a trade feed)
+//! carrying the symbols of the configured rules, and evaluates the rules on
+//! each message: price crossing a level, spread exceeding a threshold, volume
+//! spiking. The alerts raised are published to the alerts ring and, if
+//! configured, handed to the notifier thread delivering them to a webhook and
+//! a Telegram chat.
+//!
+//! The rings are polled under the policy of `configs/polling.yaml`. Once
+//! overtaken by a producer, the alerts recover per `configs/overtaken.yaml`;
+//! the rules are evaluated on each message rather than on a conflated state,
+//! so a snapshot requested by `skip-and-request-snapshot` is a skip to the head.
+//!
+//! The exit code tells the class of a failure (see `FatalKind`).
+
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::sync::atomic::Ordering;
+use std::time::{Duration, Instant};
+
+use clap::Parser;
+use ctl_core::{
+    AlertMessage, Capability, Fatal, FatalError, FatalKind, LcoresConfig, OvertakenConfig, OvertakenPolicy, Poller,
+    PollingConfig, PollingPolicy, Preflight, RingKind, RingName, ALERTS_RING_NAME,
+};
+use ctl_feed::{
+    dpdk_consume, slot_payload, MetricsRegion, RawMessage, RingRead, RingReader, TopSnapshot, METRICS_REGION_NAME,
+};
+use ctl_md_handler::{HwResourcesConfig, SymbolInfoConfig};
+use ctl_price_alerts::{Notifier, PriceAlertsConfig, RuleEngine, TradePrint};
+use ctl_shm::ShmRegion;
+use ctl_time::now_ms;
+use dpdk::{DpdkEnvBuilder, DpdkProcessType};
+use tracing::{info, warn};
+
+// Logging configuration, its levels reloaded on change
+const LOG_CONFIG_PATH: &str = "configs/logging.yaml";
+
+// The market data configuration, holding the feeds and the EAL options of the secondary processes
+const MD_CONFIG_PATH: &str = "configs/market-data/hw-resources.yaml";
+
+// The symbol IDs of the rings
+const SYMBOL_INFO_PATH: &str = "configs/market-data/symbolinfo.yaml";
+
+// The price alerts configuration
+const PRICE_ALERTS_CONFIG_PATH: &str = "configs/price-alerts/price-alerts.yaml";
+
+// The name of the consumer cursors of the alerts in the rings
+const CONSUMER_NAME: &str = "price-alerts";
+
+// Source of the alerts published to the alerts ring
+const ALERT_SOURCE: &str = "price-alerts";
+
+// The lcore of the alerts, unless configured, shared with the mostly idle tools
+const LCORES_CONFIG_PATH: &str = "configs/lcores.yaml";
+const LCORES_COMPONENT: &str = "price-alerts";
+const DEFAULT_LCORE: u32 = 15;
+
+// Polling policy between the passes over the empty rings, unless configured for this component
+const POLLING_CONFIG_PATH: &str = "configs/polling.yaml";
+const POLLING_COMPONENT: &str = "price-alerts";
+const DEFAULT_POLLING: PollingPolicy = PollingPolicy::Sleep { sleep_us: 100 };
+
+// Recovery once overtaken by a producer, unless configured for this component
+const OVERTAKEN_CONFIG_PATH: &str = "configs/overtaken.yaml";
+const OVERTAKEN_COMPONENT: &str = "price-alerts";
+const DEFAULT_OVERTAKEN: OvertakenPolicy = OvertakenPolicy::SkipToHead;
+
+// Interval between the logs of the counters of the alerts
+const STATS_LOG_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Price and spread alerts of the market data rings.
+#[derive(Debug, Parser)]
+#[command(version)]
+struct Args {
+    /// Logging configuration.
+    #[arg(long, env = "CTL_LOG_CONFIG", default_value = LOG_CONFIG_PATH)]
+    log_config: PathBuf,
+    /// Market data configuration.
+    #[arg(long, env = "CTL_MD_CONFIG", default_value = MD_CONFIG_PATH)]
+    md_config: PathBuf,
+    /// Symbol info configuration.
+    #[arg(long, env = "CTL_SYMBOL_INFO", default_value = SYMBOL_INFO_PATH)]
+    symbol_info: PathBuf,
+    /// Price alerts configuration.
+    #[arg(long, env = "CTL_PRICE_ALERTS_CONFIG", default_value = PRICE_ALERTS_CONFIG_PATH)]
+    price_alerts_config: PathBuf,
+    /// Lcores configuration.
+    #[arg(long, env = "CTL_LCORES_CONFIG", default_value = LCORES_CONFIG_PATH)]
+    lcores_config: PathBuf,
+    /// Polling configuration.
+    #[arg(long, env = "CTL_POLLING_CONFIG", default_value = POLLING_CONFIG_PATH)]
+    polling_config: PathBuf,
+    /// Overtaken consumer recovery configuration.
+    #[arg(long, env = "CTL_OVERTAKEN_CONFIG", default_value = OVERTAKEN_CONFIG_PATH)]
+    overtaken_config: PathBuf,
+}
+
+fn main() -> ExitCode {
+    ctl_core::exit_code(run())
+}
+
+fn run() -> Result<(), FatalError> {
+    let args = Args::parse();
+    let _log = ctl_log::init_from_file(&args.log_config).fatal(FatalKind::Config)?;
+
+    info!("=== Binance Spot Price Alerts ===");
+
+    let md_config = HwResourcesConfig::from_file(&args.md_config).fatal(FatalKind::Config)?;
+    let symbol_info = SymbolInfoConfig::from_file(&args.symbol_info).fatal(FatalKind::Config)?;
+    let config = PriceAlertsConfig::from_file(&args.price_alerts_config).fatal(FatalKind::Config)?;
+    md_config.eal.apply_region_prefix();
+    let lcore = LcoresConfig::from_file(&args.lcores_config)?.lcore(LCORES_COMPONENT, DEFAULT_LCORE) as usize;
+    let polling = PollingConfig::from_file(&args.polling_config)?.policy(POLLING_COMPONENT, DEFAULT_POLLING);
+    info!("Polling: {:?}", polling);
+    let overtaken = OvertakenConfig::from_file(&args.overtaken_config)?.policy(OVERTAKEN_COMPONENT, DEFAULT_OVERTAKEN);
+    info!("Overtaken: {:?}", overtaken);
+    let mut engine = RuleEngine::new(&config);
+
+    // Verify the host before the EAL initialization, which reports its failures cryptically
+    Preflight::new()
+        .require_hugepages_mounted()
+        .require_capabilities(&[Capability::IpcLock])
+        .require_primary()
+        .with_file_prefix(md_config.eal.file_prefix())
+        .run()?;
+
+    let dpdk_env = DpdkEnvBuilder::default()
+        .process_type(DpdkProcessType::Secondary)
+        .lcore_ids(vec![lcore])
+        .main_lcore_id(lcore)
+        .eal_args(md_config.eal.secondary_args())
+        .build()
+        .fatal(FatalKind::DpdkInit)?;
+
+    let metrics = ShmRegion::<MetricsRegion>::open(METRICS_REGION_NAME)?;
+    let alerts = dpdk_env.pubsub_lookup::<AlertMessage>(ALERTS_RING_NAME).fatal(FatalKind::SharedState)?;
+    metrics.check_layout::<AlertMessage>(ALERTS_RING_NAME).fatal(FatalKind::SharedState)?;
+
+    // The trades come from the trade rings, or the aggregated trade rings without a trade feed
+    let (trade_kind, trade_feed) = match md_config.find_feed("trade") {
+        Some(_) => (RingKind::Trade, "trade"),
+        None => (RingKind::AggTrade, "aggtrade"),
+    };
+
+    // Attach to the rings carrying the symbols of the rules, tracking the positions in their metrics like any consumer
+    let mut rings = Vec::new();
+    let ring_kinds = [
+        (RingKind::Top, "top", engine.top_symbols().map(str::to_string).collect::<Vec<_>>()),
+        (trade_kind, trade_feed, engine.trade_symbols().map(str::to_string).collect()),
+    ];
+    for (kind, feed_kind, rule_symbols) in ring_kinds {
+        for rule_symbol in &rule_symbols {
+            if symbol_info.symbol_id(rule_symbol).is_none() {
+                return Err(FatalError::new(
+                    FatalKind::Config,
+                    format!("Symbol '{}' of a rule not found in symbolinfo.yaml", rule_symbol),
+                ));
+            }
+            let carried = md_config
+                .find_feed(feed_kind)
+                .is_some_and(|feed| feed.feed_sets().iter().any(|set| set.ring_symbol(rule_symbol).is_some()));
+            if !carried {
+                return Err(FatalError::new(
+                    FatalKind::Config,
+                    format!("Symbol '{}' of a rule has no {} feed", rule_symbol, feed_kind),
+                ));
+            }
+        }
+        let Some(feed) = md_config.find_feed(feed_kind) else {
+            continue;
+        };
+        for feed_set in feed.feed_sets() {
+            for symbol in feed_set.ring_symbols() {
+                // The rings of an aggregated set carry the messages of all its symbols
+                let has_rules = rule_symbols.iter().any(|rule| feed_set.ring_symbol(rule) == Some(symbol.as_str()));
+                if !has_rules {
+                    continue;
+                }
+                let symbol_id = symbol_info
+                    .symbol_id(symbol)
+                    .ok_or_else(|| format!("Symbol '{}' not found in symbolinfo.yaml", symbol))
+                    .fatal(FatalKind::Config)?;
+                let name = RingName::pubsub(kind, symbol_id).to_string();
+                let reader = RingReader::attach(&metrics, &name, CONSUMER_NAME, feed_set.slot_size)
+                    .fatal(FatalKind::SharedState)?
+                    .with_overtaken(overtaken);
+                let ring = dpdk_env.pubsub_lookup::<RawMessage>(&name).fatal(FatalKind::SharedState)?;
+                let consumer = ring.attach_consumer().fatal(FatalKind::SharedState)?;
+                info!("[{}] {}", symbol, name);
+                rings.push((kind, reader, consumer));
+            }
+        }
+    }
+
+    let notifier = match config.notify.clone() {
+        Some(notify) => Some(Notifier::spawn(notify).fatal(FatalKind::Internal)?),
+        None => None,
+    };
+    info!("Evaluating {} rules over {} rings", config.rules.len(), rings.len());
+
+    let mut triggered = Vec::new();
+    let mut raised: u64 = 0;
+    let mut last_log = Instant::now();
+    let mut poller = Poller::new(polling);
+    loop {
+        let mut did_work = false;
+        for (kind, reader, consumer) in rings.iter_mut() {
+            let read = reader.read(dpdk_consume!(consumer)).fatal(FatalKind::Overtaken)?;
+            did_work |= read.did_work();
+            match read {
+                RingRead::Payload(message, payload) => {
+                    let recv_time_ms = message.header.ts_ms;
+                    let payload = slot_payload(payload);
+                    if *kind == RingKind::Top {
+                        if let Some((symbol, top)) = TopSnapshot::from_book_ticker(payload) {
+                            engine.on_top(symbol, &top, recv_time_ms, &mut triggered);
+                        }
+                    } else if let Some((symbol, trade)) = TradePrint::from_json(payload) {
+                        engine.on_trade(symbol, &trade, recv_time_ms, &mut triggered);
+                    }
+                }
+                RingRead::Overtaken { .. } => {
+                    warn!("{} consumer overtaken by producer, some messages missed", reader.name());
+                }
+                RingRead::Consumed | RingRead::Idle => {}
+            }
+        }
+
+        for alert in triggered.drain(..) {
+            raised += 1;
+            info!("{:?} {:?}: {}", alert.severity, alert.kind, alert.detail);
+            let message = AlertMessage::new(alert.kind, alert.severity, now_ms(), ALERT_SOURCE, &alert.detail);
+            if let Err(e) = alerts.publish(&message) {
+                warn!("Failed to publish alert to {}: {:?}", ALERTS_RING_NAME, e);
+            }
+            if let Some(notifier) = &notifier {
+                notifier.notify(&message);
+            }
+        }
+        if !did_work && last_log.elapsed() >= STATS_LOG_INTERVAL {
+            last_log = Instant::now();
+            info!("Alerts raised: {}, suppressed by the cooldown: {}", raised, engine.suppressed());
+            if let Some(notifier) = &notifier {
+                let stats = notifier.stats();
+                info!(
+                    "Notifications sent: {}, failed: {}, dropped for a backed up notifier: {}",
+                    stats.sent.load(Ordering::Relaxed),
+                    stats.failed.load(Ordering::Relaxed),
+                    stats.dropped.load(Ordering::Relaxed)
+                );
+            }
+        }
+        // Wait before the next pass according to the configured policy
+        poller.wait(did_work);
+    }
+}
@@ -0,0 +1,143 @@
+//! The delivery of the alerts to the webhook and Telegram chat of the
+//! configuration, from a thread of its own behind a bounded queue, so a slow
+//! or unreachable channel never holds back the rings.
+
+use std::io;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, SyncSender, TrySendError};
+use std::sync::Arc;
+use std::thread;
+
+use ctl_core::AlertMessage;
+use reqwest::blocking::{Client, RequestBuilder};
+use serde_json::json;
+use tracing::warn;
+
+use crate::{NotifyConfig, NotifyError};
+
+/// The counters of the notifications.
+#[derive(Debug, Default)]
+pub struct NotifyStats {
+    /// The notifications delivered.
+    pub sent: AtomicU64,
+    /// The notifications refused or failed.
+    pub failed: AtomicU64,
+    /// The alerts dropped while the queue was full.
+    pub dropped: AtomicU64,
+}
+
+/// The queue of the alerts to deliver, to the thread delivering them.
+#[derive(Debug)]
+pub struct Notifier {
+    sender: SyncSender<AlertMessage>,
+    stats: Arc<NotifyStats>,
+}
+
+impl Notifier {
+    /// Spawns the thread delivering the alerts to the channels of `config`.
+    ///
+    /// # Errors
+    /// Returns an error if the HTTP client cannot be built or the thread cannot be spawned.
+    pub fn spawn(config: NotifyConfig) -> io::Result<Self> {
+        let http = Client::builder().timeout(config.timeout()).build().map_err(io::Error::other)?;
+        let (sender, receiver) = mpsc::sync_channel(config.max_pending);
+        let stats = Arc::new(NotifyStats::default());
+        let thread_stats = stats.clone();
+        thread::Builder::new()
+            .name("price-alerts-notify".to_string())
+            .spawn(move || run_notifier(&config, &http, receiver, &thread_stats))?;
+        Ok(Self { sender, stats })
+    }
+
+    /// Queues an alert for delivery, dropping it if the queue is full.
+    pub fn notify(&self, alert: &AlertMessage) {
+        match self.sender.try_send(*alert) {
+            Ok(()) => {}
+            Err(TrySendError::Full(_)) => {
+                self.stats.dropped.fetch_add(1, Ordering::Relaxed);
+            }
+            Err(TrySendError::Disconnected(_)) => {
+                warn!("Notifier stopped, alert not delivered: {}", alert.detail());
+                self.stats.dropped.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+
+    /// Returns the counters of the notifications.
+    pub fn stats(&self) -> &NotifyStats {
+        &self.stats
+    }
+}
+
+/// Delivers the alerts received to every channel, until the sender is dropped.
+fn run_notifier(config: &NotifyConfig, http: &Client, receiver: Receiver<AlertMessage>, stats: &NotifyStats) {
+    for alert in receiver {
+        let mut requests = Vec::with_capacity(2);
+        if let Some(url) = &config.webhook_url {
+            requests.push(("webhook", http.post(url).json(&webhook_body(&alert))));
+        }
+        if let Some(telegram) = &config.telegram {
+            let url = format!("{}/bot{}/sendMessage", telegram.api_url, telegram.bot_token);
+            let body = json!({ "chat_id": telegram.chat_id, "text": alert_text(&alert) });
+            requests.push(("telegram", http.post(url).json(&body)));
+        }
+        for (channel, request) in requests {
+            match send(request) {
+                Ok(()) => stats.sent.fetch_add(1, Ordering::Relaxed),
+                Err(e) => {
+                    warn!("Failed to deliver alert to {}: {}", channel, e);
+                    stats.failed.fetch_add(1, Ordering::Relaxed)
+                }
+            };
+        }
+    }
+}
+
+/// Sends a notification request.
+fn send(request: RequestBuilder) -> Result<(), NotifyError> {
+    let response = request.send()?;
+    let status = response.status();
+    if !status.is_success() {
+        let body = response.text().unwrap_or_default();
+        return Err(NotifyError::Rejected { status: status.as_u16(), body });
+    }
+    Ok(())
+}
+
+/// Returns the JSON body of an alert posted to the webhook.
+fn webhook_body(alert: &AlertMessage) -> serde_json::Value {
+    json!({
+        "kind": format!("{:?}", alert.kind()),
+        "severity": format!("{:?}", alert.severity()),
+        "raised_at_ms": alert.raised_at_ms,
+        "source": alert.source(),
+        "detail": alert.detail(),
+    })
+}
+
+/// Returns the text of an alert sent to a chat.
+fn alert_text(alert: &AlertMessage) -> String {
+    format!("[{:?}] {:?}: {}", alert.severity(), alert.kind(), alert.detail())
+}
+
+#[cfg(test)]
+mod tests {
+    use ctl_core::{AlertKind, AlertSeverity};
+
+    use super::*;
+
+    #[test]
+    fn test_alert_bodies() {
+        let alert = AlertMessage::new(
+            AlertKind::PriceCross,
+            AlertSeverity::Warning,
+            1_000,
+            "price-alerts",
+            "BTCUSDT crossed above 70000 at 70010.5",
+        );
+        assert_eq!(alert_text(&alert), "[Warning] PriceCross: BTCUSDT crossed above 70000 at 70010.5");
+        let body = webhook_body(&alert);
+        assert_eq!((body["kind"].as_str(), body["severity"].as_str()), (Some("PriceCross"), Some("Warning")));
+        assert_eq!((body["raised_at_ms"].as_u64(), body["source"].as_str()), (Some(1_000), Some("price-alerts")));
+    }
+}
@@ -0,0 +1,360 @@
+//! The evaluation of the rules against the Tops and trades of their symbols.
+
+use std::collections::VecDeque;
+
+use ctl_core::{AlertKind, AlertSeverity};
+use ctl_feed::TopSnapshot;
+use hashbrown::HashMap;
+
+use crate::{AlertRule, Direction, PriceAlertsConfig, TradePrint};
+
+/// An alert raised by a rule.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Triggered {
+    /// The kind of the alert.
+    pub kind: AlertKind,
+    /// The severity of the alert.
+    pub severity: AlertSeverity,
+    /// The detail of the alert.
+    pub detail: String,
+}
+
+/// The state of a rule between the events of its symbol.
+#[derive(Debug)]
+enum RuleState {
+    PriceCross {
+        level: f64,
+        direction: Direction,
+        /// The price of the last trade, `None` until the first.
+        last_price: Option<f64>,
+    },
+    Spread {
+        max_bps: f64,
+        /// Whether the spread is past the threshold.
+        wide: bool,
+        /// Whether the widening raised an alert, its recovery raising one too.
+        alerted: bool,
+    },
+    VolumeSpike {
+        window_ms: u64,
+        baseline_windows: usize,
+        factor: f64,
+        min_qty: f64,
+        /// The index of the current window, `None` until the first trade.
+        window: Option<u64>,
+        /// Whether the current window is the first one, partly seen and left out of the mean.
+        first_window: bool,
+        /// The volume traded in the current window.
+        volume: f64,
+        /// The volumes of the windows before it, the newest last.
+        history: VecDeque<f64>,
+        /// Whether the current window spiked already.
+        spiked: bool,
+    },
+}
+
+/// A rule of a symbol, with its state.
+#[derive(Debug)]
+struct Rule {
+    symbol: String,
+    state: RuleState,
+    /// The time of the last alert of the rule, in milliseconds.
+    last_alert_ms: Option<u64>,
+}
+
+/// Returns whether a rule may raise an alert at `now_ms`, past the cooldown of
+/// its last one, counting the alert suppressed otherwise.
+fn cooled_down(last_alert_ms: &mut Option<u64>, cooldown_ms: u64, now_ms: u64, suppressed: &mut u64) -> bool {
+    if last_alert_ms.is_some_and(|at| now_ms.saturating_sub(at) < cooldown_ms) {
+        *suppressed += 1;
+        return false;
+    }
+    *last_alert_ms = Some(now_ms);
+    true
+}
+
+/// The rules, evaluated against the Tops and trades of their symbols.
+#[derive(Debug)]
+pub struct RuleEngine {
+    rules: Vec<Rule>,
+    /// The rules evaluated against the Tops, by symbol.
+    top_rules: HashMap<String, Vec<usize>>,
+    /// The rules evaluated against the trades, by symbol.
+    trade_rules: HashMap<String, Vec<usize>>,
+    /// The minimum interval between the alerts of a rule, in milliseconds.
+    cooldown_ms: u64,
+    /// The number of alerts suppressed by the cooldown.
+    suppressed: u64,
+}
+
+impl RuleEngine {
+    /// Creates the engine of the configured rules.
+    pub fn new(config: &PriceAlertsConfig) -> Self {
+        let mut engine = Self {
+            rules: Vec::with_capacity(config.rules.len()),
+            top_rules: HashMap::new(),
+            trade_rules: HashMap::new(),
+            cooldown_ms: config.cooldown_ms,
+            suppressed: 0,
+        };
+        for rule in &config.rules {
+            let state = match *rule {
+                AlertRule::PriceCross { level, direction, .. } => {
+                    RuleState::PriceCross { level, direction, last_price: None }
+                }
+                AlertRule::Spread { max_bps, .. } => RuleState::Spread { max_bps, wide: false, alerted: false },
+                AlertRule::VolumeSpike { window_secs, baseline_windows, factor, min_qty, .. } => {
+                    RuleState::VolumeSpike {
+                        window_ms: window_secs * 1_000,
+                        baseline_windows,
+                        factor,
+                        min_qty,
+                        window: None,
+                        first_window: true,
+                        volume: 0.0,
+                        history: VecDeque::with_capacity(baseline_windows),
+                        spiked: false,
+                    }
+                }
+            };
+            let rules = if rule.needs_top() { &mut engine.top_rules } else { &mut engine.trade_rules };
+            rules.entry(rule.symbol().to_string()).or_default().push(engine.rules.len());
+            engine.rules.push(Rule { symbol: rule.symbol().to_string(), state, last_alert_ms: None });
+        }
+        engine
+    }
+
+    /// Returns the symbols of the rules evaluated against the Tops.
+    pub fn top_symbols(&self) -> impl Iterator<Item = &str> {
+        self.top_rules.keys().map(String::as_str)
+    }
+
+    /// Returns the symbols of the rules evaluated against the trades.
+    pub fn trade_symbols(&self) -> impl Iterator<Item = &str> {
+        self.trade_rules.keys().map(String::as_str)
+    }
+
+    /// Returns the number of alerts suppressed by the cooldown.
+    pub fn suppressed(&self) -> u64 {
+        self.suppressed
+    }
+
+    /// Evaluates the rules of a symbol against its Top, received at `now_ms`,
+    /// pushing the alerts raised to `alerts`.
+    pub fn on_top(&mut self, symbol: &str, top: &TopSnapshot, now_ms: u64, alerts: &mut Vec<Triggered>) {
+        let Some(indices) = self.top_rules.get(symbol) else {
+            return;
+        };
+        // A one-sided book has no spread
+        if top.bid_price <= 0.0 || top.ask_price <= 0.0 {
+            return;
+        }
+        let mid = (top.bid_price + top.ask_price) / 2.0;
+        let spread_bps = (top.ask_price - top.bid_price) / mid * 10_000.0;
+        for &index in indices {
+            let rule = &mut self.rules[index];
+            let RuleState::Spread { max_bps, wide, alerted } = &mut rule.state else {
+                continue;
+            };
+            let max_bps = *max_bps;
+            if !*wide && spread_bps > max_bps {
+                *wide = true;
+                *alerted = cooled_down(&mut rule.last_alert_ms, self.cooldown_ms, now_ms, &mut self.suppressed);
+                if *alerted {
+                    alerts.push(Triggered {
+                        kind: AlertKind::SpreadWide,
+                        severity: AlertSeverity::Warning,
+                        detail: format!(
+                            "{} spread {:.2}bps above {}bps ({} x {})",
+                            rule.symbol, spread_bps, max_bps, top.bid_price, top.ask_price
+                        ),
+                    });
+                }
+            } else if *wide && spread_bps <= max_bps {
+                *wide = false;
+                if std::mem::take(alerted) {
+                    alerts.push(Triggered {
+                        kind: AlertKind::SpreadWide,
+                        severity: AlertSeverity::Info,
+                        detail: format!("{} spread back to {:.2}bps within {}bps", rule.symbol, spread_bps, max_bps),
+                    });
+                }
+            }
+        }
+    }
+
+    /// Evaluates the rules of a symbol against its trade, received at `now_ms`,
+    /// pushing the alerts raised to `alerts`.
+    pub fn on_trade(&mut self, symbol: &str, trade: &TradePrint, now_ms: u64, alerts: &mut Vec<Triggered>) {
+        let Some(indices) = self.trade_rules.get(symbol) else {
+            return;
+        };
+        for &index in indices {
+            let rule = &mut self.rules[index];
+            let (kind, detail) = match &mut rule.state {
+                RuleState::PriceCross { level, direction, last_price } => {
+                    let crossed = match (last_price.replace(trade.price), *direction) {
+                        (Some(last), Direction::Above) => last < *level && trade.price >= *level,
+                        (Some(last), Direction::Below) => last > *level && trade.price <= *level,
+                        (None, _) => false,
+                    };
+                    if !crossed {
+                        continue;
+                    }
+                    let detail = format!("{} crossed {} {} at {}", rule.symbol, direction.as_str(), level, trade.price);
+                    (AlertKind::PriceCross, detail)
+                }
+                RuleState::VolumeSpike {
+                    window_ms,
+                    baseline_windows,
+                    factor,
+                    min_qty,
+                    window,
+                    first_window,
+                    volume,
+                    history,
+                    spiked,
+                } => {
+                    let index = trade.trade_time_ms / *window_ms;
+                    match *window {
+                        None => *window = Some(index),
+                        // A late trade counts in the current window
+                        Some(current) if index > current => {
+                            if !std::mem::take(first_window) {
+                                history.push_back(*volume);
+                            }
+                            // The windows without trades, at most a full baseline of them
+                            let skipped = (index - current - 1).min(*baseline_windows as u64);
+                            history.extend((0..skipped).map(|_| 0.0));
+                            while history.len() > *baseline_windows {
+                                history.pop_front();
+                            }
+                            *window = Some(index);
+                            *volume = 0.0;
+                            *spiked = false;
+                        }
+                        Some(_) => {}
+                    }
+                    *volume += trade.qty;
+                    if *spiked || history.len() < *baseline_windows || *volume < *min_qty {
+                        continue;
+                    }
+                    let mean = history.iter().sum::<f64>() / history.len() as f64;
+                    if *volume <= *factor * mean {
+                        continue;
+                    }
+                    *spiked = true;
+                    let detail = format!(
+                        "{} traded {} in {}s, over {}x the mean {:.4} of the last {} windows",
+                        rule.symbol,
+                        volume,
+                        *window_ms / 1_000,
+                        factor,
+                        mean,
+                        baseline_windows
+                    );
+                    (AlertKind::VolumeSpike, detail)
+                }
+                RuleState::Spread { .. } => continue,
+            };
+            if cooled_down(&mut rule.last_alert_ms, self.cooldown_ms, now_ms, &mut self.suppressed) {
+                alerts.push(Triggered { kind, severity: AlertSeverity::Warning, detail });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn engine(rules: &str, cooldown_ms: u64) -> RuleEngine {
+        let yaml = format!("cooldown_ms: {}\nrules:\n{}\n", cooldown_ms, rules);
+        RuleEngine::new(&PriceAlertsConfig::from_str(&yaml).unwrap())
+    }
+
+    fn trade(trade_time_ms: u64, price: f64, qty: f64) -> TradePrint {
+        TradePrint { trade_time_ms, price, qty }
+    }
+
+    fn top(bid_price: f64, ask_price: f64) -> TopSnapshot {
+        TopSnapshot { bid_price, ask_price, bid_qty: 1.0, ask_qty: 1.0, ..TopSnapshot::default() }
+    }
+
+    #[test]
+    fn test_price_cross() {
+        let mut engine = engine("  - {kind: price_cross, symbol: BTCUSDT, level: 100, direction: above}", 1_000);
+        let mut alerts = Vec::new();
+        // The first trade has no previous price to cross from
+        engine.on_trade("BTCUSDT", &trade(0, 101.0, 1.0), 0, &mut alerts);
+        engine.on_trade("BTCUSDT", &trade(0, 99.0, 1.0), 10, &mut alerts);
+        engine.on_trade("ETHUSDT", &trade(0, 101.0, 1.0), 20, &mut alerts);
+        assert!(alerts.is_empty());
+
+        engine.on_trade("BTCUSDT", &trade(0, 100.0, 1.0), 30, &mut alerts);
+        assert_eq!(alerts.len(), 1);
+        assert_eq!((alerts[0].kind, alerts[0].severity), (AlertKind::PriceCross, AlertSeverity::Warning));
+        assert_eq!(alerts[0].detail, "BTCUSDT crossed above 100 at 100");
+
+        // Crossing back and again within the cooldown is suppressed
+        engine.on_trade("BTCUSDT", &trade(0, 99.5, 1.0), 40, &mut alerts);
+        engine.on_trade("BTCUSDT", &trade(0, 100.5, 1.0), 50, &mut alerts);
+        assert_eq!((alerts.len(), engine.suppressed()), (1, 1));
+        engine.on_trade("BTCUSDT", &trade(0, 99.5, 1.0), 1_100, &mut alerts);
+        engine.on_trade("BTCUSDT", &trade(0, 100.5, 1.0), 1_200, &mut alerts);
+        assert_eq!(alerts.len(), 2);
+    }
+
+    #[test]
+    fn test_spread() {
+        let mut engine = engine("  - {kind: spread, symbol: BTCUSDT, max_bps: 10}", 0);
+        let mut alerts = Vec::new();
+        engine.on_top("BTCUSDT", &top(99.99, 100.01), 0, &mut alerts);
+        engine.on_top("BTCUSDT", &top(0.0, 100.01), 0, &mut alerts);
+        assert!(alerts.is_empty());
+
+        // Widening raises once until the spread recovers
+        engine.on_top("BTCUSDT", &top(99.9, 100.1), 10, &mut alerts);
+        engine.on_top("BTCUSDT", &top(99.8, 100.2), 20, &mut alerts);
+        engine.on_top("BTCUSDT", &top(99.99, 100.01), 30, &mut alerts);
+        let raised: Vec<_> = alerts.iter().map(|alert| (alert.kind, alert.severity)).collect();
+        assert_eq!(
+            raised,
+            vec![(AlertKind::SpreadWide, AlertSeverity::Warning), (AlertKind::SpreadWide, AlertSeverity::Info)]
+        );
+        assert_eq!(alerts[0].detail, "BTCUSDT spread 20.00bps above 10bps (99.9 x 100.1)");
+        assert_eq!(engine.top_symbols().collect::<Vec<_>>(), vec!["BTCUSDT"]);
+        assert_eq!(engine.trade_symbols().count(), 0);
+    }
+
+    #[test]
+    fn test_volume_spike() {
+        let rule = concat!(
+            "  - {kind: volume_spike, symbol: BTCUSDT, window_secs: 1,",
+            " baseline_windows: 3, factor: 3, min_qty: 5}"
+        );
+        let mut engine = engine(rule, 0);
+        let mut alerts = Vec::new();
+        // The first window, partly seen, is left out of the mean
+        engine.on_trade("BTCUSDT", &trade(500, 100.0, 50.0), 0, &mut alerts);
+        for window in 1..4 {
+            engine.on_trade("BTCUSDT", &trade(window * 1_000, 100.0, 2.0), 0, &mut alerts);
+        }
+        assert!(alerts.is_empty());
+
+        // The mean is 2 over the three windows, spiking past 6 once per window
+        engine.on_trade("BTCUSDT", &trade(4_000, 100.0, 4.0), 0, &mut alerts);
+        assert!(alerts.is_empty());
+        engine.on_trade("BTCUSDT", &trade(4_100, 100.0, 3.0), 0, &mut alerts);
+        engine.on_trade("BTCUSDT", &trade(4_200, 100.0, 3.0), 0, &mut alerts);
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].kind, AlertKind::VolumeSpike);
+        assert_eq!(alerts[0].detail, "BTCUSDT traded 7 in 1s, over 3x the mean 2.0000 of the last 3 windows");
+
+        // The windows without trades count as no volume, any volume past the minimum spiking
+        engine.on_trade("BTCUSDT", &trade(20_000, 100.0, 4.0), 0, &mut alerts);
+        assert_eq!(alerts.len(), 1);
+        engine.on_trade("BTCUSDT", &trade(20_100, 100.0, 1.0), 0, &mut alerts);
+        assert_eq!(alerts.len(), 2);
+    }
+}
@@ -0,0 +1,56 @@
+use serde::Deserialize;
+
+/// The fields of a trade or aggregate trade stream payload the rules read.
+/// https://github.com/binance/binance-spot-api-docs/blob/master/web-socket-streams.md#trade-streams
+#[derive(Deserialize)]
+struct TradePayload<'a> {
+    #[serde(rename = "s")]
+    symbol: &'a str,
+    #[serde(rename = "p")]
+    price: &'a str,
+    #[serde(rename = "q")]
+    qty: &'a str,
+    #[serde(rename = "T")]
+    trade_time_ms: u64,
+}
+
+/// A trade, or an aggregated trade, of a symbol.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TradePrint {
+    /// The trade time, in milliseconds.
+    pub trade_time_ms: u64,
+    /// The trade price.
+    pub price: f64,
+    /// The trade quantity.
+    pub qty: f64,
+}
+
+impl TradePrint {
+    /// Parses a trade or aggregate trade payload, returning its symbol and trade.
+    /// Returns `None` if the payload is not a trade.
+    pub fn from_json(data: &[u8]) -> Option<(&str, Self)> {
+        let payload: TradePayload<'_> = serde_json::from_slice(data).ok()?;
+        let trade = Self {
+            trade_time_ms: payload.trade_time_ms,
+            price: payload.price.parse().ok()?,
+            qty: payload.qty.parse().ok()?,
+        };
+        Some((payload.symbol, trade))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_trades() {
+        let json = br#"{"e":"trade","E":123456789,"s":"BNBBTC","t":12345,"p":"0.001","q":"100","T":123456785,"m":true,"M":true}"#;
+        let expected = TradePrint { trade_time_ms: 123456785, price: 0.001, qty: 100.0 };
+        assert_eq!(TradePrint::from_json(json), Some(("BNBBTC", expected)));
+
+        let json = br#"{"e":"aggTrade","E":123456789,"s":"BNBBTC","a":12345,"p":"0.001","q":"100","f":100,"l":105,"T":123456785,"m":true,"M":true}"#;
+        assert_eq!(TradePrint::from_json(json), Some(("BNBBTC", expected)));
+        assert!(TradePrint::from_json(br#"{"u":400900217,"s":"BNBUSDT"}"#).is_none());
+    }
+}
@@ -3,11 +3,12 @@
 //! This module provides the YAML parser and validation for hardware resources
 //! configuration defined in `configs/resource-manager/hw-resources.yaml`.
 
+use ctl_core::EalConfig;
 use serde::Deserialize;
 use std::fs;
 use std::path::Path;
 
-use crate::HwResourcesConfigError;
+use crate::{HwResourcesConfigError, StandbyConfig, SymbolCheckConfig, TopologyCheckConfig};
 
 /// Hugepage size options in KB.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -36,7 +37,7 @@ impl HugepageSize {
     }
 }
 
-/// Hugepage configuration.
+/// Hugepage configuration of a page size.
 #[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
 pub struct HugepagesConfig {
     /// Hugepage size in KB (2048 for 2MB, 1048576 for 1GB).
@@ -57,6 +58,11 @@ impl HugepagesConfig {
             ))),
         }
     }
+
+    /// Returns the memory of the hugepages in KB.
+    pub fn memory_kb(&self) -> u64 {
+        self.size_kb as u64 * self.count as u64
+    }
 }
 
 /// Hardware resources configuration for the Resource Manager.
@@ -64,8 +70,21 @@ impl HugepagesConfig {
 pub struct HwResourcesConfig {
     /// CPU core to pin the resource manager process.
     pub cpu: u32,
-    /// Hugepage configuration.
-    pub hugepages: HugepagesConfig,
+    /// Hugepage configuration of each page size, e.g. a few 1GB pages for the
+    /// rings plus 2MB pages for the other pools.
+    pub hugepages: Vec<HugepagesConfig>,
+    /// EAL options of the primary process.
+    #[serde(default)]
+    pub eal: EalConfig,
+    /// Check of the market data symbols against the exchange information.
+    #[serde(default)]
+    pub symbol_check: SymbolCheckConfig,
+    /// Check of the lcores of the components against the CPU topology.
+    #[serde(default)]
+    pub topology_check: TopologyCheckConfig,
+    /// Watch of the primary by an instance started with `--standby`.
+    #[serde(default)]
+    pub standby: StandbyConfig,
 }
 
 impl HwResourcesConfig {
@@ -87,16 +106,37 @@ impl HwResourcesConfig {
 
     /// Validates the configuration.
     fn validate(&self) -> Result<(), HwResourcesConfigError> {
-        // Validate hugepage size
-        self.hugepages.size()?;
-        
-        // Validate hugepage count is non-zero
-        if self.hugepages.count == 0 {
+        if self.hugepages.is_empty() {
             return Err(HwResourcesConfigError::ValidationError(
-                "Hugepage count must be greater than 0".to_string(),
+                "At least one hugepage size must be configured".to_string(),
             ));
         }
-        
+
+        for (i, hugepages) in self.hugepages.iter().enumerate() {
+            // Validate hugepage size
+            hugepages.size()?;
+
+            // Validate hugepage count is non-zero
+            if hugepages.count == 0 {
+                return Err(HwResourcesConfigError::ValidationError(format!(
+                    "Hugepage count of {}kB pages must be greater than 0",
+                    hugepages.size_kb
+                )));
+            }
+
+            // Each size has a single sysfs path, so a second entry would overwrite the first
+            if self.hugepages[..i].iter().any(|h| h.size_kb == hugepages.size_kb) {
+                return Err(HwResourcesConfigError::ValidationError(format!(
+                    "Duplicate hugepage size: {}kB",
+                    hugepages.size_kb
+                )));
+            }
+        }
+
+        self.eal.validate().map_err(HwResourcesConfigError::ValidationError)?;
+        self.symbol_check.validate().map_err(HwResourcesConfigError::ValidationError)?;
+        self.standby.validate().map_err(HwResourcesConfigError::ValidationError)?;
+
         Ok(())
     }
 
@@ -105,15 +145,31 @@ impl HwResourcesConfig {
         self.cpu
     }
 
-    /// Returns the hugepage configuration.
-    pub fn hugepages(&self) -> &HugepagesConfig {
+    /// Returns the hugepage configuration of each page size.
+    pub fn hugepages(&self) -> &[HugepagesConfig] {
         &self.hugepages
     }
+
+    /// Returns the memory of the hugepages of all sizes in MB.
+    pub fn hugepage_memory_mb(&self) -> u64 {
+        self.hugepages.iter().map(HugepagesConfig::memory_kb).sum::<u64>() / 1024
+    }
+
+    /// Returns the EAL memory options of the primary process.
+    ///
+    /// The EAL maps every mounted hugetlbfs, so no `--huge-dir` (which would
+    /// restrict it to a single page size) is passed. Instead the memory of all
+    /// the configured pages is reserved at startup with `-m`, the EAL taking it
+    /// from the largest pages first, rather than allocated as regions are created.
+    pub fn eal_memory_args(&self) -> Vec<String> {
+        vec!["-m".to_string(), self.hugepage_memory_mb().to_string()]
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::{SymbolCheckPolicy, TopologyCheckPolicy};
     use std::io::Write;
     use tempfile::NamedTempFile;
 
@@ -128,15 +184,16 @@ mod tests {
         let content = r#"
 cpu: 3
 hugepages:
-  size_kb: 2048
-  count: 128
+  - size_kb: 2048
+    count: 128
 "#;
         let file = create_temp_config(content);
         let config = HwResourcesConfig::from_file(file.path()).unwrap();
         assert_eq!(config.cpu, 3);
         assert_eq!(config.lcore_id(), 3);
-        assert_eq!(config.hugepages.size_kb, 2048);
-        assert_eq!(config.hugepages.count, 128);
+        assert_eq!(config.hugepages.len(), 1);
+        assert_eq!(config.hugepages[0].size_kb, 2048);
+        assert_eq!(config.hugepages[0].count, 128);
     }
 
     #[test]
@@ -144,13 +201,114 @@ hugepages:
         let content = r#"
 cpu: 0
 hugepages:
-  size_kb: 1048576
-  count: 4
+  - size_kb: 1048576
+    count: 4
+"#;
+        let file = create_temp_config(content);
+        let config = HwResourcesConfig::from_file(file.path()).unwrap();
+        assert_eq!(config.hugepages[0].size().unwrap(), HugepageSize::Size1GB);
+        assert_eq!(config.hugepages[0].count, 4);
+    }
+
+    #[test]
+    fn test_parse_mixed_hugepages() {
+        let content = r#"
+cpu: 0
+hugepages:
+  - size_kb: 1048576
+    count: 2
+  - size_kb: 2048
+    count: 512
+"#;
+        let file = create_temp_config(content);
+        let config = HwResourcesConfig::from_file(file.path()).unwrap();
+        let sizes: Vec<_> = config.hugepages().iter().map(|h| h.size().unwrap()).collect();
+        assert_eq!(sizes, vec![HugepageSize::Size1GB, HugepageSize::Size2MB]);
+        assert_eq!(config.hugepage_memory_mb(), 2 * 1024 + 512 * 2);
+        assert_eq!(config.eal_memory_args(), vec!["-m".to_string(), "3072".to_string()]);
+    }
+
+    #[test]
+    fn test_parse_eal_options() {
+        let content = r#"
+cpu: 0
+hugepages:
+  - size_kb: 2048
+    count: 512
+eal:
+  file_prefix: ctl-b
+  socket_mem: [1024, 0]
+"#;
+        let file = create_temp_config(content);
+        let config = HwResourcesConfig::from_file(file.path()).unwrap();
+        assert_eq!(config.eal.file_prefix(), "ctl-b");
+        assert_eq!(
+            config.eal.primary_args(config.eal_memory_args()),
+            vec!["--file-prefix=ctl-b".to_string(), "--socket-mem=1024,0".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_parse_symbol_check() {
+        let content = r#"
+cpu: 0
+hugepages:
+  - size_kb: 2048
+    count: 512
+symbol_check:
+  policy: warn
+  quote_assets: [USDT, USDC]
+topology_check:
+  policy: fail
+standby:
+  takeover_after_ms: 3000
 "#;
         let file = create_temp_config(content);
         let config = HwResourcesConfig::from_file(file.path()).unwrap();
-        assert_eq!(config.hugepages.size().unwrap(), HugepageSize::Size1GB);
-        assert_eq!(config.hugepages.count, 4);
+        assert_eq!(config.standby, StandbyConfig { takeover_after_ms: 3_000, poll_interval_ms: 500 });
+        assert_eq!(config.symbol_check.policy, SymbolCheckPolicy::Warn);
+        assert_eq!(config.symbol_check.quote_assets, vec!["USDT".to_string(), "USDC".to_string()]);
+        assert_eq!(config.symbol_check.endpoint, ctl_rest::BINANCE_REST_ENDPOINT);
+        assert_eq!(config.topology_check.policy, TopologyCheckPolicy::Fail);
+
+        let content = r#"
+cpu: 0
+hugepages:
+  - size_kb: 2048
+    count: 512
+"#;
+        let file = create_temp_config(content);
+        let config = HwResourcesConfig::from_file(file.path()).unwrap();
+        assert_eq!(config.symbol_check, SymbolCheckConfig::default());
+        assert_eq!(config.symbol_check.policy, SymbolCheckPolicy::Fail);
+        assert_eq!(config.topology_check.policy, TopologyCheckPolicy::Warn);
+        assert_eq!(config.standby, StandbyConfig::default());
+    }
+
+    #[test]
+    fn test_duplicate_hugepage_size() {
+        let content = r#"
+cpu: 0
+hugepages:
+  - size_kb: 2048
+    count: 64
+  - size_kb: 2048
+    count: 128
+"#;
+        let file = create_temp_config(content);
+        let result = HwResourcesConfig::from_file(file.path());
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_empty_hugepages() {
+        let content = r#"
+cpu: 0
+hugepages: []
+"#;
+        let file = create_temp_config(content);
+        let result = HwResourcesConfig::from_file(file.path());
+        assert!(result.is_err());
     }
 
     #[test]
@@ -170,8 +328,8 @@ hugepages:
         let content = r#"
 cpu: 0
 hugepages:
-  size_kb: 4096
-  count: 10
+  - size_kb: 4096
+    count: 10
 "#;
         let file = create_temp_config(content);
         let result = HwResourcesConfig::from_file(file.path());
@@ -183,8 +341,8 @@ hugepages:
         let content = r#"
 cpu: 0
 hugepages:
-  size_kb: 2048
-  count: 0
+  - size_kb: 2048
+    count: 0
 "#;
         let file = create_temp_config(content);
         let result = HwResourcesConfig::from_file(file.path());
@@ -0,0 +1,144 @@
+//! Plan of the lcores of every controller component.
+//!
+//! The resource manager reads the configurations of the other components, so
+//! it collects the lcores they claim: its own `cpu`, the main and worker lcores
+//! of the market data handler, and the single-lcore components of
+//! `configs/lcores.yaml`. The plan is printed with `--check`, with the lcores
+//! claimed by several components and a conflict-free assignment.
+
+use ctl_core::{LcorePlanner, LcoreUse, LcoresConfig};
+use ctl_md_handler::{HwResourcesConfig as MdHwResourcesConfig, LcorePlan};
+
+use crate::{HwResourcesConfig, HwResourcesConfigError};
+
+/// The single-lcore components with their default lcore, as in their binaries.
+const SINGLE_LCORE_COMPONENTS: [(&str, u32, LcoreUse); 4] = [
+    ("md-subscriber", 13, LcoreUse::Exclusive),
+    ("trade-stats", 14, LcoreUse::Exclusive),
+    ("ctl-admin", 15, LcoreUse::Shared),
+    ("dq", 15, LcoreUse::Shared),
+];
+
+/// The OMS of a strategy process, only claiming an lcore when configured.
+const OMS_COMPONENT: &str = "oms";
+
+/// The arbiter of the A/B lines with its default lcore, only running under arbitration.
+const ARBITER_COMPONENT: (&str, u32) = ("arbiter", 17);
+
+/// Collects the lcores claimed by every component.
+///
+/// # Errors
+/// Returns an error if the lcores of the market data workers can't be planned.
+pub fn plan_lcores(
+    config: &HwResourcesConfig,
+    md_config: &MdHwResourcesConfig,
+    lcores: &LcoresConfig,
+) -> Result<LcorePlanner, HwResourcesConfigError> {
+    let md_plan = LcorePlan::from_config(md_config)
+        .map_err(|e| HwResourcesConfigError::ValidationError(e.to_string()))?;
+
+    let mut planner = LcorePlanner::new();
+    planner
+        .claim("resource-manager", "main", vec![config.lcore_id()], LcoreUse::Shared)
+        .claim("md-handler", "main", vec![md_config.main_cpu], LcoreUse::Shared);
+    for assignment in &md_plan.assignments {
+        let role = match &assignment.set {
+            Some(set) => format!("workers {}/{}", assignment.kind, set),
+            None => format!("workers {}", assignment.kind),
+        };
+        planner.claim("md-handler", &role, assignment.lcores.clone(), LcoreUse::Exclusive);
+    }
+
+    for (component, default, usage) in SINGLE_LCORE_COMPONENTS {
+        planner.claim_movable(component, "main", lcores.lcore(component, default), usage);
+    }
+    if let Some(lcore) = lcores.get(OMS_COMPONENT) {
+        planner.claim_movable(OMS_COMPONENT, "main", lcore, LcoreUse::Exclusive);
+    }
+    if md_config.arbitration.is_some() {
+        let (component, default) = ARBITER_COMPONENT;
+        planner.claim_movable(component, "main", lcores.lcore(component, default), LcoreUse::Exclusive);
+    }
+    Ok(planner)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ctl_core::{EalConfig, LcoreMove};
+
+    use crate::{StandbyConfig, SymbolCheckConfig, TopologyCheckConfig};
+
+    fn md_config(num_cpus: u32) -> MdHwResourcesConfig {
+        let content = format!(r#"
+- main_cpu: 0
+- worker_cpus: 1-14
+- pubsubs:
+    - feed:
+        kind: top
+        num_cpus: {num_cpus}
+        ring_size: 1024
+        symbols:
+          - BTCUSDT
+        medium:
+          - protocol: websocket
+            parser: json
+"#);
+        MdHwResourcesConfig::from_str(&content).unwrap()
+    }
+
+    fn config() -> HwResourcesConfig {
+        HwResourcesConfig {
+            cpu: 0,
+            hugepages: Vec::new(),
+            eal: EalConfig::default(),
+            symbol_check: SymbolCheckConfig::default(),
+            topology_check: TopologyCheckConfig::default(),
+            standby: StandbyConfig::default(),
+        }
+    }
+
+    #[test]
+    fn test_plan_lcores() {
+        let lcores = LcoresConfig::from_str("oms: 16\n").unwrap();
+        let planner = plan_lcores(&config(), &md_config(13), &lcores).unwrap();
+        let components: Vec<_> = planner.claims().iter().map(|claim| claim.to_string()).collect();
+        assert_eq!(
+            components,
+            [
+                "resource-manager main",
+                "md-handler main",
+                "md-handler workers top",
+                "md-subscriber main",
+                "trade-stats main",
+                "ctl-admin main",
+                "dq main",
+                "oms main",
+            ]
+        );
+
+        // The subscriber's default lcore is the last worker
+        let conflicts = planner.conflicts();
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].lcore, 13);
+        let table = planner.plan(&(0..24).collect::<Vec<_>>()).unwrap();
+        assert_eq!(table.moves, [LcoreMove { component: "md-subscriber".to_string(), from: 13, to: 17 }]);
+
+        let planner = plan_lcores(&config(), &md_config(12), &LcoresConfig::default()).unwrap();
+        assert!(planner.conflicts().is_empty());
+    }
+
+    #[test]
+    fn test_plan_arbiter_lcore() {
+        // The arbiter only claims its lcore under arbitration
+        let mut md_config = md_config(12);
+        let planner = plan_lcores(&config(), &md_config, &LcoresConfig::default()).unwrap();
+        assert!(planner.claims().iter().all(|claim| claim.component != "arbiter"));
+
+        md_config.arbitration = Some(Default::default());
+        let lcores = LcoresConfig::from_str("arbiter: 18\n").unwrap();
+        let planner = plan_lcores(&config(), &md_config, &lcores).unwrap();
+        let arbiter = planner.claims().iter().find(|claim| claim.component == "arbiter").unwrap();
+        assert_eq!(arbiter.lcores, [18]);
+    }
+}
@@ -14,7 +14,10 @@
 //!
 //! The Resource Manager is the first component to be started and must remain
 //! alive for the lifetime of the controller. If it terminates, all shared
-//! memory contracts become invalid.
+//! memory contracts become invalid. Each run stamps a new run epoch in the
+//! status table, so the components attached to a previous run stop instead of
+//! using their stale mappings. A second instance started with `--standby`
+//! watches the heartbeat of the primary, taking over once it dies.
 //!
 //! Below is the Binance Spot Controller architecture as governed by the
 //! Resource Manager.
@@ -74,6 +77,16 @@
 
 mod config;
 mod errors;
+mod lcores;
+mod rings;
+mod standby;
+mod symbols;
+mod topology;
 
 pub use config::{HugepageSize, HugepagesConfig, HwResourcesConfig};
-pub use errors::HwResourcesConfigError;
\ No newline at end of file
+pub use errors::HwResourcesConfigError;
+pub use lcores::plan_lcores;
+pub use rings::{plan_rings, PlannedRing, RingContent};
+pub use standby::{PrimaryView, StandbyConfig, StandbyDecision, StandbyMonitor, TakeoverReason};
+pub use symbols::{check_symbols, SymbolCheckConfig, SymbolCheckPolicy, SymbolIssue};
+pub use topology::{ring_node, TopologyCheckConfig, TopologyCheckPolicy};
\ No newline at end of file
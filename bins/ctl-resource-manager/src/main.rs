@@ -1,96 +1,616 @@
-use std::error::Error;
+#[cfg(not(feature = "shm-rings"))]
 use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::sync::Arc;
 
-use dpdk::{DpdkEnvBuilder, DpdkOwnedPubSubRing, DpdkProcessType};
+use clap::Parser;
+#[cfg(not(feature = "shm-rings"))]
+use dpdk::{DpdkEnv, DpdkEnvBuilder, DpdkOwnedPubSubRing, DpdkProcessType};
 use hashbrown::HashMap;
 
+use ctl_balance::{BalanceRegion, BALANCE_REGION_NAME};
+#[cfg(not(feature = "shm-rings"))]
+use ctl_core::{dpdk_config_path, primary_running, Capability, Preflight};
+use ctl_core::{
+    check_topology, new_run_epoch, online_cpus, AlertMessage, ControlMessage, CpuTopology, EalConfig, Fatal,
+    FatalError, FatalKind, HealthConfig, HealthRoutes, HealthServer, Heartbeat, LcorePlanner, LcoresConfig, StatusRegion,
+    TradingStatus, ALERTS_RING_NAME, ALERTS_RING_SIZE, CONTROL_RING_NAME, CONTROL_RING_SIZE, STATUS_REGION_NAME,
+};
 // Import ctl_feed to ensure its ring registrations are linked.
 // The `inventory` crate collects all `register_ring!` invocations at link time.
-use ctl_feed::RawMessage;
+use ctl_feed::{
+    CandleMessage, LastTopRegion, MetricsRegion, MetricsStatus, RawMessage, SignalMessage, TradeStatsMessage,
+    LAST_TOP_REGION_NAME, METRICS_REGION_NAME,
+};
 use ctl_md_handler::{HwResourcesConfig as MdHwResourcesConfig, SymbolInfoConfig};
-use ctl_resource_manager::HwResourcesConfig;
+use ctl_oms::{
+    OrderEntryMessage, OrderRateLedger, ORDER_ENTRY_RING_NAME, ORDER_ENTRY_RING_SIZE, ORDER_RATE_REGION_NAME,
+};
+use ctl_position::{PositionRegion, POSITION_REGION_NAME};
+use ctl_resource_manager::{
+    check_symbols, plan_lcores, plan_rings, ring_node, HwResourcesConfig, PlannedRing, PrimaryView, RingContent,
+    StandbyConfig, StandbyDecision, StandbyMonitor, SymbolCheckConfig, SymbolCheckPolicy, TopologyCheckPolicy,
+};
+use ctl_rest::{RestClient, WeightLedger, WEIGHT_LEDGER_REGION_NAME};
+use ctl_shm::{ShmMessage, ShmRegion};
+#[cfg(feature = "shm-rings")]
+use ctl_shm::ShmRing;
+use ctl_time::{now_ms, TimeSyncRegion, TIME_SYNC_REGION_NAME};
+use serde::Serialize;
+use tracing::{info, warn};
+
+// Logging configuration, its levels reloaded on change
+const LOG_CONFIG_PATH: &str = "configs/logging.yaml";
 
 const CONFIG_PATH: &str = "configs/resource-manager/hw-resources.yaml";
 const MD_CONFIG_PATH: &str = "configs/market-data/hw-resources.yaml";
 const SYMBOL_INFO_PATH: &str = "configs/market-data/symbolinfo.yaml";
+const LCORES_CONFIG_PATH: &str = "configs/lcores.yaml";
 
-fn main() -> Result<(), Box<dyn Error>> {
-    // Load hardware resources configuration
-    let config = HwResourcesConfig::from_file(CONFIG_PATH)?;
+// Health and status endpoints of this component, unless configured served by none
+const HEALTH_CONFIG_PATH: &str = "configs/health.yaml";
+const HEALTH_COMPONENT: &str = "resource-manager";
 
-    // Load market data configuration
-    let md_config = MdHwResourcesConfig::from_file(MD_CONFIG_PATH)?;
+/// Resource manager of the Binance Spot controller, owning its shared memory.
+#[derive(Debug, Parser)]
+#[command(version)]
+struct Args {
+    /// Logging configuration.
+    #[arg(long, env = "CTL_LOG_CONFIG", default_value = LOG_CONFIG_PATH)]
+    log_config: PathBuf,
+    /// Resource manager configuration.
+    #[arg(long, env = "CTL_RM_CONFIG", default_value = CONFIG_PATH)]
+    config: PathBuf,
+    /// Market data configuration.
+    #[arg(long, env = "CTL_MD_CONFIG", default_value = MD_CONFIG_PATH)]
+    md_config: PathBuf,
+    /// Symbol info configuration.
+    #[arg(long, env = "CTL_SYMBOL_INFO", default_value = SYMBOL_INFO_PATH)]
+    symbol_info: PathBuf,
+    /// Lcores configuration of the single-lcore components.
+    #[arg(long, env = "CTL_LCORES_CONFIG", default_value = LCORES_CONFIG_PATH)]
+    lcores_config: PathBuf,
+    /// Health endpoint configuration.
+    #[arg(long, env = "CTL_HEALTH_CONFIG", default_value = HEALTH_CONFIG_PATH)]
+    health_config: PathBuf,
+    /// Validate the configurations and print the planned rings and lcores, without
+    /// configuring the host, initializing DPDK or reaching the exchange.
+    #[arg(long)]
+    check: bool,
+    /// Start as the hot standby of a running resource manager, taking over once it dies.
+    #[arg(long, env = "CTL_RM_STANDBY")]
+    standby: bool,
+}
 
-    // Load symbol info configuration
-    let symbol_info = SymbolInfoConfig::from_file(SYMBOL_INFO_PATH)?;
+// The rings owned by this process, on the DPDK runtime or, with the
+// `shm-rings` feature, on shared memory files needing neither hugepages nor root
+#[cfg(not(feature = "shm-rings"))]
+type OwnedRing<T> = DpdkOwnedPubSubRing<T>;
+#[cfg(feature = "shm-rings")]
+type OwnedRing<T> = ShmRing<T>;
+
+// REST request weight budget per minute, keeping headroom below the exchange limit of 6000
+const REST_WEIGHT_BUDGET: u64 = 5_400;
+
+// Order budgets per 10 seconds and per day, keeping headroom below the exchange
+// limits of 100 and 200000
+const ORDER_BUDGET_10S: u64 = 90;
+const ORDER_BUDGET_1D: u64 = 180_000;
+
+/// The order budgets of the OMS reported by the `/status` endpoint.
+#[derive(Debug, Serialize)]
+struct OrderBudgetStatus {
+    /// Orders placed in the current 10 seconds.
+    count_10s: u64,
+    /// Order budget per 10 seconds.
+    limit_10s: u64,
+    /// Orders placed in the current day.
+    count_1d: u64,
+    /// Order budget per day.
+    limit_1d: u64,
+    /// Requests queued by the budget.
+    queued: u64,
+    /// Requests rejected by the budget.
+    rejected: u64,
+}
+
+/// The REST weight budget reported by the `/status` endpoint.
+#[derive(Debug, Serialize)]
+struct WeightBudgetStatus {
+    /// Weight used in the current minute.
+    used: u64,
+    /// Weight budget per minute.
+    limit: u64,
+    /// Requests rejected by the budget.
+    rejected: u64,
+}
+
+/// The status of the instance reported by the `/status` endpoint.
+#[derive(Debug, Serialize)]
+struct ManagerStatus {
+    /// The run epoch stamped in the status table.
+    run_epoch: u64,
+    /// The time the run started, in milliseconds since the epoch.
+    started_at_ms: u64,
+    /// The trading state of the status table.
+    trading: TradingStatus,
+    /// The order budgets of the OMS.
+    orders: OrderBudgetStatus,
+    /// The REST weight budget.
+    rest_weight: WeightBudgetStatus,
+    /// The rings and streams of the metrics region.
+    #[serde(flatten)]
+    metrics: MetricsStatus,
+}
+
+impl ManagerStatus {
+    /// Reads the status of the instance from its regions.
+    fn read(
+        status: &StatusRegion,
+        order_rate: &OrderRateLedger,
+        rest_weight: &WeightLedger,
+        metrics: &MetricsRegion,
+    ) -> Self {
+        let now_ms = now_ms();
+        let (count_10s, count_1d) = order_rate.counts(now_ms);
+        let (limit_10s, limit_1d) = order_rate.limits();
+        Self {
+            run_epoch: status.run_epoch(),
+            started_at_ms: status.started_at_ms(),
+            trading: status.snapshot(),
+            orders: OrderBudgetStatus {
+                count_10s,
+                limit_10s,
+                count_1d,
+                limit_1d,
+                queued: order_rate.queued(),
+                rejected: order_rate.rejected(),
+            },
+            rest_weight: WeightBudgetStatus {
+                used: rest_weight.used(now_ms),
+                limit: rest_weight.limit(),
+                rejected: rest_weight.rejected(),
+            },
+            metrics: MetricsStatus::from_region(metrics),
+        }
+    }
+}
 
-    // Configure hugepages
-    let hugepage_size = config.hugepages().size()?;
-    let hugepage_count = config.hugepages().count;
-    let sysfs_path = hugepage_size.sysfs_path();
+/// Prints the planned rings and their memory against the configured hugepages,
+/// failing if the rings can't fit.
+fn print_ring_plan(config: &HwResourcesConfig, ring_plan: &[PlannedRing]) -> Result<(), FatalError> {
+    for ring in ring_plan {
+        // The aggregated rings carry the other symbols of their set
+        let symbol = match ring.symbols {
+            1 => ring.symbol.clone(),
+            n => format!("{}+{}", ring.symbol, n - 1),
+        };
+        let content = match ring.content {
+            RingContent::Raw => format!("Raw/{}", ring.slot_size),
+            content => format!("{:?}", content),
+        };
+        println!(
+            "ring {:<16} symbol {:<12} size {:>8} {} ({} kB)",
+            ring.name,
+            symbol,
+            ring.size,
+            content,
+            ring.memory_bytes() / 1024
+        );
+    }
 
+    let ring_memory_mb = ring_plan.iter().map(PlannedRing::memory_bytes).sum::<u64>() / (1024 * 1024);
     println!(
-        "Configuring {} x {}kB hugepages via {}",
-        hugepage_count,
-        hugepage_size.size_kb(),
-        sysfs_path
+        "{} rings, {} MB of messages for {} MB of hugepages",
+        ring_plan.len(),
+        ring_memory_mb,
+        config.hugepage_memory_mb()
     );
+    if ring_memory_mb > config.hugepage_memory_mb() {
+        let detail = format!(
+            "The rings need {} MB, more than the {} MB of configured hugepages",
+            ring_memory_mb,
+            config.hugepage_memory_mb()
+        );
+        return Err(FatalError::new(FatalKind::Config, detail));
+    }
+    Ok(())
+}
+
+/// Prints the lcores claimed by every component and a conflict-free assignment,
+/// failing if the configured lcores conflict.
+fn print_lcore_plan(planner: &LcorePlanner, lcores_config: &Path) -> Result<(), FatalError> {
+    for claim in planner.claims() {
+        println!("lcores {:<24} {:?} ({:?})", claim.to_string(), claim.lcores, claim.usage);
+    }
+
+    let conflicts = planner.conflicts();
+    if conflicts.is_empty() {
+        return Ok(());
+    }
+    for conflict in &conflicts {
+        println!("lcore {} conflict: {}", conflict.lcore, conflict.claims.join(", "));
+    }
+
+    // Move the conflicting single-lcore components to the free online CPUs,
+    // or past the claimed lcores when the host can't tell
+    let available: Vec<u32> = match online_cpus() {
+        Some(cpus) => cpus.into_iter().map(|cpu| cpu as u32).collect(),
+        None => {
+            let last = planner.claims().iter().flat_map(|claim| claim.lcores.iter().copied()).max().unwrap_or(0);
+            (0..=last + planner.claims().len() as u32).collect()
+        }
+    };
+    let table = planner.plan(&available)?;
+    for planned in &table.moves {
+        println!("planned {}: {} (configured {})", planned.component, planned.to, planned.from);
+    }
+    let detail =
+        format!("{} lcore conflicts, apply the planned lcores to {}", conflicts.len(), lcores_config.display());
+    Err(FatalError::new(FatalKind::Config, detail))
+}
+
+/// Checks the latency-critical lcores of the components against the CPU
+/// topology, failing or warning per the configured policy.
+fn check_lcore_topology(config: &HwResourcesConfig, planner: &LcorePlanner) -> Result<(), FatalError> {
+    if config.topology_check.policy == TopologyCheckPolicy::Off {
+        info!("Topology check disabled");
+        return Ok(());
+    }
+
+    let topology = CpuTopology::read();
+    let ring_node = ring_node(config.lcore_id(), &config.eal, &topology);
+    let issues = check_topology(planner.claims(), &topology, ring_node);
+    if issues.is_empty() {
+        info!("Topology check passed, {} NUMA nodes, rings on node {}", topology.node_count(), ring_node);
+        return Ok(());
+    }
+
+    for issue in &issues {
+        warn!("Topology check: {}", issue);
+    }
+    if config.topology_check.policy == TopologyCheckPolicy::Fail {
+        let detail = format!("Topology check failed with {} issues", issues.len());
+        return Err(FatalError::new(FatalKind::Host, detail));
+    }
+    Ok(())
+}
+
+/// Checks the market data symbols against the exchange information, failing or
+/// warning per the configured policy.
+fn check_md_symbols(check: &SymbolCheckConfig, md_config: &MdHwResourcesConfig) -> Result<(), FatalError> {
+    if check.policy == SymbolCheckPolicy::Off {
+        info!("Symbol check disabled");
+        return Ok(());
+    }
+
+    let mut symbols: Vec<&str> = md_config.all_symbols().into_iter().collect();
+    symbols.sort_unstable();
+
+    let issues = match RestClient::new(&check.endpoint).and_then(|client| client.exchange_info()) {
+        Ok(info) => check_symbols(symbols.iter().copied(), &info, &check.quote_assets),
+        Err(e) if check.policy == SymbolCheckPolicy::Warn => {
+            warn!("Symbol check skipped, failed to fetch the exchange information: {}", e);
+            return Ok(());
+        }
+        Err(e) => {
+            let kind = if e.is_auth_failure() { FatalKind::NetworkAuth } else { FatalKind::Network };
+            let detail = format!("Symbol check failed to fetch the exchange information: {}", e);
+            return Err(FatalError::new(kind, detail));
+        }
+    };
+    if issues.is_empty() {
+        info!("Symbol check passed for {} symbols", symbols.len());
+        return Ok(());
+    }
+
+    for issue in &issues {
+        warn!("Symbol check: {}", issue);
+    }
+    if check.policy == SymbolCheckPolicy::Fail {
+        let detail = format!("Symbol check failed for {} of {} symbols", issues.len(), symbols.len());
+        return Err(FatalError::new(FatalKind::Config, detail));
+    }
+    Ok(())
+}
+
+/// Returns whether the DPDK primary process holds the lock of its runtime.
+#[cfg(not(feature = "shm-rings"))]
+fn primary_locked(eal: &EalConfig) -> Result<bool, FatalError> {
+    primary_running(&dpdk_config_path(eal.file_prefix())).fatal(FatalKind::Host)
+}
+
+/// Without DPDK there is no runtime lock, the stopped heartbeat alone telling the primary is dead.
+#[cfg(feature = "shm-rings")]
+fn primary_locked(_eal: &EalConfig) -> Result<bool, FatalError> {
+    Ok(false)
+}
+
+/// Waits as the standby of the running resource manager until it dies.
+///
+/// The standby only reads the status table of the primary, initializing
+/// neither DPDK nor the host until it takes over.
+fn wait_as_standby(config: &StandbyConfig, eal: &EalConfig) -> Result<(), FatalError> {
+    info!("Standing by, taking over once the primary stops beating for {} ms", config.takeover_after_ms);
+    let mut monitor = StandbyMonitor::new(config.clone());
+    let mut hung_warned = false;
+    loop {
+        let status = ShmRegion::<StatusRegion>::open(STATUS_REGION_NAME).ok();
+        let locked = primary_locked(eal)?;
+        let watched = monitor.watched();
+        match monitor.observe(PrimaryView::of(status.as_deref(), locked), now_ms()) {
+            StandbyDecision::Wait => {
+                if monitor.watched() != watched {
+                    info!("Watching the primary of run epoch {}", monitor.watched());
+                }
+                hung_warned = false;
+            }
+            StandbyDecision::Hung { age_ms } => {
+                if !hung_warned {
+                    warn!("Primary silent for {} ms but holding the DPDK runtime, it can't be taken over", age_ms);
+                    hung_warned = true;
+                }
+            }
+            StandbyDecision::TakeOver(reason) => {
+                warn!("Taking over the primary of run epoch {}: {:?}", monitor.watched(), reason);
+                return Ok(());
+            }
+        }
+        drop(status);
+        std::thread::sleep(std::time::Duration::from_millis(config.poll_interval_ms));
+    }
+}
+
+/// Verifies and configures the host, then initializes DPDK as the primary process.
+#[cfg(not(feature = "shm-rings"))]
+fn init_dpdk(config: &HwResourcesConfig) -> Result<DpdkEnv, FatalError> {
+    // Verify the host before configuring it, this process becoming the DPDK primary
+    Preflight::new()
+        .require_hugepages_mounted()
+        .require_lcores(&[config.lcore_id() as usize])
+        .require_capabilities(&[Capability::DacOverride, Capability::IpcLock, Capability::SysAdmin])
+        .require_no_primary()
+        .with_file_prefix(config.eal.file_prefix())
+        .run()?;
+
+    // Configure the hugepages of each size
+    let mut hugepages = Preflight::new();
+    for entry in config.hugepages() {
+        let hugepage_size = entry.size().fatal(FatalKind::Config)?;
+        let sysfs_path = hugepage_size.sysfs_path();
+
+        info!(
+            "Configuring {} x {}kB hugepages via {}",
+            entry.count,
+            hugepage_size.size_kb(),
+            sysfs_path
+        );
+
+        fs::write(sysfs_path, entry.count.to_string())
+            .map_err(|e| format!("Failed to configure hugepages at {}: {}. Run as root?", sysfs_path, e))
+            .fatal(FatalKind::Host)?;
+        hugepages = hugepages.require_hugepages(hugepage_size.size_kb(), entry.count);
+    }
 
-    fs::write(sysfs_path, hugepage_count.to_string())
-        .map_err(|e| format!("Failed to configure hugepages at {}: {}. Run as root?", sysfs_path, e))?;
+    // The kernel allocates fewer pages than requested when the memory is fragmented
+    hugepages.run()?;
 
-    // Initialize DPDK environment with configured CPU core
+    // Initialize DPDK environment with configured CPU core, reserving the memory of the hugepages
+    let eal_args = config.eal.primary_args(config.eal_memory_args());
+    info!("EAL options: {}", eal_args.join(" "));
     let dpdk_env = DpdkEnvBuilder::default()
         .process_type(DpdkProcessType::Primary)
         .lcore_ids(vec![config.lcore_id() as usize])
-        .build()?;
-
-    // Create PubSubRings for each symbol/kind combination
-    // Ring naming convention: {KIND}_{symbol_id}_PS
-    let mut rings: HashMap<String, DpdkOwnedPubSubRing<RawMessage>> = HashMap::new();
-
-    for feed in md_config.all_feeds() {
-        let kind = feed.kind.to_uppercase();
-
-        // Get ring_size based on whether feed uses sets or direct config
-        for symbol in feed.all_symbols() {
-            let symbol_id = symbol_info
-                .symbol_id(symbol)
-                .ok_or_else(|| format!("Symbol '{}' not found in symbolinfo.yaml", symbol))?;
-
-            // Get ring size for this symbol
-            let ring_size = if feed.uses_sets() {
-                // Find the set containing this symbol
-                feed.sets
-                    .iter()
-                    .find(|set| set.symbols.iter().any(|s| s == symbol))
-                    .map(|set| set.ring_size)
-                    .ok_or_else(|| format!("Symbol '{}' not found in any set", symbol))?
-            } else {
-                feed.ring_size
-                    .ok_or_else(|| format!("Feed '{}' missing ring_size", feed.kind))?
-            };
-
-            let ring_name = format!("{}_{}_PS", kind, symbol_id);
-
-            println!(
-                "Creating ring: {} (symbol: {}, size: {})",
-                ring_name, symbol, ring_size
-            );
-
-            let ring = dpdk_env.pubsub_create::<RawMessage>(&ring_name, ring_size as usize)?;
-            rings.insert(ring_name, ring);
-        }
+        .eal_args(eal_args)
+        .build()
+        .fatal(FatalKind::DpdkInit)?;
+    Ok(dpdk_env)
+}
+
+fn main() -> ExitCode {
+    ctl_core::exit_code(run())
+}
+
+fn run() -> Result<(), FatalError> {
+    let args = Args::parse();
+    let _log = ctl_log::init_from_file(&args.log_config).fatal(FatalKind::Config)?;
+
+    // Load hardware resources configuration
+    let config = HwResourcesConfig::from_file(&args.config).fatal(FatalKind::Config)?;
+
+    // Load market data configuration
+    let md_config = MdHwResourcesConfig::from_file(&args.md_config).fatal(FatalKind::Config)?;
+
+    // Load symbol info configuration
+    let symbol_info = SymbolInfoConfig::from_file(&args.symbol_info).fatal(FatalKind::Config)?;
+
+    // Load the lcores of the single-lcore components
+    let lcores = LcoresConfig::from_file(&args.lcores_config)?;
+
+    // Load the health endpoint, if any
+    let health = HealthConfig::from_file(&args.health_config)?.endpoint(HEALTH_COMPONENT).cloned();
+
+    // Every process of the instance attaches to the DPDK runtime and the regions of its file prefix
+    if md_config.eal.file_prefix() != config.eal.file_prefix() {
+        let detail = format!(
+            "EAL file_prefix '{}' of {} differs from '{}' of {}",
+            md_config.eal.file_prefix(),
+            args.md_config.display(),
+            config.eal.file_prefix(),
+            args.config.display()
+        );
+        return Err(FatalError::new(FatalKind::Config, detail));
     }
+    config.eal.apply_region_prefix();
 
-    println!(
-        "Created {} PubSubRings for market data feeds",
-        rings.len()
+    // Plan the market data rings, every symbol needing an ID in the symbol info table
+    let ring_plan = plan_rings(&md_config, &symbol_info).fatal(FatalKind::Config)?;
+
+    // Plan the lcores of every component, their conflicts only surfacing as
+    // busy-polling loops halving each other's throughput
+    let lcore_plan = plan_lcores(&config, &md_config, &lcores).fatal(FatalKind::Config)?;
+    if args.check {
+        print_ring_plan(&config, &ring_plan)?;
+        print_lcore_plan(&lcore_plan, &args.lcores_config)?;
+        check_lcore_topology(&config, &lcore_plan)?;
+        println!("Configuration OK");
+        return Ok(());
+    }
+    for conflict in lcore_plan.conflicts() {
+        warn!("Lcore {} is claimed by {}", conflict.lcore, conflict.claims.join(", "));
+    }
+    check_lcore_topology(&config, &lcore_plan)?;
+
+    // Verify the configured symbols before configuring the host and creating their rings
+    check_md_symbols(&config.symbol_check, &md_config)?;
+
+    // A standby takes over with the rings planned and the symbols checked, provisioning them anew
+    if args.standby {
+        wait_as_standby(&config.standby, &config.eal)?;
+    }
+
+    // The rings are created on the DPDK runtime, or on shared memory files with `shm-rings`
+    #[cfg(not(feature = "shm-rings"))]
+    let dpdk_env = init_dpdk(&config)?;
+    #[cfg(not(feature = "shm-rings"))]
+    macro_rules! create_ring {
+        ($message:ty, $name:expr, $size:expr) => {
+            dpdk_env.pubsub_create::<$message>($name, $size).fatal(FatalKind::DpdkInit)?
+        };
+    }
+    #[cfg(feature = "shm-rings")]
+    info!("Creating the rings on shared memory files, without DPDK");
+    #[cfg(feature = "shm-rings")]
+    macro_rules! create_ring {
+        ($message:ty, $name:expr, $size:expr) => {
+            ShmRing::<$message>::create($name, $size)?
+        };
+    }
+
+    // Create the metrics region, with an entry registered for every ring
+    let metrics = Arc::new(ShmRegion::<MetricsRegion>::create(METRICS_REGION_NAME)?);
+
+    // Create the last-value Top region, overwritten by the md-handler on every update
+    let _last_top = ShmRegion::<LastTopRegion>::create(LAST_TOP_REGION_NAME)?;
+
+    // Create the time synchronization region, maintained by ctl-time-sync
+    let _time_sync = ShmRegion::<TimeSyncRegion>::create(TIME_SYNC_REGION_NAME)?;
+
+    // Create the REST weight ledger shared by every component issuing REST requests
+    let rest_weight = Arc::new(ShmRegion::<WeightLedger>::create(WEIGHT_LEDGER_REGION_NAME)?);
+    rest_weight.set_limit(REST_WEIGHT_BUDGET);
+
+    // Create the order rate ledger, reserved in by the OMS before placing orders
+    let order_rate = Arc::new(ShmRegion::<OrderRateLedger>::create(ORDER_RATE_REGION_NAME)?);
+    order_rate.set_limits(ORDER_BUDGET_10S, ORDER_BUDGET_1D);
+
+    // Create the position table, maintained by the position tracker
+    let _positions = ShmRegion::<PositionRegion>::create(POSITION_REGION_NAME)?;
+
+    // Create the balances table, maintained by the private data handler
+    let _balances = ShmRegion::<BalanceRegion>::create(BALANCE_REGION_NAME)?;
+
+    // Create the status table, holding the halted state set by the kill switch, beating
+    // at once for a standby not to take over while the run is set up
+    let status = Arc::new(ShmRegion::<StatusRegion>::create(STATUS_REGION_NAME)?);
+    status.beat(now_ms());
+
+    // Create the market data rings of the plan, registering their metrics
+    let mut rings: HashMap<String, OwnedRing<RawMessage>> = HashMap::new();
+    let mut stats_rings: HashMap<String, OwnedRing<TradeStatsMessage>> = HashMap::new();
+    let mut kline_rings: HashMap<String, OwnedRing<CandleMessage>> = HashMap::new();
+    let mut signal_rings: HashMap<String, OwnedRing<SignalMessage>> = HashMap::new();
+
+    for planned in &ring_plan {
+        let PlannedRing { name, symbol, symbols, size, content, slot_size } = planned;
+        info!("Creating ring: {} (symbol: {}, symbols: {}, size: {})", name, symbol, symbols, size);
+
+        let size = *size as usize;
+        // Recorded for the secondaries to check the layout of their messages,
+        // and the slot size of the raw ones, on lookup
+        let registered = match content {
+            RingContent::Raw => {
+                rings.insert(name.clone(), create_ring!(RawMessage, name, size));
+                metrics.register_raw_ring(name, size as u64, *slot_size)
+            }
+            RingContent::TradeStats => {
+                stats_rings.insert(name.clone(), create_ring!(TradeStatsMessage, name, size));
+                metrics.register_ring(name, size as u64, TradeStatsMessage::LAYOUT_HASH)
+            }
+            RingContent::Candle => {
+                kline_rings.insert(name.clone(), create_ring!(CandleMessage, name, size));
+                metrics.register_ring(name, size as u64, CandleMessage::LAYOUT_HASH)
+            }
+            RingContent::Signal => {
+                signal_rings.insert(name.clone(), create_ring!(SignalMessage, name, size));
+                metrics.register_ring(name, size as u64, SignalMessage::LAYOUT_HASH)
+            }
+        };
+        registered
+            .ok_or_else(|| format!("Failed to register metrics for ring '{}'", name))
+            .fatal(FatalKind::SharedState)?;
+    }
+
+    info!(
+        "Created {} PubSubRings for market data feeds, {} for trade statistics, {} for candles and {} for signals",
+        rings.len(),
+        stats_rings.len(),
+        kline_rings.len(),
+        signal_rings.len()
     );
 
+    // Create the control ring, broadcasting the ctl-admin commands to every component
+    info!("Creating ring: {} (size: {})", CONTROL_RING_NAME, CONTROL_RING_SIZE);
+    let _control_ring = create_ring!(ControlMessage, CONTROL_RING_NAME, CONTROL_RING_SIZE);
+    metrics
+        .register_ring(CONTROL_RING_NAME, CONTROL_RING_SIZE as u64, ControlMessage::LAYOUT_HASH)
+        .ok_or_else(|| format!("Failed to register metrics for ring '{}'", CONTROL_RING_NAME))
+        .fatal(FatalKind::SharedState)?;
+
+    // Create the alerts ring, where every component publishes the events needing attention
+    info!("Creating ring: {} (size: {})", ALERTS_RING_NAME, ALERTS_RING_SIZE);
+    let _alerts_ring = create_ring!(AlertMessage, ALERTS_RING_NAME, ALERTS_RING_SIZE);
+    metrics
+        .register_ring(ALERTS_RING_NAME, ALERTS_RING_SIZE as u64, AlertMessage::LAYOUT_HASH)
+        .ok_or_else(|| format!("Failed to register metrics for ring '{}'", ALERTS_RING_NAME))
+        .fatal(FatalKind::SharedState)?;
+
+    // Create the order entry ring, where the operator console enters the manual orders of the OMS
+    info!("Creating ring: {} (size: {})", ORDER_ENTRY_RING_NAME, ORDER_ENTRY_RING_SIZE);
+    let _order_entry_ring = create_ring!(OrderEntryMessage, ORDER_ENTRY_RING_NAME, ORDER_ENTRY_RING_SIZE);
+    metrics
+        .register_ring(ORDER_ENTRY_RING_NAME, ORDER_ENTRY_RING_SIZE as u64, OrderEntryMessage::LAYOUT_HASH)
+        .ok_or_else(|| format!("Failed to register metrics for ring '{}'", ORDER_ENTRY_RING_NAME))
+        .fatal(FatalKind::SharedState)?;
+
+    // Stamp the run once its regions and rings are created, the secondaries
+    // attached to a previous run telling their mappings are stale
+    let epoch = new_run_epoch();
+    status.start_run(epoch, now_ms());
+    info!("Started run epoch {}", epoch);
+
+    // Serve the health and status endpoints, read from the regions of the instance
+    let heartbeat = Heartbeat::new();
+    if let Some(endpoint) = &health {
+        let (status, order_rate, rest_weight, metrics) =
+            (status.clone(), order_rate.clone(), rest_weight.clone(), metrics.clone());
+        let routes = HealthRoutes::new(move || {
+            let report = ManagerStatus::read(&status, &order_rate, &rest_weight, &metrics);
+            serde_json::to_string(&report).unwrap_or_default()
+        });
+        let server = HealthServer::spawn(endpoint, heartbeat.clone(), routes).fatal(FatalKind::Host)?;
+        info!("Serving /healthz and /status on {}", server.local_addr());
+    }
+
     // Keep the primary process alive to maintain shared memory.
-    // The rings HashMap keeps all DpdkOwnedPubSubRing instances alive.
+    // The rings, stats_rings, kline_rings and signal_rings HashMaps, `_control_ring` and `_alerts_ring` keep all
+    // owned rings alive, and the region handles (`metrics`, `_last_top`,
+    // `_time_sync`, `rest_weight`, `order_rate`, `_positions`, `_balances`, `status`) keep the shared
+    // regions mapped.
     loop {
+        heartbeat.beat(now_ms());
+        status.beat(now_ms());
         std::thread::sleep(std::time::Duration::from_secs(1));
     }
 
@@ -0,0 +1,286 @@
+//! Plan of the market data rings created by the Resource Manager.
+//!
+//! The rings follow from the market data configuration, one raw ring per symbol
+//! of each feed, plus the trade statistics and candle rings of each symbol of
+//! the trade feed and the signal ring of each symbol of the top feed, all named
+//! after the symbol ID of the symbol info table. The symbols of an aggregated
+//! set share each of these rings, named after the first symbol of the set. The
+//! raw rings are registered with the slot size of their feed, the payload bytes
+//! of their messages. Under A/B line arbitration, each raw ring is planned with
+//! its two line rings, the md-handler of each line publishing to its own and
+//! ctl-arbiter forwarding to the raw ring. The plan is computed once, both to
+//! create the rings and to print them with `--check`.
+
+use std::mem::size_of;
+
+use ctl_core::{Line, RingKind, RingName};
+use ctl_feed::{CandleMessage, RawMessage, SignalMessage, TradeStatsMessage};
+use ctl_md_handler::{HwResourcesConfig as MdHwResourcesConfig, SymbolInfoConfig};
+use hashbrown::HashSet;
+
+use crate::HwResourcesConfigError;
+
+/// The messages carried by a ring.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RingContent {
+    /// The raw messages of a feed, `{KIND}_{symbol_id}_PS`, and of its lines
+    /// `{KIND}_{symbol_id}_LA` and `_LB` under arbitration.
+    Raw,
+    /// The rolling trade statistics, `STATS_{symbol_id}_PS`.
+    TradeStats,
+    /// The candles, `KLINE_{symbol_id}_PS`.
+    Candle,
+    /// The derived microstructure signals, `SIGNAL_{symbol_id}_PS`.
+    Signal,
+}
+
+impl RingContent {
+    /// Returns the size of a message of the ring, in bytes.
+    pub fn message_size(&self) -> usize {
+        match self {
+            RingContent::Raw => size_of::<RawMessage>(),
+            RingContent::TradeStats => size_of::<TradeStatsMessage>(),
+            RingContent::Candle => size_of::<CandleMessage>(),
+            RingContent::Signal => size_of::<SignalMessage>(),
+        }
+    }
+}
+
+/// A ring to create.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlannedRing {
+    /// The ring name.
+    pub name: String,
+    /// The symbol of the ring.
+    pub symbol: String,
+    /// The number of symbols carried by the ring, more than one for aggregated sets.
+    pub symbols: usize,
+    /// The number of messages of the ring.
+    pub size: u32,
+    /// The messages carried by the ring.
+    pub content: RingContent,
+    /// The payload bytes of the messages of a raw ring, zero for the other rings.
+    pub slot_size: usize,
+}
+
+impl PlannedRing {
+    /// Returns the memory of the messages of the ring, in bytes.
+    pub fn memory_bytes(&self) -> u64 {
+        self.size as u64 * self.content.message_size() as u64
+    }
+}
+
+/// Plans the market data rings of the configuration, in creation order.
+///
+/// # Errors
+/// Returns an error if a symbol is missing from the symbol info table, or two
+/// rings share a name.
+pub fn plan_rings(
+    md_config: &MdHwResourcesConfig,
+    symbol_info: &SymbolInfoConfig,
+) -> Result<Vec<PlannedRing>, HwResourcesConfigError> {
+    let symbol_id = |symbol: &str| {
+        symbol_info.symbol_id(symbol).ok_or_else(|| {
+            HwResourcesConfigError::ValidationError(format!("Symbol '{}' not found in symbolinfo.yaml", symbol))
+        })
+    };
+
+    let mut rings = Vec::new();
+    for feed in md_config.all_feeds() {
+        let kind = RingKind::from_feed_kind(&feed.kind).ok_or_else(|| {
+            HwResourcesConfigError::ValidationError(format!("Feed kind '{}' has no rings", feed.kind))
+        })?;
+        for feed_set in feed.feed_sets() {
+            let symbols = if feed_set.aggregate { feed_set.symbols.len() } else { 1 };
+            for symbol in feed_set.ring_symbols() {
+                let name = RingName::pubsub(kind, symbol_id(symbol)?);
+                let lines = md_config.arbitration.map(|_| Line::ALL.map(|line| name.on_line(line)));
+                for name in std::iter::once(name).chain(lines.into_iter().flatten()) {
+                    rings.push(PlannedRing {
+                        name: name.to_string(),
+                        symbol: symbol.clone(),
+                        symbols,
+                        size: feed_set.ring_size,
+                        content: RingContent::Raw,
+                        slot_size: feed_set.slot_size,
+                    });
+                }
+            }
+        }
+    }
+
+    // The trade statistics and candle rings of each symbol of the trade feed,
+    // sized like the symbol's trade ring
+    if let Some(feed) = md_config.find_feed("trade") {
+        for feed_set in feed.feed_sets() {
+            let symbols = if feed_set.aggregate { feed_set.symbols.len() } else { 1 };
+            for symbol in feed_set.ring_symbols() {
+                let id = symbol_id(symbol)?;
+                for (kind, content) in [(RingKind::Stats, RingContent::TradeStats), (RingKind::Kline, RingContent::Candle)] {
+                    rings.push(PlannedRing {
+                        name: RingName::pubsub(kind, id).to_string(),
+                        symbol: symbol.clone(),
+                        symbols,
+                        size: feed_set.ring_size,
+                        content,
+                        slot_size: 0,
+                    });
+                }
+            }
+        }
+    }
+
+    // The signal ring of each symbol of the top feed, sized like the symbol's Top ring
+    if let Some(feed) = md_config.find_feed("top") {
+        for feed_set in feed.feed_sets() {
+            let symbols = if feed_set.aggregate { feed_set.symbols.len() } else { 1 };
+            for symbol in feed_set.ring_symbols() {
+                rings.push(PlannedRing {
+                    name: RingName::pubsub(RingKind::Signal, symbol_id(symbol)?).to_string(),
+                    symbol: symbol.clone(),
+                    symbols,
+                    size: feed_set.ring_size,
+                    content: RingContent::Signal,
+                    slot_size: 0,
+                });
+            }
+        }
+    }
+
+    let mut names = HashSet::new();
+    for ring in &rings {
+        if !names.insert(ring.name.as_str()) {
+            return Err(HwResourcesConfigError::ValidationError(format!(
+                "Duplicate ring '{}' for symbol '{}'",
+                ring.name, ring.symbol
+            )));
+        }
+    }
+
+    Ok(rings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ctl_feed::RAW_MESSAGE_SIZE;
+
+    const MD_CONFIG: &str = r#"
+- main_cpu: 0
+- worker_cpus: 1-4
+- pubsubs:
+    - feed:
+        kind: top
+        sets:
+          - name: A
+            num_cpus: 1
+            ring_size: 1024
+            symbols:
+              - BTCUSDT
+            medium:
+              - protocol: websocket
+                parser: json
+          - name: B
+            num_cpus: 1
+            ring_size: 2048
+            symbols:
+              - ETHUSDT
+            medium:
+              - protocol: websocket
+                parser: json
+    - feed:
+        kind: trade
+        num_cpus: 1
+        ring_size: 4096
+        slot_size: 256
+        symbols:
+          - ETHUSDT
+        medium:
+          - protocol: websocket
+            parser: json
+"#;
+
+    const SYMBOL_INFO: &str = r#"
+- BTCUSDT:
+    id: 0
+- ETHUSDT:
+    id: 1
+"#;
+
+    #[test]
+    fn test_plan_rings() {
+        let md_config = MdHwResourcesConfig::from_str(MD_CONFIG).unwrap();
+        let symbol_info = SymbolInfoConfig::from_str(SYMBOL_INFO).unwrap();
+        let rings = plan_rings(&md_config, &symbol_info).unwrap();
+
+        let names: Vec<_> = rings.iter().map(|ring| ring.name.as_str()).collect();
+        assert_eq!(
+            names,
+            vec!["TOP_0_PS", "TOP_1_PS", "TRADE_1_PS", "STATS_1_PS", "KLINE_1_PS", "SIGNAL_0_PS", "SIGNAL_1_PS"]
+        );
+        assert_eq!(rings[1].size, 2048);
+        assert_eq!(rings[4].size, 4096);
+        assert_eq!(rings[4].content, RingContent::Candle);
+        assert_eq!((rings[6].size, rings[6].content), (2048, RingContent::Signal));
+        assert_eq!(rings[0].memory_bytes(), 1024 * size_of::<RawMessage>() as u64);
+        let slot_sizes: Vec<_> = rings.iter().map(|ring| ring.slot_size).collect();
+        assert_eq!(slot_sizes, vec![RAW_MESSAGE_SIZE, RAW_MESSAGE_SIZE, 256, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_plan_rings_aggregated_set() {
+        let md_config = r#"
+- main_cpu: 0
+- worker_cpus: 1-4
+- pubsubs:
+    - feed:
+        kind: trade
+        sets:
+          - name: longtail
+            num_cpus: 1
+            ring_size: 4096
+            aggregate: true
+            symbols:
+              - ETHUSDT
+              - BTCUSDT
+            medium:
+              - protocol: websocket
+                parser: json
+"#;
+        let md_config = MdHwResourcesConfig::from_str(md_config).unwrap();
+        let symbol_info = SymbolInfoConfig::from_str(SYMBOL_INFO).unwrap();
+        let rings = plan_rings(&md_config, &symbol_info).unwrap();
+
+        // One ring of each content for the set, named after its first symbol
+        let names: Vec<_> = rings.iter().map(|ring| ring.name.as_str()).collect();
+        assert_eq!(names, vec!["TRADE_1_PS", "STATS_1_PS", "KLINE_1_PS"]);
+        assert!(rings.iter().all(|ring| ring.symbol == "ETHUSDT" && ring.symbols == 2));
+    }
+
+    #[test]
+    fn test_plan_rings_arbitrated() {
+        let md_config = format!("- arbitration: {{}}\n{}", MD_CONFIG);
+        let md_config = MdHwResourcesConfig::from_str(&md_config).unwrap();
+        let symbol_info = SymbolInfoConfig::from_str(SYMBOL_INFO).unwrap();
+        let rings = plan_rings(&md_config, &symbol_info).unwrap();
+
+        // The line rings of each raw ring, sized like it
+        let names: Vec<_> = rings.iter().map(|ring| ring.name.as_str()).collect();
+        assert_eq!(
+            names,
+            vec![
+                "TOP_0_PS", "TOP_0_LA", "TOP_0_LB", "TOP_1_PS", "TOP_1_LA", "TOP_1_LB", "TRADE_1_PS", "TRADE_1_LA",
+                "TRADE_1_LB", "STATS_1_PS", "KLINE_1_PS", "SIGNAL_0_PS", "SIGNAL_1_PS",
+            ]
+        );
+        assert!(rings[6..9].iter().all(|ring| ring.size == 4096 && ring.slot_size == 256));
+    }
+
+    #[test]
+    fn test_plan_rings_unknown_symbol() {
+        let md_config = MdHwResourcesConfig::from_str(MD_CONFIG).unwrap();
+        let symbol_info = SymbolInfoConfig::from_str("- BTCUSDT:\n    id: 0\n").unwrap();
+        let result = plan_rings(&md_config, &symbol_info);
+        assert!(result.unwrap_err().to_string().contains("ETHUSDT"));
+    }
+}
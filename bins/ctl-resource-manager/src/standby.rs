@@ -0,0 +1,196 @@
+//! Hot standby of the resource manager.
+//!
+//! The death of the resource manager takes the rings and regions of the whole
+//! controller down with it. A second instance started with `--standby` plans
+//! the same rings from the same configuration, then watches the heartbeat the
+//! primary beats in the status table instead of initializing DPDK. Once the
+//! primary stops beating, or its status table is gone, and it no longer holds
+//! the lock of the DPDK runtime, the standby takes over: it provisions the
+//! rings and regions anew and stamps a new run epoch, the secondaries
+//! reattaching to it.
+//!
+//! A primary that stopped beating but still holds the DPDK lock is hung rather
+//! than dead; the standby can't become the DPDK primary and keeps waiting.
+
+use ctl_core::StatusRegion;
+use serde::Deserialize;
+
+fn default_takeover_after_ms() -> u64 {
+    5_000
+}
+
+fn default_poll_interval_ms() -> u64 {
+    500
+}
+
+/// Configuration of the standby instance.
+#[derive(Debug, Clone, Deserialize, PartialEq, Eq, Hash)]
+pub struct StandbyConfig {
+    /// Age of the heartbeat of the primary past which it is taken over.
+    #[serde(default = "default_takeover_after_ms")]
+    pub takeover_after_ms: u64,
+    /// Interval between the checks of the heartbeat.
+    #[serde(default = "default_poll_interval_ms")]
+    pub poll_interval_ms: u64,
+}
+
+impl Default for StandbyConfig {
+    fn default() -> Self {
+        Self { takeover_after_ms: default_takeover_after_ms(), poll_interval_ms: default_poll_interval_ms() }
+    }
+}
+
+impl StandbyConfig {
+    /// Validates the standby configuration.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.poll_interval_ms == 0 {
+            return Err("standby 'poll_interval_ms' must be greater than 0".to_string());
+        }
+        if self.takeover_after_ms <= self.poll_interval_ms {
+            return Err(format!(
+                "standby 'takeover_after_ms' ({}) must exceed 'poll_interval_ms' ({})",
+                self.takeover_after_ms, self.poll_interval_ms
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// The state of the primary seen by the standby.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PrimaryView {
+    /// The run epoch and last beat of the status table, `None` if it is missing.
+    pub status: Option<(u64, u64)>,
+    /// Whether a process holds the lock of the DPDK runtime.
+    pub locked: bool,
+}
+
+impl PrimaryView {
+    /// Returns the view of the primary from its status table, `None` if missing.
+    pub fn of(status: Option<&StatusRegion>, locked: bool) -> Self {
+        Self { status: status.map(|status| (status.run_epoch(), status.heartbeat_ms())), locked }
+    }
+}
+
+/// Why the standby takes over.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TakeoverReason {
+    /// The status table of the primary is gone, the primary having exited.
+    PrimaryGone,
+    /// The primary stopped beating.
+    HeartbeatStale {
+        /// The age of its last beat, in milliseconds.
+        age_ms: u64,
+    },
+}
+
+/// What the standby does after a check of the primary.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StandbyDecision {
+    /// The primary is alive, or no primary was seen yet.
+    Wait,
+    /// The primary stopped beating but still holds the DPDK lock.
+    Hung {
+        /// The age of its last beat, in milliseconds.
+        age_ms: u64,
+    },
+    /// The primary failed, the standby takes over.
+    TakeOver(TakeoverReason),
+}
+
+/// Watches the heartbeat of the primary from the standby.
+#[derive(Debug, Clone)]
+pub struct StandbyMonitor {
+    /// The standby configuration.
+    config: StandbyConfig,
+    /// The run epoch of the primary last seen beating, zero until one is.
+    watched: u64,
+}
+
+impl StandbyMonitor {
+    /// Creates a monitor, watching no primary yet.
+    pub fn new(config: StandbyConfig) -> Self {
+        Self { config, watched: 0 }
+    }
+
+    /// Returns the run epoch of the primary watched, zero until one was seen.
+    pub fn watched(&self) -> u64 {
+        self.watched
+    }
+
+    /// Decides what to do from a check of the primary.
+    ///
+    /// Only a primary seen beating is taken over, so a standby started before
+    /// the primary doesn't take its place.
+    pub fn observe(&mut self, view: PrimaryView, now_ms: u64) -> StandbyDecision {
+        let age_ms = match view.status {
+            // A primary setting up its run beats before stamping its epoch
+            Some((epoch, heartbeat_ms)) if heartbeat_ms != 0 => {
+                let age_ms = now_ms.saturating_sub(heartbeat_ms);
+                if age_ms <= self.config.takeover_after_ms {
+                    if epoch != 0 {
+                        self.watched = epoch;
+                    }
+                    return StandbyDecision::Wait;
+                }
+                Some(age_ms)
+            }
+            Some(_) => return StandbyDecision::Wait,
+            None => None,
+        };
+        if self.watched == 0 {
+            return StandbyDecision::Wait;
+        }
+        match (age_ms, view.locked) {
+            (Some(age_ms), true) => StandbyDecision::Hung { age_ms },
+            (None, true) => StandbyDecision::Wait,
+            (Some(age_ms), false) => StandbyDecision::TakeOver(TakeoverReason::HeartbeatStale { age_ms }),
+            (None, false) => StandbyDecision::TakeOver(TakeoverReason::PrimaryGone),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn view(status: Option<(u64, u64)>, locked: bool) -> PrimaryView {
+        PrimaryView { status, locked }
+    }
+
+    #[test]
+    fn test_standby_takeover() {
+        let mut monitor = StandbyMonitor::new(StandbyConfig::default());
+
+        // No primary seen yet, nothing to take over
+        assert_eq!(monitor.observe(view(None, false), 10_000), StandbyDecision::Wait);
+        assert_eq!(monitor.observe(view(Some((0, 9_000)), true), 10_000), StandbyDecision::Wait);
+        assert_eq!(monitor.watched(), 0);
+
+        assert_eq!(monitor.observe(view(Some((7, 9_000)), true), 10_000), StandbyDecision::Wait);
+        assert_eq!(monitor.watched(), 7);
+        assert_eq!(monitor.observe(view(Some((7, 9_000)), true), 14_000), StandbyDecision::Wait);
+
+        // Stale but still holding the DPDK lock, the primary is hung
+        assert_eq!(
+            monitor.observe(view(Some((7, 9_000)), true), 15_000),
+            StandbyDecision::Hung { age_ms: 6_000 }
+        );
+        assert_eq!(
+            monitor.observe(view(Some((7, 9_000)), false), 15_000),
+            StandbyDecision::TakeOver(TakeoverReason::HeartbeatStale { age_ms: 6_000 })
+        );
+        assert_eq!(monitor.observe(view(None, true), 15_000), StandbyDecision::Wait);
+        assert_eq!(
+            monitor.observe(view(None, false), 15_000),
+            StandbyDecision::TakeOver(TakeoverReason::PrimaryGone)
+        );
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(StandbyConfig::default().validate().is_ok());
+        let config = StandbyConfig { takeover_after_ms: 500, poll_interval_ms: 500 };
+        assert!(config.validate().is_err());
+    }
+}
@@ -0,0 +1,164 @@
+//! Validation of the configured symbols against the exchange.
+//!
+//! Before creating the rings, the Resource Manager checks every symbol of the
+//! market data configuration against the exchange information: a symbol must
+//! be listed, trading, and quoted in one of the expected quote assets. A typo or
+//! a delisted symbol would otherwise only show as a silent stream once running.
+
+use std::fmt;
+
+use ctl_rest::{ExchangeInfo, BINANCE_REST_ENDPOINT};
+use serde::Deserialize;
+
+fn default_endpoint() -> String {
+    BINANCE_REST_ENDPOINT.to_string()
+}
+
+/// What to do when a configured symbol fails the check.
+#[derive(Debug, Clone, Copy, Default, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum SymbolCheckPolicy {
+    /// Skip the check, e.g. offline.
+    Off,
+    /// Log the failed symbols and carry on.
+    Warn,
+    /// Refuse to start.
+    #[default]
+    Fail,
+}
+
+/// Configuration of the symbol check.
+#[derive(Debug, Clone, Deserialize, PartialEq, Eq, Hash)]
+pub struct SymbolCheckConfig {
+    /// What to do when a symbol fails the check.
+    #[serde(default)]
+    pub policy: SymbolCheckPolicy,
+    /// The expected quote assets, empty accepting any.
+    #[serde(default)]
+    pub quote_assets: Vec<String>,
+    /// Base URL of the REST API serving the exchange information.
+    #[serde(default = "default_endpoint")]
+    pub endpoint: String,
+}
+
+impl Default for SymbolCheckConfig {
+    fn default() -> Self {
+        Self { policy: SymbolCheckPolicy::default(), quote_assets: Vec::new(), endpoint: default_endpoint() }
+    }
+}
+
+impl SymbolCheckConfig {
+    /// Validates the check configuration.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.endpoint.is_empty() {
+            return Err("symbol check 'endpoint' cannot be empty".to_string());
+        }
+        if self.quote_assets.iter().any(String::is_empty) {
+            return Err("symbol check 'quote_assets' contains an empty asset".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// A configured symbol failing the check.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SymbolIssue {
+    /// The symbol isn't listed on the exchange.
+    Unknown {
+        /// The symbol.
+        symbol: String,
+    },
+    /// The symbol is listed but not trading.
+    NotTrading {
+        /// The symbol.
+        symbol: String,
+        /// The trading status.
+        status: String,
+    },
+    /// The symbol is quoted in an unexpected asset.
+    QuoteAsset {
+        /// The symbol.
+        symbol: String,
+        /// The quote asset.
+        quote_asset: String,
+    },
+}
+
+impl fmt::Display for SymbolIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SymbolIssue::Unknown { symbol } => write!(f, "symbol '{}' is not listed on the exchange", symbol),
+            SymbolIssue::NotTrading { symbol, status } => {
+                write!(f, "symbol '{}' is not trading (status {})", symbol, status)
+            }
+            SymbolIssue::QuoteAsset { symbol, quote_asset } => {
+                write!(f, "symbol '{}' is quoted in unexpected asset {}", symbol, quote_asset)
+            }
+        }
+    }
+}
+
+/// Checks the symbols against the exchange information, returning the issues
+/// found, in the order of the symbols.
+pub fn check_symbols<'a>(
+    symbols: impl IntoIterator<Item = &'a str>,
+    info: &ExchangeInfo,
+    quote_assets: &[String],
+) -> Vec<SymbolIssue> {
+    let mut issues = Vec::new();
+    for symbol in symbols {
+        let Some(listed) = info.find_symbol(symbol) else {
+            issues.push(SymbolIssue::Unknown { symbol: symbol.to_string() });
+            continue;
+        };
+        if !listed.is_trading() {
+            issues.push(SymbolIssue::NotTrading { symbol: symbol.to_string(), status: listed.status.clone() });
+        }
+        if !quote_assets.is_empty() && !quote_assets.contains(&listed.quote_asset) {
+            issues.push(SymbolIssue::QuoteAsset {
+                symbol: symbol.to_string(),
+                quote_asset: listed.quote_asset.clone(),
+            });
+        }
+    }
+    issues
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ctl_rest::ExchangeSymbol;
+
+    fn listed(symbol: &str, status: &str, quote_asset: &str) -> ExchangeSymbol {
+        ExchangeSymbol {
+            symbol: symbol.to_string(),
+            status: status.to_string(),
+            base_asset: symbol.trim_end_matches(quote_asset).to_string(),
+            quote_asset: quote_asset.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_check_symbols() {
+        let info = ExchangeInfo {
+            symbols: vec![
+                listed("BTCUSDT", "TRADING", "USDT"),
+                listed("LUNAUSDT", "BREAK", "USDT"),
+                listed("ETHBTC", "TRADING", "BTC"),
+            ],
+        };
+        let quote_assets = vec!["USDT".to_string()];
+        let issues = check_symbols(["BTCUSDT", "LUNAUSDT", "ETHBTC", "BTCUSTD"], &info, &quote_assets);
+        assert_eq!(
+            issues,
+            vec![
+                SymbolIssue::NotTrading { symbol: "LUNAUSDT".to_string(), status: "BREAK".to_string() },
+                SymbolIssue::QuoteAsset { symbol: "ETHBTC".to_string(), quote_asset: "BTC".to_string() },
+                SymbolIssue::Unknown { symbol: "BTCUSTD".to_string() },
+            ]
+        );
+
+        // Any quote asset is accepted without expectations
+        assert!(check_symbols(["BTCUSDT", "ETHBTC"], &info, &[]).is_empty());
+    }
+}
@@ -0,0 +1,60 @@
+//! Check of the lcores of the components against the CPU topology.
+//!
+//! The resource manager creates the rings from its own lcore, so the EAL
+//! allocates them on the NUMA node of its `cpu`, unless `socket_mem` reserves
+//! no memory there. The latency-critical lcores should share neither a
+//! physical core nor be on another node than the rings.
+
+use ctl_core::{CpuTopology, EalConfig};
+use serde::Deserialize;
+
+/// What to do when the lcores are placed badly on the CPU topology.
+#[derive(Debug, Clone, Copy, Default, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum TopologyCheckPolicy {
+    /// Skip the check, e.g. on a development host.
+    Off,
+    /// Log the issues and carry on.
+    #[default]
+    Warn,
+    /// Refuse to start.
+    Fail,
+}
+
+/// Configuration of the topology check.
+#[derive(Debug, Clone, Default, Deserialize, PartialEq, Eq, Hash)]
+pub struct TopologyCheckConfig {
+    /// What to do when the check finds an issue.
+    #[serde(default)]
+    pub policy: TopologyCheckPolicy,
+}
+
+/// Returns the NUMA node the rings are allocated on: the node of the resource
+/// manager's lcore, or the first node `socket_mem` reserves memory on if none
+/// is reserved there.
+pub fn ring_node(lcore: u32, eal: &EalConfig, topology: &CpuTopology) -> u32 {
+    let node = topology.node(lcore);
+    match &eal.socket_mem {
+        Some(socket_mem) if socket_mem.get(node as usize).is_none_or(|&mb| mb == 0) => {
+            socket_mem.iter().position(|&mb| mb > 0).map_or(node, |node| node as u32)
+        }
+        _ => node,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ring_node() {
+        let mut topology = CpuTopology::default();
+        topology.add_node(0, &[0, 1]).add_node(1, &[2, 3]);
+
+        let mut eal = EalConfig::default();
+        assert_eq!(ring_node(2, &eal, &topology), 1);
+        eal.socket_mem = Some(vec![1024, 0]);
+        assert_eq!(ring_node(2, &eal, &topology), 0);
+        assert_eq!(ring_node(1, &eal, &topology), 0);
+    }
+}
@@ -0,0 +1,109 @@
+//! Configuration module for the signals.
+//!
+//! This module provides the YAML parser and validation for the signals
+//! configuration defined in `configs/signals/signals.yaml`.
+
+use std::fs;
+use std::path::Path;
+
+use ctl_book::BOOK_DEPTH;
+use serde::Deserialize;
+
+use crate::SignalsConfigError;
+
+/// Default number of book levels per side of the book imbalance.
+const DEFAULT_BOOK_LEVELS: usize = 5;
+
+fn default_book_levels() -> usize {
+    DEFAULT_BOOK_LEVELS
+}
+
+fn default_use_book() -> bool {
+    true
+}
+
+/// The configuration of the signals.
+#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+pub struct SignalsConfig {
+    /// The symbols the signals are published for, all the symbols of the top
+    /// feed if empty.
+    #[serde(default)]
+    pub symbols: Vec<String>,
+    /// Whether the book imbalance is read from the book snapshot regions of
+    /// the symbols whose book is built.
+    #[serde(default = "default_use_book")]
+    pub use_book: bool,
+    /// The number of book levels per side of the book imbalance.
+    #[serde(default = "default_book_levels")]
+    pub book_levels: usize,
+}
+
+impl Default for SignalsConfig {
+    fn default() -> Self {
+        Self { symbols: Vec::new(), use_book: default_use_book(), book_levels: default_book_levels() }
+    }
+}
+
+impl SignalsConfig {
+    /// Parses the signals configuration from a YAML file.
+    ///
+    /// # Errors
+    /// Returns an error if the file cannot be read, parsed, or fails validation.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, SignalsConfigError> {
+        let content = fs::read_to_string(path)?;
+        Self::from_str(&content)
+    }
+
+    /// Parses the signals configuration from a YAML string.
+    ///
+    /// # Errors
+    /// Returns an error if the YAML cannot be parsed or fails validation.
+    pub fn from_str(content: &str) -> Result<Self, SignalsConfigError> {
+        let config: Self = serde_yaml::from_str(content)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Validates the signals configuration.
+    fn validate(&self) -> Result<(), SignalsConfigError> {
+        if self.book_levels == 0 || self.book_levels > BOOK_DEPTH {
+            return Err(SignalsConfigError::ValidationError(format!(
+                "Book levels must be between 1 and {}, got {}",
+                BOOK_DEPTH, self.book_levels
+            )));
+        }
+        if self.symbols.iter().any(String::is_empty) {
+            return Err(SignalsConfigError::ValidationError("Symbol cannot be empty".to_string()));
+        }
+        Ok(())
+    }
+
+    /// Returns whether the signals of `symbol` are published.
+    pub fn includes(&self, symbol: &str) -> bool {
+        self.symbols.is_empty() || self.symbols.iter().any(|included| included == symbol)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_config() {
+        let config = SignalsConfig::from_str("symbols: [BTCUSDT]\nbook_levels: 10\n").unwrap();
+        assert_eq!(config.book_levels, 10);
+        assert!(config.use_book);
+        assert!(config.includes("BTCUSDT") && !config.includes("ETHUSDT"));
+
+        let config = SignalsConfig::from_str("use_book: false\n").unwrap();
+        assert_eq!(config, SignalsConfig { use_book: false, ..SignalsConfig::default() });
+        assert!(config.includes("ETHUSDT"));
+    }
+
+    #[test]
+    fn test_invalid_config() {
+        assert!(SignalsConfig::from_str("book_levels: 0\n").is_err());
+        assert!(SignalsConfig::from_str(&format!("book_levels: {}\n", BOOK_DEPTH + 1)).is_err());
+        assert!(SignalsConfig::from_str("symbols: ['']\n").is_err());
+    }
+}
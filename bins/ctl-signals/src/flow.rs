@@ -0,0 +1,96 @@
+use std::collections::VecDeque;
+
+use crate::TakerTrade;
+
+/// The taker buy and sell volumes over a rolling time window of trade times.
+#[derive(Debug, Clone)]
+pub struct FlowWindow {
+    /// The window length, in milliseconds.
+    window_ms: u64,
+    /// The trades in the window, oldest first.
+    trades: VecDeque<TakerTrade>,
+    /// Sum of the taker buy quantities over the window.
+    buy: f64,
+    /// Sum of the taker sell quantities over the window.
+    sell: f64,
+}
+
+impl FlowWindow {
+    /// Creates an empty window of `window_ms` milliseconds.
+    pub fn new(window_ms: u64) -> Self {
+        Self { window_ms, trades: VecDeque::new(), buy: 0.0, sell: 0.0 }
+    }
+
+    /// Adds a trade, evicting the trades that fell out of the window ending at it.
+    pub fn push(&mut self, trade: TakerTrade) {
+        self.trades.push_back(trade);
+        if trade.taker_buy {
+            self.buy += trade.qty;
+        } else {
+            self.sell += trade.qty;
+        }
+        self.evict(trade.trade_time_ms);
+    }
+
+    /// Evicts the trades that fell out of the window ending at `now_ms`.
+    pub fn evict(&mut self, now_ms: u64) {
+        while let Some(oldest) = self.trades.front() {
+            if oldest.trade_time_ms + self.window_ms > now_ms {
+                break;
+            }
+            if oldest.taker_buy {
+                self.buy -= oldest.qty;
+            } else {
+                self.sell -= oldest.qty;
+            }
+            self.trades.pop_front();
+        }
+        // Reset the running sums so floating point drift doesn't accumulate
+        if self.trades.is_empty() {
+            self.buy = 0.0;
+            self.sell = 0.0;
+        }
+    }
+
+    /// Returns the imbalance of the taker buy and sell volumes, from -1 (all
+    /// sold) to 1 (all bought), zero without volume.
+    pub fn imbalance(&self) -> f64 {
+        imbalance(self.buy, self.sell)
+    }
+}
+
+/// Returns the imbalance of two quantities, `(a - b) / (a + b)`, zero if both are zero.
+pub fn imbalance(a: f64, b: f64) -> f64 {
+    let total = a + b;
+    if total > 0.0 {
+        (a - b) / total
+    } else {
+        0.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trade(trade_time_ms: u64, qty: f64, taker_buy: bool) -> TakerTrade {
+        TakerTrade { trade_time_ms, qty, taker_buy }
+    }
+
+    #[test]
+    fn test_flow_window() {
+        let mut window = FlowWindow::new(1_000);
+        assert_eq!(window.imbalance(), 0.0);
+
+        window.push(trade(0, 3.0, true));
+        window.push(trade(500, 1.0, false));
+        assert_eq!(window.imbalance(), 0.5);
+
+        // The buy at 0 leaves the window ending at 1000
+        window.push(trade(1_000, 1.0, false));
+        assert_eq!(window.imbalance(), -1.0);
+        window.evict(3_000);
+        assert_eq!(window.imbalance(), 0.0);
+        assert_eq!(imbalance(0.0, 0.0), 0.0);
+    }
+}
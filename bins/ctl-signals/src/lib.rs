@@ -0,0 +1,21 @@
+//! Derived microstructure signals of the market data of Binance Spot.
+//!
+//! Consumes the Top and trade rings like any other consumer and, on each Top
+//! update and trade of a symbol, publishes its derived signals to its
+//! `SIGNAL_{symbol_id}_PS` ring: the microprice and the imbalance of the best
+//! bid and ask quantities of the Top, the imbalance of the first levels of the
+//! book if built, and the imbalance of the taker buy and sell volumes over the
+//! short windows of `SIGNAL_FLOW_WINDOWS_MS`. Strategies read the signals off
+//! the ring rather than each deriving them from the raw feeds.
+
+mod config;
+mod errors;
+mod flow;
+mod signal;
+mod trade;
+
+pub use config::SignalsConfig;
+pub use errors::SignalsConfigError;
+pub use flow::FlowWindow;
+pub use signal::{book_imbalance, microprice, SymbolSignals};
+pub use trade::TakerTrade;
@@ -0,0 +1,316 @@
+//! Derived Microstructure Signals Publisher for Binance Spot.
+//!
+//! Connects as a DPDK secondary process, attaches a consumer to the Top rings
+//! and the trade rings (or the aggregated trade rings without a trade feed),
+//! and on each Top update and trade of a symbol publishes its signals to its
+//! `SIGNAL_{symbol_id}_PS` ring, created by ctl-resource-manager: microprice,
+//! Top and book imbalances, and the trade-flow imbalance over short windows.
+//! The book imbalance is read from the book snapshot region of the symbol,
+//! only for the symbols whose book is built.
+//!
+//! The symbols of an aggregated set of the top feed share the signal ring
+//! named after the first symbol of the set, their signals told apart by the
+//! symbol IDs of the message headers.
+//!
+//! The rings are polled under the policy of `configs/polling.yaml`. Once
+//! overtaken by a producer, the signals recover per `configs/overtaken.yaml`;
+//! the flow windows miss the overwritten trades until they leave the windows,
+//! and the signals are derived from the next Top update rather than a
+//! conflated state, so a snapshot requested by `skip-and-request-snapshot` is
+//! a skip to the head.
+//!
+//! The exit code tells the class of a failure (see `FatalKind`).
+
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::time::{Duration, Instant};
+
+use clap::Parser;
+use ctl_book::{book_region_name, BookSnapshotRegion};
+use ctl_core::{
+    Capability, Fatal, FatalError, FatalKind, LcoresConfig, OvertakenConfig, OvertakenPolicy, Poller, PollingConfig,
+    PollingPolicy, Preflight, RingKind, RingName,
+};
+#[cfg(feature = "message-checksums")]
+use ctl_feed::Checksummed;
+use ctl_feed::{
+    dpdk_consume, slot_payload, MetricsRegion, RawMessage, RingMetrics, RingRead, RingReader, SignalMessage,
+    TopSnapshot, METRICS_REGION_NAME, SIGNAL_FLOW_WINDOWS_MS,
+};
+use ctl_md_handler::{HwResourcesConfig, SymbolInfoConfig};
+use ctl_shm::ShmRegion;
+use ctl_signals::{SignalsConfig, SymbolSignals, TakerTrade};
+use ctl_time::now_ms;
+use dpdk::{DpdkEnvBuilder, DpdkProcessType, DpdkPubSubRing};
+use hashbrown::HashMap;
+use tracing::{info, warn};
+
+// Logging configuration, its levels reloaded on change
+const LOG_CONFIG_PATH: &str = "configs/logging.yaml";
+
+// The market data configuration, holding the feeds and the EAL options of the secondary processes
+const MD_CONFIG_PATH: &str = "configs/market-data/hw-resources.yaml";
+
+// The symbol IDs of the rings
+const SYMBOL_INFO_PATH: &str = "configs/market-data/symbolinfo.yaml";
+
+// The signals configuration
+const SIGNALS_CONFIG_PATH: &str = "configs/signals/signals.yaml";
+
+// The name of the consumer cursors of the signals in the rings
+const CONSUMER_NAME: &str = "signals";
+
+// The lcore of the signals, unless configured, shared with the mostly idle tools
+const LCORES_CONFIG_PATH: &str = "configs/lcores.yaml";
+const LCORES_COMPONENT: &str = "signals";
+const DEFAULT_LCORE: u32 = 15;
+
+// Polling policy between passes without a message, unless configured for this component
+const POLLING_CONFIG_PATH: &str = "configs/polling.yaml";
+const POLLING_COMPONENT: &str = "signals";
+const DEFAULT_POLLING: PollingPolicy = PollingPolicy::Sleep { sleep_us: 100 };
+
+// Recovery once overtaken by a producer, unless configured for this component
+const OVERTAKEN_CONFIG_PATH: &str = "configs/overtaken.yaml";
+const OVERTAKEN_COMPONENT: &str = "signals";
+const DEFAULT_OVERTAKEN: OvertakenPolicy = OvertakenPolicy::SkipToHead;
+
+// Interval between the logs of the counters of the signals
+const STATS_LOG_INTERVAL: Duration = Duration::from_secs(60);
+
+/// A signal ring, published to.
+struct SignalRing<'a> {
+    /// The ring name.
+    name: String,
+    /// The ring.
+    ring: DpdkPubSubRing<SignalMessage>,
+    /// Metrics of the ring.
+    metrics: &'a RingMetrics,
+}
+
+/// The signals of a symbol and where they are published.
+struct SymbolState {
+    /// The signals.
+    signals: SymbolSignals,
+    /// The index of the signal ring of the symbol.
+    ring: usize,
+    /// The book snapshot region of the symbol, if its book is built.
+    book: Option<ShmRegion<BookSnapshotRegion>>,
+}
+
+/// Derived microstructure signals of the market data rings.
+#[derive(Debug, Parser)]
+#[command(version)]
+struct Args {
+    /// Logging configuration.
+    #[arg(long, env = "CTL_LOG_CONFIG", default_value = LOG_CONFIG_PATH)]
+    log_config: PathBuf,
+    /// Market data configuration.
+    #[arg(long, env = "CTL_MD_CONFIG", default_value = MD_CONFIG_PATH)]
+    md_config: PathBuf,
+    /// Symbol info configuration.
+    #[arg(long, env = "CTL_SYMBOL_INFO", default_value = SYMBOL_INFO_PATH)]
+    symbol_info: PathBuf,
+    /// Signals configuration.
+    #[arg(long, env = "CTL_SIGNALS_CONFIG", default_value = SIGNALS_CONFIG_PATH)]
+    signals_config: PathBuf,
+    /// Lcores configuration.
+    #[arg(long, env = "CTL_LCORES_CONFIG", default_value = LCORES_CONFIG_PATH)]
+    lcores_config: PathBuf,
+    /// Polling configuration.
+    #[arg(long, env = "CTL_POLLING_CONFIG", default_value = POLLING_CONFIG_PATH)]
+    polling_config: PathBuf,
+    /// Overtaken consumer recovery configuration.
+    #[arg(long, env = "CTL_OVERTAKEN_CONFIG", default_value = OVERTAKEN_CONFIG_PATH)]
+    overtaken_config: PathBuf,
+}
+
+/// Publishes the signals of a symbol to its ring, stamped with their sequence number.
+fn publish_signal(ring: &SignalRing<'_>, mut message: SignalMessage) -> Result<(), FatalError> {
+    message.header.stamp(ring.metrics.record_publish(), now_ms());
+    #[cfg(feature = "message-checksums")]
+    message.seal();
+    ring.ring.publish(&message).fatal(FatalKind::Internal)
+}
+
+fn main() -> ExitCode {
+    ctl_core::exit_code(run())
+}
+
+fn run() -> Result<(), FatalError> {
+    let args = Args::parse();
+    let _log = ctl_log::init_from_file(&args.log_config).fatal(FatalKind::Config)?;
+
+    info!("=== Binance Spot Signals ===");
+
+    let md_config = HwResourcesConfig::from_file(&args.md_config).fatal(FatalKind::Config)?;
+    let symbol_info = SymbolInfoConfig::from_file(&args.symbol_info).fatal(FatalKind::Config)?;
+    let config = SignalsConfig::from_file(&args.signals_config).fatal(FatalKind::Config)?;
+    md_config.eal.apply_region_prefix();
+    let polling = PollingConfig::from_file(&args.polling_config)?.policy(POLLING_COMPONENT, DEFAULT_POLLING);
+    let overtaken = OvertakenConfig::from_file(&args.overtaken_config)?.policy(OVERTAKEN_COMPONENT, DEFAULT_OVERTAKEN);
+    let lcore = LcoresConfig::from_file(&args.lcores_config)?.lcore(LCORES_COMPONENT, DEFAULT_LCORE) as usize;
+    let top_feed =
+        md_config.find_feed("top").ok_or("No top feed configured in hw-resources.yaml").fatal(FatalKind::Config)?;
+    for symbol in &config.symbols {
+        if !top_feed.feed_sets().iter().any(|set| set.ring_symbol(symbol).is_some()) {
+            return Err(FatalError::new(FatalKind::Config, format!("Symbol '{}' has no top feed", symbol)));
+        }
+    }
+
+    // Verify the host before the EAL initialization, which reports its failures cryptically
+    Preflight::new()
+        .require_hugepages_mounted()
+        .require_capabilities(&[Capability::IpcLock])
+        .require_primary()
+        .with_file_prefix(md_config.eal.file_prefix())
+        .run()?;
+
+    let dpdk_env = DpdkEnvBuilder::default()
+        .process_type(DpdkProcessType::Secondary)
+        .lcore_ids(vec![lcore])
+        .main_lcore_id(lcore)
+        .eal_args(md_config.eal.secondary_args())
+        .build()
+        .fatal(FatalKind::DpdkInit)?;
+
+    info!("Flow windows: {:?} ms", SIGNAL_FLOW_WINDOWS_MS);
+    info!("Polling: {:?}", polling);
+    info!("Overtaken: {:?}", overtaken);
+    info!("Signals: {:?}", config);
+
+    let metrics = ShmRegion::<MetricsRegion>::open(METRICS_REGION_NAME)?;
+    let lookup_id = |symbol: &str| {
+        symbol_info
+            .symbol_id(symbol)
+            .ok_or_else(|| format!("Symbol '{}' not found in symbolinfo.yaml", symbol))
+            .fatal(FatalKind::Config)
+    };
+    let find_metrics = |ring_name: &str| {
+        metrics
+            .find_ring(ring_name)
+            .ok_or_else(|| format!("Ring '{}' not found in metrics region", ring_name))
+            .fatal(FatalKind::SharedState)
+    };
+
+    // Look up the signal ring of each ring symbol of the top feed carrying a
+    // symbol of the signals, a single ring for the symbols of an aggregated set
+    let mut signal_rings = Vec::new();
+    let mut symbols: HashMap<String, SymbolState> = HashMap::new();
+    for feed_set in top_feed.feed_sets() {
+        for symbol in feed_set.symbols.iter() {
+            if !config.includes(symbol) {
+                continue;
+            }
+            let Some(ring_symbol) = feed_set.ring_symbol(symbol) else {
+                continue;
+            };
+            let name = RingName::pubsub(RingKind::Signal, lookup_id(ring_symbol)?).to_string();
+            let ring = match signal_rings.iter().position(|ring: &SignalRing<'_>| ring.name == name) {
+                Some(ring) => ring,
+                None => {
+                    metrics.check_layout::<SignalMessage>(&name).fatal(FatalKind::SharedState)?;
+                    let ring_metrics = &metrics.rings[find_metrics(&name)?];
+                    let ring = dpdk_env.pubsub_lookup::<SignalMessage>(&name).fatal(FatalKind::SharedState)?;
+                    signal_rings.push(SignalRing { name: name.clone(), ring, metrics: ring_metrics });
+                    signal_rings.len() - 1
+                }
+            };
+
+            // Only the symbols whose book is built have a book snapshot region
+            let symbol_id = lookup_id(symbol)?;
+            let book = if config.use_book {
+                ShmRegion::<BookSnapshotRegion>::open(&book_region_name(symbol_id)).ok()
+            } else {
+                None
+            };
+            info!("[{}] -> {} (book imbalance: {})", symbol, name, book.is_some());
+            let signals = SymbolSignals::new(symbol_id, config.book_levels);
+            symbols.insert(symbol.clone(), SymbolState { signals, ring, book });
+        }
+    }
+
+    // The trades come from the trade rings, or the aggregated trade rings without a trade feed
+    let (trade_kind, trade_feed) = match md_config.find_feed("trade") {
+        Some(_) => (RingKind::Trade, "trade"),
+        None => (RingKind::AggTrade, "aggtrade"),
+    };
+
+    // Attach to the Top and trade rings carrying the symbols of the signals,
+    // tracking the positions in their metrics like any consumer
+    let mut rings = Vec::new();
+    for (kind, feed_kind) in [(RingKind::Top, "top"), (trade_kind, trade_feed)] {
+        let Some(feed) = md_config.find_feed(feed_kind) else {
+            continue;
+        };
+        for feed_set in feed.feed_sets() {
+            for ring_symbol in feed_set.ring_symbols() {
+                // The rings of an aggregated set carry the messages of all its symbols
+                let carried = symbols.keys().any(|symbol| feed_set.ring_symbol(symbol) == Some(ring_symbol.as_str()));
+                if !carried {
+                    continue;
+                }
+                let name = RingName::pubsub(kind, lookup_id(ring_symbol)?).to_string();
+                let reader = RingReader::attach(&metrics, &name, CONSUMER_NAME, feed_set.slot_size)
+                    .fatal(FatalKind::SharedState)?
+                    .with_overtaken(overtaken);
+                let ring = dpdk_env.pubsub_lookup::<RawMessage>(&name).fatal(FatalKind::SharedState)?;
+                let consumer = ring.attach_consumer().fatal(FatalKind::SharedState)?;
+                info!("[{}] {}", ring_symbol, name);
+                rings.push((kind, reader, consumer));
+            }
+        }
+    }
+
+    info!("Publishing the signals of {} symbols to {} rings", symbols.len(), signal_rings.len());
+
+    let mut published: u64 = 0;
+    let mut last_log = Instant::now();
+    let mut poller = Poller::new(polling);
+    loop {
+        let mut did_work = false;
+        for (kind, reader, consumer) in rings.iter_mut() {
+            let read = reader.read(dpdk_consume!(consumer)).fatal(FatalKind::Overtaken)?;
+            did_work |= read.did_work();
+            match read {
+                RingRead::Payload(message, payload) => {
+                    let recv_time_ms = message.header.ts_ms;
+                    let payload = slot_payload(payload);
+                    let (ring, signal) = if *kind == RingKind::Top {
+                        let Some((symbol, top)) = TopSnapshot::from_book_ticker(payload) else {
+                            continue;
+                        };
+                        let Some(state) = symbols.get_mut(symbol) else {
+                            continue;
+                        };
+                        let book = state.book.as_ref().and_then(|book| book.read());
+                        (state.ring, state.signals.on_top(&top, book.as_ref(), recv_time_ms))
+                    } else {
+                        let Some((symbol, trade)) = TakerTrade::from_json(payload) else {
+                            continue;
+                        };
+                        let Some(state) = symbols.get_mut(symbol) else {
+                            continue;
+                        };
+                        (state.ring, state.signals.on_trade(trade))
+                    };
+                    publish_signal(&signal_rings[ring], signal)?;
+                    published += 1;
+                }
+                RingRead::Overtaken { .. } => {
+                    // The flow windows miss the overwritten trades until they leave the windows
+                    warn!("{} consumer overtaken by producer, some messages missed", reader.name());
+                }
+                RingRead::Consumed | RingRead::Idle => {}
+            }
+        }
+
+        if !did_work && last_log.elapsed() >= STATS_LOG_INTERVAL {
+            last_log = Instant::now();
+            info!("Signals published: {}", published);
+        }
+
+        // Wait before the next pass according to the configured policy
+        poller.wait(did_work);
+    }
+}
@@ -0,0 +1,150 @@
+use ctl_book::{BookSnapshot, Level};
+use ctl_feed::{EventType, MessageHeader, SignalMessage, TopSnapshot, SIGNAL_FLOW_WINDOWS_MS};
+
+use crate::flow::imbalance;
+use crate::{FlowWindow, TakerTrade};
+
+/// The derived signals of a symbol, updated by its Tops and trades.
+#[derive(Debug, Clone)]
+pub struct SymbolSignals {
+    /// The symbol ID.
+    symbol_id: u32,
+    /// The number of book levels per side of the book imbalance.
+    book_levels: usize,
+    /// The last Top, if any.
+    top: Option<TopSnapshot>,
+    /// The book imbalance and the levels per side it was taken over, zero
+    /// levels without a live book.
+    book: (f64, u32),
+    /// The trade time of the last trade, in milliseconds.
+    trade_time_ms: u64,
+    /// The trade flow windows, one per window of `SIGNAL_FLOW_WINDOWS_MS`.
+    flows: [FlowWindow; SIGNAL_FLOW_WINDOWS_MS.len()],
+}
+
+impl SymbolSignals {
+    /// Creates the signals of a symbol, its book imbalance taken over `book_levels` levels per side.
+    pub fn new(symbol_id: u32, book_levels: usize) -> Self {
+        Self {
+            symbol_id,
+            book_levels,
+            top: None,
+            book: (0.0, 0),
+            trade_time_ms: 0,
+            flows: SIGNAL_FLOW_WINDOWS_MS.map(FlowWindow::new),
+        }
+    }
+
+    /// Updates the signals with a Top received at `now_ms` and the book of the
+    /// symbol, if built, returning the signals to publish.
+    ///
+    /// The flow windows are evicted up to `now_ms`, so the flow of a symbol
+    /// without trades fades out rather than lingering at its last value.
+    pub fn on_top(&mut self, top: &TopSnapshot, book: Option<&BookSnapshot>, now_ms: u64) -> SignalMessage {
+        self.top = Some(*top);
+        // A book being resynced no longer tracks the exchange, left out until live again
+        self.book = match book.filter(|book| !book.is_stale()) {
+            Some(book) => (book_imbalance(book.bids(), book.asks(), self.book_levels), self.book_levels as u32),
+            None => (0.0, 0),
+        };
+        for flow in self.flows.iter_mut() {
+            flow.evict(now_ms);
+        }
+        self.message()
+    }
+
+    /// Updates the signals with a trade, returning the signals to publish.
+    pub fn on_trade(&mut self, trade: TakerTrade) -> SignalMessage {
+        self.trade_time_ms = self.trade_time_ms.max(trade.trade_time_ms);
+        for flow in self.flows.iter_mut() {
+            flow.push(trade);
+        }
+        self.message()
+    }
+
+    /// Returns the current signals.
+    pub fn message(&self) -> SignalMessage {
+        let top = self.top.unwrap_or_default();
+        let mid_price = if self.top.is_some() { (top.bid_price + top.ask_price) / 2.0 } else { 0.0 };
+        SignalMessage {
+            header: MessageHeader::new(EventType::Signal, self.symbol_id),
+            symbol_id: self.symbol_id,
+            book_levels: self.book.1,
+            update_id: top.update_id,
+            trade_time_ms: self.trade_time_ms,
+            bid_price: top.bid_price,
+            ask_price: top.ask_price,
+            mid_price,
+            microprice: microprice(&top).unwrap_or(mid_price),
+            top_imbalance: imbalance(top.bid_qty, top.ask_qty),
+            book_imbalance: self.book.0,
+            flow_imbalance: self.flows.each_ref().map(FlowWindow::imbalance),
+        }
+    }
+}
+
+/// Returns the mid price of a Top weighted by the opposite quantities, leaning
+/// toward the side with the thinner queue, `None` without quantity.
+pub fn microprice(top: &TopSnapshot) -> Option<f64> {
+    let total = top.bid_qty + top.ask_qty;
+    if total <= 0.0 {
+        return None;
+    }
+    Some((top.bid_price * top.ask_qty + top.ask_price * top.bid_qty) / total)
+}
+
+/// Returns the imbalance of the quantities of the first `levels` levels of each side.
+pub fn book_imbalance(bids: &[Level], asks: &[Level], levels: usize) -> f64 {
+    let depth = |side: &[Level]| side.iter().take(levels).map(|level| level.qty).sum::<f64>();
+    imbalance(depth(bids), depth(asks))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn top(bid_qty: f64, ask_qty: f64) -> TopSnapshot {
+        TopSnapshot { update_id: 7, bid_price: 100.0, bid_qty, ask_price: 101.0, ask_qty }
+    }
+
+    fn level(price: f64, qty: f64) -> Level {
+        Level { price, qty }
+    }
+
+    #[test]
+    fn test_top_signals() {
+        // The thin ask pulls the microprice toward it
+        assert_eq!(microprice(&top(3.0, 1.0)), Some(100.75));
+        assert_eq!(microprice(&top(0.0, 0.0)), None);
+
+        let mut signals = SymbolSignals::new(1, 5);
+        let message = signals.on_top(&top(3.0, 1.0), None, 1_000);
+        assert_eq!(message.header.symbol_id(), Some(1));
+        assert_eq!((message.update_id, message.mid_price, message.microprice), (7, 100.5, 100.75));
+        assert_eq!((message.top_imbalance, message.book_levels, message.book_imbalance), (0.5, 0, 0.0));
+    }
+
+    #[test]
+    fn test_book_imbalance() {
+        let bids = [level(100.0, 1.0), level(99.0, 2.0), level(98.0, 10.0)];
+        let asks = [level(101.0, 1.0), level(102.0, 1.0)];
+        assert_eq!(book_imbalance(&bids, &asks, 2), 1.0 / 5.0);
+        assert_eq!(book_imbalance(&bids, &[], 1), 1.0);
+        assert_eq!(book_imbalance(&[], &[], 5), 0.0);
+    }
+
+    #[test]
+    fn test_flow_signals() {
+        let mut signals = SymbolSignals::new(1, 5);
+        signals.on_trade(TakerTrade { trade_time_ms: 10_000, qty: 2.0, taker_buy: true });
+        let message = signals.on_trade(TakerTrade { trade_time_ms: 12_000, qty: 1.0, taker_buy: false });
+        assert_eq!(message.trade_time_ms, 12_000);
+        assert_eq!(message.flow_imbalance, [-1.0, 1.0 / 3.0, 1.0 / 3.0]);
+        // No Top yet
+        assert_eq!((message.mid_price, message.microprice), (0.0, 0.0));
+
+        // A Top past the shorter windows evicts their trades
+        let message = signals.on_top(&top(1.0, 1.0), None, 16_000);
+        assert_eq!(message.flow_imbalance, [0.0, -1.0, 1.0 / 3.0]);
+    }
+}
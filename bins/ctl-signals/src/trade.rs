@@ -0,0 +1,67 @@
+use serde::Deserialize;
+
+/// The fields of a trade or aggregate trade stream payload the trade flow reads.
+/// https://github.com/binance/binance-spot-api-docs/blob/master/web-socket-streams.md#trade-streams
+#[derive(Deserialize)]
+struct TradePayload<'a> {
+    #[serde(rename = "s")]
+    symbol: &'a str,
+    #[serde(rename = "q")]
+    qty: &'a str,
+    #[serde(rename = "T")]
+    trade_time_ms: u64,
+    #[serde(rename = "m")]
+    buyer_maker: bool,
+}
+
+/// A trade, or an aggregated trade, signed by the side of its taker.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TakerTrade {
+    /// The trade time, in milliseconds.
+    pub trade_time_ms: u64,
+    /// The trade quantity.
+    pub qty: f64,
+    /// Whether the taker bought, lifting the ask, rather than sold into the bid.
+    pub taker_buy: bool,
+}
+
+impl TakerTrade {
+    /// Parses a trade or aggregate trade payload, returning its symbol and trade.
+    /// Returns `None` if the payload is not a trade.
+    pub fn from_json(data: &[u8]) -> Option<(&str, Self)> {
+        let payload: TradePayload<'_> = serde_json::from_slice(data).ok()?;
+        let trade = Self {
+            trade_time_ms: payload.trade_time_ms,
+            qty: payload.qty.parse().ok()?,
+            taker_buy: !payload.buyer_maker,
+        };
+        Some((payload.symbol, trade))
+    }
+
+    /// Returns the quantity, positive if the taker bought and negative if it sold.
+    pub fn signed_qty(&self) -> f64 {
+        if self.taker_buy {
+            self.qty
+        } else {
+            -self.qty
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_trades() {
+        let json = br#"{"e":"trade","E":123456789,"s":"BNBBTC","t":12345,"p":"0.001","q":"100","T":123456785,"m":true,"M":true}"#;
+        let expected = TakerTrade { trade_time_ms: 123456785, qty: 100.0, taker_buy: false };
+        assert_eq!(TakerTrade::from_json(json), Some(("BNBBTC", expected)));
+        assert_eq!(expected.signed_qty(), -100.0);
+
+        let json = br#"{"e":"aggTrade","E":123456789,"s":"BNBBTC","a":12345,"p":"0.001","q":"100","f":100,"l":105,"T":123456785,"m":false,"M":true}"#;
+        let (_, trade) = TakerTrade::from_json(json).unwrap();
+        assert_eq!((trade.taker_buy, trade.signed_qty()), (true, 100.0));
+        assert!(TakerTrade::from_json(br#"{"u":400900217,"s":"BNBUSDT"}"#).is_none());
+    }
+}
@@ -0,0 +1,152 @@
+//! The batching of the ticks for the writer.
+//!
+//! The ticks are batched on the thread consuming the rings and handed to the
+//! writer over a bounded queue. While the queue is full the batch keeps
+//! growing, so a slow database only delays the inserts, until the buffered
+//! rows reach their limit and the newest ticks are dropped. The rings are
+//! never held back.
+
+use std::mem;
+use std::sync::mpsc::{SyncSender, TrySendError};
+use std::time::{Duration, Instant};
+
+use crate::{decode_into, Batch, TickKind, TickStoreConfig, WriterStopped};
+
+/// The batch of ticks being filled, handed to the writer once full or due.
+#[derive(Debug)]
+pub struct Batcher {
+    batch: Batch,
+    sender: SyncSender<Batch>,
+    /// The number of rows of a full batch.
+    batch_rows: usize,
+    /// The number of rows buffered past which the ticks are dropped.
+    max_buffered_rows: usize,
+    /// The longest time between the hand-offs of the batches.
+    flush_interval: Duration,
+    last_flush: Instant,
+    /// Number of ticks dropped for a backed up writer.
+    dropped: u64,
+    /// Number of payloads that were not ticks of their ring.
+    undecodable: u64,
+}
+
+impl Batcher {
+    /// Creates the batcher of the configuration, handing its batches to `sender`.
+    pub fn new(config: &TickStoreConfig, sender: SyncSender<Batch>) -> Self {
+        Self {
+            batch: Batch::default(),
+            sender,
+            batch_rows: config.batch_rows,
+            max_buffered_rows: config.max_buffered_rows,
+            flush_interval: config.flush_interval(),
+            last_flush: Instant::now(),
+            dropped: 0,
+            undecodable: 0,
+        }
+    }
+
+    /// Adds the tick of a payload of the rings of a kind, received at
+    /// `recv_time_ms`, handing the batch to the writer once full.
+    ///
+    /// # Errors
+    /// Returns an error if the writer stopped.
+    pub fn push(&mut self, kind: TickKind, payload: &[u8], recv_time_ms: u64) -> Result<(), WriterStopped> {
+        if self.batch.len() >= self.max_buffered_rows {
+            self.dropped += 1;
+            return Ok(());
+        }
+        if !decode_into(kind, payload, recv_time_ms, &mut self.batch) {
+            self.undecodable += 1;
+            return Ok(());
+        }
+        if self.batch.len() >= self.batch_rows {
+            self.flush(Instant::now())?;
+        }
+        Ok(())
+    }
+
+    /// Hands the batch to the writer if it waited for the flush interval, so
+    /// the ticks of the quiet symbols are stored too.
+    ///
+    /// # Errors
+    /// Returns an error if the writer stopped.
+    pub fn flush_due(&mut self, now: Instant) -> Result<(), WriterStopped> {
+        if self.batch.is_empty() || now.duration_since(self.last_flush) < self.flush_interval {
+            return Ok(());
+        }
+        self.flush(now)
+    }
+
+    /// Hands the batch to the writer, keeping it to grow if the queue is full.
+    fn flush(&mut self, now: Instant) -> Result<(), WriterStopped> {
+        match self.sender.try_send(mem::take(&mut self.batch)) {
+            Ok(()) => {
+                self.last_flush = now;
+                Ok(())
+            }
+            Err(TrySendError::Full(batch)) => {
+                self.batch = batch;
+                Ok(())
+            }
+            Err(TrySendError::Disconnected(_)) => Err(WriterStopped),
+        }
+    }
+
+    /// Returns the number of ticks dropped for a backed up writer.
+    pub fn dropped(&self) -> u64 {
+        self.dropped
+    }
+
+    /// Returns the number of payloads that were not ticks of their ring.
+    pub fn undecodable(&self) -> u64 {
+        self.undecodable
+    }
+
+    /// Returns the number of rows waiting for the writer's queue.
+    pub fn buffered(&self) -> usize {
+        self.batch.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc;
+
+    use super::*;
+
+    const TRADE: &[u8] = br#"{"s":"BNBBTC","t":12345,"p":"0.001","q":"100","T":123456785,"m":true}"#;
+
+    fn config(batch_rows: usize, max_buffered_rows: usize) -> TickStoreConfig {
+        let yaml = format!(
+            "backend:\n  kind: timescaledb\n  connection: host=localhost\nbatch_rows: {}\nmax_buffered_rows: {}\n",
+            batch_rows, max_buffered_rows
+        );
+        TickStoreConfig::from_str(&yaml).unwrap()
+    }
+
+    #[test]
+    fn test_batches_grow_then_drop_while_writer_backed_up() {
+        let (sender, receiver) = mpsc::sync_channel(1);
+        let mut batcher = Batcher::new(&config(2, 5), sender);
+        for _ in 0..2 {
+            batcher.push(TickKind::Trade, TRADE, 1).unwrap();
+        }
+        assert_eq!((batcher.buffered(), receiver.try_recv().unwrap().len()), (0, 2));
+        batcher.push(TickKind::Top, TRADE, 1).unwrap();
+        assert_eq!((batcher.buffered(), batcher.undecodable()), (0, 1));
+
+        // The queue holds one batch, the next one grows until the buffered rows limit
+        for _ in 0..9 {
+            batcher.push(TickKind::Trade, TRADE, 1).unwrap();
+        }
+        assert_eq!((batcher.buffered(), batcher.dropped()), (5, 2));
+
+        assert_eq!(receiver.try_recv().unwrap().len(), 2);
+        batcher.flush_due(Instant::now() + Duration::from_secs(2)).unwrap();
+        assert_eq!((batcher.buffered(), receiver.try_recv().unwrap().len()), (0, 5));
+
+        drop(receiver);
+        batcher.push(TickKind::Trade, TRADE, 1).unwrap();
+        assert!(batcher.flush_due(Instant::now() + Duration::from_secs(4)).is_err());
+    }
+}
@@ -0,0 +1,163 @@
+//! The ClickHouse tick store, inserting over the HTTP interface.
+//!
+//! Each kind of a batch is inserted as `JSONEachRow`, in one insert per table.
+//! ClickHouse has no transactions across tables, so a batch retried after a
+//! failed insert may duplicate the rows of the tables inserted before it.
+//! https://clickhouse.com/docs/en/interfaces/http
+
+use reqwest::blocking::Client;
+use serde::Serialize;
+
+use crate::{Batch, StoreError, Tables, TickStore};
+
+/// The client of a ClickHouse server.
+#[derive(Debug)]
+pub struct ClickHouseStore {
+    http: Client,
+    /// The URL of the HTTP interface.
+    url: String,
+    /// The database of the tables.
+    database: String,
+    /// The user, the default user of the server if unset.
+    user: Option<String>,
+    /// The password of the user.
+    password: Option<String>,
+    /// The tables of the tick kinds.
+    tables: Tables,
+}
+
+impl ClickHouseStore {
+    /// Creates the client of the server at `url`, inserting into the tables of `database`.
+    ///
+    /// # Errors
+    /// Returns an error if the HTTP client cannot be built.
+    pub fn new(
+        url: &str,
+        database: &str,
+        user: Option<String>,
+        password: Option<String>,
+        tables: Tables,
+    ) -> Result<Self, StoreError> {
+        Ok(Self {
+            http: Client::builder().build()?,
+            url: url.to_string(),
+            database: database.to_string(),
+            user,
+            password,
+            tables,
+        })
+    }
+
+    /// Runs a query, with its data as the body of the request.
+    fn query(&self, query: &str, data: String) -> Result<(), StoreError> {
+        let mut request = self.http.post(&self.url).query(&[("query", query)]).body(data);
+        if let Some(user) = &self.user {
+            request = request.header("X-ClickHouse-User", user);
+        }
+        if let Some(password) = &self.password {
+            request = request.header("X-ClickHouse-Key", password);
+        }
+        let response = request.send()?;
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().unwrap_or_default();
+            return Err(StoreError::ClickHouseRejected { status: status.as_u16(), body });
+        }
+        Ok(())
+    }
+
+    /// Inserts the rows of a table, if any.
+    fn insert_rows<T: Serialize>(&self, table: &str, rows: &[T]) -> Result<(), StoreError> {
+        if rows.is_empty() {
+            return Ok(());
+        }
+        let data = json_each_row(rows);
+        self.query(&format!("INSERT INTO {}.{} FORMAT JSONEachRow", self.database, table), data)
+    }
+}
+
+impl TickStore for ClickHouseStore {
+    fn create_tables(&mut self) -> Result<(), StoreError> {
+        for ddl in create_table_statements(&self.database, &self.tables) {
+            self.query(&ddl, String::new())?;
+        }
+        Ok(())
+    }
+
+    fn insert(&mut self, batch: &Batch) -> Result<(), StoreError> {
+        self.insert_rows(&self.tables.top, &batch.tops)?;
+        self.insert_rows(&self.tables.trade, &batch.trades)?;
+        self.insert_rows(&self.tables.agg_trade, &batch.agg_trades)
+    }
+}
+
+/// Returns the rows as `JSONEachRow` data, one JSON object per line.
+fn json_each_row<T: Serialize>(rows: &[T]) -> String {
+    let mut data = String::new();
+    for row in rows {
+        data.push_str(&serde_json::to_string(row).expect("serializable tick"));
+        data.push('\n');
+    }
+    data
+}
+
+/// Returns the statements creating the tables, partitioned by day and ordered
+/// by symbol and receive time, with the receive time materialized as a
+/// `DateTime64` for the queries.
+fn create_table_statements(database: &str, tables: &Tables) -> [String; 3] {
+    let table = |name: &str, columns: &str| {
+        format!(
+            "CREATE TABLE IF NOT EXISTS {}.{} (symbol LowCardinality(String), {}, recv_time_ms UInt64, \
+             time DateTime64(3) MATERIALIZED fromUnixTimestamp64Milli(toInt64(recv_time_ms))) \
+             ENGINE = MergeTree PARTITION BY toDate(time) ORDER BY (symbol, recv_time_ms)",
+            database, name, columns
+        )
+    };
+    [
+        table(
+            &tables.top,
+            "update_id UInt64, bid_price Float64, bid_qty Float64, ask_price Float64, ask_qty Float64",
+        ),
+        table(
+            &tables.trade,
+            "trade_id UInt64, price Float64, qty Float64, buyer_maker Bool, trade_time_ms UInt64",
+        ),
+        table(
+            &tables.agg_trade,
+            "agg_trade_id UInt64, first_trade_id UInt64, last_trade_id UInt64, price Float64, qty Float64, \
+             buyer_maker Bool, trade_time_ms UInt64",
+        ),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::TopTick;
+
+    #[test]
+    fn test_json_each_row() {
+        let tick = TopTick {
+            symbol: "BTCUSDT".to_string(),
+            update_id: 7,
+            bid_price: 100.5,
+            bid_qty: 1.0,
+            ask_price: 101.0,
+            ask_qty: 2.0,
+            recv_time_ms: 1_000,
+        };
+        let data = json_each_row(&[tick.clone(), tick]);
+        let line = r#"{"symbol":"BTCUSDT","update_id":7,"bid_price":100.5,"bid_qty":1.0,"ask_price":101.0,"#;
+        assert_eq!(data, format!("{0}\"ask_qty\":2.0,\"recv_time_ms\":1000}}\n", line).repeat(2));
+
+        let tables = Tables {
+            top: "t_top".to_string(),
+            trade: "t_trade".to_string(),
+            agg_trade: "t_agg".to_string(),
+        };
+        let statements = create_table_statements("md", &tables);
+        assert!(statements[0].starts_with("CREATE TABLE IF NOT EXISTS md.t_top (symbol"));
+        assert!(statements[2].contains("last_trade_id UInt64"));
+    }
+}
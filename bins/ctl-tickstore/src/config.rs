@@ -0,0 +1,217 @@
+//! Configuration module for the tick store.
+//!
+//! This module provides the YAML parser and validation for the tick store
+//! configuration defined in `configs/tickstore/tickstore.yaml`.
+
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+use ctl_retry::RetryPolicy;
+use serde::Deserialize;
+
+use crate::{TickKind, TickStoreConfigError};
+
+/// Default prefix of the tables.
+const DEFAULT_TABLE_PREFIX: &str = "ticks";
+
+/// Default number of rows of a batch.
+const DEFAULT_BATCH_ROWS: usize = 10_000;
+
+/// Default longest time a tick waits for its batch to be inserted, in milliseconds.
+const DEFAULT_FLUSH_INTERVAL_MS: u64 = 1_000;
+
+/// Default number of batches queued for the writer.
+const DEFAULT_MAX_PENDING_BATCHES: usize = 8;
+
+/// Default number of rows buffered while the writer is backed up.
+const DEFAULT_MAX_BUFFERED_ROWS: usize = 1_000_000;
+
+/// Default ClickHouse database.
+const DEFAULT_DATABASE: &str = "default";
+
+fn default_table_prefix() -> String {
+    DEFAULT_TABLE_PREFIX.to_string()
+}
+
+fn default_batch_rows() -> usize {
+    DEFAULT_BATCH_ROWS
+}
+
+fn default_flush_interval_ms() -> u64 {
+    DEFAULT_FLUSH_INTERVAL_MS
+}
+
+fn default_max_pending_batches() -> usize {
+    DEFAULT_MAX_PENDING_BATCHES
+}
+
+fn default_max_buffered_rows() -> usize {
+    DEFAULT_MAX_BUFFERED_ROWS
+}
+
+fn default_database() -> String {
+    DEFAULT_DATABASE.to_string()
+}
+
+fn default_create_tables() -> bool {
+    true
+}
+
+/// The database the ticks are inserted into.
+#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum StoreBackend {
+    /// A ClickHouse server, over its HTTP interface.
+    ClickHouse {
+        /// The URL of the HTTP interface (e.g. `http://localhost:8123`).
+        url: String,
+        /// The database of the tables.
+        #[serde(default = "default_database")]
+        database: String,
+        /// The user, the default user of the server if unset.
+        #[serde(default)]
+        user: Option<String>,
+        /// The password of the user.
+        #[serde(default)]
+        password: Option<String>,
+    },
+    /// A PostgreSQL server with the TimescaleDB extension.
+    TimescaleDb {
+        /// The connection string (e.g. `host=localhost user=ctl dbname=ticks`).
+        connection: String,
+    },
+}
+
+/// The configuration of the tick store.
+#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+pub struct TickStoreConfig {
+    /// The database the ticks are inserted into.
+    pub backend: StoreBackend,
+    /// The prefix of the tables, each named `<prefix>_<kind>` (e.g. `ticks_trade`).
+    #[serde(default = "default_table_prefix")]
+    pub table_prefix: String,
+    /// Whether the missing tables are created on startup.
+    #[serde(default = "default_create_tables")]
+    pub create_tables: bool,
+    /// The number of rows of a batch, inserted once full.
+    #[serde(default = "default_batch_rows")]
+    pub batch_rows: usize,
+    /// The longest time a tick waits for its batch to be inserted, in milliseconds.
+    #[serde(default = "default_flush_interval_ms")]
+    pub flush_interval_ms: u64,
+    /// The number of batches queued for the writer before the batches grow.
+    #[serde(default = "default_max_pending_batches")]
+    pub max_pending_batches: usize,
+    /// The number of rows buffered while the writer is backed up, past which
+    /// the newest ticks are dropped.
+    #[serde(default = "default_max_buffered_rows")]
+    pub max_buffered_rows: usize,
+    /// The retries of a failed insert before its batch is given up.
+    #[serde(default)]
+    pub retry: RetryPolicy,
+}
+
+impl TickStoreConfig {
+    /// Parses the tick store configuration from a YAML file.
+    ///
+    /// # Errors
+    /// Returns an error if the file cannot be read, parsed, or fails validation.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, TickStoreConfigError> {
+        let content = fs::read_to_string(path)?;
+        Self::from_str(&content)
+    }
+
+    /// Parses the tick store configuration from a YAML string.
+    ///
+    /// # Errors
+    /// Returns an error if the YAML cannot be parsed or fails validation.
+    pub fn from_str(content: &str) -> Result<Self, TickStoreConfigError> {
+        let config: Self = serde_yaml::from_str(content)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Validates the tick store configuration.
+    fn validate(&self) -> Result<(), TickStoreConfigError> {
+        // The names are interpolated into the queries
+        let is_identifier = |name: &str| {
+            !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        };
+        if !is_identifier(&self.table_prefix) {
+            return Err(TickStoreConfigError::ValidationError(format!(
+                "Table prefix '{}' must be alphanumeric or '_'",
+                self.table_prefix
+            )));
+        }
+        if let StoreBackend::ClickHouse { url, database, .. } = &self.backend {
+            if !url.starts_with("http://") && !url.starts_with("https://") {
+                return Err(TickStoreConfigError::ValidationError(format!(
+                    "ClickHouse URL '{}' is not an HTTP URL",
+                    url
+                )));
+            }
+            if !is_identifier(database) {
+                return Err(TickStoreConfigError::ValidationError(format!(
+                    "Database '{}' must be alphanumeric or '_'",
+                    database
+                )));
+            }
+        }
+        if self.batch_rows == 0 || self.flush_interval_ms == 0 || self.max_pending_batches == 0 {
+            return Err(TickStoreConfigError::ValidationError(
+                "Batch rows, flush interval and max pending batches must be greater than 0".to_string(),
+            ));
+        }
+        if self.max_buffered_rows < self.batch_rows {
+            return Err(TickStoreConfigError::ValidationError(format!(
+                "Max buffered rows ({}) must be at least the batch rows ({})",
+                self.max_buffered_rows, self.batch_rows
+            )));
+        }
+        self.retry.validate().map_err(TickStoreConfigError::ValidationError)
+    }
+
+    /// Returns the table of a kind of ticks.
+    pub fn table(&self, kind: TickKind) -> String {
+        format!("{}_{}", self.table_prefix, kind.as_str())
+    }
+
+    /// Returns the longest time a tick waits for its batch to be inserted.
+    pub fn flush_interval(&self) -> Duration {
+        Duration::from_millis(self.flush_interval_ms)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_config() {
+        let yaml = "backend:\n  kind: clickhouse\n  url: http://localhost:8123\n";
+        let config = TickStoreConfig::from_str(yaml).unwrap();
+        let StoreBackend::ClickHouse { database, user, .. } = &config.backend else {
+            panic!("expected a ClickHouse backend");
+        };
+        assert_eq!((database.as_str(), user), (DEFAULT_DATABASE, &None));
+        assert_eq!(config.table(TickKind::AggTrade), "ticks_aggtrade");
+        assert_eq!((config.batch_rows, config.flush_interval()), (DEFAULT_BATCH_ROWS, Duration::from_secs(1)));
+        assert!(config.create_tables);
+
+        let yaml = "backend:\n  kind: timescaledb\n  connection: host=localhost\ntable_prefix: md\nbatch_rows: 500\n";
+        let config = TickStoreConfig::from_str(yaml).unwrap();
+        assert_eq!(config.backend, StoreBackend::TimescaleDb { connection: "host=localhost".to_string() });
+        assert_eq!((config.table(TickKind::Top), config.batch_rows), ("md_top".to_string(), 500));
+    }
+
+    #[test]
+    fn test_invalid_config() {
+        let clickhouse = "backend:\n  kind: clickhouse\n  url: http://localhost:8123\n";
+        assert!(TickStoreConfig::from_str("backend:\n  kind: clickhouse\n  url: localhost:8123").is_err());
+        assert!(TickStoreConfig::from_str(&format!("{}table_prefix: 'ticks; DROP'", clickhouse)).is_err());
+        assert!(TickStoreConfig::from_str(&format!("{}batch_rows: 0", clickhouse)).is_err());
+        assert!(TickStoreConfig::from_str(&format!("{}max_buffered_rows: 10", clickhouse)).is_err());
+        assert!(TickStoreConfig::from_str("backend:\n  kind: sqlite\n").is_err());
+    }
+}
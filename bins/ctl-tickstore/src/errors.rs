@@ -0,0 +1,34 @@
+use thiserror::Error;
+
+/// Errors that can occur when parsing or validating the tick store configuration.
+#[derive(Debug, Error)]
+pub enum TickStoreConfigError {
+    /// Error reading the configuration file.
+    #[error("Failed to read configuration file: {0}")]
+    FileReadError(#[from] std::io::Error),
+    /// Error parsing the YAML configuration.
+    #[error("Failed to parse YAML configuration: {0}")]
+    YamlParseError(#[from] serde_yaml::Error),
+    /// Validation error with a descriptive message.
+    #[error("Configuration validation error: {0}")]
+    ValidationError(String),
+}
+
+/// Errors that can occur when writing the ticks to the database.
+#[derive(Debug, Error)]
+pub enum StoreError {
+    /// Error reaching ClickHouse.
+    #[error("ClickHouse request failed: {0}")]
+    ClickHouse(#[from] reqwest::Error),
+    /// ClickHouse refused a query.
+    #[error("ClickHouse refused the query: {status} {body}")]
+    ClickHouseRejected { status: u16, body: String },
+    /// Error of the TimescaleDB connection or query.
+    #[error("TimescaleDB query failed: {0}")]
+    Timescale(#[from] postgres::Error),
+}
+
+/// The writer of the batches stopped, no batch can be inserted anymore.
+#[derive(Debug, Error)]
+#[error("The writer of the tick batches stopped")]
+pub struct WriterStopped;
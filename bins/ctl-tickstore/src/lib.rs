@@ -0,0 +1,26 @@
+//! Tick store of the market data of Binance Spot.
+//!
+//! Consumes the Top, trade and aggregated trade rings like any other consumer,
+//! decoding their payloads into rows batched into ClickHouse or TimescaleDB,
+//! one table per kind (`<prefix>_top`, `<prefix>_trade`, `<prefix>_aggtrade`).
+//! The batches are inserted by a writer thread behind a bounded queue, so a
+//! slow or unreachable database never holds back the rings: the batches grow
+//! while it is backed up, then the newest ticks are dropped and counted.
+
+mod batcher;
+mod clickhouse;
+mod config;
+mod errors;
+mod store;
+mod tick;
+mod timescale;
+mod writer;
+
+pub use batcher::Batcher;
+pub use clickhouse::ClickHouseStore;
+pub use config::{StoreBackend, TickStoreConfig};
+pub use errors::{StoreError, TickStoreConfigError, WriterStopped};
+pub use store::{open_store, Tables, TickStore};
+pub use tick::{decode_into, AggTradeTick, Batch, TickKind, TopTick, TradeTick};
+pub use timescale::TimescaleStore;
+pub use writer::{run_writer, WriterStats};
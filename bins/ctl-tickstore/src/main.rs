@@ -0,0 +1,220 @@
+//! Tick Store for the Binance Spot Controller.
+//!
+//! Connects as a DPDK secondary process, attaches a consumer to the Top, trade
+//! and aggregated trade rings of the market data configuration, and batches
+//! their ticks into the ClickHouse or TimescaleDB tables of the tick store
+//! configuration.
+//!
+//! The store is optional: the plant runs without it, and it never holds back
+//! the rings. The batches are inserted by a writer thread, retrying a failed
+//! insert before giving its batch up; while the writer is backed up the
+//! batches grow, then the newest ticks are dropped and counted.
+//!
+//! The rings are polled under the policy of `configs/polling.yaml`. Once
+//! overtaken by a producer, the store recovers per `configs/overtaken.yaml`;
+//! the raw payloads aren't conflated, so a snapshot requested by
+//! `skip-and-request-snapshot` is a skip to the head.
+//!
+//! The exit code tells the class of a failure (see `FatalKind`).
+
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::sync::atomic::Ordering;
+use std::sync::mpsc;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use clap::Parser;
+use ctl_core::{
+    Capability, Fatal, FatalError, FatalKind, LcoresConfig, OvertakenConfig, OvertakenPolicy, Poller, PollingConfig,
+    PollingPolicy, Preflight, RingKind, RingName,
+};
+use ctl_feed::{dpdk_consume, MetricsRegion, RawMessage, RingRead, RingReader, METRICS_REGION_NAME};
+use ctl_md_handler::{HwResourcesConfig, SymbolInfoConfig};
+use ctl_shm::ShmRegion;
+use ctl_tickstore::{open_store, run_writer, Batcher, TickKind, TickStoreConfig, WriterStats};
+use dpdk::{DpdkEnvBuilder, DpdkProcessType};
+use tracing::{info, warn};
+
+// Logging configuration, its levels reloaded on change
+const LOG_CONFIG_PATH: &str = "configs/logging.yaml";
+
+// The market data configuration, holding the feeds and the EAL options of the secondary processes
+const MD_CONFIG_PATH: &str = "configs/market-data/hw-resources.yaml";
+
+// The symbol IDs of the rings
+const SYMBOL_INFO_PATH: &str = "configs/market-data/symbolinfo.yaml";
+
+// The tick store configuration
+const TICKSTORE_CONFIG_PATH: &str = "configs/tickstore/tickstore.yaml";
+
+// The name of the consumer cursors of the store in the rings
+const CONSUMER_NAME: &str = "tickstore";
+
+// The lcore of the store, unless configured, shared with the mostly idle tools
+const LCORES_CONFIG_PATH: &str = "configs/lcores.yaml";
+const LCORES_COMPONENT: &str = "tickstore";
+const DEFAULT_LCORE: u32 = 15;
+
+// Polling policy between the passes over the empty rings, unless configured for this component
+const POLLING_CONFIG_PATH: &str = "configs/polling.yaml";
+const POLLING_COMPONENT: &str = "tickstore";
+const DEFAULT_POLLING: PollingPolicy = PollingPolicy::Sleep { sleep_us: 100 };
+
+// Recovery once overtaken by a producer, unless configured for this component
+const OVERTAKEN_CONFIG_PATH: &str = "configs/overtaken.yaml";
+const OVERTAKEN_COMPONENT: &str = "tickstore";
+const DEFAULT_OVERTAKEN: OvertakenPolicy = OvertakenPolicy::SkipToHead;
+
+// Interval between the logs of the counters of the store
+const STATS_LOG_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Tick store of the market data rings.
+#[derive(Debug, Parser)]
+#[command(version)]
+struct Args {
+    /// Logging configuration.
+    #[arg(long, env = "CTL_LOG_CONFIG", default_value = LOG_CONFIG_PATH)]
+    log_config: PathBuf,
+    /// Market data configuration.
+    #[arg(long, env = "CTL_MD_CONFIG", default_value = MD_CONFIG_PATH)]
+    md_config: PathBuf,
+    /// Symbol info configuration.
+    #[arg(long, env = "CTL_SYMBOL_INFO", default_value = SYMBOL_INFO_PATH)]
+    symbol_info: PathBuf,
+    /// Tick store configuration.
+    #[arg(long, env = "CTL_TICKSTORE_CONFIG", default_value = TICKSTORE_CONFIG_PATH)]
+    tickstore_config: PathBuf,
+    /// Lcores configuration.
+    #[arg(long, env = "CTL_LCORES_CONFIG", default_value = LCORES_CONFIG_PATH)]
+    lcores_config: PathBuf,
+    /// Polling configuration.
+    #[arg(long, env = "CTL_POLLING_CONFIG", default_value = POLLING_CONFIG_PATH)]
+    polling_config: PathBuf,
+    /// Overtaken consumer recovery configuration.
+    #[arg(long, env = "CTL_OVERTAKEN_CONFIG", default_value = OVERTAKEN_CONFIG_PATH)]
+    overtaken_config: PathBuf,
+}
+
+fn main() -> ExitCode {
+    ctl_core::exit_code(run())
+}
+
+fn run() -> Result<(), FatalError> {
+    let args = Args::parse();
+    let _log = ctl_log::init_from_file(&args.log_config).fatal(FatalKind::Config)?;
+
+    info!("=== Binance Spot Tick Store ===");
+
+    let md_config = HwResourcesConfig::from_file(&args.md_config).fatal(FatalKind::Config)?;
+    let symbol_info = SymbolInfoConfig::from_file(&args.symbol_info).fatal(FatalKind::Config)?;
+    let config = TickStoreConfig::from_file(&args.tickstore_config).fatal(FatalKind::Config)?;
+    md_config.eal.apply_region_prefix();
+    let lcore = LcoresConfig::from_file(&args.lcores_config)?.lcore(LCORES_COMPONENT, DEFAULT_LCORE) as usize;
+    let polling = PollingConfig::from_file(&args.polling_config)?.policy(POLLING_COMPONENT, DEFAULT_POLLING);
+    info!("Polling: {:?}", polling);
+    let overtaken = OvertakenConfig::from_file(&args.overtaken_config)?.policy(OVERTAKEN_COMPONENT, DEFAULT_OVERTAKEN);
+    info!("Overtaken: {:?}", overtaken);
+
+    // Verify the host before the EAL initialization, which reports its failures cryptically
+    Preflight::new()
+        .require_hugepages_mounted()
+        .require_capabilities(&[Capability::IpcLock])
+        .require_primary()
+        .with_file_prefix(md_config.eal.file_prefix())
+        .run()?;
+
+    let dpdk_env = DpdkEnvBuilder::default()
+        .process_type(DpdkProcessType::Secondary)
+        .lcore_ids(vec![lcore])
+        .main_lcore_id(lcore)
+        .eal_args(md_config.eal.secondary_args())
+        .build()
+        .fatal(FatalKind::DpdkInit)?;
+
+    let metrics = ShmRegion::<MetricsRegion>::open(METRICS_REGION_NAME)?;
+
+    // Attach to the raw rings of the feeds, tracking the positions in their metrics like any consumer
+    let mut rings = Vec::new();
+    let ring_kinds = [
+        (RingKind::Top, "top", TickKind::Top),
+        (RingKind::Trade, "trade", TickKind::Trade),
+        (RingKind::AggTrade, "aggtrade", TickKind::AggTrade),
+    ];
+    for (kind, feed_kind, tick_kind) in ring_kinds {
+        let Some(feed) = md_config.find_feed(feed_kind) else {
+            continue;
+        };
+        for feed_set in feed.feed_sets() {
+            for symbol in feed_set.ring_symbols() {
+                let symbol_id = symbol_info
+                    .symbol_id(symbol)
+                    .ok_or_else(|| format!("Symbol '{}' not found in symbolinfo.yaml", symbol))
+                    .fatal(FatalKind::Config)?;
+                let name = RingName::pubsub(kind, symbol_id).to_string();
+                let reader = RingReader::attach(&metrics, &name, CONSUMER_NAME, feed_set.slot_size)
+                    .fatal(FatalKind::SharedState)?
+                    .with_overtaken(overtaken);
+                let ring = dpdk_env.pubsub_lookup::<RawMessage>(&name).fatal(FatalKind::SharedState)?;
+                let consumer = ring.attach_consumer().fatal(FatalKind::SharedState)?;
+                info!("[{}] {} -> {}", symbol, name, config.table(tick_kind));
+                rings.push((tick_kind, reader, consumer));
+            }
+        }
+    }
+    if rings.is_empty() {
+        return Err(FatalError::new(FatalKind::Config, "No rings to store"));
+    }
+
+    let mut store = open_store(&config).fatal(FatalKind::Network)?;
+    if config.create_tables {
+        store.create_tables().fatal(FatalKind::Network)?;
+    }
+
+    // Insert the batches on a thread of their own, so the inserts never hold back the rings
+    let (sender, receiver) = mpsc::sync_channel(config.max_pending_batches);
+    let stats = Arc::new(WriterStats::default());
+    let writer_stats = stats.clone();
+    let policy = config.retry;
+    thread::Builder::new()
+        .name("tickstore-writer".to_string())
+        .spawn(move || run_writer(store, receiver, policy, &writer_stats))
+        .fatal(FatalKind::Internal)?;
+    let mut batcher = Batcher::new(&config, sender);
+    info!("Storing {} rings into the tables of prefix '{}'", rings.len(), config.table_prefix);
+
+    let mut last_log = Instant::now();
+    let mut poller = Poller::new(polling);
+    loop {
+        let mut did_work = false;
+        for (kind, reader, consumer) in rings.iter_mut() {
+            let read = reader.read(dpdk_consume!(consumer)).fatal(FatalKind::Overtaken)?;
+            did_work |= read.did_work();
+            match read {
+                RingRead::Payload(message, payload) => {
+                    batcher.push(*kind, payload, message.header.ts_ms).fatal(FatalKind::Internal)?;
+                }
+                RingRead::Overtaken { .. } => {
+                    warn!("{} consumer overtaken by producer, some messages missed", reader.name());
+                }
+                RingRead::Consumed | RingRead::Idle => {}
+            }
+        }
+        batcher.flush_due(Instant::now()).fatal(FatalKind::Internal)?;
+
+        if !did_work && last_log.elapsed() >= STATS_LOG_INTERVAL {
+            last_log = Instant::now();
+            info!(
+                "Rows inserted: {}, given up: {}, dropped for a backed up writer: {}, undecodable: {}, buffered: {}",
+                stats.inserted.load(Ordering::Relaxed),
+                stats.failed.load(Ordering::Relaxed),
+                batcher.dropped(),
+                batcher.undecodable(),
+                batcher.buffered()
+            );
+        }
+        // Wait before the next pass according to the configured policy
+        poller.wait(did_work);
+    }
+}
@@ -0,0 +1,48 @@
+//! The databases the ticks are inserted into.
+
+use crate::{Batch, ClickHouseStore, StoreBackend, StoreError, TickKind, TickStoreConfig, TimescaleStore};
+
+/// A database the batches of ticks are inserted into.
+pub trait TickStore: Send {
+    /// Creates the tables of the ticks that don't exist.
+    fn create_tables(&mut self) -> Result<(), StoreError>;
+
+    /// Inserts a batch of ticks, each kind into its table.
+    fn insert(&mut self, batch: &Batch) -> Result<(), StoreError>;
+}
+
+/// The tables of the tick kinds.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Tables {
+    /// The table of the best bid/ask updates.
+    pub top: String,
+    /// The table of the trades.
+    pub trade: String,
+    /// The table of the aggregated trades.
+    pub agg_trade: String,
+}
+
+impl Tables {
+    /// Returns the tables of the configuration.
+    pub fn new(config: &TickStoreConfig) -> Self {
+        Self {
+            top: config.table(TickKind::Top),
+            trade: config.table(TickKind::Trade),
+            agg_trade: config.table(TickKind::AggTrade),
+        }
+    }
+}
+
+/// Connects to the database of the configuration.
+///
+/// # Errors
+/// Returns an error if the client of the database cannot be created or connected.
+pub fn open_store(config: &TickStoreConfig) -> Result<Box<dyn TickStore>, StoreError> {
+    let tables = Tables::new(config);
+    Ok(match &config.backend {
+        StoreBackend::ClickHouse { url, database, user, password } => {
+            Box::new(ClickHouseStore::new(url, database, user.clone(), password.clone(), tables)?)
+        }
+        StoreBackend::TimescaleDb { connection } => Box::new(TimescaleStore::connect(connection, tables)?),
+    })
+}
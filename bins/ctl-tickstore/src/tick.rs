@@ -0,0 +1,242 @@
+//! The ticks stored, decoded from the payloads of the rings, and the batches
+//! they are inserted in.
+
+use ctl_feed::{slot_payload, TopSnapshot};
+use serde::{Deserialize, Serialize};
+
+/// The kind of the ticks of a table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TickKind {
+    /// The best bid/ask updates.
+    Top,
+    /// The trades.
+    Trade,
+    /// The aggregated trades.
+    AggTrade,
+}
+
+impl TickKind {
+    /// Every tick kind.
+    pub const ALL: [TickKind; 3] = [TickKind::Top, TickKind::Trade, TickKind::AggTrade];
+
+    /// Returns the name of the kind, the suffix of its table (e.g. `trade`).
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TickKind::Top => "top",
+            TickKind::Trade => "trade",
+            TickKind::AggTrade => "aggtrade",
+        }
+    }
+}
+
+/// A best bid/ask update.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TopTick {
+    /// The symbol (e.g. `BTCUSDT`).
+    pub symbol: String,
+    /// The order book update ID.
+    pub update_id: u64,
+    /// The best bid price.
+    pub bid_price: f64,
+    /// The best bid quantity.
+    pub bid_qty: f64,
+    /// The best ask price.
+    pub ask_price: f64,
+    /// The best ask quantity.
+    pub ask_qty: f64,
+    /// The time the update was received, in milliseconds.
+    pub recv_time_ms: u64,
+}
+
+/// A trade.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TradeTick {
+    /// The symbol (e.g. `BTCUSDT`).
+    pub symbol: String,
+    /// The trade ID.
+    pub trade_id: u64,
+    /// The price.
+    pub price: f64,
+    /// The quantity.
+    pub qty: f64,
+    /// Whether the buyer was the maker.
+    pub buyer_maker: bool,
+    /// The time of the trade, in milliseconds.
+    pub trade_time_ms: u64,
+    /// The time the trade was received, in milliseconds.
+    pub recv_time_ms: u64,
+}
+
+/// An aggregated trade.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AggTradeTick {
+    /// The symbol (e.g. `BTCUSDT`).
+    pub symbol: String,
+    /// The aggregate trade ID.
+    pub agg_trade_id: u64,
+    /// The ID of the first trade aggregated.
+    pub first_trade_id: u64,
+    /// The ID of the last trade aggregated.
+    pub last_trade_id: u64,
+    /// The price.
+    pub price: f64,
+    /// The quantity.
+    pub qty: f64,
+    /// Whether the buyer was the maker.
+    pub buyer_maker: bool,
+    /// The time of the last trade, in milliseconds.
+    pub trade_time_ms: u64,
+    /// The time the aggregated trade was received, in milliseconds.
+    pub recv_time_ms: u64,
+}
+
+/// A trade stream payload.
+/// https://github.com/binance/binance-spot-api-docs/blob/master/web-socket-streams.md#trade-streams
+#[derive(Deserialize)]
+struct TradePayload<'a> {
+    #[serde(rename = "s")]
+    symbol: &'a str,
+    #[serde(rename = "t")]
+    trade_id: u64,
+    #[serde(rename = "p")]
+    price: &'a str,
+    #[serde(rename = "q")]
+    qty: &'a str,
+    #[serde(rename = "m")]
+    buyer_maker: bool,
+    #[serde(rename = "T")]
+    trade_time_ms: u64,
+}
+
+/// An aggregate trade stream payload.
+/// https://github.com/binance/binance-spot-api-docs/blob/master/web-socket-streams.md#aggregate-trade-streams
+#[derive(Deserialize)]
+struct AggTradePayload<'a> {
+    #[serde(rename = "s")]
+    symbol: &'a str,
+    #[serde(rename = "a")]
+    agg_trade_id: u64,
+    #[serde(rename = "f")]
+    first_trade_id: u64,
+    #[serde(rename = "l")]
+    last_trade_id: u64,
+    #[serde(rename = "p")]
+    price: &'a str,
+    #[serde(rename = "q")]
+    qty: &'a str,
+    #[serde(rename = "m")]
+    buyer_maker: bool,
+    #[serde(rename = "T")]
+    trade_time_ms: u64,
+}
+
+/// Decodes a payload of the rings of a kind, received at `recv_time_ms`, into a
+/// batch. Returns `false` if the payload is not a tick of the kind.
+pub fn decode_into(kind: TickKind, payload: &[u8], recv_time_ms: u64, batch: &mut Batch) -> bool {
+    let payload = slot_payload(payload);
+    match kind {
+        TickKind::Top => {
+            let Some((symbol, top)) = TopSnapshot::from_book_ticker(payload) else {
+                return false;
+            };
+            batch.tops.push(TopTick {
+                symbol: symbol.to_string(),
+                update_id: top.update_id,
+                bid_price: top.bid_price,
+                bid_qty: top.bid_qty,
+                ask_price: top.ask_price,
+                ask_qty: top.ask_qty,
+                recv_time_ms,
+            });
+        }
+        TickKind::Trade => {
+            let Some(tick) = decode_trade(payload, recv_time_ms) else {
+                return false;
+            };
+            batch.trades.push(tick);
+        }
+        TickKind::AggTrade => {
+            let Some(tick) = decode_agg_trade(payload, recv_time_ms) else {
+                return false;
+            };
+            batch.agg_trades.push(tick);
+        }
+    }
+    true
+}
+
+fn decode_trade(payload: &[u8], recv_time_ms: u64) -> Option<TradeTick> {
+    let trade: TradePayload<'_> = serde_json::from_slice(payload).ok()?;
+    Some(TradeTick {
+        symbol: trade.symbol.to_string(),
+        trade_id: trade.trade_id,
+        price: trade.price.parse().ok()?,
+        qty: trade.qty.parse().ok()?,
+        buyer_maker: trade.buyer_maker,
+        trade_time_ms: trade.trade_time_ms,
+        recv_time_ms,
+    })
+}
+
+fn decode_agg_trade(payload: &[u8], recv_time_ms: u64) -> Option<AggTradeTick> {
+    let trade: AggTradePayload<'_> = serde_json::from_slice(payload).ok()?;
+    Some(AggTradeTick {
+        symbol: trade.symbol.to_string(),
+        agg_trade_id: trade.agg_trade_id,
+        first_trade_id: trade.first_trade_id,
+        last_trade_id: trade.last_trade_id,
+        price: trade.price.parse().ok()?,
+        qty: trade.qty.parse().ok()?,
+        buyer_maker: trade.buyer_maker,
+        trade_time_ms: trade.trade_time_ms,
+        recv_time_ms,
+    })
+}
+
+/// The ticks of an insert, by kind.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Batch {
+    /// The best bid/ask updates.
+    pub tops: Vec<TopTick>,
+    /// The trades.
+    pub trades: Vec<TradeTick>,
+    /// The aggregated trades.
+    pub agg_trades: Vec<AggTradeTick>,
+}
+
+impl Batch {
+    /// Returns the number of rows of the batch.
+    pub fn len(&self) -> usize {
+        self.tops.len() + self.trades.len() + self.agg_trades.len()
+    }
+
+    /// Returns true if the batch has no rows.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode() {
+        let mut batch = Batch::default();
+        let top = br#"{"u":400900217,"s":"BNBUSDT","b":"25.35190000","B":"31.21000000","a":"25.36520000","A":"40.66"}"#;
+        assert!(decode_into(TickKind::Top, top, 1_000, &mut batch));
+        let trade = br#"{"e":"trade","s":"BNBBTC","t":12345,"p":"0.001","q":"100","T":123456785,"m":true}"#;
+        assert!(decode_into(TickKind::Trade, trade, 1_001, &mut batch));
+        let agg = br#"{"s":"BNBBTC","a":26129,"p":"0.016","q":"4.7","f":27781,"l":27783,"m":false,"T":1}"#;
+        assert!(decode_into(TickKind::AggTrade, agg, 1_002, &mut batch));
+        assert!(!decode_into(TickKind::Trade, top, 1_003, &mut batch));
+        assert_eq!(batch.len(), 3);
+
+        assert_eq!((batch.tops[0].symbol.as_str(), batch.tops[0].ask_qty), ("BNBUSDT", 40.66));
+        let trade = &batch.trades[0];
+        assert_eq!((trade.trade_id, trade.price, trade.buyer_maker), (12345, 0.001, true));
+        let agg = &batch.agg_trades[0];
+        assert_eq!((agg.agg_trade_id, agg.first_trade_id, agg.last_trade_id), (26129, 27781, 27783));
+        assert_eq!(agg.recv_time_ms, 1_002);
+    }
+}
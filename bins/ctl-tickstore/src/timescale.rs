@@ -0,0 +1,163 @@
+//! The TimescaleDB tick store, copying into hypertables.
+//!
+//! A batch is copied in the binary format within a single transaction, so a
+//! failed insert leaves no rows behind and its retry duplicates none.
+//! https://docs.timescale.com/use-timescale/latest/hypertables/
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use postgres::binary_copy::BinaryCopyInWriter;
+use postgres::types::Type;
+use postgres::{Client, NoTls, Transaction};
+
+use crate::{Batch, StoreError, Tables, TickStore};
+
+/// The client of a TimescaleDB server.
+pub struct TimescaleStore {
+    client: Client,
+    /// The tables of the tick kinds.
+    tables: Tables,
+}
+
+impl TimescaleStore {
+    /// Connects to the server of the connection string.
+    ///
+    /// # Errors
+    /// Returns an error if the connection fails.
+    pub fn connect(connection: &str, tables: Tables) -> Result<Self, StoreError> {
+        Ok(Self { client: Client::connect(connection, NoTls)?, tables })
+    }
+}
+
+impl TickStore for TimescaleStore {
+    fn create_tables(&mut self) -> Result<(), StoreError> {
+        for (table, columns) in table_columns(&self.tables) {
+            self.client.batch_execute(&format!(
+                "CREATE TABLE IF NOT EXISTS {} (time TIMESTAMPTZ NOT NULL, symbol TEXT NOT NULL, {}); \
+                 SELECT create_hypertable('{}', 'time', if_not_exists => TRUE); \
+                 CREATE INDEX IF NOT EXISTS {}_symbol_time ON {} (symbol, time DESC);",
+                table, columns, table, table, table
+            ))?;
+        }
+        Ok(())
+    }
+
+    fn insert(&mut self, batch: &Batch) -> Result<(), StoreError> {
+        let mut transaction = self.client.transaction()?;
+        if !batch.tops.is_empty() {
+            let columns = "time, symbol, update_id, bid_price, bid_qty, ask_price, ask_qty";
+            let types = [
+                Type::TIMESTAMPTZ,
+                Type::TEXT,
+                Type::INT8,
+                Type::FLOAT8,
+                Type::FLOAT8,
+                Type::FLOAT8,
+                Type::FLOAT8,
+            ];
+            let mut writer = copy_in(&mut transaction, &self.tables.top, columns, &types)?;
+            for tick in &batch.tops {
+                let update_id = tick.update_id as i64;
+                writer.write(&[
+                    &timestamp(tick.recv_time_ms),
+                    &tick.symbol,
+                    &update_id,
+                    &tick.bid_price,
+                    &tick.bid_qty,
+                    &tick.ask_price,
+                    &tick.ask_qty,
+                ])?;
+            }
+            writer.finish()?;
+        }
+        if !batch.trades.is_empty() {
+            let columns = "time, symbol, trade_id, price, qty, buyer_maker, trade_time_ms";
+            let types = [Type::TIMESTAMPTZ, Type::TEXT, Type::INT8, Type::FLOAT8, Type::FLOAT8, Type::BOOL, Type::INT8];
+            let mut writer = copy_in(&mut transaction, &self.tables.trade, columns, &types)?;
+            for tick in &batch.trades {
+                let (trade_id, trade_time_ms) = (tick.trade_id as i64, tick.trade_time_ms as i64);
+                writer.write(&[
+                    &timestamp(tick.recv_time_ms),
+                    &tick.symbol,
+                    &trade_id,
+                    &tick.price,
+                    &tick.qty,
+                    &tick.buyer_maker,
+                    &trade_time_ms,
+                ])?;
+            }
+            writer.finish()?;
+        }
+        if !batch.agg_trades.is_empty() {
+            let columns =
+                "time, symbol, agg_trade_id, first_trade_id, last_trade_id, price, qty, buyer_maker, trade_time_ms";
+            let types = [
+                Type::TIMESTAMPTZ,
+                Type::TEXT,
+                Type::INT8,
+                Type::INT8,
+                Type::INT8,
+                Type::FLOAT8,
+                Type::FLOAT8,
+                Type::BOOL,
+                Type::INT8,
+            ];
+            let mut writer = copy_in(&mut transaction, &self.tables.agg_trade, columns, &types)?;
+            for tick in &batch.agg_trades {
+                let ids = (tick.agg_trade_id as i64, tick.first_trade_id as i64, tick.last_trade_id as i64);
+                let trade_time_ms = tick.trade_time_ms as i64;
+                writer.write(&[
+                    &timestamp(tick.recv_time_ms),
+                    &tick.symbol,
+                    &ids.0,
+                    &ids.1,
+                    &ids.2,
+                    &tick.price,
+                    &tick.qty,
+                    &tick.buyer_maker,
+                    &trade_time_ms,
+                ])?;
+            }
+            writer.finish()?;
+        }
+        Ok(transaction.commit()?)
+    }
+}
+
+/// Starts the binary copy of the rows of a table.
+fn copy_in<'a>(
+    transaction: &'a mut Transaction<'_>,
+    table: &str,
+    columns: &str,
+    types: &[Type],
+) -> Result<BinaryCopyInWriter<'a>, StoreError> {
+    let sink = transaction.copy_in(&format!("COPY {} ({}) FROM STDIN BINARY", table, columns))?;
+    Ok(BinaryCopyInWriter::new(sink, types))
+}
+
+/// Returns the time of a timestamp in milliseconds.
+fn timestamp(time_ms: u64) -> SystemTime {
+    UNIX_EPOCH + Duration::from_millis(time_ms)
+}
+
+/// Returns the tables with their columns past the time and the symbol.
+fn table_columns(tables: &Tables) -> [(&str, &str); 3] {
+    [
+        (
+            &tables.top,
+            "update_id BIGINT NOT NULL, bid_price DOUBLE PRECISION NOT NULL, bid_qty DOUBLE PRECISION NOT NULL, \
+             ask_price DOUBLE PRECISION NOT NULL, ask_qty DOUBLE PRECISION NOT NULL",
+        ),
+        (
+            &tables.trade,
+            "trade_id BIGINT NOT NULL, price DOUBLE PRECISION NOT NULL, qty DOUBLE PRECISION NOT NULL, \
+             buyer_maker BOOLEAN NOT NULL, trade_time_ms BIGINT NOT NULL",
+        ),
+        (
+            &tables.agg_trade,
+            "agg_trade_id BIGINT NOT NULL, first_trade_id BIGINT NOT NULL, last_trade_id BIGINT NOT NULL, \
+             price DOUBLE PRECISION NOT NULL, qty DOUBLE PRECISION NOT NULL, buyer_maker BOOLEAN NOT NULL, \
+             trade_time_ms BIGINT NOT NULL",
+        ),
+    ]
+}
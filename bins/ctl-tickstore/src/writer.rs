@@ -0,0 +1,92 @@
+//! The writer of the batches, inserting them into the database on its own thread.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::Receiver;
+
+use ctl_retry::{retry, Backoff, RetryPolicy};
+use tracing::error;
+
+use crate::{Batch, TickStore};
+
+/// The counters of the writer, read by the thread consuming the rings.
+#[derive(Debug, Default)]
+pub struct WriterStats {
+    /// Number of rows inserted.
+    pub inserted: AtomicU64,
+    /// Number of rows given up after the retries of their insert.
+    pub failed: AtomicU64,
+}
+
+/// Inserts the batches received into the store until the batcher hangs up,
+/// retrying a failed insert with the backoff of `policy` before giving its
+/// batch up.
+pub fn run_writer(mut store: Box<dyn TickStore>, batches: Receiver<Batch>, policy: RetryPolicy, stats: &WriterStats) {
+    let mut backoff = Backoff::new(policy);
+    for batch in batches {
+        match retry(&mut backoff, || store.insert(&batch), |_| true) {
+            Ok(()) => {
+                stats.inserted.fetch_add(batch.len() as u64, Ordering::Relaxed);
+            }
+            Err(e) => {
+                error!("Batch of {} rows given up: {}", batch.len(), e);
+                stats.failed.fetch_add(batch.len() as u64, Ordering::Relaxed);
+                backoff.reset();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc;
+
+    use super::*;
+    use crate::{StoreError, TradeTick};
+
+    /// A store failing its first inserts.
+    struct FlakyStore {
+        failures: u32,
+    }
+
+    impl TickStore for FlakyStore {
+        fn create_tables(&mut self) -> Result<(), StoreError> {
+            Ok(())
+        }
+
+        fn insert(&mut self, _batch: &Batch) -> Result<(), StoreError> {
+            if self.failures == 0 {
+                return Ok(());
+            }
+            self.failures -= 1;
+            Err(StoreError::ClickHouseRejected { status: 503, body: String::new() })
+        }
+    }
+
+    fn batch(rows: usize) -> Batch {
+        let tick = TradeTick {
+            symbol: "BTCUSDT".to_string(),
+            trade_id: 1,
+            price: 1.0,
+            qty: 1.0,
+            buyer_maker: false,
+            trade_time_ms: 1,
+            recv_time_ms: 1,
+        };
+        Batch { trades: vec![tick; rows], ..Batch::default() }
+    }
+
+    #[test]
+    fn test_retry_then_give_up() {
+        let policy = RetryPolicy { max_retries: 2, initial_backoff_ms: 1, ..RetryPolicy::default() };
+        let (sender, receiver) = mpsc::sync_channel(4);
+        // The first batch exhausts the retries, the second succeeds on its last retry
+        sender.send(batch(3)).unwrap();
+        sender.send(batch(5)).unwrap();
+        sender.send(batch(7)).unwrap();
+        drop(sender);
+
+        let stats = WriterStats::default();
+        run_writer(Box::new(FlakyStore { failures: 5 }), receiver, policy, &stats);
+        assert_eq!((stats.inserted.load(Ordering::Relaxed), stats.failed.load(Ordering::Relaxed)), (12, 3));
+    }
+}
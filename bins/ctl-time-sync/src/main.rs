@@ -0,0 +1,127 @@
+//! Exchange Time Synchronization for Binance Spot.
+//!
+//! This binary periodically measures the offset and round trip time of the
+//! local clock against the Binance `serverTime`, publishes the estimated offset
+//! to the time synchronization region created by ctl-resource-manager, and warns
+//! when the drift would cause `recvWindow` rejections of signed requests.
+
+use std::process::ExitCode;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use ctl_core::{Fatal, FatalError, FatalKind, Preflight};
+use ctl_md_handler::HwResourcesConfig;
+use ctl_rest::{
+    RestClient, RestError, WeightLedger, BINANCE_REST_ENDPOINT, RECV_WINDOW_MS, WEIGHT_LEDGER_REGION_NAME,
+};
+use ctl_retry::{Backoff, RetryPolicy};
+use ctl_shm::ShmRegion;
+use ctl_time::{
+    check_drift, now_ms, ClockSample, DriftWarning, OffsetEstimator, TimeSyncRegion,
+    TIME_SYNC_REGION_NAME,
+};
+use tracing::{error, info, warn};
+
+// Logging configuration, its levels reloaded on change
+const LOG_CONFIG_PATH: &str = "configs/logging.yaml";
+
+// The market data configuration, holding the EAL options of the secondary processes
+const MD_CONFIG_PATH: &str = "configs/market-data/hw-resources.yaml";
+
+// Interval between server time measurements
+const SYNC_INTERVAL: Duration = Duration::from_secs(10);
+
+// Backoff of the measurements after a failure, before falling back to the sync interval
+const MEASURE_RETRY: RetryPolicy = RetryPolicy {
+    max_retries: 5,
+    initial_backoff_ms: 500,
+    max_backoff_ms: 8_000,
+    multiplier: 2,
+    jitter_pct: 20,
+};
+
+// Number of recent samples the offset is estimated from
+const ESTIMATOR_SAMPLES: usize = 8;
+
+// The headroom kept for the latency of the signed requests within their recvWindow
+const DRIFT_MARGIN_MS: u64 = 250;
+
+/// Measures the server time, bracketed by local timestamps.
+fn measure(client: &RestClient) -> Result<ClockSample, RestError> {
+    let sent_ms = now_ms();
+    let server_ms = client.server_time()?;
+    let received_ms = now_ms();
+    Ok(ClockSample { sent_ms, server_ms, received_ms })
+}
+
+/// Handles a drift that would cause signed requests to be rejected.
+fn handle_drift_warning(warning: DriftWarning) {
+    match warning {
+        DriftWarning::Ahead(ms) => warn!(
+            "Local clock {}ms ahead of the server, signed requests will be rejected",
+            ms
+        ),
+        DriftWarning::Behind(ms) => warn!(
+            "Local clock {}ms behind the server, exceeding recvWindow {}ms",
+            ms, RECV_WINDOW_MS
+        ),
+    }
+}
+
+fn main() -> ExitCode {
+    ctl_core::exit_code(run())
+}
+
+fn run() -> Result<(), FatalError> {
+    let _log = ctl_log::init_from_file(LOG_CONFIG_PATH).fatal(FatalKind::Config)?;
+
+    info!("=== Binance Spot Time Synchronization ===");
+
+    // The shared regions are only maintained while ctl-resource-manager is running
+    let eal = HwResourcesConfig::from_file(MD_CONFIG_PATH).fatal(FatalKind::Config)?.eal;
+    eal.apply_region_prefix();
+    Preflight::new().require_primary().with_file_prefix(eal.file_prefix()).run()?;
+
+    // Share the REST weight budget with the other components through the ledger
+    // created by ctl-resource-manager
+    let ledger = Arc::new(ShmRegion::<WeightLedger>::open(WEIGHT_LEDGER_REGION_NAME)?);
+    let client = RestClient::new(BINANCE_REST_ENDPOINT).fatal(FatalKind::Network)?.with_ledger(ledger);
+
+    // Attach to the time synchronization region created by ctl-resource-manager
+    let region = ShmRegion::<TimeSyncRegion>::open(TIME_SYNC_REGION_NAME)?;
+    info!("Attached to time sync region: {}", TIME_SYNC_REGION_NAME);
+    info!("Synchronizing every {:?} against {}", SYNC_INTERVAL, client.base_url());
+
+    let mut estimator = OffsetEstimator::new(ESTIMATOR_SAMPLES);
+    let mut backoff = Backoff::new(MEASURE_RETRY);
+    loop {
+        let delay = match measure(&client) {
+            Ok(sample) => {
+                backoff.reset();
+                estimator.push(sample);
+                if let Some(best) = estimator.best() {
+                    region.publish(best);
+                    info!(
+                        "[Sync] offset {}ms (rtt {}ms), last sample offset {}ms (rtt {}ms)",
+                        best.offset_ms(),
+                        best.rtt_ms(),
+                        sample.offset_ms(),
+                        sample.rtt_ms()
+                    );
+                    if let Some(warning) = check_drift(best.offset_ms(), RECV_WINDOW_MS, DRIFT_MARGIN_MS) {
+                        handle_drift_warning(warning);
+                    }
+                }
+                SYNC_INTERVAL
+            }
+            Err(e) => {
+                // Retried sooner than the sync interval, until the retries are exhausted
+                let delay = backoff.next(now_ms()).unwrap_or(SYNC_INTERVAL);
+                error!("Failed to measure server time, retrying in {:?}: {}", delay, e);
+                delay
+            }
+        };
+        thread::sleep(delay);
+    }
+}
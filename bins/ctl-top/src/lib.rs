@@ -0,0 +1,14 @@
+//! Terminal market watcher of Binance Spot.
+//!
+//! Shows, per configured symbol, the best bid/ask read from the last-value Top
+//! region, the last trade consumed from the trade rings, the spread, and the
+//! message rates of the rings measured from the heads of their metrics, so the
+//! operators get an at-a-glance console without external tooling.
+
+mod rate;
+mod ui;
+mod watch;
+
+pub use rate::RateMeter;
+pub use ui::{draw, Status};
+pub use watch::{decode_trade, LastTrade, RateKind, SymbolRow, Watch};
@@ -0,0 +1,240 @@
+//! Terminal Market Watcher for the Binance Spot Controller.
+//!
+//! Connects as a DPDK secondary process and shows, per symbol of the market
+//! data configuration, the best bid/ask of the last-value Top region, the
+//! last trade, the spread and the message rates of the rings, redrawn a few
+//! times a second. The last trades are consumed from the trade rings, or the
+//! aggregated trade rings without a trade feed; the rates are measured from
+//! the heads of the ring metrics, without consuming the rings.
+//!
+//! The trade rings are polled under the policy of `configs/polling.yaml`.
+//! Once overtaken by a producer, the watcher recovers per
+//! `configs/overtaken.yaml`; the last trade is only ever the newest one, so a
+//! snapshot requested by `skip-and-request-snapshot` is a skip to the head.
+//!
+//! Keys: `q` or `Esc` quits, the arrows select a row.
+//!
+//! The logs are only written until the terminal is taken over. The exit code
+//! tells the class of a failure (see `FatalKind`).
+
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::sync::atomic::Ordering;
+use std::time::{Duration, Instant};
+
+use clap::Parser;
+use ctl_core::{
+    Capability, Fatal, FatalError, FatalKind, LcoresConfig, OvertakenConfig, OvertakenPolicy, Poller, PollingConfig,
+    PollingPolicy, Preflight, RingKind, RingName,
+};
+use ctl_feed::{
+    dpdk_consume, LastTopRegion, MetricsRegion, RawMessage, RingRead, RingReader, LAST_TOP_REGION_NAME,
+    METRICS_REGION_NAME,
+};
+use ctl_md_handler::{HwResourcesConfig, SymbolInfoConfig};
+use ctl_shm::ShmRegion;
+use ctl_time::now_ms;
+use ctl_top::{draw, RateKind, Status, Watch};
+use dpdk::{DpdkEnvBuilder, DpdkProcessType};
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::widgets::TableState;
+use tracing::info;
+
+// Logging configuration, its levels reloaded on change
+const LOG_CONFIG_PATH: &str = "configs/logging.yaml";
+
+// The market data configuration, holding the feeds and the EAL options of the secondary processes
+const MD_CONFIG_PATH: &str = "configs/market-data/hw-resources.yaml";
+
+// The symbol IDs of the rings
+const SYMBOL_INFO_PATH: &str = "configs/market-data/symbolinfo.yaml";
+
+// The name of the consumer cursors of the watcher in the rings
+const CONSUMER_NAME: &str = "ctl-top";
+
+// The lcore of the watcher, unless configured, shared with the mostly idle tools
+const LCORES_CONFIG_PATH: &str = "configs/lcores.yaml";
+const LCORES_COMPONENT: &str = "ctl-top";
+const DEFAULT_LCORE: u32 = 15;
+
+// Interval between the redraws, reading the Top region and sampling the rates
+const REFRESH_INTERVAL: Duration = Duration::from_millis(250);
+
+// Polling policy between the passes over the empty rings, unless configured for this component
+const POLLING_CONFIG_PATH: &str = "configs/polling.yaml";
+const POLLING_COMPONENT: &str = "ctl-top";
+const DEFAULT_POLLING: PollingPolicy = PollingPolicy::Sleep { sleep_us: 1_000 };
+
+// Recovery once overtaken by a producer, unless configured for this component
+const OVERTAKEN_CONFIG_PATH: &str = "configs/overtaken.yaml";
+const OVERTAKEN_COMPONENT: &str = "ctl-top";
+const DEFAULT_OVERTAKEN: OvertakenPolicy = OvertakenPolicy::SkipToHead;
+
+/// Terminal market watcher of the configured symbols.
+#[derive(Debug, Parser)]
+#[command(version)]
+struct Args {
+    /// Logging configuration.
+    #[arg(long, env = "CTL_LOG_CONFIG", default_value = LOG_CONFIG_PATH)]
+    log_config: PathBuf,
+    /// Market data configuration.
+    #[arg(long, env = "CTL_MD_CONFIG", default_value = MD_CONFIG_PATH)]
+    md_config: PathBuf,
+    /// Symbol info configuration.
+    #[arg(long, env = "CTL_SYMBOL_INFO", default_value = SYMBOL_INFO_PATH)]
+    symbol_info: PathBuf,
+    /// Lcores configuration.
+    #[arg(long, env = "CTL_LCORES_CONFIG", default_value = LCORES_CONFIG_PATH)]
+    lcores_config: PathBuf,
+    /// Polling configuration.
+    #[arg(long, env = "CTL_POLLING_CONFIG", default_value = POLLING_CONFIG_PATH)]
+    polling_config: PathBuf,
+    /// Overtaken consumer recovery configuration.
+    #[arg(long, env = "CTL_OVERTAKEN_CONFIG", default_value = OVERTAKEN_CONFIG_PATH)]
+    overtaken_config: PathBuf,
+}
+
+/// Restores the terminal when dropped, including on a fatal error.
+struct TerminalGuard;
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        ratatui::restore();
+    }
+}
+
+fn main() -> ExitCode {
+    ctl_core::exit_code(run())
+}
+
+fn run() -> Result<(), FatalError> {
+    let args = Args::parse();
+    let _log = ctl_log::init_from_file(&args.log_config).fatal(FatalKind::Config)?;
+
+    info!("=== Binance Spot Market Watcher ===");
+
+    let md_config = HwResourcesConfig::from_file(&args.md_config).fatal(FatalKind::Config)?;
+    let symbol_info = SymbolInfoConfig::from_file(&args.symbol_info).fatal(FatalKind::Config)?;
+    md_config.eal.apply_region_prefix();
+    let lcore = LcoresConfig::from_file(&args.lcores_config)?.lcore(LCORES_COMPONENT, DEFAULT_LCORE) as usize;
+    let polling = PollingConfig::from_file(&args.polling_config)?.policy(POLLING_COMPONENT, DEFAULT_POLLING);
+    info!("Polling: {:?}", polling);
+    let overtaken = OvertakenConfig::from_file(&args.overtaken_config)?.policy(OVERTAKEN_COMPONENT, DEFAULT_OVERTAKEN);
+    info!("Overtaken: {:?}", overtaken);
+
+    // Verify the host before the EAL initialization, which reports its failures cryptically
+    Preflight::new()
+        .require_hugepages_mounted()
+        .require_capabilities(&[Capability::IpcLock])
+        .require_primary()
+        .with_file_prefix(md_config.eal.file_prefix())
+        .run()?;
+
+    let dpdk_env = DpdkEnvBuilder::default()
+        .process_type(DpdkProcessType::Secondary)
+        .lcore_ids(vec![lcore])
+        .main_lcore_id(lcore)
+        .eal_args(md_config.eal.secondary_args())
+        .build()
+        .fatal(FatalKind::DpdkInit)?;
+
+    let metrics = ShmRegion::<MetricsRegion>::open(METRICS_REGION_NAME)?;
+    let last_top = ShmRegion::<LastTopRegion>::open(LAST_TOP_REGION_NAME)?;
+
+    // The last trades come from the trade rings, or the aggregated trade rings without a trade feed
+    let trade_kind = if md_config.find_feed("trade").is_some() { RingKind::Trade } else { RingKind::AggTrade };
+
+    let mut watch = Watch::new();
+    let mut trade_rings = Vec::new();
+    let ring_kinds = [
+        (RingKind::Top, "top", RateKind::Top),
+        (RingKind::Trade, "trade", RateKind::Trade),
+        (RingKind::AggTrade, "aggtrade", RateKind::AggTrade),
+    ];
+    for (kind, feed_kind, rate_kind) in ring_kinds {
+        let Some(feed) = md_config.find_feed(feed_kind) else {
+            continue;
+        };
+        for feed_set in feed.feed_sets() {
+            for symbol in feed_set.ring_symbols() {
+                let symbol_id = symbol_info
+                    .symbol_id(symbol)
+                    .ok_or_else(|| format!("Symbol '{}' not found in symbolinfo.yaml", symbol))
+                    .fatal(FatalKind::Config)?;
+                let name = RingName::pubsub(kind, symbol_id).to_string();
+                let ring_index = metrics
+                    .find_ring(&name)
+                    .ok_or_else(|| format!("Ring '{}' not found in metrics region", name))
+                    .fatal(FatalKind::SharedState)?;
+                watch.add_symbol(symbol, symbol_id);
+                watch.add_ring(symbol, rate_kind, ring_index);
+                if kind != trade_kind {
+                    continue;
+                }
+
+                // Attach to the trade rings, tracking the positions in their metrics like any consumer
+                let reader = RingReader::attach(&metrics, &name, CONSUMER_NAME, feed_set.slot_size)
+                    .fatal(FatalKind::SharedState)?
+                    .with_overtaken(overtaken);
+                let ring = dpdk_env.pubsub_lookup::<RawMessage>(&name).fatal(FatalKind::SharedState)?;
+                let consumer = ring.attach_consumer().fatal(FatalKind::SharedState)?;
+                trade_rings.push((reader, consumer));
+            }
+        }
+    }
+    if watch.rows().is_empty() {
+        return Err(FatalError::new(FatalKind::Config, "No symbols to watch"));
+    }
+    info!("Watching {} symbols", watch.rows().len());
+
+    let mut terminal = ratatui::try_init().fatal(FatalKind::Io)?;
+    let _terminal_guard = TerminalGuard;
+    let mut table_state = TableState::default().with_selected(Some(0));
+    let mut status = Status {
+        trade_source: if trade_kind == RingKind::Trade { "trade" } else { "aggregated trade" },
+        overtaken: 0,
+    };
+
+    let mut last_refresh: Option<Instant> = None;
+    let mut poller = Poller::new(polling);
+    loop {
+        let mut did_work = false;
+        for (reader, consumer) in trade_rings.iter_mut() {
+            let read = reader.read(dpdk_consume!(consumer)).fatal(FatalKind::Overtaken)?;
+            did_work |= read.did_work();
+            match read {
+                RingRead::Payload(_, payload) => watch.record_trade(payload),
+                // The last trade only needs the newest messages, the missed ones are shown as a count
+                RingRead::Overtaken { .. } => status.overtaken += 1,
+                RingRead::Consumed | RingRead::Idle => {}
+            }
+        }
+
+        let now = Instant::now();
+        if last_refresh.is_none_or(|last| now.duration_since(last) >= REFRESH_INTERVAL) {
+            last_refresh = Some(now);
+            watch.refresh_tops(&last_top);
+            watch.sample_rates(|ring_index| metrics.rings[ring_index].head.load(Ordering::Acquire), now);
+            terminal
+                .draw(|frame| draw(frame, &watch, &mut table_state, &status, now_ms()))
+                .fatal(FatalKind::Io)?;
+
+            while event::poll(Duration::ZERO).fatal(FatalKind::Io)? {
+                let Event::Key(key) = event::read().fatal(FatalKind::Io)? else {
+                    continue;
+                };
+                if key.kind != KeyEventKind::Press {
+                    continue;
+                }
+                match key.code {
+                    KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                    KeyCode::Down => table_state.select_next(),
+                    KeyCode::Up => table_state.select_previous(),
+                    _ => {}
+                }
+            }
+        }
+        // Wait before the next pass according to the configured policy
+        poller.wait(did_work);
+    }
+}
@@ -0,0 +1,57 @@
+//! The message rates of the rings, from the heads of their metrics.
+
+use std::time::{Duration, Instant};
+
+/// The shortest window a rate is measured over, so the rates of the quiet
+/// rings don't flicker between the redraws.
+const RATE_WINDOW: Duration = Duration::from_secs(1);
+
+/// The rate of a counter that only grows, e.g. the messages published to a ring.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct RateMeter {
+    /// The counter and the time of the start of the window.
+    start: Option<(u64, Instant)>,
+    /// The rate over the last window, per second.
+    per_sec: f64,
+}
+
+impl RateMeter {
+    /// Samples the counter at `now`, measuring its rate once the window elapsed.
+    pub fn sample(&mut self, count: u64, now: Instant) {
+        let Some((start_count, start)) = self.start else {
+            self.start = Some((count, now));
+            return;
+        };
+        let elapsed = now.saturating_duration_since(start);
+        if elapsed < RATE_WINDOW {
+            return;
+        }
+        // A counter going back is a ring registered anew by a restarted primary
+        self.per_sec = count.saturating_sub(start_count) as f64 / elapsed.as_secs_f64();
+        self.start = Some((count, now));
+    }
+
+    /// Returns the rate over the last window, per second.
+    pub fn per_sec(&self) -> f64 {
+        self.per_sec
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rate_over_window() {
+        let start = Instant::now();
+        let mut rate = RateMeter::default();
+        rate.sample(100, start);
+        rate.sample(150, start + Duration::from_millis(500));
+        assert_eq!(rate.per_sec(), 0.0);
+
+        rate.sample(300, start + Duration::from_secs(2));
+        assert_eq!(rate.per_sec(), 100.0);
+        rate.sample(10, start + Duration::from_secs(3));
+        assert_eq!(rate.per_sec(), 0.0);
+    }
+}
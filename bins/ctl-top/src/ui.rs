@@ -0,0 +1,118 @@
+//! The rendering of the watch in the terminal.
+
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Cell, Paragraph, Row, Table, TableState};
+use ratatui::Frame;
+
+use crate::{RateKind, Watch};
+
+/// The column headers of the table, with their widths.
+const COLUMNS: [(&str, u16); 13] = [
+    ("Symbol", 12),
+    ("Bid qty", 12),
+    ("Bid", 14),
+    ("Ask", 14),
+    ("Ask qty", 12),
+    ("Spread", 12),
+    ("bps", 8),
+    ("Last", 14),
+    ("Last qty", 12),
+    ("Side", 4),
+    ("Age", 7),
+    ("Top/s", 8),
+    ("Trades/s", 8),
+];
+
+/// The placeholder of a value not known yet.
+const MISSING: &str = "-";
+
+/// The state of the process shown under the table.
+#[derive(Debug, Clone, Default)]
+pub struct Status {
+    /// The rings the last trades are consumed from.
+    pub trade_source: &'static str,
+    /// Number of times the consumer of a trade ring was overtaken by its producer.
+    pub overtaken: u64,
+}
+
+/// Draws the table of the symbols watched, the selected row highlighted, at
+/// `now_ms`, and the status line under it.
+pub fn draw(frame: &mut Frame<'_>, watch: &Watch, table_state: &mut TableState, status: &Status, now_ms: u64) {
+    let [table_area, status_area] = Layout::vertical([Constraint::Min(0), Constraint::Length(1)]).areas(frame.area());
+
+    let header = Row::new(COLUMNS.iter().map(|(name, _)| Cell::from(*name)))
+        .style(Style::default().add_modifier(Modifier::BOLD));
+    let rows = watch.rows().iter().map(|row| {
+        let top = row.top;
+        let (side, side_style) = match row.last_trade {
+            Some(trade) if trade.buyer_maker => ("S", Style::default().fg(Color::Red)),
+            Some(_) => ("B", Style::default().fg(Color::Green)),
+            None => (MISSING, Style::default()),
+        };
+        Row::new([
+            Cell::from(row.symbol.clone()),
+            Cell::from(value(top.map(|top| top.bid_qty))),
+            Cell::from(value(top.map(|top| top.bid_price))).style(Style::default().fg(Color::Green)),
+            Cell::from(value(top.map(|top| top.ask_price))).style(Style::default().fg(Color::Red)),
+            Cell::from(value(top.map(|top| top.ask_qty))),
+            Cell::from(value(row.spread())),
+            Cell::from(row.spread_bps().map_or(MISSING.to_string(), |bps| format!("{:.2}", bps))),
+            Cell::from(value(row.last_trade.map(|trade| trade.price))).style(side_style),
+            Cell::from(value(row.last_trade.map(|trade| trade.qty))),
+            Cell::from(side).style(side_style),
+            Cell::from(row.last_trade.map_or(MISSING.to_string(), |trade| {
+                format_age(now_ms.saturating_sub(trade.trade_time_ms))
+            })),
+            Cell::from(rate(row.rate(RateKind::Top))),
+            Cell::from(rate(row.rate(RateKind::Trade).or_else(|| row.rate(RateKind::AggTrade)))),
+        ])
+    });
+    let table = Table::new(rows, COLUMNS.iter().map(|(_, width)| Constraint::Length(*width)))
+        .header(header)
+        .block(Block::bordered().title(format!(" ctl-top: {} symbols ", watch.rows().len())))
+        .row_highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+    frame.render_stateful_widget(table, table_area, table_state);
+
+    let line = format!(
+        " q quit, \u{2191}/\u{2193} select | last trades of the {} rings | consumer overtaken {} times",
+        status.trade_source, status.overtaken
+    );
+    frame.render_widget(Paragraph::new(Line::from(line)), status_area);
+}
+
+/// Formats a price or a quantity, as published.
+fn value(value: Option<f64>) -> String {
+    value.map_or(MISSING.to_string(), |value| value.to_string())
+}
+
+/// Formats a message rate, per second.
+fn rate(rate: Option<f64>) -> String {
+    rate.map_or(MISSING.to_string(), |rate| format!("{:.0}", rate))
+}
+
+/// Formats the age of a trade, in its largest unit.
+fn format_age(age_ms: u64) -> String {
+    match age_ms {
+        0..1_000 => format!("{}ms", age_ms),
+        1_000..60_000 => format!("{:.1}s", age_ms as f64 / 1_000.0),
+        60_000..3_600_000 => format!("{}m", age_ms / 60_000),
+        _ => format!("{}h", age_ms / 3_600_000),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format() {
+        assert_eq!(format_age(350), "350ms");
+        assert_eq!(format_age(4_300), "4.3s");
+        assert_eq!(format_age(180_000), "3m");
+        assert_eq!(format_age(7_200_000), "2h");
+        assert_eq!((value(Some(0.001)), value(None)), ("0.001".to_string(), MISSING.to_string()));
+        assert_eq!(rate(Some(12.6)), "13");
+    }
+}
@@ -0,0 +1,215 @@
+//! The state of the symbols watched: their best bid/ask read from the
+//! last-value Top region, their last trade consumed from the rings, and the
+//! message rates of their rings.
+
+use std::time::Instant;
+
+use ctl_feed::{slot_payload, LastTopRegion, TopSnapshot};
+use hashbrown::HashMap;
+use serde::Deserialize;
+
+use crate::RateMeter;
+
+/// The ring kinds of a symbol whose message rates are shown.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RateKind {
+    /// The Top ring.
+    Top,
+    /// The trade ring.
+    Trade,
+    /// The aggregated trade ring.
+    AggTrade,
+}
+
+/// The last trade of a symbol.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LastTrade {
+    /// The price.
+    pub price: f64,
+    /// The quantity.
+    pub qty: f64,
+    /// Whether the buyer was the maker, i.e. the trade was a sell.
+    pub buyer_maker: bool,
+    /// The time of the trade, in milliseconds.
+    pub trade_time_ms: u64,
+}
+
+/// The fields of a trade or an aggregated trade payload shown, common to both.
+/// https://github.com/binance/binance-spot-api-docs/blob/master/web-socket-streams.md#trade-streams
+#[derive(Deserialize)]
+struct TradePayload<'a> {
+    #[serde(rename = "s")]
+    symbol: &'a str,
+    #[serde(rename = "p")]
+    price: &'a str,
+    #[serde(rename = "q")]
+    qty: &'a str,
+    #[serde(rename = "m")]
+    buyer_maker: bool,
+    #[serde(rename = "T")]
+    trade_time_ms: u64,
+}
+
+/// Decodes a trade or an aggregated trade payload, returning its symbol and trade.
+pub fn decode_trade(payload: &[u8]) -> Option<(&str, LastTrade)> {
+    let trade: TradePayload<'_> = serde_json::from_slice(slot_payload(payload)).ok()?;
+    let last = LastTrade {
+        price: trade.price.parse().ok()?,
+        qty: trade.qty.parse().ok()?,
+        buyer_maker: trade.buyer_maker,
+        trade_time_ms: trade.trade_time_ms,
+    };
+    Some((trade.symbol, last))
+}
+
+/// A symbol watched.
+#[derive(Debug, Clone)]
+pub struct SymbolRow {
+    /// The symbol (e.g. `BTCUSDT`).
+    pub symbol: String,
+    /// The symbol ID, its slot in the last-value Top region.
+    pub symbol_id: u32,
+    /// The best bid/ask, `None` until first published.
+    pub top: Option<TopSnapshot>,
+    /// The last trade, `None` until one is consumed.
+    pub last_trade: Option<LastTrade>,
+    /// The metrics index and the message rate of each ring of the symbol.
+    rates: Vec<(RateKind, usize, RateMeter)>,
+}
+
+impl SymbolRow {
+    /// Returns the spread, `None` without a two-sided Top.
+    pub fn spread(&self) -> Option<f64> {
+        let top = self.top?;
+        (top.bid_price > 0.0 && top.ask_price > 0.0).then(|| top.ask_price - top.bid_price)
+    }
+
+    /// Returns the spread in basis points of the mid price.
+    pub fn spread_bps(&self) -> Option<f64> {
+        let top = self.top?;
+        let spread = self.spread()?;
+        Some(spread / ((top.bid_price + top.ask_price) / 2.0) * 10_000.0)
+    }
+
+    /// Returns the message rate of a ring of the symbol, `None` if the ring isn't configured.
+    pub fn rate(&self, kind: RateKind) -> Option<f64> {
+        self.rates.iter().find(|(k, _, _)| *k == kind).map(|(_, _, rate)| rate.per_sec())
+    }
+}
+
+/// The symbols watched, in the order they were added.
+#[derive(Debug, Default)]
+pub struct Watch {
+    rows: Vec<SymbolRow>,
+    /// The index of the rows by symbol.
+    index: HashMap<String, usize>,
+}
+
+impl Watch {
+    /// Creates an empty watch.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a symbol, if not watched yet.
+    pub fn add_symbol(&mut self, symbol: &str, symbol_id: u32) {
+        if self.index.contains_key(symbol) {
+            return;
+        }
+        self.index.insert(symbol.to_string(), self.rows.len());
+        self.rows.push(SymbolRow {
+            symbol: symbol.to_string(),
+            symbol_id,
+            top: None,
+            last_trade: None,
+            rates: Vec::new(),
+        });
+    }
+
+    /// Adds a ring of a watched symbol, by its index in the metrics region,
+    /// to measure its message rate.
+    pub fn add_ring(&mut self, symbol: &str, kind: RateKind, ring_index: usize) {
+        if let Some(&row) = self.index.get(symbol) {
+            self.rows[row].rates.push((kind, ring_index, RateMeter::default()));
+        }
+    }
+
+    /// Records a trade or an aggregated trade payload as the last trade of its
+    /// symbol. Returns `false` if the payload isn't a trade of a watched symbol.
+    pub fn record_trade(&mut self, payload: &[u8]) -> bool {
+        let Some((symbol, trade)) = decode_trade(payload) else {
+            return false;
+        };
+        let Some(&row) = self.index.get(symbol) else {
+            return false;
+        };
+        self.rows[row].last_trade = Some(trade);
+        true
+    }
+
+    /// Reads the best bid/ask of the symbols from the last-value Top region.
+    pub fn refresh_tops(&mut self, last_top: &LastTopRegion) {
+        for row in &mut self.rows {
+            if let Some(top) = last_top.read(row.symbol_id) {
+                row.top = Some(top);
+            }
+        }
+    }
+
+    /// Samples the message rates of the rings, from the number of messages
+    /// published to each (`head` of the ring at an index of the metrics region).
+    pub fn sample_rates(&mut self, head: impl Fn(usize) -> u64, now: Instant) {
+        for row in &mut self.rows {
+            for (_, ring_index, rate) in &mut row.rates {
+                rate.sample(head(*ring_index), now);
+            }
+        }
+    }
+
+    /// Returns the symbols watched.
+    pub fn rows(&self) -> &[SymbolRow] {
+        &self.rows
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn test_watch() {
+        let mut watch = Watch::new();
+        watch.add_symbol("BNBBTC", 3);
+        watch.add_symbol("BNBBTC", 3);
+        watch.add_ring("BNBBTC", RateKind::Trade, 7);
+        assert_eq!(watch.rows().len(), 1);
+
+        let trade = br#"{"e":"trade","s":"BNBBTC","t":12345,"p":"0.001","q":"100","T":123456785,"m":true}"#;
+        assert!(watch.record_trade(trade));
+        let agg = br#"{"s":"ETHBTC","a":26129,"p":"0.016","q":"4.7","f":27781,"l":27783,"m":false,"T":1}"#;
+        assert!(!watch.record_trade(agg));
+        let last = watch.rows()[0].last_trade.unwrap();
+        assert_eq!((last.price, last.qty, last.buyer_maker), (0.001, 100.0, true));
+
+        let start = Instant::now();
+        watch.sample_rates(|index| index as u64, start);
+        watch.sample_rates(|index| index as u64 + 20, start + Duration::from_secs(2));
+        let row = &watch.rows()[0];
+        assert_eq!((row.rate(RateKind::Trade), row.rate(RateKind::Top)), (Some(10.0), None));
+        assert_eq!(row.spread(), None);
+    }
+
+    #[test]
+    fn test_spread() {
+        let mut watch = Watch::new();
+        watch.add_symbol("BTCUSDT", 1);
+        let top = TopSnapshot { update_id: 1, bid_price: 99.0, bid_qty: 1.0, ask_price: 101.0, ask_qty: 1.0 };
+        watch.rows[0].top = Some(top);
+        assert_eq!((watch.rows()[0].spread(), watch.rows()[0].spread_bps()), (Some(2.0), Some(200.0)));
+
+        watch.rows[0].top = Some(TopSnapshot { ask_price: 0.0, ..top });
+        assert_eq!(watch.rows()[0].spread_bps(), None);
+    }
+}
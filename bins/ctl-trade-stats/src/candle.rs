@@ -0,0 +1,167 @@
+use ctl_feed::{CandleMessage, EventType, MessageHeader};
+
+use crate::TradeEvent;
+
+/// Builds the OHLCV bars of a symbol at a fixed interval from its trades.
+///
+/// Bars are aligned to multiples of the interval since the epoch. A bar is
+/// completed by the first trade of a later bar, or by `flush` once its close
+/// time has passed. Intervals without trades produce no bar.
+#[derive(Debug, Clone)]
+pub struct CandleBuilder {
+    /// The symbol ID.
+    symbol_id: u32,
+    /// The bar interval, in milliseconds.
+    interval_ms: u64,
+    /// The bar being built, if any trade was received for it.
+    current: Option<CandleMessage>,
+}
+
+impl CandleBuilder {
+    /// Creates a builder of `interval_ms` bars for a symbol.
+    ///
+    /// # Panics
+    /// Panics if `interval_ms` is zero.
+    pub fn new(symbol_id: u32, interval_ms: u64) -> Self {
+        assert!(interval_ms > 0, "candle interval must be greater than 0");
+        Self { symbol_id, interval_ms, current: None }
+    }
+
+    /// Returns the bar interval, in milliseconds.
+    pub fn interval_ms(&self) -> u64 {
+        self.interval_ms
+    }
+
+    /// Returns the bar being built, if any.
+    pub fn current(&self) -> Option<&CandleMessage> {
+        self.current.as_ref()
+    }
+
+    /// Adds a trade, returning the previous bar if the trade completed it.
+    /// Trades older than the bar being built are ignored.
+    pub fn update(&mut self, trade: TradeEvent) -> Option<CandleMessage> {
+        let open_time_ms = trade.trade_time_ms - trade.trade_time_ms % self.interval_ms;
+
+        match &mut self.current {
+            Some(bar) if bar.open_time_ms == open_time_ms => {
+                bar.high = bar.high.max(trade.price);
+                bar.low = bar.low.min(trade.price);
+                bar.close = trade.price;
+                bar.volume += trade.qty;
+                bar.notional += trade.price * trade.qty;
+                bar.trade_count += 1;
+                None
+            }
+            Some(bar) if bar.open_time_ms > open_time_ms => None,
+            _ => self.current.replace(self.open(open_time_ms, trade)),
+        }
+    }
+
+    /// Adds a trade fetched after the fact, returning the previous bar if the
+    /// trade completed it. A trade of the bar being built is merged into it
+    /// without moving its close; the completed bars, already published, are
+    /// left as they were.
+    pub fn backfill(&mut self, trade: TradeEvent) -> Option<CandleMessage> {
+        let open_time_ms = trade.trade_time_ms - trade.trade_time_ms % self.interval_ms;
+
+        match &mut self.current {
+            Some(bar) if bar.open_time_ms == open_time_ms => {
+                bar.high = bar.high.max(trade.price);
+                bar.low = bar.low.min(trade.price);
+                bar.volume += trade.qty;
+                bar.notional += trade.price * trade.qty;
+                bar.trade_count += 1;
+                None
+            }
+            _ => self.update(trade),
+        }
+    }
+
+    /// Completes the bar being built if its close time is at or before `now_ms`.
+    pub fn flush(&mut self, now_ms: u64) -> Option<CandleMessage> {
+        match &self.current {
+            Some(bar) if bar.close_time_ms <= now_ms => self.current.take(),
+            _ => None,
+        }
+    }
+
+    /// Opens a bar at `open_time_ms` with its first trade.
+    fn open(&self, open_time_ms: u64, trade: TradeEvent) -> CandleMessage {
+        CandleMessage {
+            header: MessageHeader::new(EventType::Candle, self.symbol_id),
+            symbol_id: self.symbol_id,
+            interval_ms: self.interval_ms,
+            open_time_ms,
+            close_time_ms: open_time_ms + self.interval_ms,
+            open: trade.price,
+            high: trade.price,
+            low: trade.price,
+            close: trade.price,
+            volume: trade.qty,
+            notional: trade.price * trade.qty,
+            trade_count: 1,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trade(trade_time_ms: u64, price: f64, qty: f64) -> TradeEvent {
+        TradeEvent { trade_time_ms, price, qty }
+    }
+
+    #[test]
+    fn test_builds_ohlcv() {
+        let mut builder = CandleBuilder::new(1, 1_000);
+        assert_eq!(builder.update(trade(1_100, 10.0, 1.0)), None);
+        assert_eq!(builder.update(trade(1_200, 12.0, 2.0)), None);
+        assert_eq!(builder.update(trade(1_300, 9.0, 1.0)), None);
+        assert_eq!(builder.update(trade(1_999, 11.0, 1.0)), None);
+
+        let bar = builder.update(trade(2_000, 13.0, 1.0)).unwrap();
+        assert_eq!(bar.symbol_id, 1);
+        assert_eq!((bar.open_time_ms, bar.close_time_ms), (1_000, 2_000));
+        assert_eq!((bar.open, bar.high, bar.low, bar.close), (10.0, 12.0, 9.0, 11.0));
+        assert_eq!(bar.volume, 5.0);
+        assert_eq!(bar.notional, 54.0);
+        assert_eq!(bar.trade_count, 4);
+
+        assert_eq!(builder.current().unwrap().open_time_ms, 2_000);
+    }
+
+    #[test]
+    fn test_ignores_late_trades() {
+        let mut builder = CandleBuilder::new(1, 1_000);
+        builder.update(trade(2_100, 10.0, 1.0));
+        assert_eq!(builder.update(trade(1_900, 50.0, 1.0)), None);
+        assert_eq!(builder.current().unwrap().high, 10.0);
+    }
+
+    #[test]
+    fn test_backfill_keeps_close() {
+        let mut builder = CandleBuilder::new(1, 1_000);
+        builder.update(trade(1_100, 10.0, 1.0));
+        builder.update(trade(1_800, 11.0, 1.0));
+        assert_eq!(builder.backfill(trade(1_500, 14.0, 2.0)), None);
+        // The completed bars are left as they were
+        assert_eq!(builder.backfill(trade(900, 1.0, 1.0)), None);
+
+        let bar = builder.current().unwrap();
+        assert_eq!((bar.open, bar.high, bar.low, bar.close), (10.0, 14.0, 10.0, 11.0));
+        assert_eq!((bar.volume, bar.trade_count), (4.0, 3));
+    }
+
+    #[test]
+    fn test_flush_after_close() {
+        let mut builder = CandleBuilder::new(1, 60_000);
+        builder.update(trade(61_000, 10.0, 1.0));
+        assert_eq!(builder.flush(119_999), None);
+
+        let bar = builder.flush(120_000).unwrap();
+        assert_eq!(bar.open_time_ms, 60_000);
+        assert_eq!(builder.current(), None);
+        assert_eq!(builder.flush(200_000), None);
+    }
+}
@@ -0,0 +1,86 @@
+//! Configuration module for the trade statistics.
+//!
+//! This module provides the YAML parser and validation for the candle
+//! configuration defined in `configs/market-data/candles.yaml`.
+
+use std::fs;
+use std::path::Path;
+
+use hashbrown::HashSet;
+use serde::Deserialize;
+
+use crate::CandleConfigError;
+
+/// The intervals of the candles built locally from the trade stream.
+#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+pub struct CandleConfig {
+    /// The bar intervals, in milliseconds.
+    pub intervals_ms: Vec<u64>,
+}
+
+impl CandleConfig {
+    /// Parses the candle configuration from a YAML file.
+    ///
+    /// # Errors
+    /// Returns an error if the file cannot be read, parsed, or fails validation.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, CandleConfigError> {
+        let content = fs::read_to_string(path)?;
+        Self::from_str(&content)
+    }
+
+    /// Parses the candle configuration from a YAML string.
+    ///
+    /// # Errors
+    /// Returns an error if the YAML cannot be parsed or fails validation.
+    pub fn from_str(content: &str) -> Result<Self, CandleConfigError> {
+        let config: Self = serde_yaml::from_str(content)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Validates the candle configuration.
+    fn validate(&self) -> Result<(), CandleConfigError> {
+        if self.intervals_ms.is_empty() {
+            return Err(CandleConfigError::ValidationError(
+                "At least one candle interval must be configured".to_string(),
+            ));
+        }
+
+        let mut seen = HashSet::new();
+        for &interval_ms in &self.intervals_ms {
+            if interval_ms == 0 {
+                return Err(CandleConfigError::ValidationError(
+                    "Candle interval must be greater than 0".to_string(),
+                ));
+            }
+            if !seen.insert(interval_ms) {
+                return Err(CandleConfigError::ValidationError(format!(
+                    "Duplicate candle interval {}ms",
+                    interval_ms
+                )));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_config() {
+        let config = CandleConfig::from_str("intervals_ms: [1000, 60000]").unwrap();
+        assert_eq!(config.intervals_ms, vec![1000, 60000]);
+    }
+
+    #[test]
+    fn test_invalid_intervals() {
+        assert!(CandleConfig::from_str("intervals_ms: []").is_err());
+        assert!(CandleConfig::from_str("intervals_ms: [0]").is_err());
+
+        let result = CandleConfig::from_str("intervals_ms: [1000, 1000]");
+        assert!(result.unwrap_err().to_string().contains("Duplicate"));
+    }
+}
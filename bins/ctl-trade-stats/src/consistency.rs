@@ -0,0 +1,245 @@
+//! Cross-feed consistency of the trades.
+//!
+//! A trade executes against the book, so its price lies within the best
+//! bid/ask quoted when it printed. A trade far outside the concurrent Top (read
+//! from the last-value cache) or the book builder's levels is an impossible
+//! print, usually meaning the book desynced from the exchange or a ring carries
+//! the messages of another subscription.
+//!
+//! The quotes and the trades arrive on different streams, so a trade may print
+//! while the quotes already moved: the check tolerates a configurable distance
+//! outside the spread before flagging a print.
+
+use std::fs;
+use std::path::Path;
+
+use ctl_book::BookSnapshot;
+use ctl_feed::TopSnapshot;
+use serde::Deserialize;
+
+use crate::ConsistencyConfigError;
+
+/// Default distance of a trade outside the spread before it is flagged, in basis points.
+const DEFAULT_MAX_OUTSIDE_BPS: f64 = 50.0;
+
+fn default_max_outside_bps() -> f64 {
+    DEFAULT_MAX_OUTSIDE_BPS
+}
+
+fn default_check_book() -> bool {
+    true
+}
+
+/// The configuration of the consistency checker, disabled by default.
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+pub struct ConsistencyConfig {
+    /// Whether the trades are checked.
+    #[serde(default)]
+    pub enabled: bool,
+    /// Distance of a trade outside the spread before it is flagged, in basis points.
+    #[serde(default = "default_max_outside_bps")]
+    pub max_outside_bps: f64,
+    /// Whether the trades are also checked against the book builder's levels,
+    /// for the symbols with a book snapshot region.
+    #[serde(default = "default_check_book")]
+    pub check_book: bool,
+}
+
+impl Default for ConsistencyConfig {
+    fn default() -> Self {
+        Self { enabled: false, max_outside_bps: DEFAULT_MAX_OUTSIDE_BPS, check_book: true }
+    }
+}
+
+impl ConsistencyConfig {
+    /// Parses the consistency configuration from a YAML file.
+    ///
+    /// # Errors
+    /// Returns an error if the file cannot be read, parsed, or fails validation.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, ConsistencyConfigError> {
+        let content = fs::read_to_string(path)?;
+        Self::from_str(&content)
+    }
+
+    /// Parses the consistency configuration from a YAML string.
+    ///
+    /// # Errors
+    /// Returns an error if the YAML cannot be parsed or fails validation.
+    pub fn from_str(content: &str) -> Result<Self, ConsistencyConfigError> {
+        let config: Self = serde_yaml::from_str(content)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Validates the consistency configuration.
+    fn validate(&self) -> Result<(), ConsistencyConfigError> {
+        if !self.max_outside_bps.is_finite() || self.max_outside_bps < 0.0 {
+            return Err(ConsistencyConfigError::ValidationError(
+                "'max_outside_bps' must be a non-negative number".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// The quotes a trade was checked against.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuoteSource {
+    /// The last-value Top.
+    Top,
+    /// The book builder's levels.
+    Book,
+}
+
+/// A trade printing outside the spread of its concurrent quotes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ImpossiblePrint {
+    /// The quotes the trade was checked against.
+    pub source: QuoteSource,
+    /// The trade price.
+    pub price: f64,
+    /// The best bid.
+    pub bid: f64,
+    /// The best ask.
+    pub ask: f64,
+    /// The distance of the trade outside the spread, in basis points of the
+    /// nearest side; infinite for crossed quotes.
+    pub outside_bps: f64,
+}
+
+impl ImpossiblePrint {
+    /// Returns true if the quotes themselves were crossed (bid above ask).
+    pub fn is_crossed(&self) -> bool {
+        self.bid > self.ask
+    }
+}
+
+/// Checks the trades against the concurrent quotes.
+#[derive(Debug, Clone)]
+pub struct ConsistencyChecker {
+    /// The configuration.
+    config: ConsistencyConfig,
+    /// Number of trades checked.
+    checked: u64,
+    /// Number of impossible prints flagged.
+    flagged: u64,
+}
+
+impl ConsistencyChecker {
+    /// Creates a checker with no trade checked.
+    pub fn new(config: ConsistencyConfig) -> Self {
+        Self { config, checked: 0, flagged: 0 }
+    }
+
+    /// Returns the configuration.
+    pub fn config(&self) -> &ConsistencyConfig {
+        &self.config
+    }
+
+    /// Returns the number of trades checked and of impossible prints flagged.
+    pub fn counts(&self) -> (u64, u64) {
+        (self.checked, self.flagged)
+    }
+
+    /// Checks a trade price against the latest Top and the book snapshot of its
+    /// symbol, returning the first impossible print.
+    ///
+    /// The stale book snapshots, being resynced, are skipped, as are the sides
+    /// missing from the quotes.
+    pub fn check(&mut self, price: f64, top: Option<&TopSnapshot>, book: Option<&BookSnapshot>) -> Option<ImpossiblePrint> {
+        self.checked += 1;
+        let top_quotes = top.map(|top| (QuoteSource::Top, top.bid_price, top.ask_price));
+        let book_quotes = book
+            .filter(|book| self.config.check_book && !book.is_stale())
+            .and_then(|book| Some((QuoteSource::Book, book.best_bid()?.price, book.best_ask()?.price)));
+
+        let print = top_quotes
+            .into_iter()
+            .chain(book_quotes)
+            .find_map(|(source, bid, ask)| self.check_quotes(source, price, bid, ask));
+        if print.is_some() {
+            self.flagged += 1;
+        }
+        print
+    }
+
+    /// Checks a trade price against a best bid/ask, ignoring the empty quotes.
+    fn check_quotes(&self, source: QuoteSource, price: f64, bid: f64, ask: f64) -> Option<ImpossiblePrint> {
+        if bid <= 0.0 || ask <= 0.0 {
+            return None;
+        }
+        let outside_bps = if bid > ask {
+            f64::INFINITY
+        } else if price < bid {
+            (bid - price) / bid * 10_000.0
+        } else if price > ask {
+            (price - ask) / ask * 10_000.0
+        } else {
+            return None;
+        };
+        (outside_bps > self.config.max_outside_bps).then_some(ImpossiblePrint { source, price, bid, ask, outside_bps })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use ctl_book::{BookSnapshotRegion, Level};
+
+    fn top(bid_price: f64, ask_price: f64) -> TopSnapshot {
+        TopSnapshot { update_id: 1, bid_price, bid_qty: 1.0, ask_price, ask_qty: 1.0 }
+    }
+
+    fn enabled() -> ConsistencyConfig {
+        ConsistencyConfig { enabled: true, max_outside_bps: 10.0, check_book: true }
+    }
+
+    #[test]
+    fn test_parse_config() {
+        let config = ConsistencyConfig::from_str("enabled: true\nmax_outside_bps: 25").unwrap();
+        assert!(config.enabled);
+        assert_eq!(config.max_outside_bps, 25.0);
+        assert!(config.check_book);
+        assert_eq!(ConsistencyConfig::from_str("{}").unwrap(), ConsistencyConfig::default());
+        assert!(ConsistencyConfig::from_str("max_outside_bps: -1").is_err());
+    }
+
+    #[test]
+    fn test_trade_against_top() {
+        let mut checker = ConsistencyChecker::new(enabled());
+        let quotes = top(100.0, 100.1);
+        assert_eq!(checker.check(100.05, Some(&quotes), None), None);
+        // Within the tolerance of the quotes having moved
+        assert_eq!(checker.check(100.15, Some(&quotes), None), None);
+
+        let print = checker.check(101.0, Some(&quotes), None).unwrap();
+        assert_eq!(print.source, QuoteSource::Top);
+        assert!((print.outside_bps - 89.91).abs() < 0.01);
+        assert!(checker.check(98.0, Some(&quotes), None).is_some());
+        assert_eq!(checker.counts(), (4, 2));
+
+        // Crossed quotes flag every trade
+        assert!(checker.check(100.05, Some(&top(100.2, 100.1)), None).unwrap().is_crossed());
+        assert_eq!(checker.check(100.05, None, None), None);
+    }
+
+    #[test]
+    fn test_trade_against_book() {
+        let region = BookSnapshotRegion::default();
+        region.write(1, &[Level { price: 99.0, qty: 1.0 }], &[Level { price: 99.1, qty: 1.0 }]);
+        let book = region.read().unwrap();
+
+        // The Top agrees with the trade, the desynced book doesn't
+        let mut checker = ConsistencyChecker::new(enabled());
+        let print = checker.check(100.05, Some(&top(100.0, 100.1)), Some(&book)).unwrap();
+        assert_eq!(print.source, QuoteSource::Book);
+
+        // The book being resynced is skipped
+        region.mark_stale();
+        assert_eq!(checker.check(100.05, None, Some(&region.read().unwrap())), None);
+
+        let mut checker = ConsistencyChecker::new(ConsistencyConfig { check_book: false, ..enabled() });
+        assert_eq!(checker.check(100.05, None, Some(&book)), None);
+    }
+}
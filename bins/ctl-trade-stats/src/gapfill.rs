@@ -0,0 +1,171 @@
+//! Gap fill of the missed trades from the REST API.
+//!
+//! The trades missed by trade-stats, lost in the reconnect window of a feed or
+//! overwritten once its consumer sped past, would leave the statistics and
+//! candles of their symbol wrong for good. With the gap fill enabled, each gap
+//! reported by the `TradeGapDetector` is fetched from the historical trades
+//! (or the aggregate trades) endpoint, off the hot path, and the trades are
+//! injected into the trade ring of their symbol flagged as backfill, as the
+//! payloads of the stream. The other consumers of the ring see them too, and
+//! skip them (`MessageHeader::is_backfill`) or deduplicate them against the
+//! trades they received live.
+
+use std::fs;
+use std::path::Path;
+
+use ctl_feed::{EventType, TradeGap};
+use ctl_rest::{RestClient, RestError, BINANCE_REST_ENDPOINT, MAX_TRADES_LIMIT};
+use serde::Deserialize;
+
+use crate::GapFillConfigError;
+
+/// Default number of trades of a gap fetched at most, the latest ones.
+const DEFAULT_MAX_TRADES: u64 = 1_000;
+
+fn default_endpoint() -> String {
+    BINANCE_REST_ENDPOINT.to_string()
+}
+
+fn default_max_trades() -> u64 {
+    DEFAULT_MAX_TRADES
+}
+
+fn default_limit() -> u32 {
+    MAX_TRADES_LIMIT
+}
+
+/// The configuration of the gap fill, disabled by default.
+#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+pub struct GapFillConfig {
+    /// Whether the missed trades are fetched.
+    #[serde(default)]
+    pub enabled: bool,
+    /// Base URL of the REST API serving the trades.
+    #[serde(default = "default_endpoint")]
+    pub endpoint: String,
+    /// Number of trades of a gap fetched at most, the latest ones, the older
+    /// ones having the least weight in the statistics.
+    #[serde(default = "default_max_trades")]
+    pub max_trades: u64,
+    /// Number of trades fetched per request.
+    #[serde(default = "default_limit")]
+    pub limit: u32,
+}
+
+impl Default for GapFillConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            endpoint: default_endpoint(),
+            max_trades: DEFAULT_MAX_TRADES,
+            limit: MAX_TRADES_LIMIT,
+        }
+    }
+}
+
+impl GapFillConfig {
+    /// Parses the gap fill configuration from a YAML file.
+    ///
+    /// # Errors
+    /// Returns an error if the file cannot be read, parsed, or fails validation.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, GapFillConfigError> {
+        let content = fs::read_to_string(path)?;
+        Self::from_str(&content)
+    }
+
+    /// Parses the gap fill configuration from a YAML string.
+    ///
+    /// # Errors
+    /// Returns an error if the YAML cannot be parsed or fails validation.
+    pub fn from_str(content: &str) -> Result<Self, GapFillConfigError> {
+        let config: Self = serde_yaml::from_str(content)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Validates the gap fill configuration.
+    fn validate(&self) -> Result<(), GapFillConfigError> {
+        if self.endpoint.is_empty() {
+            return Err(GapFillConfigError::ValidationError("'endpoint' cannot be empty".to_string()));
+        }
+        if self.max_trades == 0 {
+            return Err(GapFillConfigError::ValidationError("'max_trades' must be greater than 0".to_string()));
+        }
+        if self.limit == 0 || self.limit > MAX_TRADES_LIMIT {
+            return Err(GapFillConfigError::ValidationError(format!(
+                "'limit' must be between 1 and {}",
+                MAX_TRADES_LIMIT
+            )));
+        }
+        Ok(())
+    }
+
+    /// Returns the range of IDs of a gap fetched, its latest `max_trades` trades.
+    pub fn fetched_range(&self, gap: &TradeGap) -> (u64, u64) {
+        (gap.from_id.max((gap.to_id + 1).saturating_sub(self.max_trades)), gap.to_id)
+    }
+}
+
+/// Fetches the trades of a gap of `symbol`, returning them oldest first as the
+/// payloads of its stream.
+///
+/// LATENCY: SLOW_PATH
+pub fn fetch_gap(
+    client: &RestClient,
+    config: &GapFillConfig,
+    symbol: &str,
+    gap: &TradeGap,
+) -> Result<Vec<String>, RestError> {
+    let (mut from_id, to_id) = config.fetched_range(gap);
+    let mut payloads = Vec::new();
+    while from_id <= to_id {
+        let limit = config.limit.min((to_id - from_id + 1).min(u32::MAX as u64) as u32);
+        let page: Vec<(u64, String)> = match gap.event_type {
+            EventType::AggTrade => client
+                .agg_trades(symbol, from_id, limit)?
+                .into_iter()
+                .map(|trade| (trade.id, trade.to_stream_payload(symbol)))
+                .collect(),
+            _ => client
+                .historical_trades(symbol, from_id, limit)?
+                .into_iter()
+                .map(|trade| (trade.id, trade.to_stream_payload(symbol)))
+                .collect(),
+        };
+        let Some(&(last_id, _)) = page.last() else {
+            // Not yet served, or no longer
+            break;
+        };
+        payloads.extend(page.into_iter().filter(|(id, _)| *id <= to_id).map(|(_, payload)| payload));
+        from_id = last_id + 1;
+    }
+    Ok(payloads)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_config() {
+        let config = GapFillConfig::from_str("enabled: true\nmax_trades: 500").unwrap();
+        assert!(config.enabled);
+        assert_eq!(config.max_trades, 500);
+        assert_eq!(config.limit, MAX_TRADES_LIMIT);
+        assert_eq!(config.endpoint, BINANCE_REST_ENDPOINT);
+        assert_eq!(GapFillConfig::from_str("{}").unwrap(), GapFillConfig::default());
+
+        assert!(GapFillConfig::from_str("max_trades: 0").is_err());
+        assert!(GapFillConfig::from_str("limit: 1001").is_err());
+        assert!(GapFillConfig::from_str("endpoint: ''").is_err());
+    }
+
+    #[test]
+    fn test_fetched_range() {
+        let config = GapFillConfig { max_trades: 100, ..Default::default() };
+        let gap = |from_id, to_id| TradeGap { symbol_id: 1, event_type: EventType::Trade, from_id, to_id };
+        assert_eq!(config.fetched_range(&gap(102, 104)), (102, 104));
+        // The latest trades of a larger gap
+        assert_eq!(config.fetched_range(&gap(1, 1_000)), (901, 1_000));
+    }
+}
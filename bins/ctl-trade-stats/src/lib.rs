@@ -0,0 +1,26 @@
+//! Rolling trade statistics and candles for Binance Spot.
+//!
+//! Aggregates the trades of the `TRADE_{symbol_id}_PS` rings into rolling-window
+//! statistics per symbol (VWAP, notional volume, trade count), published to the
+//! `STATS_{symbol_id}_PS` rings for strategies and risk checks, and into OHLCV
+//! bars at the configured intervals, published to the `KLINE_{symbol_id}_PS` rings.
+//!
+//! Optionally, the trades are cross-checked against the concurrent Top and book
+//! levels of their symbol, flagging the impossible prints, and the trades missed
+//! fetched from the REST API to fill the gaps of the statistics and candles.
+
+mod candle;
+mod config;
+mod consistency;
+mod errors;
+mod gapfill;
+mod trade;
+mod window;
+
+pub use candle::CandleBuilder;
+pub use config::CandleConfig;
+pub use consistency::{ConsistencyChecker, ConsistencyConfig, ImpossiblePrint, QuoteSource};
+pub use errors::{CandleConfigError, ConsistencyConfigError, GapFillConfigError};
+pub use gapfill::{fetch_gap, GapFillConfig};
+pub use trade::TradeEvent;
+pub use window::{RollingWindow, TradeStats};
@@ -0,0 +1,621 @@
+//! Rolling Trade Statistics Publisher for Binance Spot.
+//!
+//! This binary connects as a DPDK secondary process, consumes the
+//! `TRADE_{symbol_id}_PS` rings published by ctl-md-handler and publishes the
+//! rolling-window statistics of each symbol to its `STATS_{symbol_id}_PS` ring
+//! and its locally built candles to its `KLINE_{symbol_id}_PS` ring, all created
+//! by ctl-resource-manager.
+//!
+//! The symbols of an aggregated set share the rings named after the first symbol
+//! of the set, their trades demultiplexed by the symbol IDs of the message headers.
+//!
+//! With the consistency checks enabled, each trade is also checked against the
+//! latest Top of its symbol and the book builder's levels, the impossible
+//! prints raised on the alerts ring.
+//!
+//! With the gap fill enabled, the trades missed (lost in a reconnect window of
+//! the feed, or overwritten once sped past) are fetched from the REST API by a
+//! thread off the hot path, and injected into their trade ring flagged as
+//! backfill, to be consumed back into the statistics and candles.
+//!
+//! The trade rings are read through a `RingReader` each. Once overtaken by a
+//! producer, the statistics recover per `configs/overtaken.yaml`; the windows
+//! aren't conflated state to be read again, so a snapshot requested by
+//! `skip-and-request-snapshot` is a skip to the head, the trades missed left
+//! to the gap fill.
+//!
+//! The exit code tells the class of a failure (see `FatalKind`).
+
+use std::process::ExitCode;
+use std::sync::Arc;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use ctl_core::{
+    AlertKind, AlertMessage, AlertSeverity, Capability, Fatal, FatalError, FatalKind, LcoresConfig, OvertakenConfig,
+    OvertakenPolicy, Poller, PollingConfig, PollingPolicy, Preflight, RingKind, RingName, SchedulingConfig,
+    ALERTS_RING_NAME,
+};
+use ctl_book::{book_region_name, BookSnapshotRegion};
+use ctl_feed::{
+    dpdk_consume, fragment_count, CandleMessage, LastTopRegion, MediumTag, MessageHeader, MetricsRegion, RawMessage,
+    RingConsume, RingMetrics, RingRead, RingReader, TradeGap, TradeGapDetector, TradeStatsMessage,
+    LAST_TOP_REGION_NAME, MAX_FRAGMENTS, METRICS_REGION_NAME, RAW_MESSAGE_SIZE, STATS_WINDOWS_MS,
+};
+#[cfg(feature = "latency-histograms")]
+use ctl_feed::{LatencyRecorder, WakeLatencyRecorder};
+#[cfg(feature = "message-checksums")]
+use ctl_feed::Checksummed;
+use ctl_md_handler::{HwResourcesConfig, SymbolInfoConfig};
+use ctl_rest::{RestClient, WeightLedger, WEIGHT_LEDGER_REGION_NAME};
+use ctl_shm::ShmRegion;
+use ctl_time::now_ms;
+use ctl_trade_stats::{
+    fetch_gap, CandleBuilder, CandleConfig, ConsistencyChecker, ConsistencyConfig, GapFillConfig, ImpossiblePrint,
+    TradeEvent, TradeStats,
+};
+use dpdk::{DpdkEnvBuilder, DpdkProcessType, DpdkPubSubRing};
+use hashbrown::HashMap;
+use tracing::{info, warn};
+
+// Logging configuration, its levels reloaded on change
+const LOG_CONFIG_PATH: &str = "configs/logging.yaml";
+
+// Configuration file paths
+const MD_CONFIG_PATH: &str = "configs/market-data/hw-resources.yaml";
+const SYMBOL_INFO_PATH: &str = "configs/market-data/symbolinfo.yaml";
+const CANDLE_CONFIG_PATH: &str = "configs/market-data/candles.yaml";
+const CONSISTENCY_CONFIG_PATH: &str = "configs/market-data/consistency.yaml";
+const GAP_FILL_CONFIG_PATH: &str = "configs/market-data/gap-fill.yaml";
+
+// Interval at which candles are completed without a trade of the next bar
+const CANDLE_FLUSH_INTERVAL: Duration = Duration::from_millis(100);
+
+// Minimum interval between the impossible print alerts of a symbol, the others only counted
+const IMPOSSIBLE_PRINT_ALERT_INTERVAL: Duration = Duration::from_secs(10);
+
+// The lcore of trade-stats, unless configured, past the md-handler workers and the subscriber
+const LCORES_CONFIG_PATH: &str = "configs/lcores.yaml";
+const LCORES_COMPONENT: &str = "trade-stats";
+const DEFAULT_LCORE: u32 = 14;
+
+// Polling policy between passes without a trade, unless configured for this component
+const POLLING_CONFIG_PATH: &str = "configs/polling.yaml";
+const POLLING_COMPONENT: &str = "trade-stats";
+const DEFAULT_POLLING: PollingPolicy = PollingPolicy::BusyPoll;
+
+// Recovery once overtaken by a producer, unless configured for this component
+const OVERTAKEN_CONFIG_PATH: &str = "configs/overtaken.yaml";
+const OVERTAKEN_COMPONENT: &str = "trade-stats";
+const DEFAULT_OVERTAKEN: OvertakenPolicy = OvertakenPolicy::SkipToHead;
+
+// Scheduling of the threads of this component, unless configured left untouched
+const SCHEDULING_CONFIG_PATH: &str = "configs/scheduling.yaml";
+const SCHEDULING_COMPONENT: &str = "trade-stats";
+
+// Source of the alerts published to the alerts ring
+const ALERT_SOURCE: &str = "trade-stats";
+
+// The name of the consumer cursors of the trade rings
+const CONSUMER_NAME: &str = "trade-stats";
+
+// Latency group recording the publish times of the stats and kline rings
+#[cfg(feature = "latency-histograms")]
+const LATENCY_GROUP: &str = "trade-stats";
+
+/// The rings of a trade symbol, named by the convention `{KIND}_{symbol_id}_PS`,
+/// shared by the symbols of its set if aggregated.
+struct SymbolRings {
+    /// The symbol ID the rings are named after.
+    symbol_id: u32,
+    /// The symbol IDs carried by the rings.
+    symbol_ids: Vec<u32>,
+    /// The trade ring name.
+    trade_name: String,
+    /// The trade ring, consumed.
+    trade: DpdkPubSubRing<RawMessage>,
+    /// The slot size of the trade ring.
+    slot_size: usize,
+    /// The stats ring name.
+    stats_name: String,
+    /// The stats ring, published to.
+    stats: DpdkPubSubRing<TradeStatsMessage>,
+    /// The kline ring name.
+    kline_name: String,
+    /// The kline ring, published to.
+    kline: DpdkPubSubRing<CandleMessage>,
+}
+
+/// The consumer and metrics of the rings of a trade symbol, and the aggregates
+/// of the symbols they carry.
+struct RingStats<'a, C> {
+    /// The rings.
+    rings: &'a SymbolRings,
+    /// The consumer of the trade ring.
+    consumer: C,
+    /// Metrics of the trade ring.
+    trade_metrics: &'a RingMetrics,
+    /// The reader of the trade ring, reassembling the trades fragmented over several slots.
+    reader: RingReader<'a>,
+    /// Metrics of the stats ring.
+    stats_metrics: &'a RingMetrics,
+    /// Metrics of the kline ring.
+    kline_metrics: &'a RingMetrics,
+    /// The aggregates of the symbols carried by the rings, by symbol ID.
+    symbols: HashMap<u32, SymbolStats>,
+}
+
+/// The aggregates of a trade symbol.
+struct SymbolStats {
+    /// The symbol ID.
+    symbol_id: u32,
+    /// The rolling statistics.
+    stats: TradeStats,
+    /// The candle builders, one per configured interval.
+    candles: Vec<CandleBuilder>,
+    /// The book snapshot region of the symbol, if its book is built.
+    book: Option<ShmRegion<BookSnapshotRegion>>,
+    /// The time of the last impossible print alert of the symbol.
+    last_print_alert: Option<Instant>,
+}
+
+/// The checks of the trades against their concurrent quotes.
+struct Consistency {
+    /// The checker.
+    checker: ConsistencyChecker,
+    /// The last-value Top region.
+    last_top: ShmRegion<LastTopRegion>,
+}
+
+/// A gap of a trade ring, fetched by the gap fill thread.
+struct GapRequest {
+    /// The index of the ring in the consumed rings.
+    ring: usize,
+    /// The symbol of the gap.
+    symbol: String,
+    /// The trades missed.
+    gap: TradeGap,
+}
+
+/// The trades of a gap, fetched by the gap fill thread.
+struct GapFilled {
+    /// The index of the ring in the consumed rings.
+    ring: usize,
+    /// The trades missed.
+    gap: TradeGap,
+    /// The payloads of the trades fetched, oldest first.
+    payloads: Vec<String>,
+}
+
+/// The gap fill of the trade rings.
+struct GapFill {
+    /// The detector of the trades missed.
+    detector: TradeGapDetector,
+    /// The symbols, by symbol ID.
+    symbols: HashMap<u32, String>,
+    /// The gaps to fetch.
+    requests: Sender<GapRequest>,
+    /// The trades fetched.
+    filled: Receiver<GapFilled>,
+}
+
+impl GapFill {
+    /// Spawns the thread fetching the gaps from the REST API with `client`.
+    fn spawn(config: GapFillConfig, client: RestClient) -> Result<Self, FatalError> {
+        let (requests, request_rx) = mpsc::channel::<GapRequest>();
+        let (filled_tx, filled) = mpsc::channel();
+        thread::Builder::new()
+            .name("gap-fill".to_string())
+            .spawn(move || {
+                for GapRequest { ring, symbol, gap } in request_rx {
+                    match fetch_gap(&client, &config, &symbol, &gap) {
+                        Ok(payloads) => {
+                            if filled_tx.send(GapFilled { ring, gap, payloads }).is_err() {
+                                break;
+                            }
+                        }
+                        Err(e) => warn!("[{}] Failed to fetch trades {}..={}: {}", symbol, gap.from_id, gap.to_id, e),
+                    }
+                }
+            })
+            .fatal(FatalKind::Host)?;
+        Ok(Self { detector: TradeGapDetector::new(), symbols: HashMap::new(), requests, filled })
+    }
+
+    /// Requests the trades of a gap of a ring.
+    fn request(&self, ring: usize, gap: TradeGap) {
+        let Some(symbol) = self.symbols.get(&gap.symbol_id) else {
+            return;
+        };
+        warn!("[{}] {} trades {}..={} missed, fetching", symbol, gap.len(), gap.from_id, gap.to_id);
+        let request = GapRequest { ring, symbol: symbol.clone(), gap };
+        if self.requests.send(request).is_err() {
+            warn!("[{}] Gap fill thread exited, trades {}..={} left missing", symbol, gap.from_id, gap.to_id);
+        }
+    }
+}
+
+/// Injects a trade payload fetched to fill a gap into its trade ring, flagged
+/// as backfill and fragmented if larger than a slot.
+fn inject_backfill(
+    rings: &SymbolRings,
+    metrics: &RingMetrics,
+    gap: &TradeGap,
+    payload: &[u8],
+) -> Result<(), FatalError> {
+    let count = fragment_count(payload.len(), rings.slot_size);
+    if count > MAX_FRAGMENTS {
+        let (name, len) = (&rings.trade_name, payload.len());
+        warn!("{} backfilled trade of {} bytes exceeds {} fragments, dropped", name, len, MAX_FRAGMENTS);
+        return Ok(());
+    }
+    let mut message = RawMessage::default();
+    message.header = MessageHeader::new(gap.event_type, gap.symbol_id);
+    message.header.medium = MediumTag::Json as u8;
+    message.header.set_backfill();
+    for (index, chunk) in payload.chunks(rings.slot_size).enumerate() {
+        message.data = [0u8; RAW_MESSAGE_SIZE];
+        message.data[..chunk.len()].copy_from_slice(chunk);
+        message.header.set_fragment(index as u16, index + 1 < count);
+        message.header.stamp(metrics.record_publish(), now_ms());
+        #[cfg(feature = "message-checksums")]
+        message.seal();
+        rings.trade.publish(&message).fatal(FatalKind::Internal)?;
+    }
+    Ok(())
+}
+
+/// Publishes a completed candle to a kline ring, stamped with its sequence number.
+fn publish_candle(
+    ring: &DpdkPubSubRing<CandleMessage>,
+    metrics: &RingMetrics,
+    mut candle: CandleMessage,
+) -> Result<(), FatalError> {
+    candle.header.stamp(metrics.record_publish(), now_ms());
+    #[cfg(feature = "message-checksums")]
+    candle.seal();
+    ring.publish(&candle).fatal(FatalKind::Internal)
+}
+
+/// Checks a trade against the latest Top and the book of its symbol, alerting
+/// on an impossible print unless the symbol alerted within the alert interval.
+fn check_consistency(
+    consistency: &mut Consistency,
+    trade_name: &str,
+    symbol: &mut SymbolStats,
+    trade: TradeEvent,
+    alerts: &DpdkPubSubRing<AlertMessage>,
+) {
+    let symbol_id = symbol.symbol_id;
+    let top = consistency.last_top.read(symbol_id);
+    let book = symbol.book.as_ref().and_then(|book| book.read());
+    let Some(print) = consistency.checker.check(trade.price, top.as_ref(), book.as_ref()) else {
+        return;
+    };
+    if symbol.last_print_alert.is_some_and(|at| at.elapsed() < IMPOSSIBLE_PRINT_ALERT_INTERVAL) {
+        return;
+    }
+    symbol.last_print_alert = Some(Instant::now());
+
+    let ImpossiblePrint { source, price, bid, ask, outside_bps } = print;
+    let (checked, flagged) = consistency.checker.counts();
+    let detail = format!(
+        "{} symbol {} trade {} outside {:?} {}/{} by {:.1} bps ({} of {} flagged)",
+        trade_name, symbol_id, price, source, bid, ask, outside_bps, flagged, checked
+    );
+    warn!("{}", detail);
+    let alert = AlertMessage::new(AlertKind::ImpossiblePrint, AlertSeverity::Warning, now_ms(), ALERT_SOURCE, &detail);
+    if let Err(e) = alerts.publish(&alert) {
+        warn!("Failed to publish alert to {}: {:?}", ALERTS_RING_NAME, e);
+    }
+}
+
+fn main() -> ExitCode {
+    ctl_core::exit_code(run())
+}
+
+fn run() -> Result<(), FatalError> {
+    let _log = ctl_log::init_from_file(LOG_CONFIG_PATH).fatal(FatalKind::Config)?;
+
+    info!("=== Binance Spot Trade Statistics ===");
+    info!("Starting as DPDK secondary process...");
+
+    let md_config = HwResourcesConfig::from_file(MD_CONFIG_PATH).fatal(FatalKind::Config)?;
+    let symbol_info = SymbolInfoConfig::from_file(SYMBOL_INFO_PATH).fatal(FatalKind::Config)?;
+    let candle_config = CandleConfig::from_file(CANDLE_CONFIG_PATH).fatal(FatalKind::Config)?;
+    let consistency_config = ConsistencyConfig::from_file(CONSISTENCY_CONFIG_PATH).fatal(FatalKind::Config)?;
+    let gap_fill_config = GapFillConfig::from_file(GAP_FILL_CONFIG_PATH).fatal(FatalKind::Config)?;
+    md_config.eal.apply_region_prefix();
+    let polling = PollingConfig::from_file(POLLING_CONFIG_PATH)?.policy(POLLING_COMPONENT, DEFAULT_POLLING);
+    let overtaken = OvertakenConfig::from_file(OVERTAKEN_CONFIG_PATH)?.policy(OVERTAKEN_COMPONENT, DEFAULT_OVERTAKEN);
+    let scheduling = SchedulingConfig::from_file(SCHEDULING_CONFIG_PATH)?.policy(SCHEDULING_COMPONENT);
+    info!("Scheduling: {:?}", scheduling);
+    let lcore = LcoresConfig::from_file(LCORES_CONFIG_PATH)?.lcore(LCORES_COMPONENT, DEFAULT_LCORE) as usize;
+    let trade_feed = md_config
+        .find_feed("trade")
+        .ok_or("No trade feed configured in hw-resources.yaml")
+        .fatal(FatalKind::Config)?;
+
+    // Verify the host before the EAL initialization, which reports its failures cryptically
+    Preflight::new()
+        .require_hugepages_mounted()
+        .require_isolated_lcores(&[lcore])
+        .require_capabilities(&[Capability::IpcLock])
+        .require_capabilities(&scheduling.capabilities())
+        .require_primary()
+        .with_file_prefix(md_config.eal.file_prefix())
+        .run()?;
+
+    // Before the EAL, deriving the affinity of its control threads from the process
+    scheduling.apply()?;
+
+    // Fetch the trades missed off the hot path, sharing the REST weight budget
+    // through the ledger created by ctl-resource-manager
+    let mut gap_fill = if gap_fill_config.enabled {
+        let ledger = Arc::new(ShmRegion::<WeightLedger>::open(WEIGHT_LEDGER_REGION_NAME)?);
+        let client = RestClient::new(&gap_fill_config.endpoint).fatal(FatalKind::Network)?.with_ledger(ledger);
+        Some(GapFill::spawn(gap_fill_config.clone(), client)?)
+    } else {
+        None
+    };
+
+    let dpdk_env = DpdkEnvBuilder::default()
+        .process_type(DpdkProcessType::Secondary)
+        .lcore_ids(vec![lcore])
+        .main_lcore_id(lcore)
+        .eal_args(md_config.eal.secondary_args())
+        .build()
+        .fatal(FatalKind::DpdkInit)?;
+
+    info!("DPDK environment initialized");
+
+    // Calibrate the timestamps of the hot path before the first message
+    info!("Timestamps from the {:?} clock ({:?} Hz)", ctl_time::calibrate(), ctl_time::tsc_hz());
+    info!("Windows: {:?} ms", STATS_WINDOWS_MS);
+    info!("Candle intervals: {:?} ms", candle_config.intervals_ms);
+    info!("Polling: {:?}", polling);
+    info!("Overtaken: {:?}", overtaken);
+    info!("Consistency checks: {:?}", consistency_config);
+    info!("Gap fill: {:?}", gap_fill_config);
+
+    let metrics = Arc::new(ShmRegion::<MetricsRegion>::open(METRICS_REGION_NAME)?);
+
+    // Read the concurrent quotes of the trades from the last-value Top region
+    let check_book = consistency_config.enabled && consistency_config.check_book;
+    let mut consistency = if consistency_config.enabled {
+        let last_top = ShmRegion::<LastTopRegion>::open(LAST_TOP_REGION_NAME)?;
+        Some(Consistency { checker: ConsistencyChecker::new(consistency_config), last_top })
+    } else {
+        None
+    };
+
+    // Look up the trade, stats and kline rings of every trade symbol, a single
+    // ring of each for the symbols of an aggregated set
+    let lookup_id = |symbol: &str| {
+        symbol_info
+            .symbol_id(symbol)
+            .ok_or_else(|| format!("Symbol '{}' not found in symbolinfo.yaml", symbol))
+            .fatal(FatalKind::Config)
+    };
+    let mut rings = Vec::new();
+    for feed_set in trade_feed.feed_sets() {
+        for symbol in feed_set.ring_symbols() {
+            let symbol_id = lookup_id(symbol)?;
+            let symbol_ids = if feed_set.aggregate {
+                feed_set.symbols.iter().map(|symbol| lookup_id(symbol.as_str())).collect::<Result<Vec<u32>, _>>()?
+            } else {
+                vec![symbol_id]
+            };
+            if let Some(gap_fill) = gap_fill.as_mut() {
+                let symbols: &[String] = if feed_set.aggregate { &feed_set.symbols } else { &[] };
+                gap_fill.symbols.insert(symbol_id, symbol.to_string());
+                for (symbol, &id) in symbols.iter().zip(&symbol_ids) {
+                    gap_fill.symbols.insert(id, symbol.clone());
+                }
+            }
+            let trade_name = RingName::pubsub(RingKind::Trade, symbol_id).to_string();
+            let stats_name = RingName::pubsub(RingKind::Stats, symbol_id).to_string();
+            let kline_name = RingName::pubsub(RingKind::Kline, symbol_id).to_string();
+            info!("[{}] {} -> {}, {} ({} symbols)", symbol, trade_name, stats_name, kline_name, symbol_ids.len());
+            metrics.check_layout::<RawMessage>(&trade_name).fatal(FatalKind::SharedState)?;
+            metrics.check_slot_size(&trade_name, feed_set.slot_size).fatal(FatalKind::SharedState)?;
+            metrics.check_layout::<TradeStatsMessage>(&stats_name).fatal(FatalKind::SharedState)?;
+            metrics.check_layout::<CandleMessage>(&kline_name).fatal(FatalKind::SharedState)?;
+
+            rings.push(SymbolRings {
+                symbol_id,
+                symbol_ids,
+                trade: dpdk_env.pubsub_lookup::<RawMessage>(&trade_name).fatal(FatalKind::SharedState)?,
+                slot_size: feed_set.slot_size,
+                stats: dpdk_env.pubsub_lookup::<TradeStatsMessage>(&stats_name).fatal(FatalKind::SharedState)?,
+                kline: dpdk_env.pubsub_lookup::<CandleMessage>(&kline_name).fatal(FatalKind::SharedState)?,
+                trade_name,
+                stats_name,
+                kline_name,
+            });
+        }
+    }
+
+    let alerts = dpdk_env.pubsub_lookup::<AlertMessage>(ALERTS_RING_NAME).fatal(FatalKind::SharedState)?;
+    metrics.check_layout::<AlertMessage>(ALERTS_RING_NAME).fatal(FatalKind::SharedState)?;
+
+    // Attach to the trade rings, tracking the consumer positions and the
+    // published statistics and candles in the metrics region
+    let find_metrics = |ring_name: &str| {
+        metrics
+            .find_ring(ring_name)
+            .map(|index| &metrics.rings[index])
+            .ok_or_else(|| format!("Ring '{}' not found in metrics region", ring_name))
+            .fatal(FatalKind::SharedState)
+    };
+    let mut ring_stats = Vec::new();
+    for symbol_rings in &rings {
+        let trade_metrics = find_metrics(&symbol_rings.trade_name)?;
+        let reader = RingReader::attach(&metrics, &symbol_rings.trade_name, CONSUMER_NAME, symbol_rings.slot_size)
+            .fatal(FatalKind::SharedState)?
+            .with_overtaken(overtaken);
+
+        let mut symbols = HashMap::new();
+        for &symbol_id in &symbol_rings.symbol_ids {
+            // Only the symbols whose book is built have a book snapshot region
+            let book = if check_book {
+                ShmRegion::<BookSnapshotRegion>::open(&book_region_name(symbol_id)).ok()
+            } else {
+                None
+            };
+            if check_book && book.is_none() {
+                info!("Symbol {} has no book snapshot region, checked against its Top only", symbol_id);
+            }
+
+            symbols.insert(symbol_id, SymbolStats {
+                symbol_id,
+                stats: TradeStats::new(symbol_id),
+                candles: candle_config
+                    .intervals_ms
+                    .iter()
+                    .map(|&interval_ms| CandleBuilder::new(symbol_id, interval_ms))
+                    .collect(),
+                book,
+                last_print_alert: None,
+            });
+        }
+
+        ring_stats.push(RingStats {
+            rings: symbol_rings,
+            consumer: symbol_rings.trade.attach_consumer().fatal(FatalKind::SharedState)?,
+            trade_metrics,
+            reader,
+            stats_metrics: find_metrics(&symbol_rings.stats_name)?,
+            kline_metrics: find_metrics(&symbol_rings.kline_name)?,
+            symbols,
+        });
+    }
+
+    // Record the publish times of the stats and kline rings, and the wake
+    // latency of the trades in the latency group of their feedgroup
+    #[cfg(feature = "latency-histograms")]
+    let mut publish_latency = metrics
+        .register_latency_group(LATENCY_GROUP)
+        .and_then(|index| LatencyRecorder::new(metrics.clone(), index))
+        .ok_or_else(|| format!("No latency group available for '{}'", LATENCY_GROUP))
+        .fatal(FatalKind::SharedState)?;
+    #[cfg(feature = "latency-histograms")]
+    let mut wake_latency = WakeLatencyRecorder::new(metrics.clone());
+
+    info!("=== Trade Statistics Running ===");
+
+    let mut last_flush = Instant::now();
+    let mut poller = Poller::new(polling);
+    loop {
+        let mut did_work = false;
+
+        // Inject the trades fetched into their rings, consumed back as backfill
+        if let Some(gap_fill) = gap_fill.as_ref() {
+            while let Ok(GapFilled { ring, gap, payloads }) = gap_fill.filled.try_recv() {
+                let ring = &ring_stats[ring];
+                for payload in &payloads {
+                    inject_backfill(ring.rings, ring.trade_metrics, &gap, payload.as_bytes())?;
+                }
+                info!("{} backfilled {} of {} trades missed", ring.rings.trade_name, payloads.len(), gap.len());
+                did_work = true;
+            }
+        }
+
+        for (index, ring) in ring_stats.iter_mut().enumerate() {
+            let consumed = dpdk_consume!(ring.consumer);
+            if let RingConsume::Message(message) = &consumed {
+                #[cfg(feature = "latency-histograms")]
+                wake_latency.record(&message.header, ctl_time::monotonic_ns());
+                #[cfg(feature = "message-checksums")]
+                if !message.verify() {
+                    // Torn or corrupted in its slot, dropped rather than skewing the statistics
+                    warn!("{} trade seq {} failed its checksum, dropped", ring.rings.trade_name, message.header.seq);
+                    ring.reader.skip();
+                    did_work = true;
+                    continue;
+                }
+            }
+            let read = ring.reader.read(consumed).fatal(FatalKind::Overtaken)?;
+            did_work |= read.did_work();
+            match read {
+                RingRead::Payload(message, payload) => {
+                    // Demultiplexed by the symbol ID of the header, the ring's own symbol if unknown
+                    let symbol_id = message.header.symbol_id().unwrap_or(ring.rings.symbol_id);
+                    let Some(symbol) = ring.symbols.get_mut(&symbol_id) else {
+                        continue;
+                    };
+                    // The trades missed since the last one of the symbol, fetched off the hot path
+                    if let Some(gap_fill) = gap_fill.as_mut() {
+                        if let Some(gap) = gap_fill.detector.observe(&message.header, payload) {
+                            gap_fill.request(index, gap);
+                        }
+                    }
+                    let Some(trade) = TradeEvent::from_message(&message.fixed, payload) else {
+                        continue;
+                    };
+                    // The backfilled trades printed against quotes long gone, not checked
+                    let backfill = message.header.is_backfill();
+                    if let Some(consistency) = consistency.as_mut().filter(|_| !backfill) {
+                        check_consistency(consistency, &ring.rings.trade_name, symbol, trade, &alerts);
+                    }
+
+                    if backfill {
+                        symbol.stats.backfill(trade);
+                    } else {
+                        symbol.stats.update(trade);
+                    }
+                    let mut message = symbol.stats.message();
+                    message.header.stamp(ring.stats_metrics.record_publish(), now_ms());
+                    #[cfg(feature = "message-checksums")]
+                    message.seal();
+                    #[cfg(feature = "latency-histograms")]
+                    let start_ns = ctl_time::monotonic_ns();
+                    ring.rings.stats.publish(&message).fatal(FatalKind::Internal)?;
+                    #[cfg(feature = "latency-histograms")]
+                    {
+                        let now_ns = ctl_time::monotonic_ns();
+                        publish_latency.record_publish(now_ns - start_ns);
+                        publish_latency.maybe_flush(now_ns);
+                    }
+
+                    for builder in symbol.candles.iter_mut() {
+                        let candle = if backfill { builder.backfill(trade) } else { builder.update(trade) };
+                        if let Some(candle) = candle {
+                            publish_candle(&ring.rings.kline, ring.kline_metrics, candle)?;
+                        }
+                    }
+                }
+                RingRead::Overtaken { .. } => {
+                    // The statistics miss the overwritten trades until they leave the windows,
+                    // unless fetched by the gap fill once the next trade shows the gap
+                    let detail = format!("{} consumer overtaken by producer, some trades missed", ring.rings.trade_name);
+                    warn!("{}", detail);
+                    let alert = AlertMessage::new(AlertKind::RingOverflow, AlertSeverity::Warning, now_ms(), ALERT_SOURCE, &detail);
+                    if let Err(e) = alerts.publish(&alert) {
+                        warn!("Failed to publish alert to {}: {:?}", ALERTS_RING_NAME, e);
+                    }
+                }
+                RingRead::Consumed | RingRead::Idle => {}
+            }
+        }
+
+        // Complete the candles whose interval elapsed without a trade of the next bar
+        if last_flush.elapsed() >= CANDLE_FLUSH_INTERVAL {
+            let now_ms = now_ms();
+            for ring in ring_stats.iter_mut() {
+                for symbol in ring.symbols.values_mut() {
+                    for builder in symbol.candles.iter_mut() {
+                        if let Some(candle) = builder.flush(now_ms) {
+                            publish_candle(&ring.rings.kline, ring.kline_metrics, candle)?;
+                        }
+                    }
+                }
+            }
+            last_flush = Instant::now();
+        }
+
+        // Wait before the next pass according to the configured policy
+        poller.wait(did_work);
+    }
+}
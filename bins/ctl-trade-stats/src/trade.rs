@@ -0,0 +1,96 @@
+use ctl_feed::FixedPoint;
+use serde::Deserialize;
+
+/// A trade stream payload.
+/// https://github.com/binance/binance-spot-api-docs/blob/master/web-socket-streams.md#trade-streams
+#[derive(Deserialize)]
+struct TradePayload<'a> {
+    #[serde(rename = "T")]
+    trade_time_ms: u64,
+    #[serde(rename = "p")]
+    price: &'a str,
+    #[serde(rename = "q")]
+    qty: &'a str,
+}
+
+/// A trade aggregated into the statistics.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TradeEvent {
+    /// The trade time, in milliseconds.
+    pub trade_time_ms: u64,
+    /// The trade price.
+    pub price: f64,
+    /// The trade quantity.
+    pub qty: f64,
+}
+
+impl TradeEvent {
+    /// Parses a trade payload, ignoring the zero padding of the raw message buffer.
+    /// Returns `None` if the payload is not a trade.
+    pub fn from_json(data: &[u8]) -> Option<Self> {
+        let len = data.iter().position(|&b| b == 0).unwrap_or(data.len());
+        let payload: TradePayload<'_> = serde_json::from_slice(&data[..len]).ok()?;
+        Some(Self {
+            trade_time_ms: payload.trade_time_ms,
+            price: payload.price.parse().ok()?,
+            qty: payload.qty.parse().ok()?,
+        })
+    }
+
+    /// Reads a trade message of a payload, reassembled if fragmented, from its
+    /// fixed-point price and quantity if the parser normalized them, parsing
+    /// its payload otherwise.
+    /// Returns `None` if the message is not a trade.
+    pub fn from_message(fixed: &FixedPoint, payload: &[u8]) -> Option<Self> {
+        if !fixed.is_scaled() {
+            return Self::from_json(payload);
+        }
+        Some(Self {
+            trade_time_ms: trade_time_ms(payload)?,
+            price: fixed.price_f64(),
+            qty: fixed.qty_f64(),
+        })
+    }
+}
+
+/// Returns the `T` trade time of a trade payload, without parsing the payload.
+fn trade_time_ms(data: &[u8]) -> Option<u64> {
+    const KEY: &[u8] = b"\"T\":";
+    let start = data.windows(KEY.len()).position(|window| window == KEY)? + KEY.len();
+    let len = data[start..].iter().take_while(|b| b.is_ascii_digit()).count();
+    std::str::from_utf8(&data[start..start + len]).ok()?.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use ctl_feed::SymbolScale;
+
+    #[test]
+    fn test_parse_trade() {
+        let mut data = [0u8; 256];
+        let json = br#"{"e":"trade","E":123456789,"s":"BNBBTC","t":12345,"p":"0.001","q":"100","T":123456785,"m":true,"M":true}"#;
+        data[..json.len()].copy_from_slice(json);
+
+        let trade = TradeEvent::from_json(&data).unwrap();
+        assert_eq!(trade, TradeEvent { trade_time_ms: 123456785, price: 0.001, qty: 100.0 });
+    }
+
+    #[test]
+    fn test_trade_from_fixed_point() {
+        let json = br#"{"e":"trade","E":123456789,"s":"BNBBTC","t":12345,"p":"0.001","q":"100","T":123456785,"m":true,"M":true}"#;
+        assert_eq!(TradeEvent::from_message(&FixedPoint::default(), json), TradeEvent::from_json(json));
+
+        // The fixed-point values are read instead of the payload's
+        let fixed = FixedPoint::trade(b"0.002", b"50", SymbolScale { tick_exponent: 6, step_exponent: 2 }).unwrap();
+        let trade = TradeEvent::from_message(&fixed, json).unwrap();
+        assert_eq!(trade, TradeEvent { trade_time_ms: 123456785, price: 0.002, qty: 50.0 });
+    }
+
+    #[test]
+    fn test_parse_non_trade() {
+        assert!(TradeEvent::from_json(br#"{"u":400900217,"s":"BNBUSDT"}"#).is_none());
+        assert!(TradeEvent::from_json(b"").is_none());
+    }
+}
@@ -0,0 +1,199 @@
+use std::collections::VecDeque;
+
+use ctl_feed::{EventType, MessageHeader, TradeStatsMessage, WindowStats, STATS_WINDOWS_MS};
+
+use crate::TradeEvent;
+
+/// Trades over a rolling time window, with their running sums.
+#[derive(Debug, Clone)]
+pub struct RollingWindow {
+    /// The window length, in milliseconds.
+    window_ms: u64,
+    /// The trades in the window, oldest first.
+    trades: VecDeque<TradeEvent>,
+    /// Sum of price * quantity over the window.
+    notional: f64,
+    /// Sum of quantity over the window.
+    volume: f64,
+}
+
+impl RollingWindow {
+    /// Creates an empty window of `window_ms` milliseconds.
+    pub fn new(window_ms: u64) -> Self {
+        Self {
+            window_ms,
+            trades: VecDeque::new(),
+            notional: 0.0,
+            volume: 0.0,
+        }
+    }
+
+    /// Adds a trade, evicting the trades that fell out of the window ending at it.
+    pub fn push(&mut self, trade: TradeEvent) {
+        self.trades.push_back(trade);
+        self.notional += trade.price * trade.qty;
+        self.volume += trade.qty;
+        self.evict(trade.trade_time_ms);
+    }
+
+    /// Adds a trade fetched after the fact, in the order of the trade times,
+    /// unless it already fell out of the window ending at the latest trade.
+    pub fn backfill(&mut self, trade: TradeEvent) {
+        let latest_ms = self.trades.back().map_or(trade.trade_time_ms, |latest| latest.trade_time_ms);
+        if trade.trade_time_ms + self.window_ms <= latest_ms {
+            return;
+        }
+        let index = self.trades.partition_point(|other| other.trade_time_ms <= trade.trade_time_ms);
+        self.trades.insert(index, trade);
+        self.notional += trade.price * trade.qty;
+        self.volume += trade.qty;
+    }
+
+    /// Evicts the trades that fell out of the window ending at `now_ms`.
+    pub fn evict(&mut self, now_ms: u64) {
+        while let Some(oldest) = self.trades.front() {
+            if oldest.trade_time_ms + self.window_ms > now_ms {
+                break;
+            }
+            self.notional -= oldest.price * oldest.qty;
+            self.volume -= oldest.qty;
+            self.trades.pop_front();
+        }
+        // Reset the running sums so floating point drift doesn't accumulate
+        if self.trades.is_empty() {
+            self.notional = 0.0;
+            self.volume = 0.0;
+        }
+    }
+
+    /// Returns the statistics of the window.
+    pub fn stats(&self) -> WindowStats {
+        WindowStats {
+            window_ms: self.window_ms,
+            vwap: if self.volume > 0.0 { self.notional / self.volume } else { 0.0 },
+            notional: self.notional,
+            volume: self.volume,
+            trade_count: self.trades.len() as u64,
+        }
+    }
+}
+
+/// The rolling trade statistics of a symbol over the `STATS_WINDOWS_MS` windows.
+#[derive(Debug, Clone)]
+pub struct TradeStats {
+    /// The symbol ID.
+    symbol_id: u32,
+    /// Trade time of the last trade, in milliseconds.
+    trade_time_ms: u64,
+    /// The rolling windows, in `STATS_WINDOWS_MS` order.
+    windows: [RollingWindow; STATS_WINDOWS_MS.len()],
+}
+
+impl TradeStats {
+    /// Creates empty statistics for a symbol.
+    pub fn new(symbol_id: u32) -> Self {
+        Self {
+            symbol_id,
+            trade_time_ms: 0,
+            windows: STATS_WINDOWS_MS.map(RollingWindow::new),
+        }
+    }
+
+    /// Adds a trade to every window.
+    pub fn update(&mut self, trade: TradeEvent) {
+        self.trade_time_ms = self.trade_time_ms.max(trade.trade_time_ms);
+        for window in &mut self.windows {
+            window.push(trade);
+        }
+    }
+
+    /// Adds a trade fetched after the fact to every window it falls in.
+    pub fn backfill(&mut self, trade: TradeEvent) {
+        self.trade_time_ms = self.trade_time_ms.max(trade.trade_time_ms);
+        for window in &mut self.windows {
+            window.backfill(trade);
+        }
+    }
+
+    /// Returns the statistics message published to the symbol's stats ring.
+    pub fn message(&self) -> TradeStatsMessage {
+        TradeStatsMessage {
+            header: MessageHeader::new(EventType::TradeStats, self.symbol_id),
+            symbol_id: self.symbol_id,
+            trade_time_ms: self.trade_time_ms,
+            windows: std::array::from_fn(|i| self.windows[i].stats()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trade(trade_time_ms: u64, price: f64, qty: f64) -> TradeEvent {
+        TradeEvent { trade_time_ms, price, qty }
+    }
+
+    #[test]
+    fn test_window_vwap() {
+        let mut window = RollingWindow::new(1_000);
+        window.push(trade(100, 10.0, 1.0));
+        window.push(trade(200, 20.0, 3.0));
+
+        let stats = window.stats();
+        assert_eq!(stats.trade_count, 2);
+        assert_eq!(stats.volume, 4.0);
+        assert_eq!(stats.notional, 70.0);
+        assert_eq!(stats.vwap, 17.5);
+    }
+
+    #[test]
+    fn test_window_evicts_old_trades() {
+        let mut window = RollingWindow::new(1_000);
+        window.push(trade(100, 10.0, 1.0));
+        window.push(trade(1_100, 20.0, 1.0));
+
+        let stats = window.stats();
+        assert_eq!(stats.trade_count, 1);
+        assert_eq!(stats.vwap, 20.0);
+
+        window.evict(5_000);
+        assert_eq!(window.stats(), WindowStats { window_ms: 1_000, ..Default::default() });
+    }
+
+    #[test]
+    fn test_window_backfill() {
+        let mut window = RollingWindow::new(1_000);
+        window.push(trade(1_000, 10.0, 1.0));
+        window.push(trade(1_500, 40.0, 1.0));
+        window.backfill(trade(1_200, 20.0, 2.0));
+        // Out of the window ending at the latest trade
+        window.backfill(trade(400, 99.0, 1.0));
+
+        let stats = window.stats();
+        assert_eq!(stats.trade_count, 3);
+        assert_eq!(stats.vwap, 22.5);
+        // The backfilled trade leaves the window in its order
+        window.push(trade(2_100, 30.0, 1.0));
+        assert_eq!(window.stats().trade_count, 3);
+        window.push(trade(2_300, 30.0, 1.0));
+        assert_eq!(window.stats().trade_count, 3);
+    }
+
+    #[test]
+    fn test_trade_stats_windows() {
+        let mut stats = TradeStats::new(3);
+        stats.update(trade(0, 10.0, 1.0));
+        stats.update(trade(4_000, 20.0, 1.0));
+        stats.update(trade(4_500, 30.0, 1.0));
+
+        let message = stats.message();
+        assert_eq!(message.symbol_id, 3);
+        assert_eq!(message.header.symbol_id(), Some(3));
+        assert_eq!(message.header.event_type(), EventType::TradeStats);
+        assert_eq!(message.trade_time_ms, 4_500);
+        let counts: Vec<u64> = message.windows.iter().map(|w| w.trade_count).collect();
+        assert_eq!(counts, vec![2, 3, 3]);
+        assert_eq!(message.windows[0].vwap, 25.0);
+    }
+}
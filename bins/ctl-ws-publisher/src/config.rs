@@ -0,0 +1,107 @@
+//! Configuration module for the WebSocket re-publisher.
+//!
+//! This module provides the YAML parser and validation for the publisher
+//! configuration defined in `configs/ws-publisher/ws-publisher.yaml`.
+
+use std::fs;
+use std::net::SocketAddr;
+use std::path::Path;
+
+use serde::Deserialize;
+
+use crate::PublisherConfigError;
+
+/// Default number of frames buffered per client.
+const DEFAULT_BUFFER: usize = 4096;
+
+/// Default maximum number of connected clients.
+const DEFAULT_MAX_CLIENTS: usize = 64;
+
+fn default_buffer() -> usize {
+    DEFAULT_BUFFER
+}
+
+fn default_max_clients() -> usize {
+    DEFAULT_MAX_CLIENTS
+}
+
+/// The configuration of the WebSocket re-publisher.
+#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+pub struct PublisherConfig {
+    /// The address the WebSocket server listens on (e.g. `127.0.0.1:9443`).
+    pub listen: String,
+    /// The frames buffered per client, a client lagging further missing the
+    /// frames published meanwhile.
+    #[serde(default = "default_buffer")]
+    pub buffer: usize,
+    /// The maximum number of connected clients, the connections past it refused.
+    #[serde(default = "default_max_clients")]
+    pub max_clients: usize,
+}
+
+impl PublisherConfig {
+    /// Parses the publisher configuration from a YAML file.
+    ///
+    /// # Errors
+    /// Returns an error if the file cannot be read, parsed, or fails validation.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, PublisherConfigError> {
+        let content = fs::read_to_string(path)?;
+        Self::from_str(&content)
+    }
+
+    /// Parses the publisher configuration from a YAML string.
+    ///
+    /// # Errors
+    /// Returns an error if the YAML cannot be parsed or fails validation.
+    pub fn from_str(content: &str) -> Result<Self, PublisherConfigError> {
+        let config: Self = serde_yaml::from_str(content)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Validates the publisher configuration.
+    fn validate(&self) -> Result<(), PublisherConfigError> {
+        self.listen_addr()?;
+        if self.buffer == 0 {
+            return Err(PublisherConfigError::ValidationError("Buffer must be greater than 0".to_string()));
+        }
+        if self.max_clients == 0 {
+            return Err(PublisherConfigError::ValidationError(
+                "Max clients must be greater than 0".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Returns the address the WebSocket server listens on.
+    ///
+    /// # Errors
+    /// Returns an error if the address is not an IP address and port.
+    pub fn listen_addr(&self) -> Result<SocketAddr, PublisherConfigError> {
+        self.listen.parse().map_err(|e| {
+            PublisherConfigError::ValidationError(format!("Invalid listen address '{}': {}", self.listen, e))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_config() {
+        let config = PublisherConfig::from_str("listen: 127.0.0.1:9443").unwrap();
+        assert_eq!(config.listen_addr().unwrap(), "127.0.0.1:9443".parse().unwrap());
+        assert_eq!((config.buffer, config.max_clients), (DEFAULT_BUFFER, DEFAULT_MAX_CLIENTS));
+
+        let config = PublisherConfig::from_str("listen: 0.0.0.0:9000\nbuffer: 16\nmax_clients: 2").unwrap();
+        assert_eq!((config.buffer, config.max_clients), (16, 2));
+    }
+
+    #[test]
+    fn test_invalid_config() {
+        assert!(PublisherConfig::from_str("listen: localhost").is_err());
+        assert!(PublisherConfig::from_str("listen: 0.0.0.0:9000\nbuffer: 0").is_err());
+        assert!(PublisherConfig::from_str("listen: 0.0.0.0:9000\nmax_clients: 0").is_err());
+    }
+}
@@ -0,0 +1,26 @@
+use thiserror::Error;
+
+/// Errors that can occur when parsing or validating the publisher configuration.
+#[derive(Debug, Error)]
+pub enum PublisherConfigError {
+    /// Error reading the configuration file.
+    #[error("Failed to read configuration file: {0}")]
+    FileReadError(#[from] std::io::Error),
+    /// Error parsing the YAML configuration.
+    #[error("Failed to parse YAML configuration: {0}")]
+    YamlParseError(#[from] serde_yaml::Error),
+    /// Validation error with a descriptive message.
+    #[error("Configuration validation error: {0}")]
+    ValidationError(String),
+}
+
+/// Errors that can occur when accepting a client connection.
+#[derive(Debug, Error)]
+pub enum SessionError {
+    /// The handshake failed or was refused for its path.
+    #[error("Websocket handshake failed: {0}")]
+    Handshake(String),
+    /// Error configuring the socket of the connection.
+    #[error("Failed to configure the client socket: {0}")]
+    Socket(#[from] std::io::Error),
+}
@@ -0,0 +1,199 @@
+//! Fan-out of the payloads of the rings to the clients subscribed to their stream.
+//!
+//! The ring consumer publishes from its own thread and must never wait on a
+//! client, so each client has a bounded channel: a client whose channel is full
+//! misses the payload, counted as dropped, and a client gone away is removed
+//! on the next payload of its streams.
+
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::mpsc::{SyncSender, TrySendError};
+use std::sync::{Arc, Mutex, PoisonError};
+
+use hashbrown::{HashMap, HashSet};
+
+/// A payload of a stream, shared by the clients subscribed to it.
+#[derive(Debug, Clone)]
+pub struct Update {
+    /// The stream of the payload (e.g. `btcusdt@trade`).
+    pub stream: Arc<str>,
+    /// The payload, as received from Binance.
+    pub payload: Arc<str>,
+}
+
+/// The clients subscribed to the streams re-published.
+#[derive(Debug)]
+pub struct Hub {
+    /// The streams served.
+    streams: HashSet<String>,
+    /// The senders of the clients subscribed to each stream, by client ID.
+    subscriptions: Mutex<HashMap<String, Vec<(u64, SyncSender<Update>)>>>,
+    /// The ID of the next client.
+    next_client: AtomicU64,
+    /// Number of connected clients.
+    clients: AtomicUsize,
+    /// Number of payloads dropped for full clients.
+    dropped: AtomicU64,
+}
+
+impl Hub {
+    /// Creates the hub of the streams served.
+    pub fn new<S: Into<String>>(streams: impl IntoIterator<Item = S>) -> Arc<Self> {
+        Arc::new(Self {
+            streams: streams.into_iter().map(Into::into).collect(),
+            subscriptions: Mutex::new(HashMap::new()),
+            next_client: AtomicU64::new(0),
+            clients: AtomicUsize::new(0),
+            dropped: AtomicU64::new(0),
+        })
+    }
+
+    /// Returns true if the stream is served.
+    pub fn serves(&self, stream: &str) -> bool {
+        self.streams.contains(stream)
+    }
+
+    /// Registers a connected client, returning its ID.
+    pub fn connect(&self) -> u64 {
+        self.clients.fetch_add(1, Ordering::Relaxed);
+        self.next_client.fetch_add(1, Ordering::Relaxed)
+    }
+
+    /// Unsubscribes a disconnected client from all its streams.
+    pub fn disconnect(&self, client: u64) {
+        let mut subscriptions = self.subscriptions.lock().unwrap_or_else(PoisonError::into_inner);
+        subscriptions.retain(|_, senders| {
+            senders.retain(|(id, _)| *id != client);
+            !senders.is_empty()
+        });
+        self.clients.fetch_sub(1, Ordering::Relaxed);
+    }
+
+    /// Subscribes a client to the streams, the ones already subscribed left as is.
+    ///
+    /// # Errors
+    /// Returns the first stream not served, without subscribing any.
+    pub fn subscribe<'a>(
+        &self,
+        client: u64,
+        streams: &'a [String],
+        sender: &SyncSender<Update>,
+    ) -> Result<(), &'a str> {
+        if let Some(stream) = streams.iter().find(|stream| !self.serves(stream)) {
+            return Err(stream);
+        }
+        let mut subscriptions = self.subscriptions.lock().unwrap_or_else(PoisonError::into_inner);
+        for stream in streams {
+            let senders = subscriptions.entry(stream.clone()).or_default();
+            if !senders.iter().any(|(id, _)| *id == client) {
+                senders.push((client, sender.clone()));
+            }
+        }
+        Ok(())
+    }
+
+    /// Unsubscribes a client from the streams.
+    pub fn unsubscribe(&self, client: u64, streams: &[String]) {
+        let mut subscriptions = self.subscriptions.lock().unwrap_or_else(PoisonError::into_inner);
+        for stream in streams {
+            let Some(senders) = subscriptions.get_mut(stream.as_str()) else {
+                continue;
+            };
+            senders.retain(|(id, _)| *id != client);
+            if senders.is_empty() {
+                subscriptions.remove(stream.as_str());
+            }
+        }
+    }
+
+    /// Publishes a payload of a stream to its clients.
+    pub fn publish(&self, stream: &str, payload: &str) {
+        let mut subscriptions = self.subscriptions.lock().unwrap_or_else(PoisonError::into_inner);
+        let Some(senders) = subscriptions.get_mut(stream) else {
+            return;
+        };
+        let update = Update { stream: stream.into(), payload: payload.into() };
+        senders.retain(|(_, sender)| match sender.try_send(update.clone()) {
+            Ok(()) => true,
+            Err(TrySendError::Full(_)) => {
+                self.dropped.fetch_add(1, Ordering::Relaxed);
+                true
+            }
+            Err(TrySendError::Disconnected(_)) => false,
+        });
+        if senders.is_empty() {
+            subscriptions.remove(stream);
+        }
+    }
+
+    /// Counts a payload dropped for a client lagging its socket.
+    pub fn count_dropped(&self) {
+        self.dropped.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Returns the number of clients subscribed to a stream.
+    pub fn subscribers(&self, stream: &str) -> usize {
+        let subscriptions = self.subscriptions.lock().unwrap_or_else(PoisonError::into_inner);
+        subscriptions.get(stream).map_or(0, Vec::len)
+    }
+
+    /// Returns the number of connected clients.
+    pub fn clients(&self) -> usize {
+        self.clients.load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of payloads dropped for lagging clients.
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::mpsc;
+
+    fn streams(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn test_hub() {
+        let hub = Hub::new(["btcusdt@trade", "ethusdt@trade"]);
+        let (both_sender, both) = mpsc::sync_channel(2);
+        let (btc_sender, btc) = mpsc::sync_channel(2);
+        let (both_id, btc_id) = (hub.connect(), hub.connect());
+        hub.subscribe(both_id, &streams(&["btcusdt@trade", "ethusdt@trade"]), &both_sender).unwrap();
+        hub.subscribe(btc_id, &streams(&["btcusdt@trade", "btcusdt@trade"]), &btc_sender).unwrap();
+        assert_eq!((hub.clients(), hub.subscribers("btcusdt@trade")), (2, 2));
+
+        hub.publish("btcusdt@trade", "1");
+        hub.publish("ethusdt@trade", "2");
+        assert_eq!(&*both.try_recv().unwrap().payload, "1");
+        assert_eq!(&*both.try_recv().unwrap().stream, "ethusdt@trade");
+        assert_eq!(&*btc.try_recv().unwrap().payload, "1");
+        assert!(btc.try_recv().is_err());
+
+        // A full client misses the payload, without holding back the others
+        hub.publish("btcusdt@trade", "3");
+        hub.publish("btcusdt@trade", "4");
+        assert_eq!(&*both.try_recv().unwrap().payload, "3");
+        hub.publish("btcusdt@trade", "5");
+        assert_eq!(hub.dropped(), 1);
+
+        hub.unsubscribe(both_id, &streams(&["btcusdt@trade"]));
+        assert_eq!(hub.subscribers("btcusdt@trade"), 1);
+        hub.disconnect(btc_id);
+        assert_eq!((hub.clients(), hub.subscribers("btcusdt@trade")), (1, 0));
+    }
+
+    #[test]
+    fn test_unserved_streams() {
+        let hub = Hub::new(["btcusdt@trade"]);
+        let (sender, _receiver) = mpsc::sync_channel(1);
+        let id = hub.connect();
+        let requested = streams(&["btcusdt@trade", "btcusdt@depth"]);
+        assert_eq!(hub.subscribe(id, &requested, &sender), Err("btcusdt@depth"));
+        assert_eq!(hub.subscribers("btcusdt@trade"), 0);
+    }
+}
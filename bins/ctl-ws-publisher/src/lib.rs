@@ -0,0 +1,22 @@
+//! WebSocket re-publisher for Binance Spot.
+//!
+//! Consumes the Top, trade and aggregated trade rings like any other consumer
+//! and fans their payloads back out over a local WebSocket server mirroring the
+//! market data streams of Binance: the same stream names (`btcusdt@bookTicker`),
+//! the raw streams at `/ws/<stream>`, the combined streams at
+//! `/stream?streams=<stream>/<stream>` wrapped as `{"stream":..,"data":..}`, and
+//! the SUBSCRIBE/UNSUBSCRIBE/LIST_SUBSCRIPTIONS requests. UIs and downstream
+//! systems written against Binance can so be pointed at the controller's view
+//! of the market instead.
+
+mod config;
+mod errors;
+mod hub;
+mod session;
+mod streams;
+
+pub use config::PublisherConfig;
+pub use errors::{PublisherConfigError, SessionError};
+pub use hub::{Hub, Update};
+pub use session::Session;
+pub use streams::{frame, payload_stream, stream_suffix, symbol_stream, StreamRequest};
@@ -0,0 +1,246 @@
+//! WebSocket Re-publisher for the Binance Spot Controller.
+//!
+//! Connects as a DPDK secondary process, attaches a consumer to the Top, trade
+//! and aggregated trade rings of the market data configuration, and re-publishes
+//! their payloads to local clients over a WebSocket server speaking Binance's
+//! market data stream protocol.
+//!
+//! The rings are consumed on the main thread, pinned to the lcore of the
+//! publisher, under the polling policy of `configs/polling.yaml`, and each
+//! client is served on its own thread. A client lagging its buffer misses the
+//! payloads published meanwhile; the publisher never holds back the rings for
+//! it. Once overtaken by a producer, the publisher recovers per
+//! `configs/overtaken.yaml`; the raw payloads aren't conflated, so a snapshot
+//! requested by `skip-and-request-snapshot` is a skip to the head.
+//!
+//! The exit code tells the class of a failure (see `FatalKind`).
+
+use std::net::{TcpListener, TcpStream};
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use clap::Parser;
+use ctl_core::{
+    Capability, Fatal, FatalError, FatalKind, LcoresConfig, OvertakenConfig, OvertakenPolicy, Poller, PollingConfig,
+    PollingPolicy, Preflight, RingKind, RingName,
+};
+use ctl_feed::{dpdk_consume, slot_payload, MetricsRegion, RawMessage, RingRead, RingReader, METRICS_REGION_NAME};
+use ctl_md_handler::{HwResourcesConfig, SymbolInfoConfig};
+use ctl_shm::ShmRegion;
+use ctl_ws_publisher::{payload_stream, stream_suffix, symbol_stream, Hub, PublisherConfig, Session};
+use dpdk::{DpdkEnvBuilder, DpdkProcessType};
+use tracing::{info, warn};
+
+// Logging configuration, its levels reloaded on change
+const LOG_CONFIG_PATH: &str = "configs/logging.yaml";
+
+// The market data configuration, holding the feeds and the EAL options of the secondary processes
+const MD_CONFIG_PATH: &str = "configs/market-data/hw-resources.yaml";
+
+// The symbol IDs of the rings
+const SYMBOL_INFO_PATH: &str = "configs/market-data/symbolinfo.yaml";
+
+// The publisher configuration
+const PUBLISHER_CONFIG_PATH: &str = "configs/ws-publisher/ws-publisher.yaml";
+
+// The name of the consumer cursors of the publisher in the rings
+const CONSUMER_NAME: &str = "ws-publisher";
+
+// The lcore of the publisher, unless configured, shared with the mostly idle tools
+const LCORES_CONFIG_PATH: &str = "configs/lcores.yaml";
+const LCORES_COMPONENT: &str = "ws-publisher";
+const DEFAULT_LCORE: u32 = 15;
+
+// Polling policy between the passes over the empty rings, unless configured for this component
+const POLLING_CONFIG_PATH: &str = "configs/polling.yaml";
+const POLLING_COMPONENT: &str = "ws-publisher";
+const DEFAULT_POLLING: PollingPolicy = PollingPolicy::Sleep { sleep_us: 100 };
+
+// Recovery once overtaken by a producer, unless configured for this component
+const OVERTAKEN_CONFIG_PATH: &str = "configs/overtaken.yaml";
+const OVERTAKEN_COMPONENT: &str = "ws-publisher";
+const DEFAULT_OVERTAKEN: OvertakenPolicy = OvertakenPolicy::SkipToHead;
+
+// Interval between the logs of the payloads dropped for lagging clients
+const DROPPED_LOG_INTERVAL: Duration = Duration::from_secs(60);
+
+/// WebSocket re-publisher of the market data rings.
+#[derive(Debug, Parser)]
+#[command(version)]
+struct Args {
+    /// Logging configuration.
+    #[arg(long, env = "CTL_LOG_CONFIG", default_value = LOG_CONFIG_PATH)]
+    log_config: PathBuf,
+    /// Market data configuration.
+    #[arg(long, env = "CTL_MD_CONFIG", default_value = MD_CONFIG_PATH)]
+    md_config: PathBuf,
+    /// Symbol info configuration.
+    #[arg(long, env = "CTL_SYMBOL_INFO", default_value = SYMBOL_INFO_PATH)]
+    symbol_info: PathBuf,
+    /// Publisher configuration.
+    #[arg(long, env = "CTL_WS_PUBLISHER_CONFIG", default_value = PUBLISHER_CONFIG_PATH)]
+    publisher_config: PathBuf,
+    /// Lcores configuration.
+    #[arg(long, env = "CTL_LCORES_CONFIG", default_value = LCORES_CONFIG_PATH)]
+    lcores_config: PathBuf,
+    /// Polling configuration.
+    #[arg(long, env = "CTL_POLLING_CONFIG", default_value = POLLING_CONFIG_PATH)]
+    polling_config: PathBuf,
+    /// Overtaken consumer recovery configuration.
+    #[arg(long, env = "CTL_OVERTAKEN_CONFIG", default_value = OVERTAKEN_CONFIG_PATH)]
+    overtaken_config: PathBuf,
+}
+
+fn main() -> ExitCode {
+    ctl_core::exit_code(run())
+}
+
+fn run() -> Result<(), FatalError> {
+    let args = Args::parse();
+    let _log = ctl_log::init_from_file(&args.log_config).fatal(FatalKind::Config)?;
+
+    info!("=== Binance Spot WebSocket Re-publisher ===");
+
+    let md_config = HwResourcesConfig::from_file(&args.md_config).fatal(FatalKind::Config)?;
+    let symbol_info = SymbolInfoConfig::from_file(&args.symbol_info).fatal(FatalKind::Config)?;
+    let config = PublisherConfig::from_file(&args.publisher_config).fatal(FatalKind::Config)?;
+    let addr = config.listen_addr().fatal(FatalKind::Config)?;
+    md_config.eal.apply_region_prefix();
+    let lcore = LcoresConfig::from_file(&args.lcores_config)?.lcore(LCORES_COMPONENT, DEFAULT_LCORE) as usize;
+    let polling = PollingConfig::from_file(&args.polling_config)?.policy(POLLING_COMPONENT, DEFAULT_POLLING);
+    info!("Polling: {:?}", polling);
+    let overtaken = OvertakenConfig::from_file(&args.overtaken_config)?.policy(OVERTAKEN_COMPONENT, DEFAULT_OVERTAKEN);
+    info!("Overtaken: {:?}", overtaken);
+
+    // Verify the host before the EAL initialization, which reports its failures cryptically
+    Preflight::new()
+        .require_hugepages_mounted()
+        .require_capabilities(&[Capability::IpcLock])
+        .require_primary()
+        .with_file_prefix(md_config.eal.file_prefix())
+        .run()?;
+
+    let dpdk_env = DpdkEnvBuilder::default()
+        .process_type(DpdkProcessType::Secondary)
+        .lcore_ids(vec![lcore])
+        .main_lcore_id(lcore)
+        .eal_args(md_config.eal.secondary_args())
+        .build()
+        .fatal(FatalKind::DpdkInit)?;
+
+    let metrics = ShmRegion::<MetricsRegion>::open(METRICS_REGION_NAME)?;
+
+    // Attach to the raw rings of the feeds, tracking the positions in their metrics like any consumer
+    let mut rings = Vec::new();
+    let mut streams = Vec::new();
+    for (kind, feed_kind) in [(RingKind::Top, "top"), (RingKind::Trade, "trade"), (RingKind::AggTrade, "aggtrade")] {
+        let suffix = stream_suffix(kind).expect("raw ring kinds have a stream suffix");
+        let Some(feed) = md_config.find_feed(feed_kind) else {
+            continue;
+        };
+        for feed_set in feed.feed_sets() {
+            for symbol in feed_set.ring_symbols() {
+                let symbol_id = symbol_info
+                    .symbol_id(symbol)
+                    .ok_or_else(|| format!("Symbol '{}' not found in symbolinfo.yaml", symbol))
+                    .fatal(FatalKind::Config)?;
+                let name = RingName::pubsub(kind, symbol_id).to_string();
+                let reader = RingReader::attach(&metrics, &name, CONSUMER_NAME, feed_set.slot_size)
+                    .fatal(FatalKind::SharedState)?
+                    .with_overtaken(overtaken);
+                let ring = dpdk_env.pubsub_lookup::<RawMessage>(&name).fatal(FatalKind::SharedState)?;
+                let consumer = ring.attach_consumer().fatal(FatalKind::SharedState)?;
+                info!("[{}] {} ({} symbols)", symbol, name, feed_set.symbols.len());
+                rings.push((suffix, reader, consumer));
+            }
+            streams.extend(feed_set.symbols.iter().map(|symbol| symbol_stream(symbol, suffix)));
+        }
+    }
+    if rings.is_empty() {
+        return Err(FatalError::new(FatalKind::Config, "No top, trade or aggtrade rings to re-publish"));
+    }
+    info!("Re-publishing {} streams", streams.len());
+
+    let hub = Hub::new(streams);
+    let listener = TcpListener::bind(addr).fatal(FatalKind::Network)?;
+    info!("Serving WebSocket streams on ws://{}", addr);
+    {
+        let hub = Arc::clone(&hub);
+        thread::Builder::new()
+            .name("ws-accept".to_string())
+            .spawn(move || accept(listener, hub, config.buffer, config.max_clients))
+            .fatal(FatalKind::Internal)?;
+    }
+
+    let mut last_log = Instant::now();
+    let mut poller = Poller::new(polling);
+    loop {
+        let mut did_work = false;
+        for (suffix, reader, consumer) in rings.iter_mut() {
+            let read = reader.read(dpdk_consume!(consumer)).fatal(FatalKind::Overtaken)?;
+            did_work |= read.did_work();
+            match read {
+                RingRead::Payload(_, payload) => {
+                    let payload = slot_payload(payload);
+                    let Some(stream) = payload_stream(payload, suffix) else {
+                        continue;
+                    };
+                    if let Ok(payload) = std::str::from_utf8(payload) {
+                        hub.publish(&stream, payload);
+                    }
+                }
+                RingRead::Overtaken { .. } => {
+                    warn!("{} consumer overtaken by producer, some messages missed", reader.name());
+                }
+                RingRead::Consumed | RingRead::Idle => {}
+            }
+        }
+
+        if !did_work && last_log.elapsed() >= DROPPED_LOG_INTERVAL {
+            last_log = Instant::now();
+            info!("{} clients, payloads dropped for lagging clients: {}", hub.clients(), hub.dropped());
+        }
+        // Wait before the next pass according to the configured policy
+        poller.wait(did_work);
+    }
+}
+
+/// Accepts the client connections, each served on its own thread.
+fn accept(listener: TcpListener, hub: Arc<Hub>, buffer: usize, max_clients: usize) {
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(e) => {
+                warn!("Failed to accept a client: {}", e);
+                continue;
+            }
+        };
+        if hub.clients() >= max_clients {
+            warn!("Client {} refused, {} clients connected", peer(&stream), max_clients);
+            continue;
+        }
+        let hub = Arc::clone(&hub);
+        let spawned = thread::Builder::new().name("ws-client".to_string()).spawn(move || {
+            let peer = peer(&stream);
+            match Session::accept(stream, hub, buffer) {
+                Ok(session) => {
+                    info!("Client {} connected", peer);
+                    session.run();
+                    info!("Client {} disconnected", peer);
+                }
+                Err(e) => warn!("Client {} refused: {}", peer, e),
+            }
+        });
+        if let Err(e) = spawned {
+            warn!("Failed to spawn the thread of a client: {}", e);
+        }
+    }
+}
+
+/// Returns the address of the peer of a connection, for the logs.
+fn peer(stream: &TcpStream) -> String {
+    stream.peer_addr().map_or_else(|_| "?".to_string(), |addr| addr.to_string())
+}
@@ -0,0 +1,315 @@
+//! A client connection of the WebSocket server.
+//!
+//! The streams of the path of the connection are subscribed on the handshake,
+//! and the client then manages its subscriptions with the requests of Binance:
+//! SUBSCRIBE, UNSUBSCRIBE, LIST_SUBSCRIPTIONS, and the `combined` property of
+//! SET_PROPERTY and GET_PROPERTY. The socket is non-blocking once connected, a
+//! client lagging its socket buffer missing the frames published meanwhile.
+//! https://github.com/binance/binance-spot-api-docs/blob/master/web-socket-streams.md#live-subscribingunsubscribing-to-streams
+
+use std::io::ErrorKind;
+use std::net::TcpStream;
+use std::sync::Arc;
+use std::sync::mpsc::{self, Receiver, SyncSender};
+use std::time::Duration;
+
+use ctl_websocket::{WSRequest, WSRequestId, WSRequestKind, WSResponse};
+use serde::Deserialize;
+use serde_json::Value;
+use tungstenite::handshake::server::{ErrorResponse, Request, Response};
+use tungstenite::http::StatusCode;
+use tungstenite::protocol::WebSocketConfig;
+use tungstenite::{Message, WebSocket};
+
+use crate::{frame, Hub, SessionError, StreamRequest, Update};
+
+/// Wait for the next frame before polling the requests of the client.
+const POLL_TIMEOUT: Duration = Duration::from_millis(10);
+
+/// Bytes buffered for a client lagging its socket, past which its frames are dropped.
+const MAX_WRITE_BUFFER: usize = 1 << 20;
+
+/// Error code of the invalid requests.
+/// https://github.com/binance/binance-spot-api-docs/blob/master/web-socket-streams.md#error-messages
+const INVALID_REQUEST: i64 = 2;
+
+/// Error code of the requests that are not JSON.
+const INVALID_JSON: i64 = 3;
+
+/// The property wrapping the payloads with their stream name.
+const COMBINED_PROPERTY: &str = "combined";
+
+/// A connected client.
+pub struct Session {
+    /// The ID of the client in the hub.
+    id: u64,
+    /// The connection.
+    websocket: WebSocket<TcpStream>,
+    /// Whether the payloads are wrapped with their stream name.
+    combined: bool,
+    /// The streams subscribed, in the order of their subscription.
+    subscriptions: Vec<String>,
+    /// The hub of the streams.
+    hub: Arc<Hub>,
+    /// The sender of the payloads of the streams subscribed.
+    sender: SyncSender<Update>,
+    /// The payloads of the streams subscribed.
+    receiver: Receiver<Update>,
+}
+
+impl Session {
+    /// Accepts a client connection, subscribing the streams of its path, and
+    /// buffering `buffer` payloads for it.
+    ///
+    /// # Errors
+    /// Returns an error if the handshake fails, or is refused for a path that
+    /// is not a stream path or requests streams not served.
+    pub fn accept(stream: TcpStream, hub: Arc<Hub>, buffer: usize) -> Result<Self, SessionError> {
+        stream.set_nonblocking(false)?;
+        let mut requested = None;
+        let callback = |request: &Request, response: Response| -> Result<Response, ErrorResponse> {
+            let target = request.uri().path_and_query().map_or("/", |target| target.as_str());
+            let Some(streams) = StreamRequest::parse(target) else {
+                return Err(refusal(StatusCode::NOT_FOUND, format!("Unknown path {}", target)));
+            };
+            if let Some(stream) = streams.streams.iter().find(|stream| !hub.serves(stream)) {
+                return Err(refusal(StatusCode::BAD_REQUEST, format!("Stream {} is not served", stream)));
+            }
+            requested = Some(streams);
+            Ok(response)
+        };
+        let config = WebSocketConfig::default().max_write_buffer_size(MAX_WRITE_BUFFER);
+        let websocket = tungstenite::accept_hdr_with_config(stream, callback, Some(config))
+            .map_err(|e| SessionError::Handshake(e.to_string()))?;
+        websocket.get_ref().set_nonblocking(true)?;
+        let StreamRequest { streams, combined } = requested.expect("handshake accepted without a request");
+
+        let (sender, receiver) = mpsc::sync_channel(buffer);
+        let mut session = Self {
+            id: hub.connect(),
+            websocket,
+            combined,
+            subscriptions: Vec::new(),
+            hub,
+            sender,
+            receiver,
+        };
+        // The streams were checked on the handshake
+        let _ = session.subscribe(streams);
+        Ok(session)
+    }
+
+    /// Serves the client until it disconnects.
+    pub fn run(mut self) {
+        loop {
+            if let Ok(update) = self.receiver.recv_timeout(POLL_TIMEOUT) {
+                if !self.send_update(&update) {
+                    return;
+                }
+                while let Ok(update) = self.receiver.try_recv() {
+                    if !self.send_update(&update) {
+                        return;
+                    }
+                }
+            }
+            if !self.flush() || !self.poll_requests() {
+                return;
+            }
+        }
+    }
+
+    /// Sends the frame of a payload, `false` once the connection is lost.
+    fn send_update(&mut self, update: &Update) -> bool {
+        self.send(frame(&update.stream, &update.payload, self.combined))
+    }
+
+    /// Queues a text frame, `false` once the connection is lost.
+    fn send(&mut self, text: String) -> bool {
+        match self.websocket.write(Message::text(text)) {
+            // Queued, written by the next flush
+            Ok(()) => true,
+            Err(tungstenite::Error::Io(e)) if e.kind() == ErrorKind::WouldBlock => true,
+            Err(tungstenite::Error::WriteBufferFull(_)) => {
+                self.hub.count_dropped();
+                true
+            }
+            Err(_) => false,
+        }
+    }
+
+    /// Writes the frames queued, `false` once the connection is lost.
+    fn flush(&mut self) -> bool {
+        match self.websocket.flush() {
+            Ok(()) => true,
+            Err(tungstenite::Error::Io(e)) => e.kind() == ErrorKind::WouldBlock,
+            Err(_) => false,
+        }
+    }
+
+    /// Answers the requests received, `false` once the connection is lost.
+    fn poll_requests(&mut self) -> bool {
+        loop {
+            let request = match self.websocket.read() {
+                Ok(Message::Text(text)) => text.as_str().to_string(),
+                Ok(Message::Binary(data)) => String::from_utf8_lossy(&data).into_owned(),
+                // Pings are answered and closes acknowledged by the connection
+                Ok(_) => continue,
+                Err(tungstenite::Error::Io(e)) if e.kind() == ErrorKind::WouldBlock => return true,
+                Err(_) => return false,
+            };
+            let response = self.answer(&request);
+            if !self.send(serde_json::to_string(&response).expect("serializable response")) {
+                return false;
+            }
+        }
+    }
+
+    /// Answers a request of the client.
+    fn answer(&mut self, request: &str) -> WSResponse {
+        let Ok(value) = serde_json::from_str::<Value>(request) else {
+            return error(INVALID_JSON, "Invalid JSON".to_string(), None);
+        };
+        let id = value.get("id").and_then(|id| WSRequestId::deserialize(id).ok());
+        let Ok(WSRequest { kind, id }) = serde_json::from_value::<WSRequest>(value) else {
+            return error(INVALID_REQUEST, "Invalid request".to_string(), id);
+        };
+        let result = match kind {
+            WSRequestKind::Subscribe(streams) => match self.subscribe(streams) {
+                Ok(()) => Value::Null,
+                Err(stream) => {
+                    return error(INVALID_REQUEST, format!("Invalid request: stream {} not served", stream), id);
+                }
+            },
+            WSRequestKind::Unsubscribe(streams) => {
+                self.hub.unsubscribe(self.id, &streams);
+                self.subscriptions.retain(|stream| !streams.contains(stream));
+                Value::Null
+            }
+            WSRequestKind::ListSubscriptions => Value::from(self.subscriptions.clone()),
+            WSRequestKind::SetProperty(params) => match params.as_slice() {
+                [Value::String(property), Value::Bool(combined)] if property == COMBINED_PROPERTY => {
+                    self.combined = *combined;
+                    Value::Null
+                }
+                _ => return error(INVALID_REQUEST, "Invalid request: unknown property".to_string(), id),
+            },
+            WSRequestKind::GetProperty(params) => match params.as_slice() {
+                [property] if property == COMBINED_PROPERTY => Value::Bool(self.combined),
+                _ => return error(INVALID_REQUEST, "Invalid request: unknown property".to_string(), id),
+            },
+            WSRequestKind::OrderCancelReplace(_) => {
+                return error(INVALID_REQUEST, "Invalid request: unknown method".to_string(), id);
+            }
+        };
+        WSResponse::Result { result, id }
+    }
+
+    /// Subscribes the streams, returning the first stream not served.
+    fn subscribe(&mut self, streams: Vec<String>) -> Result<(), String> {
+        self.hub.subscribe(self.id, &streams, &self.sender).map_err(str::to_string)?;
+        for stream in streams {
+            if !self.subscriptions.contains(&stream) {
+                self.subscriptions.push(stream);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Drop for Session {
+    fn drop(&mut self) {
+        self.hub.disconnect(self.id);
+    }
+}
+
+/// Returns the error response of a request.
+fn error(code: i64, msg: String, id: Option<WSRequestId>) -> WSResponse {
+    WSResponse::Error { code, msg, id }
+}
+
+/// Returns the refusal of a handshake.
+fn refusal(status: StatusCode, reason: String) -> ErrorResponse {
+    let mut response = ErrorResponse::new(Some(reason));
+    *response.status_mut() = status;
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::net::TcpListener;
+    use std::thread;
+
+    use tungstenite::stream::MaybeTlsStream;
+
+    type Client = WebSocket<MaybeTlsStream<TcpStream>>;
+
+    /// Connects a client to a session served on its own thread.
+    fn connect(hub: &Arc<Hub>, path: &str) -> tungstenite::Result<Client> {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("ws://{}{}", listener.local_addr().unwrap(), path);
+        let hub = Arc::clone(hub);
+        thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            if let Ok(session) = Session::accept(stream, hub, 16) {
+                session.run();
+            }
+        });
+        tungstenite::connect(url).map(|(websocket, _)| websocket)
+    }
+
+    fn read_text(websocket: &mut Client) -> String {
+        loop {
+            if let Message::Text(text) = websocket.read().unwrap() {
+                return text.as_str().to_string();
+            }
+        }
+    }
+
+    #[test]
+    fn test_raw_and_combined_streams() {
+        let hub = Hub::new(["btcusdt@trade", "ethusdt@trade"]);
+        let mut raw = connect(&hub, "/ws/btcusdt@trade").unwrap();
+        let mut combined = connect(&hub, "/stream?streams=btcusdt@trade").unwrap();
+        while hub.subscribers("btcusdt@trade") < 2 {
+            thread::yield_now();
+        }
+
+        hub.publish("btcusdt@trade", r#"{"e":"trade"}"#);
+        assert_eq!(read_text(&mut raw), r#"{"e":"trade"}"#);
+        assert_eq!(read_text(&mut combined), r#"{"stream":"btcusdt@trade","data":{"e":"trade"}}"#);
+
+        assert!(connect(&hub, "/ws/btcusdt@depth").is_err());
+        assert!(connect(&hub, "/api/v3/depth").is_err());
+    }
+
+    #[test]
+    fn test_requests() {
+        let hub = Hub::new(["btcusdt@trade", "ethusdt@trade"]);
+        let mut websocket = connect(&hub, "/ws").unwrap();
+        let request = |websocket: &mut Client, text: &str| {
+            websocket.send(Message::text(text)).unwrap();
+            serde_json::from_str::<Value>(&read_text(websocket)).unwrap()
+        };
+
+        let response = request(&mut websocket, r#"{"method":"SUBSCRIBE","params":["ethusdt@trade"],"id":1}"#);
+        assert_eq!(response, serde_json::json!({"result": null, "id": 1}));
+        let response = request(&mut websocket, r#"{"method":"LIST_SUBSCRIPTIONS","id":2}"#);
+        assert_eq!(response["result"], serde_json::json!(["ethusdt@trade"]));
+
+        let response = request(&mut websocket, r#"{"method":"SET_PROPERTY","params":["combined",true],"id":3}"#);
+        assert_eq!(response["result"], Value::Null);
+        hub.publish("ethusdt@trade", "{}");
+        assert_eq!(read_text(&mut websocket), r#"{"stream":"ethusdt@trade","data":{}}"#);
+
+        let response = request(&mut websocket, r#"{"method":"SUBSCRIBE","params":["btcusdt@depth"],"id":4}"#);
+        assert_eq!((response["code"].as_i64(), response["id"].as_i64()), (Some(INVALID_REQUEST), Some(4)));
+        let response = request(&mut websocket, "not json");
+        assert_eq!(response["code"].as_i64(), Some(INVALID_JSON));
+
+        let response = request(&mut websocket, r#"{"method":"UNSUBSCRIBE","params":["ethusdt@trade"],"id":5}"#);
+        assert_eq!(response["result"], Value::Null);
+        assert_eq!(hub.subscribers("ethusdt@trade"), 0);
+    }
+}
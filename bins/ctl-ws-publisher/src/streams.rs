@@ -0,0 +1,110 @@
+//! The stream names and frames of the re-published payloads, mirroring the
+//! market data streams of Binance.
+//! https://github.com/binance/binance-spot-api-docs/blob/master/web-socket-streams.md#general-wss-information
+
+use ctl_core::RingKind;
+use ctl_feed::{AggTrade, Top, Trade};
+use ctl_websocket::{stream_name, StreamSuffix};
+use serde::Deserialize;
+
+/// The symbol of a feed payload.
+#[derive(Deserialize)]
+struct PayloadSymbol<'a> {
+    #[serde(rename = "s")]
+    symbol: &'a str,
+}
+
+/// Returns the stream name suffix of the raw rings of a kind, `None` for the rings not re-published.
+pub fn stream_suffix(kind: RingKind) -> Option<&'static str> {
+    match kind {
+        RingKind::Top => Some(Top::SUFFIX),
+        RingKind::Trade => Some(Trade::SUFFIX),
+        RingKind::AggTrade => Some(AggTrade::SUFFIX),
+        RingKind::Depth | RingKind::Stats | RingKind::Kline | RingKind::Signal => None,
+    }
+}
+
+/// Returns the stream of a symbol (e.g. `btcusdt@bookTicker` of `BTCUSDT`).
+pub fn symbol_stream(symbol: &str, suffix: &str) -> String {
+    stream_name(&symbol.to_lowercase(), suffix, None)
+}
+
+/// Returns the stream of a feed payload, `None` if it carries no symbol.
+pub fn payload_stream(payload: &[u8], suffix: &str) -> Option<String> {
+    let payload: PayloadSymbol<'_> = serde_json::from_slice(payload).ok()?;
+    Some(symbol_stream(payload.symbol, suffix))
+}
+
+/// Returns the frame of a payload of a stream, wrapped as `{"stream":..,"data":..}` on the combined connections.
+pub fn frame(stream: &str, payload: &str, combined: bool) -> String {
+    if combined {
+        format!(r#"{{"stream":"{}","data":{}}}"#, stream, payload)
+    } else {
+        payload.to_string()
+    }
+}
+
+/// The streams requested by the path of a connection.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StreamRequest {
+    /// The streams subscribed on connection.
+    pub streams: Vec<String>,
+    /// Whether the payloads are wrapped with their stream name.
+    pub combined: bool,
+}
+
+impl StreamRequest {
+    /// Parses the request target of a connection: the raw streams at `/ws` or
+    /// `/ws/<stream>[/<stream>..]`, the combined ones at `/stream` or
+    /// `/stream?streams=<stream>[/<stream>..]`. Returns `None` for other paths.
+    pub fn parse(target: &str) -> Option<Self> {
+        let (path, query) = target.split_once('?').unwrap_or((target, ""));
+        let path = path.trim_end_matches('/');
+        let (streams, combined) = if path == "/ws" {
+            ("", false)
+        } else if let Some(streams) = path.strip_prefix("/ws/") {
+            (streams, false)
+        } else if path == "/stream" {
+            (query.split('&').find_map(|param| param.strip_prefix("streams=")).unwrap_or(""), true)
+        } else {
+            return None;
+        };
+        let streams = streams.split('/').filter(|stream| !stream.is_empty()).map(str::to_string).collect();
+        Some(Self { streams, combined })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_payload_stream() {
+        let json = br#"{"u":400900217,"s":"BNBUSDT","b":"25.35190000","B":"31.21000000","a":"25.36520000"}"#;
+        assert_eq!(payload_stream(json, Top::SUFFIX).unwrap(), "bnbusdt@bookTicker");
+        assert!(payload_stream(br#"{"result":null,"id":1}"#, Top::SUFFIX).is_none());
+        assert_eq!(stream_suffix(RingKind::AggTrade), Some("aggTrade"));
+        assert_eq!(stream_suffix(RingKind::Kline), None);
+
+        assert_eq!(frame("bnbusdt@trade", r#"{"e":"trade"}"#, false), r#"{"e":"trade"}"#);
+        assert_eq!(
+            frame("bnbusdt@trade", r#"{"e":"trade"}"#, true),
+            r#"{"stream":"bnbusdt@trade","data":{"e":"trade"}}"#
+        );
+    }
+
+    #[test]
+    fn test_parse_request() {
+        let request = StreamRequest::parse("/ws/btcusdt@trade/ethusdt@trade").unwrap();
+        assert_eq!(request.streams, vec!["btcusdt@trade", "ethusdt@trade"]);
+        assert!(!request.combined);
+        assert!(StreamRequest::parse("/ws").unwrap().streams.is_empty());
+
+        let request = StreamRequest::parse("/stream?streams=btcusdt@bookTicker/ethusdt@trade").unwrap();
+        assert_eq!(request.streams, vec!["btcusdt@bookTicker", "ethusdt@trade"]);
+        assert!(request.combined);
+        assert!(StreamRequest::parse("/stream").unwrap().streams.is_empty());
+
+        assert!(StreamRequest::parse("/api/v3/depth").is_none());
+    }
+}
@@ -0,0 +1,14 @@
+use thiserror::Error;
+
+use crate::{MAX_ASSET_LEN, MAX_BALANCE_ASSETS};
+
+/// Errors that can occur when updating the balances table.
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum BalanceError {
+    /// The asset name doesn't fit in a slot.
+    #[error("Asset '{0}' is longer than {MAX_ASSET_LEN} bytes")]
+    AssetTooLong(String),
+    /// All the slots of the table are in use.
+    #[error("Balances table full ({MAX_BALANCE_ASSETS} assets), cannot add '{0}'")]
+    TableFull(String),
+}
@@ -0,0 +1,89 @@
+use serde::{Deserialize, Deserializer};
+
+/// An account event of the user data stream.
+/// https://github.com/binance/binance-spot-api-docs/blob/master/user-data-stream.md#account-update
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(tag = "e")]
+pub enum AccountEvent {
+    /// The balances of the assets that changed, sent on every balance change.
+    #[serde(rename = "outboundAccountPosition")]
+    AccountPosition {
+        /// The time of the last account update, in milliseconds.
+        #[serde(rename = "u")]
+        update_time_ms: u64,
+        /// The absolute balances of the changed assets.
+        #[serde(rename = "B")]
+        balances: Vec<PositionBalance>,
+    },
+    /// A deposit, withdrawal or transfer of an asset.
+    #[serde(rename = "balanceUpdate")]
+    BalanceUpdate {
+        /// The asset.
+        #[serde(rename = "a")]
+        asset: String,
+        /// The change of the free balance.
+        #[serde(rename = "d", deserialize_with = "de_f64_str")]
+        delta: f64,
+        /// The time the change cleared, in milliseconds.
+        #[serde(rename = "T")]
+        clear_time_ms: u64,
+    },
+}
+
+/// The balance of an asset in an `outboundAccountPosition` event.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct PositionBalance {
+    /// The asset.
+    #[serde(rename = "a")]
+    pub asset: String,
+    /// The free amount.
+    #[serde(rename = "f", deserialize_with = "de_f64_str")]
+    pub free: f64,
+    /// The amount locked in open orders.
+    #[serde(rename = "l", deserialize_with = "de_f64_str")]
+    pub locked: f64,
+}
+
+/// Deserializes a decimal sent as a JSON string.
+fn de_f64_str<'de, D: Deserializer<'de>>(deserializer: D) -> Result<f64, D::Error> {
+    let value = String::deserialize(deserializer)?;
+    value.parse().map_err(serde::de::Error::custom)
+}
+
+impl AccountEvent {
+    /// Parses an account event payload, `None` for other user data events.
+    pub fn from_json(data: &[u8]) -> Option<Self> {
+        serde_json::from_slice(data).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_account_position() {
+        let event = AccountEvent::from_json(
+            br#"{"e":"outboundAccountPosition","E":1564034571105,"u":1564034571073,"B":[{"a":"ETH","f":"10000.000000","l":"0.500000"}]}"#,
+        )
+        .unwrap();
+        let AccountEvent::AccountPosition { update_time_ms, balances } = event else {
+            panic!("expected an account position");
+        };
+        assert_eq!(update_time_ms, 1564034571073);
+        assert_eq!(balances, vec![PositionBalance { asset: "ETH".to_string(), free: 10000.0, locked: 0.5 }]);
+    }
+
+    #[test]
+    fn test_parse_balance_update() {
+        let event = AccountEvent::from_json(
+            br#"{"e":"balanceUpdate","E":1573200697110,"a":"BTC","d":"-100.00000000","T":1573200697068}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            event,
+            AccountEvent::BalanceUpdate { asset: "BTC".to_string(), delta: -100.0, clear_time_ms: 1573200697068 }
+        );
+        assert!(AccountEvent::from_json(br#"{"e":"executionReport","s":"BTCUSDT"}"#).is_none());
+    }
+}
@@ -0,0 +1,17 @@
+//! Account balances shared with the pre-trade risk checks.
+//!
+//! The private data handler applies the account events of the user data stream
+//! to a shared memory balances table created by ctl-resource-manager, and
+//! periodically reconciles it against the REST account information, so the
+//! free and locked balances can be checked without a REST call.
+
+mod errors;
+mod events;
+mod table;
+
+pub use errors::BalanceError;
+pub use events::{AccountEvent, PositionBalance};
+pub use table::{
+    Balance, BalanceDrift, BalanceRegion, BalanceSlot, BalanceTracker, BALANCE_REGION_NAME,
+    MAX_ASSET_LEN, MAX_BALANCE_ASSETS, RECONCILE_INTERVAL,
+};
@@ -0,0 +1,350 @@
+use std::hint;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering, fence};
+use std::time::Duration;
+
+use ctl_rest::AccountInfo;
+use ctl_shm::{ShmRegion, ShmSafe};
+use hashbrown::HashMap;
+
+use crate::{AccountEvent, BalanceError};
+
+/// Name of the balances table region.
+pub const BALANCE_REGION_NAME: &str = "ctl_balances";
+
+/// Maximum number of assets in the balances table.
+pub const MAX_BALANCE_ASSETS: usize = 256;
+
+/// Maximum length of an asset name, in bytes.
+pub const MAX_ASSET_LEN: usize = 16;
+
+/// Interval between the reconciliations of the balances against the REST account information.
+pub const RECONCILE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Balance differences below this are rounding, not drift (assets have at most 8 decimals).
+const DRIFT_EPSILON: f64 = 1e-9;
+
+/// The balance of an asset.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Balance {
+    /// The free amount.
+    pub free: f64,
+    /// The amount locked in open orders.
+    pub locked: f64,
+    /// The time of the account update the balance is from, in milliseconds.
+    pub update_time_ms: u64,
+}
+
+impl Balance {
+    /// Returns the total amount.
+    pub fn total(&self) -> f64 {
+        self.free + self.locked
+    }
+}
+
+/// The seqlock-protected balance of an asset, written by a single tracker.
+#[repr(C, align(64))]
+#[derive(Debug, Default)]
+pub struct BalanceSlot {
+    /// The sequence, odd while the slot is being written and zero until first written.
+    seq: AtomicU64,
+    /// The asset name, zero-padded.
+    asset: [AtomicU64; MAX_ASSET_LEN / 8],
+    /// The free amount, as `f64` bits.
+    free: AtomicU64,
+    /// The locked amount, as `f64` bits.
+    locked: AtomicU64,
+    /// The time of the account update, in milliseconds.
+    update_time_ms: AtomicU64,
+}
+
+impl BalanceSlot {
+    /// Overwrites the slot with the balance of `asset`, at most `MAX_ASSET_LEN` bytes.
+    fn write(&self, asset: &str, balance: &Balance) {
+        let mut name = [0u8; MAX_ASSET_LEN];
+        name[..asset.len()].copy_from_slice(asset.as_bytes());
+
+        let seq = self.seq.load(Ordering::Relaxed);
+        self.seq.store(seq + 1, Ordering::Relaxed);
+        fence(Ordering::Release);
+
+        for (word, chunk) in self.asset.iter().zip(name.chunks_exact(8)) {
+            word.store(u64::from_le_bytes(chunk.try_into().unwrap_or_default()), Ordering::Relaxed);
+        }
+        self.free.store(balance.free.to_bits(), Ordering::Relaxed);
+        self.locked.store(balance.locked.to_bits(), Ordering::Relaxed);
+        self.update_time_ms.store(balance.update_time_ms, Ordering::Relaxed);
+
+        self.seq.store(seq + 2, Ordering::Release);
+    }
+
+    /// Reads a consistent snapshot of the slot, `None` if it was never written.
+    pub fn read(&self) -> Option<(String, Balance)> {
+        loop {
+            let before = self.seq.load(Ordering::Acquire);
+            if before == 0 {
+                return None;
+            }
+            if before & 1 == 1 {
+                hint::spin_loop();
+                continue;
+            }
+
+            let mut name = [0u8; MAX_ASSET_LEN];
+            for (word, chunk) in self.asset.iter().zip(name.chunks_exact_mut(8)) {
+                chunk.copy_from_slice(&word.load(Ordering::Relaxed).to_le_bytes());
+            }
+            let balance = Balance {
+                free: f64::from_bits(self.free.load(Ordering::Relaxed)),
+                locked: f64::from_bits(self.locked.load(Ordering::Relaxed)),
+                update_time_ms: self.update_time_ms.load(Ordering::Relaxed),
+            };
+
+            fence(Ordering::Acquire);
+            if self.seq.load(Ordering::Relaxed) == before {
+                let len = name.iter().position(|&b| b == 0).unwrap_or(MAX_ASSET_LEN);
+                return Some((String::from_utf8_lossy(&name[..len]).into_owned(), balance));
+            }
+        }
+    }
+}
+
+/// The balances table region. Assets are assigned slots in the order they are first seen.
+#[repr(C)]
+pub struct BalanceRegion {
+    /// The number of slots in use.
+    len: AtomicU64,
+    /// The slots.
+    pub slots: [BalanceSlot; MAX_BALANCE_ASSETS],
+}
+
+// SAFETY: `BalanceRegion` is `repr(C)`, made only of atomics and valid when zeroed.
+unsafe impl ShmSafe for BalanceRegion {}
+
+impl BalanceRegion {
+    /// Returns the slots in use.
+    fn used_slots(&self) -> &[BalanceSlot] {
+        let len = (self.len.load(Ordering::Acquire) as usize).min(MAX_BALANCE_ASSETS);
+        &self.slots[..len]
+    }
+
+    /// Reads the balance of an asset, `None` if it isn't in the table.
+    pub fn read(&self, asset: &str) -> Option<Balance> {
+        self.used_slots()
+            .iter()
+            .filter_map(BalanceSlot::read)
+            .find_map(|(name, balance)| (name == asset).then_some(balance))
+    }
+
+    /// Reads the balances of all the assets in the table.
+    pub fn balances(&self) -> Vec<(String, Balance)> {
+        self.used_slots().iter().filter_map(BalanceSlot::read).collect()
+    }
+}
+
+/// A difference between the tracked balance of an asset and the exchange's.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BalanceDrift {
+    /// The asset.
+    pub asset: String,
+    /// The balance tracked from the account events.
+    pub tracked: Balance,
+    /// The balance of the REST account information.
+    pub exchange: Balance,
+}
+
+/// Applies the account events to the balances and publishes them to the
+/// balances table. There must be a single tracker per table.
+pub struct BalanceTracker {
+    /// The balances table region.
+    region: Arc<ShmRegion<BalanceRegion>>,
+    /// The balances and slot indices, by asset.
+    balances: HashMap<String, (usize, Balance)>,
+}
+
+impl BalanceTracker {
+    /// Creates a tracker over the balances table, clearing it.
+    pub fn new(region: Arc<ShmRegion<BalanceRegion>>) -> Self {
+        region.len.store(0, Ordering::Release);
+        Self { region, balances: HashMap::new() }
+    }
+
+    /// Returns the tracked balance of an asset.
+    pub fn balance(&self, asset: &str) -> Option<&Balance> {
+        self.balances.get(asset).map(|(_, balance)| balance)
+    }
+
+    /// Sets the balance of an asset, assigning it a slot if new.
+    fn set(&mut self, asset: &str, balance: Balance) -> Result<(), BalanceError> {
+        let index = match self.balances.get(asset) {
+            Some((index, _)) => *index,
+            None => {
+                if asset.len() > MAX_ASSET_LEN {
+                    return Err(BalanceError::AssetTooLong(asset.to_string()));
+                }
+                let index = self.balances.len();
+                if index == MAX_BALANCE_ASSETS {
+                    return Err(BalanceError::TableFull(asset.to_string()));
+                }
+                index
+            }
+        };
+
+        self.region.slots[index].write(asset, &balance);
+        if index == self.balances.len() {
+            self.region.len.store(index as u64 + 1, Ordering::Release);
+        }
+        self.balances.insert(asset.to_string(), (index, balance));
+        Ok(())
+    }
+
+    /// Applies an account event payload, returning the number of assets updated.
+    /// Other payloads, and changes older than the tracked balances, are ignored.
+    ///
+    /// # Errors
+    /// Returns an error if a new asset can't be added to the table.
+    pub fn on_event(&mut self, data: &[u8]) -> Result<usize, BalanceError> {
+        let mut updated = 0;
+        match AccountEvent::from_json(data) {
+            Some(AccountEvent::AccountPosition { update_time_ms, balances }) => {
+                for position in balances {
+                    if self.balance(&position.asset).is_some_and(|b| b.update_time_ms > update_time_ms) {
+                        continue;
+                    }
+                    let balance = Balance { free: position.free, locked: position.locked, update_time_ms };
+                    self.set(&position.asset, balance)?;
+                    updated += 1;
+                }
+            }
+            Some(AccountEvent::BalanceUpdate { asset, delta, clear_time_ms }) => {
+                // The account position of the same change may have been applied already
+                let current = self.balance(&asset).copied().unwrap_or_default();
+                if current.update_time_ms < clear_time_ms {
+                    let balance = Balance {
+                        free: current.free + delta,
+                        update_time_ms: clear_time_ms,
+                        ..current
+                    };
+                    self.set(&asset, balance)?;
+                    updated += 1;
+                }
+            }
+            None => {}
+        }
+        Ok(updated)
+    }
+
+    /// Reconciles the tracked balances against the REST account information,
+    /// overwriting the ones it is more recent than, and returning their drifts.
+    /// Tracked assets missing from the account information have a zero balance.
+    ///
+    /// # Errors
+    /// Returns an error if a new asset can't be added to the table.
+    pub fn reconcile(&mut self, account: &AccountInfo) -> Result<Vec<BalanceDrift>, BalanceError> {
+        let mut exchange: HashMap<&str, Balance> = self
+            .balances
+            .keys()
+            .map(|asset| (asset.as_str(), Balance { update_time_ms: account.update_time, ..Balance::default() }))
+            .collect();
+        for balance in &account.balances {
+            exchange.insert(
+                &balance.asset,
+                Balance { free: balance.free, locked: balance.locked, update_time_ms: account.update_time },
+            );
+        }
+
+        let mut drifts = Vec::new();
+        let mut updates: Vec<_> = exchange.into_iter().map(|(asset, b)| (asset.to_string(), b)).collect();
+        updates.sort_by(|a, b| a.0.cmp(&b.0));
+        for (asset, exchange) in updates {
+            let tracked = self.balance(&asset).copied();
+            if tracked.is_some_and(|t| t.update_time_ms > exchange.update_time_ms) {
+                continue;
+            }
+            let tracked = tracked.unwrap_or_default();
+            if (tracked.free - exchange.free).abs() > DRIFT_EPSILON
+                || (tracked.locked - exchange.locked).abs() > DRIFT_EPSILON
+            {
+                drifts.push(BalanceDrift { asset: asset.clone(), tracked, exchange });
+            }
+            self.set(&asset, exchange)?;
+        }
+        Ok(drifts)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ctl_rest::AccountBalance;
+
+    fn tracker(name: &str) -> (Arc<ShmRegion<BalanceRegion>>, BalanceTracker) {
+        let name = format!("ctl_test_balances_{}_{}", name, std::process::id());
+        let region = Arc::new(ShmRegion::<BalanceRegion>::create(&name).unwrap());
+        (region.clone(), BalanceTracker::new(region))
+    }
+
+    fn position(update_time_ms: u64, asset: &str, free: &str, locked: &str) -> String {
+        format!(
+            r#"{{"e":"outboundAccountPosition","E":0,"u":{},"B":[{{"a":"{}","f":"{}","l":"{}"}}]}}"#,
+            update_time_ms, asset, free, locked
+        )
+    }
+
+    #[test]
+    fn test_slot_roundtrip() {
+        let slot = BalanceSlot::default();
+        assert_eq!(slot.read(), None);
+        let balance = Balance { free: 1.0, locked: 2.0, update_time_ms: 3 };
+        slot.write("1000SATS", &balance);
+        assert_eq!(slot.read(), Some(("1000SATS".to_string(), balance)));
+    }
+
+    #[test]
+    fn test_account_events() {
+        let (region, mut tracker) = tracker("events");
+        assert_eq!(tracker.on_event(position(10, "BTC", "1.5", "0.5").as_bytes()), Ok(1));
+        assert_eq!(tracker.on_event(position(10, "USDT", "100", "0").as_bytes()), Ok(1));
+
+        // A stale position is ignored, a newer deposit is applied
+        assert_eq!(tracker.on_event(position(5, "BTC", "9", "9").as_bytes()), Ok(0));
+        let deposit = r#"{"e":"balanceUpdate","E":0,"a":"BTC","d":"0.25000000","T":20}"#;
+        assert_eq!(tracker.on_event(deposit.as_bytes()), Ok(1));
+        assert_eq!(tracker.on_event(deposit.as_bytes()), Ok(0));
+
+        let btc = region.read("BTC").unwrap();
+        assert_eq!((btc.free, btc.locked, btc.update_time_ms), (1.75, 0.5, 20));
+        assert_eq!(btc.total(), 2.25);
+        assert_eq!(region.balances().len(), 2);
+        assert_eq!(region.read("ETH"), None);
+
+        let long = position(30, "ABCDEFGHIJKLMNOPQ", "1", "0");
+        assert!(matches!(tracker.on_event(long.as_bytes()), Err(BalanceError::AssetTooLong(_))));
+    }
+
+    #[test]
+    fn test_reconcile() {
+        let (region, mut tracker) = tracker("reconcile");
+        tracker.on_event(position(10, "BTC", "1", "0").as_bytes()).unwrap();
+        tracker.on_event(position(10, "BNB", "2", "0").as_bytes()).unwrap();
+        tracker.on_event(position(50, "USDT", "100", "0").as_bytes()).unwrap();
+
+        let account = AccountInfo {
+            update_time: 40,
+            balances: vec![
+                AccountBalance { asset: "BTC".to_string(), free: 0.5, locked: 0.5 },
+                AccountBalance { asset: "ETH".to_string(), free: 3.0, locked: 0.0 },
+                AccountBalance { asset: "USDT".to_string(), free: 90.0, locked: 0.0 },
+            ],
+        };
+        let drifts = tracker.reconcile(&account).unwrap();
+        let assets: Vec<_> = drifts.iter().map(|d| d.asset.as_str()).collect();
+        assert_eq!(assets, vec!["BNB", "BTC", "ETH"]);
+
+        assert_eq!(region.read("BTC").unwrap().free, 0.5);
+        assert_eq!(region.read("BNB").unwrap().total(), 0.0);
+        assert_eq!(region.read("ETH").unwrap().free, 3.0);
+        // The account position newer than the snapshot is kept
+        assert_eq!(region.read("USDT").unwrap().free, 100.0);
+    }
+}
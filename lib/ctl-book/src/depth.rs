@@ -0,0 +1,305 @@
+//! Local order book maintained from the diff depth stream.
+//! https://github.com/binance/binance-spot-api-docs/blob/master/web-socket-streams.md#how-to-manage-a-local-order-book-correctly
+//!
+//! Each diff update covers the update IDs `[U, u]`, and must chain with the
+//! book: `U` at most one past the update ID of the book. A discontinuity means
+//! updates were missed, so the book turns stale and is resynced from a REST
+//! snapshot, the updates received meanwhile being buffered and replayed over
+//! the snapshot.
+
+use std::cmp::Ordering;
+use std::collections::VecDeque;
+
+use crate::{BookSnapshotRegion, Level};
+
+/// Maximum number of updates buffered while waiting for a snapshot, the
+/// oldest being dropped beyond (the replay then detecting the gap).
+pub const MAX_BUFFERED_UPDATES: usize = 4096;
+
+/// A diff depth update of a symbol.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DepthUpdate {
+    /// The first update ID of the update (`U`).
+    pub first_update_id: u64,
+    /// The final update ID of the update (`u`).
+    pub final_update_id: u64,
+    /// The bid levels to update, a zero quantity removing the level.
+    pub bids: Vec<Level>,
+    /// The ask levels to update, a zero quantity removing the level.
+    pub asks: Vec<Level>,
+}
+
+/// The outcome of an update or a snapshot applied to a `DepthBook`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DepthStatus {
+    /// The book is in sync, the update applied.
+    Applied,
+    /// The update precedes the book and was dropped.
+    Outdated,
+    /// The update was buffered, the book waiting for a snapshot.
+    Buffered,
+    /// The update IDs don't chain: the book is stale until resynced from a
+    /// snapshot, `expected` being the first update ID chaining with the book.
+    Gap { expected: u64, received: u64 },
+}
+
+/// A side of the book, best level first.
+#[derive(Debug, Clone, Default)]
+struct BookSide {
+    /// The levels, best first.
+    levels: Vec<Level>,
+    /// Whether the best level is the highest price.
+    descending: bool,
+}
+
+impl BookSide {
+    /// Compares the price of a level with `price`, in the order of the side.
+    fn order(&self, level: &Level, price: f64) -> Ordering {
+        let order = level.price.total_cmp(&price);
+        if self.descending { order.reverse() } else { order }
+    }
+
+    /// Sets the quantity of a level, removing it if zero.
+    fn update(&mut self, level: &Level) {
+        match self.levels.binary_search_by(|l| self.order(l, level.price)) {
+            Ok(index) if level.qty == 0.0 => {
+                self.levels.remove(index);
+            }
+            Ok(index) => self.levels[index].qty = level.qty,
+            Err(_) if level.qty == 0.0 => {}
+            Err(index) => self.levels.insert(index, *level),
+        }
+    }
+
+    /// Replaces the levels, sorting them best first.
+    fn replace(&mut self, levels: &[Level]) {
+        self.levels.clear();
+        self.levels.extend(levels.iter().filter(|level| level.qty != 0.0));
+        let descending = self.descending;
+        self.levels.sort_by(|a, b| {
+            let order = a.price.total_cmp(&b.price);
+            if descending { order.reverse() } else { order }
+        });
+    }
+}
+
+/// The local order book of a symbol, its update IDs checked for discontinuities.
+#[derive(Debug, Clone)]
+pub struct DepthBook {
+    /// The bids, highest first.
+    bids: BookSide,
+    /// The asks, lowest first.
+    asks: BookSide,
+    /// The ID of the last update applied to the book.
+    update_id: u64,
+    /// Whether the book waits for a snapshot, until then not tracking the exchange.
+    stale: bool,
+    /// The updates received while stale, replayed over the snapshot.
+    buffered: VecDeque<DepthUpdate>,
+}
+
+impl Default for DepthBook {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DepthBook {
+    /// Creates an empty book, stale until its first snapshot.
+    pub fn new() -> Self {
+        Self {
+            bids: BookSide { levels: Vec::new(), descending: true },
+            asks: BookSide { levels: Vec::new(), descending: false },
+            update_id: 0,
+            stale: true,
+            buffered: VecDeque::new(),
+        }
+    }
+
+    /// Returns true if the book waits for a snapshot, its levels not to be traded on.
+    pub fn is_stale(&self) -> bool {
+        self.stale
+    }
+
+    /// Returns the ID of the last update applied to the book.
+    pub fn update_id(&self) -> u64 {
+        self.update_id
+    }
+
+    /// Returns the bid levels, best first.
+    pub fn bids(&self) -> &[Level] {
+        &self.bids.levels
+    }
+
+    /// Returns the ask levels, best first.
+    pub fn asks(&self) -> &[Level] {
+        &self.asks.levels
+    }
+
+    /// Returns the number of updates buffered for the snapshot.
+    pub fn buffered(&self) -> usize {
+        self.buffered.len()
+    }
+
+    /// Applies a diff update, buffering it while the book is stale.
+    ///
+    /// On `DepthStatus::Gap`, the caller marks the shared snapshot stale and
+    /// fetches a snapshot for `DepthBook::resync`.
+    ///
+    /// LATENCY: FAST_PATH
+    pub fn apply(&mut self, update: DepthUpdate) -> DepthStatus {
+        if self.stale {
+            self.buffer(update);
+            return DepthStatus::Buffered;
+        }
+        let status = self.chain(&update);
+        if let DepthStatus::Gap { .. } = status {
+            self.stale = true;
+            self.buffer(update);
+        }
+        status
+    }
+
+    /// Resyncs the book from a snapshot, replaying the buffered updates following it.
+    ///
+    /// Returns `DepthStatus::Gap` if the snapshot is older than the buffered
+    /// updates, or those don't chain, the book staying stale until another
+    /// snapshot.
+    ///
+    /// LATENCY: SLOW_PATH
+    pub fn resync(&mut self, last_update_id: u64, bids: &[Level], asks: &[Level]) -> DepthStatus {
+        // Drop the updates the snapshot already includes
+        while self.buffered.front().is_some_and(|update| update.final_update_id <= last_update_id) {
+            self.buffered.pop_front();
+        }
+        let first_buffered = self.buffered.front().map(|update| update.first_update_id);
+        if let Some(first_update_id) = first_buffered.filter(|&id| id > last_update_id + 1) {
+            return DepthStatus::Gap { expected: last_update_id + 1, received: first_update_id };
+        }
+
+        self.bids.replace(bids);
+        self.asks.replace(asks);
+        self.update_id = last_update_id;
+        self.stale = false;
+        while let Some(update) = self.buffered.pop_front() {
+            if let status @ DepthStatus::Gap { .. } = self.chain(&update) {
+                self.stale = true;
+                self.buffered.push_front(update);
+                return status;
+            }
+        }
+        DepthStatus::Applied
+    }
+
+    /// Publishes the book to its shared snapshot, or marks the snapshot stale.
+    ///
+    /// LATENCY: FAST_PATH
+    pub fn publish(&self, region: &BookSnapshotRegion) {
+        if self.stale {
+            region.mark_stale();
+        } else {
+            region.write(self.update_id, self.bids(), self.asks());
+        }
+    }
+
+    /// Applies an update if it chains with the book.
+    fn chain(&mut self, update: &DepthUpdate) -> DepthStatus {
+        if update.final_update_id <= self.update_id {
+            return DepthStatus::Outdated;
+        }
+        if update.first_update_id > self.update_id + 1 {
+            return DepthStatus::Gap { expected: self.update_id + 1, received: update.first_update_id };
+        }
+        for level in &update.bids {
+            self.bids.update(level);
+        }
+        for level in &update.asks {
+            self.asks.update(level);
+        }
+        self.update_id = update.final_update_id;
+        DepthStatus::Applied
+    }
+
+    /// Buffers an update for the snapshot, dropping the oldest beyond the limit.
+    fn buffer(&mut self, update: DepthUpdate) {
+        if self.buffered.len() == MAX_BUFFERED_UPDATES {
+            self.buffered.pop_front();
+        }
+        self.buffered.push_back(update);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn level(price: f64, qty: f64) -> Level {
+        Level { price, qty }
+    }
+
+    fn update(first: u64, last: u64, bids: &[(f64, f64)], asks: &[(f64, f64)]) -> DepthUpdate {
+        DepthUpdate {
+            first_update_id: first,
+            final_update_id: last,
+            bids: bids.iter().map(|&(p, q)| level(p, q)).collect(),
+            asks: asks.iter().map(|&(p, q)| level(p, q)).collect(),
+        }
+    }
+
+    fn synced_book() -> DepthBook {
+        let mut book = DepthBook::new();
+        assert_eq!(book.apply(update(99, 101, &[(99.0, 1.0)], &[])), DepthStatus::Buffered);
+        let status = book.resync(100, &[level(100.0, 1.0), level(101.0, 2.0)], &[level(102.0, 3.0)]);
+        assert_eq!(status, DepthStatus::Applied);
+        book
+    }
+
+    #[test]
+    fn test_resync_replays_buffered() {
+        let book = synced_book();
+        assert!(!book.is_stale());
+        assert_eq!(book.update_id(), 101);
+        assert_eq!(book.buffered(), 0);
+        assert_eq!(book.bids(), [level(101.0, 2.0), level(100.0, 1.0), level(99.0, 1.0)]);
+        assert_eq!(book.asks(), [level(102.0, 3.0)]);
+    }
+
+    #[test]
+    fn test_apply_chained_updates() {
+        let mut book = synced_book();
+        assert_eq!(book.apply(update(102, 103, &[(101.0, 0.0)], &[(101.5, 1.0)])), DepthStatus::Applied);
+        assert_eq!(book.bids()[0], level(100.0, 1.0));
+        assert_eq!(book.asks()[0], level(101.5, 1.0));
+        assert_eq!(book.apply(update(100, 103, &[(50.0, 1.0)], &[])), DepthStatus::Outdated);
+        assert_eq!(book.bids().len(), 2);
+    }
+
+    #[test]
+    fn test_gap_marks_stale_until_resync() {
+        let mut book = synced_book();
+        let region = BookSnapshotRegion::default();
+        book.publish(&region);
+        assert!(!region.read().unwrap().is_stale());
+
+        // Updates 102-104 missed
+        let status = book.apply(update(105, 106, &[(100.0, 5.0)], &[]));
+        assert_eq!(status, DepthStatus::Gap { expected: 102, received: 105 });
+        assert!(book.is_stale());
+        book.publish(&region);
+        let snapshot = region.read().unwrap();
+        assert!(snapshot.is_stale());
+        assert_eq!(snapshot.update_id, 101);
+        assert_eq!(book.apply(update(107, 107, &[], &[])), DepthStatus::Buffered);
+
+        // A snapshot older than the buffered updates doesn't resync the book
+        let status = book.resync(103, &[level(100.0, 4.0)], &[]);
+        assert_eq!(status, DepthStatus::Gap { expected: 104, received: 105 });
+        assert!(book.is_stale());
+
+        assert_eq!(book.resync(105, &[level(100.0, 4.0)], &[level(103.0, 1.0)]), DepthStatus::Applied);
+        assert_eq!(book.update_id(), 107);
+        assert_eq!(book.bids(), [level(100.0, 5.0)]);
+        book.publish(&region);
+        assert!(!region.read().unwrap().is_stale());
+    }
+}
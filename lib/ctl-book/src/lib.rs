@@ -0,0 +1,18 @@
+//! Order book state shared with the strategies.
+//!
+//! The book builder writes a fixed-depth snapshot of each symbol's book into a
+//! shared memory region, so strategies can read consistent snapshots lock-free
+//! without consuming the full depth stream themselves.
+//!
+//! The local book is maintained from the diff depth updates, resynced from a
+//! REST snapshot when their update IDs don't chain, its shared snapshot marked
+//! stale meanwhile.
+
+mod depth;
+mod snapshot;
+
+pub use depth::{DepthBook, DepthStatus, DepthUpdate, MAX_BUFFERED_UPDATES};
+
+pub use snapshot::{
+    BookLevel, BookSnapshot, BookSnapshotRegion, Level, BOOK_DEPTH, book_region_name,
+};
@@ -0,0 +1,307 @@
+//! Fixed-depth L2 book snapshot in shared memory.
+//!
+//! Each symbol has its own region named `ctl_book_{symbol_id}`, written by a
+//! single book builder. The region carries a version counter used as a seqlock:
+//! it is odd while the builder updates the snapshot, and readers retry until
+//! they read the snapshot under the same even version.
+//!
+//! The builder marks a snapshot stale while it resyncs the book after missed
+//! updates, so readers never trade on a book silently diverging from the exchange.
+
+use std::hint;
+use std::sync::atomic::{AtomicU64, Ordering, fence};
+
+use ctl_shm::ShmSafe;
+
+/// Number of price levels per side of the book snapshot.
+pub const BOOK_DEPTH: usize = 32;
+
+/// Returns the name of the book snapshot region of a symbol.
+pub fn book_region_name(symbol_id: u32) -> String {
+    format!("ctl_book_{}", symbol_id)
+}
+
+/// A price level of a book snapshot.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Level {
+    /// The level price.
+    pub price: f64,
+    /// The quantity at the level.
+    pub qty: f64,
+}
+
+/// A price level in the shared region.
+#[repr(C)]
+#[derive(Debug, Default)]
+pub struct BookLevel {
+    /// The level price, as `f64` bits.
+    price: AtomicU64,
+    /// The quantity at the level, as `f64` bits.
+    qty: AtomicU64,
+}
+
+impl BookLevel {
+    /// Stores a level.
+    fn store(&self, level: &Level) {
+        self.price.store(level.price.to_bits(), Ordering::Relaxed);
+        self.qty.store(level.qty.to_bits(), Ordering::Relaxed);
+    }
+
+    /// Loads the level.
+    fn load(&self) -> Level {
+        Level {
+            price: f64::from_bits(self.price.load(Ordering::Relaxed)),
+            qty: f64::from_bits(self.qty.load(Ordering::Relaxed)),
+        }
+    }
+}
+
+/// A consistent copy of a book snapshot.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BookSnapshot {
+    /// The version the snapshot was read under.
+    pub version: u64,
+    /// The order book update ID.
+    pub update_id: u64,
+    /// Whether the book is being resynced, its levels no longer tracking the exchange.
+    stale: bool,
+    /// Number of bid levels in use.
+    bid_depth: usize,
+    /// Number of ask levels in use.
+    ask_depth: usize,
+    /// The bid levels, best first.
+    bid_levels: [Level; BOOK_DEPTH],
+    /// The ask levels, best first.
+    ask_levels: [Level; BOOK_DEPTH],
+}
+
+impl BookSnapshot {
+    /// Returns true if the book is being resynced, its levels not to be traded on.
+    pub fn is_stale(&self) -> bool {
+        self.stale
+    }
+
+    /// Returns the bid levels, best first.
+    pub fn bids(&self) -> &[Level] {
+        &self.bid_levels[..self.bid_depth]
+    }
+
+    /// Returns the ask levels, best first.
+    pub fn asks(&self) -> &[Level] {
+        &self.ask_levels[..self.ask_depth]
+    }
+
+    /// Returns the best bid, if any.
+    pub fn best_bid(&self) -> Option<Level> {
+        self.bids().first().copied()
+    }
+
+    /// Returns the best ask, if any.
+    pub fn best_ask(&self) -> Option<Level> {
+        self.asks().first().copied()
+    }
+}
+
+/// The book snapshot region of a symbol.
+#[repr(C, align(64))]
+#[derive(Debug, Default)]
+pub struct BookSnapshotRegion {
+    /// The version, odd while the snapshot is being written and zero until first written.
+    version: AtomicU64,
+    /// The order book update ID.
+    update_id: AtomicU64,
+    /// Non-zero while the book is being resynced, until the next snapshot is written.
+    stale: AtomicU64,
+    /// Number of bid levels in use.
+    bid_depth: AtomicU64,
+    /// Number of ask levels in use.
+    ask_depth: AtomicU64,
+    /// The bid levels, best first.
+    bids: [BookLevel; BOOK_DEPTH],
+    /// The ask levels, best first.
+    asks: [BookLevel; BOOK_DEPTH],
+}
+
+// SAFETY: `BookSnapshotRegion` is `repr(C)`, made only of atomics and valid when zeroed.
+unsafe impl ShmSafe for BookSnapshotRegion {}
+
+impl BookSnapshotRegion {
+    /// Returns the current version, zero if never written.
+    pub fn version(&self) -> u64 {
+        self.version.load(Ordering::Acquire)
+    }
+
+    /// Writes a snapshot of the book, keeping the best `BOOK_DEPTH` levels of each side.
+    /// The snapshot is no longer stale.
+    ///
+    /// Must only be called by the single builder of the symbol's book.
+    ///
+    /// LATENCY: FAST_PATH
+    pub fn write(&self, update_id: u64, bids: &[Level], asks: &[Level]) {
+        let version = self.version.load(Ordering::Relaxed);
+        self.version.store(version + 1, Ordering::Relaxed);
+        fence(Ordering::Release);
+
+        let bids = &bids[..bids.len().min(BOOK_DEPTH)];
+        let asks = &asks[..asks.len().min(BOOK_DEPTH)];
+        for (slot, level) in self.bids.iter().zip(bids) {
+            slot.store(level);
+        }
+        for (slot, level) in self.asks.iter().zip(asks) {
+            slot.store(level);
+        }
+        self.update_id.store(update_id, Ordering::Relaxed);
+        self.stale.store(0, Ordering::Relaxed);
+        self.bid_depth.store(bids.len() as u64, Ordering::Relaxed);
+        self.ask_depth.store(asks.len() as u64, Ordering::Relaxed);
+
+        self.version.store(version + 2, Ordering::Release);
+    }
+
+    /// Marks the snapshot stale, keeping its levels, until the next snapshot is written.
+    ///
+    /// Must only be called by the single builder of the symbol's book.
+    ///
+    /// LATENCY: SLOW_PATH
+    pub fn mark_stale(&self) {
+        let version = self.version.load(Ordering::Relaxed);
+        self.version.store(version + 1, Ordering::Relaxed);
+        fence(Ordering::Release);
+        self.stale.store(1, Ordering::Relaxed);
+        self.version.store(version + 2, Ordering::Release);
+    }
+
+    /// Reads the snapshot once, `None` if it was never written or is being written.
+    pub fn try_read(&self) -> Option<BookSnapshot> {
+        let version = self.version.load(Ordering::Acquire);
+        if version == 0 || version & 1 == 1 {
+            return None;
+        }
+
+        let bid_depth = (self.bid_depth.load(Ordering::Relaxed) as usize).min(BOOK_DEPTH);
+        let ask_depth = (self.ask_depth.load(Ordering::Relaxed) as usize).min(BOOK_DEPTH);
+        let mut snapshot = BookSnapshot {
+            version,
+            update_id: self.update_id.load(Ordering::Relaxed),
+            stale: self.stale.load(Ordering::Relaxed) != 0,
+            bid_depth,
+            ask_depth,
+            bid_levels: [Level::default(); BOOK_DEPTH],
+            ask_levels: [Level::default(); BOOK_DEPTH],
+        };
+        for (level, slot) in snapshot.bid_levels.iter_mut().zip(&self.bids[..bid_depth]) {
+            *level = slot.load();
+        }
+        for (level, slot) in snapshot.ask_levels.iter_mut().zip(&self.asks[..ask_depth]) {
+            *level = slot.load();
+        }
+
+        fence(Ordering::Acquire);
+        (self.version.load(Ordering::Relaxed) == version).then_some(snapshot)
+    }
+
+    /// Reads a consistent snapshot, retrying while it is being written.
+    /// Returns `None` if it was never written.
+    pub fn read(&self) -> Option<BookSnapshot> {
+        loop {
+            if self.version() == 0 {
+                return None;
+            }
+            if let Some(snapshot) = self.try_read() {
+                return Some(snapshot);
+            }
+            hint::spin_loop();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::Arc;
+    use std::sync::atomic::AtomicBool;
+    use std::thread;
+
+    fn levels(start: f64, step: f64, n: usize) -> Vec<Level> {
+        (0..n)
+            .map(|i| Level { price: start + step * i as f64, qty: (i + 1) as f64 })
+            .collect()
+    }
+
+    #[test]
+    fn test_region_name() {
+        assert_eq!(book_region_name(3), "ctl_book_3");
+    }
+
+    #[test]
+    fn test_write_read() {
+        let region = BookSnapshotRegion::default();
+        assert_eq!(region.read(), None);
+
+        region.write(7, &levels(100.0, -0.5, 3), &levels(100.5, 0.5, 2));
+        let snapshot = region.read().unwrap();
+        assert_eq!(snapshot.version, 2);
+        assert_eq!(snapshot.update_id, 7);
+        assert_eq!(snapshot.bids().len(), 3);
+        assert_eq!(snapshot.asks().len(), 2);
+        assert_eq!(snapshot.best_bid(), Some(Level { price: 100.0, qty: 1.0 }));
+        assert_eq!(snapshot.best_ask(), Some(Level { price: 100.5, qty: 1.0 }));
+        assert!(!snapshot.is_stale());
+    }
+
+    #[test]
+    fn test_mark_stale() {
+        let region = BookSnapshotRegion::default();
+        region.write(7, &levels(100.0, -0.5, 3), &levels(100.5, 0.5, 2));
+        region.mark_stale();
+        let snapshot = region.read().unwrap();
+        assert!(snapshot.is_stale());
+        assert_eq!(snapshot.version, 4);
+        assert_eq!(snapshot.bids().len(), 3);
+
+        // The next snapshot written clears the mark
+        region.write(8, &levels(100.0, -0.5, 3), &[]);
+        assert!(!region.read().unwrap().is_stale());
+    }
+
+    #[test]
+    fn test_write_truncates_depth() {
+        let region = BookSnapshotRegion::default();
+        region.write(1, &levels(100.0, -0.5, BOOK_DEPTH + 8), &[]);
+        let snapshot = region.read().unwrap();
+        assert_eq!(snapshot.bids().len(), BOOK_DEPTH);
+        assert!(snapshot.asks().is_empty());
+        assert_eq!(snapshot.best_ask(), None);
+    }
+
+    #[test]
+    fn test_consistent_under_concurrent_write() {
+        let region = Arc::new(BookSnapshotRegion::default());
+        let done = Arc::new(AtomicBool::new(false));
+
+        let writer = {
+            let region = region.clone();
+            let done = done.clone();
+            thread::spawn(move || {
+                for update_id in 1..20_000u64 {
+                    let depth = (update_id as usize % BOOK_DEPTH) + 1;
+                    let level = Level { price: update_id as f64, qty: update_id as f64 };
+                    region.write(update_id, &vec![level; depth], &vec![level; depth]);
+                }
+                done.store(true, Ordering::Release);
+            })
+        };
+
+        while !done.load(Ordering::Acquire) {
+            if let Some(snapshot) = region.read() {
+                let depth = (snapshot.update_id as usize % BOOK_DEPTH) + 1;
+                assert_eq!(snapshot.bids().len(), depth);
+                assert_eq!(snapshot.asks().len(), depth);
+                let price = snapshot.update_id as f64;
+                assert!(snapshot.bids().iter().chain(snapshot.asks()).all(|l| l.price == price));
+            }
+        }
+        writer.join().unwrap();
+    }
+}
@@ -0,0 +1,212 @@
+//! Alerts published by any component to the alerts ring.
+//!
+//! Every component publishing to the ring gives operators a single place to
+//! watch for the events needing attention (`ctl-admin alerts`), instead of
+//! the logs of each process.
+
+use ctl_shm::ShmMessage;
+
+use crate::text::{read_padded, write_padded};
+
+/// Name of the alerts ring, created by ctl-resource-manager.
+pub const ALERTS_RING_NAME: &str = "ALERTS_PS";
+
+/// Number of slots of the alerts ring.
+pub const ALERTS_RING_SIZE: usize = 4096;
+
+/// Maximum length of the source of an alert, in bytes.
+pub const ALERT_SOURCE_SIZE: usize = 32;
+
+/// Maximum length of the detail of an alert, in bytes.
+pub const ALERT_DETAIL_SIZE: usize = 128;
+
+/// The event raising an alert.
+///
+/// Stored as a `u8` in the message, since a ring slot may hold any byte.
+#[repr(u8)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum AlertKind {
+    /// Unknown event.
+    Unknown = 0,
+    /// A feed connection was lost.
+    FeedDisconnect = 1,
+    /// A feed switched to another endpoint.
+    EndpointFailover = 2,
+    /// A consumer lags the producer of a ring, about to be sped past.
+    ConsumerLag = 3,
+    /// A consumer was sped past, or a producer dropped messages of a full ring.
+    RingOverflow = 4,
+    /// An order was rejected.
+    OrderReject = 5,
+    /// A risk limit was breached.
+    RiskBreach = 6,
+    /// A worker exited with an error.
+    WorkerFailure = 7,
+    /// A command of the control ring was applied.
+    ControlAck = 8,
+    /// The subscriptions of a connection drifted from the ones it requested.
+    SubscriptionDrift = 9,
+    /// A stream received no message for too long.
+    SilentStream = 10,
+    /// A consumed message failed its checksum, torn or corrupted in its slot.
+    CorruptMessage = 11,
+    /// The update IDs of a depth stream didn't chain, its book resynced from a snapshot.
+    BookResync = 12,
+    /// A circuit breaker tripped on a sustained error rate, halting trading.
+    BreakerTripped = 13,
+    /// A trade printed far outside the spread of its concurrent quotes.
+    ImpossiblePrint = 14,
+    /// A line of an arbitrated feed went silent while the other one carries its events.
+    LineSilent = 15,
+    /// The price of a symbol crossed a configured level.
+    PriceCross = 16,
+    /// The spread of a symbol widened past a configured threshold.
+    SpreadWide = 17,
+    /// The traded volume of a symbol spiked past a multiple of its recent volume.
+    VolumeSpike = 18,
+}
+
+impl AlertKind {
+    /// Returns the kind stored in a message.
+    pub fn from_u8(kind: u8) -> Self {
+        match kind {
+            1 => AlertKind::FeedDisconnect,
+            2 => AlertKind::EndpointFailover,
+            3 => AlertKind::ConsumerLag,
+            4 => AlertKind::RingOverflow,
+            5 => AlertKind::OrderReject,
+            6 => AlertKind::RiskBreach,
+            7 => AlertKind::WorkerFailure,
+            8 => AlertKind::ControlAck,
+            9 => AlertKind::SubscriptionDrift,
+            10 => AlertKind::SilentStream,
+            11 => AlertKind::CorruptMessage,
+            12 => AlertKind::BookResync,
+            13 => AlertKind::BreakerTripped,
+            14 => AlertKind::ImpossiblePrint,
+            15 => AlertKind::LineSilent,
+            16 => AlertKind::PriceCross,
+            17 => AlertKind::SpreadWide,
+            18 => AlertKind::VolumeSpike,
+            _ => AlertKind::Unknown,
+        }
+    }
+}
+
+/// The severity of an alert.
+#[repr(u8)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum AlertSeverity {
+    /// Informational, e.g. a recovered condition.
+    Info = 0,
+    /// Degraded, the component keeps running.
+    Warning = 1,
+    /// Needs immediate attention.
+    Critical = 2,
+}
+
+impl AlertSeverity {
+    /// Returns the severity stored in a message, `Critical` if unknown.
+    pub fn from_u8(severity: u8) -> Self {
+        match severity {
+            0 => AlertSeverity::Info,
+            1 => AlertSeverity::Warning,
+            _ => AlertSeverity::Critical,
+        }
+    }
+}
+
+/// A message of the alerts ring.
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+pub struct AlertMessage {
+    /// The `AlertKind`.
+    pub kind: u8,
+    /// The `AlertSeverity`.
+    pub severity: u8,
+    /// The time the alert was raised, in milliseconds since the epoch.
+    pub raised_at_ms: u64,
+    /// The component raising the alert, zero-padded UTF-8.
+    pub source: [u8; ALERT_SOURCE_SIZE],
+    /// The detail of the event, zero-padded UTF-8.
+    pub detail: [u8; ALERT_DETAIL_SIZE],
+}
+
+impl Default for AlertMessage {
+    fn default() -> Self {
+        Self {
+            kind: AlertKind::Unknown as u8,
+            severity: AlertSeverity::Info as u8,
+            raised_at_ms: 0,
+            source: [0u8; ALERT_SOURCE_SIZE],
+            detail: [0u8; ALERT_DETAIL_SIZE],
+        }
+    }
+}
+
+// SAFETY: `AlertMessage` is `repr(C)`, made only of integers and bytes, valid for any bytes.
+unsafe impl ShmMessage for AlertMessage {
+    const LAYOUT_HASH: u64 = ctl_shm::layout_hash!(AlertMessage { kind, severity, raised_at_ms, source, detail });
+}
+
+impl AlertMessage {
+    /// Creates an alert, truncating the source and the detail to their sizes.
+    pub fn new(kind: AlertKind, severity: AlertSeverity, raised_at_ms: u64, source: &str, detail: &str) -> Self {
+        let mut message = Self { kind: kind as u8, severity: severity as u8, raised_at_ms, ..Self::default() };
+        write_padded(&mut message.source, source);
+        write_padded(&mut message.detail, detail);
+        message
+    }
+
+    /// Returns the kind of the alert.
+    pub fn kind(&self) -> AlertKind {
+        AlertKind::from_u8(self.kind)
+    }
+
+    /// Returns the severity of the alert.
+    pub fn severity(&self) -> AlertSeverity {
+        AlertSeverity::from_u8(self.severity)
+    }
+
+    /// Returns the component raising the alert.
+    pub fn source(&self) -> &str {
+        read_padded(&self.source)
+    }
+
+    /// Returns the detail of the event.
+    pub fn detail(&self) -> &str {
+        read_padded(&self.detail)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_alert_message() {
+        let message = AlertMessage::new(
+            AlertKind::EndpointFailover,
+            AlertSeverity::Warning,
+            1_000,
+            "md-handler",
+            "top/A switched endpoint after 3 consecutive failures",
+        );
+        assert_eq!(message.kind(), AlertKind::EndpointFailover);
+        assert_eq!(message.severity(), AlertSeverity::Warning);
+        assert_eq!(message.raised_at_ms, 1_000);
+        assert_eq!(message.source(), "md-handler");
+        assert_eq!(message.detail(), "top/A switched endpoint after 3 consecutive failures");
+        assert_eq!(AlertMessage::default().kind(), AlertKind::Unknown);
+        assert_eq!(AlertKind::from_u8(AlertKind::RiskBreach as u8), AlertKind::RiskBreach);
+        assert_eq!(AlertKind::from_u8(AlertKind::VolumeSpike as u8), AlertKind::VolumeSpike);
+    }
+
+    #[test]
+    fn test_detail_truncated() {
+        let detail = "x".repeat(ALERT_DETAIL_SIZE + 10);
+        let message = AlertMessage::new(AlertKind::RingOverflow, AlertSeverity::Critical, 0, "trade-stats", &detail);
+        assert_eq!(message.detail().len(), ALERT_DETAIL_SIZE);
+        assert!(AlertSeverity::Critical > AlertSeverity::Warning);
+    }
+}
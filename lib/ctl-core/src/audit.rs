@@ -0,0 +1,271 @@
+//! Append-only journal of the control-plane actions.
+//!
+//! Every action changing what the controller does, as opposed to the market
+//! data or orders it handles, is appended to the audit journal with its time,
+//! its origin (the component, process and user) and its reason: the kill
+//! switch and the breaker trips, the feed pauses, the resubscriptions and the
+//! configuration reloads. The components share the journal, each record a
+//! single append of one JSON line, so that `ctl-admin audit` replays in order
+//! what the operators and the components did around an incident, and the
+//! control state it left the controller in.
+
+use std::collections::BTreeSet;
+use std::env;
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use std::sync::Arc;
+
+use ctl_time::now_ms;
+use serde::{Deserialize, Serialize};
+
+use crate::{AuditError, BreakerSignal};
+
+/// Default path of the audit journal, shared by the components.
+pub const DEFAULT_AUDIT_JOURNAL_PATH: &str = "journals/audit.journal";
+
+/// A control-plane action.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum AuditAction {
+    /// Trading was halted, by the kill switch or a stale stream.
+    Halt,
+    /// Trading was resumed after a halt.
+    Resume,
+    /// A circuit breaker tripped, halting trading.
+    BreakerTripped { signal: BreakerSignal },
+    /// A tripped circuit breaker was reset, resuming trading.
+    ResetBreaker,
+    /// The publishing of the market data of a target was paused, empty for every target.
+    PauseFeed { target: String },
+    /// The publishing of the market data of a target was resumed, empty for every target.
+    ResumeFeed { target: String },
+    /// Streams lost by a feed were subscribed again.
+    Resubscribe { feed: String, streams: Vec<String> },
+    /// A configuration file was reloaded.
+    ConfigReload { path: String },
+    /// A strategy plugin was loaded on its host.
+    LoadStrategy { name: String },
+    /// A strategy plugin was unloaded from its host.
+    UnloadStrategy { name: String },
+    /// The running strategy was hot-swapped for a plugin.
+    SwapStrategy { name: String },
+}
+
+/// The origin of an action.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditOrigin {
+    /// The component that took the action, e.g. `ctl-admin`.
+    pub component: String,
+    /// The process of the component.
+    pub pid: u32,
+    /// The user running the component, the one behind `sudo` if any.
+    pub user: String,
+}
+
+impl AuditOrigin {
+    /// Returns the origin of the actions of this process, as `component`.
+    pub fn current(component: &str) -> Self {
+        let user = env::var("SUDO_USER").or_else(|_| env::var("USER")).unwrap_or_default();
+        Self { component: component.to_string(), pid: std::process::id(), user }
+    }
+}
+
+/// A record of the audit journal, one JSON object per line.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditRecord {
+    /// The time of the action, in milliseconds since the epoch.
+    pub at_ms: u64,
+    /// The origin of the action.
+    pub origin: AuditOrigin,
+    /// The action.
+    #[serde(flatten)]
+    pub action: AuditAction,
+    /// The reason given for the action, empty if none.
+    #[serde(default)]
+    pub reason: String,
+}
+
+/// The audit journal of a component, appending its actions.
+///
+/// Cloned to record from several threads, e.g. the log reload watcher.
+#[derive(Debug, Clone)]
+pub struct AuditJournal {
+    /// The journal, opened for appending.
+    file: Arc<File>,
+    /// The origin of the actions recorded.
+    origin: AuditOrigin,
+}
+
+impl AuditJournal {
+    /// Opens the journal at `path` for the actions of `component`, creating it
+    /// and its directory if missing.
+    ///
+    /// # Errors
+    /// Returns an error if the journal can't be created or opened.
+    pub fn open(path: impl AsRef<Path>, component: &str) -> Result<Self, AuditError> {
+        let path = path.as_ref();
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            fs::create_dir_all(dir)?;
+        }
+        let file = OpenOptions::new().append(true).create(true).open(path)?;
+        Ok(Self { file: Arc::new(file), origin: AuditOrigin::current(component) })
+    }
+
+    /// Appends an action taken now and syncs it to disk.
+    ///
+    /// The record is written in a single append, so that the records of the
+    /// components sharing the journal don't interleave.
+    ///
+    /// LATENCY: SLOW_PATH
+    pub fn record(&self, action: AuditAction, reason: &str) -> Result<(), AuditError> {
+        let record = AuditRecord { at_ms: now_ms(), origin: self.origin.clone(), action, reason: reason.to_string() };
+        let mut line = serde_json::to_vec(&record)?;
+        line.push(b'\n');
+        (&*self.file).write_all(&line)?;
+        self.file.sync_data()?;
+        Ok(())
+    }
+
+    /// Reads the records of the journal at `path`, in the order appended.
+    ///
+    /// A torn last record (an append in progress) is ignored.
+    ///
+    /// # Errors
+    /// Returns an error if the file can't be read, or a complete record is corrupt.
+    pub fn read(path: impl AsRef<Path>) -> Result<Vec<AuditRecord>, AuditError> {
+        let contents = fs::read(path)?;
+        contents
+            .split_inclusive(|&b| b == b'\n')
+            .take_while(|line| line.ends_with(b"\n"))
+            .enumerate()
+            .map(|(index, line)| {
+                serde_json::from_slice(line).map_err(|source| AuditError::CorruptRecord { line: index + 1, source })
+            })
+            .collect()
+    }
+}
+
+/// The control state of the controller, reconstructed by replaying the journal.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ControlState {
+    /// The time trading was halted, `None` while trading.
+    pub halted_at_ms: Option<u64>,
+    /// The signal of the tripped circuit breaker, `None` unless tripped.
+    pub tripped_by: Option<BreakerSignal>,
+    /// The paused targets, the empty target pausing every feed.
+    pub paused: BTreeSet<String>,
+}
+
+impl ControlState {
+    /// Replays the records in order, from the state of a fresh start.
+    pub fn replay<'a>(records: impl IntoIterator<Item = &'a AuditRecord>) -> Self {
+        let mut state = Self::default();
+        for record in records {
+            state.apply(record);
+        }
+        state
+    }
+
+    /// Applies a record to the state.
+    pub fn apply(&mut self, record: &AuditRecord) {
+        match &record.action {
+            AuditAction::Halt => {
+                self.halted_at_ms.get_or_insert(record.at_ms);
+            }
+            AuditAction::BreakerTripped { signal } => {
+                self.halted_at_ms.get_or_insert(record.at_ms);
+                self.tripped_by.get_or_insert(*signal);
+            }
+            // A halt of a tripped breaker isn't resumed, only reset
+            AuditAction::Resume if self.tripped_by.is_none() => self.halted_at_ms = None,
+            AuditAction::Resume => {}
+            AuditAction::ResetBreaker => {
+                self.halted_at_ms = None;
+                self.tripped_by = None;
+            }
+            AuditAction::PauseFeed { target } => {
+                self.paused.insert(target.clone());
+            }
+            // Resuming every target resumes the targets paused one by one
+            AuditAction::ResumeFeed { target } if target.is_empty() => self.paused.clear(),
+            AuditAction::ResumeFeed { target } => {
+                self.paused.remove(target);
+            }
+            AuditAction::Resubscribe { .. }
+            | AuditAction::ConfigReload { .. }
+            | AuditAction::LoadStrategy { .. }
+            | AuditAction::UnloadStrategy { .. }
+            | AuditAction::SwapStrategy { .. } => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::io::Read;
+
+    #[test]
+    fn test_record_and_read() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("journals/audit.journal");
+
+        let admin = AuditJournal::open(&path, "ctl-admin").unwrap();
+        let handler = AuditJournal::open(&path, "md-handler").unwrap();
+        admin.record(AuditAction::PauseFeed { target: "top/A".to_string() }, "maintenance").unwrap();
+        let streams = vec!["btcusdt@bookTicker".to_string()];
+        handler.clone().record(AuditAction::Resubscribe { feed: "top/A".to_string(), streams }, "").unwrap();
+
+        // A torn last record is ignored
+        let mut contents = String::new();
+        File::open(&path).unwrap().read_to_string(&mut contents).unwrap();
+        assert!(contents.starts_with(r#"{"at_ms":"#));
+        assert!(contents.contains(r#""action":"pause_feed","target":"top/A","reason":"maintenance"}"#));
+        fs::write(&path, format!("{}{{\"at_ms\":1", contents)).unwrap();
+
+        let records = AuditJournal::read(&path).unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].origin.component, "ctl-admin");
+        assert_eq!(records[0].origin.pid, std::process::id());
+        assert_eq!(records[0].reason, "maintenance");
+        assert!(matches!(&records[1].action, AuditAction::Resubscribe { feed, .. } if feed == "top/A"));
+
+        fs::write(&path, "{\"at_ms\":1}\n").unwrap();
+        assert!(matches!(AuditJournal::read(&path), Err(AuditError::CorruptRecord { line: 1, .. })));
+    }
+
+    #[test]
+    fn test_replay_control_state() {
+        let origin = AuditOrigin::current("ctl-admin");
+        let record = |at_ms, action| AuditRecord { at_ms, origin: origin.clone(), action, reason: String::new() };
+        let pause = |target: &str| AuditAction::PauseFeed { target: target.to_string() };
+        let resume = |target: &str| AuditAction::ResumeFeed { target: target.to_string() };
+
+        let mut records = vec![
+            record(1, AuditAction::Halt),
+            record(2, AuditAction::Halt),
+            record(3, pause("top/A")),
+            record(4, pause("BTCUSDT")),
+            record(5, resume("top/A")),
+        ];
+        let state = ControlState::replay(&records);
+        assert_eq!(state.halted_at_ms, Some(1));
+        assert_eq!(state.paused, BTreeSet::from(["BTCUSDT".to_string()]));
+
+        // A tripped breaker isn't resumed, only reset
+        records.extend([
+            record(6, AuditAction::Resume),
+            record(7, AuditAction::BreakerTripped { signal: BreakerSignal::RateLimitBan }),
+            record(8, AuditAction::Resume),
+            record(9, resume("")),
+        ]);
+        let state = ControlState::replay(&records);
+        assert_eq!((state.halted_at_ms, state.tripped_by), (Some(7), Some(BreakerSignal::RateLimitBan)));
+        assert!(state.paused.is_empty());
+
+        records.push(record(10, AuditAction::ResetBreaker));
+        assert_eq!(ControlState::replay(&records), ControlState::default());
+    }
+}
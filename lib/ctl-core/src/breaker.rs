@@ -0,0 +1,271 @@
+//! Circuit breaker on sustained error rates.
+//!
+//! Each component records the errors it observes (rate limit bans, parse
+//! errors, execution reports not received in time) to its breaker. Once an
+//! error rate crosses its threshold, the breaker trips in the status table,
+//! which also halts trading: the OMS cancels the open orders, the strategies
+//! stop quoting and ctl-md-handler pauses the feeds. Unlike a halt, a trip is
+//! only cleared by an operator, with `ctl-admin reset-breaker`.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use ctl_shm::ShmRegion;
+use serde::{Deserialize, Serialize};
+
+use crate::{AuditAction, AuditJournal, StatusRegion};
+
+/// An error counted by the circuit breaker.
+///
+/// Stored as a `u8` in the status table.
+#[repr(u8)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum BreakerSignal {
+    /// Unknown error.
+    Unknown = 0,
+    /// The exchange banned the requests for exceeding the rate limits (-1003, HTTP 418/429).
+    RateLimitBan = 1,
+    /// A message of the exchange failed to parse.
+    ParseError = 2,
+    /// The execution report of an order request wasn't received in time.
+    ExecutionReportTimeout = 3,
+}
+
+impl BreakerSignal {
+    /// Returns the signal stored in the status table.
+    pub fn from_u8(signal: u8) -> Self {
+        match signal {
+            1 => BreakerSignal::RateLimitBan,
+            2 => BreakerSignal::ParseError,
+            3 => BreakerSignal::ExecutionReportTimeout,
+            _ => BreakerSignal::Unknown,
+        }
+    }
+}
+
+/// The error rate tripping the breaker.
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Eq, Hash)]
+pub struct BreakerThreshold {
+    /// Errors within a window tripping the breaker (0 never trips).
+    pub max_errors: u64,
+    /// Length of the window, in milliseconds.
+    pub window_ms: u64,
+}
+
+impl BreakerThreshold {
+    const fn new(max_errors: u64, window_ms: u64) -> Self {
+        Self { max_errors, window_ms }
+    }
+}
+
+fn default_rate_limit_ban() -> BreakerThreshold {
+    BreakerThreshold::new(3, 60_000)
+}
+
+fn default_parse_error() -> BreakerThreshold {
+    BreakerThreshold::new(100, 10_000)
+}
+
+fn default_execution_report_timeout() -> BreakerThreshold {
+    BreakerThreshold::new(5, 60_000)
+}
+
+/// The thresholds of the circuit breaker, by signal.
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Eq, Hash)]
+pub struct BreakerConfig {
+    #[serde(default = "default_rate_limit_ban")]
+    pub rate_limit_ban: BreakerThreshold,
+    #[serde(default = "default_parse_error")]
+    pub parse_error: BreakerThreshold,
+    #[serde(default = "default_execution_report_timeout")]
+    pub execution_report_timeout: BreakerThreshold,
+}
+
+impl Default for BreakerConfig {
+    fn default() -> Self {
+        Self {
+            rate_limit_ban: default_rate_limit_ban(),
+            parse_error: default_parse_error(),
+            execution_report_timeout: default_execution_report_timeout(),
+        }
+    }
+}
+
+impl BreakerConfig {
+    /// Validates the breaker configuration.
+    pub fn validate(&self) -> Result<(), String> {
+        for (name, threshold) in self.thresholds() {
+            if threshold.window_ms == 0 {
+                return Err(format!("breaker threshold '{}' must have 'window_ms' greater than 0", name));
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the threshold of a signal, `None` for an unknown one.
+    pub fn threshold(&self, signal: BreakerSignal) -> Option<BreakerThreshold> {
+        match signal {
+            BreakerSignal::RateLimitBan => Some(self.rate_limit_ban),
+            BreakerSignal::ParseError => Some(self.parse_error),
+            BreakerSignal::ExecutionReportTimeout => Some(self.execution_report_timeout),
+            BreakerSignal::Unknown => None,
+        }
+    }
+
+    fn thresholds(&self) -> [(&'static str, BreakerThreshold); 3] {
+        [
+            ("rate_limit_ban", self.rate_limit_ban),
+            ("parse_error", self.parse_error),
+            ("execution_report_timeout", self.execution_report_timeout),
+        ]
+    }
+}
+
+/// The errors of a signal in the current window.
+#[derive(Debug, Default)]
+struct ErrorWindow {
+    /// The window (since the epoch) the errors belong to.
+    window: AtomicU64,
+    /// The errors in the window.
+    count: AtomicU64,
+}
+
+impl ErrorWindow {
+    /// Records an error at `now_ms`, returning the errors in its window.
+    fn record(&self, now_ms: u64, window_ms: u64) -> u64 {
+        let window = now_ms / window_ms;
+        let current = self.window.load(Ordering::Acquire);
+        // Only the caller winning the rollover resets the count
+        if window > current
+            && self
+                .window
+                .compare_exchange(current, window, Ordering::AcqRel, Ordering::Acquire)
+                .is_ok()
+        {
+            self.count.store(0, Ordering::Release);
+        }
+        self.count.fetch_add(1, Ordering::AcqRel) + 1
+    }
+}
+
+/// Counts the errors of a component, tripping in the status table once an
+/// error rate crosses its threshold.
+pub struct CircuitBreaker {
+    /// The thresholds.
+    config: BreakerConfig,
+    /// The status table the trip is recorded in.
+    status: Arc<ShmRegion<StatusRegion>>,
+    /// The errors of each known signal, by signal minus one.
+    windows: [ErrorWindow; 3],
+    /// The audit journal the trips are recorded in, if any.
+    audit: Option<AuditJournal>,
+}
+
+impl std::fmt::Debug for CircuitBreaker {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CircuitBreaker")
+            .field("config", &self.config)
+            .field("windows", &self.windows)
+            .finish_non_exhaustive()
+    }
+}
+
+impl CircuitBreaker {
+    /// Creates a breaker tripping in `status` per `config`.
+    pub fn new(config: BreakerConfig, status: Arc<ShmRegion<StatusRegion>>) -> Self {
+        Self { config, status, windows: Default::default(), audit: None }
+    }
+
+    /// Records the trips in the audit journal.
+    pub fn with_audit(mut self, audit: AuditJournal) -> Self {
+        self.audit = Some(audit);
+        self
+    }
+
+    /// Returns the thresholds.
+    pub fn config(&self) -> &BreakerConfig {
+        &self.config
+    }
+
+    /// Records an error at `now_ms`, returning true if it tripped the breaker.
+    ///
+    /// Tripping halts trading in the status table, the caller cancelling its
+    /// open orders. Once tripped, the errors don't trip the breaker again until
+    /// it is reset by an operator.
+    pub fn record(&self, signal: BreakerSignal, now_ms: u64) -> bool {
+        let Some(threshold) = self.config.threshold(signal).filter(|threshold| threshold.max_errors > 0) else {
+            return false;
+        };
+        let errors = self.windows[signal as usize - 1].record(now_ms, threshold.window_ms.max(1));
+        let tripped = errors >= threshold.max_errors && self.status.trip(signal, now_ms);
+        if let Some(audit) = self.audit.as_ref().filter(|_| tripped) {
+            let reason = format!("{} errors within {} ms", errors, threshold.window_ms);
+            // The trip stands even if the journal fails, recorded in the status table all the same
+            let _ = audit.record(AuditAction::BreakerTripped { signal }, &reason);
+        }
+        tripped
+    }
+
+    /// Returns true while the breaker is tripped.
+    ///
+    /// LATENCY: FAST_PATH
+    pub fn is_tripped(&self) -> bool {
+        self.status.is_tripped()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn status(suffix: &str) -> Arc<ShmRegion<StatusRegion>> {
+        let name = format!("ctl_test_breaker_{}_{}", suffix, std::process::id());
+        Arc::new(ShmRegion::<StatusRegion>::create(&name).unwrap())
+    }
+
+    #[test]
+    fn test_trips_on_error_rate() {
+        let config = BreakerConfig { rate_limit_ban: BreakerThreshold::new(2, 1_000), ..BreakerConfig::default() };
+        let status = status("rate");
+        let breaker = CircuitBreaker::new(config, status.clone());
+
+        // The errors of a past window don't count
+        assert!(!breaker.record(BreakerSignal::RateLimitBan, 0));
+        assert!(!breaker.record(BreakerSignal::RateLimitBan, 1_000));
+        assert!(!breaker.record(BreakerSignal::ParseError, 1_500));
+        assert!(!breaker.is_tripped() && !status.is_halted());
+
+        assert!(breaker.record(BreakerSignal::RateLimitBan, 1_999));
+        assert!(breaker.is_tripped() && status.is_halted());
+        assert_eq!((status.tripped_by(), status.tripped_at_ms()), (BreakerSignal::RateLimitBan, 1_999));
+
+        // Tripped once until reset, even by another signal
+        assert!(!breaker.record(BreakerSignal::RateLimitBan, 1_999));
+        assert!(status.reset_breaker());
+        assert!(!breaker.is_tripped());
+        assert!(breaker.record(BreakerSignal::RateLimitBan, 1_999));
+    }
+
+    #[test]
+    fn test_disabled_and_unknown_signals() {
+        let config = BreakerConfig { parse_error: BreakerThreshold::new(0, 1_000), ..BreakerConfig::default() };
+        let breaker = CircuitBreaker::new(config, status("disabled"));
+        for _ in 0..1_000 {
+            assert!(!breaker.record(BreakerSignal::ParseError, 0));
+            assert!(!breaker.record(BreakerSignal::Unknown, 0));
+        }
+        assert!(!breaker.is_tripped());
+    }
+
+    #[test]
+    fn test_config_defaults_and_validate() {
+        let config: BreakerConfig = serde_yaml::from_str("parse_error:\n  max_errors: 10\n  window_ms: 1000").unwrap();
+        assert_eq!(config.parse_error, BreakerThreshold::new(10, 1_000));
+        assert_eq!(config.rate_limit_ban, BreakerConfig::default().rate_limit_ban);
+        assert!(config.validate().is_ok());
+
+        let config = BreakerConfig { execution_report_timeout: BreakerThreshold::new(5, 0), ..config };
+        assert!(config.validate().is_err());
+    }
+}
@@ -0,0 +1,105 @@
+//! C declarations of the control and alerts rings (see `ctl_shm::CHeader`).
+
+use ctl_shm::{c_struct, declare_c_message, CHeader, ShmError};
+
+use crate::{
+    AlertKind, AlertMessage, AlertSeverity, ControlCommand, ControlMessage, ALERTS_RING_NAME, ALERTS_RING_SIZE,
+    ALERT_DETAIL_SIZE, ALERT_SOURCE_SIZE, CONTROL_REASON_SIZE, CONTROL_RING_NAME, CONTROL_RING_SIZE,
+    CONTROL_TARGET_SIZE,
+};
+
+/// Declares the messages of the control and alerts rings in a C header.
+///
+/// # Errors
+/// Returns an error if the C layout of a message differs from the Rust one.
+pub fn declare_c_types(header: &mut CHeader) -> Result<(), ShmError> {
+    header.section("Control ring (ctl-core)");
+    header.define_str("CTL_CONTROL_RING_NAME", CONTROL_RING_NAME);
+    header.define("CTL_CONTROL_RING_SIZE", CONTROL_RING_SIZE as u64);
+    header.define("CTL_CONTROL_REASON_SIZE", CONTROL_REASON_SIZE as u64);
+    header.define("CTL_CONTROL_TARGET_SIZE", CONTROL_TARGET_SIZE as u64);
+    header.enumeration(
+        "CTL_CONTROL_COMMAND",
+        &[
+            ("UNKNOWN", ControlCommand::Unknown as u64),
+            ("HALT", ControlCommand::Halt as u64),
+            ("RESUME", ControlCommand::Resume as u64),
+            ("PAUSE_FEED", ControlCommand::PauseFeed as u64),
+            ("RESUME_FEED", ControlCommand::ResumeFeed as u64),
+            ("LOAD_STRATEGY", ControlCommand::LoadStrategy as u64),
+            ("UNLOAD_STRATEGY", ControlCommand::UnloadStrategy as u64),
+            ("SWAP_STRATEGY", ControlCommand::SwapStrategy as u64),
+        ],
+    );
+    let control = c_struct!(ControlMessage as "ctl_control_message" {
+        command: "uint8_t",
+        issued_at_ms: "uint64_t",
+        reason: "uint8_t"[CONTROL_REASON_SIZE],
+        target: "uint8_t"[CONTROL_TARGET_SIZE],
+    });
+    declare_c_message::<ControlMessage>(header, "A message of the control ring, its text zero-padded UTF-8.", control)?;
+
+    header.section("Alerts ring (ctl-core)");
+    header.define_str("CTL_ALERTS_RING_NAME", ALERTS_RING_NAME);
+    header.define("CTL_ALERTS_RING_SIZE", ALERTS_RING_SIZE as u64);
+    header.define("CTL_ALERT_SOURCE_SIZE", ALERT_SOURCE_SIZE as u64);
+    header.define("CTL_ALERT_DETAIL_SIZE", ALERT_DETAIL_SIZE as u64);
+    header.enumeration(
+        "CTL_ALERT_KIND",
+        &[
+            ("UNKNOWN", AlertKind::Unknown as u64),
+            ("FEED_DISCONNECT", AlertKind::FeedDisconnect as u64),
+            ("ENDPOINT_FAILOVER", AlertKind::EndpointFailover as u64),
+            ("CONSUMER_LAG", AlertKind::ConsumerLag as u64),
+            ("RING_OVERFLOW", AlertKind::RingOverflow as u64),
+            ("ORDER_REJECT", AlertKind::OrderReject as u64),
+            ("RISK_BREACH", AlertKind::RiskBreach as u64),
+            ("WORKER_FAILURE", AlertKind::WorkerFailure as u64),
+            ("CONTROL_ACK", AlertKind::ControlAck as u64),
+            ("SUBSCRIPTION_DRIFT", AlertKind::SubscriptionDrift as u64),
+            ("SILENT_STREAM", AlertKind::SilentStream as u64),
+            ("CORRUPT_MESSAGE", AlertKind::CorruptMessage as u64),
+            ("BOOK_RESYNC", AlertKind::BookResync as u64),
+            ("BREAKER_TRIPPED", AlertKind::BreakerTripped as u64),
+            ("IMPOSSIBLE_PRINT", AlertKind::ImpossiblePrint as u64),
+            ("LINE_SILENT", AlertKind::LineSilent as u64),
+            ("PRICE_CROSS", AlertKind::PriceCross as u64),
+            ("SPREAD_WIDE", AlertKind::SpreadWide as u64),
+            ("VOLUME_SPIKE", AlertKind::VolumeSpike as u64),
+        ],
+    );
+    header.enumeration(
+        "CTL_ALERT_SEVERITY",
+        &[
+            ("INFO", AlertSeverity::Info as u64),
+            ("WARNING", AlertSeverity::Warning as u64),
+            ("CRITICAL", AlertSeverity::Critical as u64),
+        ],
+    );
+    let alert = c_struct!(AlertMessage as "ctl_alert_message" {
+        kind: "uint8_t",
+        severity: "uint8_t",
+        raised_at_ms: "uint64_t",
+        source: "uint8_t"[ALERT_SOURCE_SIZE],
+        detail: "uint8_t"[ALERT_DETAIL_SIZE],
+    });
+    declare_c_message::<AlertMessage>(header, "A message of the alerts ring, its text zero-padded UTF-8.", alert)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_declare_c_types() {
+        let mut header = CHeader::new("CTL_TEST_H");
+        declare_c_types(&mut header).unwrap();
+        let rendered = header.render();
+        assert!(rendered.contains("#define CTL_CONTROL_RING_NAME \"CONTROL_PS\"\n"));
+        assert!(rendered.contains("#define CTL_CONTROL_COMMAND_RESUME_FEED 4\n"));
+        assert!(rendered.contains("#define CTL_ALERT_KIND_LINE_SILENT 15\n"));
+        assert!(rendered.contains("#define CTL_ALERT_KIND_VOLUME_SPIKE 18\n"));
+        assert!(rendered.contains("CTL_STATIC_ASSERT(sizeof(ctl_control_message) == 112, "));
+        assert!(rendered.contains("CTL_STATIC_ASSERT(offsetof(ctl_alert_message, detail) == 48, "));
+    }
+}
@@ -0,0 +1,178 @@
+//! Deterministic client order IDs.
+//!
+//! An ID is `ctl-{component_id}-{epoch}-{seq}`: the ID of the component (e.g. a
+//! strategy) that requested the order, the time the generator was started in
+//! milliseconds and a per-generator sequence number, both in base 36. The epoch
+//! changes on every restart, so IDs never collide with the ones issued before a
+//! restart, and the component ID lets execution reports be routed back to the
+//! originating component.
+
+use std::fmt;
+use std::str::FromStr;
+
+use ctl_time::now_ms;
+
+use crate::ClientOrderIdError;
+
+/// Maximum length of a Binance client order ID (`newClientOrderId`).
+/// https://github.com/binance/binance-spot-api-docs/blob/master/rest-api.md#new-order-trade
+pub const MAX_CLIENT_ORDER_ID_LEN: usize = 36;
+
+/// Prefix distinguishing the controller's IDs from the ones the exchange generates.
+const PREFIX: &str = "ctl";
+
+/// Radix of the epoch and sequence number, keeping the IDs within the allowed
+/// length and alphabet: `ctl-65535-{8 chars until 2059}-{13 chars}` is 32 long.
+const RADIX: u32 = 36;
+
+/// A client order ID generated by a controller component.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ClientOrderId {
+    /// The ID of the component that requested the order.
+    pub component_id: u16,
+    /// The start time of the generator, in milliseconds since the epoch.
+    pub epoch_ms: u64,
+    /// The sequence number of the order within the generator.
+    pub seq: u64,
+}
+
+/// Formats `value` in base 36.
+fn fmt_radix(f: &mut fmt::Formatter<'_>, mut value: u64) -> fmt::Result {
+    let mut digits = [0u8; 13];
+    let mut start = digits.len();
+    loop {
+        start -= 1;
+        digits[start] = char::from_digit((value % RADIX as u64) as u32, RADIX).unwrap_or('0') as u8;
+        value /= RADIX as u64;
+        if value == 0 {
+            break;
+        }
+    }
+    // The digits are ASCII alphanumerics
+    f.write_str(std::str::from_utf8(&digits[start..]).map_err(|_| fmt::Error)?)
+}
+
+impl fmt::Display for ClientOrderId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-{}-", PREFIX, self.component_id)?;
+        fmt_radix(f, self.epoch_ms)?;
+        f.write_str("-")?;
+        fmt_radix(f, self.seq)
+    }
+}
+
+impl FromStr for ClientOrderId {
+    type Err = ClientOrderIdError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || ClientOrderIdError::InvalidFormat(s.to_string());
+        let mut parts = s.split('-');
+        if parts.next() != Some(PREFIX) {
+            return Err(invalid());
+        }
+        let mut next = |radix: u32| {
+            parts
+                .next()
+                .filter(|part| !part.is_empty() && part.bytes().all(|b| b.is_ascii_alphanumeric()))
+                .and_then(|part| u64::from_str_radix(part, radix).ok())
+                .ok_or_else(invalid)
+        };
+        let component_id = u16::try_from(next(10)?).map_err(|_| invalid())?;
+        let epoch_ms = next(RADIX)?;
+        let seq = next(RADIX)?;
+        if parts.next().is_some() {
+            return Err(invalid());
+        }
+        Ok(Self { component_id, epoch_ms, seq })
+    }
+}
+
+/// Generates the client order IDs of a component.
+#[derive(Debug, Clone)]
+pub struct ClientOrderIdGenerator {
+    /// The ID of the component.
+    component_id: u16,
+    /// The start time of the generator, in milliseconds since the epoch.
+    epoch_ms: u64,
+    /// The sequence number of the next ID.
+    next_seq: u64,
+}
+
+impl ClientOrderIdGenerator {
+    /// Creates a generator for a component, with the current time as its epoch.
+    ///
+    /// IDs are unique across restarts as long as the component isn't restarted
+    /// within the same millisecond, and the clock doesn't step back between runs.
+    pub fn start(component_id: u16) -> Self {
+        Self::new(component_id, now_ms())
+    }
+
+    /// Creates a generator for a component with the given epoch.
+    pub fn new(component_id: u16, epoch_ms: u64) -> Self {
+        Self { component_id, epoch_ms, next_seq: 0 }
+    }
+
+    /// Returns the ID of the component.
+    pub fn component_id(&self) -> u16 {
+        self.component_id
+    }
+
+    /// Returns the next client order ID.
+    ///
+    /// LATENCY: FAST_PATH
+    pub fn next_id(&mut self) -> ClientOrderId {
+        let seq = self.next_seq;
+        self.next_seq += 1;
+        ClientOrderId { component_id: self.component_id, epoch_ms: self.epoch_ms, seq }
+    }
+
+    /// Returns true if `id` was generated by this generator.
+    pub fn is_own(&self, id: &ClientOrderId) -> bool {
+        id.component_id == self.component_id && id.epoch_ms == self.epoch_ms && id.seq < self.next_seq
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_and_parse() {
+        let id = ClientOrderId { component_id: 42, epoch_ms: 1_760_000_000_000, seq: 35 };
+        let s = id.to_string();
+        assert_eq!(s, "ctl-42-mgj6k3cw-z");
+        assert_eq!(s.parse::<ClientOrderId>().unwrap(), id);
+    }
+
+    #[test]
+    fn test_fits_binance_constraints() {
+        let id = ClientOrderId { component_id: u16::MAX, epoch_ms: 2_800_000_000_000, seq: u64::MAX };
+        let s = id.to_string();
+        assert!(s.len() <= MAX_CLIENT_ORDER_ID_LEN, "{} is too long", s);
+        assert!(s.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'-'));
+        assert_eq!(s.parse::<ClientOrderId>().unwrap(), id);
+    }
+
+    #[test]
+    fn test_parse_foreign_ids() {
+        for s in ["web_3f2c1b", "ctl-1-abc", "ctl-70000-a-b", "ctl-1-a-b-c", "ctl-1--b", "ctl-1-a-+b"] {
+            assert!(s.parse::<ClientOrderId>().is_err(), "{}", s);
+        }
+    }
+
+    #[test]
+    fn test_generator_unique_across_restarts() {
+        let mut first = ClientOrderIdGenerator::new(3, 1_000);
+        let a = first.next_id();
+        let b = first.next_id();
+        assert_ne!(a, b);
+        assert!(first.is_own(&b));
+
+        let mut restarted = ClientOrderIdGenerator::new(3, 1_001);
+        let c = restarted.next_id();
+        assert_eq!(c.seq, a.seq);
+        assert_ne!(c.to_string(), a.to_string());
+        assert!(!restarted.is_own(&a));
+        assert_eq!(c.to_string().parse::<ClientOrderId>().unwrap().component_id, 3);
+    }
+}
@@ -0,0 +1,161 @@
+//! Commands broadcast to every component through the control ring.
+
+use ctl_shm::ShmMessage;
+
+use crate::text::{read_padded, write_padded};
+
+/// Name of the control ring, created by ctl-resource-manager.
+pub const CONTROL_RING_NAME: &str = "CONTROL_PS";
+
+/// Number of slots of the control ring.
+pub const CONTROL_RING_SIZE: usize = 1024;
+
+/// Maximum length of the reason of a command, in bytes.
+pub const CONTROL_REASON_SIZE: usize = 64;
+
+/// Maximum length of the target of a command, in bytes.
+pub const CONTROL_TARGET_SIZE: usize = 32;
+
+/// A command of the control ring.
+///
+/// Stored as a `u8` in the message, since a ring slot may hold any byte.
+#[repr(u8)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ControlCommand {
+    /// Unknown command, ignored.
+    Unknown = 0,
+    /// Stop trading: the OMS cancels all open orders and rejects new ones,
+    /// and the strategies stop quoting.
+    Halt = 1,
+    /// Resume trading after a halt.
+    Resume = 2,
+    /// Stop publishing the market data of the target, keeping its connections.
+    PauseFeed = 3,
+    /// Resume publishing the market data of the target after a pause.
+    ResumeFeed = 4,
+    /// Load the strategy plugin named by the target on its host.
+    LoadStrategy = 5,
+    /// Unload the strategy plugin named by the target, its orders left live.
+    UnloadStrategy = 6,
+    /// Hot-swap the running strategy for the plugin named by the target, its
+    /// orders going to the new instance.
+    SwapStrategy = 7,
+}
+
+impl ControlCommand {
+    /// Returns the command stored in a message.
+    pub fn from_u8(command: u8) -> Self {
+        match command {
+            1 => ControlCommand::Halt,
+            2 => ControlCommand::Resume,
+            3 => ControlCommand::PauseFeed,
+            4 => ControlCommand::ResumeFeed,
+            5 => ControlCommand::LoadStrategy,
+            6 => ControlCommand::UnloadStrategy,
+            7 => ControlCommand::SwapStrategy,
+            _ => ControlCommand::Unknown,
+        }
+    }
+}
+
+/// A message of the control ring.
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+pub struct ControlMessage {
+    /// The `ControlCommand`.
+    pub command: u8,
+    /// The time the command was issued, in milliseconds since the epoch.
+    pub issued_at_ms: u64,
+    /// The reason the command was issued, zero-padded UTF-8.
+    pub reason: [u8; CONTROL_REASON_SIZE],
+    /// The target of the command, zero-padded UTF-8: a symbol set (`{kind}/{set}`),
+    /// a feed kind or a symbol, empty for every target, or a strategy plugin.
+    pub target: [u8; CONTROL_TARGET_SIZE],
+}
+
+impl Default for ControlMessage {
+    fn default() -> Self {
+        Self {
+            command: ControlCommand::Unknown as u8,
+            issued_at_ms: 0,
+            reason: [0u8; CONTROL_REASON_SIZE],
+            target: [0u8; CONTROL_TARGET_SIZE],
+        }
+    }
+}
+
+// SAFETY: `ControlMessage` is `repr(C)`, made only of integers and bytes, valid for any bytes.
+unsafe impl ShmMessage for ControlMessage {
+    const LAYOUT_HASH: u64 = ctl_shm::layout_hash!(ControlMessage { command, issued_at_ms, reason, target });
+}
+
+impl ControlMessage {
+    /// Creates a message of `command`, truncating the reason to `CONTROL_REASON_SIZE` bytes.
+    pub fn new(command: ControlCommand, issued_at_ms: u64, reason: &str) -> Self {
+        let mut message = Self { command: command as u8, issued_at_ms, ..Self::default() };
+        write_padded(&mut message.reason, reason);
+        message
+    }
+
+    /// Sets the target of the command, truncated to `CONTROL_TARGET_SIZE` bytes.
+    pub fn with_target(mut self, target: &str) -> Self {
+        write_padded(&mut self.target, target);
+        self
+    }
+
+    /// Returns the command of the message.
+    pub fn command(&self) -> ControlCommand {
+        ControlCommand::from_u8(self.command)
+    }
+
+    /// Returns the reason the command was issued.
+    pub fn reason(&self) -> &str {
+        read_padded(&self.reason)
+    }
+
+    /// Returns the target of the command, empty for every target.
+    pub fn target(&self) -> &str {
+        read_padded(&self.target)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_control_message() {
+        let message = ControlMessage::new(ControlCommand::Halt, 1_000, "manual halt");
+        assert_eq!(message.command(), ControlCommand::Halt);
+        assert_eq!(message.issued_at_ms, 1_000);
+        assert_eq!(message.reason(), "manual halt");
+        assert_eq!(ControlMessage::default().command(), ControlCommand::Unknown);
+        assert_eq!(message.target(), "");
+    }
+
+    #[test]
+    fn test_feed_command_target() {
+        let message = ControlMessage::new(ControlCommand::PauseFeed, 0, "maintenance").with_target("top/A");
+        assert_eq!(message.command(), ControlCommand::PauseFeed);
+        assert_eq!(message.target(), "top/A");
+        assert_eq!(ControlCommand::from_u8(ControlCommand::ResumeFeed as u8), ControlCommand::ResumeFeed);
+    }
+
+    #[test]
+    fn test_strategy_command_target() {
+        let message = ControlMessage::new(ControlCommand::SwapStrategy, 0, "new build").with_target("quoter");
+        assert_eq!(message.command(), ControlCommand::SwapStrategy);
+        assert_eq!(message.target(), "quoter");
+        assert_eq!(ControlCommand::from_u8(ControlCommand::LoadStrategy as u8), ControlCommand::LoadStrategy);
+        assert_eq!(ControlCommand::from_u8(ControlCommand::UnloadStrategy as u8), ControlCommand::UnloadStrategy);
+    }
+
+    #[test]
+    fn test_reason_truncated_on_char_boundary() {
+        // The 64th byte falls in the middle of a two-byte character
+        let reason = format!("a{}", "é".repeat(CONTROL_REASON_SIZE));
+        let message = ControlMessage::new(ControlCommand::Halt, 0, &reason);
+        assert_eq!(message.reason().len(), CONTROL_REASON_SIZE - 1);
+        assert!(reason.starts_with(message.reason()));
+    }
+}
@@ -0,0 +1,146 @@
+//! Options of the DPDK Environment Abstraction Layer.
+//!
+//! The resource manager and the market data handler configurations carry an
+//! `eal` section, passed through to the EAL of their processes. Every process
+//! of a controller instance must use the same `file_prefix`: it names the DPDK
+//! runtime directory and the hugepage files the secondaries attach to, and
+//! prefixes the shared memory regions, so two instances with distinct prefixes
+//! coexist on one host.
+
+use serde::Deserialize;
+
+use crate::DPDK_FILE_PREFIX;
+
+/// The EAL options managed by the components, rejected in `extra_args`.
+const MANAGED_ARGS: &[&str] = &[
+    "-l",
+    "--lcores",
+    "-c",
+    "--main-lcore",
+    "--proc-type",
+    "--file-prefix",
+    "--socket-mem",
+    "-m",
+    "--no-telemetry",
+];
+
+/// The EAL options of a process.
+#[derive(Debug, Clone, Default, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct EalConfig {
+    /// The prefix of the DPDK runtime directory and hugepage files, `rte` if unset.
+    pub file_prefix: Option<String>,
+    /// The hugepage memory reserved on each NUMA socket, in MB, by socket ID.
+    /// Only applies to the primary process.
+    pub socket_mem: Option<Vec<u64>>,
+    /// Disables the telemetry socket of the process.
+    pub no_telemetry: bool,
+    /// Additional options passed to the EAL as is.
+    pub extra_args: Vec<String>,
+}
+
+impl EalConfig {
+    /// Validates the options.
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(prefix) = &self.file_prefix {
+            if prefix.is_empty() || !prefix.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+                return Err(format!(
+                    "Invalid EAL file_prefix '{}'. Must be non-empty ASCII alphanumerics, '-' or '_'",
+                    prefix
+                ));
+            }
+        }
+        if self.socket_mem.as_ref().is_some_and(|mem| mem.is_empty() || mem.iter().all(|&mb| mb == 0)) {
+            return Err("EAL socket_mem must reserve memory on at least one socket".to_string());
+        }
+        for arg in &self.extra_args {
+            let option = arg.split('=').next().unwrap_or_default();
+            if MANAGED_ARGS.contains(&option) {
+                return Err(format!("EAL option '{}' is managed by the component, not allowed in extra_args", option));
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the file prefix, the EAL default if unset.
+    pub fn file_prefix(&self) -> &str {
+        self.file_prefix.as_deref().unwrap_or(DPDK_FILE_PREFIX)
+    }
+
+    /// Returns the EAL arguments of a secondary process.
+    pub fn secondary_args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+        if let Some(prefix) = &self.file_prefix {
+            args.push(format!("--file-prefix={}", prefix));
+        }
+        if self.no_telemetry {
+            args.push("--no-telemetry".to_string());
+        }
+        args.extend(self.extra_args.iter().cloned());
+        args
+    }
+
+    /// Returns the EAL arguments of the primary process, with `memory_args`
+    /// reserving the hugepage memory unless `socket_mem` pins it per socket.
+    pub fn primary_args(&self, memory_args: Vec<String>) -> Vec<String> {
+        let mut args = self.secondary_args();
+        match &self.socket_mem {
+            Some(socket_mem) => {
+                let mem: Vec<_> = socket_mem.iter().map(u64::to_string).collect();
+                args.push(format!("--socket-mem={}", mem.join(",")));
+            }
+            None => args.extend(memory_args),
+        }
+        args
+    }
+
+    /// Prefixes the shared memory regions of this process with the file
+    /// prefix, if set, to be called before any region is created or opened.
+    pub fn apply_region_prefix(&self) {
+        if let Some(prefix) = &self.file_prefix {
+            ctl_shm::set_region_prefix(prefix);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(content: &str) -> EalConfig {
+        serde_yaml::from_str(content).unwrap()
+    }
+
+    #[test]
+    fn test_default_args() {
+        let config = EalConfig::default();
+        assert_eq!(config.file_prefix(), "rte");
+        assert!(config.secondary_args().is_empty());
+        assert_eq!(config.primary_args(vec!["-m".to_string(), "2048".to_string()]), vec!["-m", "2048"]);
+    }
+
+    #[test]
+    fn test_instance_args() {
+        let config = parse("file_prefix: ctl-b\nsocket_mem: [1024, 0]\nno_telemetry: true\nextra_args: [--log-level=lib.eal:debug]\n");
+        assert!(config.validate().is_ok());
+        assert_eq!(config.file_prefix(), "ctl-b");
+        assert_eq!(
+            config.secondary_args(),
+            vec!["--file-prefix=ctl-b", "--no-telemetry", "--log-level=lib.eal:debug"]
+        );
+        // The socket memory replaces the reservation of the hugepage memory
+        assert_eq!(
+            config.primary_args(vec!["-m".to_string(), "2048".to_string()]),
+            vec!["--file-prefix=ctl-b", "--no-telemetry", "--log-level=lib.eal:debug", "--socket-mem=1024,0"]
+        );
+    }
+
+    #[test]
+    fn test_invalid_options() {
+        assert!(parse("file_prefix: ''\n").validate().is_err());
+        assert!(parse("file_prefix: a/b\n").validate().is_err());
+        assert!(parse("socket_mem: [0, 0]\n").validate().is_err());
+        assert!(parse("extra_args: [--file-prefix=x]\n").validate().is_err());
+        assert!(parse("extra_args: ['-l', '1-3']\n").validate().is_err());
+    }
+}
@@ -0,0 +1,242 @@
+use std::fmt;
+use std::path::PathBuf;
+
+use ctl_shm::ShmError;
+use thiserror::Error;
+
+use crate::Capability;
+
+/// Errors that can occur when parsing a client order ID.
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum ClientOrderIdError {
+    /// The ID wasn't generated by a controller component.
+    #[error("Invalid client order ID '{0}'")]
+    InvalidFormat(String),
+}
+
+/// Errors that can occur when building or parsing a ring name.
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum RingNameError {
+    /// The name isn't `{KIND}_{symbol_id}_{suffix}`.
+    #[error("Invalid ring name '{0}', expected {{KIND}}_{{symbol_id}}_{{suffix}}")]
+    InvalidFormat(String),
+    /// The kind isn't a ring kind.
+    #[error("Unknown ring kind '{0}'")]
+    UnknownKind(String),
+    /// The suffix isn't a ring suffix.
+    #[error("Unknown ring suffix '{0}'")]
+    UnknownSuffix(String),
+    /// The line isn't a line of an arbitrated feed.
+    #[error("Unknown line '{0}', expected a or b")]
+    UnknownLine(String),
+}
+
+/// Errors that can occur when parsing or validating the lcores configuration.
+#[derive(Debug, Error)]
+pub enum LcoresConfigError {
+    /// Error reading the configuration file.
+    #[error("Failed to read configuration file: {0}")]
+    FileReadError(#[from] std::io::Error),
+    /// Error parsing the YAML configuration.
+    #[error("Failed to parse YAML configuration: {0}")]
+    YamlParseError(#[from] serde_yaml::Error),
+    /// Validation error with a descriptive message.
+    #[error("Configuration validation error: {0}")]
+    ValidationError(String),
+}
+
+/// Errors that can occur when planning a conflict-free lcore assignment.
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum LcoreConflictError {
+    /// Two claims the planner can't move share an lcore.
+    #[error("Lcore {lcore} is claimed by both {first} and {second}")]
+    Overlap {
+        /// The lcore.
+        lcore: u32,
+        /// The claim placed first.
+        first: String,
+        /// The conflicting claim.
+        second: String,
+    },
+    /// No free lcore is left to move a conflicting component to.
+    #[error("No free lcore left for {0}")]
+    NoFreeLcore(String),
+}
+
+/// Errors that can occur when parsing or validating the polling configuration.
+#[derive(Debug, Error)]
+pub enum PollingConfigError {
+    /// Error reading the configuration file.
+    #[error("Failed to read configuration file: {0}")]
+    FileReadError(#[from] std::io::Error),
+    /// Error parsing the YAML configuration.
+    #[error("Failed to parse YAML configuration: {0}")]
+    YamlParseError(#[from] serde_yaml::Error),
+    /// Validation error with a descriptive message.
+    #[error("Configuration validation error: {0}")]
+    ValidationError(String),
+}
+
+/// Errors that can occur when parsing the overtaken consumer recovery configuration.
+#[derive(Debug, Error)]
+pub enum OvertakenConfigError {
+    /// Error reading the configuration file.
+    #[error("Failed to read configuration file: {0}")]
+    FileReadError(#[from] std::io::Error),
+    /// Error parsing the YAML configuration.
+    #[error("Failed to parse YAML configuration: {0}")]
+    YamlParseError(#[from] serde_yaml::Error),
+}
+
+/// Errors that can occur when parsing or validating the health configuration.
+#[derive(Debug, Error)]
+pub enum HealthConfigError {
+    /// Error reading the configuration file.
+    #[error("Failed to read configuration file: {0}")]
+    FileReadError(#[from] std::io::Error),
+    /// Error parsing the YAML configuration.
+    #[error("Failed to parse YAML configuration: {0}")]
+    YamlParseError(#[from] serde_yaml::Error),
+    /// Validation error with a descriptive message.
+    #[error("Configuration validation error: {0}")]
+    ValidationError(String),
+}
+
+/// Errors that can occur when checking the run of the resource manager a secondary attached to.
+#[derive(Debug, Error)]
+pub enum EpochError {
+    /// The resource manager hasn't stamped its run epoch, still setting up its run.
+    #[error("The resource manager has not started its run yet")]
+    NotStarted,
+    /// The resource manager restarted, the mappings of the previous run being stale.
+    #[error("The resource manager restarted (run epoch {attached} attached, now {current}), shared memory is stale")]
+    Restarted {
+        /// The run epoch attached to.
+        attached: u64,
+        /// The run epoch of the status region, zero while the new run is set up.
+        current: u64,
+    },
+    /// The status region is gone, the resource manager having exited.
+    #[error("The resource manager exited: {0}")]
+    Gone(#[source] ShmError),
+}
+
+/// Errors that can occur when recording to or reading the audit journal.
+#[derive(Debug, Error)]
+pub enum AuditError {
+    /// Error opening, writing or reading the journal.
+    #[error("Failed to access the audit journal: {0}")]
+    FileError(#[from] std::io::Error),
+    /// Error encoding a record.
+    #[error("Failed to encode the audit record: {0}")]
+    EncodeError(#[from] serde_json::Error),
+    /// A complete record of the journal doesn't parse.
+    #[error("Corrupt audit record at line {line}: {source}")]
+    CorruptRecord {
+        /// The line of the record, from 1.
+        line: usize,
+        /// The parse error.
+        source: serde_json::Error,
+    },
+}
+
+/// Errors that can occur when parsing or validating the telemetry configuration.
+#[derive(Debug, Error)]
+pub enum TelemetryConfigError {
+    /// Error reading the configuration file.
+    #[error("Failed to read configuration file: {0}")]
+    FileReadError(#[from] std::io::Error),
+    /// Error parsing the YAML configuration.
+    #[error("Failed to parse YAML configuration: {0}")]
+    YamlParseError(#[from] serde_yaml::Error),
+    /// Validation error with a descriptive message.
+    #[error("Configuration validation error: {0}")]
+    ValidationError(String),
+}
+
+/// Errors that can occur when parsing or validating the scheduling configuration.
+#[derive(Debug, Error)]
+pub enum SchedulingConfigError {
+    /// Error reading the configuration file.
+    #[error("Failed to read configuration file: {0}")]
+    FileReadError(#[from] std::io::Error),
+    /// Error parsing the YAML configuration.
+    #[error("Failed to parse YAML configuration: {0}")]
+    YamlParseError(#[from] serde_yaml::Error),
+    /// Validation error with a descriptive message.
+    #[error("Configuration validation error: {0}")]
+    ValidationError(String),
+}
+
+/// Errors that can occur when applying a scheduling policy to the threads.
+#[derive(Debug, Error)]
+pub enum SchedulingError {
+    /// The memory couldn't be locked.
+    #[error("Failed to lock the memory: {0}. Grant cap_ipc_lock or raise RLIMIT_MEMLOCK")]
+    MemoryLock(std::io::Error),
+    /// The affinity of a thread couldn't be set.
+    #[error("Failed to set the CPU affinity of thread {tid}: {source}")]
+    Affinity { tid: i32, source: std::io::Error },
+    /// The priority of a thread couldn't be set.
+    #[error("Failed to set SCHED_FIFO priority {priority} of thread {tid}: {source}. Grant cap_sys_nice")]
+    Priority { tid: i32, priority: i32, source: std::io::Error },
+    /// Error reading a system file.
+    #[error("Failed to read {}: {source}", .path.display())]
+    ReadError { path: PathBuf, source: std::io::Error },
+}
+
+/// An unmet requirement of a component on the host, found by a preflight.
+#[derive(Debug, Error)]
+pub enum PreflightError {
+    /// No hugetlbfs filesystem is mounted.
+    #[error("No hugetlbfs mount in /proc/mounts. Mount one with `mount -t hugetlbfs nodev /dev/hugepages`")]
+    HugepagesNotMounted,
+    /// No hugetlbfs filesystem of the page size is mounted.
+    #[error(
+        "No hugetlbfs mount of {size_kb}kB pages in /proc/mounts. Mount one with `mount -t hugetlbfs -o pagesize={size_kb}K nodev <dir>`"
+    )]
+    HugepageSizeNotMounted { size_kb: u32 },
+    /// Fewer hugepages of the page size are free than required.
+    #[error(
+        "Only {free} free {size_kb}kB hugepages, {required} required. Raise /sys/kernel/mm/hugepages/hugepages-{size_kb}kB/nr_hugepages or stop the process holding them"
+    )]
+    InsufficientHugepages { size_kb: u32, free: u32, required: u32 },
+    /// A configured lcore isn't an online CPU.
+    #[error("lcore {lcore} is not an online CPU (online: {online}). Fix the CPUs of the component's configuration")]
+    LcoreOffline { lcore: usize, online: String },
+    /// A configured lcore isn't isolated from the scheduler.
+    #[error("lcore {lcore} is not isolated (isolated: {isolated}). Add it to the isolcpus= kernel parameter and reboot")]
+    LcoreNotIsolated { lcore: usize, isolated: String },
+    /// The process lacks a capability.
+    #[error("Missing capability {capability}. Run as root or grant it with `setcap {capability}+ep <binary>`")]
+    MissingCapability { capability: Capability },
+    /// The DPDK primary process isn't running.
+    #[error("The DPDK primary process is not running ({} is not locked). Start ctl-resource-manager first", .path.display())]
+    PrimaryNotRunning { path: PathBuf },
+    /// A DPDK primary process is already running.
+    #[error("A DPDK primary process is already running ({} is locked). Stop it before starting ctl-resource-manager", .path.display())]
+    PrimaryAlreadyRunning { path: PathBuf },
+    /// Error reading a system file.
+    #[error("Failed to read {}: {source}", .path.display())]
+    ReadError { path: PathBuf, source: std::io::Error },
+}
+
+/// The unmet requirements of a preflight.
+#[derive(Debug)]
+pub struct PreflightFailure {
+    /// The unmet requirements, in check order.
+    pub errors: Vec<PreflightError>,
+}
+
+impl fmt::Display for PreflightFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let plural = if self.errors.len() == 1 { "" } else { "s" };
+        write!(f, "{} preflight check{} failed:", self.errors.len(), plural)?;
+        for error in &self.errors {
+            write!(f, "\n  - {}", error)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for PreflightFailure {}
@@ -0,0 +1,244 @@
+//! Exit codes of the binaries, by class of the error stopping them.
+//!
+//! A supervisor (systemd, Kubernetes) restarts a component that exited, but a
+//! restart only gets past some failures: a network outage clears up by itself,
+//! while a configuration error or a missing DPDK primary stays until an operator
+//! or the primary steps in. The binaries return a `FatalError` from their main,
+//! and exit with the code of its class, so a supervisor can tell them apart
+//! (e.g. with `RestartPreventExitStatus=` of systemd).
+//!
+//! The codes of the classes start at 10, past the generic failure 1 and the
+//! usage error 2 of the argument parser.
+
+use std::error::Error;
+use std::fmt;
+use std::process::ExitCode;
+
+use ctl_shm::ShmError;
+
+use crate::{
+    AuditError, EpochError, HealthConfigError, LcoreConflictError, LcoresConfigError, OvertakenConfigError,
+    PollingConfigError, PreflightError, PreflightFailure, RingNameError, SchedulingConfigError, SchedulingError,
+    TelemetryConfigError,
+};
+
+/// The class of a fatal error, selecting the exit code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[repr(u8)]
+pub enum FatalKind {
+    /// An unclassified error.
+    Internal = 1,
+    /// The command line is invalid.
+    Usage = 2,
+    /// A configuration file is missing, malformed or invalid.
+    Config = 10,
+    /// The host doesn't meet a requirement of the component, e.g. hugepages,
+    /// isolated lcores or capabilities.
+    Host = 11,
+    /// The DPDK primary process, ctl-resource-manager, isn't running.
+    PrimaryMissing = 12,
+    /// The DPDK environment failed to initialize.
+    DpdkInit = 13,
+    /// A shared ring or region is missing or doesn't match the binary, e.g.
+    /// a stale binary or a resource manager of another configuration.
+    SharedState = 14,
+    /// The exchange refused the credentials of the component.
+    NetworkAuth = 15,
+    /// The exchange couldn't be reached.
+    Network = 16,
+    /// A file couldn't be read or written.
+    Io = 17,
+    /// A ring consumer was overtaken by its producer under the fail-fast policy.
+    Overtaken = 18,
+    /// The DPDK primary process restarted, leaving the shared memory mapped
+    /// by the component stale.
+    PrimaryRestarted = 19,
+}
+
+impl FatalKind {
+    /// Every fatal error class.
+    pub const ALL: [FatalKind; 12] = [
+        FatalKind::Internal,
+        FatalKind::Usage,
+        FatalKind::Config,
+        FatalKind::Host,
+        FatalKind::PrimaryMissing,
+        FatalKind::DpdkInit,
+        FatalKind::SharedState,
+        FatalKind::NetworkAuth,
+        FatalKind::Network,
+        FatalKind::Io,
+        FatalKind::Overtaken,
+        FatalKind::PrimaryRestarted,
+    ];
+
+    /// Returns the exit code of the class.
+    pub fn code(self) -> u8 {
+        self as u8
+    }
+
+    /// Returns the class of an exit code, `None` if it isn't one.
+    pub fn from_code(code: u8) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.code() == code)
+    }
+
+    /// Returns the name of the class.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            FatalKind::Internal => "internal",
+            FatalKind::Usage => "usage",
+            FatalKind::Config => "config",
+            FatalKind::Host => "host",
+            FatalKind::PrimaryMissing => "primary-missing",
+            FatalKind::DpdkInit => "dpdk-init",
+            FatalKind::SharedState => "shared-state",
+            FatalKind::NetworkAuth => "network-auth",
+            FatalKind::Network => "network",
+            FatalKind::Io => "io",
+            FatalKind::Overtaken => "overtaken",
+            FatalKind::PrimaryRestarted => "primary-restarted",
+        }
+    }
+}
+
+impl fmt::Display for FatalKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// An error stopping a binary, with its class.
+#[derive(Debug)]
+pub struct FatalError {
+    /// The class of the error.
+    kind: FatalKind,
+    /// The error.
+    source: Box<dyn Error>,
+}
+
+impl FatalError {
+    /// Creates a fatal error of a class.
+    pub fn new(kind: FatalKind, source: impl Into<Box<dyn Error>>) -> Self {
+        Self { kind, source: source.into() }
+    }
+
+    /// Returns the class of the error.
+    pub fn kind(&self) -> FatalKind {
+        self.kind
+    }
+
+    /// Returns the exit code of the error.
+    pub fn exit_code(&self) -> ExitCode {
+        ExitCode::from(self.kind.code())
+    }
+}
+
+impl fmt::Display for FatalError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.source)
+    }
+}
+
+impl Error for FatalError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(self.source.as_ref())
+    }
+}
+
+/// Classifies the error of a result as fatal.
+pub trait Fatal<T> {
+    /// Returns the error as a fatal error of a class.
+    fn fatal(self, kind: FatalKind) -> Result<T, FatalError>;
+}
+
+impl<T, E: Into<Box<dyn Error>>> Fatal<T> for Result<T, E> {
+    fn fatal(self, kind: FatalKind) -> Result<T, FatalError> {
+        self.map_err(|e| FatalError::new(kind, e))
+    }
+}
+
+/// Returns the exit code of the result of the main of a binary, printing its
+/// fatal error if any.
+pub fn exit_code(result: Result<(), FatalError>) -> ExitCode {
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("Error ({}): {}", e.kind, e);
+            e.exit_code()
+        }
+    }
+}
+
+// The errors of ctl-core and of the shared regions, classified once for every binary
+macro_rules! fatal_from {
+    ($kind:expr => $($error:ty),*) => {
+        $(
+            impl From<$error> for FatalError {
+                fn from(e: $error) -> Self {
+                    FatalError::new($kind, e)
+                }
+            }
+        )*
+    };
+}
+
+fatal_from!(FatalKind::Config =>
+    HealthConfigError, LcoresConfigError, LcoreConflictError, OvertakenConfigError, PollingConfigError,
+    RingNameError, SchedulingConfigError, TelemetryConfigError);
+fatal_from!(FatalKind::Host => SchedulingError);
+fatal_from!(FatalKind::SharedState => ShmError);
+fatal_from!(FatalKind::Io => std::io::Error, AuditError);
+
+impl From<PreflightFailure> for FatalError {
+    fn from(e: PreflightFailure) -> Self {
+        // The primary is started by the operator, unlike the host being fixed
+        let missing = e.errors.iter().any(|error| matches!(error, PreflightError::PrimaryNotRunning { .. }));
+        FatalError::new(if missing { FatalKind::PrimaryMissing } else { FatalKind::Host }, e)
+    }
+}
+
+impl From<EpochError> for FatalError {
+    fn from(e: EpochError) -> Self {
+        // A primary not set up yet or gone is waited for like a missing one
+        let kind = match e {
+            EpochError::Restarted { .. } => FatalKind::PrimaryRestarted,
+            EpochError::NotStarted | EpochError::Gone(_) => FatalKind::PrimaryMissing,
+        };
+        FatalError::new(kind, e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_codes() {
+        for kind in FatalKind::ALL {
+            assert_eq!(FatalKind::from_code(kind.code()), Some(kind));
+        }
+        assert_eq!(FatalKind::from_code(3), None);
+        assert_eq!(FatalKind::Config.code(), 10);
+    }
+
+    #[test]
+    fn test_classify() {
+        let e: FatalError = PollingConfigError::ValidationError("bad".to_string()).into();
+        assert_eq!(e.kind(), FatalKind::Config);
+
+        let failure = |errors| FatalError::from(PreflightFailure { errors });
+        let primary = PreflightError::PrimaryNotRunning { path: PathBuf::from("/var/run/dpdk/rte/config") };
+        assert_eq!(failure(vec![PreflightError::HugepagesNotMounted, primary]).kind(), FatalKind::PrimaryMissing);
+        assert_eq!(failure(vec![PreflightError::HugepagesNotMounted]).kind(), FatalKind::Host);
+
+        let restarted = FatalError::from(EpochError::Restarted { attached: 1, current: 2 });
+        assert_eq!(restarted.kind(), FatalKind::PrimaryRestarted);
+        assert_eq!(FatalError::from(EpochError::NotStarted).kind(), FatalKind::PrimaryMissing);
+
+        let result: Result<(), String> = Err("unreachable".to_string());
+        let e = result.fatal(FatalKind::Network).unwrap_err();
+        assert_eq!((e.kind(), e.to_string()), (FatalKind::Network, "unreachable".to_string()));
+    }
+}
@@ -0,0 +1,382 @@
+//! HTTP health and status endpoints of the components.
+//!
+//! A component can serve, off its hot path, a tiny HTTP server answering
+//! `GET /healthz` and `GET /status` for the health checks of the infrastructure
+//! (Kubernetes probes, load balancers, monitoring). `/healthz` answers 200 while
+//! the main loop of the component beats its `Heartbeat`, and 503 before the
+//! first beat or once the last one is older than `stale_after_ms`, e.g. a main
+//! loop stuck in a syscall. `/status` answers the JSON status of the component
+//! (feeds, rings, lag). A component rendering its counters for Prometheus also
+//! answers `GET /metrics`, if enabled on its endpoint. The endpoint is selected
+//! per component in `configs/health.yaml`, the components not listed serving none.
+
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use ctl_time::now_ms;
+use serde::Deserialize;
+
+use crate::{HealthConfigError, PROMETHEUS_CONTENT_TYPE};
+
+/// Default age of the last heartbeat past which the component is unhealthy.
+pub const DEFAULT_STALE_AFTER_MS: u64 = 5_000;
+
+// Largest request read, the headers of a health check fitting well within it
+const MAX_REQUEST_SIZE: usize = 4096;
+
+// Time a client is given to send its request or read the response, the
+// requests being served one at a time
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(1);
+
+fn default_stale_after_ms() -> u64 {
+    DEFAULT_STALE_AFTER_MS
+}
+
+/// The HTTP endpoint of a component.
+#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct HealthEndpoint {
+    /// The address to listen on, e.g. `127.0.0.1:9101`.
+    pub bind: String,
+    /// Age of the last heartbeat past which the component is unhealthy, in milliseconds.
+    #[serde(default = "default_stale_after_ms")]
+    pub stale_after_ms: u64,
+    /// Whether `/metrics` is served, by the components rendering their counters.
+    #[serde(default)]
+    pub metrics: bool,
+}
+
+impl HealthEndpoint {
+    /// Validates the endpoint configuration.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.bind.parse::<SocketAddr>().is_err() {
+            return Err(format!("'bind' must be an address and port, got '{}'", self.bind));
+        }
+        if self.stale_after_ms == 0 {
+            return Err("'stale_after_ms' must be non-zero".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// The HTTP endpoints of the components, by component name.
+#[derive(Debug, Clone, Default, Deserialize, PartialEq, Eq)]
+#[serde(transparent)]
+pub struct HealthConfig {
+    components: HashMap<String, HealthEndpoint>,
+}
+
+impl HealthConfig {
+    /// Parses the health configuration from a YAML file.
+    ///
+    /// # Errors
+    /// Returns an error if the file cannot be read, parsed, or fails validation.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, HealthConfigError> {
+        let content = fs::read_to_string(path)?;
+        Self::from_str(&content)
+    }
+
+    /// Parses the health configuration from a YAML string.
+    ///
+    /// # Errors
+    /// Returns an error if the YAML cannot be parsed or fails validation.
+    pub fn from_str(content: &str) -> Result<Self, HealthConfigError> {
+        // An empty file (or one of comments only) configures no endpoint
+        let config: Option<Self> = serde_yaml::from_str(content)?;
+        let config = config.unwrap_or_default();
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Validates the health configuration.
+    fn validate(&self) -> Result<(), HealthConfigError> {
+        for (component, endpoint) in &self.components {
+            endpoint
+                .validate()
+                .map_err(|e| HealthConfigError::ValidationError(format!("Component '{}': {}", component, e)))?;
+        }
+        Ok(())
+    }
+
+    /// Returns the endpoint of a component, `None` if it serves none.
+    pub fn endpoint(&self, component: &str) -> Option<&HealthEndpoint> {
+        self.components.get(component)
+    }
+}
+
+/// The heartbeat of the main loop of a component, beaten on every iteration
+/// and read by the health server.
+#[derive(Debug, Clone, Default)]
+pub struct Heartbeat(Arc<AtomicU64>);
+
+impl Heartbeat {
+    /// Creates a heartbeat never beaten.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records a beat at `now_ms`.
+    pub fn beat(&self, now_ms: u64) {
+        self.0.store(now_ms, Ordering::Relaxed);
+    }
+
+    /// Returns the age of the last beat at `now_ms`, `None` if never beaten.
+    pub fn age_ms(&self, now_ms: u64) -> Option<u64> {
+        match self.0.load(Ordering::Relaxed) {
+            0 => None,
+            last_ms => Some(now_ms.saturating_sub(last_ms)),
+        }
+    }
+}
+
+/// The response to a request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HealthResponse {
+    /// The HTTP status code.
+    pub code: u16,
+    /// The content type of the body.
+    pub content_type: &'static str,
+    /// The body, JSON but for `/metrics`.
+    pub body: String,
+}
+
+impl HealthResponse {
+    /// Returns the reason phrase of the status code.
+    fn reason(&self) -> &'static str {
+        match self.code {
+            200 => "OK",
+            404 => "Not Found",
+            405 => "Method Not Allowed",
+            503 => "Service Unavailable",
+            _ => "Bad Request",
+        }
+    }
+}
+
+/// The renderers of the bodies of the endpoints of a component.
+pub struct HealthRoutes {
+    /// Renders the JSON of `/status`.
+    status: Box<dyn Fn() -> String + Send>,
+    /// Renders the Prometheus text of `/metrics`, `None` if not served.
+    metrics: Option<Box<dyn Fn() -> String + Send>>,
+}
+
+impl HealthRoutes {
+    /// Creates the routes of a component, `/status` rendered by `status`.
+    pub fn new<F>(status: F) -> Self
+    where
+        F: Fn() -> String + Send + 'static,
+    {
+        Self { status: Box::new(status), metrics: None }
+    }
+
+    /// Renders `/metrics` with `metrics`, served if enabled on the endpoint.
+    pub fn with_metrics<F>(mut self, metrics: F) -> Self
+    where
+        F: Fn() -> String + Send + 'static,
+    {
+        self.metrics = Some(Box::new(metrics));
+        self
+    }
+}
+
+impl std::fmt::Debug for HealthRoutes {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HealthRoutes").field("metrics", &self.metrics.is_some()).finish()
+    }
+}
+
+/// Answers a request from its request line, e.g. `GET /healthz HTTP/1.1`.
+fn respond(request_line: &str, heartbeat: &Heartbeat, stale_after_ms: u64, routes: &HealthRoutes) -> HealthResponse {
+    let json = |code, body| HealthResponse { code, content_type: "application/json", body };
+    let error = |code, message| json(code, format!("{{\"error\":\"{}\"}}", message));
+    let mut parts = request_line.split_whitespace();
+    let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+        return error(400, "bad request");
+    };
+    // The query string, if any, is ignored
+    let path = target.split('?').next().unwrap_or(target);
+    let metrics = routes.metrics.as_ref().filter(|_| path == "/metrics");
+    if !matches!(path, "/healthz" | "/status") && metrics.is_none() {
+        return error(404, "not found");
+    }
+    if method != "GET" {
+        return error(405, "method not allowed");
+    }
+    if let Some(metrics) = metrics {
+        return HealthResponse { code: 200, content_type: PROMETHEUS_CONTENT_TYPE, body: metrics() };
+    }
+    if path == "/status" {
+        return json(200, (routes.status)());
+    }
+
+    let age_ms = heartbeat.age_ms(now_ms());
+    let healthy = age_ms.is_some_and(|age_ms| age_ms <= stale_after_ms);
+    let age = age_ms.map(|age_ms| age_ms.to_string()).unwrap_or_else(|| "null".to_string());
+    json(
+        if healthy { 200 } else { 503 },
+        format!("{{\"healthy\":{},\"heartbeat_age_ms\":{}}}", healthy, age),
+    )
+}
+
+/// Reads the request line of a connection and writes the response.
+fn serve(mut stream: TcpStream, heartbeat: &Heartbeat, stale_after_ms: u64, routes: &HealthRoutes) -> io::Result<()> {
+    stream.set_read_timeout(Some(REQUEST_TIMEOUT))?;
+    stream.set_write_timeout(Some(REQUEST_TIMEOUT))?;
+
+    // Only the request line is needed, but the headers are read through, a
+    // socket closed with unread data resetting the connection before the response
+    let mut request = Vec::new();
+    let mut buf = [0u8; 512];
+    while !request.windows(4).any(|window| window == b"\r\n\r\n") && request.len() < MAX_REQUEST_SIZE {
+        let read = stream.read(&mut buf)?;
+        if read == 0 {
+            break;
+        }
+        request.extend_from_slice(&buf[..read]);
+    }
+    let request = String::from_utf8_lossy(&request);
+    let request_line = request.lines().next().unwrap_or_default();
+
+    let response = respond(request_line, heartbeat, stale_after_ms, routes);
+    write!(
+        stream,
+        "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        response.code,
+        response.reason(),
+        response.content_type,
+        response.body.len(),
+        response.body
+    )?;
+    stream.flush()
+}
+
+/// The HTTP server of the health and status endpoints of a component.
+#[derive(Debug)]
+pub struct HealthServer {
+    /// The address listened on.
+    local_addr: SocketAddr,
+}
+
+impl HealthServer {
+    /// Listens on the endpoint, serving the requests on a thread of its own:
+    /// `/healthz` from the heartbeat, `/status` and `/metrics` from the bodies
+    /// rendered by `routes`, `/metrics` only if enabled on the endpoint.
+    ///
+    /// The thread inherits the scheduling of the calling thread, so it is to be
+    /// spawned from the main thread, off the isolated lcores.
+    ///
+    /// LATENCY: SLOW_PATH
+    ///
+    /// # Errors
+    /// Returns an error if the address can't be listened on.
+    pub fn spawn(endpoint: &HealthEndpoint, heartbeat: Heartbeat, mut routes: HealthRoutes) -> io::Result<Self> {
+        let listener = TcpListener::bind(&endpoint.bind)?;
+        let local_addr = listener.local_addr()?;
+        let stale_after_ms = endpoint.stale_after_ms;
+        if !endpoint.metrics {
+            routes.metrics = None;
+        }
+        thread::Builder::new().name("health".to_string()).spawn(move || {
+            for stream in listener.incoming() {
+                // A client going away or timing out only fails its own request
+                if let Ok(stream) = stream {
+                    let _ = serve(stream, &heartbeat, stale_after_ms, &routes);
+                }
+            }
+        })?;
+        Ok(Self { local_addr })
+    }
+
+    /// Returns the address listened on.
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_config() {
+        let config = HealthConfig::from_str(
+            r#"
+md-handler:
+  bind: 127.0.0.1:9101
+resource-manager:
+  bind: 0.0.0.0:9100
+  stale_after_ms: 10000
+  metrics: true
+"#,
+        )
+        .unwrap();
+        let endpoint = config.endpoint("md-handler").unwrap();
+        assert_eq!((endpoint.bind.as_str(), endpoint.stale_after_ms), ("127.0.0.1:9101", DEFAULT_STALE_AFTER_MS));
+        let endpoint = config.endpoint("resource-manager").unwrap();
+        assert_eq!((endpoint.stale_after_ms, endpoint.metrics), (10_000, true));
+        assert!(!config.endpoint("md-handler").unwrap().metrics);
+        assert_eq!(config.endpoint("md-subscriber"), None);
+
+        assert_eq!(HealthConfig::from_str("# none\n").unwrap(), HealthConfig::default());
+        assert!(HealthConfig::from_str("a:\n  bind: localhost\n").is_err());
+        assert!(HealthConfig::from_str("a:\n  bind: 127.0.0.1:9101\n  stale_after_ms: 0\n").is_err());
+    }
+
+    #[test]
+    fn test_respond() {
+        let heartbeat = Heartbeat::new();
+        let routes = HealthRoutes::new(|| "{\"feeds\":[]}".to_string());
+        let get = |line| respond(line, &heartbeat, 1_000, &routes);
+
+        // Unhealthy until the first beat
+        assert_eq!(get("GET /healthz HTTP/1.1").code, 503);
+        heartbeat.beat(now_ms());
+        let response = get("GET /healthz?verbose=1 HTTP/1.1");
+        assert_eq!(response.code, 200);
+        assert!(response.body.starts_with("{\"healthy\":true,"));
+        heartbeat.beat(now_ms() - 2_000);
+        assert_eq!(get("GET /healthz HTTP/1.1").code, 503);
+
+        let response = get("GET /status HTTP/1.1");
+        assert_eq!((response.code, response.body.as_str()), (200, "{\"feeds\":[]}"));
+        assert_eq!(get("GET /metrics HTTP/1.1").code, 404);
+        assert_eq!(get("POST /status HTTP/1.1").code, 405);
+        assert_eq!(get("").code, 400);
+
+        let routes = HealthRoutes::new(String::new).with_metrics(|| "ctl_up 1\n".to_string());
+        let response = respond("GET /metrics HTTP/1.1", &heartbeat, 1_000, &routes);
+        assert_eq!((response.code, response.content_type), (200, PROMETHEUS_CONTENT_TYPE));
+        assert_eq!(response.body, "ctl_up 1\n");
+        assert_eq!(respond("POST /metrics HTTP/1.1", &heartbeat, 1_000, &routes).code, 405);
+    }
+
+    #[test]
+    fn test_serve() {
+        let endpoint = HealthEndpoint { bind: "127.0.0.1:0".to_string(), stale_after_ms: 1_000, metrics: false };
+        let heartbeat = Heartbeat::new();
+        heartbeat.beat(now_ms());
+        let routes = HealthRoutes::new(|| "{}".to_string()).with_metrics(|| "ctl_up 1\n".to_string());
+        let server = HealthServer::spawn(&endpoint, heartbeat, routes).unwrap();
+        let get = |path: &str| {
+            let mut stream = TcpStream::connect(server.local_addr()).unwrap();
+            write!(stream, "GET {} HTTP/1.1\r\nHost: localhost\r\n\r\n", path).unwrap();
+            let mut response = String::new();
+            stream.read_to_string(&mut response).unwrap();
+            response
+        };
+
+        let response = get("/healthz");
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(response.contains("\r\n\r\n{\"healthy\":true,"));
+        // Not enabled on the endpoint
+        assert!(get("/metrics").starts_with("HTTP/1.1 404 Not Found\r\n"));
+    }
+}
@@ -0,0 +1,314 @@
+//! Lcore assignment of the controller components.
+//!
+//! The market data handler plans its worker lcores from `worker_cpus`, while
+//! the single-lcore components (subscriber, trade-stats, admin, the OMS of a
+//! strategy) take theirs from `configs/lcores.yaml`. Nothing stops those from
+//! landing on a worker or on each other, two busy-polling loops then halving
+//! each other's throughput, so the planner collects the lcores claimed by
+//! every component, detects the overlaps, and moves the single-lcore
+//! components to free lcores for a conflict-free assignment.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use serde::Deserialize;
+
+use crate::{LcoreConflictError, LcoresConfigError};
+
+/// The lcores of the single-lcore components, by component name.
+#[derive(Debug, Clone, Default, Deserialize, PartialEq, Eq)]
+#[serde(transparent)]
+pub struct LcoresConfig {
+    components: HashMap<String, u32>,
+}
+
+impl LcoresConfig {
+    /// Parses the lcores configuration from a YAML file.
+    ///
+    /// # Errors
+    /// Returns an error if the file cannot be read, parsed, or fails validation.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, LcoresConfigError> {
+        let content = fs::read_to_string(path)?;
+        Self::from_str(&content)
+    }
+
+    /// Parses the lcores configuration from a YAML string.
+    ///
+    /// # Errors
+    /// Returns an error if the YAML cannot be parsed or fails validation.
+    pub fn from_str(content: &str) -> Result<Self, LcoresConfigError> {
+        let config: Self = serde_yaml::from_str(content)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Validates the lcores configuration.
+    fn validate(&self) -> Result<(), LcoresConfigError> {
+        let mut components: Vec<_> = self.components.iter().collect();
+        components.sort();
+        for (i, (component, lcore)) in components.iter().enumerate() {
+            if let Some((other, _)) = components[..i].iter().find(|(_, other)| other == lcore) {
+                return Err(LcoresConfigError::ValidationError(format!(
+                    "Components '{}' and '{}' share lcore {}",
+                    other, component, lcore
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the lcore of a component, `default` if it isn't configured.
+    pub fn lcore(&self, component: &str, default: u32) -> u32 {
+        self.components.get(component).copied().unwrap_or(default)
+    }
+
+    /// Returns the configured lcore of a component, `None` if it isn't configured.
+    pub fn get(&self, component: &str) -> Option<u32> {
+        self.components.get(component).copied()
+    }
+}
+
+/// How a component uses its lcores.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LcoreUse {
+    /// A busy-polling loop owning the lcore, e.g. a worker.
+    Exclusive,
+    /// A mostly idle thread, e.g. a main thread, which may share its lcore
+    /// with the other shared threads.
+    Shared,
+}
+
+/// The lcores claimed by a component.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LcoreClaim {
+    /// The component name (e.g. `md-handler`).
+    pub component: String,
+    /// The role of the lcores in the component (e.g. `workers top/A`).
+    pub role: String,
+    /// The claimed lcores.
+    pub lcores: Vec<u32>,
+    /// How the component uses its lcores.
+    pub usage: LcoreUse,
+    /// Whether the planner may move the claim to a free lcore, only for the
+    /// single-lcore components of `configs/lcores.yaml`.
+    pub movable: bool,
+}
+
+impl fmt::Display for LcoreClaim {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.component, self.role)
+    }
+}
+
+/// An lcore claimed by several components, at least one of them exclusively.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LcoreConflict {
+    /// The lcore.
+    pub lcore: u32,
+    /// The claims of the lcore, as `component role`.
+    pub claims: Vec<String>,
+}
+
+/// A single-lcore component moved off a conflicting lcore.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LcoreMove {
+    /// The component name.
+    pub component: String,
+    /// The configured lcore.
+    pub from: u32,
+    /// The planned lcore.
+    pub to: u32,
+}
+
+/// A conflict-free lcore assignment.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LcoreTable {
+    /// The claims with their planned lcores, in claim order.
+    pub claims: Vec<LcoreClaim>,
+    /// The components moved off their configured lcore.
+    pub moves: Vec<LcoreMove>,
+}
+
+/// Collects the lcores claimed by the components and plans a conflict-free
+/// assignment.
+#[derive(Debug, Clone, Default)]
+pub struct LcorePlanner {
+    /// The claims in claim order.
+    claims: Vec<LcoreClaim>,
+}
+
+impl LcorePlanner {
+    /// Creates a planner with no claim.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Claims lcores the planner can't move, e.g. those planned from `worker_cpus`.
+    pub fn claim(&mut self, component: &str, role: &str, lcores: Vec<u32>, usage: LcoreUse) -> &mut Self {
+        self.push(component, role, lcores, usage, false)
+    }
+
+    /// Claims the lcore of a single-lcore component, which the planner moves
+    /// to a free lcore on a conflict.
+    pub fn claim_movable(&mut self, component: &str, role: &str, lcore: u32, usage: LcoreUse) -> &mut Self {
+        self.push(component, role, vec![lcore], usage, true)
+    }
+
+    fn push(&mut self, component: &str, role: &str, lcores: Vec<u32>, usage: LcoreUse, movable: bool) -> &mut Self {
+        self.claims.push(LcoreClaim {
+            component: component.to_string(),
+            role: role.to_string(),
+            lcores,
+            usage,
+            movable,
+        });
+        self
+    }
+
+    /// Returns the claims in claim order.
+    pub fn claims(&self) -> &[LcoreClaim] {
+        &self.claims
+    }
+
+    /// Returns the lcores claimed by several components, at least one of them
+    /// exclusively, in lcore order.
+    pub fn conflicts(&self) -> Vec<LcoreConflict> {
+        let mut by_lcore: Vec<(u32, &LcoreClaim)> = self
+            .claims
+            .iter()
+            .flat_map(|claim| claim.lcores.iter().map(move |&lcore| (lcore, claim)))
+            .collect();
+        by_lcore.sort_by_key(|&(lcore, _)| lcore);
+
+        by_lcore
+            .chunk_by(|a, b| a.0 == b.0)
+            .filter(|claims| claims.len() > 1 && claims.iter().any(|(_, c)| c.usage == LcoreUse::Exclusive))
+            .map(|claims| LcoreConflict {
+                lcore: claims[0].0,
+                claims: claims.iter().map(|(_, claim)| claim.to_string()).collect(),
+            })
+            .collect()
+    }
+
+    /// Plans a conflict-free assignment, moving the conflicting single-lcore
+    /// components to the lowest free lcores of `available` (e.g. the online CPUs).
+    ///
+    /// The fixed claims are placed first, in claim order, then the movable
+    /// ones, so a movable claim never displaces a fixed one.
+    ///
+    /// # Errors
+    /// Returns an error if two fixed claims conflict, or no free lcore is left
+    /// for a conflicting single-lcore component.
+    pub fn plan(&self, available: &[u32]) -> Result<LcoreTable, LcoreConflictError> {
+        // The claims placed on each lcore
+        let mut placed: HashMap<u32, Vec<(String, LcoreUse)>> = HashMap::new();
+        let fits = |placed: &HashMap<u32, Vec<(String, LcoreUse)>>, lcore: u32, usage: LcoreUse| {
+            placed.get(&lcore).is_none_or(|claims| {
+                usage == LcoreUse::Shared && claims.iter().all(|(_, u)| *u == LcoreUse::Shared)
+            })
+        };
+
+        for claim in self.claims.iter().filter(|claim| !claim.movable) {
+            for &lcore in &claim.lcores {
+                if !fits(&placed, lcore, claim.usage) {
+                    return Err(LcoreConflictError::Overlap {
+                        lcore,
+                        first: placed[&lcore][0].0.clone(),
+                        second: claim.to_string(),
+                    });
+                }
+                placed.entry(lcore).or_default().push((claim.to_string(), claim.usage));
+            }
+        }
+
+        // The movable claims keep their lcore when it fits, before the others
+        // are moved, so a moved claim never takes the lcore of another
+        let mut claims = self.claims.clone();
+        let mut conflicting = Vec::new();
+        for (i, claim) in claims.iter().enumerate().filter(|(_, claim)| claim.movable) {
+            if fits(&placed, claim.lcores[0], claim.usage) {
+                placed.entry(claim.lcores[0]).or_default().push((claim.to_string(), claim.usage));
+            } else {
+                conflicting.push(i);
+            }
+        }
+
+        let mut moves = Vec::new();
+        for i in conflicting {
+            let claim = &mut claims[i];
+            let free = available.iter().copied().find(|lcore| !placed.contains_key(lcore));
+            let free = free.ok_or_else(|| LcoreConflictError::NoFreeLcore(claim.to_string()))?;
+            moves.push(LcoreMove { component: claim.component.clone(), from: claim.lcores[0], to: free });
+            claim.lcores = vec![free];
+            placed.entry(free).or_default().push((claim.to_string(), claim.usage));
+        }
+
+        Ok(LcoreTable { claims, moves })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn planner() -> LcorePlanner {
+        let mut planner = LcorePlanner::new();
+        planner
+            .claim("resource-manager", "main", vec![0], LcoreUse::Shared)
+            .claim("md-handler", "main", vec![0], LcoreUse::Shared)
+            .claim("md-handler", "workers top", (1..=13).collect(), LcoreUse::Exclusive)
+            .claim_movable("md-subscriber", "main", 13, LcoreUse::Exclusive)
+            .claim_movable("trade-stats", "main", 14, LcoreUse::Exclusive)
+            .claim_movable("ctl-admin", "main", 0, LcoreUse::Shared);
+        planner
+    }
+
+    #[test]
+    fn test_parse_config() {
+        let config = LcoresConfig::from_str("md-subscriber: 13\ntrade-stats: 14\n").unwrap();
+        assert_eq!(config.lcore("md-subscriber", 1), 13);
+        assert_eq!(config.lcore("ctl-admin", 15), 15);
+        assert_eq!(config.get("oms"), None);
+
+        let result = LcoresConfig::from_str("md-subscriber: 13\ntrade-stats: 13\n");
+        assert!(result.unwrap_err().to_string().contains("share lcore 13"));
+    }
+
+    #[test]
+    fn test_conflicts() {
+        let conflicts = planner().conflicts();
+        assert_eq!(
+            conflicts,
+            vec![LcoreConflict {
+                lcore: 13,
+                claims: vec!["md-handler workers top".to_string(), "md-subscriber main".to_string()],
+            }]
+        );
+    }
+
+    #[test]
+    fn test_plan_moves_conflicting_components() {
+        let table = planner().plan(&(0..32).collect::<Vec<_>>()).unwrap();
+        assert_eq!(table.moves, vec![LcoreMove { component: "md-subscriber".to_string(), from: 13, to: 15 }]);
+        let subscriber = table.claims.iter().find(|claim| claim.component == "md-subscriber").unwrap();
+        assert_eq!(subscriber.lcores, vec![15]);
+
+        assert!(matches!(planner().plan(&[0, 1, 13, 14]), Err(LcoreConflictError::NoFreeLcore(_))));
+    }
+
+    #[test]
+    fn test_plan_fixed_overlap() {
+        let mut planner = planner();
+        planner.claim("oms", "main", vec![4], LcoreUse::Exclusive);
+        assert_eq!(
+            planner.plan(&[]),
+            Err(LcoreConflictError::Overlap {
+                lcore: 4,
+                first: "md-handler workers top".to_string(),
+                second: "oms main".to_string(),
+            })
+        );
+    }
+}
@@ -0,0 +1,64 @@
+//! Core types shared across the controller components.
+
+mod alert;
+mod audit;
+mod breaker;
+mod c_header;
+mod client_order_id;
+mod control;
+mod eal;
+mod errors;
+mod exit;
+mod health;
+mod lcores;
+mod overtaken;
+mod polling;
+mod preflight;
+mod prometheus;
+mod reattach;
+mod ring_name;
+mod sched;
+mod status;
+mod text;
+mod topology;
+mod trace;
+
+pub use alert::{
+    AlertKind, AlertMessage, AlertSeverity, ALERTS_RING_NAME, ALERTS_RING_SIZE, ALERT_DETAIL_SIZE,
+    ALERT_SOURCE_SIZE,
+};
+pub use audit::{AuditAction, AuditJournal, AuditOrigin, AuditRecord, ControlState, DEFAULT_AUDIT_JOURNAL_PATH};
+pub use breaker::{BreakerConfig, BreakerSignal, BreakerThreshold, CircuitBreaker};
+pub use c_header::declare_c_types;
+pub use client_order_id::{ClientOrderId, ClientOrderIdGenerator, MAX_CLIENT_ORDER_ID_LEN};
+pub use control::{
+    ControlCommand, ControlMessage, CONTROL_REASON_SIZE, CONTROL_TARGET_SIZE, CONTROL_RING_NAME, CONTROL_RING_SIZE,
+};
+pub use eal::EalConfig;
+pub use errors::{
+    AuditError, ClientOrderIdError, EpochError, HealthConfigError, LcoreConflictError, LcoresConfigError,
+    OvertakenConfigError, PollingConfigError, PreflightError, PreflightFailure, RingNameError, SchedulingConfigError,
+    SchedulingError, TelemetryConfigError,
+};
+pub use exit::{exit_code, Fatal, FatalError, FatalKind};
+pub use health::{
+    HealthConfig, HealthEndpoint, HealthResponse, HealthRoutes, HealthServer, Heartbeat, DEFAULT_STALE_AFTER_MS,
+};
+pub use lcores::{LcoreClaim, LcoreConflict, LcoreMove, LcorePlanner, LcoreTable, LcoreUse, LcoresConfig};
+pub use overtaken::{OvertakenConfig, OvertakenPolicy};
+pub use polling::{Poller, PollingConfig, PollingPolicy, Wait};
+pub use preflight::{
+    dpdk_config_path, effective_capabilities, hugepages_sysfs_dir, hugetlbfs_mounts, online_cpus, parse_cpu_list,
+    primary_running, Capability, Preflight, DPDK_FILE_PREFIX,
+};
+pub use prometheus::{MetricType, PrometheusText, PROMETHEUS_CONTENT_TYPE};
+pub use reattach::{reattach, reattaching, wait_for_run, REATTACH_EPOCH_ENV, REATTACH_POLL_INTERVAL};
+pub use ring_name::{Line, RingKind, RingName, RingSuffix};
+pub use sched::{SchedulingConfig, SchedulingPolicy};
+pub use status::{new_run_epoch, EpochWatch, StatusRegion, TradingStatus, STATUS_REGION_NAME};
+pub use text::{read_padded, write_padded};
+pub use topology::{check_topology, CpuTopology, TopologyIssue};
+pub use trace::{
+    OtlpConfig, Span, TelemetryConfig, TelemetryPolicy, TraceContext, Tracer, DEFAULT_BATCH_SIZE,
+    DEFAULT_FLUSH_INTERVAL_MS,
+};
@@ -0,0 +1,84 @@
+//! Recovery policies of the consumers overtaken by their producer.
+//!
+//! A consumer too slow for its ring is sped past, the messages it didn't read
+//! being overwritten. A monitor can skip them, but a strategy having lost book
+//! deltas trades on a book diverging from the exchange. The recovery applied
+//! once overtaken is selected per component in `configs/overtaken.yaml`.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use serde::Deserialize;
+
+use crate::OvertakenConfigError;
+
+/// The recovery of a consumer overtaken by its producer.
+#[derive(Debug, Clone, Copy, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case", tag = "policy")]
+pub enum OvertakenPolicy {
+    /// Skip the messages overwritten, resuming at the oldest message still held.
+    #[default]
+    SkipToHead,
+    /// Skip the messages overwritten, then read the conflated state of the
+    /// symbols again before handling the next deltas.
+    SkipAndRequestSnapshot,
+    /// Stop the component, for a supervisor to restart it from a snapshot.
+    FailFast,
+}
+
+/// The recovery policies of the components, by component name.
+#[derive(Debug, Clone, Default, Deserialize, PartialEq, Eq)]
+#[serde(transparent)]
+pub struct OvertakenConfig {
+    components: HashMap<String, OvertakenPolicy>,
+}
+
+impl OvertakenConfig {
+    /// Parses the recovery configuration from a YAML file.
+    ///
+    /// # Errors
+    /// Returns an error if the file cannot be read or parsed.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, OvertakenConfigError> {
+        let content = fs::read_to_string(path)?;
+        Self::from_str(&content)
+    }
+
+    /// Parses the recovery configuration from a YAML string.
+    ///
+    /// # Errors
+    /// Returns an error if the YAML cannot be parsed.
+    pub fn from_str(content: &str) -> Result<Self, OvertakenConfigError> {
+        Ok(serde_yaml::from_str(content)?)
+    }
+
+    /// Returns the policy of a component, `default` if it isn't configured.
+    pub fn policy(&self, component: &str, default: OvertakenPolicy) -> OvertakenPolicy {
+        self.components.get(component).copied().unwrap_or(default)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_config() {
+        let config = OvertakenConfig::from_str(
+            r#"
+md-subscriber:
+  policy: skip-and-request-snapshot
+strategy:
+  policy: fail-fast
+"#,
+        )
+        .unwrap();
+        assert_eq!(
+            config.policy("md-subscriber", OvertakenPolicy::SkipToHead),
+            OvertakenPolicy::SkipAndRequestSnapshot
+        );
+        assert_eq!(config.policy("strategy", OvertakenPolicy::SkipToHead), OvertakenPolicy::FailFast);
+        assert_eq!(config.policy("monitor", OvertakenPolicy::SkipToHead), OvertakenPolicy::SkipToHead);
+        assert!(OvertakenConfig::from_str("a:\n  policy: skip\n").is_err());
+    }
+}
@@ -0,0 +1,276 @@
+//! Polling policies of the component main loops.
+//!
+//! A latency-critical consumer wants to notice a message as soon as it's
+//! published, at the cost of a core; a housekeeping thread would rather give
+//! the core back. The policy applied between the polls that found no work is
+//! selected per component in `configs/polling.yaml`.
+
+use std::collections::HashMap;
+use std::fs;
+use std::hint;
+use std::path::Path;
+use std::thread;
+use std::time::Duration;
+
+use serde::Deserialize;
+
+use crate::PollingConfigError;
+
+/// The wait between the polls that found no work.
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case", tag = "policy")]
+pub enum PollingPolicy {
+    /// Poll again immediately.
+    BusyPoll,
+    /// Spin with the pause instruction before polling again, easing the
+    /// pressure on the sibling hyperthread and the memory bus.
+    Spin {
+        /// Pause instructions per idle poll.
+        spins: u32,
+    },
+    /// Spin, then yield, then sleep with an exponentially growing duration,
+    /// until a poll finds work.
+    AdaptiveBackoff {
+        /// Idle polls spinning before yielding.
+        spin_polls: u32,
+        /// Idle polls yielding before sleeping.
+        yield_polls: u32,
+        /// The first sleep, in microseconds.
+        min_sleep_us: u64,
+        /// The longest sleep, in microseconds.
+        max_sleep_us: u64,
+    },
+    /// Sleep for a fixed duration.
+    Sleep {
+        /// The sleep, in microseconds.
+        sleep_us: u64,
+    },
+}
+
+impl PollingPolicy {
+    /// Validates the policy configuration.
+    pub fn validate(&self) -> Result<(), String> {
+        match *self {
+            PollingPolicy::Spin { spins: 0 } => Err("spin policy must have a non-zero 'spins'".to_string()),
+            PollingPolicy::Sleep { sleep_us: 0 } => {
+                Err("sleep policy must have a non-zero 'sleep_us'".to_string())
+            }
+            PollingPolicy::AdaptiveBackoff { min_sleep_us, max_sleep_us, .. }
+                if min_sleep_us == 0 || min_sleep_us > max_sleep_us =>
+            {
+                Err(format!(
+                    "adaptive-backoff policy must have 0 < 'min_sleep_us' <= 'max_sleep_us', got {} and {}",
+                    min_sleep_us, max_sleep_us
+                ))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// The wait before the next poll.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Wait {
+    /// Poll again immediately.
+    None,
+    /// Spin with the pause instruction this many times.
+    Spin(u32),
+    /// Yield the core to the scheduler.
+    Yield,
+    /// Sleep.
+    Sleep(Duration),
+}
+
+/// Applies a polling policy to a main loop.
+#[derive(Debug, Clone)]
+pub struct Poller {
+    /// The policy.
+    policy: PollingPolicy,
+    /// Consecutive polls that found no work.
+    idle_polls: u64,
+    /// The next adaptive backoff sleep, in microseconds.
+    sleep_us: u64,
+}
+
+impl Poller {
+    /// Creates a poller applying `policy`.
+    pub fn new(policy: PollingPolicy) -> Self {
+        let sleep_us = match policy {
+            PollingPolicy::AdaptiveBackoff { min_sleep_us, .. } => min_sleep_us,
+            _ => 0,
+        };
+        Self { policy, idle_polls: 0, sleep_us }
+    }
+
+    /// Returns the policy.
+    pub fn policy(&self) -> PollingPolicy {
+        self.policy
+    }
+
+    /// Returns the wait after a poll, resetting the backoff if it found work.
+    ///
+    /// LATENCY: FAST_PATH
+    pub fn next_wait(&mut self, did_work: bool) -> Wait {
+        if did_work {
+            self.idle_polls = 0;
+            if let PollingPolicy::AdaptiveBackoff { min_sleep_us, .. } = self.policy {
+                self.sleep_us = min_sleep_us;
+            }
+            return Wait::None;
+        }
+
+        self.idle_polls += 1;
+        match self.policy {
+            PollingPolicy::BusyPoll => Wait::None,
+            PollingPolicy::Spin { spins } => Wait::Spin(spins),
+            PollingPolicy::Sleep { sleep_us } => Wait::Sleep(Duration::from_micros(sleep_us)),
+            PollingPolicy::AdaptiveBackoff { spin_polls, yield_polls, max_sleep_us, .. } => {
+                if self.idle_polls <= spin_polls as u64 {
+                    Wait::Spin(1)
+                } else if self.idle_polls <= spin_polls as u64 + yield_polls as u64 {
+                    Wait::Yield
+                } else {
+                    let sleep = Duration::from_micros(self.sleep_us);
+                    self.sleep_us = (self.sleep_us * 2).min(max_sleep_us);
+                    Wait::Sleep(sleep)
+                }
+            }
+        }
+    }
+
+    /// Waits after a poll according to the policy.
+    ///
+    /// LATENCY: FAST_PATH (except when yielding or sleeping)
+    pub fn wait(&mut self, did_work: bool) {
+        match self.next_wait(did_work) {
+            Wait::None => {}
+            Wait::Spin(spins) => {
+                for _ in 0..spins {
+                    hint::spin_loop();
+                }
+            }
+            Wait::Yield => thread::yield_now(),
+            Wait::Sleep(duration) => thread::sleep(duration),
+        }
+    }
+}
+
+/// The polling policies of the components, by component name.
+#[derive(Debug, Clone, Default, Deserialize, PartialEq, Eq)]
+#[serde(transparent)]
+pub struct PollingConfig {
+    components: HashMap<String, PollingPolicy>,
+}
+
+impl PollingConfig {
+    /// Parses the polling configuration from a YAML file.
+    ///
+    /// # Errors
+    /// Returns an error if the file cannot be read, parsed, or fails validation.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, PollingConfigError> {
+        let content = fs::read_to_string(path)?;
+        Self::from_str(&content)
+    }
+
+    /// Parses the polling configuration from a YAML string.
+    ///
+    /// # Errors
+    /// Returns an error if the YAML cannot be parsed or fails validation.
+    pub fn from_str(content: &str) -> Result<Self, PollingConfigError> {
+        let config: Self = serde_yaml::from_str(content)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Validates the polling configuration.
+    fn validate(&self) -> Result<(), PollingConfigError> {
+        for (component, policy) in &self.components {
+            policy.validate().map_err(|e| {
+                PollingConfigError::ValidationError(format!("Component '{}': {}", component, e))
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Returns the policy of a component, `default` if it isn't configured.
+    pub fn policy(&self, component: &str, default: PollingPolicy) -> PollingPolicy {
+        self.components.get(component).copied().unwrap_or(default)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_config() {
+        let config = PollingConfig::from_str(
+            r#"
+md-handler:
+  policy: adaptive-backoff
+  spin_polls: 2
+  yield_polls: 1
+  min_sleep_us: 50
+  max_sleep_us: 120
+md-subscriber:
+  policy: busy-poll
+trade-stats:
+  policy: spin
+  spins: 64
+"#,
+        )
+        .unwrap();
+        assert_eq!(config.policy("md-subscriber", PollingPolicy::Spin { spins: 1 }), PollingPolicy::BusyPoll);
+        assert_eq!(config.policy("trade-stats", PollingPolicy::BusyPoll), PollingPolicy::Spin { spins: 64 });
+        let default = PollingPolicy::Sleep { sleep_us: 10_000 };
+        assert_eq!(config.policy("ctl-admin", default), default);
+    }
+
+    #[test]
+    fn test_invalid_policies() {
+        assert!(PollingConfig::from_str("a:\n  policy: spin\n  spins: 0\n").is_err());
+        assert!(PollingConfig::from_str("a:\n  policy: sleep\n  sleep_us: 0\n").is_err());
+        assert!(PollingConfig::from_str("a:\n  policy: busy-wait\n").is_err());
+
+        let result = PollingConfig::from_str(
+            "a:\n  policy: adaptive-backoff\n  spin_polls: 1\n  yield_polls: 1\n  min_sleep_us: 10\n  max_sleep_us: 5\n",
+        );
+        assert!(result.unwrap_err().to_string().contains("Component 'a'"));
+    }
+
+    #[test]
+    fn test_adaptive_backoff() {
+        let mut poller = Poller::new(PollingPolicy::AdaptiveBackoff {
+            spin_polls: 2,
+            yield_polls: 1,
+            min_sleep_us: 50,
+            max_sleep_us: 120,
+        });
+        let waits: Vec<_> = (0..6).map(|_| poller.next_wait(false)).collect();
+        assert_eq!(
+            waits,
+            vec![
+                Wait::Spin(1),
+                Wait::Spin(1),
+                Wait::Yield,
+                Wait::Sleep(Duration::from_micros(50)),
+                Wait::Sleep(Duration::from_micros(100)),
+                Wait::Sleep(Duration::from_micros(120)),
+            ]
+        );
+
+        // Work resets the backoff
+        assert_eq!(poller.next_wait(true), Wait::None);
+        assert_eq!(poller.next_wait(false), Wait::Spin(1));
+    }
+
+    #[test]
+    fn test_fixed_policies() {
+        assert_eq!(Poller::new(PollingPolicy::BusyPoll).next_wait(false), Wait::None);
+        assert_eq!(Poller::new(PollingPolicy::Spin { spins: 8 }).next_wait(false), Wait::Spin(8));
+        assert_eq!(
+            Poller::new(PollingPolicy::Sleep { sleep_us: 10 }).next_wait(false),
+            Wait::Sleep(Duration::from_micros(10))
+        );
+    }
+}
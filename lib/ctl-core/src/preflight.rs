@@ -0,0 +1,442 @@
+//! Startup checks of the host environment.
+//!
+//! The EAL reports a missing hugetlbfs mount, an offline lcore or a dead
+//! primary process as cryptic initialization failures, often after the
+//! component loaded its configuration and logged its plan. Each binary runs a
+//! [`Preflight`] describing what it needs before initializing DPDK, and exits
+//! with every unmet requirement and how to fix it.
+
+use std::fmt;
+use std::fs::{self, File};
+use std::io;
+use std::os::fd::AsRawFd;
+use std::path::{Path, PathBuf};
+
+use crate::{PreflightError, PreflightFailure};
+
+/// The mounted filesystems.
+const MOUNTS_PATH: &str = "/proc/mounts";
+/// The status of this process, holding its capability sets.
+const STATUS_PATH: &str = "/proc/self/status";
+/// The online CPUs, as a CPU list.
+const ONLINE_CPUS_PATH: &str = "/sys/devices/system/cpu/online";
+/// The CPUs isolated from the scheduler by `isolcpus=`, as a CPU list.
+const ISOLATED_CPUS_PATH: &str = "/sys/devices/system/cpu/isolated";
+/// The file prefix of the DPDK runtime directory, the EAL default.
+pub const DPDK_FILE_PREFIX: &str = "rte";
+
+/// A Linux capability needed by a component.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Capability {
+    /// Bypasses the file permissions, e.g. to write the hugepage sysfs files.
+    DacOverride,
+    /// Locks the hugepage memory.
+    IpcLock,
+    /// Reads the physical addresses of the hugepages from `/proc/self/pagemap`.
+    SysAdmin,
+    /// Raises the scheduling priority of the worker threads.
+    SysNice,
+}
+
+impl Capability {
+    /// Returns the bit of the capability in the capability sets.
+    pub fn bit(&self) -> u32 {
+        match self {
+            Capability::DacOverride => 1,
+            Capability::IpcLock => 14,
+            Capability::SysAdmin => 21,
+            Capability::SysNice => 23,
+        }
+    }
+
+    /// Returns the capability name.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Capability::DacOverride => "cap_dac_override",
+            Capability::IpcLock => "cap_ipc_lock",
+            Capability::SysAdmin => "cap_sys_admin",
+            Capability::SysNice => "cap_sys_nice",
+        }
+    }
+}
+
+impl fmt::Display for Capability {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// The expected state of the DPDK primary process.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PrimaryState {
+    /// A secondary process attaches to the running primary.
+    Running,
+    /// The primary process must be the only one.
+    Stopped,
+}
+
+/// The requirements of a component on the host, checked at startup.
+#[derive(Debug, Clone, Default)]
+pub struct Preflight {
+    /// Whether a hugetlbfs mount is required, of any page size.
+    hugepages_mounted: bool,
+    /// The free hugepages required, as (page size in kB, pages).
+    hugepages: Vec<(u32, u32)>,
+    /// The lcores that must be online CPUs.
+    lcores: Vec<usize>,
+    /// The lcores that must also be isolated.
+    isolated_lcores: Vec<usize>,
+    /// The capabilities required in the effective set.
+    capabilities: Vec<Capability>,
+    /// The expected state of the DPDK primary process, unchecked if `None`.
+    primary: Option<PrimaryState>,
+    /// The file prefix of the DPDK runtime directory, the EAL default if `None`.
+    file_prefix: Option<String>,
+}
+
+impl Preflight {
+    /// Creates a preflight without requirements.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Requires a hugetlbfs mount, the memory of the primary being mapped from it.
+    pub fn require_hugepages_mounted(mut self) -> Self {
+        self.hugepages_mounted = true;
+        self
+    }
+
+    /// Requires a hugetlbfs mount of `size_kb` pages, and `pages` of them free.
+    pub fn require_hugepages(mut self, size_kb: u32, pages: u32) -> Self {
+        self.hugepages.push((size_kb, pages));
+        self
+    }
+
+    /// Requires the lcores to be online CPUs.
+    pub fn require_lcores(mut self, lcores: &[usize]) -> Self {
+        self.lcores.extend_from_slice(lcores);
+        self
+    }
+
+    /// Requires the lcores to be online CPUs isolated from the scheduler, for
+    /// the threads polling on them not to be preempted.
+    pub fn require_isolated_lcores(mut self, lcores: &[usize]) -> Self {
+        self.isolated_lcores.extend_from_slice(lcores);
+        self
+    }
+
+    /// Requires the capabilities in the effective set of the process.
+    pub fn require_capabilities(mut self, capabilities: &[Capability]) -> Self {
+        self.capabilities.extend_from_slice(capabilities);
+        self
+    }
+
+    /// Requires the DPDK primary process (ctl-resource-manager) to be running.
+    pub fn require_primary(mut self) -> Self {
+        self.primary = Some(PrimaryState::Running);
+        self
+    }
+
+    /// Requires no DPDK primary process to be running, for this process to become it.
+    pub fn require_no_primary(mut self) -> Self {
+        self.primary = Some(PrimaryState::Stopped);
+        self
+    }
+
+    /// Checks the DPDK primary process of the controller instance using `file_prefix`.
+    pub fn with_file_prefix(mut self, file_prefix: &str) -> Self {
+        self.file_prefix = Some(file_prefix.to_string());
+        self
+    }
+
+    /// Checks every requirement.
+    ///
+    /// LATENCY: SLOW_PATH
+    ///
+    /// # Errors
+    /// Returns every unmet requirement, or the system files that couldn't be read.
+    pub fn run(&self) -> Result<(), PreflightFailure> {
+        let mut errors = Vec::new();
+        if let Err(e) = self.check_hugepages(&mut errors) {
+            errors.push(e);
+        }
+        if let Err(e) = self.check_lcores(&mut errors) {
+            errors.push(e);
+        }
+        if let Err(e) = self.check_capabilities(&mut errors) {
+            errors.push(e);
+        }
+        if let Err(e) = self.check_primary(&mut errors) {
+            errors.push(e);
+        }
+
+        if errors.is_empty() { Ok(()) } else { Err(PreflightFailure { errors }) }
+    }
+
+    /// Checks the hugetlbfs mounts and the free hugepages.
+    fn check_hugepages(&self, errors: &mut Vec<PreflightError>) -> Result<(), PreflightError> {
+        if !self.hugepages_mounted && self.hugepages.is_empty() {
+            return Ok(());
+        }
+        let mounts = hugetlbfs_mounts(&read(MOUNTS_PATH)?);
+        if self.hugepages_mounted && mounts.is_empty() {
+            errors.push(PreflightError::HugepagesNotMounted);
+        }
+
+        for &(size_kb, pages) in &self.hugepages {
+            if !mounts.iter().any(|&page_kb| page_kb.is_none_or(|kb| kb == size_kb)) {
+                errors.push(PreflightError::HugepageSizeNotMounted { size_kb });
+            }
+            let path = hugepages_sysfs_dir(size_kb).join("free_hugepages");
+            let free = match fs::read_to_string(&path) {
+                Ok(content) => content.trim().parse().unwrap_or_default(),
+                // The kernel doesn't support the page size
+                Err(e) if e.kind() == io::ErrorKind::NotFound => 0,
+                Err(source) => return Err(PreflightError::ReadError { path, source }),
+            };
+            if free < pages {
+                errors.push(PreflightError::InsufficientHugepages { size_kb, free, required: pages });
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks that the lcores are online and, as required, isolated.
+    fn check_lcores(&self, errors: &mut Vec<PreflightError>) -> Result<(), PreflightError> {
+        if self.lcores.is_empty() && self.isolated_lcores.is_empty() {
+            return Ok(());
+        }
+        let online_list = read(ONLINE_CPUS_PATH)?;
+        let online = parse_cpu_list(&online_list)
+            .ok_or_else(|| invalid_content(ONLINE_CPUS_PATH, &online_list))?;
+        for &lcore in self.lcores.iter().chain(&self.isolated_lcores) {
+            if !online.contains(&lcore) {
+                errors.push(PreflightError::LcoreOffline { lcore, online: online_list.trim().to_string() });
+            }
+        }
+
+        if self.isolated_lcores.is_empty() {
+            return Ok(());
+        }
+        let isolated_list = read(ISOLATED_CPUS_PATH)?;
+        let isolated = parse_cpu_list(&isolated_list)
+            .ok_or_else(|| invalid_content(ISOLATED_CPUS_PATH, &isolated_list))?;
+        for &lcore in &self.isolated_lcores {
+            if online.contains(&lcore) && !isolated.contains(&lcore) {
+                let isolated = match isolated_list.trim() {
+                    "" => "none".to_string(),
+                    list => list.to_string(),
+                };
+                errors.push(PreflightError::LcoreNotIsolated { lcore, isolated });
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks the effective capabilities of the process.
+    fn check_capabilities(&self, errors: &mut Vec<PreflightError>) -> Result<(), PreflightError> {
+        if self.capabilities.is_empty() {
+            return Ok(());
+        }
+        let status = read(STATUS_PATH)?;
+        let effective = effective_capabilities(&status).ok_or_else(|| invalid_content(STATUS_PATH, "no CapEff"))?;
+        for &capability in &self.capabilities {
+            if effective & (1 << capability.bit()) == 0 {
+                errors.push(PreflightError::MissingCapability { capability });
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks the state of the DPDK primary process.
+    fn check_primary(&self, errors: &mut Vec<PreflightError>) -> Result<(), PreflightError> {
+        let Some(expected) = self.primary else {
+            return Ok(());
+        };
+        let path = dpdk_config_path(self.file_prefix.as_deref().unwrap_or(DPDK_FILE_PREFIX));
+        let running = primary_running(&path).map_err(|source| PreflightError::ReadError { path: path.clone(), source })?;
+        match (expected, running) {
+            (PrimaryState::Running, false) => errors.push(PreflightError::PrimaryNotRunning { path }),
+            (PrimaryState::Stopped, true) => errors.push(PreflightError::PrimaryAlreadyRunning { path }),
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+/// Reads a system file.
+fn read(path: &str) -> Result<String, PreflightError> {
+    fs::read_to_string(path).map_err(|source| PreflightError::ReadError { path: PathBuf::from(path), source })
+}
+
+/// Returns the error of a system file with unexpected content.
+fn invalid_content(path: &str, content: &str) -> PreflightError {
+    PreflightError::ReadError {
+        path: PathBuf::from(path),
+        source: io::Error::new(io::ErrorKind::InvalidData, format!("unexpected content '{}'", content.trim())),
+    }
+}
+
+/// Returns the sysfs directory of the hugepages of a page size.
+pub fn hugepages_sysfs_dir(size_kb: u32) -> PathBuf {
+    PathBuf::from(format!("/sys/kernel/mm/hugepages/hugepages-{}kB", size_kb))
+}
+
+/// Returns the page size of each hugetlbfs mount of `/proc/mounts`, in kB,
+/// `None` if the mount doesn't state it (the default hugepage size).
+pub fn hugetlbfs_mounts(mounts: &str) -> Vec<Option<u32>> {
+    mounts
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let (_device, _mount_point, fs_type, options) =
+                (fields.next()?, fields.next()?, fields.next()?, fields.next().unwrap_or_default());
+            (fs_type == "hugetlbfs").then(|| {
+                options
+                    .split(',')
+                    .find_map(|option| option.strip_prefix("pagesize="))
+                    .and_then(parse_page_size_kb)
+            })
+        })
+        .collect()
+}
+
+/// Parses a page size with a `K`, `M` or `G` suffix, e.g. `2M`, in kB.
+fn parse_page_size_kb(size: &str) -> Option<u32> {
+    let (value, unit) = size.split_at(size.len().checked_sub(1)?);
+    let value: u32 = value.parse().ok()?;
+    match unit {
+        "K" | "k" => Some(value),
+        "M" | "m" => value.checked_mul(1024),
+        "G" | "g" => value.checked_mul(1024 * 1024),
+        _ => None,
+    }
+}
+
+/// Parses a kernel CPU list, e.g. `0-3,8,10-11`. An empty list has no CPUs.
+pub fn parse_cpu_list(list: &str) -> Option<Vec<usize>> {
+    let mut cpus = Vec::new();
+    for range in list.trim().split(',').filter(|r| !r.is_empty()) {
+        match range.split_once('-') {
+            Some((first, last)) => {
+                let (first, last): (usize, usize) = (first.parse().ok()?, last.parse().ok()?);
+                if first > last {
+                    return None;
+                }
+                cpus.extend(first..=last);
+            }
+            None => cpus.push(range.parse().ok()?),
+        }
+    }
+    Some(cpus)
+}
+
+/// Returns the online CPUs of the host, `None` if they can't be read.
+pub fn online_cpus() -> Option<Vec<usize>> {
+    fs::read_to_string(ONLINE_CPUS_PATH).ok().and_then(|list| parse_cpu_list(&list))
+}
+
+/// Returns the effective capability set of a `/proc/{pid}/status`.
+pub fn effective_capabilities(status: &str) -> Option<u64> {
+    status
+        .lines()
+        .find_map(|line| line.strip_prefix("CapEff:"))
+        .and_then(|caps| u64::from_str_radix(caps.trim(), 16).ok())
+}
+
+/// Returns the path of the config file of the DPDK runtime directory, locked
+/// by the primary process for its lifetime.
+pub fn dpdk_config_path(file_prefix: &str) -> PathBuf {
+    // SAFETY: getuid has no preconditions and cannot fail.
+    let runtime_dir = if unsafe { libc::getuid() } == 0 {
+        PathBuf::from("/var/run")
+    } else {
+        std::env::var_os("XDG_RUNTIME_DIR").map(PathBuf::from).unwrap_or_else(|| PathBuf::from("/tmp"))
+    };
+    runtime_dir.join("dpdk").join(file_prefix).join("config")
+}
+
+/// Returns whether a process holds a lock on the DPDK config file, i.e. the
+/// primary process is running. A stale file of a dead primary isn't locked.
+pub fn primary_running(path: &Path) -> io::Result<bool> {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(false),
+        Err(e) => return Err(e),
+    };
+
+    // SAFETY: flock is plain data, valid when zeroed.
+    let mut lock: libc::flock = unsafe { std::mem::zeroed() };
+    lock.l_type = libc::F_WRLCK as libc::c_short;
+    lock.l_whence = libc::SEEK_SET as libc::c_short;
+    // SAFETY: the descriptor is open for the call and `lock` is a valid flock.
+    let ret = unsafe { libc::fcntl(file.as_raw_fd(), libc::F_GETLK, &mut lock) };
+    if ret < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(lock.l_type != libc::F_UNLCK as libc::c_short)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_cpu_list() {
+        assert_eq!(parse_cpu_list("0-3,8,10-11\n"), Some(vec![0, 1, 2, 3, 8, 10, 11]));
+        assert_eq!(parse_cpu_list("5"), Some(vec![5]));
+        assert_eq!(parse_cpu_list("\n"), Some(vec![]));
+        assert_eq!(parse_cpu_list("3-1"), None);
+        assert_eq!(parse_cpu_list("a-b"), None);
+    }
+
+    #[test]
+    fn test_hugetlbfs_mounts() {
+        let mounts = "\
+sysfs /sys sysfs rw,nosuid,nodev,noexec,relatime 0 0
+hugetlbfs /dev/hugepages hugetlbfs rw,relatime,pagesize=2M 0 0
+nodev /mnt/huge-1G hugetlbfs rw,relatime,pagesize=1024M 0 0
+nodev /mnt/huge hugetlbfs rw,relatime 0 0
+";
+        assert_eq!(hugetlbfs_mounts(mounts), vec![Some(2048), Some(1048576), None]);
+        assert!(hugetlbfs_mounts("tmpfs /dev/shm tmpfs rw 0 0\n").is_empty());
+    }
+
+    #[test]
+    fn test_effective_capabilities() {
+        let status = "Name:\tctl-md-handler\nCapInh:\t0000000000000000\nCapEff:\t0000000000004002\n";
+        let effective = effective_capabilities(status).unwrap();
+        assert_ne!(effective & (1 << Capability::IpcLock.bit()), 0);
+        assert_ne!(effective & (1 << Capability::DacOverride.bit()), 0);
+        assert_eq!(effective & (1 << Capability::SysAdmin.bit()), 0);
+        assert_eq!(effective_capabilities("Name:\tx\n"), None);
+    }
+
+    #[test]
+    fn test_primary_running() {
+        // A missing runtime directory means the primary never started
+        assert!(!primary_running(Path::new("/nonexistent/dpdk/rte/config")).unwrap());
+
+        // An unlocked config file is left by a dead primary
+        let dir = std::env::temp_dir().join(format!("ctl-preflight-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("config");
+        fs::write(&path, b"").unwrap();
+        assert!(!primary_running(&path).unwrap());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_failure_lists_errors() {
+        let failure = PreflightFailure {
+            errors: vec![
+                PreflightError::HugepagesNotMounted,
+                PreflightError::MissingCapability { capability: Capability::IpcLock },
+            ],
+        };
+        let message = failure.to_string();
+        assert!(message.starts_with("2 preflight checks failed"));
+        assert!(message.contains("cap_ipc_lock"));
+        assert!(Preflight::new().run().is_ok());
+    }
+}
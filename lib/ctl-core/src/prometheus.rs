@@ -0,0 +1,102 @@
+//! Prometheus text exposition of the metrics of a component.
+//!
+//! The components serving a `/metrics` endpoint render their counters in the
+//! text format of Prometheus (version 0.0.4): a `# HELP` and `# TYPE` line per
+//! metric family, then one sample per set of labels.
+
+use std::fmt::Write;
+
+/// The content type of the text exposition format.
+pub const PROMETHEUS_CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// The type of a metric family.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MetricType {
+    /// A value only going up, e.g. the messages received.
+    Counter,
+    /// A value going up and down, e.g. a message rate.
+    Gauge,
+}
+
+impl MetricType {
+    /// Returns the name of the type in the `# TYPE` line.
+    fn as_str(self) -> &'static str {
+        match self {
+            MetricType::Counter => "counter",
+            MetricType::Gauge => "gauge",
+        }
+    }
+}
+
+/// Escapes a label value, or with `quote` false the text of a `# HELP` line.
+fn escape(s: &str, quote: bool) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '"' if quote => escaped.push_str("\\\""),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// A text exposition being written, family by family.
+#[derive(Debug, Default)]
+pub struct PrometheusText {
+    /// The text written so far.
+    out: String,
+}
+
+impl PrometheusText {
+    /// Creates an empty exposition.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Starts a metric family, its samples to follow.
+    pub fn family(&mut self, name: &str, kind: MetricType, help: &str) {
+        let _ = writeln!(self.out, "# HELP {} {}", name, escape(help, false));
+        let _ = writeln!(self.out, "# TYPE {} {}", name, kind.as_str());
+    }
+
+    /// Writes a sample of the family `name` with its labels.
+    pub fn sample(&mut self, name: &str, labels: &[(&str, &str)], value: u64) {
+        self.out.push_str(name);
+        if !labels.is_empty() {
+            let labels: Vec<String> =
+                labels.iter().map(|(label, value)| format!("{}=\"{}\"", label, escape(value, true))).collect();
+            let _ = write!(self.out, "{{{}}}", labels.join(","));
+        }
+        let _ = writeln!(self.out, " {}", value);
+    }
+
+    /// Returns the text of the exposition.
+    pub fn finish(self) -> String {
+        self.out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exposition() {
+        let mut text = PrometheusText::new();
+        text.family("ctl_messages_total", MetricType::Counter, "Messages received.\nPer stream.");
+        text.sample("ctl_messages_total", &[("feed", "top"), ("symbol", "BTC\"USDT\\")], 42);
+        text.family("ctl_up", MetricType::Gauge, "Up.");
+        text.sample("ctl_up", &[], 1);
+        assert_eq!(
+            text.finish(),
+            "# HELP ctl_messages_total Messages received.\\nPer stream.\n\
+             # TYPE ctl_messages_total counter\n\
+             ctl_messages_total{feed=\"top\",symbol=\"BTC\\\"USDT\\\\\"} 42\n\
+             # HELP ctl_up Up.\n\
+             # TYPE ctl_up gauge\n\
+             ctl_up 1\n"
+        );
+    }
+}
@@ -0,0 +1,88 @@
+//! Reattachment of the secondaries to a restarted resource manager.
+//!
+//! Once ctl-resource-manager restarts, the rings and regions a secondary mapped
+//! are those of the previous run (see `EpochWatch`). The EAL of a process can't
+//! be initialized twice, so a secondary reattaches by executing its binary
+//! anew: the new image starts without the DPDK state of the previous run, waits
+//! for the resource manager to set up its next run, and then looks up its rings
+//! and subscribes its streams as on any start, keeping its PID for the
+//! supervisor. The stale run epoch is passed to the new image in
+//! `CTL_REATTACH_EPOCH`, telling it to wait instead of failing its preflight.
+
+use std::env;
+use std::os::unix::process::CommandExt;
+use std::process::Command;
+use std::thread;
+use std::time::Duration;
+
+use ctl_shm::ShmRegion;
+
+use crate::{EpochWatch, FatalError, FatalKind, StatusRegion};
+
+/// The environment variable holding the stale run epoch of a reattaching secondary.
+pub const REATTACH_EPOCH_ENV: &str = "CTL_REATTACH_EPOCH";
+
+/// Interval between the checks of a reattaching secondary for the next run.
+pub const REATTACH_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Executes the binary anew with its arguments, to reattach it to the next run
+/// of the resource manager.
+///
+/// The image is replaced, unmapping the rings and regions of the stale run and
+/// stopping the workers, so this only returns if the binary can't be executed.
+///
+/// LATENCY: SLOW_PATH
+pub fn reattach(watch: &EpochWatch) -> FatalError {
+    let mut args = env::args_os();
+    let Some(program) = args.next() else {
+        return FatalError::new(FatalKind::PrimaryRestarted, "The command line of the process is empty");
+    };
+    // The program is resolved as on the first start, so an upgraded binary is executed
+    let e = Command::new(program).args(args).env(REATTACH_EPOCH_ENV, watch.attached().to_string()).exec();
+    FatalError::new(FatalKind::PrimaryRestarted, e)
+}
+
+/// Returns the stale run epoch if this process was executed to reattach.
+pub fn reattaching() -> Option<u64> {
+    env::var(REATTACH_EPOCH_ENV).ok()?.parse().ok()
+}
+
+/// Waits for the resource manager to set up a run other than `stale` in the
+/// status region `region`, polling every `interval`, and returns its epoch.
+///
+/// LATENCY: SLOW_PATH
+pub fn wait_for_run(region: &str, stale: u64, interval: Duration) -> u64 {
+    loop {
+        // The region is missing while the resource manager is down, and unstamped while it starts
+        let epoch = ShmRegion::<StatusRegion>::open(region).map(|status| status.run_epoch()).unwrap_or_default();
+        if epoch != 0 && epoch != stale {
+            return epoch;
+        }
+        thread::sleep(interval);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wait_for_run() {
+        let name = format!("ctl_reattach_test_{}", std::process::id());
+        let stale = ShmRegion::<StatusRegion>::create(&name).unwrap();
+        stale.start_run(1, 1_000);
+
+        let waiting = {
+            let name = name.clone();
+            thread::spawn(move || wait_for_run(&name, 1, Duration::from_millis(1)))
+        };
+        thread::sleep(Duration::from_millis(10));
+        assert!(!waiting.is_finished());
+
+        let restarted = ShmRegion::<StatusRegion>::create(&name).unwrap();
+        thread::sleep(Duration::from_millis(10));
+        assert!(!waiting.is_finished());
+        restarted.start_run(2, 2_000);
+        assert_eq!(waiting.join().unwrap(), 2);
+    }
+}
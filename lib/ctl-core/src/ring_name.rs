@@ -0,0 +1,322 @@
+//! Names of the per-symbol rings.
+//!
+//! A per-symbol ring is named `{KIND}_{symbol_id}_{suffix}`, e.g. `TRADE_3_PS`
+//! for the pub-sub ring of the trades of symbol 3. The resource manager creates
+//! the rings under these names and every other component looks them up, so the
+//! names are built and parsed here rather than formatted by each binary.
+//!
+//! Under feed arbitration, the two md-handlers of the A/B lines publish to the
+//! line rings of each raw ring, e.g. `TRADE_3_LA` and `TRADE_3_LB`, the arbiter
+//! forwarding the first copy of each event to `TRADE_3_PS`.
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::RingNameError;
+
+/// The messages carried by a per-symbol ring.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum RingKind {
+    /// The best bid/ask updates of the `top` feed.
+    Top,
+    /// The trades of the `trade` feed.
+    Trade,
+    /// The aggregated trades of the `aggtrade` feed.
+    AggTrade,
+    /// The order book diffs of the `depth` feed.
+    Depth,
+    /// The rolling trade statistics.
+    Stats,
+    /// The candles.
+    Kline,
+    /// The derived microstructure signals.
+    Signal,
+}
+
+impl RingKind {
+    /// Every ring kind.
+    pub const ALL: [RingKind; 7] = [
+        RingKind::Top,
+        RingKind::Trade,
+        RingKind::AggTrade,
+        RingKind::Depth,
+        RingKind::Stats,
+        RingKind::Kline,
+        RingKind::Signal,
+    ];
+
+    /// Returns the kind of the name of a ring (e.g. `TRADE`).
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RingKind::Top => "TOP",
+            RingKind::Trade => "TRADE",
+            RingKind::AggTrade => "AGGTRADE",
+            RingKind::Depth => "DEPTH",
+            RingKind::Stats => "STATS",
+            RingKind::Kline => "KLINE",
+            RingKind::Signal => "SIGNAL",
+        }
+    }
+
+    /// Returns the kind of the raw ring of a feed kind of the configuration
+    /// (e.g. `trade`), `None` for the feed kinds without rings.
+    pub fn from_feed_kind(kind: &str) -> Option<Self> {
+        match kind {
+            "top" => Some(RingKind::Top),
+            "trade" => Some(RingKind::Trade),
+            "aggtrade" => Some(RingKind::AggTrade),
+            "depth" => Some(RingKind::Depth),
+            _ => None,
+        }
+    }
+
+    /// Returns true if the ring carries the raw messages of a feed.
+    pub fn is_feed(&self) -> bool {
+        matches!(self, RingKind::Top | RingKind::Trade | RingKind::AggTrade | RingKind::Depth)
+    }
+}
+
+impl fmt::Display for RingKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for RingKind {
+    type Err = RingNameError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        RingKind::ALL
+            .into_iter()
+            .find(|kind| kind.as_str() == s)
+            .ok_or_else(|| RingNameError::UnknownKind(s.to_string()))
+    }
+}
+
+/// The type of a per-symbol ring.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum RingSuffix {
+    /// A pub-sub ring, every consumer reading every message.
+    PubSub,
+    /// The ring of the A line of an arbitrated feed, read by the arbiter.
+    LineA,
+    /// The ring of the B line of an arbitrated feed, read by the arbiter.
+    LineB,
+}
+
+impl RingSuffix {
+    /// Every ring suffix.
+    pub const ALL: [RingSuffix; 3] = [RingSuffix::PubSub, RingSuffix::LineA, RingSuffix::LineB];
+
+    /// Returns the suffix of the name of a ring (e.g. `PS`).
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RingSuffix::PubSub => "PS",
+            RingSuffix::LineA => "LA",
+            RingSuffix::LineB => "LB",
+        }
+    }
+
+    /// Returns the line of a line ring, `None` for the pub-sub rings.
+    pub fn line(&self) -> Option<Line> {
+        match self {
+            RingSuffix::PubSub => None,
+            RingSuffix::LineA => Some(Line::A),
+            RingSuffix::LineB => Some(Line::B),
+        }
+    }
+}
+
+impl fmt::Display for RingSuffix {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for RingSuffix {
+    type Err = RingNameError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        RingSuffix::ALL
+            .into_iter()
+            .find(|suffix| suffix.as_str() == s)
+            .ok_or_else(|| RingNameError::UnknownSuffix(s.to_string()))
+    }
+}
+
+/// A line of an arbitrated feed, published by its own md-handler.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Line {
+    /// The A line.
+    A,
+    /// The B line.
+    B,
+}
+
+impl Line {
+    /// Every line.
+    pub const ALL: [Line; 2] = [Line::A, Line::B];
+
+    /// Returns the name of the line (e.g. `a`).
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Line::A => "a",
+            Line::B => "b",
+        }
+    }
+
+    /// Returns the index of the line, from zero.
+    pub fn index(&self) -> usize {
+        *self as usize
+    }
+
+    /// Returns the other line.
+    pub fn other(&self) -> Line {
+        match self {
+            Line::A => Line::B,
+            Line::B => Line::A,
+        }
+    }
+
+    /// Returns the suffix of the rings of the line.
+    pub fn suffix(&self) -> RingSuffix {
+        match self {
+            Line::A => RingSuffix::LineA,
+            Line::B => RingSuffix::LineB,
+        }
+    }
+}
+
+impl fmt::Display for Line {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Line {
+    type Err = RingNameError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Line::ALL
+            .into_iter()
+            .find(|line| line.as_str().eq_ignore_ascii_case(s))
+            .ok_or_else(|| RingNameError::UnknownLine(s.to_string()))
+    }
+}
+
+/// The name of a per-symbol ring, `{KIND}_{symbol_id}_{suffix}`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct RingName {
+    /// The messages carried by the ring.
+    pub kind: RingKind,
+    /// The symbol ID of the symbol info table the ring is named after.
+    pub symbol_id: u32,
+    /// The type of the ring.
+    pub suffix: RingSuffix,
+}
+
+impl RingName {
+    /// Returns the name of the pub-sub ring of a kind for a symbol ID.
+    pub fn pubsub(kind: RingKind, symbol_id: u32) -> Self {
+        Self { kind, symbol_id, suffix: RingSuffix::PubSub }
+    }
+
+    /// Returns the name of the pub-sub ring of a feed kind of the configuration
+    /// (e.g. `trade`) for a symbol ID.
+    ///
+    /// # Errors
+    /// Returns an error if the feed kind has no rings.
+    pub fn feed(kind: &str, symbol_id: u32) -> Result<Self, RingNameError> {
+        let kind = RingKind::from_feed_kind(kind).ok_or_else(|| RingNameError::UnknownKind(kind.to_string()))?;
+        Ok(Self::pubsub(kind, symbol_id))
+    }
+
+    /// Returns the name of the ring of a line, published by its md-handler in
+    /// place of this pub-sub ring.
+    pub fn on_line(self, line: Line) -> Self {
+        Self { suffix: line.suffix(), ..self }
+    }
+
+    /// Returns the name of the pub-sub ring a line ring is arbitrated to.
+    pub fn to_pubsub(self) -> Self {
+        Self { suffix: RingSuffix::PubSub, ..self }
+    }
+}
+
+impl fmt::Display for RingName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}_{}_{}", self.kind, self.symbol_id, self.suffix)
+    }
+}
+
+impl FromStr for RingName {
+    type Err = RingNameError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.split('_');
+        let (Some(kind), Some(symbol_id), Some(suffix), None) = (parts.next(), parts.next(), parts.next(), parts.next())
+        else {
+            return Err(RingNameError::InvalidFormat(s.to_string()));
+        };
+        let symbol_id = symbol_id
+            .parse()
+            .ok()
+            .filter(|_| symbol_id.bytes().all(|b| b.is_ascii_digit()))
+            .ok_or_else(|| RingNameError::InvalidFormat(s.to_string()))?;
+        Ok(Self { kind: kind.parse()?, symbol_id, suffix: suffix.parse()? })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_and_parse() {
+        let name = RingName::pubsub(RingKind::Trade, 3);
+        assert_eq!(name.to_string(), "TRADE_3_PS");
+        assert_eq!("TRADE_3_PS".parse::<RingName>().unwrap(), name);
+        assert_eq!(RingName::feed("aggtrade", 12).unwrap().to_string(), "AGGTRADE_12_PS");
+        assert_eq!(RingName::feed("ticker", 0), Err(RingNameError::UnknownKind("ticker".to_string())));
+
+        for kind in RingKind::ALL {
+            let name = RingName::pubsub(kind, 7);
+            assert_eq!(name.to_string().parse::<RingName>().unwrap(), name);
+        }
+    }
+
+    #[test]
+    fn test_parse_invalid_names() {
+        for s in ["TRADE_3", "TRADE_3_PS_X", "TRADE__PS", "TRADE_+3_PS", "TRADE_x_PS", "ALERTS_PS"] {
+            assert!(matches!(s.parse::<RingName>(), Err(RingNameError::InvalidFormat(_))), "{}", s);
+        }
+        assert_eq!("TICKER_3_PS".parse::<RingName>(), Err(RingNameError::UnknownKind("TICKER".to_string())));
+        assert_eq!("TOP_3_MP".parse::<RingName>(), Err(RingNameError::UnknownSuffix("MP".to_string())));
+    }
+
+    #[test]
+    fn test_line_rings() {
+        let name = RingName::pubsub(RingKind::Top, 3);
+        assert_eq!(name.on_line(Line::A).to_string(), "TOP_3_LA");
+        assert_eq!(name.on_line(Line::B).to_string(), "TOP_3_LB");
+        let line_b = "TOP_3_LB".parse::<RingName>().unwrap();
+        assert_eq!(line_b.suffix.line(), Some(Line::B));
+        assert_eq!(line_b.to_pubsub(), name);
+        assert_eq!(name.suffix.line(), None);
+
+        assert_eq!("B".parse::<Line>(), Ok(Line::B));
+        assert_eq!(Line::A.other(), Line::B);
+        assert_eq!("c".parse::<Line>(), Err(RingNameError::UnknownLine("c".to_string())));
+    }
+
+    #[test]
+    fn test_feed_kinds() {
+        assert_eq!(RingKind::from_feed_kind("top"), Some(RingKind::Top));
+        assert!(RingKind::Trade.is_feed());
+        assert_eq!(RingKind::from_feed_kind("depth"), Some(RingKind::Depth));
+        assert!(RingKind::Depth.is_feed());
+        assert!(!RingKind::Kline.is_feed());
+        assert!(!RingKind::Signal.is_feed());
+        assert_eq!(RingKind::from_feed_kind("kline"), None);
+    }
+}
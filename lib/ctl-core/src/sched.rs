@@ -0,0 +1,228 @@
+//! Real-time scheduling and memory locking of the component threads.
+//!
+//! DPDK pins the lcore threads, but the other threads of a component (the log
+//! writer, the EAL control threads) float over every CPU and compete with the
+//! rest of the host, and a page fault on first touch or after reclaim stalls
+//! any thread. The scheduling policy, the memory locking and the CPU affinity of
+//! these threads are selected per component in `configs/scheduling.yaml`.
+
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::mem;
+use std::path::Path;
+
+use serde::Deserialize;
+
+use crate::{parse_cpu_list, Capability, SchedulingConfigError, SchedulingError};
+
+/// The threads of this process.
+const TASKS_DIR: &str = "/proc/self/task";
+
+/// The scheduling of the threads of a component, the defaults leaving them as
+/// the kernel started them.
+#[derive(Debug, Clone, Default, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct SchedulingPolicy {
+    /// The `SCHED_FIFO` priority of the threads, 1 to 99, unset keeping the
+    /// default time-sharing policy.
+    pub fifo_priority: Option<i32>,
+    /// Locks the current and future memory of the process in RAM.
+    pub mlockall: bool,
+    /// The CPUs of the threads as a CPU list (e.g. `0,16-17`), unset keeping
+    /// the inherited affinity.
+    pub affinity: Option<String>,
+}
+
+impl SchedulingPolicy {
+    /// Validates the policy configuration.
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(priority) = self.fifo_priority {
+            if !(1..=99).contains(&priority) {
+                return Err(format!("'fifo_priority' must be between 1 and 99, got {}", priority));
+            }
+        }
+        if let Some(affinity) = &self.affinity {
+            if self.affinity_cpus().is_none_or(|cpus| cpus.is_empty()) {
+                return Err(format!("'affinity' must be a non-empty CPU list, got '{}'", affinity));
+            }
+            if self.affinity_cpus().is_some_and(|cpus| cpus.iter().any(|&cpu| cpu >= libc::CPU_SETSIZE as usize)) {
+                return Err(format!("'affinity' CPUs must be below {}, got '{}'", libc::CPU_SETSIZE, affinity));
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the CPUs of the affinity, `None` if unset or invalid.
+    pub fn affinity_cpus(&self) -> Option<Vec<usize>> {
+        self.affinity.as_deref().and_then(parse_cpu_list)
+    }
+
+    /// Returns the capabilities needed to apply the policy, for the preflight.
+    pub fn capabilities(&self) -> Vec<Capability> {
+        let mut capabilities = Vec::new();
+        if self.fifo_priority.is_some() {
+            capabilities.push(Capability::SysNice);
+        }
+        if self.mlockall {
+            capabilities.push(Capability::IpcLock);
+        }
+        capabilities
+    }
+
+    /// Applies the policy to every current thread of the process, the threads
+    /// created later inheriting it.
+    ///
+    /// Call it before the EAL initialization: the EAL re-pins the lcore
+    /// threads, and derives the affinity of its control threads from the
+    /// affinity of the process.
+    ///
+    /// LATENCY: SLOW_PATH
+    ///
+    /// # Errors
+    /// Returns an error if the threads can't be listed or a setting is refused,
+    /// usually for lack of `cap_sys_nice` or `cap_ipc_lock`.
+    pub fn apply(&self) -> Result<(), SchedulingError> {
+        if self.mlockall {
+            // SAFETY: mlockall has no memory-safety preconditions.
+            if unsafe { libc::mlockall(libc::MCL_CURRENT | libc::MCL_FUTURE) } != 0 {
+                return Err(SchedulingError::MemoryLock(io::Error::last_os_error()));
+            }
+        }
+        if self.fifo_priority.is_none() && self.affinity.is_none() {
+            return Ok(());
+        }
+
+        for tid in thread_ids()? {
+            if let Some(cpus) = self.affinity_cpus() {
+                set_affinity(tid, &cpus).map_err(|source| SchedulingError::Affinity { tid, source })?;
+            }
+            if let Some(priority) = self.fifo_priority {
+                set_fifo(tid, priority).map_err(|source| SchedulingError::Priority { tid, priority, source })?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Returns the IDs of the threads of this process.
+fn thread_ids() -> Result<Vec<libc::pid_t>, SchedulingError> {
+    let read_error = |source| SchedulingError::ReadError { path: Path::new(TASKS_DIR).to_path_buf(), source };
+    let mut tids = Vec::new();
+    for entry in fs::read_dir(TASKS_DIR).map_err(read_error)? {
+        let entry = entry.map_err(read_error)?;
+        if let Some(tid) = entry.file_name().to_str().and_then(|name| name.parse().ok()) {
+            tids.push(tid);
+        }
+    }
+    Ok(tids)
+}
+
+/// Restricts a thread to CPUs.
+fn set_affinity(tid: libc::pid_t, cpus: &[usize]) -> io::Result<()> {
+    // SAFETY: cpu_set_t is plain data, valid when zeroed.
+    let mut set: libc::cpu_set_t = unsafe { mem::zeroed() };
+    for &cpu in cpus {
+        // SAFETY: the CPUs are below CPU_SETSIZE, checked by the validation.
+        unsafe { libc::CPU_SET(cpu, &mut set) };
+    }
+    // SAFETY: `set` is a valid cpu_set_t of the given size.
+    if unsafe { libc::sched_setaffinity(tid, mem::size_of::<libc::cpu_set_t>(), &set) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// Schedules a thread with `SCHED_FIFO` at a priority.
+fn set_fifo(tid: libc::pid_t, priority: i32) -> io::Result<()> {
+    let param = libc::sched_param { sched_priority: priority };
+    // SAFETY: `param` is a valid sched_param for the call.
+    if unsafe { libc::sched_setscheduler(tid, libc::SCHED_FIFO, &param) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// The scheduling policies of the components, by component name.
+#[derive(Debug, Clone, Default, Deserialize, PartialEq, Eq)]
+#[serde(transparent)]
+pub struct SchedulingConfig {
+    components: HashMap<String, SchedulingPolicy>,
+}
+
+impl SchedulingConfig {
+    /// Parses the scheduling configuration from a YAML file.
+    ///
+    /// # Errors
+    /// Returns an error if the file cannot be read, parsed, or fails validation.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, SchedulingConfigError> {
+        let content = fs::read_to_string(path)?;
+        Self::from_str(&content)
+    }
+
+    /// Parses the scheduling configuration from a YAML string.
+    ///
+    /// # Errors
+    /// Returns an error if the YAML cannot be parsed or fails validation.
+    pub fn from_str(content: &str) -> Result<Self, SchedulingConfigError> {
+        let config: Self = serde_yaml::from_str(content)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Validates the scheduling configuration.
+    fn validate(&self) -> Result<(), SchedulingConfigError> {
+        for (component, policy) in &self.components {
+            policy.validate().map_err(|e| {
+                SchedulingConfigError::ValidationError(format!("Component '{}': {}", component, e))
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Returns the policy of a component, the default leaving its threads
+    /// untouched if it isn't configured.
+    pub fn policy(&self, component: &str) -> SchedulingPolicy {
+        self.components.get(component).cloned().unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_config() {
+        let config = SchedulingConfig::from_str(
+            r#"
+md-handler:
+  fifo_priority: 50
+  affinity: 0,16-17
+md-subscriber:
+  mlockall: true
+"#,
+        )
+        .unwrap();
+        let policy = config.policy("md-handler");
+        assert_eq!(policy.fifo_priority, Some(50));
+        assert_eq!(policy.affinity_cpus(), Some(vec![0, 16, 17]));
+        assert_eq!(policy.capabilities(), vec![Capability::SysNice]);
+        assert_eq!(config.policy("md-subscriber").capabilities(), vec![Capability::IpcLock]);
+        assert_eq!(config.policy("trade-stats"), SchedulingPolicy::default());
+    }
+
+    #[test]
+    fn test_invalid_policies() {
+        assert!(SchedulingConfig::from_str("a:\n  fifo_priority: 0\n").is_err());
+        assert!(SchedulingConfig::from_str("a:\n  fifo_priority: 100\n").is_err());
+        assert!(SchedulingConfig::from_str("a:\n  affinity: 3-1\n").is_err());
+        assert!(SchedulingConfig::from_str("a:\n  affinity: '4096'\n").is_err());
+        let result = SchedulingConfig::from_str("a:\n  affinity: ''\n");
+        assert!(result.unwrap_err().to_string().contains("Component 'a'"));
+    }
+
+    #[test]
+    fn test_apply_default_policy() {
+        assert!(SchedulingPolicy::default().apply().is_ok());
+    }
+}
@@ -0,0 +1,328 @@
+//! The status table shared by the components.
+//!
+//! The status region, created by ctl-resource-manager, holds the trading state
+//! every component checks: the halted flag is set by the kill switch before the
+//! HALT command is broadcast, so a component that misses the command (e.g. one
+//! started afterwards) still sees the halt. The degraded flag is set by the OMS
+//! while its WS API session is down, the cancels falling back to the REST API.
+//! The tripped flag is set by a circuit breaker along with the halt, and only
+//! cleared by an operator.
+//!
+//! The region also carries the run epoch of ctl-resource-manager, stamped once
+//! the regions and rings of its run are created. A restarted resource manager
+//! creates them anew, leaving the secondaries attached to the previous run with
+//! stale mappings: they record the epoch they attached to in an `EpochWatch`,
+//! and check it against the region found by name to stop using them. The
+//! heartbeat of the resource manager is watched by a standby instance, taking
+//! over once it stops.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use ctl_shm::{ShmRegion, ShmSafe};
+use ctl_time::now_ns;
+use serde::Serialize;
+
+use crate::{BreakerSignal, EpochError};
+
+/// Name of the status region.
+pub const STATUS_REGION_NAME: &str = "ctl_status";
+
+/// The trading state shared by the components.
+#[repr(C, align(64))]
+#[derive(Debug, Default)]
+pub struct StatusRegion {
+    /// Non-zero while trading is halted.
+    halted: AtomicU64,
+    /// The time of the last halt, in milliseconds since the epoch.
+    halted_at_ms: AtomicU64,
+    /// Number of halts since the region was created.
+    halt_count: AtomicU64,
+    /// Non-zero while order entry is degraded to the REST fallback.
+    degraded: AtomicU64,
+    /// The time order entry was last degraded, in milliseconds since the epoch.
+    degraded_at_ms: AtomicU64,
+    /// Non-zero while a circuit breaker is tripped, until reset by an operator.
+    tripped: AtomicU64,
+    /// The signal that last tripped a breaker, stored as a `u8`.
+    tripped_by: AtomicU64,
+    /// The time a breaker last tripped, in milliseconds since the epoch.
+    tripped_at_ms: AtomicU64,
+    /// The run epoch of the resource manager, zero until its run is set up.
+    run_epoch: AtomicU64,
+    /// The time the run started, in milliseconds since the epoch.
+    started_at_ms: AtomicU64,
+    /// The last beat of the resource manager, in milliseconds since the epoch.
+    heartbeat_ms: AtomicU64,
+}
+
+// SAFETY: `StatusRegion` is `repr(C)`, made only of atomics and valid when zeroed.
+unsafe impl ShmSafe for StatusRegion {}
+
+impl StatusRegion {
+    /// Halts trading, returning false if it was already halted.
+    pub fn halt(&self, now_ms: u64) -> bool {
+        if self.halted.swap(1, Ordering::AcqRel) != 0 {
+            return false;
+        }
+        self.halted_at_ms.store(now_ms, Ordering::Release);
+        self.halt_count.fetch_add(1, Ordering::Relaxed);
+        true
+    }
+
+    /// Resumes trading, returning false if it wasn't halted.
+    pub fn resume(&self) -> bool {
+        self.halted.swap(0, Ordering::AcqRel) != 0
+    }
+
+    /// Returns true while trading is halted.
+    ///
+    /// LATENCY: FAST_PATH
+    pub fn is_halted(&self) -> bool {
+        self.halted.load(Ordering::Acquire) != 0
+    }
+
+    /// Returns the time of the last halt, zero if never halted.
+    pub fn halted_at_ms(&self) -> u64 {
+        self.halted_at_ms.load(Ordering::Acquire)
+    }
+
+    /// Returns the number of halts.
+    pub fn halt_count(&self) -> u64 {
+        self.halt_count.load(Ordering::Relaxed)
+    }
+
+    /// Sets or clears the degraded flag, returning false if it was already in that state.
+    pub fn set_degraded(&self, degraded: bool, now_ms: u64) -> bool {
+        if (self.degraded.swap(u64::from(degraded), Ordering::AcqRel) != 0) == degraded {
+            return false;
+        }
+        if degraded {
+            self.degraded_at_ms.store(now_ms, Ordering::Release);
+        }
+        true
+    }
+
+    /// Returns true while order entry is degraded to the REST fallback.
+    pub fn is_degraded(&self) -> bool {
+        self.degraded.load(Ordering::Acquire) != 0
+    }
+
+    /// Returns the time order entry was last degraded, zero if never.
+    pub fn degraded_at_ms(&self) -> u64 {
+        self.degraded_at_ms.load(Ordering::Acquire)
+    }
+
+    /// Trips the circuit breaker, halting trading, returning false if it was already tripped.
+    pub fn trip(&self, signal: BreakerSignal, now_ms: u64) -> bool {
+        if self.tripped.swap(1, Ordering::AcqRel) != 0 {
+            return false;
+        }
+        self.tripped_by.store(signal as u64, Ordering::Release);
+        self.tripped_at_ms.store(now_ms, Ordering::Release);
+        self.halt(now_ms);
+        true
+    }
+
+    /// Resets the circuit breaker, returning false if it wasn't tripped.
+    ///
+    /// Trading stays halted until resumed.
+    pub fn reset_breaker(&self) -> bool {
+        self.tripped.swap(0, Ordering::AcqRel) != 0
+    }
+
+    /// Returns true while the circuit breaker is tripped.
+    ///
+    /// LATENCY: FAST_PATH
+    pub fn is_tripped(&self) -> bool {
+        self.tripped.load(Ordering::Acquire) != 0
+    }
+
+    /// Returns the signal that last tripped the breaker, unknown if never tripped.
+    pub fn tripped_by(&self) -> BreakerSignal {
+        BreakerSignal::from_u8(self.tripped_by.load(Ordering::Acquire) as u8)
+    }
+
+    /// Returns the time the breaker last tripped, zero if never.
+    pub fn tripped_at_ms(&self) -> u64 {
+        self.tripped_at_ms.load(Ordering::Acquire)
+    }
+
+    /// Stamps the run epoch of the resource manager, once its regions and rings are created.
+    pub fn start_run(&self, epoch: u64, now_ms: u64) {
+        self.started_at_ms.store(now_ms, Ordering::Release);
+        self.run_epoch.store(epoch, Ordering::Release);
+    }
+
+    /// Returns the run epoch, zero while the run is being set up.
+    pub fn run_epoch(&self) -> u64 {
+        self.run_epoch.load(Ordering::Acquire)
+    }
+
+    /// Returns the time the run started, zero while it is being set up.
+    pub fn started_at_ms(&self) -> u64 {
+        self.started_at_ms.load(Ordering::Acquire)
+    }
+
+    /// Records a beat of the resource manager, watched by a standby instance.
+    pub fn beat(&self, now_ms: u64) {
+        self.heartbeat_ms.store(now_ms, Ordering::Release);
+    }
+
+    /// Returns the time of the last beat of the resource manager, zero if it never beat.
+    pub fn heartbeat_ms(&self) -> u64 {
+        self.heartbeat_ms.load(Ordering::Acquire)
+    }
+
+    /// Returns the trading state, e.g. for a status report.
+    pub fn snapshot(&self) -> TradingStatus {
+        let tripped = self.is_tripped();
+        TradingStatus {
+            halted: self.is_halted(),
+            degraded: self.is_degraded(),
+            tripped,
+            tripped_by: tripped.then(|| self.tripped_by()),
+        }
+    }
+}
+
+/// The trading state read from the status table.
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+pub struct TradingStatus {
+    /// Whether trading is halted.
+    pub halted: bool,
+    /// Whether the OMS falls back to the REST API.
+    pub degraded: bool,
+    /// Whether a circuit breaker tripped.
+    pub tripped: bool,
+    /// The signal that tripped the breaker, while tripped.
+    pub tripped_by: Option<BreakerSignal>,
+}
+
+/// Returns a new run epoch, the start time of the run in nanoseconds, never zero.
+pub fn new_run_epoch() -> u64 {
+    now_ns().max(1)
+}
+
+/// The run of the resource manager a secondary attached to.
+#[derive(Debug, Clone)]
+pub struct EpochWatch {
+    /// The name of the status region.
+    region: String,
+    /// The run epoch attached to.
+    attached: u64,
+}
+
+impl EpochWatch {
+    /// Records the run epoch of the status region `region` mapped as `status`.
+    ///
+    /// # Errors
+    /// Returns an error if the resource manager hasn't stamped its run yet.
+    pub fn attach(region: &str, status: &StatusRegion) -> Result<Self, EpochError> {
+        match status.run_epoch() {
+            0 => Err(EpochError::NotStarted),
+            attached => Ok(Self { region: region.to_string(), attached }),
+        }
+    }
+
+    /// Returns the run epoch attached to.
+    pub fn attached(&self) -> u64 {
+        self.attached
+    }
+
+    /// Checks that the status region found by name is still of the run attached to.
+    ///
+    /// The mapping of the secondary keeps the region of its run even once the
+    /// resource manager recreated it, so the region is opened again by name.
+    ///
+    /// LATENCY: SLOW_PATH
+    ///
+    /// # Errors
+    /// Returns an error if the resource manager restarted, or exited.
+    pub fn check(&self) -> Result<(), EpochError> {
+        let current = ShmRegion::<StatusRegion>::open(&self.region).map_err(EpochError::Gone)?.run_epoch();
+        if current != self.attached {
+            return Err(EpochError::Restarted { attached: self.attached, current });
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_halt_and_resume() {
+        let status = StatusRegion::default();
+        assert!(!status.is_halted());
+        assert!(!status.resume());
+
+        assert!(status.halt(1_000));
+        assert!(!status.halt(2_000));
+        assert!(status.is_halted());
+        assert_eq!(status.halted_at_ms(), 1_000);
+
+        assert!(status.resume());
+        assert!(!status.is_halted());
+        assert!(status.halt(3_000));
+        assert_eq!(status.halt_count(), 2);
+    }
+
+    #[test]
+    fn test_degraded_flag() {
+        let status = StatusRegion::default();
+        assert!(!status.is_degraded());
+        assert!(!status.set_degraded(false, 1_000));
+
+        assert!(status.set_degraded(true, 2_000));
+        assert!(!status.set_degraded(true, 3_000));
+        assert!(status.is_degraded());
+        assert_eq!(status.degraded_at_ms(), 2_000);
+
+        assert!(status.set_degraded(false, 4_000));
+        assert!(!status.is_degraded());
+    }
+
+    #[test]
+    fn test_trip_and_reset() {
+        let status = StatusRegion::default();
+        assert!(!status.is_tripped());
+        assert!(!status.reset_breaker());
+
+        assert!(status.trip(BreakerSignal::ParseError, 1_000));
+        assert!(!status.trip(BreakerSignal::RateLimitBan, 2_000));
+        assert!(status.is_tripped() && status.is_halted());
+        assert_eq!((status.tripped_by(), status.tripped_at_ms()), (BreakerSignal::ParseError, 1_000));
+
+        let snapshot = status.snapshot();
+        assert_eq!((snapshot.halted, snapshot.tripped_by), (true, Some(BreakerSignal::ParseError)));
+
+        // Resetting the breaker doesn't resume trading
+        assert!(status.reset_breaker());
+        assert!(!status.is_tripped() && status.is_halted());
+        assert_eq!(status.snapshot().tripped_by, None);
+    }
+
+    #[test]
+    fn test_epoch_watch() {
+        let name = format!("ctl_status_epoch_test_{}", std::process::id());
+        let status = ShmRegion::<StatusRegion>::create(&name).unwrap();
+        assert!(matches!(EpochWatch::attach(&name, &status), Err(EpochError::NotStarted)));
+
+        let epoch = new_run_epoch();
+        status.start_run(epoch, 1_000);
+        let watch = EpochWatch::attach(&name, &status).unwrap();
+        assert_eq!((watch.attached(), status.started_at_ms()), (epoch, 1_000));
+        assert!(watch.check().is_ok());
+
+        // A restarted resource manager recreates the region, unstamped until its run is set up
+        let restarted = ShmRegion::<StatusRegion>::create(&name).unwrap();
+        assert!(matches!(watch.check(), Err(EpochError::Restarted { current: 0, .. })));
+        restarted.start_run(epoch + 1, 2_000);
+        assert!(matches!(watch.check(), Err(EpochError::Restarted { current, .. }) if current == epoch + 1));
+        assert_eq!(status.run_epoch(), epoch);
+
+        drop(restarted);
+        assert!(matches!(watch.check(), Err(EpochError::Gone(_))));
+    }
+}
@@ -0,0 +1,16 @@
+//! Zero-padded UTF-8 strings of the fixed-size ring messages.
+
+/// Copies `s` into `buf`, truncated on a char boundary to the buffer size.
+pub fn write_padded(buf: &mut [u8], s: &str) {
+    let mut len = s.len().min(buf.len());
+    while !s.is_char_boundary(len) {
+        len -= 1;
+    }
+    buf[..len].copy_from_slice(&s.as_bytes()[..len]);
+}
+
+/// Returns the string of a zero-padded buffer.
+pub fn read_padded(buf: &[u8]) -> &str {
+    let len = buf.iter().position(|&b| b == 0).unwrap_or(buf.len());
+    std::str::from_utf8(&buf[..len]).unwrap_or_default()
+}
@@ -0,0 +1,226 @@
+//! CPU topology of the host.
+//!
+//! Two hyperthreads of a physical core share its execution units and caches,
+//! so two busy-polling loops on SMT siblings slow each other down as much as
+//! sharing an lcore would, and a loop reading a ring allocated on a remote
+//! NUMA node pays the interconnect on every message. The topology is read from
+//! sysfs to check the lcores claimed by the components against it.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use crate::{parse_cpu_list, LcoreClaim, LcoreUse};
+
+/// The CPU directories of the host.
+const CPU_SYSFS_DIR: &str = "/sys/devices/system/cpu";
+/// The NUMA node directories of the host, missing without NUMA.
+const NODE_SYSFS_DIR: &str = "/sys/devices/system/node";
+
+/// The physical cores and NUMA nodes of the CPUs.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CpuTopology {
+    /// The physical core of each CPU, as its lowest SMT sibling.
+    cores: HashMap<u32, u32>,
+    /// The NUMA node of each CPU.
+    nodes: HashMap<u32, u32>,
+}
+
+impl CpuTopology {
+    /// Reads the topology of the online CPUs from sysfs.
+    ///
+    /// The CPUs without a readable topology are left out, every CPU being its
+    /// own core on node 0 for the checks.
+    pub fn read() -> Self {
+        let mut topology = Self::default();
+        let online = fs::read_to_string(Path::new(CPU_SYSFS_DIR).join("online"))
+            .ok()
+            .and_then(|list| parse_cpu_list(&list))
+            .unwrap_or_default();
+        for cpu in online {
+            let siblings = Path::new(CPU_SYSFS_DIR).join(format!("cpu{}/topology/thread_siblings_list", cpu));
+            if let Some(siblings) = fs::read_to_string(siblings).ok().and_then(|list| parse_cpu_list(&list)) {
+                topology.add_core(&siblings.into_iter().map(|cpu| cpu as u32).collect::<Vec<_>>());
+            }
+        }
+
+        let Ok(entries) = fs::read_dir(NODE_SYSFS_DIR) else {
+            return topology;
+        };
+        for entry in entries.flatten() {
+            let name = entry.file_name();
+            let Some(node) = name.to_str().and_then(|name| name.strip_prefix("node")?.parse().ok()) else {
+                continue;
+            };
+            let cpus = fs::read_to_string(entry.path().join("cpulist")).ok().and_then(|list| parse_cpu_list(&list));
+            if let Some(cpus) = cpus {
+                topology.add_node(node, &cpus.into_iter().map(|cpu| cpu as u32).collect::<Vec<_>>());
+            }
+        }
+        topology
+    }
+
+    /// Adds a physical core of SMT siblings.
+    pub fn add_core(&mut self, siblings: &[u32]) -> &mut Self {
+        if let Some(&core) = siblings.iter().min() {
+            self.cores.extend(siblings.iter().map(|&cpu| (cpu, core)));
+        }
+        self
+    }
+
+    /// Adds the CPUs of a NUMA node.
+    pub fn add_node(&mut self, node: u32, cpus: &[u32]) -> &mut Self {
+        self.nodes.extend(cpus.iter().map(|&cpu| (cpu, node)));
+        self
+    }
+
+    /// Returns true if two distinct CPUs are SMT siblings of a physical core.
+    pub fn are_siblings(&self, a: u32, b: u32) -> bool {
+        a != b && self.cores.get(&a).is_some_and(|core| self.cores.get(&b) == Some(core))
+    }
+
+    /// Returns the NUMA node of a CPU, 0 without NUMA.
+    pub fn node(&self, cpu: u32) -> u32 {
+        self.nodes.get(&cpu).copied().unwrap_or(0)
+    }
+
+    /// Returns the number of NUMA nodes, 1 without NUMA.
+    pub fn node_count(&self) -> usize {
+        let mut nodes: Vec<_> = self.nodes.values().collect();
+        nodes.sort();
+        nodes.dedup();
+        nodes.len().max(1)
+    }
+}
+
+/// A latency-critical lcore placed badly on the CPU topology.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TopologyIssue {
+    /// Two exclusive lcores are SMT siblings of a physical core.
+    SharedCore {
+        /// The first claim, as `component role`.
+        first: String,
+        /// Its lcore.
+        first_lcore: u32,
+        /// The second claim, as `component role`.
+        second: String,
+        /// Its lcore.
+        second_lcore: u32,
+    },
+    /// An exclusive lcore is on another NUMA node than the rings.
+    RemoteNode {
+        /// The claim, as `component role`.
+        claim: String,
+        /// The lcore.
+        lcore: u32,
+        /// The NUMA node of the lcore.
+        node: u32,
+        /// The NUMA node of the rings.
+        ring_node: u32,
+    },
+}
+
+impl fmt::Display for TopologyIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TopologyIssue::SharedCore { first, first_lcore, second, second_lcore } => write!(
+                f,
+                "{} (lcore {}) and {} (lcore {}) share a physical core",
+                first, first_lcore, second, second_lcore
+            ),
+            TopologyIssue::RemoteNode { claim, lcore, node, ring_node } => write!(
+                f,
+                "{} (lcore {}) is on NUMA node {}, the rings on node {}",
+                claim, lcore, node, ring_node
+            ),
+        }
+    }
+}
+
+/// Checks the exclusive lcores of the claims against the CPU topology, the
+/// rings being allocated on `ring_node`.
+pub fn check_topology(claims: &[LcoreClaim], topology: &CpuTopology, ring_node: u32) -> Vec<TopologyIssue> {
+    let exclusive: Vec<(String, u32)> = claims
+        .iter()
+        .filter(|claim| claim.usage == LcoreUse::Exclusive)
+        .flat_map(|claim| claim.lcores.iter().map(move |&lcore| (claim.to_string(), lcore)))
+        .collect();
+
+    let mut issues = Vec::new();
+    for (i, (first, first_lcore)) in exclusive.iter().enumerate() {
+        for (second, second_lcore) in &exclusive[i + 1..] {
+            if topology.are_siblings(*first_lcore, *second_lcore) {
+                issues.push(TopologyIssue::SharedCore {
+                    first: first.clone(),
+                    first_lcore: *first_lcore,
+                    second: second.clone(),
+                    second_lcore: *second_lcore,
+                });
+            }
+        }
+    }
+    for (claim, lcore) in &exclusive {
+        let node = topology.node(*lcore);
+        if node != ring_node {
+            issues.push(TopologyIssue::RemoteNode { claim: claim.clone(), lcore: *lcore, node, ring_node });
+        }
+    }
+    issues
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::LcorePlanner;
+
+    /// Two nodes of two cores of two hyperthreads, the siblings numbered apart.
+    fn topology() -> CpuTopology {
+        let mut topology = CpuTopology::default();
+        topology
+            .add_core(&[0, 4])
+            .add_core(&[1, 5])
+            .add_core(&[2, 6])
+            .add_core(&[3, 7])
+            .add_node(0, &[0, 1, 4, 5])
+            .add_node(1, &[2, 3, 6, 7]);
+        topology
+    }
+
+    #[test]
+    fn test_topology() {
+        let topology = topology();
+        assert!(topology.are_siblings(1, 5));
+        assert!(!topology.are_siblings(1, 2));
+        assert!(!topology.are_siblings(1, 1));
+        assert_eq!(topology.node(6), 1);
+        assert_eq!(topology.node_count(), 2);
+        assert_eq!(CpuTopology::default().node_count(), 1);
+    }
+
+    #[test]
+    fn test_check_topology() {
+        let mut planner = LcorePlanner::new();
+        planner
+            .claim("md-handler", "main", vec![0], LcoreUse::Shared)
+            .claim("md-handler", "workers top", vec![1], LcoreUse::Exclusive)
+            .claim_movable("md-subscriber", "main", 5, LcoreUse::Exclusive)
+            .claim_movable("ctl-admin", "main", 4, LcoreUse::Shared)
+            .claim_movable("trade-stats", "main", 2, LcoreUse::Exclusive);
+
+        let issues = check_topology(planner.claims(), &topology(), 0);
+        assert_eq!(
+            issues,
+            vec![
+                TopologyIssue::SharedCore {
+                    first: "md-handler workers top".to_string(),
+                    first_lcore: 1,
+                    second: "md-subscriber main".to_string(),
+                    second_lcore: 5,
+                },
+                TopologyIssue::RemoteNode { claim: "trade-stats main".to_string(), lcore: 2, node: 1, ring_node: 0 },
+            ]
+        );
+        assert!(issues[0].to_string().contains("share a physical core"));
+    }
+}
@@ -0,0 +1,641 @@
+//! Trace propagation through the pipeline, with an optional OTLP export.
+//!
+//! The latency histograms tell how often a stage is slow, not which message a
+//! tail-latency outlier was nor where its time went. A sampled share of the
+//! messages is stamped at the receive point with a `TraceContext`, a trace ID
+//! and the ID of the span of the stage, carried in the header of the ring
+//! messages and then along the order requests. Each stage records its span as
+//! a child of the span of the previous one: the parse up to the publish to the
+//! ring (md-handler), the consume (the subscribers), the order decision (the
+//! strategies) and the order submission (the OMS), so that a trace follows a
+//! message end to end across the processes.
+//!
+//! The spans are exported by a thread of the component to an OpenTelemetry
+//! collector over OTLP/HTTP (JSON), if configured, the hot path only handing
+//! them over a bounded channel. The sampling and the export are configured per
+//! component in `configs/telemetry.yaml`, the components not listed tracing none.
+
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::path::Path;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, SyncSender, TrySendError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use ctl_time::{monotonic_ns, now_ns};
+use serde::{Deserialize, Serialize};
+
+use crate::TelemetryConfigError;
+
+/// Default number of spans exported per request.
+pub const DEFAULT_BATCH_SIZE: usize = 256;
+
+/// Default interval between the exports of a partial batch, in milliseconds.
+pub const DEFAULT_FLUSH_INTERVAL_MS: u64 = 1_000;
+
+// Spans buffered between the hot path and the exporter, the spans past it dropped
+const SPAN_CHANNEL_CAPACITY: usize = 4096;
+
+// Time given to the collector to accept the connection and answer an export
+const EXPORT_TIMEOUT: Duration = Duration::from_secs(2);
+
+// The path of the traces under the OTLP/HTTP endpoint
+const OTLP_TRACES_PATH: &str = "/v1/traces";
+
+// The OTLP kind of the spans, SPAN_KIND_INTERNAL
+const SPAN_KIND_INTERNAL: u8 = 1;
+
+fn default_batch_size() -> usize {
+    DEFAULT_BATCH_SIZE
+}
+
+fn default_flush_interval_ms() -> u64 {
+    DEFAULT_FLUSH_INTERVAL_MS
+}
+
+thread_local! {
+    // The state of the span ID generator of the thread
+    static ID_STATE: Cell<u64> = Cell::new(id_seed());
+}
+
+/// Returns a seed of the span ID generator, distinct across the threads and processes.
+fn id_seed() -> u64 {
+    let local = 0u8;
+    monotonic_ns() ^ now_ns().rotate_left(21) ^ ((std::process::id() as u64) << 32) ^ (&local as *const u8 as u64)
+}
+
+/// Returns a random non-zero ID, the splitmix64 of the state of the thread.
+///
+/// LATENCY: FAST_PATH
+fn next_id() -> u64 {
+    ID_STATE.with(|state| loop {
+        let next = state.get().wrapping_add(0x9E37_79B9_7F4A_7C15);
+        state.set(next);
+        let mut z = next;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^= z >> 31;
+        if z != 0 {
+            return z;
+        }
+    })
+}
+
+/// The trace of a message and the span of the stage that last handled it,
+/// zero if the message isn't traced.
+///
+/// The trace ID is 64 bits, to fit the message header, and exported
+/// zero-extended to the 128 bits of the W3C trace context.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct TraceContext {
+    /// The trace ID, zero if untraced.
+    pub trace_id: u64,
+    /// The span ID of the stage.
+    pub span_id: u64,
+}
+
+impl TraceContext {
+    /// Returns true if the message is traced.
+    pub fn is_traced(&self) -> bool {
+        self.trace_id != 0
+    }
+
+    /// Returns the W3C `traceparent` of the context, e.g. for the logs of an order.
+    pub fn traceparent(&self) -> String {
+        format!("00-{:032x}-{:016x}-01", self.trace_id, self.span_id)
+    }
+
+    /// Parses a W3C `traceparent`, `None` if malformed or if its trace ID
+    /// doesn't fit 64 bits.
+    pub fn from_traceparent(traceparent: &str) -> Option<Self> {
+        let mut parts = traceparent.split('-');
+        let (Some("00"), Some(trace_id), Some(span_id), Some(_flags), None) =
+            (parts.next(), parts.next(), parts.next(), parts.next(), parts.next())
+        else {
+            return None;
+        };
+        if trace_id.len() != 32 || span_id.len() != 16 || trace_id[..16].bytes().any(|b| b != b'0') {
+            return None;
+        }
+        let context = Self {
+            trace_id: u64::from_str_radix(&trace_id[16..], 16).ok()?,
+            span_id: u64::from_str_radix(span_id, 16).ok()?,
+        };
+        context.is_traced().then_some(context)
+    }
+}
+
+/// A span of a stage of the pipeline.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Span {
+    /// The name of the stage, e.g. `md.parse`.
+    pub name: &'static str,
+    /// The trace and the ID of the span.
+    pub context: TraceContext,
+    /// The span ID of the previous stage, zero for the root span of the trace.
+    pub parent_span_id: u64,
+    /// The start of the stage, in nanoseconds since the epoch.
+    pub start_ns: u64,
+    /// The end of the stage, in nanoseconds since the epoch.
+    pub end_ns: u64,
+    /// The attributes of the span, e.g. the symbol ID.
+    pub attributes: Vec<(&'static str, u64)>,
+}
+
+/// The tracer of a component: samples the messages traced from their receive
+/// point, and records the spans of its stages for the exporter.
+#[derive(Debug, Clone)]
+pub struct Tracer {
+    /// One message in this many is traced, none if zero.
+    sample_every: u64,
+    /// The messages seen by `sample`.
+    seen: u64,
+    /// The spans shorter than this aren't exported, in nanoseconds.
+    min_span_ns: u64,
+    /// The channel of the spans to the exporter, `None` if not exported.
+    sink: Option<SyncSender<Span>>,
+    /// The spans dropped, the channel being full or their export failing.
+    dropped: Arc<AtomicU64>,
+}
+
+impl Tracer {
+    /// Creates a tracer tracing one message in `sample_every` (none if zero),
+    /// the spans recorded not exported.
+    pub fn new(sample_every: u64) -> Self {
+        Self { sample_every, seen: 0, min_span_ns: 0, sink: None, dropped: Arc::default() }
+    }
+
+    /// Hands the spans lasting at least `min_span_ns` to `sink`, the others
+    /// only propagated.
+    pub fn with_sink(mut self, sink: SyncSender<Span>, min_span_ns: u64) -> Self {
+        self.sink = Some(sink);
+        self.min_span_ns = min_span_ns;
+        self
+    }
+
+    /// Returns the start time of the trace of a message received now, if sampled.
+    ///
+    /// LATENCY: FAST_PATH
+    pub fn sample(&mut self) -> Option<u64> {
+        if self.sample_every == 0 {
+            return None;
+        }
+        self.seen += 1;
+        (self.seen % self.sample_every == 0).then(now_ns)
+    }
+
+    /// Records the span `name` of a stage started at `start_ns` and ending now,
+    /// as a child of `parent`, or as the root of a new trace if `parent` isn't
+    /// traced. Returns the context of the span, the parent of the next stage.
+    ///
+    /// LATENCY: FAST_PATH (traced messages only)
+    pub fn record(
+        &self,
+        name: &'static str,
+        parent: TraceContext,
+        start_ns: u64,
+        attributes: &[(&'static str, u64)],
+    ) -> TraceContext {
+        let end_ns = now_ns();
+        let trace_id = if parent.is_traced() { parent.trace_id } else { next_id() };
+        let context = TraceContext { trace_id, span_id: next_id() };
+        let sink = self.sink.as_ref().filter(|_| end_ns.saturating_sub(start_ns) >= self.min_span_ns);
+        if let Some(sink) = sink {
+            let span = Span {
+                name,
+                context,
+                parent_span_id: parent.span_id,
+                start_ns,
+                end_ns,
+                attributes: attributes.to_vec(),
+            };
+            if let Err(TrySendError::Full(_)) = sink.try_send(span) {
+                self.dropped.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        context
+    }
+
+    /// Returns the spans dropped, the exporter lagging or the collector failing.
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+}
+
+/// The export of the spans of a component to an OpenTelemetry collector.
+#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct OtlpConfig {
+    /// The OTLP/HTTP endpoint of the collector, e.g. `http://127.0.0.1:4318`.
+    pub endpoint: String,
+    /// The spans exported per request.
+    #[serde(default = "default_batch_size")]
+    pub batch_size: usize,
+    /// The interval between the exports of a partial batch, in milliseconds.
+    #[serde(default = "default_flush_interval_ms")]
+    pub flush_interval_ms: u64,
+}
+
+impl OtlpConfig {
+    /// Returns the address (`host:port`) and the traces path of the endpoint,
+    /// `None` unless an `http://host:port` URL.
+    fn target(&self) -> Option<(&str, String)> {
+        let rest = self.endpoint.strip_prefix("http://")?;
+        let (address, path) = rest.split_once('/').unwrap_or((rest, ""));
+        let (host, port) = address.rsplit_once(':')?;
+        if host.is_empty() || port.parse::<u16>().is_err() {
+            return None;
+        }
+        let path = match path.trim_end_matches('/') {
+            "" => OTLP_TRACES_PATH.to_string(),
+            prefix => format!("/{}{}", prefix, OTLP_TRACES_PATH),
+        };
+        Some((address, path))
+    }
+
+    /// Validates the export configuration.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.target().is_none() {
+            return Err(format!("'endpoint' must be an http://host:port URL, got '{}'", self.endpoint));
+        }
+        if self.batch_size == 0 {
+            return Err("'batch_size' must be non-zero".to_string());
+        }
+        if self.flush_interval_ms == 0 {
+            return Err("'flush_interval_ms' must be non-zero".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// The tracing of a component.
+#[derive(Debug, Clone, Default, Deserialize, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct TelemetryPolicy {
+    /// One message in this many is traced from its receive point, none if
+    /// zero; only the components receiving from the exchange sample.
+    #[serde(default)]
+    pub sample_every: u64,
+    /// The spans shorter than this aren't exported, in microseconds, so that
+    /// only the outliers are.
+    #[serde(default)]
+    pub min_span_us: u64,
+    /// The export of the spans, the spans only propagated without it.
+    #[serde(default)]
+    pub otlp: Option<OtlpConfig>,
+}
+
+impl TelemetryPolicy {
+    /// Returns true if the component traces messages or exports spans.
+    pub fn is_enabled(&self) -> bool {
+        self.sample_every > 0 || self.otlp.is_some()
+    }
+
+    /// Creates the tracer of the component, spawning the exporter of its spans
+    /// if configured. Returns `None` if the component doesn't trace.
+    ///
+    /// The exporter runs on a thread of its own, inheriting the scheduling of
+    /// the calling thread, so it is to be spawned from the main thread, off the
+    /// isolated lcores. It exits once every clone of the tracer is dropped.
+    ///
+    /// # Errors
+    /// Returns an error if the endpoint is invalid or the thread can't be spawned.
+    pub fn tracer(&self, service_name: &str) -> io::Result<Option<Tracer>> {
+        if !self.is_enabled() {
+            return Ok(None);
+        }
+        let tracer = Tracer::new(self.sample_every);
+        let Some(otlp) = &self.otlp else {
+            return Ok(Some(tracer));
+        };
+        let (address, path) = otlp
+            .target()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, format!("invalid endpoint {}", otlp.endpoint)))?;
+        let exporter = OtlpExporter {
+            address: address.to_string(),
+            path,
+            service_name: service_name.to_string(),
+            batch_size: otlp.batch_size,
+            flush_interval: Duration::from_millis(otlp.flush_interval_ms),
+            dropped: tracer.dropped.clone(),
+        };
+        let (sink, spans) = mpsc::sync_channel(SPAN_CHANNEL_CAPACITY);
+        thread::Builder::new().name("otlp".to_string()).spawn(move || exporter.run(spans))?;
+        Ok(Some(tracer.with_sink(sink, self.min_span_us * 1_000)))
+    }
+
+    /// Validates the tracing configuration.
+    pub fn validate(&self) -> Result<(), String> {
+        match &self.otlp {
+            Some(otlp) => otlp.validate(),
+            None => Ok(()),
+        }
+    }
+}
+
+/// The tracing of the components, by component name.
+#[derive(Debug, Clone, Default, Deserialize, PartialEq, Eq)]
+#[serde(transparent)]
+pub struct TelemetryConfig {
+    components: HashMap<String, TelemetryPolicy>,
+}
+
+impl TelemetryConfig {
+    /// Parses the telemetry configuration from a YAML file.
+    ///
+    /// # Errors
+    /// Returns an error if the file cannot be read, parsed, or fails validation.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, TelemetryConfigError> {
+        let content = fs::read_to_string(path)?;
+        Self::from_str(&content)
+    }
+
+    /// Parses the telemetry configuration from a YAML string.
+    ///
+    /// # Errors
+    /// Returns an error if the YAML cannot be parsed or fails validation.
+    pub fn from_str(content: &str) -> Result<Self, TelemetryConfigError> {
+        // An empty file (or one of comments only) traces no component
+        let config: Option<Self> = serde_yaml::from_str(content)?;
+        let config = config.unwrap_or_default();
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Validates the telemetry configuration.
+    fn validate(&self) -> Result<(), TelemetryConfigError> {
+        for (component, policy) in &self.components {
+            policy
+                .validate()
+                .map_err(|e| TelemetryConfigError::ValidationError(format!("Component '{}': {}", component, e)))?;
+        }
+        Ok(())
+    }
+
+    /// Returns the tracing of a component, disabled if it isn't configured.
+    pub fn policy(&self, component: &str) -> TelemetryPolicy {
+        self.components.get(component).cloned().unwrap_or_default()
+    }
+}
+
+/// An attribute of the OTLP JSON encoding.
+#[derive(Serialize)]
+struct KeyValue<'a> {
+    key: &'a str,
+    value: AnyValue<'a>,
+}
+
+/// A value of an attribute, the 64-bit integers encoded as strings.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+enum AnyValue<'a> {
+    StringValue(&'a str),
+    IntValue(String),
+}
+
+/// A span of the OTLP JSON encoding, its IDs in hexadecimal.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct OtlpSpan<'a> {
+    trace_id: String,
+    span_id: String,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    parent_span_id: String,
+    name: &'a str,
+    kind: u8,
+    start_time_unix_nano: String,
+    end_time_unix_nano: String,
+    attributes: Vec<KeyValue<'a>>,
+}
+
+impl<'a> From<&'a Span> for OtlpSpan<'a> {
+    fn from(span: &'a Span) -> Self {
+        Self {
+            trace_id: format!("{:032x}", span.context.trace_id),
+            span_id: format!("{:016x}", span.context.span_id),
+            parent_span_id: match span.parent_span_id {
+                0 => String::new(),
+                parent => format!("{:016x}", parent),
+            },
+            name: span.name,
+            kind: SPAN_KIND_INTERNAL,
+            start_time_unix_nano: span.start_ns.to_string(),
+            end_time_unix_nano: span.end_ns.to_string(),
+            attributes: span
+                .attributes
+                .iter()
+                .map(|&(key, value)| KeyValue { key, value: AnyValue::IntValue(value.to_string()) })
+                .collect(),
+        }
+    }
+}
+
+/// Returns the OTLP JSON export request of the spans of a service.
+fn export_body(service_name: &str, spans: &[Span]) -> String {
+    let spans: Vec<OtlpSpan<'_>> = spans.iter().map(OtlpSpan::from).collect();
+    let resource = [KeyValue { key: "service.name", value: AnyValue::StringValue(service_name) }];
+    let request = serde_json::json!({
+        "resourceSpans": [{
+            "resource": { "attributes": resource },
+            "scopeSpans": [{ "scope": { "name": "ctl" }, "spans": spans }]
+        }]
+    });
+    request.to_string()
+}
+
+/// The exporter of the spans of a component to an OTLP/HTTP collector.
+struct OtlpExporter {
+    /// The `host:port` of the collector.
+    address: String,
+    /// The path of the traces.
+    path: String,
+    /// The `service.name` of the spans.
+    service_name: String,
+    /// The spans exported per request.
+    batch_size: usize,
+    /// The interval between the exports of a partial batch.
+    flush_interval: Duration,
+    /// The spans dropped, shared with the tracer.
+    dropped: Arc<AtomicU64>,
+}
+
+impl OtlpExporter {
+    /// Exports the spans received in batches, until every tracer is dropped.
+    ///
+    /// LATENCY: SLOW_PATH
+    fn run(self, spans: Receiver<Span>) {
+        let mut batch = Vec::with_capacity(self.batch_size);
+        let mut last_flush = Instant::now();
+        loop {
+            match spans.recv_timeout(self.flush_interval.saturating_sub(last_flush.elapsed())) {
+                Ok(span) => batch.push(span),
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => {
+                    self.flush(&mut batch);
+                    return;
+                }
+            }
+            if batch.len() >= self.batch_size || last_flush.elapsed() >= self.flush_interval {
+                self.flush(&mut batch);
+                last_flush = Instant::now();
+            }
+        }
+    }
+
+    /// Exports a batch, counting its spans dropped if the export fails.
+    fn flush(&self, batch: &mut Vec<Span>) {
+        if batch.is_empty() {
+            return;
+        }
+        // A collector down loses the spans of its downtime, never holding the exporter up
+        if self.post(&export_body(&self.service_name, batch)).is_err() {
+            self.dropped.fetch_add(batch.len() as u64, Ordering::Relaxed);
+        }
+        batch.clear();
+    }
+
+    /// Posts an export request to the collector, failing unless it answers 2xx.
+    fn post(&self, body: &str) -> io::Result<()> {
+        let addr = self
+            .address
+            .to_socket_addrs()?
+            .next()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, format!("{} doesn't resolve", self.address)))?;
+        let mut stream = TcpStream::connect_timeout(&addr, EXPORT_TIMEOUT)?;
+        stream.set_read_timeout(Some(EXPORT_TIMEOUT))?;
+        stream.set_write_timeout(Some(EXPORT_TIMEOUT))?;
+        write!(
+            stream,
+            "POST {} HTTP/1.1\r\nHost: {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\
+             Connection: close\r\n\r\n{}",
+            self.path,
+            self.address,
+            body.len(),
+            body
+        )?;
+        stream.flush()?;
+
+        // The status line starts with `HTTP/1.1 200`
+        let mut status = [0u8; 12];
+        stream.read_exact(&mut status)?;
+        match status[9] {
+            b'2' => Ok(()),
+            _ => Err(io::Error::other(format!("collector answered {}", String::from_utf8_lossy(&status[9..])))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::net::TcpListener;
+
+    #[test]
+    fn test_parse_config() {
+        let config = TelemetryConfig::from_str(
+            r#"
+md-handler:
+  sample_every: 1000
+  min_span_us: 50
+  otlp:
+    endpoint: http://127.0.0.1:4318
+md-subscriber:
+  otlp:
+    endpoint: http://collector:4318/otlp/
+    batch_size: 64
+"#,
+        )
+        .unwrap();
+        let policy = config.policy("md-handler");
+        assert_eq!((policy.sample_every, policy.min_span_us), (1000, 50));
+        let otlp = policy.otlp.unwrap();
+        assert_eq!((otlp.batch_size, otlp.flush_interval_ms), (DEFAULT_BATCH_SIZE, DEFAULT_FLUSH_INTERVAL_MS));
+        assert_eq!(otlp.target(), Some(("127.0.0.1:4318", "/v1/traces".to_string())));
+        let otlp = config.policy("md-subscriber").otlp.unwrap();
+        assert_eq!(otlp.target(), Some(("collector:4318", "/otlp/v1/traces".to_string())));
+        assert!(!config.policy("ctl-admin").is_enabled());
+
+        assert_eq!(TelemetryConfig::from_str("# none\n").unwrap(), TelemetryConfig::default());
+        assert!(TelemetryConfig::from_str("a:\n  otlp:\n    endpoint: https://collector:4318\n").is_err());
+        assert!(TelemetryConfig::from_str("a:\n  otlp:\n    endpoint: http://collector\n").is_err());
+        assert!(TelemetryConfig::from_str("a:\n  sample_rate: 0.1\n").is_err());
+    }
+
+    #[test]
+    fn test_traceparent() {
+        let context = TraceContext { trace_id: 0x4bf9_2f35_77b3_4da6, span_id: 0x00f0_67aa_0ba9_02b7 };
+        let traceparent = context.traceparent();
+        assert_eq!(traceparent, "00-00000000000000004bf92f3577b34da6-00f067aa0ba902b7-01");
+        assert_eq!(TraceContext::from_traceparent(&traceparent), Some(context));
+        assert_eq!(TraceContext::from_traceparent("00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01"), None);
+        assert_eq!(TraceContext::from_traceparent("00-4bf92f3577b34da6-01"), None);
+    }
+
+    #[test]
+    fn test_sample_and_record() {
+        let (sink, spans) = mpsc::sync_channel(1);
+        let mut tracer = Tracer::new(2).with_sink(sink, 0);
+        assert_eq!(tracer.sample(), None);
+        let start_ns = tracer.sample().unwrap();
+        assert_eq!(tracer.sample(), None);
+
+        // The root span starts a trace, continued by the next stage
+        let root = tracer.record("md.parse", TraceContext::default(), start_ns, &[("symbol_id", 7)]);
+        assert!(root.is_traced());
+        let child = tracer.record("md.consume", root, now_ns(), &[]);
+        assert_eq!(child.trace_id, root.trace_id);
+        assert_ne!(child.span_id, root.span_id);
+        assert_eq!(tracer.dropped(), 1);
+
+        let span = spans.try_recv().unwrap();
+        assert_eq!((span.name, span.context, span.parent_span_id), ("md.parse", root, 0));
+        assert_eq!(span.attributes, vec![("symbol_id", 7)]);
+        assert!(span.end_ns >= span.start_ns);
+
+        // Shorter than the export threshold, only propagated
+        let (sink, spans) = mpsc::sync_channel(1);
+        let tracer = Tracer::new(0).with_sink(sink, 60_000_000_000);
+        assert!(tracer.record("md.parse", TraceContext::default(), now_ns(), &[]).is_traced());
+        assert!(spans.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_export() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let policy = TelemetryPolicy {
+            sample_every: 1,
+            min_span_us: 0,
+            otlp: Some(OtlpConfig {
+                endpoint: format!("http://{}", listener.local_addr().unwrap()),
+                batch_size: DEFAULT_BATCH_SIZE,
+                flush_interval_ms: DEFAULT_FLUSH_INTERVAL_MS,
+            }),
+        };
+        let tracer = policy.tracer("md-handler").unwrap().unwrap();
+        let context = tracer.record("md.parse", TraceContext::default(), now_ns(), &[("seq", 42)]);
+        // The exporter flushes once the tracer is dropped
+        drop(tracer);
+
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut request = Vec::new();
+        let mut buf = [0u8; 4096];
+        while !String::from_utf8_lossy(&request).ends_with("}]}]}") {
+            let read = stream.read(&mut buf).unwrap();
+            assert_ne!(read, 0);
+            request.extend_from_slice(&buf[..read]);
+        }
+        stream.write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\n{}").unwrap();
+
+        let request = String::from_utf8(request).unwrap();
+        assert!(request.starts_with("POST /v1/traces HTTP/1.1\r\n"));
+        assert!(request.contains(&format!("\"traceId\":\"{:032x}\"", context.trace_id)));
+        assert!(request.contains("\"stringValue\":\"md-handler\""));
+        assert!(request.contains("{\"key\":\"seq\",\"value\":{\"intValue\":\"42\"}}"));
+    }
+}
@@ -0,0 +1,360 @@
+//! Arbitration of the A/B lines of a feed.
+//!
+//! Under arbitration, two md-handlers subscribe to the same streams over their
+//! own connections, each publishing to its line ring (`{KIND}_{symbol_id}_LA`,
+//! `_LB`). The arbiter consumes both lines and forwards the first copy of each
+//! event to the pub-sub ring, so that the loss or the lag of a line is covered
+//! by the other one without the consumers knowing. The copies of an event are
+//! told apart by its ID, the trade ID of the trades, the aggregate trade ID of
+//! the aggregated trades and the order book update ID of the bookTicker and
+//! depth updates, an event being forwarded only if its ID wasn't forwarded yet
+//! for its symbol and event type, within the window of a `Deduper`. An event a
+//! line lost is forwarded from the other line even once later events were.
+//!
+//! The fragments of a payload are buffered per line until its last one, and
+//! forwarded together, so that the fragments of the two lines never interleave
+//! in the pub-sub ring.
+
+use ctl_core::Line;
+use hashbrown::HashMap;
+
+use crate::join::payload_number;
+use crate::{slot_payload, Dedup, Deduper, EventType, RawMessage, DEFAULT_DEDUP_WINDOW};
+
+/// Returns the ID of the event of a JSON payload, the same on both lines, or
+/// `None` for the events without one.
+///
+/// LATENCY: FAST_PATH
+pub fn payload_event_id(event_type: EventType, data: &[u8]) -> Option<u64> {
+    match event_type {
+        EventType::BookTicker | EventType::Depth => payload_number(data, b'u'),
+        EventType::Trade => payload_number(data, b't'),
+        EventType::AggTrade => payload_number(data, b'a'),
+        _ => None,
+    }
+}
+
+/// The arbitration of a payload pushed to an `Arbiter`.
+#[derive(Debug, Clone, Copy)]
+pub enum Arbitration<'a> {
+    /// The first copy of the event, its messages (its fragments, if any) to forward.
+    Forward(&'a [RawMessage]),
+    /// A copy of an event already forwarded, from either line, or an event too
+    /// far behind the window to be told apart from one.
+    Duplicate,
+    /// A leading fragment, buffered until the last one.
+    Partial,
+    /// A fragment of a payload missing one of its fragments, dropped.
+    Dropped,
+}
+
+/// The counters of a line.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LineStats {
+    /// Number of payloads received.
+    pub payloads: u64,
+    /// Number of payloads forwarded, the line being first.
+    pub forwarded: u64,
+    /// Number of payloads already forwarded from the other line, or replayed.
+    pub duplicates: u64,
+    /// Number of payloads behind the window of the deduper, dropped.
+    pub stale: u64,
+    /// Number of payloads dropped for a lost fragment.
+    pub dropped: u64,
+    /// The time of the last message of the line, or of the start of the arbiter,
+    /// in milliseconds since the epoch.
+    pub last_message_ms: u64,
+}
+
+/// A line turning silent, or carrying messages again.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LineSilence {
+    /// The line.
+    pub line: Line,
+    /// Whether the line turned silent, rather than recovered.
+    pub silent: bool,
+    /// The time since the last message of the line, in milliseconds.
+    pub quiet_ms: u64,
+}
+
+/// Forwards the first copy of each event of the two lines of a ring.
+#[derive(Debug)]
+pub struct Arbiter {
+    /// The payload bytes of the messages of the rings.
+    slot_size: usize,
+    /// The IDs of the events forwarded, by symbol ID and event type.
+    deduper: Deduper,
+    /// The leading fragments of the payloads being received, by line, symbol
+    /// ID, event type and medium.
+    partials: HashMap<(Line, u32, u8, u8), Vec<RawMessage>>,
+    /// The messages of the payload to forward.
+    ready: Vec<RawMessage>,
+    /// Number of payloads forwarded without an event ID.
+    unkeyed: u64,
+    /// The counters of each line.
+    lines: [LineStats; 2],
+    /// Whether each line is reported silent.
+    silent: [bool; 2],
+}
+
+impl Arbiter {
+    /// Creates the arbiter of the rings of `slot_size` payload bytes, started at `now_ms`.
+    pub fn new(slot_size: usize, now_ms: u64) -> Self {
+        let line = LineStats { last_message_ms: now_ms, ..LineStats::default() };
+        Self {
+            slot_size,
+            deduper: Deduper::new(DEFAULT_DEDUP_WINDOW),
+            partials: HashMap::new(),
+            ready: Vec::new(),
+            unkeyed: 0,
+            lines: [line; 2],
+            silent: [false; 2],
+        }
+    }
+
+    /// Remembers the IDs of the `window` events forwarded behind the last one
+    /// of each symbol and event type.
+    pub fn with_dedup_window(mut self, window: u64) -> Self {
+        self.deduper = Deduper::new(window);
+        self
+    }
+
+    /// Returns the counters of a line.
+    pub fn stats(&self, line: Line) -> &LineStats {
+        &self.lines[line.index()]
+    }
+
+    /// Returns the number of payloads forwarded without an event ID.
+    pub fn unkeyed(&self) -> u64 {
+        self.unkeyed
+    }
+
+    /// Drops the payloads being received on a line, its consumer having been
+    /// sped past.
+    ///
+    /// LATENCY: SLOW_PATH
+    pub fn reset(&mut self, line: Line) {
+        let stats = &mut self.lines[line.index()];
+        self.partials.retain(|key, partial| {
+            if key.0 == line && !partial.is_empty() {
+                stats.dropped += 1;
+                return false;
+            }
+            true
+        });
+    }
+
+    /// Pushes a message consumed from a line at `now_ms`, returning the
+    /// messages to forward once its payload is the first copy of its event.
+    ///
+    /// LATENCY: FAST_PATH
+    pub fn push(&mut self, line: Line, message: &RawMessage, now_ms: u64) -> Arbitration<'_> {
+        let stats = &mut self.lines[line.index()];
+        stats.last_message_ms = now_ms;
+        let header = message.header;
+        self.ready.clear();
+
+        if header.is_fragment() {
+            let partial = self.partials.entry((line, header.symbol_id, header.event_type, header.medium)).or_default();
+            let index = header.fragment_index as usize;
+            if index == 0 {
+                // A new payload, the previous one lost its last fragment
+                if !partial.is_empty() {
+                    stats.dropped += 1;
+                }
+                partial.clear();
+            } else if index != partial.len() {
+                // A fragment was lost, the payload is dropped once
+                if !partial.is_empty() {
+                    stats.dropped += 1;
+                    partial.clear();
+                }
+                return Arbitration::Dropped;
+            }
+            partial.push(*message);
+            if header.is_continued() {
+                return Arbitration::Partial;
+            }
+            std::mem::swap(&mut self.ready, partial);
+        } else {
+            self.ready.push(*message);
+        }
+        stats.payloads += 1;
+
+        let Some(id) = event_id(&self.ready, self.slot_size) else {
+            self.unkeyed += 1;
+            stats.forwarded += 1;
+            return Arbitration::Forward(&self.ready);
+        };
+        match self.deduper.check((header.symbol_id, header.event_type), id) {
+            Dedup::New => {
+                stats.forwarded += 1;
+                Arbitration::Forward(&self.ready)
+            }
+            Dedup::Duplicate => {
+                stats.duplicates += 1;
+                Arbitration::Duplicate
+            }
+            Dedup::Stale => {
+                stats.stale += 1;
+                Arbitration::Duplicate
+            }
+        }
+    }
+
+    /// Checks the lines at `now_ms`, returning those turning silent or
+    /// carrying messages again.
+    ///
+    /// A line is silent once it received no message for `silent_after_ms`
+    /// while the other line did, both lines being quiet when the market is.
+    ///
+    /// LATENCY: SLOW_PATH
+    pub fn check_silence(&mut self, now_ms: u64, silent_after_ms: u64) -> Vec<LineSilence> {
+        let quiet_ms = self.lines.map(|stats| now_ms.saturating_sub(stats.last_message_ms));
+        let mut changes = Vec::new();
+        for line in Line::ALL {
+            let (quiet, other) = (quiet_ms[line.index()], quiet_ms[line.other().index()]);
+            let silent = quiet > silent_after_ms && other <= silent_after_ms;
+            // A quiet market doesn't recover a silent line, only its messages do
+            let recovered = quiet <= silent_after_ms;
+            let was_silent = &mut self.silent[line.index()];
+            if silent != *was_silent && (silent || recovered) {
+                *was_silent = silent;
+                changes.push(LineSilence { line, silent, quiet_ms: quiet });
+            }
+        }
+        changes
+    }
+}
+
+/// Returns the event ID of the payload of a message, reassembled from its fragments.
+///
+/// LATENCY: FAST_PATH
+fn event_id(messages: &[RawMessage], slot_size: usize) -> Option<u64> {
+    let event_type = messages.first()?.header.event_type();
+    match messages {
+        [message] => payload_event_id(event_type, slot_payload(&message.data[..slot_size])),
+        _ => {
+            let data: Vec<u8> = messages.iter().flat_map(|message| message.data[..slot_size].iter().copied()).collect();
+            payload_event_id(event_type, slot_payload(&data))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{MessageHeader, RAW_MESSAGE_SIZE};
+
+    fn message(event_type: EventType, payload: &str) -> RawMessage {
+        let mut message = RawMessage { header: MessageHeader::new(event_type, 1), ..RawMessage::default() };
+        message.data[..payload.len()].copy_from_slice(payload.as_bytes());
+        message
+    }
+
+    fn trade(id: u64) -> RawMessage {
+        message(EventType::Trade, &format!(r#"{{"e":"trade","s":"BTCUSDT","t":{},"p":"1.0"}}"#, id))
+    }
+
+    fn forwarded(arbitration: Arbitration<'_>) -> usize {
+        match arbitration {
+            Arbitration::Forward(messages) => messages.len(),
+            _ => 0,
+        }
+    }
+
+    #[test]
+    fn test_payload_event_id() {
+        let ticker = br#"{"u":400900217,"s":"BNBUSDT","b":"25.35","a":"25.36"}"#;
+        assert_eq!(payload_event_id(EventType::BookTicker, ticker), Some(400900217));
+        let agg = br#"{"e":"aggTrade","s":"BNBBTC","a":12345,"p":"0.001","f":100,"l":105}"#;
+        assert_eq!(payload_event_id(EventType::AggTrade, agg), Some(12345));
+        let trade = br#"{"e":"trade","E":1,"s":"BNBBTC","t":42,"p":"0.001","T":1}"#;
+        assert_eq!(payload_event_id(EventType::Trade, trade), Some(42));
+        // The quoted ask price of a ticker isn't an aggregate trade ID
+        assert_eq!(payload_event_id(EventType::AggTrade, ticker), None);
+        assert_eq!(payload_event_id(EventType::Candle, trade), None);
+    }
+
+    #[test]
+    fn test_first_copy_forwarded() {
+        let mut arbiter = Arbiter::new(RAW_MESSAGE_SIZE, 0);
+        assert_eq!(forwarded(arbiter.push(Line::A, &trade(1), 1)), 1);
+        assert!(matches!(arbiter.push(Line::B, &trade(1), 2), Arbitration::Duplicate));
+        // Line B is ahead
+        assert_eq!(forwarded(arbiter.push(Line::B, &trade(2), 3)), 1);
+        assert!(matches!(arbiter.push(Line::A, &trade(2), 4), Arbitration::Duplicate));
+        // Line A lost the third trade, forwarded from line B once the fourth was
+        assert_eq!(forwarded(arbiter.push(Line::A, &trade(4), 4)), 1);
+        assert_eq!(forwarded(arbiter.push(Line::B, &trade(3), 4)), 1);
+        assert!(matches!(arbiter.push(Line::B, &trade(4), 4), Arbitration::Duplicate));
+        // The events of other symbols and event types are arbitrated apart
+        let mut other = trade(1);
+        other.header.symbol_id = 2;
+        assert_eq!(forwarded(arbiter.push(Line::A, &other, 5)), 1);
+        let ticker = message(EventType::BookTicker, r#"{"u":1,"s":"BTCUSDT"}"#);
+        assert_eq!(forwarded(arbiter.push(Line::A, &ticker, 6)), 1);
+        // Without an ID, every copy is forwarded
+        let unkeyed = message(EventType::Trade, r#"{"s":"BTCUSDT"}"#);
+        assert_eq!(forwarded(arbiter.push(Line::B, &unkeyed, 7)), 1);
+        assert_eq!(arbiter.unkeyed(), 1);
+
+        let (a, b) = (arbiter.stats(Line::A), arbiter.stats(Line::B));
+        assert_eq!((a.payloads, a.forwarded, a.duplicates, a.last_message_ms), (5, 4, 1, 6));
+        assert_eq!((b.payloads, b.forwarded, b.duplicates, b.last_message_ms), (5, 3, 2, 7));
+    }
+
+    #[test]
+    fn test_fragments_forwarded_together() {
+        let mut arbiter = Arbiter::new(64, 0);
+        let payload = format!(r#"{{"e":"depthUpdate","s":"BTCUSDT","U":157,"u":160,"b":[["{}"]]}}"#, "1".repeat(100));
+        let fragments: Vec<RawMessage> = payload
+            .as_bytes()
+            .chunks(64)
+            .enumerate()
+            .map(|(index, chunk)| {
+                let mut fragment = message(EventType::Depth, std::str::from_utf8(chunk).unwrap());
+                fragment.header.set_fragment(index as u16, (index + 1) * 64 < payload.len());
+                fragment
+            })
+            .collect();
+        assert_eq!(fragments.len(), 3);
+
+        // The fragments of the lines interleave, only the first complete copy is forwarded
+        assert!(matches!(arbiter.push(Line::A, &fragments[0], 1), Arbitration::Partial));
+        assert!(matches!(arbiter.push(Line::B, &fragments[0], 1), Arbitration::Partial));
+        assert!(matches!(arbiter.push(Line::B, &fragments[1], 1), Arbitration::Partial));
+        assert!(matches!(arbiter.push(Line::A, &fragments[1], 1), Arbitration::Partial));
+        assert_eq!(forwarded(arbiter.push(Line::B, &fragments[2], 1)), 3);
+        assert!(matches!(arbiter.push(Line::A, &fragments[2], 1), Arbitration::Duplicate));
+
+        // A lost fragment drops the payload once
+        assert!(matches!(arbiter.push(Line::A, &fragments[0], 2), Arbitration::Partial));
+        assert!(matches!(arbiter.push(Line::A, &fragments[2], 2), Arbitration::Dropped));
+        assert_eq!(arbiter.stats(Line::A).dropped, 1);
+        arbiter.push(Line::B, &fragments[0], 2);
+        arbiter.reset(Line::B);
+        assert_eq!(arbiter.stats(Line::B).dropped, 1);
+    }
+
+    #[test]
+    fn test_silent_line() {
+        let mut arbiter = Arbiter::new(RAW_MESSAGE_SIZE, 0);
+        arbiter.push(Line::A, &trade(1), 1_000);
+        assert!(arbiter.check_silence(5_000, 5_000).is_empty());
+
+        // Line B received nothing while line A carried the events
+        arbiter.push(Line::A, &trade(2), 5_500);
+        let changes = arbiter.check_silence(6_000, 5_000);
+        assert_eq!(changes, [LineSilence { line: Line::B, silent: true, quiet_ms: 6_000 }]);
+        assert!(arbiter.check_silence(6_500, 5_000).is_empty());
+
+        // Both lines quiet, line B stays silent until it carries messages again
+        assert!(arbiter.check_silence(20_000, 5_000).is_empty());
+        arbiter.push(Line::A, &trade(3), 20_000);
+        arbiter.push(Line::B, &trade(3), 20_000);
+        let changes = arbiter.check_silence(20_100, 5_000);
+        assert_eq!(changes, [LineSilence { line: Line::B, silent: false, quiet_ms: 100 }]);
+    }
+}
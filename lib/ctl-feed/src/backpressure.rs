@@ -0,0 +1,292 @@
+//! Producer behavior when a ring has no free slot for a new message.
+//!
+//! The policy is selected per ring in the market data configuration and applied
+//! by an `OverflowGuard` before writing a slot: the parser admits each message
+//! before handing it to the feedgroup worker, dropping it when refused, and the
+//! leading fragments are published through a `GuardedPublisher`. A slot is free
+//! unless it holds a message an attached consumer hasn't read yet, the
+//! consumers whose process is no longer alive aside.
+//! Each outcome is counted in the ring's `OverflowCounters` so operators know
+//! when consumers can't keep up.
+
+use std::hint;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+use serde::Deserialize;
+
+use crate::{DeadCursors, RingError, RingMetricsHandle, RingPublisher};
+
+/// The producer behavior when the ring is full.
+#[derive(Debug, Clone, Copy, Default, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "kebab-case", tag = "policy")]
+pub enum OverflowPolicy {
+    /// Drop the message being published, keeping the unread ones.
+    DropNewest,
+    /// Publish the message over the oldest unread slot (consumers are sped past).
+    #[default]
+    OverwriteOldest,
+    /// Wait for a free slot, dropping the message once the timeout elapses.
+    BlockWithTimeout {
+        /// Maximum time to wait for a free slot, in microseconds.
+        timeout_us: u64,
+    },
+}
+
+/// The result of admitting a message into a ring under an `OverflowPolicy`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PublishOutcome {
+    /// A free slot was available.
+    Published,
+    /// The ring was full and the message must be dropped.
+    DroppedNewest,
+    /// The ring was full and the message overwrites the oldest unread slot.
+    Overwritten,
+    /// A free slot became available while blocking.
+    PublishedAfterBlock,
+    /// No free slot became available before the timeout, the message must be dropped.
+    TimedOut,
+}
+
+impl PublishOutcome {
+    /// Returns true if the message should be written to the ring.
+    pub fn should_publish(&self) -> bool {
+        matches!(
+            self,
+            PublishOutcome::Published | PublishOutcome::Overwritten | PublishOutcome::PublishedAfterBlock
+        )
+    }
+}
+
+/// Per-ring counters of publish outcomes.
+///
+/// Laid out as `repr(C)` so it can be placed in the shared metrics region.
+#[repr(C)]
+#[derive(Debug, Default)]
+pub struct OverflowCounters {
+    /// Messages published into a free slot.
+    pub published: AtomicU64,
+    /// Messages dropped because the ring was full.
+    pub dropped_newest: AtomicU64,
+    /// Messages that overwrote the oldest unread slot.
+    pub overwritten: AtomicU64,
+    /// Messages published after blocking for a free slot.
+    pub published_after_block: AtomicU64,
+    /// Messages dropped after blocking timed out.
+    pub timed_out: AtomicU64,
+}
+
+impl OverflowCounters {
+    /// Counts a publish outcome.
+    ///
+    /// LATENCY: FAST_PATH
+    pub fn record(&self, outcome: PublishOutcome) {
+        let counter = match outcome {
+            PublishOutcome::Published => &self.published,
+            PublishOutcome::DroppedNewest => &self.dropped_newest,
+            PublishOutcome::Overwritten => &self.overwritten,
+            PublishOutcome::PublishedAfterBlock => &self.published_after_block,
+            PublishOutcome::TimedOut => &self.timed_out,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+impl OverflowPolicy {
+    /// Validates the policy configuration.
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            OverflowPolicy::BlockWithTimeout { timeout_us: 0 } => {
+                Err("block-with-timeout policy must have a non-zero 'timeout_us'".to_string())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Decides whether a message can be published, given a probe reporting if
+    /// the ring has a free slot, and counts the outcome.
+    ///
+    /// LATENCY: FAST_PATH (except while blocking)
+    pub fn admit<F>(&self, counters: &OverflowCounters, mut has_free_slot: F) -> PublishOutcome
+    where
+        F: FnMut() -> bool,
+    {
+        let outcome = if has_free_slot() {
+            PublishOutcome::Published
+        } else {
+            match *self {
+                OverflowPolicy::DropNewest => PublishOutcome::DroppedNewest,
+                OverflowPolicy::OverwriteOldest => PublishOutcome::Overwritten,
+                OverflowPolicy::BlockWithTimeout { timeout_us } => {
+                    let deadline = Instant::now() + Duration::from_micros(timeout_us);
+                    loop {
+                        if has_free_slot() {
+                            break PublishOutcome::PublishedAfterBlock;
+                        }
+                        if Instant::now() >= deadline {
+                            break PublishOutcome::TimedOut;
+                        }
+                        hint::spin_loop();
+                    }
+                }
+            }
+        };
+        counters.record(outcome);
+        outcome
+    }
+}
+
+/// Applies the overflow policy of a ring, counting the outcomes in its metrics.
+#[derive(Debug, Clone)]
+pub struct OverflowGuard {
+    /// The policy of the ring.
+    policy: OverflowPolicy,
+    /// The metrics of the ring, its consumer cursors and overflow counters.
+    metrics: RingMetricsHandle,
+    /// The cursors of the consumers no longer alive, skipped for the free slots.
+    dead: Arc<DeadCursors>,
+}
+
+impl OverflowGuard {
+    /// Creates the guard of the ring of `metrics`.
+    pub fn new(policy: OverflowPolicy, metrics: RingMetricsHandle) -> Self {
+        Self { policy, metrics, dead: Arc::default() }
+    }
+
+    /// Returns the policy of the ring.
+    pub fn policy(&self) -> OverflowPolicy {
+        self.policy
+    }
+
+    /// Decides whether the next message can be published, and counts the outcome.
+    ///
+    /// LATENCY: FAST_PATH (except while blocking)
+    pub fn admit(&self) -> PublishOutcome {
+        let ring = self.metrics.get();
+        let dead = self.dead.get(ring);
+        self.policy.admit(&ring.overflow, || ring.has_free_slot(dead))
+    }
+}
+
+/// A ring publisher applying the overflow policy of its ring before each publish.
+#[derive(Debug)]
+pub struct GuardedPublisher<R> {
+    /// The ring.
+    ring: R,
+    /// The overflow policy of the ring.
+    guard: OverflowGuard,
+}
+
+impl<R> GuardedPublisher<R> {
+    /// Wraps the publisher of a ring with the guard of the ring.
+    pub fn new(ring: R, guard: OverflowGuard) -> Self {
+        Self { ring, guard }
+    }
+}
+
+impl<T, R: RingPublisher<T>> RingPublisher<T> for GuardedPublisher<R> {
+    /// Publishes a message once admitted, returning `RingError::Full` when it's dropped.
+    fn publish(&self, message: &T) -> Result<(), RingError> {
+        if !self.guard.admit().should_publish() {
+            return Err(RingError::Full(self.guard.metrics.get().name()));
+        }
+        self.ring.publish(message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::Arc;
+
+    use ctl_shm::ShmRegion;
+
+    use crate::{MemoryRing, MetricsRegion};
+
+    #[test]
+    fn test_free_slot_publishes() {
+        let counters = OverflowCounters::default();
+        let outcome = OverflowPolicy::DropNewest.admit(&counters, || true);
+        assert_eq!(outcome, PublishOutcome::Published);
+        assert_eq!(counters.published.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn test_full_ring_outcomes() {
+        let counters = OverflowCounters::default();
+        assert_eq!(
+            OverflowPolicy::DropNewest.admit(&counters, || false),
+            PublishOutcome::DroppedNewest
+        );
+        assert_eq!(
+            OverflowPolicy::OverwriteOldest.admit(&counters, || false),
+            PublishOutcome::Overwritten
+        );
+        assert_eq!(
+            OverflowPolicy::BlockWithTimeout { timeout_us: 10 }.admit(&counters, || false),
+            PublishOutcome::TimedOut
+        );
+        assert_eq!(counters.dropped_newest.load(Ordering::Relaxed), 1);
+        assert_eq!(counters.overwritten.load(Ordering::Relaxed), 1);
+        assert_eq!(counters.timed_out.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn test_block_until_free_slot() {
+        let counters = OverflowCounters::default();
+        let mut probes = 0;
+        let outcome = OverflowPolicy::BlockWithTimeout { timeout_us: 1_000_000 }.admit(&counters, || {
+            probes += 1;
+            probes > 3
+        });
+        assert_eq!(outcome, PublishOutcome::PublishedAfterBlock);
+        assert!(outcome.should_publish());
+    }
+
+    #[test]
+    fn test_validate_zero_timeout() {
+        assert!(OverflowPolicy::BlockWithTimeout { timeout_us: 0 }.validate().is_err());
+        assert!(OverflowPolicy::DropNewest.validate().is_ok());
+    }
+
+    #[test]
+    fn test_guarded_publisher_full_ring() {
+        let name = format!("ctl_feed_backpressure_test_{}", std::process::id());
+        let region = Arc::new(ShmRegion::<MetricsRegion>::create(&name).unwrap());
+        region.register_raw_ring("TRADE_0_PS", 4, 64).unwrap();
+        let metrics = RingMetricsHandle::lookup(region.clone(), "TRADE_0_PS").unwrap();
+        let ring = metrics.get();
+        let consumer = ring.attach_consumer().unwrap();
+        let publisher =
+            |policy| GuardedPublisher::new(MemoryRing::<u64>::new(4), OverflowGuard::new(policy, metrics.clone()));
+        let (drop_newest, overwrite_oldest, block) = (
+            publisher(OverflowPolicy::DropNewest),
+            publisher(OverflowPolicy::OverwriteOldest),
+            publisher(OverflowPolicy::BlockWithTimeout { timeout_us: 10 }),
+        );
+
+        // The producer sequences each message before publishing it
+        for value in 0..4 {
+            ring.record_publish();
+            drop_newest.publish(&value).unwrap();
+        }
+        assert_eq!(ring.overflow.published.load(Ordering::Relaxed), 4);
+
+        // The consumer is a ring behind, nothing is free
+        assert!(matches!(drop_newest.publish(&4), Err(RingError::Full(name)) if name == "TRADE_0_PS"));
+        assert!(matches!(block.publish(&4), Err(RingError::Full(_))));
+        overwrite_oldest.publish(&4).unwrap();
+        assert_eq!(ring.overflow.dropped_newest.load(Ordering::Relaxed), 1);
+        assert_eq!(ring.overflow.timed_out.load(Ordering::Relaxed), 1);
+        assert_eq!(ring.overflow.overwritten.load(Ordering::Relaxed), 1);
+        assert_eq!((drop_newest.ring.published(), block.ring.published()), (4, 0));
+        assert_eq!(overwrite_oldest.ring.published(), 1);
+
+        // Once it consumes, a slot is free again
+        ring.consumers[consumer].advance();
+        drop_newest.publish(&4).unwrap();
+        assert_eq!(ring.overflow.published.load(Ordering::Relaxed), 5);
+    }
+}
@@ -0,0 +1,145 @@
+//! C declarations of the market data messages (see `ctl_shm::CHeader`).
+
+use ctl_shm::{c_struct, declare_c_message, CHeader, ShmError};
+
+use crate::{
+    CandleMessage, EventType, FixedPoint, MediumTag, MessageHeader, RawMessage, SignalMessage, TradeStatsMessage,
+    WindowStats, MIN_SLOT_SIZE, RAW_MESSAGE_SIZE, SIGNAL_FLOW_WINDOWS_MS, STATS_WINDOWS_MS, UNKNOWN_SYMBOL_ID,
+};
+
+/// Declares the messages of the market data rings in a C header.
+///
+/// # Errors
+/// Returns an error if the C layout of a message differs from the Rust one.
+pub fn declare_c_types(header: &mut CHeader) -> Result<(), ShmError> {
+    header.section("Market data messages (ctl-feed)");
+    header.define("CTL_RAW_MESSAGE_SIZE", RAW_MESSAGE_SIZE as u64);
+    header.define("CTL_MIN_SLOT_SIZE", MIN_SLOT_SIZE as u64);
+    header.define("CTL_UNKNOWN_SYMBOL_ID", u64::from(UNKNOWN_SYMBOL_ID));
+    header.define("CTL_STATS_WINDOWS", STATS_WINDOWS_MS.len() as u64);
+    header.define("CTL_SIGNAL_FLOW_WINDOWS", SIGNAL_FLOW_WINDOWS_MS.len() as u64);
+    header.enumeration(
+        "CTL_EVENT_TYPE",
+        &[
+            ("UNKNOWN", EventType::Unknown as u64),
+            ("BOOK_TICKER", EventType::BookTicker as u64),
+            ("TRADE", EventType::Trade as u64),
+            ("AGG_TRADE", EventType::AggTrade as u64),
+            ("DEPTH", EventType::Depth as u64),
+            ("TRADE_STATS", EventType::TradeStats as u64),
+            ("CANDLE", EventType::Candle as u64),
+            ("SIGNAL", EventType::Signal as u64),
+        ],
+    );
+    header.enumeration(
+        "CTL_MEDIUM",
+        &[
+            ("UNKNOWN", MediumTag::Unknown as u64),
+            ("JSON", MediumTag::Json as u64),
+            ("SBE", MediumTag::Sbe as u64),
+            ("FIX", MediumTag::Fix as u64),
+        ],
+    );
+
+    let message_header = c_struct!(MessageHeader as "ctl_message_header" {
+        symbol_id: "uint32_t",
+        event_type: "uint8_t",
+        medium: "uint8_t",
+        latency_group: "uint8_t",
+        checksum: "uint32_t",
+        fragment_index: "uint16_t",
+        continued: "uint8_t",
+        backfill: "uint8_t",
+        seq: "uint64_t",
+        ts_ms: "uint64_t",
+        published_ns: "uint64_t",
+        trace_id: "uint64_t",
+        span_id: "uint64_t",
+    });
+    header.structure("The header published in front of every message.", message_header)?;
+    let fixed = c_struct!(FixedPoint as "ctl_fixed_point" {
+        price: "int64_t",
+        qty: "int64_t",
+        ask_price: "int64_t",
+        ask_qty: "int64_t",
+        tick_exponent: "uint8_t",
+        step_exponent: "uint8_t",
+        scaled: "uint8_t",
+    });
+    header.structure("The prices and quantities of a message, in units of its tick and step.", fixed)?;
+    let raw = c_struct!(RawMessage as "ctl_raw_message" {
+        header: "ctl_message_header",
+        fixed: "ctl_fixed_point",
+        data: "uint8_t"[RAW_MESSAGE_SIZE],
+    });
+    declare_c_message::<RawMessage>(header, "A raw message, its payload zero-padded.", raw)?;
+
+    let window = c_struct!(WindowStats as "ctl_window_stats" {
+        window_ms: "uint64_t",
+        vwap: "double",
+        notional: "double",
+        volume: "double",
+        trade_count: "uint64_t",
+    });
+    header.structure("Trade statistics over a rolling window.", window)?;
+    let stats = c_struct!(TradeStatsMessage as "ctl_trade_stats_message" {
+        header: "ctl_message_header",
+        symbol_id: "uint32_t",
+        trade_time_ms: "uint64_t",
+        windows: "ctl_window_stats"[STATS_WINDOWS_MS.len()],
+    });
+    declare_c_message::<TradeStatsMessage>(header, "Rolling trade statistics of a symbol.", stats)?;
+
+    let candle = c_struct!(CandleMessage as "ctl_candle_message" {
+        header: "ctl_message_header",
+        symbol_id: "uint32_t",
+        interval_ms: "uint64_t",
+        open_time_ms: "uint64_t",
+        close_time_ms: "uint64_t",
+        open: "double",
+        high: "double",
+        low: "double",
+        close: "double",
+        volume: "double",
+        notional: "double",
+        trade_count: "uint64_t",
+    });
+    declare_c_message::<CandleMessage>(header, "A completed OHLCV bar of a symbol.", candle)?;
+
+    let signal = c_struct!(SignalMessage as "ctl_signal_message" {
+        header: "ctl_message_header",
+        symbol_id: "uint32_t",
+        book_levels: "uint32_t",
+        update_id: "uint64_t",
+        trade_time_ms: "uint64_t",
+        bid_price: "double",
+        ask_price: "double",
+        mid_price: "double",
+        microprice: "double",
+        top_imbalance: "double",
+        book_imbalance: "double",
+        flow_imbalance: "double"[SIGNAL_FLOW_WINDOWS_MS.len()],
+    });
+    declare_c_message::<SignalMessage>(header, "Derived microstructure signals of a symbol.", signal)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ctl_shm::ShmMessage;
+
+    #[test]
+    fn test_declare_c_types() {
+        let mut header = CHeader::new("CTL_TEST_H");
+        declare_c_types(&mut header).unwrap();
+        let rendered = header.render();
+        assert!(rendered.contains("#define CTL_EVENT_TYPE_CANDLE 6\n"));
+        assert!(rendered.contains("CTL_STATIC_ASSERT(sizeof(ctl_message_header) == 56, "));
+        assert!(rendered.contains("CTL_STATIC_ASSERT(offsetof(ctl_raw_message, data) == 96, "));
+        assert!(rendered.contains("    ctl_window_stats windows[3];\n"));
+        assert!(rendered.contains("    double flow_imbalance[3];\n"));
+        assert!(rendered.contains("#define CTL_EVENT_TYPE_SIGNAL 7\n"));
+        let hash = format!("#define CTL_RAW_MESSAGE_LAYOUT_HASH UINT64_C({:#018x})", RawMessage::LAYOUT_HASH);
+        assert!(rendered.contains(&hash));
+    }
+}
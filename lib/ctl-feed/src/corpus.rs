@@ -0,0 +1,151 @@
+//! Tests of the payload parsers against a corpus of captured Binance payloads.
+//!
+//! The corpus, under `testdata/payloads`, holds one raw payload per line: the
+//! bookTicker, trade, aggTrade and depth streams, the responses and errors of
+//! the requests, and the combined-stream wrappers. The property tests check the
+//! parsers never panic on arbitrary or mangled payloads, producing either a
+//! message or an error.
+
+use ctl_websocket::WSResponse;
+use proptest::prelude::*;
+
+use crate::{
+    payload_symbol, DummyParser, DummyParserError, MediumTag, RawMessage, TopSnapshot, RAW_MESSAGE_SIZE,
+};
+
+const BOOK_TICKER: &str = include_str!("../testdata/payloads/book_ticker.jsonl");
+const TRADE: &str = include_str!("../testdata/payloads/trade.jsonl");
+const AGG_TRADE: &str = include_str!("../testdata/payloads/agg_trade.jsonl");
+const DEPTH: &str = include_str!("../testdata/payloads/depth.jsonl");
+const RESPONSES: &str = include_str!("../testdata/payloads/responses.jsonl");
+const COMBINED: &str = include_str!("../testdata/payloads/combined.jsonl");
+
+/// Returns the payloads of a corpus file.
+fn payloads(corpus: &'static str) -> impl Iterator<Item = &'static [u8]> {
+    corpus.lines().filter(|line| !line.is_empty()).map(str::as_bytes)
+}
+
+/// Returns every payload of the corpus.
+fn all_payloads() -> Vec<&'static [u8]> {
+    [BOOK_TICKER, TRADE, AGG_TRADE, DEPTH, RESPONSES, COMBINED]
+        .into_iter()
+        .flat_map(payloads)
+        .collect()
+}
+
+/// Parses a payload with every parser, checking the message of the raw parser.
+fn parse_all(data: &[u8]) {
+    let mut message = RawMessage::default();
+    match DummyParser::new(MediumTag::Json).fill_raw(data, &mut message) {
+        Ok(()) => {
+            assert_eq!(&message.data[..data.len()], data);
+            assert!(message.data[data.len()..].iter().all(|&b| b == 0));
+            assert_eq!(message.header.medium(), MediumTag::Json);
+        }
+        Err(DummyParserError::Oversized { len, max }) => assert!(len == data.len() && len > max),
+        Err(DummyParserError::General) => assert!(std::str::from_utf8(data).is_err()),
+        Err(e) => panic!("unexpected error {}", e),
+    }
+    let _ = TopSnapshot::from_book_ticker(data);
+    let _ = payload_symbol(data);
+    if WSResponse::is_response(data) {
+        let _ = serde_json::from_slice::<WSResponse>(data);
+    }
+}
+
+#[test]
+fn test_book_ticker_corpus() {
+    for data in payloads(BOOK_TICKER) {
+        let (symbol, top) = TopSnapshot::from_book_ticker(data).unwrap();
+        assert_eq!(Some(symbol), payload_symbol(data));
+        assert!(top.bid_price > 0.0 && top.bid_price < top.ask_price);
+    }
+    // Only bookTicker payloads carry a Top
+    for data in payloads(TRADE).chain(payloads(DEPTH)).chain(payloads(RESPONSES)).chain(payloads(COMBINED)) {
+        assert_eq!(TopSnapshot::from_book_ticker(data), None);
+    }
+}
+
+#[test]
+fn test_payload_symbol_corpus() {
+    for data in payloads(TRADE).chain(payloads(AGG_TRADE)).chain(payloads(COMBINED)) {
+        assert!(payload_symbol(data).is_some());
+    }
+    // The partial depth snapshots and the responses carry no symbol
+    assert_eq!(payloads(DEPTH).filter(|data| payload_symbol(data).is_some()).count(), 2);
+    assert!(payloads(RESPONSES).all(|data| payload_symbol(data).is_none()));
+}
+
+#[test]
+fn test_responses_corpus() {
+    for data in payloads(RESPONSES) {
+        assert!(WSResponse::is_response(data));
+        serde_json::from_slice::<WSResponse>(data).unwrap();
+    }
+    // Stream payloads, combined or not, are never taken for responses
+    for corpus in [BOOK_TICKER, TRADE, AGG_TRADE, DEPTH, COMBINED] {
+        assert!(payloads(corpus).all(|data| !WSResponse::is_response(data)));
+    }
+}
+
+#[test]
+fn test_raw_parser_corpus() {
+    for data in all_payloads() {
+        parse_all(data);
+    }
+    // The 10-level depth snapshot exceeds the raw message buffer
+    let mut message = RawMessage::default();
+    let oversized: Vec<_> = payloads(DEPTH).filter(|data| data.len() > RAW_MESSAGE_SIZE).collect();
+    assert_eq!(oversized.len(), 1);
+    assert!(matches!(
+        DummyParser::default().fill_raw(oversized[0], &mut message),
+        Err(DummyParserError::Oversized { .. })
+    ));
+}
+
+#[test]
+fn test_slot_size_corpus() {
+    // The book tickers fit a 128 byte slot, the larger payloads are rejected
+    let parser = DummyParser::default().with_slot_size(128);
+    let mut message = RawMessage::default();
+    for data in payloads(BOOK_TICKER) {
+        parser.fill_raw(data, &mut message).unwrap();
+        assert_eq!(&message.data[..data.len()], data);
+    }
+    let larger: Vec<_> = all_payloads().into_iter().filter(|data| data.len() > 128).collect();
+    assert!(!larger.is_empty());
+    for data in larger {
+        assert!(matches!(
+            parser.fill_raw(data, &mut message),
+            Err(DummyParserError::Oversized { max: 128, .. })
+        ));
+    }
+}
+
+proptest! {
+    #[test]
+    fn prop_arbitrary_bytes(data in proptest::collection::vec(any::<u8>(), 0..2 * RAW_MESSAGE_SIZE)) {
+        parse_all(&data);
+    }
+
+    #[test]
+    fn prop_truncated_payloads(index in any::<prop::sample::Index>(), cut in any::<prop::sample::Index>()) {
+        let payloads = all_payloads();
+        let data = index.get(&payloads);
+        parse_all(&data[..cut.index(data.len() + 1)]);
+    }
+
+    #[test]
+    fn prop_mutated_payloads(
+        index in any::<prop::sample::Index>(),
+        mutations in proptest::collection::vec((any::<prop::sample::Index>(), any::<u8>()), 1..8),
+    ) {
+        let payloads = all_payloads();
+        let mut data = index.get(&payloads).to_vec();
+        for (position, byte) in mutations {
+            let position = position.index(data.len());
+            data[position] = byte;
+        }
+        parse_all(&data);
+    }
+}
@@ -0,0 +1,228 @@
+//! Deduplication of the events of the feeds.
+//!
+//! The same event can be received twice: from both lines of an arbitrated feed,
+//! replayed on a resubscribe, or recorded twice in a session. A `Deduper`
+//! remembers the IDs of the events seen per key (the symbol ID and event type),
+//! the trade IDs of the trades and the order book update IDs of the updates,
+//! within a bounded window behind the highest ID seen. An event arriving late,
+//! out of order, is told apart from a copy as long as it is within the window;
+//! an event behind the window can't be and is dropped as stale.
+
+use std::hash::Hash;
+
+use hashbrown::HashMap;
+
+use crate::{payload_event_id, MessageHeader};
+
+/// The IDs remembered behind the highest one of a key, unless configured.
+pub const DEFAULT_DEDUP_WINDOW: u64 = 4096;
+
+/// The outcome of an event checked by a `Deduper`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Dedup {
+    /// The first copy of the event.
+    New,
+    /// A copy of an event already seen.
+    Duplicate,
+    /// An event behind the window, not told apart from a copy.
+    Stale,
+}
+
+/// The IDs seen of a key, one bit per ID within the window.
+#[derive(Debug, Clone)]
+struct SeenIds {
+    /// The highest ID seen.
+    high: u64,
+    /// The bits of the IDs seen, indexed by the ID modulo their number.
+    bits: Vec<u64>,
+}
+
+impl SeenIds {
+    fn new(id: u64, words: usize) -> Self {
+        let mut seen = Self { high: id, bits: vec![0; words] };
+        seen.set(id);
+        seen
+    }
+
+    /// The number of IDs the bits hold.
+    fn capacity(&self) -> u64 {
+        self.bits.len() as u64 * 64
+    }
+
+    fn bit(&self, id: u64) -> (usize, u64) {
+        let index = id % self.capacity();
+        ((index / 64) as usize, 1 << (index % 64))
+    }
+
+    fn set(&mut self, id: u64) {
+        let (word, mask) = self.bit(id);
+        self.bits[word] |= mask;
+    }
+
+    fn clear(&mut self, id: u64) {
+        let (word, mask) = self.bit(id);
+        self.bits[word] &= !mask;
+    }
+
+    fn is_set(&self, id: u64) -> bool {
+        let (word, mask) = self.bit(id);
+        self.bits[word] & mask != 0
+    }
+
+    /// Records an ID, returning whether it is new within `window` IDs of the highest one.
+    ///
+    /// LATENCY: FAST_PATH
+    fn check(&mut self, id: u64, window: u64) -> Dedup {
+        if id > self.high {
+            // The bits of the IDs skipped are reused from those fallen behind
+            if id - self.high >= self.capacity() {
+                self.bits.fill(0);
+            } else {
+                for skipped in self.high + 1..id {
+                    self.clear(skipped);
+                }
+            }
+            self.high = id;
+            self.set(id);
+            return Dedup::New;
+        }
+        if self.high - id >= window {
+            return Dedup::Stale;
+        }
+        if self.is_set(id) {
+            return Dedup::Duplicate;
+        }
+        self.set(id);
+        Dedup::New
+    }
+}
+
+/// Tells the first copy of each event apart by its ID, within a bounded window per key.
+#[derive(Debug, Clone)]
+pub struct Deduper<K = (u32, u8)> {
+    /// The IDs seen, by key.
+    seen: HashMap<K, SeenIds>,
+    /// The IDs remembered behind the highest one of a key.
+    window: u64,
+    /// Number of copies of events already seen.
+    duplicates: u64,
+    /// Number of events behind the window.
+    stale: u64,
+}
+
+impl<K: Hash + Eq> Deduper<K> {
+    /// Creates a deduper remembering `window` IDs behind the highest one of each key.
+    pub fn new(window: u64) -> Self {
+        Self { seen: HashMap::new(), window: window.max(1), duplicates: 0, stale: 0 }
+    }
+
+    /// Returns the number of IDs remembered behind the highest one of a key.
+    pub fn window(&self) -> u64 {
+        self.window
+    }
+
+    /// Returns the number of copies of events already seen.
+    pub fn duplicates(&self) -> u64 {
+        self.duplicates
+    }
+
+    /// Returns the number of events behind the window.
+    pub fn stale(&self) -> u64 {
+        self.stale
+    }
+
+    /// Checks the event `id` of a key, recording it.
+    ///
+    /// LATENCY: FAST_PATH
+    pub fn check(&mut self, key: K, id: u64) -> Dedup {
+        let dedup = match self.seen.get_mut(&key) {
+            Some(seen) => seen.check(id, self.window),
+            None => {
+                self.seen.insert(key, SeenIds::new(id, self.window.div_ceil(64) as usize));
+                Dedup::New
+            }
+        };
+        match dedup {
+            Dedup::New => {}
+            Dedup::Duplicate => self.duplicates += 1,
+            Dedup::Stale => self.stale += 1,
+        }
+        dedup
+    }
+
+    /// Returns true if the event `id` of a key is its first copy, recording it.
+    ///
+    /// LATENCY: FAST_PATH
+    pub fn admit(&mut self, key: K, id: u64) -> bool {
+        self.check(key, id) == Dedup::New
+    }
+}
+
+impl Deduper {
+    /// Returns true if the JSON payload of a message is the first copy of its
+    /// event, keyed by its symbol ID and event type. The events without an ID
+    /// always pass.
+    ///
+    /// LATENCY: FAST_PATH
+    pub fn admit_payload(&mut self, header: &MessageHeader, payload: &[u8]) -> bool {
+        match payload_event_id(header.event_type(), payload) {
+            Some(id) => self.admit((header.symbol_id, header.event_type), id),
+            None => true,
+        }
+    }
+}
+
+impl<K: Hash + Eq> Default for Deduper<K> {
+    fn default() -> Self {
+        Self::new(DEFAULT_DEDUP_WINDOW)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::EventType;
+
+    #[test]
+    fn test_dedup_window() {
+        let mut deduper: Deduper = Deduper::new(128);
+        let key = (1, EventType::Trade as u8);
+        assert_eq!(deduper.check(key, 100), Dedup::New);
+        assert_eq!(deduper.check(key, 100), Dedup::Duplicate);
+        assert_eq!(deduper.check(key, 103), Dedup::New);
+        // Late, out of order, but not seen yet
+        assert_eq!(deduper.check(key, 101), Dedup::New);
+        assert_eq!(deduper.check(key, 101), Dedup::Duplicate);
+        assert_eq!(deduper.check(key, 102), Dedup::New);
+        // Other keys are deduplicated apart
+        assert_eq!(deduper.check((2, EventType::Trade as u8), 100), Dedup::New);
+
+        // The window slides past the old IDs, their bits reused
+        assert_eq!(deduper.check(key, 300), Dedup::New);
+        assert_eq!(deduper.check(key, 103), Dedup::Stale);
+        assert_eq!(deduper.check(key, 173), Dedup::New);
+        assert_eq!(deduper.check(key, 172), Dedup::Stale);
+        assert_eq!(deduper.check(key, 301), Dedup::New);
+        assert_eq!(deduper.check(key, 174), Dedup::New);
+        assert_eq!(deduper.check(key, 174), Dedup::Duplicate);
+        assert_eq!((deduper.duplicates(), deduper.stale()), (3, 2));
+    }
+
+    #[test]
+    fn test_admit_payload() {
+        let mut deduper = Deduper::default();
+        let header = MessageHeader::new(EventType::BookTicker, 1);
+        let ticker = br#"{"u":400900217,"s":"BNBUSDT","b":"25.35","a":"25.36"}"#;
+        assert!(deduper.admit_payload(&header, ticker));
+        assert!(!deduper.admit_payload(&header, ticker));
+        // The trades of the symbol are keyed apart from its updates
+        let trade = br#"{"e":"trade","s":"BNBUSDT","t":400900217,"p":"25.35"}"#;
+        assert!(deduper.admit_payload(&MessageHeader::new(EventType::Trade, 1), trade));
+        // Without an ID, every copy passes
+        let unkeyed = br#"{"s":"BNBUSDT"}"#;
+        assert!(deduper.admit_payload(&header, unkeyed));
+        assert!(deduper.admit_payload(&header, unkeyed));
+        assert_eq!(deduper.duplicates(), 1);
+    }
+}
@@ -0,0 +1,243 @@
+//! Discovery of the rings of the instance.
+//!
+//! Every ring created by ctl-resource-manager is registered in the metrics
+//! region with the layout hash of its messages, which tags the element type of
+//! the ring. The consumers enumerate the rings matching a name pattern, e.g.
+//! `TOP_*_PS`, instead of reconstructing their names from the configuration,
+//! and look them up on the DPDK runtime with their layout checked, or open the
+//! shared memory rings of a resource manager built with `shm-rings`.
+
+use std::sync::atomic::Ordering;
+
+use ctl_core::{AlertMessage, ControlMessage};
+use ctl_shm::{ShmMessage, ShmRing};
+use dpdk::{DpdkEnv, DpdkPubSubRing};
+
+use crate::{CandleMessage, MetricsRegion, RawMessage, RingError, SignalMessage, TradeStatsMessage};
+
+/// A ring name pattern, where `*` matches any (possibly empty) run of characters.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RingPattern(String);
+
+impl RingPattern {
+    /// Creates a pattern, e.g. `TOP_*_PS`.
+    pub fn new(pattern: &str) -> Self {
+        Self(pattern.to_string())
+    }
+
+    /// Returns true if the ring name matches the pattern.
+    pub fn matches(&self, name: &str) -> bool {
+        let mut parts = self.0.split('*');
+        // Without a wildcard, the pattern is the name itself
+        let first = parts.next().unwrap_or_default();
+        let Some(mut rest) = name.strip_prefix(first) else {
+            return false;
+        };
+        let mut parts: Vec<&str> = parts.collect();
+        let Some(last) = parts.pop() else {
+            return rest.is_empty();
+        };
+        for part in parts {
+            match rest.find(part) {
+                Some(index) => rest = &rest[index + part.len()..],
+                None => return false,
+            }
+        }
+        rest.len() >= last.len() && rest.ends_with(last)
+    }
+}
+
+/// A ring registered in the metrics region.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiscoveredRing {
+    /// The ring name.
+    pub name: String,
+    /// The ring size.
+    pub ring_size: u64,
+    /// The layout hash of the messages of the ring, tagging its element type.
+    pub layout_hash: u64,
+    /// The payload bytes of the messages of a raw ring, zero for the other rings.
+    pub slot_size: u64,
+}
+
+impl DiscoveredRing {
+    /// Returns true if the ring holds `T` messages.
+    pub fn holds<T: ShmMessage>(&self) -> bool {
+        self.layout_hash == T::LAYOUT_HASH
+    }
+}
+
+impl MetricsRegion {
+    /// Returns the registered rings matching a pattern, sorted by name.
+    ///
+    /// LATENCY: SLOW_PATH
+    pub fn discover(&self, pattern: &RingPattern) -> Vec<DiscoveredRing> {
+        let mut rings: Vec<DiscoveredRing> = self
+            .registered()
+            .map(|ring| DiscoveredRing {
+                name: ring.name(),
+                ring_size: ring.ring_size.load(Ordering::Acquire),
+                layout_hash: ring.layout_hash.load(Ordering::Acquire),
+                slot_size: ring.slot_size.load(Ordering::Acquire),
+            })
+            .filter(|ring| pattern.matches(&ring.name))
+            .collect();
+        rings.sort_by(|a, b| a.name.cmp(&b.name));
+        rings
+    }
+}
+
+/// A message type whose rings are looked up on the DPDK runtime.
+pub trait DpdkLookup: ShmMessage + Sized {
+    /// Looks up the ring of `name` holding the messages.
+    fn pubsub_lookup(dpdk_env: &DpdkEnv, name: &str) -> Result<DpdkPubSubRing<Self>, RingError>;
+}
+
+// The DPDK lookup is implemented per message type, like the DPDK publishers
+// of the `ring` module.
+macro_rules! dpdk_lookup {
+    ($($message:ty),*) => {
+        $(
+            impl DpdkLookup for $message {
+                fn pubsub_lookup(dpdk_env: &DpdkEnv, name: &str) -> Result<DpdkPubSubRing<Self>, RingError> {
+                    dpdk_env
+                        .pubsub_lookup::<$message>(name)
+                        .map_err(|e| RingError::Lookup { ring: name.to_string(), reason: e.to_string() })
+                }
+            }
+        )*
+    };
+}
+
+dpdk_lookup!(RawMessage, TradeStatsMessage, CandleMessage, SignalMessage, AlertMessage, ControlMessage);
+
+/// The rings of the DPDK runtime, discovered through the metrics region.
+pub struct RingDirectory<'a> {
+    /// The DPDK runtime holding the rings.
+    dpdk_env: &'a DpdkEnv,
+    /// The metrics region registering the rings.
+    metrics: &'a MetricsRegion,
+}
+
+impl<'a> RingDirectory<'a> {
+    /// Creates the directory of the rings of the runtime registered in the metrics region.
+    pub fn new(dpdk_env: &'a DpdkEnv, metrics: &'a MetricsRegion) -> Self {
+        Self { dpdk_env, metrics }
+    }
+
+    /// Returns the rings matching a pattern, sorted by name.
+    pub fn discover(&self, pattern: &RingPattern) -> Vec<DiscoveredRing> {
+        self.metrics.discover(pattern)
+    }
+
+    /// Returns the rings of `T` messages matching a pattern, sorted by name.
+    pub fn discover_of<T: ShmMessage>(&self, pattern: &RingPattern) -> Vec<DiscoveredRing> {
+        let mut rings = self.discover(pattern);
+        rings.retain(|ring| ring.holds::<T>());
+        rings
+    }
+
+    /// Looks up a ring of `T` messages, checking its layout.
+    ///
+    /// LATENCY: SLOW_PATH
+    pub fn lookup<T: DpdkLookup>(&self, name: &str) -> Result<DpdkPubSubRing<T>, RingError> {
+        self.metrics.check_layout::<T>(name)?;
+        T::pubsub_lookup(self.dpdk_env, name)
+    }
+}
+
+/// The shared memory rings of ctl-shm, discovered through the metrics region.
+pub struct ShmRingDirectory<'a> {
+    /// The metrics region registering the rings.
+    metrics: &'a MetricsRegion,
+}
+
+impl<'a> ShmRingDirectory<'a> {
+    /// Creates the directory of the shared memory rings registered in the metrics region.
+    pub fn new(metrics: &'a MetricsRegion) -> Self {
+        Self { metrics }
+    }
+
+    /// Returns the rings matching a pattern, sorted by name.
+    pub fn discover(&self, pattern: &RingPattern) -> Vec<DiscoveredRing> {
+        self.metrics.discover(pattern)
+    }
+
+    /// Returns the rings of `T` messages matching a pattern, sorted by name.
+    pub fn discover_of<T: ShmMessage>(&self, pattern: &RingPattern) -> Vec<DiscoveredRing> {
+        let mut rings = self.discover(pattern);
+        rings.retain(|ring| ring.holds::<T>());
+        rings
+    }
+
+    /// Opens a ring of `T` messages, checking its layout.
+    ///
+    /// LATENCY: SLOW_PATH
+    pub fn lookup<T: ShmMessage>(&self, name: &str) -> Result<ShmRing<T>, RingError> {
+        self.metrics.check_layout::<T>(name)?;
+        ShmRing::open(name).map_err(|e| RingError::Lookup { ring: name.to_string(), reason: e.to_string() })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use ctl_shm::ShmRegion;
+
+    #[test]
+    fn test_ring_pattern() {
+        let pattern = RingPattern::new("TOP_*_PS");
+        assert!(pattern.matches("TOP_0_PS"));
+        assert!(pattern.matches("TOP_1234_PS"));
+        assert!(pattern.matches("TOP__PS"));
+        assert!(!pattern.matches("TRADE_0_PS"));
+        assert!(!pattern.matches("TOP_0_PSX"));
+        // The prefix and suffix don't overlap
+        assert!(!RingPattern::new("TOP_*_TOP_").matches("TOP_"));
+
+        assert!(RingPattern::new("*").matches("CTL_ALERTS"));
+        assert!(RingPattern::new("*_*_PS").matches("KLINE_3_PS"));
+        assert!(RingPattern::new("CTL_ALERTS").matches("CTL_ALERTS"));
+        assert!(!RingPattern::new("CTL_ALERTS").matches("CTL_ALERTS_2"));
+    }
+
+    #[test]
+    fn test_discover() {
+        let name = format!("ctl_feed_discovery_test_{}", std::process::id());
+        let region = ShmRegion::<MetricsRegion>::create(&name).unwrap();
+        region.register_ring("TRADE_1_PS", 1024, RawMessage::LAYOUT_HASH).unwrap();
+        region.register_ring("TOP_1_PS", 1024, RawMessage::LAYOUT_HASH).unwrap();
+        region.register_ring("TOP_0_PS", 2048, RawMessage::LAYOUT_HASH).unwrap();
+        region.register_ring("KLINE_0_PS", 256, CandleMessage::LAYOUT_HASH).unwrap();
+
+        let tops = region.discover(&RingPattern::new("TOP_*_PS"));
+        let names: Vec<&str> = tops.iter().map(|ring| ring.name.as_str()).collect();
+        assert_eq!(names, ["TOP_0_PS", "TOP_1_PS"]);
+        assert_eq!(tops[0].ring_size, 2048);
+
+        let all = region.discover(&RingPattern::new("*_PS"));
+        assert_eq!(all.len(), 4);
+        let candles: Vec<&DiscoveredRing> = all.iter().filter(|ring| ring.holds::<CandleMessage>()).collect();
+        assert_eq!(candles.len(), 1);
+        assert_eq!(candles[0].name, "KLINE_0_PS");
+    }
+
+    #[test]
+    fn test_shm_lookup() {
+        let name = format!("ctl_feed_discovery_shm_test_{}", std::process::id());
+        let region = ShmRegion::<MetricsRegion>::create(&name).unwrap();
+        let ring_name = format!("TOP_{}_PS", std::process::id());
+        let _ring = ShmRing::<RawMessage>::create(&ring_name, 16).unwrap();
+        region.register_ring(&ring_name, 16, RawMessage::LAYOUT_HASH).unwrap();
+
+        let directory = ShmRingDirectory::new(&region);
+        let rings = directory.discover_of::<RawMessage>(&RingPattern::new("TOP_*_PS"));
+        assert_eq!(rings.len(), 1);
+        assert!(directory.discover_of::<CandleMessage>(&RingPattern::new("TOP_*_PS")).is_empty());
+        assert_eq!(directory.lookup::<RawMessage>(&ring_name).unwrap().capacity(), 16);
+        // The layout of the messages is checked before opening the ring
+        assert!(matches!(directory.lookup::<CandleMessage>(&ring_name), Err(RingError::LayoutMismatch { .. })));
+        assert!(directory.lookup::<RawMessage>("TOP_MISSING_PS").is_err());
+    }
+}
@@ -0,0 +1,254 @@
+//! Fragmentation of the payloads larger than the slot of their ring.
+//!
+//! A payload exceeding the slot size of its raw ring, e.g. a depth snapshot on
+//! a ring sized for book tickers, is published as consecutive fragments of one
+//! slot each instead of being dropped. Every fragment carries the header of
+//! the payload, numbered by its `fragment_index` and flagged as `continued` but
+//! for the last one. The producer publishes the leading fragments through a
+//! `FragmentSink` and hands the last one to the feedgroup worker, so the
+//! fragments of a payload are published in order.
+//!
+//! The fragments of different payloads interleave in a ring published to by
+//! several workers, so the consumers reassemble them by symbol, event and
+//! medium with a `Reassembler`: a payload is either delivered whole or, when a
+//! fragment was lost (e.g. after being sped past), dropped and counted, never
+//! truncated.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::mem;
+use std::sync::Arc;
+
+use crate::{RawMessage, RingError, RingPublisher, RAW_MESSAGE_SIZE};
+
+/// Maximum number of fragments of a payload.
+pub const MAX_FRAGMENTS: usize = 16;
+
+/// Returns the number of fragments of a payload of `len` bytes in slots of
+/// `slot_size` bytes.
+pub fn fragment_count(len: usize, slot_size: usize) -> usize {
+    len.div_ceil(slot_size).max(1)
+}
+
+/// Returns the payload of a slot, up to its zero padding.
+///
+/// LATENCY: FAST_PATH
+pub fn slot_payload(slot: &[u8]) -> &[u8] {
+    let len = slot.iter().position(|&b| b == 0).unwrap_or(slot.len());
+    &slot[..len]
+}
+
+/// The ring the parser publishes the leading fragments of a payload to.
+#[derive(Clone)]
+pub struct FragmentSink(Arc<dyn RingPublisher<RawMessage> + Send + Sync>);
+
+impl FragmentSink {
+    /// Creates the sink publishing to a ring, the one of the feedgroup.
+    pub fn new<R: RingPublisher<RawMessage> + Send + Sync + 'static>(ring: R) -> Self {
+        Self(Arc::new(ring))
+    }
+
+    /// Publishes a leading fragment.
+    ///
+    /// LATENCY: FAST_PATH
+    pub fn publish(&self, fragment: &RawMessage) -> Result<(), RingError> {
+        self.0.publish(fragment)
+    }
+}
+
+impl fmt::Debug for FragmentSink {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("FragmentSink")
+    }
+}
+
+/// The result of pushing a message to a `Reassembler`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Reassembly<'a> {
+    /// The whole payload, of an unfragmented message or of the last fragment.
+    Complete(&'a [u8]),
+    /// A leading fragment, buffered until the last one.
+    Partial,
+    /// A fragment of a payload missing one of its fragments, dropped.
+    Dropped,
+}
+
+/// The payload being reassembled from the fragments of a symbol, event and medium.
+#[derive(Debug, Default)]
+struct Partial {
+    /// The leading fragments received, one slot each.
+    data: Vec<u8>,
+    /// Whether a fragment of the payload was lost, the others being dropped
+    /// until the last one.
+    broken: bool,
+}
+
+/// Reassembles the payloads of a raw ring from their fragments.
+#[derive(Debug)]
+pub struct Reassembler {
+    /// The slot size of the ring.
+    slot_size: usize,
+    /// The payloads being reassembled, by symbol ID, event type and medium.
+    partials: HashMap<(u32, u8, u8), Partial>,
+    /// The last payload reassembled.
+    complete: Vec<u8>,
+    /// Number of payloads dropped for a lost fragment.
+    dropped: u64,
+}
+
+impl Reassembler {
+    /// Creates a reassembler of the fragments of a ring of `slot_size` byte slots.
+    pub fn new(slot_size: usize) -> Self {
+        Self {
+            slot_size: slot_size.min(RAW_MESSAGE_SIZE),
+            partials: HashMap::new(),
+            complete: Vec::new(),
+            dropped: 0,
+        }
+    }
+
+    /// Returns the number of payloads dropped for a lost fragment.
+    pub fn dropped(&self) -> u64 {
+        self.dropped
+    }
+
+    /// Drops the payloads being reassembled, e.g. once sped past, their
+    /// remaining fragments being dropped as they arrive.
+    ///
+    /// LATENCY: SLOW_PATH
+    pub fn reset(&mut self) {
+        for partial in self.partials.values_mut() {
+            if !partial.data.is_empty() {
+                self.dropped += 1;
+                partial.data.clear();
+                partial.broken = true;
+            }
+        }
+    }
+
+    /// Pushes a consumed message, returning its payload once complete.
+    ///
+    /// LATENCY: FAST_PATH
+    pub fn push<'a>(&'a mut self, message: &'a RawMessage) -> Reassembly<'a> {
+        let header = &message.header;
+        let slot = &message.data[..self.slot_size];
+        if !header.is_fragment() {
+            return Reassembly::Complete(slot_payload(slot));
+        }
+
+        let index = header.fragment_index as usize;
+        let last = !header.is_continued();
+        let partial = self.partials.entry((header.symbol_id, header.event_type, header.medium)).or_default();
+        if index == 0 {
+            // A new payload, the previous one lost its last fragment
+            if !partial.data.is_empty() && !partial.broken {
+                self.dropped += 1;
+            }
+            partial.data.clear();
+            partial.broken = false;
+        } else if partial.broken || index != partial.data.len() / self.slot_size {
+            // A fragment was lost, the payload is dropped once
+            if !partial.broken {
+                self.dropped += 1;
+            }
+            partial.data.clear();
+            partial.broken = !last;
+            return Reassembly::Dropped;
+        }
+
+        if !last {
+            partial.data.extend_from_slice(slot);
+            return Reassembly::Partial;
+        }
+        partial.data.extend_from_slice(slot_payload(slot));
+        mem::swap(&mut self.complete, &mut partial.data);
+        partial.data.clear();
+        Reassembly::Complete(&self.complete)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{EventType, MessageHeader};
+
+    /// Splits a payload into its fragments, as published by the parser.
+    fn fragments(payload: &[u8], slot_size: usize, symbol_id: u32) -> Vec<RawMessage> {
+        let count = fragment_count(payload.len(), slot_size);
+        payload
+            .chunks(slot_size)
+            .enumerate()
+            .map(|(index, chunk)| {
+                let header = MessageHeader::new(EventType::Depth, symbol_id);
+                let mut message = RawMessage { header, ..Default::default() };
+                message.header.set_fragment(index as u16, index + 1 < count);
+                message.data[..chunk.len()].copy_from_slice(chunk);
+                message
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_fragment_count() {
+        assert_eq!(fragment_count(0, 64), 1);
+        assert_eq!(fragment_count(64, 64), 1);
+        assert_eq!(fragment_count(65, 64), 2);
+        assert_eq!(fragment_count(686, 128), 6);
+    }
+
+    #[test]
+    fn test_reassemble_interleaved() {
+        let first: Vec<u8> = (0..200).map(|i| b'a' + (i % 26) as u8).collect();
+        let second: Vec<u8> = (0..150).map(|i| b'A' + (i % 26) as u8).collect();
+        let first = fragments(&first, 64, 1);
+        let second = fragments(&second, 64, 2);
+        assert_eq!((first.len(), second.len()), (4, 3));
+
+        let mut reassembler = Reassembler::new(64);
+        let mut complete = Vec::new();
+        for message in [&first[0], &second[0], &first[1], &second[1], &first[2], &second[2], &first[3]] {
+            if let Reassembly::Complete(payload) = reassembler.push(message) {
+                complete.push(payload.to_vec());
+            }
+        }
+        assert_eq!(complete.len(), 2);
+        assert_eq!(complete[0].len(), 150);
+        assert_eq!(complete[1].len(), 200);
+        assert_eq!(reassembler.dropped(), 0);
+
+        // The unfragmented messages are complete as is
+        let message = fragments(b"{}", 64, 1);
+        assert_eq!(reassembler.push(&message[0]), Reassembly::Complete(b"{}"));
+    }
+
+    #[test]
+    fn test_lost_fragment() {
+        let payload: Vec<u8> = vec![b'x'; 300];
+        let messages = fragments(&payload, 64, 1);
+        assert_eq!(messages.len(), 5);
+
+        // The payload missing its second fragment is dropped once
+        let mut reassembler = Reassembler::new(64);
+        assert_eq!(reassembler.push(&messages[0]), Reassembly::Partial);
+        for message in &messages[2..] {
+            assert_eq!(reassembler.push(message), Reassembly::Dropped);
+        }
+        assert_eq!(reassembler.dropped(), 1);
+
+        // The next payload is reassembled
+        let mut complete = None;
+        for message in &messages {
+            if let Reassembly::Complete(payload) = reassembler.push(message) {
+                complete = Some(payload.len());
+            }
+        }
+        assert_eq!(complete, Some(300));
+
+        // Sped past in the middle of a payload
+        reassembler.push(&messages[0]);
+        reassembler.reset();
+        assert_eq!(reassembler.push(&messages[1]), Reassembly::Dropped);
+        assert_eq!(reassembler.push(&messages[4]), Reassembly::Dropped);
+        assert_eq!(reassembler.dropped(), 2);
+    }
+}
@@ -0,0 +1,120 @@
+//! Detection of the trades missed by a consumer.
+//!
+//! The trade IDs of a symbol, and its aggregate trade IDs, follow each other
+//! without a hole on the exchange. A jump between the IDs of two trades of a
+//! ring means the trades in between never reached the consumer: lost in the
+//! reconnect window of a feed, or overwritten before the consumer read them
+//! once sped past. A `TradeGapDetector` reports the missing range, for the
+//! consumer to fetch it from the REST API and inject it into the ring flagged
+//! as backfill (see `MessageHeader::is_backfill`).
+
+use hashbrown::HashMap;
+
+use crate::{payload_event_id, EventType, MessageHeader};
+
+/// A range of trades of a symbol missed by a consumer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TradeGap {
+    /// The symbol ID.
+    pub symbol_id: u32,
+    /// The event type, a trade or an aggregated trade.
+    pub event_type: EventType,
+    /// The first missing ID.
+    pub from_id: u64,
+    /// The last missing ID.
+    pub to_id: u64,
+}
+
+impl TradeGap {
+    /// Returns the number of trades missing.
+    pub fn len(&self) -> u64 {
+        self.to_id - self.from_id + 1
+    }
+
+    /// Returns false, a gap missing one trade at least.
+    pub fn is_empty(&self) -> bool {
+        false
+    }
+}
+
+/// Reports the jumps between the IDs of the consecutive trades of each symbol.
+#[derive(Debug, Clone, Default)]
+pub struct TradeGapDetector {
+    /// The last ID received live, by symbol ID and event type.
+    last_ids: HashMap<(u32, u8), u64>,
+    /// Number of gaps reported.
+    gaps: u64,
+}
+
+impl TradeGapDetector {
+    /// Creates a detector having seen no trade yet.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of gaps reported.
+    pub fn gaps(&self) -> u64 {
+        self.gaps
+    }
+
+    /// Observes the JSON payload of a trade message, returning the trades
+    /// missed since the last trade of its symbol, if any.
+    ///
+    /// The first trade of a symbol starts its sequence, and the backfilled and
+    /// older trades don't move it.
+    ///
+    /// LATENCY: FAST_PATH
+    pub fn observe(&mut self, header: &MessageHeader, payload: &[u8]) -> Option<TradeGap> {
+        let event_type = header.event_type();
+        if header.is_backfill() || !matches!(event_type, EventType::Trade | EventType::AggTrade) {
+            return None;
+        }
+        let id = payload_event_id(event_type, payload)?;
+        let last = self.last_ids.entry((header.symbol_id, header.event_type)).or_insert(id);
+        if id <= *last {
+            return None;
+        }
+        let gap = (id > *last + 1).then(|| TradeGap {
+            symbol_id: header.symbol_id,
+            event_type,
+            from_id: *last + 1,
+            to_id: id - 1,
+        });
+        *last = id;
+        self.gaps += gap.is_some() as u64;
+        gap
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trade(id: u64) -> String {
+        format!(r#"{{"e":"trade","s":"BTCUSDT","t":{},"p":"1.0","q":"1.0"}}"#, id)
+    }
+
+    #[test]
+    fn test_trade_gaps() {
+        let mut detector = TradeGapDetector::new();
+        let header = MessageHeader::new(EventType::Trade, 1);
+        assert_eq!(detector.observe(&header, trade(100).as_bytes()), None);
+        assert_eq!(detector.observe(&header, trade(101).as_bytes()), None);
+        let gap = detector.observe(&header, trade(105).as_bytes()).unwrap();
+        assert_eq!(gap, TradeGap { symbol_id: 1, event_type: EventType::Trade, from_id: 102, to_id: 104 });
+        assert_eq!(gap.len(), 3);
+
+        // The backfilled and duplicate trades don't move the sequence
+        let mut backfill = header;
+        backfill.set_backfill();
+        assert_eq!(detector.observe(&backfill, trade(103).as_bytes()), None);
+        assert_eq!(detector.observe(&header, trade(105).as_bytes()), None);
+        assert_eq!(detector.observe(&header, trade(106).as_bytes()), None);
+
+        // The symbols and the other event types are tracked apart
+        assert_eq!(detector.observe(&MessageHeader::new(EventType::Trade, 2), trade(1).as_bytes()), None);
+        let ticker = br#"{"u":1,"s":"BTCUSDT"}"#;
+        assert_eq!(detector.observe(&MessageHeader::new(EventType::BookTicker, 1), ticker), None);
+        assert_eq!(detector.gaps(), 1);
+    }
+}
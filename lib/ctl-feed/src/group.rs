@@ -1,12 +1,18 @@
 use atx_feed::FeedGroup;
+use ctl_fix::FixConn;
 use ctl_websocket::WSConn;
 use derive_more::From;
 
-use crate::{AggTrade, DummyParser, Top, Trade};
+use crate::{AggTrade, Depth, DummyParser, FixParser, SbeParser, Top, Trade};
 
 #[derive(From)]
 pub enum FeedGroups<'a> {
     JsonTop(FeedGroup<'a, WSConn<Top>, Top, DummyParser>),
     JsonTrade(FeedGroup<'a, WSConn<Trade>, Trade, DummyParser>),
     JsonAggTrade(FeedGroup<'a, WSConn<AggTrade>, AggTrade, DummyParser>),
+    JsonDepth(FeedGroup<'a, WSConn<Depth>, Depth, DummyParser>),
+    FixTop(FeedGroup<'a, FixConn<Top>, Top, FixParser>),
+    FixTrade(FeedGroup<'a, FixConn<Trade>, Trade, FixParser>),
+    SbeTop(FeedGroup<'a, WSConn<Top>, Top, SbeParser>),
+    SbeTrade(FeedGroup<'a, WSConn<Trade>, Trade, SbeParser>),
 }
\ No newline at end of file
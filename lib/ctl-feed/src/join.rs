@@ -0,0 +1,144 @@
+//! Conflated state of a consumer attaching to a ring mid-stream.
+//!
+//! A consumer attaching to a ring reads the messages published from then on,
+//! so a restarted strategy would wait for the organic updates of each symbol
+//! before knowing its market. A late joiner instead attaches its consumer first,
+//! then reads the conflated state of its symbols (the latest Top of the
+//! last-value region, the book snapshot of ctl-book) and only then streams the
+//! deltas. The deltas published between the attach and the read are already
+//! in the state, and are skipped by a `JoinGate` on their order book update ID.
+
+use hashbrown::HashMap;
+
+use crate::{EventType, LastTopRegion, MessageHeader, TopSnapshot};
+
+/// Returns the order book update ID of a bookTicker or diff depth payload, its
+/// `u` field, without parsing the payload.
+///
+/// LATENCY: FAST_PATH
+pub fn payload_update_id(data: &[u8]) -> Option<u64> {
+    payload_number(data, b'u')
+}
+
+/// Returns the unsigned integer of the first single-letter `field` of a JSON
+/// payload, `None` if missing or quoted, without parsing the payload.
+///
+/// LATENCY: FAST_PATH
+pub(crate) fn payload_number(data: &[u8], field: u8) -> Option<u64> {
+    let key = [b'"', field, b'"', b':'];
+    let start = data.windows(key.len()).position(|window| window == key)? + key.len();
+    let len = data[start..].iter().position(|b| !b.is_ascii_digit()).unwrap_or(data.len() - start);
+    std::str::from_utf8(&data[start..start + len]).ok()?.parse().ok()
+}
+
+/// Skips the deltas already reflected in the state read by a late joiner.
+///
+/// The update ID of the state of each symbol and event type is recorded once
+/// read, the deltas up to it being skipped, until the first newer one of the
+/// symbol passes the gate.
+#[derive(Debug, Clone, Default)]
+pub struct JoinGate {
+    /// The update ID of the state read, by symbol ID and event type.
+    update_ids: HashMap<(u32, u8), u64>,
+    /// Number of deltas skipped.
+    skipped: u64,
+}
+
+impl JoinGate {
+    /// Creates a gate letting every delta through.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records the update ID of the state of a symbol, the deltas of the event
+    /// type up to it being skipped.
+    pub fn record(&mut self, symbol_id: u32, event_type: EventType, update_id: u64) {
+        let recorded = self.update_ids.entry((symbol_id, event_type as u8)).or_default();
+        *recorded = (*recorded).max(update_id);
+    }
+
+    /// Reads the latest Top of a symbol from the last-value region, recording
+    /// its update ID. Returns `None` if it was never written.
+    ///
+    /// LATENCY: SLOW_PATH
+    pub fn read_top(&mut self, region: &LastTopRegion, symbol_id: u32) -> Option<TopSnapshot> {
+        let top = region.read(symbol_id)?;
+        self.record(symbol_id, EventType::BookTicker, top.update_id);
+        Some(top)
+    }
+
+    /// Returns true once every recorded symbol was passed by a newer delta.
+    pub fn is_open(&self) -> bool {
+        self.update_ids.is_empty()
+    }
+
+    /// Returns the number of deltas skipped.
+    pub fn skipped(&self) -> u64 {
+        self.skipped
+    }
+
+    /// Returns true if a delta is newer than the state read, and is to be
+    /// handled. The deltas without an update ID always pass.
+    ///
+    /// LATENCY: FAST_PATH
+    pub fn admit(&mut self, header: &MessageHeader, payload: &[u8]) -> bool {
+        if self.update_ids.is_empty() {
+            return true;
+        }
+        let key = (header.symbol_id, header.event_type);
+        let Some(&recorded) = self.update_ids.get(&key) else {
+            return true;
+        };
+        let Some(update_id) = payload_update_id(payload) else {
+            return true;
+        };
+        if update_id <= recorded {
+            self.skipped += 1;
+            return false;
+        }
+        self.update_ids.remove(&key);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header(event_type: EventType, symbol_id: u32) -> MessageHeader {
+        MessageHeader::new(event_type, symbol_id)
+    }
+
+    #[test]
+    fn test_payload_update_id() {
+        let ticker = br#"{"u":400900217,"s":"BNBUSDT","b":"25.35190000"}"#;
+        assert_eq!(payload_update_id(ticker), Some(400900217));
+        let depth = br#"{"e":"depthUpdate","E":1,"s":"BNBBTC","U":157,"u":160,"b":[]}"#;
+        assert_eq!(payload_update_id(depth), Some(160));
+        assert_eq!(payload_update_id(br#"{"e":"trade","p":"0.001"}"#), None);
+        assert_eq!(payload_update_id(br#"{"u":12"#), Some(12));
+    }
+
+    #[test]
+    fn test_gate() {
+        let mut gate = JoinGate::new();
+        assert!(gate.is_open());
+        gate.record(1, EventType::BookTicker, 10);
+        gate.record(1, EventType::Depth, 20);
+
+        // The deltas up to the state are skipped, those of other symbols or without update ID pass
+        let ticker = header(EventType::BookTicker, 1);
+        assert!(!gate.admit(&ticker, br#"{"u":9}"#));
+        assert!(!gate.admit(&ticker, br#"{"u":10}"#));
+        assert!(gate.admit(&header(EventType::BookTicker, 2), br#"{"u":5}"#));
+        assert!(gate.admit(&header(EventType::Trade, 1), br#"{"e":"trade"}"#));
+        assert!(gate.admit(&ticker, br#"{"u":11}"#));
+        assert_eq!(gate.skipped(), 2);
+
+        // Once passed, the symbol's deltas are no longer checked
+        assert!(gate.admit(&ticker, br#"{"u":10}"#));
+        assert!(!gate.is_open());
+        assert!(gate.admit(&header(EventType::Depth, 1), br#"{"U":15,"u":21}"#));
+        assert!(gate.is_open());
+    }
+}
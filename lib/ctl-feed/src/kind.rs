@@ -20,6 +20,13 @@ pub struct Trade;
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct AggTrade;
 
+/// Diff Depth feed kind.
+/// This feed provides the price level updates of the order book, at the update speed of the stream.
+/// https://github.com/binance/binance-spot-api-docs/blob/master/web-socket-streams.md#diff-depth-stream
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Depth;
+
 impl FeedKind for Top {}
 impl FeedKind for Trade {}
-impl FeedKind for AggTrade {}
\ No newline at end of file
+impl FeedKind for AggTrade {}
+impl FeedKind for Depth {}
\ No newline at end of file
@@ -0,0 +1,313 @@
+//! Last-value conflation cache for the Top-of-book feed.
+//!
+//! Besides publishing every update to the pub-sub rings, the md-handler
+//! overwrites a per-symbol "latest Top" slot in a shared memory region, created
+//! by ctl-resource-manager. Latency-insensitive consumers read the current best
+//! bid/ask from it without draining a ring.
+//!
+//! Each slot is protected by a seqlock: the writer makes the sequence odd while
+//! updating the slot, and readers retry until they read the slot under the same
+//! even sequence, giving up after `MAX_READ_RETRIES` on a slot left odd by a
+//! writer that died mid-write.
+
+use std::hint;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering, fence};
+
+use ctl_shm::{ShmRegion, ShmSafe};
+use hashbrown::HashMap;
+use serde::Deserialize;
+
+/// Name of the last-value Top region.
+pub const LAST_TOP_REGION_NAME: &str = "ctl_md_last_top";
+
+/// Maximum number of symbols in the last-value Top region, indexed by symbol ID.
+pub const MAX_LAST_TOP_SYMBOLS: usize = 1024;
+
+/// Maximum retries of a read of a slot being written, before giving it up as torn.
+const MAX_READ_RETRIES: u32 = 1 << 16;
+
+/// The best bid/ask of a symbol.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct TopSnapshot {
+    /// The order book update ID.
+    pub update_id: u64,
+    /// The best bid price.
+    pub bid_price: f64,
+    /// The best bid quantity.
+    pub bid_qty: f64,
+    /// The best ask price.
+    pub ask_price: f64,
+    /// The best ask quantity.
+    pub ask_qty: f64,
+}
+
+/// A bookTicker stream payload.
+/// https://github.com/binance/binance-spot-api-docs/blob/master/web-socket-streams.md#individual-symbol-book-ticker-streams
+#[derive(Deserialize)]
+struct BookTicker<'a> {
+    #[serde(rename = "u")]
+    update_id: u64,
+    #[serde(rename = "s")]
+    symbol: &'a str,
+    #[serde(rename = "b")]
+    bid_price: &'a str,
+    #[serde(rename = "B")]
+    bid_qty: &'a str,
+    #[serde(rename = "a")]
+    ask_price: &'a str,
+    #[serde(rename = "A")]
+    ask_qty: &'a str,
+}
+
+impl TopSnapshot {
+    /// Parses a bookTicker payload, returning its symbol and best bid/ask.
+    pub fn from_book_ticker(data: &[u8]) -> Option<(&str, Self)> {
+        let ticker: BookTicker<'_> = serde_json::from_slice(data).ok()?;
+        let top = Self {
+            update_id: ticker.update_id,
+            bid_price: ticker.bid_price.parse().ok()?,
+            bid_qty: ticker.bid_qty.parse().ok()?,
+            ask_price: ticker.ask_price.parse().ok()?,
+            ask_qty: ticker.ask_qty.parse().ok()?,
+        };
+        Some((ticker.symbol, top))
+    }
+}
+
+/// The seqlock-protected latest Top of a symbol.
+#[repr(C, align(64))]
+#[derive(Debug, Default)]
+pub struct TopSlot {
+    /// The sequence, odd while the slot is being written and zero until first written.
+    seq: AtomicU64,
+    /// The order book update ID.
+    update_id: AtomicU64,
+    /// The best bid price, as `f64` bits.
+    bid_price: AtomicU64,
+    /// The best bid quantity, as `f64` bits.
+    bid_qty: AtomicU64,
+    /// The best ask price, as `f64` bits.
+    ask_price: AtomicU64,
+    /// The best ask quantity, as `f64` bits.
+    ask_qty: AtomicU64,
+}
+
+impl TopSlot {
+    /// Overwrites the slot with `top`, unless it already holds a more recent update
+    /// (e.g. written by another medium of the same symbol).
+    /// Returns true if the slot was written.
+    ///
+    /// LATENCY: FAST_PATH
+    pub fn write(&self, top: &TopSnapshot) -> bool {
+        // Acquire the slot against concurrent writers
+        let seq = loop {
+            let seq = self.seq.load(Ordering::Relaxed);
+            if seq & 1 == 0
+                && self.seq
+                    .compare_exchange_weak(seq, seq + 1, Ordering::Acquire, Ordering::Relaxed)
+                    .is_ok()
+            {
+                break seq;
+            }
+            hint::spin_loop();
+        };
+        fence(Ordering::Release);
+
+        let fresh = seq == 0 || top.update_id > self.update_id.load(Ordering::Relaxed);
+        if fresh {
+            self.update_id.store(top.update_id, Ordering::Relaxed);
+            self.bid_price.store(top.bid_price.to_bits(), Ordering::Relaxed);
+            self.bid_qty.store(top.bid_qty.to_bits(), Ordering::Relaxed);
+            self.ask_price.store(top.ask_price.to_bits(), Ordering::Relaxed);
+            self.ask_qty.store(top.ask_qty.to_bits(), Ordering::Relaxed);
+        }
+
+        // Release the slot, leaving the sequence unchanged if nothing was written
+        self.seq.store(if fresh { seq + 2 } else { seq }, Ordering::Release);
+        fresh
+    }
+
+    /// Reads a consistent snapshot of the slot, `None` if it was never written,
+    /// or still being written after `MAX_READ_RETRIES` retries.
+    pub fn read(&self) -> Option<TopSnapshot> {
+        for _ in 0..MAX_READ_RETRIES {
+            let before = self.seq.load(Ordering::Acquire);
+            if before == 0 {
+                return None;
+            }
+            if before & 1 == 1 {
+                hint::spin_loop();
+                continue;
+            }
+
+            let top = TopSnapshot {
+                update_id: self.update_id.load(Ordering::Relaxed),
+                bid_price: f64::from_bits(self.bid_price.load(Ordering::Relaxed)),
+                bid_qty: f64::from_bits(self.bid_qty.load(Ordering::Relaxed)),
+                ask_price: f64::from_bits(self.ask_price.load(Ordering::Relaxed)),
+                ask_qty: f64::from_bits(self.ask_qty.load(Ordering::Relaxed)),
+            };
+
+            fence(Ordering::Acquire);
+            if self.seq.load(Ordering::Relaxed) == before {
+                return Some(top);
+            }
+        }
+        None
+    }
+}
+
+/// The last-value Top region, holding one slot per symbol ID.
+#[repr(C)]
+pub struct LastTopRegion {
+    /// The slots, indexed by symbol ID.
+    pub slots: [TopSlot; MAX_LAST_TOP_SYMBOLS],
+}
+
+// SAFETY: `LastTopRegion` is `repr(C)`, made only of atomics and valid when zeroed.
+unsafe impl ShmSafe for LastTopRegion {}
+
+impl LastTopRegion {
+    /// Returns the slot of a symbol ID, `None` if out of range.
+    pub fn slot(&self, symbol_id: u32) -> Option<&TopSlot> {
+        self.slots.get(symbol_id as usize)
+    }
+
+    /// Reads the latest Top of a symbol ID, `None` if out of range or never written.
+    pub fn read(&self, symbol_id: u32) -> Option<TopSnapshot> {
+        self.slot(symbol_id)?.read()
+    }
+}
+
+/// A handle writing the latest Top of a set of symbols.
+#[derive(Clone)]
+pub struct LastTopHandle {
+    /// The last-value Top region.
+    region: Arc<ShmRegion<LastTopRegion>>,
+    /// The symbol IDs by exchange symbol name.
+    symbols: HashMap<String, u32>,
+}
+
+impl LastTopHandle {
+    /// Creates a handle writing the latest Top of `symbols` (name and ID).
+    /// Returns `None` if a symbol ID is out of the region's range.
+    pub fn new(region: Arc<ShmRegion<LastTopRegion>>, symbols: &[(String, u32)]) -> Option<Self> {
+        if symbols.iter().any(|(_, id)| *id as usize >= MAX_LAST_TOP_SYMBOLS) {
+            return None;
+        }
+        Some(Self {
+            region,
+            symbols: symbols.iter().cloned().collect(),
+        })
+    }
+
+    /// Updates the latest Top from a bookTicker payload.
+    /// Payloads that can't be parsed or belong to an unknown symbol are ignored.
+    ///
+    /// LATENCY: FAST_PATH
+    pub fn update(&self, data: &[u8]) {
+        let Some((symbol, top)) = TopSnapshot::from_book_ticker(data) else { return };
+        if let Some(slot) = self.symbols.get(symbol).and_then(|&id| self.region.slot(id)) {
+            slot.write(&top);
+        }
+    }
+}
+
+impl std::fmt::Debug for LastTopHandle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LastTopHandle")
+            .field("symbols", &self.symbols)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::atomic::AtomicBool;
+    use std::thread;
+
+    fn top(update_id: u64, bid_price: f64) -> TopSnapshot {
+        TopSnapshot { update_id, bid_price, bid_qty: 1.0, ask_price: bid_price + 0.5, ask_qty: 2.0 }
+    }
+
+    #[test]
+    fn test_parse_book_ticker() {
+        let data = br#"{"u":400900217,"s":"BNBUSDT","b":"25.35190000","B":"31.21000000","a":"25.36520000","A":"40.66000000"}"#;
+        let (symbol, top) = TopSnapshot::from_book_ticker(data).unwrap();
+        assert_eq!(symbol, "BNBUSDT");
+        assert_eq!(top.update_id, 400900217);
+        assert_eq!(top.bid_price, 25.3519);
+        assert_eq!(top.ask_qty, 40.66);
+        assert!(TopSnapshot::from_book_ticker(br#"{"e":"trade"}"#).is_none());
+    }
+
+    #[test]
+    fn test_slot_write_read() {
+        let slot = TopSlot::default();
+        assert_eq!(slot.read(), None);
+
+        assert!(slot.write(&top(10, 100.0)));
+        assert_eq!(slot.read(), Some(top(10, 100.0)));
+
+        assert!(slot.write(&top(11, 101.0)));
+        assert_eq!(slot.read(), Some(top(11, 101.0)));
+    }
+
+    #[test]
+    fn test_slot_ignores_stale_update() {
+        let slot = TopSlot::default();
+        assert!(slot.write(&top(11, 101.0)));
+        assert!(!slot.write(&top(10, 100.0)));
+        assert_eq!(slot.read(), Some(top(11, 101.0)));
+    }
+
+    #[test]
+    fn test_slot_torn_read_given_up() {
+        let slot = TopSlot::default();
+        assert!(slot.write(&top(10, 100.0)));
+        // A writer dying mid-write leaves the sequence odd
+        slot.seq.fetch_add(1, Ordering::Relaxed);
+        assert_eq!(slot.read(), None);
+    }
+
+    #[test]
+    fn test_slot_consistent_under_concurrent_writes() {
+        let slot = Arc::new(TopSlot::default());
+        let done = Arc::new(AtomicBool::new(false));
+
+        let writer = {
+            let slot = slot.clone();
+            let done = done.clone();
+            thread::spawn(move || {
+                for update_id in 1..100_000u64 {
+                    let price = update_id as f64;
+                    slot.write(&TopSnapshot {
+                        update_id,
+                        bid_price: price,
+                        bid_qty: price,
+                        ask_price: price,
+                        ask_qty: price,
+                    });
+                }
+                done.store(true, Ordering::Release);
+            })
+        };
+
+        while !done.load(Ordering::Acquire) {
+            if let Some(top) = slot.read() {
+                let price = top.update_id as f64;
+                assert_eq!(top, TopSnapshot {
+                    update_id: top.update_id,
+                    bid_price: price,
+                    bid_qty: price,
+                    ask_price: price,
+                    ask_qty: price,
+                });
+            }
+        }
+        writer.join().unwrap();
+    }
+}
@@ -0,0 +1,482 @@
+//! HDR latency histograms of the hot path, per feedgroup.
+//!
+//! Each feedgroup registers a `LatencyGroup` in the metrics region, holding the
+//! histograms of its parse time, ring publish time and consumer wake latency
+//! (from the publish timestamp in the message header until a consumer reads the
+//! message). Recording goes to histograms local to the recording thread, so the
+//! hot path touches no shared cache line; they are merged into the region
+//! periodically and when the recorder is dropped.
+//!
+//! The histograms are log-linear: values below 64ns have their own bucket, and
+//! every power of two above is split into 32 buckets, bounding the error of a
+//! recorded value to about 3%.
+//!
+//! The region layout doesn't depend on the `latency-histograms` feature, only
+//! the recording does, so processes built with and without it can share the
+//! region.
+
+use std::sync::atomic::{AtomicU8, AtomicU64, Ordering};
+#[cfg(feature = "latency-histograms")]
+use std::sync::Arc;
+
+#[cfg(feature = "latency-histograms")]
+use ctl_shm::ShmRegion;
+
+#[cfg(feature = "latency-histograms")]
+use crate::{MessageHeader, MetricsRegion};
+
+/// Number of buckets of a histogram.
+pub const HISTOGRAM_BUCKETS: usize = 1024;
+
+/// Largest value tracked, in nanoseconds (about 68s); larger values count in the last bucket.
+pub const MAX_TRACKABLE_NS: u64 = (1 << 36) - 1;
+
+/// Maximum number of latency groups in the metrics region.
+pub const MAX_LATENCY_GROUPS: usize = 32;
+
+/// Maximum length of a latency group name.
+pub const LATENCY_GROUP_NAME_SIZE: usize = 32;
+
+/// Interval at which the local histograms are merged into the metrics region, in nanoseconds.
+pub const LATENCY_FLUSH_INTERVAL_NS: u64 = 1_000_000_000;
+
+/// Bits of the sub-buckets: values below `1 << SUB_BUCKET_BITS` have their own bucket.
+const SUB_BUCKET_BITS: u32 = 6;
+
+/// Number of buckets of the linear range.
+const SUB_BUCKETS: usize = 1 << SUB_BUCKET_BITS;
+
+/// Number of buckets of every power of two above the linear range.
+const HALF_SUB_BUCKETS: usize = SUB_BUCKETS / 2;
+
+/// Returns the bucket of a value.
+pub fn bucket_index(value_ns: u64) -> usize {
+    let value = value_ns.min(MAX_TRACKABLE_NS);
+    if value < SUB_BUCKETS as u64 {
+        return value as usize;
+    }
+    let msb = 63 - value.leading_zeros();
+    let shift = msb - (SUB_BUCKET_BITS - 1);
+    let group = (msb - SUB_BUCKET_BITS) as usize;
+    SUB_BUCKETS + group * HALF_SUB_BUCKETS + ((value >> shift) as usize - HALF_SUB_BUCKETS)
+}
+
+/// Returns the highest value of a bucket.
+pub fn bucket_high(index: usize) -> u64 {
+    if index < SUB_BUCKETS {
+        return index as u64;
+    }
+    let offset = index - SUB_BUCKETS;
+    let shift = (offset / HALF_SUB_BUCKETS) as u32 + 1;
+    let sub = (offset % HALF_SUB_BUCKETS + HALF_SUB_BUCKETS) as u64;
+    ((sub + 1) << shift) - 1
+}
+
+/// A histogram of latencies, recorded by a single thread.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LatencyHistogram {
+    /// The counts of each bucket.
+    counts: Box<[u64]>,
+    /// The number of values recorded.
+    count: u64,
+    /// The sum of the values recorded, in nanoseconds.
+    sum_ns: u64,
+    /// The largest value recorded, in nanoseconds.
+    max_ns: u64,
+}
+
+impl Default for LatencyHistogram {
+    fn default() -> Self {
+        Self { counts: vec![0; HISTOGRAM_BUCKETS].into_boxed_slice(), count: 0, sum_ns: 0, max_ns: 0 }
+    }
+}
+
+impl LatencyHistogram {
+    /// Creates an empty histogram.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records a value.
+    ///
+    /// LATENCY: FAST_PATH
+    pub fn record(&mut self, value_ns: u64) {
+        self.counts[bucket_index(value_ns)] += 1;
+        self.count += 1;
+        self.sum_ns = self.sum_ns.saturating_add(value_ns);
+        self.max_ns = self.max_ns.max(value_ns);
+    }
+
+    /// Returns the number of values recorded.
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// Returns true if no value was recorded.
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    /// Returns the largest value recorded, in nanoseconds.
+    pub fn max_ns(&self) -> u64 {
+        self.max_ns
+    }
+
+    /// Returns the mean of the values recorded, in nanoseconds.
+    pub fn mean_ns(&self) -> f64 {
+        if self.count == 0 { 0.0 } else { self.sum_ns as f64 / self.count as f64 }
+    }
+
+    /// Returns the value below which a `quantile` (in [0, 1]) of the values
+    /// fall, within the bucket precision, zero if empty.
+    pub fn value_at_quantile(&self, quantile: f64) -> u64 {
+        if self.count == 0 {
+            return 0;
+        }
+        let target = ((quantile.clamp(0.0, 1.0) * self.count as f64).ceil() as u64).max(1);
+        let mut seen = 0;
+        for (index, &count) in self.counts.iter().enumerate() {
+            seen += count;
+            if seen >= target {
+                return bucket_high(index).min(self.max_ns);
+            }
+        }
+        self.max_ns
+    }
+
+    /// Clears the recorded values.
+    pub fn clear(&mut self) {
+        self.counts.fill(0);
+        self.count = 0;
+        self.sum_ns = 0;
+        self.max_ns = 0;
+    }
+}
+
+/// A histogram of latencies in the metrics region, merged from the local histograms.
+#[repr(C)]
+#[derive(Debug)]
+pub struct SharedHistogram {
+    /// The number of values recorded.
+    pub count: AtomicU64,
+    /// The sum of the values recorded, in nanoseconds.
+    pub sum_ns: AtomicU64,
+    /// The largest value recorded, in nanoseconds.
+    pub max_ns: AtomicU64,
+    /// The counts of each bucket.
+    buckets: [AtomicU64; HISTOGRAM_BUCKETS],
+}
+
+impl Default for SharedHistogram {
+    fn default() -> Self {
+        Self {
+            count: AtomicU64::new(0),
+            sum_ns: AtomicU64::new(0),
+            max_ns: AtomicU64::new(0),
+            buckets: std::array::from_fn(|_| AtomicU64::new(0)),
+        }
+    }
+}
+
+impl SharedHistogram {
+    /// Adds the values of a local histogram.
+    ///
+    /// LATENCY: SLOW_PATH
+    pub fn merge(&self, histogram: &LatencyHistogram) {
+        if histogram.is_empty() {
+            return;
+        }
+        for (bucket, &count) in self.buckets.iter().zip(histogram.counts.iter()) {
+            if count != 0 {
+                bucket.fetch_add(count, Ordering::Relaxed);
+            }
+        }
+        self.sum_ns.fetch_add(histogram.sum_ns, Ordering::Relaxed);
+        self.max_ns.fetch_max(histogram.max_ns, Ordering::Relaxed);
+        self.count.fetch_add(histogram.count, Ordering::Release);
+    }
+
+    /// Returns a copy of the histogram.
+    ///
+    /// The copy may miss part of a merge happening concurrently.
+    pub fn snapshot(&self) -> LatencyHistogram {
+        let count = self.count.load(Ordering::Acquire);
+        LatencyHistogram {
+            counts: self.buckets.iter().map(|b| b.load(Ordering::Relaxed)).collect(),
+            count,
+            sum_ns: self.sum_ns.load(Ordering::Relaxed),
+            max_ns: self.max_ns.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// The latency histograms of a feedgroup.
+#[repr(C)]
+#[derive(Debug, Default)]
+pub struct LatencyGroup {
+    /// Zero when free, one while being registered, two once registered.
+    state: AtomicU64,
+    /// The group name, zero padded.
+    name: [AtomicU8; LATENCY_GROUP_NAME_SIZE],
+    /// Time to parse a payload into a ring message.
+    pub parse: SharedHistogram,
+    /// Time to publish a message to the ring.
+    pub publish: SharedHistogram,
+    /// Time from the publish timestamp of a message until a consumer reads it.
+    pub wake: SharedHistogram,
+}
+
+impl LatencyGroup {
+    /// Returns true if the group is registered.
+    pub fn is_registered(&self) -> bool {
+        self.state.load(Ordering::Acquire) == 2
+    }
+
+    /// Returns the group name.
+    pub fn name(&self) -> String {
+        let bytes: Vec<u8> = self.name
+            .iter()
+            .map(|b| b.load(Ordering::Relaxed))
+            .take_while(|&b| b != 0)
+            .collect();
+        String::from_utf8_lossy(&bytes).into_owned()
+    }
+
+    /// Returns true if the group is registered under `name`.
+    pub(crate) fn has_name(&self, name: &str) -> bool {
+        self.is_registered() && self.name() == name
+    }
+
+    /// Claims the group for `name`, returning false if it's taken.
+    pub(crate) fn claim(&self, name: &str) -> bool {
+        if self.state.compare_exchange(0, 1, Ordering::AcqRel, Ordering::Relaxed).is_err() {
+            return false;
+        }
+        for (slot, byte) in self.name.iter().zip(name.bytes()) {
+            slot.store(byte, Ordering::Relaxed);
+        }
+        self.state.store(2, Ordering::Release);
+        true
+    }
+}
+
+/// Records the latencies of a feedgroup into local histograms, merged into its
+/// latency group periodically and when dropped.
+///
+/// Clones start with empty histograms, so each worker thread can own one.
+#[cfg(feature = "latency-histograms")]
+pub struct LatencyRecorder {
+    /// The metrics region.
+    region: Arc<ShmRegion<MetricsRegion>>,
+    /// The index of the latency group.
+    group: usize,
+    /// The parse times not yet merged.
+    parse: LatencyHistogram,
+    /// The publish times not yet merged.
+    publish: LatencyHistogram,
+    /// The wake latencies not yet merged.
+    wake: LatencyHistogram,
+    /// The time of the last merge, in nanoseconds.
+    last_flush_ns: u64,
+}
+
+#[cfg(feature = "latency-histograms")]
+impl LatencyRecorder {
+    /// Creates a recorder of a latency group, `None` if the index is out of range.
+    pub fn new(region: Arc<ShmRegion<MetricsRegion>>, group: usize) -> Option<Self> {
+        if group >= MAX_LATENCY_GROUPS {
+            return None;
+        }
+        Some(Self {
+            region,
+            group,
+            parse: LatencyHistogram::new(),
+            publish: LatencyHistogram::new(),
+            wake: LatencyHistogram::new(),
+            last_flush_ns: ctl_time::monotonic_ns(),
+        })
+    }
+
+    /// Returns the index of the latency group, tagged in the message headers.
+    pub fn group(&self) -> u8 {
+        self.group as u8
+    }
+
+    /// Records a parse time.
+    ///
+    /// LATENCY: FAST_PATH
+    pub fn record_parse(&mut self, value_ns: u64) {
+        self.parse.record(value_ns);
+    }
+
+    /// Records a publish time.
+    ///
+    /// LATENCY: FAST_PATH
+    pub fn record_publish(&mut self, value_ns: u64) {
+        self.publish.record(value_ns);
+    }
+
+    /// Records a wake latency.
+    ///
+    /// LATENCY: FAST_PATH
+    pub fn record_wake(&mut self, value_ns: u64) {
+        self.wake.record(value_ns);
+    }
+
+    /// Merges the local histograms into the region if the flush interval elapsed.
+    ///
+    /// LATENCY: FAST_PATH (except when merging)
+    pub fn maybe_flush(&mut self, now_ns: u64) {
+        if now_ns.saturating_sub(self.last_flush_ns) >= LATENCY_FLUSH_INTERVAL_NS {
+            self.flush();
+            self.last_flush_ns = now_ns;
+        }
+    }
+
+    /// Merges the local histograms into the region.
+    ///
+    /// LATENCY: SLOW_PATH
+    pub fn flush(&mut self) {
+        let group = &self.region.latency[self.group];
+        for (shared, local) in [
+            (&group.parse, &mut self.parse),
+            (&group.publish, &mut self.publish),
+            (&group.wake, &mut self.wake),
+        ] {
+            shared.merge(local);
+            local.clear();
+        }
+    }
+}
+
+#[cfg(feature = "latency-histograms")]
+impl Clone for LatencyRecorder {
+    fn clone(&self) -> Self {
+        Self {
+            region: self.region.clone(),
+            group: self.group,
+            parse: LatencyHistogram::new(),
+            publish: LatencyHistogram::new(),
+            wake: LatencyHistogram::new(),
+            last_flush_ns: self.last_flush_ns,
+        }
+    }
+}
+
+#[cfg(feature = "latency-histograms")]
+impl Drop for LatencyRecorder {
+    fn drop(&mut self) {
+        self.flush();
+    }
+}
+
+#[cfg(feature = "latency-histograms")]
+impl std::fmt::Debug for LatencyRecorder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LatencyRecorder")
+            .field("group", &self.region.latency[self.group].name())
+            .finish()
+    }
+}
+
+/// Records the wake latency of the messages a consumer reads, into the latency
+/// group of the feedgroup that published each message.
+#[cfg(feature = "latency-histograms")]
+pub struct WakeLatencyRecorder {
+    /// The metrics region.
+    region: Arc<ShmRegion<MetricsRegion>>,
+    /// The recorders, by latency group.
+    recorders: Vec<Option<LatencyRecorder>>,
+}
+
+#[cfg(feature = "latency-histograms")]
+impl WakeLatencyRecorder {
+    /// Creates a recorder of the wake latencies.
+    pub fn new(region: Arc<ShmRegion<MetricsRegion>>) -> Self {
+        Self { region, recorders: (0..MAX_LATENCY_GROUPS).map(|_| None).collect() }
+    }
+
+    /// Records the wake latency of a message read at `now_ns`, skipping the
+    /// messages published without a timestamp.
+    ///
+    /// LATENCY: FAST_PATH
+    pub fn record(&mut self, header: &MessageHeader, now_ns: u64) {
+        if header.published_ns == 0 {
+            return;
+        }
+        let group = header.latency_group as usize;
+        let Some(slot) = self.recorders.get_mut(group) else {
+            return;
+        };
+        let recorder = slot.get_or_insert_with(|| {
+            LatencyRecorder::new(self.region.clone(), group).expect("group index is in range")
+        });
+        recorder.record_wake(now_ns.saturating_sub(header.published_ns));
+        recorder.maybe_flush(now_ns);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bucket_bounds() {
+        for value in [0, 1, 63, 64, 65, 127, 128, 1_000, 123_456, 5_000_000_000, MAX_TRACKABLE_NS] {
+            let index = bucket_index(value);
+            assert!(bucket_high(index) >= value, "{} above its bucket", value);
+            if index > 0 {
+                assert!(bucket_high(index - 1) < value, "{} below its bucket", value);
+            }
+        }
+        assert_eq!(bucket_index(MAX_TRACKABLE_NS), HISTOGRAM_BUCKETS - 1);
+        assert_eq!(bucket_index(u64::MAX), HISTOGRAM_BUCKETS - 1);
+
+        // The precision is within 1/32 of the value
+        let value = 1_000_000;
+        assert!((bucket_high(bucket_index(value)) - value) * 32 <= value);
+    }
+
+    #[test]
+    fn test_quantiles() {
+        let mut histogram = LatencyHistogram::new();
+        assert_eq!(histogram.value_at_quantile(0.5), 0);
+        for value in 1..=100 {
+            histogram.record(value * 1_000);
+        }
+        assert_eq!(histogram.count(), 100);
+        assert_eq!(histogram.max_ns(), 100_000);
+        assert_eq!(histogram.mean_ns(), 50_500.0);
+
+        let p50 = histogram.value_at_quantile(0.5);
+        assert!((50_000..=50_000 + 50_000 / 32).contains(&p50));
+        assert_eq!(histogram.value_at_quantile(1.0), 100_000);
+
+        histogram.clear();
+        assert!(histogram.is_empty());
+    }
+
+    #[test]
+    fn test_merge_into_shared() {
+        let shared = SharedHistogram::default();
+        let mut histogram = LatencyHistogram::new();
+        histogram.record(10);
+        histogram.record(2_000);
+        shared.merge(&histogram);
+        shared.merge(&histogram);
+
+        let snapshot = shared.snapshot();
+        assert_eq!(snapshot.count(), 4);
+        assert_eq!(snapshot.max_ns(), 2_000);
+        assert_eq!(snapshot.value_at_quantile(0.5), 10);
+    }
+
+    #[test]
+    fn test_group_claim() {
+        let group = LatencyGroup::default();
+        assert!(!group.is_registered());
+        assert!(group.claim("top/A@websocket/json"));
+        assert!(group.has_name("top/A@websocket/json"));
+        assert!(!group.claim("trade/A@websocket/json"));
+    }
+}
@@ -3,8 +3,72 @@ mod group;
 mod protocol;
 mod parser;
 mod messages;
+mod backpressure;
+mod metrics;
+mod lastvalue;
+mod latency;
+mod pause;
+mod streams;
+mod ring;
+mod reader;
+mod checksum;
+mod c_header;
+mod discovery;
+mod filter;
+mod fixed;
+mod fragment;
+mod join;
+mod dedup;
+mod arbiter;
+mod gapfill;
+mod status;
+mod shm_group;
+#[cfg(test)]
+mod corpus;
 
-pub use kind::{ Top, Trade, AggTrade };
+pub use kind::{ Top, Trade, AggTrade, Depth };
 pub use group::FeedGroups;
-pub use parser::DummyParser;
-pub use messages::{RawMessage, RAW_MESSAGE_SIZE};
\ No newline at end of file
+pub use shm_group::{ParseInto, ShmFeedGroup, ShmFeedGroupError, ShmFeedGroups, ShmJoinHandle};
+pub use parser::{
+    DummyParser, DummyParserError, FixParser, ParseErrorCounter, ParseOutcome, ParseSkip, SbeParser,
+    SBE_BEST_BID_ASK_SUFFIX,
+};
+pub use pause::PauseHandle;
+pub use checksum::{crc32, Checksummed, Crc32};
+pub use c_header::declare_c_types;
+pub use discovery::{DiscoveredRing, DpdkLookup, RingDirectory, RingPattern, ShmRingDirectory};
+pub use filter::{FilteredConsumer, MessageFilter};
+pub use fixed::{fixed_to_f64, parse_fixed, FixedPoint, SymbolScale, MAX_EXPONENT};
+pub use fragment::{fragment_count, slot_payload, FragmentSink, Reassembler, Reassembly, MAX_FRAGMENTS};
+pub use join::{payload_update_id, JoinGate};
+pub use dedup::{Dedup, Deduper, DEFAULT_DEDUP_WINDOW};
+pub use arbiter::{payload_event_id, Arbiter, Arbitration, LineSilence, LineStats};
+pub use gapfill::{TradeGap, TradeGapDetector};
+pub use status::{ConsumerStatus, MetricsStatus, RingStatus, StreamStatus};
+pub use ring::{RingConsume, RingConsumer, RingError, RingLike, RingPublisher};
+pub use reader::{RingRead, RingReader};
+#[cfg(any(test, feature = "test-rings"))]
+pub use ring::{MemoryRing, MemoryRingConsumer};
+pub use streams::{
+    payload_field, payload_symbol, StreamRate, StreamReport, StreamStats, StreamStatsHandle, MAX_STREAMS, SILENT_STREAM_AFTER_MS,
+    STREAM_NAME_SIZE,
+};
+pub use messages::{
+    RawMessage, MessageHeader, MediumTag, EventType, RingMessage, RAW_MESSAGE_SIZE, MIN_SLOT_SIZE, UNKNOWN_SYMBOL_ID,
+    validate_slot_size,
+    TradeStatsMessage, WindowStats, STATS_WINDOWS_MS, CandleMessage, SignalMessage, SIGNAL_FLOW_WINDOWS_MS,
+};
+pub use backpressure::{GuardedPublisher, OverflowGuard, OverflowPolicy, OverflowCounters, PublishOutcome};
+pub use metrics::{
+    MetricsRegion, RingMetrics, RingMetricsHandle, ConsumerCursor, DeadCursors, LagAlert,
+    CONSUMER_NAME_SIZE, LIVENESS_CHECK_INTERVAL_NS, METRICS_REGION_NAME, MAX_METRIC_RINGS, MAX_RING_CONSUMERS,
+    RING_NAME_SIZE,
+};
+pub use lastvalue::{
+    LastTopRegion, LastTopHandle, TopSlot, TopSnapshot, LAST_TOP_REGION_NAME, MAX_LAST_TOP_SYMBOLS,
+};pub use latency::{
+    LatencyGroup, LatencyHistogram, SharedHistogram, bucket_high, bucket_index, HISTOGRAM_BUCKETS,
+    LATENCY_FLUSH_INTERVAL_NS, LATENCY_GROUP_NAME_SIZE, MAX_LATENCY_GROUPS, MAX_TRACKABLE_NS,
+};
+#[cfg(feature = "latency-histograms")]
+pub use latency::{LatencyRecorder, WakeLatencyRecorder};
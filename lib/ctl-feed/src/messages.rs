@@ -3,9 +3,306 @@
 //! These types are used as the element types in DPDK shared memory rings.
 //! Each type is registered via `register_ring!` for automatic allocation.
 
-/// Maximum size for raw message buffer.
+use ctl_core::TraceContext;
+use ctl_shm::ShmMessage;
+
+use crate::FixedPoint;
+
+/// Returns the layout hash of a message, covering the fields of its header.
+macro_rules! message_layout_hash {
+    ($type:ty { $($field:tt),* }) => {
+        ctl_shm::layout_hash!($type {
+            header,
+            header.symbol_id,
+            header.event_type,
+            header.medium,
+            header.latency_group,
+            header.checksum,
+            header.fragment_index,
+            header.continued,
+            header.seq,
+            header.ts_ms,
+            header.published_ns,
+            header.trace_id,
+            header.span_id,
+            $($field),*
+        })
+    };
+}
+
+/// Maximum size for raw message buffer, the largest slot size of a raw ring.
 pub const RAW_MESSAGE_SIZE: usize = 512;
 
+/// Smallest slot size of a raw ring, fitting a book ticker.
+pub const MIN_SLOT_SIZE: usize = 64;
+
+/// Validates the slot size of a raw ring, the payload bytes of its messages.
+///
+/// The slot size is a multiple of 8 between `MIN_SLOT_SIZE` and `RAW_MESSAGE_SIZE`.
+pub fn validate_slot_size(slot_size: usize) -> Result<(), String> {
+    if !(MIN_SLOT_SIZE..=RAW_MESSAGE_SIZE).contains(&slot_size) || slot_size % 8 != 0 {
+        return Err(format!(
+            "slot size {} is not a multiple of 8 between {} and {}",
+            slot_size, MIN_SLOT_SIZE, RAW_MESSAGE_SIZE
+        ));
+    }
+    Ok(())
+}
+
+/// The symbol ID of a message header whose symbol is unknown.
+pub const UNKNOWN_SYMBOL_ID: u32 = u32::MAX;
+
+/// The medium (protocol/parser) that produced a message.
+///
+/// Stored as a `u8` in the message header, since a ring slot may hold any byte.
+#[repr(u8)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum MediumTag {
+    /// Unknown medium.
+    Unknown = 0,
+    /// JSON payloads.
+    Json = 1,
+    /// SBE payloads.
+    Sbe = 2,
+    /// FIX payloads.
+    Fix = 3,
+}
+
+impl MediumTag {
+    /// Returns the tag for a parser type of the configuration (e.g., "json").
+    pub fn from_parser(parser: &str) -> Option<Self> {
+        match parser {
+            "json" => Some(MediumTag::Json),
+            "sbe" => Some(MediumTag::Sbe),
+            "fix" => Some(MediumTag::Fix),
+            _ => None,
+        }
+    }
+
+    /// Returns the tag stored in a message header.
+    pub fn from_u8(tag: u8) -> Self {
+        match tag {
+            1 => MediumTag::Json,
+            2 => MediumTag::Sbe,
+            3 => MediumTag::Fix,
+            _ => MediumTag::Unknown,
+        }
+    }
+}
+
+/// The event carried by a message.
+///
+/// Stored as a `u8` in the message header, since a ring slot may hold any byte.
+#[repr(u8)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum EventType {
+    /// Unknown event.
+    Unknown = 0,
+    /// A best bid/ask update (bookTicker).
+    BookTicker = 1,
+    /// A trade.
+    Trade = 2,
+    /// An aggregated trade.
+    AggTrade = 3,
+    /// An order book depth update.
+    Depth = 4,
+    /// Rolling trade statistics.
+    TradeStats = 5,
+    /// A completed candle.
+    Candle = 6,
+    /// Derived microstructure signals.
+    Signal = 7,
+}
+
+impl EventType {
+    /// Returns the event type of its name (e.g., "agg_trade").
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "book_ticker" => Some(EventType::BookTicker),
+            "trade" => Some(EventType::Trade),
+            "agg_trade" => Some(EventType::AggTrade),
+            "depth" => Some(EventType::Depth),
+            "trade_stats" => Some(EventType::TradeStats),
+            "candle" => Some(EventType::Candle),
+            "signal" => Some(EventType::Signal),
+            _ => None,
+        }
+    }
+
+    /// Returns the event type stored in a message header.
+    pub fn from_u8(event_type: u8) -> Self {
+        match event_type {
+            1 => EventType::BookTicker,
+            2 => EventType::Trade,
+            3 => EventType::AggTrade,
+            4 => EventType::Depth,
+            5 => EventType::TradeStats,
+            6 => EventType::Candle,
+            7 => EventType::Signal,
+            _ => EventType::Unknown,
+        }
+    }
+}
+
+/// The header published in front of every message.
+///
+/// It identifies the symbol and the event of the message, so a consumer of a
+/// ring multiplexing several symbols demultiplexes the messages without parsing
+/// their bodies. A payload larger than the slot of its ring is published as
+/// fragments, numbered and flagged in their headers (see `fragment`).
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct MessageHeader {
+    /// The symbol ID of the symbol info table, `UNKNOWN_SYMBOL_ID` if unknown.
+    pub symbol_id: u32,
+    /// The `EventType` of the message.
+    pub event_type: u8,
+    /// The `MediumTag` of the medium that produced the message.
+    pub medium: u8,
+    /// The index of the latency group of the feedgroup that produced the message.
+    pub latency_group: u8,
+    /// The CRC32 of the message, zero if unsealed; sealed with the `message-checksums` feature.
+    pub checksum: u32,
+    /// The index of the fragment in its payload, zero if not fragmented.
+    pub fragment_index: u16,
+    /// Non-zero if more fragments of the payload follow.
+    pub continued: u8,
+    /// Non-zero if the event was fetched after the fact to fill a gap of its
+    /// ring, rather than received live.
+    pub backfill: u8,
+    /// The sequence number of the message in its ring, from 1; zero if not sequenced.
+    pub seq: u64,
+    /// The time the message was received (or produced), in milliseconds since the epoch.
+    pub ts_ms: u64,
+    /// The monotonic time the message was handed to the ring, in nanoseconds;
+    /// zero unless built with the `latency-histograms` feature.
+    pub published_ns: u64,
+    /// The trace ID of the message, zero unless sampled for tracing.
+    pub trace_id: u64,
+    /// The span ID of the stage that published the message, the parent of the
+    /// span of its consumers.
+    pub span_id: u64,
+}
+
+impl Default for MessageHeader {
+    fn default() -> Self {
+        Self {
+            symbol_id: UNKNOWN_SYMBOL_ID,
+            event_type: EventType::Unknown as u8,
+            medium: MediumTag::Unknown as u8,
+            latency_group: 0,
+            checksum: 0,
+            fragment_index: 0,
+            continued: 0,
+            backfill: 0,
+            seq: 0,
+            ts_ms: 0,
+            published_ns: 0,
+            trace_id: 0,
+            span_id: 0,
+        }
+    }
+}
+
+impl MessageHeader {
+    /// Creates the header of an event of a symbol, to be stamped on publish.
+    pub fn new(event_type: EventType, symbol_id: u32) -> Self {
+        Self { symbol_id, event_type: event_type as u8, ..Self::default() }
+    }
+
+    /// Stamps the header with the sequence number of the message in its ring and its time.
+    ///
+    /// LATENCY: FAST_PATH
+    pub fn stamp(&mut self, seq: u64, ts_ms: u64) {
+        self.seq = seq;
+        self.ts_ms = ts_ms;
+    }
+
+    /// Returns the symbol ID of the message, `None` if unknown.
+    pub fn symbol_id(&self) -> Option<u32> {
+        (self.symbol_id != UNKNOWN_SYMBOL_ID).then_some(self.symbol_id)
+    }
+
+    /// Returns true if more fragments of the payload follow the message.
+    pub fn is_continued(&self) -> bool {
+        self.continued != 0
+    }
+
+    /// Returns true if the message is a fragment of a payload larger than its slot.
+    pub fn is_fragment(&self) -> bool {
+        self.fragment_index != 0 || self.is_continued()
+    }
+
+    /// Marks the message as the fragment `index` of its payload, followed by
+    /// more fragments if `continued`.
+    ///
+    /// LATENCY: FAST_PATH
+    pub fn set_fragment(&mut self, index: u16, continued: bool) {
+        self.fragment_index = index;
+        self.continued = continued as u8;
+    }
+
+    /// Returns true if the event was fetched to fill a gap, rather than received live.
+    pub fn is_backfill(&self) -> bool {
+        self.backfill != 0
+    }
+
+    /// Flags the event as fetched to fill a gap of its ring.
+    pub fn set_backfill(&mut self) {
+        self.backfill = 1;
+    }
+
+    /// Returns the trace context of the message, untraced unless sampled.
+    pub fn trace(&self) -> TraceContext {
+        TraceContext { trace_id: self.trace_id, span_id: self.span_id }
+    }
+
+    /// Stamps the header with the trace context of the stage publishing the message.
+    ///
+    /// LATENCY: FAST_PATH
+    pub fn set_trace(&mut self, context: TraceContext) {
+        self.trace_id = context.trace_id;
+        self.span_id = context.span_id;
+    }
+
+    /// Returns the event carried by the message.
+    pub fn event_type(&self) -> EventType {
+        EventType::from_u8(self.event_type)
+    }
+
+    /// Returns the medium that produced the message.
+    pub fn medium(&self) -> MediumTag {
+        MediumTag::from_u8(self.medium)
+    }
+}
+
+/// A message of the rings, prefixed by a `MessageHeader`.
+pub trait RingMessage: ShmMessage {
+    /// Returns the header of the message.
+    fn header(&self) -> &MessageHeader;
+
+    /// Returns the header of the message, mutably.
+    fn header_mut(&mut self) -> &mut MessageHeader;
+}
+
+macro_rules! ring_message {
+    ($($message:ty),*) => {
+        $(
+            impl RingMessage for $message {
+                fn header(&self) -> &MessageHeader {
+                    &self.header
+                }
+
+                fn header_mut(&mut self) -> &mut MessageHeader {
+                    &mut self.header
+                }
+            }
+        )*
+    };
+}
+
+ring_message!(RawMessage, TradeStatsMessage, CandleMessage, SignalMessage);
+
 /// A raw message buffer for unparsed data.
 ///
 /// This is a simple byte array used by DummyParser before proper
@@ -13,6 +310,10 @@ pub const RAW_MESSAGE_SIZE: usize = 512;
 #[repr(C)]
 #[derive(Copy, Clone, Debug)]
 pub struct RawMessage {
+    /// The message header.
+    pub header: MessageHeader,
+    /// The prices and quantities of the message in fixed-point, if its symbol has a scale.
+    pub fixed: FixedPoint,
     /// The raw bytes of the message.
     pub data: [u8; RAW_MESSAGE_SIZE],
 }
@@ -20,11 +321,140 @@ pub struct RawMessage {
 impl Default for RawMessage {
     fn default() -> Self {
         Self {
+            header: MessageHeader::default(),
+            fixed: FixedPoint::default(),
             data: [0u8; RAW_MESSAGE_SIZE],
         }
     }
 }
 
+// SAFETY: `RawMessage` is `repr(C)`, made only of integers and bytes, valid for any bytes.
+unsafe impl ShmMessage for RawMessage {
+    const LAYOUT_HASH: u64 = message_layout_hash!(RawMessage { fixed, data });
+}
+
+/// The rolling windows of the trade statistics, in milliseconds.
+pub const STATS_WINDOWS_MS: [u64; 3] = [1_000, 5_000, 60_000];
+
+/// Trade statistics over a rolling window.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct WindowStats {
+    /// The window length, in milliseconds.
+    pub window_ms: u64,
+    /// Volume weighted average price, zero without trades.
+    pub vwap: f64,
+    /// Notional volume (sum of price * quantity).
+    pub notional: f64,
+    /// Base asset volume.
+    pub volume: f64,
+    /// Number of trades.
+    pub trade_count: u64,
+}
+
+/// Rolling trade statistics of a symbol, published to the `STATS_{symbol_id}_PS` rings.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct TradeStatsMessage {
+    /// The message header.
+    pub header: MessageHeader,
+    /// The symbol ID.
+    pub symbol_id: u32,
+    /// Trade time of the last trade, in milliseconds.
+    pub trade_time_ms: u64,
+    /// The statistics of each window of `STATS_WINDOWS_MS`.
+    pub windows: [WindowStats; STATS_WINDOWS_MS.len()],
+}
+
+// SAFETY: `TradeStatsMessage` is `repr(C)`, made only of integers and floats, valid for any bytes.
+unsafe impl ShmMessage for TradeStatsMessage {
+    const LAYOUT_HASH: u64 = message_layout_hash!(TradeStatsMessage { symbol_id, trade_time_ms, windows });
+}
+
+/// A completed OHLCV bar of a symbol, published to the `KLINE_{symbol_id}_PS` rings.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct CandleMessage {
+    /// The message header.
+    pub header: MessageHeader,
+    /// The symbol ID.
+    pub symbol_id: u32,
+    /// The bar interval, in milliseconds.
+    pub interval_ms: u64,
+    /// Open time of the bar (inclusive), in milliseconds.
+    pub open_time_ms: u64,
+    /// Close time of the bar (exclusive), in milliseconds.
+    pub close_time_ms: u64,
+    /// The first trade price.
+    pub open: f64,
+    /// The highest trade price.
+    pub high: f64,
+    /// The lowest trade price.
+    pub low: f64,
+    /// The last trade price.
+    pub close: f64,
+    /// Base asset volume.
+    pub volume: f64,
+    /// Notional volume (sum of price * quantity).
+    pub notional: f64,
+    /// Number of trades.
+    pub trade_count: u64,
+}
+
+// SAFETY: `CandleMessage` is `repr(C)`, made only of integers and floats, valid for any bytes.
+unsafe impl ShmMessage for CandleMessage {
+    const LAYOUT_HASH: u64 = message_layout_hash!(CandleMessage {
+        symbol_id, interval_ms, open_time_ms, close_time_ms, open, high, low, close, volume, notional, trade_count
+    });
+}
+
+/// The trade-flow windows of the signals, in milliseconds.
+pub const SIGNAL_FLOW_WINDOWS_MS: [u64; 3] = [1_000, 5_000, 30_000];
+
+/// Derived microstructure signals of a symbol, published to the `SIGNAL_{symbol_id}_PS`
+/// rings on each Top update and trade of the symbol.
+///
+/// The imbalances range from -1 (all on the ask, or all sold) to 1 (all on the
+/// bid, or all bought), zero without quantity.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct SignalMessage {
+    /// The message header.
+    pub header: MessageHeader,
+    /// The symbol ID.
+    pub symbol_id: u32,
+    /// The number of book levels per side of the book imbalance, zero without a live book.
+    pub book_levels: u32,
+    /// The order book update ID of the last Top.
+    pub update_id: u64,
+    /// Trade time of the last trade, in milliseconds.
+    pub trade_time_ms: u64,
+    /// The best bid price of the last Top.
+    pub bid_price: f64,
+    /// The best ask price of the last Top.
+    pub ask_price: f64,
+    /// The mid price of the last Top.
+    pub mid_price: f64,
+    /// The mid price weighted by the opposite quantities of the last Top, leaning
+    /// toward the side with the thinner queue.
+    pub microprice: f64,
+    /// The imbalance of the best bid and ask quantities of the last Top.
+    pub top_imbalance: f64,
+    /// The imbalance of the quantities of the first `book_levels` levels of the book.
+    pub book_imbalance: f64,
+    /// The imbalance of the taker buy and sell volumes over each window of
+    /// `SIGNAL_FLOW_WINDOWS_MS`.
+    pub flow_imbalance: [f64; SIGNAL_FLOW_WINDOWS_MS.len()],
+}
+
+// SAFETY: `SignalMessage` is `repr(C)`, made only of integers and floats, valid for any bytes.
+unsafe impl ShmMessage for SignalMessage {
+    const LAYOUT_HASH: u64 = message_layout_hash!(SignalMessage {
+        symbol_id, book_levels, update_id, trade_time_ms, bid_price, ask_price, mid_price, microprice, top_imbalance,
+        book_imbalance, flow_imbalance
+    });
+}
+
 // Future: Add structured message types for different feed kinds
 // 
 // #[repr(C)]
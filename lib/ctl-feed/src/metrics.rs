@@ -0,0 +1,671 @@
+//! Shared metrics region for the market data rings.
+//!
+//! The region is created by ctl-resource-manager alongside the rings, with one
+//! `RingMetrics` entry registered per ring. The producer advances the ring head
+//! and each consumer advances its own cursor, so the lag of every consumer
+//! against the producer can be observed by any attached process. The region
+//! also holds the latency histograms of the feedgroups (see `latency`) and the
+//! message statistics of every stream (see `streams`).
+//!
+//! Every consumer attached to a ring reads every message published after it
+//! attached (fan-out): the consumers of a ring, e.g. a strategy, a recorder and
+//! a monitor, never compete for its messages. Each consumer has its own cursor,
+//! attached under the name of the consumer so its lag is attributed and a
+//! second instance of the same consumer is refused; the cursor of a consumer
+//! whose process died is taken over by its next instance, and no longer holds
+//! back the producer of a ring with a blocking overflow policy (see
+//! `DeadCursors`).
+//!
+//! Each entry also records the layout hash of the messages of its ring, as the
+//! DPDK rings carry no metadata: the secondaries check it with `check_layout`
+//! after looking a ring up, so a stale binary fails instead of reading garbage.
+//! The raw rings also record their slot size, the payload bytes of their
+//! messages configured per feed kind, checked on lookup with `check_slot_size`.
+
+use std::path::Path;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU8, AtomicU64, Ordering};
+
+use ctl_shm::{ShmMessage, ShmRegion, ShmSafe};
+
+use crate::{
+    validate_slot_size, RawMessage, RingError, LatencyGroup, OverflowCounters, StreamStats, LATENCY_GROUP_NAME_SIZE,
+    MAX_LATENCY_GROUPS, MAX_STREAMS, STREAM_NAME_SIZE,
+};
+
+/// Name of the market data metrics region.
+pub const METRICS_REGION_NAME: &str = "ctl_md_metrics";
+
+/// Maximum number of rings tracked in the metrics region.
+pub const MAX_METRIC_RINGS: usize = 256;
+
+/// Maximum number of consumers tracked per ring.
+pub const MAX_RING_CONSUMERS: usize = 8;
+
+/// Maximum length of a ring name in the metrics region.
+pub const RING_NAME_SIZE: usize = 32;
+
+/// Maximum length of a consumer name in the metrics region.
+pub const CONSUMER_NAME_SIZE: usize = 32;
+
+/// Interval between two checks of the processes of the consumers of a ring.
+pub const LIVENESS_CHECK_INTERVAL_NS: u64 = 1_000_000_000;
+
+/// Stores a name into zero padded bytes.
+fn store_name(slots: &[AtomicU8], name: &str) {
+    for (index, slot) in slots.iter().enumerate() {
+        slot.store(name.as_bytes().get(index).copied().unwrap_or(0), Ordering::Relaxed);
+    }
+}
+
+/// Loads a name from zero padded bytes.
+fn load_name(slots: &[AtomicU8]) -> String {
+    let bytes: Vec<u8> = slots
+        .iter()
+        .map(|b| b.load(Ordering::Relaxed))
+        .take_while(|&b| b != 0)
+        .collect();
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+/// Returns true if the process of `pid` is alive.
+fn process_alive(pid: u64) -> bool {
+    Path::new(&format!("/proc/{}", pid)).exists()
+}
+
+/// The position of a consumer attached to a ring.
+#[repr(C)]
+#[derive(Debug, Default)]
+pub struct ConsumerCursor {
+    /// Non-zero when the cursor is in use.
+    pub active: AtomicU64,
+    /// Number of messages consumed (or skipped) by the consumer.
+    pub position: AtomicU64,
+    /// Number of times the consumer was sped past by the producer.
+    pub overruns: AtomicU64,
+    /// The process of the consumer, zero if unknown.
+    pub pid: AtomicU64,
+    /// The consumer name, zero padded, empty for an anonymous consumer.
+    name: [AtomicU8; CONSUMER_NAME_SIZE],
+}
+
+impl ConsumerCursor {
+    /// Returns true if the cursor is in use.
+    pub fn is_active(&self) -> bool {
+        self.active.load(Ordering::Acquire) != 0
+    }
+
+    /// Returns the consumer name, empty for an anonymous consumer.
+    pub fn name(&self) -> String {
+        load_name(&self.name)
+    }
+
+    /// Advances the cursor past one consumed message.
+    ///
+    /// LATENCY: FAST_PATH
+    pub fn advance(&self) {
+        self.position.fetch_add(1, Ordering::Release);
+    }
+
+    /// Records that the consumer was sped past, resynchronizing it to `head`.
+    pub fn sped_past(&self, head: u64) {
+        self.overruns.fetch_add(1, Ordering::Relaxed);
+        self.position.store(head, Ordering::Release);
+    }
+}
+
+/// A consumer that is about to be (or has been) sped past by the producer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LagAlert {
+    /// The ring name.
+    pub ring: String,
+    /// The consumer cursor index.
+    pub consumer: usize,
+    /// The consumer name, empty for an anonymous consumer.
+    pub consumer_name: String,
+    /// Messages published but not yet consumed.
+    pub lag: u64,
+    /// The ring size.
+    pub ring_size: u64,
+}
+
+/// Metrics of a single ring.
+#[repr(C)]
+#[derive(Debug, Default)]
+pub struct RingMetrics {
+    /// The ring name, zero padded.
+    name: [AtomicU8; RING_NAME_SIZE],
+    /// The ring size, zero when the entry is unused.
+    pub ring_size: AtomicU64,
+    /// The layout hash of the messages of the ring (see `ShmMessage::LAYOUT_HASH`).
+    pub layout_hash: AtomicU64,
+    /// The payload bytes of the messages of a raw ring, zero for the rings of
+    /// fixed-size messages.
+    pub slot_size: AtomicU64,
+    /// Number of messages published by the producer.
+    pub head: AtomicU64,
+    /// Publish outcomes under the ring's overflow policy.
+    pub overflow: OverflowCounters,
+    /// Highest lag observed across consumers.
+    pub max_lag: AtomicU64,
+    /// Number of lag alerts raised.
+    pub lag_alerts: AtomicU64,
+    /// The consumers attached to the ring.
+    pub consumers: [ConsumerCursor; MAX_RING_CONSUMERS],
+}
+
+impl RingMetrics {
+    /// Returns true if the entry is registered.
+    pub fn is_registered(&self) -> bool {
+        self.ring_size.load(Ordering::Acquire) != 0
+    }
+
+    /// Returns the ring name.
+    pub fn name(&self) -> String {
+        load_name(&self.name)
+    }
+
+    /// Returns true if the entry is registered under `name`.
+    fn has_name(&self, name: &str) -> bool {
+        self.is_registered() && self.name() == name
+    }
+
+    /// Records a message published by the producer.
+    ///
+    /// LATENCY: FAST_PATH
+    pub fn record_publish(&self) -> u64 {
+        self.head.fetch_add(1, Ordering::Release) + 1
+    }
+
+    /// Attaches an anonymous consumer at the current head, returning its cursor index.
+    pub fn attach_consumer(&self) -> Option<usize> {
+        let index = self.consumers.iter().position(|c| {
+            c.active
+                .compare_exchange(0, 1, Ordering::AcqRel, Ordering::Relaxed)
+                .is_ok()
+        })?;
+        let cursor = &self.consumers[index];
+        store_name(&cursor.name, "");
+        cursor.pid.store(0, Ordering::Relaxed);
+        cursor.position.store(self.head.load(Ordering::Acquire), Ordering::Release);
+        Some(index)
+    }
+
+    /// Attaches the consumer `name` of process `pid` at the current head,
+    /// returning its cursor index.
+    ///
+    /// The cursor left attached under `name` by a process that died is taken
+    /// over, its overrun count kept.
+    ///
+    /// # Errors
+    /// Returns an error if the name is empty or too long, if a live process is
+    /// attached under the same name, or if every cursor is in use.
+    ///
+    /// LATENCY: SLOW_PATH
+    pub fn attach_named_consumer(&self, name: &str, pid: u64) -> Result<usize, RingError> {
+        if name.is_empty() || name.len() > CONSUMER_NAME_SIZE {
+            return Err(RingError::InvalidConsumer(name.to_string()));
+        }
+        let head = self.head.load(Ordering::Acquire);
+        if let Some(index) = self.consumers.iter().position(|c| c.is_active() && c.name() == name) {
+            let cursor = &self.consumers[index];
+            let owner = cursor.pid.load(Ordering::Acquire);
+            if owner == 0 || process_alive(owner) {
+                return Err(RingError::ConsumerAttached { ring: self.name(), consumer: name.to_string() });
+            }
+            if cursor.pid.compare_exchange(owner, pid, Ordering::AcqRel, Ordering::Relaxed).is_err() {
+                // Taken over by another instance in the meantime
+                return Err(RingError::ConsumerAttached { ring: self.name(), consumer: name.to_string() });
+            }
+            cursor.position.store(head, Ordering::Release);
+            return Ok(index);
+        }
+        let index = self.attach_consumer().ok_or_else(|| RingError::NoFreeCursor(self.name()))?;
+        let cursor = &self.consumers[index];
+        store_name(&cursor.name, name);
+        cursor.pid.store(pid, Ordering::Release);
+        Ok(index)
+    }
+
+    /// Returns the index of the cursor attached under the consumer `name`.
+    pub fn find_consumer(&self, name: &str) -> Option<usize> {
+        self.consumers.iter().position(|c| c.is_active() && c.name() == name)
+    }
+
+    /// Returns an iterator over the attached cursors, with their index.
+    pub fn attached(&self) -> impl Iterator<Item = (usize, &ConsumerCursor)> {
+        self.consumers.iter().enumerate().filter(|(_, c)| c.is_active())
+    }
+
+    /// Detaches a consumer, releasing its cursor.
+    pub fn detach_consumer(&self, index: usize) {
+        self.consumers[index].active.store(0, Ordering::Release);
+    }
+
+    /// Returns the lag of a consumer behind the producer, if it is attached.
+    pub fn lag(&self, index: usize) -> Option<u64> {
+        let cursor = &self.consumers[index];
+        if cursor.active.load(Ordering::Acquire) == 0 {
+            return None;
+        }
+        let head = self.head.load(Ordering::Acquire);
+        Some(head.saturating_sub(cursor.position.load(Ordering::Acquire)))
+    }
+
+    /// Returns true if the producer can publish without overwriting a message
+    /// not yet consumed by an attached consumer, the cursors of `dead`, one bit
+    /// per cursor index (see `dead_cursors`), aside.
+    ///
+    /// LATENCY: FAST_PATH
+    pub fn has_free_slot(&self, dead: u64) -> bool {
+        let ring_size = self.ring_size.load(Ordering::Acquire);
+        (0..MAX_RING_CONSUMERS)
+            .filter(|index| dead & (1 << index) == 0)
+            .filter_map(|index| self.lag(index))
+            .all(|lag| lag < ring_size)
+    }
+
+    /// Returns the attached cursors whose process is no longer alive, one bit
+    /// per cursor index. The cursors of an unknown process are kept.
+    ///
+    /// LATENCY: SLOW_PATH
+    pub fn dead_cursors(&self) -> u64 {
+        self.attached()
+            .filter(|(_, cursor)| {
+                let pid = cursor.pid.load(Ordering::Acquire);
+                pid != 0 && !process_alive(pid)
+            })
+            .fold(0, |dead, (index, _)| dead | (1 << index))
+    }
+
+    /// Checks the lag of every attached consumer, raising an alert for those
+    /// whose lag reaches `threshold_pct` percent of the ring size.
+    ///
+    /// LATENCY: SLOW_PATH
+    pub fn check_lag(&self, threshold_pct: u64) -> Vec<LagAlert> {
+        let ring_size = self.ring_size.load(Ordering::Acquire);
+        let mut alerts = Vec::new();
+        if ring_size == 0 {
+            return alerts;
+        }
+        for index in 0..MAX_RING_CONSUMERS {
+            let Some(lag) = self.lag(index) else { continue };
+            self.max_lag.fetch_max(lag, Ordering::Relaxed);
+            if lag * 100 >= ring_size * threshold_pct {
+                self.lag_alerts.fetch_add(1, Ordering::Relaxed);
+                let consumer_name = self.consumers[index].name();
+                alerts.push(LagAlert { ring: self.name(), consumer: index, consumer_name, lag, ring_size });
+            }
+        }
+        alerts
+    }
+}
+
+/// The cursors of a ring whose process is no longer alive, checked at most
+/// every `LIVENESS_CHECK_INTERVAL_NS` by the producer, the verdict cached in
+/// between.
+#[derive(Debug, Default)]
+pub struct DeadCursors {
+    /// The dead cursors, one bit per cursor index.
+    dead: AtomicU64,
+    /// Monotonic time of the last check, zero before the first one.
+    checked_ns: AtomicU64,
+}
+
+impl DeadCursors {
+    /// Returns the dead cursors of `ring`, checking their processes again once
+    /// the interval elapsed.
+    ///
+    /// LATENCY: FAST_PATH (except when checking)
+    pub fn get(&self, ring: &RingMetrics) -> u64 {
+        let now_ns = ctl_time::monotonic_ns();
+        let checked_ns = self.checked_ns.load(Ordering::Relaxed);
+        let due = checked_ns == 0 || now_ns.saturating_sub(checked_ns) >= LIVENESS_CHECK_INTERVAL_NS;
+        // A single clone of the guard checks per interval, the others keep the cached verdict
+        if due && self.checked_ns.compare_exchange(checked_ns, now_ns, Ordering::AcqRel, Ordering::Relaxed).is_ok() {
+            self.dead.store(ring.dead_cursors(), Ordering::Relaxed);
+        }
+        self.dead.load(Ordering::Relaxed)
+    }
+}
+
+/// The metrics region, holding one entry per ring.
+#[repr(C)]
+pub struct MetricsRegion {
+    /// The ring entries.
+    pub rings: [RingMetrics; MAX_METRIC_RINGS],
+    /// The latency groups, registered by the feedgroups.
+    pub latency: [LatencyGroup; MAX_LATENCY_GROUPS],
+    /// The stream statistics, registered by the feedgroups.
+    pub streams: [StreamStats; MAX_STREAMS],
+}
+
+// SAFETY: `MetricsRegion` is `repr(C)`, made only of atomics and valid when zeroed.
+unsafe impl ShmSafe for MetricsRegion {}
+
+impl MetricsRegion {
+    /// Registers a ring of messages of `layout_hash`, returning its entry index.
+    /// Returns `None` if the name is too long or the region is full.
+    ///
+    /// LATENCY: SLOW_PATH
+    pub fn register_ring(&self, name: &str, ring_size: u64, layout_hash: u64) -> Option<usize> {
+        if name.is_empty() || name.len() > RING_NAME_SIZE || ring_size == 0 {
+            return None;
+        }
+        if let Some(index) = self.find_ring(name) {
+            self.rings[index].slot_size.store(0, Ordering::Release);
+            self.rings[index].layout_hash.store(layout_hash, Ordering::Release);
+            return Some(index);
+        }
+        let index = self.rings.iter().position(|r| !r.is_registered())?;
+        let entry = &self.rings[index];
+        store_name(&entry.name, name);
+        entry.layout_hash.store(layout_hash, Ordering::Relaxed);
+        entry.slot_size.store(0, Ordering::Relaxed);
+        entry.ring_size.store(ring_size, Ordering::Release);
+        Some(index)
+    }
+
+    /// Registers a ring of `RawMessage` whose messages carry up to `slot_size`
+    /// payload bytes, returning its entry index.
+    /// Returns `None` if the name is too long or the region is full.
+    ///
+    /// LATENCY: SLOW_PATH
+    pub fn register_raw_ring(&self, name: &str, ring_size: u64, slot_size: usize) -> Option<usize> {
+        let index = self.register_ring(name, ring_size, RawMessage::LAYOUT_HASH)?;
+        self.rings[index].slot_size.store(slot_size as u64, Ordering::Release);
+        Some(index)
+    }
+
+    /// Finds the entry index of a ring by name.
+    pub fn find_ring(&self, name: &str) -> Option<usize> {
+        self.rings.iter().position(|r| r.has_name(name))
+    }
+
+    /// Checks that a ring was created for messages of the layout of `T`, the
+    /// one this binary was built with.
+    ///
+    /// LATENCY: SLOW_PATH
+    pub fn check_layout<T: ShmMessage>(&self, name: &str) -> Result<(), RingError> {
+        let index = self.find_ring(name).ok_or_else(|| RingError::Unregistered(name.to_string()))?;
+        let found = self.rings[index].layout_hash.load(Ordering::Acquire);
+        if found != T::LAYOUT_HASH {
+            return Err(RingError::LayoutMismatch { ring: name.to_string(), expected: T::LAYOUT_HASH, found });
+        }
+        Ok(())
+    }
+
+    /// Returns the slot size of a raw ring, checked against the `RawMessage`
+    /// this binary was built with.
+    ///
+    /// LATENCY: SLOW_PATH
+    pub fn slot_size(&self, name: &str) -> Result<usize, RingError> {
+        let index = self.find_ring(name).ok_or_else(|| RingError::Unregistered(name.to_string()))?;
+        let slot_size = self.rings[index].slot_size.load(Ordering::Acquire) as usize;
+        validate_slot_size(slot_size).map_err(|reason| RingError::InvalidSlotSize { ring: name.to_string(), reason })?;
+        Ok(slot_size)
+    }
+
+    /// Checks that a raw ring was registered with the slot size the producer
+    /// is configured with, the configurations having changed since the
+    /// resource manager created the ring otherwise.
+    ///
+    /// LATENCY: SLOW_PATH
+    pub fn check_slot_size(&self, name: &str, expected: usize) -> Result<(), RingError> {
+        let found = self.slot_size(name)?;
+        if found != expected {
+            return Err(RingError::SlotSizeMismatch { ring: name.to_string(), expected, found });
+        }
+        Ok(())
+    }
+
+    /// Returns an iterator over the registered ring entries.
+    pub fn registered(&self) -> impl Iterator<Item = &RingMetrics> {
+        self.rings.iter().filter(|r| r.is_registered())
+    }
+
+    /// Registers a latency group, returning its index.
+    /// Returns `None` if the name is too long or the region is full.
+    ///
+    /// LATENCY: SLOW_PATH
+    pub fn register_latency_group(&self, name: &str) -> Option<usize> {
+        if name.is_empty() || name.len() > LATENCY_GROUP_NAME_SIZE {
+            return None;
+        }
+        if let Some(index) = self.find_latency_group(name) {
+            return Some(index);
+        }
+        self.latency.iter().position(|group| group.claim(name))
+    }
+
+    /// Finds the index of a latency group by name.
+    pub fn find_latency_group(&self, name: &str) -> Option<usize> {
+        self.latency.iter().position(|group| group.has_name(name))
+    }
+
+    /// Registers a stream at `now_ms`, returning its entry index.
+    /// Returns `None` if the name is too long or the region is full.
+    ///
+    /// LATENCY: SLOW_PATH
+    pub fn register_stream(&self, name: &str, now_ms: u64) -> Option<usize> {
+        if name.is_empty() || name.len() > STREAM_NAME_SIZE {
+            return None;
+        }
+        if let Some(index) = self.find_stream(name) {
+            return Some(index);
+        }
+        self.streams.iter().position(|stream| stream.claim(name, now_ms))
+    }
+
+    /// Finds the entry index of a stream by name.
+    pub fn find_stream(&self, name: &str) -> Option<usize> {
+        self.streams.iter().position(|stream| stream.has_name(name))
+    }
+
+    /// Returns an iterator over the registered stream entries.
+    pub fn registered_streams(&self) -> impl Iterator<Item = &StreamStats> {
+        self.streams.iter().filter(|stream| stream.is_registered())
+    }
+}
+
+/// A handle to the metrics entry of a single ring.
+#[derive(Clone)]
+pub struct RingMetricsHandle {
+    /// The metrics region.
+    region: Arc<ShmRegion<MetricsRegion>>,
+    /// The entry index of the ring.
+    index: usize,
+}
+
+impl RingMetricsHandle {
+    /// Looks up the metrics entry of a ring by name.
+    pub fn lookup(region: Arc<ShmRegion<MetricsRegion>>, ring_name: &str) -> Option<Self> {
+        let index = region.find_ring(ring_name)?;
+        Some(Self { region, index })
+    }
+
+    /// Returns the ring metrics.
+    pub fn get(&self) -> &RingMetrics {
+        &self.region.rings[self.index]
+    }
+}
+
+impl std::fmt::Debug for RingMetricsHandle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RingMetricsHandle")
+            .field("ring", &self.get().name())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ring(name: &str, ring_size: u64) -> RingMetrics {
+        let metrics = RingMetrics::default();
+        for (slot, byte) in metrics.name.iter().zip(name.bytes()) {
+            slot.store(byte, Ordering::Relaxed);
+        }
+        metrics.ring_size.store(ring_size, Ordering::Relaxed);
+        metrics
+    }
+
+    #[test]
+    fn test_name() {
+        let metrics = ring("TOP_0_PS", 16);
+        assert_eq!(metrics.name(), "TOP_0_PS");
+        assert!(metrics.has_name("TOP_0_PS"));
+        assert!(!metrics.has_name("TOP_1_PS"));
+    }
+
+    #[test]
+    fn test_consumer_lag() {
+        let metrics = ring("TOP_0_PS", 16);
+        metrics.record_publish();
+        let consumer = metrics.attach_consumer().unwrap();
+        assert_eq!(metrics.lag(consumer), Some(0));
+
+        for _ in 0..4 {
+            metrics.record_publish();
+        }
+        assert_eq!(metrics.lag(consumer), Some(4));
+
+        metrics.consumers[consumer].advance();
+        assert_eq!(metrics.lag(consumer), Some(3));
+
+        metrics.detach_consumer(consumer);
+        assert_eq!(metrics.lag(consumer), None);
+    }
+
+    #[test]
+    fn test_named_consumers() {
+        let metrics = ring("TRADE_0_PS", 16);
+        let pid = std::process::id() as u64;
+        let strategy = metrics.attach_named_consumer("strategy", pid).unwrap();
+        metrics.record_publish();
+        let recorder = metrics.attach_named_consumer("recorder", pid).unwrap();
+        assert_ne!(strategy, recorder);
+        assert_eq!(metrics.find_consumer("recorder"), Some(recorder));
+        assert_eq!(metrics.consumers[recorder].name(), "recorder");
+
+        // A second instance of a live consumer is refused
+        assert!(matches!(
+            metrics.attach_named_consumer("recorder", pid),
+            Err(RingError::ConsumerAttached { .. })
+        ));
+        assert!(matches!(metrics.attach_named_consumer("", pid), Err(RingError::InvalidConsumer(_))));
+
+        // Every consumer reads every message, at its own position
+        metrics.record_publish();
+        metrics.consumers[recorder].advance();
+        assert_eq!(metrics.lag(strategy), Some(2));
+        assert_eq!(metrics.lag(recorder), Some(0));
+        assert_eq!(metrics.attached().count(), 2);
+    }
+
+    #[test]
+    fn test_named_consumer_takeover() {
+        let metrics = ring("TRADE_0_PS", 16);
+        // No process has this PID, above the kernel maximum
+        let dead = metrics.attach_named_consumer("monitor", u32::MAX as u64).unwrap();
+        metrics.record_publish();
+        let pid = std::process::id() as u64;
+        assert_eq!(metrics.attach_named_consumer("monitor", pid).unwrap(), dead);
+        assert_eq!(metrics.consumers[dead].pid.load(Ordering::Relaxed), pid);
+        assert_eq!(metrics.lag(dead), Some(0));
+    }
+
+    #[test]
+    fn test_dead_cursors() {
+        let metrics = ring("TRADE_0_PS", 4);
+        let alive = metrics.attach_named_consumer("recorder", std::process::id() as u64).unwrap();
+        // No process has this PID, above the kernel maximum
+        let dead = metrics.attach_named_consumer("monitor", u32::MAX as u64).unwrap();
+        let anonymous = metrics.attach_consumer().unwrap();
+        assert_eq!(metrics.dead_cursors(), 1 << dead);
+
+        // The dead consumer a ring behind holds back the producer until skipped
+        for _ in 0..4 {
+            metrics.record_publish();
+        }
+        metrics.consumers[alive].position.store(4, Ordering::Relaxed);
+        metrics.consumers[anonymous].position.store(4, Ordering::Relaxed);
+        assert!(!metrics.has_free_slot(0));
+        let cursors = DeadCursors::default();
+        assert!(metrics.has_free_slot(cursors.get(&metrics)));
+
+        // The verdict is cached until the next check
+        metrics.detach_consumer(dead);
+        assert_eq!(cursors.get(&metrics), 1 << dead);
+        assert_eq!(metrics.dead_cursors(), 0);
+    }
+
+    #[test]
+    fn test_check_lag_threshold() {
+        let metrics = ring("TRADE_0_PS", 16);
+        let consumer = metrics.attach_consumer().unwrap();
+        for _ in 0..11 {
+            metrics.record_publish();
+        }
+        assert!(metrics.check_lag(75).is_empty());
+
+        metrics.record_publish();
+        let alerts = metrics.check_lag(75);
+        assert_eq!(
+            alerts,
+            vec![LagAlert { ring: "TRADE_0_PS".to_string(), consumer, consumer_name: String::new(), lag: 12, ring_size: 16 }]
+        );
+        assert_eq!(metrics.max_lag.load(Ordering::Relaxed), 12);
+        assert_eq!(metrics.lag_alerts.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn test_sped_past_resyncs() {
+        let metrics = ring("TOP_0_PS", 4);
+        let consumer = metrics.attach_consumer().unwrap();
+        for _ in 0..6 {
+            metrics.record_publish();
+        }
+        metrics.consumers[consumer].sped_past(metrics.head.load(Ordering::Relaxed));
+        assert_eq!(metrics.lag(consumer), Some(0));
+        assert_eq!(metrics.consumers[consumer].overruns.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn test_check_layout() {
+        use crate::{CandleMessage, RawMessage};
+
+        let name = format!("ctl_feed_metrics_test_layout_{}", std::process::id());
+        let region = ShmRegion::<MetricsRegion>::create(&name).unwrap();
+        region.register_ring("TRADE_0_PS", 1024, RawMessage::LAYOUT_HASH).unwrap();
+
+        assert!(region.check_layout::<RawMessage>("TRADE_0_PS").is_ok());
+        assert!(matches!(
+            region.check_layout::<CandleMessage>("TRADE_0_PS"),
+            Err(RingError::LayoutMismatch { found, .. }) if found == RawMessage::LAYOUT_HASH
+        ));
+        assert!(matches!(region.check_layout::<RawMessage>("TOP_0_PS"), Err(RingError::Unregistered(_))));
+    }
+
+    #[test]
+    fn test_slot_size() {
+        use crate::{CandleMessage, RAW_MESSAGE_SIZE};
+
+        let name = format!("ctl_feed_metrics_test_slot_{}", std::process::id());
+        let region = ShmRegion::<MetricsRegion>::create(&name).unwrap();
+        region.register_raw_ring("TOP_0_PS", 1024, 128).unwrap();
+        region.register_raw_ring("TRADE_0_PS", 1024, RAW_MESSAGE_SIZE).unwrap();
+        region.register_ring("KLINE_0_PS", 1024, CandleMessage::LAYOUT_HASH).unwrap();
+
+        assert!(region.check_layout::<RawMessage>("TOP_0_PS").is_ok());
+        assert_eq!(region.slot_size("TOP_0_PS").unwrap(), 128);
+        assert!(region.check_slot_size("TRADE_0_PS", RAW_MESSAGE_SIZE).is_ok());
+        assert!(matches!(
+            region.check_slot_size("TOP_0_PS", 256),
+            Err(RingError::SlotSizeMismatch { expected: 256, found: 128, .. })
+        ));
+        assert!(matches!(region.slot_size("KLINE_0_PS"), Err(RingError::InvalidSlotSize { .. })));
+        assert!(matches!(region.slot_size("STATS_0_PS"), Err(RingError::Unregistered(_))));
+    }
+}
@@ -1,7 +1,120 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+
 use thiserror::Error;
 
 #[derive(Debug, Error)]
 pub enum DummyParserError {
     #[error("dummy parser error")]
     General,
-}
\ No newline at end of file
+    #[error("message dropped, publishing paused")]
+    Paused,
+    #[error("message dropped, ring full under its overflow policy")]
+    Overflow,
+    #[error("message of {len} bytes exceeds the {max} byte buffer")]
+    Oversized { len: usize, max: usize },
+    #[error("malformed FIX message, {0}")]
+    MalformedFix(&'static str),
+    #[error("malformed SBE message, {0}")]
+    MalformedSbe(&'static str),
+    #[error("fragment publish failed, {0}")]
+    Fragment(String),
+    #[error("publish failed, {0}")]
+    Publish(String),
+}
+
+impl DummyParserError {
+    /// Returns true if the message failed to parse or publish, unlike a
+    /// message dropped on purpose while paused or by the overflow policy of
+    /// its ring, counted in the ring metrics.
+    pub fn is_failure(&self) -> bool {
+        !matches!(self, DummyParserError::Paused | DummyParserError::Overflow)
+    }
+}
+
+/// The outcome of a message parsed by the `DummyParser`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseOutcome {
+    /// Parsed into the message buffer, for the worker to publish to the ring of its feedgroup.
+    Parsed,
+    /// Published by the parser to the ring of its symbol.
+    Routed,
+    /// Published by the parser to the ring of its feedgroup, timing the publish.
+    Published,
+}
+
+impl ParseOutcome {
+    /// Returns the result of the parse handed to the feedgroup worker, which
+    /// publishes the parsed messages and skips the others.
+    ///
+    /// LATENCY: FAST_PATH
+    pub fn to_worker(self) -> Result<(), ParseSkip> {
+        match self {
+            ParseOutcome::Parsed => Ok(()),
+            ParseOutcome::Routed => Err(ParseSkip::Routed),
+            ParseOutcome::Published => Err(ParseSkip::Published),
+        }
+    }
+}
+
+/// Why a feedgroup worker skips a message rather than publishing it to the
+/// ring of its feedgroup, the only other result of its parse.
+#[derive(Debug, Error)]
+pub enum ParseSkip {
+    #[error(transparent)]
+    Error(#[from] DummyParserError),
+    #[error("message published to the ring of its symbol")]
+    Routed,
+    #[error("message published to the ring of its feedgroup")]
+    Published,
+}
+
+/// The messages a feedgroup failed to parse, counted by its parser and read
+/// by the thread reporting them.
+#[derive(Debug, Clone, Default)]
+pub struct ParseErrorCounter(Arc<AtomicU64>);
+
+impl ParseErrorCounter {
+    /// Creates a counter at zero.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Counts the error of a message, unless dropped while paused.
+    ///
+    /// LATENCY: FAST_PATH
+    pub fn record(&self, error: &DummyParserError) {
+        if error.is_failure() {
+            self.0.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Returns the messages failed to parse.
+    pub fn count(&self) -> u64 {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_error_counter() {
+        let counter = ParseErrorCounter::new();
+        let shared = counter.clone();
+        shared.record(&DummyParserError::MalformedFix("symbol"));
+        shared.record(&DummyParserError::Oversized { len: 4096, max: 2048 });
+        // Dropped on purpose, not failures
+        shared.record(&DummyParserError::Paused);
+        shared.record(&DummyParserError::Overflow);
+        assert_eq!(counter.count(), 2);
+    }
+
+    #[test]
+    fn test_parse_outcome() {
+        assert!(ParseOutcome::Parsed.to_worker().is_ok());
+        assert!(matches!(ParseOutcome::Routed.to_worker(), Err(ParseSkip::Routed)));
+        assert!(matches!(ParseOutcome::Published.to_worker(), Err(ParseSkip::Published)));
+    }
+}
@@ -0,0 +1,249 @@
+//! Translation of the FIX market data into the Binance JSON payloads.
+//!
+//! The consumers of the rings read the payloads of the websocket streams, so
+//! the events polled from a `FixConn` are translated into the payload of the
+//! equivalent stream (e.g. `bookTicker`), then parsed by the wrapped
+//! `DummyParser` like a websocket payload, tagged with the FIX medium.
+
+use std::io::Write;
+
+use atx_feed::FeedParseProtocol;
+use ctl_fix::{parse_utc_timestamp, tag, FixConn, FixMessage};
+use dpdk::Aligned;
+use hashbrown::HashMap;
+
+use crate::{DummyParser, EventType, ParseInto, RawMessage, Top, Trade};
+use super::{DummyParserError, ParseOutcome, ParseSkip};
+
+/// The `MDEntryType` of the bids and offers.
+const BID: &[u8] = b"0";
+const OFFER: &[u8] = b"1";
+
+/// The `MDUpdateAction` deleting an entry.
+const DELETE: &[u8] = b"2";
+
+/// The `AggressorSide` of a trade whose buyer is the maker.
+const SELL: &[u8] = b"2";
+
+/// The best bid and offer of a symbol, kept across its book ticker updates.
+#[derive(Debug, Clone, Default)]
+struct BookTop {
+    bid_price: String,
+    bid_qty: String,
+    ask_price: String,
+    ask_qty: String,
+}
+
+/// Returns a field value safe to embed in a JSON string.
+fn json_str(value: Option<&[u8]>, field: &'static str) -> Result<&str, DummyParserError> {
+    value
+        .filter(|value| !value.iter().any(|&b| b == b'"' || b == b'\\' || b < b' '))
+        .and_then(|value| std::str::from_utf8(value).ok())
+        .ok_or(DummyParserError::MalformedFix(field))
+}
+
+/// Returns the value of an unset side of the book.
+fn or_zero(value: &str) -> &str {
+    if value.is_empty() { "0" } else { value }
+}
+
+/// Parses the events of the FIX market data sessions.
+#[derive(Debug, Clone)]
+pub struct FixParser {
+    /// The parser of the translated payloads.
+    inner: DummyParser,
+    /// The best bid and offer of the symbols, by symbol.
+    books: HashMap<String, BookTop>,
+    /// The payload translated from the last event.
+    payload: Vec<u8>,
+}
+
+impl From<DummyParser> for FixParser {
+    fn from(inner: DummyParser) -> Self {
+        Self::new(inner)
+    }
+}
+
+impl FixParser {
+    /// Creates a parser of the FIX events, parsing the translated payloads with `inner`.
+    pub fn new(inner: DummyParser) -> Self {
+        Self { inner, books: HashMap::new(), payload: Vec::with_capacity(256) }
+    }
+
+    /// Translates a book ticker event into its `bookTicker` payload, updating
+    /// the best bid and offer of its symbol with its entries.
+    ///
+    /// LATENCY: FAST_PATH
+    fn translate_top(&mut self, event: &[u8]) -> Result<(), DummyParserError> {
+        let event = FixMessage::new(event);
+        let symbol = json_str(event.get(tag::SYMBOL), "symbol")?;
+        let book = self.books.entry_ref(symbol).or_default();
+        for entry in event.entries(tag::NO_MD_ENTRIES) {
+            let (price, qty) = match entry.get(tag::MD_ENTRY_TYPE) {
+                Some(BID) => (&mut book.bid_price, &mut book.bid_qty),
+                Some(OFFER) => (&mut book.ask_price, &mut book.ask_qty),
+                _ => continue,
+            };
+            price.clear();
+            qty.clear();
+            if entry.get(tag::MD_UPDATE_ACTION) != Some(DELETE) {
+                price.push_str(json_str(entry.get(tag::MD_ENTRY_PX), "entry price")?);
+                qty.push_str(json_str(entry.get(tag::MD_ENTRY_SIZE), "entry size")?);
+            }
+        }
+
+        self.payload.clear();
+        let _ = write!(
+            self.payload,
+            r#"{{"u":{},"s":"{}","b":"{}","B":"{}","a":"{}","A":"{}"}}"#,
+            event.get_u64(tag::LAST_BOOK_UPDATE_ID).unwrap_or_default(),
+            symbol,
+            or_zero(&book.bid_price),
+            or_zero(&book.bid_qty),
+            or_zero(&book.ask_price),
+            or_zero(&book.ask_qty)
+        );
+        Ok(())
+    }
+
+    /// Translates a trade event into its `trade` payload.
+    ///
+    /// LATENCY: FAST_PATH
+    fn translate_trade(&mut self, event: &[u8]) -> Result<(), DummyParserError> {
+        let event = FixMessage::new(event);
+        let entry = event.entries(tag::NO_MD_ENTRIES).next().ok_or(DummyParserError::MalformedFix("no entry"))?;
+        let trade_time_ms = entry
+            .get(tag::TRANSACT_TIME)
+            .or_else(|| event.get(tag::TRANSACT_TIME))
+            .and_then(parse_utc_timestamp)
+            .ok_or(DummyParserError::MalformedFix("transact time"))?;
+
+        self.payload.clear();
+        let _ = write!(
+            self.payload,
+            r#"{{"e":"trade","E":{},"s":"{}","t":{},"p":"{}","q":"{}","T":{},"m":{}}}"#,
+            trade_time_ms,
+            json_str(event.get(tag::SYMBOL), "symbol")?,
+            entry.get_u64(tag::TRADE_ID).unwrap_or_default(),
+            json_str(entry.get(tag::MD_ENTRY_PX), "entry price")?,
+            json_str(entry.get(tag::MD_ENTRY_SIZE), "entry size")?,
+            trade_time_ms,
+            entry.get(tag::AGGRESSOR_SIDE) == Some(SELL)
+        );
+        Ok(())
+    }
+
+    /// Parses the translated payload of an event into the message buffer.
+    ///
+    /// LATENCY: FAST_PATH
+    fn parse_payload(
+            &mut self,
+            message: &mut RawMessage,
+            event_type: EventType,
+        ) -> Result<ParseOutcome, DummyParserError> {
+
+        let payload = std::mem::take(&mut self.payload);
+        let parsed = self.inner.parse_event(&payload, message, event_type);
+        self.payload = payload;
+        parsed
+    }
+}
+
+impl FeedParseProtocol<FixConn<Top>, Top> for FixParser {
+
+    type FeedParsedMessage = RawMessage;
+    type FeedParseError = ParseSkip;
+
+    fn parse(
+            &mut self,
+            raw_data: atx_feed::FeedData,
+            parsed_data: &mut Aligned<Self::FeedParsedMessage>
+        ) -> Result<(), Self::FeedParseError> {
+
+        ParseInto::<FixConn<Top>>::parse_into(self, raw_data, parsed_data.get_mut())
+    }
+}
+
+impl ParseInto<FixConn<Top>> for FixParser {
+    fn parse_into(&mut self, raw_data: atx_feed::FeedData, message: &mut RawMessage) -> Result<(), ParseSkip> {
+        self.translate_top(raw_data).inspect_err(|e| self.inner.record_error(e))?;
+        self.parse_payload(message, EventType::BookTicker)?.to_worker()
+    }
+}
+
+impl FeedParseProtocol<FixConn<Trade>, Trade> for FixParser {
+
+    type FeedParsedMessage = RawMessage;
+    type FeedParseError = ParseSkip;
+
+    fn parse(
+            &mut self,
+            raw_data: atx_feed::FeedData,
+            parsed_data: &mut Aligned<Self::FeedParsedMessage>
+        ) -> Result<(), Self::FeedParseError> {
+
+        ParseInto::<FixConn<Trade>>::parse_into(self, raw_data, parsed_data.get_mut())
+    }
+}
+
+impl ParseInto<FixConn<Trade>> for FixParser {
+    fn parse_into(&mut self, raw_data: atx_feed::FeedData, message: &mut RawMessage) -> Result<(), ParseSkip> {
+        self.translate_trade(raw_data).inspect_err(|e| self.inner.record_error(e))?;
+        self.parse_payload(message, EventType::Trade)?.to_worker()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{payload_symbol, MediumTag};
+
+    fn event(fields: &str) -> Vec<u8> {
+        fields.replace('|', "\x01").into_bytes()
+    }
+
+    fn payload(parser: &FixParser) -> &str {
+        std::str::from_utf8(&parser.payload).unwrap()
+    }
+
+    #[test]
+    fn test_translate_top() {
+        let mut parser = FixParser::new(DummyParser::new(MediumTag::Fix));
+        let snapshot = event("35=W|55=BTCUSDT|262=BOOK_TICKER_BTCUSDT|25044=10|268=2|269=0|270=60000.00|271=1.5|55=BTCUSDT|269=1|270=60001.00|271=2.5|55=BTCUSDT|");
+        parser.translate_top(&snapshot).unwrap();
+        assert_eq!(
+            payload(&parser),
+            r#"{"u":10,"s":"BTCUSDT","b":"60000.00","B":"1.5","a":"60001.00","A":"2.5"}"#
+        );
+
+        // An update of the offer keeps the bid, and the payload reads as a websocket one
+        let update = event("35=X|55=BTCUSDT|262=BOOK_TICKER_BTCUSDT|25044=11|268=1|279=1|269=1|270=60002.00|271=0.5|55=BTCUSDT|");
+        parser.translate_top(&update).unwrap();
+        assert_eq!(
+            payload(&parser),
+            r#"{"u":11,"s":"BTCUSDT","b":"60000.00","B":"1.5","a":"60002.00","A":"0.5"}"#
+        );
+        assert_eq!(payload_symbol(&parser.payload), Some("BTCUSDT"));
+
+        let deleted = event("35=X|55=BTCUSDT|25044=12|268=1|279=2|269=0|55=BTCUSDT|");
+        parser.translate_top(&deleted).unwrap();
+        assert!(payload(&parser).contains(r#""b":"0","B":"0""#));
+
+        assert!(parser.translate_top(&event("35=X|268=1|279=1|269=1|270=1|271=1|")).is_err());
+    }
+
+    #[test]
+    fn test_translate_trade() {
+        let mut parser = FixParser::new(DummyParser::new(MediumTag::Fix));
+        let trade = event("35=X|55=BTCUSDT|262=TRADE_BTCUSDT|268=1|279=0|269=2|270=60000.00|271=0.01|1003=42|60=20231114-22:13:20.123456|2446=2|55=BTCUSDT|");
+        parser.translate_trade(&trade).unwrap();
+        assert_eq!(
+            payload(&parser),
+            r#"{"e":"trade","E":1700000000123,"s":"BTCUSDT","t":42,"p":"60000.00","q":"0.01","T":1700000000123,"m":true}"#
+        );
+
+        let injected = event("35=X|55=BTC\"USDT|268=1|279=0|269=2|270=1|271=1|60=20231114-22:13:20|");
+        assert!(matches!(parser.translate_trade(&injected), Err(DummyParserError::MalformedFix("symbol"))));
+    }
+}
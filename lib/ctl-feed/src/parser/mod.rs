@@ -1,5 +1,9 @@
 mod parser;
+mod fix;
+mod sbe;
 mod error;
 
-pub use error::DummyParserError;
-pub use parser::DummyParser;
\ No newline at end of file
+pub use error::{DummyParserError, ParseErrorCounter, ParseOutcome, ParseSkip};
+pub use parser::DummyParser;
+pub use fix::FixParser;
+pub use sbe::{SbeParser, SBE_BEST_BID_ASK_SUFFIX};
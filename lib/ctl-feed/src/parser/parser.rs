@@ -1,17 +1,564 @@
+use std::collections::HashMap;
+
 use atx_feed::FeedParseProtocol;
+use ctl_core::{TraceContext, Tracer};
 use ctl_websocket::WSConn;
 use dpdk::Aligned;
 
-use crate::{AggTrade, Top, Trade, RawMessage};
-use super::DummyParserError;
+use crate::{
+    fragment_count, payload_symbol, AggTrade, Depth, Top, Trade, EventType, FixedPoint, FragmentSink, LastTopHandle, MediumTag,
+    OverflowGuard, ParseInto, PauseHandle, RawMessage, RingError, RingMetricsHandle, StreamStatsHandle, SymbolScale,
+    MAX_FRAGMENTS, RAW_MESSAGE_SIZE, UNKNOWN_SYMBOL_ID,
+};
+#[cfg(feature = "latency-histograms")]
+use crate::LatencyRecorder;
+#[cfg(feature = "message-checksums")]
+use crate::Checksummed;
+use super::{DummyParserError, ParseErrorCounter, ParseOutcome, ParseSkip};
 
+/// The ring of a symbol the parser publishes to, the worker publishing to the
+/// ring of the feedgroup only.
 #[derive(Debug, Clone)]
-pub struct DummyParser;
+struct SymbolRing {
+    /// The ring, admitting the messages under its overflow policy.
+    ring: FragmentSink,
+    /// The metrics of the ring, sequencing its messages.
+    metrics: RingMetricsHandle,
+}
+
+#[derive(Debug, Clone)]
+pub struct DummyParser {
+    /// The medium tagged in the header of the parsed messages.
+    medium: MediumTag,
+    /// The symbol IDs tagged in the header of the parsed messages, by uppercase symbol.
+    symbol_ids: HashMap<String, u32>,
+    /// The tick and step exponents of the prices and quantities, by symbol ID.
+    scales: HashMap<u32, SymbolScale>,
+    /// The payload bytes of the messages of the ring, the larger payloads
+    /// fragmented, or rejected without a fragment sink.
+    slot_size: usize,
+    /// The ring the leading fragments of the payloads larger than a slot are published to.
+    fragments: Option<FragmentSink>,
+    /// Metrics of the ring the parsed messages are published to.
+    metrics: Option<RingMetricsHandle>,
+    /// Overflow policy of the ring, dropping the messages it refuses.
+    overflow: Option<OverflowGuard>,
+    /// The rings of the symbols published to their own ring, by symbol ID.
+    symbol_rings: HashMap<u32, SymbolRing>,
+    /// Last-value cache of the Top feed, overwritten on every update.
+    last_top: Option<LastTopHandle>,
+    /// Pause state of the feedgroup, dropping the paused messages.
+    pause: Option<PauseHandle>,
+    /// Message statistics of the streams of the feedgroup.
+    streams: Option<StreamStatsHandle>,
+    /// The messages of the feedgroup failed to parse.
+    parse_errors: Option<ParseErrorCounter>,
+    /// Tracer of the sampled messages, the root of their trace.
+    tracer: Option<Tracer>,
+    /// Recorder of the parse and publish times, in the feedgroup's latency group.
+    #[cfg(feature = "latency-histograms")]
+    latency: Option<LatencyRecorder>,
+    /// The ring of the feedgroup, published to by the parser to time the publish.
+    publisher: Option<FragmentSink>,
+    /// The ring of the feedgroup the leading events of the payloads carrying
+    /// several are published to, the messages admitted by the parser.
+    group_ring: Option<FragmentSink>,
+}
+
+impl Default for DummyParser {
+    fn default() -> Self {
+        Self::new(MediumTag::Json)
+    }
+}
+
+impl DummyParser {
+    /// Creates a new DummyParser tagging messages with the given medium.
+    pub fn new(medium: MediumTag) -> Self {
+        Self {
+            medium,
+            symbol_ids: HashMap::new(),
+            scales: HashMap::new(),
+            slot_size: RAW_MESSAGE_SIZE,
+            fragments: None,
+            metrics: None,
+            overflow: None,
+            symbol_rings: HashMap::new(),
+            last_top: None,
+            pause: None,
+            streams: None,
+            parse_errors: None,
+            tracer: None,
+            #[cfg(feature = "latency-histograms")]
+            latency: None,
+            publisher: None,
+            group_ring: None,
+        }
+    }
+
+    /// Tags the parsed messages with the symbol IDs of their symbols.
+    pub fn with_symbol_ids(mut self, symbols: &[(String, u32)]) -> Self {
+        self.symbol_ids = symbols.iter().map(|(symbol, id)| (symbol.to_uppercase(), *id)).collect();
+        self
+    }
+
+    /// Converts the prices and quantities of the trades and book tickers into
+    /// fixed-point at the scale of their symbol, by symbol ID.
+    pub fn with_scales(mut self, scales: &[(u32, SymbolScale)]) -> Self {
+        self.scales = scales.iter().copied().collect();
+        self
+    }
+
+    /// Writes the payloads into `slot_size` bytes, the slot size registered for
+    /// the ring, instead of the whole `RAW_MESSAGE_SIZE` buffer.
+    pub fn with_slot_size(mut self, slot_size: usize) -> Self {
+        self.slot_size = slot_size.min(RAW_MESSAGE_SIZE);
+        self
+    }
+
+    /// Publishes the payloads larger than a slot as fragments, the leading ones
+    /// to `sink`, the ring of the feedgroup, instead of rejecting them.
+    pub fn with_fragments(mut self, sink: FragmentSink) -> Self {
+        self.fragments = Some(sink);
+        self
+    }
+
+    /// Publishes the leading events of the payloads carrying several, e.g. the
+    /// trades of an SBE trades event, to `ring`, the ring of the feedgroup,
+    /// the last one handed to the worker. The ring is not guarded, the events
+    /// being admitted under its overflow policy before they're parsed.
+    pub fn with_group_ring(mut self, ring: FragmentSink) -> Self {
+        self.group_ring = Some(ring);
+        self
+    }
+
+    /// Records published messages in the ring metrics, and sequences them.
+    pub fn with_metrics(mut self, metrics: RingMetricsHandle) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Admits every message under the overflow policy of the ring before it's
+    /// handed to the worker, dropping the messages refused on a full ring.
+    pub fn with_overflow(mut self, overflow: OverflowGuard) -> Self {
+        self.overflow = Some(overflow);
+        self
+    }
+
+    /// Publishes the messages of the symbol `symbol_id` to `ring`, the ring of
+    /// the symbol, instead of handing them to the worker publishing to the
+    /// ring of the feedgroup. Their leading fragments are published to it too,
+    /// the ring admitting every slot under its overflow policy (see
+    /// `GuardedPublisher`), and they are sequenced in its `metrics`.
+    pub fn with_symbol_ring(mut self, symbol_id: u32, ring: FragmentSink, metrics: RingMetricsHandle) -> Self {
+        self.symbol_rings.insert(symbol_id, SymbolRing { ring, metrics });
+        self
+    }
+
+    /// Overwrites the last-value cache on every Top update.
+    pub fn with_last_top(mut self, last_top: LastTopHandle) -> Self {
+        self.last_top = Some(last_top);
+        self
+    }
+
+    /// Drops the messages of the feedgroup, or of its symbols, while paused.
+    pub fn with_pause(mut self, pause: PauseHandle) -> Self {
+        self.pause = Some(pause);
+        self
+    }
+
+    /// Counts the messages of each stream, paused or not.
+    pub fn with_stream_stats(mut self, streams: StreamStatsHandle) -> Self {
+        self.streams = Some(streams);
+        self
+    }
+
+    /// Counts the messages failed to parse, or to publish as fragments.
+    pub fn with_parse_errors(mut self, parse_errors: ParseErrorCounter) -> Self {
+        self.parse_errors = Some(parse_errors);
+        self
+    }
+
+    /// Traces the messages sampled by `tracer` from their receive, recording
+    /// their parse up to the publish as the `md.parse` span stamped in their header.
+    pub fn with_tracer(mut self, tracer: Tracer) -> Self {
+        self.tracer = Some(tracer);
+        self
+    }
+
+    /// Counts the error of a message, unless dropped while paused.
+    ///
+    /// LATENCY: FAST_PATH
+    pub(crate) fn record_error(&self, error: &DummyParserError) {
+        if let Some(parse_errors) = &self.parse_errors {
+            parse_errors.record(error);
+        }
+    }
+
+    /// Counts a received message in the statistics of its stream.
+    ///
+    /// LATENCY: FAST_PATH
+    fn record_stream(&self, raw_data: atx_feed::FeedData, now_ms: u64) {
+        if let Some(streams) = &self.streams {
+            streams.record(raw_data, now_ms);
+        }
+    }
+
+    /// Rejects the message if publishing is paused, so the worker drops it.
+    ///
+    /// LATENCY: FAST_PATH
+    fn check_paused(&self, raw_data: atx_feed::FeedData) -> Result<(), DummyParserError> {
+        match &self.pause {
+            Some(pause) if pause.drops(raw_data) => Err(DummyParserError::Paused),
+            _ => Ok(()),
+        }
+    }
+
+    /// Rejects the message if the overflow policy of the ring refuses it, so
+    /// the worker drops it, the outcome counted in the ring metrics.
+    ///
+    /// LATENCY: FAST_PATH (except while blocking)
+    fn check_overflow(&self) -> Result<(), DummyParserError> {
+        match &self.overflow {
+            Some(overflow) if !overflow.admit().should_publish() => Err(DummyParserError::Overflow),
+            _ => Ok(()),
+        }
+    }
+
+    /// Returns the ID of the symbol of a payload if it's published to the ring
+    /// of its symbol by the parser.
+    ///
+    /// LATENCY: FAST_PATH
+    fn routed_symbol(&self, raw_data: &[u8]) -> Option<u32> {
+        if self.symbol_rings.is_empty() {
+            return None;
+        }
+        payload_symbol(raw_data)
+            .and_then(|symbol| self.symbol_ids.get(symbol))
+            .copied()
+            .filter(|id| self.symbol_rings.contains_key(id))
+    }
+
+    /// Publishes a message to the ring of its symbol.
+    ///
+    /// LATENCY: FAST_PATH
+    fn publish_routed(&self, symbol_ring: &SymbolRing, message: &RawMessage) -> Result<ParseOutcome, DummyParserError> {
+        self.publish_to(&symbol_ring.ring, message).map(|()| ParseOutcome::Routed)
+    }
+
+    /// Publishes a message to the ring of the feedgroup if the parser has its
+    /// publisher, leaving it to the worker otherwise.
+    ///
+    /// LATENCY: FAST_PATH
+    fn publish_group(&self, message: &RawMessage) -> Result<ParseOutcome, DummyParserError> {
+        match &self.publisher {
+            Some(publisher) => self.publish_to(publisher, message).map(|()| ParseOutcome::Published),
+            None => Ok(ParseOutcome::Parsed),
+        }
+    }
+
+    /// Publishes a message to a ring, its refusal by a full ring counted as an
+    /// overflow.
+    ///
+    /// LATENCY: FAST_PATH
+    fn publish_to(&self, ring: &FragmentSink, message: &RawMessage) -> Result<(), DummyParserError> {
+        ring.publish(message)
+            .map_err(|e| match e {
+                RingError::Full(_) => DummyParserError::Overflow,
+                e => DummyParserError::Publish(e.to_string()),
+            })
+            .inspect_err(|e| self.record_error(e))
+    }
+
+    /// Records the parse times, and timestamps the messages with their latency
+    /// group so consumers can record their wake latency.
+    #[cfg(feature = "latency-histograms")]
+    pub fn with_latency(mut self, latency: LatencyRecorder) -> Self {
+        self.latency = Some(latency);
+        self
+    }
+
+    /// Publishes the parsed messages to `ring`, the ring of the feedgroup,
+    /// instead of handing them to the worker, so their publish times are
+    /// recorded with the latencies. The messages are admitted under the
+    /// overflow policy of the ring before they're parsed.
+    #[cfg(feature = "latency-histograms")]
+    pub fn with_publisher(mut self, ring: FragmentSink) -> Self {
+        self.publisher = Some(ring);
+        self
+    }
+
+    /// Returns the current time if the latencies are recorded.
+    ///
+    /// LATENCY: FAST_PATH
+    #[cfg(feature = "latency-histograms")]
+    fn latency_start(&self) -> Option<u64> {
+        self.latency.as_ref().map(|_| ctl_time::monotonic_ns())
+    }
+
+    /// Records the parse time of a message started at `start_ns`, and
+    /// timestamps its header as handed to the ring.
+    ///
+    /// The ring publish is timed apart (see `latency_publish`), the wake
+    /// latency the consumers record including it.
+    ///
+    /// LATENCY: FAST_PATH
+    #[cfg(feature = "latency-histograms")]
+    fn latency_end(&mut self, start_ns: Option<u64>, message: &mut RawMessage) {
+        let (Some(latency), Some(start_ns)) = (self.latency.as_mut(), start_ns) else {
+            return;
+        };
+        let now_ns = ctl_time::monotonic_ns();
+        latency.record_parse(now_ns - start_ns);
+        latency.maybe_flush(now_ns);
+
+        let header = &mut message.header;
+        header.latency_group = latency.group();
+        header.published_ns = now_ns;
+    }
+
+    /// Records the publish time of a message published by the parser, started
+    /// at `start_ns`.
+    ///
+    /// LATENCY: FAST_PATH
+    #[cfg(feature = "latency-histograms")]
+    fn latency_publish(&mut self, start_ns: Option<u64>) {
+        let (Some(latency), Some(start_ns)) = (self.latency.as_mut(), start_ns) else {
+            return;
+        };
+        latency.record_publish(ctl_time::monotonic_ns() - start_ns);
+    }
+
+    /// Records the `md.parse` span of a sampled message started at `start_ns`,
+    /// the root of its trace, and stamps the header of the message with it.
+    ///
+    /// LATENCY: FAST_PATH (sampled messages only)
+    fn record_trace(&self, start_ns: u64, message: &mut RawMessage) {
+        let Some(tracer) = &self.tracer else {
+            return;
+        };
+        let header = &mut message.header;
+        let attributes = [("symbol_id", header.symbol_id as u64), ("seq", header.seq)];
+        let context = tracer.record("md.parse", TraceContext::default(), start_ns, &attributes);
+        header.set_trace(context);
+    }
+
+    /// Copies the raw data into the message buffer, tags the message header
+    /// with its event and stamps it with its sequence number and receive time.
+    ///
+    /// The prices and quantities are left unscaled if a value is finer than
+    /// the scale of the symbol, consumers then parsing the payload.
+    ///
+    /// The message is counted in the ring metrics here, as the worker publishes
+    /// every successfully parsed message; without metrics it isn't sequenced.
+    /// The message of a symbol published to its own ring (`routed`) is counted
+    /// in the metrics of that ring.
+    ///
+    /// A payload larger than the slot is fragmented with a fragment sink, the
+    /// message buffer left with its last fragment.
+    ///
+    /// LATENCY: FAST_PATH
+    fn write_raw(
+            &self,
+            raw_data: atx_feed::FeedData,
+            message: &mut RawMessage,
+            event_type: EventType,
+            now_ms: u64,
+            routed: Option<u32>,
+        ) -> Result<(), DummyParserError> {
+
+        let (fragments, metrics) = match routed.and_then(|id| self.symbol_rings.get(&id)) {
+            Some(symbol_ring) => (Some(&symbol_ring.ring), Some(&symbol_ring.metrics)),
+            None => (self.fragments.as_ref(), self.metrics.as_ref()),
+        };
+        let sink = fragments.filter(|_| raw_data.len() > self.slot_size);
+        match sink {
+            Some(_) => self.tag(raw_data, message)?,
+            None => self.fill_raw(raw_data, message)?,
+        }
+        message.header.event_type = event_type as u8;
+        message.fixed = self
+            .scales
+            .get(&message.header.symbol_id)
+            .and_then(|&scale| FixedPoint::from_payload(raw_data, event_type, scale))
+            .unwrap_or_default();
+        if let Some(sink) = sink {
+            self.write_fragments(sink, metrics, raw_data, message, now_ms)?;
+        }
+        message.header.stamp(record_publish(metrics), now_ms);
+        Ok(())
+    }
+
+    /// Publishes the leading fragments of a payload larger than the slot to
+    /// the sink, and copies its last fragment into the message.
+    ///
+    /// The leading fragments carry the header of the message, sequenced and
+    /// sealed one by one, without a publish time for the wake latency. A
+    /// fragment refused by the overflow policy of the ring drops the message.
+    ///
+    /// LATENCY: FAST_PATH
+    fn write_fragments(
+            &self,
+            sink: &FragmentSink,
+            metrics: Option<&RingMetricsHandle>,
+            raw_data: &[u8],
+            message: &mut RawMessage,
+            now_ms: u64,
+        ) -> Result<(), DummyParserError> {
+
+        let count = fragment_count(raw_data.len(), self.slot_size);
+        if count > MAX_FRAGMENTS {
+            return Err(DummyParserError::Oversized { len: raw_data.len(), max: MAX_FRAGMENTS * self.slot_size });
+        }
+        message.header.published_ns = 0;
+        let mut chunks = raw_data.chunks(self.slot_size);
+        for (index, chunk) in chunks.by_ref().take(count - 1).enumerate() {
+            self.fill_slot(chunk, message);
+            message.header.set_fragment(index as u16, true);
+            message.header.stamp(record_publish(metrics), now_ms);
+            #[cfg(feature = "message-checksums")]
+            message.seal();
+            sink.publish(message).map_err(|e| match e {
+                RingError::Full(_) => DummyParserError::Overflow,
+                e => DummyParserError::Fragment(e.to_string()),
+            })?;
+        }
+        self.fill_slot(chunks.next().unwrap_or_default(), message);
+        message.header.set_fragment((count - 1) as u16, false);
+        Ok(())
+    }
+
+    /// Parses the payload of an event into the message buffer: counts it in
+    /// its stream, drops it while paused or refused by the overflow policy of
+    /// the ring, and overwrites the last-value cache with the book tickers. A
+    /// message sampled for tracing is stamped with its `md.parse` span, the
+    /// others, and the leading fragments, untraced.
+    ///
+    /// The message of a symbol with its own ring is published to it here, the
+    /// outcome `Routed` telling the worker to skip it. With the publisher of
+    /// the feedgroup, the other messages are published here too, `Published`,
+    /// and their publish times recorded with the latencies.
+    ///
+    /// The payload is the Binance JSON payload of the event, as received on the
+    /// websocket streams or translated by the `FixParser`.
+    ///
+    /// LATENCY: FAST_PATH
+    pub(crate) fn parse_event(
+            &mut self,
+            raw_data: atx_feed::FeedData,
+            message: &mut RawMessage,
+            event_type: EventType,
+        ) -> Result<ParseOutcome, DummyParserError> {
+
+        let now_ms = ctl_time::now_ms();
+        let trace_start_ns = self.tracer.as_mut().and_then(Tracer::sample);
+        self.record_stream(raw_data, now_ms);
+        self.check_paused(raw_data)?;
+        // The messages routed to the ring of their symbol are admitted by that ring on publish
+        let routed = self.routed_symbol(raw_data);
+        if routed.is_none() {
+            self.check_overflow()?;
+        }
+        #[cfg(feature = "latency-histograms")]
+        let start_ns = self.latency_start();
+        message.header.set_trace(TraceContext::default());
+        self.write_raw(raw_data, message, event_type, now_ms, routed).inspect_err(|e| self.record_error(e))?;
+        if let Some(last_top) = self.last_top.as_ref().filter(|_| event_type == EventType::BookTicker) {
+            last_top.update(raw_data);
+        }
+        if let Some(trace_start_ns) = trace_start_ns {
+            self.record_trace(trace_start_ns, message);
+        }
+        #[cfg(feature = "latency-histograms")]
+        self.latency_end(start_ns, message);
+        #[cfg(feature = "message-checksums")]
+        message.seal();
+        #[cfg(feature = "latency-histograms")]
+        let publish_start_ns = self.latency_start();
+        let outcome = match routed.and_then(|id| self.symbol_rings.get(&id)) {
+            Some(symbol_ring) => self.publish_routed(symbol_ring, message),
+            None => self.publish_group(message),
+        };
+        #[cfg(feature = "latency-histograms")]
+        if matches!(outcome, Ok(ParseOutcome::Routed | ParseOutcome::Published)) {
+            self.latency_publish(publish_start_ns);
+        }
+        outcome
+    }
+
+    /// Parses the payload of a leading event of a payload carrying several,
+    /// publishing it rather than handing it to the worker, which is left with
+    /// the last event.
+    ///
+    /// The event is parsed like any other (see `parse_event`). Unless published
+    /// by the parser to the ring of its symbol or, with the publisher of the
+    /// feedgroup, to the ring of the feedgroup, it's published to the group
+    /// ring, or dropped without one.
+    ///
+    /// LATENCY: FAST_PATH
+    pub(crate) fn publish_leading(
+            &mut self,
+            raw_data: atx_feed::FeedData,
+            message: &mut RawMessage,
+            event_type: EventType,
+        ) -> Result<(), DummyParserError> {
+
+        match (self.parse_event(raw_data, message, event_type)?, &self.group_ring) {
+            (ParseOutcome::Parsed, Some(ring)) => self.publish_to(ring, message),
+            _ => Ok(()),
+        }
+    }
+
+    /// Copies the raw data into a message and tags its header, the message
+    /// being published by the caller, e.g. to a `RingPublisher` in tests.
+    ///
+    /// Only the slot of the message is written, the bytes past it left as
+    /// they are, zero in the buffers of the workers.
+    ///
+    /// LATENCY: FAST_PATH
+    pub fn fill_raw(&self, raw_data: &[u8], message: &mut RawMessage) -> Result<(), DummyParserError> {
+        if raw_data.len() > self.slot_size {
+            return Err(DummyParserError::Oversized { len: raw_data.len(), max: self.slot_size });
+        }
+        self.tag(raw_data, message)?;
+        self.fill_slot(raw_data, message);
+        message.header.set_fragment(0, false);
+        Ok(())
+    }
+
+    /// Tags the header of a message with the medium and the symbol of its payload.
+    ///
+    /// LATENCY: FAST_PATH
+    fn tag(&self, raw_data: &[u8], message: &mut RawMessage) -> Result<(), DummyParserError> {
+        std::str::from_utf8(raw_data).map_err(|_| DummyParserError::General)?;
+        message.header.medium = self.medium as u8;
+        message.header.symbol_id = payload_symbol(raw_data)
+            .and_then(|symbol| self.symbol_ids.get(symbol))
+            .copied()
+            .unwrap_or(UNKNOWN_SYMBOL_ID);
+        Ok(())
+    }
+
+    /// Copies a payload, or a fragment of it, into the slot of a message, zero padded.
+    ///
+    /// LATENCY: FAST_PATH
+    fn fill_slot(&self, bytes: &[u8], message: &mut RawMessage) {
+        let buf = &mut message.data[..self.slot_size];
+        buf[..bytes.len()].copy_from_slice(bytes);
+        buf[bytes.len()..].fill(0);
+    }
+}
+
+/// Counts a message published in the metrics of its ring, returning its sequence number.
+///
+/// LATENCY: FAST_PATH
+fn record_publish(metrics: Option<&RingMetricsHandle>) -> u64 {
+    metrics.map_or(0, |metrics| metrics.get().record_publish())
+}
 
 impl FeedParseProtocol<WSConn<Top>, Top> for DummyParser {
 
     type FeedParsedMessage = RawMessage;
-    type FeedParseError = DummyParserError;
+    type FeedParseError = ParseSkip;
 
     fn parse(
             &mut self, 
@@ -19,23 +566,20 @@ impl FeedParseProtocol<WSConn<Top>, Top> for DummyParser {
             parsed_data: &mut Aligned<Self::FeedParsedMessage>
         ) -> Result<(), Self::FeedParseError> {
 
-        std::str::from_utf8(raw_data)
-            .map(|s| {
-                let bytes = s.as_bytes();
-                let buf = &mut parsed_data.get_mut().data;
-                buf[..bytes.len()].copy_from_slice(bytes);
-                buf[bytes.len()..].fill(0);
-            })
-            .map_err(|_| DummyParserError::General)?;
-        // println!("parsed_data: {}", String::from_utf8_lossy(&parsed_data.get().data)); // TODO: REMOVE
-        Ok(())
+        ParseInto::<WSConn<Top>>::parse_into(self, raw_data, parsed_data.get_mut())
+    }
+}
+
+impl ParseInto<WSConn<Top>> for DummyParser {
+    fn parse_into(&mut self, raw_data: atx_feed::FeedData, message: &mut RawMessage) -> Result<(), ParseSkip> {
+        self.parse_event(raw_data, message, EventType::BookTicker)?.to_worker()
     }
 }
 
 impl FeedParseProtocol<WSConn<Trade>, Trade> for DummyParser {
 
     type FeedParsedMessage = RawMessage;
-    type FeedParseError = DummyParserError;
+    type FeedParseError = ParseSkip;
 
     fn parse(
             &mut self, 
@@ -43,23 +587,20 @@ impl FeedParseProtocol<WSConn<Trade>, Trade> for DummyParser {
             parsed_data: &mut Aligned<Self::FeedParsedMessage>
         ) -> Result<(), Self::FeedParseError> {
 
-        std::str::from_utf8(raw_data)
-            .map(|s| {
-                let bytes = s.as_bytes();
-                let buf = &mut parsed_data.get_mut().data;
-                buf[..bytes.len()].copy_from_slice(bytes);
-                buf[bytes.len()..].fill(0);
-            })
-            .map_err(|_| DummyParserError::General)?;
-        // println!("parsed_data: {}", String::from_utf8_lossy(&parsed_data.get().data)); // TODO: REMOVE
-        Ok(())
+        ParseInto::<WSConn<Trade>>::parse_into(self, raw_data, parsed_data.get_mut())
+    }
+}
+
+impl ParseInto<WSConn<Trade>> for DummyParser {
+    fn parse_into(&mut self, raw_data: atx_feed::FeedData, message: &mut RawMessage) -> Result<(), ParseSkip> {
+        self.parse_event(raw_data, message, EventType::Trade)?.to_worker()
     }
 }
 
 impl FeedParseProtocol<WSConn<AggTrade>, AggTrade> for DummyParser {
 
     type FeedParsedMessage = RawMessage;
-    type FeedParseError = DummyParserError;
+    type FeedParseError = ParseSkip;
 
     fn parse(
             &mut self, 
@@ -67,15 +608,115 @@ impl FeedParseProtocol<WSConn<AggTrade>, AggTrade> for DummyParser {
             parsed_data: &mut Aligned<Self::FeedParsedMessage>
         ) -> Result<(), Self::FeedParseError> {
 
-        std::str::from_utf8(raw_data)
-            .map(|s| {
-                let bytes = s.as_bytes();
-                let buf = &mut parsed_data.get_mut().data;
-                buf[..bytes.len()].copy_from_slice(bytes);
-                buf[bytes.len()..].fill(0);
-            })
-            .map_err(|_| DummyParserError::General)?;
-        // println!("parsed_data: {}", String::from_utf8_lossy(&parsed_data.get().data)); // TODO: REMOVE
-        Ok(())
+        ParseInto::<WSConn<AggTrade>>::parse_into(self, raw_data, parsed_data.get_mut())
+    }
+}
+
+impl ParseInto<WSConn<AggTrade>> for DummyParser {
+    fn parse_into(&mut self, raw_data: atx_feed::FeedData, message: &mut RawMessage) -> Result<(), ParseSkip> {
+        self.parse_event(raw_data, message, EventType::AggTrade)?.to_worker()
+    }
+}
+
+impl FeedParseProtocol<WSConn<Depth>, Depth> for DummyParser {
+
+    type FeedParsedMessage = RawMessage;
+    type FeedParseError = ParseSkip;
+
+    fn parse(
+            &mut self, 
+            raw_data: atx_feed::FeedData,
+            parsed_data: &mut Aligned<Self::FeedParsedMessage>
+        ) -> Result<(), Self::FeedParseError> {
+
+        ParseInto::<WSConn<Depth>>::parse_into(self, raw_data, parsed_data.get_mut())
     }
-}
\ No newline at end of file
+}
+
+impl ParseInto<WSConn<Depth>> for DummyParser {
+    fn parse_into(&mut self, raw_data: atx_feed::FeedData, message: &mut RawMessage) -> Result<(), ParseSkip> {
+        self.parse_event(raw_data, message, EventType::Depth)?.to_worker()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::Arc;
+    use std::sync::atomic::Ordering;
+
+    use ctl_shm::{ShmRegion, ShmRing};
+
+    use crate::{MetricsRegion, RingConsume, RingConsumer, RingLike};
+
+    #[test]
+    fn test_symbol_rings() {
+        let pid = std::process::id();
+        let region = Arc::new(ShmRegion::<MetricsRegion>::create(&format!("ctl_feed_parser_test_{}", pid)).unwrap());
+        region.register_raw_ring("TRADE_0_PS", 16, 64).unwrap();
+        region.register_raw_ring("TRADE_1_PS", 16, 64).unwrap();
+        let metrics = |ring| RingMetricsHandle::lookup(region.clone(), ring).unwrap();
+        let eth_name = format!("ctl_feed_parser_test_eth_{}", pid);
+        let eth_ring = ShmRing::<RawMessage>::create(&eth_name, 16).unwrap();
+        let mut eth = RingLike::consumer(&eth_ring);
+        let eth_sink = FragmentSink::new(ShmRing::<RawMessage>::open(&eth_name).unwrap());
+        // A set of two symbols with a ring each, the worker publishing to the first one
+        let mut parser = DummyParser::new(MediumTag::Json)
+            .with_symbol_ids(&[("BTCUSDT".to_string(), 0), ("ETHUSDT".to_string(), 1)])
+            .with_slot_size(64)
+            .with_metrics(metrics("TRADE_0_PS"))
+            .with_symbol_ring(1, eth_sink, metrics("TRADE_1_PS"));
+
+        let payload: &[u8] = br#"{"e":"trade","s":"BTCUSDT","t":1}"#;
+        let mut message = RawMessage::default();
+        assert_eq!(parser.parse_event(payload, &mut message, EventType::Trade).unwrap(), ParseOutcome::Parsed);
+        assert_eq!((message.header.symbol_id(), message.header.seq), (Some(0), 1));
+        assert!(matches!(RingConsumer::consume(&mut eth), RingConsume::Empty));
+
+        // The messages of the other symbol are published to its ring by the parser, dropped by the worker
+        let payload: &[u8] = br#"{"e":"trade","s":"ETHUSDT","t":2}"#;
+        let mut message = RawMessage::default();
+        let routed = parser.parse_event(payload, &mut message, EventType::Trade);
+        assert_eq!(routed.unwrap(), ParseOutcome::Routed);
+        let RingConsume::Message(routed) = RingConsumer::consume(&mut eth) else {
+            panic!("expected the ETHUSDT trade on its ring");
+        };
+        assert_eq!((routed.header.symbol_id(), routed.header.seq), (Some(1), 1));
+        assert_eq!(&routed.data[..payload.len()], payload);
+        assert_eq!(metrics("TRADE_0_PS").get().head.load(Ordering::Relaxed), 1);
+        assert_eq!(metrics("TRADE_1_PS").get().head.load(Ordering::Relaxed), 1);
+    }
+
+    #[cfg(feature = "latency-histograms")]
+    #[test]
+    fn test_timed_publish() {
+        let pid = std::process::id();
+        let region = Arc::new(ShmRegion::<MetricsRegion>::create(&format!("ctl_feed_publish_test_{}", pid)).unwrap());
+        region.register_raw_ring("TRADE_0_PS", 16, 64).unwrap();
+        let ring_name = format!("ctl_feed_publish_test_btc_{}", pid);
+        let ring = ShmRing::<RawMessage>::create(&ring_name, 16).unwrap();
+        let mut consumer = RingLike::consumer(&ring);
+        let mut parser = DummyParser::new(MediumTag::Json)
+            .with_symbol_ids(&[("BTCUSDT".to_string(), 0)])
+            .with_slot_size(64)
+            .with_metrics(RingMetricsHandle::lookup(region.clone(), "TRADE_0_PS").unwrap())
+            .with_latency(LatencyRecorder::new(region.clone(), 0).unwrap())
+            .with_publisher(FragmentSink::new(ShmRing::<RawMessage>::open(&ring_name).unwrap()));
+
+        // The message is published by the parser, the worker skipping it
+        let payload: &[u8] = br#"{"e":"trade","s":"BTCUSDT","t":1}"#;
+        let mut message = RawMessage::default();
+        let published = parser.parse_event(payload, &mut message, EventType::Trade);
+        assert_eq!(published.unwrap(), ParseOutcome::Published);
+        let RingConsume::Message(published) = RingConsumer::consume(&mut consumer) else {
+            panic!("expected the BTCUSDT trade on the ring of the feedgroup");
+        };
+        assert_eq!(&published.data[..payload.len()], payload);
+
+        // The recorder merges its publish time into the region once dropped
+        drop(parser);
+        assert_eq!(region.latency[0].publish.snapshot().count(), 1);
+        assert_eq!(region.latency[0].parse.snapshot().count(), 1);
+    }
+}
@@ -0,0 +1,463 @@
+//! Translation of the SBE market data streams into the Binance JSON payloads.
+//!
+//! The SBE streams are subscribed over websocket like the JSON ones, their
+//! events received as binary frames encoded with the stream schema. Like the
+//! FIX events, each event is translated into the payload of the equivalent
+//! JSON stream (e.g. `bookTicker` for a `bestBidAsk` event), then parsed by the
+//! wrapped `DummyParser`, tagged with the SBE medium.
+//!
+//! A trades event carries the trades of a match, each translated into its own
+//! `trade` payload: the leading ones are published by the parser, the last one
+//! handed to the worker.
+
+use std::io::Write;
+
+use atx_feed::FeedParseProtocol;
+use ctl_websocket::WSConn;
+use dpdk::Aligned;
+
+use crate::{DummyParser, EventType, ParseInto, RawMessage, Top, Trade};
+use super::{DummyParserError, ParseOutcome, ParseSkip};
+
+/// The stream suffix of the best bid and ask events, subscribed by the Top
+/// feeds instead of `bookTicker`.
+pub const SBE_BEST_BID_ASK_SUFFIX: &str = "bestBidAsk";
+
+/// The ID of the schema of the SBE streams.
+const SCHEMA_ID: u16 = 1;
+
+/// The templates of the trades and best bid and ask events.
+const TRADES_TEMPLATE: u16 = 10000;
+const BEST_BID_ASK_TEMPLATE: u16 = 10001;
+
+/// The message header: block length, template ID, schema ID and version, `u16` each.
+const HEADER_SIZE: usize = 8;
+
+/// The header of a repeating group: block length `u16` and entry count `u32`.
+const GROUP_SIZE_SIZE: usize = 6;
+
+/// The root block of a best bid and ask event: event time, book update ID,
+/// price and quantity exponents, then the bid and ask prices and quantities.
+const BEST_BID_ASK_BLOCK: usize = 50;
+
+/// The root block of a trades event: event and transact times, price and quantity exponents.
+const TRADES_BLOCK: usize = 18;
+
+/// A trade of a trades event: ID, price, quantity and buyer maker flag.
+const TRADE_ENTRY: usize = 25;
+
+/// Reads `N` bytes of a message at `offset`.
+fn read<const N: usize>(event: &[u8], offset: usize) -> Result<[u8; N], DummyParserError> {
+    event
+        .get(offset..offset + N)
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or(DummyParserError::MalformedSbe("truncated"))
+}
+
+fn read_u16(event: &[u8], offset: usize) -> Result<u16, DummyParserError> {
+    read(event, offset).map(u16::from_le_bytes)
+}
+
+fn read_u32(event: &[u8], offset: usize) -> Result<u32, DummyParserError> {
+    read(event, offset).map(u32::from_le_bytes)
+}
+
+fn read_i64(event: &[u8], offset: usize) -> Result<i64, DummyParserError> {
+    read(event, offset).map(i64::from_le_bytes)
+}
+
+fn read_i8(event: &[u8], offset: usize) -> Result<i8, DummyParserError> {
+    read(event, offset).map(i8::from_le_bytes)
+}
+
+/// Returns the root block length of an event of `template`, checking its header.
+fn block_length(event: &[u8], template: u16, min: usize) -> Result<usize, DummyParserError> {
+    if read_u16(event, 4)? != SCHEMA_ID {
+        return Err(DummyParserError::MalformedSbe("schema"));
+    }
+    if read_u16(event, 2)? != template {
+        return Err(DummyParserError::MalformedSbe("template"));
+    }
+    let block_length = read_u16(event, 0)? as usize;
+    if block_length < min {
+        return Err(DummyParserError::MalformedSbe("block length"));
+    }
+    Ok(block_length)
+}
+
+/// Returns the symbol of an event, the `varString8` at `offset`, safe to embed in a JSON string.
+fn read_symbol(event: &[u8], offset: usize) -> Result<&str, DummyParserError> {
+    let [len] = read::<1>(event, offset)?;
+    event
+        .get(offset + 1..offset + 1 + len as usize)
+        .filter(|symbol| !symbol.is_empty() && symbol.iter().all(u8::is_ascii_alphanumeric))
+        .and_then(|symbol| std::str::from_utf8(symbol).ok())
+        .ok_or(DummyParserError::MalformedSbe("symbol"))
+}
+
+/// Writes the decimal `mantissa * 10^exponent`, with the digits of the exponent.
+///
+/// LATENCY: FAST_PATH
+fn write_decimal(out: &mut Vec<u8>, mantissa: i64, exponent: i8) {
+    if mantissa < 0 {
+        out.push(b'-');
+    }
+    let mut buf = [0u8; 20];
+    let mut start = buf.len();
+    let mut value = mantissa.unsigned_abs();
+    loop {
+        start -= 1;
+        buf[start] = b'0' + (value % 10) as u8;
+        value /= 10;
+        if value == 0 {
+            break;
+        }
+    }
+    let digits = &buf[start..];
+    let scale = exponent.unsigned_abs() as usize;
+    if exponent >= 0 {
+        out.extend_from_slice(digits);
+        out.resize(out.len() + scale, b'0');
+    } else if digits.len() > scale {
+        let (int, frac) = digits.split_at(digits.len() - scale);
+        out.extend_from_slice(int);
+        out.push(b'.');
+        out.extend_from_slice(frac);
+    } else {
+        out.extend_from_slice(b"0.");
+        out.resize(out.len() + scale - digits.len(), b'0');
+        out.extend_from_slice(digits);
+    }
+}
+
+/// A trades event, its trades checked to lie within the message.
+struct TradesEvent<'a> {
+    /// The event time, in microseconds.
+    event_time_us: i64,
+    /// The time of the match, in microseconds.
+    transact_time_us: i64,
+    price_exponent: i8,
+    qty_exponent: i8,
+    /// The entries of the trades.
+    entries: &'a [u8],
+    /// The block length of an entry.
+    entry_length: usize,
+    /// The number of trades, at least one.
+    count: usize,
+    symbol: &'a str,
+}
+
+impl<'a> TradesEvent<'a> {
+    /// Decodes the root block, the trades and the symbol of a trades event.
+    ///
+    /// LATENCY: FAST_PATH
+    fn decode(event: &'a [u8]) -> Result<Self, DummyParserError> {
+        let block_length = block_length(event, TRADES_TEMPLATE, TRADES_BLOCK)?;
+        let group = HEADER_SIZE + block_length;
+        let entry_length = read_u16(event, group)? as usize;
+        let count = read_u32(event, group + 2)? as usize;
+        if entry_length < TRADE_ENTRY {
+            return Err(DummyParserError::MalformedSbe("trade block length"));
+        }
+        if count == 0 {
+            return Err(DummyParserError::MalformedSbe("no trade"));
+        }
+        let start = group + GROUP_SIZE_SIZE;
+        let end = count
+            .checked_mul(entry_length)
+            .and_then(|len| start.checked_add(len))
+            .filter(|&end| end <= event.len())
+            .ok_or(DummyParserError::MalformedSbe("truncated"))?;
+        Ok(Self {
+            event_time_us: read_i64(event, HEADER_SIZE)?,
+            transact_time_us: read_i64(event, HEADER_SIZE + 8)?,
+            price_exponent: read_i8(event, HEADER_SIZE + 16)?,
+            qty_exponent: read_i8(event, HEADER_SIZE + 17)?,
+            entries: &event[start..end],
+            entry_length,
+            count,
+            symbol: read_symbol(event, end)?,
+        })
+    }
+
+    /// Writes the `trade` payload of the trade at `index`.
+    ///
+    /// LATENCY: FAST_PATH
+    fn write_trade(&self, index: usize, out: &mut Vec<u8>) -> Result<(), DummyParserError> {
+        let entry = &self.entries[index * self.entry_length..];
+        out.clear();
+        let _ = write!(
+            out,
+            r#"{{"e":"trade","E":{},"s":"{}","t":{},"p":""#,
+            self.event_time_us / 1000,
+            self.symbol,
+            read_i64(entry, 0)?
+        );
+        write_decimal(out, read_i64(entry, 8)?, self.price_exponent);
+        out.extend_from_slice(br#"","q":""#);
+        write_decimal(out, read_i64(entry, 16)?, self.qty_exponent);
+        let [is_buyer_maker] = read::<1>(entry, 24)?;
+        let _ = write!(out, r#"","T":{},"m":{}}}"#, self.transact_time_us / 1000, is_buyer_maker != 0);
+        Ok(())
+    }
+}
+
+/// Parses the events of the SBE market data streams.
+#[derive(Debug, Clone)]
+pub struct SbeParser {
+    /// The parser of the translated payloads.
+    inner: DummyParser,
+    /// The payload translated from the last event.
+    payload: Vec<u8>,
+}
+
+impl From<DummyParser> for SbeParser {
+    fn from(inner: DummyParser) -> Self {
+        Self::new(inner)
+    }
+}
+
+impl SbeParser {
+    /// Creates a parser of the SBE events, parsing the translated payloads with `inner`.
+    pub fn new(inner: DummyParser) -> Self {
+        Self { inner, payload: Vec::with_capacity(256) }
+    }
+
+    /// Translates a best bid and ask event into its `bookTicker` payload.
+    ///
+    /// LATENCY: FAST_PATH
+    fn translate_top(&mut self, event: &[u8]) -> Result<(), DummyParserError> {
+        let block_length = block_length(event, BEST_BID_ASK_TEMPLATE, BEST_BID_ASK_BLOCK)?;
+        let symbol = read_symbol(event, HEADER_SIZE + block_length)?;
+        let price_exponent = read_i8(event, HEADER_SIZE + 16)?;
+        let qty_exponent = read_i8(event, HEADER_SIZE + 17)?;
+
+        let out = &mut self.payload;
+        out.clear();
+        let _ = write!(out, r#"{{"u":{},"s":"{}","b":""#, read_i64(event, HEADER_SIZE + 8)?, symbol);
+        write_decimal(out, read_i64(event, HEADER_SIZE + 18)?, price_exponent);
+        out.extend_from_slice(br#"","B":""#);
+        write_decimal(out, read_i64(event, HEADER_SIZE + 26)?, qty_exponent);
+        out.extend_from_slice(br#"","a":""#);
+        write_decimal(out, read_i64(event, HEADER_SIZE + 34)?, price_exponent);
+        out.extend_from_slice(br#"","A":""#);
+        write_decimal(out, read_i64(event, HEADER_SIZE + 42)?, qty_exponent);
+        out.extend_from_slice(br#""}"#);
+        Ok(())
+    }
+
+    /// Publishes the leading trades of a trades event, and translates the last
+    /// one into its `trade` payload.
+    ///
+    /// LATENCY: FAST_PATH
+    fn translate_trades(&mut self, event: &[u8], message: &mut RawMessage) -> Result<(), DummyParserError> {
+        let trades = TradesEvent::decode(event).inspect_err(|e| self.inner.record_error(e))?;
+        for index in 0..trades.count - 1 {
+            trades.write_trade(index, &mut self.payload).inspect_err(|e| self.inner.record_error(e))?;
+            let payload = std::mem::take(&mut self.payload);
+            let published = self.inner.publish_leading(&payload, message, EventType::Trade);
+            self.payload = payload;
+            published?;
+        }
+        trades.write_trade(trades.count - 1, &mut self.payload).inspect_err(|e| self.inner.record_error(e))
+    }
+
+    /// Parses the translated payload of an event into the message buffer.
+    ///
+    /// LATENCY: FAST_PATH
+    fn parse_payload(
+            &mut self,
+            message: &mut RawMessage,
+            event_type: EventType,
+        ) -> Result<ParseOutcome, DummyParserError> {
+
+        let payload = std::mem::take(&mut self.payload);
+        let parsed = self.inner.parse_event(&payload, message, event_type);
+        self.payload = payload;
+        parsed
+    }
+}
+
+impl FeedParseProtocol<WSConn<Top>, Top> for SbeParser {
+
+    type FeedParsedMessage = RawMessage;
+    type FeedParseError = ParseSkip;
+
+    fn parse(
+            &mut self,
+            raw_data: atx_feed::FeedData,
+            parsed_data: &mut Aligned<Self::FeedParsedMessage>
+        ) -> Result<(), Self::FeedParseError> {
+
+        ParseInto::<WSConn<Top>>::parse_into(self, raw_data, parsed_data.get_mut())
+    }
+}
+
+impl ParseInto<WSConn<Top>> for SbeParser {
+    fn parse_into(&mut self, raw_data: atx_feed::FeedData, message: &mut RawMessage) -> Result<(), ParseSkip> {
+        self.translate_top(raw_data).inspect_err(|e| self.inner.record_error(e))?;
+        self.parse_payload(message, EventType::BookTicker)?.to_worker()
+    }
+}
+
+impl FeedParseProtocol<WSConn<Trade>, Trade> for SbeParser {
+
+    type FeedParsedMessage = RawMessage;
+    type FeedParseError = ParseSkip;
+
+    fn parse(
+            &mut self,
+            raw_data: atx_feed::FeedData,
+            parsed_data: &mut Aligned<Self::FeedParsedMessage>
+        ) -> Result<(), Self::FeedParseError> {
+
+        ParseInto::<WSConn<Trade>>::parse_into(self, raw_data, parsed_data.get_mut())
+    }
+}
+
+impl ParseInto<WSConn<Trade>> for SbeParser {
+    fn parse_into(&mut self, raw_data: atx_feed::FeedData, message: &mut RawMessage) -> Result<(), ParseSkip> {
+        self.translate_trades(raw_data, message)?;
+        self.parse_payload(message, EventType::Trade)?.to_worker()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use ctl_shm::ShmRing;
+
+    use crate::{payload_symbol, FragmentSink, MediumTag, RingConsume, RingConsumer, RingLike};
+
+    fn header(block_length: usize, template: u16) -> Vec<u8> {
+        let mut event = Vec::new();
+        for field in [block_length as u16, template, SCHEMA_ID, 0] {
+            event.extend_from_slice(&field.to_le_bytes());
+        }
+        event
+    }
+
+    fn push_symbol(event: &mut Vec<u8>, symbol: &str) {
+        event.push(symbol.len() as u8);
+        event.extend_from_slice(symbol.as_bytes());
+    }
+
+    fn best_bid_ask(update_id: i64, prices: [i64; 4], symbol: &str) -> Vec<u8> {
+        let mut event = header(BEST_BID_ASK_BLOCK, BEST_BID_ASK_TEMPLATE);
+        event.extend_from_slice(&1_700_000_000_123_456i64.to_le_bytes());
+        event.extend_from_slice(&update_id.to_le_bytes());
+        event.extend_from_slice(&[-2i8 as u8, -8i8 as u8]);
+        for mantissa in prices {
+            event.extend_from_slice(&mantissa.to_le_bytes());
+        }
+        push_symbol(&mut event, symbol);
+        event
+    }
+
+    fn trades(trades: &[(i64, i64, i64, bool)], symbol: &str) -> Vec<u8> {
+        let mut event = header(TRADES_BLOCK, TRADES_TEMPLATE);
+        event.extend_from_slice(&1_700_000_000_123_456i64.to_le_bytes());
+        event.extend_from_slice(&1_700_000_000_120_000i64.to_le_bytes());
+        event.extend_from_slice(&[-2i8 as u8, -8i8 as u8]);
+        event.extend_from_slice(&(TRADE_ENTRY as u16).to_le_bytes());
+        event.extend_from_slice(&(trades.len() as u32).to_le_bytes());
+        for &(id, price, qty, is_buyer_maker) in trades {
+            event.extend_from_slice(&id.to_le_bytes());
+            event.extend_from_slice(&price.to_le_bytes());
+            event.extend_from_slice(&qty.to_le_bytes());
+            event.push(is_buyer_maker as u8);
+        }
+        push_symbol(&mut event, symbol);
+        event
+    }
+
+    fn payload(parser: &SbeParser) -> &str {
+        std::str::from_utf8(&parser.payload).unwrap()
+    }
+
+    #[test]
+    fn test_write_decimal() {
+        let decimal = |mantissa, exponent| {
+            let mut out = Vec::new();
+            write_decimal(&mut out, mantissa, exponent);
+            String::from_utf8(out).unwrap()
+        };
+        assert_eq!(decimal(6_000_012_000_000, -8), "60000.12000000");
+        assert_eq!(decimal(1_500_000, -8), "0.01500000");
+        assert_eq!(decimal(0, -2), "0.00");
+        assert_eq!(decimal(-125, -2), "-1.25");
+        assert_eq!(decimal(15, 2), "1500");
+        assert_eq!(decimal(i64::MIN, 0), i64::MIN.to_string());
+    }
+
+    #[test]
+    fn test_translate_top() {
+        let mut parser = SbeParser::new(DummyParser::new(MediumTag::Sbe));
+        let event = best_bid_ask(10, [6_000_000, 150_000_000, 6_000_100, 250_000_000], "BTCUSDT");
+        parser.translate_top(&event).unwrap();
+        assert_eq!(
+            payload(&parser),
+            r#"{"u":10,"s":"BTCUSDT","b":"60000.00","B":"1.50000000","a":"60001.00","A":"2.50000000"}"#
+        );
+        assert_eq!(payload_symbol(&parser.payload), Some("BTCUSDT"));
+
+        assert!(matches!(
+            parser.translate_top(&event[..event.len() - 2]),
+            Err(DummyParserError::MalformedSbe("symbol"))
+        ));
+        assert!(matches!(parser.translate_top(&event[..20]), Err(DummyParserError::MalformedSbe("truncated"))));
+        let injected = best_bid_ask(11, [1, 1, 1, 1], "BTC\"USDT");
+        assert!(matches!(parser.translate_top(&injected), Err(DummyParserError::MalformedSbe("symbol"))));
+        let mut other_schema = event.clone();
+        other_schema[4] = 2;
+        assert!(matches!(parser.translate_top(&other_schema), Err(DummyParserError::MalformedSbe("schema"))));
+        let trade = trades(&[(1, 1, 1, false)], "BTCUSDT");
+        assert!(matches!(parser.translate_top(&trade), Err(DummyParserError::MalformedSbe("template"))));
+    }
+
+    #[test]
+    fn test_translate_trades() {
+        let event = trades(&[(41, 6_000_000, 1_000_000, false), (42, 6_000_100, 2_000_000, true)], "BTCUSDT");
+        let trades_event = TradesEvent::decode(&event).unwrap();
+        assert_eq!((trades_event.count, trades_event.symbol), (2, "BTCUSDT"));
+        let mut out = Vec::new();
+        trades_event.write_trade(1, &mut out).unwrap();
+        assert_eq!(
+            std::str::from_utf8(&out).unwrap(),
+            r#"{"e":"trade","E":1700000000123,"s":"BTCUSDT","t":42,"p":"60001.00","q":"0.02000000","T":1700000000120,"m":true}"#
+        );
+
+        let no_trade = trades(&[], "BTCUSDT");
+        assert!(matches!(TradesEvent::decode(&no_trade), Err(DummyParserError::MalformedSbe("no trade"))));
+        let mut overflowing = event.clone();
+        overflowing[HEADER_SIZE + TRADES_BLOCK + 2..][..4].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(matches!(TradesEvent::decode(&overflowing), Err(DummyParserError::MalformedSbe("truncated"))));
+    }
+
+    #[test]
+    fn test_leading_trades() {
+        let ring_name = format!("ctl_feed_sbe_test_{}", std::process::id());
+        let ring = ShmRing::<RawMessage>::create(&ring_name, 16).unwrap();
+        let mut consumer = RingLike::consumer(&ring);
+        let inner = DummyParser::new(MediumTag::Sbe)
+            .with_symbol_ids(&[("BTCUSDT".to_string(), 0)])
+            .with_group_ring(FragmentSink::new(ShmRing::<RawMessage>::open(&ring_name).unwrap()));
+        let mut parser = SbeParser::new(inner);
+
+        // The leading trades are published by the parser, the last one left to the worker
+        let event = trades(&[(41, 1, 1, false), (42, 2, 2, false), (43, 3, 3, true)], "BTCUSDT");
+        let mut message = RawMessage::default();
+        parser.translate_trades(&event, &mut message).unwrap();
+        assert_eq!(parser.parse_payload(&mut message, EventType::Trade).unwrap(), ParseOutcome::Parsed);
+        assert!(std::str::from_utf8(&message.data).unwrap().contains(r#""t":43,"#));
+        for id in [41, 42] {
+            let RingConsume::Message(published) = RingConsumer::consume(&mut consumer) else {
+                panic!("expected the leading trade {} on the ring of the feedgroup", id);
+            };
+            assert_eq!(published.header.medium(), MediumTag::Sbe);
+            assert!(std::str::from_utf8(&published.data).unwrap().contains(&format!(r#""t":{},"#, id)));
+        }
+        assert!(matches!(RingConsumer::consume(&mut consumer), RingConsume::Empty));
+    }
+}
@@ -0,0 +1,128 @@
+//! Pausing the publishing of a FeedGroup.
+//!
+//! A paused FeedGroup keeps its connections and subscriptions, and keeps
+//! reading its feeds, but its parser drops the messages of the paused symbols
+//! instead of handing them to the ring. The handle is shared between the main
+//! thread, applying the pause commands of the control ring, and the parser.
+
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use hashbrown::HashMap;
+use serde::Deserialize;
+
+/// The symbol of a stream payload, shared by the Top, Trade and AggTrade payloads.
+#[derive(Deserialize)]
+struct Payload<'a> {
+    #[serde(rename = "s")]
+    symbol: &'a str,
+}
+
+/// The pause state of a FeedGroup.
+#[derive(Debug, Default)]
+struct PauseState {
+    /// The whole FeedGroup is paused.
+    all: AtomicBool,
+    /// The paused symbols, by exchange symbol name.
+    symbols: HashMap<String, AtomicBool>,
+    /// The number of paused symbols, to skip looking up the symbol of a payload.
+    paused_symbols: AtomicUsize,
+}
+
+/// A handle pausing the publishing of a FeedGroup, or of some of its symbols.
+#[derive(Debug, Clone, Default)]
+pub struct PauseHandle {
+    state: Arc<PauseState>,
+}
+
+impl PauseHandle {
+    /// Creates a handle of a FeedGroup publishing `symbols`, none paused.
+    pub fn new(symbols: &[String]) -> Self {
+        let symbols = symbols.iter().map(|symbol| (symbol.clone(), AtomicBool::new(false))).collect();
+        Self { state: Arc::new(PauseState { symbols, ..PauseState::default() }) }
+    }
+
+    /// Pauses or resumes the whole FeedGroup. Returns false if it already was.
+    pub fn set_paused(&self, paused: bool) -> bool {
+        self.state.all.swap(paused, Ordering::AcqRel) != paused
+    }
+
+    /// Pauses or resumes a symbol. Returns `None` if the symbol isn't published
+    /// by the FeedGroup, false if it already was.
+    pub fn set_symbol_paused(&self, symbol: &str, paused: bool) -> Option<bool> {
+        let flag = self.state.symbols.get(symbol)?;
+        let changed = flag.swap(paused, Ordering::AcqRel) != paused;
+        if changed {
+            if paused {
+                self.state.paused_symbols.fetch_add(1, Ordering::AcqRel);
+            } else {
+                self.state.paused_symbols.fetch_sub(1, Ordering::AcqRel);
+            }
+        }
+        Some(changed)
+    }
+
+    /// Returns true if the whole FeedGroup is paused.
+    pub fn is_paused(&self) -> bool {
+        self.state.all.load(Ordering::Acquire)
+    }
+
+    /// Returns true if the FeedGroup publishes `symbol`.
+    pub fn has_symbol(&self, symbol: &str) -> bool {
+        self.state.symbols.contains_key(symbol)
+    }
+
+    /// Returns true if a payload must be dropped: the FeedGroup or its symbol is paused.
+    ///
+    /// The payload is only parsed for its symbol while a symbol is paused.
+    ///
+    /// LATENCY: FAST_PATH
+    pub fn drops(&self, data: &[u8]) -> bool {
+        if self.is_paused() {
+            return true;
+        }
+        if self.state.paused_symbols.load(Ordering::Acquire) == 0 {
+            return false;
+        }
+        let Ok(payload) = serde_json::from_slice::<Payload<'_>>(data) else {
+            return false;
+        };
+        self.state
+            .symbols
+            .get(payload.symbol)
+            .is_some_and(|flag| flag.load(Ordering::Acquire))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BTC: &[u8] = br#"{"u":1,"s":"BTCUSDT","b":"1.0","B":"1","a":"2.0","A":"1"}"#;
+    const ETH: &[u8] = br#"{"e":"trade","s":"ETHUSDT","p":"1.0","q":"1"}"#;
+
+    #[test]
+    fn test_pause_group() {
+        let handle = PauseHandle::new(&["BTCUSDT".to_string(), "ETHUSDT".to_string()]);
+        assert!(!handle.drops(BTC));
+        assert!(handle.set_paused(true));
+        assert!(!handle.set_paused(true));
+        assert!(handle.drops(BTC) && handle.drops(ETH));
+        assert!(handle.clone().set_paused(false));
+        assert!(!handle.drops(ETH));
+    }
+
+    #[test]
+    fn test_pause_symbol() {
+        let handle = PauseHandle::new(&["BTCUSDT".to_string(), "ETHUSDT".to_string()]);
+        assert_eq!(handle.set_symbol_paused("BTCUSDT", true), Some(true));
+        assert_eq!(handle.set_symbol_paused("BTCUSDT", true), Some(false));
+        assert_eq!(handle.set_symbol_paused("SOLUSDT", true), None);
+        assert!(handle.drops(BTC));
+        assert!(!handle.drops(ETH));
+
+        assert_eq!(handle.set_symbol_paused("BTCUSDT", false), Some(true));
+        assert!(!handle.drops(BTC));
+        assert!(!handle.is_paused());
+    }
+}
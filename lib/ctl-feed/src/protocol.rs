@@ -1,115 +1,40 @@
-use atx_feed::{FeedProtocol, FeedProtocolOps, Streams};
-use ctl_websocket::{WSConn, WSRequest, WSRequestKind};
+//! Stream suffixes and market data entries of the feed kinds, providing their
+//! `FeedProtocol` implementations for `WSConn` and `FixConn`.
 
-use crate::{AggTrade, Top, Trade};
+use ctl_fix::{EntrySplit, MarketDataEntries};
+use ctl_websocket::StreamSuffix;
 
-impl FeedProtocol<Top> for WSConn<Top> {
-    /// Updates the subscribed streams for book ticker feed kind.
-    /// 
-    /// LATENCY: SLOW_PATH
-    /// ERROR: FULLY_HANDLED
-    fn update(&mut self, streams: &Streams<Top>) -> Result<(), Self::FeedProtocolError> {
-        
-        let unsubscribe = self.streams().difference(streams);
-        let unsubscribe_streams = unsubscribe.into_iter()
-            .map(|s| format!("{}@bookTicker", s.name)) // TODO: Add a better way to do this.
-            .collect::<Vec<String>>();
-        if !unsubscribe_streams.is_empty() {
-            let req: WSRequest = (
-                WSRequestKind::Unsubscribe(unsubscribe_streams), 
-                None
-            ).into();
-            let request_json = serde_json::to_vec(&req)?;
-            self.send(&request_json)?;
-        }
+use crate::{AggTrade, Depth, Top, Trade};
 
-        let subscribe = streams.difference(self.streams());
-        let subscribe_streams = subscribe.into_iter()
-            .map(|s| format!("{}@bookTicker", s.name))
-            .collect::<Vec<String>>();
-        if !subscribe_streams.is_empty() {
-            let req: WSRequest = (
-                WSRequestKind::Subscribe(subscribe_streams), 
-                None
-            ).into();
-            let request_json = serde_json::to_vec(&req)?;
-            self.send(&request_json)?;
-        }
-
-        Ok(())
-    }
+impl StreamSuffix for Top {
+    const SUFFIX: &'static str = "bookTicker";
 }
 
-impl FeedProtocol<Trade> for WSConn<Trade> {
-    /// Updates the subscribed streams for trade feed kind.
-    /// 
-    /// LATENCY: SLOW_PATH
-    /// ERROR: FULLY_HANDLED
-    fn update(&mut self, streams: &Streams<Trade>) -> Result<(), Self::FeedProtocolError> {
-        
-        let unsubscribe = self.streams().difference(streams);
-        let unsubscribe_streams = unsubscribe.into_iter()
-            .map(|s| format!("{}@trade", s.name)) // TODO: Add a better way to do this.
-            .collect::<Vec<String>>();
-        if !unsubscribe_streams.is_empty() {
-            let req: WSRequest = (
-                WSRequestKind::Unsubscribe(unsubscribe_streams), 
-                None
-            ).into();
-            let request_json = serde_json::to_vec(&req)?;
-            self.send(&request_json)?;
-        }
+impl StreamSuffix for Trade {
+    const SUFFIX: &'static str = "trade";
+}
 
-        let subscribe = streams.difference(self.streams());
-        let subscribe_streams = subscribe.into_iter()
-            .map(|s| format!("{}@trade", s.name)) // TODO: Add a better way to do this.
-            .collect::<Vec<String>>();
-        if !subscribe_streams.is_empty() {
-            let req: WSRequest = (
-                WSRequestKind::Subscribe(subscribe_streams), 
-                None
-            ).into();
-            let request_json = serde_json::to_vec(&req)?;
-            self.send(&request_json)?;
-        }
+impl StreamSuffix for AggTrade {
+    const SUFFIX: &'static str = "aggTrade";
+}
 
-        Ok(())
-    }
+impl StreamSuffix for Depth {
+    const SUFFIX: &'static str = "depth";
 }
 
-impl FeedProtocol<AggTrade> for WSConn<AggTrade> {
-    /// Updates the subscribed streams for aggregated trade feed kind.
-    /// 
-    /// LATENCY: SLOW_PATH
-    /// ERROR: FULLY_HANDLED
-    fn update(&mut self, streams: &Streams<AggTrade>) -> Result<(), Self::FeedProtocolError> {
-        
-        let unsubscribe = self.streams().difference(streams);
-        let unsubscribe_streams = unsubscribe.into_iter()
-            .map(|s| format!("{}@aggTrade", s.name))
-            .collect::<Vec<String>>();
-        if !unsubscribe_streams.is_empty() {
-            let req: WSRequest = (
-                WSRequestKind::Unsubscribe(unsubscribe_streams), 
-                None
-            ).into();
-            let request_json = serde_json::to_vec(&req)?;
-            self.send(&request_json)?;
-        }
+// Aggregated trades and depth diffs aren't published on the FIX market data sessions
 
-        let subscribe = streams.difference(self.streams());
-        let subscribe_streams = subscribe.into_iter()
-            .map(|s| format!("{}@aggTrade", s.name))
-            .collect::<Vec<String>>();
-        if !subscribe_streams.is_empty() {
-            let req: WSRequest = (
-                WSRequestKind::Subscribe(subscribe_streams), 
-                None
-            ).into();
-            let request_json = serde_json::to_vec(&req)?;
-            self.send(&request_json)?;
-        }
+impl MarketDataEntries for Top {
+    const NAME: &'static str = "BOOK_TICKER";
+    // The best bid and offer
+    const ENTRY_TYPES: &'static [&'static str] = &["0", "1"];
+    const MARKET_DEPTH: u32 = 1;
+    const SPLIT: EntrySplit = EntrySplit::PerSymbol;
+}
 
-        Ok(())
-    }
-}
\ No newline at end of file
+impl MarketDataEntries for Trade {
+    const NAME: &'static str = "TRADE";
+    const ENTRY_TYPES: &'static [&'static str] = &["2"];
+    const MARKET_DEPTH: u32 = 1;
+    const SPLIT: EntrySplit = EntrySplit::PerEntry;
+}
@@ -0,0 +1,223 @@
+//! Reading of the raw rings by their consumers.
+//!
+//! The subscriber, the trade statistics, the gateway, the re-publisher, the
+//! sinks, the alerts, the signals and the watcher read the payloads of the
+//! `RawMessage` rings alike: each message is committed before it's
+//! read, its position tracked by the consumer cursor of the component in the
+//! ring metrics, and the payloads fragmented over several slots reassembled.
+//! Once sped past by the producer, the consumer skips to the head of the ring
+//! and recovers per the `OvertakenPolicy` of the component.
+//!
+//! A `RingReader` does so for one ring, fed by `dpdk_consume!` from the DPDK
+//! consumer of the ring, or by any `RingConsumer` in the tests.
+
+use std::sync::atomic::Ordering;
+
+use ctl_core::OvertakenPolicy;
+
+use crate::{ConsumerCursor, MetricsRegion, RawMessage, Reassembler, Reassembly, RingConsume, RingError, RingMetrics};
+
+/// Consumes the next message of a DPDK pub/sub ring of `RawMessage`s, as a
+/// `RingConsume`, committing the message before it's read.
+///
+/// A commit lost to a concurrent consumer is retried on the next consume, as
+/// a message in flight.
+///
+/// LATENCY: FAST_PATH
+#[macro_export]
+macro_rules! dpdk_consume {
+    ($consumer:expr) => {
+        match $consumer.consume_start() {
+            ::dpdk::ConsumeStartState::Success(mut guard) => match guard.try_commit() {
+                Ok(_) => $crate::RingConsume::Message(*guard.as_ref().get()),
+                Err(_) => $crate::RingConsume::InFlight,
+            },
+            ::dpdk::ConsumeStartState::InFlight(_guard) => $crate::RingConsume::InFlight,
+            ::dpdk::ConsumeStartState::SpedPast(_guard) => $crate::RingConsume::SpedPast,
+            ::dpdk::ConsumeStartState::Empty => $crate::RingConsume::Empty,
+        }
+    };
+}
+
+/// The result of a read of a ring.
+#[derive(Debug)]
+pub enum RingRead<'r> {
+    /// A complete payload, and the message of its last fragment.
+    Payload(&'r RawMessage, &'r [u8]),
+    /// A message consumed without completing a payload: a leading fragment,
+    /// or a fragment whose payload was lost.
+    Consumed,
+    /// Sped past by the producer, the messages overwritten skipped. The
+    /// conflated state of the symbols is to be read again if `snapshot`.
+    Overtaken { snapshot: bool },
+    /// No message to read.
+    Idle,
+}
+
+impl RingRead<'_> {
+    /// Returns true if the read did work, for the polling policy.
+    pub fn did_work(&self) -> bool {
+        !matches!(self, RingRead::Idle)
+    }
+}
+
+/// A reader of a raw ring, tracking its position in the ring metrics.
+pub struct RingReader<'a> {
+    /// The metrics of the ring.
+    metrics: &'a RingMetrics,
+    /// The index of the consumer cursor of the reader in the ring metrics.
+    cursor: usize,
+    /// The reassembler of the fragmented payloads, at the slot size of the ring.
+    reassembler: Reassembler,
+    /// The recovery once overtaken by the producer.
+    overtaken: OvertakenPolicy,
+    /// The last message read.
+    message: RawMessage,
+}
+
+impl<'a> RingReader<'a> {
+    /// Attaches the consumer cursor `consumer` to the metrics of the ring
+    /// `name`, after checking the layout and the slot size of the ring.
+    ///
+    /// # Errors
+    /// Returns an error if the ring isn't registered in the metrics region,
+    /// was registered by a stale binary or with another slot size, or has no
+    /// free consumer cursor.
+    pub fn attach(region: &'a MetricsRegion, name: &str, consumer: &str, slot_size: usize) -> Result<Self, RingError> {
+        region.check_layout::<RawMessage>(name)?;
+        region.check_slot_size(name, slot_size)?;
+        let index = region.find_ring(name).ok_or_else(|| RingError::Unregistered(name.to_string()))?;
+        let metrics = &region.rings[index];
+        let cursor = metrics.attach_named_consumer(consumer, std::process::id() as u64)?;
+        Ok(Self::new(metrics, cursor, slot_size))
+    }
+
+    /// Creates the reader of the ring of `metrics`, at the consumer cursor of index `cursor`.
+    pub fn new(metrics: &'a RingMetrics, cursor: usize, slot_size: usize) -> Self {
+        Self {
+            metrics,
+            cursor,
+            reassembler: Reassembler::new(slot_size),
+            overtaken: OvertakenPolicy::SkipToHead,
+            message: RawMessage::default(),
+        }
+    }
+
+    /// Recovers with `policy` once overtaken by the producer.
+    pub fn with_overtaken(mut self, policy: OvertakenPolicy) -> Self {
+        self.overtaken = policy;
+        self
+    }
+
+    /// Returns the name of the ring.
+    pub fn name(&self) -> String {
+        self.metrics.name()
+    }
+
+    /// Returns the consumer cursor of the reader.
+    pub fn cursor(&self) -> &ConsumerCursor {
+        &self.metrics.consumers[self.cursor]
+    }
+
+    /// Detaches the consumer cursor of the reader, e.g. on exit.
+    pub fn detach(&self) {
+        self.metrics.detach_consumer(self.cursor);
+    }
+
+    /// Consumes a message without reading it, e.g. skipped by a filter or
+    /// failing its checksum.
+    ///
+    /// LATENCY: FAST_PATH
+    pub fn skip(&mut self) {
+        self.metrics.consumers[self.cursor].advance();
+    }
+
+    /// Reads the result of a consume of the ring.
+    ///
+    /// # Errors
+    /// Returns `RingError::Overtaken` once overtaken under the fail-fast policy.
+    ///
+    /// LATENCY: FAST_PATH
+    pub fn read(&mut self, consumed: RingConsume<RawMessage>) -> Result<RingRead<'_>, RingError> {
+        match consumed {
+            RingConsume::Message(message) => {
+                self.metrics.consumers[self.cursor].advance();
+                self.message = message;
+                match self.reassembler.push(&self.message) {
+                    Reassembly::Complete(payload) => Ok(RingRead::Payload(&self.message, payload)),
+                    Reassembly::Partial | Reassembly::Dropped => Ok(RingRead::Consumed),
+                }
+            }
+            RingConsume::SpedPast => {
+                self.reassembler.reset();
+                self.metrics.consumers[self.cursor].sped_past(self.metrics.head.load(Ordering::Acquire));
+                match self.overtaken {
+                    OvertakenPolicy::SkipToHead => Ok(RingRead::Overtaken { snapshot: false }),
+                    OvertakenPolicy::SkipAndRequestSnapshot => Ok(RingRead::Overtaken { snapshot: true }),
+                    OvertakenPolicy::FailFast => Err(RingError::Overtaken(self.metrics.name())),
+                }
+            }
+            RingConsume::InFlight | RingConsume::Empty => Ok(RingRead::Idle),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use ctl_shm::ShmRegion;
+
+    use crate::{MemoryRing, RingConsumer, RingLike, RingPublisher};
+
+    fn message(payload: &str) -> RawMessage {
+        let mut message = RawMessage::default();
+        message.data[..payload.len()].copy_from_slice(payload.as_bytes());
+        message
+    }
+
+    #[test]
+    fn test_ring_reader() {
+        let name = format!("ctl_feed_reader_test_{}", std::process::id());
+        let region = ShmRegion::<MetricsRegion>::create(&name).unwrap();
+        region.register_raw_ring("TRADE_0_PS", 4, 64).unwrap();
+        let attach = |name, slot_size| RingReader::attach(&region, name, "sink", slot_size);
+        assert!(matches!(attach("TRADE_0_PS", 128), Err(RingError::SlotSizeMismatch { .. })));
+        assert!(matches!(attach("TRADE_1_PS", 64), Err(RingError::Unregistered(_))));
+
+        let ring = MemoryRing::<RawMessage>::new(4);
+        let mut consumer = ring.consumer();
+        let mut reader = attach("TRADE_0_PS", 64).unwrap();
+        assert!(matches!(reader.read(consumer.consume()).unwrap(), RingRead::Idle));
+
+        region.rings[0].record_publish();
+        ring.publish(&message(r#"{"s":"BTCUSDT"}"#)).unwrap();
+        match reader.read(consumer.consume()).unwrap() {
+            RingRead::Payload(_, payload) => assert!(payload.starts_with(br#"{"s":"BTCUSDT"}"#)),
+            other => panic!("unexpected read {:?}", other),
+        }
+        assert_eq!(reader.cursor().position.load(Ordering::Relaxed), 1);
+
+        // A message skipped is consumed all the same
+        region.rings[0].record_publish();
+        ring.publish(&message(r#"{"s":"ETHUSDT"}"#)).unwrap();
+        assert!(matches!(consumer.consume(), RingConsume::Message(_)));
+        reader.skip();
+        assert_eq!(reader.cursor().position.load(Ordering::Relaxed), 2);
+
+        // Sped past, the reader skips to the head, then fails under the fail-fast policy
+        for _ in 0..10 {
+            region.rings[0].record_publish();
+            ring.publish(&message("{}")).unwrap();
+        }
+        assert!(matches!(reader.read(consumer.consume()).unwrap(), RingRead::Overtaken { snapshot: false }));
+        assert_eq!(reader.cursor().position.load(Ordering::Relaxed), 12);
+        let mut reader = reader.with_overtaken(OvertakenPolicy::SkipAndRequestSnapshot);
+        assert!(matches!(reader.read(RingConsume::SpedPast).unwrap(), RingRead::Overtaken { snapshot: true }));
+        let mut reader = reader.with_overtaken(OvertakenPolicy::FailFast);
+        assert!(matches!(reader.read(RingConsume::SpedPast), Err(RingError::Overtaken(ring)) if ring == "TRADE_0_PS"));
+
+        reader.detach();
+        assert!(attach("TRADE_0_PS", 64).is_ok());
+    }
+}
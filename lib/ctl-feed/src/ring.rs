@@ -0,0 +1,295 @@
+//! Ring abstraction over the pub/sub ring backends.
+//!
+//! The publishing and consuming logic of the components is written against
+//! `RingPublisher` and `RingLike`, so it can run on the DPDK rings in
+//! production, on the shared memory rings of ctl-shm, or on the in-process
+//! `MemoryRing` (`test-rings` feature) in unit and integration tests, without a
+//! DPDK primary process.
+
+#[cfg(any(test, feature = "test-rings"))]
+use std::sync::Mutex;
+
+use ctl_core::{AlertMessage, ControlMessage};
+use ctl_shm::{ShmConsume, ShmMessage, ShmRing, ShmRingConsumer};
+use dpdk::DpdkPubSubRing;
+use thiserror::Error;
+
+use crate::{CandleMessage, RawMessage, SignalMessage, TradeStatsMessage};
+
+#[derive(Debug, Error)]
+pub enum RingError {
+    #[error("ring error: publish failed: {0}")]
+    Publish(String),
+    #[error("ring error: lookup of ring {ring} failed: {reason}")]
+    Lookup { ring: String, reason: String },
+    #[error("ring error: invalid consumer name '{0}'")]
+    InvalidConsumer(String),
+    #[error("ring error: consumer {consumer} is already attached to ring {ring}")]
+    ConsumerAttached { ring: String, consumer: String },
+    #[error("ring error: no free consumer cursor on ring {0}")]
+    NoFreeCursor(String),
+    #[error("ring error: ring {0} is not registered in the metrics region")]
+    Unregistered(String),
+    #[error("ring error: ring {ring} layout hash {found:#018x} does not match expected hash {expected:#018x}, rebuild the stale binary")]
+    LayoutMismatch { ring: String, expected: u64, found: u64 },
+    #[error("ring error: ring {ring} {reason}")]
+    InvalidSlotSize { ring: String, reason: String },
+    #[error("ring error: ring {ring} slot size {found} does not match the configured slot size {expected}, restart the resource manager")]
+    SlotSizeMismatch { ring: String, expected: usize, found: usize },
+    #[error("ring error: consumer of ring {0} overtaken by its producer, messages missed")]
+    Overtaken(String),
+    #[error("ring error: ring {0} is full, message dropped under its overflow policy")]
+    Full(String),
+}
+
+/// The result of a consume.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RingConsume<T> {
+    /// The next message.
+    Message(T),
+    /// The consumer was lapped by the producers, skipping messages.
+    SpedPast,
+    /// The next message is being written.
+    InFlight,
+    /// No message was published since the last consume.
+    Empty,
+}
+
+/// A ring messages are published to.
+pub trait RingPublisher<T> {
+    /// Publishes a message.
+    ///
+    /// LATENCY: FAST_PATH
+    fn publish(&self, message: &T) -> Result<(), RingError>;
+}
+
+/// A consumer of a ring, at its own position.
+pub trait RingConsumer<T> {
+    /// Consumes the next message.
+    ///
+    /// LATENCY: FAST_PATH
+    fn consume(&mut self) -> RingConsume<T>;
+}
+
+/// A ring both published to and consumed.
+pub trait RingLike<T>: RingPublisher<T> {
+    /// The consumer of the ring.
+    type Consumer<'a>: RingConsumer<T>
+    where
+        Self: 'a;
+
+    /// Attaches a consumer reading the messages published from now on.
+    fn consumer(&self) -> Self::Consumer<'_>;
+}
+
+impl<T, R: RingPublisher<T> + ?Sized> RingPublisher<T> for &R {
+    fn publish(&self, message: &T) -> Result<(), RingError> {
+        (**self).publish(message)
+    }
+}
+
+// The DPDK rings are consumed through their commit guards by the components,
+// only their publishing is abstracted.
+macro_rules! dpdk_publisher {
+    ($($message:ty),*) => {
+        $(
+            impl RingPublisher<$message> for DpdkPubSubRing<$message> {
+                fn publish(&self, message: &$message) -> Result<(), RingError> {
+                    DpdkPubSubRing::publish(self, message)
+                        .map(|_| ())
+                        .map_err(|e| RingError::Publish(e.to_string()))
+                }
+            }
+        )*
+    };
+}
+
+dpdk_publisher!(RawMessage, TradeStatsMessage, CandleMessage, SignalMessage, AlertMessage, ControlMessage);
+
+impl<T: ShmMessage> RingPublisher<T> for ShmRing<T> {
+    fn publish(&self, message: &T) -> Result<(), RingError> {
+        ShmRing::publish(self, message);
+        Ok(())
+    }
+}
+
+impl<T: ShmMessage> RingLike<T> for ShmRing<T> {
+    type Consumer<'a> = ShmRingConsumer<'a, T>;
+
+    fn consumer(&self) -> Self::Consumer<'_> {
+        ShmRing::consumer(self)
+    }
+}
+
+impl<T: ShmMessage> RingConsumer<T> for ShmRingConsumer<'_, T> {
+    fn consume(&mut self) -> RingConsume<T> {
+        match ShmRingConsumer::consume(self) {
+            ShmConsume::Message(message) => RingConsume::Message(message),
+            ShmConsume::SpedPast { .. } => RingConsume::SpedPast,
+            ShmConsume::InFlight => RingConsume::InFlight,
+            ShmConsume::Empty => RingConsume::Empty,
+        }
+    }
+}
+
+/// An in-process ring, overwriting its oldest message once full like the shared rings.
+#[cfg(any(test, feature = "test-rings"))]
+#[derive(Debug)]
+pub struct MemoryRing<T> {
+    /// The number of slots.
+    capacity: usize,
+    /// The held messages and the number of messages published.
+    state: Mutex<(Vec<T>, u64)>,
+}
+
+#[cfg(any(test, feature = "test-rings"))]
+impl<T: Copy> MemoryRing<T> {
+    /// Creates an empty ring of `capacity` slots.
+    pub fn new(capacity: usize) -> Self {
+        assert!(capacity > 0, "MemoryRing capacity must be non-zero");
+        Self { capacity, state: Mutex::new((Vec::with_capacity(capacity), 0)) }
+    }
+
+    /// Returns the number of messages published.
+    pub fn published(&self) -> u64 {
+        self.state.lock().unwrap().1
+    }
+}
+
+#[cfg(any(test, feature = "test-rings"))]
+impl<T: Copy> RingPublisher<T> for MemoryRing<T> {
+    fn publish(&self, message: &T) -> Result<(), RingError> {
+        let mut state = self.state.lock().unwrap();
+        let (slots, head) = &mut *state;
+        let index = (*head % self.capacity as u64) as usize;
+        if index < slots.len() {
+            slots[index] = *message;
+        } else {
+            slots.push(*message);
+        }
+        *head += 1;
+        Ok(())
+    }
+}
+
+#[cfg(any(test, feature = "test-rings"))]
+impl<T: Copy> RingLike<T> for MemoryRing<T> {
+    type Consumer<'a>
+        = MemoryRingConsumer<'a, T>
+    where
+        Self: 'a;
+
+    fn consumer(&self) -> Self::Consumer<'_> {
+        MemoryRingConsumer { ring: self, next: self.published() }
+    }
+}
+
+/// A consumer of a `MemoryRing`.
+#[cfg(any(test, feature = "test-rings"))]
+#[derive(Debug)]
+pub struct MemoryRingConsumer<'a, T> {
+    /// The ring.
+    ring: &'a MemoryRing<T>,
+    /// The sequence number of the next message to consume.
+    next: u64,
+}
+
+#[cfg(any(test, feature = "test-rings"))]
+impl<T: Copy> RingConsumer<T> for MemoryRingConsumer<'_, T> {
+    fn consume(&mut self) -> RingConsume<T> {
+        let state = self.ring.state.lock().unwrap();
+        let (slots, head) = &*state;
+        if self.next >= *head {
+            return RingConsume::Empty;
+        }
+        let capacity = self.ring.capacity as u64;
+        if *head - self.next > capacity {
+            self.next = *head - capacity;
+            return RingConsume::SpedPast;
+        }
+        let message = slots[(self.next % capacity) as usize];
+        self.next += 1;
+        RingConsume::Message(message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{DummyParser, MediumTag};
+
+    /// Drains the messages of a consumer, stopping at the first non-message.
+    fn drain<T, C: RingConsumer<T>>(consumer: &mut C) -> (Vec<T>, RingConsume<T>) {
+        let mut messages = Vec::new();
+        loop {
+            match consumer.consume() {
+                RingConsume::Message(message) => messages.push(message),
+                other => return (messages, other),
+            }
+        }
+    }
+
+    #[test]
+    fn test_memory_ring() {
+        let ring = MemoryRing::<u64>::new(4);
+        ring.publish(&0).unwrap();
+        // A consumer only reads the messages published after it attached
+        let mut first = ring.consumer();
+        for value in 1..4 {
+            ring.publish(&value).unwrap();
+        }
+        let mut second = ring.consumer();
+        assert_eq!(drain(&mut first), (vec![1, 2, 3], RingConsume::Empty));
+        assert_eq!(second.consume(), RingConsume::Empty);
+
+        ring.publish(&4).unwrap();
+        assert_eq!(drain(&mut first), (vec![4], RingConsume::Empty));
+        assert_eq!(drain(&mut second), (vec![4], RingConsume::Empty));
+    }
+
+    #[test]
+    fn test_memory_ring_sped_past() {
+        let ring = MemoryRing::<u64>::new(4);
+        let mut consumer = ring.consumer();
+        for value in 0..10 {
+            ring.publish(&value).unwrap();
+        }
+        assert_eq!(consumer.consume(), RingConsume::SpedPast);
+        assert_eq!(drain(&mut consumer), (vec![6, 7, 8, 9], RingConsume::Empty));
+    }
+
+    #[test]
+    fn test_parse_publish_consume() {
+        let ring = MemoryRing::<RawMessage>::new(16);
+        let mut consumer = ring.consumer();
+        let parser = DummyParser::new(MediumTag::Sbe).with_symbol_ids(&[("btcusdt".to_string(), 7)]);
+
+        let payloads = [r#"{"u":1,"s":"BTCUSDT"}"#, r#"{"u":2,"s":"ETHUSDT"}"#];
+        for payload in payloads {
+            let mut message = RawMessage::default();
+            parser.fill_raw(payload.as_bytes(), &mut message).unwrap();
+            ring.publish(&message).unwrap();
+        }
+
+        let (messages, _) = drain(&mut consumer);
+        assert_eq!(messages.len(), payloads.len());
+        assert_eq!(messages[0].header.symbol_id(), Some(7));
+        assert_eq!(messages[1].header.symbol_id(), None);
+        for (message, payload) in messages.iter().zip(payloads) {
+            assert_eq!(message.header.medium(), MediumTag::Sbe);
+            assert_eq!(&message.data[..payload.len()], payload.as_bytes());
+            assert!(message.data[payload.len()..].iter().all(|&b| b == 0));
+        }
+    }
+
+    #[test]
+    fn test_shm_ring_like() {
+        let name = format!("ctl_feed_ring_test_{}", std::process::id());
+        let ring = ShmRing::<RawMessage>::create(&name, 8).unwrap();
+        let mut consumer = RingLike::consumer(&ring);
+        RingPublisher::publish(&ring, &RawMessage::default()).unwrap();
+        assert!(matches!(RingConsumer::consume(&mut consumer), RingConsume::Message(_)));
+        assert!(matches!(RingConsumer::consume(&mut consumer), RingConsume::Empty));
+    }
+}
@@ -0,0 +1,312 @@
+//! FeedGroups run on plain threads, publishing to rings of any backend.
+//!
+//! The FeedGroups of atx-feed run their workers on the lcores of a DPDK
+//! environment, publishing to DPDK rings. A `ShmFeedGroup` runs the same feeds
+//! and parsers on threads of the process instead, publishing through the
+//! `RingPublisher` of its ring, so ctl-md-handler can publish to the shared
+//! memory rings of ctl-shm on a host without hugepages or an EAL (`shm-rings`
+//! feature of ctl-md-handler).
+//!
+//! The workers poll their feeds, parse the polled events with the `ParseInto`
+//! parse of the parser and publish the parsed messages, as the workers of
+//! atx-feed do with the `FeedParseProtocol` parse. They take no commands, the
+//! pause of the parsers and the failover of the feed connections applying as
+//! on the DPDK workers, and run until the process exits.
+
+use std::marker::PhantomData;
+use std::sync::Mutex;
+use std::thread::{self, JoinHandle};
+
+use atx_feed::{FeedGroupWorkerFeedback, FeedKind, FeedPoll, FeedProtocol, FeedProtocolOps};
+use ctl_fix::FixConn;
+use ctl_websocket::WSConn;
+use derive_more::From;
+use dpdk::DpdkLCoreId;
+use thiserror::Error;
+
+use crate::{AggTrade, Depth, DummyParser, FixParser, FragmentSink, ParseSkip, RawMessage, SbeParser, Top, Trade};
+
+#[derive(Debug, Error)]
+pub enum ShmFeedGroupError {
+    #[error("feedgroup {0} has no worker lcores")]
+    NoWorkers(&'static str),
+    #[error("feedgroup {0} has no feeds")]
+    NoFeeds(&'static str),
+    #[error("feedgroup {0} is already running")]
+    AlreadyRunning(&'static str),
+    #[error("feedgroup {name} failed to spawn a worker: {source}")]
+    Spawn { name: &'static str, source: std::io::Error },
+    #[error("feedgroup {name} worker panicked: {reason}")]
+    Panicked { name: &'static str, reason: String },
+}
+
+/// Parses the events polled from a feed connection `F` into the message the
+/// workers publish to the ring of their FeedGroup, skipping the others.
+///
+/// The parse of the `FeedParseProtocol` implementations, on a message buffer
+/// of the worker rather than one aligned by DPDK.
+pub trait ParseInto<F> {
+    /// Parses an event into `message`.
+    ///
+    /// LATENCY: FAST_PATH
+    fn parse_into(&mut self, raw_data: atx_feed::FeedData, message: &mut RawMessage) -> Result<(), ParseSkip>;
+}
+
+/// A FeedGroup whose workers are threads of the process.
+pub struct ShmFeedGroup<'a, F, K, P> {
+    /// The FeedGroup name.
+    name: &'static str,
+    /// The lcores planned for the workers, one worker per feed up to their number.
+    worker_lcore_ids: Vec<DpdkLCoreId>,
+    /// The ring the workers publish the parsed messages to.
+    publisher: FragmentSink,
+    /// The parser, cloned for each worker.
+    parser: P,
+    /// The feeds, handed to the workers once run.
+    feeds: Vec<F>,
+    _marker: PhantomData<(&'a (), fn() -> K)>,
+}
+
+impl<'a, F, K, P> ShmFeedGroup<'a, F, K, P> {
+    /// Creates the FeedGroup of feeds publishing to a ring.
+    ///
+    /// # Errors
+    /// Returns an error without worker lcores or feeds.
+    pub fn new(
+        name: &'static str,
+        worker_lcore_ids: Vec<DpdkLCoreId>,
+        publisher: FragmentSink,
+        parser: P,
+        feeds: Vec<F>,
+    ) -> Result<Self, ShmFeedGroupError> {
+        if worker_lcore_ids.is_empty() {
+            return Err(ShmFeedGroupError::NoWorkers(name));
+        }
+        if feeds.is_empty() {
+            return Err(ShmFeedGroupError::NoFeeds(name));
+        }
+        Ok(Self { name, worker_lcore_ids, publisher, parser, feeds, _marker: PhantomData })
+    }
+
+    /// Returns the feedback of the workers, which take no commands.
+    pub fn poll_feedback(&mut self) -> Option<FeedGroupWorkerFeedback<F, K>>
+    where
+        F: FeedProtocol<K>,
+        K: FeedKind,
+    {
+        None
+    }
+}
+
+impl<F, K, P> ShmFeedGroup<'_, F, K, P>
+where
+    F: FeedProtocolOps + Send + 'static,
+    P: ParseInto<F> + Clone + Send + 'static,
+{
+    /// Starts the workers, spreading the feeds over one worker per planned
+    /// lcore, up to one per feed.
+    ///
+    /// # Errors
+    /// Returns an error if the FeedGroup already ran or a worker can't be spawned.
+    pub fn run(&mut self) -> Result<ShmJoinHandle, ShmFeedGroupError> {
+        if self.feeds.is_empty() {
+            return Err(ShmFeedGroupError::AlreadyRunning(self.name));
+        }
+        let workers = self.worker_lcore_ids.len().min(self.feeds.len());
+        let mut assigned: Vec<Vec<F>> = (0..workers).map(|_| Vec::new()).collect();
+        for (i, feed) in self.feeds.drain(..).enumerate() {
+            assigned[i % workers].push(feed);
+        }
+
+        let lcore_ids = self.worker_lcore_ids[..workers].to_vec();
+        let mut handles = Vec::with_capacity(workers);
+        for (feeds, lcore_id) in assigned.into_iter().zip(&lcore_ids) {
+            let (parser, publisher) = (self.parser.clone(), self.publisher.clone());
+            let handle = thread::Builder::new()
+                .name(format!("{}@{}", self.name, lcore_id))
+                .spawn(move || work(feeds, parser, publisher))
+                .map_err(|source| ShmFeedGroupError::Spawn { name: self.name, source })?;
+            handles.push(handle);
+        }
+        Ok(ShmJoinHandle { name: self.name, lcore_ids, handles: Mutex::new(handles) })
+    }
+}
+
+/// Polls the feeds of a worker, publishing the messages parsed from their events.
+///
+/// The errors of a poll drop the event, the feeds recovering from the failures
+/// of their connections themselves.
+///
+/// LATENCY: FAST_PATH
+fn work<F, P>(mut feeds: Vec<F>, mut parser: P, publisher: FragmentSink) -> Result<(), ShmFeedGroupError>
+where
+    F: FeedProtocolOps,
+    P: ParseInto<F>,
+{
+    let mut message = Box::<RawMessage>::default();
+    loop {
+        let mut polled = false;
+        for feed in feeds.iter_mut() {
+            let Ok(FeedPoll::Data(raw_data)) = feed.poll() else {
+                continue;
+            };
+            polled = true;
+            // The parser counts its errors, and publishes the routed messages itself
+            if parser.parse_into(raw_data, &mut message).is_ok() {
+                // Rejected under the overflow policy of the ring, counted in its metrics
+                let _ = publisher.publish(&message);
+            }
+        }
+        if !polled {
+            thread::yield_now();
+        }
+    }
+}
+
+/// The handle of the workers of a running `ShmFeedGroup`.
+pub struct ShmJoinHandle {
+    /// The FeedGroup name.
+    name: &'static str,
+    /// The lcores of the workers.
+    lcore_ids: Vec<DpdkLCoreId>,
+    /// The workers, joined once all of them exited.
+    handles: Mutex<Vec<JoinHandle<Result<(), ShmFeedGroupError>>>>,
+}
+
+impl ShmJoinHandle {
+    /// Returns the lcores of the workers.
+    pub fn lcore_ids(&self) -> &[DpdkLCoreId] {
+        &self.lcore_ids
+    }
+
+    /// Joins the workers once all of them exited, returning their results,
+    /// `None` while any of them runs or once joined.
+    pub fn try_join(&self) -> Option<Result<Vec<Result<(), ShmFeedGroupError>>, ShmFeedGroupError>> {
+        let mut handles = self.handles.lock().ok()?;
+        if handles.is_empty() || !handles.iter().all(JoinHandle::is_finished) {
+            return None;
+        }
+        let results = handles
+            .drain(..)
+            .map(|handle| {
+                handle.join().unwrap_or_else(|panic| {
+                    let reason = panic
+                        .downcast_ref::<&str>()
+                        .map(|reason| reason.to_string())
+                        .or_else(|| panic.downcast_ref::<String>().cloned())
+                        .unwrap_or_default();
+                    Err(ShmFeedGroupError::Panicked { name: self.name, reason })
+                })
+            })
+            .collect();
+        Some(Ok(results))
+    }
+}
+
+/// The `ShmFeedGroup`s of the feed kinds and mediums, as the `FeedGroups` of
+/// the DPDK workers.
+#[derive(From)]
+pub enum ShmFeedGroups<'a> {
+    JsonTop(ShmFeedGroup<'a, WSConn<Top>, Top, DummyParser>),
+    JsonTrade(ShmFeedGroup<'a, WSConn<Trade>, Trade, DummyParser>),
+    JsonAggTrade(ShmFeedGroup<'a, WSConn<AggTrade>, AggTrade, DummyParser>),
+    JsonDepth(ShmFeedGroup<'a, WSConn<Depth>, Depth, DummyParser>),
+    FixTop(ShmFeedGroup<'a, FixConn<Top>, Top, FixParser>),
+    FixTrade(ShmFeedGroup<'a, FixConn<Trade>, Trade, FixParser>),
+    SbeTop(ShmFeedGroup<'a, WSConn<Top>, Top, SbeParser>),
+    SbeTrade(ShmFeedGroup<'a, WSConn<Trade>, Trade, SbeParser>),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::time::{Duration, Instant};
+
+    use atx_feed::FeedData;
+    use ctl_shm::ShmRing;
+
+    use crate::{EventType, MediumTag, RingConsume, RingConsumer, RingLike};
+
+    /// Bound on the consumes waiting for the workers.
+    const WAIT: Duration = Duration::from_secs(5);
+
+    /// A feed polling its events once.
+    struct ScriptedFeed(Vec<Vec<u8>>, Vec<u8>);
+
+    impl FeedProtocolOps for ScriptedFeed {
+        type FeedProtocolError = std::io::Error;
+
+        fn poll(&mut self) -> Result<FeedPoll<'_>, Self::FeedProtocolError> {
+            if self.0.is_empty() {
+                return Ok(FeedPoll::Empty);
+            }
+            self.1 = self.0.remove(0);
+            Ok(FeedPoll::Data(&self.1))
+        }
+
+        fn send(&mut self, _data: FeedData) -> Result<(), Self::FeedProtocolError> {
+            Ok(())
+        }
+    }
+
+    impl ParseInto<ScriptedFeed> for DummyParser {
+        fn parse_into(&mut self, raw_data: FeedData, message: &mut RawMessage) -> Result<(), ParseSkip> {
+            self.parse_event(raw_data, message, EventType::Trade)?.to_worker()
+        }
+    }
+
+    #[test]
+    fn test_run() {
+        let name = format!("ctl_feed_shm_group_test_{}", std::process::id());
+        let ring = ShmRing::<RawMessage>::create(&name, 64).unwrap();
+        let mut consumer = RingLike::consumer(&ring);
+        let feed = ScriptedFeed(vec![br#"{"e":"trade","s":"BTCUSDT","t":1}"#.to_vec()], Vec::new());
+        let publisher = FragmentSink::new(ShmRing::<RawMessage>::open(&name).unwrap());
+        let mut group: ShmFeedGroup<'_, ScriptedFeed, Trade, DummyParser> =
+            ShmFeedGroup::new("trade/test", vec![1, 2], publisher, DummyParser::new(MediumTag::Websocket), vec![feed])
+                .unwrap();
+
+        // One worker for the single feed
+        let handle = group.run().unwrap();
+        assert_eq!(handle.lcore_ids(), &[1]);
+        assert!(matches!(group.run(), Err(ShmFeedGroupError::AlreadyRunning(_))));
+
+        let start = Instant::now();
+        let message = loop {
+            if let RingConsume::Message(message) = RingConsumer::consume(&mut consumer) {
+                break message;
+            }
+            assert!(start.elapsed() < WAIT, "no message published");
+            thread::sleep(Duration::from_millis(1));
+        };
+        assert!(std::str::from_utf8(&message.data).unwrap().contains(r#""t":1}"#));
+        // The worker keeps polling its feed
+        assert!(handle.try_join().is_none());
+    }
+
+    #[test]
+    fn test_new_errors() {
+        let name = format!("ctl_feed_shm_group_new_test_{}", std::process::id());
+        let ring = ShmRing::<RawMessage>::create(&name, 64).unwrap();
+        let parser = DummyParser::new(MediumTag::Websocket);
+        let created = ShmFeedGroup::<'_, ScriptedFeed, Trade, DummyParser>::new(
+            "trade/test",
+            Vec::new(),
+            FragmentSink::new(ring),
+            parser.clone(),
+            vec![ScriptedFeed(Vec::new(), Vec::new())],
+        );
+        assert!(matches!(created, Err(ShmFeedGroupError::NoWorkers(_))));
+
+        let ring = ShmRing::<RawMessage>::open(&name).unwrap();
+        let created = ShmFeedGroup::<'_, ScriptedFeed, Trade, DummyParser>::new(
+            "trade/test",
+            vec![1],
+            FragmentSink::new(ring),
+            parser,
+            Vec::new(),
+        );
+        assert!(matches!(created, Err(ShmFeedGroupError::NoFeeds(_))));
+    }
+}
@@ -0,0 +1,165 @@
+//! JSON status of the rings and streams of the metrics region.
+//!
+//! The components serving a `/status` endpoint report the rings and streams of
+//! the metrics region as read at the request: the producer head, the lag of
+//! each consumer and the message rate of each stream.
+
+use std::sync::atomic::Ordering;
+
+use serde::Serialize;
+
+use crate::{MetricsRegion, RingMetrics, StreamStats};
+
+/// The status of a consumer attached to a ring.
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct ConsumerStatus {
+    /// The cursor index.
+    pub index: usize,
+    /// The consumer name, empty for an anonymous consumer.
+    pub name: String,
+    /// The process of the consumer, zero if unknown.
+    pub pid: u64,
+    /// Messages consumed (or skipped).
+    pub position: u64,
+    /// Messages published but not yet consumed.
+    pub lag: u64,
+    /// Times the consumer was sped past.
+    pub overruns: u64,
+}
+
+/// The status of a ring.
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct RingStatus {
+    /// The ring name.
+    pub name: String,
+    /// The ring size.
+    pub ring_size: u64,
+    /// Messages published.
+    pub head: u64,
+    /// The largest lag observed.
+    pub max_lag: u64,
+    /// Lag alerts raised.
+    pub lag_alerts: u64,
+    /// Messages dropped, full or timed out.
+    pub dropped: u64,
+    /// Messages that overwrote an unread slot.
+    pub overwritten: u64,
+    /// The attached consumers.
+    pub consumers: Vec<ConsumerStatus>,
+}
+
+impl RingStatus {
+    /// Reads the status of a ring.
+    pub fn from_metrics(ring: &RingMetrics) -> Self {
+        let overflow = &ring.overflow;
+        let consumers = ring
+            .attached()
+            .map(|(index, cursor)| ConsumerStatus {
+                index,
+                name: cursor.name(),
+                pid: cursor.pid.load(Ordering::Relaxed),
+                position: cursor.position.load(Ordering::Acquire),
+                lag: ring.lag(index).unwrap_or_default(),
+                overruns: cursor.overruns.load(Ordering::Relaxed),
+            })
+            .collect();
+        Self {
+            name: ring.name(),
+            ring_size: ring.ring_size.load(Ordering::Acquire),
+            head: ring.head.load(Ordering::Acquire),
+            max_lag: ring.max_lag.load(Ordering::Relaxed),
+            lag_alerts: ring.lag_alerts.load(Ordering::Relaxed),
+            dropped: overflow.dropped_newest.load(Ordering::Relaxed) + overflow.timed_out.load(Ordering::Relaxed),
+            overwritten: overflow.overwritten.load(Ordering::Relaxed),
+            consumers,
+        }
+    }
+}
+
+/// The status of a stream.
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct StreamStatus {
+    /// The stream name.
+    pub name: String,
+    /// Messages received.
+    pub messages: u64,
+    /// The message rate over the last sample interval, in messages per second.
+    pub rate: u64,
+    /// The time the last message was received, in milliseconds since the epoch.
+    pub last_message_ms: u64,
+}
+
+impl StreamStatus {
+    /// Reads the status of a stream.
+    pub fn from_stats(stream: &StreamStats) -> Self {
+        Self {
+            name: stream.name(),
+            messages: stream.messages.load(Ordering::Relaxed),
+            rate: stream.rate.load(Ordering::Relaxed),
+            last_message_ms: stream.last_message_ms.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// The status of the registered rings and streams.
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct MetricsStatus {
+    /// The rings.
+    pub rings: Vec<RingStatus>,
+    /// The streams.
+    pub streams: Vec<StreamStatus>,
+}
+
+impl MetricsStatus {
+    /// Reads the status of the rings and streams of the region.
+    ///
+    /// LATENCY: SLOW_PATH
+    pub fn from_region(region: &MetricsRegion) -> Self {
+        Self {
+            rings: region.registered().map(RingStatus::from_metrics).collect(),
+            streams: region.registered_streams().map(StreamStatus::from_stats).collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use ctl_shm::ShmRegion;
+
+    #[test]
+    fn test_status() {
+        let name = format!("ctl_feed_status_test_{}", std::process::id());
+        let region = ShmRegion::<MetricsRegion>::create(&name).unwrap();
+        let index = region.register_raw_ring("TOP_0_PS", 1024, 128).unwrap();
+        let ring = &region.rings[index];
+        let consumer = ring.attach_named_consumer("md-subscriber", 42).unwrap();
+        for _ in 0..3 {
+            ring.record_publish();
+        }
+        ring.consumers[consumer].advance();
+        region.register_stream("btcusdt@bookTicker", 1_000).unwrap();
+
+        let status = MetricsStatus::from_region(&region);
+        assert_eq!(status.rings.len(), 1);
+        let ring = &status.rings[0];
+        assert_eq!((ring.name.as_str(), ring.ring_size, ring.head), ("TOP_0_PS", 1024, 3));
+        assert_eq!(
+            ring.consumers,
+            vec![ConsumerStatus {
+                index: consumer,
+                name: "md-subscriber".to_string(),
+                pid: 42,
+                position: 1,
+                lag: 2,
+                overruns: 0
+            }]
+        );
+        assert_eq!(status.streams[0].name, "btcusdt@bookTicker");
+        assert_eq!(status.streams[0].last_message_ms, 1_000);
+
+        let json = serde_json::to_value(&status).unwrap();
+        assert_eq!(json["rings"][0]["consumers"][0]["lag"], 2);
+    }
+}
@@ -0,0 +1,264 @@
+//! Per-stream message statistics.
+//!
+//! A ring carries the streams of every symbol of its set, so its counters can't
+//! tell a single dead stream from a quiet market. The parser of each feedgroup
+//! counts its messages per stream in a `StreamStats` entry of the metrics region,
+//! and the market data handler periodically samples the entries into message
+//! rates, from which the busiest and the silent streams are reported.
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU8, AtomicU64, Ordering};
+
+use ctl_shm::ShmRegion;
+use hashbrown::HashMap;
+
+use crate::MetricsRegion;
+
+/// Maximum number of streams tracked in the metrics region.
+pub const MAX_STREAMS: usize = 1024;
+
+/// Maximum length of a stream name in the metrics region.
+pub const STREAM_NAME_SIZE: usize = 48;
+
+/// Time without a message after which a stream is reported silent, in milliseconds.
+pub const SILENT_STREAM_AFTER_MS: u64 = 60_000;
+
+/// Returns the symbol of a stream payload, without parsing the payload.
+///
+/// Every Binance market data payload carries its symbol in the `s` field.
+///
+/// LATENCY: FAST_PATH
+pub fn payload_symbol(data: &[u8]) -> Option<&str> {
+    payload_field(data, b's').and_then(|symbol| std::str::from_utf8(symbol).ok())
+}
+
+/// Returns the string value of a single letter field of a stream payload, e.g.
+/// the `p` price of a trade, without parsing the payload.
+///
+/// LATENCY: FAST_PATH
+pub fn payload_field(data: &[u8], field: u8) -> Option<&[u8]> {
+    let key = [b'"', field, b'"', b':', b'"'];
+    let start = data.windows(key.len()).position(|window| window == key)? + key.len();
+    let len = data[start..].iter().position(|&b| b == b'"')?;
+    Some(&data[start..start + len])
+}
+
+/// The message statistics of a single stream.
+#[repr(C)]
+#[derive(Debug, Default)]
+pub struct StreamStats {
+    /// Zero when free, one while being registered, two once registered.
+    state: AtomicU64,
+    /// The stream name, zero padded.
+    name: [AtomicU8; STREAM_NAME_SIZE],
+    /// Number of messages received on the stream.
+    pub messages: AtomicU64,
+    /// The time the last message was received, in milliseconds since the epoch.
+    pub last_message_ms: AtomicU64,
+    /// The message count at the last sample.
+    pub sampled_messages: AtomicU64,
+    /// The message rate over the last sample interval, in messages per second.
+    pub rate: AtomicU64,
+}
+
+impl StreamStats {
+    /// Returns true if the entry is registered.
+    pub fn is_registered(&self) -> bool {
+        self.state.load(Ordering::Acquire) == 2
+    }
+
+    /// Returns the stream name.
+    pub fn name(&self) -> String {
+        let bytes: Vec<u8> = self.name
+            .iter()
+            .map(|b| b.load(Ordering::Relaxed))
+            .take_while(|&b| b != 0)
+            .collect();
+        String::from_utf8_lossy(&bytes).into_owned()
+    }
+
+    /// Returns true if the entry is registered under `name`.
+    pub(crate) fn has_name(&self, name: &str) -> bool {
+        self.is_registered() && self.name() == name
+    }
+
+    /// Claims the entry for `name`, returning false if it's taken.
+    pub(crate) fn claim(&self, name: &str, now_ms: u64) -> bool {
+        if self.state.compare_exchange(0, 1, Ordering::AcqRel, Ordering::Relaxed).is_err() {
+            return false;
+        }
+        for (slot, byte) in self.name.iter().zip(name.bytes()) {
+            slot.store(byte, Ordering::Relaxed);
+        }
+        // A stream never receiving a message turns silent from its registration
+        self.last_message_ms.store(now_ms, Ordering::Relaxed);
+        self.state.store(2, Ordering::Release);
+        true
+    }
+
+    /// Records a message received at `now_ms`.
+    ///
+    /// LATENCY: FAST_PATH
+    pub fn record(&self, now_ms: u64) {
+        self.messages.fetch_add(1, Ordering::Relaxed);
+        self.last_message_ms.store(now_ms, Ordering::Relaxed);
+    }
+
+    /// Samples the message rate over the `elapsed_ms` since the last sample,
+    /// returning it in messages per second. Only called by a single sampler.
+    ///
+    /// LATENCY: SLOW_PATH
+    pub fn sample(&self, elapsed_ms: u64) -> u64 {
+        let messages = self.messages.load(Ordering::Relaxed);
+        let delta = messages.saturating_sub(self.sampled_messages.swap(messages, Ordering::Relaxed));
+        let rate = delta * 1_000 / elapsed_ms.max(1);
+        self.rate.store(rate, Ordering::Relaxed);
+        rate
+    }
+
+    /// Returns the last sampled rate of the stream.
+    pub fn rate(&self) -> StreamRate {
+        StreamRate {
+            stream: self.name(),
+            rate: self.rate.load(Ordering::Relaxed),
+            last_message_ms: self.last_message_ms.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Returns true if no message was received for `silent_after_ms` at `now_ms`.
+    pub fn is_silent(&self, now_ms: u64, silent_after_ms: u64) -> bool {
+        now_ms.saturating_sub(self.last_message_ms.load(Ordering::Relaxed)) >= silent_after_ms
+    }
+}
+
+/// The sampled message rate of a stream.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StreamRate {
+    /// The stream name.
+    pub stream: String,
+    /// The message rate, in messages per second.
+    pub rate: u64,
+    /// The time the last message was received, in milliseconds since the epoch.
+    pub last_message_ms: u64,
+}
+
+/// The busiest and silent streams of a sample.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StreamReport {
+    /// The busiest streams, busiest first.
+    pub busiest: Vec<StreamRate>,
+    /// The streams without a message for the silence threshold.
+    pub silent: Vec<StreamRate>,
+}
+
+impl StreamReport {
+    /// Builds the report of the registered streams, keeping the `top` busiest.
+    pub fn from_streams<'a>(
+        streams: impl Iterator<Item = &'a StreamStats>,
+        top: usize,
+        now_ms: u64,
+        silent_after_ms: u64,
+    ) -> Self {
+        let streams: Vec<&StreamStats> = streams.collect();
+        let silent = streams.iter().filter(|stats| stats.is_silent(now_ms, silent_after_ms)).map(|stats| stats.rate());
+        Self { silent: silent.collect(), busiest: Self::busiest(streams.into_iter(), top) }
+    }
+
+    /// Returns the `top` busiest of the registered streams, busiest first.
+    pub fn busiest<'a>(streams: impl Iterator<Item = &'a StreamStats>, top: usize) -> Vec<StreamRate> {
+        let mut busiest: Vec<StreamRate> = streams.map(StreamStats::rate).collect();
+        busiest.sort_by(|a, b| b.rate.cmp(&a.rate).then_with(|| a.stream.cmp(&b.stream)));
+        busiest.truncate(top);
+        busiest
+    }
+}
+
+/// A handle recording the messages of a feedgroup per stream.
+#[derive(Clone)]
+pub struct StreamStatsHandle {
+    /// The metrics region.
+    region: Arc<ShmRegion<MetricsRegion>>,
+    /// The stream entry indices, by exchange symbol name.
+    symbols: HashMap<String, usize>,
+}
+
+impl StreamStatsHandle {
+    /// Registers the streams of a feedgroup, given by symbol and stream name.
+    /// Returns `None` if a stream name is too long or the region is full.
+    pub fn register(region: Arc<ShmRegion<MetricsRegion>>, streams: &[(String, String)], now_ms: u64) -> Option<Self> {
+        let mut symbols = HashMap::new();
+        for (symbol, stream) in streams {
+            symbols.insert(symbol.clone(), region.register_stream(stream, now_ms)?);
+        }
+        Some(Self { region, symbols })
+    }
+
+    /// Records a message received at `now_ms`. Payloads of an unknown symbol are ignored.
+    ///
+    /// LATENCY: FAST_PATH
+    pub fn record(&self, data: &[u8], now_ms: u64) {
+        let Some(&index) = payload_symbol(data).and_then(|symbol| self.symbols.get(symbol)) else {
+            return;
+        };
+        self.region.streams[index].record(now_ms);
+    }
+}
+
+impl std::fmt::Debug for StreamStatsHandle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("StreamStatsHandle")
+            .field("symbols", &self.symbols)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stream(name: &str) -> StreamStats {
+        let stats = StreamStats::default();
+        assert!(stats.claim(name, 0));
+        stats
+    }
+
+    #[test]
+    fn test_payload_symbol() {
+        assert_eq!(payload_symbol(br#"{"u":400900217,"s":"BNBUSDT","b":"25.35"}"#), Some("BNBUSDT"));
+        assert_eq!(payload_symbol(br#"{"e":"trade","E":1,"s":"BTCUSDT","t":2}"#), Some("BTCUSDT"));
+        assert_eq!(payload_symbol(br#"{"result":null,"id":1}"#), None);
+        assert_eq!(payload_field(br#"{"e":"trade","p":"0.001","q":"100"}"#, b'q'), Some(&b"100"[..]));
+        assert_eq!(payload_field(br#"{"u":1,"b":"25.35","B":"31.21"}"#, b'B'), Some(&b"31.21"[..]));
+    }
+
+    #[test]
+    fn test_sample_rate() {
+        let stats = stream("btcusdt@trade");
+        assert!(stats.has_name("btcusdt@trade"));
+        assert!(!stats.claim("ethusdt@trade", 0));
+        for _ in 0..50 {
+            stats.record(1_000);
+        }
+        assert_eq!(stats.sample(500), 100);
+        assert_eq!(stats.sample(500), 0);
+        assert!(!stats.is_silent(1_500, 1_000));
+        assert!(stats.is_silent(2_000, 1_000));
+    }
+
+    #[test]
+    fn test_report() {
+        let streams = [stream("a@trade"), stream("b@trade"), stream("c@trade")];
+        streams[0].rate.store(10, Ordering::Relaxed);
+        streams[1].rate.store(30, Ordering::Relaxed);
+        streams[0].last_message_ms.store(5_000, Ordering::Relaxed);
+        streams[1].last_message_ms.store(5_000, Ordering::Relaxed);
+
+        let report = StreamReport::from_streams(streams.iter(), 2, 5_000, 1_000);
+        let busiest: Vec<_> = report.busiest.iter().map(|rate| rate.stream.as_str()).collect();
+        assert_eq!(busiest, vec!["b@trade", "a@trade"]);
+        // c never received a message since its registration
+        assert_eq!(report.silent.len(), 1);
+        assert_eq!(report.silent[0].stream, "c@trade");
+        assert_eq!(StreamReport::busiest(streams.iter(), 2), report.busiest);
+    }
+}
@@ -0,0 +1,405 @@
+//! Encoding and decoding of the FIX 4.4 messages.
+//!
+//! A message is a sequence of `tag=value` fields separated by SOH, framed by
+//! the `BeginString` (8) and `BodyLength` (9) fields in front and the
+//! `CheckSum` (10) field behind. The decoded messages borrow the receive
+//! buffer, their fields being scanned on access.
+//! https://github.com/binance/binance-spot-api-docs/blob/master/fix-api.md
+
+use std::fmt::Display;
+use std::io::Write;
+
+use crate::FixConnectorError;
+
+/// The field separator.
+pub const SOH: u8 = 0x01;
+
+/// The `BeginString` of the messages.
+pub const BEGIN_STRING: &str = "FIX.4.4";
+
+/// The length of the `CheckSum` trailer, `10=nnn` and its separator.
+const TRAILER_LEN: usize = 7;
+
+/// The tags of the fields used by the market data sessions.
+pub mod tag {
+    pub const BEGIN_STRING: u32 = 8;
+    pub const BODY_LENGTH: u32 = 9;
+    pub const CHECKSUM: u32 = 10;
+    pub const MSG_SEQ_NUM: u32 = 34;
+    pub const MSG_TYPE: u32 = 35;
+    pub const SENDER_COMP_ID: u32 = 49;
+    pub const SENDING_TIME: u32 = 52;
+    pub const SYMBOL: u32 = 55;
+    pub const TARGET_COMP_ID: u32 = 56;
+    pub const TEXT: u32 = 58;
+    pub const TRANSACT_TIME: u32 = 60;
+    pub const RAW_DATA_LENGTH: u32 = 95;
+    pub const RAW_DATA: u32 = 96;
+    pub const ENCRYPT_METHOD: u32 = 98;
+    pub const HEART_BT_INT: u32 = 108;
+    pub const TEST_REQ_ID: u32 = 112;
+    pub const RESET_SEQ_NUM_FLAG: u32 = 141;
+    pub const NO_RELATED_SYM: u32 = 146;
+    pub const MD_REQ_ID: u32 = 262;
+    pub const SUBSCRIPTION_REQUEST_TYPE: u32 = 263;
+    pub const MARKET_DEPTH: u32 = 264;
+    pub const AGGREGATED_BOOK: u32 = 266;
+    pub const NO_MD_ENTRY_TYPES: u32 = 267;
+    pub const NO_MD_ENTRIES: u32 = 268;
+    pub const MD_ENTRY_TYPE: u32 = 269;
+    pub const MD_ENTRY_PX: u32 = 270;
+    pub const MD_ENTRY_SIZE: u32 = 271;
+    pub const MD_UPDATE_ACTION: u32 = 279;
+    pub const MD_REQ_REJ_REASON: u32 = 281;
+    pub const USERNAME: u32 = 553;
+    pub const TRADE_ID: u32 = 1003;
+    pub const AGGRESSOR_SIDE: u32 = 2446;
+    pub const LAST_BOOK_UPDATE_ID: u32 = 25044;
+    pub const MESSAGE_HANDLING: u32 = 25035;
+}
+
+/// The message types (`MsgType`, 35) used by the market data sessions.
+pub mod msg_type {
+    pub const HEARTBEAT: &str = "0";
+    pub const TEST_REQUEST: &str = "1";
+    pub const REJECT: &str = "3";
+    pub const LOGOUT: &str = "5";
+    pub const LOGON: &str = "A";
+    pub const NEWS: &str = "B";
+    pub const MARKET_DATA_REQUEST: &str = "V";
+    pub const MARKET_DATA_SNAPSHOT: &str = "W";
+    pub const MARKET_DATA_INCREMENTAL_REFRESH: &str = "X";
+    pub const MARKET_DATA_REQUEST_REJECT: &str = "Y";
+}
+
+/// The header fields of an outgoing message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FixHeader<'a> {
+    /// The `SenderCompID` of the session.
+    pub sender_comp_id: &'a str,
+    /// The `TargetCompID` of the session.
+    pub target_comp_id: &'a str,
+    /// The `MsgSeqNum` of the message.
+    pub seq: u64,
+    /// The `SendingTime` of the message, as a FIX UTC timestamp.
+    pub sending_time: &'a str,
+}
+
+/// Appends a `tag=value` field to a buffer.
+pub fn write_field(buf: &mut Vec<u8>, tag: u32, value: impl Display) {
+    let _ = write!(buf, "{}={}", tag, value);
+    buf.push(SOH);
+}
+
+/// Appends a `tag=value` field of raw bytes to a buffer.
+pub fn write_raw_field(buf: &mut Vec<u8>, tag: u32, value: &[u8]) {
+    let _ = write!(buf, "{}=", tag);
+    buf.extend_from_slice(value);
+    buf.push(SOH);
+}
+
+/// Returns the FIX checksum of bytes, their sum modulo 256.
+pub fn checksum(bytes: &[u8]) -> u8 {
+    bytes.iter().fold(0u8, |sum, &b| sum.wrapping_add(b))
+}
+
+/// Encodes a message of `msg_type` with its body `fields`, framed and checksummed.
+///
+/// LATENCY: SLOW_PATH
+pub fn encode(msg_type: &str, fields: &[u8], header: &FixHeader<'_>) -> Vec<u8> {
+    let mut body = Vec::with_capacity(fields.len() + 64);
+    write_field(&mut body, tag::MSG_TYPE, msg_type);
+    write_field(&mut body, tag::SENDER_COMP_ID, header.sender_comp_id);
+    write_field(&mut body, tag::TARGET_COMP_ID, header.target_comp_id);
+    write_field(&mut body, tag::MSG_SEQ_NUM, header.seq);
+    write_field(&mut body, tag::SENDING_TIME, header.sending_time);
+    body.extend_from_slice(fields);
+
+    let mut message = Vec::with_capacity(body.len() + 32);
+    write_field(&mut message, tag::BEGIN_STRING, BEGIN_STRING);
+    write_field(&mut message, tag::BODY_LENGTH, body.len());
+    message.extend_from_slice(&body);
+    let sum = checksum(&message);
+    write_field(&mut message, tag::CHECKSUM, format_args!("{:03}", sum));
+    message
+}
+
+/// Parses the decimal digits of a field value.
+pub fn parse_uint(value: &[u8]) -> Option<u64> {
+    if value.is_empty() || value.len() > 19 {
+        return None;
+    }
+    value.iter().try_fold(0u64, |n, &b| b.is_ascii_digit().then(|| n * 10 + (b - b'0') as u64))
+}
+
+/// Returns the length of the first message of `buf`, `None` while incomplete.
+///
+/// # Errors
+/// Fails on a malformed frame or a checksum mismatch, the stream being out of sync.
+///
+/// LATENCY: FAST_PATH
+pub fn frame_len(buf: &[u8]) -> Result<Option<usize>, FixConnectorError> {
+    const PREFIX: &[u8] = b"8=FIX.4.4\x019=";
+    if buf.len() < PREFIX.len() {
+        return if PREFIX.starts_with(buf) { Ok(None) } else { Err(FixConnectorError::Malformed("begin string")) };
+    }
+    if !buf.starts_with(PREFIX) {
+        return Err(FixConnectorError::Malformed("begin string"));
+    }
+
+    let rest = &buf[PREFIX.len()..];
+    let Some(end) = rest.iter().position(|&b| b == SOH) else {
+        return if rest.len() > 19 { Err(FixConnectorError::Malformed("body length")) } else { Ok(None) };
+    };
+    let body_len = parse_uint(&rest[..end]).ok_or(FixConnectorError::Malformed("body length"))? as usize;
+    let body_end = PREFIX.len() + end + 1 + body_len;
+    let total = body_end + TRAILER_LEN;
+    if buf.len() < total {
+        return Ok(None);
+    }
+
+    let trailer = &buf[body_end..total];
+    if !trailer.starts_with(b"10=") || trailer[TRAILER_LEN - 1] != SOH {
+        return Err(FixConnectorError::Malformed("checksum field"));
+    }
+    let expected = parse_uint(&trailer[3..6]).ok_or(FixConnectorError::Malformed("checksum field"))?;
+    if checksum(&buf[..body_end]) as u64 != expected {
+        return Err(FixConnectorError::Checksum);
+    }
+    Ok(Some(total))
+}
+
+/// Returns the field at `pos` of a message and the position of the next one.
+fn field_at(bytes: &[u8], pos: usize) -> Option<(u32, &[u8], usize)> {
+    let rest = bytes.get(pos..)?;
+    let eq = rest.iter().position(|&b| b == b'=')?;
+    let tag = parse_uint(&rest[..eq])? as u32;
+    let value_start = eq + 1;
+    let len = rest[value_start..].iter().position(|&b| b == SOH).unwrap_or(rest.len() - value_start);
+    let value = &rest[value_start..value_start + len];
+    Some((tag, value, pos + value_start + len + 1))
+}
+
+/// The fields of a message, in order. A malformed field ends the iteration.
+#[derive(Debug, Clone, Copy)]
+pub struct FixFields<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Iterator for FixFields<'a> {
+    type Item = (u32, &'a [u8]);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (tag, value, next) = field_at(self.bytes, self.pos)?;
+        self.pos = next;
+        Some((tag, value))
+    }
+}
+
+/// The entries of the repeating group of a message, each a message of its fields.
+///
+/// An entry starts at the delimiter field, the first field of the group.
+#[derive(Debug, Clone, Copy)]
+pub struct FixEntries<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+    delimiter: u32,
+}
+
+impl<'a> Iterator for FixEntries<'a> {
+    type Item = FixMessage<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let start = self.pos;
+        let (_, _, mut end) = field_at(self.bytes, start)?;
+        while let Some((tag, _, next)) = field_at(self.bytes, end) {
+            if tag == self.delimiter {
+                break;
+            }
+            end = next;
+        }
+        self.pos = end;
+        Some(FixMessage::new(&self.bytes[start..end.min(self.bytes.len())]))
+    }
+}
+
+/// A decoded message, or a part of one, borrowing its bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FixMessage<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> FixMessage<'a> {
+    /// Wraps the bytes of a message.
+    pub fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes }
+    }
+
+    /// Returns the bytes of the message.
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.bytes
+    }
+
+    /// Returns the fields of the message, in order.
+    pub fn fields(&self) -> FixFields<'a> {
+        FixFields { bytes: self.bytes, pos: 0 }
+    }
+
+    /// Returns the value of the first field of `tag`.
+    ///
+    /// LATENCY: FAST_PATH
+    pub fn get(&self, tag: u32) -> Option<&'a [u8]> {
+        self.fields().find(|&(t, _)| t == tag).map(|(_, value)| value)
+    }
+
+    /// Returns the value of the first field of `tag`, as a string.
+    pub fn get_str(&self, tag: u32) -> Option<&'a str> {
+        self.get(tag).and_then(|value| std::str::from_utf8(value).ok())
+    }
+
+    /// Returns the value of the first field of `tag`, as an unsigned integer.
+    pub fn get_u64(&self, tag: u32) -> Option<u64> {
+        self.get(tag).and_then(parse_uint)
+    }
+
+    /// Returns the `MsgType` of the message.
+    pub fn msg_type(&self) -> Option<&'a str> {
+        self.get_str(tag::MSG_TYPE)
+    }
+
+    /// Returns the entries of the repeating group counted by the `count_tag` field,
+    /// e.g. `NoMDEntries` (268). No entries if the message has no such group.
+    pub fn entries(&self, count_tag: u32) -> FixEntries<'a> {
+        let mut pos = 0;
+        while let Some((tag, _, next)) = field_at(self.bytes, pos) {
+            pos = next;
+            if tag == count_tag {
+                let delimiter = field_at(self.bytes, pos).map_or(0, |(tag, _, _)| tag);
+                return FixEntries { bytes: self.bytes, pos, delimiter };
+            }
+        }
+        FixEntries { bytes: self.bytes, pos: self.bytes.len(), delimiter: 0 }
+    }
+}
+
+/// Returns the days since the epoch of a proleptic Gregorian date.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year.rem_euclid(400);
+    let doy = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+/// Returns the proleptic Gregorian date of the days since the epoch.
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let doe = days.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    (yoe + era * 400 + i64::from(month <= 2), month, day)
+}
+
+/// Formats a time, in milliseconds since the epoch, as a FIX UTC timestamp
+/// (`YYYYMMDD-HH:MM:SS.sss`).
+pub fn utc_timestamp(ms: u64) -> String {
+    let secs = (ms / 1_000) as i64;
+    let (year, month, day) = civil_from_days(secs.div_euclid(86_400));
+    let secs_of_day = secs.rem_euclid(86_400);
+    format!(
+        "{:04}{:02}{:02}-{:02}:{:02}:{:02}.{:03}",
+        year,
+        month,
+        day,
+        secs_of_day / 3_600,
+        secs_of_day / 60 % 60,
+        secs_of_day % 60,
+        ms % 1_000
+    )
+}
+
+/// Parses a FIX UTC timestamp, with or without its fractional seconds, into
+/// milliseconds since the epoch.
+pub fn parse_utc_timestamp(value: &[u8]) -> Option<u64> {
+    if value.len() < 17 || value[8] != b'-' || value[11] != b':' || value[14] != b':' {
+        return None;
+    }
+    let number = |range: std::ops::Range<usize>| parse_uint(&value[range]).map(|n| n as i64);
+    let days = days_from_civil(number(0..4)?, number(4..6)?, number(6..8)?);
+    let secs = days * 86_400 + number(9..11)? * 3_600 + number(12..14)? * 60 + number(15..17)?;
+    // Milliseconds from the first three fractional digits, if any
+    let millis = match value.get(17) {
+        None => 0,
+        Some(b'.') => {
+            let digits = &value[18..value.len().min(21)];
+            parse_uint(digits)? as i64 * 10i64.pow(3 - digits.len() as u32)
+        }
+        Some(_) => return None,
+    };
+    u64::try_from(secs * 1_000 + millis).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header(seq: u64) -> FixHeader<'static> {
+        FixHeader { sender_comp_id: "CTLMD00", target_comp_id: "SPOT", seq, sending_time: "20240101-00:00:00.000" }
+    }
+
+    #[test]
+    fn test_encode_frame() {
+        let mut fields = Vec::new();
+        write_field(&mut fields, tag::TEST_REQ_ID, "ping");
+        let message = encode(msg_type::TEST_REQUEST, &fields, &header(7));
+        assert!(message.starts_with(b"8=FIX.4.4\x019="));
+
+        // A message is framed once complete, its trailing bytes left out
+        let mut buf = message.clone();
+        buf.extend_from_slice(b"8=FIX");
+        assert_eq!(frame_len(&buf).unwrap(), Some(message.len()));
+        assert_eq!(frame_len(&message[..message.len() - 1]).unwrap(), None);
+        assert_eq!(frame_len(b"8=FI").unwrap(), None);
+        assert!(frame_len(b"9=12\x01").is_err());
+
+        let decoded = FixMessage::new(&message);
+        assert_eq!(decoded.msg_type(), Some(msg_type::TEST_REQUEST));
+        assert_eq!(decoded.get_u64(tag::MSG_SEQ_NUM), Some(7));
+        assert_eq!(decoded.get_str(tag::TEST_REQ_ID), Some("ping"));
+
+        // A corrupted byte fails the checksum
+        let mut corrupted = message.clone();
+        let index = corrupted.len() - 10;
+        corrupted[index] ^= 1;
+        assert!(matches!(frame_len(&corrupted), Err(FixConnectorError::Checksum)));
+    }
+
+    #[test]
+    fn test_entries() {
+        let message = FixMessage::new(b"35=X\x01262=TRADE_BTCUSDT\x01268=2\x01279=0\x01269=2\x0155=BTCUSDT\x01270=1.5\x01279=0\x01269=2\x01270=1.6\x01");
+        let prices: Vec<&str> = message.entries(tag::NO_MD_ENTRIES).filter_map(|entry| entry.get_str(tag::MD_ENTRY_PX)).collect();
+        assert_eq!(prices, ["1.5", "1.6"]);
+        let symbols: Vec<Option<&str>> = message.entries(tag::NO_MD_ENTRIES).map(|entry| entry.get_str(tag::SYMBOL)).collect();
+        assert_eq!(symbols, [Some("BTCUSDT"), None]);
+        assert_eq!(message.entries(tag::NO_MD_ENTRY_TYPES).count(), 0);
+    }
+
+    #[test]
+    fn test_utc_timestamp() {
+        assert_eq!(utc_timestamp(0), "19700101-00:00:00.000");
+        assert_eq!(utc_timestamp(1_700_000_000_123), "20231114-22:13:20.123");
+        assert_eq!(utc_timestamp(951_782_400_000), "20000229-00:00:00.000");
+
+        assert_eq!(parse_utc_timestamp(b"20231114-22:13:20.123"), Some(1_700_000_000_123));
+        assert_eq!(parse_utc_timestamp(b"20231114-22:13:20.123456"), Some(1_700_000_000_123));
+        assert_eq!(parse_utc_timestamp(b"20231114-22:13:20.1"), Some(1_700_000_000_100));
+        assert_eq!(parse_utc_timestamp(b"20231114-22:13:20"), Some(1_700_000_000_000));
+        assert_eq!(parse_utc_timestamp(b"2023-11-14T22:13:20"), None);
+    }
+}
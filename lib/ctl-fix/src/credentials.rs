@@ -0,0 +1,91 @@
+//! Signing of the FIX logons with an Ed25519 API key.
+//! https://github.com/binance/binance-spot-api-docs/blob/master/fix-api.md#signaturecomputation
+
+use std::env;
+use std::fmt;
+use std::fs;
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use ed25519_dalek::pkcs8::DecodePrivateKey;
+use ed25519_dalek::{Signer, SigningKey};
+
+use crate::{FixConnectorError, FixHeader, SOH, msg_type};
+
+/// Environment variable holding the Ed25519 API key of the FIX sessions.
+pub const FIX_API_KEY_ENV: &str = "BINANCE_FIX_API_KEY";
+
+/// Environment variable holding the path of the PKCS#8 PEM private key of the API key.
+pub const FIX_PRIVATE_KEY_ENV: &str = "BINANCE_FIX_PRIVATE_KEY";
+
+/// An Ed25519 API key.
+#[derive(Clone)]
+pub struct FixCredentials {
+    /// The API key, sent in the `Username` field of the logon.
+    pub api_key: String,
+    /// The private key the logons are signed with.
+    signing_key: SigningKey,
+}
+
+impl fmt::Debug for FixCredentials {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FixCredentials")
+            .field("api_key", &self.api_key)
+            .field("signing_key", &"<redacted>")
+            .finish()
+    }
+}
+
+impl FixCredentials {
+    /// Creates credentials from an API key and its private key.
+    pub fn new(api_key: impl Into<String>, signing_key: SigningKey) -> Self {
+        Self { api_key: api_key.into(), signing_key }
+    }
+
+    /// Reads the credentials from the `BINANCE_FIX_API_KEY` variable and the
+    /// private key file at `BINANCE_FIX_PRIVATE_KEY`.
+    pub fn from_env() -> Result<Self, FixConnectorError> {
+        let var = |name: &'static str| env::var(name).map_err(|_| FixConnectorError::MissingCredentials(name));
+        let api_key = var(FIX_API_KEY_ENV)?;
+        let pem = fs::read_to_string(var(FIX_PRIVATE_KEY_ENV)?)?;
+        let signing_key = SigningKey::from_pkcs8_pem(&pem).map_err(|e| FixConnectorError::InvalidKey(e.to_string()))?;
+        Ok(Self::new(api_key, signing_key))
+    }
+
+    /// Returns the base64-encoded signature of the logon sent with `header`.
+    ///
+    /// The signed payload is the `MsgType`, `SenderCompID`, `TargetCompID`,
+    /// `MsgSeqNum` and `SendingTime` of the logon, joined by SOH.
+    pub fn sign_logon(&self, header: &FixHeader<'_>) -> String {
+        let sep = char::from(SOH);
+        let payload = format!(
+            "{}{sep}{}{sep}{}{sep}{}{sep}{}",
+            msg_type::LOGON,
+            header.sender_comp_id,
+            header.target_comp_id,
+            header.seq,
+            header.sending_time
+        );
+        STANDARD.encode(self.signing_key.sign(payload.as_bytes()).to_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use ed25519_dalek::{Signature, Verifier};
+
+    #[test]
+    fn test_sign_logon() {
+        let credentials = FixCredentials::new("key", SigningKey::from_bytes(&[7; 32]));
+        let header = FixHeader { sender_comp_id: "CTLMD00", target_comp_id: "SPOT", seq: 1, sending_time: "20240101-00:00:00.000" };
+        let signature = STANDARD.decode(credentials.sign_logon(&header)).unwrap();
+        let signature = Signature::from_slice(&signature).unwrap();
+
+        let payload = b"A\x01CTLMD00\x01SPOT\x011\x0120240101-00:00:00.000";
+        let verifying_key = credentials.signing_key.verifying_key();
+        assert!(verifying_key.verify(payload, &signature).is_ok());
+        assert!(!format!("{:?}", credentials).contains("7"));
+    }
+}
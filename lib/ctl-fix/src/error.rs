@@ -0,0 +1,49 @@
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum FixConnectorError {
+    #[error("fix connector error: io error {0}")]
+    IoError(#[from] std::io::Error),
+    #[error("fix connector error: tls error {0}")]
+    TlsError(#[from] native_tls::Error),
+    #[error("fix connector error: tls handshake with {0} failed")]
+    Handshake(String),
+    #[error("fix connector error: invalid endpoint '{0}', expected tcp://host:port or tls://host:port")]
+    InvalidEndpoint(String),
+    #[error("fix connector error: malformed message, {0}")]
+    Malformed(&'static str),
+    #[error("fix connector error: checksum mismatch")]
+    Checksum,
+    #[error("fix connector error: logon rejected: {0}")]
+    LogonRejected(String),
+    #[error("fix connector error: no logon response within {0:?}")]
+    LogonTimeout(std::time::Duration),
+    #[error("fix connector error: disconnected by the server")]
+    Disconnected,
+    #[error("fix connector error: missing credentials, {0} not set")]
+    MissingCredentials(&'static str),
+    #[error("fix connector error: invalid private key {0}")]
+    InvalidKey(String),
+}
+
+impl FixConnectorError {
+    /// Returns true if the session was refused for its credentials: missing,
+    /// an invalid private key, or a rejected logon. Reconnecting won't help
+    /// until they are fixed.
+    pub fn is_auth_failure(&self) -> bool {
+        matches!(self, Self::MissingCredentials(_) | Self::InvalidKey(_) | Self::LogonRejected(_))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_auth_failures() {
+        assert!(FixConnectorError::MissingCredentials("BINANCE_FIX_API_KEY").is_auth_failure());
+        assert!(FixConnectorError::LogonRejected("Invalid API key".to_string()).is_auth_failure());
+        assert!(!FixConnectorError::Disconnected.is_auth_failure());
+        assert!(!FixConnectorError::Handshake("fix-md.binance.com".to_string()).is_auth_failure());
+    }
+}
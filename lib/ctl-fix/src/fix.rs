@@ -0,0 +1,579 @@
+use std::collections::VecDeque;
+use std::io::{ErrorKind, Read, Write};
+use std::sync::mpsc::Sender;
+use std::time::{Duration, Instant};
+
+use atx_feed::{FeedData, FeedPoll, FeedProtocolOps, Stream, Streams};
+use ctl_websocket::{EndpointRotation, EndpointSwitch, FailoverPolicy, FailoverTrigger, SwitchReason};
+use hashbrown::HashMap;
+
+use crate::transport::Transport;
+use crate::{
+    encode, frame_len, msg_type, split_entries, tag, utc_timestamp, write_field, FixConnectorError, FixCredentials,
+    FixHeader, FixMessage, MarketDataEntries,
+};
+
+/// The `TargetCompID` of the Binance Spot FIX sessions.
+pub const BINANCE_TARGET_COMP_ID: &str = "SPOT";
+
+/// Default heartbeat interval of the sessions.
+const DEFAULT_HEARTBEAT_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Bound on each step of the connection and the wait for the logon response.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The `MessageHandling` of the logon: the messages are processed in order.
+const SEQUENTIAL_MESSAGE_HANDLING: u32 = 2;
+
+/// The `SubscriptionRequestType` of the market data requests.
+const SUBSCRIBE: u32 = 1;
+const UNSUBSCRIBE: u32 = 2;
+
+/// The identity and timers of a FIX session.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FixSession {
+    /// The `SenderCompID`, unique among the sessions of the API key.
+    pub sender_comp_id: String,
+    /// The `TargetCompID`.
+    pub target_comp_id: String,
+    /// The interval of the heartbeats of both sides.
+    pub heartbeat_interval: Duration,
+}
+
+impl FixSession {
+    /// Creates a session of `sender_comp_id` with Binance Spot.
+    pub fn new(sender_comp_id: &str) -> Self {
+        Self {
+            sender_comp_id: sender_comp_id.to_string(),
+            target_comp_id: BINANCE_TARGET_COMP_ID.to_string(),
+            heartbeat_interval: DEFAULT_HEARTBEAT_INTERVAL,
+        }
+    }
+
+    /// Encodes a message of the session at sequence number `seq`.
+    fn encode(&self, msg_type: &str, fields: &[u8], seq: u64) -> Vec<u8> {
+        let sending_time = utc_timestamp(ctl_time::now_ms());
+        let header = FixHeader {
+            sender_comp_id: &self.sender_comp_id,
+            target_comp_id: &self.target_comp_id,
+            seq,
+            sending_time: &sending_time,
+        };
+        encode(msg_type, fields, &header)
+    }
+}
+
+/// A market data request rejected by the server, or a message it rejected.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FixReject {
+    /// The `MDReqID` of the rejected request, empty for a session level reject.
+    pub md_req_id: String,
+    /// The reason given by the server.
+    pub reason: String,
+}
+
+/// The exchange FIX market data connector.
+/// This provides all the necessary methods to connect to the exchange FIX market data sessions.
+pub struct FixConn<K: MarketDataEntries> {
+    /// The stream of the session, logged on.
+    transport: Transport,
+    /// The identity and timers of the session.
+    session: FixSession,
+    /// The API key the logons are signed with.
+    credentials: FixCredentials,
+    /// The next outgoing sequence number.
+    next_seq: u64,
+    /// The streams being subscribed to.
+    streams: Streams<K>,
+    /// The fields of the market data requests of the subscribed streams, by `MDReqID`,
+    /// sent again on endpoint switches.
+    subscriptions: HashMap<String, Vec<u8>>,
+    /// Buffer of the received bytes, holding the incomplete messages.
+    recv_buffer: Vec<u8>,
+    /// The events split from the received market data messages, not yet polled.
+    events: VecDeque<Vec<u8>>,
+    /// The event returned by the last poll.
+    data: Vec<u8>,
+    /// The rejects received, not yet taken by the caller.
+    rejects: VecDeque<FixReject>,
+    /// The endpoints of the connection and the health of the active one.
+    rotation: EndpointRotation,
+    /// The feed name and channel endpoint switches are reported to.
+    failover_reporter: Option<(String, Sender<EndpointSwitch>)>,
+    /// The time the last message was sent.
+    last_sent: Instant,
+    /// The time the last message was received.
+    last_received: Instant,
+    /// Set once a test request awaits its heartbeat.
+    test_request_sent: bool,
+}
+
+impl<K: MarketDataEntries> FixConn<K> {
+    /// Creates a new FixConn instance logged on to `endpoint`.
+    pub fn new(endpoint: &str, session: FixSession, credentials: FixCredentials) -> Result<Self, FixConnectorError> {
+        Self::with_endpoints(vec![endpoint.to_string()], FailoverPolicy::default(), session, credentials)
+    }
+
+    /// Creates a new FixConn instance failing over between `endpoints`, primary first.
+    ///
+    /// Logs on to the first endpoint accepting the logon.
+    ///
+    /// # Panics
+    /// Panics if `endpoints` is empty.
+    pub fn with_endpoints(
+        endpoints: Vec<String>,
+        policy: FailoverPolicy,
+        session: FixSession,
+        credentials: FixCredentials,
+    ) -> Result<Self, FixConnectorError> {
+        let mut rotation = EndpointRotation::new(endpoints, policy);
+        let mut attempts = 1;
+        let (transport, recv_buffer) = loop {
+            match Self::logon(rotation.active(), &session, &credentials) {
+                Ok(logged_on) => break logged_on,
+                Err(e) if attempts >= rotation.endpoints().len() => return Err(e),
+                Err(_) => {
+                    rotation.advance(Instant::now());
+                    attempts += 1;
+                }
+            }
+        };
+        let now = Instant::now();
+        Ok(Self {
+            transport,
+            session,
+            credentials,
+            // The logon is the first message of the session
+            next_seq: 2,
+            streams: Streams::new(),
+            subscriptions: HashMap::new(),
+            recv_buffer,
+            events: VecDeque::new(),
+            data: Vec::with_capacity(1024),
+            rejects: VecDeque::new(),
+            rotation,
+            failover_reporter: None,
+            last_sent: now,
+            last_received: now,
+            test_request_sent: false,
+        })
+    }
+
+    /// Connects to `endpoint` and logs on, resetting the sequence numbers.
+    ///
+    /// Returns the stream, switched to non-blocking reads, and the bytes received after the logon.
+    fn logon(
+        endpoint: &str,
+        session: &FixSession,
+        credentials: &FixCredentials,
+    ) -> Result<(Transport, Vec<u8>), FixConnectorError> {
+        let mut transport = Transport::connect(endpoint, CONNECT_TIMEOUT)?;
+
+        let sending_time = utc_timestamp(ctl_time::now_ms());
+        let header = FixHeader {
+            sender_comp_id: &session.sender_comp_id,
+            target_comp_id: &session.target_comp_id,
+            seq: 1,
+            sending_time: &sending_time,
+        };
+        let signature = credentials.sign_logon(&header);
+        let mut fields = Vec::new();
+        write_field(&mut fields, tag::ENCRYPT_METHOD, 0);
+        write_field(&mut fields, tag::HEART_BT_INT, session.heartbeat_interval.as_secs());
+        write_field(&mut fields, tag::RAW_DATA_LENGTH, signature.len());
+        write_field(&mut fields, tag::RAW_DATA, &signature);
+        write_field(&mut fields, tag::RESET_SEQ_NUM_FLAG, "Y");
+        write_field(&mut fields, tag::USERNAME, &credentials.api_key);
+        write_field(&mut fields, tag::MESSAGE_HANDLING, SEQUENTIAL_MESSAGE_HANDLING);
+        transport.write_all(&encode(msg_type::LOGON, &fields, &header))?;
+
+        // Wait for the logon response, the stream blocking for at most the timeout per read
+        let start = Instant::now();
+        let mut buffer = Vec::with_capacity(4096);
+        let mut chunk = [0u8; 4096];
+        loop {
+            while let Some(len) = frame_len(&buffer)? {
+                let message = FixMessage::new(&buffer[..len]);
+                match message.msg_type() {
+                    Some(msg_type::LOGON) => {
+                        buffer.drain(..len);
+                        transport.set_nonblocking()?;
+                        return Ok((transport, buffer));
+                    }
+                    Some(msg_type::LOGOUT | msg_type::REJECT) => {
+                        let text = message.get_str(tag::TEXT).unwrap_or_default();
+                        return Err(FixConnectorError::LogonRejected(text.to_string()));
+                    }
+                    _ => {
+                        buffer.drain(..len);
+                    }
+                }
+            }
+            if start.elapsed() >= CONNECT_TIMEOUT {
+                return Err(FixConnectorError::LogonTimeout(CONNECT_TIMEOUT));
+            }
+            match transport.read(&mut chunk) {
+                Ok(0) => return Err(FixConnectorError::Disconnected),
+                Ok(n) => buffer.extend_from_slice(&chunk[..n]),
+                Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+
+    /// Reports the endpoint switches of the connection, as `feed`, to `reporter`.
+    pub fn set_failover_reporter(&mut self, feed: &str, reporter: Sender<EndpointSwitch>) {
+        self.failover_reporter = Some((feed.to_string(), reporter));
+    }
+
+    /// Switches to the next endpoint when requested through `trigger`.
+    pub fn set_failover_trigger(&mut self, trigger: FailoverTrigger) {
+        self.rotation.set_trigger(trigger);
+    }
+
+    /// Returns the active endpoint.
+    pub fn active_endpoint(&self) -> &str {
+        self.rotation.active()
+    }
+
+    /// Returns a reference to the subscribed streams.
+    pub fn streams(&self) -> &Streams<K> {
+        &self.streams
+    }
+
+    /// Returns the next reject received, if any.
+    pub fn poll_reject(&mut self) -> Option<FixReject> {
+        self.rejects.pop_front()
+    }
+
+    /// Returns the `MDReqID` of the subscription of a symbol.
+    pub fn md_req_id(symbol: &str) -> String {
+        format!("{}_{}", K::NAME, symbol.to_uppercase())
+    }
+
+    /// Updates the subscribed streams to `streams`, sending a market data
+    /// request per added or removed symbol.
+    ///
+    /// LATENCY: SLOW_PATH
+    /// ERROR: FULLY_HANDLED
+    pub fn update_streams(&mut self, streams: &Streams<K>) -> Result<(), FixConnectorError> {
+        let removed: Vec<Stream<K>> = self.streams.difference(streams).cloned().collect();
+        let added: Vec<Stream<K>> = streams.difference(&self.streams).cloned().collect();
+
+        for stream in &removed {
+            let md_req_id = Self::md_req_id(stream.name);
+            self.subscriptions.remove(&md_req_id);
+            let fields = Self::market_data_request(&md_req_id, stream.name, UNSUBSCRIBE);
+            self.send_message(msg_type::MARKET_DATA_REQUEST, &fields)?;
+            self.streams.remove(stream);
+        }
+
+        for stream in added {
+            let md_req_id = Self::md_req_id(stream.name);
+            let fields = Self::market_data_request(&md_req_id, stream.name, SUBSCRIBE);
+            self.send_message(msg_type::MARKET_DATA_REQUEST, &fields)?;
+            self.subscriptions.insert(md_req_id, fields);
+            self.streams.insert(stream);
+        }
+
+        Ok(())
+    }
+
+    /// Returns the fields of the market data request of a symbol.
+    fn market_data_request(md_req_id: &str, symbol: &str, request_type: u32) -> Vec<u8> {
+        let mut fields = Vec::with_capacity(128);
+        write_field(&mut fields, tag::MD_REQ_ID, md_req_id);
+        write_field(&mut fields, tag::SUBSCRIPTION_REQUEST_TYPE, request_type);
+        write_field(&mut fields, tag::MARKET_DEPTH, K::MARKET_DEPTH);
+        write_field(&mut fields, tag::AGGREGATED_BOOK, "Y");
+        write_field(&mut fields, tag::NO_RELATED_SYM, 1);
+        write_field(&mut fields, tag::SYMBOL, symbol.to_uppercase());
+        write_field(&mut fields, tag::NO_MD_ENTRY_TYPES, K::ENTRY_TYPES.len());
+        for entry_type in K::ENTRY_TYPES {
+            write_field(&mut fields, tag::MD_ENTRY_TYPE, entry_type);
+        }
+        fields
+    }
+
+    /// Sends a message of the session with its body `fields`.
+    ///
+    /// LATENCY: SLOW_PATH
+    pub fn send_message(&mut self, msg_type: &str, fields: &[u8]) -> Result<(), FixConnectorError> {
+        let message = self.session.encode(msg_type, fields, self.next_seq);
+        self.next_seq += 1;
+        self.transport.write_all(&message)?;
+        self.last_sent = Instant::now();
+        Ok(())
+    }
+
+    /// Handles a failure of the active endpoint, switching endpoints once the
+    /// failover policy's failure threshold is reached.
+    ///
+    /// LATENCY: SLOW_PATH
+    /// ERROR: FULLY_HANDLED
+    fn handle_failure(&mut self) {
+        if let Some(reason) = self.rotation.record_failure() {
+            // On a failed switch, the next endpoint is tried once the threshold is reached again
+            let _ = self.switch_endpoint(reason);
+        }
+    }
+
+    /// Switches to the next endpoint, reporting the switch, logging on and resubscribing the streams.
+    ///
+    /// LATENCY: SLOW_PATH
+    fn switch_endpoint(&mut self, reason: SwitchReason) -> Result<(), FixConnectorError> {
+        let (from, to) = self.rotation.advance(Instant::now());
+        if let Some((feed, reporter)) = &self.failover_reporter {
+            let _ = reporter.send(EndpointSwitch { feed: feed.clone(), from, to: to.clone(), reason });
+        }
+
+        let (transport, recv_buffer) = Self::logon(&to, &self.session, &self.credentials)?;
+        self.transport = transport;
+        self.recv_buffer = recv_buffer;
+        self.next_seq = 2;
+        self.last_received = Instant::now();
+        self.test_request_sent = false;
+        let requests: Vec<Vec<u8>> = self.subscriptions.values().cloned().collect();
+        for fields in requests {
+            self.send_message(msg_type::MARKET_DATA_REQUEST, &fields)?;
+        }
+        Ok(())
+    }
+
+    /// Reads the bytes available on the stream.
+    ///
+    /// LATENCY: FAST_PATH
+    fn read(&mut self) -> Result<(), FixConnectorError> {
+        let mut chunk = [0u8; 4096];
+        match self.transport.read(&mut chunk) {
+            Ok(0) => Err(FixConnectorError::Disconnected),
+            Ok(n) => {
+                self.recv_buffer.extend_from_slice(&chunk[..n]);
+                Ok(())
+            }
+            Err(e) if e.kind() == ErrorKind::WouldBlock => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Handles a received message: the market data is split into events, the
+    /// session messages are answered.
+    ///
+    /// LATENCY: FAST_PATH
+    fn handle_message(&mut self, message: &FixMessage<'_>) -> Result<(), FixConnectorError> {
+        match message.msg_type() {
+            Some(msg_type::MARKET_DATA_SNAPSHOT | msg_type::MARKET_DATA_INCREMENTAL_REFRESH) => {
+                split_entries(message, K::SPLIT, &mut self.events);
+            }
+            Some(msg_type::TEST_REQUEST) => {
+                let mut fields = Vec::new();
+                write_field(&mut fields, tag::TEST_REQ_ID, message.get_str(tag::TEST_REQ_ID).unwrap_or_default());
+                self.send_message(msg_type::HEARTBEAT, &fields)?;
+            }
+            Some(msg_type::MARKET_DATA_REQUEST_REJECT | msg_type::REJECT) => {
+                let reason = message
+                    .get_str(tag::TEXT)
+                    .or_else(|| message.get_str(tag::MD_REQ_REJ_REASON))
+                    .unwrap_or_default();
+                self.rejects.push_back(FixReject {
+                    md_req_id: message.get_str(tag::MD_REQ_ID).unwrap_or_default().to_string(),
+                    reason: reason.to_string(),
+                });
+            }
+            Some(msg_type::LOGOUT) => return Err(FixConnectorError::Disconnected),
+            // Heartbeats, and the news announcing the maintenances followed by a logout
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Reads and handles the received messages, returning false on a failure of the endpoint.
+    ///
+    /// LATENCY: FAST_PATH
+    fn receive(&mut self) -> bool {
+        if self.read().is_err() {
+            return false;
+        }
+        let mut buffer = std::mem::take(&mut self.recv_buffer);
+        let mut handled = 0;
+        let mut healthy = true;
+        while healthy {
+            match frame_len(&buffer[handled..]) {
+                Ok(Some(len)) => {
+                    let message = FixMessage::new(&buffer[handled..handled + len]);
+                    healthy = self.handle_message(&message).is_ok();
+                    handled += len;
+                    let now = Instant::now();
+                    self.rotation.record_data(now);
+                    self.last_received = now;
+                    self.test_request_sent = false;
+                }
+                Ok(None) => break,
+                // Out of sync with the server, the buffered bytes are dropped
+                Err(_) => {
+                    handled = buffer.len();
+                    healthy = false;
+                }
+            }
+        }
+        buffer.drain(..handled);
+        self.recv_buffer = buffer;
+        healthy
+    }
+
+    /// Sends the heartbeats of a quiet session, and a test request once the
+    /// server stayed silent for a heartbeat interval.
+    ///
+    /// LATENCY: FAST_PATH (SLOW_PATH when due)
+    /// ERROR: FULLY_HANDLED
+    fn maintain(&mut self, now: Instant) {
+        let interval = self.session.heartbeat_interval;
+        let sent = if now.saturating_duration_since(self.last_received) >= interval && !self.test_request_sent {
+            self.test_request_sent = true;
+            let mut fields = Vec::new();
+            write_field(&mut fields, tag::TEST_REQ_ID, ctl_time::now_ms());
+            self.send_message(msg_type::TEST_REQUEST, &fields)
+        } else if now.saturating_duration_since(self.last_sent) >= interval {
+            self.send_message(msg_type::HEARTBEAT, &[])
+        } else {
+            Ok(())
+        };
+        if sent.is_err() {
+            self.handle_failure();
+        }
+    }
+}
+
+impl<K: MarketDataEntries> FeedProtocolOps for FixConn<K> {
+    type FeedProtocolError = FixConnectorError;
+
+    /// Polls the active endpoint for the next market data event.
+    ///
+    /// Failures of the endpoint are handled by failing over to the next endpoint,
+    /// as is an endpoint that stayed silent for too long while subscribed.
+    ///
+    /// LATENCY: FAST_PATH
+    fn poll(&mut self) -> Result<FeedPoll<'_>, Self::FeedProtocolError> {
+        if self.events.is_empty() && !self.receive() {
+            self.handle_failure();
+            return Ok(FeedPoll::Empty);
+        }
+
+        let Some(event) = self.events.pop_front() else {
+            let now = Instant::now();
+            match self.rotation.check_stale(now) {
+                // A failed switch is retried once the new endpoint turns stale or fails
+                Some(reason) if !self.subscriptions.is_empty() => {
+                    let _ = self.switch_endpoint(reason);
+                }
+                _ => self.maintain(now),
+            }
+            return Ok(FeedPoll::Empty);
+        };
+        self.data = event;
+        Ok(FeedPoll::Data(&self.data))
+    }
+
+    /// Sends a message given by its body, from its `MsgType` field on.
+    fn send(&mut self, data: FeedData) -> Result<(), Self::FeedProtocolError> {
+        let message = FixMessage::new(data);
+        let msg_type = message.msg_type().ok_or(FixConnectorError::Malformed("missing message type"))?.to_string();
+        let fields = data
+            .iter()
+            .position(|&b| b == crate::SOH)
+            .map_or(&[][..], |end| &data[end + 1..]);
+        self.send_message(&msg_type, fields)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+    use std::thread;
+
+    use atx_feed::FeedKind;
+    use ed25519_dalek::SigningKey;
+
+    use crate::{EntrySplit, SOH};
+
+    /// Bound on the polls waiting for the server.
+    const WAIT: Duration = Duration::from_secs(5);
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    struct Trades;
+
+    impl FeedKind for Trades {}
+
+    impl MarketDataEntries for Trades {
+        const NAME: &'static str = "TRADE";
+        const ENTRY_TYPES: &'static [&'static str] = &["2"];
+        const MARKET_DEPTH: u32 = 1;
+        const SPLIT: EntrySplit = EntrySplit::PerEntry;
+    }
+
+    fn server_message(msg_type: &str, fields: &str, seq: u64) -> Vec<u8> {
+        let header = FixHeader { sender_comp_id: "SPOT", target_comp_id: "CTLMD00", seq, sending_time: "20240101-00:00:00.000" };
+        encode(msg_type, fields.replace('|', &char::from(SOH).to_string()).as_bytes(), &header)
+    }
+
+    /// Reads the next message sent by the client.
+    fn read_message(stream: &mut std::net::TcpStream, buffer: &mut Vec<u8>) -> Vec<u8> {
+        let mut chunk = [0u8; 1024];
+        loop {
+            if let Some(len) = frame_len(buffer).unwrap() {
+                return buffer.drain(..len).collect();
+            }
+            let n = stream.read(&mut chunk).unwrap();
+            assert!(n > 0, "client disconnected");
+            buffer.extend_from_slice(&chunk[..n]);
+        }
+    }
+
+    #[test]
+    fn test_logon_subscribe_and_receive() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let endpoint = format!("tcp://{}", listener.local_addr().unwrap());
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut buffer = Vec::new();
+            let logon = read_message(&mut stream, &mut buffer);
+            stream.write_all(&server_message(msg_type::LOGON, "98=0|108=30|", 1)).unwrap();
+            let request = read_message(&mut stream, &mut buffer);
+            let trades = "262=TRADE_BTCUSDT|268=2|279=0|269=2|55=BTCUSDT|270=60000.00|271=0.01|1003=1|\
+                          279=0|269=2|270=60001.00|271=0.02|1003=2|";
+            stream.write_all(&server_message(msg_type::MARKET_DATA_INCREMENTAL_REFRESH, trades, 2)).unwrap();
+            (logon, request)
+        });
+
+        let credentials = FixCredentials::new("key", SigningKey::from_bytes(&[7; 32]));
+        let mut conn = FixConn::<Trades>::new(&endpoint, FixSession::new("CTLMD00"), credentials).unwrap();
+        let mut streams = Streams::new();
+        streams.insert(Stream::new("btcusdt"));
+        conn.update_streams(&streams).unwrap();
+
+        let mut prices = Vec::new();
+        let start = Instant::now();
+        while prices.len() < 2 {
+            assert!(start.elapsed() < WAIT, "no market data within {:?}", WAIT);
+            if let FeedPoll::Data(data) = conn.poll().unwrap() {
+                let event = FixMessage::new(data);
+                assert_eq!(event.get_str(tag::SYMBOL), Some("BTCUSDT"));
+                prices.push(event.get_str(tag::MD_ENTRY_PX).unwrap().to_string());
+            }
+        }
+        assert_eq!(prices, ["60000.00", "60001.00"]);
+
+        let (logon, request) = server.join().unwrap();
+        let logon = FixMessage::new(&logon);
+        assert_eq!(logon.msg_type(), Some(msg_type::LOGON));
+        assert_eq!(logon.get_str(tag::USERNAME), Some("key"));
+        assert_eq!(logon.get_str(tag::RESET_SEQ_NUM_FLAG), Some("Y"));
+        let request = FixMessage::new(&request);
+        assert_eq!(request.msg_type(), Some(msg_type::MARKET_DATA_REQUEST));
+        assert_eq!(request.get_u64(tag::MSG_SEQ_NUM), Some(2));
+        assert_eq!(request.get_str(tag::MD_REQ_ID), Some("TRADE_BTCUSDT"));
+        assert_eq!(request.get_str(tag::SYMBOL), Some("BTCUSDT"));
+        assert_eq!(request.get_str(tag::MD_ENTRY_TYPE), Some("2"));
+    }
+}
@@ -0,0 +1,15 @@
+mod fix;
+mod codec;
+mod credentials;
+mod error;
+mod protocol;
+mod transport;
+
+pub use fix::{FixConn, FixReject, FixSession, BINANCE_TARGET_COMP_ID};
+pub use codec::{
+    checksum, encode, frame_len, msg_type, parse_uint, parse_utc_timestamp, tag, utc_timestamp, write_field,
+    write_raw_field, FixEntries, FixFields, FixHeader, FixMessage, BEGIN_STRING, SOH,
+};
+pub use credentials::{FixCredentials, FIX_API_KEY_ENV, FIX_PRIVATE_KEY_ENV};
+pub use error::FixConnectorError;
+pub use protocol::{split_entries, EntrySplit, MarketDataEntries};
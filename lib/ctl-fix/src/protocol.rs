@@ -0,0 +1,156 @@
+use std::collections::VecDeque;
+
+use atx_feed::{FeedKind, FeedProtocol, Streams};
+
+use crate::{FixConn, FixMessage, tag, write_field, write_raw_field};
+
+/// How the entries of a market data message are split into events.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntrySplit {
+    /// Every entry is an event, e.g. a trade.
+    PerEntry,
+    /// The consecutive entries of a symbol are an event, e.g. the bid and
+    /// offer of a book ticker update.
+    PerSymbol,
+}
+
+/// The market data requested for a feed kind (e.g. the bid and offer entries
+/// of the book ticker).
+///
+/// Implementing this for a feed kind provides the `FeedProtocol`
+/// implementation of `FixConn` for that kind.
+pub trait MarketDataEntries: FeedKind {
+    /// The name of the market data, prefixing the `MDReqID` of the subscriptions.
+    const NAME: &'static str;
+    /// The `MDEntryType` (269) values requested.
+    const ENTRY_TYPES: &'static [&'static str];
+    /// The `MarketDepth` (264) requested.
+    const MARKET_DEPTH: u32;
+    /// How the entries of the received messages are split into events.
+    const SPLIT: EntrySplit;
+}
+
+impl<K: MarketDataEntries> FeedProtocol<K> for FixConn<K> {
+    /// Updates the subscribed symbols for the feed kind.
+    ///
+    /// The rejected requests are surfaced through `FixConn::poll_reject`.
+    ///
+    /// LATENCY: SLOW_PATH
+    /// ERROR: FULLY_HANDLED
+    fn update(&mut self, streams: &Streams<K>) -> Result<(), Self::FeedProtocolError> {
+        self.update_streams(streams)
+    }
+}
+
+/// Splits a market data snapshot or incremental refresh into single events,
+/// pushed to `events`, each a message of the same type whose entries all
+/// carry their `Symbol` (55).
+///
+/// A symbol is only given on the first of its entries, or once for the whole
+/// snapshot, and is carried over to the following entries.
+///
+/// LATENCY: FAST_PATH
+pub fn split_entries(message: &FixMessage<'_>, split: EntrySplit, events: &mut VecDeque<Vec<u8>>) {
+    let Some(msg_type) = message.msg_type() else {
+        return;
+    };
+    // The fields of the message outside its session header and entries
+    let mut head = Vec::new();
+    let mut symbol = None;
+    for (field, value) in message.fields() {
+        match field {
+            tag::NO_MD_ENTRIES => break,
+            tag::SYMBOL => symbol = Some(value),
+            tag::BEGIN_STRING | tag::BODY_LENGTH | tag::MSG_TYPE | tag::SENDER_COMP_ID | tag::TARGET_COMP_ID
+            | tag::MSG_SEQ_NUM | tag::SENDING_TIME | tag::CHECKSUM => {}
+            _ => write_raw_field(&mut head, field, value),
+        }
+    }
+
+    // The entries of each event, with their symbol and count
+    let mut split_events: Vec<(&[u8], usize, Vec<u8>)> = Vec::new();
+    for entry in message.entries(tag::NO_MD_ENTRIES) {
+        symbol = entry.get(tag::SYMBOL).or(symbol);
+        let Some(entry_symbol) = symbol else {
+            continue;
+        };
+        let mut fields = Vec::with_capacity(entry.as_bytes().len() + 16);
+        for (field, value) in entry.fields().filter(|&(field, _)| field != tag::CHECKSUM) {
+            write_raw_field(&mut fields, field, value);
+        }
+        if entry.get(tag::SYMBOL).is_none() {
+            write_raw_field(&mut fields, tag::SYMBOL, entry_symbol);
+        }
+        match split_events.last_mut() {
+            Some((last, count, last_fields)) if split == EntrySplit::PerSymbol && *last == entry_symbol => {
+                *count += 1;
+                last_fields.extend_from_slice(&fields);
+            }
+            _ => split_events.push((entry_symbol, 1, fields)),
+        }
+    }
+
+    for (symbol, count, fields) in split_events {
+        let mut event = Vec::with_capacity(head.len() + fields.len() + 32);
+        write_field(&mut event, tag::MSG_TYPE, msg_type);
+        write_raw_field(&mut event, tag::SYMBOL, symbol);
+        event.extend_from_slice(&head);
+        write_field(&mut event, tag::NO_MD_ENTRIES, count);
+        event.extend_from_slice(&fields);
+        events.push_back(event);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::msg_type;
+
+    fn split(message: &[u8], split: EntrySplit) -> Vec<Vec<u8>> {
+        let mut events = VecDeque::new();
+        split_entries(&FixMessage::new(message), split, &mut events);
+        events.into()
+    }
+
+    #[test]
+    fn test_split_per_entry() {
+        let message = b"8=FIX.4.4\x019=100\x0135=X\x0134=2\x01262=TRADE_BTCUSDT\x01268=3\x01\
+            279=0\x01269=2\x0155=BTCUSDT\x01270=1.5\x01\
+            279=0\x01269=2\x01270=1.6\x01\
+            279=0\x01269=2\x0155=ETHUSDT\x01270=2.5\x0110=000\x01";
+        let events = split(message, EntrySplit::PerEntry);
+        assert_eq!(events.len(), 3);
+
+        // Every event carries its symbol and its single entry
+        let expected = [("BTCUSDT", "1.5"), ("BTCUSDT", "1.6"), ("ETHUSDT", "2.5")];
+        for (event, (symbol, price)) in events.iter().zip(expected) {
+            let event = FixMessage::new(event);
+            assert_eq!(event.msg_type(), Some(msg_type::MARKET_DATA_INCREMENTAL_REFRESH));
+            assert_eq!(event.get_str(tag::MD_REQ_ID), Some("TRADE_BTCUSDT"));
+            assert_eq!(event.get_u64(tag::MSG_SEQ_NUM), None);
+            assert_eq!(event.get_u64(tag::NO_MD_ENTRIES), Some(1));
+            let entries: Vec<FixMessage<'_>> = event.entries(tag::NO_MD_ENTRIES).collect();
+            assert_eq!(entries.len(), 1);
+            assert_eq!(entries[0].get_str(tag::SYMBOL), Some(symbol));
+            assert_eq!(entries[0].get_str(tag::MD_ENTRY_PX), Some(price));
+        }
+    }
+
+    #[test]
+    fn test_split_per_symbol() {
+        // A snapshot gives its symbol once, before its entries
+        let snapshot = b"35=W\x01262=BOOK_TICKER_BTCUSDT\x0155=BTCUSDT\x0125044=42\x01268=2\x01\
+            269=0\x01270=1.5\x01271=3\x01269=1\x01270=1.6\x01271=4\x01";
+        let events = split(snapshot, EntrySplit::PerSymbol);
+        assert_eq!(events.len(), 1);
+        let event = FixMessage::new(&events[0]);
+        assert_eq!(event.get_u64(tag::LAST_BOOK_UPDATE_ID), Some(42));
+        assert_eq!(event.get_u64(tag::NO_MD_ENTRIES), Some(2));
+        let symbols: Vec<Option<&str>> = event.entries(tag::NO_MD_ENTRIES).map(|entry| entry.get_str(tag::SYMBOL)).collect();
+        assert_eq!(symbols, [Some("BTCUSDT"), Some("BTCUSDT")]);
+
+        // Without a symbol, the entries are dropped
+        assert!(split(b"35=X\x01268=1\x01279=0\x01269=0\x01270=1.5\x01", EntrySplit::PerSymbol).is_empty());
+    }
+}
@@ -0,0 +1,85 @@
+//! The TCP transport of the FIX sessions, optionally over TLS.
+//!
+//! The Binance FIX endpoints only accept TLS (`tls://host:port`), plain TCP
+//! (`tcp://host:port`) serving a local TLS proxy or the tests.
+
+use std::io::{self, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::time::Duration;
+
+use native_tls::{TlsConnector, TlsStream};
+
+use crate::FixConnectorError;
+
+/// The stream of a FIX session.
+pub(crate) enum Transport {
+    /// Plain TCP.
+    Tcp(TcpStream),
+    /// TLS over TCP.
+    Tls(Box<TlsStream<TcpStream>>),
+}
+
+impl Transport {
+    /// Connects to a `tcp://` or `tls://` endpoint, blocking for at most `timeout` per step.
+    pub(crate) fn connect(endpoint: &str, timeout: Duration) -> Result<Self, FixConnectorError> {
+        let invalid = || FixConnectorError::InvalidEndpoint(endpoint.to_string());
+        let (tls, address) = match endpoint.split_once("://") {
+            Some(("tls", address)) => (true, address),
+            Some(("tcp", address)) => (false, address),
+            _ => return Err(invalid()),
+        };
+        let addr = address.to_socket_addrs().map_err(|_| invalid())?.next().ok_or_else(invalid)?;
+
+        let stream = TcpStream::connect_timeout(&addr, timeout)?;
+        stream.set_nodelay(true)?;
+        stream.set_read_timeout(Some(timeout))?;
+        stream.set_write_timeout(Some(timeout))?;
+        if !tls {
+            return Ok(Transport::Tcp(stream));
+        }
+
+        let host = address.rsplit_once(':').map_or(address, |(host, _)| host);
+        let stream = TlsConnector::new()?
+            .connect(host, stream)
+            .map_err(|_| FixConnectorError::Handshake(endpoint.to_string()))?;
+        Ok(Transport::Tls(Box::new(stream)))
+    }
+
+    /// Returns the underlying TCP stream.
+    fn tcp(&self) -> &TcpStream {
+        match self {
+            Transport::Tcp(stream) => stream,
+            Transport::Tls(stream) => stream.get_ref(),
+        }
+    }
+
+    /// Switches the stream to non-blocking reads, once logged on.
+    pub(crate) fn set_nonblocking(&self) -> io::Result<()> {
+        self.tcp().set_nonblocking(true)
+    }
+}
+
+impl Read for Transport {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            Transport::Tcp(stream) => stream.read(buf),
+            Transport::Tls(stream) => stream.read(buf),
+        }
+    }
+}
+
+impl Write for Transport {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Transport::Tcp(stream) => stream.write(buf),
+            Transport::Tls(stream) => stream.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Transport::Tcp(stream) => stream.flush(),
+            Transport::Tls(stream) => stream.flush(),
+        }
+    }
+}
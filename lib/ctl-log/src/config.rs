@@ -0,0 +1,144 @@
+//! Configuration of the logger, defined in `configs/logging.yaml`.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+use serde::Deserialize;
+use tracing_subscriber::filter::LevelFilter;
+use tracing_subscriber::EnvFilter;
+
+use crate::LogConfigError;
+
+/// The output format of the log records.
+#[derive(Debug, Clone, Copy, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum LogFormat {
+    /// Human readable lines.
+    #[default]
+    Text,
+    /// One JSON object per record, for log shippers.
+    Json,
+}
+
+/// The logging configuration.
+#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+pub struct LogConfig {
+    /// The level of the targets not listed in `targets`.
+    #[serde(default = "default_level")]
+    pub level: String,
+    /// The output format, fixed for the lifetime of a process.
+    #[serde(default)]
+    pub format: LogFormat,
+    /// The level of each target, a crate (e.g. `ctl_md_handler`) or a module
+    /// path within it (e.g. `ctl_websocket::failover`).
+    #[serde(default)]
+    pub targets: BTreeMap<String, String>,
+}
+
+fn default_level() -> String {
+    "info".to_string()
+}
+
+impl Default for LogConfig {
+    fn default() -> Self {
+        Self { level: default_level(), format: LogFormat::default(), targets: BTreeMap::new() }
+    }
+}
+
+impl LogConfig {
+    /// Parses the logging configuration from a YAML file.
+    ///
+    /// # Errors
+    /// Returns an error if the file cannot be read, parsed, or fails validation.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, LogConfigError> {
+        let content = fs::read_to_string(path)?;
+        Self::from_str(&content)
+    }
+
+    /// Parses the logging configuration from a YAML string.
+    ///
+    /// # Errors
+    /// Returns an error if the YAML cannot be parsed or fails validation.
+    pub fn from_str(content: &str) -> Result<Self, LogConfigError> {
+        let config: Self = serde_yaml::from_str(content)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Validates the logging configuration.
+    fn validate(&self) -> Result<(), LogConfigError> {
+        parse_level(&self.level).map_err(|e| LogConfigError::ValidationError(format!("Default {}", e)))?;
+        for (target, level) in &self.targets {
+            if target.is_empty() || target.contains([',', '=', '[', ']', ' ']) {
+                return Err(LogConfigError::ValidationError(format!("Invalid log target '{}'", target)));
+            }
+            parse_level(level)
+                .map_err(|e| LogConfigError::ValidationError(format!("Target '{}': {}", target, e)))?;
+        }
+        Ok(())
+    }
+
+    /// Returns the filter directives, e.g. `info,ctl_feed=debug`.
+    pub fn directives(&self) -> String {
+        let mut directives = self.level.clone();
+        for (target, level) in &self.targets {
+            directives.push_str(&format!(",{}={}", target, level));
+        }
+        directives
+    }
+
+    /// Returns the filter of the log records.
+    ///
+    /// # Errors
+    /// Returns an error if the directives are invalid.
+    pub fn filter(&self) -> Result<EnvFilter, LogConfigError> {
+        EnvFilter::builder()
+            .parse(self.directives())
+            .map_err(|e| LogConfigError::ValidationError(format!("Invalid log directives: {}", e)))
+    }
+}
+
+/// Parses a level (`off`, `error`, `warn`, `info`, `debug` or `trace`).
+fn parse_level(level: &str) -> Result<LevelFilter, String> {
+    LevelFilter::from_str(level).map_err(|_| format!("invalid log level '{}'", level))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_config() {
+        let config = LogConfig::from_str(
+            r#"
+level: warn
+format: json
+targets:
+  ctl_md_handler: debug
+  ctl_websocket::failover: trace
+"#,
+        )
+        .unwrap();
+        assert_eq!(config.format, LogFormat::Json);
+        assert_eq!(config.directives(), "warn,ctl_md_handler=debug,ctl_websocket::failover=trace");
+        assert!(config.filter().is_ok());
+    }
+
+    #[test]
+    fn test_defaults() {
+        let config = LogConfig::from_str("{}").unwrap();
+        assert_eq!(config, LogConfig::default());
+        assert_eq!(config.directives(), "info");
+    }
+
+    #[test]
+    fn test_invalid_config() {
+        assert!(LogConfig::from_str("level: loud\n").is_err());
+        assert!(LogConfig::from_str("format: xml\n").is_err());
+        let result = LogConfig::from_str("targets:\n  ctl_feed: verbose\n");
+        assert!(result.unwrap_err().to_string().contains("Target 'ctl_feed'"));
+        assert!(LogConfig::from_str("targets:\n  'a=b': info\n").is_err());
+    }
+}
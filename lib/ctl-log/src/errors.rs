@@ -0,0 +1,21 @@
+use thiserror::Error;
+
+/// Errors that can occur when parsing the logging configuration or installing the logger.
+#[derive(Debug, Error)]
+pub enum LogConfigError {
+    /// Error reading the configuration file.
+    #[error("Failed to read configuration file: {0}")]
+    FileReadError(#[from] std::io::Error),
+    /// Error parsing the YAML configuration.
+    #[error("Failed to parse YAML configuration: {0}")]
+    YamlParseError(#[from] serde_yaml::Error),
+    /// Validation error with a descriptive message.
+    #[error("Configuration validation error: {0}")]
+    ValidationError(String),
+    /// A logger was already installed in the process.
+    #[error("Failed to install the logger: {0}")]
+    InitError(#[from] tracing_subscriber::util::TryInitError),
+    /// The logger of the process was dropped.
+    #[error("Failed to reload the log levels: {0}")]
+    ReloadError(#[from] tracing_subscriber::reload::Error),
+}
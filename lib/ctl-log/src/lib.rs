@@ -0,0 +1,45 @@
+//! Structured logging of the controller components.
+//!
+//! Every binary installs a `tracing` logger configured by `configs/logging.yaml`:
+//! a default level, the level of each target (the crate or module emitting
+//! the record) and the output format, human readable text or JSON for log
+//! shippers. The levels are reloaded when the file changes, without a restart.
+//!
+//! Per-message logging on the hot paths goes through [`hot_debug!`], compiled
+//! out unless the `hot-path` feature is enabled.
+
+mod config;
+mod errors;
+mod logger;
+
+pub use config::{LogConfig, LogFormat};
+pub use errors::LogConfigError;
+pub use logger::{init, init_from_file, LogHandle, LOG_RELOAD_INTERVAL};
+
+#[doc(hidden)]
+pub use tracing as __tracing;
+
+/// Logs a debug record from a hot path, compiled in with the `hot-path` feature.
+///
+/// LATENCY: FAST_PATH (compiled out without the `hot-path` feature)
+#[cfg(feature = "hot-path")]
+#[macro_export]
+macro_rules! hot_debug {
+    ($($arg:tt)+) => {
+        $crate::__tracing::debug!($($arg)+)
+    };
+}
+
+/// Logs a debug record from a hot path, compiled in with the `hot-path` feature.
+///
+/// LATENCY: FAST_PATH (compiled out without the `hot-path` feature)
+#[cfg(not(feature = "hot-path"))]
+#[macro_export]
+macro_rules! hot_debug {
+    ($($arg:tt)+) => {
+        // Type checks the arguments without evaluating them
+        if false {
+            let _ = ::std::format_args!($($arg)+);
+        }
+    };
+}
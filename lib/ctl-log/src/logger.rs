@@ -0,0 +1,120 @@
+//! Installation of the process logger and reload of its levels.
+
+use std::path::Path;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{fmt, reload, EnvFilter, Registry};
+
+use crate::{LogConfig, LogConfigError, LogFormat};
+
+/// The interval at which the configuration file is checked for changes.
+pub const LOG_RELOAD_INTERVAL: Duration = Duration::from_secs(1);
+
+/// A callback of the reloads of the configuration file, given its path.
+type ReloadHook = Box<dyn Fn(&Path) + Send>;
+
+/// The handle of the process logger, adjusting its levels at runtime.
+#[derive(Clone)]
+pub struct LogHandle {
+    /// The reload handle of the filter layer.
+    filter: reload::Handle<EnvFilter, Registry>,
+    /// The output format installed.
+    format: LogFormat,
+    /// The callbacks run after each reload of the watched file.
+    hooks: Arc<Mutex<Vec<ReloadHook>>>,
+}
+
+impl LogHandle {
+    /// Returns the output format installed.
+    pub fn format(&self) -> LogFormat {
+        self.format
+    }
+
+    /// Applies the levels of `config`. The format is fixed at installation.
+    ///
+    /// # Errors
+    /// Returns an error if the directives are invalid or the logger was dropped.
+    pub fn reload(&self, config: &LogConfig) -> Result<(), LogConfigError> {
+        if config.format != self.format {
+            tracing::warn!(
+                "Log format changed from {:?} to {:?}, applied on the next restart",
+                self.format,
+                config.format
+            );
+        }
+        self.filter.reload(config.filter()?)?;
+        Ok(())
+    }
+
+    /// Runs `hook` with the path of the configuration file after each reload
+    /// of the levels from it, e.g. to record the reload in an audit journal.
+    pub fn on_reload(&self, hook: impl Fn(&Path) + Send + 'static) {
+        self.hooks.lock().unwrap_or_else(PoisonError::into_inner).push(Box::new(hook));
+    }
+
+    /// Spawns a thread reloading the levels whenever the configuration file
+    /// at `path` is modified. An invalid configuration keeps the current levels.
+    ///
+    /// LATENCY: SLOW_PATH
+    pub fn watch<P: AsRef<Path>>(&self, path: P) -> thread::JoinHandle<()> {
+        let handle = self.clone();
+        let path = path.as_ref().to_path_buf();
+        thread::Builder::new()
+            .name("ctl-log-watch".to_string())
+            .spawn(move || {
+                let mut modified = modified_time(&path);
+                loop {
+                    thread::sleep(LOG_RELOAD_INTERVAL);
+                    let current = modified_time(&path);
+                    if current == modified {
+                        continue;
+                    }
+                    modified = current;
+                    match LogConfig::from_file(&path).and_then(|config| handle.reload(&config)) {
+                        Ok(()) => {
+                            tracing::info!("Reloaded log levels from {}", path.display());
+                            for hook in handle.hooks.lock().unwrap_or_else(PoisonError::into_inner).iter() {
+                                hook(&path);
+                            }
+                        }
+                        Err(e) => tracing::warn!("Kept the log levels, {}: {}", path.display(), e),
+                    }
+                }
+            })
+            .expect("Failed to spawn the log configuration watcher")
+    }
+}
+
+/// Returns the modification time of a file, `None` if it cannot be read.
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    path.metadata().and_then(|m| m.modified()).ok()
+}
+
+/// Installs the process logger configured by `config`.
+///
+/// # Errors
+/// Returns an error if the directives are invalid or a logger is already installed.
+pub fn init(config: &LogConfig) -> Result<LogHandle, LogConfigError> {
+    let (filter, handle) = reload::Layer::new(config.filter()?);
+    let registry = tracing_subscriber::registry().with(filter);
+    match config.format {
+        LogFormat::Text => registry.with(fmt::layer().with_target(true)).try_init()?,
+        LogFormat::Json => registry.with(fmt::layer().json().with_current_span(false)).try_init()?,
+    }
+    Ok(LogHandle { filter: handle, format: config.format, hooks: Arc::default() })
+}
+
+/// Installs the process logger configured by the file at `path`, and watches
+/// the file for level changes.
+///
+/// # Errors
+/// Returns an error if the configuration is invalid or a logger is already installed.
+pub fn init_from_file<P: AsRef<Path>>(path: P) -> Result<LogHandle, LogConfigError> {
+    let handle = init(&LogConfig::from_file(&path)?)?;
+    handle.watch(path);
+    Ok(handle)
+}
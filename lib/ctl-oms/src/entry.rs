@@ -0,0 +1,329 @@
+//! Manual orders entered by the operators through the order entry ring.
+//!
+//! The operator console publishes its orders and cancels to the ring, each
+//! addressed to the OMS of a component. The OMS handles the entries addressed
+//! to it as requests of its own strategy ([`OrderManager::on_entry`]), so a
+//! manual order goes through the same checks: the rules of its symbol, the
+//! order rate budget, the halt and the circuit breaker. Its outcome is
+//! journaled like any order; a rejected entry is for the OMS to raise on the
+//! alerts ring, where the console follows it.
+//!
+//! [`OrderManager::on_entry`]: crate::OrderManager::on_entry
+
+use ctl_core::{read_padded, write_padded, MAX_CLIENT_ORDER_ID_LEN};
+use ctl_shm::{c_struct, declare_c_message, CHeader, ShmError, ShmMessage};
+
+use crate::{NewOrder, OmsError, OrderRequest, OrderType, Side, TimeInForce};
+
+/// Name of the order entry ring, created by ctl-resource-manager.
+pub const ORDER_ENTRY_RING_NAME: &str = "ORDER_ENTRY_PS";
+
+/// Number of slots of the order entry ring.
+pub const ORDER_ENTRY_RING_SIZE: usize = 256;
+
+/// Maximum length of the symbol of an entry, in bytes.
+/// https://github.com/binance/binance-spot-api-docs/blob/master/rest-api.md#exchange-information
+pub const ENTRY_SYMBOL_SIZE: usize = 20;
+
+/// Maximum length of the operator of an entry, in bytes.
+pub const ENTRY_OPERATOR_SIZE: usize = 32;
+
+/// The action of an entry.
+///
+/// Stored as a `u8` in the message, since a ring slot may hold any byte.
+#[repr(u8)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum EntryAction {
+    /// Unknown action, rejected.
+    Unknown = 0,
+    /// Places a new order.
+    New = 1,
+    /// Cancels an order.
+    Cancel = 2,
+}
+
+impl EntryAction {
+    /// Returns the action stored in a message.
+    pub fn from_u8(action: u8) -> Self {
+        match action {
+            1 => EntryAction::New,
+            2 => EntryAction::Cancel,
+            _ => EntryAction::Unknown,
+        }
+    }
+}
+
+/// The codes of the sides, order types and times in force of the entries,
+/// zero being unknown.
+const SIDES: [Side; 2] = [Side::Buy, Side::Sell];
+const ORDER_TYPES: [OrderType; 3] = [OrderType::Limit, OrderType::LimitMaker, OrderType::Market];
+const TIMES_IN_FORCE: [TimeInForce; 3] = [TimeInForce::Gtc, TimeInForce::Ioc, TimeInForce::Fok];
+
+/// Returns the code of a value, its position in `values` plus one, zero if absent.
+fn encode<T: PartialEq>(values: &[T], value: &T) -> u8 {
+    values.iter().position(|v| v == value).map_or(0, |index| index as u8 + 1)
+}
+
+/// Returns the value of a code, `None` if unknown.
+fn decode<T: Copy>(values: &[T], code: u8) -> Option<T> {
+    values.get((code as usize).checked_sub(1)?).copied()
+}
+
+/// A message of the order entry ring.
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+pub struct OrderEntryMessage {
+    /// The `EntryAction`.
+    pub action: u8,
+    /// The side of a new order: 1 buy, 2 sell.
+    pub side: u8,
+    /// The type of a new order: 1 limit, 2 limit maker, 3 market.
+    pub order_type: u8,
+    /// The time in force of a new limit order: 1 GTC, 2 IOC, 3 FOK.
+    pub time_in_force: u8,
+    /// The component ID of the OMS the entry is addressed to.
+    pub component_id: u16,
+    /// The time the entry was issued, in milliseconds since the epoch.
+    pub issued_at_ms: u64,
+    /// The limit price of a new order, zero for a market order.
+    pub price: f64,
+    /// The quantity of a new order.
+    pub qty: f64,
+    /// The client order ID of the new order, or of the order to cancel, zero-padded.
+    pub client_order_id: [u8; MAX_CLIENT_ORDER_ID_LEN],
+    /// The symbol of a new order, zero-padded.
+    pub symbol: [u8; ENTRY_SYMBOL_SIZE],
+    /// The operator who entered the order, zero-padded UTF-8.
+    pub operator: [u8; ENTRY_OPERATOR_SIZE],
+}
+
+impl Default for OrderEntryMessage {
+    fn default() -> Self {
+        Self {
+            action: EntryAction::Unknown as u8,
+            side: 0,
+            order_type: 0,
+            time_in_force: 0,
+            component_id: 0,
+            issued_at_ms: 0,
+            price: 0.0,
+            qty: 0.0,
+            client_order_id: [0u8; MAX_CLIENT_ORDER_ID_LEN],
+            symbol: [0u8; ENTRY_SYMBOL_SIZE],
+            operator: [0u8; ENTRY_OPERATOR_SIZE],
+        }
+    }
+}
+
+// SAFETY: `OrderEntryMessage` is `repr(C)`, made only of numbers and bytes, valid for any bytes.
+unsafe impl ShmMessage for OrderEntryMessage {
+    const LAYOUT_HASH: u64 = ctl_shm::layout_hash!(OrderEntryMessage {
+        action,
+        side,
+        order_type,
+        time_in_force,
+        component_id,
+        issued_at_ms,
+        price,
+        qty,
+        client_order_id,
+        symbol,
+        operator
+    });
+}
+
+impl OrderEntryMessage {
+    /// Creates the entry of a new order for the OMS of `component_id`.
+    ///
+    /// # Errors
+    /// Returns an error if the order type can't be entered manually (only
+    /// limit, limit maker and market orders can), or its client order ID or
+    /// symbol doesn't fit the message.
+    pub fn new_order(component_id: u16, order: &NewOrder, issued_at_ms: u64, operator: &str) -> Result<Self, OmsError> {
+        let invalid = |reason| {
+            Err(OmsError::InvalidOrder { client_order_id: order.client_order_id.clone(), reason })
+        };
+        let order_type = encode(&ORDER_TYPES, &order.order_type);
+        if order_type == 0 {
+            return invalid("order type not enterable manually");
+        }
+        if order.client_order_id.len() > MAX_CLIENT_ORDER_ID_LEN || order.symbol.len() > ENTRY_SYMBOL_SIZE {
+            return invalid("client order ID or symbol too long");
+        }
+        let mut message = Self {
+            action: EntryAction::New as u8,
+            side: encode(&SIDES, &order.side),
+            order_type,
+            time_in_force: encode(&TIMES_IN_FORCE, &order.time_in_force.unwrap_or_default()),
+            component_id,
+            issued_at_ms,
+            price: order.price,
+            qty: order.qty,
+            ..Self::default()
+        };
+        write_padded(&mut message.client_order_id, &order.client_order_id);
+        write_padded(&mut message.symbol, &order.symbol);
+        write_padded(&mut message.operator, operator);
+        Ok(message)
+    }
+
+    /// Creates the entry canceling an order of the OMS of `component_id`.
+    ///
+    /// # Errors
+    /// Returns an error if the client order ID doesn't fit the message.
+    pub fn cancel(
+        component_id: u16,
+        client_order_id: &str,
+        issued_at_ms: u64,
+        operator: &str,
+    ) -> Result<Self, OmsError> {
+        if client_order_id.len() > MAX_CLIENT_ORDER_ID_LEN {
+            return Err(OmsError::InvalidOrder {
+                client_order_id: client_order_id.to_string(),
+                reason: "client order ID too long",
+            });
+        }
+        let mut message = Self { action: EntryAction::Cancel as u8, component_id, issued_at_ms, ..Self::default() };
+        write_padded(&mut message.client_order_id, client_order_id);
+        write_padded(&mut message.operator, operator);
+        Ok(message)
+    }
+
+    /// Returns the action of the entry.
+    pub fn action(&self) -> EntryAction {
+        EntryAction::from_u8(self.action)
+    }
+
+    /// Returns the client order ID of the new order, or of the order to cancel.
+    pub fn client_order_id(&self) -> &str {
+        read_padded(&self.client_order_id)
+    }
+
+    /// Returns the symbol of a new order.
+    pub fn symbol(&self) -> &str {
+        read_padded(&self.symbol)
+    }
+
+    /// Returns the operator who entered the order.
+    pub fn operator(&self) -> &str {
+        read_padded(&self.operator)
+    }
+
+    /// Returns the request of the entry.
+    ///
+    /// # Errors
+    /// Returns an error if the action, or the side, type or time in force of a
+    /// new order, is unknown.
+    pub fn request(&self) -> Result<OrderRequest, OmsError> {
+        let client_order_id = self.client_order_id().to_string();
+        match self.action() {
+            EntryAction::New => {
+                let (Some(side), Some(order_type)) = (decode(&SIDES, self.side), decode(&ORDER_TYPES, self.order_type))
+                else {
+                    return Err(OmsError::InvalidOrder { client_order_id, reason: "unknown side or order type" });
+                };
+                let time_in_force = if order_type.has_time_in_force() {
+                    let Some(time_in_force) = decode(&TIMES_IN_FORCE, self.time_in_force) else {
+                        return Err(OmsError::InvalidOrder { client_order_id, reason: "unknown time in force" });
+                    };
+                    Some(time_in_force)
+                } else {
+                    None
+                };
+                Ok(OrderRequest::New(NewOrder {
+                    client_order_id,
+                    symbol: self.symbol().to_string(),
+                    side,
+                    order_type,
+                    price: self.price,
+                    qty: self.qty,
+                    stop_price: None,
+                    time_in_force,
+                    iceberg_qty: None,
+                    self_trade_prevention_mode: None,
+                }))
+            }
+            EntryAction::Cancel => Ok(OrderRequest::Cancel { client_order_id }),
+            EntryAction::Unknown => Err(OmsError::InvalidOrder { client_order_id, reason: "unknown entry action" }),
+        }
+    }
+}
+
+/// Declares the messages of the order entry ring in a C header.
+pub(crate) fn declare_c_types(header: &mut CHeader) -> Result<(), ShmError> {
+    header.section("Order entry ring (ctl-oms)");
+    header.define_str("CTL_ORDER_ENTRY_RING_NAME", ORDER_ENTRY_RING_NAME);
+    header.define("CTL_ORDER_ENTRY_RING_SIZE", ORDER_ENTRY_RING_SIZE as u64);
+    header.define("CTL_ENTRY_SYMBOL_SIZE", ENTRY_SYMBOL_SIZE as u64);
+    header.define("CTL_ENTRY_OPERATOR_SIZE", ENTRY_OPERATOR_SIZE as u64);
+    header.enumeration(
+        "CTL_ENTRY_ACTION",
+        &[
+            ("UNKNOWN", EntryAction::Unknown as u64),
+            ("NEW", EntryAction::New as u64),
+            ("CANCEL", EntryAction::Cancel as u64),
+        ],
+    );
+    let entry = c_struct!(OrderEntryMessage as "ctl_order_entry_message" {
+        action: "uint8_t",
+        side: "uint8_t",
+        order_type: "uint8_t",
+        time_in_force: "uint8_t",
+        component_id: "uint16_t",
+        issued_at_ms: "uint64_t",
+        price: "double",
+        qty: "double",
+        client_order_id: "uint8_t"[MAX_CLIENT_ORDER_ID_LEN],
+        symbol: "uint8_t"[ENTRY_SYMBOL_SIZE],
+        operator: "uint8_t"[ENTRY_OPERATOR_SIZE],
+    });
+    declare_c_message::<OrderEntryMessage>(header, "A message of the order entry ring, its text zero-padded.", entry)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn order(order_type: OrderType) -> NewOrder {
+        NewOrder {
+            client_order_id: "ctl-7-lz2a4k1c-0".to_string(),
+            symbol: "BTCUSDT".to_string(),
+            side: Side::Sell,
+            order_type,
+            price: 65_000.5,
+            qty: 0.25,
+            stop_price: None,
+            time_in_force: Some(TimeInForce::Ioc),
+            iceberg_qty: None,
+            self_trade_prevention_mode: None,
+        }
+    }
+
+    #[test]
+    fn test_new_order_round_trip() {
+        let message = OrderEntryMessage::new_order(7, &order(OrderType::Limit), 1_000, "alice").unwrap();
+        assert_eq!((message.action(), message.component_id, message.operator()), (EntryAction::New, 7, "alice"));
+        assert_eq!(message.request().unwrap(), OrderRequest::New(order(OrderType::Limit)));
+
+        // A market order has no time in force
+        let market = OrderEntryMessage::new_order(7, &order(OrderType::Market), 1_000, "alice").unwrap();
+        let OrderRequest::New(new) = market.request().unwrap() else {
+            panic!("expected a new order");
+        };
+        assert_eq!((new.order_type, new.time_in_force), (OrderType::Market, None));
+    }
+
+    #[test]
+    fn test_cancel_and_invalid_entries() {
+        let message = OrderEntryMessage::cancel(7, "ctl-7-lz2a4k1c-0", 1_000, "bob").unwrap();
+        let request = OrderRequest::Cancel { client_order_id: "ctl-7-lz2a4k1c-0".to_string() };
+        assert_eq!(message.request().unwrap(), request);
+
+        assert!(OrderEntryMessage::new_order(7, &order(OrderType::StopLoss), 0, "bob").is_err());
+        assert!(OrderEntryMessage::cancel(7, &"x".repeat(MAX_CLIENT_ORDER_ID_LEN + 1), 0, "bob").is_err());
+        assert!(OrderEntryMessage::default().request().is_err());
+        let limit = OrderEntryMessage::new_order(7, &order(OrderType::Limit), 0, "bob").unwrap();
+        assert!(OrderEntryMessage { side: 3, ..limit }.request().is_err());
+        assert!(OrderEntryMessage { time_in_force: 0, ..limit }.request().is_err());
+    }
+}
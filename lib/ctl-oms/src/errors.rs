@@ -0,0 +1,80 @@
+use thiserror::Error;
+
+use crate::{ListOrderStatus, OrderStatus};
+
+/// Errors that can occur when managing orders.
+#[derive(Debug, Error)]
+pub enum OmsError {
+    /// An order was submitted with a client order ID already in use.
+    #[error("Duplicate client order ID '{0}'")]
+    DuplicateOrder(String),
+    /// An order list was submitted with a client order list ID already in use.
+    #[error("Duplicate client order list ID '{0}'")]
+    DuplicateOrderList(String),
+    /// An order list was submitted with legs its contingency doesn't allow.
+    #[error("Invalid order list '{list_client_order_id}': {reason}")]
+    InvalidOrderList { list_client_order_id: String, reason: &'static str },
+    /// An order was submitted with parameters the rules of its symbol don't allow.
+    #[error("Invalid order '{client_order_id}': {reason}")]
+    InvalidOrder { client_order_id: String, reason: &'static str },
+    /// An order was submitted on a symbol missing from the exchange rules.
+    #[error("Unknown symbol '{0}'")]
+    UnknownSymbol(String),
+    /// A request would place more orders than left in the order rate budget of a window.
+    #[error("Order rate budget per {interval} exceeded: {count} orders placed, {orders} more over the limit of {limit}")]
+    OrderRateExceeded { interval: &'static str, count: u64, orders: u64, limit: u64 },
+    /// An order was submitted while trading is halted.
+    #[error("Order '{0}' rejected, trading is halted")]
+    Halted(String),
+    /// A new order was submitted while the WS API session is down, only cancels
+    /// falling back to the REST API.
+    #[error("Order '{0}' rejected, order entry is degraded to the REST fallback")]
+    Degraded(String),
+    /// Error of a request falling back to the REST API.
+    #[error("REST fallback error: {0}")]
+    RestError(#[from] ctl_rest::RestError),
+    /// An update was received for an order not being tracked.
+    #[error("Unknown client order ID '{0}'")]
+    UnknownOrder(String),
+    /// An update was received for an order list not being tracked.
+    #[error("Unknown client order list ID '{0}'")]
+    UnknownOrderList(String),
+    /// A cancel-replace targeted a leg of an order list, only canceled with its list.
+    #[error("Order '{0}' is a leg of an order list")]
+    ListLeg(String),
+    /// A cancel-replace would place its new order on another symbol than the canceled order's.
+    #[error("Cancel-replace of order '{client_order_id}' on {symbol} with an order on {new_symbol}")]
+    CancelReplaceSymbol { client_order_id: String, symbol: String, new_symbol: String },
+    /// An update would move an order through a transition its lifecycle doesn't allow.
+    #[error("Invalid transition of order '{client_order_id}' from {from:?} to {to:?}")]
+    InvalidTransition { client_order_id: String, from: OrderStatus, to: OrderStatus },
+    /// An update would move an order list through a transition its lifecycle doesn't allow.
+    #[error("Invalid transition of order list '{list_client_order_id}' from {from:?} to {to:?}")]
+    InvalidListTransition { list_client_order_id: String, from: ListOrderStatus, to: ListOrderStatus },
+    /// An update would decrease the executed quantity of an order.
+    #[error("Executed quantity of order '{client_order_id}' decreased from {from} to {to}")]
+    ExecutedQtyDecreased { client_order_id: String, from: f64, to: f64 },
+    /// A complete journal record couldn't be parsed.
+    #[error("Corrupt journal record at line {line}: {source}")]
+    CorruptJournal { line: usize, source: serde_json::Error },
+    /// Error reading or writing the journal.
+    #[error("Journal I/O error: {0}")]
+    IoError(#[from] std::io::Error),
+    /// Error serializing a journal record.
+    #[error("Journal serde json error: {0}")]
+    SerdeError(#[from] serde_json::Error),
+}
+
+/// Errors that can occur when parsing or validating the paper trading configuration.
+#[derive(Debug, Error)]
+pub enum PaperConfigError {
+    /// Error reading the configuration file.
+    #[error("Failed to read configuration file: {0}")]
+    FileReadError(#[from] std::io::Error),
+    /// Error parsing the YAML configuration.
+    #[error("Failed to parse YAML configuration: {0}")]
+    YamlParseError(#[from] serde_yaml::Error),
+    /// Validation error with a descriptive message.
+    #[error("Configuration validation error: {0}")]
+    ValidationError(String),
+}
@@ -0,0 +1,251 @@
+//! REST fallback of the order actions.
+//!
+//! The order requests are sent over the authenticated WS API session. While
+//! it is down, the cancels are sent over the REST API instead, so the
+//! strategies can always flatten their risk, and the new orders are rejected.
+//! The degraded mode is flagged in the status region for ctl-admin.
+
+use std::sync::Arc;
+
+use ctl_core::StatusRegion;
+use ctl_rest::{RestClient, RestError};
+use ctl_shm::ShmRegion;
+use serde::Deserialize;
+
+use crate::manager::de_f64_str;
+use crate::{
+    ListOrderStatus, ListStatusUpdate, OmsError, OrderManager, OrderRequest, OrderStatus, OrderUpdate, RequestOutcome,
+};
+
+/// Request weight of the cancel order endpoint.
+const CANCEL_ORDER_WEIGHT: u64 = 1;
+
+/// Request weight of the cancel order list endpoint.
+const CANCEL_ORDER_LIST_WEIGHT: u64 = 1;
+
+/// An order of the cancel order endpoint response.
+/// https://github.com/binance/binance-spot-api-docs/blob/master/rest-api.md#cancel-order-trade
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CanceledOrder {
+    pub symbol: String,
+    /// The client order ID of the canceled order.
+    pub orig_client_order_id: String,
+    pub order_id: u64,
+    #[serde(deserialize_with = "de_f64_str")]
+    pub executed_qty: f64,
+    pub status: OrderStatus,
+}
+
+impl From<&CanceledOrder> for OrderUpdate {
+    fn from(canceled: &CanceledOrder) -> Self {
+        Self {
+            client_order_id: canceled.orig_client_order_id.clone(),
+            order_id: Some(canceled.order_id),
+            status: canceled.status,
+            executed_qty: canceled.executed_qty,
+        }
+    }
+}
+
+/// The cancel order list endpoint response.
+/// https://github.com/binance/binance-spot-api-docs/blob/master/rest-api.md#cancel-order-list-trade
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CanceledOrderList {
+    pub order_list_id: i64,
+    pub list_client_order_id: String,
+    pub list_order_status: ListOrderStatus,
+    /// The legs, as canceled.
+    pub order_reports: Vec<CanceledOrder>,
+}
+
+impl From<&CanceledOrderList> for ListStatusUpdate {
+    fn from(canceled: &CanceledOrderList) -> Self {
+        Self {
+            list_client_order_id: canceled.list_client_order_id.clone(),
+            order_list_id: Some(canceled.order_list_id),
+            status: canceled.list_order_status,
+        }
+    }
+}
+
+/// Where an order request went.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Route {
+    /// To the WS API session, sent only if accepted by the OMS.
+    WsApi(RequestOutcome),
+    /// A cancel sent over the REST API, the OMS being updated from its response.
+    Rest,
+}
+
+/// Routes the order requests to the WS API session, or to the REST API while
+/// it is down.
+pub struct RestFallback {
+    /// The REST client, with the credentials signing the cancels.
+    rest: RestClient,
+    /// The status table the degraded mode is flagged in, if any.
+    status: Option<Arc<ShmRegion<StatusRegion>>>,
+    /// True while the WS API session is down.
+    degraded: bool,
+}
+
+impl RestFallback {
+    /// Creates the fallback over a REST client, the WS API session being up.
+    pub fn new(rest: RestClient) -> Self {
+        Self { rest, status: None, degraded: false }
+    }
+
+    /// Flags the degraded mode in the status table.
+    pub fn with_status(mut self, status: Arc<ShmRegion<StatusRegion>>) -> Self {
+        self.status = Some(status);
+        self
+    }
+
+    /// Returns true while the requests fall back to the REST API.
+    pub fn is_degraded(&self) -> bool {
+        self.degraded
+    }
+
+    /// Records the WS API session going down (or back up), returning false if
+    /// the state didn't change.
+    pub fn on_session(&mut self, connected: bool, now_ms: u64) -> bool {
+        if self.degraded != connected {
+            return false;
+        }
+        self.degraded = !connected;
+        if let Some(status) = &self.status {
+            status.set_degraded(self.degraded, now_ms);
+        }
+        true
+    }
+
+    /// Applies an order request to the OMS, and routes it.
+    ///
+    /// While the WS API session is down, the cancels are sent over the REST API
+    /// and the OMS updated from the response, the orders (or lists) still queued
+    /// by the order rate budget being withdrawn without reaching the exchange.
+    /// A failed REST request is recorded to the OMS, a rate limited one to its
+    /// circuit breaker (see [`OrderManager::on_request_failed`]), the OMS being
+    /// halted if it tripped, with its open orders to cancel.
+    ///
+    /// # Errors
+    /// Returns an error if the OMS rejects the request, a new order (or list)
+    /// is requested while degraded, or the REST request fails.
+    ///
+    /// LATENCY: SLOW_PATH
+    pub fn route(&self, oms: &mut OrderManager, request: OrderRequest, now_ms: u64) -> Result<Route, OmsError> {
+        if !self.degraded {
+            return oms.on_request(request, now_ms).map(Route::WsApi);
+        }
+        match &request {
+            OrderRequest::New(order) | OrderRequest::CancelReplace { order, .. } => {
+                return Err(OmsError::Degraded(order.client_order_id.clone()));
+            }
+            OrderRequest::NewList(list) => return Err(OmsError::Degraded(list.list_client_order_id.clone())),
+            OrderRequest::Cancel { .. } | OrderRequest::CancelList { .. } => {}
+        }
+
+        let outcome = oms.on_request(request.clone(), now_ms)?;
+        if outcome != RequestOutcome::Accepted {
+            return Ok(Route::WsApi(outcome));
+        }
+        match request {
+            OrderRequest::Cancel { client_order_id } => {
+                let symbol = oms
+                    .orders()
+                    .get(&client_order_id)
+                    .map(|order| order.symbol.clone())
+                    .ok_or_else(|| OmsError::UnknownOrder(client_order_id.clone()))?;
+                let query = [("symbol", symbol.as_str()), ("origClientOrderId", client_order_id.as_str())];
+                let canceled: CanceledOrder = self
+                    .rest
+                    .delete_signed("/api/v3/order", &query, CANCEL_ORDER_WEIGHT)
+                    .map_err(|e| on_rest_error(oms, &client_order_id, e, now_ms))?;
+                oms.on_update(OrderUpdate::from(&canceled))?;
+            }
+            OrderRequest::CancelList { list_client_order_id } => {
+                let symbol = oms
+                    .orders()
+                    .get_list(&list_client_order_id)
+                    .map(|list| list.symbol.clone())
+                    .ok_or_else(|| OmsError::UnknownOrderList(list_client_order_id.clone()))?;
+                let query = [("symbol", symbol.as_str()), ("listClientOrderId", list_client_order_id.as_str())];
+                let canceled: CanceledOrderList = self
+                    .rest
+                    .delete_signed("/api/v3/orderList", &query, CANCEL_ORDER_LIST_WEIGHT)
+                    .map_err(|e| on_rest_error(oms, &list_client_order_id, e, now_ms))?;
+                for leg in &canceled.order_reports {
+                    oms.on_update(OrderUpdate::from(leg))?;
+                }
+                oms.on_list_status(ListStatusUpdate::from(&canceled))?;
+            }
+            OrderRequest::New(_) | OrderRequest::CancelReplace { .. } | OrderRequest::NewList(_) => {}
+        }
+        Ok(Route::Rest)
+    }
+}
+
+/// Records a REST request failed by the exchange to the OMS, by the ID it's
+/// reported under, returning its error.
+fn on_rest_error(oms: &mut OrderManager, client_order_id: &str, error: RestError, now_ms: u64) -> OmsError {
+    // The OMS is halted if the breaker tripped, its open orders canceled by the host
+    oms.on_request_failed(client_order_id, error.is_rate_limited(), now_ms);
+    OmsError::RestError(error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{NewOrder, OrderType, Side};
+
+    #[test]
+    fn test_deserialize_canceled_order_list() {
+        let json = r#"{"orderListId":0,"contingencyType":"OCO","listStatusType":"ALL_DONE","listOrderStatus":"ALL_DONE","listClientOrderId":"C3wyj4WVEktd7u9aVBRXcN","transactionTime":1574040868128,"symbol":"LTCBTC","orders":[{"symbol":"LTCBTC","orderId":2,"clientOrderId":"pO9ufTiFGg3nw2fOdgeOXa"},{"symbol":"LTCBTC","orderId":3,"clientOrderId":"TXOvglzXuaubXAaENpaRCB"}],"orderReports":[{"symbol":"LTCBTC","origClientOrderId":"pO9ufTiFGg3nw2fOdgeOXa","orderId":2,"orderListId":0,"clientOrderId":"unfWT8ig8i0uj6lPuYLez6","transactTime":1688005070874,"price":"1.00000000","origQty":"10.00000000","executedQty":"0.00000000","cummulativeQuoteQty":"0.00000000","status":"CANCELED","timeInForce":"GTC","type":"STOP_LOSS_LIMIT","side":"SELL","stopPrice":"1.00000000","selfTradePreventionMode":"NONE"},{"symbol":"LTCBTC","origClientOrderId":"TXOvglzXuaubXAaENpaRCB","orderId":3,"orderListId":0,"clientOrderId":"unfWT8ig8i0uj6lPuYLez6","transactTime":1688005070874,"price":"3.00000000","origQty":"10.00000000","executedQty":"2.50000000","cummulativeQuoteQty":"0.00000000","status":"CANCELED","timeInForce":"GTC","type":"LIMIT_MAKER","side":"SELL","selfTradePreventionMode":"NONE"}]}"#;
+        let canceled: CanceledOrderList = serde_json::from_str(json).unwrap();
+        let update = ListStatusUpdate::from(&canceled);
+        assert_eq!((update.order_list_id, update.status), (Some(0), ListOrderStatus::AllDone));
+
+        let leg = OrderUpdate::from(&canceled.order_reports[1]);
+        assert_eq!(leg.client_order_id, "TXOvglzXuaubXAaENpaRCB");
+        assert_eq!((leg.order_id, leg.status, leg.executed_qty), (Some(3), OrderStatus::Canceled, 2.5));
+    }
+
+    #[test]
+    fn test_degraded_rejects_new_orders() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut oms = OrderManager::open(dir.path().join("orders.journal")).unwrap();
+        let name = format!("ctl_test_status_{}", std::process::id());
+        let status = Arc::new(ShmRegion::<StatusRegion>::create(&name).unwrap());
+        let mut fallback = RestFallback::new(RestClient::new("http://127.0.0.1:9").unwrap()).with_status(status.clone());
+
+        let new = |id: &str| {
+            OrderRequest::New(NewOrder {
+                client_order_id: id.to_string(),
+                symbol: "BTCUSDT".to_string(),
+                side: Side::Sell,
+                order_type: OrderType::Limit,
+                price: 60000.0,
+                qty: 0.01,
+                stop_price: None,
+                time_in_force: None,
+                iceberg_qty: None,
+                self_trade_prevention_mode: None,
+            })
+        };
+        assert_eq!(fallback.route(&mut oms, new("a"), 0).unwrap(), Route::WsApi(RequestOutcome::Accepted));
+
+        assert!(fallback.on_session(false, 1_000));
+        assert!(!fallback.on_session(false, 2_000));
+        assert!(fallback.is_degraded() && status.is_degraded());
+        assert!(matches!(fallback.route(&mut oms, new("b"), 3_000), Err(OmsError::Degraded(_))));
+        assert!(oms.orders().get("b").is_none());
+        assert!(matches!(
+            fallback.route(&mut oms, OrderRequest::Cancel { client_order_id: "x".to_string() }, 3_000),
+            Err(OmsError::UnknownOrder(_))
+        ));
+
+        assert!(fallback.on_session(true, 4_000));
+        assert!(!status.is_degraded());
+    }
+}
@@ -0,0 +1,19 @@
+//! The orders shared by the unit tests of the order table, the journal and
+//! the manager.
+
+use crate::{Order, OrderStatus, Side};
+
+/// A pending buy limit order of `orig_qty` ETHUSDT at 2000.
+pub(crate) fn order(client_order_id: &str, orig_qty: f64) -> Order {
+    Order {
+        client_order_id: client_order_id.to_string(),
+        order_id: None,
+        symbol: "ETHUSDT".to_string(),
+        side: Side::Buy,
+        price: 2000.0,
+        orig_qty,
+        executed_qty: 0.0,
+        status: OrderStatus::PendingNew,
+        list_client_order_id: None,
+    }
+}
@@ -0,0 +1,293 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, ErrorKind, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{ListStatusUpdate, OmsError, Order, OrderList, OrderTable, OrderUpdate};
+
+/// A record of the journal, one JSON object per line.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum JournalRecord {
+    /// An order was submitted (or adopted from the exchange).
+    Submitted(Order),
+    /// An order changed status or was filled.
+    Updated(OrderUpdate),
+    /// An order list was submitted, with its legs.
+    ListSubmitted { list: OrderList, legs: Vec<Order> },
+    /// An order list changed status.
+    ListUpdated(ListStatusUpdate),
+}
+
+/// An append-only journal of the order transitions.
+///
+/// Every record is synced to disk before it is applied, so the order table
+/// replayed from the journal after a crash never lags the state the OMS acted on.
+#[derive(Debug)]
+pub struct Journal {
+    file: File,
+}
+
+impl Journal {
+    /// Opens the journal at `path`, creating it if missing, and replays it into an order table.
+    ///
+    /// A torn last record (a crash in the middle of an append) is discarded.
+    ///
+    /// # Errors
+    /// Returns an error if the file can't be read, or a complete record is corrupt.
+    pub fn open(path: impl AsRef<Path>) -> Result<(Self, OrderTable), OmsError> {
+        let mut file = OpenOptions::new().read(true).append(true).create(true).open(path)?;
+        let mut contents = Vec::new();
+        file.read_to_end(&mut contents)?;
+
+        let (table, valid_len) = replay(&contents)?;
+        if valid_len < contents.len() {
+            file.set_len(valid_len as u64)?;
+            file.sync_data()?;
+        }
+        Ok((Self { file }, table))
+    }
+
+    /// Replays the journal at `path` into an order table without opening it for
+    /// writing, e.g. to list the open orders of a running OMS.
+    ///
+    /// A torn last record (an append in progress) is ignored.
+    ///
+    /// # Errors
+    /// Returns an error if the file can't be read, or a complete record is corrupt.
+    pub fn read(path: impl AsRef<Path>) -> Result<OrderTable, OmsError> {
+        let contents = fs::read(path)?;
+        Ok(replay(&contents)?.0)
+    }
+
+    /// Appends a record and syncs it to disk.
+    ///
+    /// LATENCY: SLOW_PATH
+    pub fn append(&mut self, record: &JournalRecord) -> Result<(), OmsError> {
+        let mut line = serde_json::to_vec(record)?;
+        line.push(b'\n');
+        self.file.write_all(&line)?;
+        self.file.sync_data()?;
+        Ok(())
+    }
+}
+
+/// Replays the journal records into an order table, returning it with the
+/// length of the journal up to the last complete record.
+fn replay(contents: &[u8]) -> Result<(OrderTable, usize), OmsError> {
+    let mut table = OrderTable::default();
+    let mut valid_len = 0;
+
+    for (index, line) in contents.split_inclusive(|&b| b == b'\n').enumerate() {
+        if !line.ends_with(b"\n") {
+            break;
+        }
+        let record = serde_json::from_slice(line)
+            .map_err(|source| OmsError::CorruptJournal { line: index + 1, source })?;
+        match record {
+            JournalRecord::Submitted(order) => table.insert(order)?,
+            JournalRecord::Updated(update) => {
+                table.update(&update)?;
+            }
+            JournalRecord::ListSubmitted { list, legs } => table.insert_list(list, legs)?,
+            JournalRecord::ListUpdated(update) => {
+                table.update_list(&update)?;
+            }
+        }
+        valid_len += line.len();
+    }
+    Ok((table, valid_len))
+}
+
+/// A follower of the records appended to a journal, e.g. by the OMS of
+/// another process.
+///
+/// The tail reads the complete lines appended since its last poll, leaving a
+/// record being appended for the next one, and starts over when the journal
+/// is truncated or replaced by a shorter one.
+#[derive(Debug)]
+pub struct JournalTail {
+    /// The path of the journal.
+    path: PathBuf,
+    /// The length of the journal read so far.
+    offset: u64,
+    /// The start of a record still being appended.
+    partial: Vec<u8>,
+    /// Number of times the journal was read over from its start.
+    resets: u64,
+}
+
+impl JournalTail {
+    /// Follows the journal at `path` from its end, or from its start if
+    /// `from_start`. A missing journal is followed from its creation.
+    ///
+    /// # Errors
+    /// Returns an error if the journal exists but its length cannot be read.
+    pub fn open(path: impl AsRef<Path>, from_start: bool) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let offset = match path.metadata() {
+            Ok(metadata) if !from_start => metadata.len(),
+            Ok(_) => 0,
+            Err(e) if e.kind() == ErrorKind::NotFound => 0,
+            Err(e) => return Err(e),
+        };
+        Ok(Self { path, offset, partial: Vec::new(), resets: 0 })
+    }
+
+    /// Returns the path of the journal.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns the number of times the journal was read over from its start,
+    /// truncated or replaced, the records read before being stale.
+    pub fn resets(&self) -> u64 {
+        self.resets
+    }
+
+    /// Reads the records appended since the last poll, one line each.
+    ///
+    /// # Errors
+    /// Returns an error if the journal cannot be read.
+    pub fn poll(&mut self) -> io::Result<Vec<Vec<u8>>> {
+        let mut file = match File::open(&self.path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e),
+        };
+        let len = file.metadata()?.len();
+        if len < self.offset {
+            // Truncated or replaced, start over
+            self.offset = 0;
+            self.partial.clear();
+            self.resets += 1;
+        }
+        if len == self.offset {
+            return Ok(Vec::new());
+        }
+
+        file.seek(SeekFrom::Start(self.offset))?;
+        let read = file.take(len - self.offset).read_to_end(&mut self.partial)?;
+        self.offset += read as u64;
+
+        let Some(end) = self.partial.iter().rposition(|&b| b == b'\n') else {
+            return Ok(Vec::new());
+        };
+        let rest = self.partial.split_off(end + 1);
+        let complete = std::mem::replace(&mut self.partial, rest);
+        Ok(complete
+            .split(|&b| b == b'\n')
+            .filter(|line| !line.is_empty())
+            .map(<[u8]>::to_vec)
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::order;
+    use crate::OrderStatus;
+
+    fn update(client_order_id: &str, status: OrderStatus, executed_qty: f64) -> JournalRecord {
+        JournalRecord::Updated(OrderUpdate {
+            client_order_id: client_order_id.to_string(),
+            order_id: Some(1),
+            status,
+            executed_qty,
+        })
+    }
+
+    #[test]
+    fn test_replay_after_reopen() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("orders.journal");
+
+        let (mut journal, table) = Journal::open(&path).unwrap();
+        assert!(table.is_empty());
+        journal.append(&JournalRecord::Submitted(order("a", 1.0))).unwrap();
+        journal.append(&JournalRecord::Submitted(order("b", 1.0))).unwrap();
+        journal.append(&update("a", OrderStatus::New, 0.0)).unwrap();
+        journal.append(&update("a", OrderStatus::PartiallyFilled, 0.4)).unwrap();
+        journal.append(&update("b", OrderStatus::Rejected, 0.0)).unwrap();
+        drop(journal);
+
+        let (_, table) = Journal::open(&path).unwrap();
+        assert_eq!(table.len(), 2);
+        let a = table.get("a").unwrap();
+        assert_eq!((a.status, a.executed_qty, a.order_id), (OrderStatus::PartiallyFilled, 0.4, Some(1)));
+        assert_eq!(table.get("b").unwrap().status, OrderStatus::Rejected);
+        assert_eq!(table.open_orders().count(), 1);
+    }
+
+    #[test]
+    fn test_discards_torn_record() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("orders.journal");
+
+        let (mut journal, _) = Journal::open(&path).unwrap();
+        journal.append(&JournalRecord::Submitted(order("a", 1.0))).unwrap();
+        drop(journal);
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(br#"{"type":"updated","client_order_id":"a","#).unwrap();
+        drop(file);
+
+        // Read as is, the torn record left in place for the writer
+        let len = fs::metadata(&path).unwrap().len();
+        assert_eq!(Journal::read(&path).unwrap().get("a").unwrap().status, OrderStatus::PendingNew);
+        assert_eq!(fs::metadata(&path).unwrap().len(), len);
+
+        let (mut journal, table) = Journal::open(&path).unwrap();
+        assert_eq!(table.get("a").unwrap().status, OrderStatus::PendingNew);
+
+        // Records appended after the torn one stay readable
+        journal.append(&update("a", OrderStatus::New, 0.0)).unwrap();
+        drop(journal);
+        let (_, table) = Journal::open(&path).unwrap();
+        assert_eq!(table.get("a").unwrap().status, OrderStatus::New);
+    }
+
+    fn append(path: &Path, data: &str) {
+        OpenOptions::new().create(true).append(true).open(path).unwrap().write_all(data.as_bytes()).unwrap();
+    }
+
+    #[test]
+    fn test_journal_tail() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("oms.journal");
+        append(&path, "{\"type\":\"submitted\"}\n");
+
+        let mut from_end = JournalTail::open(&path, false).unwrap();
+        let mut from_start = JournalTail::open(&path, true).unwrap();
+        assert!(from_end.poll().unwrap().is_empty());
+        assert_eq!(from_start.poll().unwrap(), vec![b"{\"type\":\"submitted\"}".to_vec()]);
+
+        // A record being appended is read once complete
+        append(&path, "{\"type\":\"updated\"}\n{\"type\":");
+        assert_eq!(from_end.poll().unwrap(), vec![b"{\"type\":\"updated\"}".to_vec()]);
+        append(&path, "\"list_updated\"}\n");
+        assert_eq!(from_end.poll().unwrap(), vec![b"{\"type\":\"list_updated\"}".to_vec()]);
+
+        // A truncated journal is read from its start
+        fs::write(&path, "{}\n").unwrap();
+        assert_eq!(from_end.poll().unwrap(), vec![b"{}".to_vec()]);
+        assert_eq!(from_end.resets(), 1);
+    }
+
+    #[test]
+    fn test_tail_missing_journal() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("oms.journal");
+        let mut tail = JournalTail::open(&path, false).unwrap();
+        assert!(tail.poll().unwrap().is_empty());
+        append(&path, "{}\n");
+        assert_eq!(tail.poll().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_corrupt_record() {
+        let contents = b"{\"type\":\"submitted\"}\n";
+        assert!(matches!(replay(contents), Err(OmsError::CorruptJournal { line: 1, .. })));
+    }
+}
@@ -0,0 +1,48 @@
+//! Order management state.
+//!
+//! Tracks each order through its lifecycle on the exchange, journals every
+//! transition to an append-only file before applying it, and reconciles the
+//! replayed state against the exchange's open orders, so the OMS recovers its
+//! state after a crash. The strategies drive it with order requests, a
+//! cancel-replace requoting an order in a single round trip, and order lists (OCO,
+//! OTO, OTOCO) linking the legs of a bracket. The orders placed are budgeted
+//! per the exchange's order rate limits, in a shared region, the requests
+//! beyond them being queued or rejected. While the WS API session is down, the
+//! cancels fall back to the REST API. Sustained error rates trip a circuit
+//! breaker, halting trading until reset by an operator. In paper mode the
+//! orders are filled by a simulated exchange fed with the live market data
+//! instead. The requests decided on a traced message record their submission
+//! as a span of its trace. The operators enter manual orders through the
+//! order entry ring, checked like those of the strategies.
+
+mod entry;
+mod errors;
+mod fallback;
+#[cfg(test)]
+mod fixtures;
+mod journal;
+mod list;
+mod manager;
+mod order;
+mod paper;
+mod rate;
+mod request;
+mod rules;
+
+pub use entry::{
+    EntryAction, OrderEntryMessage, ENTRY_OPERATOR_SIZE, ENTRY_SYMBOL_SIZE, ORDER_ENTRY_RING_NAME,
+    ORDER_ENTRY_RING_SIZE,
+};
+pub use errors::{OmsError, PaperConfigError};
+pub use fallback::{CanceledOrder, CanceledOrderList, RestFallback, Route};
+pub use journal::{Journal, JournalRecord, JournalTail};
+pub use list::{ListOrderStatus, ListStatusUpdate, OrderList, OrderListKind};
+pub use manager::{OpenOrder, OrderManager, Reconciliation, RequestOutcome};
+pub use order::{Order, OrderStatus, OrderTable, OrderUpdate, Side};
+pub use paper::{ExecutionType, OmsMode, PaperConfig, PaperExchange, PaperReport, Quote};
+pub use rate::{
+    declare_c_types, OrderRateLedger, RateLimitCount, Throttle, ORDER_COUNT_10S_HEADER, ORDER_COUNT_1D_HEADER,
+    ORDER_RATE_REGION_NAME,
+};
+pub use request::{NewOrder, NewOrderList, OrderRequest};
+pub use rules::{ExchangeRules, OrderType, StpMode, SymbolRules, TimeInForce};
@@ -0,0 +1,182 @@
+use serde::{Deserialize, Serialize};
+
+use crate::OmsError;
+
+/// The contingency linking the legs of an order list.
+/// https://github.com/binance/binance-spot-api-docs/blob/master/enums.md#contingencytype
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum OrderListKind {
+    /// One-cancels-the-other: two legs, the fill of one expiring the other.
+    Oco,
+    /// One-triggers-the-other: a working leg, and a pending leg placed once it is filled.
+    Oto,
+    /// One-triggers-a-one-cancels-the-other: a working leg, and a pending OCO
+    /// pair placed once it is filled.
+    Otoco,
+}
+
+impl OrderListKind {
+    /// Returns the number of legs of the lists of this kind.
+    pub fn num_legs(self) -> usize {
+        match self {
+            Self::Oco | Self::Oto => 2,
+            Self::Otoco => 3,
+        }
+    }
+
+    /// Returns the index of the first leg of the OCO pair, if the kind has one.
+    pub fn oco_pair(self) -> Option<usize> {
+        match self {
+            Self::Oco => Some(0),
+            Self::Oto => None,
+            Self::Otoco => Some(1),
+        }
+    }
+}
+
+/// The status of an order list.
+/// https://github.com/binance/binance-spot-api-docs/blob/master/enums.md#order-list-order-status
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ListOrderStatus {
+    /// Submitted, not yet acknowledged by the exchange.
+    Pending,
+    /// Accepted by the exchange, some legs still working or pending.
+    Executing,
+    /// All the legs are done (filled, canceled or expired).
+    AllDone,
+    /// Rejected by the exchange.
+    Reject,
+}
+
+impl ListOrderStatus {
+    /// Returns true if the list can no longer change.
+    pub fn is_terminal(self) -> bool {
+        matches!(self, Self::AllDone | Self::Reject)
+    }
+
+    /// Returns true if the list lifecycle allows moving from `self` to `next`.
+    pub fn can_transition_to(self, next: Self) -> bool {
+        use ListOrderStatus::*;
+        match self {
+            Pending => next != Pending,
+            Executing => next == AllDone,
+            AllDone | Reject => false,
+        }
+    }
+}
+
+/// An order list tracked by the OMS, its legs being tracked as orders.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OrderList {
+    /// The client order list ID, unique across the lists tracked.
+    pub list_client_order_id: String,
+    /// The exchange order list ID, once acknowledged.
+    pub order_list_id: Option<i64>,
+    /// The contingency of the legs.
+    pub kind: OrderListKind,
+    /// The symbol of the legs.
+    pub symbol: String,
+    /// The client order IDs of the legs, the working leg first.
+    pub legs: Vec<String>,
+    /// The current status.
+    pub status: ListOrderStatus,
+}
+
+/// A change of the status of an order list, from a list status event.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ListStatusUpdate {
+    /// The client order list ID of the list.
+    pub list_client_order_id: String,
+    /// The exchange order list ID, if known.
+    pub order_list_id: Option<i64>,
+    /// The new status.
+    pub status: ListOrderStatus,
+}
+
+/// A list status payload of the user data stream.
+/// https://github.com/binance/binance-spot-api-docs/blob/master/user-data-stream.md#order-update
+#[derive(Debug, Deserialize)]
+struct ListStatusEvent<'a> {
+    #[serde(rename = "e")]
+    event_type: &'a str,
+    #[serde(rename = "g")]
+    order_list_id: i64,
+    #[serde(rename = "L")]
+    list_order_status: ListOrderStatus,
+    #[serde(rename = "C")]
+    list_client_order_id: String,
+}
+
+impl ListStatusUpdate {
+    /// Parses a list status payload, `None` if it isn't one.
+    pub fn from_json(data: &[u8]) -> Option<Self> {
+        let event: ListStatusEvent = serde_json::from_slice(data).ok()?;
+        (event.event_type == "listStatus").then(|| Self {
+            list_client_order_id: event.list_client_order_id,
+            order_list_id: Some(event.order_list_id),
+            status: event.list_order_status,
+        })
+    }
+}
+
+impl OrderList {
+    /// Checks that `update` is a valid transition of the list, returning false
+    /// if it leaves the list unchanged (e.g. a duplicated list status).
+    ///
+    /// # Errors
+    /// Returns an error if the lifecycle doesn't allow the transition.
+    pub fn check(&self, update: &ListStatusUpdate) -> Result<bool, OmsError> {
+        if update.status == self.status {
+            return Ok(self.order_list_id.is_none() && update.order_list_id.is_some());
+        }
+        if !self.status.can_transition_to(update.status) {
+            return Err(OmsError::InvalidListTransition {
+                list_client_order_id: self.list_client_order_id.clone(),
+                from: self.status,
+                to: update.status,
+            });
+        }
+        Ok(true)
+    }
+
+    /// Applies a checked update to the list.
+    pub(crate) fn apply(&mut self, update: &ListStatusUpdate) {
+        self.status = update.status;
+        if update.order_list_id.is_some() {
+            self.order_list_id = update.order_list_id;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_list_transitions() {
+        use ListOrderStatus::*;
+        assert!(Pending.can_transition_to(Executing));
+        assert!(Pending.can_transition_to(Reject));
+        assert!(Executing.can_transition_to(AllDone));
+        assert!(!Executing.can_transition_to(Reject));
+        assert!(AllDone.is_terminal() && Reject.is_terminal());
+        assert!(!AllDone.can_transition_to(Executing));
+        assert_eq!(OrderListKind::Otoco.num_legs(), 3);
+        assert_eq!(OrderListKind::Otoco.oco_pair(), Some(1));
+    }
+
+    #[test]
+    fn test_parse_list_status() {
+        let json = br#"{"e":"listStatus","E":1564035303637,"s":"ETHBTC","g":2,"c":"OCO","l":"EXEC_STARTED","L":"EXECUTING","r":"NONE","C":"F4QN4G8DlFATFlIUQ0cjdD","T":1564035303625,"O":[{"s":"ETHBTC","i":17,"c":"AJYsMjErWJesZvqlJCTUgL"},{"s":"ETHBTC","i":18,"c":"bfYPSQdLoqAJeNrOr9adzq"}]}"#;
+        let update = ListStatusUpdate::from_json(json).unwrap();
+        assert_eq!(update.list_client_order_id, "F4QN4G8DlFATFlIUQ0cjdD");
+        assert_eq!(update.order_list_id, Some(2));
+        assert_eq!(update.status, ListOrderStatus::Executing);
+
+        let done = br#"{"e":"listStatus","g":2,"c":"OCO","l":"ALL_DONE","L":"ALL_DONE","C":"F4QN4G8DlFATFlIUQ0cjdD"}"#;
+        assert_eq!(ListStatusUpdate::from_json(done).unwrap().status, ListOrderStatus::AllDone);
+        assert!(ListStatusUpdate::from_json(br#"{"e":"executionReport","g":2,"L":"ALL_DONE","C":"x"}"#).is_none());
+    }
+}
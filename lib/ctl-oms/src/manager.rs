@@ -0,0 +1,1040 @@
+use std::collections::VecDeque;
+use std::path::Path;
+use std::sync::Arc;
+
+use ctl_core::{BreakerSignal, CircuitBreaker, ControlCommand, ControlMessage, TraceContext, Tracer};
+use ctl_shm::ShmRegion;
+use hashbrown::HashMap;
+use serde::{Deserialize, Deserializer};
+
+use crate::{
+    ExchangeRules, Journal, JournalRecord, ListStatusUpdate, NewOrder, NewOrderList, OmsError, Order,
+    OrderEntryMessage, OrderRateLedger, OrderRequest, OrderStatus, OrderTable, OrderUpdate, Side, Throttle,
+};
+
+/// An order of the current open orders endpoint response.
+/// https://github.com/binance/binance-spot-api-docs/blob/master/rest-api.md#current-open-orders-user_data
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OpenOrder {
+    pub symbol: String,
+    pub order_id: u64,
+    pub client_order_id: String,
+    #[serde(deserialize_with = "de_f64_str")]
+    pub price: f64,
+    #[serde(deserialize_with = "de_f64_str")]
+    pub orig_qty: f64,
+    #[serde(deserialize_with = "de_f64_str")]
+    pub executed_qty: f64,
+    pub status: OrderStatus,
+    pub side: Side,
+}
+
+/// Deserializes a decimal sent as a JSON string.
+pub(crate) fn de_f64_str<'de, D: Deserializer<'de>>(deserializer: D) -> Result<f64, D::Error> {
+    let value = String::deserialize(deserializer)?;
+    value.parse().map_err(serde::de::Error::custom)
+}
+
+impl From<&OpenOrder> for Order {
+    fn from(open: &OpenOrder) -> Self {
+        Self {
+            client_order_id: open.client_order_id.clone(),
+            order_id: Some(open.order_id),
+            symbol: open.symbol.clone(),
+            side: open.side,
+            price: open.price,
+            orig_qty: open.orig_qty,
+            executed_qty: open.executed_qty,
+            status: open.status,
+            list_client_order_id: None,
+        }
+    }
+}
+
+/// The outcome of reconciling an order against the exchange's open orders.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Reconciliation {
+    /// An order open on the exchange but missing from the journal was adopted.
+    Adopted(String),
+    /// An open order progressed on the exchange while the OMS was down.
+    Updated(String),
+    /// An order open in the journal is no longer open on the exchange. Its final
+    /// status must be queried and applied with [`OrderManager::on_update`]; an
+    /// order the exchange doesn't know of was never sent and should be rejected.
+    Missing(String),
+}
+
+/// The outcome of an order request accepted by the OMS.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RequestOutcome {
+    /// The request was applied, and is to be sent to the exchange.
+    Accepted,
+    /// The request is held back by the order rate budget, until released by
+    /// [`OrderManager::release_throttled`].
+    Queued,
+    /// The request canceled an order (or list) still queued, dropped without
+    /// reaching the exchange.
+    Withdrawn,
+}
+
+/// Tracks the orders through their lifecycle, journaling every transition
+/// before applying it.
+pub struct OrderManager {
+    journal: Journal,
+    orders: OrderTable,
+    /// True while trading is halted by the kill switch.
+    halted: bool,
+    /// The order rules of the symbols the requested orders are checked against, if any.
+    rules: Option<ExchangeRules>,
+    /// The shared order rate budget the new orders are reserved in, if any.
+    rate: Option<Arc<ShmRegion<OrderRateLedger>>>,
+    /// What happens to the requests beyond the order rate budget.
+    throttle: Throttle,
+    /// The requests held back by the order rate budget, in arrival order.
+    throttled: VecDeque<OrderRequest>,
+    /// The circuit breaker the errors are recorded to, if any.
+    breaker: Option<Arc<CircuitBreaker>>,
+    /// Time allowed for the execution report of a request sent, if checked.
+    report_timeout_ms: Option<u64>,
+    /// The deadlines of the execution reports awaited, by the ID the requests are reported under.
+    reports_due: HashMap<String, u64>,
+    /// The tracer of the submissions of the traced requests, if any.
+    tracer: Option<Tracer>,
+}
+
+impl std::fmt::Debug for OrderManager {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("OrderManager")
+            .field("journal", &self.journal)
+            .field("orders", &self.orders)
+            .field("halted", &self.halted)
+            .field("rules", &self.rules)
+            .field("throttle", &self.throttle)
+            .field("throttled", &self.throttled)
+            .field("breaker", &self.breaker)
+            .field("report_timeout_ms", &self.report_timeout_ms)
+            .field("reports_due", &self.reports_due)
+            .field("tracer", &self.tracer)
+            .finish_non_exhaustive()
+    }
+}
+
+impl OrderManager {
+    /// Opens the OMS over the journal at `path`, recovering the orders it recorded.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, OmsError> {
+        let (journal, orders) = Journal::open(path)?;
+        Ok(Self {
+            journal,
+            orders,
+            halted: false,
+            rules: None,
+            rate: None,
+            throttle: Throttle::default(),
+            throttled: VecDeque::new(),
+            breaker: None,
+            report_timeout_ms: None,
+            reports_due: HashMap::new(),
+            tracer: None,
+        })
+    }
+
+    /// Checks the order requests against the order rules of the symbols.
+    pub fn with_rules(mut self, rules: ExchangeRules) -> Self {
+        self.rules = Some(rules);
+        self
+    }
+
+    /// Reserves the orders of the requests in the shared order rate budget,
+    /// queueing or rejecting those beyond it.
+    pub fn with_rate_budget(mut self, rate: Arc<ShmRegion<OrderRateLedger>>, throttle: Throttle) -> Self {
+        self.rate = Some(rate);
+        self.throttle = throttle;
+        self
+    }
+
+    /// Records the errors to a circuit breaker, halting trading once it trips.
+    pub fn with_breaker(mut self, breaker: Arc<CircuitBreaker>) -> Self {
+        self.breaker = Some(breaker);
+        self
+    }
+
+    /// Expects the execution report (or list status) of each request sent
+    /// within `timeout_ms`, the late ones recorded to the circuit breaker by
+    /// [`OrderManager::check_reports`].
+    pub fn with_report_deadline(mut self, timeout_ms: u64) -> Self {
+        self.report_timeout_ms = Some(timeout_ms);
+        self
+    }
+
+    /// Records the submissions of the traced requests as the `oms.submit` span.
+    pub fn with_tracer(mut self, tracer: Tracer) -> Self {
+        self.tracer = Some(tracer);
+        self
+    }
+
+    /// Returns the orders tracked.
+    pub fn orders(&self) -> &OrderTable {
+        &self.orders
+    }
+
+    /// Returns true while trading is halted.
+    pub fn is_halted(&self) -> bool {
+        self.halted
+    }
+
+    /// Halts trading: new orders are rejected until [`OrderManager::resume`].
+    /// Returns the symbols with open orders, to cancel on the exchange.
+    pub fn halt(&mut self) -> Vec<String> {
+        self.halted = true;
+        let mut symbols: Vec<_> = self.orders.open_orders().map(|order| order.symbol.clone()).collect();
+        symbols.sort();
+        symbols.dedup();
+        symbols
+    }
+
+    /// Resumes trading after a halt.
+    pub fn resume(&mut self) {
+        self.halted = false;
+    }
+
+    /// Records an error of the order entry (e.g. a rate limit ban, or an
+    /// execution report not received in time) to the circuit breaker.
+    /// Returns the symbols with open orders to cancel if it tripped, trading
+    /// being halted until the breaker is reset by an operator.
+    pub fn on_error(&mut self, signal: BreakerSignal, now_ms: u64) -> Vec<String> {
+        if self.breaker.as_ref().is_some_and(|breaker| breaker.record(signal, now_ms)) {
+            return self.halt();
+        }
+        Vec::new()
+    }
+
+    /// Records a request the exchange failed, over the WS API or the REST
+    /// API, by the ID it's reported under: no execution report is awaited for
+    /// it, and a rejection for exceeding the rate limits (-1003, HTTP 418/429,
+    /// see `WSResponse::is_rate_limited` and `RestError::is_rate_limited`) is
+    /// recorded to the circuit breaker, see [`OrderManager::on_error`].
+    pub fn on_request_failed(&mut self, client_order_id: &str, rate_limited: bool, now_ms: u64) -> Vec<String> {
+        self.reports_due.remove(client_order_id);
+        if !rate_limited {
+            return Vec::new();
+        }
+        self.on_error(BreakerSignal::RateLimitBan, now_ms)
+    }
+
+    /// Records the requests whose execution report (or list status) is past
+    /// its deadline to the circuit breaker, no longer awaiting them. Returns
+    /// the symbols with open orders to cancel if it tripped, see
+    /// [`OrderManager::on_error`].
+    ///
+    /// LATENCY: SLOW_PATH
+    pub fn check_reports(&mut self, now_ms: u64) -> Vec<String> {
+        let mut late = 0;
+        self.reports_due.retain(|_, due_ms| {
+            let on_time = *due_ms > now_ms;
+            late += usize::from(!on_time);
+            on_time
+        });
+        let mut symbols = Vec::new();
+        for _ in 0..late {
+            symbols.extend(self.on_error(BreakerSignal::ExecutionReportTimeout, now_ms));
+        }
+        symbols
+    }
+
+    /// Applies a command of the control ring, returning the symbols with open
+    /// orders to cancel on a halt.
+    pub fn on_control(&mut self, message: &ControlMessage) -> Vec<String> {
+        match message.command() {
+            ControlCommand::Halt => self.halt(),
+            ControlCommand::Resume => {
+                self.resume();
+                Vec::new()
+            }
+            // Market data commands, applied by ctl-md-handler, and strategy commands, by the strategy hosts
+            ControlCommand::PauseFeed
+            | ControlCommand::ResumeFeed
+            | ControlCommand::LoadStrategy
+            | ControlCommand::UnloadStrategy
+            | ControlCommand::SwapStrategy
+            | ControlCommand::Unknown => Vec::new(),
+        }
+    }
+
+    /// Records a new order, before it is sent to the exchange.
+    ///
+    /// # Errors
+    /// Returns an error if trading is halted, its client order ID is already
+    /// in use, or the journal can't be written.
+    pub fn submit(&mut self, order: Order) -> Result<(), OmsError> {
+        if self.halted {
+            return Err(OmsError::Halted(order.client_order_id));
+        }
+        self.record(order)
+    }
+
+    /// Records the new order of a cancel-replace, before the request is sent
+    /// to the exchange.
+    ///
+    /// The canceled order is updated by its execution report like on a cancel.
+    /// The new order is rejected with [`OrderManager::on_update`] if the
+    /// exchange didn't place it (e.g. in `STOP_ON_FAILURE` mode, the cancel
+    /// having failed).
+    ///
+    /// # Errors
+    /// Returns an error if trading is halted, the order to cancel isn't
+    /// tracked, is a leg of an order list or is on another symbol, the new
+    /// client order ID is already in use, or the journal can't be written.
+    pub fn cancel_replace(&mut self, cancel_client_order_id: &str, order: Order) -> Result<(), OmsError> {
+        if self.halted {
+            return Err(OmsError::Halted(order.client_order_id));
+        }
+        let canceled = self
+            .orders
+            .get(cancel_client_order_id)
+            .ok_or_else(|| OmsError::UnknownOrder(cancel_client_order_id.to_string()))?;
+        if canceled.list_client_order_id.is_some() {
+            return Err(OmsError::ListLeg(cancel_client_order_id.to_string()));
+        }
+        if canceled.symbol != order.symbol {
+            return Err(OmsError::CancelReplaceSymbol {
+                client_order_id: cancel_client_order_id.to_string(),
+                symbol: canceled.symbol.clone(),
+                new_symbol: order.symbol,
+            });
+        }
+        self.record(order)
+    }
+
+    /// Records a new order list and its legs, before it is sent to the exchange.
+    ///
+    /// The legs are updated by their execution reports: the leg of an OCO pair
+    /// left when the other fills expires, and the pending legs of an OTO stay
+    /// `PendingNew` until the working leg fills. The list itself is updated by
+    /// its list status events with [`OrderManager::on_list_status`].
+    ///
+    /// # Errors
+    /// Returns an error if trading is halted, the legs don't match the
+    /// contingency or the rules of the symbol, the list or a leg ID is
+    /// already in use, or the journal can't be written.
+    pub fn submit_list(&mut self, request: NewOrderList) -> Result<(), OmsError> {
+        if self.halted {
+            return Err(OmsError::Halted(request.list_client_order_id));
+        }
+        request.check()?;
+        for leg in &request.legs {
+            self.check_order(leg)?;
+        }
+        let (list, legs) = request.into_list();
+        self.orders.check_insert_list(&list, &legs)?;
+        self.journal.append(&JournalRecord::ListSubmitted { list: list.clone(), legs: legs.clone() })?;
+        self.orders.insert_list(list, legs)
+    }
+
+    /// Applies a list status event to an order list, returning false if it
+    /// left the list unchanged. A rejected list rejects its legs not yet
+    /// acknowledged, the exchange not reporting them.
+    ///
+    /// # Errors
+    /// Returns an error if the list isn't tracked, the transition is invalid,
+    /// or the journal can't be written.
+    pub fn on_list_status(&mut self, update: ListStatusUpdate) -> Result<bool, OmsError> {
+        self.reports_due.remove(&update.list_client_order_id);
+        if !self.orders.check_list_update(&update)? {
+            return Ok(false);
+        }
+        self.journal.append(&JournalRecord::ListUpdated(update.clone()))?;
+        self.orders.update_list(&update)
+    }
+
+    /// Applies an order request of a strategy, before it is sent to the exchange.
+    ///
+    /// The new orders are checked against the rules of their symbol, if set,
+    /// and reserved in the order rate budget, if set: a request beyond it is
+    /// rejected or queued by the [`Throttle`], and checked once released, the
+    /// requests queued after it keeping their order. Cancels aren't budgeted,
+    /// a cancel of a queued order (or list) withdrawing it. Cancels are
+    /// accepted while halted, and journaled with their execution report (or
+    /// list status).
+    ///
+    /// # Errors
+    /// Returns an error if the request is beyond the order rate budget and
+    /// rejected, an order doesn't match the rules of its symbol, or the request
+    /// can't be submitted, see [`OrderManager::submit`],
+    /// [`OrderManager::cancel_replace`] and [`OrderManager::submit_list`].
+    pub fn on_request(&mut self, request: OrderRequest, now_ms: u64) -> Result<RequestOutcome, OmsError> {
+        if self.withdraw(&request) {
+            return Ok(RequestOutcome::Withdrawn);
+        }
+        let orders = request.num_orders();
+        // Requests rejected while halted reserve nothing
+        let Some(rate) = self.rate.clone().filter(|_| orders > 0 && !self.halted) else {
+            return self.apply(request, now_ms).map(|()| RequestOutcome::Accepted);
+        };
+        if self.throttle == Throttle::Queue && !self.throttled.is_empty() {
+            return Ok(self.enqueue(request, &rate));
+        }
+        match rate.try_reserve(orders, now_ms) {
+            Ok(()) => self.apply(request, now_ms).map(|()| RequestOutcome::Accepted),
+            Err(_) if self.throttle == Throttle::Queue => Ok(self.enqueue(request, &rate)),
+            Err(err) => {
+                rate.record_rejected();
+                Err(err)
+            }
+        }
+    }
+
+    /// Applies an order request decided on a traced message, see
+    /// [`OrderManager::on_request`], recording its submission as the
+    /// `oms.submit` span, a child of `trace`, the span of the decision.
+    ///
+    /// # Errors
+    /// Returns the errors of [`OrderManager::on_request`].
+    pub fn on_traced_request(
+        &mut self,
+        request: OrderRequest,
+        trace: TraceContext,
+        now_ms: u64,
+    ) -> Result<RequestOutcome, OmsError> {
+        let start_ns = self.tracer.as_ref().filter(|_| trace.is_traced()).map(|_| ctl_time::now_ns());
+        let orders = request.num_orders();
+        let outcome = self.on_request(request, now_ms);
+        if let (Some(tracer), Some(start_ns)) = (&self.tracer, start_ns) {
+            tracer.record("oms.submit", trace, start_ns, &[("num_orders", orders)]);
+        }
+        outcome
+    }
+
+    /// Applies an order entered manually by an operator, see
+    /// [`OrderManager::on_request`]: a manual order is checked and budgeted
+    /// like the orders of the strategy. The entries of the ring addressed to
+    /// other components are for the host to skip.
+    ///
+    /// # Errors
+    /// Returns an error if the entry is malformed, or the errors of
+    /// [`OrderManager::on_request`].
+    pub fn on_entry(&mut self, entry: &OrderEntryMessage, now_ms: u64) -> Result<RequestOutcome, OmsError> {
+        self.on_request(entry.request()?, now_ms)
+    }
+
+    /// Returns the number of requests held back by the order rate budget.
+    pub fn num_throttled(&self) -> usize {
+        self.throttled.len()
+    }
+
+    /// Releases the queued requests the order rate budget now allows, in
+    /// arrival order, with the outcome of applying each: the accepted ones
+    /// are to be sent to the exchange. The queue is drained while halted,
+    /// the requests being rejected.
+    pub fn release_throttled(&mut self, now_ms: u64) -> Vec<(OrderRequest, Result<(), OmsError>)> {
+        let mut released = Vec::new();
+        let Some(rate) = self.rate.clone() else {
+            return released;
+        };
+        while let Some(request) = self.throttled.front() {
+            if !self.halted && rate.try_reserve(request.num_orders(), now_ms).is_err() {
+                break;
+            }
+            let Some(request) = self.throttled.pop_front() else {
+                break;
+            };
+            let result = self.apply(request.clone(), now_ms);
+            released.push((request, result));
+        }
+        rate.set_queued(self.throttled.len() as u64);
+        released
+    }
+
+    /// Queues a request beyond the order rate budget.
+    fn enqueue(&mut self, request: OrderRequest, rate: &OrderRateLedger) -> RequestOutcome {
+        self.throttled.push_back(request);
+        rate.set_queued(self.throttled.len() as u64);
+        RequestOutcome::Queued
+    }
+
+    /// Drops the queued new order (or list) a cancel targets, returning true if any.
+    fn withdraw(&mut self, request: &OrderRequest) -> bool {
+        let position = self.throttled.iter().position(|queued| match (request, queued) {
+            (OrderRequest::Cancel { client_order_id }, OrderRequest::New(order)) => {
+                order.client_order_id == *client_order_id
+            }
+            (OrderRequest::CancelList { list_client_order_id }, OrderRequest::NewList(list)) => {
+                list.list_client_order_id == *list_client_order_id
+            }
+            _ => false,
+        });
+        let Some(position) = position else {
+            return false;
+        };
+        self.throttled.remove(position);
+        if let Some(rate) = &self.rate {
+            rate.set_queued(self.throttled.len() as u64);
+        }
+        true
+    }
+
+    /// Applies a request within the order rate budget, awaiting its execution
+    /// report once accepted if the reports are checked.
+    fn apply(&mut self, request: OrderRequest, now_ms: u64) -> Result<(), OmsError> {
+        let awaited = self
+            .report_timeout_ms
+            .map(|timeout_ms| (request.client_order_id().to_string(), now_ms + timeout_ms));
+        match request {
+            OrderRequest::New(order) => {
+                self.check_order(&order)?;
+                self.submit(order.into())
+            }
+            OrderRequest::Cancel { client_order_id } => match self.orders.get(&client_order_id) {
+                Some(_) => Ok(()),
+                None => Err(OmsError::UnknownOrder(client_order_id)),
+            },
+            OrderRequest::CancelReplace { cancel_client_order_id, order, .. } => {
+                self.check_order(&order)?;
+                self.cancel_replace(&cancel_client_order_id, order.into())
+            }
+            OrderRequest::NewList(list) => self.submit_list(list),
+            OrderRequest::CancelList { list_client_order_id } => match self.orders.get_list(&list_client_order_id) {
+                Some(_) => Ok(()),
+                None => Err(OmsError::UnknownOrderList(list_client_order_id)),
+            },
+        }?;
+        if let Some((client_order_id, due_ms)) = awaited {
+            self.reports_due.insert(client_order_id, due_ms);
+        }
+        Ok(())
+    }
+
+    /// Checks a requested order against the rules of its symbol, if set.
+    fn check_order(&self, order: &NewOrder) -> Result<(), OmsError> {
+        match &self.rules {
+            Some(rules) => rules.check(order),
+            None => Ok(()),
+        }
+    }
+
+    /// Journals and inserts an order.
+    fn record(&mut self, order: Order) -> Result<(), OmsError> {
+        self.orders.check_insert(&order)?;
+        self.journal.append(&JournalRecord::Submitted(order.clone()))?;
+        self.orders.insert(order)
+    }
+
+    /// Applies an execution report or queried status to an order, returning
+    /// false if it left the order unchanged. Unchanged orders aren't journaled.
+    ///
+    /// # Errors
+    /// Returns an error if the order isn't tracked, the transition is invalid,
+    /// or the journal can't be written.
+    pub fn on_update(&mut self, update: OrderUpdate) -> Result<bool, OmsError> {
+        self.reports_due.remove(&update.client_order_id);
+        if !self.orders.check_update(&update)? {
+            return Ok(false);
+        }
+        self.journal.append(&JournalRecord::Updated(update.clone()))?;
+        self.orders.update(&update)
+    }
+
+    /// Reconciles the recovered orders against the exchange's open orders,
+    /// applying the changes missed while the OMS was down.
+    ///
+    /// # Errors
+    /// Returns an error if an update is invalid, or the journal can't be written.
+    pub fn reconcile(&mut self, open_orders: &[OpenOrder]) -> Result<Vec<Reconciliation>, OmsError> {
+        let mut outcomes = Vec::new();
+
+        for open in open_orders {
+            if self.orders.get(&open.client_order_id).is_none() {
+                self.record(Order::from(open))?;
+                outcomes.push(Reconciliation::Adopted(open.client_order_id.clone()));
+                continue;
+            }
+            let update = OrderUpdate {
+                client_order_id: open.client_order_id.clone(),
+                order_id: Some(open.order_id),
+                status: open.status,
+                executed_qty: open.executed_qty,
+            };
+            if self.on_update(update)? {
+                outcomes.push(Reconciliation::Updated(open.client_order_id.clone()));
+            }
+        }
+
+        let mut missing: Vec<_> = self
+            .orders
+            .open_orders()
+            .filter(|order| !open_orders.iter().any(|o| o.client_order_id == order.client_order_id))
+            .map(|order| order.client_order_id.clone())
+            .collect();
+        missing.sort();
+        outcomes.extend(missing.into_iter().map(Reconciliation::Missing));
+
+        Ok(outcomes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::order;
+    use crate::{ListOrderStatus, OrderListKind, OrderType, StpMode, TimeInForce};
+
+    fn open_order(client_order_id: &str, status: &str, executed_qty: &str) -> OpenOrder {
+        serde_json::from_str(&format!(
+            r#"{{"symbol":"ETHUSDT","orderId":9,"orderListId":-1,"clientOrderId":"{}","price":"2000.00000000","origQty":"1.00000000","executedQty":"{}","cummulativeQuoteQty":"0.00000000","status":"{}","timeInForce":"GTC","type":"LIMIT","side":"BUY"}}"#,
+            client_order_id, executed_qty, status
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_deserialize_open_order() {
+        let open = open_order("a", "PARTIALLY_FILLED", "0.25000000");
+        assert_eq!(open.order_id, 9);
+        assert_eq!(open.price, 2000.0);
+        assert_eq!(open.executed_qty, 0.25);
+        assert_eq!(open.status, OrderStatus::PartiallyFilled);
+        assert_eq!(open.side, Side::Buy);
+    }
+
+    #[test]
+    fn test_halt_rejects_new_orders() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut oms = OrderManager::open(dir.path().join("orders.journal")).unwrap();
+        oms.submit(order("a", 1.0)).unwrap();
+        oms.submit(Order { symbol: "BTCUSDT".to_string(), ..order("b", 1.0) }).unwrap();
+        oms.submit(order("c", 1.0)).unwrap();
+
+        let halt = ControlMessage::new(ControlCommand::Halt, 0, "test");
+        assert_eq!(oms.on_control(&halt), vec!["BTCUSDT".to_string(), "ETHUSDT".to_string()]);
+        assert!(oms.is_halted());
+        assert!(matches!(oms.submit(order("d", 1.0)), Err(OmsError::Halted(_))));
+
+        // Open orders still progress while halted, e.g. to their cancellation
+        let cancel = OrderUpdate {
+            client_order_id: "a".to_string(),
+            order_id: Some(1),
+            status: OrderStatus::Canceled,
+            executed_qty: 0.0,
+        };
+        assert!(oms.on_update(cancel).unwrap());
+
+        oms.on_control(&ControlMessage::new(ControlCommand::Resume, 0, ""));
+        assert!(oms.submit(order("d", 1.0)).is_ok());
+    }
+
+    #[test]
+    fn test_breaker_halts_on_error_rate() {
+        let dir = tempfile::tempdir().unwrap();
+        let name = format!("ctl_test_oms_breaker_{}", std::process::id());
+        let status = Arc::new(ShmRegion::<ctl_core::StatusRegion>::create(&name).unwrap());
+        let config = ctl_core::BreakerConfig {
+            execution_report_timeout: ctl_core::BreakerThreshold { max_errors: 2, window_ms: 60_000 },
+            ..Default::default()
+        };
+        let mut oms = OrderManager::open(dir.path().join("orders.journal"))
+            .unwrap()
+            .with_breaker(Arc::new(CircuitBreaker::new(config, status.clone())));
+        oms.submit(order("a", 1.0)).unwrap();
+
+        assert!(oms.on_error(BreakerSignal::ExecutionReportTimeout, 1_000).is_empty());
+        assert!(!oms.is_halted());
+        assert_eq!(oms.on_error(BreakerSignal::ExecutionReportTimeout, 2_000), vec!["ETHUSDT".to_string()]);
+        assert!(oms.is_halted() && status.is_tripped() && status.is_halted());
+        assert!(matches!(oms.submit(order("b", 1.0)), Err(OmsError::Halted(_))));
+    }
+
+    #[test]
+    fn test_breaker_trips_on_rate_limits() {
+        let dir = tempfile::tempdir().unwrap();
+        let name = format!("ctl_test_oms_rate_limits_{}", std::process::id());
+        let status = Arc::new(ShmRegion::<ctl_core::StatusRegion>::create(&name).unwrap());
+        let config = ctl_core::BreakerConfig {
+            rate_limit_ban: ctl_core::BreakerThreshold { max_errors: 2, window_ms: 60_000 },
+            ..Default::default()
+        };
+        let mut oms = OrderManager::open(dir.path().join("orders.journal"))
+            .unwrap()
+            .with_breaker(Arc::new(CircuitBreaker::new(config, status.clone())));
+        oms.on_request(OrderRequest::New(new_order("a")), 0).unwrap();
+        oms.on_request(OrderRequest::New(new_order("b")), 0).unwrap();
+
+        // A REST request rejected with 418, then a WS API request with -1003
+        let rest = ctl_rest::RestError::Status { path: "/api/v3/order".to_string(), status: 418, body: String::new() };
+        assert!(oms.on_request_failed("a", rest.is_rate_limited(), 1_000).is_empty());
+        assert!(!oms.is_halted());
+        let json = r#"{"id":"b","status":429,"error":{"code":-1003,"msg":"Too much request weight used."}}"#;
+        let ws_api: ctl_websocket::WSResponse = serde_json::from_str(json).unwrap();
+        assert_eq!(oms.on_request_failed("b", ws_api.is_rate_limited(), 2_000), vec!["ETHUSDT".to_string()]);
+        assert!(oms.is_halted() && status.is_tripped());
+        assert_eq!(status.tripped_by(), BreakerSignal::RateLimitBan);
+    }
+
+    #[test]
+    fn test_breaker_trips_on_late_reports() {
+        let dir = tempfile::tempdir().unwrap();
+        let name = format!("ctl_test_oms_reports_{}", std::process::id());
+        let status = Arc::new(ShmRegion::<ctl_core::StatusRegion>::create(&name).unwrap());
+        let config = ctl_core::BreakerConfig {
+            execution_report_timeout: ctl_core::BreakerThreshold { max_errors: 2, window_ms: 60_000 },
+            ..Default::default()
+        };
+        let mut oms = OrderManager::open(dir.path().join("orders.journal"))
+            .unwrap()
+            .with_breaker(Arc::new(CircuitBreaker::new(config, status.clone())))
+            .with_report_deadline(500);
+        for id in ["a", "b", "c"] {
+            oms.on_request(OrderRequest::New(new_order(id)), 1_000).unwrap();
+        }
+
+        // The reported orders, and those the exchange failed, are no longer awaited
+        let update = OrderUpdate {
+            client_order_id: "a".to_string(),
+            order_id: Some(1),
+            status: OrderStatus::New,
+            executed_qty: 0.0,
+        };
+        oms.on_update(update).unwrap();
+        assert!(oms.on_request_failed("b", false, 1_200).is_empty());
+        assert!(oms.check_reports(1_400).is_empty());
+        // The report of c is late, a first timeout not tripping the breaker
+        assert!(oms.check_reports(1_500).is_empty());
+        assert!(!oms.is_halted());
+
+        oms.on_request(OrderRequest::Cancel { client_order_id: "a".to_string() }, 1_500).unwrap();
+        assert_eq!(oms.check_reports(2_000), vec!["ETHUSDT".to_string()]);
+        assert!(oms.is_halted() && status.is_tripped());
+        assert_eq!(status.tripped_by(), BreakerSignal::ExecutionReportTimeout);
+        assert!(oms.check_reports(3_000).is_empty());
+    }
+
+    #[test]
+    fn test_cancel_replace() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("orders.journal");
+        let mut oms = OrderManager::open(&path).unwrap();
+        oms.submit(order("a", 1.0)).unwrap();
+
+        let requote = |cancel: &str, id: &str, symbol: &str| OrderRequest::CancelReplace {
+            cancel_client_order_id: cancel.to_string(),
+            allow_cancel_failure: false,
+            order: NewOrder {
+                client_order_id: id.to_string(),
+                symbol: symbol.to_string(),
+                side: Side::Buy,
+                order_type: OrderType::Limit,
+                price: 2001.0,
+                qty: 1.0,
+                stop_price: None,
+                time_in_force: None,
+                iceberg_qty: None,
+                self_trade_prevention_mode: None,
+            },
+        };
+        assert!(matches!(oms.on_request(requote("x", "b", "ETHUSDT"), 0), Err(OmsError::UnknownOrder(_))));
+        assert!(matches!(
+            oms.on_request(requote("a", "b", "BTCUSDT"), 0),
+            Err(OmsError::CancelReplaceSymbol { .. })
+        ));
+        oms.on_request(requote("a", "b", "ETHUSDT"), 0).unwrap();
+        assert_eq!(oms.orders().get("b").unwrap().status, OrderStatus::PendingNew);
+        assert_eq!(oms.orders().get("a").unwrap().status, OrderStatus::PendingNew);
+        assert!(matches!(oms.on_request(requote("b", "b", "ETHUSDT"), 0), Err(OmsError::DuplicateOrder(_))));
+
+        // The cancel failed in STOP_ON_FAILURE mode, the new order not being placed
+        let rejected = OrderUpdate {
+            client_order_id: "b".to_string(),
+            order_id: None,
+            status: OrderStatus::Rejected,
+            executed_qty: 0.0,
+        };
+        assert!(oms.on_update(rejected).unwrap());
+        drop(oms);
+
+        let oms = OrderManager::open(&path).unwrap();
+        assert_eq!(oms.orders().get("b").unwrap().status, OrderStatus::Rejected);
+        assert_eq!(oms.orders().open_orders().count(), 1);
+    }
+
+    #[test]
+    fn test_request_checked_against_rules() {
+        let dir = tempfile::tempdir().unwrap();
+        let rules: ExchangeRules = serde_json::from_str(
+            r#"{"symbols":[{"symbol":"ETHUSDT","orderTypes":["LIMIT","MARKET"],"icebergAllowed":false,"allowedSelfTradePreventionModes":["EXPIRE_MAKER"]}]}"#,
+        )
+        .unwrap();
+        let mut oms = OrderManager::open(dir.path().join("orders.journal")).unwrap().with_rules(rules);
+
+        let new = |id: &str, time_in_force, iceberg_qty| NewOrder {
+            client_order_id: id.to_string(),
+            symbol: "ETHUSDT".to_string(),
+            side: Side::Buy,
+            order_type: OrderType::Limit,
+            price: 2000.0,
+            qty: 1.0,
+            stop_price: None,
+            time_in_force,
+            iceberg_qty,
+            self_trade_prevention_mode: Some(StpMode::ExpireMaker),
+        };
+        oms.on_request(OrderRequest::New(new("a", Some(TimeInForce::Ioc), None)), 0).unwrap();
+        assert!(matches!(
+            oms.on_request(OrderRequest::New(new("b", None, Some(0.1))), 0),
+            Err(OmsError::InvalidOrder { reason: "iceberg orders not allowed on the symbol", .. })
+        ));
+        assert!(oms.orders().get("b").is_none());
+    }
+
+    fn new_order(client_order_id: &str) -> NewOrder {
+        NewOrder {
+            client_order_id: client_order_id.to_string(),
+            symbol: "ETHUSDT".to_string(),
+            side: Side::Buy,
+            order_type: OrderType::Limit,
+            price: 2000.0,
+            qty: 1.0,
+            stop_price: None,
+            time_in_force: None,
+            iceberg_qty: None,
+            self_trade_prevention_mode: None,
+        }
+    }
+
+    #[test]
+    fn test_requests_throttled_by_order_rate() {
+        let dir = tempfile::tempdir().unwrap();
+        let name = format!("ctl_test_order_rate_{}", std::process::id());
+        let rate = Arc::new(ShmRegion::<OrderRateLedger>::create(&name).unwrap());
+        rate.set_limits(2, 0);
+
+        let mut oms = OrderManager::open(dir.path().join("queued.journal"))
+            .unwrap()
+            .with_rate_budget(rate.clone(), Throttle::Queue);
+        let new = |id: &str| OrderRequest::New(new_order(id));
+        assert_eq!(oms.on_request(new("a"), 0).unwrap(), RequestOutcome::Accepted);
+        assert_eq!(oms.on_request(new("b"), 0).unwrap(), RequestOutcome::Accepted);
+        assert_eq!(oms.on_request(new("c"), 1_000).unwrap(), RequestOutcome::Queued);
+        assert_eq!(oms.on_request(new("d"), 1_000).unwrap(), RequestOutcome::Queued);
+        assert_eq!(oms.on_request(new("e"), 1_000).unwrap(), RequestOutcome::Queued);
+        assert_eq!(rate.queued(), 3);
+
+        // Cancels aren't budgeted, withdrawing the queued orders
+        let cancel = |id: &str| OrderRequest::Cancel { client_order_id: id.to_string() };
+        assert_eq!(oms.on_request(cancel("a"), 1_000).unwrap(), RequestOutcome::Accepted);
+        assert_eq!(oms.on_request(cancel("d"), 1_000).unwrap(), RequestOutcome::Withdrawn);
+        assert!(oms.orders().get("c").is_none());
+
+        assert!(oms.release_throttled(5_000).is_empty());
+        let released = oms.release_throttled(10_000);
+        assert_eq!(released.len(), 2);
+        assert!(released.iter().all(|(_, result)| result.is_ok()));
+        assert_eq!(oms.num_throttled(), 0);
+        assert_eq!(rate.queued(), 0);
+        assert_eq!(oms.orders().get("e").unwrap().status, OrderStatus::PendingNew);
+
+        // Beyond the budget, the requests are rejected instead
+        let mut oms = OrderManager::open(dir.path().join("rejected.journal"))
+            .unwrap()
+            .with_rate_budget(rate.clone(), Throttle::Reject);
+        assert!(matches!(
+            oms.on_request(new("f"), 11_000),
+            Err(OmsError::OrderRateExceeded { interval: "10s", count: 2, orders: 1, limit: 2 })
+        ));
+        assert_eq!(rate.rejected(), 1);
+        assert!(oms.orders().get("f").is_none());
+    }
+
+    #[test]
+    fn test_traced_request() {
+        let dir = tempfile::tempdir().unwrap();
+        let (sink, spans) = std::sync::mpsc::sync_channel(4);
+        let mut oms = OrderManager::open(dir.path().join("orders.journal"))
+            .unwrap()
+            .with_tracer(Tracer::new(0).with_sink(sink, 0));
+
+        // Only the requests decided on a traced message are recorded
+        let decision = TraceContext { trace_id: 7, span_id: 9 };
+        oms.on_traced_request(OrderRequest::New(new_order("a")), decision, 0).unwrap();
+        oms.on_traced_request(OrderRequest::New(new_order("b")), TraceContext::default(), 0).unwrap();
+        let span = spans.try_recv().unwrap();
+        assert_eq!((span.name, span.context.trace_id, span.parent_span_id), ("oms.submit", 7, 9));
+        assert_eq!(span.attributes, vec![("num_orders", 1)]);
+        assert!(spans.try_recv().is_err());
+        assert!(oms.orders().get("b").is_some());
+    }
+
+    #[test]
+    fn test_manual_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut oms = OrderManager::open(dir.path().join("orders.journal")).unwrap();
+        let entry = OrderEntryMessage::new_order(7, &new_order("a"), 0, "alice").unwrap();
+        assert_eq!(oms.on_entry(&entry, 0).unwrap(), RequestOutcome::Accepted);
+        assert_eq!(oms.orders().get("a").unwrap().status, OrderStatus::PendingNew);
+
+        // Manual orders are rejected while halted like any, their cancels accepted
+        oms.halt();
+        let entry = OrderEntryMessage::new_order(7, &new_order("b"), 0, "alice").unwrap();
+        assert!(matches!(oms.on_entry(&entry, 0), Err(OmsError::Halted(_))));
+        let cancel = OrderEntryMessage::cancel(7, "a", 0, "alice").unwrap();
+        assert_eq!(oms.on_entry(&cancel, 0).unwrap(), RequestOutcome::Accepted);
+        assert!(oms.on_entry(&OrderEntryMessage::default(), 0).is_err());
+    }
+
+    #[test]
+    fn test_oco_lifecycle() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("orders.journal");
+        let mut oms = OrderManager::open(&path).unwrap();
+
+        let leg = |id: &str, price: f64, stop_price: Option<f64>| NewOrder {
+            client_order_id: id.to_string(),
+            symbol: "ETHUSDT".to_string(),
+            side: Side::Sell,
+            order_type: if stop_price.is_some() { OrderType::StopLossLimit } else { OrderType::LimitMaker },
+            price,
+            qty: 1.0,
+            stop_price,
+            time_in_force: None,
+            iceberg_qty: None,
+            self_trade_prevention_mode: None,
+        };
+        let oco = NewOrderList {
+            list_client_order_id: "oco".to_string(),
+            kind: OrderListKind::Oco,
+            legs: vec![leg("limit", 2100.0, None), leg("stop", 1890.0, Some(1900.0))],
+        };
+        oms.on_request(OrderRequest::NewList(oco.clone()), 0).unwrap();
+        assert!(matches!(oms.submit_list(oco), Err(OmsError::DuplicateOrderList(_))));
+        assert!(matches!(
+            oms.cancel_replace("limit", order("b", 1.0)),
+            Err(OmsError::ListLeg(_))
+        ));
+
+        let status = |list_status| ListStatusUpdate {
+            list_client_order_id: "oco".to_string(),
+            order_list_id: Some(2),
+            status: list_status,
+        };
+        assert!(oms.on_list_status(status(ListOrderStatus::Executing)).unwrap());
+        assert!(!oms.on_list_status(status(ListOrderStatus::Executing)).unwrap());
+        for (id, order_id) in [("limit", 10), ("stop", 11)] {
+            let update = OrderUpdate {
+                client_order_id: id.to_string(),
+                order_id: Some(order_id),
+                status: OrderStatus::New,
+                executed_qty: 0.0,
+            };
+            assert!(oms.on_update(update).unwrap());
+        }
+
+        // The limit leg fills, expiring the stop leg
+        let filled = OrderUpdate {
+            client_order_id: "limit".to_string(),
+            order_id: Some(10),
+            status: OrderStatus::Filled,
+            executed_qty: 1.0,
+        };
+        assert!(oms.on_update(filled).unwrap());
+        let expired = OrderUpdate {
+            client_order_id: "stop".to_string(),
+            order_id: Some(11),
+            status: OrderStatus::Expired,
+            executed_qty: 0.0,
+        };
+        assert!(oms.on_update(expired).unwrap());
+        assert!(oms.on_list_status(status(ListOrderStatus::AllDone)).unwrap());
+        assert!(matches!(
+            oms.on_list_status(status(ListOrderStatus::Executing)),
+            Err(OmsError::InvalidListTransition { .. })
+        ));
+        drop(oms);
+
+        let oms = OrderManager::open(&path).unwrap();
+        let list = oms.orders().get_list("oco").unwrap();
+        assert_eq!((list.status, list.order_list_id), (ListOrderStatus::AllDone, Some(2)));
+        assert_eq!(oms.orders().get("stop").unwrap().list_client_order_id.as_deref(), Some("oco"));
+        assert_eq!(oms.orders().open_orders().count(), 0);
+        assert_eq!(oms.orders().open_lists().count(), 0);
+    }
+
+    #[test]
+    fn test_rejected_list_rejects_legs() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("orders.journal");
+        let mut oms = OrderManager::open(&path).unwrap();
+
+        let leg = |id: &str, side: Side| NewOrder {
+            client_order_id: id.to_string(),
+            symbol: "ETHUSDT".to_string(),
+            side,
+            order_type: OrderType::Limit,
+            price: 2000.0,
+            qty: 1.0,
+            stop_price: None,
+            time_in_force: None,
+            iceberg_qty: None,
+            self_trade_prevention_mode: None,
+        };
+        let oto = NewOrderList {
+            list_client_order_id: "oto".to_string(),
+            kind: OrderListKind::Oto,
+            legs: vec![leg("working", Side::Buy), leg("pending", Side::Sell)],
+        };
+        oms.submit_list(oto).unwrap();
+        let reject = ListStatusUpdate {
+            list_client_order_id: "oto".to_string(),
+            order_list_id: None,
+            status: ListOrderStatus::Reject,
+        };
+        assert!(oms.on_list_status(reject).unwrap());
+        drop(oms);
+
+        let oms = OrderManager::open(&path).unwrap();
+        assert_eq!(oms.orders().get("working").unwrap().status, OrderStatus::Rejected);
+        assert_eq!(oms.orders().get("pending").unwrap().status, OrderStatus::Rejected);
+    }
+
+    #[test]
+    fn test_reconcile_after_restart() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("orders.journal");
+
+        let mut oms = OrderManager::open(&path).unwrap();
+        oms.submit(order("filled", 1.0)).unwrap();
+        oms.submit(order("partial", 1.0)).unwrap();
+        oms.submit(order("unchanged", 1.0)).unwrap();
+        for id in ["filled", "partial", "unchanged"] {
+            let update = OrderUpdate {
+                client_order_id: id.to_string(),
+                order_id: Some(9),
+                status: OrderStatus::New,
+                executed_qty: 0.0,
+            };
+            assert!(oms.on_update(update).unwrap());
+        }
+        drop(oms);
+
+        let mut oms = OrderManager::open(&path).unwrap();
+        let outcomes = oms
+            .reconcile(&[
+                open_order("partial", "PARTIALLY_FILLED", "0.50000000"),
+                open_order("unchanged", "NEW", "0.00000000"),
+                open_order("foreign", "NEW", "0.00000000"),
+            ])
+            .unwrap();
+        assert_eq!(
+            outcomes,
+            vec![
+                Reconciliation::Updated("partial".to_string()),
+                Reconciliation::Adopted("foreign".to_string()),
+                Reconciliation::Missing("filled".to_string()),
+            ]
+        );
+        drop(oms);
+
+        // The reconciled state is journaled
+        let oms = OrderManager::open(&path).unwrap();
+        assert_eq!(oms.orders().get("partial").unwrap().executed_qty, 0.5);
+        assert_eq!(oms.orders().get("foreign").unwrap().status, OrderStatus::New);
+        assert_eq!(oms.orders().open_orders().count(), 4);
+    }
+}
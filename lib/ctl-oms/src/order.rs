@@ -0,0 +1,347 @@
+use hashbrown::HashMap;
+use serde::{Deserialize, Serialize};
+
+use crate::{ListOrderStatus, ListStatusUpdate, OmsError, OrderList};
+
+/// The status of an order.
+/// https://github.com/binance/binance-spot-api-docs/blob/master/enums.md#order-status-status
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum OrderStatus {
+    /// Submitted, not yet acknowledged by the exchange.
+    PendingNew,
+    /// Accepted by the exchange.
+    New,
+    /// Part of the quantity has been filled.
+    PartiallyFilled,
+    /// The whole quantity has been filled.
+    Filled,
+    /// Canceled by the user.
+    Canceled,
+    /// Rejected by the exchange.
+    Rejected,
+    /// Canceled by the exchange (time in force, or self-trade prevention).
+    #[serde(alias = "EXPIRED_IN_MATCH")]
+    Expired,
+}
+
+impl OrderStatus {
+    /// Returns true if the order can no longer change.
+    pub fn is_terminal(self) -> bool {
+        matches!(self, Self::Filled | Self::Canceled | Self::Rejected | Self::Expired)
+    }
+
+    /// Returns true if the order lifecycle allows moving from `self` to `next`.
+    ///
+    /// Partial fills may repeat. An order may be filled, canceled or expired
+    /// straight from its submission, but only rejected from it.
+    pub fn can_transition_to(self, next: Self) -> bool {
+        use OrderStatus::*;
+        match self {
+            PendingNew => next != PendingNew,
+            New => matches!(next, PartiallyFilled | Filled | Canceled | Expired),
+            PartiallyFilled => matches!(next, PartiallyFilled | Filled | Canceled | Expired),
+            Filled | Canceled | Rejected | Expired => false,
+        }
+    }
+}
+
+/// The side of an order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum Side {
+    Buy,
+    Sell,
+}
+
+/// An order tracked by the OMS.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Order {
+    /// The client order ID, unique across the orders tracked.
+    pub client_order_id: String,
+    /// The exchange order ID, once acknowledged.
+    pub order_id: Option<u64>,
+    /// The symbol.
+    pub symbol: String,
+    /// The side.
+    pub side: Side,
+    /// The limit price.
+    pub price: f64,
+    /// The quantity ordered.
+    pub orig_qty: f64,
+    /// The quantity filled.
+    pub executed_qty: f64,
+    /// The current status.
+    pub status: OrderStatus,
+    /// The client order list ID of the order list the order is a leg of, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub list_client_order_id: Option<String>,
+}
+
+/// A change of the status or fills of an order, from an execution report or a query.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OrderUpdate {
+    /// The client order ID of the order.
+    pub client_order_id: String,
+    /// The exchange order ID, if known.
+    pub order_id: Option<u64>,
+    /// The new status.
+    pub status: OrderStatus,
+    /// The cumulative quantity filled.
+    pub executed_qty: f64,
+}
+
+impl Order {
+    /// Checks that `update` is a valid transition of the order, returning false
+    /// if it leaves the order unchanged (e.g. a duplicated execution report).
+    ///
+    /// # Errors
+    /// Returns an error if the lifecycle doesn't allow the transition, or the
+    /// executed quantity would decrease.
+    pub fn check(&self, update: &OrderUpdate) -> Result<bool, OmsError> {
+        if update.executed_qty < self.executed_qty {
+            return Err(OmsError::ExecutedQtyDecreased {
+                client_order_id: self.client_order_id.clone(),
+                from: self.executed_qty,
+                to: update.executed_qty,
+            });
+        }
+        if update.status == self.status && update.executed_qty == self.executed_qty {
+            return Ok(self.order_id.is_none() && update.order_id.is_some());
+        }
+        if !self.status.can_transition_to(update.status) {
+            return Err(OmsError::InvalidTransition {
+                client_order_id: self.client_order_id.clone(),
+                from: self.status,
+                to: update.status,
+            });
+        }
+        Ok(true)
+    }
+
+    /// Applies a checked update to the order.
+    fn apply(&mut self, update: &OrderUpdate) {
+        self.status = update.status;
+        self.executed_qty = update.executed_qty;
+        if update.order_id.is_some() {
+            self.order_id = update.order_id;
+        }
+    }
+}
+
+/// The orders and order lists tracked, by client order ID and client order list ID.
+#[derive(Debug, Clone, Default)]
+pub struct OrderTable {
+    orders: HashMap<String, Order>,
+    lists: HashMap<String, OrderList>,
+}
+
+impl OrderTable {
+    /// Returns the order with the client order ID, if tracked.
+    pub fn get(&self, client_order_id: &str) -> Option<&Order> {
+        self.orders.get(client_order_id)
+    }
+
+    /// Returns the number of orders tracked.
+    pub fn len(&self) -> usize {
+        self.orders.len()
+    }
+
+    /// Returns true if no order is tracked.
+    pub fn is_empty(&self) -> bool {
+        self.orders.is_empty()
+    }
+
+    /// Returns the orders not in a terminal status.
+    pub fn open_orders(&self) -> impl Iterator<Item = &Order> {
+        self.orders.values().filter(|order| !order.status.is_terminal())
+    }
+
+    /// Checks that `order` can be inserted.
+    ///
+    /// # Errors
+    /// Returns an error if its client order ID is already tracked.
+    pub fn check_insert(&self, order: &Order) -> Result<(), OmsError> {
+        if self.orders.contains_key(&order.client_order_id) {
+            return Err(OmsError::DuplicateOrder(order.client_order_id.clone()));
+        }
+        Ok(())
+    }
+
+    /// Inserts a new order.
+    ///
+    /// # Errors
+    /// Returns an error if its client order ID is already tracked.
+    pub fn insert(&mut self, order: Order) -> Result<(), OmsError> {
+        self.check_insert(&order)?;
+        self.orders.insert(order.client_order_id.clone(), order);
+        Ok(())
+    }
+
+    /// Checks that `update` is a valid transition of a tracked order, returning
+    /// false if it leaves the order unchanged.
+    ///
+    /// # Errors
+    /// Returns an error if the order isn't tracked or the transition is invalid.
+    pub fn check_update(&self, update: &OrderUpdate) -> Result<bool, OmsError> {
+        self.orders
+            .get(&update.client_order_id)
+            .ok_or_else(|| OmsError::UnknownOrder(update.client_order_id.clone()))?
+            .check(update)
+    }
+
+    /// Applies an update to a tracked order, returning false if it left the order unchanged.
+    ///
+    /// # Errors
+    /// Returns an error if the order isn't tracked or the transition is invalid.
+    pub fn update(&mut self, update: &OrderUpdate) -> Result<bool, OmsError> {
+        let order = self
+            .orders
+            .get_mut(&update.client_order_id)
+            .ok_or_else(|| OmsError::UnknownOrder(update.client_order_id.clone()))?;
+        let changed = order.check(update)?;
+        if changed {
+            order.apply(update);
+        }
+        Ok(changed)
+    }
+
+    /// Returns the order list with the client order list ID, if tracked.
+    pub fn get_list(&self, list_client_order_id: &str) -> Option<&OrderList> {
+        self.lists.get(list_client_order_id)
+    }
+
+    /// Returns the order lists not in a terminal status.
+    pub fn open_lists(&self) -> impl Iterator<Item = &OrderList> {
+        self.lists.values().filter(|list| !list.status.is_terminal())
+    }
+
+    /// Checks that an order list and its legs can be inserted.
+    ///
+    /// # Errors
+    /// Returns an error if its client order list ID or the client order ID of
+    /// a leg is already tracked.
+    pub fn check_insert_list(&self, list: &OrderList, legs: &[Order]) -> Result<(), OmsError> {
+        if self.lists.contains_key(&list.list_client_order_id) {
+            return Err(OmsError::DuplicateOrderList(list.list_client_order_id.clone()));
+        }
+        for (index, leg) in legs.iter().enumerate() {
+            self.check_insert(leg)?;
+            if legs[..index].iter().any(|other| other.client_order_id == leg.client_order_id) {
+                return Err(OmsError::DuplicateOrder(leg.client_order_id.clone()));
+            }
+        }
+        Ok(())
+    }
+
+    /// Inserts a new order list and its legs.
+    ///
+    /// # Errors
+    /// Returns an error if the list or one of its legs is already tracked.
+    pub fn insert_list(&mut self, list: OrderList, legs: Vec<Order>) -> Result<(), OmsError> {
+        self.check_insert_list(&list, &legs)?;
+        for leg in legs {
+            self.orders.insert(leg.client_order_id.clone(), leg);
+        }
+        self.lists.insert(list.list_client_order_id.clone(), list);
+        Ok(())
+    }
+
+    /// Checks that `update` is a valid transition of a tracked order list,
+    /// returning false if it leaves the list unchanged.
+    ///
+    /// # Errors
+    /// Returns an error if the list isn't tracked or the transition is invalid.
+    pub fn check_list_update(&self, update: &ListStatusUpdate) -> Result<bool, OmsError> {
+        self.lists
+            .get(&update.list_client_order_id)
+            .ok_or_else(|| OmsError::UnknownOrderList(update.list_client_order_id.clone()))?
+            .check(update)
+    }
+
+    /// Applies a status update to a tracked order list, returning false if it
+    /// left the list unchanged. A rejected list rejects its legs not yet acknowledged.
+    ///
+    /// # Errors
+    /// Returns an error if the list isn't tracked or the transition is invalid.
+    pub fn update_list(&mut self, update: &ListStatusUpdate) -> Result<bool, OmsError> {
+        let list = self
+            .lists
+            .get_mut(&update.list_client_order_id)
+            .ok_or_else(|| OmsError::UnknownOrderList(update.list_client_order_id.clone()))?;
+        let changed = list.check(update)?;
+        if !changed {
+            return Ok(false);
+        }
+        list.apply(update);
+
+        if update.status == ListOrderStatus::Reject {
+            for leg in &list.legs {
+                if let Some(order) = self.orders.get_mut(leg).filter(|order| order.status == OrderStatus::PendingNew) {
+                    order.status = OrderStatus::Rejected;
+                }
+            }
+        }
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::order;
+
+    fn update(status: OrderStatus, executed_qty: f64) -> OrderUpdate {
+        OrderUpdate { client_order_id: "a".to_string(), order_id: Some(7), status, executed_qty }
+    }
+
+    #[test]
+    fn test_transitions() {
+        use OrderStatus::*;
+        assert!(PendingNew.can_transition_to(New));
+        assert!(PendingNew.can_transition_to(Rejected));
+        assert!(PendingNew.can_transition_to(Filled));
+        assert!(New.can_transition_to(PartiallyFilled));
+        assert!(!New.can_transition_to(Rejected));
+        assert!(PartiallyFilled.can_transition_to(PartiallyFilled));
+        assert!(!PartiallyFilled.can_transition_to(New));
+        for terminal in [Filled, Canceled, Rejected, Expired] {
+            assert!(terminal.is_terminal());
+            assert!(!terminal.can_transition_to(New));
+        }
+    }
+
+    #[test]
+    fn test_order_lifecycle() {
+        let mut table = OrderTable::default();
+        table.insert(order("a", 2.0)).unwrap();
+        assert!(matches!(table.insert(order("a", 2.0)), Err(OmsError::DuplicateOrder(_))));
+
+        assert!(table.update(&update(OrderStatus::New, 0.0)).unwrap());
+        assert_eq!(table.get("a").unwrap().order_id, Some(7));
+        assert!(table.update(&update(OrderStatus::PartiallyFilled, 0.5)).unwrap());
+        assert!(table.update(&update(OrderStatus::PartiallyFilled, 1.5)).unwrap());
+
+        // A duplicated report leaves the order unchanged
+        assert!(!table.update(&update(OrderStatus::PartiallyFilled, 1.5)).unwrap());
+        assert!(matches!(
+            table.update(&update(OrderStatus::PartiallyFilled, 1.0)),
+            Err(OmsError::ExecutedQtyDecreased { .. })
+        ));
+
+        assert!(table.update(&update(OrderStatus::Filled, 2.0)).unwrap());
+        assert!(matches!(
+            table.update(&update(OrderStatus::Canceled, 2.0)),
+            Err(OmsError::InvalidTransition { from: OrderStatus::Filled, .. })
+        ));
+        assert_eq!(table.open_orders().count(), 0);
+    }
+
+    #[test]
+    fn test_deserialize_status() {
+        let status: OrderStatus = serde_json::from_str(r#""PARTIALLY_FILLED""#).unwrap();
+        assert_eq!(status, OrderStatus::PartiallyFilled);
+        let status: OrderStatus = serde_json::from_str(r#""EXPIRED_IN_MATCH""#).unwrap();
+        assert_eq!(status, OrderStatus::Expired);
+    }
+}
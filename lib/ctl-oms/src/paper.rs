@@ -0,0 +1,539 @@
+//! Paper trading: a simulated exchange filling the orders against live market data.
+//!
+//! In `--paper` mode the OMS sends the orders to a [`PaperExchange`] fed with
+//! the Top and Trade rings instead of Binance. Orders and cancels reach it after
+//! the configured latency. An order marketable on arrival fills as taker against
+//! the opposite best level, at up to its quantity and with slippage; the rest
+//! rests at its limit price and fills as maker when the opposite best level or a
+//! trade crosses it. Queue position isn't modelled: a resting order fills only
+//! once the market trades through its price.
+
+use std::collections::VecDeque;
+use std::fs;
+use std::path::Path;
+
+use hashbrown::HashMap;
+use serde::Deserialize;
+
+use crate::{Order, OrderStatus, OrderUpdate, PaperConfigError, Side};
+
+/// Quantities below this are rounding, not a remaining quantity.
+const QTY_EPSILON: f64 = 1e-12;
+
+/// The mode the OMS runs in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OmsMode {
+    /// Orders are sent to Binance.
+    Live,
+    /// Orders are filled by the simulated exchange.
+    Paper,
+}
+
+impl OmsMode {
+    /// Returns the mode selected by the command line arguments (`--paper`).
+    pub fn from_args<I: IntoIterator<Item = String>>(args: I) -> Self {
+        if args.into_iter().any(|arg| arg == "--paper") {
+            OmsMode::Paper
+        } else {
+            OmsMode::Live
+        }
+    }
+}
+
+/// The latency, slippage and fee model of the simulated exchange.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct PaperConfig {
+    /// Delay before an order or cancel reaches the exchange, in milliseconds.
+    #[serde(default)]
+    pub latency_ms: u64,
+    /// Price penalty of the taker fills, in basis points.
+    #[serde(default)]
+    pub slippage_bps: f64,
+    /// Commission of the maker fills, in basis points.
+    #[serde(default)]
+    pub maker_fee_bps: f64,
+    /// Commission of the taker fills, in basis points.
+    #[serde(default)]
+    pub taker_fee_bps: f64,
+    /// Asset the commissions are charged in.
+    pub fee_asset: String,
+}
+
+impl PaperConfig {
+    /// Parses the paper trading configuration from a YAML file.
+    ///
+    /// # Errors
+    /// Returns an error if the file cannot be read, parsed, or fails validation.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, PaperConfigError> {
+        let content = fs::read_to_string(path)?;
+        Self::from_str(&content)
+    }
+
+    /// Parses the paper trading configuration from a YAML string.
+    ///
+    /// # Errors
+    /// Returns an error if the YAML cannot be parsed or fails validation.
+    pub fn from_str(content: &str) -> Result<Self, PaperConfigError> {
+        let config: Self = serde_yaml::from_str(content)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Validates the paper trading configuration.
+    fn validate(&self) -> Result<(), PaperConfigError> {
+        for (name, bps) in [
+            ("slippage_bps", self.slippage_bps),
+            ("maker_fee_bps", self.maker_fee_bps),
+            ("taker_fee_bps", self.taker_fee_bps),
+        ] {
+            if !(0.0..10_000.0).contains(&bps) {
+                return Err(PaperConfigError::ValidationError(format!(
+                    "{} must be in [0, 10000), got {}",
+                    name, bps
+                )));
+            }
+        }
+        if self.fee_asset.is_empty() {
+            return Err(PaperConfigError::ValidationError("fee_asset must not be empty".to_string()));
+        }
+        Ok(())
+    }
+}
+
+/// The best bid/ask of a symbol.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Quote {
+    pub bid_price: f64,
+    pub bid_qty: f64,
+    pub ask_price: f64,
+    pub ask_qty: f64,
+}
+
+/// The type of an execution.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExecutionType {
+    New,
+    Trade,
+    Canceled,
+    Rejected,
+}
+
+impl ExecutionType {
+    fn as_str(self) -> &'static str {
+        match self {
+            ExecutionType::New => "NEW",
+            ExecutionType::Trade => "TRADE",
+            ExecutionType::Canceled => "CANCELED",
+            ExecutionType::Rejected => "REJECTED",
+        }
+    }
+}
+
+/// An execution report of the simulated exchange.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PaperReport {
+    /// The order update, applied to the OMS.
+    pub update: OrderUpdate,
+    /// The symbol.
+    pub symbol: String,
+    /// The side.
+    pub side: Side,
+    /// The type of the execution.
+    pub execution_type: ExecutionType,
+    /// The price of the fill, zero unless a trade.
+    pub last_price: f64,
+    /// The quantity of the fill, zero unless a trade.
+    pub last_qty: f64,
+    /// The commission of the fill.
+    pub commission: f64,
+    /// The asset of the commission.
+    pub commission_asset: String,
+    /// True if the fill was as maker.
+    pub is_maker: bool,
+    /// The trade ID, `-1` unless a trade.
+    pub trade_id: i64,
+    /// The time of the execution, in milliseconds.
+    pub transact_time_ms: u64,
+}
+
+impl PaperReport {
+    /// Formats the report as a user data stream `executionReport` payload, so
+    /// the consumers of the live reports process it unchanged.
+    pub fn to_json(&self) -> String {
+        let side = match self.side {
+            Side::Buy => "BUY",
+            Side::Sell => "SELL",
+        };
+        let status = serde_json::to_string(&self.update.status).unwrap_or_default();
+        let commission_asset = if self.execution_type == ExecutionType::Trade {
+            format!("\"{}\"", self.commission_asset)
+        } else {
+            "null".to_string()
+        };
+        format!(
+            r#"{{"e":"executionReport","E":{time},"s":"{symbol}","c":"{client_order_id}","S":"{side}","x":"{execution_type}","X":{status},"i":{order_id},"l":"{last_qty}","z":"{executed_qty}","L":"{last_price}","n":"{commission}","N":{commission_asset},"T":{time},"t":{trade_id},"m":{is_maker}}}"#,
+            time = self.transact_time_ms,
+            symbol = self.symbol,
+            client_order_id = self.update.client_order_id,
+            side = side,
+            execution_type = self.execution_type.as_str(),
+            status = status,
+            order_id = self.update.order_id.unwrap_or_default(),
+            last_qty = self.last_qty,
+            executed_qty = self.update.executed_qty,
+            last_price = self.last_price,
+            commission = self.commission,
+            commission_asset = commission_asset,
+            trade_id = self.trade_id,
+            is_maker = self.is_maker,
+        )
+    }
+}
+
+/// A request waiting for the latency to elapse.
+#[derive(Debug, Clone)]
+enum Action {
+    Submit(Order),
+    Cancel(String),
+}
+
+/// A simulated exchange filling the orders against live market data.
+#[derive(Debug, Clone)]
+pub struct PaperExchange {
+    /// The latency, slippage and fee model.
+    config: PaperConfig,
+    /// The requests in flight, by arrival time (the latency is constant).
+    pending: VecDeque<(u64, Action)>,
+    /// The orders resting on the simulated book.
+    resting: Vec<Order>,
+    /// The latest best bid/ask, by symbol.
+    quotes: HashMap<String, Quote>,
+    /// The next exchange order ID.
+    next_order_id: u64,
+    /// The next trade ID.
+    next_trade_id: i64,
+}
+
+impl PaperExchange {
+    /// Creates a simulated exchange with the given model.
+    pub fn new(config: PaperConfig) -> Self {
+        Self {
+            config,
+            pending: VecDeque::new(),
+            resting: Vec::new(),
+            quotes: HashMap::new(),
+            next_order_id: 1,
+            next_trade_id: 1,
+        }
+    }
+
+    /// Returns the orders resting on the simulated book.
+    pub fn open_orders(&self) -> &[Order] {
+        &self.resting
+    }
+
+    /// Sends an order, reaching the exchange after the latency.
+    pub fn submit(&mut self, order: Order, now_ms: u64) {
+        self.pending.push_back((now_ms + self.config.latency_ms, Action::Submit(order)));
+    }
+
+    /// Sends a cancel of an order, reaching the exchange after the latency.
+    pub fn cancel(&mut self, client_order_id: &str, now_ms: u64) {
+        self.pending
+            .push_back((now_ms + self.config.latency_ms, Action::Cancel(client_order_id.to_string())));
+    }
+
+    /// Processes the requests that reached the exchange by `now_ms`.
+    pub fn advance(&mut self, now_ms: u64) -> Vec<PaperReport> {
+        let mut reports = Vec::new();
+        while self.pending.front().is_some_and(|(arrival_ms, _)| *arrival_ms <= now_ms) {
+            let Some((arrival_ms, action)) = self.pending.pop_front() else {
+                break;
+            };
+            match action {
+                Action::Submit(order) => self.accept(order, arrival_ms, &mut reports),
+                Action::Cancel(client_order_id) => {
+                    if let Some(index) = self.resting.iter().position(|o| o.client_order_id == client_order_id) {
+                        let mut order = self.resting.remove(index);
+                        order.status = OrderStatus::Canceled;
+                        reports.push(self.report(&order, ExecutionType::Canceled, arrival_ms));
+                    }
+                }
+            }
+        }
+        reports
+    }
+
+    /// Updates the best bid/ask of a symbol, filling the resting orders it crosses.
+    pub fn on_quote(&mut self, symbol: &str, quote: Quote, now_ms: u64) -> Vec<PaperReport> {
+        let mut reports = self.advance(now_ms);
+        self.quotes.insert(symbol.to_string(), quote);
+
+        for index in 0..self.resting.len() {
+            let order = &self.resting[index];
+            if order.symbol != symbol {
+                continue;
+            }
+            let available = match order.side {
+                Side::Buy if quote.ask_price <= order.price => quote.ask_qty,
+                Side::Sell if quote.bid_price >= order.price => quote.bid_qty,
+                _ => continue,
+            };
+            let price = order.price;
+            self.fill(index, price, available, true, now_ms, &mut reports);
+        }
+        self.resting.retain(|order| !order.status.is_terminal());
+        reports
+    }
+
+    /// Processes a trade of a symbol, filling the resting orders it trades through.
+    pub fn on_trade(&mut self, symbol: &str, price: f64, qty: f64, now_ms: u64) -> Vec<PaperReport> {
+        let mut reports = self.advance(now_ms);
+
+        let mut available = qty;
+        for index in 0..self.resting.len() {
+            let order = &self.resting[index];
+            let through = match order.side {
+                Side::Buy => price < order.price,
+                Side::Sell => price > order.price,
+            };
+            if order.symbol != symbol || !through || available <= QTY_EPSILON {
+                continue;
+            }
+            let limit = order.price;
+            available -= self.fill(index, limit, available, true, now_ms, &mut reports);
+        }
+        self.resting.retain(|order| !order.status.is_terminal());
+        reports
+    }
+
+    /// Accepts an order that reached the exchange, filling it as taker if marketable.
+    fn accept(&mut self, mut order: Order, now_ms: u64, reports: &mut Vec<PaperReport>) {
+        order.order_id = Some(self.next_order_id);
+        self.next_order_id += 1;
+
+        if order.price <= 0.0 || order.orig_qty <= 0.0 {
+            order.status = OrderStatus::Rejected;
+            reports.push(self.report(&order, ExecutionType::Rejected, now_ms));
+            return;
+        }
+        order.status = OrderStatus::New;
+        order.executed_qty = 0.0;
+        reports.push(self.report(&order, ExecutionType::New, now_ms));
+        self.resting.push(order);
+
+        let index = self.resting.len() - 1;
+        let order = &self.resting[index];
+        let Some(quote) = self.quotes.get(&order.symbol) else {
+            return;
+        };
+        let slippage = self.config.slippage_bps / 10_000.0;
+        let taker = match order.side {
+            Side::Buy if quote.ask_price <= order.price => {
+                Some(((quote.ask_price * (1.0 + slippage)).min(order.price), quote.ask_qty))
+            }
+            Side::Sell if quote.bid_price >= order.price => {
+                Some(((quote.bid_price * (1.0 - slippage)).max(order.price), quote.bid_qty))
+            }
+            _ => None,
+        };
+        if let Some((price, available)) = taker {
+            self.fill(index, price, available, false, now_ms, reports);
+            self.resting.retain(|order| !order.status.is_terminal());
+        }
+    }
+
+    /// Fills the resting order at `index` at `price`, up to `available`.
+    /// Returns the quantity filled.
+    fn fill(
+        &mut self,
+        index: usize,
+        price: f64,
+        available: f64,
+        is_maker: bool,
+        now_ms: u64,
+        reports: &mut Vec<PaperReport>,
+    ) -> f64 {
+        let order = &mut self.resting[index];
+        let qty = (order.orig_qty - order.executed_qty).min(available);
+        if qty <= QTY_EPSILON {
+            return 0.0;
+        }
+        order.executed_qty += qty;
+        order.status = if order.orig_qty - order.executed_qty <= QTY_EPSILON {
+            OrderStatus::Filled
+        } else {
+            OrderStatus::PartiallyFilled
+        };
+
+        let fee_bps = if is_maker { self.config.maker_fee_bps } else { self.config.taker_fee_bps };
+        let order = order.clone();
+        let mut report = self.report(&order, ExecutionType::Trade, now_ms);
+        report.last_price = price;
+        report.last_qty = qty;
+        report.commission = price * qty * fee_bps / 10_000.0;
+        report.is_maker = is_maker;
+        report.trade_id = self.next_trade_id;
+        self.next_trade_id += 1;
+        reports.push(report);
+        qty
+    }
+
+    /// Creates a report of the order's current state.
+    fn report(&self, order: &Order, execution_type: ExecutionType, now_ms: u64) -> PaperReport {
+        PaperReport {
+            update: OrderUpdate {
+                client_order_id: order.client_order_id.clone(),
+                order_id: order.order_id,
+                status: order.status,
+                executed_qty: order.executed_qty,
+            },
+            symbol: order.symbol.clone(),
+            side: order.side,
+            execution_type,
+            last_price: 0.0,
+            last_qty: 0.0,
+            commission: 0.0,
+            commission_asset: self.config.fee_asset.clone(),
+            is_maker: false,
+            trade_id: -1,
+            transact_time_ms: now_ms,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> PaperConfig {
+        PaperConfig::from_str(
+            "latency_ms: 5\nslippage_bps: 10.0\nmaker_fee_bps: 0.0\ntaker_fee_bps: 10.0\nfee_asset: USDT\n",
+        )
+        .unwrap()
+    }
+
+    fn order(client_order_id: &str, side: Side, price: f64, qty: f64) -> Order {
+        Order {
+            client_order_id: client_order_id.to_string(),
+            order_id: None,
+            symbol: "BTCUSDT".to_string(),
+            side,
+            price,
+            orig_qty: qty,
+            executed_qty: 0.0,
+            status: OrderStatus::PendingNew,
+            list_client_order_id: None,
+        }
+    }
+
+    fn quote(bid_price: f64, ask_price: f64, qty: f64) -> Quote {
+        Quote { bid_price, bid_qty: qty, ask_price, ask_qty: qty }
+    }
+
+    #[test]
+    fn test_parse_config() {
+        let config = config();
+        assert_eq!(config.latency_ms, 5);
+        assert_eq!(config.fee_asset, "USDT");
+        assert!(PaperConfig::from_str("slippage_bps: -1.0\nfee_asset: USDT\n").is_err());
+        assert!(PaperConfig::from_str("fee_asset: \"\"\n").is_err());
+    }
+
+    #[test]
+    fn test_mode_from_args() {
+        let args = |args: &[&str]| args.iter().map(|a| a.to_string()).collect::<Vec<_>>();
+        assert_eq!(OmsMode::from_args(args(&["ctl-oms", "--paper"])), OmsMode::Paper);
+        assert_eq!(OmsMode::from_args(args(&["ctl-oms"])), OmsMode::Live);
+    }
+
+    #[test]
+    fn test_latency_and_taker_fill() {
+        let mut exchange = PaperExchange::new(config());
+        exchange.on_quote("BTCUSDT", quote(99.0, 100.0, 1.0), 0);
+        exchange.submit(order("a", Side::Buy, 101.0, 3.0), 10);
+        assert!(exchange.advance(14).is_empty());
+
+        let reports = exchange.advance(15);
+        assert_eq!(reports.len(), 2);
+        assert_eq!(reports[0].execution_type, ExecutionType::New);
+        assert_eq!(reports[0].transact_time_ms, 15);
+
+        // Fills the best ask quantity with slippage, the rest rests
+        let fill = &reports[1];
+        assert_eq!(fill.execution_type, ExecutionType::Trade);
+        assert_eq!((fill.last_price, fill.last_qty), (100.1, 1.0));
+        assert!((fill.commission - 0.1001).abs() < 1e-12);
+        assert!(!fill.is_maker);
+        assert_eq!(fill.update.status, OrderStatus::PartiallyFilled);
+        assert_eq!(exchange.open_orders().len(), 1);
+    }
+
+    #[test]
+    fn test_slippage_capped_at_limit() {
+        let mut exchange = PaperExchange::new(config());
+        exchange.on_quote("BTCUSDT", quote(99.0, 100.0, 5.0), 0);
+        exchange.submit(order("a", Side::Buy, 100.05, 1.0), 0);
+        let reports = exchange.advance(5);
+        assert_eq!(reports[1].last_price, 100.05);
+        assert_eq!(reports[1].update.status, OrderStatus::Filled);
+        assert!(exchange.open_orders().is_empty());
+    }
+
+    #[test]
+    fn test_resting_maker_fills() {
+        let mut exchange = PaperExchange::new(config());
+        exchange.on_quote("BTCUSDT", quote(99.0, 100.0, 1.0), 0);
+        exchange.submit(order("a", Side::Sell, 102.0, 2.0), 0);
+        exchange.advance(5);
+
+        // A trade at the limit doesn't fill, a trade through it does
+        assert!(exchange.on_trade("BTCUSDT", 102.0, 5.0, 10).is_empty());
+        let reports = exchange.on_trade("BTCUSDT", 102.5, 0.5, 11);
+        assert_eq!((reports[0].last_price, reports[0].last_qty), (102.0, 0.5));
+        assert!(reports[0].is_maker);
+
+        // The bid crossing the limit fills the rest
+        let reports = exchange.on_quote("BTCUSDT", quote(103.0, 104.0, 10.0), 12);
+        assert_eq!(reports[0].last_qty, 1.5);
+        assert_eq!(reports[0].update.status, OrderStatus::Filled);
+        assert_eq!(reports[0].update.executed_qty, 2.0);
+        assert!(exchange.open_orders().is_empty());
+    }
+
+    #[test]
+    fn test_cancel_and_reject() {
+        let mut exchange = PaperExchange::new(config());
+        exchange.submit(order("a", Side::Buy, 90.0, 1.0), 0);
+        exchange.submit(order("b", Side::Buy, 90.0, 0.0), 0);
+        exchange.cancel("a", 1);
+
+        let reports = exchange.advance(6);
+        let types: Vec<_> = reports.iter().map(|r| (r.update.client_order_id.as_str(), r.execution_type)).collect();
+        assert_eq!(
+            types,
+            vec![("a", ExecutionType::New), ("b", ExecutionType::Rejected), ("a", ExecutionType::Canceled)]
+        );
+        assert_eq!(reports[2].update.status, OrderStatus::Canceled);
+    }
+
+    #[test]
+    fn test_report_json() {
+        let mut exchange = PaperExchange::new(config());
+        exchange.on_quote("BTCUSDT", quote(99.0, 100.0, 1.0), 0);
+        exchange.submit(order("a", Side::Buy, 100.0, 1.0), 0);
+        let reports = exchange.advance(5);
+
+        let json: serde_json::Value = serde_json::from_str(&reports[1].to_json()).unwrap();
+        assert_eq!(json["e"], "executionReport");
+        assert_eq!(json["x"], "TRADE");
+        assert_eq!(json["X"], "FILLED");
+        assert_eq!(json["L"], "100");
+        assert_eq!(json["N"], "USDT");
+        assert_eq!(json["t"], 1);
+
+        let json: serde_json::Value = serde_json::from_str(&reports[0].to_json()).unwrap();
+        assert_eq!(json["x"], "NEW");
+        assert!(json["N"].is_null());
+    }
+}
@@ -0,0 +1,304 @@
+//! Shared order rate budget.
+//!
+//! Binance limits the orders placed per account over a 10-second window and a
+//! day (UTC), a request exceeding either being rejected. The ledger lives in a
+//! shared memory region created by ctl-resource-manager, so the budget left is
+//! observable by any attached process: the OMS reserves the orders of each
+//! request before sending it, and updates the counts from those reported by
+//! the exchange, in the `X-MBX-ORDER-COUNT-*` response headers or the
+//! `rateLimits` of the websocket API responses.
+//! https://github.com/binance/binance-spot-api-docs/blob/master/rest-api.md#unfilled-order-count
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use ctl_shm::{c_struct, CHeader, ShmError, ShmSafe};
+use serde::{Deserialize, Serialize};
+
+use crate::OmsError;
+
+/// Name of the order rate ledger region.
+pub const ORDER_RATE_REGION_NAME: &str = "ctl_oms_order_rate";
+
+/// The response header reporting the orders placed in the current 10 seconds.
+pub const ORDER_COUNT_10S_HEADER: &str = "x-mbx-order-count-10s";
+
+/// The response header reporting the orders placed in the current day.
+pub const ORDER_COUNT_1D_HEADER: &str = "x-mbx-order-count-1d";
+
+/// Length of the short window, in milliseconds.
+const WINDOW_10S_MS: u64 = 10_000;
+
+/// Length of the daily window, in milliseconds.
+const WINDOW_1D_MS: u64 = 86_400_000;
+
+/// What the OMS does with a request beyond the order rate budget.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Throttle {
+    /// The request is rejected, the strategy retrying it if still wanted.
+    #[default]
+    Reject,
+    /// The request is queued, and released in arrival order once the budget allows it.
+    Queue,
+}
+
+/// A rate limit usage of a websocket API response.
+/// https://github.com/binance/binance-spot-api-docs/blob/master/web-socket-api.md#rate-limits
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RateLimitCount {
+    /// The limited quantity, e.g. `ORDERS` or `REQUEST_WEIGHT`.
+    pub rate_limit_type: String,
+    /// The unit of the window, e.g. `SECOND` or `DAY`.
+    pub interval: String,
+    /// The number of units of the window.
+    pub interval_num: u64,
+    /// The limit over the window.
+    pub limit: u64,
+    /// The usage in the current window.
+    pub count: u64,
+}
+
+/// A window of the order count.
+#[repr(C)]
+#[derive(Debug, Default)]
+struct OrderWindow {
+    /// The budget of orders per window, zero when unlimited.
+    limit: AtomicU64,
+    /// The window (since the epoch) the count belongs to.
+    window: AtomicU64,
+    /// The orders placed in the window.
+    count: AtomicU64,
+}
+
+impl OrderWindow {
+    /// Resets the count when `now_ms` is in a later window.
+    fn roll(&self, now_ms: u64, window_ms: u64) {
+        let window = now_ms / window_ms;
+        let current = self.window.load(Ordering::Acquire);
+        if window <= current {
+            return;
+        }
+        // Only the caller winning the rollover resets the count
+        if self
+            .window
+            .compare_exchange(current, window, Ordering::AcqRel, Ordering::Acquire)
+            .is_ok()
+        {
+            self.count.store(0, Ordering::Release);
+        }
+    }
+
+    /// Adds `orders` to the count if within the limit, returning the count before them on failure.
+    fn try_add(&self, orders: u64) -> Result<u64, u64> {
+        let limit = self.limit.load(Ordering::Relaxed);
+        self.count.fetch_update(Ordering::AcqRel, Ordering::Acquire, |count| {
+            (limit == 0 || count + orders <= limit).then_some(count + orders)
+        })
+    }
+}
+
+/// The orders placed in the current windows, and the requests held back by the budget.
+#[repr(C)]
+#[derive(Debug, Default)]
+pub struct OrderRateLedger {
+    /// The 10-second window.
+    short: OrderWindow,
+    /// The daily window.
+    daily: OrderWindow,
+    /// Number of requests queued until the budget allows them.
+    queued: AtomicU64,
+    /// Number of requests rejected by the budget.
+    rejected: AtomicU64,
+}
+
+// SAFETY: `OrderRateLedger` is `repr(C)`, made only of atomics and valid when zeroed.
+unsafe impl ShmSafe for OrderRateLedger {}
+
+/// Declares the order rate ledger region and the messages of the order entry
+/// ring in a C header.
+///
+/// # Errors
+/// Returns an error if the C layout of the ledger or of a message differs from
+/// the Rust one.
+pub fn declare_c_types(header: &mut CHeader) -> Result<(), ShmError> {
+    header.section("Order rate ledger region (ctl-oms)");
+    header.define_str("CTL_ORDER_RATE_REGION_NAME", ORDER_RATE_REGION_NAME);
+    let window = c_struct!(OrderWindow as "ctl_order_window" {
+        limit: "uint64_t",
+        window: "uint64_t",
+        count: "uint64_t",
+    });
+    header.structure("A window of the order count, its fields accessed atomically.", window)?;
+    let ledger = c_struct!(OrderRateLedger as "ctl_order_rate_ledger" {
+        short: "ctl_order_window",
+        daily: "ctl_order_window",
+        queued: "uint64_t",
+        rejected: "uint64_t",
+    });
+    header.structure("The orders placed in the current windows, its fields accessed atomically.", ledger)?;
+    crate::entry::declare_c_types(header)
+}
+
+impl OrderRateLedger {
+    /// Sets the order budgets per 10 seconds and per day, zero for unlimited.
+    pub fn set_limits(&self, limit_10s: u64, limit_1d: u64) {
+        self.short.limit.store(limit_10s, Ordering::Relaxed);
+        self.daily.limit.store(limit_1d, Ordering::Relaxed);
+    }
+
+    /// Returns the order budgets per 10 seconds and per day.
+    pub fn limits(&self) -> (u64, u64) {
+        (self.short.limit.load(Ordering::Relaxed), self.daily.limit.load(Ordering::Relaxed))
+    }
+
+    /// Returns the orders placed in the 10 seconds and the day of `now_ms`.
+    pub fn counts(&self, now_ms: u64) -> (u64, u64) {
+        self.roll(now_ms);
+        (self.short.count.load(Ordering::Acquire), self.daily.count.load(Ordering::Acquire))
+    }
+
+    /// Returns the orders left in the budgets of the 10 seconds and the day of
+    /// `now_ms`, `u64::MAX` when unlimited.
+    pub fn remaining(&self, now_ms: u64) -> (u64, u64) {
+        let (count_10s, count_1d) = self.counts(now_ms);
+        let remaining = |limit: u64, count: u64| if limit == 0 { u64::MAX } else { limit.saturating_sub(count) };
+        let (limit_10s, limit_1d) = self.limits();
+        (remaining(limit_10s, count_10s), remaining(limit_1d, count_1d))
+    }
+
+    /// Returns the number of requests queued by the budget.
+    pub fn queued(&self) -> u64 {
+        self.queued.load(Ordering::Relaxed)
+    }
+
+    /// Sets the number of requests queued by the budget.
+    pub fn set_queued(&self, queued: u64) {
+        self.queued.store(queued, Ordering::Relaxed);
+    }
+
+    /// Returns the number of requests rejected by the budget.
+    pub fn rejected(&self) -> u64 {
+        self.rejected.load(Ordering::Relaxed)
+    }
+
+    /// Records a request rejected by the budget.
+    pub fn record_rejected(&self) {
+        self.rejected.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Resets the counts of the windows `now_ms` is past.
+    fn roll(&self, now_ms: u64) {
+        self.short.roll(now_ms, WINDOW_10S_MS);
+        self.daily.roll(now_ms, WINDOW_1D_MS);
+    }
+
+    /// Reserves the orders of a request in both windows.
+    ///
+    /// # Errors
+    /// Returns an error if the request would exceed either budget, nothing being reserved.
+    pub fn try_reserve(&self, orders: u64, now_ms: u64) -> Result<(), OmsError> {
+        self.roll(now_ms);
+        self.short.try_add(orders).map_err(|count| OmsError::OrderRateExceeded {
+            interval: "10s",
+            count,
+            orders,
+            limit: self.limits().0,
+        })?;
+        self.daily.try_add(orders).map(drop).map_err(|count| {
+            // Saturating, the window may have rolled over since the reservation
+            let _ = self.short.count.fetch_update(Ordering::AcqRel, Ordering::Acquire, |short| {
+                Some(short.saturating_sub(orders))
+            });
+            OmsError::OrderRateExceeded { interval: "1d", count, orders, limit: self.limits().1 }
+        })
+    }
+
+    /// Returns the time the 10-second budget resets after `now_ms`, when the
+    /// queued requests may be retried.
+    pub fn next_window_ms(&self, now_ms: u64) -> u64 {
+        (now_ms / WINDOW_10S_MS + 1) * WINDOW_10S_MS
+    }
+
+    /// Records the order counts reported by the exchange for the windows of `now_ms`.
+    ///
+    /// The reported counts are authoritative, but reservations of concurrent
+    /// requests not yet accounted for by the exchange are kept.
+    pub fn record_counts(&self, count_10s: Option<u64>, count_1d: Option<u64>, now_ms: u64) {
+        self.roll(now_ms);
+        if let Some(count) = count_10s {
+            self.short.count.fetch_max(count, Ordering::AcqRel);
+        }
+        if let Some(count) = count_1d {
+            self.daily.count.fetch_max(count, Ordering::AcqRel);
+        }
+    }
+
+    /// Records the order counts of the `rateLimits` of a websocket API response.
+    pub fn record_rate_limits(&self, rate_limits: &[RateLimitCount], now_ms: u64) {
+        let count = |interval: &str, interval_num: u64| {
+            rate_limits
+                .iter()
+                .find(|rate| rate.rate_limit_type == "ORDERS" && rate.interval == interval && rate.interval_num == interval_num)
+                .map(|rate| rate.count)
+        };
+        self.record_counts(count("SECOND", 10), count("DAY", 1), now_ms);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reserve_within_budgets() {
+        let ledger = OrderRateLedger::default();
+        ledger.set_limits(3, 4);
+        ledger.try_reserve(2, 0).unwrap();
+        assert!(matches!(
+            ledger.try_reserve(2, 1_000),
+            Err(OmsError::OrderRateExceeded { interval: "10s", count: 2, orders: 2, limit: 3 })
+        ));
+        ledger.try_reserve(1, 2_000).unwrap();
+        assert_eq!(ledger.remaining(2_000), (0, 1));
+        assert_eq!(ledger.next_window_ms(2_000), 10_000);
+
+        // The short window resets, but not the daily one, leaving the short count untouched
+        ledger.try_reserve(1, 10_000).unwrap();
+        assert!(matches!(ledger.try_reserve(1, 10_000), Err(OmsError::OrderRateExceeded { interval: "1d", .. })));
+        assert_eq!(ledger.counts(10_000), (1, 4));
+        ledger.try_reserve(1, WINDOW_1D_MS).unwrap();
+        assert_eq!(ledger.counts(WINDOW_1D_MS), (1, 1));
+    }
+
+    #[test]
+    fn test_record_reported_counts() {
+        let ledger = OrderRateLedger::default();
+        assert_eq!(ledger.remaining(0), (u64::MAX, u64::MAX));
+        ledger.try_reserve(2, 0).unwrap();
+
+        let rate_limits: Vec<RateLimitCount> = serde_json::from_str(
+            r#"[{"rateLimitType":"REQUEST_WEIGHT","interval":"MINUTE","intervalNum":1,"limit":6000,"count":12},{"rateLimitType":"ORDERS","interval":"SECOND","intervalNum":10,"limit":50,"count":5},{"rateLimitType":"ORDERS","interval":"DAY","intervalNum":1,"limit":160000,"count":120}]"#,
+        )
+        .unwrap();
+        ledger.record_rate_limits(&rate_limits, 100);
+        assert_eq!(ledger.counts(100), (5, 120));
+
+        // A stale, lower report doesn't drop concurrent reservations
+        ledger.record_counts(Some(1), None, 200);
+        assert_eq!(ledger.counts(200), (5, 120));
+    }
+
+    #[test]
+    fn test_declare_c_types() {
+        let mut header = CHeader::new("CTL_TEST_H");
+        declare_c_types(&mut header).unwrap();
+        let rendered = header.render();
+        assert!(rendered.contains("#define CTL_ORDER_RATE_REGION_NAME \"ctl_oms_order_rate\"\n"));
+        assert!(rendered.contains("CTL_STATIC_ASSERT(offsetof(ctl_order_rate_ledger, queued) == 48, "));
+        assert!(rendered.contains("#define CTL_ORDER_ENTRY_RING_NAME \"ORDER_ENTRY_PS\"\n"));
+        assert!(rendered.contains("#define CTL_ENTRY_ACTION_CANCEL 2\n"));
+        assert!(rendered.contains("CTL_STATIC_ASSERT(offsetof(ctl_order_entry_message, symbol) == 68, "));
+        assert!(rendered.contains("CTL_STATIC_ASSERT(sizeof(ctl_order_entry_message) == 120, "));
+    }
+}
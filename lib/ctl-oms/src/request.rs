@@ -0,0 +1,219 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{ListOrderStatus, OmsError, Order, OrderList, OrderListKind, OrderStatus, OrderType, Side, StpMode, TimeInForce};
+
+/// A new order requested by a strategy.
+///
+/// The parameters are checked against the rules of the symbol with
+/// [`ExchangeRules::check`](crate::ExchangeRules::check).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NewOrder {
+    /// The client order ID, unique across the orders tracked.
+    pub client_order_id: String,
+    /// The symbol.
+    pub symbol: String,
+    /// The side.
+    pub side: Side,
+    /// The type, a limit order by default.
+    #[serde(default)]
+    pub order_type: OrderType,
+    /// The limit price.
+    pub price: f64,
+    /// The quantity.
+    pub qty: f64,
+    /// The trigger price of a stop order (or stop leg of an order list).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stop_price: Option<f64>,
+    /// The time in force of the order types resting at a limit price, GTC if unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub time_in_force: Option<TimeInForce>,
+    /// The visible quantity of an iceberg order.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub iceberg_qty: Option<f64>,
+    /// The STP mode, the symbol's default if unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub self_trade_prevention_mode: Option<StpMode>,
+}
+
+impl From<NewOrder> for Order {
+    fn from(order: NewOrder) -> Self {
+        Self {
+            client_order_id: order.client_order_id,
+            order_id: None,
+            symbol: order.symbol,
+            side: order.side,
+            price: order.price,
+            orig_qty: order.qty,
+            executed_qty: 0.0,
+            status: OrderStatus::PendingNew,
+            list_client_order_id: None,
+        }
+    }
+}
+
+/// A new order list requested by a strategy.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NewOrderList {
+    /// The client order list ID, unique across the lists tracked.
+    pub list_client_order_id: String,
+    /// The contingency of the legs.
+    pub kind: OrderListKind,
+    /// The legs, the working leg first for an OTO or OTOCO.
+    pub legs: Vec<NewOrder>,
+}
+
+impl NewOrderList {
+    /// Checks that the legs are those of the contingency: their number, a
+    /// single symbol, and the two legs of an OCO pair on the same side.
+    ///
+    /// # Errors
+    /// Returns an error describing the first invalid leg.
+    pub fn check(&self) -> Result<(), OmsError> {
+        let invalid = |reason| {
+            Err(OmsError::InvalidOrderList { list_client_order_id: self.list_client_order_id.clone(), reason })
+        };
+        if self.legs.len() != self.kind.num_legs() {
+            return invalid("wrong number of legs");
+        }
+        if self.legs.iter().any(|leg| leg.symbol != self.legs[0].symbol) {
+            return invalid("legs on different symbols");
+        }
+        if self.kind.oco_pair().is_some_and(|first| self.legs[first].side != self.legs[first + 1].side) {
+            return invalid("OCO legs on different sides");
+        }
+        Ok(())
+    }
+
+    /// Splits the request into the list and its legs, pending submission.
+    pub fn into_list(self) -> (OrderList, Vec<Order>) {
+        let legs: Vec<Order> = self
+            .legs
+            .into_iter()
+            .map(|leg| Order { list_client_order_id: Some(self.list_client_order_id.clone()), ..Order::from(leg) })
+            .collect();
+        let list = OrderList {
+            list_client_order_id: self.list_client_order_id,
+            order_list_id: None,
+            kind: self.kind,
+            symbol: legs.first().map(|leg| leg.symbol.clone()).unwrap_or_default(),
+            legs: legs.iter().map(|leg| leg.client_order_id.clone()).collect(),
+            status: ListOrderStatus::Pending,
+        };
+        (list, legs)
+    }
+}
+
+/// A request of a strategy to the OMS.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum OrderRequest {
+    /// Places a new order.
+    New(NewOrder),
+    /// Cancels an order.
+    Cancel {
+        /// The client order ID of the order to cancel.
+        client_order_id: String,
+    },
+    /// Cancels an order and places a new one in a single round trip
+    /// (`order.cancelReplace`), e.g. to requote.
+    CancelReplace {
+        /// The client order ID of the order to cancel.
+        cancel_client_order_id: String,
+        /// Whether the new order is placed even if the cancel fails
+        /// (`ALLOW_FAILURE`), rather than only once it succeeded.
+        #[serde(default)]
+        allow_cancel_failure: bool,
+        /// The new order, on the symbol of the canceled one.
+        order: NewOrder,
+    },
+    /// Places an order list.
+    NewList(NewOrderList),
+    /// Cancels an order list, with all its legs.
+    CancelList {
+        /// The client order list ID of the list to cancel.
+        list_client_order_id: String,
+    },
+}
+
+impl OrderRequest {
+    /// Returns the number of orders the request places, counted by the
+    /// exchange's order rate limits: cancels place none.
+    pub fn num_orders(&self) -> u64 {
+        match self {
+            Self::New(_) | Self::CancelReplace { .. } => 1,
+            Self::NewList(list) => list.legs.len() as u64,
+            Self::Cancel { .. } | Self::CancelList { .. } => 0,
+        }
+    }
+
+    /// Returns the ID the exchange reports the request under: the client order
+    /// ID of the new order or of the order to cancel, or the client order list
+    /// ID of the list.
+    pub fn client_order_id(&self) -> &str {
+        match self {
+            Self::New(order) | Self::CancelReplace { order, .. } => &order.client_order_id,
+            Self::Cancel { client_order_id } => client_order_id,
+            Self::NewList(list) => &list.list_client_order_id,
+            Self::CancelList { list_client_order_id } => list_client_order_id,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_deserialize_cancel_replace() {
+        let json = r#"{"type":"cancel_replace","cancel_client_order_id":"a","order":{"client_order_id":"b","symbol":"BTCUSDT","side":"BUY","price":100.5,"qty":0.1}}"#;
+        let request: OrderRequest = serde_json::from_str(json).unwrap();
+        let OrderRequest::CancelReplace { cancel_client_order_id, allow_cancel_failure, order } = request else {
+            panic!("not a cancel-replace");
+        };
+        assert_eq!(cancel_client_order_id, "a");
+        assert!(!allow_cancel_failure);
+
+        let order = Order::from(order);
+        assert_eq!((order.side, order.price, order.orig_qty), (Side::Buy, 100.5, 0.1));
+        assert_eq!(order.status, OrderStatus::PendingNew);
+    }
+
+    fn leg(client_order_id: &str, side: Side, price: f64, stop_price: Option<f64>) -> NewOrder {
+        NewOrder {
+            client_order_id: client_order_id.to_string(),
+            symbol: "BTCUSDT".to_string(),
+            side,
+            order_type: if stop_price.is_some() { OrderType::StopLossLimit } else { OrderType::LimitMaker },
+            price,
+            qty: 1.0,
+            stop_price,
+            time_in_force: None,
+            iceberg_qty: None,
+            self_trade_prevention_mode: None,
+        }
+    }
+
+    #[test]
+    fn test_new_order_list() {
+        let otoco = NewOrderList {
+            list_client_order_id: "bracket".to_string(),
+            kind: OrderListKind::Otoco,
+            legs: vec![
+                leg("entry", Side::Buy, 100.0, None),
+                leg("take-profit", Side::Sell, 110.0, None),
+                leg("stop-loss", Side::Sell, 94.0, Some(95.0)),
+            ],
+        };
+        otoco.check().unwrap();
+        let (list, legs) = otoco.clone().into_list();
+        assert_eq!((list.symbol.as_str(), list.status), ("BTCUSDT", ListOrderStatus::Pending));
+        assert_eq!(list.legs, ["entry", "take-profit", "stop-loss"]);
+        assert!(legs.iter().all(|leg| leg.list_client_order_id.as_deref() == Some("bracket")));
+
+        let mut invalid = otoco.clone();
+        invalid.legs[2].side = Side::Buy;
+        assert!(matches!(invalid.check(), Err(OmsError::InvalidOrderList { reason: "OCO legs on different sides", .. })));
+        let oco = NewOrderList { kind: OrderListKind::Oco, ..otoco };
+        assert!(matches!(oco.check(), Err(OmsError::InvalidOrderList { reason: "wrong number of legs", .. })));
+    }
+}
@@ -0,0 +1,281 @@
+use hashbrown::HashMap;
+use serde::{Deserialize, Serialize};
+
+use crate::{NewOrder, OmsError};
+
+/// The type of an order.
+/// https://github.com/binance/binance-spot-api-docs/blob/master/enums.md#order-types-ordertypes-type
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum OrderType {
+    #[default]
+    Limit,
+    /// A limit order rejected if it would immediately match as taker.
+    LimitMaker,
+    Market,
+    StopLoss,
+    StopLossLimit,
+    TakeProfit,
+    TakeProfitLimit,
+}
+
+impl OrderType {
+    /// Returns the name of the order type on the exchange.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Limit => "LIMIT",
+            Self::LimitMaker => "LIMIT_MAKER",
+            Self::Market => "MARKET",
+            Self::StopLoss => "STOP_LOSS",
+            Self::StopLossLimit => "STOP_LOSS_LIMIT",
+            Self::TakeProfit => "TAKE_PROFIT",
+            Self::TakeProfitLimit => "TAKE_PROFIT_LIMIT",
+        }
+    }
+
+    /// Returns true if the orders of the type rest at a limit price with a time in force.
+    pub fn has_time_in_force(self) -> bool {
+        matches!(self, Self::Limit | Self::StopLossLimit | Self::TakeProfitLimit)
+    }
+
+    /// Returns true if the orders of the type are triggered at a stop price.
+    pub fn has_stop_price(self) -> bool {
+        matches!(self, Self::StopLoss | Self::StopLossLimit | Self::TakeProfit | Self::TakeProfitLimit)
+    }
+}
+
+/// How long an order stays working.
+/// https://github.com/binance/binance-spot-api-docs/blob/master/enums.md#time-in-force-timeinforce
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum TimeInForce {
+    /// Good-til-canceled: working until filled or canceled.
+    #[default]
+    Gtc,
+    /// Immediate-or-cancel: the part not filled on arrival expires.
+    Ioc,
+    /// Fill-or-kill: expires on arrival unless fully filled.
+    Fok,
+}
+
+/// How the exchange prevents an order trading against an order of the same account.
+/// https://github.com/binance/binance-spot-api-docs/blob/master/enums.md#stp-modes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum StpMode {
+    /// Self-trades are allowed.
+    #[serde(rename = "NONE")]
+    Disabled,
+    ExpireTaker,
+    ExpireMaker,
+    ExpireBoth,
+    /// Both orders are decreased by the quantity that would self-trade.
+    Decrement,
+}
+
+impl StpMode {
+    /// Returns the name of the mode on the exchange.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Disabled => "NONE",
+            Self::ExpireTaker => "EXPIRE_TAKER",
+            Self::ExpireMaker => "EXPIRE_MAKER",
+            Self::ExpireBoth => "EXPIRE_BOTH",
+            Self::Decrement => "DECREMENT",
+        }
+    }
+}
+
+/// The order rules of a symbol, from the exchange information endpoint response.
+/// https://github.com/binance/binance-spot-api-docs/blob/master/rest-api.md#exchange-information
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SymbolRules {
+    pub symbol: String,
+    /// The order types accepted.
+    #[serde(default)]
+    pub order_types: Vec<String>,
+    /// Whether the orders may be icebergs.
+    #[serde(default)]
+    pub iceberg_allowed: bool,
+    /// The STP modes accepted.
+    #[serde(default)]
+    pub allowed_self_trade_prevention_modes: Vec<String>,
+}
+
+impl SymbolRules {
+    /// Checks that the symbol accepts the order type and parameters of `order`.
+    ///
+    /// # Errors
+    /// Returns an error describing the first parameter the symbol or the order
+    /// type doesn't accept.
+    pub fn check(&self, order: &NewOrder) -> Result<(), OmsError> {
+        let invalid = |reason| {
+            Err(OmsError::InvalidOrder { client_order_id: order.client_order_id.clone(), reason })
+        };
+        let order_type = order.order_type;
+        if !self.order_types.iter().any(|name| name == order_type.as_str()) {
+            return invalid("order type not allowed on the symbol");
+        }
+        if order.time_in_force.is_some() && !order_type.has_time_in_force() {
+            return invalid("time in force on an order type without one");
+        }
+        if order.stop_price.is_some() != order_type.has_stop_price() {
+            return invalid("stop price missing, or on an order type without one");
+        }
+
+        if let Some(iceberg_qty) = order.iceberg_qty {
+            if !self.iceberg_allowed {
+                return invalid("iceberg orders not allowed on the symbol");
+            }
+            if !(order_type == OrderType::LimitMaker || order_type.has_time_in_force()) {
+                return invalid("iceberg quantity on an order type without a limit price");
+            }
+            if order.time_in_force.unwrap_or_default() != TimeInForce::Gtc {
+                return invalid("iceberg orders must be GTC");
+            }
+            if iceberg_qty <= 0.0 || iceberg_qty >= order.qty {
+                return invalid("iceberg quantity not within the order quantity");
+            }
+        }
+
+        let stp_allowed = order
+            .self_trade_prevention_mode
+            .is_none_or(|mode| self.allowed_self_trade_prevention_modes.iter().any(|name| name == mode.as_str()));
+        if !stp_allowed {
+            return invalid("STP mode not allowed on the symbol");
+        }
+        Ok(())
+    }
+}
+
+/// The symbols of the exchange information endpoint response.
+#[derive(Deserialize)]
+struct ExchangeInfoSymbols {
+    symbols: Vec<SymbolRules>,
+}
+
+/// The order rules of the symbols, from the exchange information endpoint response.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(from = "ExchangeInfoSymbols")]
+pub struct ExchangeRules {
+    symbols: HashMap<String, SymbolRules>,
+}
+
+impl From<ExchangeInfoSymbols> for ExchangeRules {
+    fn from(info: ExchangeInfoSymbols) -> Self {
+        Self::new(info.symbols)
+    }
+}
+
+impl ExchangeRules {
+    /// Creates the rules of the symbols.
+    pub fn new(symbols: Vec<SymbolRules>) -> Self {
+        Self { symbols: symbols.into_iter().map(|rules| (rules.symbol.clone(), rules)).collect() }
+    }
+
+    /// Returns the rules of a symbol, if listed.
+    pub fn get(&self, symbol: &str) -> Option<&SymbolRules> {
+        self.symbols.get(symbol)
+    }
+
+    /// Checks that the symbol of `order` is listed and accepts its type and parameters.
+    ///
+    /// # Errors
+    /// Returns an error if the symbol isn't listed or rejects the order.
+    pub fn check(&self, order: &NewOrder) -> Result<(), OmsError> {
+        self.get(&order.symbol)
+            .ok_or_else(|| OmsError::UnknownSymbol(order.symbol.clone()))?
+            .check(order)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Side;
+
+    const EXCHANGE_INFO: &str = r#"{"timezone":"UTC","serverTime":1565246363776,"rateLimits":[],"exchangeFilters":[],"symbols":[{"symbol":"BTCUSDT","status":"TRADING","baseAsset":"BTC","quoteAsset":"USDT","orderTypes":["LIMIT","LIMIT_MAKER","MARKET","STOP_LOSS_LIMIT","TAKE_PROFIT_LIMIT"],"icebergAllowed":true,"defaultSelfTradePreventionMode":"EXPIRE_MAKER","allowedSelfTradePreventionModes":["EXPIRE_TAKER","EXPIRE_MAKER","EXPIRE_BOTH"],"filters":[]},{"symbol":"ETHBTC","status":"TRADING","baseAsset":"ETH","quoteAsset":"BTC","orderTypes":["LIMIT","MARKET"],"icebergAllowed":false,"filters":[]}]}"#;
+
+    fn order(symbol: &str, order_type: OrderType) -> NewOrder {
+        NewOrder {
+            client_order_id: "a".to_string(),
+            symbol: symbol.to_string(),
+            side: Side::Buy,
+            order_type,
+            price: 100.0,
+            qty: 10.0,
+            stop_price: None,
+            time_in_force: None,
+            iceberg_qty: None,
+            self_trade_prevention_mode: None,
+        }
+    }
+
+    fn reason(result: Result<(), OmsError>) -> &'static str {
+        match result {
+            Err(OmsError::InvalidOrder { reason, .. }) => reason,
+            result => panic!("not an invalid order: {:?}", result),
+        }
+    }
+
+    #[test]
+    fn test_check_order_parameters() {
+        let rules: ExchangeRules = serde_json::from_str(EXCHANGE_INFO).unwrap();
+        let limit = order("BTCUSDT", OrderType::Limit);
+        rules.check(&limit).unwrap();
+        rules
+            .check(&NewOrder {
+                time_in_force: Some(TimeInForce::Gtc),
+                iceberg_qty: Some(1.0),
+                self_trade_prevention_mode: Some(StpMode::ExpireBoth),
+                ..limit.clone()
+            })
+            .unwrap();
+        rules.check(&NewOrder { stop_price: Some(99.0), ..order("BTCUSDT", OrderType::StopLossLimit) }).unwrap();
+
+        assert!(matches!(rules.check(&order("XRPUSDT", OrderType::Limit)), Err(OmsError::UnknownSymbol(_))));
+        assert_eq!(reason(rules.check(&order("BTCUSDT", OrderType::StopLoss))), "order type not allowed on the symbol");
+        assert_eq!(
+            reason(rules.check(&NewOrder { time_in_force: Some(TimeInForce::Ioc), ..order("BTCUSDT", OrderType::Market) })),
+            "time in force on an order type without one"
+        );
+        assert_eq!(
+            reason(rules.check(&order("BTCUSDT", OrderType::TakeProfitLimit))),
+            "stop price missing, or on an order type without one"
+        );
+        assert_eq!(
+            reason(rules.check(&NewOrder { time_in_force: Some(TimeInForce::Fok), iceberg_qty: Some(1.0), ..limit.clone() })),
+            "iceberg orders must be GTC"
+        );
+        assert_eq!(
+            reason(rules.check(&NewOrder { iceberg_qty: Some(10.0), ..limit.clone() })),
+            "iceberg quantity not within the order quantity"
+        );
+        assert_eq!(
+            reason(rules.check(&NewOrder { iceberg_qty: Some(1.0), ..order("ETHBTC", OrderType::Limit) })),
+            "iceberg orders not allowed on the symbol"
+        );
+        assert_eq!(
+            reason(rules.check(&NewOrder { self_trade_prevention_mode: Some(StpMode::Disabled), ..limit })),
+            "STP mode not allowed on the symbol"
+        );
+    }
+
+    #[test]
+    fn test_deserialize_order_parameters() {
+        let order: NewOrder = serde_json::from_str(
+            r#"{"client_order_id":"a","symbol":"BTCUSDT","side":"SELL","order_type":"LIMIT_MAKER","price":100.0,"qty":1.0,"iceberg_qty":0.1,"self_trade_prevention_mode":"NONE"}"#,
+        )
+        .unwrap();
+        assert_eq!(order.order_type, OrderType::LimitMaker);
+        assert_eq!(order.time_in_force, None);
+        assert_eq!(order.self_trade_prevention_mode, Some(StpMode::Disabled));
+
+        let order: NewOrder = serde_json::from_str(
+            r#"{"client_order_id":"a","symbol":"BTCUSDT","side":"BUY","price":100.0,"qty":1.0,"time_in_force":"IOC"}"#,
+        )
+        .unwrap();
+        assert_eq!((order.order_type, order.time_in_force), (OrderType::Limit, Some(TimeInForce::Ioc)));
+    }
+}
@@ -0,0 +1,15 @@
+//! Position tracking from execution reports.
+//!
+//! The tracker applies the fills of the user data stream execution reports to
+//! per-symbol positions, and publishes them to a shared memory table created by
+//! ctl-resource-manager, where strategies and tooling read them lock-free.
+
+mod position;
+mod report;
+mod table;
+
+pub use position::{Fill, Position, Side};
+pub use report::ExecutionReport;
+pub use table::{
+    PositionRegion, PositionSlot, PositionTracker, MAX_POSITION_SYMBOLS, POSITION_REGION_NAME,
+};
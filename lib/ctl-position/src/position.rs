@@ -0,0 +1,133 @@
+/// The side of a fill.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    Buy,
+    Sell,
+}
+
+/// A fill of an order.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Fill {
+    /// The side.
+    pub side: Side,
+    /// The fill price.
+    pub price: f64,
+    /// The filled base quantity.
+    pub qty: f64,
+    /// The commission charged in the base asset.
+    pub base_commission: f64,
+    /// The commission charged in the quote asset.
+    pub quote_commission: f64,
+}
+
+/// The position of a symbol.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Position {
+    /// The net base asset position, negative when short.
+    pub base: f64,
+    /// The net quote asset flow of the fills.
+    pub quote: f64,
+    /// The average entry price of the open base position, zero when flat.
+    pub avg_entry_price: f64,
+    /// The realized PnL in the quote asset, net of the quote commissions.
+    pub realized_pnl: f64,
+    /// The number of fills applied.
+    pub fill_count: u64,
+}
+
+impl Position {
+    /// Applies a fill to the position.
+    ///
+    /// Fills extending the position move the average entry price; fills
+    /// reducing it realize the PnL against it, and a fill flipping the position
+    /// opens the remainder at the fill price. Base commissions reduce the
+    /// position without moving the entry price.
+    pub fn apply(&mut self, fill: &Fill) {
+        let qty = match fill.side {
+            Side::Buy => fill.qty,
+            Side::Sell => -fill.qty,
+        };
+        let open = self.base.abs();
+
+        if self.base == 0.0 || self.base.signum() == qty.signum() {
+            self.avg_entry_price = (self.avg_entry_price * open + fill.price * fill.qty) / (open + fill.qty);
+        } else {
+            let closed = fill.qty.min(open);
+            self.realized_pnl += closed * (fill.price - self.avg_entry_price) * self.base.signum();
+            if fill.qty > open {
+                self.avg_entry_price = fill.price;
+            } else if fill.qty == open {
+                self.avg_entry_price = 0.0;
+            }
+        }
+
+        self.base += qty - fill.base_commission;
+        self.quote -= qty * fill.price + fill.quote_commission;
+        self.realized_pnl -= fill.quote_commission;
+        self.fill_count += 1;
+    }
+
+    /// Returns the unrealized PnL of the open position at `mark_price`.
+    pub fn unrealized_pnl(&self, mark_price: f64) -> f64 {
+        self.base * (mark_price - self.avg_entry_price)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fill(side: Side, price: f64, qty: f64) -> Fill {
+        Fill { side, price, qty, base_commission: 0.0, quote_commission: 0.0 }
+    }
+
+    #[test]
+    fn test_average_entry_and_realized_pnl() {
+        let mut position = Position::default();
+        position.apply(&fill(Side::Buy, 100.0, 1.0));
+        position.apply(&fill(Side::Buy, 130.0, 2.0));
+        assert_eq!(position.base, 3.0);
+        assert_eq!(position.avg_entry_price, 120.0);
+        assert_eq!(position.quote, -360.0);
+        assert_eq!(position.unrealized_pnl(125.0), 15.0);
+
+        position.apply(&fill(Side::Sell, 150.0, 1.0));
+        assert_eq!(position.base, 2.0);
+        assert_eq!(position.avg_entry_price, 120.0);
+        assert_eq!(position.realized_pnl, 30.0);
+
+        position.apply(&fill(Side::Sell, 110.0, 2.0));
+        assert_eq!(position.base, 0.0);
+        assert_eq!(position.avg_entry_price, 0.0);
+        assert_eq!(position.realized_pnl, 10.0);
+        assert_eq!(position.quote, 10.0);
+        assert_eq!(position.fill_count, 4);
+    }
+
+    #[test]
+    fn test_flip_position() {
+        let mut position = Position::default();
+        position.apply(&fill(Side::Buy, 100.0, 1.0));
+        position.apply(&fill(Side::Sell, 90.0, 3.0));
+        assert_eq!(position.base, -2.0);
+        assert_eq!(position.avg_entry_price, 90.0);
+        assert_eq!(position.realized_pnl, -10.0);
+
+        // Covering the short below its entry realizes a profit
+        position.apply(&fill(Side::Buy, 80.0, 2.0));
+        assert_eq!(position.realized_pnl, 10.0);
+        assert_eq!(position.base, 0.0);
+    }
+
+    #[test]
+    fn test_commissions() {
+        let mut position = Position::default();
+        position.apply(&Fill { base_commission: 0.001, ..fill(Side::Buy, 100.0, 1.0) });
+        assert_eq!(position.base, 0.999);
+        assert_eq!(position.avg_entry_price, 100.0);
+
+        position.apply(&Fill { quote_commission: 0.1, ..fill(Side::Sell, 100.0, 0.5) });
+        assert!((position.realized_pnl + 0.1).abs() < 1e-12);
+        assert!((position.quote + 50.1).abs() < 1e-12);
+    }
+}
@@ -0,0 +1,102 @@
+use serde::Deserialize;
+
+use crate::{Fill, Side};
+
+/// An execution report payload of the user data stream.
+/// https://github.com/binance/binance-spot-api-docs/blob/master/user-data-stream.md#order-update
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct ExecutionReport<'a> {
+    #[serde(rename = "e")]
+    pub event_type: &'a str,
+    #[serde(rename = "s")]
+    pub symbol: &'a str,
+    #[serde(rename = "c")]
+    pub client_order_id: &'a str,
+    #[serde(rename = "S")]
+    pub side: &'a str,
+    #[serde(rename = "x")]
+    pub execution_type: &'a str,
+    #[serde(rename = "X")]
+    pub order_status: &'a str,
+    #[serde(rename = "i")]
+    pub order_id: u64,
+    #[serde(rename = "l")]
+    pub last_qty: &'a str,
+    #[serde(rename = "L")]
+    pub last_price: &'a str,
+    #[serde(rename = "n")]
+    pub commission: &'a str,
+    #[serde(rename = "N")]
+    pub commission_asset: Option<&'a str>,
+    #[serde(rename = "t")]
+    pub trade_id: i64,
+}
+
+impl<'a> ExecutionReport<'a> {
+    /// Parses an execution report payload, `None` if it isn't one.
+    pub fn from_json(data: &'a [u8]) -> Option<Self> {
+        let report: Self = serde_json::from_slice(data).ok()?;
+        (report.event_type == "executionReport").then_some(report)
+    }
+
+    /// Returns the fill of a trade execution, `None` for other executions.
+    ///
+    /// The commission is attributed to the base or quote asset by matching
+    /// the commission asset against the symbol; commissions in a third asset
+    /// (e.g. BNB) don't affect the position.
+    pub fn fill(&self) -> Option<Fill> {
+        if self.execution_type != "TRADE" {
+            return None;
+        }
+        let side = match self.side {
+            "BUY" => Side::Buy,
+            "SELL" => Side::Sell,
+            _ => return None,
+        };
+        let commission: f64 = self.commission.parse().ok()?;
+        let (base_commission, quote_commission) = match self.commission_asset {
+            Some(asset) if self.symbol.starts_with(asset) => (commission, 0.0),
+            Some(asset) if self.symbol.ends_with(asset) => (0.0, commission),
+            _ => (0.0, 0.0),
+        };
+        Some(Fill {
+            side,
+            price: self.last_price.parse().ok()?,
+            qty: self.last_qty.parse().ok()?,
+            base_commission,
+            quote_commission,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TRADE_REPORT: &str = r#"{"e":"executionReport","E":1499405658658,"s":"ETHBTC","c":"ctl-1-mgj6k3cw-0","S":"SELL","o":"LIMIT","f":"GTC","q":"1.00000000","p":"0.10264410","P":"0.00000000","F":"0.00000000","g":-1,"C":"","x":"TRADE","X":"PARTIALLY_FILLED","r":"NONE","i":4293153,"l":"0.25000000","z":"0.25000000","L":"0.10264410","n":"0.00002566","N":"BTC","T":1499405658657,"t":12,"I":8641984,"w":false,"m":true,"M":true,"O":1499405658657,"Z":"0.02566102","Y":"0.02566102","Q":"0.00000000","W":1499405658657,"V":"NONE"}"#;
+
+    #[test]
+    fn test_parse_trade_report() {
+        let report = ExecutionReport::from_json(TRADE_REPORT.as_bytes()).unwrap();
+        assert_eq!(report.client_order_id, "ctl-1-mgj6k3cw-0");
+        assert_eq!(report.trade_id, 12);
+
+        let fill = report.fill().unwrap();
+        assert_eq!(fill.side, Side::Sell);
+        assert_eq!(fill.price, 0.1026441);
+        assert_eq!(fill.qty, 0.25);
+        assert_eq!((fill.base_commission, fill.quote_commission), (0.0, 0.00002566));
+    }
+
+    #[test]
+    fn test_non_trade_report() {
+        let new = TRADE_REPORT
+            .replace(r#""x":"TRADE""#, r#""x":"NEW""#)
+            .replace(r#""N":"BTC""#, r#""N":null"#);
+        let report = ExecutionReport::from_json(new.as_bytes()).unwrap();
+        assert_eq!(report.commission_asset, None);
+        assert_eq!(report.fill(), None);
+
+        assert!(ExecutionReport::from_json(br#"{"e":"outboundAccountPosition"}"#).is_none());
+    }
+}
@@ -0,0 +1,203 @@
+use std::hint;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering, fence};
+
+use ctl_shm::{ShmRegion, ShmSafe};
+use hashbrown::HashMap;
+
+use crate::{ExecutionReport, Position};
+
+/// Name of the position table region.
+pub const POSITION_REGION_NAME: &str = "ctl_positions";
+
+/// Maximum number of symbols in the position table, indexed by symbol ID.
+pub const MAX_POSITION_SYMBOLS: usize = 1024;
+
+/// The seqlock-protected position of a symbol, written by a single tracker.
+#[repr(C, align(64))]
+#[derive(Debug, Default)]
+pub struct PositionSlot {
+    /// The sequence, odd while the slot is being written and zero until first written.
+    seq: AtomicU64,
+    /// The net base asset position, as `f64` bits.
+    base: AtomicU64,
+    /// The net quote asset flow, as `f64` bits.
+    quote: AtomicU64,
+    /// The average entry price, as `f64` bits.
+    avg_entry_price: AtomicU64,
+    /// The realized PnL, as `f64` bits.
+    realized_pnl: AtomicU64,
+    /// The number of fills applied.
+    fill_count: AtomicU64,
+}
+
+impl PositionSlot {
+    /// Overwrites the slot with `position`.
+    fn write(&self, position: &Position) {
+        let seq = self.seq.load(Ordering::Relaxed);
+        self.seq.store(seq + 1, Ordering::Relaxed);
+        fence(Ordering::Release);
+
+        self.base.store(position.base.to_bits(), Ordering::Relaxed);
+        self.quote.store(position.quote.to_bits(), Ordering::Relaxed);
+        self.avg_entry_price.store(position.avg_entry_price.to_bits(), Ordering::Relaxed);
+        self.realized_pnl.store(position.realized_pnl.to_bits(), Ordering::Relaxed);
+        self.fill_count.store(position.fill_count, Ordering::Relaxed);
+
+        self.seq.store(seq + 2, Ordering::Release);
+    }
+
+    /// Reads a consistent snapshot of the slot, `None` if it was never written.
+    pub fn read(&self) -> Option<Position> {
+        loop {
+            let before = self.seq.load(Ordering::Acquire);
+            if before == 0 {
+                return None;
+            }
+            if before & 1 == 1 {
+                hint::spin_loop();
+                continue;
+            }
+
+            let position = Position {
+                base: f64::from_bits(self.base.load(Ordering::Relaxed)),
+                quote: f64::from_bits(self.quote.load(Ordering::Relaxed)),
+                avg_entry_price: f64::from_bits(self.avg_entry_price.load(Ordering::Relaxed)),
+                realized_pnl: f64::from_bits(self.realized_pnl.load(Ordering::Relaxed)),
+                fill_count: self.fill_count.load(Ordering::Relaxed),
+            };
+
+            fence(Ordering::Acquire);
+            if self.seq.load(Ordering::Relaxed) == before {
+                return Some(position);
+            }
+        }
+    }
+}
+
+/// The position table region, holding one slot per symbol ID.
+#[repr(C)]
+pub struct PositionRegion {
+    /// The slots, indexed by symbol ID.
+    pub slots: [PositionSlot; MAX_POSITION_SYMBOLS],
+}
+
+// SAFETY: `PositionRegion` is `repr(C)`, made only of atomics and valid when zeroed.
+unsafe impl ShmSafe for PositionRegion {}
+
+impl PositionRegion {
+    /// Reads the position of a symbol ID, `None` if out of range or without fills.
+    pub fn read(&self, symbol_id: u32) -> Option<Position> {
+        self.slots.get(symbol_id as usize)?.read()
+    }
+}
+
+/// The position of a symbol, with the last trade applied to it.
+#[derive(Debug, Clone, Copy, Default)]
+struct TrackedPosition {
+    position: Position,
+    last_trade_id: Option<i64>,
+}
+
+/// Applies the fills of execution reports to the positions and publishes them
+/// to the position table. There must be a single tracker per table.
+pub struct PositionTracker {
+    /// The position table region.
+    region: Arc<ShmRegion<PositionRegion>>,
+    /// The symbol IDs by exchange symbol name.
+    symbols: HashMap<String, u32>,
+    /// The positions, by symbol ID.
+    positions: HashMap<u32, TrackedPosition>,
+}
+
+impl PositionTracker {
+    /// Creates a tracker of the positions of `symbols` (name and ID).
+    /// Returns `None` if a symbol ID is out of the table's range.
+    pub fn new(region: Arc<ShmRegion<PositionRegion>>, symbols: &[(String, u32)]) -> Option<Self> {
+        if symbols.iter().any(|(_, id)| *id as usize >= MAX_POSITION_SYMBOLS) {
+            return None;
+        }
+        Some(Self {
+            region,
+            symbols: symbols.iter().cloned().collect(),
+            positions: HashMap::new(),
+        })
+    }
+
+    /// Returns the position of a symbol ID, if it had fills.
+    pub fn position(&self, symbol_id: u32) -> Option<&Position> {
+        self.positions.get(&symbol_id).map(|tracked| &tracked.position)
+    }
+
+    /// Applies the fill of an execution report payload, returning the symbol ID
+    /// and its updated position.
+    ///
+    /// Payloads that aren't trade executions of a known symbol are ignored, as
+    /// are trades already applied (e.g. a report redelivered after a reconnect).
+    ///
+    /// LATENCY: FAST_PATH
+    pub fn on_report(&mut self, data: &[u8]) -> Option<(u32, Position)> {
+        let report = ExecutionReport::from_json(data)?;
+        let fill = report.fill()?;
+        let symbol_id = *self.symbols.get(report.symbol)?;
+
+        let tracked = self.positions.entry(symbol_id).or_default();
+        if tracked.last_trade_id.is_some_and(|last| report.trade_id <= last) {
+            return None;
+        }
+        tracked.last_trade_id = Some(report.trade_id);
+        tracked.position.apply(&fill);
+
+        self.region.slots[symbol_id as usize].write(&tracked.position);
+        Some((symbol_id, tracked.position))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report(side: &str, price: &str, qty: &str, trade_id: i64) -> String {
+        format!(
+            r#"{{"e":"executionReport","s":"BTCUSDT","c":"ctl-1-a-0","S":"{}","x":"TRADE","X":"PARTIALLY_FILLED","i":1,"l":"{}","L":"{}","n":"0","N":null,"t":{}}}"#,
+            side, qty, price, trade_id
+        )
+    }
+
+    #[test]
+    fn test_slot_roundtrip() {
+        let slot = PositionSlot::default();
+        assert_eq!(slot.read(), None);
+
+        let position = Position {
+            base: 1.5,
+            quote: -150.0,
+            avg_entry_price: 100.0,
+            realized_pnl: 2.0,
+            fill_count: 3,
+        };
+        slot.write(&position);
+        assert_eq!(slot.read(), Some(position));
+    }
+
+    #[test]
+    fn test_tracker_applies_fills_once() {
+        let name = format!("ctl_test_positions_{}", std::process::id());
+        let region = Arc::new(ShmRegion::<PositionRegion>::create(&name).unwrap());
+        let mut tracker = PositionTracker::new(region.clone(), &[("BTCUSDT".to_string(), 3)]).unwrap();
+
+        tracker.on_report(report("BUY", "100", "2", 10).as_bytes()).unwrap();
+        let (symbol_id, position) = tracker.on_report(report("SELL", "110", "1", 11).as_bytes()).unwrap();
+        assert_eq!(symbol_id, 3);
+        assert_eq!((position.base, position.realized_pnl), (1.0, 10.0));
+
+        // Redelivered trades and unknown symbols are ignored
+        assert!(tracker.on_report(report("SELL", "110", "1", 11).as_bytes()).is_none());
+        let unknown = report("BUY", "1", "1", 12).replace("BTCUSDT", "ETHUSDT");
+        assert!(tracker.on_report(unknown.as_bytes()).is_none());
+
+        assert_eq!(region.read(3), Some(position));
+        assert_eq!(region.read(4), None);
+        assert!(PositionTracker::new(region, &[("X".to_string(), 1024)]).is_none());
+    }
+}
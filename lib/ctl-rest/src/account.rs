@@ -0,0 +1,48 @@
+use serde::{Deserialize, Deserializer};
+
+/// The response of the account information endpoint.
+/// https://github.com/binance/binance-spot-api-docs/blob/master/rest-api.md#account-information-user_data
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountInfo {
+    /// The time of the last account update, in milliseconds since the epoch.
+    pub update_time: u64,
+    /// The balances of the assets.
+    pub balances: Vec<AccountBalance>,
+}
+
+/// The balance of an asset.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct AccountBalance {
+    /// The asset.
+    pub asset: String,
+    /// The free amount.
+    #[serde(deserialize_with = "de_f64_str")]
+    pub free: f64,
+    /// The amount locked in open orders.
+    #[serde(deserialize_with = "de_f64_str")]
+    pub locked: f64,
+}
+
+/// Deserializes a decimal sent as a JSON string.
+fn de_f64_str<'de, D: Deserializer<'de>>(deserializer: D) -> Result<f64, D::Error> {
+    let value = String::deserialize(deserializer)?;
+    value.parse().map_err(serde::de::Error::custom)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_deserialize_account_info() {
+        let account: AccountInfo = serde_json::from_str(
+            r#"{"makerCommission":15,"takerCommission":15,"canTrade":true,"updateTime":123456789,"accountType":"SPOT","balances":[{"asset":"BTC","free":"4723846.89208129","locked":"0.00000000"},{"asset":"LTC","free":"4763368.68006011","locked":"0.50000000"}],"permissions":["SPOT"],"uid":354937868}"#,
+        )
+        .unwrap();
+        assert_eq!(account.update_time, 123456789);
+        assert_eq!(account.balances.len(), 2);
+        assert_eq!(account.balances[1].asset, "LTC");
+        assert_eq!(account.balances[1].locked, 0.5);
+    }
+}
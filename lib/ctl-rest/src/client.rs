@@ -0,0 +1,355 @@
+use std::sync::Arc;
+
+use ctl_retry::{retry, Backoff, RetryBudget, RetryPolicy};
+use ctl_shm::ShmRegion;
+use ctl_time::{now_ms, TimeSyncRegion};
+use reqwest::blocking::{Client, RequestBuilder, Response};
+use reqwest::{Method, StatusCode};
+use serde::Deserialize;
+use serde::de::{DeserializeOwned, IgnoredAny};
+use url::form_urlencoded;
+
+use crate::{
+    depth_weight, AccountInfo, AggTrade, Credentials, DepthSnapshot, ExchangeInfo, HistoricalTrade, Kline, RestError,
+    WeightLedger, AGG_TRADES_WEIGHT, HISTORICAL_TRADES_WEIGHT, KLINES_WEIGHT, USED_WEIGHT_HEADER,
+};
+
+/// Base URL of the Binance Spot REST API.
+pub const BINANCE_REST_ENDPOINT: &str = "https://api.binance.com";
+
+/// The response of the check server time endpoint.
+/// https://github.com/binance/binance-spot-api-docs/blob/master/rest-api.md#check-server-time
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServerTime {
+    /// The server time, in milliseconds since the epoch.
+    pub server_time: u64,
+}
+
+/// Request weight of the check server time endpoint.
+const SERVER_TIME_WEIGHT: u64 = 1;
+
+/// Request weight of the account information endpoint.
+const ACCOUNT_WEIGHT: u64 = 20;
+
+/// Request weight of the exchange information endpoint, listing all the symbols.
+const EXCHANGE_INFO_WEIGHT: u64 = 20;
+
+/// Request weight of the cancel all open orders on a symbol endpoint.
+const CANCEL_OPEN_ORDERS_WEIGHT: u64 = 1;
+
+/// The `recvWindow` of the signed requests, in milliseconds.
+pub const RECV_WINDOW_MS: u64 = 5_000;
+
+/// A blocking Binance Spot REST API client.
+#[derive(Clone)]
+pub struct RestClient {
+    /// The base URL of the API.
+    base_url: String,
+    /// The underlying HTTP client.
+    http: Client,
+    /// The request weight ledger shared across components, if any.
+    ledger: Option<Arc<ShmRegion<WeightLedger>>>,
+    /// The API key signed requests are issued with, if any.
+    credentials: Option<Credentials>,
+    /// The clock offset estimated by ctl-time-sync, timestamping the signed requests.
+    time_sync: Option<Arc<ShmRegion<TimeSyncRegion>>>,
+    /// When the requests failing transiently are issued again, if ever.
+    retry: Option<RetryPolicy>,
+    /// The retry budget shared with other clients, if any.
+    retry_budget: Option<Arc<RetryBudget>>,
+}
+
+impl RestClient {
+    /// Creates a new client for the API at `base_url`.
+    pub fn new(base_url: &str) -> Result<Self, RestError> {
+        Ok(Self {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            http: Client::builder().build()?,
+            ledger: None,
+            credentials: None,
+            time_sync: None,
+            retry: None,
+            retry_budget: None,
+        })
+    }
+
+    /// Issues the signed requests with the API key.
+    pub fn with_credentials(mut self, credentials: Credentials) -> Self {
+        self.credentials = Some(credentials);
+        self
+    }
+
+    /// Timestamps the signed requests with the server time estimated by ctl-time-sync,
+    /// rather than the local clock.
+    pub fn with_time_sync(mut self, time_sync: Arc<ShmRegion<TimeSyncRegion>>) -> Self {
+        self.time_sync = Some(time_sync);
+        self
+    }
+
+    /// Consults the shared weight ledger before issuing requests, and updates
+    /// it from the used weight reported in the responses.
+    pub fn with_ledger(mut self, ledger: Arc<ShmRegion<WeightLedger>>) -> Self {
+        self.ledger = Some(ledger);
+        self
+    }
+
+    /// Issues the requests failing transiently (connection errors, timeouts and
+    /// 5xx statuses) again per `policy`, the signed requests being signed anew.
+    pub fn with_retry(mut self, policy: RetryPolicy) -> Self {
+        self.retry = Some(policy);
+        self
+    }
+
+    /// Takes the retries from a budget shared with other clients.
+    pub fn with_retry_budget(mut self, budget: Arc<RetryBudget>) -> Self {
+        self.retry_budget = Some(budget);
+        self
+    }
+
+    /// Returns the base URL of the API.
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// Issues a GET request of the given `weight` to `path` with the query parameters,
+    /// deserializing the JSON response.
+    ///
+    /// # Errors
+    /// Returns an error without issuing the request if it would exceed the
+    /// shared weight budget, or while requests are banned by the exchange.
+    ///
+    /// LATENCY: SLOW_PATH
+    pub fn get<T: DeserializeOwned>(
+        &self,
+        path: &str,
+        query: &[(&str, &str)],
+        weight: u64,
+    ) -> Result<T, RestError> {
+        self.send(|| Ok(self.http.get(format!("{}{}", self.base_url, path)).query(query)), path, weight)
+    }
+
+    /// Issues a signed GET request of the given `weight` to a `USER_DATA` endpoint,
+    /// deserializing the JSON response.
+    ///
+    /// # Errors
+    /// Returns an error if the client has no credentials, or as [`RestClient::get`].
+    ///
+    /// LATENCY: SLOW_PATH
+    pub fn get_signed<T: DeserializeOwned>(
+        &self,
+        path: &str,
+        query: &[(&str, &str)],
+        weight: u64,
+    ) -> Result<T, RestError> {
+        self.send(|| self.signed(Method::GET, path, query), path, weight)
+    }
+
+    /// Issues a signed DELETE request of the given `weight` to a `TRADE` endpoint,
+    /// deserializing the JSON response.
+    ///
+    /// # Errors
+    /// Returns an error if the client has no credentials, or as [`RestClient::get`].
+    ///
+    /// LATENCY: SLOW_PATH
+    pub fn delete_signed<T: DeserializeOwned>(
+        &self,
+        path: &str,
+        query: &[(&str, &str)],
+        weight: u64,
+    ) -> Result<T, RestError> {
+        self.send(|| self.signed(Method::DELETE, path, query), path, weight)
+    }
+
+    /// Builds a request with the signed query parameters and the API key header.
+    fn signed(
+        &self,
+        method: Method,
+        path: &str,
+        query: &[(&str, &str)],
+    ) -> Result<RequestBuilder, RestError> {
+        let credentials = self
+            .credentials
+            .as_ref()
+            .ok_or(RestError::MissingCredentials("credentials"))?;
+
+        let timestamp_ms = match &self.time_sync {
+            Some(time_sync) if time_sync.is_synced() => time_sync.server_time_ms(now_ms()),
+            _ => now_ms(),
+        };
+        let mut payload = form_urlencoded::Serializer::new(String::new())
+            .extend_pairs(query)
+            .append_pair("recvWindow", &RECV_WINDOW_MS.to_string())
+            .append_pair("timestamp", &timestamp_ms.to_string())
+            .finish();
+        let signature = credentials.sign(&payload);
+        payload.push_str("&signature=");
+        payload.push_str(&signature);
+
+        Ok(self
+            .http
+            .request(method, format!("{}{}?{}", self.base_url, path, payload))
+            .header("X-MBX-APIKEY", &credentials.api_key))
+    }
+
+    /// Sends the request built by `build`, building it again for each retry
+    /// per the retry policy, if any.
+    fn send<T: DeserializeOwned>(
+        &self,
+        build: impl Fn() -> Result<RequestBuilder, RestError>,
+        path: &str,
+        weight: u64,
+    ) -> Result<T, RestError> {
+        let Some(policy) = self.retry else {
+            return self.send_once(build()?, path, weight);
+        };
+        let mut backoff = Backoff::new(policy);
+        if let Some(budget) = &self.retry_budget {
+            backoff = backoff.with_budget(budget.clone());
+        }
+        retry(&mut backoff, || self.send_once(build()?, path, weight), RestError::is_transient)
+    }
+
+    /// Sends a request within the shared weight budget, deserializing the JSON response.
+    fn send_once<T: DeserializeOwned>(
+        &self,
+        request: RequestBuilder,
+        path: &str,
+        weight: u64,
+    ) -> Result<T, RestError> {
+        if let Some(ledger) = &self.ledger {
+            ledger.try_reserve(weight, now_ms())?;
+        }
+
+        let response = request.send()?;
+        self.record_limits(&response);
+
+        let status = response.status();
+        let body = response.text()?;
+        if !status.is_success() {
+            return Err(RestError::Status { path: path.to_string(), status: status.as_u16(), body });
+        }
+        Ok(serde_json::from_str(&body)?)
+    }
+
+    /// Returns the server time, in milliseconds since the epoch.
+    ///
+    /// LATENCY: SLOW_PATH
+    pub fn server_time(&self) -> Result<u64, RestError> {
+        let time: ServerTime = self.get("/api/v3/time", &[], SERVER_TIME_WEIGHT)?;
+        Ok(time.server_time)
+    }
+
+    /// Returns the account information, with the balances of all assets.
+    ///
+    /// LATENCY: SLOW_PATH
+    pub fn account(&self) -> Result<AccountInfo, RestError> {
+        self.get_signed("/api/v3/account", &[("omitZeroBalances", "true")], ACCOUNT_WEIGHT)
+    }
+
+    /// Returns the exchange information of all the symbols, trading or not.
+    ///
+    /// LATENCY: SLOW_PATH
+    pub fn exchange_info(&self) -> Result<ExchangeInfo, RestError> {
+        self.get("/api/v3/exchangeInfo", &[("showPermissionSets", "false")], EXCHANGE_INFO_WEIGHT)
+    }
+
+    /// Returns the snapshot of the order book of a symbol, with `limit` levels per side.
+    ///
+    /// LATENCY: SLOW_PATH
+    pub fn depth(&self, symbol: &str, limit: u32) -> Result<DepthSnapshot, RestError> {
+        let limit_str = limit.to_string();
+        let query = [("symbol", symbol), ("limit", limit_str.as_str())];
+        self.get("/api/v3/depth", &query, depth_weight(limit))
+    }
+
+    /// Returns up to `limit` trades of a symbol from the trade ID `from_id`, oldest first.
+    ///
+    /// LATENCY: SLOW_PATH
+    pub fn historical_trades(&self, symbol: &str, from_id: u64, limit: u32) -> Result<Vec<HistoricalTrade>, RestError> {
+        let (from_id_str, limit_str) = (from_id.to_string(), limit.to_string());
+        let query = [("symbol", symbol), ("fromId", from_id_str.as_str()), ("limit", limit_str.as_str())];
+        self.get("/api/v3/historicalTrades", &query, HISTORICAL_TRADES_WEIGHT)
+    }
+
+    /// Returns up to `limit` aggregate trades of a symbol from the aggregate
+    /// trade ID `from_id`, oldest first.
+    ///
+    /// LATENCY: SLOW_PATH
+    pub fn agg_trades(&self, symbol: &str, from_id: u64, limit: u32) -> Result<Vec<AggTrade>, RestError> {
+        let (from_id_str, limit_str) = (from_id.to_string(), limit.to_string());
+        let query = [("symbol", symbol), ("fromId", from_id_str.as_str()), ("limit", limit_str.as_str())];
+        self.get("/api/v3/aggTrades", &query, AGG_TRADES_WEIGHT)
+    }
+
+    /// Returns up to `limit` klines of a symbol at `interval` (e.g. `1m`), opened
+    /// from `start_time_ms` to `end_time_ms` inclusive, oldest first.
+    ///
+    /// LATENCY: SLOW_PATH
+    pub fn klines(
+        &self,
+        symbol: &str,
+        interval: &str,
+        start_time_ms: u64,
+        end_time_ms: u64,
+        limit: u32,
+    ) -> Result<Vec<Kline>, RestError> {
+        let (start_str, end_str, limit_str) = (start_time_ms.to_string(), end_time_ms.to_string(), limit.to_string());
+        let query = [
+            ("symbol", symbol),
+            ("interval", interval),
+            ("startTime", start_str.as_str()),
+            ("endTime", end_str.as_str()),
+            ("limit", limit_str.as_str()),
+        ];
+        self.get("/api/v3/klines", &query, KLINES_WEIGHT)
+    }
+
+    /// Cancels all the open orders of a symbol.
+    ///
+    /// LATENCY: SLOW_PATH
+    pub fn cancel_open_orders(&self, symbol: &str) -> Result<(), RestError> {
+        let query = [("symbol", symbol)];
+        let _: IgnoredAny = self.delete_signed("/api/v3/openOrders", &query, CANCEL_OPEN_ORDERS_WEIGHT)?;
+        Ok(())
+    }
+
+    /// Updates the shared weight ledger from the rate limit headers of a response.
+    ///
+    /// The exchange answers 429 when the limits are exceeded and 418 once the IP
+    /// is banned, with the seconds to wait in the `Retry-After` header.
+    fn record_limits(&self, response: &Response) {
+        let Some(ledger) = &self.ledger else {
+            return;
+        };
+        let now_ms = now_ms();
+
+        let header = |name: &str| {
+            response
+                .headers()
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.parse::<u64>().ok())
+        };
+        if let Some(used) = header(USED_WEIGHT_HEADER) {
+            ledger.record_used(used, now_ms);
+        }
+
+        let status = response.status();
+        if status == StatusCode::TOO_MANY_REQUESTS || status == StatusCode::IM_A_TEAPOT {
+            let retry_after_s = header(reqwest::header::RETRY_AFTER.as_str()).unwrap_or(60);
+            ledger.ban_until(now_ms + retry_after_s * 1_000);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_deserialize_server_time() {
+        let time: ServerTime = serde_json::from_str(r#"{"serverTime":1499827319559}"#).unwrap();
+        assert_eq!(time.server_time, 1499827319559);
+    }
+}
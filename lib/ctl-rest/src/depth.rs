@@ -0,0 +1,62 @@
+use serde::Deserialize;
+
+/// The response of the order book endpoint.
+/// https://github.com/binance/binance-spot-api-docs/blob/master/rest-api.md#order-book
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DepthSnapshot {
+    /// The ID of the last update included in the snapshot.
+    pub last_update_id: u64,
+    /// The bid levels as `[price, quantity]` decimal strings, best first.
+    pub bids: Vec<[String; 2]>,
+    /// The ask levels as `[price, quantity]` decimal strings, best first.
+    pub asks: Vec<[String; 2]>,
+}
+
+/// Parses `[price, quantity]` levels, skipping the malformed ones.
+fn parse_levels(levels: &[[String; 2]]) -> impl Iterator<Item = (f64, f64)> + '_ {
+    levels
+        .iter()
+        .filter_map(|[price, qty]| Some((price.parse().ok()?, qty.parse().ok()?)))
+}
+
+impl DepthSnapshot {
+    /// Returns the bid levels as `(price, quantity)`, best first.
+    pub fn bid_levels(&self) -> impl Iterator<Item = (f64, f64)> + '_ {
+        parse_levels(&self.bids)
+    }
+
+    /// Returns the ask levels as `(price, quantity)`, best first.
+    pub fn ask_levels(&self) -> impl Iterator<Item = (f64, f64)> + '_ {
+        parse_levels(&self.asks)
+    }
+}
+
+/// Returns the request weight of the order book endpoint for a `limit` of levels per side.
+pub fn depth_weight(limit: u32) -> u64 {
+    match limit {
+        0..=100 => 5,
+        101..=500 => 25,
+        501..=1000 => 50,
+        _ => 250,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_deserialize_depth_snapshot() {
+        let snapshot: DepthSnapshot = serde_json::from_str(
+            r#"{"lastUpdateId":1027024,"bids":[["4.00000000","431.00000000"]],"asks":[["4.00000200","12.00000000"],["4.00000300","x"]]}"#,
+        )
+        .unwrap();
+        assert_eq!(snapshot.last_update_id, 1027024);
+        assert_eq!(snapshot.bid_levels().collect::<Vec<_>>(), [(4.0, 431.0)]);
+        assert_eq!(snapshot.ask_levels().collect::<Vec<_>>(), [(4.000002, 12.0)]);
+        assert_eq!(depth_weight(100), 5);
+        assert_eq!(depth_weight(1000), 50);
+        assert_eq!(depth_weight(5000), 250);
+    }
+}
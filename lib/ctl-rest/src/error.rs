@@ -0,0 +1,92 @@
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum RestError {
+    #[error("rest error: http error {0}")]
+    Http(#[from] reqwest::Error),
+    #[error("rest error: {path} returned status {status}: {body}")]
+    Status { path: String, status: u16, body: String },
+    #[error("rest error: request weight {weight} exceeds the budget, {used} of {limit} used")]
+    WeightBudgetExceeded { used: u64, weight: u64, limit: u64 },
+    #[error("rest error: requests banned by the exchange until {until_ms}")]
+    Banned { until_ms: u64 },
+    #[error("rest error: missing {0} for a signed request")]
+    MissingCredentials(&'static str),
+    #[error("rest error: serde json error {0}")]
+    SerdeError(#[from] serde_json::Error),
+}
+
+impl RestError {
+    /// Returns true if the request may succeed when issued again: connection
+    /// errors, timeouts and server errors. The rate limits are enforced by the
+    /// weight ledger instead.
+    pub fn is_transient(&self) -> bool {
+        match self {
+            Self::Http(e) => e.is_connect() || e.is_timeout(),
+            Self::Status { status, .. } => *status >= 500,
+            _ => false,
+        }
+    }
+
+    /// Returns true if the exchange rejected the request for exceeding the
+    /// rate limits (-1003, HTTP 418/429), to record to the circuit breaker.
+    pub fn is_rate_limited(&self) -> bool {
+        match self {
+            Self::Banned { .. } => true,
+            Self::Status { status, body, .. } => matches!(status, 418 | 429) || body.contains("-1003"),
+            _ => false,
+        }
+    }
+
+    /// Returns true if the exchange refused the credentials of the request:
+    /// HTTP 401, an invalid API key (-2014, -2015) or signature (-1022), or no
+    /// credentials at all. Retrying won't help until they are fixed.
+    pub fn is_auth_failure(&self) -> bool {
+        match self {
+            Self::MissingCredentials(_) => true,
+            Self::Status { status, body, .. } => {
+                *status == 401 || ["-2014", "-2015", "-1022"].iter().any(|code| body.contains(code))
+            }
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_transient_errors() {
+        let status = |status| RestError::Status { path: "/api/v3/depth".to_string(), status, body: String::new() };
+        assert!(status(503).is_transient());
+        assert!(!status(400).is_transient());
+        assert!(!status(429).is_transient());
+        assert!(!RestError::Banned { until_ms: 0 }.is_transient());
+    }
+
+    #[test]
+    fn test_rate_limited_errors() {
+        let status = |status| RestError::Status { path: "/api/v3/order".to_string(), status, body: String::new() };
+        assert!(status(429).is_rate_limited());
+        assert!(status(418).is_rate_limited());
+        assert!(!status(503).is_rate_limited());
+        let body = r#"{"code":-1003,"msg":"Too many requests."}"#.to_string();
+        assert!(RestError::Status { path: "/api/v3/order".to_string(), status: 400, body }.is_rate_limited());
+        assert!(RestError::Banned { until_ms: 0 }.is_rate_limited());
+    }
+
+    #[test]
+    fn test_auth_failures() {
+        let status = |status, body: &str| RestError::Status {
+            path: "/api/v3/account".to_string(),
+            status,
+            body: body.to_string(),
+        };
+        assert!(status(401, "").is_auth_failure());
+        assert!(status(400, r#"{"code":-1022,"msg":"Signature for this request is not valid."}"#).is_auth_failure());
+        assert!(!status(400, r#"{"code":-1121,"msg":"Invalid symbol."}"#).is_auth_failure());
+        assert!(!status(403, "").is_auth_failure());
+        assert!(RestError::MissingCredentials("API key").is_auth_failure());
+    }
+}
@@ -0,0 +1,57 @@
+use serde::Deserialize;
+
+/// The response of the exchange information endpoint, restricted to the symbols.
+/// https://github.com/binance/binance-spot-api-docs/blob/master/rest-api.md#exchange-information
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct ExchangeInfo {
+    /// The symbols listed on the exchange.
+    pub symbols: Vec<ExchangeSymbol>,
+}
+
+impl ExchangeInfo {
+    /// Finds a symbol by its exchange name.
+    pub fn find_symbol(&self, symbol: &str) -> Option<&ExchangeSymbol> {
+        self.symbols.iter().find(|s| s.symbol == symbol)
+    }
+}
+
+/// A symbol listed on the exchange.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExchangeSymbol {
+    /// The exchange symbol name, e.g. BTCUSDT.
+    pub symbol: String,
+    /// The trading status, e.g. TRADING or BREAK.
+    /// https://github.com/binance/binance-spot-api-docs/blob/master/enums.md#symbol-status-status
+    pub status: String,
+    /// The base asset, e.g. BTC.
+    pub base_asset: String,
+    /// The quote asset, e.g. USDT.
+    pub quote_asset: String,
+}
+
+impl ExchangeSymbol {
+    /// Returns true if the symbol is trading.
+    pub fn is_trading(&self) -> bool {
+        self.status == "TRADING"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_deserialize_exchange_info() {
+        let info: ExchangeInfo = serde_json::from_str(
+            r#"{"timezone":"UTC","serverTime":1565246363776,"rateLimits":[],"exchangeFilters":[],"symbols":[{"symbol":"ETHBTC","status":"TRADING","baseAsset":"ETH","baseAssetPrecision":8,"quoteAsset":"BTC","quotePrecision":8,"orderTypes":["LIMIT","MARKET"],"filters":[]},{"symbol":"LUNAUSDT","status":"BREAK","baseAsset":"LUNA","baseAssetPrecision":8,"quoteAsset":"USDT","quotePrecision":8,"orderTypes":["LIMIT"],"filters":[]}]}"#,
+        )
+        .unwrap();
+        assert_eq!(info.symbols.len(), 2);
+        let eth = info.find_symbol("ETHBTC").unwrap();
+        assert!(eth.is_trading());
+        assert_eq!(eth.quote_asset, "BTC");
+        assert!(!info.find_symbol("LUNAUSDT").unwrap().is_trading());
+        assert!(info.find_symbol("BTCUSDT").is_none());
+    }
+}
@@ -0,0 +1,90 @@
+use serde::Deserialize;
+use serde::de::IgnoredAny;
+
+/// Request weight of the kline/candlestick data endpoint.
+pub const KLINES_WEIGHT: u64 = 2;
+
+/// The maximum number of klines returned per request.
+pub const MAX_KLINES_LIMIT: u32 = 1000;
+
+/// The row of a kline, as listed by the endpoint.
+#[derive(Deserialize)]
+struct KlineRow(
+    u64,
+    String,
+    String,
+    String,
+    String,
+    String,
+    u64,
+    String,
+    u64,
+    String,
+    String,
+    // An unused field, always "0"
+    #[allow(dead_code)]
+    IgnoredAny,
+);
+
+/// A kline of the kline/candlestick data endpoint.
+/// https://github.com/binance/binance-spot-api-docs/blob/master/rest-api.md#klinecandlestick-data
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(from = "KlineRow")]
+pub struct Kline {
+    /// The open time, in milliseconds since the epoch.
+    pub open_time_ms: u64,
+    /// The open price, as a decimal string.
+    pub open: String,
+    /// The high price, as a decimal string.
+    pub high: String,
+    /// The low price, as a decimal string.
+    pub low: String,
+    /// The close price, as a decimal string.
+    pub close: String,
+    /// The base asset volume, as a decimal string.
+    pub volume: String,
+    /// The close time, in milliseconds since the epoch.
+    pub close_time_ms: u64,
+    /// The quote asset volume, as a decimal string.
+    pub quote_volume: String,
+    /// The number of trades.
+    pub trade_count: u64,
+    /// The base asset volume bought by the takers, as a decimal string.
+    pub taker_buy_volume: String,
+    /// The quote asset volume bought by the takers, as a decimal string.
+    pub taker_buy_quote_volume: String,
+}
+
+impl From<KlineRow> for Kline {
+    fn from(row: KlineRow) -> Self {
+        Self {
+            open_time_ms: row.0,
+            open: row.1,
+            high: row.2,
+            low: row.3,
+            close: row.4,
+            volume: row.5,
+            close_time_ms: row.6,
+            quote_volume: row.7,
+            trade_count: row.8,
+            taker_buy_volume: row.9,
+            taker_buy_quote_volume: row.10,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_deserialize_klines() {
+        let klines: Vec<Kline> = serde_json::from_str(
+            r#"[[1499040000000,"0.01634790","0.80000000","0.01575800","0.01577100","148976.11427815",1499644799999,"2434.19055334",308,"1756.87402397","28.46694368","0"]]"#,
+        )
+        .unwrap();
+        assert_eq!(klines[0].open_time_ms, 1499040000000);
+        assert_eq!((klines[0].open.as_str(), klines[0].close.as_str()), ("0.01634790", "0.01577100"));
+        assert_eq!((klines[0].close_time_ms, klines[0].trade_count), (1499644799999, 308));
+    }
+}
@@ -0,0 +1,25 @@
+//! Binance Spot REST API client.
+//!
+//! A blocking client for the REST endpoints used by the controller components
+//! (time synchronization, snapshots, trade gap fills, historical klines, symbol
+//! validation, order management).
+
+mod account;
+mod client;
+mod depth;
+mod error;
+mod exchange_info;
+mod klines;
+mod signing;
+mod trades;
+mod weight;
+
+pub use account::{AccountBalance, AccountInfo};
+pub use client::{RestClient, ServerTime, BINANCE_REST_ENDPOINT, RECV_WINDOW_MS};
+pub use depth::{depth_weight, DepthSnapshot};
+pub use error::RestError;
+pub use exchange_info::{ExchangeInfo, ExchangeSymbol};
+pub use klines::{Kline, KLINES_WEIGHT, MAX_KLINES_LIMIT};
+pub use signing::{Credentials, API_KEY_ENV, SECRET_KEY_ENV};
+pub use trades::{AggTrade, HistoricalTrade, AGG_TRADES_WEIGHT, HISTORICAL_TRADES_WEIGHT, MAX_TRADES_LIMIT};
+pub use weight::{WeightLedger, USED_WEIGHT_HEADER, WEIGHT_LEDGER_REGION_NAME};
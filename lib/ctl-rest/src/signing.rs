@@ -0,0 +1,86 @@
+//! Signing of the `USER_DATA` and `TRADE` endpoint requests.
+//! https://github.com/binance/binance-spot-api-docs/blob/master/rest-api.md#signed-trade-and-user_data-endpoint-security
+
+use std::env;
+use std::fmt;
+use std::fmt::Write;
+
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+use crate::RestError;
+
+/// Environment variable holding the API key.
+pub const API_KEY_ENV: &str = "BINANCE_API_KEY";
+
+/// Environment variable holding the HMAC secret key.
+pub const SECRET_KEY_ENV: &str = "BINANCE_SECRET_KEY";
+
+/// An HMAC API key.
+#[derive(Clone)]
+pub struct Credentials {
+    /// The API key, sent in the `X-MBX-APIKEY` header.
+    pub api_key: String,
+    /// The secret key the requests are signed with.
+    secret_key: String,
+}
+
+impl fmt::Debug for Credentials {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Credentials")
+            .field("api_key", &self.api_key)
+            .field("secret_key", &"<redacted>")
+            .finish()
+    }
+}
+
+impl Credentials {
+    /// Creates credentials from an API key and its secret key.
+    pub fn new(api_key: impl Into<String>, secret_key: impl Into<String>) -> Self {
+        Self { api_key: api_key.into(), secret_key: secret_key.into() }
+    }
+
+    /// Reads the credentials from the `BINANCE_API_KEY` and `BINANCE_SECRET_KEY` variables.
+    pub fn from_env() -> Result<Self, RestError> {
+        let var = |name: &'static str| env::var(name).map_err(|_| RestError::MissingCredentials(name));
+        Ok(Self::new(var(API_KEY_ENV)?, var(SECRET_KEY_ENV)?))
+    }
+
+    /// Returns the hex-encoded HMAC SHA256 signature of `payload`.
+    pub fn sign(&self, payload: &str) -> String {
+        let mut mac = Hmac::<Sha256>::new_from_slice(self.secret_key.as_bytes())
+            .expect("HMAC accepts keys of any length");
+        mac.update(payload.as_bytes());
+
+        let signature = mac.finalize().into_bytes();
+        let mut hex = String::with_capacity(signature.len() * 2);
+        for byte in signature {
+            let _ = write!(hex, "{:02x}", byte);
+        }
+        hex
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sign_documented_example() {
+        let credentials = Credentials::new(
+            "vmPUZE6mv9SD5VNHk4HlWFsOr6aKE2zvsw0MuIgwCIPy6utIco14y7Ju91duEh8A",
+            "NhqPtmdSJYdKjVHjA7PZj4Mge3R5YNiP1e3UZjInClVN65XAbvqqM6A7H5fATj0j",
+        );
+        let payload = "symbol=LTCBTC&side=BUY&type=LIMIT&timeInForce=GTC&quantity=1&price=0.1&recvWindow=5000&timestamp=1499827319559";
+        assert_eq!(
+            credentials.sign(payload),
+            "c8db56825ae71d6d79447849e617115f4a920fa2acdcab2b053c4b2838bd6b71"
+        );
+    }
+
+    #[test]
+    fn test_debug_redacts_secret() {
+        let credentials = Credentials::new("key", "secret");
+        assert!(!format!("{:?}", credentials).contains("\"secret\""));
+    }
+}
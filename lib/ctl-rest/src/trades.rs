@@ -0,0 +1,121 @@
+use serde::Deserialize;
+
+/// Request weight of the old trade lookup endpoint.
+pub const HISTORICAL_TRADES_WEIGHT: u64 = 25;
+
+/// Request weight of the compressed/aggregate trades endpoint.
+pub const AGG_TRADES_WEIGHT: u64 = 4;
+
+/// The maximum number of trades returned per request by the trade endpoints.
+pub const MAX_TRADES_LIMIT: u32 = 1000;
+
+/// A trade of the old trade lookup endpoint.
+/// https://github.com/binance/binance-spot-api-docs/blob/master/rest-api.md#old-trade-lookup
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoricalTrade {
+    /// The trade ID.
+    pub id: u64,
+    /// The price, as a decimal string.
+    pub price: String,
+    /// The quantity, as a decimal string.
+    pub qty: String,
+    /// The trade time, in milliseconds since the epoch.
+    pub time: u64,
+    /// Whether the buyer was the maker.
+    pub is_buyer_maker: bool,
+    /// Whether the trade was the best price match.
+    pub is_best_match: bool,
+}
+
+impl HistoricalTrade {
+    /// Returns the trade as the payload of the trade stream of `symbol`, as
+    /// received live, its event time being its trade time.
+    pub fn to_stream_payload(&self, symbol: &str) -> String {
+        format!(
+            r#"{{"e":"trade","E":{},"s":"{}","t":{},"p":"{}","q":"{}","T":{},"m":{},"M":{}}}"#,
+            self.time, symbol, self.id, self.price, self.qty, self.time, self.is_buyer_maker, self.is_best_match
+        )
+    }
+}
+
+/// An aggregate trade of the compressed/aggregate trades endpoint.
+/// https://github.com/binance/binance-spot-api-docs/blob/master/rest-api.md#compressedaggregate-trades-list
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct AggTrade {
+    /// The aggregate trade ID.
+    #[serde(rename = "a")]
+    pub id: u64,
+    /// The price, as a decimal string.
+    #[serde(rename = "p")]
+    pub price: String,
+    /// The quantity, as a decimal string.
+    #[serde(rename = "q")]
+    pub qty: String,
+    /// The first trade ID aggregated.
+    #[serde(rename = "f")]
+    pub first_trade_id: u64,
+    /// The last trade ID aggregated.
+    #[serde(rename = "l")]
+    pub last_trade_id: u64,
+    /// The trade time, in milliseconds since the epoch.
+    #[serde(rename = "T")]
+    pub time: u64,
+    /// Whether the buyer was the maker.
+    #[serde(rename = "m")]
+    pub is_buyer_maker: bool,
+    /// Whether the trade was the best price match.
+    #[serde(rename = "M")]
+    pub is_best_match: bool,
+}
+
+impl AggTrade {
+    /// Returns the aggregate trade as the payload of the aggregate trade stream
+    /// of `symbol`, as received live, its event time being its trade time.
+    pub fn to_stream_payload(&self, symbol: &str) -> String {
+        format!(
+            r#"{{"e":"aggTrade","E":{},"s":"{}","a":{},"p":"{}","q":"{}","f":{},"l":{},"T":{},"m":{},"M":{}}}"#,
+            self.time,
+            symbol,
+            self.id,
+            self.price,
+            self.qty,
+            self.first_trade_id,
+            self.last_trade_id,
+            self.time,
+            self.is_buyer_maker,
+            self.is_best_match
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_deserialize_historical_trades() {
+        let trades: Vec<HistoricalTrade> = serde_json::from_str(
+            r#"[{"id":28457,"price":"4.00000100","qty":"12.00000000","quoteQty":"48.000012","time":1499865549590,"isBuyerMaker":true,"isBestMatch":true}]"#,
+        )
+        .unwrap();
+        assert_eq!(trades[0].id, 28457);
+        assert_eq!(
+            trades[0].to_stream_payload("BNBBTC"),
+            r#"{"e":"trade","E":1499865549590,"s":"BNBBTC","t":28457,"p":"4.00000100","q":"12.00000000","T":1499865549590,"m":true,"M":true}"#
+        );
+    }
+
+    #[test]
+    fn test_deserialize_agg_trades() {
+        let trades: Vec<AggTrade> = serde_json::from_str(
+            r#"[{"a":26129,"p":"0.01633102","q":"4.70443515","f":27781,"l":27781,"T":1498793709153,"m":true,"M":true}]"#,
+        )
+        .unwrap();
+        assert_eq!((trades[0].id, trades[0].first_trade_id, trades[0].last_trade_id), (26129, 27781, 27781));
+        assert_eq!(
+            trades[0].to_stream_payload("BNBBTC"),
+            r#"{"e":"aggTrade","E":1498793709153,"s":"BNBBTC","a":26129,"p":"0.01633102","q":"4.70443515","f":27781,"l":27781,"T":1498793709153,"m":true,"M":true}"#
+        );
+    }
+}
@@ -0,0 +1,170 @@
+//! Shared REST request weight budget.
+//!
+//! Binance limits the request weight per IP over fixed one-minute windows,
+//! across every component issuing requests. The ledger lives in a shared memory
+//! region created by ctl-resource-manager: clients reserve the weight of a
+//! request before issuing it, and update the ledger from the used weight
+//! reported in the `X-MBX-USED-WEIGHT-1M` response header.
+//! https://github.com/binance/binance-spot-api-docs/blob/master/rest-api.md#ip-limits
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use ctl_shm::ShmSafe;
+
+use crate::RestError;
+
+/// Name of the REST weight ledger region.
+pub const WEIGHT_LEDGER_REGION_NAME: &str = "ctl_rest_weight";
+
+/// The response header reporting the weight used in the current minute.
+pub const USED_WEIGHT_HEADER: &str = "x-mbx-used-weight-1m";
+
+/// Length of a weight window, in milliseconds.
+const WINDOW_MS: u64 = 60_000;
+
+/// The request weight used across components in the current minute.
+#[repr(C)]
+#[derive(Debug, Default)]
+pub struct WeightLedger {
+    /// The weight budget per minute, zero when unlimited.
+    limit: AtomicU64,
+    /// The minute (since the epoch) the used weight belongs to.
+    minute: AtomicU64,
+    /// The weight used in the minute.
+    used: AtomicU64,
+    /// Local time until which requests are banned (HTTP 418/429), in milliseconds.
+    banned_until_ms: AtomicU64,
+    /// Number of requests rejected by the budget.
+    rejected: AtomicU64,
+}
+
+// SAFETY: `WeightLedger` is `repr(C)`, made only of atomics and valid when zeroed.
+unsafe impl ShmSafe for WeightLedger {}
+
+impl WeightLedger {
+    /// Sets the weight budget per minute, zero for unlimited.
+    pub fn set_limit(&self, limit: u64) {
+        self.limit.store(limit, Ordering::Relaxed);
+    }
+
+    /// Returns the weight budget per minute.
+    pub fn limit(&self) -> u64 {
+        self.limit.load(Ordering::Relaxed)
+    }
+
+    /// Returns the weight used in the minute of `now_ms`.
+    pub fn used(&self, now_ms: u64) -> u64 {
+        self.roll(now_ms);
+        self.used.load(Ordering::Acquire)
+    }
+
+    /// Returns the number of requests rejected by the budget.
+    pub fn rejected(&self) -> u64 {
+        self.rejected.load(Ordering::Relaxed)
+    }
+
+    /// Resets the used weight when `now_ms` is in a later minute.
+    fn roll(&self, now_ms: u64) {
+        let minute = now_ms / WINDOW_MS;
+        let current = self.minute.load(Ordering::Acquire);
+        if minute <= current {
+            return;
+        }
+        // Only the caller winning the rollover resets the used weight
+        if self
+            .minute
+            .compare_exchange(current, minute, Ordering::AcqRel, Ordering::Acquire)
+            .is_ok()
+        {
+            self.used.store(0, Ordering::Release);
+        }
+    }
+
+    /// Reserves the weight of a request, returning the weight used including it.
+    ///
+    /// # Errors
+    /// Returns an error if requests are banned, or the request would exceed the budget.
+    pub fn try_reserve(&self, weight: u64, now_ms: u64) -> Result<u64, RestError> {
+        let banned_until_ms = self.banned_until_ms.load(Ordering::Acquire);
+        if now_ms < banned_until_ms {
+            self.rejected.fetch_add(1, Ordering::Relaxed);
+            return Err(RestError::Banned { until_ms: banned_until_ms });
+        }
+
+        self.roll(now_ms);
+        let limit = self.limit();
+        self.used
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |used| {
+                (limit == 0 || used + weight <= limit).then_some(used + weight)
+            })
+            .map(|used| used + weight)
+            .map_err(|used| {
+                self.rejected.fetch_add(1, Ordering::Relaxed);
+                RestError::WeightBudgetExceeded { used, weight, limit }
+            })
+    }
+
+    /// Records the used weight reported by the exchange for the minute of `now_ms`.
+    ///
+    /// The reported weight is authoritative, but reservations of concurrent
+    /// requests not yet accounted for by the exchange are kept.
+    pub fn record_used(&self, used: u64, now_ms: u64) {
+        self.roll(now_ms);
+        self.used.fetch_max(used, Ordering::AcqRel);
+    }
+
+    /// Bans requests until `until_ms`, after the exchange rejected a request for
+    /// exceeding the limits.
+    pub fn ban_until(&self, until_ms: u64) {
+        self.banned_until_ms.fetch_max(until_ms, Ordering::AcqRel);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reserve_within_budget() {
+        let ledger = WeightLedger::default();
+        ledger.set_limit(10);
+        assert_eq!(ledger.try_reserve(4, 0).unwrap(), 4);
+        assert_eq!(ledger.try_reserve(6, 1_000).unwrap(), 10);
+        assert!(matches!(
+            ledger.try_reserve(1, 2_000),
+            Err(RestError::WeightBudgetExceeded { used: 10, weight: 1, limit: 10 })
+        ));
+        assert_eq!(ledger.rejected(), 1);
+    }
+
+    #[test]
+    fn test_budget_resets_every_minute() {
+        let ledger = WeightLedger::default();
+        ledger.set_limit(10);
+        ledger.try_reserve(10, 59_999).unwrap();
+        assert!(ledger.try_reserve(1, 59_999).is_err());
+        assert_eq!(ledger.try_reserve(1, 60_000).unwrap(), 1);
+    }
+
+    #[test]
+    fn test_record_used_from_header() {
+        let ledger = WeightLedger::default();
+        ledger.try_reserve(2, 0).unwrap();
+        ledger.record_used(40, 100);
+        assert_eq!(ledger.used(200), 40);
+
+        // A stale, lower report doesn't drop concurrent reservations
+        ledger.record_used(30, 300);
+        assert_eq!(ledger.used(300), 40);
+    }
+
+    #[test]
+    fn test_unlimited_and_banned() {
+        let ledger = WeightLedger::default();
+        assert!(ledger.try_reserve(1_000_000, 0).is_ok());
+
+        ledger.ban_until(5_000);
+        assert!(matches!(ledger.try_reserve(1, 4_999), Err(RestError::Banned { until_ms: 5_000 })));
+        assert!(ledger.try_reserve(1, 5_000).is_ok());
+    }
+}
@@ -0,0 +1,149 @@
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use ctl_time::now_ms;
+
+use crate::{Jitter, RetryBudget, RetryPolicy};
+
+/// Tracks the consecutive retries of an operation.
+#[derive(Debug, Clone)]
+pub struct Backoff {
+    /// The policy.
+    policy: RetryPolicy,
+    /// Consecutive retries since the operation last succeeded.
+    retries: u32,
+    /// The source of the jitter.
+    jitter: Jitter,
+    /// The retry budget shared with other operations, if any.
+    budget: Option<Arc<RetryBudget>>,
+}
+
+impl Backoff {
+    /// Creates a tracker retrying per `policy`.
+    pub fn new(policy: RetryPolicy) -> Self {
+        Self { policy, retries: 0, jitter: Jitter::from_entropy(), budget: None }
+    }
+
+    /// Takes the retries from a budget shared with other operations.
+    pub fn with_budget(mut self, budget: Arc<RetryBudget>) -> Self {
+        self.budget = Some(budget);
+        self
+    }
+
+    /// Draws the jitter from `jitter`, e.g. a seeded source for reproducible backoffs.
+    pub fn with_jitter(mut self, jitter: Jitter) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    /// Returns the policy.
+    pub fn policy(&self) -> &RetryPolicy {
+        &self.policy
+    }
+
+    /// Returns the consecutive retries since the operation last succeeded.
+    pub fn retries(&self) -> u32 {
+        self.retries
+    }
+
+    /// Records a failure of the operation at `now_ms`, returning the backoff
+    /// before retrying it, `None` if the retries or the budget are exhausted.
+    pub fn next(&mut self, now_ms: u64) -> Option<Duration> {
+        if self.retries >= self.policy.max_retries {
+            return None;
+        }
+        if self.budget.as_ref().is_some_and(|budget| !budget.try_acquire(now_ms)) {
+            return None;
+        }
+        self.retries += 1;
+        Some(self.policy.jittered(self.retries, &mut self.jitter))
+    }
+
+    /// Records a success of the operation, resetting the consecutive retries.
+    pub fn reset(&mut self) {
+        self.retries = 0;
+    }
+}
+
+/// Runs `op` until it succeeds, fails with an error `retryable` rejects, or
+/// the retries of `backoff` are exhausted, sleeping the backoff between the
+/// attempts. Returns the last result.
+///
+/// LATENCY: SLOW_PATH
+pub fn retry<T, E>(
+    backoff: &mut Backoff,
+    mut op: impl FnMut() -> Result<T, E>,
+    retryable: impl Fn(&E) -> bool,
+) -> Result<T, E> {
+    loop {
+        match op() {
+            Err(e) if retryable(&e) => match backoff.next(now_ms()) {
+                Some(delay) => thread::sleep(delay),
+                None => return Err(e),
+            },
+            result => {
+                backoff.reset();
+                return result;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy() -> RetryPolicy {
+        RetryPolicy { max_retries: 2, initial_backoff_ms: 1, max_backoff_ms: 2, multiplier: 2, jitter_pct: 0 }
+    }
+
+    #[test]
+    fn test_backoff_until_exhausted() {
+        let mut backoff = Backoff::new(policy()).with_jitter(Jitter::new(7));
+        assert_eq!(backoff.next(0), Some(Duration::from_millis(1)));
+        assert_eq!(backoff.next(0), Some(Duration::from_millis(2)));
+        assert_eq!(backoff.next(0), None);
+        assert_eq!(backoff.retries(), 2);
+
+        backoff.reset();
+        assert_eq!(backoff.next(0), Some(Duration::from_millis(1)));
+    }
+
+    #[test]
+    fn test_budget_shared_across_operations() {
+        let budget = Arc::new(RetryBudget::new(1, 1_000));
+        let mut first = Backoff::new(policy()).with_budget(budget.clone());
+        let mut second = Backoff::new(policy()).with_budget(budget.clone());
+        assert!(first.next(0).is_some());
+        assert!(second.next(0).is_none());
+        assert_eq!(budget.denied(), 1);
+    }
+
+    #[test]
+    fn test_retry_until_success_or_permanent_error() {
+        let mut backoff = Backoff::new(policy());
+        let mut attempts = 0;
+        let result: Result<u32, &str> = retry(
+            &mut backoff,
+            || {
+                attempts += 1;
+                if attempts < 3 { Err("transient") } else { Ok(attempts) }
+            },
+            |e| *e == "transient",
+        );
+        assert_eq!(result, Ok(3));
+        assert_eq!(backoff.retries(), 0);
+
+        let mut attempts = 0;
+        let result: Result<(), &str> = retry(
+            &mut backoff,
+            || {
+                attempts += 1;
+                Err("permanent")
+            },
+            |e| *e == "transient",
+        );
+        assert_eq!((result, attempts), (Err("permanent"), 1));
+    }
+}
@@ -0,0 +1,79 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// A budget of retries over a fixed window, shared by the operations retrying
+/// against the same service.
+#[derive(Debug)]
+pub struct RetryBudget {
+    /// Retries allowed per window.
+    limit: u64,
+    /// Length of the window, in milliseconds.
+    window_ms: u64,
+    /// The window (since the epoch) the retries belong to.
+    window: AtomicU64,
+    /// The retries in the window.
+    used: AtomicU64,
+    /// Number of retries denied by the budget.
+    denied: AtomicU64,
+}
+
+impl RetryBudget {
+    /// Creates a budget of `limit` retries per window of `window_ms`.
+    pub fn new(limit: u64, window_ms: u64) -> Self {
+        Self {
+            limit,
+            window_ms: window_ms.max(1),
+            window: AtomicU64::new(0),
+            used: AtomicU64::new(0),
+            denied: AtomicU64::new(0),
+        }
+    }
+
+    /// Takes a retry from the budget of the window of `now_ms`, returning false
+    /// if the budget is spent.
+    pub fn try_acquire(&self, now_ms: u64) -> bool {
+        let window = now_ms / self.window_ms;
+        let current = self.window.load(Ordering::Acquire);
+        // Only the caller winning the rollover resets the retries
+        if window > current
+            && self
+                .window
+                .compare_exchange(current, window, Ordering::AcqRel, Ordering::Acquire)
+                .is_ok()
+        {
+            self.used.store(0, Ordering::Release);
+        }
+
+        let acquired = self
+            .used
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |used| (used < self.limit).then_some(used + 1))
+            .is_ok();
+        if !acquired {
+            self.denied.fetch_add(1, Ordering::Relaxed);
+        }
+        acquired
+    }
+
+    /// Returns the number of retries denied by the budget.
+    pub fn denied(&self) -> u64 {
+        self.denied.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_budget_per_window() {
+        let budget = RetryBudget::new(2, 1_000);
+        assert!(budget.try_acquire(0));
+        assert!(budget.try_acquire(500));
+        assert!(!budget.try_acquire(999));
+        assert_eq!(budget.denied(), 1);
+
+        // The budget is restored in the next window
+        assert!(budget.try_acquire(1_000));
+        assert!(budget.try_acquire(1_999));
+        assert!(!budget.try_acquire(1_999));
+    }
+}
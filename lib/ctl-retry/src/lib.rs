@@ -0,0 +1,16 @@
+//! Retry and backoff policies shared by the controller components.
+//!
+//! A failed operation (a websocket reconnect, a REST call, a rejected request)
+//! is attempted again after an exponentially growing backoff, randomized by a
+//! jitter so that the components failing together don't retry in lockstep,
+//! until its retries are exhausted. A retry budget bounds the retries of all
+//! the operations sharing it over a window, so that an outage doesn't turn
+//! into a retry storm.
+
+mod backoff;
+mod budget;
+mod policy;
+
+pub use backoff::{retry, Backoff};
+pub use budget::RetryBudget;
+pub use policy::{Jitter, RetryPolicy};
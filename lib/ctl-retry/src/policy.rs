@@ -0,0 +1,173 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::Deserialize;
+
+const DEFAULT_MAX_RETRIES: u32 = 5;
+const DEFAULT_INITIAL_BACKOFF_MS: u64 = 250;
+const DEFAULT_MAX_BACKOFF_MS: u64 = 10_000;
+const DEFAULT_MULTIPLIER: u32 = 2;
+
+fn default_max_retries() -> u32 {
+    DEFAULT_MAX_RETRIES
+}
+
+fn default_initial_backoff_ms() -> u64 {
+    DEFAULT_INITIAL_BACKOFF_MS
+}
+
+fn default_max_backoff_ms() -> u64 {
+    DEFAULT_MAX_BACKOFF_MS
+}
+
+fn default_multiplier() -> u32 {
+    DEFAULT_MULTIPLIER
+}
+
+/// When a failed operation is attempted again.
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Eq, Hash)]
+pub struct RetryPolicy {
+    /// Number of retries of a failed operation before giving up (0 never retries).
+    #[serde(default = "default_max_retries")]
+    pub max_retries: u32,
+    /// Backoff before the first retry, in milliseconds.
+    #[serde(default = "default_initial_backoff_ms")]
+    pub initial_backoff_ms: u64,
+    /// Longest backoff before a retry, in milliseconds.
+    #[serde(default = "default_max_backoff_ms")]
+    pub max_backoff_ms: u64,
+    /// Factor the backoff grows by on each retry.
+    #[serde(default = "default_multiplier")]
+    pub multiplier: u32,
+    /// Share of the backoff randomized, in percent: a backoff `b` is drawn
+    /// uniformly from `[b * (1 - jitter_pct / 100), b]` (0 disables it).
+    #[serde(default)]
+    pub jitter_pct: u32,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: DEFAULT_MAX_RETRIES,
+            initial_backoff_ms: DEFAULT_INITIAL_BACKOFF_MS,
+            max_backoff_ms: DEFAULT_MAX_BACKOFF_MS,
+            multiplier: DEFAULT_MULTIPLIER,
+            jitter_pct: 0,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Validates the policy configuration.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.initial_backoff_ms == 0 || self.initial_backoff_ms > self.max_backoff_ms {
+            return Err(format!(
+                "retry policy must have 0 < 'initial_backoff_ms' <= 'max_backoff_ms', got {} and {}",
+                self.initial_backoff_ms, self.max_backoff_ms
+            ));
+        }
+        if self.multiplier == 0 {
+            return Err("retry policy 'multiplier' must be greater than 0".to_string());
+        }
+        if self.jitter_pct > 100 {
+            return Err(format!("retry policy 'jitter_pct' must be at most 100, got {}", self.jitter_pct));
+        }
+        Ok(())
+    }
+
+    /// Returns the backoff before a retry, by retry starting at 1, without jitter.
+    pub fn backoff(&self, retry: u32) -> Duration {
+        let factor = u64::from(self.multiplier).saturating_pow(retry.saturating_sub(1));
+        Duration::from_millis(self.initial_backoff_ms.saturating_mul(factor).min(self.max_backoff_ms))
+    }
+
+    /// Returns the backoff before a retry, by retry starting at 1, randomized by the jitter.
+    pub fn jittered(&self, retry: u32, jitter: &mut Jitter) -> Duration {
+        let backoff = self.backoff(retry).as_millis() as u64;
+        let spread = backoff.saturating_mul(u64::from(self.jitter_pct.min(100))) / 100;
+        Duration::from_millis(backoff - jitter.below(spread + 1))
+    }
+}
+
+/// The pseudo-random source of the jitter (xorshift64*), not for cryptographic use.
+#[derive(Debug, Clone)]
+pub struct Jitter {
+    /// The generator state, never zero.
+    state: u64,
+}
+
+impl Default for Jitter {
+    fn default() -> Self {
+        Self::from_entropy()
+    }
+}
+
+impl Jitter {
+    /// Creates a source from a seed, for reproducible jitters.
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed.max(1) }
+    }
+
+    /// Creates a source seeded from the clock and the process ID, so that the
+    /// components retrying together draw different jitters.
+    pub fn from_entropy() -> Self {
+        let nanos = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |elapsed| elapsed.as_nanos() as u64);
+        Self::new(nanos ^ (u64::from(std::process::id()) << 32))
+    }
+
+    /// Returns a value drawn uniformly from `[0, bound)`, zero if `bound` is zero.
+    pub fn below(&mut self, bound: u64) -> u64 {
+        if bound == 0 {
+            return 0;
+        }
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_F491_4F6C_DD1D) % bound
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy() -> RetryPolicy {
+        RetryPolicy { max_retries: 3, initial_backoff_ms: 100, max_backoff_ms: 250, multiplier: 2, jitter_pct: 0 }
+    }
+
+    #[test]
+    fn test_backoff() {
+        let policy = policy();
+        assert_eq!(policy.backoff(1), Duration::from_millis(100));
+        assert_eq!(policy.backoff(2), Duration::from_millis(200));
+        assert_eq!(policy.backoff(3), Duration::from_millis(250));
+        assert_eq!(policy.backoff(u32::MAX), Duration::from_millis(250));
+
+        let tripled = RetryPolicy { multiplier: 3, max_backoff_ms: 1_000, ..policy };
+        assert_eq!(tripled.backoff(3), Duration::from_millis(900));
+        assert_eq!(RetryPolicy { multiplier: 1, ..policy }.backoff(5), Duration::from_millis(100));
+    }
+
+    #[test]
+    fn test_jittered_within_spread() {
+        let policy = RetryPolicy { jitter_pct: 50, ..policy() };
+        let mut jitter = Jitter::new(42);
+        let backoffs: Vec<_> = (0..100).map(|_| policy.jittered(2, &mut jitter)).collect();
+        assert!(backoffs.iter().all(|backoff| (100..=200).contains(&backoff.as_millis())));
+        assert!(backoffs.iter().any(|backoff| *backoff != backoffs[0]));
+
+        // Without jitter, the backoff isn't randomized
+        assert_eq!(policy().jittered(2, &mut jitter), Duration::from_millis(200));
+    }
+
+    #[test]
+    fn test_validate_and_defaults() {
+        let policy: RetryPolicy = serde_yaml::from_str("max_backoff_ms: 30000\njitter_pct: 20").unwrap();
+        assert_eq!(policy, RetryPolicy { max_backoff_ms: 30_000, jitter_pct: 20, ..RetryPolicy::default() });
+        assert!(policy.validate().is_ok());
+
+        assert!(RetryPolicy { initial_backoff_ms: 0, ..policy }.validate().is_err());
+        assert!(RetryPolicy { max_backoff_ms: 100, ..policy }.validate().is_err());
+        assert!(RetryPolicy { multiplier: 0, ..policy }.validate().is_err());
+        assert!(RetryPolicy { jitter_pct: 101, ..policy }.validate().is_err());
+    }
+}
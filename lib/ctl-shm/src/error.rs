@@ -0,0 +1,17 @@
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum ShmError {
+    #[error("shm error: region {name} io error {source}")]
+    Io { name: String, source: std::io::Error },
+    #[error("shm error: region {name} size {found} does not match expected size {expected}")]
+    SizeMismatch { name: String, expected: usize, found: usize },
+    #[error("shm error: ring {name} capacity {capacity} is not a power of two")]
+    InvalidCapacity { name: String, capacity: usize },
+    #[error("shm error: ring {name} layout hash {found:#018x} does not match expected hash {expected:#018x}, rebuild the stale binary")]
+    LayoutMismatch { name: String, expected: u64, found: u64 },
+    #[error("shm error: ring {name} is invalid: {reason}")]
+    InvalidRing { name: String, reason: &'static str },
+    #[error("shm error: C layout of {name} does not match: {reason}")]
+    CLayout { name: String, reason: String },
+}
@@ -0,0 +1,283 @@
+//! C header generation for the shared memory types.
+//!
+//! Components written in C or C++ (e.g. legacy strategies) attach to the same
+//! rings and regions as the Rust ones, so they need the exact layout of the
+//! shared types. Each crate declares its shared types with `c_struct!`, which
+//! records the size, alignment and field offsets of the Rust type, and the
+//! header lays them out as C would: a declaration whose natural C layout
+//! differs from the Rust one (a missing field, a reordered one) fails the
+//! generation. The rendered header also asserts every size and offset at
+//! compile time, and defines the layout hash of each ring message, checked
+//! against the ring header when attaching.
+
+use std::fmt::Write;
+
+use crate::ShmError;
+
+/// A field of a C struct.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CField {
+    /// The name of the field.
+    pub name: &'static str,
+    /// The C type of the field, or of its elements for an array.
+    pub ctype: String,
+    /// The number of elements of an array, zero for a scalar.
+    pub len: usize,
+    /// The offset of the field in the Rust type.
+    pub offset: usize,
+}
+
+/// The layout of a `#[repr(C)]` type, declared with `c_struct!`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CStruct {
+    /// The C name of the type (e.g. `ctl_raw_message`).
+    pub name: String,
+    /// The size of the Rust type.
+    pub size: usize,
+    /// The alignment of the Rust type.
+    pub align: usize,
+    /// The fields, in declaration order.
+    pub fields: Vec<CField>,
+}
+
+/// Declares the C layout of a `#[repr(C)]` type, listing every field with its C type.
+///
+/// ```ignore
+/// let raw = ctl_shm::c_struct!(RawMessage as "ctl_raw_message" {
+///     header: "ctl_message_header",
+///     fixed: "ctl_fixed_point",
+///     data: "uint8_t"[RAW_MESSAGE_SIZE],
+/// });
+/// ```
+#[macro_export]
+macro_rules! c_struct {
+    ($type:ty as $name:literal { $($field:ident: $ctype:literal $([$len:expr])?),* $(,)? }) => {
+        $crate::CStruct {
+            name: $name.to_string(),
+            size: ::core::mem::size_of::<$type>(),
+            align: ::core::mem::align_of::<$type>(),
+            fields: vec![$(
+                $crate::CField {
+                    name: stringify!($field),
+                    ctype: $ctype.to_string(),
+                    len: 0 $(+ $len)?,
+                    offset: ::core::mem::offset_of!($type, $field),
+                }
+            ),*],
+        }
+    };
+}
+
+/// Returns the size and alignment of a C scalar type.
+fn scalar_layout(ctype: &str) -> Option<(usize, usize)> {
+    match ctype {
+        "uint8_t" | "int8_t" | "char" => Some((1, 1)),
+        "uint16_t" | "int16_t" => Some((2, 2)),
+        "uint32_t" | "int32_t" | "float" => Some((4, 4)),
+        "uint64_t" | "int64_t" | "double" => Some((8, 8)),
+        _ => None,
+    }
+}
+
+/// A C header of the shared types, rendered with `render`.
+#[derive(Debug)]
+pub struct CHeader {
+    /// The include guard.
+    guard: String,
+    /// The declarations, in order.
+    body: String,
+    /// The structs declared so far, for the fields nesting them.
+    structs: Vec<CStruct>,
+}
+
+impl CHeader {
+    /// Creates an empty header with the include guard `guard` (e.g. `CTL_SHARED_H`).
+    pub fn new(guard: &str) -> Self {
+        Self { guard: guard.to_string(), body: String::new(), structs: Vec::new() }
+    }
+
+    /// Starts a section of declarations, titled by a comment.
+    pub fn section(&mut self, title: &str) {
+        let _ = write!(self.body, "\n/* {} */\n\n", title);
+    }
+
+    /// Defines an integer constant.
+    pub fn define(&mut self, name: &str, value: u64) {
+        let _ = writeln!(self.body, "#define {} {}", name, value);
+    }
+
+    /// Defines a 64-bit constant, e.g. a layout hash.
+    pub fn define_u64(&mut self, name: &str, value: u64) {
+        let _ = writeln!(self.body, "#define {} UINT64_C({:#018x})", name, value);
+    }
+
+    /// Defines a string constant, e.g. the name of a ring.
+    pub fn define_str(&mut self, name: &str, value: &str) {
+        let _ = writeln!(self.body, "#define {} \"{}\"", name, value.escape_default());
+    }
+
+    /// Defines the values of an enum stored as an integer, `{prefix}_{VARIANT}`.
+    pub fn enumeration(&mut self, prefix: &str, variants: &[(&str, u64)]) {
+        for (variant, value) in variants {
+            let _ = writeln!(self.body, "#define {}_{} {}", prefix, variant, value);
+        }
+        self.body.push('\n');
+    }
+
+    /// Declares a struct, checking that C lays it out as Rust does.
+    ///
+    /// The fields nest scalars or the structs declared before.
+    ///
+    /// # Errors
+    /// Returns an error if a field has an unknown type, or the natural C layout
+    /// of the fields differs from the Rust layout.
+    pub fn structure(&mut self, doc: &str, layout: CStruct) -> Result<(), ShmError> {
+        let error = |reason: String| ShmError::CLayout { name: layout.name.clone(), reason };
+
+        let mut offset = 0;
+        let mut align = 1;
+        for field in &layout.fields {
+            let (size, field_align) = self
+                .layout_of(&field.ctype)
+                .ok_or_else(|| error(format!("field {} has unknown type {}", field.name, field.ctype)))?;
+            offset = offset.next_multiple_of(field_align);
+            if offset != field.offset {
+                return Err(error(format!(
+                    "field {} is at offset {} in C, {} in Rust",
+                    field.name, offset, field.offset
+                )));
+            }
+            offset += size * field.len.max(1);
+            align = align.max(field_align);
+        }
+        let size = offset.next_multiple_of(align);
+        if (size, align) != (layout.size, layout.align) {
+            return Err(error(format!(
+                "size {} (align {}) in C, {} (align {}) in Rust, a field is missing",
+                size, align, layout.size, layout.align
+            )));
+        }
+
+        let _ = writeln!(self.body, "/* {} */", doc);
+        let _ = writeln!(self.body, "typedef struct {} {{", layout.name);
+        for field in &layout.fields {
+            let _ = match field.len {
+                0 => writeln!(self.body, "    {} {};", field.ctype, field.name),
+                len => writeln!(self.body, "    {} {}[{}];", field.ctype, field.name, len),
+            };
+        }
+        let _ = writeln!(self.body, "}} {};\n", layout.name);
+        let _ = writeln!(
+            self.body,
+            "CTL_STATIC_ASSERT(sizeof({name}) == {size}, \"size of {name}\");",
+            name = layout.name,
+            size = layout.size
+        );
+        for field in &layout.fields {
+            let _ = writeln!(
+                self.body,
+                "CTL_STATIC_ASSERT(offsetof({name}, {field}) == {offset}, \"offset of {name}.{field}\");",
+                name = layout.name,
+                field = field.name,
+                offset = field.offset
+            );
+        }
+        self.body.push('\n');
+        self.structs.push(layout);
+        Ok(())
+    }
+
+    /// Returns the size and alignment of a C type, a scalar or a declared struct.
+    fn layout_of(&self, ctype: &str) -> Option<(usize, usize)> {
+        scalar_layout(ctype).or_else(|| {
+            self.structs.iter().find(|layout| layout.name == ctype).map(|layout| (layout.size, layout.align))
+        })
+    }
+
+    /// Renders the header, compiling as C11 and C++11.
+    pub fn render(&self) -> String {
+        let mut header = String::new();
+        let _ = writeln!(header, "/* Generated by `ctl-admin header`, do not edit. */");
+        let _ = writeln!(header, "#ifndef {}", self.guard);
+        let _ = writeln!(header, "#define {}\n", self.guard);
+        header.push_str("#include <stddef.h>\n#include <stdint.h>\n\n");
+        header.push_str("#ifdef __cplusplus\n#define CTL_STATIC_ASSERT(cond, msg) static_assert(cond, msg)\n");
+        header.push_str("#else\n#define CTL_STATIC_ASSERT(cond, msg) _Static_assert(cond, msg)\n#endif\n");
+        header.push_str(&self.body);
+        let _ = writeln!(header, "\n#endif /* {} */", self.guard);
+        header
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[repr(C)]
+    struct Inner {
+        id: u32,
+        flag: u8,
+    }
+
+    #[repr(C)]
+    struct Outer {
+        kind: u8,
+        inner: Inner,
+        ts_ms: u64,
+        name: [u8; 5],
+    }
+
+    fn inner() -> CStruct {
+        c_struct!(Inner as "ctl_inner" { id: "uint32_t", flag: "uint8_t" })
+    }
+
+    #[test]
+    fn test_render_structs() {
+        let mut header = CHeader::new("CTL_TEST_H");
+        header.section("Test types");
+        header.define("CTL_NAME_SIZE", 5);
+        header.define_str("CTL_RING_NAME", "TEST_PS");
+        header.enumeration("CTL_KIND", &[("UNKNOWN", 0), ("TOP", 1)]);
+        header.structure("An inner struct.", inner()).unwrap();
+        let outer = c_struct!(Outer as "ctl_outer" {
+            kind: "uint8_t",
+            inner: "ctl_inner",
+            ts_ms: "uint64_t",
+            name: "uint8_t"[5],
+        });
+        header.structure("An outer struct.", outer).unwrap();
+
+        let rendered = header.render();
+        assert!(rendered.starts_with("/* Generated by `ctl-admin header`, do not edit. */\n#ifndef CTL_TEST_H\n"));
+        assert!(rendered.ends_with("#endif /* CTL_TEST_H */\n"));
+        assert!(rendered.contains("#define CTL_NAME_SIZE 5\n#define CTL_RING_NAME \"TEST_PS\"\n"));
+        assert!(rendered.contains("#define CTL_KIND_TOP 1\n"));
+        assert!(rendered.contains("typedef struct ctl_outer {\n    uint8_t kind;\n    ctl_inner inner;\n"));
+        assert!(rendered.contains("    uint8_t name[5];\n} ctl_outer;\n"));
+        assert!(rendered.contains("CTL_STATIC_ASSERT(sizeof(ctl_outer) == 32, \"size of ctl_outer\");"));
+        let assertion = "CTL_STATIC_ASSERT(offsetof(ctl_outer, ts_ms) == 16, \"offset of ctl_outer.ts_ms\");";
+        assert!(rendered.contains(assertion));
+    }
+
+    #[test]
+    fn test_layout_mismatch() {
+        let mut header = CHeader::new("CTL_TEST_H");
+        // Nesting an undeclared struct
+        let outer = c_struct!(Outer as "ctl_outer" {
+            kind: "uint8_t",
+            inner: "ctl_inner",
+            ts_ms: "uint64_t",
+            name: "uint8_t"[5],
+        });
+        assert!(matches!(header.structure("", outer), Err(ShmError::CLayout { .. })));
+
+        // Missing a field
+        header.structure("", inner()).unwrap();
+        let missing = c_struct!(Outer as "ctl_outer" { kind: "uint8_t", inner: "ctl_inner", ts_ms: "uint64_t" });
+        assert!(header.structure("", missing).is_err());
+
+        // Mistyped field, moving the next one
+        let mistyped = c_struct!(Inner as "ctl_inner2" { id: "uint8_t", flag: "uint8_t" });
+        assert!(header.structure("", mistyped).is_err());
+    }
+}
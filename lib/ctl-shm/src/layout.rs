@@ -0,0 +1,91 @@
+//! Layout hashes of the shared memory types.
+//!
+//! The processes sharing a ring are separate binaries, so a stale one may map a
+//! message type whose layout changed since it was built, reading garbage. The
+//! owner of a ring records the layout hash of its messages when creating it,
+//! and every process attaching to the ring checks its own, failing loudly on a
+//! mismatch. The hash covers the type name, size, alignment, the name and offset
+//! of every field, and the version of the crate defining the type.
+
+/// The FNV-1a offset basis, the hash of no bytes.
+pub const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+
+/// The FNV-1a prime.
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// Feeds bytes to a FNV-1a hash.
+pub const fn fnv1a(mut hash: u64, bytes: &[u8]) -> u64 {
+    let mut i = 0;
+    while i < bytes.len() {
+        hash ^= bytes[i] as u64;
+        hash = hash.wrapping_mul(FNV_PRIME);
+        i += 1;
+    }
+    hash
+}
+
+/// Returns the layout hash of a `#[repr(C)]` type from its fields, in a const context.
+///
+/// Every field must be listed, by name or tuple index, nested fields (e.g.
+/// `header.seq`) covering the layout of the nested types:
+///
+/// ```ignore
+/// const LAYOUT_HASH: u64 = ctl_shm::layout_hash!(RawMessage { header, header.seq, data });
+/// ```
+#[macro_export]
+macro_rules! layout_hash {
+    ($type:ty { $($($field:tt).+),* $(,)? }) => {{
+        let mut hash = $crate::fnv1a($crate::FNV_OFFSET_BASIS, stringify!($type).as_bytes());
+        hash = $crate::fnv1a(hash, env!("CARGO_PKG_VERSION").as_bytes());
+        hash = $crate::fnv1a(hash, &(::core::mem::size_of::<$type>() as u64).to_le_bytes());
+        hash = $crate::fnv1a(hash, &(::core::mem::align_of::<$type>() as u64).to_le_bytes());
+        $(
+            hash = $crate::fnv1a(hash, stringify!($($field).+).as_bytes());
+            hash = $crate::fnv1a(hash, &(::core::mem::offset_of!($type, $($field).+) as u64).to_le_bytes());
+        )*
+        hash
+    }};
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[repr(C)]
+    struct Before {
+        a: u32,
+        b: u64,
+    }
+
+    #[repr(C)]
+    struct After {
+        a: u64,
+        b: u64,
+    }
+
+    #[repr(C)]
+    struct Nested {
+        before: Before,
+        c: u8,
+    }
+
+    #[test]
+    fn test_fnv1a() {
+        assert_eq!(fnv1a(FNV_OFFSET_BASIS, b""), FNV_OFFSET_BASIS);
+        assert_eq!(fnv1a(FNV_OFFSET_BASIS, b"a"), 0xaf63_dc4c_8601_ec8c);
+        assert_eq!(fnv1a(fnv1a(FNV_OFFSET_BASIS, b"fo"), b"o"), fnv1a(FNV_OFFSET_BASIS, b"foo"));
+    }
+
+    #[test]
+    fn test_layout_hash() {
+        const BEFORE: u64 = layout_hash!(Before { a, b });
+        assert_eq!(BEFORE, layout_hash!(Before { a, b }));
+        // The field offsets, names and the type name are covered
+        assert_ne!(BEFORE, layout_hash!(Before { b, a }));
+        assert_ne!(BEFORE, layout_hash!(Before { a }));
+        assert_ne!(layout_hash!(After { a, b }), layout_hash!(Before { a, b }));
+
+        // Nested fields cover the layout of the nested types
+        assert_ne!(layout_hash!(Nested { before, c }), layout_hash!(Nested { before, before.a, before.b, c }));
+    }
+}
@@ -0,0 +1,26 @@
+//! Named shared memory regions for controller state that is not carried by rings.
+//!
+//! A region holds a single `#[repr(C)]` value mapped from `/dev/shm` by every
+//! process attached to it. The owner (usually ctl-resource-manager) creates the
+//! region and the other components open it by name.
+//!
+//! The `ring` module provides pub/sub rings on the same shared memory files, for
+//! running the controller without DPDK (see the `shm-rings` feature of the binaries).
+//!
+//! The `layout` module hashes the layout of the shared types, so the processes
+//! attaching to a ring or region detect a stale binary built for another layout.
+//!
+//! The `header` module renders the shared types as a C header, for the C and
+//! C++ components attaching to the same rings and regions.
+
+mod error;
+mod header;
+mod layout;
+mod region;
+mod ring;
+
+pub use error::ShmError;
+pub use header::{CField, CHeader, CStruct};
+pub use layout::{fnv1a, FNV_OFFSET_BASIS};
+pub use region::{set_region_prefix, ShmRegion, ShmSafe, SHM_DIR};
+pub use ring::{declare_c_message, declare_c_ring, ShmConsume, ShmMessage, ShmRing, ShmRingConsumer};
@@ -0,0 +1,199 @@
+use std::fs::{self, File, OpenOptions};
+use std::marker::PhantomData;
+use std::ops::Deref;
+use std::os::fd::AsRawFd;
+use std::path::PathBuf;
+use std::ptr::NonNull;
+use std::sync::OnceLock;
+
+use crate::ShmError;
+
+/// The directory holding the shared memory region files.
+pub const SHM_DIR: &str = "/dev/shm";
+
+/// The prefix of the region files of this process's controller instance.
+static REGION_PREFIX: OnceLock<String> = OnceLock::new();
+
+/// Prefixes the files of the regions created or opened by this process with
+/// `prefix`, separating controller instances sharing a host.
+///
+/// Returns `false` if a prefix was already set, which is kept.
+pub fn set_region_prefix(prefix: &str) -> bool {
+    REGION_PREFIX.set(prefix.to_string()).is_ok()
+}
+
+/// Returns the path of the file of a region.
+pub(crate) fn region_path(name: &str) -> PathBuf {
+    match REGION_PREFIX.get() {
+        Some(prefix) => PathBuf::from(SHM_DIR).join(format!("{}_{}", prefix, name)),
+        None => PathBuf::from(SHM_DIR).join(name),
+    }
+}
+
+/// Marker for types that can be placed in a shared memory region.
+///
+/// # Safety
+///
+/// Implementors must be `#[repr(C)]`, contain no pointers, be valid when all
+/// bytes are zero (regions are zero-initialized on creation), and only be
+/// mutated through interior mutability that is safe across processes (atomics).
+pub unsafe trait ShmSafe: Sync {}
+
+/// A named shared memory region holding a single `T`.
+pub struct ShmRegion<T: ShmSafe> {
+    /// The mapped value.
+    ptr: NonNull<T>,
+    /// The path of the region file.
+    path: PathBuf,
+    /// Whether this process created the region (and removes it on drop).
+    owner: bool,
+    _marker: PhantomData<T>,
+}
+
+// SAFETY: `T: ShmSafe` is `Sync` and only accessed through shared references.
+unsafe impl<T: ShmSafe> Send for ShmRegion<T> {}
+unsafe impl<T: ShmSafe> Sync for ShmRegion<T> {}
+
+impl<T: ShmSafe> ShmRegion<T> {
+    /// Creates the region, replacing any stale region of the same name.
+    /// The value is zero-initialized.
+    ///
+    /// LATENCY: SLOW_PATH
+    pub fn create(name: &str) -> Result<Self, ShmError> {
+        let path = region_path(name);
+        let io_err = |source| ShmError::Io { name: name.to_string(), source };
+
+        // Remove the stale region so that existing mappings are not reused.
+        if path.exists() {
+            fs::remove_file(&path).map_err(io_err)?;
+        }
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create_new(true)
+            .open(&path)
+            .map_err(io_err)?;
+        file.set_len(size_of::<T>() as u64).map_err(io_err)?;
+
+        let ptr = Self::map(&file).map_err(io_err)?;
+        Ok(Self { ptr, path, owner: true, _marker: PhantomData })
+    }
+
+    /// Opens an existing region created by the owner.
+    ///
+    /// LATENCY: SLOW_PATH
+    pub fn open(name: &str) -> Result<Self, ShmError> {
+        let path = region_path(name);
+        let io_err = |source| ShmError::Io { name: name.to_string(), source };
+
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(&path)
+            .map_err(io_err)?;
+        let found = file.metadata().map_err(io_err)?.len() as usize;
+        if found != size_of::<T>() {
+            return Err(ShmError::SizeMismatch {
+                name: name.to_string(),
+                expected: size_of::<T>(),
+                found,
+            });
+        }
+
+        let ptr = Self::map(&file).map_err(io_err)?;
+        Ok(Self { ptr, path, owner: false, _marker: PhantomData })
+    }
+
+    /// Returns whether this process created the region.
+    pub fn is_owner(&self) -> bool {
+        self.owner
+    }
+
+    /// Maps the region file as a shared read-write mapping.
+    fn map(file: &File) -> Result<NonNull<T>, std::io::Error> {
+        // SAFETY: the file is open read-write and sized to `size_of::<T>()`.
+        let ptr = unsafe {
+            libc::mmap(
+                std::ptr::null_mut(),
+                size_of::<T>(),
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_SHARED,
+                file.as_raw_fd(),
+                0,
+            )
+        };
+        if ptr == libc::MAP_FAILED {
+            return Err(std::io::Error::last_os_error());
+        }
+        NonNull::new(ptr as *mut T).ok_or_else(std::io::Error::last_os_error)
+    }
+}
+
+impl<T: ShmSafe> Deref for ShmRegion<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        // SAFETY: the mapping is valid for the lifetime of `self` and `T` is
+        // valid when zero-initialized.
+        unsafe { self.ptr.as_ref() }
+    }
+}
+
+impl<T: ShmSafe> Drop for ShmRegion<T> {
+    fn drop(&mut self) {
+        // SAFETY: the pointer and length are those returned by `mmap`.
+        unsafe {
+            libc::munmap(self.ptr.as_ptr() as *mut libc::c_void, size_of::<T>());
+        }
+        if self.owner {
+            let _ = fs::remove_file(&self.path);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    #[repr(C)]
+    struct Counters {
+        a: AtomicU64,
+        b: AtomicU64,
+    }
+
+    unsafe impl ShmSafe for Counters {}
+
+    #[test]
+    fn test_create_and_open() {
+        let name = format!("ctl_shm_test_{}", std::process::id());
+        let owner = ShmRegion::<Counters>::create(&name).unwrap();
+        assert!(owner.is_owner());
+        assert_eq!(owner.a.load(Ordering::Relaxed), 0);
+
+        let other = ShmRegion::<Counters>::open(&name).unwrap();
+        owner.a.fetch_add(3, Ordering::Relaxed);
+        other.b.fetch_add(5, Ordering::Relaxed);
+        assert_eq!(other.a.load(Ordering::Relaxed), 3);
+        assert_eq!(owner.b.load(Ordering::Relaxed), 5);
+    }
+
+    #[test]
+    fn test_open_size_mismatch() {
+        let name = format!("ctl_shm_test_mismatch_{}", std::process::id());
+        let _owner = ShmRegion::<Counters>::create(&name).unwrap();
+        let result = ShmRegion::<AtomicU64Wrapper>::open(&name);
+        assert!(matches!(result, Err(ShmError::SizeMismatch { .. })));
+    }
+
+    #[test]
+    fn test_open_missing() {
+        let result = ShmRegion::<Counters>::open("ctl_shm_test_missing_region");
+        assert!(matches!(result, Err(ShmError::Io { .. })));
+    }
+
+    #[repr(C)]
+    struct AtomicU64Wrapper(AtomicU64);
+
+    unsafe impl ShmSafe for AtomicU64Wrapper {}
+}
@@ -0,0 +1,515 @@
+//! Lock-free pub/sub rings in named shared memory.
+//!
+//! An alternative to the DPDK rings, for hosts without hugepages, root or a
+//! DPDK primary process (see the `shm-rings` feature of the binaries). A ring
+//! is a file of `/dev/shm` holding a header and a power of two number of slots.
+//!
+//! Producers claim the next sequence number from the head and stamp the slot
+//! they wrote with it, so any number of processes may publish. Each consumer
+//! keeps its own position, copies the slot of its next sequence number and
+//! checks the stamp didn't change during the copy. A consumer lapped by the
+//! producers skips to the oldest message still held, as with the DPDK rings.
+
+use std::cell::UnsafeCell;
+use std::fs::{self, File, OpenOptions};
+use std::marker::PhantomData;
+use std::mem::{offset_of, MaybeUninit};
+use std::os::fd::AsRawFd;
+use std::path::PathBuf;
+use std::ptr::{self, NonNull};
+use std::sync::atomic::{fence, AtomicU64, Ordering};
+
+use crate::region::region_path;
+use crate::{CField, CHeader, CStruct, ShmError};
+
+/// Marks an initialized ring header, "CTLRING1".
+const RING_MAGIC: u64 = 0x4354_4c52_494e_4731;
+
+/// The stamp of a slot being written.
+const WRITING: u64 = u64::MAX;
+
+/// Marker for the message types of a shared memory ring.
+///
+/// # Safety
+///
+/// Implementors must be `#[repr(C)]`, contain no pointers, and be valid for
+/// any bytes, e.g. enums stored as their `u8` tag: a consumer racing a lapping
+/// producer may copy a torn message before discarding it.
+pub unsafe trait ShmMessage: Copy + Send + 'static {
+    /// The hash of the layout of the message (see `layout_hash!`), checked by
+    /// the processes attaching to its rings.
+    const LAYOUT_HASH: u64;
+}
+
+/// The header of a ring file.
+#[repr(C)]
+struct RingHeader {
+    /// `RING_MAGIC` once the ring is initialized.
+    magic: AtomicU64,
+    /// The number of slots.
+    capacity: AtomicU64,
+    /// The size of a slot, checked by the processes opening the ring.
+    slot_size: AtomicU64,
+    /// The layout hash of the messages, checked by the processes opening the ring.
+    layout_hash: AtomicU64,
+    /// The next sequence number to publish.
+    head: AtomicU64,
+}
+
+/// A slot of a ring.
+#[repr(C)]
+struct Slot<T> {
+    /// The sequence number of the message plus one, zero if never written.
+    stamp: AtomicU64,
+    /// The message.
+    value: UnsafeCell<MaybeUninit<T>>,
+}
+
+/// The result of a consume.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShmConsume<T> {
+    /// The next message.
+    Message(T),
+    /// The consumer was lapped by the producers, skipping the `missed` messages.
+    SpedPast {
+        /// The number of skipped messages.
+        missed: u64,
+    },
+    /// The next message is being written.
+    InFlight,
+    /// No message was published since the last consume.
+    Empty,
+}
+
+/// A named shared memory pub/sub ring of `T` messages.
+pub struct ShmRing<T: ShmMessage> {
+    /// The mapped ring file.
+    ptr: NonNull<u8>,
+    /// The length of the mapping.
+    len: usize,
+    /// The number of slots.
+    capacity: u64,
+    /// The path of the ring file.
+    path: PathBuf,
+    /// Whether this process created the ring (and removes it on drop).
+    owner: bool,
+    _marker: PhantomData<T>,
+}
+
+// SAFETY: the header and stamps are atomics, and the slots are only copied in
+// and out under the stamp protocol.
+unsafe impl<T: ShmMessage> Send for ShmRing<T> {}
+unsafe impl<T: ShmMessage> Sync for ShmRing<T> {}
+
+impl<T: ShmMessage> ShmRing<T> {
+    /// Creates the ring of `capacity` slots, replacing any stale ring of the same name.
+    ///
+    /// LATENCY: SLOW_PATH
+    pub fn create(name: &str, capacity: usize) -> Result<Self, ShmError> {
+        if !capacity.is_power_of_two() {
+            return Err(ShmError::InvalidCapacity { name: name.to_string(), capacity });
+        }
+        let path = ring_path(name);
+        let io_err = |source| ShmError::Io { name: name.to_string(), source };
+
+        // Remove the stale ring so that existing mappings are not reused.
+        if path.exists() {
+            fs::remove_file(&path).map_err(io_err)?;
+        }
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create_new(true)
+            .open(&path)
+            .map_err(io_err)?;
+        let len = Self::file_len(capacity as u64);
+        file.set_len(len as u64).map_err(io_err)?;
+
+        let ptr = map(&file, len).map_err(io_err)?;
+        let ring = Self { ptr, len, capacity: capacity as u64, path, owner: true, _marker: PhantomData };
+        let header = ring.header();
+        header.capacity.store(capacity as u64, Ordering::Relaxed);
+        header.slot_size.store(size_of::<Slot<T>>() as u64, Ordering::Relaxed);
+        header.layout_hash.store(T::LAYOUT_HASH, Ordering::Relaxed);
+        header.magic.store(RING_MAGIC, Ordering::Release);
+        Ok(ring)
+    }
+
+    /// Opens an existing ring created by the owner.
+    ///
+    /// LATENCY: SLOW_PATH
+    pub fn open(name: &str) -> Result<Self, ShmError> {
+        let path = ring_path(name);
+        let io_err = |source| ShmError::Io { name: name.to_string(), source };
+
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(&path)
+            .map_err(io_err)?;
+        let len = file.metadata().map_err(io_err)?.len() as usize;
+        if len < size_of::<RingHeader>() {
+            return Err(ShmError::InvalidRing { name: name.to_string(), reason: "truncated header" });
+        }
+
+        let ptr = map(&file, len).map_err(io_err)?;
+        // Unmapped on drop from here, a non-owner leaving the file in place
+        let mut ring = Self { ptr, len, capacity: 0, path, owner: false, _marker: PhantomData };
+        let header = ring.header();
+        if header.magic.load(Ordering::Acquire) != RING_MAGIC {
+            return Err(ShmError::InvalidRing { name: name.to_string(), reason: "not initialized" });
+        }
+        let slot_size = header.slot_size.load(Ordering::Relaxed) as usize;
+        if slot_size != size_of::<Slot<T>>() {
+            return Err(ShmError::SizeMismatch {
+                name: name.to_string(),
+                expected: size_of::<Slot<T>>(),
+                found: slot_size,
+            });
+        }
+        let layout_hash = header.layout_hash.load(Ordering::Relaxed);
+        if layout_hash != T::LAYOUT_HASH {
+            return Err(ShmError::LayoutMismatch { name: name.to_string(), expected: T::LAYOUT_HASH, found: layout_hash });
+        }
+        let capacity = header.capacity.load(Ordering::Relaxed);
+        if !capacity.is_power_of_two() || Self::file_len(capacity) != len {
+            return Err(ShmError::InvalidRing { name: name.to_string(), reason: "size does not match its capacity" });
+        }
+        ring.capacity = capacity;
+        Ok(ring)
+    }
+
+    /// Returns the number of slots.
+    pub fn capacity(&self) -> usize {
+        self.capacity as usize
+    }
+
+    /// Returns whether this process created the ring.
+    pub fn is_owner(&self) -> bool {
+        self.owner
+    }
+
+    /// Returns the number of messages published, including those being written.
+    pub fn published(&self) -> u64 {
+        self.header().head.load(Ordering::Acquire)
+    }
+
+    /// Publishes a message, overwriting the oldest one once the ring is full.
+    ///
+    /// A producer lapping another one still writing the same slot corrupts the
+    /// message, so the ring must be sized well above the number of producers.
+    ///
+    /// LATENCY: FAST_PATH
+    pub fn publish(&self, value: &T) {
+        let seq = self.header().head.fetch_add(1, Ordering::AcqRel);
+        let slot = self.slot(seq);
+        slot.stamp.store(WRITING, Ordering::Relaxed);
+        fence(Ordering::Release);
+        // SAFETY: the slot is within the mapping, and readers discard the
+        // copies made while its stamp is not `seq + 1`.
+        unsafe { ptr::write_volatile(slot.value.get(), MaybeUninit::new(*value)) };
+        slot.stamp.store(seq + 1, Ordering::Release);
+    }
+
+    /// Attaches a consumer reading the messages published from now on.
+    pub fn consumer(&self) -> ShmRingConsumer<'_, T> {
+        ShmRingConsumer { ring: self, next: self.published() }
+    }
+
+    /// Returns the length of the ring file of `capacity` slots.
+    fn file_len(capacity: u64) -> usize {
+        slots_offset::<T>() + capacity as usize * size_of::<Slot<T>>()
+    }
+
+    fn header(&self) -> &RingHeader {
+        // SAFETY: the mapping starts with the header, zero-initialized atomics
+        // being valid.
+        unsafe { &*(self.ptr.as_ptr() as *const RingHeader) }
+    }
+
+    fn slot(&self, seq: u64) -> &Slot<T> {
+        let index = (seq & (self.capacity - 1)) as usize;
+        // SAFETY: the index is below the capacity, the mapping holding
+        // `capacity` slots after the aligned header.
+        unsafe { &*(self.ptr.as_ptr().add(slots_offset::<T>()) as *const Slot<T>).add(index) }
+    }
+}
+
+impl<T: ShmMessage> Drop for ShmRing<T> {
+    fn drop(&mut self) {
+        // SAFETY: the pointer and length are those returned by `mmap`.
+        unsafe {
+            libc::munmap(self.ptr.as_ptr() as *mut libc::c_void, self.len);
+        }
+        if self.owner {
+            let _ = fs::remove_file(&self.path);
+        }
+    }
+}
+
+/// A consumer of a ring, at its own position.
+pub struct ShmRingConsumer<'a, T: ShmMessage> {
+    /// The ring.
+    ring: &'a ShmRing<T>,
+    /// The sequence number of the next message to consume.
+    next: u64,
+}
+
+impl<T: ShmMessage> ShmRingConsumer<'_, T> {
+    /// Returns the sequence number of the next message to consume.
+    pub fn position(&self) -> u64 {
+        self.next
+    }
+
+    /// Consumes the next message.
+    ///
+    /// LATENCY: FAST_PATH
+    pub fn consume(&mut self) -> ShmConsume<T> {
+        let head = self.ring.published();
+        if self.next >= head {
+            return ShmConsume::Empty;
+        }
+        if head - self.next > self.ring.capacity {
+            return self.skip(head);
+        }
+
+        let slot = self.ring.slot(self.next);
+        let expected = self.next + 1;
+        let stamp = slot.stamp.load(Ordering::Acquire);
+        if stamp == WRITING || stamp < expected {
+            return ShmConsume::InFlight;
+        }
+        if stamp > expected {
+            return self.skip(self.ring.published());
+        }
+
+        // SAFETY: the slot was written with `expected`, and `T` is valid for any
+        // bytes should a producer overwrite it during the copy.
+        let value = unsafe { ptr::read_volatile(slot.value.get()).assume_init() };
+        fence(Ordering::Acquire);
+        if slot.stamp.load(Ordering::Relaxed) != expected {
+            return self.skip(self.ring.published());
+        }
+        self.next = expected;
+        ShmConsume::Message(value)
+    }
+
+    /// Skips to the oldest message held at `head`.
+    fn skip(&mut self, head: u64) -> ShmConsume<T> {
+        let oldest = head.saturating_sub(self.ring.capacity).max(self.next + 1);
+        let missed = oldest - self.next;
+        self.next = oldest;
+        ShmConsume::SpedPast { missed }
+    }
+}
+
+/// Returns the path of the file of a ring.
+fn ring_path(name: &str) -> PathBuf {
+    region_path(&format!("{}.ring", name))
+}
+
+/// Returns the offset of the slots in a ring file, after the aligned header.
+fn slots_offset<T>() -> usize {
+    size_of::<RingHeader>().next_multiple_of(align_of::<Slot<T>>())
+}
+
+/// Declares the header of the ring files in a C header.
+///
+/// # Errors
+/// Returns an error if the C layout of the ring header differs from the Rust one.
+pub fn declare_c_ring(header: &mut CHeader) -> Result<(), ShmError> {
+    header.define_u64("CTL_RING_MAGIC", RING_MAGIC);
+    header.define_u64("CTL_RING_WRITING", WRITING);
+    header.structure(
+        "The header of a ring file of /dev/shm, its fields accessed atomically. The slots follow it, \
+         aligned, a producer stamping a slot with CTL_RING_WRITING during its write, then with the \
+         sequence number of its message plus one.",
+        crate::c_struct!(RingHeader as "ctl_ring_header" {
+            magic: "uint64_t",
+            capacity: "uint64_t",
+            slot_size: "uint64_t",
+            layout_hash: "uint64_t",
+            head: "uint64_t",
+        }),
+    )
+}
+
+/// Declares a ring message in a C header, with its layout hash and the slot of its rings.
+///
+/// The constants are prefixed by the upper-case C name of the message, e.g.
+/// `CTL_RAW_MESSAGE_LAYOUT_HASH` and `CTL_RAW_MESSAGE_SLOTS_OFFSET`.
+///
+/// # Errors
+/// Returns an error if the C layout of the message differs from the Rust one.
+pub fn declare_c_message<T: ShmMessage>(header: &mut CHeader, doc: &str, layout: CStruct) -> Result<(), ShmError> {
+    let name = layout.name.clone();
+    let prefix = name.to_uppercase();
+    header.structure(doc, layout)?;
+    header.define_u64(&format!("{}_LAYOUT_HASH", prefix), T::LAYOUT_HASH);
+    header.define(&format!("{}_SLOTS_OFFSET", prefix), slots_offset::<T>() as u64);
+    let slot = CStruct {
+        name: format!("{}_slot", name),
+        size: size_of::<Slot<T>>(),
+        align: align_of::<Slot<T>>(),
+        fields: vec![
+            CField { name: "stamp", ctype: "uint64_t".to_string(), len: 0, offset: offset_of!(Slot<T>, stamp) },
+            CField { name: "value", ctype: name.clone(), len: 0, offset: offset_of!(Slot<T>, value) },
+        ],
+    };
+    header.structure(&format!("A slot of the rings of {}, its stamp accessed atomically.", name), slot)
+}
+
+/// Maps `len` bytes of a ring file as a shared read-write mapping.
+fn map(file: &File, len: usize) -> Result<NonNull<u8>, std::io::Error> {
+    // SAFETY: the file is open read-write and at least `len` long.
+    let ptr = unsafe {
+        libc::mmap(
+            std::ptr::null_mut(),
+            len,
+            libc::PROT_READ | libc::PROT_WRITE,
+            libc::MAP_SHARED,
+            file.as_raw_fd(),
+            0,
+        )
+    };
+    if ptr == libc::MAP_FAILED {
+        return Err(std::io::Error::last_os_error());
+    }
+    NonNull::new(ptr as *mut u8).ok_or_else(std::io::Error::last_os_error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::thread;
+
+    #[repr(C)]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    struct Message {
+        producer: u64,
+        seq: u64,
+    }
+
+    unsafe impl ShmMessage for Message {
+        const LAYOUT_HASH: u64 = crate::layout_hash!(Message { producer, seq });
+    }
+
+    #[test]
+    fn test_declare_c_message() {
+        let mut header = CHeader::new("CTL_TEST_H");
+        declare_c_ring(&mut header).unwrap();
+        let message = crate::c_struct!(Message as "ctl_message" { producer: "uint64_t", seq: "uint64_t" });
+        declare_c_message::<Message>(&mut header, "A test message.", message).unwrap();
+
+        let rendered = header.render();
+        assert!(rendered.contains("#define CTL_RING_MAGIC UINT64_C(0x43544c52494e4731)\n"));
+        let hash = format!("#define CTL_MESSAGE_LAYOUT_HASH UINT64_C({:#018x})", Message::LAYOUT_HASH);
+        assert!(rendered.contains(&hash));
+        assert!(rendered.contains("#define CTL_MESSAGE_SLOTS_OFFSET 40\n"));
+        assert!(rendered.contains("typedef struct ctl_message_slot {\n    uint64_t stamp;\n    ctl_message value;\n}"));
+        assert!(rendered.contains("CTL_STATIC_ASSERT(sizeof(ctl_message_slot) == 24, \"size of ctl_message_slot\");"));
+    }
+
+    fn ring_name(test: &str) -> String {
+        format!("ctl_shm_ring_test_{}_{}", test, std::process::id())
+    }
+
+    #[test]
+    fn test_publish_and_consume() {
+        let name = ring_name("order");
+        let owner = ShmRing::<Message>::create(&name, 8).unwrap();
+        let other = ShmRing::<Message>::open(&name).unwrap();
+        assert!(owner.is_owner() && !other.is_owner());
+        assert_eq!(other.capacity(), 8);
+
+        let mut consumer = other.consumer();
+        assert_eq!(consumer.consume(), ShmConsume::Empty);
+        for seq in 0..3 {
+            owner.publish(&Message { producer: 0, seq });
+        }
+        for seq in 0..3 {
+            assert_eq!(consumer.consume(), ShmConsume::Message(Message { producer: 0, seq }));
+        }
+        assert_eq!(consumer.consume(), ShmConsume::Empty);
+        assert_eq!(consumer.position(), 3);
+    }
+
+    #[test]
+    fn test_sped_past() {
+        let name = ring_name("lapped");
+        let ring = ShmRing::<Message>::create(&name, 4).unwrap();
+        let mut consumer = ring.consumer();
+        for seq in 0..10 {
+            ring.publish(&Message { producer: 0, seq });
+        }
+        // 10 published, the last 4 held
+        assert_eq!(consumer.consume(), ShmConsume::SpedPast { missed: 6 });
+        assert_eq!(consumer.consume(), ShmConsume::Message(Message { producer: 0, seq: 6 }));
+    }
+
+    #[test]
+    fn test_multiple_producers() {
+        let name = ring_name("producers");
+        let ring = Arc::new(ShmRing::<Message>::create(&name, 4096).unwrap());
+        let mut consumer = ring.consumer();
+
+        let producers: Vec<_> = (0..4)
+            .map(|producer| {
+                let ring = ring.clone();
+                thread::spawn(move || {
+                    for seq in 0..500 {
+                        ring.publish(&Message { producer, seq });
+                    }
+                })
+            })
+            .collect();
+        for producer in producers {
+            producer.join().unwrap();
+        }
+
+        // Every producer's messages arrive in its publishing order
+        let mut next = [0u64; 4];
+        while let ShmConsume::Message(message) = consumer.consume() {
+            assert_eq!(message.seq, next[message.producer as usize]);
+            next[message.producer as usize] += 1;
+        }
+        assert_eq!(next, [500; 4]);
+    }
+
+    #[test]
+    fn test_open_mismatch() {
+        let name = ring_name("mismatch");
+        let _owner = ShmRing::<Message>::create(&name, 8).unwrap();
+        let result = ShmRing::<U64Wrapper>::open(&name);
+        assert!(matches!(result, Err(ShmError::SizeMismatch { .. })));
+        let result = ShmRing::<Reordered>::open(&name);
+        assert!(matches!(result, Err(ShmError::LayoutMismatch { .. })));
+
+        assert!(matches!(
+            ShmRing::<Message>::create(&ring_name("capacity"), 6),
+            Err(ShmError::InvalidCapacity { .. })
+        ));
+        assert!(matches!(ShmRing::<Message>::open(&ring_name("missing")), Err(ShmError::Io { .. })));
+    }
+
+    #[repr(C)]
+    #[derive(Clone, Copy)]
+    struct U64Wrapper(u64);
+
+    unsafe impl ShmMessage for U64Wrapper {
+        const LAYOUT_HASH: u64 = crate::layout_hash!(U64Wrapper { 0 });
+    }
+
+    /// A message of the size of `Message`, of another layout.
+    #[repr(C)]
+    #[derive(Clone, Copy)]
+    struct Reordered {
+        seq: u64,
+        producer: u64,
+    }
+
+    unsafe impl ShmMessage for Reordered {
+        const LAYOUT_HASH: u64 = crate::layout_hash!(Reordered { seq, producer });
+    }
+}
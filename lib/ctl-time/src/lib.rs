@@ -0,0 +1,16 @@
+//! Time utilities for the controller components.
+//!
+//! Provides the timestamps of the hot path, read from the calibrated time
+//! stamp counter, and the estimation of the local clock offset against the
+//! exchange server time, shared with every component through a shared memory
+//! region maintained by ctl-time-sync.
+
+mod sync;
+mod region;
+mod tsc;
+
+pub use sync::{ClockSample, OffsetEstimator, DriftWarning, check_drift};
+pub use region::{TimeSyncRegion, TIME_SYNC_REGION_NAME};
+pub use tsc::{
+    calibrate, clock_source, monotonic_ns, now_ms, now_ns, system_monotonic_ns, system_now_ns, tsc_hz, ClockSource,
+};
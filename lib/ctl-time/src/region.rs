@@ -0,0 +1,67 @@
+use std::sync::atomic::{AtomicI64, AtomicU64, Ordering};
+
+use ctl_shm::ShmSafe;
+
+use crate::ClockSample;
+
+/// Name of the time synchronization region.
+pub const TIME_SYNC_REGION_NAME: &str = "ctl_time_sync";
+
+/// The estimated clock offset against the exchange, shared by ctl-time-sync.
+#[repr(C)]
+#[derive(Debug, Default)]
+pub struct TimeSyncRegion {
+    /// Estimated offset (server time minus local time), in milliseconds.
+    pub offset_ms: AtomicI64,
+    /// Round trip time of the sample the offset was estimated from, in milliseconds.
+    pub rtt_ms: AtomicU64,
+    /// Local time of the last synchronization, in milliseconds (zero if never synchronized).
+    pub synced_at_ms: AtomicU64,
+    /// Number of synchronizations.
+    pub syncs: AtomicU64,
+}
+
+// SAFETY: `TimeSyncRegion` is `repr(C)`, made only of atomics and valid when zeroed.
+unsafe impl ShmSafe for TimeSyncRegion {}
+
+impl TimeSyncRegion {
+    /// Publishes the offset estimated from `sample`.
+    pub fn publish(&self, sample: &ClockSample) {
+        self.offset_ms.store(sample.offset_ms(), Ordering::Relaxed);
+        self.rtt_ms.store(sample.rtt_ms(), Ordering::Relaxed);
+        self.syncs.fetch_add(1, Ordering::Relaxed);
+        self.synced_at_ms.store(sample.received_ms, Ordering::Release);
+    }
+
+    /// Returns true if an offset was ever published.
+    pub fn is_synced(&self) -> bool {
+        self.synced_at_ms.load(Ordering::Acquire) != 0
+    }
+
+    /// Returns the estimated offset (server time minus local time), in milliseconds.
+    pub fn offset_ms(&self) -> i64 {
+        self.offset_ms.load(Ordering::Relaxed)
+    }
+
+    /// Returns the estimated server time for the local time `local_ms`, in milliseconds.
+    pub fn server_time_ms(&self, local_ms: u64) -> u64 {
+        local_ms.saturating_add_signed(self.offset_ms())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_publish() {
+        let region = TimeSyncRegion::default();
+        assert!(!region.is_synced());
+
+        region.publish(&ClockSample { sent_ms: 1_000, server_ms: 1_550, received_ms: 1_100 });
+        assert!(region.is_synced());
+        assert_eq!(region.offset_ms(), 500);
+        assert_eq!(region.rtt_ms.load(Ordering::Relaxed), 100);
+        assert_eq!(region.server_time_ms(2_000), 2_500);
+    }
+}
@@ -0,0 +1,139 @@
+//! Estimation of the local clock offset against the exchange server time.
+//!
+//! Each sample brackets a server time request between two local timestamps.
+//! The server time is assumed taken halfway through the round trip, so the
+//! sample with the lowest round trip time bounds the offset error most tightly.
+
+use std::collections::VecDeque;
+
+/// Binance rejects signed requests whose timestamp is ahead of the server time by this much.
+const MAX_AHEAD_MS: i64 = 1_000;
+
+/// A measurement of the server time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClockSample {
+    /// Local time the request was sent, in milliseconds.
+    pub sent_ms: u64,
+    /// The server time of the response, in milliseconds.
+    pub server_ms: u64,
+    /// Local time the response was received, in milliseconds.
+    pub received_ms: u64,
+}
+
+impl ClockSample {
+    /// Returns the round trip time, in milliseconds.
+    pub fn rtt_ms(&self) -> u64 {
+        self.received_ms.saturating_sub(self.sent_ms)
+    }
+
+    /// Returns the offset of the server clock against the local clock
+    /// (server time minus local time), in milliseconds.
+    pub fn offset_ms(&self) -> i64 {
+        let midpoint = self.sent_ms + self.rtt_ms() / 2;
+        self.server_ms as i64 - midpoint as i64
+    }
+}
+
+/// Estimates the clock offset from the lowest round trip time sample of the recent ones.
+#[derive(Debug, Clone)]
+pub struct OffsetEstimator {
+    /// Maximum number of recent samples kept.
+    capacity: usize,
+    /// The recent samples, oldest first.
+    samples: VecDeque<ClockSample>,
+}
+
+impl OffsetEstimator {
+    /// Creates an estimator over the `capacity` most recent samples.
+    ///
+    /// # Panics
+    /// Panics if `capacity` is zero.
+    pub fn new(capacity: usize) -> Self {
+        assert!(capacity > 0, "estimator capacity must be greater than 0");
+        Self { capacity, samples: VecDeque::with_capacity(capacity) }
+    }
+
+    /// Adds a sample, dropping the oldest one beyond the capacity.
+    pub fn push(&mut self, sample: ClockSample) {
+        if self.samples.len() == self.capacity {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(sample);
+    }
+
+    /// Returns the lowest round trip time sample, if any.
+    pub fn best(&self) -> Option<&ClockSample> {
+        self.samples.iter().min_by_key(|s| s.rtt_ms())
+    }
+
+    /// Returns the estimated offset (server time minus local time), in milliseconds.
+    pub fn offset_ms(&self) -> Option<i64> {
+        self.best().map(ClockSample::offset_ms)
+    }
+}
+
+/// A clock drift that would cause signed requests to be rejected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DriftWarning {
+    /// The local clock is ahead of the server by this many milliseconds.
+    Ahead(i64),
+    /// The local clock is behind the server by this many milliseconds.
+    Behind(i64),
+}
+
+/// Checks whether requests timestamped with the local clock would be rejected
+/// for the given `recvWindow`, keeping `margin_ms` of headroom for the request latency.
+///
+/// Binance rejects a request if its timestamp is more than 1s ahead of the server
+/// time, or more than `recvWindow` behind it.
+/// https://github.com/binance/binance-spot-api-docs/blob/master/rest-api.md#timing-security
+pub fn check_drift(offset_ms: i64, recv_window_ms: u64, margin_ms: u64) -> Option<DriftWarning> {
+    let margin_ms = margin_ms as i64;
+    if -offset_ms + margin_ms >= MAX_AHEAD_MS {
+        Some(DriftWarning::Ahead(-offset_ms))
+    } else if offset_ms + margin_ms > recv_window_ms as i64 {
+        Some(DriftWarning::Behind(offset_ms))
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(sent_ms: u64, server_ms: u64, received_ms: u64) -> ClockSample {
+        ClockSample { sent_ms, server_ms, received_ms }
+    }
+
+    #[test]
+    fn test_sample_offset() {
+        let s = sample(1_000, 1_550, 1_100);
+        assert_eq!(s.rtt_ms(), 100);
+        assert_eq!(s.offset_ms(), 500);
+        assert_eq!(sample(1_000, 900, 1_100).offset_ms(), -150);
+    }
+
+    #[test]
+    fn test_estimator_uses_lowest_rtt() {
+        let mut estimator = OffsetEstimator::new(2);
+        assert_eq!(estimator.offset_ms(), None);
+
+        estimator.push(sample(0, 100, 400));
+        estimator.push(sample(1_000, 1_025, 1_010));
+        assert_eq!(estimator.offset_ms(), Some(20));
+
+        // The best sample is dropped once it falls out of the window
+        estimator.push(sample(2_000, 2_150, 2_100));
+        estimator.push(sample(3_000, 3_250, 3_200));
+        assert_eq!(estimator.best().unwrap().rtt_ms(), 100);
+    }
+
+    #[test]
+    fn test_check_drift() {
+        assert_eq!(check_drift(0, 5_000, 100), None);
+        assert_eq!(check_drift(-950, 5_000, 100), Some(DriftWarning::Ahead(950)));
+        assert_eq!(check_drift(4_950, 5_000, 100), Some(DriftWarning::Behind(4_950)));
+        assert_eq!(check_drift(4_800, 5_000, 100), None);
+    }
+}
@@ -0,0 +1,410 @@
+//! Calibrated TSC timestamps.
+//!
+//! The timestamps of the hot path (message headers, latency histograms) read
+//! the time stamp counter, a few cycles, instead of the system clocks, whose
+//! `clock_gettime` falls back to a syscall under some clock sources. The
+//! counter is scaled to nanoseconds by its rate, measured against the
+//! monotonic clock over `CALIBRATION_WINDOW` on first use (or on `calibrate`
+//! at startup), and anchored to the wall and monotonic clocks.
+//!
+//! Every `RESYNC_INTERVAL_NS`, the first reader past the interval re-syncs the
+//! calibration: the rate is measured anew over the interval and the anchors
+//! moved to the clocks, so the drift of the counter and the steps of the wall
+//! clock (NTP) are caught up. The monotonic time never goes back across a
+//! re-sync, so the latencies between two timestamps are never negative.
+//!
+//! Without an invariant TSC (a constant rate across the P-states, ticking
+//! through the C-states), or off x86_64, the timestamps are the system clocks.
+
+use std::hint;
+use std::sync::atomic::{fence, AtomicU64, AtomicU8, Ordering};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// The window over which the rate of the counter is first measured.
+const CALIBRATION_WINDOW: Duration = Duration::from_millis(10);
+
+/// The interval between the re-syncs of the calibration to the clocks.
+const RESYNC_INTERVAL_NS: u64 = 1_000_000_000;
+
+/// The fractional bits of the nanoseconds per tick.
+const SCALE_SHIFT: u32 = 32;
+
+// The states of the calibration
+const UNCALIBRATED: u8 = 0;
+const CALIBRATING: u8 = 1;
+const CALIBRATED: u8 = 2;
+const UNAVAILABLE: u8 = 3;
+
+/// The clock source of the timestamps.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClockSource {
+    /// The calibrated time stamp counter.
+    Tsc,
+    /// The system clocks, without an invariant TSC or until calibrated.
+    System,
+}
+
+/// Returns the current wall-clock time of the system clock, in nanoseconds
+/// since the epoch.
+///
+/// LATENCY: SLOW_PATH
+pub fn system_now_ns() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos() as u64)
+        .unwrap_or_default()
+}
+
+/// Returns the time of the system-wide monotonic clock, in nanoseconds.
+///
+/// LATENCY: SLOW_PATH
+pub fn system_monotonic_ns() -> u64 {
+    let mut ts = libc::timespec { tv_sec: 0, tv_nsec: 0 };
+    // SAFETY: `ts` is a valid timespec and CLOCK_MONOTONIC is always available on Linux.
+    unsafe { libc::clock_gettime(libc::CLOCK_MONOTONIC, &mut ts) };
+    ts.tv_sec as u64 * 1_000_000_000 + ts.tv_nsec as u64
+}
+
+/// Returns the current local wall-clock time, in milliseconds since the epoch.
+///
+/// LATENCY: FAST_PATH
+pub fn now_ms() -> u64 {
+    now_ns() / 1_000_000
+}
+
+/// Returns the current local wall-clock time, in nanoseconds since the epoch.
+///
+/// LATENCY: FAST_PATH
+pub fn now_ns() -> u64 {
+    match tsc_now() {
+        Some((calibration, tsc)) => calibration.wall_ns(tsc),
+        None => system_now_ns(),
+    }
+}
+
+/// Returns the time of the system-wide monotonic clock, in nanoseconds.
+///
+/// Unlike `Instant`, comparable across the processes of the host, so it can
+/// timestamp the messages published to the shared rings; the calibrations of
+/// two processes agree within a few microseconds.
+///
+/// LATENCY: FAST_PATH
+pub fn monotonic_ns() -> u64 {
+    match tsc_now() {
+        Some((calibration, tsc)) => calibration.monotonic_ns(tsc),
+        None => system_monotonic_ns(),
+    }
+}
+
+/// Calibrates the counter unless already done, returning the clock source of
+/// the timestamps.
+///
+/// Called at startup, so the first timestamp of the hot path doesn't wait
+/// for the calibration window.
+///
+/// LATENCY: SLOW_PATH
+pub fn calibrate() -> ClockSource {
+    if CLOCK.state.compare_exchange(UNCALIBRATED, CALIBRATING, Ordering::AcqRel, Ordering::Acquire).is_ok() {
+        match measure() {
+            Some(calibration) => {
+                CLOCK.store(&calibration);
+                CLOCK.state.store(CALIBRATED, Ordering::Release);
+            }
+            None => CLOCK.state.store(UNAVAILABLE, Ordering::Release),
+        }
+    }
+    clock_source()
+}
+
+/// Returns the clock source of the timestamps.
+pub fn clock_source() -> ClockSource {
+    match CLOCK.state.load(Ordering::Acquire) {
+        CALIBRATED => ClockSource::Tsc,
+        _ => ClockSource::System,
+    }
+}
+
+/// Returns the measured rate of the counter, in ticks per second, once calibrated.
+pub fn tsc_hz() -> Option<u64> {
+    (clock_source() == ClockSource::Tsc).then(|| CLOCK.load().ticks(1_000_000_000))
+}
+
+/// Returns the current calibration and counter, re-synced past the interval,
+/// `None` for the system clocks.
+///
+/// LATENCY: FAST_PATH
+#[inline]
+fn tsc_now() -> Option<(Calibration, u64)> {
+    match CLOCK.state.load(Ordering::Acquire) {
+        CALIBRATED => {}
+        UNCALIBRATED if calibrate() == ClockSource::Tsc => {}
+        _ => return None,
+    }
+    let tsc = rdtsc();
+    let calibration = CLOCK.load();
+    if calibration.elapsed_ns(tsc) < RESYNC_INTERVAL_NS {
+        return Some((calibration, tsc));
+    }
+    // Past the interval, a single reader re-syncs, the others keep the calibration read
+    let resynced = calibration.resynced(Anchor::now());
+    if CLOCK.store(&resynced) {
+        return Some((resynced, resynced.base.tsc));
+    }
+    Some((calibration, tsc))
+}
+
+/// Measures the rate of the counter over the calibration window, `None`
+/// without an invariant TSC.
+fn measure() -> Option<Calibration> {
+    if !has_invariant_tsc() {
+        return None;
+    }
+    let from = Anchor::now();
+    let started = Instant::now();
+    while started.elapsed() < CALIBRATION_WINDOW {
+        hint::spin_loop();
+    }
+    Calibration::measure(from, Anchor::now())
+}
+
+#[cfg(target_arch = "x86_64")]
+#[inline(always)]
+fn rdtsc() -> u64 {
+    // SAFETY: rdtsc is available on every x86_64 CPU.
+    unsafe { std::arch::x86_64::_rdtsc() }
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+#[inline(always)]
+fn rdtsc() -> u64 {
+    0
+}
+
+/// Returns true if the counter is invariant, ticking at a constant rate whatever
+/// the P-state and the C-state of the core.
+#[cfg(target_arch = "x86_64")]
+fn has_invariant_tsc() -> bool {
+    use std::arch::x86_64::__cpuid;
+
+    // The advanced power management leaf, EDX bit 8
+    let max_leaf = __cpuid(0x8000_0000).eax;
+    max_leaf >= 0x8000_0007 && __cpuid(0x8000_0007).edx & (1 << 8) != 0
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+fn has_invariant_tsc() -> bool {
+    false
+}
+
+/// A reading of the counter with the clocks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Anchor {
+    /// The counter.
+    tsc: u64,
+    /// The wall-clock time, in nanoseconds since the epoch.
+    wall_ns: u64,
+    /// The monotonic time, in nanoseconds.
+    mono_ns: u64,
+}
+
+impl Anchor {
+    /// Reads the clocks, at the midpoint of the counters read around them.
+    fn now() -> Self {
+        let before = rdtsc();
+        let (mono_ns, wall_ns) = (system_monotonic_ns(), system_now_ns());
+        let after = rdtsc();
+        Self { tsc: before + after.saturating_sub(before) / 2, wall_ns, mono_ns }
+    }
+}
+
+/// The scale of the counter to nanoseconds, from an anchor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Calibration {
+    /// The reading the times are extrapolated from.
+    base: Anchor,
+    /// Nanoseconds per tick, in fixed point of `SCALE_SHIFT` fractional bits.
+    mult: u64,
+}
+
+impl Calibration {
+    /// Returns the calibration of the rate between two readings, anchored at
+    /// the last, `None` if the counter or the clock didn't advance.
+    fn measure(from: Anchor, to: Anchor) -> Option<Self> {
+        let ticks = to.tsc.checked_sub(from.tsc).filter(|&ticks| ticks > 0)?;
+        let ns = to.mono_ns.checked_sub(from.mono_ns).filter(|&ns| ns > 0)?;
+        let mult = ((ns as u128) << SCALE_SHIFT) / ticks as u128;
+        Some(Self { base: to, mult: mult as u64 })
+    }
+
+    /// Returns the nanoseconds from the anchor to the counter `tsc`, zero
+    /// before the anchor (a counter read before a concurrent re-sync).
+    ///
+    /// LATENCY: FAST_PATH
+    #[inline]
+    fn elapsed_ns(&self, tsc: u64) -> u64 {
+        ((tsc.saturating_sub(self.base.tsc) as u128 * self.mult as u128) >> SCALE_SHIFT) as u64
+    }
+
+    /// Returns the ticks of the counter in `ns` nanoseconds.
+    fn ticks(&self, ns: u64) -> u64 {
+        (((ns as u128) << SCALE_SHIFT) / self.mult.max(1) as u128) as u64
+    }
+
+    /// Returns the wall-clock time at the counter `tsc`.
+    #[inline]
+    fn wall_ns(&self, tsc: u64) -> u64 {
+        self.base.wall_ns + self.elapsed_ns(tsc)
+    }
+
+    /// Returns the monotonic time at the counter `tsc`.
+    #[inline]
+    fn monotonic_ns(&self, tsc: u64) -> u64 {
+        self.base.mono_ns + self.elapsed_ns(tsc)
+    }
+
+    /// Returns the calibration re-synced to the reading `now`, its rate
+    /// measured since the anchor.
+    fn resynced(&self, now: Anchor) -> Self {
+        let mult = Self::measure(self.base, now).map_or(self.mult, |measured| measured.mult);
+        // Never back, the monotonic time extrapolated up to now kept while ahead of the clock
+        let mono_ns = now.mono_ns.max(self.monotonic_ns(now.tsc));
+        Self { base: Anchor { mono_ns, ..now }, mult }
+    }
+}
+
+/// The calibration shared by the threads of the process, read under a
+/// sequence lock so a re-sync is never seen half-written.
+struct TscClock {
+    /// The state of the calibration.
+    state: AtomicU8,
+    /// The sequence of the writes, odd while one is in progress.
+    seq: AtomicU64,
+    base_tsc: AtomicU64,
+    base_wall_ns: AtomicU64,
+    base_mono_ns: AtomicU64,
+    mult: AtomicU64,
+}
+
+static CLOCK: TscClock = TscClock {
+    state: AtomicU8::new(UNCALIBRATED),
+    seq: AtomicU64::new(0),
+    base_tsc: AtomicU64::new(0),
+    base_wall_ns: AtomicU64::new(0),
+    base_mono_ns: AtomicU64::new(0),
+    mult: AtomicU64::new(0),
+};
+
+impl TscClock {
+    /// Reads the calibration, retrying while it is written.
+    ///
+    /// LATENCY: FAST_PATH
+    #[inline]
+    fn load(&self) -> Calibration {
+        loop {
+            let seq = self.seq.load(Ordering::Acquire);
+            if seq & 1 == 0 {
+                let calibration = Calibration {
+                    base: Anchor {
+                        tsc: self.base_tsc.load(Ordering::Relaxed),
+                        wall_ns: self.base_wall_ns.load(Ordering::Relaxed),
+                        mono_ns: self.base_mono_ns.load(Ordering::Relaxed),
+                    },
+                    mult: self.mult.load(Ordering::Relaxed),
+                };
+                fence(Ordering::Acquire);
+                if self.seq.load(Ordering::Relaxed) == seq {
+                    return calibration;
+                }
+            }
+            hint::spin_loop();
+        }
+    }
+
+    /// Writes the calibration, returning false if another write is in progress.
+    fn store(&self, calibration: &Calibration) -> bool {
+        let seq = self.seq.load(Ordering::Relaxed);
+        if seq & 1 != 0 || self.seq.compare_exchange(seq, seq + 1, Ordering::Acquire, Ordering::Relaxed).is_err() {
+            return false;
+        }
+        fence(Ordering::Release);
+        self.base_tsc.store(calibration.base.tsc, Ordering::Relaxed);
+        self.base_wall_ns.store(calibration.base.wall_ns, Ordering::Relaxed);
+        self.base_mono_ns.store(calibration.base.mono_ns, Ordering::Relaxed);
+        self.mult.store(calibration.mult, Ordering::Relaxed);
+        self.seq.store(seq + 2, Ordering::Release);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn anchor(tsc: u64, wall_ns: u64, mono_ns: u64) -> Anchor {
+        Anchor { tsc, wall_ns, mono_ns }
+    }
+
+    #[test]
+    fn test_calibration() {
+        // A 2GHz counter
+        let calibration = Calibration::measure(anchor(1_000, 0, 0), anchor(20_001_000, 5_000_000, 10_000_000)).unwrap();
+        assert_eq!(calibration.ticks(1_000_000_000), 2_000_000_000);
+        assert_eq!(calibration.elapsed_ns(20_001_000 + 2_000), 1_000);
+        assert_eq!(calibration.wall_ns(20_001_000 + 2_000_000), 6_000_000);
+        assert_eq!(calibration.monotonic_ns(20_001_000 + 2_000_000), 11_000_000);
+        // A counter read before the anchor
+        assert_eq!(calibration.monotonic_ns(0), 10_000_000);
+        assert!(Calibration::measure(anchor(1_000, 0, 0), anchor(1_000, 0, 10)).is_none());
+    }
+
+    #[test]
+    fn test_resync() {
+        let calibration = Calibration::measure(anchor(0, 0, 0), anchor(2_000, 1_000, 1_000)).unwrap();
+
+        // The counter ran faster than measured, its rate corrected and the monotonic time kept
+        let resynced = calibration.resynced(anchor(2_000_002_000, 1_000_000_000, 999_001_000));
+        assert_eq!(resynced.ticks(1_000_000_000), 2_002_002_002);
+        assert_eq!(resynced.base.mono_ns, 1_000_001_000);
+        assert_eq!(resynced.base.wall_ns, 1_000_000_000);
+
+        // Slower, the monotonic time caught up with the clock
+        let resynced = calibration.resynced(anchor(2_000_002_000, 1_000_000_000, 1_001_001_000));
+        assert_eq!(resynced.base.mono_ns, 1_001_001_000);
+        assert_eq!(resynced.ticks(1_000_000_000), 1_998_001_998);
+    }
+
+    #[test]
+    fn test_timestamps() {
+        calibrate();
+        let (before, now, after) = (system_now_ns(), now_ns(), system_now_ns());
+        // Within the error of the calibration
+        assert!(now + 1_000_000 >= before && now <= after + 1_000_000);
+        let mut last = monotonic_ns();
+        for _ in 0..10_000 {
+            let now = monotonic_ns();
+            assert!(now >= last);
+            last = now;
+        }
+        assert_eq!(tsc_hz().is_some(), clock_source() == ClockSource::Tsc);
+    }
+
+    #[test]
+    fn test_store_load() {
+        let clock = TscClock {
+            state: AtomicU8::new(CALIBRATED),
+            seq: AtomicU64::new(0),
+            base_tsc: AtomicU64::new(0),
+            base_wall_ns: AtomicU64::new(0),
+            base_mono_ns: AtomicU64::new(0),
+            mult: AtomicU64::new(0),
+        };
+        let calibration = Calibration { base: anchor(1, 2, 3), mult: 4 << SCALE_SHIFT };
+        assert!(clock.store(&calibration));
+        assert_eq!(clock.load(), calibration);
+        assert_eq!(clock.seq.load(Ordering::Relaxed), 2);
+
+        // A write in progress elsewhere
+        clock.seq.store(3, Ordering::Relaxed);
+        assert!(!clock.store(&calibration));
+    }
+}
@@ -0,0 +1,137 @@
+//! Reassembly of the messages fragmented into continuation frames.
+//!
+//! A message may be sent as a first frame followed by continuation frames, the
+//! last one flagged final, e.g. the large depth snapshots. The frames are
+//! appended to a single buffer, the message being polled once complete. A
+//! message growing past the maximum size is dropped and reported once, as a
+//! `WebsocketConnectorError::MessageTooLarge`, its remaining frames discarded.
+
+use crate::WebsocketConnectorError;
+
+/// Default maximum size of a reassembled message, in bytes.
+pub const DEFAULT_MAX_MESSAGE_SIZE: usize = 1 << 20;
+
+/// Reassembles the frames of the messages of a connection.
+#[derive(Debug, Clone)]
+pub struct MessageAssembler {
+    /// The frames of the message received so far, or the complete message.
+    buffer: Vec<u8>,
+    /// Maximum size of a message, in bytes.
+    max_message_size: usize,
+    /// Whether the buffer holds a complete message, cleared by the next frame.
+    complete: bool,
+    /// Whether the frames are discarded until the final one of an oversized message.
+    discarding: bool,
+}
+
+impl Default for MessageAssembler {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_MESSAGE_SIZE)
+    }
+}
+
+impl MessageAssembler {
+    /// Creates an assembler of messages of up to `max_message_size` bytes.
+    pub fn new(max_message_size: usize) -> Self {
+        Self { buffer: Vec::with_capacity(4096), max_message_size, complete: false, discarding: false }
+    }
+
+    /// Returns the maximum size of a message, in bytes.
+    pub fn max_message_size(&self) -> usize {
+        self.max_message_size
+    }
+
+    /// Sets the maximum size of the messages, in bytes.
+    pub fn set_max_message_size(&mut self, max_message_size: usize) {
+        self.max_message_size = max_message_size;
+    }
+
+    /// Appends a frame, returning true once it completes the message, held by `message`.
+    ///
+    /// # Errors
+    /// Returns `MessageTooLarge` when the frame grows the message past the
+    /// maximum size, the message being dropped.
+    ///
+    /// LATENCY: FAST_PATH
+    pub fn push(&mut self, frame: &[u8], is_final: bool) -> Result<bool, WebsocketConnectorError> {
+        if self.complete {
+            self.buffer.clear();
+            self.complete = false;
+        }
+        if self.discarding {
+            self.discarding = !is_final;
+            return Ok(false);
+        }
+        let size = self.buffer.len() + frame.len();
+        if size > self.max_message_size {
+            self.buffer.clear();
+            self.discarding = !is_final;
+            return Err(WebsocketConnectorError::MessageTooLarge { size, max: self.max_message_size });
+        }
+        self.buffer.extend_from_slice(frame);
+        self.complete = is_final;
+        Ok(is_final)
+    }
+
+    /// Returns the last complete message, or the frames received so far of the next one.
+    pub fn message(&self) -> &[u8] {
+        &self.buffer
+    }
+
+    /// Returns true if frames of an incomplete message were received.
+    pub fn is_partial(&self) -> bool {
+        !self.complete && (self.discarding || !self.buffer.is_empty())
+    }
+
+    /// Drops the incomplete message, e.g. when the connection is reopened.
+    pub fn reset(&mut self) {
+        self.buffer.clear();
+        self.complete = false;
+        self.discarding = false;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reassemble_frames() {
+        let mut assembler = MessageAssembler::new(16);
+        assert!(assembler.push(b"{}", true).unwrap());
+        assert_eq!(assembler.message(), b"{}");
+
+        assert!(!assembler.push(b"{\"a\":", false).unwrap());
+        assert!(assembler.is_partial());
+        assert!(!assembler.push(b"[1,", false).unwrap());
+        assert!(assembler.push(b"2]}", true).unwrap());
+        assert!(!assembler.is_partial());
+        assert_eq!(assembler.message(), br#"{"a":[1,2]}"#);
+
+        // A reopened connection starts a new message
+        assembler.push(b"{\"b\":", false).unwrap();
+        assembler.reset();
+        assert!(assembler.push(b"{}", true).unwrap());
+        assert_eq!(assembler.message(), b"{}");
+    }
+
+    #[test]
+    fn test_oversized_message_dropped() {
+        let mut assembler = MessageAssembler::new(8);
+        assembler.push(b"0123", false).unwrap();
+        let err = assembler.push(b"45678", false).unwrap_err();
+        assert!(matches!(err, WebsocketConnectorError::MessageTooLarge { size: 9, max: 8 }));
+
+        // The rest of the message is discarded, the next one received
+        assert!(!assembler.push(b"9", false).unwrap());
+        assert!(assembler.is_partial());
+        assert!(!assembler.push(b"}", true).unwrap());
+        assert!(assembler.push(b"{}", true).unwrap());
+        assert_eq!(assembler.message(), b"{}");
+
+        // As is a single oversized frame
+        assert!(assembler.push(b"012345678", true).is_err());
+        assert!(!assembler.is_partial());
+        assert!(assembler.push(b"{}", true).unwrap());
+    }
+}
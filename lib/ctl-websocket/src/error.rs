@@ -7,4 +7,8 @@ pub enum WebsocketConnectorError {
     WebsocketConnError(#[from] WebsocketConnError),
     #[error("websocket connector error: serde json error {0}")]
     SerdeError(#[from] serde_json::Error),
+    #[error("websocket connector error: cannot resolve {0}")]
+    ResolveError(String),
+    #[error("websocket connector error: message of {size} bytes exceeds the maximum of {max} bytes")]
+    MessageTooLarge { size: usize, max: usize },
 }
\ No newline at end of file
@@ -0,0 +1,339 @@
+//! Endpoint failover for websocket feeds.
+//!
+//! A feed may be configured with a primary and backup endpoints. The connection
+//! switches to the next endpoint (cycling back to the primary) after repeated
+//! failures or when no data was received for too long, and reports the switch
+//! through an `EndpointSwitch`. A switch may also be requested from another
+//! thread through a `FailoverTrigger`, e.g. when a single stream turns stale.
+//! With an `EndpointRanking`, the connection switches to the best-ranked other
+//! endpoint rather than to the next one.
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+use serde::Deserialize;
+
+use crate::EndpointRanking;
+
+/// Default number of consecutive failures before switching endpoints.
+const DEFAULT_MAX_FAILURES: u32 = 3;
+
+/// Default time without data before the endpoint is considered stale, in milliseconds.
+const DEFAULT_STALE_AFTER_MS: u64 = 10_000;
+
+fn default_max_failures() -> u32 {
+    DEFAULT_MAX_FAILURES
+}
+
+fn default_stale_after_ms() -> u64 {
+    DEFAULT_STALE_AFTER_MS
+}
+
+/// When a feed switches to its next endpoint.
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Eq, Hash)]
+pub struct FailoverPolicy {
+    /// Number of consecutive failures before switching endpoints.
+    #[serde(default = "default_max_failures")]
+    pub max_failures: u32,
+    /// Time without data before switching endpoints, in milliseconds (0 disables it).
+    #[serde(default = "default_stale_after_ms")]
+    pub stale_after_ms: u64,
+}
+
+impl Default for FailoverPolicy {
+    fn default() -> Self {
+        Self {
+            max_failures: DEFAULT_MAX_FAILURES,
+            stale_after_ms: DEFAULT_STALE_AFTER_MS,
+        }
+    }
+}
+
+impl FailoverPolicy {
+    /// Validates the policy configuration.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.max_failures == 0 {
+            return Err("failover 'max_failures' must be greater than 0".to_string());
+        }
+        Ok(())
+    }
+
+    /// Returns the staleness threshold, `None` if disabled.
+    pub fn stale_after(&self) -> Option<Duration> {
+        (self.stale_after_ms > 0).then(|| Duration::from_millis(self.stale_after_ms))
+    }
+}
+
+/// Why a feed switched endpoints.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SwitchReason {
+    /// The endpoint failed this many consecutive times.
+    Failures(u32),
+    /// No data was received from the endpoint for this long.
+    Stale(Duration),
+    /// The switch was requested through the `FailoverTrigger` of the feed.
+    Requested,
+}
+
+/// A handle requesting a feed to switch to its next endpoint, shared with the
+/// thread polling the feed.
+#[derive(Debug, Clone, Default)]
+pub struct FailoverTrigger {
+    /// Set until the feed switches.
+    requested: Arc<AtomicBool>,
+}
+
+impl FailoverTrigger {
+    /// Creates a trigger with no switch requested.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Requests the feed to switch on its next idle poll, returning false if a
+    /// switch was already requested.
+    pub fn request(&self) -> bool {
+        !self.requested.swap(true, Ordering::AcqRel)
+    }
+
+    /// Takes the requested switch, returning false if none was requested.
+    ///
+    /// LATENCY: FAST_PATH
+    pub fn take(&self) -> bool {
+        self.requested.load(Ordering::Relaxed) && self.requested.swap(false, Ordering::AcqRel)
+    }
+}
+
+/// A switch of a feed from one endpoint to another.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EndpointSwitch {
+    /// The feed that switched endpoints.
+    pub feed: String,
+    /// The endpoint switched from.
+    pub from: String,
+    /// The endpoint switched to.
+    pub to: String,
+    /// Why the feed switched endpoints.
+    pub reason: SwitchReason,
+}
+
+/// The endpoints of a feed and the health of the active one.
+#[derive(Debug, Clone)]
+pub struct EndpointRotation {
+    /// The endpoints, primary first.
+    endpoints: Vec<String>,
+    /// Index of the active endpoint.
+    active: usize,
+    /// The failover policy.
+    policy: FailoverPolicy,
+    /// Consecutive failures of the active endpoint.
+    failures: u32,
+    /// When data was last received from the active endpoint.
+    last_data: Instant,
+    /// The trigger of the switches requested by other threads.
+    trigger: FailoverTrigger,
+    /// The ranking of the endpoints by latency, if they are probed.
+    ranking: Option<EndpointRanking>,
+}
+
+impl EndpointRotation {
+    /// Creates a rotation starting at the primary (first) endpoint.
+    ///
+    /// # Panics
+    /// Panics if `endpoints` is empty.
+    pub fn new(endpoints: Vec<String>, policy: FailoverPolicy) -> Self {
+        assert!(!endpoints.is_empty(), "at least one endpoint is required");
+        Self {
+            endpoints,
+            active: 0,
+            policy,
+            failures: 0,
+            last_data: Instant::now(),
+            trigger: FailoverTrigger::new(),
+            ranking: None,
+        }
+    }
+
+    /// Returns the active endpoint.
+    pub fn active(&self) -> &str {
+        &self.endpoints[self.active]
+    }
+
+    /// Returns all endpoints, primary first.
+    pub fn endpoints(&self) -> &[String] {
+        &self.endpoints
+    }
+
+    /// Returns the consecutive failures of the active endpoint.
+    pub fn failures(&self) -> u32 {
+        self.failures
+    }
+
+    /// Records data received from the active endpoint.
+    ///
+    /// LATENCY: FAST_PATH
+    pub fn record_data(&mut self, now: Instant) {
+        self.failures = 0;
+        self.last_data = now;
+    }
+
+    /// Records a reconnection to the active endpoint, restarting its staleness
+    /// clock. Its failures are only reset by data.
+    pub fn record_reconnect(&mut self, now: Instant) {
+        self.last_data = now;
+    }
+
+    /// Records a failure of the active endpoint, returning the reason to switch
+    /// if the failure threshold is reached.
+    pub fn record_failure(&mut self) -> Option<SwitchReason> {
+        self.failures += 1;
+        (self.failures >= self.policy.max_failures).then_some(SwitchReason::Failures(self.failures))
+    }
+
+    /// Takes the switches requested through `trigger`, e.g. one kept across reconnections.
+    pub fn set_trigger(&mut self, trigger: FailoverTrigger) {
+        self.trigger = trigger;
+    }
+
+    /// Returns the trigger requesting a switch from another thread.
+    pub fn trigger(&self) -> &FailoverTrigger {
+        &self.trigger
+    }
+
+    /// Switches to the best-ranked other endpoint per `ranking`, rather than to the next one.
+    pub fn set_ranking(&mut self, ranking: EndpointRanking) {
+        self.ranking = Some(ranking);
+    }
+
+    /// Returns the reason to switch if a switch was requested, or no data was
+    /// received for too long.
+    pub fn check_stale(&self, now: Instant) -> Option<SwitchReason> {
+        if self.trigger.take() {
+            return Some(SwitchReason::Requested);
+        }
+        let stale_after = self.policy.stale_after()?;
+        let elapsed = now.saturating_duration_since(self.last_data);
+        (elapsed >= stale_after).then_some(SwitchReason::Stale(elapsed))
+    }
+
+    /// Advances to the next endpoint, or the best-ranked other one if ranked,
+    /// returning the endpoints switched from and to.
+    pub fn advance(&mut self, now: Instant) -> (String, String) {
+        let from = self.active().to_string();
+        let best = self.ranking.as_ref().and_then(|ranking| {
+            let ranked = ranking.rank(&self.endpoints);
+            let best = ranked.into_iter().find(|endpoint| *endpoint != from)?;
+            self.endpoints.iter().position(|endpoint| *endpoint == best)
+        });
+        self.active = best.unwrap_or((self.active + 1) % self.endpoints.len());
+        self.failures = 0;
+        self.last_data = now;
+        (from, self.active().to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::EndpointProbe;
+
+    fn rotation(policy: FailoverPolicy) -> EndpointRotation {
+        EndpointRotation::new(
+            vec![
+                "wss://stream.binance.com:9443/ws".to_string(),
+                "wss://stream.binance.com:443/ws".to_string(),
+            ],
+            policy,
+        )
+    }
+
+    #[test]
+    fn test_switch_after_repeated_failures() {
+        let mut rotation = rotation(FailoverPolicy { max_failures: 2, stale_after_ms: 0 });
+        assert_eq!(rotation.record_failure(), None);
+        assert_eq!(rotation.record_failure(), Some(SwitchReason::Failures(2)));
+
+        let (from, to) = rotation.advance(Instant::now());
+        assert_eq!(from, "wss://stream.binance.com:9443/ws");
+        assert_eq!(to, "wss://stream.binance.com:443/ws");
+
+        // Cycles back to the primary
+        let (_, to) = rotation.advance(Instant::now());
+        assert_eq!(to, "wss://stream.binance.com:9443/ws");
+    }
+
+    #[test]
+    fn test_data_resets_failures() {
+        let mut rotation = rotation(FailoverPolicy { max_failures: 2, stale_after_ms: 0 });
+        assert_eq!(rotation.record_failure(), None);
+        assert_eq!(rotation.failures(), 1);
+        rotation.record_data(Instant::now());
+        assert_eq!(rotation.failures(), 0);
+        assert_eq!(rotation.record_failure(), None);
+    }
+
+    #[test]
+    fn test_stale_endpoint() {
+        let rotation = rotation(FailoverPolicy { max_failures: 3, stale_after_ms: 100 });
+        let now = rotation.last_data;
+        assert_eq!(rotation.check_stale(now + Duration::from_millis(50)), None);
+        assert_eq!(
+            rotation.check_stale(now + Duration::from_millis(100)),
+            Some(SwitchReason::Stale(Duration::from_millis(100)))
+        );
+
+        let disabled = self::rotation(FailoverPolicy { max_failures: 3, stale_after_ms: 0 });
+        assert_eq!(disabled.check_stale(now + Duration::from_secs(60)), None);
+    }
+
+    #[test]
+    fn test_requested_switch() {
+        let mut rotation = rotation(FailoverPolicy { max_failures: 3, stale_after_ms: 0 });
+        let trigger = FailoverTrigger::new();
+        rotation.set_trigger(trigger.clone());
+        let now = rotation.last_data;
+        assert_eq!(rotation.check_stale(now), None);
+
+        assert!(trigger.request());
+        assert!(!trigger.request());
+        assert_eq!(rotation.check_stale(now), Some(SwitchReason::Requested));
+        assert_eq!(rotation.check_stale(now), None);
+    }
+
+    #[test]
+    fn test_switch_to_best_ranked() {
+        let mut rotation = EndpointRotation::new(
+            vec![
+                "wss://stream.binance.com:9443/ws".to_string(),
+                "wss://stream.binance.com:443/ws".to_string(),
+                "wss://data-stream.binance.vision/ws".to_string(),
+            ],
+            FailoverPolicy::default(),
+        );
+        let ranking = EndpointRanking::new();
+        rotation.set_ranking(ranking.clone());
+        // Nothing probed, to the next one
+        let (_, to) = rotation.advance(Instant::now());
+        assert_eq!(to, "wss://stream.binance.com:443/ws");
+
+        for (endpoint, ms) in [("wss://stream.binance.com:9443/ws", 10), ("wss://data-stream.binance.vision/ws", 5)] {
+            let mut probe = EndpointProbe::new(endpoint);
+            probe.tcp_connect = Some(Duration::from_millis(ms));
+            probe.first_message = Some(Duration::from_millis(ms));
+            ranking.record(probe);
+        }
+        let (_, to) = rotation.advance(Instant::now());
+        assert_eq!(to, "wss://data-stream.binance.vision/ws");
+        // The best-ranked other than the active one
+        let (_, to) = rotation.advance(Instant::now());
+        assert_eq!(to, "wss://stream.binance.com:9443/ws");
+    }
+
+    #[test]
+    fn test_deserialize_policy_defaults() {
+        let policy: FailoverPolicy = serde_json::from_str(r#"{"max_failures":5}"#).unwrap();
+        assert_eq!(policy, FailoverPolicy { max_failures: 5, stale_after_ms: DEFAULT_STALE_AFTER_MS });
+        assert!(FailoverPolicy { max_failures: 0, stale_after_ms: 0 }.validate().is_err());
+    }
+}
@@ -0,0 +1,218 @@
+//! Subscription ledger of a connection.
+//!
+//! The ledger records the streams a connection believes it is subscribed to.
+//! It periodically checks that belief against the LIST_SUBSCRIPTIONS response of
+//! the server, since a server-side hiccup can drop subscriptions silently, the
+//! connection staying up without data for the lost streams. The missing streams
+//! are resubscribed, and every mismatch is reported through a `SubscriptionDrift`.
+//!
+//! A stream only becomes active once the server acknowledged its SUBSCRIBE
+//! request, or listed it in its subscriptions.
+
+use std::time::{Duration, Instant};
+
+use crate::WSRequestId;
+
+/// A mismatch between the ledger and the subscriptions listed by the server.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SubscriptionDrift {
+    /// The feed of the connection.
+    pub feed: String,
+    /// The endpoint of the connection.
+    pub endpoint: String,
+    /// The streams of the ledger not listed by the server, resubscribed.
+    pub missing: Vec<String>,
+    /// The streams listed by the server not in the ledger.
+    pub unexpected: Vec<String>,
+}
+
+/// The streams a connection is subscribed to, and its reconciliation schedule.
+#[derive(Debug, Clone)]
+pub struct SubscriptionLedger {
+    /// The subscribed stream names, in subscription order.
+    streams: Vec<String>,
+    /// The subscribed streams acknowledged by the server.
+    active: Vec<String>,
+    /// Interval between the reconciliations, `None` if disabled.
+    interval: Option<Duration>,
+    /// When the subscriptions were last listed.
+    last_check: Instant,
+    /// The LIST_SUBSCRIPTIONS request awaiting its response.
+    pending: Option<WSRequestId>,
+}
+
+impl SubscriptionLedger {
+    /// Creates an empty ledger, reconciled every `interval` if set.
+    pub fn new(interval: Option<Duration>, now: Instant) -> Self {
+        Self { streams: Vec::new(), active: Vec::new(), interval, last_check: now, pending: None }
+    }
+
+    /// Sets the interval between the reconciliations, `None` disables them.
+    pub fn set_interval(&mut self, interval: Option<Duration>) {
+        self.interval = interval;
+    }
+
+    /// Returns the subscribed stream names.
+    pub fn streams(&self) -> &[String] {
+        &self.streams
+    }
+
+    /// Returns the subscribed streams acknowledged by the server.
+    pub fn active(&self) -> &[String] {
+        &self.active
+    }
+
+    /// Returns true if the stream is subscribed and acknowledged by the server.
+    pub fn is_active(&self, name: &str) -> bool {
+        self.active.iter().any(|active| active == name)
+    }
+
+    /// Returns true if the stream is subscribed, acknowledged or not.
+    pub fn contains(&self, name: &str) -> bool {
+        self.streams.iter().any(|stream| stream == name)
+    }
+
+    /// Returns true if no stream is subscribed.
+    pub fn is_empty(&self) -> bool {
+        self.streams.is_empty()
+    }
+
+    /// Records subscribed streams.
+    pub fn subscribe(&mut self, names: &[String]) {
+        for name in names {
+            if !self.streams.contains(name) {
+                self.streams.push(name.clone());
+            }
+        }
+    }
+
+    /// Records unsubscribed streams.
+    pub fn unsubscribe(&mut self, names: &[String]) {
+        self.streams.retain(|name| !names.contains(name));
+        self.active.retain(|name| !names.contains(name));
+    }
+
+    /// Marks subscribed streams as acknowledged by the server, ignoring the
+    /// streams unsubscribed since.
+    pub fn activate(&mut self, names: &[String]) {
+        for name in names {
+            if self.contains(name) && !self.is_active(name) {
+                self.active.push(name.clone());
+            }
+        }
+    }
+
+    /// Marks every stream as unacknowledged, e.g. when resubscribed on another endpoint.
+    pub fn deactivate_all(&mut self) {
+        self.active.clear();
+    }
+
+    /// Returns true if the subscriptions are due to be listed at `now`.
+    pub fn is_due(&self, now: Instant) -> bool {
+        match self.interval {
+            Some(interval) => {
+                self.pending.is_none() && !self.streams.is_empty() && now.duration_since(self.last_check) >= interval
+            }
+            None => false,
+        }
+    }
+
+    /// Records the LIST_SUBSCRIPTIONS request issued at `now`.
+    pub fn on_list_request(&mut self, id: WSRequestId, now: Instant) {
+        self.pending = Some(id);
+        self.last_check = now;
+    }
+
+    /// Returns true if `id` is the pending LIST_SUBSCRIPTIONS request.
+    pub fn is_list_request(&self, id: &WSRequestId) -> bool {
+        self.pending.as_ref() == Some(id)
+    }
+
+    /// Drops the pending LIST_SUBSCRIPTIONS request, e.g. when its response won't arrive.
+    pub fn clear_pending(&mut self) {
+        self.pending = None;
+    }
+
+    /// Reconciles the ledger with the streams listed by the server, ending the
+    /// pending request. The listed streams become active, the missing ones
+    /// inactive. Returns the missing and unexpected streams.
+    pub fn reconcile(&mut self, listed: &[String]) -> (Vec<String>, Vec<String>) {
+        self.pending = None;
+        self.active.retain(|name| listed.contains(name));
+        self.activate(listed);
+        let missing = self.streams.iter().filter(|name| !listed.contains(name)).cloned().collect();
+        let unexpected = listed.iter().filter(|name| !self.streams.contains(name)).cloned().collect();
+        (missing, unexpected)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn test_reconcile() {
+        let mut ledger = SubscriptionLedger::new(None, Instant::now());
+        ledger.subscribe(&names(&["btcusdt@bookTicker", "ethusdt@bookTicker", "btcusdt@bookTicker"]));
+        ledger.subscribe(&names(&["solusdt@bookTicker"]));
+        ledger.unsubscribe(&names(&["solusdt@bookTicker"]));
+        assert_eq!(ledger.streams(), names(&["btcusdt@bookTicker", "ethusdt@bookTicker"]).as_slice());
+
+        let (missing, unexpected) = ledger.reconcile(&names(&["btcusdt@bookTicker", "adausdt@bookTicker"]));
+        assert_eq!(missing, names(&["ethusdt@bookTicker"]));
+        assert_eq!(unexpected, names(&["adausdt@bookTicker"]));
+        assert_eq!(ledger.active(), names(&["btcusdt@bookTicker"]).as_slice());
+
+        let (missing, unexpected) = ledger.reconcile(&names(&["ethusdt@bookTicker", "btcusdt@bookTicker"]));
+        assert!(missing.is_empty() && unexpected.is_empty());
+    }
+
+    #[test]
+    fn test_activate_on_acknowledgement() {
+        let mut ledger = SubscriptionLedger::new(None, Instant::now());
+        ledger.subscribe(&names(&["btcusdt@trade", "ethusdt@trade"]));
+        assert!(ledger.active().is_empty());
+
+        ledger.activate(&names(&["btcusdt@trade", "solusdt@trade"]));
+        assert!(ledger.is_active("btcusdt@trade"));
+        assert!(!ledger.is_active("ethusdt@trade"));
+        // Streams outside the ledger never become active
+        assert!(!ledger.is_active("solusdt@trade"));
+
+        ledger.unsubscribe(&names(&["btcusdt@trade"]));
+        assert!(ledger.active().is_empty());
+        ledger.activate(&names(&["ethusdt@trade"]));
+        ledger.deactivate_all();
+        assert!(ledger.active().is_empty());
+        assert!(ledger.contains("ethusdt@trade"));
+    }
+
+    #[test]
+    fn test_schedule() {
+        let start = Instant::now();
+        let interval = Duration::from_secs(30);
+        let mut ledger = SubscriptionLedger::new(Some(interval), start);
+        // Nothing to reconcile without subscriptions
+        assert!(!ledger.is_due(start + interval));
+
+        ledger.subscribe(&names(&["btcusdt@trade"]));
+        assert!(!ledger.is_due(start));
+        assert!(ledger.is_due(start + interval));
+
+        let id = WSRequestId::Int(7);
+        ledger.on_list_request(id.clone(), start + interval);
+        assert!(ledger.is_list_request(&id));
+        assert!(!ledger.is_due(start + interval * 3));
+
+        ledger.reconcile(&names(&["btcusdt@trade"]));
+        assert!(!ledger.is_list_request(&id));
+        assert!(ledger.is_due(start + interval * 2));
+
+        ledger.set_interval(None);
+        assert!(!ledger.is_due(start + interval * 3));
+    }
+}
@@ -1,9 +1,34 @@
 mod websocket;
 mod requests;
 mod error;
+mod stream;
+mod protocol;
+mod failover;
+mod reconnect;
+mod assembler;
+mod stats;
+mod ledger;
+mod retry;
+mod resolve;
+mod probe;
+#[cfg(test)]
+mod mock;
 
-pub use websocket::WSConn;
+pub use websocket::{WSConn, StreamsUpdate, RequestAck};
 pub use requests::{
-    WSRequest, WSRequestKind, WSRequestId, WSRequestError, RequestIdString
+    WSRequest, WSRequestKind, WSRequestId, WSRequestError, RequestIdString,
+    WSResponse, WSAck, WSApiError, CancelReplaceMode, CancelReplaceParams, CancelReplaceResult,
+    CancelReplaceStatus,
 };
-pub use error::WebsocketConnectorError;
\ No newline at end of file
+pub use error::WebsocketConnectorError;
+pub use stream::{UpdateSpeed, has_update_speed, stream_name};
+pub use protocol::StreamSuffix;
+pub use failover::{FailoverPolicy, FailoverTrigger, EndpointRotation, EndpointSwitch, SwitchReason};
+pub use reconnect::ReconnectPolicy;
+pub use assembler::{MessageAssembler, DEFAULT_MAX_MESSAGE_SIZE};
+pub use stats::{ConnectionStats, ConnectionStatsHandle};
+pub use ledger::{SubscriptionDrift, SubscriptionLedger};
+pub use retry::{RetryQueue, ScheduledRetry};
+pub use resolve::{AddressHealth, AddressPolicy, AddressPool};
+pub use probe::{EndpointProbe, EndpointProber, EndpointRanking, ProbeConfig, PROBE_ENDPOINTS, probe_endpoint};
+pub use ctl_retry::RetryPolicy;
\ No newline at end of file
@@ -0,0 +1,196 @@
+//! A local websocket server scripted with canned Binance frames, for the
+//! integration tests of `WSConn`.
+//!
+//! Each accepted connection plays the next script of the server, step by step:
+//! sending stream frames, answering the requests of the client with
+//! acknowledgements, errors or subscription lists, and disconnecting. Once its
+//! script is played, a connection stays open, recording the requests of the
+//! client without answering them, until the server is dropped.
+
+use std::collections::VecDeque;
+use std::io::ErrorKind;
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use serde_json::Value;
+use tungstenite::protocol::frame::coding::{Data, OpCode};
+use tungstenite::protocol::frame::Frame;
+use tungstenite::{Message, WebSocket};
+
+use crate::{WSRequest, WSResponse};
+
+/// Read timeout of the server sockets, bounding the shutdown of the server.
+const READ_TIMEOUT: Duration = Duration::from_millis(10);
+
+/// A step of the script of a connection.
+#[derive(Debug, Clone)]
+pub(crate) enum MockStep {
+    /// Sends a text frame, e.g. a trade payload.
+    Send(String),
+    /// Sends a text message fragmented into a first frame and continuation
+    /// frames, one per part, e.g. a large depth snapshot.
+    SendFragments(Vec<String>),
+    /// Answers the next request with a success (`"result": null`).
+    Ack,
+    /// Answers the next request with an error.
+    Reject { code: i64, msg: String },
+    /// Answers the next request, a LIST_SUBSCRIPTIONS, with the streams.
+    List(Vec<String>),
+    /// Drops the connection without a close frame.
+    Disconnect,
+}
+
+/// A scripted websocket server on a local port.
+pub(crate) struct MockServer {
+    /// The URL of the server.
+    url: String,
+    /// The requests received, by connection index.
+    received: Arc<Mutex<Vec<(usize, WSRequest)>>>,
+    /// Set when the server is dropped.
+    stop: Arc<AtomicBool>,
+    /// The accepting thread.
+    handle: Option<JoinHandle<()>>,
+}
+
+impl MockServer {
+    /// Starts a server playing `scripts`, one per accepted connection.
+    /// The connections accepted once the scripts are exhausted are refused.
+    pub(crate) fn start(scripts: Vec<Vec<MockStep>>) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        listener.set_nonblocking(true).unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let stop = Arc::new(AtomicBool::new(false));
+
+        let handle = {
+            let received = received.clone();
+            let stop = stop.clone();
+            thread::spawn(move || accept(listener, scripts.into(), received, stop))
+        };
+        Self { url, received, stop, handle: Some(handle) }
+    }
+
+    /// Returns the URL of the server.
+    pub(crate) fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Returns the requests received, by connection index.
+    pub(crate) fn received(&self) -> Vec<(usize, WSRequest)> {
+        self.received.lock().unwrap().clone()
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+/// Accepts the connections, each playing its script on its own thread.
+fn accept(
+    listener: TcpListener,
+    mut scripts: VecDeque<Vec<MockStep>>,
+    received: Arc<Mutex<Vec<(usize, WSRequest)>>>,
+    stop: Arc<AtomicBool>,
+) {
+    let mut connections = Vec::new();
+    let mut index = 0;
+    while !stop.load(Ordering::Relaxed) {
+        match listener.accept() {
+            Ok((stream, _)) => {
+                let Some(script) = scripts.pop_front() else {
+                    // Refused, no script left
+                    continue;
+                };
+                let received = received.clone();
+                let stop = stop.clone();
+                connections.push(thread::spawn(move || play(stream, index, script, received, stop)));
+                index += 1;
+            }
+            Err(e) if e.kind() == ErrorKind::WouldBlock => thread::sleep(READ_TIMEOUT),
+            Err(_) => break,
+        }
+    }
+    for connection in connections {
+        let _ = connection.join();
+    }
+}
+
+/// Plays the script of a connection, then records its requests until stopped.
+fn play(
+    stream: TcpStream,
+    index: usize,
+    script: Vec<MockStep>,
+    received: Arc<Mutex<Vec<(usize, WSRequest)>>>,
+    stop: Arc<AtomicBool>,
+) {
+    stream.set_nonblocking(false).unwrap();
+    let Ok(mut websocket) = tungstenite::accept(stream) else {
+        return;
+    };
+    websocket.get_ref().set_read_timeout(Some(READ_TIMEOUT)).unwrap();
+
+    let mut next_request = |websocket: &mut WebSocket<TcpStream>| -> Option<WSRequest> {
+        let request = read_request(websocket, &stop)?;
+        received.lock().unwrap().push((index, request.clone()));
+        Some(request)
+    };
+
+    for step in script {
+        let reply = match step {
+            MockStep::Send(text) => {
+                if websocket.send(Message::text(text)).is_err() {
+                    return;
+                }
+                continue;
+            }
+            MockStep::SendFragments(parts) => {
+                let last = parts.len().saturating_sub(1);
+                for (i, part) in parts.into_iter().enumerate() {
+                    let opcode = OpCode::Data(if i == 0 { Data::Text } else { Data::Continue });
+                    if websocket.send(Message::Frame(Frame::message(part, opcode, i == last))).is_err() {
+                        return;
+                    }
+                }
+                continue;
+            }
+            MockStep::Disconnect => return,
+            reply => reply,
+        };
+        let Some(request) = next_request(&mut websocket) else {
+            return;
+        };
+        let id = request.id;
+        let response = match reply {
+            MockStep::Reject { code, msg } => WSResponse::Error { code, msg, id },
+            MockStep::List(streams) => WSResponse::Result { result: Value::from(streams), id },
+            _ => WSResponse::Result { result: Value::Null, id },
+        };
+        if websocket.send(Message::text(serde_json::to_string(&response).unwrap())).is_err() {
+            return;
+        }
+    }
+
+    while next_request(&mut websocket).is_some() {}
+}
+
+/// Reads the next request of the client, `None` once the connection closed or the server stopped.
+fn read_request(websocket: &mut WebSocket<TcpStream>, stop: &AtomicBool) -> Option<WSRequest> {
+    while !stop.load(Ordering::Relaxed) {
+        match websocket.read() {
+            Ok(Message::Text(text)) => return serde_json::from_str(text.as_str()).ok(),
+            Ok(Message::Binary(data)) => return serde_json::from_slice(&data).ok(),
+            Ok(_) => {}
+            Err(tungstenite::Error::Io(e)) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {}
+            Err(_) => return None,
+        }
+    }
+    None
+}
@@ -0,0 +1,378 @@
+//! Latency probing and ranking of the candidate endpoints.
+//!
+//! The market data is served from several endpoints, `stream.binance.com` on
+//! ports 9443 and 443 and `data-stream.binance.vision`, whose latency from a
+//! given host differs and changes over the day. An `EndpointProber` measures,
+//! for each candidate endpoint, the TCP connect, the TLS handshake and the time
+//! from subscribing to a stream to its first message, and records the results
+//! into an `EndpointRanking` shared with the feeds. The feeds are created on
+//! the best-ranked of their endpoints and fail over to the best-ranked other
+//! one.
+//!
+//! An endpoint is ranked by the sum of its three latencies. An endpoint not yet
+//! probed is ranked after those probed, and one whose probe failed last.
+
+use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use atx_feed::{FeedKind, FeedPoll, FeedProtocolOps};
+use hashbrown::HashMap;
+use native_tls::TlsConnector;
+use serde::Deserialize;
+
+use crate::resolve::host_port;
+use crate::{WSConn, WSRequestKind};
+
+/// The candidate endpoints probed by default.
+pub const PROBE_ENDPOINTS: [&str; 3] = [
+    "wss://stream.binance.com:9443/ws",
+    "wss://stream.binance.com:443/ws",
+    "wss://data-stream.binance.vision/ws",
+];
+
+/// Default stream subscribed to, to time the first message.
+const DEFAULT_STREAM: &str = "btcusdt@bookTicker";
+
+/// Default interval between the probes of the endpoints, in milliseconds.
+const DEFAULT_INTERVAL_MS: u64 = 60_000;
+
+/// Default time allowed to each phase of a probe, in milliseconds.
+const DEFAULT_TIMEOUT_MS: u64 = 5_000;
+
+/// Interval between the polls awaiting the first message.
+const POLL_INTERVAL: Duration = Duration::from_micros(100);
+
+fn default_endpoints() -> Vec<String> {
+    PROBE_ENDPOINTS.iter().map(|endpoint| endpoint.to_string()).collect()
+}
+
+fn default_stream() -> String {
+    DEFAULT_STREAM.to_string()
+}
+
+fn default_interval_ms() -> u64 {
+    DEFAULT_INTERVAL_MS
+}
+
+fn default_timeout_ms() -> u64 {
+    DEFAULT_TIMEOUT_MS
+}
+
+/// Which endpoints are probed, and how often.
+#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+pub struct ProbeConfig {
+    /// The candidate endpoints, also those of the feeds configured without endpoints.
+    #[serde(default = "default_endpoints")]
+    pub endpoints: Vec<String>,
+    /// The stream subscribed to, to time the first message.
+    #[serde(default = "default_stream")]
+    pub stream: String,
+    /// Interval between the probes of the endpoints, in milliseconds.
+    #[serde(default = "default_interval_ms")]
+    pub interval_ms: u64,
+    /// Time allowed to each phase of a probe, in milliseconds.
+    #[serde(default = "default_timeout_ms")]
+    pub timeout_ms: u64,
+}
+
+impl Default for ProbeConfig {
+    fn default() -> Self {
+        Self {
+            endpoints: default_endpoints(),
+            stream: default_stream(),
+            interval_ms: DEFAULT_INTERVAL_MS,
+            timeout_ms: DEFAULT_TIMEOUT_MS,
+        }
+    }
+}
+
+impl ProbeConfig {
+    /// Validates the probe configuration.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.endpoints.is_empty() {
+            return Err("probe 'endpoints' must not be empty".to_string());
+        }
+        if let Some(endpoint) = self.endpoints.iter().find(|endpoint| host_port(endpoint).is_none()) {
+            return Err(format!("probe endpoint '{}' is not a websocket URL", endpoint));
+        }
+        if self.stream.is_empty() {
+            return Err("probe 'stream' must not be empty".to_string());
+        }
+        if self.interval_ms == 0 {
+            return Err("probe 'interval_ms' must be greater than 0".to_string());
+        }
+        if self.timeout_ms == 0 {
+            return Err("probe 'timeout_ms' must be greater than 0".to_string());
+        }
+        Ok(())
+    }
+
+    /// Returns the interval between the probes of the endpoints.
+    pub fn interval(&self) -> Duration {
+        Duration::from_millis(self.interval_ms)
+    }
+
+    /// Returns the time allowed to each phase of a probe.
+    pub fn timeout(&self) -> Duration {
+        Duration::from_millis(self.timeout_ms)
+    }
+}
+
+/// The latencies measured by a probe of an endpoint.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EndpointProbe {
+    /// The endpoint probed.
+    pub endpoint: String,
+    /// Time to open the TCP connection, `None` if it didn't open.
+    pub tcp_connect: Option<Duration>,
+    /// Time of the TLS handshake, `None` if it failed or the endpoint isn't `wss://`.
+    pub tls_handshake: Option<Duration>,
+    /// Time from subscribing to the stream to its first message, `None` if none came.
+    pub first_message: Option<Duration>,
+    /// Why the probe failed, if it did.
+    pub error: Option<String>,
+}
+
+impl EndpointProbe {
+    /// Creates a probe of `endpoint` with nothing measured.
+    pub fn new(endpoint: &str) -> Self {
+        Self {
+            endpoint: endpoint.to_string(),
+            tcp_connect: None,
+            tls_handshake: None,
+            first_message: None,
+            error: None,
+        }
+    }
+
+    /// Returns the latency the endpoint is ranked by, `None` if the probe failed.
+    pub fn latency(&self) -> Option<Duration> {
+        if self.error.is_some() {
+            return None;
+        }
+        Some(self.tcp_connect? + self.tls_handshake.unwrap_or_default() + self.first_message?)
+    }
+
+    /// Returns the key the endpoint is ranked by, the lowest first.
+    fn rank_key(probe: Option<&Self>) -> (u8, Duration) {
+        match probe.map(Self::latency) {
+            Some(Some(latency)) => (0, latency),
+            None => (1, Duration::ZERO),
+            Some(None) => (2, Duration::ZERO),
+        }
+    }
+}
+
+/// The feed kind of the connections of the probes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct Probe;
+
+impl FeedKind for Probe {}
+
+/// Probes `endpoint`: its TCP connect, TLS handshake and the first message of
+/// `stream`, each phase within `timeout`.
+///
+/// LATENCY: SLOW_PATH
+pub fn probe_endpoint(endpoint: &str, stream: &str, timeout: Duration) -> EndpointProbe {
+    let mut probe = EndpointProbe::new(endpoint);
+    if let Err(e) = measure(&mut probe, stream, timeout) {
+        probe.error = Some(e);
+    }
+    probe
+}
+
+/// Measures the phases of `probe` in turn, up to the first failing.
+fn measure(probe: &mut EndpointProbe, stream: &str, timeout: Duration) -> Result<(), String> {
+    let (host, port) = host_port(&probe.endpoint).ok_or_else(|| "not a websocket URL".to_string())?;
+    let addr = (host.as_str(), port)
+        .to_socket_addrs()
+        .map_err(|e| format!("cannot resolve {}: {}", host, e))?
+        .next()
+        .ok_or_else(|| format!("cannot resolve {}", host))?;
+
+    let start = Instant::now();
+    let tcp = TcpStream::connect_timeout(&addr, timeout).map_err(|e| format!("TCP connect: {}", e))?;
+    probe.tcp_connect = Some(start.elapsed());
+
+    if probe.endpoint.starts_with("wss://") {
+        tcp.set_read_timeout(Some(timeout)).map_err(|e| e.to_string())?;
+        tcp.set_write_timeout(Some(timeout)).map_err(|e| e.to_string())?;
+        let connector = TlsConnector::new().map_err(|e| format!("TLS: {}", e))?;
+        let start = Instant::now();
+        connector.connect(&host, tcp).map_err(|e| format!("TLS handshake: {}", e))?;
+        probe.tls_handshake = Some(start.elapsed());
+    } else {
+        drop(tcp);
+    }
+
+    let mut conn = WSConn::<Probe>::new(&probe.endpoint).map_err(|e| e.to_string())?;
+    let start = Instant::now();
+    conn.send_request(WSRequestKind::Subscribe(vec![stream.to_string()])).map_err(|e| e.to_string())?;
+    while start.elapsed() < timeout {
+        if let FeedPoll::Data(_) = conn.poll().map_err(|e| e.to_string())? {
+            probe.first_message = Some(start.elapsed());
+            return Ok(());
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+    Err(format!("no message within {:?}", timeout))
+}
+
+/// The last probes of the endpoints, shared between the prober and the feeds.
+#[derive(Debug, Clone, Default)]
+pub struct EndpointRanking {
+    /// The last probe of each endpoint probed.
+    probes: Arc<Mutex<HashMap<String, EndpointProbe>>>,
+}
+
+impl EndpointRanking {
+    /// Creates a ranking with no endpoint probed.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records the last probe of its endpoint.
+    pub fn record(&self, probe: EndpointProbe) {
+        self.probes.lock().unwrap_or_else(PoisonError::into_inner).insert(probe.endpoint.clone(), probe);
+    }
+
+    /// Returns the last probe of `endpoint`, if it was probed.
+    pub fn probe(&self, endpoint: &str) -> Option<EndpointProbe> {
+        self.probes.lock().unwrap_or_else(PoisonError::into_inner).get(endpoint).cloned()
+    }
+
+    /// Returns the last probes of the endpoints, the best-ranked first.
+    pub fn probes(&self) -> Vec<EndpointProbe> {
+        let mut probes: Vec<EndpointProbe> =
+            self.probes.lock().unwrap_or_else(PoisonError::into_inner).values().cloned().collect();
+        probes.sort_by(|a, b| {
+            (EndpointProbe::rank_key(Some(a)), &a.endpoint).cmp(&(EndpointProbe::rank_key(Some(b)), &b.endpoint))
+        });
+        probes
+    }
+
+    /// Returns `endpoints` the best-ranked first, those ranked alike in their given order.
+    pub fn rank(&self, endpoints: &[String]) -> Vec<String> {
+        let probes = self.probes.lock().unwrap_or_else(PoisonError::into_inner);
+        let mut ranked = endpoints.to_vec();
+        ranked.sort_by_key(|endpoint| EndpointProbe::rank_key(probes.get(endpoint)));
+        ranked
+    }
+}
+
+/// Probes the candidate endpoints every interval, recording the results into a ranking.
+#[derive(Debug, Clone)]
+pub struct EndpointProber {
+    /// Which endpoints are probed, and how often.
+    config: ProbeConfig,
+    /// The ranking the probes are recorded into.
+    ranking: EndpointRanking,
+}
+
+impl EndpointProber {
+    /// Creates a prober of the endpoints of `config`, recording into `ranking`.
+    pub fn new(config: ProbeConfig, ranking: EndpointRanking) -> Self {
+        Self { config, ranking }
+    }
+
+    /// Returns the ranking the probes are recorded into.
+    pub fn ranking(&self) -> &EndpointRanking {
+        &self.ranking
+    }
+
+    /// Probes all the endpoints concurrently, recording the results.
+    ///
+    /// LATENCY: SLOW_PATH
+    pub fn probe_all(&self) {
+        let timeout = self.config.timeout();
+        thread::scope(|scope| {
+            for endpoint in &self.config.endpoints {
+                scope.spawn(move || self.ranking.record(probe_endpoint(endpoint, &self.config.stream, timeout)));
+            }
+        });
+    }
+
+    /// Spawns the thread probing the endpoints every interval, the first
+    /// interval after the probes of the first run.
+    ///
+    /// # Errors
+    /// Returns an error if the thread cannot be spawned.
+    pub fn spawn(self) -> std::io::Result<JoinHandle<()>> {
+        thread::Builder::new().name("endpoint-prober".to_string()).spawn(move || {
+            loop {
+                thread::sleep(self.config.interval());
+                self.probe_all();
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::mock::{MockServer, MockStep};
+
+    const TICKER: &str = r#"{"u":1,"s":"BTCUSDT","b":"60000.00","B":"1.0","a":"60000.01","A":"1.0"}"#;
+
+    fn probe(endpoint: &str, total_ms: Option<u64>) -> EndpointProbe {
+        let mut probe = EndpointProbe::new(endpoint);
+        match total_ms {
+            Some(ms) => {
+                probe.tcp_connect = Some(Duration::from_millis(ms / 2));
+                probe.first_message = Some(Duration::from_millis(ms - ms / 2));
+            }
+            None => probe.error = Some("TCP connect: refused".to_string()),
+        }
+        probe
+    }
+
+    #[test]
+    fn test_probe_mock_endpoint() {
+        // The bare TCP connect takes the first connection, the websocket the second
+        let server = MockServer::start(vec![vec![], vec![MockStep::Ack, MockStep::Send(TICKER.to_string())]]);
+        let probe = probe_endpoint(server.url(), DEFAULT_STREAM, Duration::from_secs(5));
+        assert_eq!(probe.error, None);
+        assert!(probe.tcp_connect.is_some());
+        assert_eq!(probe.tls_handshake, None);
+        assert!(probe.first_message.is_some());
+        assert!(probe.latency().is_some());
+
+        let probe = probe_endpoint("http://localhost", DEFAULT_STREAM, Duration::from_secs(5));
+        assert_eq!(probe.error.as_deref(), Some("not a websocket URL"));
+        assert_eq!(probe.latency(), None);
+    }
+
+    #[test]
+    fn test_rank_endpoints() {
+        let endpoints = default_endpoints();
+        let ranking = EndpointRanking::new();
+        // Nothing probed, in the given order
+        assert_eq!(ranking.rank(&endpoints), endpoints);
+
+        ranking.record(probe(&endpoints[0], None));
+        ranking.record(probe(&endpoints[2], Some(20)));
+        let order = |indices: [usize; 3]| indices.map(|index| endpoints[index].clone()).to_vec();
+        assert_eq!(ranking.rank(&endpoints), order([2, 1, 0]));
+
+        ranking.record(probe(&endpoints[0], Some(10)));
+        ranking.record(probe(&endpoints[1], Some(30)));
+        assert_eq!(ranking.rank(&endpoints), order([0, 2, 1]));
+        let probed: Vec<String> = ranking.probes().into_iter().map(|probe| probe.endpoint).collect();
+        assert_eq!(probed, ranking.rank(&endpoints));
+    }
+
+    #[test]
+    fn test_deserialize_config_defaults() {
+        let config: ProbeConfig = serde_json::from_str(r#"{"interval_ms":30000}"#).unwrap();
+        assert_eq!(config, ProbeConfig { interval_ms: 30_000, ..ProbeConfig::default() });
+        assert!(config.validate().is_ok());
+        assert!(ProbeConfig { endpoints: vec![], ..ProbeConfig::default() }.validate().is_err());
+        assert!(ProbeConfig { endpoints: vec!["stream.binance.com".to_string()], ..ProbeConfig::default() }
+            .validate()
+            .is_err());
+        assert!(ProbeConfig { timeout_ms: 0, ..ProbeConfig::default() }.validate().is_err());
+    }
+}
@@ -0,0 +1,29 @@
+use atx_feed::{FeedKind, FeedProtocol, Streams};
+
+use crate::WSConn;
+
+/// The stream name suffix of a feed kind (e.g. `bookTicker` in `btcusdt@bookTicker`).
+///
+/// Implementing this for a feed kind provides the `FeedProtocol` implementation
+/// of `WSConn` for that kind.
+pub trait StreamSuffix: FeedKind {
+    /// The suffix appended to the lowercase symbol to form the stream name.
+    const SUFFIX: &'static str;
+}
+
+impl<K: StreamSuffix> FeedProtocol<K> for WSConn<K> {
+    /// Updates the subscribed streams for the feed kind.
+    ///
+    /// The UNSUBSCRIBE and SUBSCRIBE requests are issued as a single batch, their
+    /// request IDs kept as `WSConn::last_update`, as the trait returns none, and
+    /// their responses surfaced through `WSConn::poll_ack` or the
+    /// acknowledgement reporter. The streams become active once acknowledged,
+    /// the rejected requests being retried.
+    ///
+    /// LATENCY: SLOW_PATH
+    /// ERROR: FULLY_HANDLED
+    fn update(&mut self, streams: &Streams<K>) -> Result<(), Self::FeedProtocolError> {
+        self.update_streams(streams, K::SUFFIX)?;
+        Ok(())
+    }
+}
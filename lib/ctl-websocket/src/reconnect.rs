@@ -0,0 +1,117 @@
+//! Reconnection backoff of websocket feeds.
+//!
+//! A failed connection is reopened to the same endpoint after an exponentially
+//! growing backoff, until the failover policy's `max_failures` consecutive
+//! failures switch the feed to its next endpoint. The backoffs are jittered, so
+//! that the feeds dropped together (e.g. on a network outage) don't reconnect
+//! in lockstep. Testnet endpoints are usually retried more patiently than the
+//! production ones, hence a policy per feed.
+
+use std::time::Duration;
+
+use ctl_retry::RetryPolicy;
+use serde::Deserialize;
+
+const DEFAULT_INITIAL_BACKOFF_MS: u64 = 100;
+const DEFAULT_MAX_BACKOFF_MS: u64 = 5_000;
+const DEFAULT_MULTIPLIER: u32 = 2;
+const DEFAULT_JITTER_PCT: u32 = 20;
+
+fn default_initial_backoff_ms() -> u64 {
+    DEFAULT_INITIAL_BACKOFF_MS
+}
+
+fn default_max_backoff_ms() -> u64 {
+    DEFAULT_MAX_BACKOFF_MS
+}
+
+fn default_multiplier() -> u32 {
+    DEFAULT_MULTIPLIER
+}
+
+fn default_jitter_pct() -> u32 {
+    DEFAULT_JITTER_PCT
+}
+
+/// When a feed reconnects to its endpoint after a failure.
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Eq, Hash)]
+pub struct ReconnectPolicy {
+    /// Backoff before the first reconnection, in milliseconds.
+    #[serde(default = "default_initial_backoff_ms")]
+    pub initial_backoff_ms: u64,
+    /// Longest backoff before a reconnection, in milliseconds.
+    #[serde(default = "default_max_backoff_ms")]
+    pub max_backoff_ms: u64,
+    /// Factor the backoff grows by on each consecutive failure.
+    #[serde(default = "default_multiplier")]
+    pub multiplier: u32,
+    /// Share of the backoff randomized, in percent (0 disables it).
+    #[serde(default = "default_jitter_pct")]
+    pub jitter_pct: u32,
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        Self {
+            initial_backoff_ms: DEFAULT_INITIAL_BACKOFF_MS,
+            max_backoff_ms: DEFAULT_MAX_BACKOFF_MS,
+            multiplier: DEFAULT_MULTIPLIER,
+            jitter_pct: DEFAULT_JITTER_PCT,
+        }
+    }
+}
+
+impl ReconnectPolicy {
+    /// Validates the policy configuration.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.initial_backoff_ms == 0 || self.initial_backoff_ms > self.max_backoff_ms {
+            return Err(format!(
+                "reconnect policy must have 0 < 'initial_backoff_ms' <= 'max_backoff_ms', got {} and {}",
+                self.initial_backoff_ms, self.max_backoff_ms
+            ));
+        }
+        self.retry_policy().validate()
+    }
+
+    /// Returns the backoff policy of the reconnections, the failover policy
+    /// bounding the consecutive attempts.
+    pub fn retry_policy(&self) -> RetryPolicy {
+        RetryPolicy {
+            max_retries: u32::MAX,
+            initial_backoff_ms: self.initial_backoff_ms,
+            max_backoff_ms: self.max_backoff_ms,
+            multiplier: self.multiplier,
+            jitter_pct: self.jitter_pct,
+        }
+    }
+
+    /// Returns the backoff before a reconnection, by consecutive failure starting at 1, without jitter.
+    pub fn backoff(&self, failure: u32) -> Duration {
+        self.retry_policy().backoff(failure)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff() {
+        let policy = ReconnectPolicy { initial_backoff_ms: 100, max_backoff_ms: 1_000, multiplier: 3, jitter_pct: 0 };
+        assert_eq!(policy.backoff(1), Duration::from_millis(100));
+        assert_eq!(policy.backoff(2), Duration::from_millis(300));
+        assert_eq!(policy.backoff(3), Duration::from_millis(900));
+        assert_eq!(policy.backoff(u32::MAX), Duration::from_millis(1_000));
+        assert!(policy.validate().is_ok());
+        assert!(ReconnectPolicy { initial_backoff_ms: 0, ..policy }.validate().is_err());
+        assert!(ReconnectPolicy { max_backoff_ms: 50, ..policy }.validate().is_err());
+        assert!(ReconnectPolicy { multiplier: 0, ..policy }.validate().is_err());
+        assert!(ReconnectPolicy { jitter_pct: 101, ..policy }.validate().is_err());
+    }
+
+    #[test]
+    fn test_deserialize_policy_defaults() {
+        let policy: ReconnectPolicy = serde_json::from_str(r#"{"initial_backoff_ms":1000}"#).unwrap();
+        assert_eq!(policy, ReconnectPolicy { initial_backoff_ms: 1_000, ..ReconnectPolicy::default() });
+    }
+}
@@ -1,7 +1,11 @@
 mod request;
+mod order;
+mod response;
 mod id;
 mod error;
 
 pub use id::{WSRequestId, RequestIdString};
 pub use request::{WSRequest, WSRequestKind};
+pub use response::{WSResponse, WSAck, WSApiError};
+pub use order::{CancelReplaceMode, CancelReplaceParams, CancelReplaceResult, CancelReplaceStatus};
 pub use error::WSRequestError;
\ No newline at end of file
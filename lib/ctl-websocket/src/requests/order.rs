@@ -0,0 +1,175 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+// ----------------------------- Cancel-Replace Request ------------------------------
+
+/// What the exchange does with the new order when the cancel of a cancel-replace fails.
+/// https://github.com/binance/binance-spot-api-docs/blob/master/web-socket-api.md#cancel-and-replace-order-trade
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum CancelReplaceMode {
+    /// The new order is placed only if the cancel succeeds.
+    #[default]
+    StopOnFailure,
+    /// The new order is placed whether the cancel succeeds or not.
+    AllowFailure,
+}
+
+/// The parameters of an `order.cancelReplace` request, canceling an order and
+/// placing a new one in a single round trip.
+/// https://github.com/binance/binance-spot-api-docs/blob/master/web-socket-api.md#cancel-and-replace-order-trade
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CancelReplaceParams {
+    /// The symbol of both orders.
+    pub symbol: String,
+    /// What to do with the new order if the cancel fails.
+    pub cancel_replace_mode: CancelReplaceMode,
+    /// The exchange order ID of the order to cancel.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cancel_order_id: Option<u64>,
+    /// The client order ID of the order to cancel.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cancel_orig_client_order_id: Option<String>,
+    /// The side of the new order (`BUY` or `SELL`).
+    pub side: String,
+    /// The type of the new order (e.g. `LIMIT`).
+    #[serde(rename = "type")]
+    pub order_type: String,
+    /// The time in force of the new order (e.g. `GTC`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub time_in_force: Option<String>,
+    /// The limit price of the new order, as a decimal string.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub price: Option<String>,
+    /// The quantity of the new order, as a decimal string.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quantity: Option<String>,
+    /// The client order ID of the new order.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub new_client_order_id: Option<String>,
+    /// The API key the request is signed with.
+    pub api_key: String,
+    /// The validity window of the request after `timestamp`, in milliseconds.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recv_window: Option<u64>,
+    /// The time the request was created, in milliseconds.
+    pub timestamp: u64,
+    /// The hex-encoded HMAC SHA256 signature of `signature_payload`.
+    #[serde(default)]
+    pub signature: String,
+}
+
+impl CancelReplaceParams {
+    /// Returns the payload to sign: the parameters other than the signature,
+    /// sorted by name, as `name=value` pairs joined with `&`.
+    /// https://github.com/binance/binance-spot-api-docs/blob/master/web-socket-api.md#signed-request-example-hmac
+    pub fn signature_payload(&self) -> String {
+        let Ok(Value::Object(params)) = serde_json::to_value(self) else {
+            return String::new();
+        };
+        let mut pairs: Vec<(String, String)> = params
+            .into_iter()
+            .filter(|(name, _)| name != "signature")
+            .map(|(name, value)| match value {
+                Value::String(value) => (name, value),
+                value => (name, value.to_string()),
+            })
+            .collect();
+        pairs.sort();
+
+        pairs
+            .iter()
+            .map(|(name, value)| format!("{}={}", name, value))
+            .collect::<Vec<_>>()
+            .join("&")
+    }
+}
+
+// ----------------------------- Cancel-Replace Response ------------------------------
+
+/// The outcome of each half of a cancel-replace.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum CancelReplaceStatus {
+    Success,
+    Failure,
+    /// The new order wasn't placed, the cancel having failed in `STOP_ON_FAILURE` mode.
+    NotAttempted,
+}
+
+/// The result of an `order.cancelReplace` request, returned on success and
+/// as the error data when either half failed.
+/// https://github.com/binance/binance-spot-api-docs/blob/master/web-socket-api.md#cancel-and-replace-order-trade
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CancelReplaceResult {
+    /// The outcome of the cancel.
+    pub cancel_result: CancelReplaceStatus,
+    /// The outcome of the new order.
+    pub new_order_result: CancelReplaceStatus,
+    /// The canceled order, or the error of the cancel.
+    pub cancel_response: Value,
+    /// The new order, or its error, `None` if not attempted.
+    #[serde(default)]
+    pub new_order_response: Option<Value>,
+}
+
+impl CancelReplaceResult {
+    /// Returns true if the order was canceled.
+    pub fn is_canceled(&self) -> bool {
+        self.cancel_result == CancelReplaceStatus::Success
+    }
+
+    /// Returns true if the new order was placed.
+    pub fn is_placed(&self) -> bool {
+        self.new_order_result == CancelReplaceStatus::Success
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn params() -> CancelReplaceParams {
+        CancelReplaceParams {
+            symbol: "BTCUSDT".to_string(),
+            cancel_replace_mode: CancelReplaceMode::AllowFailure,
+            cancel_order_id: None,
+            cancel_orig_client_order_id: Some("4d96324ff9d44481926157".to_string()),
+            side: "SELL".to_string(),
+            order_type: "LIMIT".to_string(),
+            time_in_force: Some("GTC".to_string()),
+            price: Some("23416.10000000".to_string()),
+            quantity: Some("0.00847000".to_string()),
+            new_client_order_id: None,
+            api_key: "vmPUZE6mv9SD5VNHk4HlWFsOr6aKE2zvsw0MuIgwCIPy6utIco14y7Ju91duEh8A".to_string(),
+            recv_window: None,
+            timestamp: 1660813156900,
+            signature: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_signature_payload() {
+        assert_eq!(
+            params().signature_payload(),
+            "apiKey=vmPUZE6mv9SD5VNHk4HlWFsOr6aKE2zvsw0MuIgwCIPy6utIco14y7Ju91duEh8A\
+             &cancelOrigClientOrderId=4d96324ff9d44481926157&cancelReplaceMode=ALLOW_FAILURE\
+             &price=23416.10000000&quantity=0.00847000&side=SELL&symbol=BTCUSDT\
+             &timeInForce=GTC&timestamp=1660813156900&type=LIMIT"
+        );
+        let signed = CancelReplaceParams { signature: "abc".to_string(), ..params() };
+        assert_eq!(signed.signature_payload(), params().signature_payload());
+    }
+
+    #[test]
+    fn test_deserialize_cancel_replace_result() {
+        let json = r#"{"cancelResult":"FAILURE","newOrderResult":"NOT_ATTEMPTED","cancelResponse":{"code":-2011,"msg":"Unknown order sent."},"newOrderResponse":null}"#;
+        let result: CancelReplaceResult = serde_json::from_str(json).unwrap();
+        assert!(!result.is_canceled());
+        assert!(!result.is_placed());
+        assert_eq!(result.new_order_result, CancelReplaceStatus::NotAttempted);
+        assert_eq!(result.new_order_response, None);
+    }
+}
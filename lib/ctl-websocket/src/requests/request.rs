@@ -1,7 +1,7 @@
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
-use super::WSRequestId;
+use super::{CancelReplaceParams, WSRequestId};
 
 // ----------------------------- Websocket Request ------------------------------
 
@@ -55,11 +55,20 @@ pub enum WSRequestKind {
     /// https://github.com/binance/binance-spot-api-docs/blob/master/web-socket-streams.md#getting-properties
     #[serde(rename = "GET_PROPERTY")]
     GetProperty(Vec<String>),
+
+    // ============================================
+    // Websocket API Trading Requests
+    // ============================================
+    /// Cancel an order and place a new one in a single request.
+    /// https://github.com/binance/binance-spot-api-docs/blob/master/web-socket-api.md#cancel-and-replace-order-trade
+    #[serde(rename = "order.cancelReplace")]
+    OrderCancelReplace(CancelReplaceParams),
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::CancelReplaceMode;
     use serde_json::json;
 
     // ======================== Serialization Tests ========================
@@ -192,6 +201,56 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_serialize_order_cancel_replace() {
+        let req = WSRequest {
+            kind: WSRequestKind::OrderCancelReplace(CancelReplaceParams {
+                symbol: "BTCUSDT".to_string(),
+                cancel_replace_mode: CancelReplaceMode::StopOnFailure,
+                cancel_order_id: Some(125690984230),
+                cancel_orig_client_order_id: None,
+                side: "BUY".to_string(),
+                order_type: "LIMIT".to_string(),
+                time_in_force: Some("GTC".to_string()),
+                price: Some("23400.00000000".to_string()),
+                quantity: Some("0.00100000".to_string()),
+                new_client_order_id: Some("quote-2".to_string()),
+                api_key: "key".to_string(),
+                recv_window: Some(5000),
+                timestamp: 1660813156900,
+                signature: "sig".to_string(),
+            }),
+            id: Some(WSRequestId::try_from("cancel-replace-1").unwrap()),
+        };
+
+        let json = serde_json::to_value(&req).unwrap();
+        assert_eq!(
+            json,
+            json!({
+                "method": "order.cancelReplace",
+                "params": {
+                    "symbol": "BTCUSDT",
+                    "cancelReplaceMode": "STOP_ON_FAILURE",
+                    "cancelOrderId": 125690984230u64,
+                    "side": "BUY",
+                    "type": "LIMIT",
+                    "timeInForce": "GTC",
+                    "price": "23400.00000000",
+                    "quantity": "0.00100000",
+                    "newClientOrderId": "quote-2",
+                    "apiKey": "key",
+                    "recvWindow": 5000,
+                    "timestamp": 1660813156900u64,
+                    "signature": "sig"
+                },
+                "id": "cancel-replace-1"
+            })
+        );
+
+        let deserialized: WSRequest = serde_json::from_value(json).unwrap();
+        assert_eq!(deserialized, req);
+    }
+
     // ======================== Deserialization Tests ========================
 
     #[test]
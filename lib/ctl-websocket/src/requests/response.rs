@@ -0,0 +1,184 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::{CancelReplaceResult, WSRequestId, WSRequestKind};
+
+// ----------------------------- Websocket Response ------------------------------
+
+/// A websocket response to a request issued on Binance WebSocket APIs.
+/// https://github.com/binance/binance-spot-api-docs/blob/master/web-socket-streams.md#live-subscribingunsubscribing-to-streams
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum WSResponse {
+    /// The request failed.
+    /// https://github.com/binance/binance-spot-api-docs/blob/master/web-socket-streams.md#error-messages
+    Error {
+        /// The error code.
+        code: i64,
+        /// The error message.
+        msg: String,
+        /// The identifier of the request.
+        id: Option<WSRequestId>,
+    },
+    /// A websocket API request failed.
+    /// https://github.com/binance/binance-spot-api-docs/blob/master/web-socket-api.md#response-format
+    ApiError {
+        /// The HTTP status code of the failure.
+        status: u16,
+        /// The error.
+        error: WSApiError,
+        /// The identifier of the request.
+        id: Option<WSRequestId>,
+    },
+    /// The request succeeded.
+    Result {
+        /// The result of the request (`null` for SUBSCRIBE/UNSUBSCRIBE).
+        result: Value,
+        /// The identifier of the request.
+        id: Option<WSRequestId>,
+    },
+}
+
+/// The error of a failed websocket API request.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WSApiError {
+    /// The error code.
+    pub code: i64,
+    /// The error message.
+    pub msg: String,
+    /// Details of the failure, such as the outcome of each half of a cancel-replace.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<Value>,
+}
+
+impl WSResponse {
+    /// Returns the identifier of the request this response belongs to.
+    pub fn id(&self) -> Option<&WSRequestId> {
+        match self {
+            WSResponse::Error { id, .. } => id.as_ref(),
+            WSResponse::ApiError { id, .. } => id.as_ref(),
+            WSResponse::Result { id, .. } => id.as_ref(),
+        }
+    }
+
+    /// Returns true if the request succeeded.
+    pub fn is_ok(&self) -> bool {
+        matches!(self, WSResponse::Result { .. })
+    }
+
+    /// Returns true if the exchange rejected the request for exceeding the
+    /// rate limits (-1003, HTTP 418/429), to record to the circuit breaker.
+    pub fn is_rate_limited(&self) -> bool {
+        match self {
+            WSResponse::Error { code, .. } => *code == -1003,
+            WSResponse::ApiError { status, error, .. } => matches!(status, 418 | 429) || error.code == -1003,
+            WSResponse::Result { .. } => false,
+        }
+    }
+
+    /// Returns the outcome of a cancel-replace, on success as well as when
+    /// either of the cancel or the new order failed.
+    pub fn cancel_replace(&self) -> Option<CancelReplaceResult> {
+        let result = match self {
+            WSResponse::Result { result, .. } => result,
+            WSResponse::ApiError { error, .. } => error.data.as_ref()?,
+            WSResponse::Error { .. } => return None,
+        };
+        serde_json::from_value(result.clone()).ok()
+    }
+
+    /// Returns true if the raw frame looks like a response rather than stream data.
+    ///
+    /// LATENCY: FAST_PATH
+    pub fn is_response(data: &[u8]) -> bool {
+        data.starts_with(b"{\"result\"")
+            || data.starts_with(b"{\"id\"")
+            || data.starts_with(b"{\"code\"")
+    }
+}
+
+/// The outcome of a tracked request, pairing the issued request with its response.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WSAck {
+    /// The identifier of the request.
+    pub id: WSRequestId,
+    /// The request that was issued, if it was tracked by the connection.
+    pub request: Option<WSRequestKind>,
+    /// The response received for the request.
+    pub response: WSResponse,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_deserialize_result_response() {
+        let json = r#"{"result":null,"id":312}"#;
+        let resp: WSResponse = serde_json::from_str(json).unwrap();
+        assert_eq!(
+            resp,
+            WSResponse::Result { result: Value::Null, id: Some(WSRequestId::Int(312)) }
+        );
+        assert!(resp.is_ok());
+        assert_eq!(resp.id(), Some(&WSRequestId::Int(312)));
+    }
+
+    #[test]
+    fn test_deserialize_list_subscriptions_response() {
+        let json = r#"{"result":["btcusdt@aggTrade"],"id":3}"#;
+        let resp: WSResponse = serde_json::from_str(json).unwrap();
+        assert_eq!(
+            resp,
+            WSResponse::Result {
+                result: serde_json::json!(["btcusdt@aggTrade"]),
+                id: Some(WSRequestId::Int(3)),
+            }
+        );
+    }
+
+    #[test]
+    fn test_deserialize_error_response() {
+        let json = r#"{"code":2,"msg":"Invalid request: unknown variant","id":1}"#;
+        let resp: WSResponse = serde_json::from_str(json).unwrap();
+        assert!(!resp.is_ok());
+        assert_eq!(resp.id(), Some(&WSRequestId::Int(1)));
+        assert!(!resp.is_rate_limited());
+
+        let json = r#"{"id":4,"status":418,"error":{"code":-1003,"msg":"Way too much request weight used."}}"#;
+        let resp: WSResponse = serde_json::from_str(json).unwrap();
+        assert!(resp.is_rate_limited());
+    }
+
+    #[test]
+    fn test_deserialize_cancel_replace_responses() {
+        let json = r#"{"id":"cr-1","status":200,"result":{"cancelResult":"SUCCESS","newOrderResult":"SUCCESS","cancelResponse":{"symbol":"BTCUSDT","origClientOrderId":"quote-1","status":"CANCELED"},"newOrderResponse":{"symbol":"BTCUSDT","orderId":12569099453,"clientOrderId":"quote-2","status":"NEW"}},"rateLimits":[]}"#;
+        let resp: WSResponse = serde_json::from_str(json).unwrap();
+        assert!(resp.is_ok());
+        let result = resp.cancel_replace().unwrap();
+        assert!(result.is_canceled() && result.is_placed());
+
+        // The cancel failed, but the new order was placed in ALLOW_FAILURE mode
+        let json = r#"{"id":"cr-2","status":409,"error":{"code":-2021,"msg":"Order cancel-replace partially failed.","data":{"cancelResult":"FAILURE","newOrderResult":"SUCCESS","cancelResponse":{"code":-2011,"msg":"Unknown order sent."},"newOrderResponse":{"symbol":"BTCUSDT","clientOrderId":"quote-3","status":"NEW"}}},"rateLimits":[]}"#;
+        let resp: WSResponse = serde_json::from_str(json).unwrap();
+        assert!(!resp.is_ok());
+        assert_eq!(resp.id(), Some(&WSRequestId::try_from("cr-2").unwrap()));
+        assert!(matches!(&resp, WSResponse::ApiError { status: 409, error, .. } if error.code == -2021));
+        let result = resp.cancel_replace().unwrap();
+        assert!(!result.is_canceled() && result.is_placed());
+
+        // An error without data isn't a cancel-replace outcome
+        let json = r#"{"id":3,"status":400,"error":{"code":-1102,"msg":"Mandatory parameter 'symbol' was not sent."}}"#;
+        let resp: WSResponse = serde_json::from_str(json).unwrap();
+        assert!(resp.cancel_replace().is_none());
+        assert!(WSResponse::is_response(json.as_bytes()));
+    }
+
+    #[test]
+    fn test_is_response() {
+        assert!(WSResponse::is_response(br#"{"result":null,"id":1}"#));
+        assert!(WSResponse::is_response(br#"{"code":2,"msg":"x","id":1}"#));
+        assert!(!WSResponse::is_response(br#"{"u":400900217,"s":"BNBUSDT"}"#));
+        assert!(!WSResponse::is_response(br#"{"e":"trade","E":123456789}"#));
+    }
+}
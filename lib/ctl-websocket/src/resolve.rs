@@ -0,0 +1,344 @@
+//! Resolution of the endpoints to their full address set.
+//!
+//! The host of an endpoint (e.g. `stream.binance.com`) resolves to several
+//! addresses, of which a connection resolving it by itself takes whichever the
+//! system resolver returns first, healthy or not. An `AddressPool` resolves the
+//! host to all its addresses and tracks the health of each, so the connection
+//! is pinned to the healthiest one: an address failing to connect, or slower to
+//! connect than `max_connect_ms`, is quarantined for `quarantine_ms` while the
+//! others are tried. The host is resolved again every `resolve_interval_ms`,
+//! the addresses still resolved keeping their health.
+
+use std::net::{SocketAddr, ToSocketAddrs};
+use std::time::{Duration, Instant};
+
+use serde::Deserialize;
+
+use crate::WebsocketConnectorError;
+
+/// Default time above which a connection is slow, in milliseconds.
+const DEFAULT_MAX_CONNECT_MS: u64 = 1_000;
+
+/// Default time an address is left aside after failing, in milliseconds.
+const DEFAULT_QUARANTINE_MS: u64 = 30_000;
+
+/// Default interval between the resolutions of the host, in milliseconds.
+const DEFAULT_RESOLVE_INTERVAL_MS: u64 = 300_000;
+
+fn default_pinned() -> bool {
+    true
+}
+
+fn default_max_connect_ms() -> u64 {
+    DEFAULT_MAX_CONNECT_MS
+}
+
+fn default_quarantine_ms() -> u64 {
+    DEFAULT_QUARANTINE_MS
+}
+
+fn default_resolve_interval_ms() -> u64 {
+    DEFAULT_RESOLVE_INTERVAL_MS
+}
+
+/// How a feed picks the address of the host of its endpoint.
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Eq, Hash)]
+pub struct AddressPolicy {
+    /// Whether the connection is pinned to an address of the resolved set,
+    /// rather than to the one the system resolver returns.
+    #[serde(default = "default_pinned")]
+    pub pinned: bool,
+    /// Time to connect above which another address is tried, in milliseconds.
+    #[serde(default = "default_max_connect_ms")]
+    pub max_connect_ms: u64,
+    /// Time an address is left aside after failing or connecting slowly, in milliseconds.
+    #[serde(default = "default_quarantine_ms")]
+    pub quarantine_ms: u64,
+    /// Interval between the resolutions of the host, in milliseconds.
+    #[serde(default = "default_resolve_interval_ms")]
+    pub resolve_interval_ms: u64,
+}
+
+impl Default for AddressPolicy {
+    fn default() -> Self {
+        Self {
+            pinned: true,
+            max_connect_ms: DEFAULT_MAX_CONNECT_MS,
+            quarantine_ms: DEFAULT_QUARANTINE_MS,
+            resolve_interval_ms: DEFAULT_RESOLVE_INTERVAL_MS,
+        }
+    }
+}
+
+impl AddressPolicy {
+    /// Validates the policy configuration.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.max_connect_ms == 0 {
+            return Err("addresses 'max_connect_ms' must be greater than 0".to_string());
+        }
+        if self.resolve_interval_ms == 0 {
+            return Err("addresses 'resolve_interval_ms' must be greater than 0".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// The health of an address of the host.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AddressHealth {
+    /// The address.
+    pub addr: SocketAddr,
+    /// Number of connections established to the address.
+    pub connects: u64,
+    /// Number of consecutive failures of the address.
+    pub failures: u32,
+    /// Time the last connection took, in milliseconds.
+    pub connect_ms: Option<u64>,
+    /// Until when the address is left aside.
+    pub quarantined_until: Option<Instant>,
+}
+
+impl AddressHealth {
+    fn new(addr: SocketAddr) -> Self {
+        Self { addr, connects: 0, failures: 0, connect_ms: None, quarantined_until: None }
+    }
+
+    /// Returns true if the address is left aside at `now`.
+    pub fn is_quarantined(&self, now: Instant) -> bool {
+        self.quarantined_until.is_some_and(|until| now < until)
+    }
+}
+
+/// Splits a `ws://` or `wss://` URL into its host and port.
+pub(crate) fn host_port(url: &str) -> Option<(String, u16)> {
+    let (scheme, rest) = url.split_once("://")?;
+    let default_port = match scheme {
+        "ws" => 80,
+        "wss" => 443,
+        _ => return None,
+    };
+    let authority = rest.split(['/', '?']).next()?;
+    let (host, port) = match authority.strip_prefix('[') {
+        // An IPv6 literal
+        Some(bracketed) => {
+            let (host, port) = bracketed.split_once(']')?;
+            (host, port.strip_prefix(':'))
+        }
+        None => match authority.rsplit_once(':') {
+            Some((host, port)) => (host, Some(port)),
+            None => (authority, None),
+        },
+    };
+    let port = port.map_or(Some(default_port), |port| port.parse().ok())?;
+    (!host.is_empty()).then(|| (host.to_string(), port))
+}
+
+/// The addresses of the host of an endpoint and their health.
+#[derive(Debug, Clone)]
+pub struct AddressPool {
+    /// The host of the endpoint.
+    host: String,
+    /// The port of the endpoint.
+    port: u16,
+    /// The address policy.
+    policy: AddressPolicy,
+    /// The resolved addresses, in the order of the resolver.
+    addresses: Vec<AddressHealth>,
+    /// When the host was last resolved.
+    resolved_at: Option<Instant>,
+    /// The address of the connection.
+    active: Option<SocketAddr>,
+}
+
+impl AddressPool {
+    /// Creates the pool of the host of `url`, resolved on the first connection.
+    ///
+    /// # Errors
+    /// Returns an error if `url` isn't a `ws://` or `wss://` URL with a host.
+    pub fn new(url: &str, policy: AddressPolicy) -> Result<Self, WebsocketConnectorError> {
+        let (host, port) = host_port(url).ok_or_else(|| WebsocketConnectorError::ResolveError(url.to_string()))?;
+        Ok(Self { host, port, policy, addresses: Vec::new(), resolved_at: None, active: None })
+    }
+
+    /// Returns the host of the endpoint.
+    pub fn host(&self) -> &str {
+        &self.host
+    }
+
+    /// Returns the address policy.
+    pub fn policy(&self) -> &AddressPolicy {
+        &self.policy
+    }
+
+    /// Returns the resolved addresses and their health.
+    pub fn addresses(&self) -> &[AddressHealth] {
+        &self.addresses
+    }
+
+    /// Returns the address of the connection, if connected.
+    pub fn active(&self) -> Option<SocketAddr> {
+        self.active
+    }
+
+    /// Resolves the host to all its addresses if never resolved or the
+    /// resolution interval elapsed.
+    ///
+    /// A failed resolution keeps the addresses resolved before, if any.
+    ///
+    /// LATENCY: SLOW_PATH
+    pub fn resolve_if_due(&mut self, now: Instant) -> Result<(), WebsocketConnectorError> {
+        let interval = Duration::from_millis(self.policy.resolve_interval_ms);
+        if self.resolved_at.is_some_and(|at| now.saturating_duration_since(at) < interval) {
+            return Ok(());
+        }
+        match (self.host.as_str(), self.port).to_socket_addrs() {
+            Ok(resolved) => {
+                self.update(resolved.collect(), now);
+                Ok(())
+            }
+            Err(_) if !self.addresses.is_empty() => {
+                self.resolved_at = Some(now);
+                Ok(())
+            }
+            Err(_) => Err(WebsocketConnectorError::ResolveError(self.host.clone())),
+        }
+    }
+
+    /// Replaces the addresses with the resolved ones, the addresses still
+    /// resolved keeping their health.
+    pub fn update(&mut self, resolved: Vec<SocketAddr>, now: Instant) {
+        let mut addresses: Vec<AddressHealth> = Vec::with_capacity(resolved.len());
+        for addr in resolved {
+            if addresses.iter().any(|health| health.addr == addr) {
+                continue;
+            }
+            let known = self.addresses.iter().find(|health| health.addr == addr);
+            addresses.push(known.copied().unwrap_or_else(|| AddressHealth::new(addr)));
+        }
+        self.addresses = addresses;
+        self.resolved_at = Some(now);
+    }
+
+    /// Returns the addresses to try in order at `now`: the healthy ones, the
+    /// fastest to connect first and the untried ones after them, then the
+    /// quarantined ones, the first to leave quarantine first.
+    pub fn candidates(&self, now: Instant) -> Vec<SocketAddr> {
+        let (mut healthy, mut quarantined): (Vec<&AddressHealth>, Vec<&AddressHealth>) =
+            self.addresses.iter().partition(|health| !health.is_quarantined(now));
+        // Stable, the resolver order kept among the untried addresses
+        healthy.sort_by_key(|health| (health.failures, health.connect_ms.is_none(), health.connect_ms));
+        quarantined.sort_by_key(|health| health.quarantined_until);
+        healthy.into_iter().chain(quarantined).map(|health| health.addr).collect()
+    }
+
+    /// Records a connection to `addr` established in `elapsed`, returning false
+    /// if slower than allowed, the address then quarantined.
+    pub fn record_connect(&mut self, addr: SocketAddr, elapsed: Duration, now: Instant) -> bool {
+        let quarantine = Duration::from_millis(self.policy.quarantine_ms);
+        let fast = elapsed <= Duration::from_millis(self.policy.max_connect_ms);
+        if let Some(health) = self.addresses.iter_mut().find(|health| health.addr == addr) {
+            health.connects += 1;
+            health.failures = 0;
+            health.connect_ms = Some(elapsed.as_millis() as u64);
+            health.quarantined_until = (!fast).then_some(now + quarantine);
+        }
+        fast
+    }
+
+    /// Records a failure of `addr`, quarantining it.
+    pub fn record_failure(&mut self, addr: SocketAddr, now: Instant) {
+        let quarantine = Duration::from_millis(self.policy.quarantine_ms);
+        if let Some(health) = self.addresses.iter_mut().find(|health| health.addr == addr) {
+            health.failures += 1;
+            health.quarantined_until = Some(now + quarantine);
+        }
+        if self.active == Some(addr) {
+            self.active = None;
+        }
+    }
+
+    /// Records a failure of the connection, quarantining its address.
+    pub fn record_active_failure(&mut self, now: Instant) {
+        if let Some(addr) = self.active {
+            self.record_failure(addr, now);
+        }
+    }
+
+    /// Pins the connection to `addr`.
+    pub fn pin(&mut self, addr: SocketAddr) {
+        self.active = Some(addr);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn addr(last: u8) -> SocketAddr {
+        SocketAddr::from(([10, 0, 0, last], 9443))
+    }
+
+    fn pool() -> AddressPool {
+        let policy = AddressPolicy { max_connect_ms: 100, quarantine_ms: 1_000, ..AddressPolicy::default() };
+        AddressPool::new("wss://stream.binance.com:9443/ws", policy).unwrap()
+    }
+
+    #[test]
+    fn test_host_port() {
+        assert_eq!(host_port("wss://stream.binance.com:9443/ws"), Some(("stream.binance.com".to_string(), 9443)));
+        let combined = host_port("wss://stream.binance.com/stream?streams=a");
+        assert_eq!(combined, Some(("stream.binance.com".to_string(), 443)));
+        assert_eq!(host_port("ws://127.0.0.1:8080"), Some(("127.0.0.1".to_string(), 8080)));
+        assert_eq!(host_port("ws://[::1]:8080/ws"), Some(("::1".to_string(), 8080)));
+        assert_eq!(host_port("http://stream.binance.com"), None);
+        assert_eq!(host_port("wss://:9443/ws"), None);
+    }
+
+    #[test]
+    fn test_candidates_by_health() {
+        let mut pool = pool();
+        let now = Instant::now();
+        pool.update(vec![addr(1), addr(2), addr(3), addr(1)], now);
+        assert_eq!(pool.candidates(now), vec![addr(1), addr(2), addr(3)]);
+
+        // A failure quarantines the address, a slow connection too
+        pool.record_failure(addr(1), now);
+        assert!(!pool.record_connect(addr(2), Duration::from_millis(250), now));
+        assert!(pool.record_connect(addr(3), Duration::from_millis(20), now));
+        pool.pin(addr(3));
+        assert_eq!(pool.candidates(now), vec![addr(3), addr(1), addr(2)]);
+        assert_eq!(pool.active(), Some(addr(3)));
+
+        // Out of quarantine, the fastest first
+        let later = now + Duration::from_secs(2);
+        assert_eq!(pool.candidates(later), vec![addr(3), addr(2), addr(1)]);
+        pool.record_active_failure(later);
+        assert_eq!(pool.active(), None);
+        assert_eq!(pool.candidates(later)[0], addr(2));
+    }
+
+    #[test]
+    fn test_update_keeps_health() {
+        let mut pool = pool();
+        let now = Instant::now();
+        pool.update(vec![addr(1), addr(2)], now);
+        pool.record_failure(addr(2), now);
+
+        pool.update(vec![addr(2), addr(4)], now);
+        let addresses: Vec<(SocketAddr, u32)> =
+            pool.addresses().iter().map(|health| (health.addr, health.failures)).collect();
+        assert_eq!(addresses, vec![(addr(2), 1), (addr(4), 0)]);
+    }
+
+    #[test]
+    fn test_resolve_literal() {
+        let mut pool = AddressPool::new("ws://127.0.0.1:8080/ws", AddressPolicy::default()).unwrap();
+        pool.resolve_if_due(Instant::now()).unwrap();
+        assert_eq!(pool.candidates(Instant::now()), vec![SocketAddr::from(([127, 0, 0, 1], 8080))]);
+        assert!(AddressPool::new("stream.binance.com:9443", AddressPolicy::default()).is_err());
+
+        let policy: AddressPolicy = serde_json::from_str(r#"{"max_connect_ms":500}"#).unwrap();
+        assert_eq!(policy, AddressPolicy { max_connect_ms: 500, ..AddressPolicy::default() });
+        assert!(AddressPolicy { resolve_interval_ms: 0, ..policy }.validate().is_err());
+    }
+}
@@ -0,0 +1,121 @@
+//! Retries of the SUBSCRIBE and UNSUBSCRIBE requests rejected by the server.
+//!
+//! A request answered with an error response is issued again after an
+//! exponentially growing backoff, per the shared `ctl_retry::RetryPolicy`,
+//! until `max_retries` attempts were rejected.
+
+use std::time::Instant;
+
+use ctl_retry::{Jitter, RetryPolicy};
+
+use crate::WSRequestKind;
+
+/// A rejected request waiting to be issued again.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScheduledRetry {
+    /// The request to issue again.
+    pub kind: WSRequestKind,
+    /// The retry, starting at 1.
+    pub retry: u32,
+    /// When the request is issued again.
+    pub due: Instant,
+}
+
+/// The rejected requests of a connection waiting to be issued again.
+#[derive(Debug, Clone, Default)]
+pub struct RetryQueue {
+    /// The policy.
+    policy: RetryPolicy,
+    /// The source of the jitter of the backoffs.
+    jitter: Jitter,
+    /// The scheduled retries.
+    scheduled: Vec<ScheduledRetry>,
+}
+
+impl RetryQueue {
+    /// Creates an empty queue retrying per `policy`.
+    pub fn new(policy: RetryPolicy) -> Self {
+        Self { policy, jitter: Jitter::from_entropy(), scheduled: Vec::new() }
+    }
+
+    /// Sets the retry policy of the requests rejected from now on.
+    pub fn set_policy(&mut self, policy: RetryPolicy) {
+        self.policy = policy;
+    }
+
+    /// Schedules the retry of a request rejected after `retries` retries.
+    /// Returns false if the retries are exhausted.
+    pub fn schedule(&mut self, kind: WSRequestKind, retries: u32, now: Instant) -> bool {
+        if retries >= self.policy.max_retries {
+            return false;
+        }
+        let retry = retries + 1;
+        let due = now + self.policy.jittered(retry, &mut self.jitter);
+        self.scheduled.push(ScheduledRetry { kind, retry, due });
+        true
+    }
+
+    /// Takes the retries due at `now`.
+    pub fn take_due(&mut self, now: Instant) -> Vec<ScheduledRetry> {
+        if self.scheduled.iter().all(|retry| retry.due > now) {
+            return Vec::new();
+        }
+        let (due, scheduled) = self.scheduled.drain(..).partition(|retry| retry.due <= now);
+        self.scheduled = scheduled;
+        due
+    }
+
+    /// Drops the scheduled retries, e.g. when the streams are resubscribed on another endpoint.
+    pub fn clear(&mut self) {
+        self.scheduled.clear();
+    }
+
+    /// Returns the number of scheduled retries.
+    pub fn len(&self) -> usize {
+        self.scheduled.len()
+    }
+
+    /// Returns true if no retry is scheduled.
+    pub fn is_empty(&self) -> bool {
+        self.scheduled.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    fn policy() -> RetryPolicy {
+        RetryPolicy { max_retries: 2, initial_backoff_ms: 100, max_backoff_ms: 150, ..RetryPolicy::default() }
+    }
+
+    #[test]
+    fn test_backoff() {
+        let policy = policy();
+        assert_eq!(policy.backoff(1), Duration::from_millis(100));
+        assert_eq!(policy.backoff(2), Duration::from_millis(150));
+        assert_eq!(policy.backoff(u32::MAX), Duration::from_millis(150));
+    }
+
+    #[test]
+    fn test_schedule_until_exhausted() {
+        let start = Instant::now();
+        let mut queue = RetryQueue::new(policy());
+        let kind = WSRequestKind::Subscribe(vec!["btcusdt@trade".to_string()]);
+        assert!(queue.schedule(kind.clone(), 0, start));
+        assert!(queue.take_due(start).is_empty());
+
+        let due = queue.take_due(start + Duration::from_millis(100));
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].retry, 1);
+        assert!(queue.is_empty());
+
+        assert!(queue.schedule(kind.clone(), 1, start));
+        assert!(!queue.schedule(kind, 2, start));
+        assert_eq!(queue.len(), 1);
+        queue.clear();
+        assert!(queue.is_empty());
+    }
+}
@@ -0,0 +1,191 @@
+//! Frame-level statistics of a websocket connection.
+//!
+//! The connection counts the frames and bytes it receives, and times its last
+//! data and the round trip of its requests, the periodic LIST_SUBSCRIPTIONS of
+//! the reconciliation serving as pings answered by the server itself, and the
+//! malformed responses it drops. The frame
+//! and byte rates are sampled by the connection over windows of at least a
+//! second. The statistics are shared through a `ConnectionStatsHandle`, read
+//! off the hot path (health endpoint, metrics exporter) while the connection
+//! polls on its worker, and kept across its reconnections.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+
+/// Shortest window the frame and byte rates are sampled over.
+const RATE_WINDOW: Duration = Duration::from_secs(1);
+
+/// Value of the unset times (no request answered, no data received).
+const UNSET: u64 = u64::MAX;
+
+/// The statistics of a connection, as of a snapshot.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct ConnectionStats {
+    /// Frames received.
+    pub frames: u64,
+    /// Payload bytes of the frames received.
+    pub bytes: u64,
+    /// Frames received per second, over the last window.
+    pub frame_rate: u64,
+    /// Payload bytes received per second, over the last window.
+    pub byte_rate: u64,
+    /// Round trip of the last request answered, in microseconds, `None` before the first.
+    pub rtt_us: Option<u64>,
+    /// Time since the last frame, in milliseconds, `None` before the first.
+    pub since_last_data_ms: Option<u64>,
+    /// Malformed responses dropped.
+    pub bad_responses: u64,
+}
+
+/// The counters of a connection, written by the connection.
+#[derive(Debug)]
+struct Counters {
+    /// The origin of the times, in microseconds since it.
+    epoch: Instant,
+    /// Frames received.
+    frames: AtomicU64,
+    /// Payload bytes of the frames received.
+    bytes: AtomicU64,
+    /// Frames per second over the last window.
+    frame_rate: AtomicU64,
+    /// Payload bytes per second over the last window.
+    byte_rate: AtomicU64,
+    /// Round trip of the last request answered, in microseconds.
+    rtt_us: AtomicU64,
+    /// Time of the last frame.
+    last_data_us: AtomicU64,
+    /// Malformed responses dropped.
+    bad_responses: AtomicU64,
+    /// Start of the current rate window.
+    window_start_us: AtomicU64,
+    /// Frames received at the start of the current rate window.
+    window_frames: AtomicU64,
+    /// Bytes received at the start of the current rate window.
+    window_bytes: AtomicU64,
+}
+
+/// A handle to the statistics of a connection, shared with the threads reading them.
+#[derive(Debug, Clone)]
+pub struct ConnectionStatsHandle(Arc<Counters>);
+
+impl Default for ConnectionStatsHandle {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ConnectionStatsHandle {
+    /// Creates the statistics of a connection, nothing received yet.
+    pub fn new() -> Self {
+        Self(Arc::new(Counters {
+            epoch: Instant::now(),
+            frames: AtomicU64::new(0),
+            bytes: AtomicU64::new(0),
+            frame_rate: AtomicU64::new(0),
+            byte_rate: AtomicU64::new(0),
+            rtt_us: AtomicU64::new(UNSET),
+            last_data_us: AtomicU64::new(UNSET),
+            bad_responses: AtomicU64::new(0),
+            window_start_us: AtomicU64::new(0),
+            window_frames: AtomicU64::new(0),
+            window_bytes: AtomicU64::new(0),
+        }))
+    }
+
+    /// Returns the time `at` in microseconds since the epoch of the statistics.
+    fn micros(&self, at: Instant) -> u64 {
+        at.saturating_duration_since(self.0.epoch).as_micros() as u64
+    }
+
+    /// Records a frame of `len` payload bytes received at `now`.
+    ///
+    /// LATENCY: FAST_PATH
+    pub fn record_frame(&self, len: usize, now: Instant) {
+        self.0.frames.fetch_add(1, Ordering::Relaxed);
+        self.0.bytes.fetch_add(len as u64, Ordering::Relaxed);
+        self.0.last_data_us.store(self.micros(now), Ordering::Relaxed);
+        self.sample(now);
+    }
+
+    /// Records the round trip of the last request answered.
+    pub fn record_rtt(&self, rtt: Duration) {
+        self.0.rtt_us.store(rtt.as_micros() as u64, Ordering::Relaxed);
+    }
+
+    /// Records a malformed response, dropped by the connection.
+    pub fn record_bad_response(&self) {
+        self.0.bad_responses.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Samples the frame and byte rates once the current window lasted long enough.
+    ///
+    /// LATENCY: FAST_PATH
+    pub fn sample(&self, now: Instant) {
+        let now_us = self.micros(now);
+        let elapsed_us = now_us.saturating_sub(self.0.window_start_us.load(Ordering::Relaxed));
+        if elapsed_us < RATE_WINDOW.as_micros() as u64 {
+            return;
+        }
+        let frames = self.0.frames.load(Ordering::Relaxed);
+        let bytes = self.0.bytes.load(Ordering::Relaxed);
+        let rate = |count: u64, start: u64| count.saturating_sub(start).saturating_mul(1_000_000) / elapsed_us;
+        self.0.frame_rate.store(rate(frames, self.0.window_frames.load(Ordering::Relaxed)), Ordering::Relaxed);
+        self.0.byte_rate.store(rate(bytes, self.0.window_bytes.load(Ordering::Relaxed)), Ordering::Relaxed);
+        self.0.window_start_us.store(now_us, Ordering::Relaxed);
+        self.0.window_frames.store(frames, Ordering::Relaxed);
+        self.0.window_bytes.store(bytes, Ordering::Relaxed);
+    }
+
+    /// Returns the statistics as of `now`.
+    pub fn snapshot(&self, now: Instant) -> ConnectionStats {
+        let set = |value: u64| (value != UNSET).then_some(value);
+        let now_us = self.micros(now);
+        ConnectionStats {
+            frames: self.0.frames.load(Ordering::Relaxed),
+            bytes: self.0.bytes.load(Ordering::Relaxed),
+            frame_rate: self.0.frame_rate.load(Ordering::Relaxed),
+            byte_rate: self.0.byte_rate.load(Ordering::Relaxed),
+            rtt_us: set(self.0.rtt_us.load(Ordering::Relaxed)),
+            since_last_data_ms: set(self.0.last_data_us.load(Ordering::Relaxed))
+                .map(|last_us| now_us.saturating_sub(last_us) / 1_000),
+            bad_responses: self.0.bad_responses.load(Ordering::Relaxed),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_connection_stats() {
+        let stats = ConnectionStatsHandle::new();
+        let start = stats.0.epoch;
+        assert_eq!(stats.snapshot(start), ConnectionStats::default());
+
+        let shared = stats.clone();
+        for i in 0..4 {
+            shared.record_frame(100, start + Duration::from_millis(i * 100));
+        }
+        shared.record_rtt(Duration::from_micros(1_500));
+        shared.record_bad_response();
+        let snapshot = stats.snapshot(start + Duration::from_millis(500));
+        assert_eq!((snapshot.frames, snapshot.bytes, snapshot.frame_rate), (4, 400, 0));
+        assert_eq!((snapshot.rtt_us, snapshot.since_last_data_ms), (Some(1_500), Some(200)));
+        assert_eq!(snapshot.bad_responses, 1);
+
+        // The rates are sampled once the window lasted a second
+        shared.record_frame(100, start + Duration::from_millis(1_000));
+        shared.sample(start + Duration::from_millis(1_500));
+        let snapshot = stats.snapshot(start + Duration::from_millis(1_500));
+        assert_eq!((snapshot.frame_rate, snapshot.byte_rate), (5, 500));
+
+        // Then over the next window, without frames
+        shared.sample(start + Duration::from_millis(2_000));
+        assert_eq!(stats.snapshot(start + Duration::from_millis(2_000)).frame_rate, 0);
+        assert_eq!(stats.snapshot(start + Duration::from_millis(2_000)).since_last_data_ms, Some(1_000));
+    }
+}
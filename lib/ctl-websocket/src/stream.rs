@@ -0,0 +1,78 @@
+use serde::{Deserialize, Serialize};
+
+/// Update speed qualifier appended to a stream name (e.g. `btcusdt@depth@100ms`).
+/// https://github.com/binance/binance-spot-api-docs/blob/master/web-socket-streams.md#diff-depth-stream
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum UpdateSpeed {
+    /// Updates pushed every 100ms.
+    #[serde(rename = "100ms")]
+    Ms100,
+    /// Updates pushed every 1000ms.
+    #[serde(rename = "1000ms")]
+    Ms1000,
+}
+
+impl UpdateSpeed {
+    /// Returns the qualifier as it appears in the stream name.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            UpdateSpeed::Ms100 => "100ms",
+            UpdateSpeed::Ms1000 => "1000ms",
+        }
+    }
+}
+
+/// Returns true if the streams of `suffix` take an update speed qualifier:
+/// the depth streams (`depth`, `depth5`, ...).
+pub fn has_update_speed(suffix: &str) -> bool {
+    suffix.starts_with("depth")
+}
+
+/// Builds a stream name of the form `{symbol}@{suffix}[@{speed}]`.
+///
+/// The speed is only valid on the streams of `has_update_speed`, the others
+/// are refused by the exchange when qualified.
+pub fn stream_name(symbol: &str, suffix: &str, update_speed: Option<UpdateSpeed>) -> String {
+    match update_speed {
+        Some(speed) => format!("{}@{}@{}", symbol, suffix, speed.as_str()),
+        None => format!("{}@{}", symbol, suffix),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stream_name_without_speed() {
+        assert_eq!(stream_name("btcusdt", "bookTicker", None), "btcusdt@bookTicker");
+    }
+
+    #[test]
+    fn test_stream_name_with_speed() {
+        assert_eq!(
+            stream_name("btcusdt", "depth", Some(UpdateSpeed::Ms100)),
+            "btcusdt@depth@100ms"
+        );
+        assert_eq!(
+            stream_name("btcusdt", "depth", Some(UpdateSpeed::Ms1000)),
+            "btcusdt@depth@1000ms"
+        );
+    }
+
+    #[test]
+    fn test_has_update_speed() {
+        assert!(has_update_speed("depth"));
+        assert!(has_update_speed("depth20"));
+        assert!(!has_update_speed("kline_1m"));
+        assert!(!has_update_speed("bookTicker"));
+        assert!(!has_update_speed("trade"));
+    }
+
+    #[test]
+    fn test_update_speed_serde() {
+        let speed: UpdateSpeed = serde_json::from_str(r#""100ms""#).unwrap();
+        assert_eq!(speed, UpdateSpeed::Ms100);
+        assert!(serde_json::from_str::<UpdateSpeed>(r#""250ms""#).is_err());
+    }
+}
@@ -1,7 +1,41 @@
-use atx_feed::{FeedData, FeedKind, FeedPoll, FeedProtocolOps, Streams};
+use std::collections::VecDeque;
+use std::net::SocketAddr;
+use std::sync::mpsc::Sender;
+use std::time::{Duration, Instant};
+
+use atx_feed::{FeedData, FeedKind, FeedPoll, FeedProtocolOps, Stream, Streams};
 use atx_websocket::{WebsocketConfig, WebsocketConn};
+use ctl_retry::Jitter;
+use hashbrown::HashMap;
+
+use crate::{
+    AddressHealth, AddressPolicy, AddressPool, ConnectionStats, ConnectionStatsHandle, EndpointRanking,
+    EndpointRotation, EndpointSwitch, FailoverPolicy, FailoverTrigger, MessageAssembler, ReconnectPolicy, RetryPolicy,
+    RetryQueue, SubscriptionDrift, SubscriptionLedger, SwitchReason, UpdateSpeed, WSAck, WSRequest, WSRequestId,
+    WSRequestKind, WSResponse, WebsocketConnectorError, stream_name,
+};
 
-use crate::WebsocketConnectorError;
+/// Maximum number of responses held for `poll_ack` without an acknowledgement
+/// reporter, the oldest dropped once reached.
+const MAX_QUEUED_ACKS: usize = 256;
+
+/// The requests issued by a single streams update.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StreamsUpdate {
+    /// The UNSUBSCRIBE request ID, if any streams were removed.
+    pub unsubscribe: Option<WSRequestId>,
+    /// The SUBSCRIBE request ID, if any streams were added.
+    pub subscribe: Option<WSRequestId>,
+}
+
+/// The response to a request of a feed, as reported to its acknowledgement reporter.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RequestAck {
+    /// The feed the connection belongs to.
+    pub feed: String,
+    /// The request and its response.
+    pub ack: WSAck,
+}
 
 /// The exchange websocket connector.
 /// This provides all the necessary methods to connect to the exchange websocket.
@@ -10,40 +44,673 @@ pub struct WSConn<K: FeedKind> {
     websocket: WebsocketConn,
     /// The streams being subscribed to.
     streams: Streams<K>,
-    /// Buffer for storing received message data.
-    recv_buffer: Vec<u8>,
+    /// The reassembly of the received frames into messages.
+    assembler: MessageAssembler,
+    /// Optional update speed qualifier appended to generated stream names.
+    update_speed: Option<UpdateSpeed>,
+    /// The next request ID to be issued.
+    next_request_id: u64,
+    /// Issued requests awaiting a response, and when they were issued.
+    pending: HashMap<WSRequestId, (WSRequestKind, Instant)>,
+    /// Responses received for issued requests, not yet taken by the caller,
+    /// bounded by `MAX_QUEUED_ACKS` and unused once an acknowledgement reporter is set.
+    acks: VecDeque<WSAck>,
+    /// The requests issued by the last streams update.
+    last_update: StreamsUpdate,
+    /// The retries of the pending requests reissued after a rejection.
+    retries_issued: HashMap<WSRequestId, u32>,
+    /// The rejected SUBSCRIBE and UNSUBSCRIBE requests waiting to be issued again.
+    retries: RetryQueue,
+    /// The endpoints of the connection and the health of the active one.
+    rotation: EndpointRotation,
+    /// The backoff of the reconnections to the active endpoint after a failure.
+    reconnect: ReconnectPolicy,
+    /// The source of the jitter of the reconnection backoffs.
+    jitter: Jitter,
+    /// When the failed connection is reopened, `None` while connected.
+    reconnect_due: Option<Instant>,
+    /// The addresses of the host of each endpoint and their health, by endpoint.
+    addresses: HashMap<String, AddressPool>,
+    /// Names of the streams subscribed to through `update_streams`, resubscribed on endpoint
+    /// switches and reconciled with the subscriptions listed by the server.
+    ledger: SubscriptionLedger,
+    /// The frame-level statistics of the connection.
+    stats: ConnectionStatsHandle,
+    /// The feed name and channel endpoint switches are reported to.
+    failover_reporter: Option<(String, Sender<EndpointSwitch>)>,
+    /// The feed name and channel subscription drifts are reported to.
+    drift_reporter: Option<(String, Sender<SubscriptionDrift>)>,
+    /// The feed name and channel the responses to the issued requests are reported to.
+    ack_reporter: Option<(String, Sender<RequestAck>)>,
 }
 
 impl<K: FeedKind> WSConn<K> {
     /// Creates a new WSConn instance.
     pub fn new(url: &str) -> Result<Self, WebsocketConnectorError> {
-        let mut websocket = WebsocketConn::new(url, WebsocketConfig::default())?;
-        websocket.connect()?;
+        Self::with_endpoints(vec![url.to_string()], FailoverPolicy::default())
+    }
+
+    /// Creates a new WSConn instance failing over between `endpoints`, primary first.
+    ///
+    /// Connects to the first endpoint accepting the connection.
+    ///
+    /// # Panics
+    /// Panics if `endpoints` is empty.
+    pub fn with_endpoints(
+        endpoints: Vec<String>,
+        policy: FailoverPolicy,
+    ) -> Result<Self, WebsocketConnectorError> {
+        Self::with_address_policy(endpoints, policy, AddressPolicy::default())
+    }
+
+    /// Creates a new WSConn instance failing over between `endpoints`, primary
+    /// first, and between the addresses of their hosts per `address_policy`.
+    ///
+    /// Connects to the first endpoint accepting the connection.
+    ///
+    /// # Errors
+    /// Returns an error if an endpoint isn't a websocket URL, or no endpoint
+    /// accepts the connection.
+    ///
+    /// # Panics
+    /// Panics if `endpoints` is empty.
+    pub fn with_address_policy(
+        endpoints: Vec<String>,
+        policy: FailoverPolicy,
+        address_policy: AddressPolicy,
+    ) -> Result<Self, WebsocketConnectorError> {
+        let mut addresses = HashMap::new();
+        for endpoint in &endpoints {
+            addresses.insert(endpoint.clone(), AddressPool::new(endpoint, address_policy)?);
+        }
+        let mut rotation = EndpointRotation::new(endpoints, policy);
+        let mut attempts = 1;
+        let websocket = loop {
+            let pool = addresses.get_mut(rotation.active()).expect("a pool per endpoint");
+            match Self::connect(rotation.active(), pool) {
+                Ok(websocket) => break websocket,
+                Err(e) if attempts >= rotation.endpoints().len() => return Err(e),
+                Err(_) => {
+                    rotation.advance(Instant::now());
+                    attempts += 1;
+                }
+            }
+        };
         Ok(Self {
             websocket,
             streams: Streams::new(),
-            recv_buffer: Vec::with_capacity(4096),
+            assembler: MessageAssembler::default(),
+            update_speed: None,
+            next_request_id: 1,
+            pending: HashMap::new(),
+            acks: VecDeque::new(),
+            last_update: StreamsUpdate::default(),
+            retries_issued: HashMap::new(),
+            retries: RetryQueue::default(),
+            rotation,
+            reconnect: ReconnectPolicy::default(),
+            jitter: Jitter::from_entropy(),
+            reconnect_due: None,
+            addresses,
+            ledger: SubscriptionLedger::new(None, Instant::now()),
+            stats: ConnectionStatsHandle::new(),
+            failover_reporter: None,
+            drift_reporter: None,
+            ack_reporter: None,
         })
     }
 
+    /// Opens a websocket connection to `url`, pinned to the healthiest address
+    /// of its host unless the address policy leaves it to the system resolver.
+    ///
+    /// The addresses are tried in turn until one connects within the time
+    /// allowed, else the connection to the first slow one is kept.
+    ///
+    /// LATENCY: SLOW_PATH
+    fn connect(url: &str, pool: &mut AddressPool) -> Result<WebsocketConn, WebsocketConnectorError> {
+        if !pool.policy().pinned {
+            return Self::connect_to(url, None);
+        }
+        pool.resolve_if_due(Instant::now())?;
+        let mut slow = None;
+        let mut error = None;
+        for addr in pool.candidates(Instant::now()) {
+            let start = Instant::now();
+            match Self::connect_to(url, Some(addr)) {
+                Ok(websocket) if pool.record_connect(addr, start.elapsed(), Instant::now()) => {
+                    pool.pin(addr);
+                    return Ok(websocket);
+                }
+                Ok(websocket) => {
+                    slow.get_or_insert((addr, websocket));
+                }
+                Err(e) => {
+                    pool.record_failure(addr, Instant::now());
+                    error = Some(e);
+                }
+            }
+        }
+        match (slow, error) {
+            (Some((addr, websocket)), _) => {
+                pool.pin(addr);
+                Ok(websocket)
+            }
+            (None, Some(e)) => Err(e),
+            (None, None) => Err(WebsocketConnectorError::ResolveError(pool.host().to_string())),
+        }
+    }
+
+    /// Opens a websocket connection to `url`, at `addr` if given rather than
+    /// at the address the host resolves to, the TLS server name and the Host
+    /// header still those of the URL.
+    fn connect_to(url: &str, addr: Option<SocketAddr>) -> Result<WebsocketConn, WebsocketConnectorError> {
+        let config = WebsocketConfig { connect_addr: addr, ..WebsocketConfig::default() };
+        let mut websocket = WebsocketConn::new(url, config)?;
+        websocket.connect()?;
+        Ok(websocket)
+    }
+
+    /// Reports the endpoint switches of the connection, as `feed`, to `reporter`.
+    pub fn set_failover_reporter(&mut self, feed: &str, reporter: Sender<EndpointSwitch>) {
+        self.failover_reporter = Some((feed.to_string(), reporter));
+    }
+
+    /// Switches to the next endpoint when requested through `trigger`.
+    pub fn set_failover_trigger(&mut self, trigger: FailoverTrigger) {
+        self.rotation.set_trigger(trigger);
+    }
+
+    /// Fails over to the best-ranked other endpoint per `ranking`, rather than to the next one.
+    pub fn set_endpoint_ranking(&mut self, ranking: EndpointRanking) {
+        self.rotation.set_ranking(ranking);
+    }
+
+    /// Sets the backoff of the reconnections to the active endpoint after a failure.
+    pub fn set_reconnect_policy(&mut self, policy: ReconnectPolicy) {
+        self.reconnect = policy;
+    }
+
+    /// Sets the maximum size of a message reassembled from its frames, in bytes.
+    pub fn set_max_message_size(&mut self, max_message_size: usize) {
+        self.assembler.set_max_message_size(max_message_size);
+    }
+
+    /// Records the statistics of the connection in `stats`, e.g. one kept across reconnections.
+    pub fn set_stats(&mut self, stats: ConnectionStatsHandle) {
+        self.stats = stats;
+    }
+
+    /// Returns the frame-level statistics of the connection: frame and byte
+    /// rates, round trip of the requests and time since the last data.
+    pub fn stats(&self) -> ConnectionStats {
+        self.stats.snapshot(Instant::now())
+    }
+
+    /// Reconciles the subscriptions with the server every `interval`, `None` disables it.
+    pub fn set_reconcile_interval(&mut self, interval: Option<Duration>) {
+        self.ledger.set_interval(interval);
+    }
+
+    /// Sets the retry policy of the rejected SUBSCRIBE and UNSUBSCRIBE requests.
+    pub fn set_retry_policy(&mut self, policy: RetryPolicy) {
+        self.retries.set_policy(policy);
+    }
+
+    /// Returns the subscribed streams acknowledged by the server.
+    pub fn active_streams(&self) -> &[String] {
+        self.ledger.active()
+    }
+
+    /// Reports the subscription drifts of the connection, as `feed`, to `reporter`.
+    pub fn set_drift_reporter(&mut self, feed: &str, reporter: Sender<SubscriptionDrift>) {
+        self.drift_reporter = Some((feed.to_string(), reporter));
+    }
+
+    /// Reports the responses to the issued requests, as `feed`, to `reporter`
+    /// instead of holding them for `poll_ack`.
+    pub fn set_ack_reporter(&mut self, feed: &str, reporter: Sender<RequestAck>) {
+        self.ack_reporter = Some((feed.to_string(), reporter));
+    }
+
+    /// Returns the subscription ledger of the connection.
+    pub fn ledger(&self) -> &SubscriptionLedger {
+        &self.ledger
+    }
+
+    /// Returns the active endpoint.
+    pub fn active_endpoint(&self) -> &str {
+        self.rotation.active()
+    }
+
+    /// Returns the address the active endpoint is pinned to, `None` if left to
+    /// the system resolver.
+    pub fn active_address(&self) -> Option<SocketAddr> {
+        self.addresses.get(self.rotation.active()).and_then(AddressPool::active)
+    }
+
+    /// Returns the addresses of the host of the active endpoint and their health.
+    pub fn addresses(&self) -> &[AddressHealth] {
+        self.addresses.get(self.rotation.active()).map_or(&[], AddressPool::addresses)
+    }
+
+    /// Sets the update speed qualifier used when generating stream names.
+    pub fn set_update_speed(&mut self, update_speed: Option<UpdateSpeed>) {
+        self.update_speed = update_speed;
+    }
+
+    /// Returns the configured update speed qualifier.
+    pub fn update_speed(&self) -> Option<UpdateSpeed> {
+        self.update_speed
+    }
+
+    /// Returns the full stream name for a symbol and stream suffix,
+    /// honoring the configured update speed.
+    pub fn stream_name(&self, symbol: &str, suffix: &str) -> String {
+        stream_name(symbol, suffix, self.update_speed)
+    }
+
     /// Returns a reference to the subscribed streams.
     pub fn streams(&self) -> &Streams<K> {
         &self.streams
     }
+
+    /// Updates the subscribed streams to `streams`, computing the difference once
+    /// and issuing the UNSUBSCRIBE and SUBSCRIBE requests as a single batch tagged
+    /// with request IDs, also kept as the `last_update`.
+    ///
+    /// The responses are matched on `poll` and surfaced through `poll_ack`, or
+    /// the acknowledgement reporter. The added streams become active once
+    /// acknowledged, and the rejected requests are issued again per the retry
+    /// policy.
+    ///
+    /// LATENCY: SLOW_PATH
+    /// ERROR: FULLY_HANDLED
+    pub fn update_streams(
+        &mut self,
+        streams: &Streams<K>,
+        suffix: &str,
+    ) -> Result<StreamsUpdate, WebsocketConnectorError> {
+        let removed: Vec<Stream<K>> = self.streams.difference(streams).cloned().collect();
+        let added: Vec<Stream<K>> = streams.difference(&self.streams).cloned().collect();
+        let unsubscribe: Vec<String> = removed.iter().map(|s| self.stream_name(s.name, suffix)).collect();
+        let subscribe: Vec<String> = added.iter().map(|s| self.stream_name(s.name, suffix)).collect();
+
+        let mut batch = Vec::with_capacity(2);
+        if !unsubscribe.is_empty() {
+            batch.push(WSRequestKind::Unsubscribe(unsubscribe.clone()));
+        }
+        if !subscribe.is_empty() {
+            batch.push(WSRequestKind::Subscribe(subscribe.clone()));
+        }
+        // The ledger only records the streams once their requests are sent, as the reconciliation checks it
+        let mut ids = self.send_batch(batch)?.into_iter();
+        self.ledger.unsubscribe(&unsubscribe);
+        self.ledger.subscribe(&subscribe);
+
+        let update = StreamsUpdate {
+            unsubscribe: if removed.is_empty() { None } else { ids.next() },
+            subscribe: if added.is_empty() { None } else { ids.next() },
+        };
+        for stream in &removed {
+            self.streams.remove(stream);
+        }
+        for stream in added {
+            self.streams.insert(stream);
+        }
+        self.last_update = update.clone();
+        Ok(update)
+    }
+
+    /// Returns the requests issued by the last streams update.
+    pub fn last_update(&self) -> &StreamsUpdate {
+        &self.last_update
+    }
+
+    /// Sends a request tagged with a fresh request ID and tracks it until its response arrives.
+    ///
+    /// LATENCY: SLOW_PATH
+    pub fn send_request(&mut self, kind: WSRequestKind) -> Result<WSRequestId, WebsocketConnectorError> {
+        let mut ids = self.send_batch(vec![kind])?;
+        Ok(ids.remove(0))
+    }
+
+    /// Sends a batch of requests tagged with fresh request IDs, in order, and
+    /// tracks them until their responses arrive.
+    ///
+    /// The stream API takes a single method per message, so the batch is sent
+    /// as consecutive frames: every request is serialized before the first is
+    /// sent, nothing being sent if any of them fails to.
+    ///
+    /// LATENCY: SLOW_PATH
+    pub fn send_batch(&mut self, kinds: Vec<WSRequestKind>) -> Result<Vec<WSRequestId>, WebsocketConnectorError> {
+        let mut requests = Vec::with_capacity(kinds.len());
+        for (offset, kind) in kinds.into_iter().enumerate() {
+            let id = WSRequestId::from(self.next_request_id + offset as u64);
+            let req: WSRequest = (kind, Some(id.clone())).into();
+            let request_json = serde_json::to_vec(&req)?;
+            requests.push((id, req.kind, request_json));
+        }
+        self.next_request_id += requests.len() as u64;
+
+        let mut ids = Vec::with_capacity(requests.len());
+        for (id, kind, request_json) in requests {
+            self.send(&request_json)?;
+            self.pending.insert(id.clone(), (kind, Instant::now()));
+            ids.push(id);
+        }
+        Ok(ids)
+    }
+
+    /// Returns the next response received for an issued request, if any.
+    pub fn poll_ack(&mut self) -> Option<WSAck> {
+        self.acks.pop_front()
+    }
+
+    /// Returns the number of issued requests still awaiting a response.
+    pub fn num_pending(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Handles a failure of the active endpoint, reconnecting to it after the
+    /// reconnection backoff, or switching endpoints once the failover policy's
+    /// failure threshold is reached.
+    ///
+    /// LATENCY: SLOW_PATH
+    /// ERROR: FULLY_HANDLED
+    fn handle_failure(&mut self) {
+        // The requests failing on a connection awaiting its reconnection are a single failure
+        if self.reconnect_due.is_some() {
+            return;
+        }
+        // The next connection to the endpoint tries its other addresses first
+        if let Some(pool) = self.addresses.get_mut(self.rotation.active()) {
+            pool.record_active_failure(Instant::now());
+        }
+        match self.rotation.record_failure() {
+            // A failed switch reconnects to the new endpoint after the backoff
+            Some(reason) => {
+                let _ = self.switch_endpoint(reason);
+            }
+            None => self.schedule_reconnect(Instant::now()),
+        }
+    }
+
+    /// Schedules the reconnection to the active endpoint, after the jittered
+    /// backoff of its consecutive failures.
+    fn schedule_reconnect(&mut self, now: Instant) {
+        let failure = self.rotation.failures().max(1);
+        self.reconnect_due = Some(now + self.reconnect.retry_policy().jittered(failure, &mut self.jitter));
+    }
+
+    /// Reopens the connection to the active endpoint, resubscribing the streams.
+    ///
+    /// LATENCY: SLOW_PATH
+    /// ERROR: FULLY_HANDLED
+    fn reconnect(&mut self) {
+        self.reconnect_due = None;
+        let pool = self.addresses.get_mut(self.rotation.active()).expect("a pool per endpoint");
+        let reconnected = match Self::connect(self.rotation.active(), pool) {
+            Ok(websocket) => {
+                self.websocket = websocket;
+                self.rotation.record_reconnect(Instant::now());
+                self.resubscribe()
+            }
+            Err(e) => Err(e),
+        };
+        if reconnected.is_err() {
+            self.handle_failure();
+        }
+    }
+
+    /// Switches to the next endpoint, reporting the switch and resubscribing the streams.
+    ///
+    /// LATENCY: SLOW_PATH
+    fn switch_endpoint(&mut self, reason: SwitchReason) -> Result<(), WebsocketConnectorError> {
+        let (from, to) = self.rotation.advance(Instant::now());
+        if let Some((feed, reporter)) = &self.failover_reporter {
+            let _ = reporter.send(EndpointSwitch { feed: feed.clone(), from, to: to.clone(), reason });
+        }
+
+        let pool = self.addresses.get_mut(&to).expect("a pool per endpoint");
+        self.reconnect_due = None;
+        match Self::connect(&to, pool) {
+            Ok(websocket) => self.websocket = websocket,
+            Err(e) => {
+                self.schedule_reconnect(Instant::now());
+                return Err(e);
+            }
+        }
+        self.resubscribe()
+    }
+
+    /// Resubscribes the streams of the ledger on a new connection.
+    ///
+    /// Requests pending on the previous connection are dropped, as their responses won't arrive.
+    ///
+    /// LATENCY: SLOW_PATH
+    fn resubscribe(&mut self) -> Result<(), WebsocketConnectorError> {
+        self.assembler.reset();
+        self.pending.clear();
+        self.retries_issued.clear();
+        self.retries.clear();
+        self.ledger.clear_pending();
+        self.ledger.deactivate_all();
+        if !self.ledger.is_empty() {
+            self.send_request(WSRequestKind::Subscribe(self.ledger.streams().to_vec()))?;
+        }
+        Ok(())
+    }
+
+    /// Lists the subscriptions of the server if the reconciliation is due.
+    ///
+    /// LATENCY: FAST_PATH (SLOW_PATH when due)
+    /// ERROR: FULLY_HANDLED
+    fn maybe_list_subscriptions(&mut self, now: Instant) {
+        if !self.ledger.is_due(now) {
+            return;
+        }
+        match self.send_request(WSRequestKind::ListSubscriptions) {
+            Ok(id) => self.ledger.on_list_request(id, now),
+            Err(_) => self.handle_failure(),
+        }
+    }
+
+    /// Issues the rejected requests whose retry backoff elapsed.
+    ///
+    /// The streams subscribed or unsubscribed since the rejection are left out.
+    ///
+    /// LATENCY: FAST_PATH (SLOW_PATH when due)
+    /// ERROR: FULLY_HANDLED
+    fn maybe_retry(&mut self, now: Instant) {
+        if self.retries.is_empty() {
+            return;
+        }
+        for retry in self.retries.take_due(now) {
+            let kind = match retry.kind {
+                WSRequestKind::Subscribe(mut names) => {
+                    names.retain(|name| self.ledger.contains(name) && !self.ledger.is_active(name));
+                    if names.is_empty() {
+                        continue;
+                    }
+                    WSRequestKind::Subscribe(names)
+                }
+                WSRequestKind::Unsubscribe(mut names) => {
+                    names.retain(|name| !self.ledger.contains(name));
+                    if names.is_empty() {
+                        continue;
+                    }
+                    WSRequestKind::Unsubscribe(names)
+                }
+                kind => kind,
+            };
+            match self.send_request(kind) {
+                Ok(id) => {
+                    self.retries_issued.insert(id, retry.retry);
+                }
+                Err(_) => self.handle_failure(),
+            }
+        }
+    }
+
+    /// Applies the response of a SUBSCRIBE or UNSUBSCRIBE request: the
+    /// acknowledged streams become active, a rejected request is retried.
+    fn on_streams_response(&mut self, request: &WSRequestKind, response: &WSResponse, retries: u32) {
+        match request {
+            WSRequestKind::Subscribe(names) if response.is_ok() => self.ledger.activate(names),
+            WSRequestKind::Subscribe(_) | WSRequestKind::Unsubscribe(_) if !response.is_ok() => {
+                // Once exhausted, the reconciliation resubscribes the missing streams
+                self.retries.schedule(request.clone(), retries, Instant::now());
+            }
+            _ => {}
+        }
+    }
+
+    /// Reconciles the ledger with the subscriptions listed by the server,
+    /// resubscribing the missing streams and reporting the drift.
+    ///
+    /// LATENCY: SLOW_PATH
+    fn reconcile(&mut self, response: &WSResponse) -> Result<(), WebsocketConnectorError> {
+        let WSResponse::Result { result, .. } = response else {
+            // The next reconciliation lists the subscriptions again
+            self.ledger.clear_pending();
+            return Ok(());
+        };
+        let Ok(listed) = serde_json::from_value::<Vec<String>>(result.clone()) else {
+            self.stats.record_bad_response();
+            self.ledger.clear_pending();
+            return Ok(());
+        };
+        let (missing, unexpected) = self.ledger.reconcile(&listed);
+        if missing.is_empty() && unexpected.is_empty() {
+            return Ok(());
+        }
+
+        if let Some((feed, reporter)) = &self.drift_reporter {
+            let _ = reporter.send(SubscriptionDrift {
+                feed: feed.clone(),
+                endpoint: self.rotation.active().to_string(),
+                missing: missing.clone(),
+                unexpected,
+            });
+        }
+        if !missing.is_empty() {
+            self.send_request(WSRequestKind::Subscribe(missing))?;
+        }
+        Ok(())
+    }
+
+    /// Matches the response held in the receive buffer against the issued requests.
+    ///
+    /// A malformed response is counted in the statistics and dropped, the
+    /// connection kept.
+    ///
+    /// LATENCY: SLOW_PATH
+    /// ERROR: FULLY_HANDLED
+    fn handle_response(&mut self) {
+        let Ok(response) = serde_json::from_slice::<WSResponse>(self.assembler.message()) else {
+            self.stats.record_bad_response();
+            return;
+        };
+        let Some(id) = response.id().cloned() else {
+            return;
+        };
+        let request = self.pending.remove(&id).map(|(request, issued)| {
+            self.stats.record_rtt(issued.elapsed());
+            request
+        });
+        // The reconciliations are internal to the connection
+        if self.ledger.is_list_request(&id) {
+            if self.reconcile(&response).is_err() {
+                self.handle_failure();
+            }
+            return;
+        }
+        let retries = self.retries_issued.remove(&id).unwrap_or(0);
+        if let Some(request) = &request {
+            self.on_streams_response(request, &response, retries);
+        }
+        self.report_ack(WSAck { id, request, response });
+    }
+
+    /// Reports the response to an issued request, or holds it for `poll_ack`,
+    /// dropping the oldest held once `MAX_QUEUED_ACKS` are.
+    fn report_ack(&mut self, ack: WSAck) {
+        if let Some((feed, reporter)) = &self.ack_reporter {
+            let _ = reporter.send(RequestAck { feed: feed.clone(), ack });
+            return;
+        }
+        if self.acks.len() == MAX_QUEUED_ACKS {
+            self.acks.pop_front();
+        }
+        self.acks.push_back(ack);
+    }
 }
 
 impl<K: FeedKind> FeedProtocolOps for WSConn<K> {
     type FeedProtocolError = WebsocketConnectorError;
 
+    /// Polls the active endpoint for the next stream message.
+    ///
+    /// Failures of the endpoint are handled by reconnecting to it after a
+    /// backoff, and failing over to the next endpoint once they repeat, as is
+    /// an endpoint that stayed silent for too long while subscribed.
+    ///
+    /// A message fragmented into continuation frames is polled once reassembled.
+    ///
+    /// # Errors
+    /// Returns `MessageTooLarge` for a message exceeding the maximum size,
+    /// dropped without affecting the connection.
+    ///
+    /// LATENCY: FAST_PATH
     fn poll(&mut self) -> Result<FeedPoll<'_>, Self::FeedProtocolError> {
-        match self.websocket.poll()? {
-            Some(msg) => {
-                self.recv_buffer.clear();
-                self.recv_buffer.extend_from_slice(msg.as_bytes());
-                Ok(FeedPoll::Data(&self.recv_buffer))
+        if let Some(due) = self.reconnect_due {
+            if Instant::now() >= due {
+                self.reconnect();
+            }
+            return Ok(FeedPoll::Empty);
+        }
+        let received = match self.websocket.poll() {
+            Ok(Some(msg)) => {
+                let now = Instant::now();
+                self.stats.record_frame(msg.as_bytes().len(), now);
+                if !self.assembler.push(msg.as_bytes(), msg.is_final())? {
+                    // The rest of the message is carried by the next frames
+                    self.rotation.record_data(now);
+                    return Ok(FeedPoll::Empty);
+                }
+                true
+            }
+            Ok(None) => false,
+            Err(_) => {
+                self.handle_failure();
+                return Ok(FeedPoll::Empty);
+            }
+        };
+
+        if !received {
+            if !self.ledger.is_empty() {
+                let now = Instant::now();
+                self.stats.sample(now);
+                if let Some(reason) = self.rotation.check_stale(now) {
+                    // A failed switch is retried once the new endpoint turns stale or fails
+                    let _ = self.switch_endpoint(reason);
+                } else {
+                    self.maybe_list_subscriptions(now);
+                    self.maybe_retry(now);
+                }
             }
-            None => Ok(FeedPoll::Empty),
+            return Ok(FeedPoll::Empty);
         }
+
+        let now = Instant::now();
+        self.rotation.record_data(now);
+        self.maybe_list_subscriptions(now);
+        self.maybe_retry(now);
+        if WSResponse::is_response(self.assembler.message()) {
+            self.handle_response();
+            return Ok(FeedPoll::Empty);
+        }
+        Ok(FeedPoll::Data(self.assembler.message()))
     }
 
     fn send(&mut self, data: FeedData) -> Result<(), Self::FeedProtocolError> {
@@ -51,4 +718,291 @@ impl<K: FeedKind> FeedProtocolOps for WSConn<K> {
         self.websocket.send_text(text)?;
         Ok(())
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+
+    use crate::mock::{MockServer, MockStep};
+
+    const TRADE: &str = r#"{"e":"trade","E":1,"s":"BTCUSDT","t":1,"p":"60000.00","q":"0.01","T":1,"m":true}"#;
+    const STREAM: &str = "btcusdt@trade";
+
+    /// Bound on the polls waiting for the server.
+    const WAIT: Duration = Duration::from_secs(5);
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    struct Trades;
+
+    impl FeedKind for Trades {}
+
+    fn trade_streams() -> Streams<Trades> {
+        let mut streams = Streams::new();
+        streams.insert(Stream::new("btcusdt"));
+        streams
+    }
+
+    /// Polls the connection until a stream frame arrives.
+    fn poll_data(conn: &mut WSConn<Trades>) -> Vec<u8> {
+        let start = Instant::now();
+        while start.elapsed() < WAIT {
+            if let FeedPoll::Data(data) = conn.poll().unwrap() {
+                return data.to_vec();
+            }
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        panic!("no stream data within {:?}", WAIT);
+    }
+
+    /// Polls the connection until a response arrives.
+    fn poll_ack(conn: &mut WSConn<Trades>) -> WSAck {
+        let start = Instant::now();
+        while start.elapsed() < WAIT {
+            conn.poll().unwrap();
+            if let Some(ack) = conn.poll_ack() {
+                return ack;
+            }
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        panic!("no response within {:?}", WAIT);
+    }
+
+    /// Polls the connection until `done` holds.
+    fn poll_until(conn: &mut WSConn<Trades>, mut done: impl FnMut(&WSConn<Trades>) -> bool) {
+        let start = Instant::now();
+        while !done(conn) {
+            assert!(start.elapsed() < WAIT, "condition not met within {:?}", WAIT);
+            conn.poll().unwrap();
+            std::thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    fn subscribe() -> WSRequestKind {
+        WSRequestKind::Subscribe(vec![STREAM.to_string()])
+    }
+
+    #[test]
+    fn test_subscribe_and_receive() {
+        let server = MockServer::start(vec![vec![MockStep::Ack, MockStep::Send(TRADE.to_string())]]);
+        let mut conn = WSConn::<Trades>::new(server.url()).unwrap();
+
+        let update = conn.update_streams(&trade_streams(), "trade").unwrap();
+        let ack = poll_ack(&mut conn);
+        assert_eq!(Some(&ack.id), update.subscribe.as_ref());
+        assert_eq!(ack.request, Some(subscribe()));
+        assert!(ack.response.is_ok());
+        assert_eq!(conn.active_streams(), [STREAM.to_string()].as_slice());
+        assert_eq!(conn.num_pending(), 0);
+
+        assert_eq!(poll_data(&mut conn), TRADE.as_bytes());
+        let stats = conn.stats();
+        // The acknowledgement, then the trade
+        assert_eq!(stats.frames, 2);
+        assert!(stats.bytes as usize > TRADE.len());
+        assert!(stats.rtt_us.is_some());
+        assert!(stats.since_last_data_ms.is_some());
+        let received = server.received();
+        assert_eq!(received.len(), 1);
+        assert_eq!(received[0].1.kind, subscribe());
+    }
+
+    #[test]
+    fn test_update_streams_batched() {
+        let server = MockServer::start(vec![vec![MockStep::Ack, MockStep::Ack, MockStep::Ack]]);
+        let mut conn = WSConn::<Trades>::new(server.url()).unwrap();
+        let (reporter, acks) = mpsc::channel();
+        conn.set_ack_reporter("trade", reporter);
+        conn.update_streams(&trade_streams(), "trade").unwrap();
+
+        // Switching symbols issues the UNSUBSCRIBE and SUBSCRIBE together
+        let mut streams = Streams::new();
+        streams.insert(Stream::new("ethusdt"));
+        let update = conn.update_streams(&streams, "trade").unwrap();
+        assert_eq!(conn.last_update(), &update);
+        assert_eq!(conn.num_pending(), 3);
+        poll_until(&mut conn, |conn| conn.num_pending() == 0);
+
+        let reported: Vec<RequestAck> = acks.try_iter().collect();
+        assert_eq!(reported.len(), 3);
+        assert!(reported.iter().all(|ack| ack.feed == "trade" && ack.ack.response.is_ok()));
+        assert_eq!(Some(&reported[1].ack.id), update.unsubscribe.as_ref());
+        assert_eq!(Some(&reported[2].ack.id), update.subscribe.as_ref());
+        assert!(conn.poll_ack().is_none());
+        let received = server.received();
+        assert_eq!(received[1].1.kind, WSRequestKind::Unsubscribe(vec![STREAM.to_string()]));
+        assert_eq!(received[2].1.kind, WSRequestKind::Subscribe(vec!["ethusdt@trade".to_string()]));
+        assert_eq!(conn.active_streams(), ["ethusdt@trade".to_string()].as_slice());
+    }
+
+    #[test]
+    fn test_failed_update_leaves_ledger() {
+        let server = MockServer::start(vec![vec![MockStep::Ack, MockStep::Disconnect]]);
+        let mut conn = WSConn::<Trades>::new(server.url()).unwrap();
+        conn.set_reconnect_policy(ReconnectPolicy {
+            initial_backoff_ms: 60_000,
+            max_backoff_ms: 60_000,
+            multiplier: 2,
+            jitter_pct: 0,
+        });
+        conn.update_streams(&trade_streams(), "trade").unwrap();
+        poll_ack(&mut conn);
+        poll_until(&mut conn, |conn| conn.reconnect_due.is_some());
+
+        // Switching symbols on the closed connection fails, once the socket reports it
+        let mut streams = Streams::new();
+        streams.insert(Stream::new("ethusdt"));
+        let updates = [(streams, "ethusdt@trade"), (trade_streams(), STREAM)];
+        let mut subscribed = STREAM;
+        let start = Instant::now();
+        for (streams, name) in updates.iter().cycle() {
+            assert!(start.elapsed() < WAIT, "no failed update within {:?}", WAIT);
+            if conn.update_streams(streams, "trade").is_err() {
+                break;
+            }
+            subscribed = *name;
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        assert_eq!(conn.ledger().streams(), [subscribed.to_string()].as_slice());
+    }
+
+    #[test]
+    fn test_malformed_response_dropped() {
+        let server = MockServer::start(vec![vec![
+            MockStep::Send(r#"{"id":1,"result""#.to_string()),
+            MockStep::Send(TRADE.to_string()),
+        ]]);
+        let mut conn = WSConn::<Trades>::new(server.url()).unwrap();
+
+        // The connection keeps polling past the malformed response
+        assert_eq!(poll_data(&mut conn), TRADE.as_bytes());
+        assert_eq!(conn.stats().bad_responses, 1);
+    }
+
+    #[test]
+    fn test_rejected_subscribe_retried() {
+        let server = MockServer::start(vec![vec![
+            MockStep::Reject { code: 2, msg: "Invalid request".to_string() },
+            MockStep::Ack,
+        ]]);
+        let mut conn = WSConn::<Trades>::new(server.url()).unwrap();
+        conn.set_retry_policy(RetryPolicy {
+            max_retries: 3,
+            initial_backoff_ms: 10,
+            max_backoff_ms: 10,
+            ..RetryPolicy::default()
+        });
+
+        conn.update_streams(&trade_streams(), "trade").unwrap();
+        let rejected = poll_ack(&mut conn);
+        assert!(matches!(rejected.response, WSResponse::Error { code: 2, .. }));
+        assert!(conn.active_streams().is_empty());
+
+        // The retry is issued once its backoff elapsed, under a new request ID
+        let retried = poll_ack(&mut conn);
+        assert_ne!(retried.id, rejected.id);
+        assert!(retried.response.is_ok());
+        assert_eq!(conn.active_streams(), [STREAM.to_string()].as_slice());
+        assert_eq!(server.received().len(), 2);
+    }
+
+    #[test]
+    fn test_failover_resubscribes() {
+        let primary = MockServer::start(vec![vec![MockStep::Ack, MockStep::Disconnect]]);
+        let backup = MockServer::start(vec![vec![MockStep::Ack, MockStep::Send(TRADE.to_string())]]);
+        let policy = FailoverPolicy { max_failures: 1, stale_after_ms: 0 };
+        let mut conn =
+            WSConn::<Trades>::with_endpoints(vec![primary.url().to_string(), backup.url().to_string()], policy)
+                .unwrap();
+        let (reporter, switches) = mpsc::channel();
+        conn.set_failover_reporter("trade", reporter);
+
+        conn.update_streams(&trade_streams(), "trade").unwrap();
+        poll_ack(&mut conn);
+
+        // The primary drops the connection, the streams are resubscribed on the backup
+        assert_eq!(poll_data(&mut conn), TRADE.as_bytes());
+        assert_eq!(conn.active_endpoint(), backup.url());
+        let switch = switches.try_recv().unwrap();
+        assert_eq!((switch.from.as_str(), switch.to.as_str()), (primary.url(), backup.url()));
+        assert_eq!(backup.received()[0].1.kind, subscribe());
+        poll_until(&mut conn, |conn| conn.ledger().is_active(STREAM));
+    }
+
+    #[test]
+    fn test_reconnect_resubscribes() {
+        let server = MockServer::start(vec![
+            vec![MockStep::Ack, MockStep::Disconnect],
+            vec![MockStep::Ack, MockStep::Send(TRADE.to_string())],
+        ]);
+        let mut conn = WSConn::<Trades>::new(server.url()).unwrap();
+        conn.set_reconnect_policy(ReconnectPolicy {
+            initial_backoff_ms: 10,
+            max_backoff_ms: 10,
+            multiplier: 2,
+            jitter_pct: 0,
+        });
+        let (reporter, switches) = mpsc::channel();
+        conn.set_failover_reporter("trade", reporter);
+
+        conn.update_streams(&trade_streams(), "trade").unwrap();
+        poll_ack(&mut conn);
+
+        // The server drops the connection, the streams are resubscribed once reconnected
+        assert_eq!(poll_data(&mut conn), TRADE.as_bytes());
+        assert_eq!(conn.active_endpoint(), server.url());
+        assert!(switches.try_recv().is_err());
+        let received = server.received();
+        assert_eq!(received.last().map(|(index, request)| (*index, &request.kind)), Some((1, &subscribe())));
+        poll_until(&mut conn, |conn| conn.ledger().is_active(STREAM));
+    }
+
+    #[test]
+    fn test_fragmented_message_reassembled() {
+        let parts = [&TRADE[..20], &TRADE[20..40], &TRADE[40..]].map(str::to_string).to_vec();
+        let server = MockServer::start(vec![vec![
+            MockStep::SendFragments(parts.clone()),
+            MockStep::SendFragments(parts),
+            MockStep::Send(TRADE.to_string()),
+        ]]);
+        let mut conn = WSConn::<Trades>::new(server.url()).unwrap();
+        assert_eq!(poll_data(&mut conn), TRADE.as_bytes());
+
+        // The next oversized message is dropped, the connection kept
+        conn.set_max_message_size(TRADE.len() - 1);
+        let start = Instant::now();
+        let err = loop {
+            assert!(start.elapsed() < WAIT, "no oversized message within {:?}", WAIT);
+            match conn.poll() {
+                Err(e) => break e,
+                Ok(FeedPoll::Data(_)) => panic!("oversized message polled"),
+                Ok(_) => std::thread::sleep(Duration::from_millis(1)),
+            }
+        };
+        assert!(matches!(err, WebsocketConnectorError::MessageTooLarge { max, .. } if max == TRADE.len() - 1));
+        conn.set_max_message_size(TRADE.len());
+        assert_eq!(poll_data(&mut conn), TRADE.as_bytes());
+    }
+
+    #[test]
+    fn test_reconcile_resubscribes_missing() {
+        let server = MockServer::start(vec![vec![MockStep::Ack, MockStep::List(Vec::new()), MockStep::Ack]]);
+        let mut conn = WSConn::<Trades>::new(server.url()).unwrap();
+        let (reporter, drifts) = mpsc::channel();
+        conn.set_drift_reporter("trade", reporter);
+
+        conn.update_streams(&trade_streams(), "trade").unwrap();
+        poll_ack(&mut conn);
+        conn.set_reconcile_interval(Some(Duration::from_millis(10)));
+
+        // The server lost the subscription, the reconciliation resubscribes it
+        poll_until(&mut conn, |_| server.received().len() >= 3);
+        let drift = drifts.try_recv().unwrap();
+        assert_eq!(drift.missing, vec![STREAM.to_string()]);
+        assert!(drift.unexpected.is_empty());
+        let received = server.received();
+        assert_eq!(received[1].1.kind, WSRequestKind::ListSubscriptions);
+        assert_eq!(received[2].1.kind, subscribe());
+    }
+}
@@ -0,0 +1,9 @@
+#!/bin/bash
+set -e
+
+# Change to project root directory
+cd "$(dirname "$0")/.."
+
+cargo build --release --bin ctl-arbiter
+# Options and their CTL_* environment overrides are passed through
+sudo --preserve-env=CTL_LOG_CONFIG,CTL_MD_CONFIG,CTL_POLLING_CONFIG ./target/release/ctl-arbiter "$@"
@@ -5,4 +5,5 @@ set -e
 cd "$(dirname "$0")/.."
 
 cargo build --release --bin ctl-md-handler
-sudo ./target/release/ctl-md-handler
\ No newline at end of file
+# Options and their CTL_* environment overrides are passed through
+sudo --preserve-env=CTL_LOG_CONFIG,CTL_MD_CONFIG,CTL_SYMBOL_INFO,CTL_POLLING_CONFIG,CTL_WS_ENDPOINT,CTL_MD_LINE ./target/release/ctl-md-handler "$@"
\ No newline at end of file
@@ -5,4 +5,5 @@ set -e
 cd "$(dirname "$0")/.."
 
 cargo build --release --bin ctl-md-subscriber
-sudo ./target/release/ctl-md-subscriber
\ No newline at end of file
+# Options and their CTL_* environment overrides are passed through
+sudo --preserve-env=CTL_LOG_CONFIG,CTL_MD_CONFIG,CTL_POLLING_CONFIG ./target/release/ctl-md-subscriber "$@"
\ No newline at end of file
@@ -5,4 +5,5 @@ set -e
 cd "$(dirname "$0")/.."
 
 cargo build --release --bin ctl-resource-manager
-sudo ./target/release/ctl-resource-manager
\ No newline at end of file
+# Options and their CTL_* environment overrides are passed through
+sudo --preserve-env=CTL_LOG_CONFIG,CTL_RM_CONFIG,CTL_MD_CONFIG,CTL_SYMBOL_INFO ./target/release/ctl-resource-manager "$@"
\ No newline at end of file